@@ -0,0 +1,233 @@
+//! Per-op executor latency and parser throughput.
+//!
+//! Run with: `cargo bench --bench executor_ops`
+//! Refresh the committed baseline: `scripts/bench-baseline.sh`
+//!
+//! Each op runs against keyspaces of several sizes with several value
+//! sizes, so a redesign of SDS, the dictionary or the reply encoder can be
+//! compared against `benches/baselines/executor_ops.json` op by op.
+
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use redis_sim::redis::{Command, CommandExecutor, RespCodec, RespParser, RespValue, SDS};
+use std::time::Duration;
+
+const KEY_COUNTS: [usize; 2] = [1_000, 100_000];
+const VALUE_SIZES: [usize; 2] = [16, 1024];
+
+fn key(i: usize) -> String {
+    format!("key:{:08}", i)
+}
+
+/// Executor holding `keys` strings of `value_size` bytes
+fn with_strings(keys: usize, value_size: usize) -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    let value = vec![b'v'; value_size];
+    for i in 0..keys {
+        executor.set_direct(&key(i), &value);
+    }
+    executor
+}
+
+fn configure(group: &mut criterion::BenchmarkGroup<'_, criterion::measurement::WallTime>) {
+    group.warm_up_time(Duration::from_millis(500));
+    group.measurement_time(Duration::from_secs(2));
+    group.throughput(Throughput::Elements(1));
+}
+
+fn bench_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("executor_ops");
+    configure(&mut group);
+
+    for keys in KEY_COUNTS {
+        for value_size in VALUE_SIZES {
+            let id = format!("{}_keys/{}_bytes", keys, value_size);
+            let mut executor = with_strings(keys, value_size);
+
+            let gets: Vec<Command> = (0..keys).map(|i| Command::Get(key(i))).collect();
+            let mut next = 0;
+            group.bench_function(BenchmarkId::new("get", &id), |b| {
+                b.iter(|| {
+                    next = (next + 7919) % keys;
+                    executor.execute(black_box(&gets[next]))
+                })
+            });
+
+            let value = SDS::new(vec![b'w'; value_size]);
+            let sets: Vec<Command> = (0..keys)
+                .map(|i| Command::set(key(i), value.clone()))
+                .collect();
+            group.bench_function(BenchmarkId::new("set", &id), |b| {
+                b.iter(|| {
+                    next = (next + 7919) % keys;
+                    executor.execute(black_box(&sets[next]))
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_collections(c: &mut Criterion) {
+    let mut group = c.benchmark_group("executor_ops");
+    configure(&mut group);
+
+    for elements in KEY_COUNTS {
+        for value_size in VALUE_SIZES {
+            let id = format!("{}_elements/{}_bytes", elements, value_size);
+            let value = SDS::new(vec![b'v'; value_size]);
+            let mut executor = CommandExecutor::new();
+
+            let hsets: Vec<Command> = (0..elements)
+                .map(|i| {
+                    Command::HSet(
+                        "hash".to_string(),
+                        vec![(SDS::from_str(&key(i)), value.clone())],
+                    )
+                })
+                .collect();
+            for cmd in &hsets {
+                executor.execute(cmd);
+            }
+            let mut next = 0;
+            group.bench_function(BenchmarkId::new("hset", &id), |b| {
+                b.iter(|| {
+                    next = (next + 7919) % elements;
+                    executor.execute(black_box(&hsets[next]))
+                })
+            });
+
+            // Members carry the value size; scores change on every call
+            let member = |i: usize| {
+                let mut bytes = key(i).into_bytes();
+                bytes.resize(value_size.max(bytes.len()), b'm');
+                SDS::new(bytes)
+            };
+            for i in 0..elements {
+                executor.execute(&zadd(i as f64, member(i)));
+            }
+            let members: Vec<SDS> = (0..elements).map(member).collect();
+            let mut score = 0.0;
+            group.bench_function(BenchmarkId::new("zadd", &id), |b| {
+                b.iter(|| {
+                    next = (next + 7919) % elements;
+                    score += 1.0;
+                    executor.execute(black_box(&zadd(score, members[next].clone())))
+                })
+            });
+
+            // Push then pop so the list stays at `elements`
+            executor.execute(&Command::LPush(
+                "list".to_string(),
+                vec![value.clone(); elements],
+            ));
+            let lpush = Command::LPush("list".to_string(), vec![value.clone()]);
+            let rpop = Command::RPop("list".to_string());
+            group.bench_function(BenchmarkId::new("lpush_rpop", &id), |b| {
+                b.iter(|| {
+                    executor.execute(black_box(&lpush));
+                    executor.execute(black_box(&rpop))
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn zadd(score: f64, member: SDS) -> Command {
+    Command::ZAdd {
+        key: "zset".to_string(),
+        pairs: vec![(score, member)],
+        nx: false,
+        xx: false,
+        gt: false,
+        lt: false,
+        ch: false,
+    }
+}
+
+fn bench_scan_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("executor_ops");
+    configure(&mut group);
+
+    for keys in KEY_COUNTS {
+        let mut executor = with_strings(keys, 16);
+        let mut cursor = 0u64;
+        group.bench_function(
+            BenchmarkId::new("scan_step", format!("{}_keys", keys)),
+            |b| {
+                b.iter(|| {
+                    let reply = executor.execute(black_box(&Command::Scan {
+                        cursor,
+                        pattern: None,
+                        count: Some(10),
+                        type_filter: None,
+                    }));
+                    cursor = next_cursor(&reply);
+                    reply
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn next_cursor(reply: &RespValue) -> u64 {
+    match reply {
+        RespValue::Array(Some(parts)) => match &parts[0] {
+            RespValue::BulkString(Some(bytes)) => {
+                std::str::from_utf8(bytes).unwrap().parse().unwrap()
+            }
+            other => panic!("SCAN cursor was {:?}", other),
+        },
+        other => panic!("SCAN returned {:?}", other),
+    }
+}
+
+/// RESP encoding of `SET key:00000001 <value>`
+fn set_request(value_size: usize) -> Vec<u8> {
+    let value = vec![b'v'; value_size];
+    RespParser::encode(&RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"SET".to_vec())),
+        RespValue::BulkString(Some(key(1).into_bytes())),
+        RespValue::BulkString(Some(value)),
+    ])))
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parser");
+    group.warm_up_time(Duration::from_millis(500));
+    group.measurement_time(Duration::from_secs(2));
+
+    for value_size in [16, 1024, 16 * 1024] {
+        let request = set_request(value_size);
+        group.throughput(Throughput::Bytes(request.len() as u64));
+
+        group.bench_function(BenchmarkId::new("resp", value_size), |b| {
+            b.iter(|| RespParser::parse(black_box(&request)).unwrap())
+        });
+
+        group.bench_function(BenchmarkId::new("resp_zero_copy", value_size), |b| {
+            b.iter(|| {
+                let mut buf = BytesMut::from(black_box(request.as_slice()));
+                RespCodec::parse(&mut buf).unwrap()
+            })
+        });
+
+        let (resp, _) = RespParser::parse(&request).unwrap();
+        group.bench_function(BenchmarkId::new("command", value_size), |b| {
+            b.iter(|| Command::from_resp(black_box(&resp)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_strings,
+    bench_collections,
+    bench_scan_step,
+    bench_parser,
+);
+
+criterion_main!(benches);
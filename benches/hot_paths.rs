@@ -5,10 +5,10 @@
 //!
 //! These benchmarks measure the microsecond-level hot paths that
 //! dominate Redis performance: set_direct, get_direct, hashing,
-//! and RESP encoding.
+//! RESP encoding and SCAN MATCH.
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
-use redis_sim::redis::{CommandExecutor, RespValue, SDS};
+use redis_sim::redis::{Command, CommandExecutor, GlobPattern, RespValue, SDS};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
@@ -251,6 +251,51 @@ fn bench_sds_operations(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark MATCH over a large keyspace: compiling the glob per key (the
+/// old behaviour) against one compiled pattern, and a full SCAN step
+fn bench_scan_match(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_match");
+
+    let keys: Vec<String> = (0..100_000)
+        .map(|i| format!("{}:{}:field", ["user", "order", "session"][i % 3], i))
+        .collect();
+    let pattern = b"user:*[05]:f*";
+    group.throughput(Throughput::Elements(keys.len() as u64));
+
+    group.bench_function("compile_per_key", |b| {
+        b.iter(|| {
+            keys.iter()
+                .filter(|k| GlobPattern::compile(black_box(pattern)).matches(k.as_bytes()))
+                .count()
+        })
+    });
+
+    let compiled = GlobPattern::compile(pattern);
+    group.bench_function("compiled", |b| {
+        b.iter(|| {
+            keys.iter()
+                .filter(|k| compiled.matches(black_box(k.as_bytes())))
+                .count()
+        })
+    });
+
+    let mut executor = CommandExecutor::new();
+    for key in &keys {
+        executor.set_direct(key, b"v");
+    }
+    let scan = Command::Scan {
+        cursor: 0,
+        pattern: Some("user:*[05]:f*".to_string()),
+        count: Some(1000),
+        type_filter: None,
+    };
+    group.bench_function("scan_step", |b| {
+        b.iter(|| executor.execute(black_box(&scan)))
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_set_direct,
@@ -261,6 +306,7 @@ criterion_group!(
     bench_bytes_copy,
     bench_resp_value,
     bench_sds_operations,
+    bench_scan_match,
 );
 
 criterion_main!(benches);
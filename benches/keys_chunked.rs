@@ -0,0 +1,63 @@
+//! KEYS as one pass vs. as a chunked scan over a large keyspace.
+//!
+//! Run with: `cargo bench --bench keys_chunked`
+//! Keyspace size: `KEYS_BENCH_KEYS` (default 10M)
+//!
+//! A shard actor answers one message at a time, so the longest single step
+//! is how long every other command on that shard may wait. For each
+//! `keys-chunk-size` this prints the total time of a `KEYS *` and its
+//! longest chunk - the worst-case hold time chunking is meant to bound.
+
+use redis_sim::redis::{Command, CommandExecutor};
+use std::time::{Duration, Instant};
+
+const CHUNK_SIZES: [usize; 4] = [0, 1_000_000, 100_000, 10_000];
+
+fn main() {
+    let keys: usize = std::env::var("KEYS_BENCH_KEYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000_000);
+
+    let started = Instant::now();
+    let mut executor = CommandExecutor::new();
+    for i in 0..keys {
+        executor.set_direct(&format!("key:{:08}", i), b"v");
+    }
+    println!("loaded {} keys in {:?}", keys, started.elapsed());
+
+    let started = Instant::now();
+    executor.execute(&Command::Keys("*".to_string()));
+    println!("KEYS (executor, one pass): {:?}", started.elapsed());
+
+    println!(
+        "{:>12} {:>8} {:>12} {:>14}",
+        "chunk-size", "chunks", "total", "longest-chunk"
+    );
+    for chunk_size in CHUNK_SIZES {
+        let mut cursor = 0;
+        let mut chunks = 0;
+        let mut matched = 0;
+        let mut longest = Duration::ZERO;
+        let started = Instant::now();
+        loop {
+            let chunk_started = Instant::now();
+            let (next, found) = executor.keys_chunk("*", cursor, chunk_size);
+            longest = longest.max(chunk_started.elapsed());
+            matched += found.len();
+            chunks += 1;
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(matched, keys, "a chunked KEYS must return every key");
+        println!(
+            "{:>12} {:>8} {:>12.2?} {:>14.2?}",
+            chunk_size,
+            chunks,
+            started.elapsed(),
+            longest
+        );
+    }
+}
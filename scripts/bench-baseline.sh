@@ -0,0 +1,57 @@
+#!/bin/bash
+# Refresh the committed executor benchmark baseline.
+#
+# Runs the executor_ops criterion suite and writes the mean and median of
+# every benchmark (nanoseconds per iteration) to
+# benches/baselines/executor_ops.json, so a performance change can ship
+# with before/after numbers in its diff.
+#
+# Usage:
+#   ./scripts/bench-baseline.sh            # run the suite, then write the baseline
+#   ./scripts/bench-baseline.sh --no-run   # only collect the last run's results
+#
+# Compare a branch against the last run on main with criterion directly:
+#   cargo bench --bench executor_ops -- --save-baseline main   # on main
+#   cargo bench --bench executor_ops -- --baseline main        # on the branch
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+if [ "${1:-}" != "--no-run" ]; then
+    cargo bench --bench executor_ops
+fi
+
+mkdir -p benches/baselines
+python3 - <<'PY'
+import json, os, platform, subprocess
+
+root = "target/criterion"
+results = {}
+for group in ("executor_ops", "parser"):
+    for dirpath, _, files in os.walk(os.path.join(root, group)):
+        if os.path.basename(dirpath) != "new" or "estimates.json" not in files:
+            continue
+        bench = os.path.relpath(os.path.dirname(dirpath), root)
+        with open(os.path.join(dirpath, "estimates.json")) as f:
+            estimates = json.load(f)
+        results[bench] = {
+            "mean_ns": round(estimates["mean"]["point_estimate"], 1),
+            "median_ns": round(estimates["median"]["point_estimate"], 1),
+        }
+
+if not results:
+    raise SystemExit("no executor_ops results under target/criterion; run the suite first")
+
+commit = subprocess.run(
+    ["git", "rev-parse", "--short", "HEAD"], capture_output=True, text=True
+).stdout.strip()
+baseline = {
+    "commit": commit,
+    "machine": f"{platform.system()} {platform.machine()}, {os.cpu_count()} cpus",
+    "benchmarks": dict(sorted(results.items())),
+}
+with open("benches/baselines/executor_ops.json", "w") as f:
+    json.dump(baseline, f, indent=2)
+    f.write("\n")
+print(f"wrote {len(results)} benchmarks to benches/baselines/executor_ops.json")
+PY
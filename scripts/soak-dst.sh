@@ -9,8 +9,14 @@
 #   ./scripts/soak-dst.sh              # default: 5 minutes
 #   ./scripts/soak-dst.sh 600          # 10 minutes
 #   SOAK_OPS=2000 ./scripts/soak-dst.sh 300  # 5 min, 2000 ops/seed
+#   ./scripts/soak-dst.sh replay-corpus  # re-run known-bad seeds in tests/dst-corpus
 set -euo pipefail
 
+if [ "${1:-}" = "replay-corpus" ]; then
+    cd "$(dirname "$0")/.."
+    exec cargo test --release --test dst_batch_verification test_replay_corpus -- --nocapture
+fi
+
 DURATION_SECS="${1:-300}"
 OPS_PER_SEED="${SOAK_OPS:-1000}"
 END_TIME=$(($(date +%s) + DURATION_SECS))
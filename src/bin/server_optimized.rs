@@ -8,6 +8,8 @@
 //! | Variable | Default | Description |
 //! |----------|---------|-------------|
 //! | REDIS_PORT | 6379 | Server port (Redis default) |
+//! | REDIS_SHUTDOWN_DRAIN_TIMEOUT_MS | 10000 | On SIGTERM/SIGINT, time clients get to drain before being aborted |
+//! | REDIS_WATCHDOG_PERIOD_MS | 0 | Log a diagnostic dump when a shard spends longer than this on one command; 0 disables |
 //!
 //! ## TLS Configuration (requires `tls` feature)
 //!
@@ -25,6 +27,16 @@
 //! | REDIS_REQUIRE_PASS | - | Simple password for AUTH command |
 //! | ACL_FILE | - | Path to ACL configuration file |
 //!
+//! ## Logging
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | REDIS_LOG_LEVEL | RUST_LOG, else notice | Levels, e.g. `notice,replication=debug` (also `CONFIG SET loglevel`) |
+//! | REDIS_LOG_FORMAT | text | `text` or `json` |
+//! | REDIS_LOG_FILE | - | Log to this file instead of stdout, rotated by size |
+//! | REDIS_LOG_MAX_BYTES | 67108864 | Rotate the log file past this size |
+//! | REDIS_LOG_MAX_FILES | 5 | Rotated log files kept |
+//!
 //! ## Datadog (when built with --features datadog)
 //!
 //! | Variable | Default | Description |
@@ -43,6 +55,8 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 use redis_sim::observability::{init_tracing, shutdown, DatadogConfig};
 use redis_sim::production::{OptimizedRedisServer, ServerConfig};
+use redis_sim::release::{ServerMode, StartupBanner};
+use tracing::{info, warn};
 
 const DEFAULT_PORT: u16 = 6379;
 
@@ -63,63 +77,63 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let addr = format!("0.0.0.0:{}", port);
     let server = OptimizedRedisServer::new(addr.clone());
 
-    println!("Redis Rust Server (Drop-in Replacement)");
-    println!("========================================");
-    println!();
-    println!("Listening on {}", addr);
-    println!();
-    println!("Performance optimizations:");
-    println!("  - jemalloc custom allocator");
-    println!("  - Actor-based shards (lock-free)");
-    println!("  - Connection pooling");
-    println!("  - Buffer pooling");
-    #[cfg(feature = "datadog")]
-    println!("  - Datadog observability enabled");
-    println!();
+    let perf_config_path =
+        std::env::var("PERF_CONFIG_PATH").unwrap_or_else(|_| "perf_config.toml".to_string());
+    let config_source = if std::path::Path::new(&perf_config_path).exists() {
+        format!("environment, {}", perf_config_path)
+    } else {
+        "environment".to_string()
+    };
+    StartupBanner {
+        mode: ServerMode::Standalone,
+        port,
+        config_source,
+        persistence: "disabled".to_string(),
+    }
+    .log();
+    info!(
+        addr = %addr,
+        datadog = cfg!(feature = "datadog"),
+        "Redis Rust server (drop-in replacement) starting"
+    );
 
-    // Display security configuration
-    println!("Security:");
+    // Security configuration
     if security_config.tls_enabled() {
         #[cfg(feature = "tls")]
         {
             let tls = security_config.tls.as_ref().unwrap();
-            println!("  - TLS: ENABLED");
-            println!("    Certificate: {:?}", tls.cert_path);
-            println!("    Key: {:?}", tls.key_path);
-            if let Some(ca) = &tls.ca_path {
-                println!("    CA: {:?}", ca);
-            }
-            if tls.require_client_cert {
-                println!("    Client certificates: REQUIRED");
-            }
+            info!(
+                cert = ?tls.cert_path,
+                key = ?tls.key_path,
+                ca = ?tls.ca_path,
+                require_client_cert = tls.require_client_cert,
+                "TLS enabled"
+            );
         }
         #[cfg(not(feature = "tls"))]
         {
-            println!("  - TLS: Configured but feature not enabled (build with --features tls)");
+            warn!("TLS configured but the tls feature is not enabled (build with --features tls)");
         }
     } else {
-        println!("  - TLS: disabled");
+        info!("TLS disabled");
     }
 
     if security_config.acl_enabled() {
         #[cfg(feature = "acl")]
         {
-            println!("  - ACL: ENABLED");
-            if security_config.acl.require_pass.is_some() {
-                println!("    Authentication: password required");
-            }
-            if let Some(acl_file) = &security_config.acl.acl_file {
-                println!("    ACL file: {:?}", acl_file);
-            }
+            info!(
+                require_pass = security_config.acl.require_pass.is_some(),
+                acl_file = ?security_config.acl.acl_file,
+                "ACL enabled"
+            );
         }
         #[cfg(not(feature = "acl"))]
         {
-            println!("  - ACL: Configured but feature not enabled (build with --features acl)");
+            warn!("ACL configured but the acl feature is not enabled (build with --features acl)");
         }
     } else {
-        println!("  - ACL: disabled (no authentication)");
+        info!("ACL disabled (no authentication)");
     }
-    println!();
 
     server.run().await?;
 
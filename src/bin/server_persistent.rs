@@ -38,6 +38,58 @@
 //! | REDIS_WAL_DIR | /tmp/redis-wal | WAL file directory |
 //! | REDIS_WAL_FSYNC | everysec | Fsync policy: always, everysec, no |
 //!
+//! ## Shutdown
+//!
+//! On SIGTERM, SIGINT or a SHUTDOWN command the server stops accepting,
+//! drains clients (each finishes the commands it has read, then receives
+//! `-SHUTDOWN`), final-fsyncs the WAL, flushes the write buffer and writes a
+//! shutdown checkpoint. `SHUTDOWN NOSAVE` skips the checkpoint only.
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | REDIS_SHUTDOWN_DRAIN_TIMEOUT_MS | 10000 | Time clients get to drain before being aborted |
+//!
+//! ## Health
+//!
+//! The health port (port + 1) answers `GET /healthz` with each component's
+//! status - persistence workers, WAL fsync lag, replication and event loop
+//! lag - and replies 503 when one is down; `DEBUG HEALTH` returns the same
+//! report. Any other path answers a plain 200 OK.
+//!
+//! ## Watchdog
+//!
+//! When enabled, a shard stuck on one command for longer than the period
+//! logs a dump of every shard, the WAL queue and the persistence workers.
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | REDIS_WATCHDOG_PERIOD_MS | 0 | Stall threshold; 0 disables the watchdog |
+//!
+//! ## Import
+//!
+//! `server_persistent --import <file>` recovers as usual, bulk-loads the
+//! file - an RDB snapshot, or RESP commands as `redis-cli --pipe` takes
+//! them - writes one checkpoint and exits without serving. Strings and
+//! hashes persist (the types replication carries); needs a persistent
+//! store.
+//!
+//! ## Diff
+//!
+//! `server_persistent --diff <before> <after>` compares two snapshots -
+//! checkpoints or RDB files, in any mix - prints the keys added, removed,
+//! changed and with a changed TTL, with counts per type, and exits 0 when
+//! the keyspaces match, 1 when they differ. It needs no store or config.
+//!
+//! ## Logging
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | REDIS_LOG_LEVEL | RUST_LOG, else notice | Levels, e.g. `notice,replication=debug` (also `CONFIG SET loglevel`) |
+//! | REDIS_LOG_FORMAT | text | `text` or `json` |
+//! | REDIS_LOG_FILE | - | Log to this file instead of stdout, rotated by size |
+//! | REDIS_LOG_MAX_BYTES | 67108864 | Rotate the log file past this size |
+//! | REDIS_LOG_MAX_FILES | 5 | Rotated log files kept |
+//!
 //! ## Datadog (when built with --features datadog)
 //!
 //! | Variable | Default | Description |
@@ -54,14 +106,19 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
-use bytes::{BufMut, BytesMut};
 use parking_lot::RwLock;
 use redis_sim::observability::{init_tracing, shutdown, DatadogConfig};
-use redis_sim::production::{GossipManager, ReplicatedShardedState};
-use redis_sim::redis::{Command, RespCodec, RespValue};
+use redis_sim::redis::import::read_import;
+use redis_sim::production::{
+    drain_clients, handle_persistent_connection, shutdown_trigger, Activity, ClientRegistry,
+    EventLoopLag, GossipManager, HealthCheck, ReplicatedShardedState, ShutdownConfig, Watchdog,
+    WatchdogConfig,
+};
+use redis_sim::release::{ServerMode, StartupBanner};
 use redis_sim::replication::{ConsistencyLevel, GossipState, ReplicationConfig};
 use redis_sim::streaming::{
-    create_integration, ObjectStoreType, StreamingConfig, StreamingIntegrationTrait, WorkerHandles,
+    create_integration, ObjectStoreType, Snapshot, StreamingConfig, StreamingIntegrationTrait,
+    WorkerHandles,
 };
 use redis_sim::streaming::wal_config::{FsyncPolicy, WalConfig};
 use redis_sim::streaming::wal_store::LocalWalStore;
@@ -70,7 +127,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::signal;
+use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn};
 
 // Redis-compatible defaults for drop-in replacement
@@ -342,6 +399,7 @@ impl ClusterConfig {
 async fn start_gossip_listener(
     port: u16,
     state: Arc<ReplicatedShardedState>,
+    received: Activity,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use tokio::io::AsyncReadExt;
     use tokio::task::JoinHandle;
@@ -378,8 +436,11 @@ async fn start_gossip_listener(
         debug!("Gossip connection from {} (active peers: {})", peer_addr, active_peers.len());
 
         let state_clone = state.clone();
+        let received = received.clone();
         let handle = tokio::spawn(async move {
-            if let Err(e) = handle_gossip_connection(stream, state_clone, MAX_MESSAGE_SIZE).await {
+            if let Err(e) =
+                handle_gossip_connection(stream, state_clone, received, MAX_MESSAGE_SIZE).await
+            {
                 warn!("Gossip connection error from {}: {}", peer_addr, e);
             }
         });
@@ -402,6 +463,7 @@ async fn start_gossip_listener(
 async fn handle_gossip_connection(
     mut stream: TcpStream,
     state: Arc<ReplicatedShardedState>,
+    received: Activity,
     max_message_size: usize,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use redis_sim::replication::gossip::GossipMessage;
@@ -434,12 +496,33 @@ async fn handle_gossip_connection(
         // Deserialize and process
         match GossipMessage::deserialize(&msg_buf) {
             Ok(msg) => {
+                // Deltas and heartbeats alike show the peer is replicating
+                received.record();
                 let source = msg.source_replica();
-                if let Some(deltas) = msg.into_deltas() {
-                    if !deltas.is_empty() {
-                        debug!("Received {} deltas from replica {}", deltas.len(), source.0);
-                        // Apply via CRDT merge (idempotent operation)
-                        state.apply_remote_deltas(deltas);
+                match msg {
+                    GossipMessage::Ack {
+                        target_replica,
+                        offset,
+                        ..
+                    } => state.record_replication_ack(source, target_replica, offset),
+                    GossipMessage::Heartbeat { .. } => state.ack_heartbeat(source),
+                    msg => {
+                        let offsets = msg.offsets();
+                        if let Some(deltas) = msg.into_deltas() {
+                            if !deltas.is_empty() {
+                                debug!(
+                                    "Received {} deltas from replica {}",
+                                    deltas.len(),
+                                    source.0
+                                );
+                                // Apply via CRDT merge (idempotent operation)
+                                state.apply_remote_deltas(deltas);
+                            }
+                        }
+                        // Acknowledged once handed to the shards, for the sender's WAIT
+                        if let Some((first, last)) = offsets {
+                            state.record_replication_delivery(source, first, last);
+                        }
                     }
                 }
             }
@@ -574,6 +657,11 @@ impl Config {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some((before, after)) = diff_paths_from_args()? {
+        let identical = run_diff(&before, &after)?;
+        std::process::exit(if identical { 0 } else { 1 });
+    }
+
     // Initialize observability (Datadog when feature enabled, basic tracing otherwise)
     let dd_config = DatadogConfig::from_env();
     init_tracing(&dd_config)?;
@@ -582,51 +670,46 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let config = Config::from_env();
     let streaming_config = config.to_streaming_config()?;
 
-    println!("Redis Server with Streaming Persistence");
-    println!("========================================");
-    println!();
-    println!("Configuration:");
-    println!("  Port: {}", config.port);
-    println!("  Store: {}", config.store_type);
-    match config.store_type.as_str() {
-        "localfs" => println!("  Path: {}", config.data_path.display()),
+    let persistence = match config.store_type.as_str() {
+        "localfs" => format!("localfs ({})", config.data_path.display()),
         #[cfg(feature = "s3")]
-        "s3" => {
-            println!(
-                "  Bucket: {}",
-                config.s3_bucket.as_deref().unwrap_or("(not set)")
-            );
-            println!("  Prefix: {}", config.s3_prefix);
-            if let Some(endpoint) = &config.s3_endpoint {
-                println!("  Endpoint: {}", endpoint);
-            }
-        }
-        _ => {}
+        "s3" => format!(
+            "s3 ({}/{})",
+            config.s3_bucket.as_deref().unwrap_or("(not set)"),
+            config.s3_prefix
+        ),
+        "memory" => "disabled".to_string(),
+        other => other.to_string(),
+    };
+    StartupBanner {
+        mode: ServerMode::Standalone,
+        port: config.port,
+        config_source: "environment".to_string(),
+        persistence,
+    }
+    .log();
+    info!(
+        store = %config.store_type,
+        datadog = cfg!(feature = "datadog"),
+        "Redis server with streaming persistence starting"
+    );
+    #[cfg(feature = "s3")]
+    if config.store_type == "s3" {
+        info!(endpoint = ?config.s3_endpoint, "S3 store");
     }
-    #[cfg(feature = "datadog")]
-    println!("  Datadog observability enabled");
 
     // Load cluster configuration from Kubernetes environment
     let cluster_config = ClusterConfig::from_env();
     let repl_config = cluster_config.to_replication_config();
 
-    println!();
-    println!("Cluster Configuration:");
-    println!(
-        "  Replication: {}",
-        if cluster_config.enabled {
-            "enabled"
-        } else {
-            "disabled"
-        }
+    info!(
+        replication = cluster_config.enabled,
+        replica_id = %cluster_config.replica_id,
+        gossip_port = cluster_config.gossip_port,
+        cluster_size = cluster_config.cluster_size,
+        peers = ?cluster_config.peers,
+        "Cluster configuration"
     );
-    println!("  Replica ID: {}", cluster_config.replica_id);
-    if cluster_config.enabled {
-        println!("  Gossip Port: {}", cluster_config.gossip_port);
-        println!("  Cluster Size: {}", cluster_config.cluster_size);
-        println!("  Peers: {:?}", cluster_config.peers);
-    }
-    println!();
 
     // Create state with replication config
     let mut state = ReplicatedShardedState::new(repl_config.clone());
@@ -674,8 +757,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             if !wal_entries.is_empty() {
                 let mut deltas = Vec::with_capacity(wal_entries.len());
                 for entry in &wal_entries {
-                    match entry.to_delta() {
-                        Ok(delta) => deltas.push(delta),
+                    match entry.to_deltas() {
+                        Ok(entry_deltas) => deltas.extend(entry_deltas),
                         Err(e) => {
                             warn!("Skipping corrupt WAL entry: {}", e);
                         }
@@ -690,12 +773,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         }
     }
 
+    if let Some(path) = import_path_from_args()? {
+        return run_import(&path, &config, integration.as_ref(), &state).await;
+    }
+
     // Only start persistence workers for non-memory store types.
     // Memory-mode pods don't need persistence — the streaming pipeline would accumulate
     // deltas in the InMemoryObjectStore's segment HashMap, growing unbounded (~300Mi/min).
     let worker_handles = if config.store_type != "memory" {
         let (handles, sender) = integration.start_workers().await?;
         state.set_delta_sink(sender);
+        state.set_persistence_health(handles.health());
         Some(handles)
     } else {
         info!("Skipping persistence pipeline for store_type=memory");
@@ -726,11 +814,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             };
 
             info!(
-                "WAL enabled: dir={}, fsync={:?}",
-                wc.wal_dir.display(),
-                wc.fsync_policy
+                dir = %wc.wal_dir.display(),
+                fsync = ?wc.fsync_policy,
+                "WAL enabled"
             );
-            println!("  WAL: enabled ({:?} fsync)", wc.fsync_policy);
 
             state.set_wal_handle(wal_handle.clone());
             Some((wal_handle, wal_join, tick_task))
@@ -741,8 +828,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         None
     };
 
+    // Components DEBUG HEALTH and /healthz report on
+    let (event_loop_lag, event_loop_task) = EventLoopLag::spawn();
+    let mut health_check = HealthCheck::new().with_event_loop(event_loop_lag);
+    if let Some(handles) = &worker_handles {
+        health_check = health_check.with_persistence(handles.health(), handles.exited_probe());
+    }
+    if let Some((wal_handle, wal_join, _)) = &wal_task {
+        health_check = health_check.with_wal(wal_handle.clone(), wal_join.abort_handle());
+    }
+    let gossip_received = Activity::new();
+    if cluster_config.enabled {
+        health_check = health_check.with_replication(gossip_received.clone());
+    }
+    let health_check = Arc::new(health_check);
+    state.set_health_check(health_check.clone());
+
     let state = Arc::new(state);
 
+    // Dropped at the end of main, which stops the watchdog thread
+    let _watchdog = WatchdogConfig::from_env().period.and_then(|period| {
+        let mut watchdog = Watchdog::new(period, state.shard_progress());
+        if let Some((wal_handle, wal_join, _)) = &wal_task {
+            let wal_handle = wal_handle.clone();
+            let wal_task = wal_join.abort_handle();
+            watchdog = watchdog.with_probe("wal", move || {
+                let state = if wal_task.is_finished() { "exited" } else { "running" };
+                format!("{}, {} queued", state, wal_handle.queue_depth())
+            });
+        }
+        if let Some(handles) = &worker_handles {
+            watchdog = watchdog.with_probe("streaming", handles.status_probe());
+        }
+        watchdog
+            .spawn()
+            .map_err(|e| error!("Failed to start watchdog: {}", e))
+            .ok()
+    });
+
     // Start gossip server and loop if replication is enabled
     if cluster_config.enabled {
         info!(
@@ -760,7 +883,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let gossip_port = cluster_config.gossip_port;
         let state_for_gossip = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = start_gossip_listener(gossip_port, state_for_gossip).await {
+            if let Err(e) =
+                start_gossip_listener(gossip_port, state_for_gossip, gossip_received).await
+            {
                 error!("Gossip server error: {}", e);
             }
         });
@@ -782,9 +907,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Gossip replication started");
     }
 
-    println!("Starting server...");
-    println!();
-
     // Start health check server on port+1
     let health_port = config.port + 1;
     let health_addr = format!("0.0.0.0:{}", health_port);
@@ -796,21 +918,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let listener = TcpListener::bind(&addr).await?;
 
     info!("Server listening on {}", addr);
-    println!("Server listening on {}", addr);
-    println!("Health check on {}", health_addr);
-    println!("Press Ctrl+C to shutdown gracefully");
-    println!();
+
+    let shutdown_config = ShutdownConfig::from_env();
+    let client_registry = Arc::new(ClientRegistry::new());
+    let mut connections = JoinSet::new();
+    let trigger = shutdown_trigger(&client_registry);
+    tokio::pin!(trigger);
 
     // Accept connections until shutdown
-    loop {
+    let cause = loop {
+        // Reap finished connection tasks so the set stays bounded
+        while connections.try_join_next().is_some() {}
+
         tokio::select! {
             // Main Redis connections
             result = listener.accept() => {
                 match result {
                     Ok((stream, addr)) => {
                         let state = state.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = handle_connection(stream, state).await {
+                        let registry = client_registry.clone();
+                        let session = registry.register(&addr.to_string(), None);
+                        connections.spawn(async move {
+                            if let Err(e) =
+                                handle_persistent_connection(stream, state, registry, session).await
+                            {
                                 error!("Connection error from {}: {}", addr, e);
                             }
                         });
@@ -824,8 +955,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             result = health_listener.accept() => {
                 match result {
                     Ok((stream, _)) => {
+                        let health_check = health_check.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_health_check(stream).await {
+                            if let Err(e) = handle_health_check(stream, &health_check).await {
                                 warn!("Health check error: {}", e);
                             }
                         });
@@ -835,13 +967,27 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                     }
                 }
             }
-            _ = signal::ctrl_c() => {
-                info!("Shutdown signal received");
-                println!("\nShutdown signal received, flushing data...");
-                break;
+            cause = &mut trigger => {
+                info!("{} received, draining clients and flushing data", cause);
+                break cause;
             }
         }
-    }
+    };
+
+    // Stop accepting, then drain. Once drain_clients returns no connection
+    // can acknowledge anything, so the flushes below cover every ack.
+    drop(listener);
+    drop(health_listener);
+    let report = drain_clients(
+        &client_registry,
+        &mut connections,
+        shutdown_config.drain_timeout,
+    )
+    .await;
+    info!(
+        "Client drain complete: {} drained, {} aborted",
+        report.drained, report.aborted
+    );
 
     // Graceful shutdown — WAL first (closest to write path), then streaming
     if let Some((wal_handle, wal_join, tick_task)) = wal_task {
@@ -858,129 +1004,154 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     if let Some(handles) = worker_handles {
         info!("Shutting down streaming persistence workers...");
         handles.shutdown().await;
+
+        if cause.save() {
+            info!("Writing shutdown checkpoint...");
+            match integration.write_checkpoint(&state).await {
+                Ok(Some(checkpoint)) => info!(
+                    "Shutdown checkpoint {} written ({} keys)",
+                    checkpoint.key, checkpoint.key_count
+                ),
+                Ok(None) => info!("No segments written, skipping shutdown checkpoint"),
+                Err(e) => error!("Shutdown checkpoint failed: {}", e),
+            }
+        } else {
+            info!("{}: skipping shutdown checkpoint", cause);
+        }
     }
 
+    event_loop_task.abort();
+
     // Shutdown observability (flush pending spans/metrics)
     shutdown();
 
-    println!("Server shutdown complete");
     info!("Server shutdown complete");
 
     Ok(())
 }
 
-async fn handle_health_check(
-    mut stream: TcpStream,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Read the HTTP request (we don't care about the content)
-    let mut buf = [0u8; 1024];
-    let _ = stream.read(&mut buf).await?;
-
-    // Send a simple HTTP 200 OK response
-    let response = "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nOK";
-    stream.write_all(response.as_bytes()).await?;
-    stream.flush().await?;
-
-    Ok(())
+/// The file after `--import`, if the flag was given
+fn import_path_from_args() -> Result<Option<PathBuf>, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--import" {
+            return args
+                .next()
+                .map(|path| Some(PathBuf::from(path)))
+                .ok_or_else(|| "--import needs a file path".to_string());
+        }
+    }
+    Ok(None)
 }
 
-async fn handle_connection(
-    mut stream: TcpStream,
-    state: Arc<ReplicatedShardedState>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Enable TCP_NODELAY for lower latency
-    let _ = stream.set_nodelay(true);
-
-    let mut read_buf = [0u8; 8192];
-    let mut buffer = BytesMut::with_capacity(4096);
-    let mut write_buffer = BytesMut::with_capacity(4096);
-
-    loop {
-        let n = stream.read(&mut read_buf).await?;
-        if n == 0 {
-            break;
+/// The two files after `--diff`, if the flag was given
+fn diff_paths_from_args() -> Result<Option<(PathBuf, PathBuf)>, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--diff" {
+            return match (args.next(), args.next()) {
+                (Some(before), Some(after)) => {
+                    Ok(Some((PathBuf::from(before), PathBuf::from(after))))
+                }
+                _ => Err("--diff needs two file paths".to_string()),
+            };
         }
+    }
+    Ok(None)
+}
 
-        buffer.extend_from_slice(&read_buf[..n]);
-
-        // Process all available commands (pipelining support)
-        loop {
-            match RespCodec::parse(&mut buffer) {
-                Ok(Some(resp_value)) => match Command::from_resp_zero_copy(&resp_value) {
-                    Ok(cmd) => {
-                        let response = state.execute(cmd).await;
-                        encode_resp_into(&response, &mut write_buffer);
-                    }
-                    Err(e) => {
-                        encode_error_into(&e, &mut write_buffer);
-                    }
-                },
-                Ok(None) => break, // Need more data
-                Err(e) => {
-                    encode_error_into(&format!("protocol error: {}", e), &mut write_buffer);
-                    buffer.clear();
-                    break;
-                }
+/// `--diff`: print the keyspace diff of two snapshots; true if they match
+fn run_diff(
+    before: &std::path::Path,
+    after: &std::path::Path,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let read = |path: &std::path::Path| -> Result<Snapshot, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Snapshot::read(&bytes).map_err(|e| format!("Invalid snapshot {}: {}", path.display(), e))
+    };
+    let (old, new) = (read(before)?, read(after)?);
+    for (path, snapshot) in [(before, &old), (after, &new)] {
+        println!(
+            "{}: {} with {} keys{}",
+            path.display(),
+            snapshot.format,
+            snapshot.keys.len(),
+            match snapshot.other_db_keys {
+                0 => String::new(),
+                n => format!(" ({} in other databases not compared)", n),
             }
-        }
+        );
+    }
+    let diff = old.diff(&new);
+    print!("{}", diff);
+    Ok(diff.is_empty())
+}
 
-        // Flush all responses
-        if !write_buffer.is_empty() {
-            stream.write_all(&write_buffer).await?;
-            stream.flush().await?;
-            write_buffer.clear();
-        }
+/// `--import`: bulk-load `path` into the recovered state and persist it
+/// with a single checkpoint
+async fn run_import(
+    path: &std::path::Path,
+    config: &Config,
+    integration: &dyn StreamingIntegrationTrait,
+    state: &ReplicatedShardedState,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if config.store_type == "memory" {
+        return Err("--import needs a persistent store (REDIS_STORE_TYPE=localfs or s3)".into());
     }
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read import file {}: {}", path.display(), e))?;
+    let now_unix_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let plan = read_import(&bytes, now_unix_ms)
+        .map_err(|e| format!("Invalid import file {}: {}", path.display(), e))?;
+    info!(
+        "Importing {}: {} keys to load, {} commands to execute, {} expired and {} in other databases skipped",
+        path.display(),
+        plan.loaded_keys(),
+        plan.executed_commands(),
+        plan.skipped_expired,
+        plan.skipped_other_dbs
+    );
 
+    let (report, failed) = state.import(plan).await;
+    if failed > 0 {
+        warn!("Import: {} commands replied with an error", failed);
+    }
+    let checkpoint = integration.write_import_checkpoint(state).await?;
+    info!(
+        loaded = report.loaded,
+        replaced = report.replaced,
+        skipped = report.skipped,
+        checkpoint = %checkpoint.key,
+        checkpoint_keys = checkpoint.key_count,
+        "Import complete"
+    );
     Ok(())
 }
 
-fn encode_resp_into(value: &RespValue, buf: &mut BytesMut) {
-    match value {
-        RespValue::SimpleString(s) => {
-            buf.put_u8(b'+');
-            buf.extend_from_slice(s.as_bytes());
-            buf.extend_from_slice(b"\r\n");
-        }
-        RespValue::Error(s) => {
-            buf.put_u8(b'-');
-            buf.extend_from_slice(s.as_bytes());
-            buf.extend_from_slice(b"\r\n");
-        }
-        RespValue::Integer(n) => {
-            buf.put_u8(b':');
-            buf.extend_from_slice(n.to_string().as_bytes());
-            buf.extend_from_slice(b"\r\n");
-        }
-        RespValue::BulkString(None) => {
-            buf.extend_from_slice(b"$-1\r\n");
-        }
-        RespValue::BulkString(Some(data)) => {
-            buf.put_u8(b'$');
-            buf.extend_from_slice(data.len().to_string().as_bytes());
-            buf.extend_from_slice(b"\r\n");
-            buf.extend_from_slice(data);
-            buf.extend_from_slice(b"\r\n");
-        }
-        RespValue::Array(None) => {
-            buf.extend_from_slice(b"*-1\r\n");
-        }
-        RespValue::Array(Some(elements)) => {
-            buf.put_u8(b'*');
-            buf.extend_from_slice(elements.len().to_string().as_bytes());
-            buf.extend_from_slice(b"\r\n");
-            for elem in elements {
-                encode_resp_into(elem, buf);
-            }
-        }
-    }
-}
+async fn handle_health_check(
+    mut stream: TcpStream,
+    health_check: &HealthCheck,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Only the request line matters: `GET /healthz HTTP/1.1`
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
 
-fn encode_error_into(msg: &str, buf: &mut BytesMut) {
-    buf.put_u8(b'-');
-    buf.extend_from_slice(b"ERR ");
-    buf.extend_from_slice(msg.as_bytes());
-    buf.extend_from_slice(b"\r\n");
+    let response = if path == "/healthz" {
+        health_check.report().to_http()
+    } else {
+        // Plain liveness, as the Docker HEALTHCHECK probes `/`
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nOK".to_string()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await?;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -0,0 +1,83 @@
+//! Workload Replay
+//!
+//! Replays a MONITOR or audit-log capture from production against the
+//! simulated server, deterministically and in virtual time, and prints how
+//! it coped (see `redis_sim::simulator::replay`).
+//!
+//! ## Usage
+//!
+//! ```bash
+//! # Capture a minute of production traffic
+//! timeout 60 redis-cli -h prod MONITOR > capture.txt
+//!
+//! # Replay it as recorded, then ten times faster with 1ms per command
+//! workload-replay capture.txt
+//! REPLAY_SPEEDUP=10 REPLAY_LATENCY_MS=1 workload-replay capture.txt
+//! ```
+//!
+//! ## Environment Variables
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | REPLAY_FORMAT | monitor | Capture format: monitor or audit |
+//! | REPLAY_SPEEDUP | 1 | Time compression factor |
+//! | REPLAY_SEED | 42 | Simulation seed |
+//! | REPLAY_LATENCY_MS | 0 | Execution time charged per command |
+
+use redis_sim::simulator::{
+    replay_workload, CaptureFormat, LatencyDistribution, LatencyProfile, ReplayConfig,
+    WorkloadCapture,
+};
+use std::process::ExitCode;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> Result<T, String> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| format!("{}: invalid value '{}'", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+fn run() -> Result<(), String> {
+    let path = std::env::args()
+        .nth(1)
+        .ok_or("usage: workload-replay <capture-file>")?;
+    let format: CaptureFormat = env_or("REPLAY_FORMAT", CaptureFormat::Monitor)?;
+    let speedup: u64 = env_or("REPLAY_SPEEDUP", 1)?;
+    if speedup == 0 {
+        return Err("REPLAY_SPEEDUP must be at least 1".to_string());
+    }
+    let config = ReplayConfig {
+        seed: env_or("REPLAY_SEED", 42)?,
+        speedup,
+        latency: LatencyProfile::new(LatencyDistribution::fixed_ms(env_or(
+            "REPLAY_LATENCY_MS",
+            0,
+        )?)),
+    };
+
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let capture = WorkloadCapture::parse(&text, format).map_err(|e| format!("{}: {}", path, e))?;
+    println!(
+        "{}: {} commands from {} clients over {}ms ({} lines skipped)",
+        path,
+        capture.commands.len(),
+        capture.num_clients(),
+        capture.duration_us() / 1000,
+        capture.skipped_lines
+    );
+    println!("replaying at {}x, seed {}", config.speedup, config.seed);
+    print!("{}", replay_workload(&capture, &config));
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -104,6 +104,9 @@ impl FaultConfig {
         config.set(faults::replication::SPLIT_BRAIN, 0.0001); // 0.01%
         config.set(faults::replication::STALE_REPLICA, 0.01); // 1%
 
+        // Protocol faults
+        config.set(faults::protocol::SHRINK_LIMITS, 0.005); // 0.5%
+
         config
     }
 
@@ -153,6 +156,9 @@ impl FaultConfig {
         config.set(faults::replication::SPLIT_BRAIN, 0.001); // 0.1%
         config.set(faults::replication::STALE_REPLICA, 0.05); // 5%
 
+        // Protocol faults (elevated)
+        config.set(faults::protocol::SHRINK_LIMITS, 0.02); // 2%
+
         config
     }
 
@@ -91,6 +91,10 @@ pub mod object_store {
     pub const RENAME_FAIL: &str = "object_store.rename_fail";
     /// Slow object store response
     pub const SLOW: &str = "object_store.slow";
+    /// Whole-store (region) outage begins
+    pub const OUTAGE: &str = "object_store.outage";
+    /// Burst of 503 SlowDown throttling begins
+    pub const THROTTLE: &str = "object_store.throttle";
 }
 
 /// Replication faults - distributed system chaos
@@ -107,6 +111,13 @@ pub mod replication {
     pub const STALE_REPLICA: &str = "replication.stale_replica";
 }
 
+/// Protocol faults - request framing and limits
+pub mod protocol {
+    /// Parse a read under `ProtocolLimits::shrunk()`, so ordinary requests
+    /// hit the protocol-error and disconnect paths
+    pub const SHRINK_LIMITS: &str = "protocol.shrink_limits";
+}
+
 /// All fault identifiers for iteration
 pub const ALL_FAULTS: &[&str] = &[
     // Network
@@ -149,18 +160,34 @@ pub const ALL_FAULTS: &[&str] = &[
     object_store::LIST_INCOMPLETE,
     object_store::RENAME_FAIL,
     object_store::SLOW,
+    object_store::OUTAGE,
+    object_store::THROTTLE,
     // Replication
     replication::GOSSIP_DROP,
     replication::GOSSIP_DELAY,
     replication::GOSSIP_CORRUPT,
     replication::SPLIT_BRAIN,
     replication::STALE_REPLICA,
+    // Protocol
+    protocol::SHRINK_LIMITS,
 ];
 
+/// Resolve a fault name given at runtime (e.g. `DEBUG BUGGIFY SET`) to its
+/// identifier, or `None` if no such fault exists
+pub fn lookup(name: &str) -> Option<&'static str> {
+    ALL_FAULTS.iter().copied().find(|fault| *fault == name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("network.packet_drop"), Some(network::PACKET_DROP));
+        assert_eq!(lookup("network.no_such_fault"), None);
+    }
+
     #[test]
     fn test_all_faults_unique() {
         let mut seen = std::collections::HashSet::new();
@@ -137,6 +137,24 @@ pub fn set_config(config: FaultConfig) {
     });
 }
 
+/// Set one fault's base probability for the current thread, leaving the
+/// rest of the configuration (and the stats) untouched
+pub fn set_fault_probability(fault_id: &'static str, probability: f64) {
+    debug_assert!(
+        (0.0..=1.0).contains(&probability),
+        "Precondition: probability must be in [0, 1]"
+    );
+    BUGGIFY_CONTEXT.with(|ctx| {
+        ctx.borrow_mut().config.set(fault_id, probability);
+    });
+}
+
+/// Effective probability of a fault on the current thread (after the global
+/// multiplier)
+pub fn fault_probability(fault_id: &str) -> f64 {
+    BUGGIFY_CONTEXT.with(|ctx| ctx.borrow().config.get(fault_id))
+}
+
 /// Get current buggify stats for the thread
 pub fn get_stats() -> BuggifyStats {
     BUGGIFY_CONTEXT.with(|ctx| ctx.borrow().stats.clone())
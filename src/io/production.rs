@@ -15,6 +15,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::time;
+use tracing::warn;
 
 /// Zero-cost production time source
 ///
@@ -245,7 +246,7 @@ impl ProductionRng {
     fn lock_inner(&self) -> std::sync::MutexGuard<'_, rand::rngs::StdRng> {
         self.inner.lock().unwrap_or_else(|poisoned| {
             // Log the poison but recover - RNG state is still usable
-            eprintln!("ProductionRng: recovering from poisoned lock");
+            warn!("Production RNG lock poisoned; recovering its state");
             poisoned.into_inner()
         })
     }
@@ -56,12 +56,15 @@
 
 pub mod buggify;
 pub mod io;
+pub mod logging;
 pub mod production;
 pub mod redis;
+pub mod release;
 pub mod replication;
 pub mod security;
 pub mod simulator;
 pub mod streaming;
+mod sync;
 
 // Stateright model checking (exhaustive state exploration)
 // Run with: cargo test stateright -- --ignored --nocapture
@@ -0,0 +1,201 @@
+//! Log levels: a default plus per-subsystem overrides.
+//!
+//! A spec is comma separated. A bare level sets the default and
+//! `<subsystem>=<level>` overrides it below one target, so
+//! `notice,replication=debug` logs replication at debug and everything
+//! else at notice. A subsystem is a short name from `SUBSYSTEMS` or any
+//! target path, matched as a prefix the way `RUST_LOG` matches it.
+//!
+//! | Level | tracing |
+//! |-------|---------|
+//! | `debug` | DEBUG |
+//! | `verbose`, `notice` | INFO |
+//! | `warning` | WARN |
+//! | `nothing` | OFF |
+//!
+//! tracing's own names (`trace`, `info`, `warn`, `error`, `off`) work too.
+//!
+//! # TigerStyle Invariants
+//!
+//! - A spec renders back to a spec that parses to the same levels
+//! - A target has at most one override; a later one replaces it
+
+use std::fmt;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+
+/// Short names for the targets operators tune most often
+pub const SUBSYSTEMS: &[(&str, &str)] = &[
+    ("simulator", "redis_sim::simulator"),
+    ("production", "redis_sim::production"),
+    ("persistence", "redis_sim::streaming"),
+    ("replication", "redis_sim::replication"),
+    ("executor", "redis_sim::redis"),
+    ("security", "redis_sim::security"),
+];
+
+/// Redis's level names, then tracing's
+const LEVEL_NAMES: &[(&str, LevelFilter)] = &[
+    ("debug", LevelFilter::DEBUG),
+    ("verbose", LevelFilter::INFO),
+    ("notice", LevelFilter::INFO),
+    ("warning", LevelFilter::WARN),
+    ("nothing", LevelFilter::OFF),
+    ("trace", LevelFilter::TRACE),
+    ("info", LevelFilter::INFO),
+    ("warn", LevelFilter::WARN),
+    ("error", LevelFilter::ERROR),
+    ("off", LevelFilter::OFF),
+];
+
+fn level(name: &str) -> Result<(&'static str, LevelFilter), String> {
+    let name = name.trim().to_ascii_lowercase();
+    LEVEL_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .copied()
+        .ok_or_else(|| format!("invalid log level '{}'", name))
+}
+
+/// The target a subsystem name stands for
+fn target(subsystem: &str) -> &str {
+    SUBSYSTEMS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(subsystem))
+        .map_or(subsystem, |(_, target)| *target)
+}
+
+/// A parsed level spec
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLevels {
+    default: &'static str,
+    /// (subsystem as written, level name)
+    overrides: Vec<(String, &'static str)>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        LogLevels {
+            default: "notice",
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl LogLevels {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut levels = LogLevels::default();
+        let mut directives = spec.split(',').map(str::trim).filter(|d| !d.is_empty());
+        let Some(first) = directives.next() else {
+            return Err("log level spec is empty".to_string());
+        };
+        for directive in std::iter::once(first).chain(directives) {
+            match directive.split_once('=') {
+                None => levels.default = level(directive)?.0,
+                Some((subsystem, name)) => {
+                    let subsystem = subsystem.trim();
+                    if subsystem.is_empty() {
+                        return Err(format!("missing subsystem in '{}'", directive));
+                    }
+                    let name = level(name)?.0;
+                    levels
+                        .overrides
+                        .retain(|(s, _)| target(s) != target(subsystem));
+                    levels.overrides.push((subsystem.to_string(), name));
+                }
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        levels.verify_invariants();
+
+        Ok(levels)
+    }
+
+    /// The filter these levels describe
+    pub fn to_targets(&self) -> Targets {
+        let filter = |name: &str| level(name).expect("stored levels are valid").1;
+        Targets::new()
+            .with_default(filter(self.default))
+            .with_targets(
+                self.overrides
+                    .iter()
+                    .map(|(subsystem, name)| (target(subsystem).to_string(), filter(name))),
+            )
+    }
+
+    /// The level events from `target_path` must reach to be logged; the
+    /// longest matching override wins, as in `Targets`
+    pub fn level_for(&self, target_path: &str) -> LevelFilter {
+        let name = self
+            .overrides
+            .iter()
+            .filter(|(subsystem, _)| target_path.starts_with(target(subsystem)))
+            .max_by_key(|(subsystem, _)| target(subsystem).len())
+            .map_or(self.default, |(_, name)| *name);
+        level(name).expect("stored levels are valid").1
+    }
+
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        for (i, (subsystem, _)) in self.overrides.iter().enumerate() {
+            debug_assert!(
+                self.overrides[..i]
+                    .iter()
+                    .all(|(s, _)| target(s) != target(subsystem)),
+                "Invariant violated: target of '{}' has two overrides",
+                subsystem
+            );
+        }
+    }
+}
+
+impl fmt::Display for LogLevels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.default)?;
+        for (subsystem, name) in &self.overrides {
+            write!(f, ",{}={}", subsystem, name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_apply_below_their_subsystem() {
+        let levels =
+            LogLevels::parse("warning, replication=debug,redis_sim::streaming=nothing").unwrap();
+        assert_eq!(
+            levels.level_for("redis_sim::replication::gossip"),
+            LevelFilter::DEBUG
+        );
+        assert_eq!(
+            levels.level_for("redis_sim::streaming::wal"),
+            LevelFilter::OFF
+        );
+        assert_eq!(
+            levels.level_for("redis_sim::production::server"),
+            LevelFilter::WARN
+        );
+        assert_eq!(
+            levels.to_string(),
+            "warning,replication=debug,redis_sim::streaming=nothing"
+        );
+        assert_eq!(LogLevels::parse(&levels.to_string()), Ok(levels));
+    }
+
+    #[test]
+    fn test_later_directives_win_and_bad_specs_fail() {
+        let levels = LogLevels::parse("INFO,persistence=debug,redis_sim::streaming=warn").unwrap();
+        assert_eq!(levels.to_string(), "info,redis_sim::streaming=warn");
+        assert_eq!(
+            LogLevels::parse("loud"),
+            Err("invalid log level 'loud'".to_string())
+        );
+        assert!(LogLevels::parse(" , ").is_err());
+        assert!(LogLevels::parse("=debug").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Structured logging: per-subsystem levels, text or JSON, rotated files.
+//!
+//! Library code only emits `tracing` events; binaries install the
+//! subscriber. `init` installs a filter and a formatter; with Datadog,
+//! `observability::init_tracing` stacks its APM layer on the same
+//! `filter_layer` and `fmt_layer`.
+//!
+//! The filter is reloadable: `set_levels` swaps it on a running server, and
+//! `CONFIG SET loglevel` calls it (see `levels.rs` for the spec). Without an
+//! installed subscriber, as in simulation, `set_levels` only validates.
+//!
+//! # Environment Variables
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | `REDIS_LOG_LEVEL` | `RUST_LOG`, else `notice` | Level spec |
+//! | `REDIS_LOG_FORMAT` | `text` | `text` or `json` |
+//! | `REDIS_LOG_FILE` | unset (stdout) | Log file, rotated by size |
+//! | `REDIS_LOG_MAX_BYTES` | `67108864` | Size at which the file rotates |
+//! | `REDIS_LOG_MAX_FILES` | `5` | Rotated files kept (`<file>.1` newest) |
+//!
+//! Modules:
+//! - `levels.rs`: Level specs and the subsystem names
+//! - `rotation.rs`: The size-rotated file sink
+
+mod levels;
+mod rotation;
+
+pub use levels::{LogLevels, SUBSYSTEMS};
+pub use rotation::RotatingFile;
+
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::Subscriber;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Layer, Registry};
+
+/// Line format of the log sink
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable, one event per line
+    #[default]
+    Text,
+    /// One JSON object per event, fields and span context included
+    Json,
+}
+
+/// Logging configuration, loaded from environment variables
+#[derive(Clone, Debug)]
+pub struct LogConfig {
+    pub levels: LogLevels,
+    pub format: LogFormat,
+    /// Log file (None = stdout)
+    pub file: Option<PathBuf>,
+    pub max_file_bytes: u64,
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            levels: LogLevels::default(),
+            format: LogFormat::Text,
+            file: None,
+            max_file_bytes: 64 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Load from the environment; an invalid level spec is an error rather
+    /// than a silently different level
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = LogConfig::default();
+        let spec = std::env::var("REDIS_LOG_LEVEL").or_else(|_| std::env::var("RUST_LOG"));
+        let levels = match spec {
+            Ok(spec) => LogLevels::parse(&spec)?,
+            Err(_) => defaults.levels,
+        };
+        let format = match std::env::var("REDIS_LOG_FORMAT").as_deref() {
+            Ok("json") => LogFormat::Json,
+            Ok("text") | Err(_) => LogFormat::Text,
+            Ok(other) => return Err(format!("invalid REDIS_LOG_FORMAT '{}'", other)),
+        };
+        Ok(LogConfig {
+            levels,
+            format,
+            file: std::env::var("REDIS_LOG_FILE").ok().map(PathBuf::from),
+            max_file_bytes: std::env::var("REDIS_LOG_MAX_BYTES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.max_file_bytes),
+            max_files: std::env::var("REDIS_LOG_MAX_FILES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.max_files),
+        })
+    }
+}
+
+/// Swaps the installed filter; set once by `filter_layer`
+static RELOAD: OnceLock<reload::Handle<Targets, Registry>> = OnceLock::new();
+
+/// The reloadable level filter. Must be the first layer on the registry.
+pub fn filter_layer(levels: &LogLevels) -> reload::Layer<Targets, Registry> {
+    let (layer, handle) = reload::Layer::new(levels.to_targets());
+    if RELOAD.set(handle).is_err() {
+        tracing::warn!("log filter installed twice; CONFIG SET loglevel drives the first");
+    }
+    layer
+}
+
+/// The event formatter writing to stdout or the rotated log file
+pub fn fmt_layer<S>(config: &LogConfig) -> std::io::Result<Box<dyn Layer<S> + Send + Sync>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let layer = tracing_subscriber::fmt::layer();
+    Ok(match (&config.file, config.format) {
+        (None, LogFormat::Text) => layer.boxed(),
+        (None, LogFormat::Json) => layer.json().boxed(),
+        (Some(path), format) => {
+            let file = RotatingFile::open(path, config.max_file_bytes, config.max_files)?;
+            let layer = layer.with_ansi(false).with_writer(Mutex::new(file));
+            match format {
+                LogFormat::Text => layer.boxed(),
+                LogFormat::Json => layer.json().boxed(),
+            }
+        }
+    })
+}
+
+/// Install the logging subscriber for a binary without Datadog
+pub fn init(config: &LogConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    tracing_subscriber::registry()
+        .with(filter_layer(&config.levels))
+        .with(fmt_layer(config)?)
+        .try_init()?;
+    Ok(())
+}
+
+/// Apply a level spec to the running subscriber, as `CONFIG SET loglevel`
+pub fn set_levels(levels: &LogLevels) -> Result<(), String> {
+    match RELOAD.get() {
+        Some(handle) => handle
+            .reload(levels.to_targets())
+            .map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,132 @@
+//! Size-based rotation for the log file sink.
+//!
+//! `RotatingFile` appends to `<path>` until a write would take it past
+//! `max_bytes`. It then renames `<path>.1` .. `<path>.<n-1>` up by one,
+//! renames `<path>` to `<path>.1` and starts a fresh `<path>`, so `.1` is
+//! always the newest rotated file. At most `max_files` rotated files are
+//! kept and the oldest is deleted. With `max_files` 0 the file is truncated
+//! instead.
+//!
+//! The fmt layer writes each event with one `write_all`, and a write is never
+//! split across files, so every line lands whole in one file. A single event
+//! larger than `max_bytes` makes a file larger than the limit.
+//!
+//! # TigerStyle Invariants
+//!
+//! - `written` is the size of the current file
+//! - The current file only passes `max_bytes` by a single write
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    /// Bytes in the current file, including what was there at open
+    written: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl RotatingFile {
+    /// Open `path` for appending, continuing a file a previous run left
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        debug_assert!(max_bytes > 0, "Precondition: max_bytes must be positive");
+
+        let path = path.into();
+        let file = append(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            written,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    /// `<path>.<n>`
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        name.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            match fs::remove_file(self.rotated_path(self.max_files)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            for n in (1..self.max_files).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = append(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_the_newest_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("server.log");
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["line one\n", "line two\n", "line three\n", "line four\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line four\n");
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(1)).unwrap(),
+            "line three\n"
+        );
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(2)).unwrap(),
+            "line two\n"
+        );
+        assert!(!file.rotated_path(3).exists());
+
+        // Reopening continues the current file and its size
+        drop(file);
+        let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+        file.write_all(b"five\n").unwrap();
+        assert_eq!(
+            fs::read_to_string(file.rotated_path(1)).unwrap(),
+            "line four\n"
+        );
+    }
+}
@@ -24,6 +24,7 @@ fn test_basic_operations() {
         seed: 42,
         max_time: VirtualTime::from_secs(10),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
@@ -81,6 +82,7 @@ fn run_simulation_with_seed(seed: u64) -> Vec<u64> {
         seed,
         max_time: VirtualTime::from_secs(5),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
@@ -100,6 +102,7 @@ fn test_network_faults() {
         seed: 99,
         max_time: VirtualTime::from_secs(10),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
@@ -154,6 +157,7 @@ fn test_ttl_expiration() {
         seed: 123,
         max_time: VirtualTime::from_secs(20),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
@@ -208,6 +212,7 @@ fn test_atomic_counters() {
         seed: 456,
         max_time: VirtualTime::from_secs(10),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
@@ -263,6 +268,7 @@ fn test_advanced_string_ops() {
         seed: 789,
         max_time: VirtualTime::from_secs(10),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
@@ -321,6 +327,7 @@ fn test_buggify() {
         seed: 777,
         max_time: VirtualTime::from_secs(1),
         simulation_start_epoch: 0,
+        ..Default::default()
     };
 
     let mut sim = Simulation::new(config);
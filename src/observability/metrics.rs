@@ -193,6 +193,46 @@ impl Metrics {
             self.histogram("ttl.evictions.batch_size", count as f64, &[]);
         }
     }
+
+    /// Sample how many keys expire within each horizon, plus keys with a TTL
+    #[inline]
+    pub fn record_expiry_outlook(&self, volatile_keys: u64, expiring: &[(&str, u64)]) {
+        self.gauge("ttl.volatile_keys", volatile_keys as f64, &[]);
+        for (horizon, count) in expiring {
+            let within_tag = format!("within:{}", horizon);
+            self.gauge("ttl.expiring_keys", *count as f64, &[&within_tag]);
+        }
+    }
+
+    /// Record one anti-entropy round with a peer and the interval until the next
+    #[inline]
+    pub fn record_anti_entropy_round(
+        &self,
+        peer: u64,
+        bytes_sent: u64,
+        bytes_received: u64,
+        keys_exchanged: u64,
+        next_interval_ms: u64,
+    ) {
+        let peer_tag = format!("peer:{}", peer);
+        self.incr("anti_entropy.rounds", &[&peer_tag]);
+        self.histogram("anti_entropy.bytes_sent", bytes_sent as f64, &[&peer_tag]);
+        self.histogram(
+            "anti_entropy.bytes_received",
+            bytes_received as f64,
+            &[&peer_tag],
+        );
+        self.histogram(
+            "anti_entropy.keys_exchanged",
+            keys_exchanged as f64,
+            &[&peer_tag],
+        );
+        self.gauge(
+            "anti_entropy.sync_interval_ms",
+            next_interval_ms as f64,
+            &[&peer_tag],
+        );
+    }
 }
 
 // Implement MetricsRecorder trait for DST compatibility
@@ -232,6 +272,28 @@ impl MetricsRecorder for Metrics {
     fn record_ttl_eviction(&self, count: usize) {
         Metrics::record_ttl_eviction(self, count)
     }
+
+    fn record_expiry_outlook(&self, volatile_keys: u64, expiring: &[(&str, u64)]) {
+        Metrics::record_expiry_outlook(self, volatile_keys, expiring)
+    }
+
+    fn record_anti_entropy_round(
+        &self,
+        peer: u64,
+        bytes_sent: u64,
+        bytes_received: u64,
+        keys_exchanged: u64,
+        next_interval_ms: u64,
+    ) {
+        Metrics::record_anti_entropy_round(
+            self,
+            peer,
+            bytes_sent,
+            bytes_received,
+            keys_exchanged,
+            next_interval_ms,
+        )
+    }
 }
 
 #[cfg(test)]
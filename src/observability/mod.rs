@@ -52,6 +52,6 @@ pub use tracing_setup::{init as init_tracing, shutdown};
 
 // DST-compatible metrics abstractions
 pub use recorder::{
-    noop_metrics, simulated_metrics, MetricType, MetricsRecorder, NoopMetrics, RecordedMetric,
-    SharedMetrics, SimulatedMetrics,
+    noop_metrics, simulated_metrics, MetricType, MetricsQuery, MetricsRecorder, NoopMetrics,
+    RecordedMetric, SharedMetrics, SimulatedMetrics,
 };
@@ -7,6 +7,7 @@
 //! Following TigerStyle principles: all I/O through trait abstractions.
 
 use parking_lot::Mutex;
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -54,6 +55,45 @@ pub trait MetricsRecorder: Send + Sync + 'static {
             self.histogram("ttl.evictions.batch_size", count as f64, &[]);
         }
     }
+
+    /// Sample how many keys expire within each horizon (e.g. `1m`), one
+    /// gauge series per horizon, plus the number of keys with a TTL
+    fn record_expiry_outlook(&self, volatile_keys: u64, expiring: &[(&str, u64)]) {
+        self.gauge("ttl.volatile_keys", volatile_keys as f64, &[]);
+        for (horizon, count) in expiring {
+            let within_tag = format!("within:{}", horizon);
+            self.gauge("ttl.expiring_keys", *count as f64, &[&within_tag]);
+        }
+    }
+
+    /// Record one anti-entropy round with a peer and the interval until the next
+    fn record_anti_entropy_round(
+        &self,
+        peer: u64,
+        bytes_sent: u64,
+        bytes_received: u64,
+        keys_exchanged: u64,
+        next_interval_ms: u64,
+    ) {
+        let peer_tag = format!("peer:{}", peer);
+        self.incr("anti_entropy.rounds", &[&peer_tag]);
+        self.histogram("anti_entropy.bytes_sent", bytes_sent as f64, &[&peer_tag]);
+        self.histogram(
+            "anti_entropy.bytes_received",
+            bytes_received as f64,
+            &[&peer_tag],
+        );
+        self.histogram(
+            "anti_entropy.keys_exchanged",
+            keys_exchanged as f64,
+            &[&peer_tag],
+        );
+        self.gauge(
+            "anti_entropy.sync_interval_ms",
+            next_interval_ms as f64,
+            &[&peer_tag],
+        );
+    }
 }
 
 /// No-op metrics recorder - zero overhead when metrics are disabled
@@ -213,6 +253,77 @@ impl MetricsRecorder for SimulatedMetrics {
     }
 }
 
+/// A dashboard query over one metric: its points, optionally filtered by
+/// tag and split into one series per value of a tag key.
+///
+/// `run` answers it from a `SimulatedMetrics` recording, so a test can
+/// assert on the series a dashboard would plot; `to_datadog` renders the
+/// same query for a Datadog widget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsQuery {
+    name: String,
+    tags: Vec<String>,
+    group_by: Option<String>,
+}
+
+impl MetricsQuery {
+    pub fn new(name: &str) -> Self {
+        MetricsQuery {
+            name: name.to_string(),
+            tags: Vec::new(),
+            group_by: None,
+        }
+    }
+
+    /// Only points carrying `tag` (`key:value`)
+    pub fn tagged(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// One series per value of the `key` tag
+    pub fn by(mut self, key: &str) -> Self {
+        self.group_by = Some(key.to_string());
+        self
+    }
+
+    /// Points in recording order, per group value ("*" when ungrouped)
+    pub fn run(&self, metrics: &SimulatedMetrics) -> BTreeMap<String, Vec<f64>> {
+        let mut series: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for metric in metrics.get_by_name(&self.name) {
+            if !self.tags.iter().all(|tag| metric.tags.contains(tag)) {
+                continue;
+            }
+            let group = match &self.group_by {
+                None => "*".to_string(),
+                Some(key) => {
+                    let prefix = format!("{}:", key);
+                    match metric.tags.iter().find_map(|t| t.strip_prefix(&prefix)) {
+                        Some(value) => value.to_string(),
+                        None => continue,
+                    }
+                }
+            };
+            series.entry(group).or_default().push(metric.value);
+        }
+        series
+    }
+
+    /// The same query as a Datadog metric query under `prefix`
+    pub fn to_datadog(&self, prefix: &str) -> String {
+        let scope = if self.tags.is_empty() {
+            "*".to_string()
+        } else {
+            self.tags.join(",")
+        };
+        let mut query = format!("max:{}.{}{{{}}}", prefix, self.name, scope);
+        if let Some(key) = &self.group_by {
+            query.push_str(&format!(" by {{{}}}", key));
+        }
+        query
+    }
+}
+
 /// Arc wrapper for trait object usage
 pub type SharedMetrics = Arc<dyn MetricsRecorder>;
 
@@ -261,6 +372,59 @@ mod tests {
         assert_eq!(durations.len(), 3);
     }
 
+    #[test]
+    fn test_simulated_metrics_anti_entropy_round() {
+        let metrics = SimulatedMetrics::new();
+
+        metrics.record_anti_entropy_round(2, 6000, 6100, 12, 250);
+
+        assert_eq!(metrics.get_by_name("anti_entropy.rounds").len(), 1);
+        let interval = metrics.get_by_name("anti_entropy.sync_interval_ms");
+        assert_eq!(interval[0].value, 250.0);
+        assert_eq!(interval[0].tags, vec!["peer:2".to_string()]);
+    }
+
+    #[test]
+    fn test_simulated_metrics_expiry_outlook() {
+        let metrics = SimulatedMetrics::new();
+
+        metrics.record_expiry_outlook(40, &[("1m", 3), ("5m", 10), ("1h", 25)]);
+
+        assert_eq!(metrics.get_by_name("ttl.volatile_keys")[0].value, 40.0);
+        let expiring = metrics.get_by_name("ttl.expiring_keys");
+        assert_eq!(expiring.len(), 3);
+        assert_eq!(expiring[1].value, 10.0);
+        assert_eq!(expiring[1].tags, vec!["within:5m".to_string()]);
+    }
+
+    #[test]
+    fn test_metrics_query_plots_an_expiry_wave() {
+        let metrics = SimulatedMetrics::new();
+
+        // A batch written with a 1h TTL moves down the horizons as it nears
+        metrics.record_expiry_outlook(100, &[("1m", 0), ("5m", 0), ("1h", 0)]);
+        metrics.record_expiry_outlook(100, &[("1m", 0), ("5m", 0), ("1h", 90)]);
+        metrics.record_expiry_outlook(100, &[("1m", 0), ("5m", 90), ("1h", 90)]);
+        metrics.record_expiry_outlook(100, &[("1m", 90), ("5m", 90), ("1h", 90)]);
+
+        let query = MetricsQuery::new("ttl.expiring_keys").by("within");
+        let series = query.run(&metrics);
+        assert_eq!(series["1h"], vec![0.0, 90.0, 90.0, 90.0]);
+        assert_eq!(series["5m"], vec![0.0, 0.0, 90.0, 90.0]);
+        assert_eq!(series["1m"], vec![0.0, 0.0, 0.0, 90.0]);
+        assert_eq!(
+            query.to_datadog("redis_rust"),
+            "max:redis_rust.ttl.expiring_keys{*} by {within}"
+        );
+
+        let imminent = MetricsQuery::new("ttl.expiring_keys").tagged("within:1m");
+        assert_eq!(imminent.run(&metrics)["*"], vec![0.0, 0.0, 0.0, 90.0]);
+        assert_eq!(
+            imminent.to_datadog("redis_rust"),
+            "max:redis_rust.ttl.expiring_keys{within:1m}"
+        );
+    }
+
     #[test]
     fn test_simulated_metrics_connection_tracking() {
         let metrics = SimulatedMetrics::new();
@@ -1,6 +1,7 @@
 //! Tracing and APM Setup
 //!
-//! Initializes tracing-subscriber with OpenTelemetry for Datadog APM.
+//! Initializes tracing-subscriber with OpenTelemetry for Datadog APM, on top
+//! of the level filter and formatter from `crate::logging`.
 
 use opentelemetry_datadog::DatadogPropagator;
 use opentelemetry_sdk::trace::Sampler;
@@ -15,7 +16,7 @@ use super::config::DatadogConfig;
 ///
 /// Sets up:
 /// - OpenTelemetry with Datadog exporter for distributed tracing
-/// - tracing-subscriber with environment-based filtering
+/// - tracing-subscriber with the reloadable level filter and log sink
 /// - Gossip/replication spans are excluded from OTel to prevent unbounded
 ///   memory growth from the batch exporter accumulating high-frequency spans
 pub fn init(config: &DatadogConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -52,16 +53,20 @@ pub fn init(config: &DatadogConfig) -> Result<(), Box<dyn std::error::Error + Se
                 .with_target("redis_sim::replication::anti_entropy", tracing::Level::ERROR)
         );
 
-    // Environment filter for log levels (applies to fmt layer — gossip still logs to stdout)
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    // Level filter and formatter shared with non-Datadog builds (see
+    // `crate::logging`). DD_LOGS_INJECTION switches the formatter to JSON.
+    let mut log_config = crate::logging::LogConfig::from_env()?;
+    if config.logs_injection {
+        log_config.format = crate::logging::LogFormat::Json;
+    }
 
     // Build subscriber with all layers
-    // fmt layer: logs everything per env_filter (including gossip — visible in kubectl logs)
+    // filter: reloadable levels (CONFIG SET loglevel), applies to every layer
+    // fmt layer: logs everything the filter passes (including gossip — visible in kubectl logs)
     // otel layer: excludes gossip targets (prevents span accumulation OOM)
     tracing_subscriber::registry()
-        .with(env_filter)
-        .with(tracing_subscriber::fmt::layer())
+        .with(crate::logging::filter_layer(&log_config.levels))
+        .with(crate::logging::fmt_layer(&log_config)?)
         .with(otel_layer)
         .init();
 
@@ -22,6 +22,15 @@ pub trait MetricsRecorder: Send + Sync + 'static {
     fn record_connection(&self, _event: &str) {}
     fn set_connections(&self, _count: usize) {}
     fn record_ttl_eviction(&self, _count: usize) {}
+    fn record_anti_entropy_round(
+        &self,
+        _peer: u64,
+        _bytes_sent: u64,
+        _bytes_received: u64,
+        _keys_exchanged: u64,
+        _next_interval_ms: u64,
+    ) {
+    }
 }
 
 /// Arc wrapper for trait object usage
@@ -92,6 +101,20 @@ impl Metrics {
     #[inline(always)]
     pub fn record_ttl_eviction(&self, _count: usize) {}
 
+    #[inline(always)]
+    pub fn record_expiry_outlook(&self, _volatile_keys: u64, _expiring: &[(&str, u64)]) {}
+
+    #[inline(always)]
+    pub fn record_anti_entropy_round(
+        &self,
+        _peer: u64,
+        _bytes_sent: u64,
+        _bytes_received: u64,
+        _keys_exchanged: u64,
+        _next_interval_ms: u64,
+    ) {
+    }
+
     #[inline(always)]
     pub fn timer(&self, _name: &'static str) -> Timer {
         Timer {
@@ -135,14 +158,11 @@ impl DatadogConfig {
     }
 }
 
-/// No-op tracing initialization - uses basic fmt subscriber
+/// Tracing without APM - just the logging subscriber (see `crate::logging`)
 pub fn init_tracing(
     _config: &DatadogConfig,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .try_init()
-        .map_err(|e| e.to_string().into())
+    crate::logging::init(&crate::logging::LogConfig::from_env()?)
 }
 
 /// No-op shutdown
@@ -0,0 +1,632 @@
+//! Client Session Registry
+//!
+//! Tracks every live connection and the ACL user it is authenticated as, so
+//! that ACL mutations can revoke sessions the way real Redis does: deleting a
+//! user (ACL DELUSER) or disabling it (ACL SETUSER ... off) terminates every
+//! connection authenticated as that user.
+//!
+//! Connections register on accept and unregister when their read loop exits.
+//! Killing a session only flips a flag and wakes the connection; the
+//! connection itself closes the socket after flushing pending replies.
+//!
+//! Sessions also carry what CLIENT LIST reports (name, age, last command),
+//! and CLIENT KILL revokes them by id, address or user the same way. CLIENT
+//! PAUSE is registry-wide: connections wait in `wait_unpaused` before
+//! running a command the pause holds, until it times out or CLIENT UNPAUSE.
+//!
+//! On graceful shutdown the registry drains instead: every session (and any
+//! registered afterwards) is asked to finish the commands it has already
+//! read, reply, send a `-SHUTDOWN` notice and close. SHUTDOWN itself goes
+//! through the registry too: `request_shutdown` wakes the server, which
+//! then drains it.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Every session id in `by_user` exists in `sessions`
+//! - A session appears under at most one username
+//! - A pause is only recorded with a deadline in the future
+
+use super::shutdown::ShutdownRequest;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// A single registered connection
+#[derive(Debug)]
+pub struct ClientSession {
+    id: u64,
+    addr: String,
+    killed: AtomicBool,
+    draining: AtomicBool,
+    close_notify: Notify,
+    /// Set by CLIENT SETNAME; None until then
+    name: Mutex<Option<String>>,
+    created: Instant,
+    /// Last command run and when, for CLIENT LIST's `cmd` and `idle`
+    last_command: Mutex<(String, Instant)>,
+}
+
+impl ClientSession {
+    /// Unique, monotonically increasing client id
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Peer address of the connection
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Name set with CLIENT SETNAME
+    pub fn name(&self) -> Option<String> {
+        self.name.lock().clone()
+    }
+
+    /// Set or (with None) clear the name
+    pub fn set_name(&self, name: Option<String>) {
+        *self.name.lock() = name;
+    }
+
+    /// Record the command the connection is about to run, as CLIENT LIST
+    /// names it (e.g. `client|list`)
+    pub fn record_command(&self, label: String) {
+        *self.last_command.lock() = (label, Instant::now());
+    }
+
+    /// True once the session has been revoked
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::Acquire)
+    }
+
+    /// True once the server has asked the session to drain for shutdown
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    /// Resolves once the session has been revoked.
+    pub async fn killed(&self) {
+        while !self.is_killed() {
+            self.close_notify.notified().await;
+        }
+    }
+
+    /// Resolves once the session has been revoked or asked to drain.
+    ///
+    /// `Notify::notify_one` stores a permit, so a close that races with the
+    /// connection entering its read is never lost.
+    pub async fn close_requested(&self) {
+        while !self.is_killed() && !self.is_draining() {
+            self.close_notify.notified().await;
+        }
+    }
+
+    fn kill(&self) {
+        self.killed.store(true, Ordering::Release);
+        self.close_notify.notify_one();
+    }
+
+    fn drain(&self) {
+        self.draining.store(true, Ordering::Release);
+        self.close_notify.notify_one();
+    }
+}
+
+/// An active CLIENT PAUSE
+#[derive(Debug, Clone, Copy)]
+struct Pause {
+    until: Instant,
+    /// WRITE mode: reads keep running
+    writes_only: bool,
+}
+
+#[derive(Debug, Default)]
+struct RegistryInner {
+    sessions: HashMap<u64, Arc<ClientSession>>,
+    usernames: HashMap<u64, String>,
+    by_user: HashMap<String, HashSet<u64>>,
+}
+
+/// Registry of live client sessions, keyed by id and by authenticated username
+#[derive(Debug)]
+pub struct ClientRegistry {
+    next_id: AtomicU64,
+    inner: Mutex<RegistryInner>,
+    draining: AtomicBool,
+    /// Woken whenever the last session unregisters
+    empty_notify: Notify,
+    pause: Mutex<Option<Pause>>,
+    /// Woken by CLIENT UNPAUSE
+    unpause_notify: Notify,
+    /// The first SHUTDOWN received
+    shutdown: Mutex<Option<ShutdownRequest>>,
+    /// Woken by SHUTDOWN
+    shutdown_notify: Notify,
+}
+
+impl ClientRegistry {
+    pub fn new() -> Self {
+        ClientRegistry {
+            // Redis client ids start at 1
+            next_id: AtomicU64::new(1),
+            inner: Mutex::new(RegistryInner::default()),
+            draining: AtomicBool::new(false),
+            empty_notify: Notify::new(),
+            pause: Mutex::new(None),
+            unpause_notify: Notify::new(),
+            shutdown: Mutex::new(None),
+            shutdown_notify: Notify::new(),
+        }
+    }
+
+    /// Register a new connection, optionally already authenticated
+    pub fn register(&self, addr: &str, username: Option<&str>) -> Arc<ClientSession> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let created = Instant::now();
+        let session = Arc::new(ClientSession {
+            id,
+            addr: addr.to_string(),
+            killed: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
+            close_notify: Notify::new(),
+            name: Mutex::new(None),
+            created,
+            // Redis reports a client that has run nothing yet as cmd=NULL
+            last_command: Mutex::new(("NULL".to_string(), created)),
+        });
+
+        let mut inner = self.inner.lock();
+        inner.sessions.insert(id, session.clone());
+        if let Some(name) = username {
+            Self::bind_user(&mut inner, id, name);
+        }
+        Self::verify_invariants(&inner);
+        // Checked under the lock so a concurrent drain_all cannot miss it
+        if self.draining.load(Ordering::Acquire) {
+            session.drain();
+        }
+        drop(inner);
+
+        session
+    }
+
+    /// Record that a session (re-)authenticated as `username`
+    pub fn set_user(&self, id: u64, username: &str) {
+        let mut inner = self.inner.lock();
+        if !inner.sessions.contains_key(&id) {
+            return;
+        }
+        Self::unbind_user(&mut inner, id);
+        Self::bind_user(&mut inner, id, username);
+        Self::verify_invariants(&inner);
+    }
+
+    /// Remove a session; called when the connection closes
+    pub fn unregister(&self, id: u64) {
+        let mut inner = self.inner.lock();
+        Self::unbind_user(&mut inner, id);
+        inner.sessions.remove(&id);
+        Self::verify_invariants(&inner);
+        if inner.sessions.is_empty() {
+            self.empty_notify.notify_waiters();
+        }
+    }
+
+    /// Revoke every session authenticated as `username`. Returns how many
+    /// sessions were killed.
+    pub fn kill_user(&self, username: &str) -> usize {
+        let inner = self.inner.lock();
+        let Some(ids) = inner.by_user.get(username) else {
+            return 0;
+        };
+        let mut killed = 0;
+        for id in ids {
+            if let Some(session) = inner.sessions.get(id) {
+                if !session.is_killed() {
+                    session.kill();
+                    killed += 1;
+                }
+            }
+        }
+        killed
+    }
+
+    /// CLIENT KILL with filters: revoke every session matching all the
+    /// given ones, except `skip` (the caller, unless SKIPME no). Returns how
+    /// many sessions were killed.
+    pub fn kill_matching(
+        &self,
+        id: Option<u64>,
+        addr: Option<&str>,
+        user: Option<&str>,
+        skip: Option<u64>,
+    ) -> usize {
+        let inner = self.inner.lock();
+        let mut killed = 0;
+        for session in inner.sessions.values() {
+            let matches = id.map_or(true, |id| session.id == id)
+                && addr.map_or(true, |addr| session.addr == addr)
+                && user.map_or(true, |user| {
+                    inner.usernames.get(&session.id).map(String::as_str) == Some(user)
+                })
+                && skip != Some(session.id);
+            if matches && !session.is_killed() {
+                session.kill();
+                killed += 1;
+            }
+        }
+        killed
+    }
+
+    /// CLIENT INFO: the CLIENT LIST line of one session
+    pub fn describe(&self, id: u64) -> Option<String> {
+        let inner = self.inner.lock();
+        let session = inner.sessions.get(&id)?;
+        Some(Self::render(&inner, session, Instant::now()))
+    }
+
+    /// CLIENT LIST: one line per live session, in id order
+    pub fn list(&self) -> String {
+        let inner = self.inner.lock();
+        let now = Instant::now();
+        let mut sessions: Vec<&Arc<ClientSession>> = inner.sessions.values().collect();
+        sessions.sort_by_key(|session| session.id);
+        sessions
+            .into_iter()
+            .map(|session| Self::render(&inner, session, now))
+            .collect()
+    }
+
+    fn render(inner: &RegistryInner, session: &ClientSession, now: Instant) -> String {
+        let (cmd, last) = session.last_command.lock().clone();
+        format!(
+            "id={} addr={} name={} age={} idle={} flags=N db=0 cmd={} user={}\n",
+            session.id,
+            session.addr,
+            session.name().unwrap_or_default(),
+            now.saturating_duration_since(session.created).as_secs(),
+            now.saturating_duration_since(last).as_secs(),
+            cmd,
+            inner
+                .usernames
+                .get(&session.id)
+                .map_or("default", String::as_str),
+        )
+    }
+
+    /// CLIENT PAUSE: hold commands for `timeout` (only writes if
+    /// `writes_only`). A pause already in force is only ever extended or
+    /// made stricter, as in Redis.
+    pub fn pause(&self, timeout: Duration, writes_only: bool) {
+        let now = Instant::now();
+        let mut pause = self.pause.lock();
+        let mut next = Pause {
+            until: now + timeout,
+            writes_only,
+        };
+        if let Some(current) = (*pause).filter(|p| p.until > now) {
+            next.until = next.until.max(current.until);
+            next.writes_only &= current.writes_only;
+        }
+        *pause = (next.until > now).then_some(next);
+
+        // TigerStyle: Postcondition
+        debug_assert!(
+            (*pause).map_or(true, |p| p.until > now),
+            "Postcondition violated: a recorded pause must end in the future"
+        );
+    }
+
+    /// CLIENT UNPAUSE: release every held command
+    pub fn unpause(&self) {
+        *self.pause.lock() = None;
+        self.unpause_notify.notify_waiters();
+    }
+
+    /// True while a pause is in force
+    pub fn is_paused(&self) -> bool {
+        self.pause_deadline(true).is_some()
+    }
+
+    /// When the pause holding a command (a write if `writes`) ends
+    fn pause_deadline(&self, writes: bool) -> Option<Instant> {
+        let pause = (*self.pause.lock())?;
+        (pause.until > Instant::now() && (writes || !pause.writes_only)).then_some(pause.until)
+    }
+
+    /// Wait until no pause holds a command (a write if `writes`)
+    pub async fn wait_unpaused(&self, writes: bool) {
+        loop {
+            let notified = self.unpause_notify.notified();
+            tokio::pin!(notified);
+            // Register interest before checking, so an unpause in between
+            // is not missed
+            notified.as_mut().enable();
+            let Some(until) = self.pause_deadline(writes) else {
+                return;
+            };
+            tokio::select! {
+                _ = tokio::time::sleep_until(until.into()) => {}
+                _ = notified => {}
+            }
+        }
+    }
+
+    /// SHUTDOWN: ask the server to shut down. Only the first request counts.
+    pub fn request_shutdown(&self, request: ShutdownRequest) {
+        self.shutdown.lock().get_or_insert(request);
+        self.shutdown_notify.notify_waiters();
+    }
+
+    /// Wait until a client sends SHUTDOWN
+    pub async fn shutdown_requested(&self) -> ShutdownRequest {
+        loop {
+            let notified = self.shutdown_notify.notified();
+            tokio::pin!(notified);
+            // Register interest before checking, so a request in between
+            // is not missed
+            notified.as_mut().enable();
+            if let Some(request) = *self.shutdown.lock() {
+                return request;
+            }
+            notified.await;
+        }
+    }
+
+    /// Ask every live session, and every session registered from now on, to
+    /// drain and close. Returns how many live sessions were asked.
+    pub fn drain_all(&self) -> usize {
+        let inner = self.inner.lock();
+        self.draining.store(true, Ordering::Release);
+        for session in inner.sessions.values() {
+            session.drain();
+        }
+        inner.sessions.len()
+    }
+
+    /// True once `drain_all` has been called
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    /// Wait until every session has unregistered
+    pub async fn wait_empty(&self) {
+        loop {
+            let notified = self.empty_notify.notified();
+            tokio::pin!(notified);
+            // Register interest before checking, so an unregister in between
+            // is not missed
+            notified.as_mut().enable();
+            if self.is_empty() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Number of live sessions
+    pub fn len(&self) -> usize {
+        self.inner.lock().sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of live sessions authenticated as `username`
+    pub fn user_session_count(&self, username: &str) -> usize {
+        self.inner
+            .lock()
+            .by_user
+            .get(username)
+            .map_or(0, |ids| ids.len())
+    }
+
+    fn bind_user(inner: &mut RegistryInner, id: u64, username: &str) {
+        inner.usernames.insert(id, username.to_string());
+        inner
+            .by_user
+            .entry(username.to_string())
+            .or_default()
+            .insert(id);
+    }
+
+    fn unbind_user(inner: &mut RegistryInner, id: u64) {
+        if let Some(old) = inner.usernames.remove(&id) {
+            if let Some(ids) = inner.by_user.get_mut(&old) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    inner.by_user.remove(&old);
+                }
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn verify_invariants(inner: &RegistryInner) {
+        for (name, ids) in &inner.by_user {
+            debug_assert!(!ids.is_empty(), "Invariant: empty session set for user '{}'", name);
+            for id in ids {
+                debug_assert!(
+                    inner.sessions.contains_key(id),
+                    "Invariant: session {} under user '{}' is not registered",
+                    id,
+                    name
+                );
+                debug_assert_eq!(
+                    inner.usernames.get(id).map(String::as_str),
+                    Some(name.as_str()),
+                    "Invariant: session {} must appear under exactly one username",
+                    id
+                );
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn verify_invariants(_inner: &RegistryInner) {}
+}
+
+impl Default for ClientRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_assigns_unique_ids() {
+        let registry = ClientRegistry::new();
+        let a = registry.register("127.0.0.1:1", Some("default"));
+        let b = registry.register("127.0.0.1:2", None);
+        assert_ne!(a.id(), b.id());
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.user_session_count("default"), 1);
+
+        registry.unregister(a.id());
+        registry.unregister(b.id());
+        assert!(registry.is_empty());
+        assert_eq!(registry.user_session_count("default"), 0);
+    }
+
+    #[test]
+    fn test_kill_user_only_affects_that_user() {
+        let registry = ClientRegistry::new();
+        let alice1 = registry.register("a:1", Some("alice"));
+        let alice2 = registry.register("a:2", None);
+        registry.set_user(alice2.id(), "alice");
+        let bob = registry.register("b:1", Some("bob"));
+
+        assert_eq!(registry.kill_user("alice"), 2);
+        assert!(alice1.is_killed());
+        assert!(alice2.is_killed());
+        assert!(!bob.is_killed());
+
+        // Already-killed sessions are not counted twice
+        assert_eq!(registry.kill_user("alice"), 0);
+        assert_eq!(registry.kill_user("nobody"), 0);
+    }
+
+    #[test]
+    fn test_reauth_moves_session_between_users() {
+        let registry = ClientRegistry::new();
+        let s = registry.register("c:1", Some("default"));
+        registry.set_user(s.id(), "alice");
+        assert_eq!(registry.user_session_count("default"), 0);
+        assert_eq!(registry.user_session_count("alice"), 1);
+
+        assert_eq!(registry.kill_user("default"), 0);
+        assert!(!s.is_killed());
+    }
+
+    #[tokio::test]
+    async fn test_drain_all_reaches_existing_and_new_sessions() {
+        let registry = Arc::new(ClientRegistry::new());
+        let a = registry.register("e:1", Some("alice"));
+        assert_eq!(registry.drain_all(), 1);
+        assert!(a.is_draining());
+        assert!(!a.is_killed(), "draining is not revocation");
+
+        // Accepted after the drain started: must drain immediately
+        let b = registry.register("e:2", None);
+        assert!(b.is_draining());
+        tokio::time::timeout(std::time::Duration::from_secs(1), b.close_requested())
+            .await
+            .expect("close_requested() must resolve for a draining session");
+
+        let waiter = {
+            let registry = registry.clone();
+            tokio::spawn(async move { registry.wait_empty().await })
+        };
+        registry.unregister(a.id());
+        registry.unregister(b.id());
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .expect("wait_empty() must resolve once every session is gone")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_list_and_kill_by_filters() {
+        let registry = ClientRegistry::new();
+        let a = registry.register("10.0.0.1:5000", Some("alice"));
+        let b = registry.register("10.0.0.2:5000", Some("bob"));
+        a.set_name(Some("worker".to_string()));
+        b.record_command("get".to_string());
+
+        let list = registry.list();
+        let lines: Vec<&str> = list.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&format!("id={} addr=10.0.0.1:5000 name=worker ", a.id())));
+        assert!(lines[0].ends_with(" cmd=NULL user=alice"));
+        assert!(lines[1].ends_with(" cmd=get user=bob"));
+        assert_eq!(
+            registry.describe(b.id()).as_deref(),
+            Some(&*format!("{}\n", lines[1]))
+        );
+
+        // Every filter must match, and the caller can be skipped
+        assert_eq!(
+            registry.kill_matching(Some(a.id()), Some("10.0.0.2:5000"), None, None),
+            0
+        );
+        assert_eq!(
+            registry.kill_matching(None, None, Some("bob"), Some(b.id())),
+            0
+        );
+        assert_eq!(
+            registry.kill_matching(None, Some("10.0.0.2:5000"), None, None),
+            1
+        );
+        assert!(b.is_killed());
+        assert!(!a.is_killed());
+    }
+
+    #[tokio::test]
+    async fn test_pause_holds_writes_until_unpause() {
+        let registry = Arc::new(ClientRegistry::new());
+        registry.pause(Duration::from_secs(60), true);
+        assert!(registry.is_paused());
+        // Reads run under a WRITE pause; a shorter ALL pause makes it stricter
+        tokio::time::timeout(Duration::from_secs(1), registry.wait_unpaused(false))
+            .await
+            .expect("reads are not held by a WRITE pause");
+        registry.pause(Duration::from_millis(1), false);
+        assert!(registry.pause_deadline(false).unwrap() > Instant::now() + Duration::from_secs(30));
+
+        let waiter = {
+            let registry = registry.clone();
+            tokio::spawn(async move { registry.wait_unpaused(true).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        registry.unpause();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("wait_unpaused() must resolve on unpause")
+            .unwrap();
+
+        // A pause also lapses on its own
+        registry.pause(Duration::from_millis(20), false);
+        tokio::time::timeout(Duration::from_secs(1), registry.wait_unpaused(false))
+            .await
+            .expect("wait_unpaused() must resolve once the pause times out");
+        assert!(!registry.is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_kill_before_wait_is_not_lost() {
+        let registry = ClientRegistry::new();
+        let s = registry.register("d:1", Some("alice"));
+        registry.kill_user("alice");
+        // Must resolve immediately rather than hang
+        tokio::time::timeout(std::time::Duration::from_secs(1), s.killed())
+            .await
+            .expect("killed() must resolve after kill_user");
+    }
+}
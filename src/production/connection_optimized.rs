@@ -1,11 +1,22 @@
+use super::client_registry::{ClientRegistry, ClientSession};
 use super::connection_pool::BufferPoolAsync;
 use super::perf_config::{BatchingConfig, BufferConfig};
+use super::reply_shape::{Protocol, ReplyShape};
+use super::reply_stream;
+use super::shutdown::{ShutdownRequest, SHUTDOWN_NOTICE};
+use super::tenant_keyspace::{confine_command, TenantKeyspace};
+use super::transaction_coordinator::WatchGuard;
 use super::ShardedActorState;
 use crate::observability::{spans, Metrics};
-use crate::redis::{Command, RespCodec, RespValue};
-use crate::security::{AclManager, AclUser};
+use crate::redis::{
+    blocking, double_text, Command, ConnectionState, Invalidation, ProtocolLimits, PubSubMessage,
+    PubSubSession, RespCodec, RespValue, TrackingMode, TrackingSession,
+};
+use crate::release::{ServerMode, REDIS_VERSION};
+use crate::security::{AclManager, AclUser, TlsStats};
 use bytes::{BufMut, BytesMut};
 use parking_lot::RwLock;
+use std::borrow::Cow;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
@@ -40,6 +51,10 @@ pub struct ConnectionConfig {
     pub read_buffer_size: usize,
     pub min_pipeline_buffer: usize,
     pub batch_threshold: usize,
+    /// Reply bytes buffered before flushing; larger replies are streamed
+    pub write_high_watermark: usize,
+    /// Request sizes beyond which the connection is closed
+    pub protocol_limits: ProtocolLimits,
 }
 
 impl Default for ConnectionConfig {
@@ -49,6 +64,8 @@ impl Default for ConnectionConfig {
             read_buffer_size: 8192,
             min_pipeline_buffer: 60,
             batch_threshold: 2,
+            write_high_watermark: 1024 * 1024,
+            protocol_limits: ProtocolLimits::default(),
         }
     }
 }
@@ -61,6 +78,8 @@ impl ConnectionConfig {
             read_buffer_size: buffers.read_size,
             min_pipeline_buffer: batching.min_pipeline_buffer,
             batch_threshold: batching.batch_threshold,
+            write_high_watermark: buffers.write_high_watermark,
+            protocol_limits: ProtocolLimits::default(),
         }
     }
 }
@@ -78,12 +97,21 @@ pub struct OptimizedConnectionHandler<S> {
     acl_manager: Arc<RwLock<AclManager>>,
     /// Currently authenticated user (None = not authenticated yet)
     authenticated_user: Option<Arc<AclUser>>,
-    /// Connection-level transaction state (MULTI/EXEC)
-    in_transaction: bool,
-    transaction_queue: Vec<Command>,
-    transaction_errors: bool,
-    /// Watched keys with their values at WATCH time (for optimistic locking)
-    watched_keys: Vec<(String, RespValue)>,
+    /// MULTI queue, watched keys (registered on their shards), Pub/Sub and
+    /// tracking sessions: everything RESET discards besides the fields below
+    conn: ConnectionState<WatchGuard>,
+    /// Registry of live sessions, used to revoke connections on ACL changes
+    client_registry: Arc<ClientRegistry>,
+    /// This connection's entry in the registry
+    session: Arc<ClientSession>,
+    /// Server-wide TLS handshake counters (None = TLS not serving)
+    tls_stats: Option<Arc<TlsStats>>,
+    /// Virtual keyspace of the authenticated user (None = shared keyspace)
+    tenant: Option<TenantKeyspace>,
+    /// Set when streaming a large reply failed; the connection closes
+    write_error: Option<std::io::Error>,
+    /// Protocol negotiated with HELLO; RESP3 reshapes some replies
+    protocol: Protocol,
 }
 
 impl<S> OptimizedConnectionHandler<S>
@@ -100,6 +128,8 @@ where
         config: ConnectionConfig,
         acl_manager: Arc<RwLock<AclManager>>,
         client_cert_cn: Option<String>,
+        client_registry: Arc<ClientRegistry>,
+        tls_stats: Option<Arc<TlsStats>>,
     ) -> Self {
         let buffer = buffer_pool.acquire();
         let write_buffer = buffer_pool.acquire();
@@ -153,6 +183,12 @@ where
             }
         };
 
+        let session = client_registry.register(
+            &client_addr,
+            authenticated_user.as_ref().map(|u| u.name.as_str()),
+        );
+        let tenant = Self::tenant_of(authenticated_user.as_deref());
+
         OptimizedConnectionHandler {
             stream,
             state,
@@ -164,10 +200,13 @@ where
             config,
             acl_manager,
             authenticated_user,
-            in_transaction: false,
-            transaction_queue: Vec::new(),
-            transaction_errors: false,
-            watched_keys: Vec::new(),
+            conn: ConnectionState::new(),
+            client_registry,
+            session,
+            tls_stats,
+            tenant,
+            write_error: None,
+            protocol: Protocol::default(),
         }
     }
 
@@ -183,9 +222,51 @@ where
 
             // Use config for read buffer size (stack-allocate with max expected size)
             let mut read_buf = vec![0u8; self.config.read_buffer_size];
+            let session = self.session.clone();
 
             loop {
-                match self.stream.read(&mut read_buf).await {
+                let read_result = tokio::select! {
+                    biased;
+                    _ = session.close_requested() => {
+                        if session.is_killed() {
+                            info!("Client {} session revoked, closing connection", self.client_addr);
+                            self.metrics.record_connection("killed");
+                        } else {
+                            self.send_shutdown_notice().await;
+                        }
+                        break;
+                    }
+                    message = Self::next_pubsub_message(&mut self.conn.pubsub) => {
+                        match message {
+                            Some(message) => {
+                                if let Err(e) = self.write_pubsub_messages(message).await {
+                                    error!("Write failed to {}: {}", self.client_addr, e);
+                                    break;
+                                }
+                                continue;
+                            }
+                            None => {
+                                warn!(
+                                    "Client {} fell too far behind its subscriptions, closing connection",
+                                    self.client_addr
+                                );
+                                self.metrics.record_connection("killed");
+                                break;
+                            }
+                        }
+                    }
+                    invalidation = Self::next_invalidation(&mut self.conn.tracking) => {
+                        if let Some(invalidation) = invalidation {
+                            if let Err(e) = self.write_invalidations(invalidation).await {
+                                error!("Write failed to {}: {}", self.client_addr, e);
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                    result = self.stream.read(&mut read_buf) => result,
+                };
+                match read_result {
                     Ok(0) => {
                         info!("Client disconnected: {}", self.client_addr);
                         break;
@@ -214,7 +295,15 @@ where
                         let min_pipeline_buffer = self.config.min_pipeline_buffer;
                         let batch_threshold = self.config.batch_threshold;
 
-                        if self.buffer.len() >= min_pipeline_buffer && !self.in_transaction {
+                        // Batches skip CLIENT PAUSE and read tracking, so those
+                        // send every command through the sequential path below
+                        if self.buffer.len() >= min_pipeline_buffer
+                            && !self.conn.in_transaction
+                            && !self.is_subscribed()
+                            && self.tenant.is_none()
+                            && !self.client_registry.is_paused()
+                            && self.conn.tracking.is_none()
+                        {
                             // Try GET batching first
                             let (get_keys, get_count) = self.collect_get_keys();
 
@@ -231,8 +320,9 @@ where
                                         duration_ms / results.len() as f64,
                                         success,
                                     );
-                                    Self::encode_resp_into(response, &mut self.write_buffer);
+                                    self.write_reply(response).await;
                                 }
+                                self.session.record_command("get".to_string());
                                 commands_executed += get_count;
                             }
 
@@ -254,8 +344,9 @@ where
                                             duration_ms / results.len() as f64,
                                             success,
                                         );
-                                        Self::encode_resp_into(response, &mut self.write_buffer);
+                                        self.write_reply(response).await;
                                     }
+                                    self.session.record_command("set".to_string());
                                     commands_executed += set_count;
                                 }
                             }
@@ -263,28 +354,39 @@ where
 
                         // Process remaining commands sequentially
                         loop {
+                            // A revoked session must not run the rest of its pipeline
+                            if session.is_killed() || self.write_error.is_some() {
+                                break;
+                            }
                             match self.try_execute_command().await {
                                 CommandResult::Executed => {
                                     commands_executed += 1;
                                     // Don't flush yet - continue processing pipeline
                                 }
                                 CommandResult::NeedMoreData => break,
+                                CommandResult::Shutdown => {
+                                    commands_executed += 1;
+                                    self.buffer.clear();
+                                    break;
+                                }
                                 CommandResult::ParseError(e) => {
                                     warn!(
-                                        "Parse error from {}: {}, draining buffer",
-                                        self.client_addr, e
+                                        "{} from {}, closing connection",
+                                        e, self.client_addr
                                     );
                                     self.buffer.clear();
-                                    Self::encode_error_into(
-                                        "protocol error",
-                                        &mut self.write_buffer,
-                                    );
+                                    Self::encode_error_into(&e, &mut self.write_buffer);
                                     had_parse_error = true;
                                     break;
                                 }
                             }
                         }
 
+                        if let Some(e) = self.write_error.take() {
+                            error!("Write failed to {}: {}", self.client_addr, e);
+                            break;
+                        }
+
                         // Flush ALL responses at once (critical for pipelining performance)
                         if !self.write_buffer.is_empty() {
                             if let Err(e) = self.stream.write_all(&self.write_buffer).await {
@@ -299,8 +401,25 @@ where
                             self.write_buffer.clear();
                         }
 
+                        // The stream can't be framed past a protocol error; the
+                        // error reply was flushed above, now close like Redis
                         if had_parse_error {
-                            // Continue to next read after parse error
+                            self.metrics.record_connection("protocol_error");
+                            break;
+                        }
+
+                        // Replies to the revoking command (if it came from this
+                        // connection) were flushed above; now close.
+                        if session.is_killed() {
+                            info!("Client {} session revoked, closing connection", self.client_addr);
+                            self.metrics.record_connection("killed");
+                            break;
+                        }
+
+                        // Shutdown: the batch already read has been answered
+                        if session.is_draining() {
+                            self.send_shutdown_notice().await;
+                            break;
                         }
 
                         debug!("Processed {} commands in pipeline batch", commands_executed);
@@ -312,6 +431,7 @@ where
                 }
             }
 
+            self.client_registry.unregister(self.session.id());
             self.metrics.record_connection("closed");
             self.buffer_pool.release(self.buffer);
             self.buffer_pool.release(self.write_buffer);
@@ -325,7 +445,19 @@ where
         // Try fast path first for GET/SET commands (80%+ of traffic)
         // Fast path skips ACL key checks for performance - only safe when user has ~* (all keys)
         // MUST NOT use fast path during MULTI — commands must be queued
-        if self.user_has_unrestricted_keys() && !self.in_transaction {
+        // MUST NOT use fast path while subscribed — only Pub/Sub commands are allowed
+        // MUST NOT use fast path for tenants — their keys are rewritten at dispatch
+        // MUST NOT use fast path under CLIENT PAUSE — commands must wait for it
+        // MUST NOT use fast path under CLIENT TRACKING — reads must be remembered
+        // MUST NOT use fast path while a script is busy — commands get BUSY
+        if self.user_has_unrestricted_keys()
+            && !self.conn.in_transaction
+            && !self.is_subscribed()
+            && self.tenant.is_none()
+            && !self.client_registry.is_paused()
+            && self.conn.tracking.is_none()
+            && !self.state.scripts().is_busy()
+        {
             match self.try_fast_path().await {
                 FastPathResult::Handled => return CommandResult::Executed,
                 FastPathResult::NeedMoreData => return CommandResult::NeedMoreData,
@@ -333,62 +465,85 @@ where
             }
         }
 
-        match RespCodec::parse(&mut self.buffer) {
+        match RespCodec::parse_with_limits(&mut self.buffer, &self.config.protocol_limits) {
             Ok(Some(resp_value)) => match Command::from_resp_zero_copy(&resp_value) {
                 Ok(cmd) => {
                     let cmd_name = cmd.name();
                     let start = Instant::now();
+                    let (name, subcommand) = Self::command_names(&cmd);
+                    self.session
+                        .record_command(subcommand.unwrap_or(name).to_lowercase());
+
+                    // A script past busy-reply-threshold holds its shard
+                    let allowed = Self::allowed_while_busy(&cmd);
+                    if let Some(busy) = self.state.scripts().busy_reply(allowed) {
+                        // As in Redis, the rejected command fails the transaction
+                        if self.conn.in_transaction {
+                            self.conn.transaction_errors = true;
+                        }
+                        self.metrics.record_command(cmd_name, 0.0, false);
+                        self.write_reply(&busy).await;
+                        return CommandResult::Executed;
+                    }
+
+                    // Pub/Sub commands may answer with several replies
+                    if let Some(replies) = self.try_pubsub_command(&cmd) {
+                        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+                        let success = !replies.iter().any(|r| matches!(r, RespValue::Error(_)));
+                        self.metrics.record_command(cmd_name, duration_ms, success);
+                        for reply in &replies {
+                            self.write_reply(reply).await;
+                        }
+                        return CommandResult::Executed;
+                    }
 
                     // Handle connection-level transaction state
-                    let response = if self.in_transaction {
+                    let response = if self.conn.in_transaction {
                         match &cmd {
                             Command::Exec => {
-                                self.in_transaction = false;
-                                if self.transaction_errors {
+                                self.conn.in_transaction = false;
+                                if self.conn.transaction_errors {
                                     // Abort: previous errors during queueing
-                                    self.transaction_queue.clear();
-                                    self.transaction_errors = false;
-                                    self.watched_keys.clear();
+                                    self.conn.queued_commands.clear();
+                                    self.conn.transaction_errors = false;
+                                    self.conn.watched_keys.clear();
                                     RespValue::err("EXECABORT Transaction discarded because of previous errors.")
                                 } else {
-                                    // Check watched keys for modifications
-                                    // NOTE: This is a value-based comparison, not a dirty-flag check like Redis.
-                                    // If a key changes and reverts to the same value, EXEC will succeed here
-                                    // but would abort in Redis. This is an intentional simplification for the
-                                    // sharded architecture -- we can't track per-key modification flags across shards.
-                                    let watched = std::mem::take(&mut self.watched_keys);
-                                    let mut watch_failed = false;
-                                    for (key, old_value) in &watched {
-                                        let current = self
-                                            .state
-                                            .execute(&Command::Get(key.clone()))
-                                            .await;
-                                        if !resp_values_equal(&current, old_value) {
-                                            watch_failed = true;
-                                            break;
-                                        }
+                                    // The coordinator holds the shards involved and
+                                    // checks the watch stamps (transaction_coordinator)
+                                    let watched: Vec<WatchGuard> = self
+                                        .conn
+                                        .watched_keys
+                                        .drain(..)
+                                        .map(|(_, guard)| guard)
+                                        .collect();
+                                    let queued = std::mem::take(&mut self.conn.queued_commands);
+                                    let writes = queued.iter().any(|c| !c.is_read_only());
+                                    self.client_registry.wait_unpaused(writes).await;
+                                    for queued_cmd in &queued {
+                                        self.remember_reads(queued_cmd);
                                     }
-                                    if watch_failed {
-                                        self.transaction_queue.clear();
-                                        RespValue::Array(None) // Null array = WATCH failed
-                                    } else {
-                                        let queued = std::mem::take(&mut self.transaction_queue);
-                                        let mut results = Vec::with_capacity(queued.len());
-                                        for queued_cmd in &queued {
-                                            let r = self.state.execute(queued_cmd).await;
-                                            results.push(r);
-                                        }
-                                        RespValue::Array(Some(results))
+                                    match self.state.execute_transaction(&watched, &queued).await {
+                                        Some(replies) => RespValue::Array(Some(
+                                            queued
+                                                .iter()
+                                                .zip(replies)
+                                                .map(|(c, r)| self.unconfine_reply(c, r))
+                                                .collect(),
+                                        )),
+                                        None => RespValue::Array(None), // Null array = WATCH failed
                                     }
                                 }
                             }
                             Command::Discard => {
-                                self.in_transaction = false;
-                                self.transaction_queue.clear();
-                                self.transaction_errors = false;
-                                self.watched_keys.clear();
+                                self.conn.in_transaction = false;
+                                self.conn.queued_commands.clear();
+                                self.conn.transaction_errors = false;
+                                self.conn.watched_keys.clear();
                                 RespValue::simple("OK")
                             }
+                            // RESET runs at once and ends the transaction
+                            Command::Reset => self.handle_reset(),
                             Command::Multi => {
                                 RespValue::err("ERR MULTI calls can not be nested")
                             }
@@ -400,37 +555,42 @@ where
                                 // (mimics Redis channel ACL enforcement at queue time)
                                 let upper = name.to_uppercase();
                                 if matches!(upper.as_str(),
-                                    "PUBLISH" | "SPUBLISH" | "SUBSCRIBE" | "SSUBSCRIBE"
-                                    | "PSUBSCRIBE" | "UNSUBSCRIBE" | "SUNSUBSCRIBE" | "PUNSUBSCRIBE"
+                                    "SPUBLISH" | "SSUBSCRIBE" | "SUNSUBSCRIBE"
                                 ) {
-                                    self.transaction_errors = true;
+                                    self.conn.transaction_errors = true;
                                     RespValue::err("NOPERM this user has no permissions to access the channel used as argument")
                                 } else {
                                     // Other stubs in MULTI: queue them
-                                    self.transaction_queue.push(cmd.clone());
+                                    self.conn.queued_commands.push(cmd.clone());
                                     RespValue::simple("QUEUED")
                                 }
                             }
                             Command::Unknown(name) => {
                                 // Unknown command during MULTI: return error, mark transaction
-                                self.transaction_errors = true;
+                                self.conn.transaction_errors = true;
                                 RespValue::err(format!(
                                     "ERR unknown command '{}', with args beginning with: ",
                                     name.to_lowercase()
                                 ))
                             }
-                            _ => {
-                                // Queue the command
-                                self.transaction_queue.push(cmd.clone());
-                                RespValue::simple("QUEUED")
-                            }
+                            _ => match confine_command(self.tenant.as_ref(), &cmd) {
+                                Ok(confined) => {
+                                    // Queue the command (already in the shared keyspace)
+                                    self.conn.queued_commands.push(confined.into_owned());
+                                    RespValue::simple("QUEUED")
+                                }
+                                Err(e) => {
+                                    self.conn.transaction_errors = true;
+                                    RespValue::err(e)
+                                }
+                            },
                         }
                     } else {
                         match &cmd {
                             Command::Multi => {
-                                self.in_transaction = true;
-                                self.transaction_queue.clear();
-                                self.transaction_errors = false;
+                                self.conn.in_transaction = true;
+                                self.conn.queued_commands.clear();
+                                self.conn.transaction_errors = false;
                                 RespValue::simple("OK")
                             }
                             Command::Exec => {
@@ -439,21 +599,21 @@ where
                             Command::Discard => {
                                 RespValue::err("ERR DISCARD without MULTI")
                             }
-                            Command::Watch(keys) => {
-                                // Snapshot watched key values for optimistic locking
-                                for key in keys {
-                                    let snapshot = self
-                                        .state
-                                        .execute(&Command::Get(key.clone()))
-                                        .await;
-                                    self.watched_keys.push((key.clone(), snapshot));
+                            Command::Watch(_) => {
+                                // Register each key on its shard for optimistic locking
+                                let confined = confine_command(self.tenant.as_ref(), &cmd)
+                                    .expect("WATCH is confinable");
+                                for key in confined.get_keys() {
+                                    let guard = self.state.watch(&key).await;
+                                    self.conn.watch(key, guard);
                                 }
                                 RespValue::simple("OK")
                             }
                             Command::Unwatch => {
-                                self.watched_keys.clear();
+                                self.conn.watched_keys.clear();
                                 RespValue::simple("OK")
                             }
+                            Command::Reset => self.handle_reset(),
                             // Handle AUTH and ACL commands specially
                             Command::Auth { username, password } => {
                                 self.handle_auth(username.as_deref(), password)
@@ -481,7 +641,12 @@ where
                             } => self.handle_acl_dryrun(username, command, args),
                             Command::AclLog { count } => self.handle_acl_log(*count),
                             Command::AclLogReset => self.handle_acl_log_reset(),
-                            // Stub commands (PubSub, HELLO, etc.) — skip ACL check
+                            Command::Hello {
+                                protover,
+                                auth,
+                                setname,
+                            } => self.handle_hello(*protover, auth.as_ref(), setname.as_deref()),
+                            // Stub commands (PubSub, CLIENT, etc.) — skip ACL check
                             Command::Unknown(ref name) if Self::is_stub_command(name) => {
                                 Self::handle_stub_command(name)
                             }
@@ -528,8 +693,37 @@ where
                                         );
                                     }
                                     RespValue::err(acl_err)
+                                } else if let Command::Shutdown { save } = &cmd {
+                                    // No reply: the -SHUTDOWN notice follows once the
+                                    // server drains this connection
+                                    self.metrics.record_command(cmd_name, 0.0, true);
+                                    self.client_registry.request_shutdown(ShutdownRequest {
+                                        save: *save != Some(false),
+                                    });
+                                    return CommandResult::Shutdown;
+                                } else if let Some(reply) = self.handle_client_command(&cmd) {
+                                    reply
+                                } else if matches!(cmd, Command::Info) {
+                                    self.client_registry.wait_unpaused(false).await;
+                                    let info = self.state.execute(&cmd).await;
+                                    self.append_tls_info(info)
                                 } else {
-                                    self.state.execute(&cmd).await
+                                    self.client_registry
+                                        .wait_unpaused(!cmd.is_read_only())
+                                        .await;
+                                    match confine_command(self.tenant.as_ref(), &cmd) {
+                                        Ok(mut confined) => {
+                                            self.pin_stream_ids(&mut confined).await;
+                                            self.remember_reads(&confined);
+                                            let reply = self.state.execute(&confined).await;
+                                            // Again, in case the read expired a key
+                                            self.remember_reads(&confined);
+                                            let reply =
+                                                self.wait_if_blocked(&confined, reply).await;
+                                            self.unconfine_reply(&confined, reply)
+                                        }
+                                        Err(e) => RespValue::err(e),
+                                    }
                                 }
                             }
                         }
@@ -539,14 +733,14 @@ where
                     let success = !matches!(&response, RespValue::Error(_));
                     self.metrics.record_command(cmd_name, duration_ms, success);
 
-                    Self::encode_resp_into(&response, &mut self.write_buffer);
+                    self.write_shaped_reply(&response, ReplyShape::of(&cmd)).await;
                     CommandResult::Executed
                 }
                 Err(e) => {
                     self.metrics.record_command("PARSE_ERROR", 0.0, false);
                     // If in a transaction, mark it as having errors
-                    if self.in_transaction {
-                        self.transaction_errors = true;
+                    if self.conn.in_transaction {
+                        self.conn.transaction_errors = true;
                     }
                     Self::encode_error_into(&e, &mut self.write_buffer);
                     CommandResult::Executed
@@ -557,6 +751,36 @@ where
         }
     }
 
+    /// Commands that run while a script is busy: SCRIPT KILL, SHUTDOWN
+    /// NOSAVE and those that never reach a shard
+    fn allowed_while_busy(cmd: &Command) -> bool {
+        matches!(
+            cmd,
+            Command::ScriptKill
+                | Command::Shutdown { save: Some(false) }
+                | Command::Auth { .. }
+                | Command::Hello { .. }
+                | Command::Multi
+                | Command::Discard
+                | Command::Unwatch
+                | Command::Reset
+        )
+    }
+
+    /// Append the `# TLS` section to an INFO reply when TLS is serving
+    fn append_tls_info(&self, info: RespValue) -> RespValue {
+        match (&self.tls_stats, info) {
+            (Some(stats), RespValue::BulkString(Some(mut body))) => {
+                if !body.ends_with(b"\r\n\r\n") {
+                    body.extend_from_slice(b"\r\n");
+                }
+                body.extend_from_slice(stats.render_info().as_bytes());
+                RespValue::BulkString(Some(body))
+            }
+            (_, other) => other,
+        }
+    }
+
     /// Check if the authenticated user has unrestricted key access (~*).
     /// Returns false if no user is authenticated.
     fn user_has_unrestricted_keys(&self) -> bool {
@@ -566,27 +790,24 @@ where
         }
     }
 
-    /// Check ACL permissions for a command
-    /// Uses the latest user state from the ACL manager (not the cached connection copy)
-    fn check_acl_permission(&self, cmd: &Command) -> Result<(), String> {
-        let manager = self.acl_manager.read();
+    /// The virtual keyspace a user is confined to, if any
+    fn tenant_of(user: Option<&AclUser>) -> Option<TenantKeyspace> {
+        user.and_then(|u| u.keyspace()).map(TenantKeyspace::new)
+    }
 
-        // If auth is required but user not authenticated, reject
-        if manager.requires_auth() && self.authenticated_user.is_none() {
-            return Err("NOAUTH Authentication required".to_string());
+    /// Strip the tenant prefix from key names in a reply (no-op for non-tenants)
+    fn unconfine_reply(&self, confined: &Command, reply: RespValue) -> RespValue {
+        match &self.tenant {
+            Some(tenant) => tenant.unconfine_reply(confined, reply),
+            None => reply,
         }
+    }
 
-        // Get the latest user state from the manager (not the cached connection copy,
-        // which may be stale after ACL SETUSER modifications)
-        let user = match &self.authenticated_user {
-            Some(cached) => manager.get_user(&cached.name),
-            None => None,
-        };
-        let user_ref = user.as_deref();
-
-        // Get the actual command name (for Unknown commands, use the stored name)
-        // Also build a subcommand form for pipe-delimited ACL checks (e.g., "DEBUG OBJECT" → "DEBUG|OBJECT")
-        let (cmd_name, subcmd_form) = match cmd {
+    /// A command's name and, for subcommands, its pipe form (e.g. "DEBUG
+    /// OBJECT" → "DEBUG|OBJECT"), as ACL rules and CLIENT LIST name it.
+    /// For Unknown commands the stored name is used.
+    fn command_names(cmd: &Command) -> (String, Option<String>) {
+        match cmd {
             Command::Unknown(name) => {
                 let parts: Vec<&str> = name.split_whitespace().collect();
                 let base = parts[0].to_string();
@@ -600,13 +821,46 @@ where
             // Known commands with subcommands — build pipe form
             Command::DebugObject(_) => ("DEBUG".to_string(), Some("DEBUG|OBJECT".to_string())),
             Command::DebugSleep(_) => ("DEBUG".to_string(), Some("DEBUG|SLEEP".to_string())),
+            Command::DebugSetActiveExpire(_) => {
+                ("DEBUG".to_string(), Some("DEBUG|SET-ACTIVE-EXPIRE".to_string()))
+            }
+            Command::DebugReload => ("DEBUG".to_string(), Some("DEBUG|RELOAD".to_string())),
+            Command::DebugProtocol(_) => ("DEBUG".to_string(), Some("DEBUG|PROTOCOL".to_string())),
             Command::DebugSet(sub, _) => ("DEBUG".to_string(), Some(format!("DEBUG|{}", sub))),
             Command::ClientSetName(_) => ("CLIENT".to_string(), Some("CLIENT|SETNAME".to_string())),
             Command::ClientGetName => ("CLIENT".to_string(), Some("CLIENT|GETNAME".to_string())),
             Command::ClientId => ("CLIENT".to_string(), Some("CLIENT|ID".to_string())),
             Command::ClientInfo => ("CLIENT".to_string(), Some("CLIENT|INFO".to_string())),
+            Command::ClientList => ("CLIENT".to_string(), Some("CLIENT|LIST".to_string())),
+            Command::ClientKill { .. } => ("CLIENT".to_string(), Some("CLIENT|KILL".to_string())),
+            Command::ClientPause { .. } => ("CLIENT".to_string(), Some("CLIENT|PAUSE".to_string())),
+            Command::ClientUnpause => ("CLIENT".to_string(), Some("CLIENT|UNPAUSE".to_string())),
+            Command::ClientTracking { .. } => {
+                ("CLIENT".to_string(), Some("CLIENT|TRACKING".to_string()))
+            }
             _ => (cmd.name().to_string(), None),
+        }
+    }
+
+    /// Check ACL permissions for a command
+    /// Uses the latest user state from the ACL manager (not the cached connection copy)
+    fn check_acl_permission(&self, cmd: &Command) -> Result<(), String> {
+        let manager = self.acl_manager.read();
+
+        // If auth is required but user not authenticated, reject
+        if manager.requires_auth() && self.authenticated_user.is_none() {
+            return Err("NOAUTH Authentication required".to_string());
+        }
+
+        // Get the latest user state from the manager (not the cached connection copy,
+        // which may be stale after ACL SETUSER modifications)
+        let user = match &self.authenticated_user {
+            Some(cached) => manager.get_user(&cached.name),
+            None => None,
         };
+        let user_ref = user.as_deref();
+
+        let (cmd_name, subcmd_form) = Self::command_names(cmd);
 
         // Check if the user has subcommand-level permission (e.g., +debug|object)
         #[cfg(feature = "acl")]
@@ -647,7 +901,23 @@ where
 
         match manager.authenticate(username, password) {
             Ok(user) => {
+                #[cfg(feature = "acl")]
+                let user = if manager.needs_rehash(&user, password) {
+                    // Migrate a legacy hash now that the plaintext is known
+                    drop(manager);
+                    let mut manager = self.acl_manager.write();
+                    if manager.rehash_password(username, password) {
+                        info!("Upgraded password hash for user '{}' to {}", username, manager.hash_scheme().name());
+                    }
+                    manager.get_user(username).unwrap_or(user)
+                } else {
+                    drop(manager);
+                    user
+                };
+                #[cfg(not(feature = "acl"))]
                 drop(manager); // Release read lock before mutating self
+                self.client_registry.set_user(self.session.id(), &user.name);
+                self.tenant = Self::tenant_of(Some(&user));
                 self.authenticated_user = Some(user);
                 info!(
                     "Client {} authenticated as '{}'",
@@ -726,7 +996,7 @@ where
             let manager = self.acl_manager.read();
             match AclCommandHandler::handle_getuser(&manager, username) {
                 Some(info) => {
-                    let result = vec![
+                    let mut result = vec![
                         RespValue::BulkString(Some(b"flags".to_vec())),
                         RespValue::Array(Some(
                             info.flags
@@ -750,6 +1020,10 @@ where
                         RespValue::BulkString(Some(b"selectors".to_vec())),
                         RespValue::Array(Some(Vec::new())),
                     ];
+                    if let Some(prefix) = info.keyspace {
+                        result.push(RespValue::BulkString(Some(b"keyspace".to_vec())));
+                        result.push(RespValue::BulkString(Some(prefix.into_bytes())));
+                    }
 
                     RespValue::Array(Some(result))
                 }
@@ -789,8 +1063,22 @@ where
             use crate::security::acl::AclCommandHandler;
             let mut manager = self.acl_manager.write();
             let rule_refs: Vec<&str> = rules.iter().map(|s| s.as_str()).collect();
+            let old_keyspace = manager
+                .get_user(username)
+                .and_then(|u| u.keyspace.clone());
             match AclCommandHandler::handle_setuser(&mut manager, username, &rule_refs) {
-                Ok(()) => RespValue::simple("OK"),
+                Ok(()) => {
+                    let user = manager.get_user(username);
+                    let disabled = user.as_ref().is_some_and(|u| !u.enabled);
+                    // Live sessions cached the old prefix; moving a tenant's
+                    // keyspace under them would mix the two views
+                    let moved = user.is_some_and(|u| u.keyspace != old_keyspace);
+                    drop(manager);
+                    if disabled || moved {
+                        self.revoke_sessions(username);
+                    }
+                    RespValue::simple("OK")
+                }
                 Err(e) => RespValue::err(e.to_string()),
             }
         }
@@ -809,7 +1097,13 @@ where
             let mut manager = self.acl_manager.write();
             let username_refs: Vec<&str> = usernames.iter().map(|s| s.as_str()).collect();
             match AclCommandHandler::handle_deluser(&mut manager, &username_refs) {
-                Ok(count) => RespValue::Integer(count as i64),
+                Ok(count) => {
+                    drop(manager);
+                    for username in usernames {
+                        self.revoke_sessions(username);
+                    }
+                    RespValue::Integer(count as i64)
+                }
                 Err(e) => RespValue::err(e),
             }
         }
@@ -820,6 +1114,29 @@ where
         }
     }
 
+    /// Tell a draining client the server is going away
+    async fn send_shutdown_notice(&mut self) {
+        info!("Client {} drained for shutdown, closing connection", self.client_addr);
+        self.metrics.record_connection("drained");
+        Self::encode_resp_into(&RespValue::err(SHUTDOWN_NOTICE), &mut self.write_buffer);
+        let _ = self.stream.write_all(&self.write_buffer).await;
+        let _ = self.stream.flush().await;
+        self.write_buffer.clear();
+    }
+
+    /// Terminate every connection authenticated as `username` (including this
+    /// one, which closes after its current replies are flushed)
+    #[cfg(feature = "acl")]
+    fn revoke_sessions(&self, username: &str) {
+        let killed = self.client_registry.kill_user(username);
+        if killed > 0 {
+            info!(
+                "Revoked {} session(s) for ACL user '{}' (requested by {})",
+                killed, username, self.client_addr
+            );
+        }
+    }
+
     /// Handle ACL CAT command
     fn handle_acl_cat(&self, category: Option<&str>) -> RespValue {
         #[cfg(feature = "acl")]
@@ -863,33 +1180,18 @@ where
     }
 
     /// Handle ACL GENPASS command
+    ///
+    /// Ungated: `security::password` and `ProductionRng` are always built, so
+    /// default builds get the same CSPRNG-backed passwords as `acl` ones.
     fn handle_acl_genpass(&self, bits: Option<u32>) -> RespValue {
-        #[cfg(feature = "acl")]
-        {
-            use crate::security::acl::AclCommandHandler;
-            match AclCommandHandler::handle_genpass(bits) {
-                Ok(password) => RespValue::BulkString(Some(password.into_bytes())),
-                Err(e) => RespValue::err(e),
-            }
-        }
-        #[cfg(not(feature = "acl"))]
-        {
-            // Simple fallback password generation
-            use std::time::{SystemTime, UNIX_EPOCH};
-            let bits = bits.unwrap_or(256).min(1024);
-            let bytes = (bits as usize + 7) / 8;
-            let seed = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("system clock before UNIX_EPOCH")
-                .as_nanos();
-            let mut result = String::with_capacity(bytes * 2);
-            let mut state = seed;
-            for _ in 0..bytes {
-                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
-                let byte = ((state >> 33) & 0xFF) as u8;
-                result.push_str(&format!("{:02x}", byte));
+        use crate::security::password::{generate_password, genpass_bits};
+
+        match genpass_bits(bits) {
+            Ok(bits) => {
+                let mut rng = crate::io::production::ProductionRng::new();
+                RespValue::BulkString(Some(generate_password(&mut rng, bits).into_bytes()))
             }
-            RespValue::BulkString(Some(result.into_bytes()))
+            Err(e) => RespValue::err(e),
         }
     }
 
@@ -1011,52 +1313,429 @@ where
         }
     }
 
-    /// Check if a command name is a stub command (PubSub, HELLO, CLIENT subcommands, etc.)
+    /// True while the connection has at least one channel subscription
+    fn is_subscribed(&self) -> bool {
+        self.conn.pubsub.as_ref().is_some_and(PubSubSession::is_subscribed)
+    }
+
+    /// Handle (P)SUBSCRIBE/(P)UNSUBSCRIBE and the RESP2 subscribed-mode rules.
+    ///
+    /// Returns `None` for commands that take the regular path.
+    fn try_pubsub_command(&mut self, cmd: &Command) -> Option<Vec<RespValue>> {
+        let is_subscription = matches!(
+            cmd,
+            Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+        );
+
+        if self.is_subscribed() {
+            let allowed = is_subscription
+                || matches!(cmd, Command::Ping(_) | Command::Reset)
+                || matches!(cmd, Command::Unknown(name) if matches!(
+                    name.to_uppercase().as_str(),
+                    "SSUBSCRIBE" | "SUNSUBSCRIBE" | "QUIT"
+                ));
+            if !allowed {
+                let name = match cmd {
+                    Command::Unknown(name) => name.to_lowercase(),
+                    _ => cmd.name().to_lowercase(),
+                };
+                return Some(vec![RespValue::err(format!(
+                    "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+                    name
+                ))]);
+            }
+            if let Command::Ping(msg) = cmd {
+                let payload = msg.as_ref().map(|m| m.as_bytes().to_vec()).unwrap_or_default();
+                return Some(vec![RespValue::Array(Some(vec![
+                    RespValue::BulkString(Some(b"pong".to_vec())),
+                    RespValue::BulkString(Some(payload)),
+                ]))]);
+            }
+        }
+
+        if !is_subscription {
+            return None;
+        }
+        if self.conn.in_transaction {
+            self.conn.transaction_errors = true;
+            return Some(vec![RespValue::err(
+                "ERR Command not allowed inside a transaction",
+            )]);
+        }
+        if let Err(acl_err) = self.check_acl_permission(cmd) {
+            return Some(vec![RespValue::err(acl_err)]);
+        }
+
+        let replies = match cmd {
+            Command::Subscribe(channels) => {
+                let manager = self.state.pubsub();
+                self.conn.pubsub
+                    .get_or_insert_with(|| manager.session())
+                    .subscribe(channels)
+            }
+            Command::Unsubscribe(channels) => match self.conn.pubsub.as_mut() {
+                Some(session) => session.unsubscribe(channels),
+                // Never subscribed: same reply as unsubscribing from nothing
+                None => self.state.pubsub().session().unsubscribe(channels),
+            },
+            Command::PSubscribe(patterns) => {
+                let manager = self.state.pubsub();
+                self.conn.pubsub
+                    .get_or_insert_with(|| manager.session())
+                    .psubscribe(patterns)
+            }
+            Command::PUnsubscribe(patterns) => match self.conn.pubsub.as_mut() {
+                Some(session) => session.punsubscribe(patterns),
+                None => self.state.pubsub().session().punsubscribe(patterns),
+            },
+            _ => unreachable!("is_subscription covers exactly (P)SUBSCRIBE/(P)UNSUBSCRIBE"),
+        };
+        Some(replies)
+    }
+
+    /// Next message for this connection; pending forever if it never subscribed
+    async fn next_pubsub_message(pubsub: &mut Option<PubSubSession>) -> Option<PubSubMessage> {
+        match pubsub {
+            Some(session) => session.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Pin the `$` IDs of an XREAD BLOCK before its first attempt, so a retry
+    /// after a wakeup still sees the entries added since the command arrived
+    async fn pin_stream_ids(&self, cmd: &mut Cow<'_, Command>) {
+        let probes = blocking::last_id_probes(cmd);
+        if probes.is_empty() {
+            return;
+        }
+        let mut replies = Vec::with_capacity(probes.len());
+        for probe in &probes {
+            replies.push(self.state.execute(probe).await);
+        }
+        blocking::pin_last_ids(cmd.to_mut(), &replies);
+    }
+
+    /// Turn the nil reply of a blocking command (BLPOP/BRPOP/BLMOVE,
+    /// BZPOPMIN/BZPOPMAX, XREAD/XREADGROUP BLOCK) into a wait: register on
+    /// its keys, retry, and sleep until a write signals one of them or the
+    /// tokio timer fires. Replies nil on timeout and when the
+    /// session is closed while blocked. Inside MULTI the queued command runs
+    /// through EXEC instead and never blocks, as in Redis.
+    async fn wait_if_blocked(&self, cmd: &Command, reply: RespValue) -> RespValue {
+        let Some((keys, timeout_ms)) = cmd.blocking_keys() else {
+            return reply;
+        };
+        if !matches!(reply, RespValue::Array(None) | RespValue::BulkString(None)) {
+            return reply;
+        }
+
+        let deadline = (timeout_ms > 0)
+            .then(|| tokio::time::Instant::now() + std::time::Duration::from_millis(timeout_ms));
+        // Registered before the retry, so a push after the first attempt wakes us
+        let blocked = self.state.blocking().block(keys);
+        loop {
+            let reply = self.state.execute(cmd).await;
+            if !matches!(reply, RespValue::Array(None) | RespValue::BulkString(None)) {
+                return reply;
+            }
+            let timer = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = blocked.ready() => {}
+                _ = timer => return reply,
+                _ = self.session.close_requested() => return reply,
+            }
+        }
+    }
+
+    /// Push `first` and every other already-queued message in one write
+    async fn write_pubsub_messages(&mut self, first: PubSubMessage) -> std::io::Result<()> {
+        let encode = self.encoder();
+        encode(&first.to_resp(), &mut self.write_buffer);
+        if let Some(session) = self.conn.pubsub.as_mut() {
+            while let Some(message) = session.try_recv() {
+                encode(&message.to_resp(), &mut self.write_buffer);
+            }
+        }
+        let result = self.stream.write_all(&self.write_buffer).await;
+        self.write_buffer.clear();
+        result?;
+        self.stream.flush().await
+    }
+
+    /// Check if a command name is a stub command (PubSub, CLIENT subcommands, etc.)
     fn is_stub_command(name: &str) -> bool {
         let upper = name.to_uppercase();
-        matches!(
-            upper.as_str(),
-            "PUBLISH" | "SPUBLISH" | "SUBSCRIBE" | "SSUBSCRIBE" | "PSUBSCRIBE"
-                | "UNSUBSCRIBE" | "SUNSUBSCRIBE" | "PUNSUBSCRIBE"
-                | "HELLO" | "RESET"
-        ) || upper.starts_with("CLIENT ")
+        matches!(upper.as_str(), "SPUBLISH" | "SSUBSCRIBE" | "SUNSUBSCRIBE")
+          || upper.starts_with("CLIENT ")
           || upper.starts_with("CONFIG ")
           || upper.starts_with("ACL ")
     }
 
+    /// CLIENT subcommands backed by the session registry. None for any
+    /// other command. These never wait for CLIENT PAUSE, so UNPAUSE can
+    /// always get through.
+    fn handle_client_command(&mut self, cmd: &Command) -> Option<RespValue> {
+        let reply = match cmd {
+            Command::ClientSetName(name) => {
+                if let Err(e) = Self::check_client_name(name) {
+                    return Some(e);
+                }
+                self.session
+                    .set_name((!name.is_empty()).then(|| name.clone()));
+                RespValue::ok()
+            }
+            Command::ClientGetName => {
+                RespValue::BulkString(self.session.name().map(String::into_bytes))
+            }
+            Command::ClientId => RespValue::Integer(self.session.id() as i64),
+            Command::ClientInfo => RespValue::BulkString(
+                self.client_registry
+                    .describe(self.session.id())
+                    .map(String::into_bytes),
+            ),
+            Command::ClientList => {
+                RespValue::BulkString(Some(self.client_registry.list().into_bytes()))
+            }
+            Command::ClientKill {
+                id,
+                addr,
+                user,
+                skip_me,
+                legacy,
+            } => {
+                let skip = skip_me.then(|| self.session.id());
+                let killed = self.client_registry.kill_matching(
+                    *id,
+                    addr.as_deref(),
+                    user.as_deref(),
+                    skip,
+                );
+                match (*legacy, killed) {
+                    (true, 0) => RespValue::err("ERR No such client"),
+                    (true, _) => RespValue::ok(),
+                    (false, n) => RespValue::Integer(n as i64),
+                }
+            }
+            Command::ClientPause {
+                timeout_ms,
+                writes_only,
+            } => {
+                self.client_registry
+                    .pause(std::time::Duration::from_millis(*timeout_ms), *writes_only);
+                RespValue::ok()
+            }
+            Command::ClientUnpause => {
+                self.client_registry.unpause();
+                RespValue::ok()
+            }
+            Command::ClientTracking {
+                on,
+                bcast,
+                prefixes,
+            } => self.handle_client_tracking(*on, *bcast, prefixes),
+            _ => return None,
+        };
+        Some(reply)
+    }
+
+    /// CLIENT TRACKING ON|OFF. Turning it on again in the same mode keeps
+    /// the remembered keys and, for BCAST, adds the new prefixes. A tenant
+    /// only ever hears about its own keys.
+    fn handle_client_tracking(&mut self, on: bool, bcast: bool, prefixes: &[String]) -> RespValue {
+        if !on {
+            self.conn.tracking = None;
+            return RespValue::ok();
+        }
+        let prefixes: Vec<String> = match &self.tenant {
+            Some(tenant) if prefixes.is_empty() => vec![tenant.prefix().to_string()],
+            Some(tenant) => prefixes
+                .iter()
+                .map(|prefix| format!("{}{}", tenant.prefix(), prefix))
+                .collect(),
+            None => prefixes.to_vec(),
+        };
+
+        if let Some(session) = &self.conn.tracking {
+            if session.is_bcast() != bcast {
+                return RespValue::err(
+                    "ERR You can't switch BCAST mode on/off before disabling tracking for this client, and then re-enabling it with a different mode.",
+                );
+            }
+            if bcast {
+                session.add_prefixes(&prefixes);
+            }
+            return RespValue::ok();
+        }
+        let mode = if bcast {
+            TrackingMode::Bcast {
+                prefixes: prefixes.into_iter().collect(),
+            }
+        } else {
+            TrackingMode::Default
+        };
+        self.conn.tracking = Some(self.state.tracking().session(mode));
+        RespValue::ok()
+    }
+
+    /// Default-mode tracking: remember the keys a read-only command reads.
+    /// Called before the command runs, so a concurrent write on another
+    /// shard is never missed.
+    fn remember_reads(&self, cmd: &Command) {
+        if let Some(session) = &self.conn.tracking {
+            if cmd.is_read_only() && !session.is_bcast() {
+                session.remember(&cmd.get_keys());
+            }
+        }
+    }
+
+    /// Next invalidation for this connection; pending forever without tracking
+    async fn next_invalidation(tracking: &mut Option<TrackingSession>) -> Option<Invalidation> {
+        match tracking {
+            Some(session) => session.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Push `first` and every other already-queued invalidation in one
+    /// write. A RESP2 connection drops them, as Redis does without REDIRECT.
+    async fn write_invalidations(&mut self, first: Invalidation) -> std::io::Result<()> {
+        let mut pending = vec![first];
+        if let Some(session) = self.conn.tracking.as_mut() {
+            while let Some(invalidation) = session.try_recv() {
+                pending.push(invalidation);
+            }
+        }
+        if self.protocol == Protocol::Resp2 {
+            return Ok(());
+        }
+        for invalidation in pending {
+            let invalidation = match (&self.tenant, invalidation) {
+                (Some(tenant), Invalidation::Keys(keys)) => Invalidation::Keys(
+                    keys.into_iter()
+                        .map(|key| {
+                            key.strip_prefix(tenant.prefix())
+                                .map_or_else(|| key.clone(), str::to_string)
+                        })
+                        .collect(),
+                ),
+                (_, invalidation) => invalidation,
+            };
+            Self::encode_resp3_into(&invalidation.to_resp(), &mut self.write_buffer);
+        }
+        let result = self.stream.write_all(&self.write_buffer).await;
+        self.write_buffer.clear();
+        result?;
+        self.stream.flush().await
+    }
+
+    /// HELLO [protover [AUTH username password] [SETNAME clientname]]:
+    /// authenticate, name the connection and switch protocol, then return
+    /// basic server info as a map. A failed step changes nothing after it.
+    fn handle_hello(
+        &mut self,
+        protover: Option<i64>,
+        auth: Option<&(String, String)>,
+        setname: Option<&str>,
+    ) -> RespValue {
+        let protocol = match protover.map(Protocol::from_version) {
+            Some(Some(protocol)) => protocol,
+            Some(None) => return RespValue::err("NOPROTO unsupported protocol version"),
+            None => self.protocol,
+        };
+        if let Some(Err(e)) = setname.map(Self::check_client_name) {
+            return e;
+        }
+        if let Some((username, password)) = auth {
+            let reply = self.handle_auth(Some(username), password);
+            if matches!(reply, RespValue::Error(_)) {
+                return reply;
+            }
+        }
+        if self.acl_manager.read().requires_auth() && self.authenticated_user.is_none() {
+            return RespValue::err(
+                "NOAUTH HELLO must be called with the client already authenticated, otherwise \
+                 the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the \
+                 client and select the RESP protocol version at the same time",
+            );
+        }
+        if let Some(name) = setname {
+            self.session
+                .set_name((!name.is_empty()).then(|| name.to_string()));
+        }
+        self.protocol = protocol;
+
+        let bulk = |s: &str| RespValue::BulkString(Some(s.as_bytes().to_vec()));
+        RespValue::Map(vec![
+            (bulk("server"), bulk("redis")),
+            (bulk("version"), bulk(REDIS_VERSION)),
+            (bulk("proto"), RespValue::Integer(self.protocol.version())),
+            (bulk("id"), RespValue::Integer(1)),
+            (bulk("mode"), bulk(ServerMode::Standalone.name())),
+            (bulk("role"), bulk("master")),
+            (bulk("modules"), RespValue::Array(Some(Vec::new()))),
+        ])
+    }
+
+    /// CLIENT SETNAME and HELLO SETNAME take names without spaces or
+    /// special characters
+    fn check_client_name(name: &str) -> Result<(), RespValue> {
+        if name.bytes().any(|b| !(b'!'..=b'~').contains(&b)) {
+            return Err(RespValue::err(
+                "ERR Client names cannot contain spaces, newlines or special characters.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// RESET: drop MULTI, WATCH, subscriptions and tracking, then put the
+    /// connection back to how it was accepted: default user (or none when
+    /// auth is required), RESP2 and no client name.
+    fn handle_reset(&mut self) -> RespValue {
+        self.conn.reset();
+
+        let manager = self.acl_manager.read();
+        let user = (!manager.requires_auth()).then(|| manager.default_user());
+        drop(manager);
+        self.client_registry.set_user(self.session.id(), "default");
+        self.tenant = Self::tenant_of(user.as_deref());
+        self.authenticated_user = user;
+        self.protocol = Protocol::default();
+        self.session.set_name(None);
+
+        // TigerStyle: Postcondition - nothing survives from the old session
+        debug_assert!(
+            !self.conn.in_transaction && !self.is_subscribed() && self.conn.tracking.is_none(),
+            "Postcondition violated: RESET must leave a fresh connection"
+        );
+        RespValue::simple("RESET")
+    }
+
     /// Handle stub commands — return benign responses
     fn handle_stub_command(name: &str) -> RespValue {
         match name.to_uppercase().as_str() {
-            "PUBLISH" | "SPUBLISH" => RespValue::Integer(0),
-            "SUBSCRIBE" | "SSUBSCRIBE" => RespValue::Array(Some(vec![
+            "SPUBLISH" => RespValue::Integer(0),
+            "SSUBSCRIBE" => RespValue::Array(Some(vec![
                 RespValue::BulkString(Some(b"subscribe".to_vec())),
                 RespValue::BulkString(Some(b"channel".to_vec())),
                 RespValue::Integer(1),
             ])),
-            "PSUBSCRIBE" => RespValue::Array(Some(vec![
-                RespValue::BulkString(Some(b"psubscribe".to_vec())),
-                RespValue::BulkString(Some(b"*".to_vec())),
-                RespValue::Integer(1),
-            ])),
-            "UNSUBSCRIBE" | "SUNSUBSCRIBE" => RespValue::Array(Some(vec![
+            "SUNSUBSCRIBE" => RespValue::Array(Some(vec![
                 RespValue::BulkString(Some(b"unsubscribe".to_vec())),
                 RespValue::BulkString(None),
                 RespValue::Integer(0),
             ])),
-            "PUNSUBSCRIBE" => RespValue::Array(Some(vec![
-                RespValue::BulkString(Some(b"punsubscribe".to_vec())),
-                RespValue::BulkString(None),
-                RespValue::Integer(0),
-            ])),
             // CLIENT subcommands
             name if name.starts_with("CLIENT ") => {
                 let sub = &name[7..];
                 match sub {
-                    "LIST" => {
-                        // Return empty client list
-                        RespValue::BulkString(Some(Vec::new()))
-                    }
-                    "KILL" => RespValue::simple("OK"),
                     "NO-EVICT" => RespValue::simple("OK"),
                     _ => RespValue::err(format!("ERR unknown subcommand '{}'", sub.to_lowercase())),
                 }
@@ -1071,26 +1750,6 @@ where
                     _ => RespValue::simple("OK"),
                 }
             }
-            "HELLO" => {
-                // Return basic RESP2 server info
-                RespValue::Array(Some(vec![
-                    RespValue::BulkString(Some(b"server".to_vec())),
-                    RespValue::BulkString(Some(b"redis".to_vec())),
-                    RespValue::BulkString(Some(b"version".to_vec())),
-                    RespValue::BulkString(Some(b"7.0.0".to_vec())),
-                    RespValue::BulkString(Some(b"proto".to_vec())),
-                    RespValue::Integer(2),
-                    RespValue::BulkString(Some(b"id".to_vec())),
-                    RespValue::Integer(1),
-                    RespValue::BulkString(Some(b"mode".to_vec())),
-                    RespValue::BulkString(Some(b"standalone".to_vec())),
-                    RespValue::BulkString(Some(b"role".to_vec())),
-                    RespValue::BulkString(Some(b"master".to_vec())),
-                    RespValue::BulkString(Some(b"modules".to_vec())),
-                    RespValue::Array(Some(Vec::new())),
-                ]))
-            }
-            "RESET" => RespValue::simple("RESET"),
             // ACL stub subcommands
             name if name.starts_with("ACL ") => {
                 let sub = &name[4..];
@@ -1147,6 +1806,9 @@ where
             let Ok(key_len) = parse_usize_fast(len_str).ok_or(()) else {
                 break; // Invalid, stop
             };
+            if !self.fast_path_allows(key_len) {
+                break;
+            }
 
             // Check we have complete key + trailing \r\n
             let key_start = HEADER_LEN + 1 + len_end + 1;
@@ -1208,6 +1870,9 @@ where
             let Ok(key_len) = parse_usize_fast(key_len_str).ok_or(()) else {
                 break; // Invalid, stop
             };
+            if !self.fast_path_allows(key_len) {
+                break;
+            }
 
             // Calculate key position
             let key_start = HEADER_LEN + 1 + key_len_crlf + 2; // After $<keylen>\r\n
@@ -1233,6 +1898,9 @@ where
             let Ok(val_len) = parse_usize_fast(val_len_str).ok_or(()) else {
                 break; // Invalid
             };
+            if !self.fast_path_allows(val_len) {
+                break;
+            }
 
             // Calculate value position and total length
             let val_start = val_len_start + 1 + val_len_crlf + 2; // After $<vallen>\r\n
@@ -1255,6 +1923,26 @@ where
         (pairs, count)
     }
 
+    /// Whether a fast path may take a bulk string `len` bytes long; longer
+    /// ones are left to `RespCodec`, which rejects them
+    #[inline]
+    fn fast_path_allows(&self, len: usize) -> bool {
+        len <= self.config.protocol_limits.max_bulk_len
+    }
+
+    /// A fast path still waiting for a length header's CRLF: more data,
+    /// unless `header` is already longer than any length, in which case
+    /// `RespCodec` decides
+    #[inline]
+    fn await_length_header(header: &[u8]) -> FastPathResult {
+        // "-9223372036854775808" is the longest length that parses
+        const MAX_LENGTH_DIGITS: usize = 20;
+        if header.len() > MAX_LENGTH_DIGITS {
+            return FastPathResult::NotFastPath;
+        }
+        FastPathResult::NeedMoreData
+    }
+
     /// Fast path for GET/SET commands - bypasses full RESP parsing
     ///
     /// RESP format for GET: *2\r\n$3\r\nGET\r\n$<keylen>\r\n<key>\r\n
@@ -1301,7 +1989,7 @@ where
 
         // Find \r\n after key length
         let Some(crlf_pos) = memchr::memchr(b'\r', &after_header[1..]) else {
-            return FastPathResult::NeedMoreData;
+            return Self::await_length_header(&after_header[1..]);
         };
         let len_end = crlf_pos + 1; // Position of \r relative to after_header[1..]
 
@@ -1311,6 +1999,9 @@ where
         let Ok(key_len) = parse_usize_fast(len_str).ok_or(()) else {
             return FastPathResult::NotFastPath; // Invalid length
         };
+        if !self.fast_path_allows(key_len) {
+            return FastPathResult::NotFastPath;
+        }
 
         // Check we have complete key + trailing \r\n
         let key_start = HEADER_LEN + 1 + len_end + 1; // After $<len>\r\n
@@ -1332,8 +2023,9 @@ where
         let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
         let success = !matches!(&response, RespValue::Error(_));
         self.metrics.record_command("GET", duration_ms, success);
+        self.session.record_command("get".to_string());
 
-        Self::encode_resp_into(&response, &mut self.write_buffer);
+        self.write_reply(&response).await;
         FastPathResult::Handled
     }
 
@@ -1356,7 +2048,7 @@ where
         }
 
         let Some(key_len_crlf) = memchr::memchr(b'\r', &after_header[1..]) else {
-            return FastPathResult::NeedMoreData;
+            return Self::await_length_header(&after_header[1..]);
         };
 
         let key_len_str = &after_header[1..key_len_crlf + 1];
@@ -1364,6 +2056,9 @@ where
         let Ok(key_len) = parse_usize_fast(key_len_str).ok_or(()) else {
             return FastPathResult::NotFastPath;
         };
+        if !self.fast_path_allows(key_len) {
+            return FastPathResult::NotFastPath;
+        }
 
         // Calculate key position
         let key_start = HEADER_LEN + 1 + key_len_crlf + 2; // After $<keylen>\r\n
@@ -1381,7 +2076,7 @@ where
 
         let after_key = &buf[val_len_start + 1..];
         let Some(val_len_crlf) = memchr::memchr(b'\r', after_key) else {
-            return FastPathResult::NeedMoreData;
+            return Self::await_length_header(after_key);
         };
 
         let val_len_str = &after_key[..val_len_crlf];
@@ -1389,6 +2084,9 @@ where
         let Ok(val_len) = parse_usize_fast(val_len_str).ok_or(()) else {
             return FastPathResult::NotFastPath;
         };
+        if !self.fast_path_allows(val_len) {
+            return FastPathResult::NotFastPath;
+        }
 
         // Calculate value position and total length
         let val_start = val_len_start + 1 + val_len_crlf + 2; // After $<vallen>\r\n
@@ -1411,11 +2109,76 @@ where
         let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
         let success = !matches!(&response, RespValue::Error(_));
         self.metrics.record_command("SET", duration_ms, success);
+        self.session.record_command("set".to_string());
 
         Self::encode_resp_into(&response, &mut self.write_buffer);
         FastPathResult::Handled
     }
 
+    /// The encoder for the negotiated protocol
+    fn encoder(&self) -> fn(&RespValue, &mut BytesMut) {
+        match self.protocol {
+            Protocol::Resp2 => Self::encode_resp_into,
+            Protocol::Resp3 => Self::encode_resp3_into,
+        }
+    }
+
+    /// Queue a reply in the negotiated protocol, streaming it when it would
+    /// push the write buffer past the high-watermark (see `reply_stream`)
+    #[inline]
+    async fn write_reply(&mut self, value: &RespValue) {
+        let high_watermark = self.config.write_high_watermark;
+        if self.write_error.is_some() {
+            return;
+        }
+        let encode = self.encoder();
+        // The RESP2 length bounds RESP3's, whose null is shorter
+        if self.write_buffer.len() + reply_stream::encoded_len(value) <= high_watermark {
+            encode(value, &mut self.write_buffer);
+            return;
+        }
+        if let Err(e) = reply_stream::write_streamed(
+            &mut self.stream,
+            &mut self.write_buffer,
+            value,
+            high_watermark,
+            encode,
+        )
+        .await
+        {
+            self.write_buffer.clear();
+            self.write_error = Some(e);
+        }
+    }
+
+    /// Queue a command's reply in the type the negotiated protocol gives it
+    /// (see `reply_shape`)
+    async fn write_shaped_reply(&mut self, value: &RespValue, shape: ReplyShape) {
+        if self.protocol == Protocol::Resp2 || self.write_error.is_some() {
+            return self.write_reply(value).await;
+        }
+        if shape.write_scalar(value, &mut self.write_buffer) {
+            return;
+        }
+        let Some(elements) = shape.write_header(value, &mut self.write_buffer) else {
+            return self.write_reply(value).await;
+        };
+        for (index, element) in elements.iter().enumerate() {
+            let element_shape = shape.element(index);
+            if element_shape.write_scalar(element, &mut self.write_buffer) {
+                continue;
+            }
+            match element_shape.write_header(element, &mut self.write_buffer) {
+                Some(fields) => {
+                    for field in fields {
+                        self.write_reply(field).await;
+                    }
+                }
+                None => self.write_reply(element).await,
+            }
+        }
+    }
+
     /// Encode RESP value into buffer
     /// P3 optimization: Use itoa for fast integer encoding when opt-itoa-encode is enabled
     #[inline]
@@ -1487,6 +2250,67 @@ where
                     Self::encode_resp_into(elem, buf);
                 }
             }
+            resp3 => Self::encode_resp_into(&resp3.to_resp2(), buf),
+        }
+    }
+
+    /// Encode a RESP value for a RESP3 connection: RESP3 types as they
+    /// are, nil bulk strings and arrays as the null type, the rest as RESP2
+    fn encode_resp3_into(value: &RespValue, buf: &mut BytesMut) {
+        match value {
+            RespValue::BulkString(None) | RespValue::Array(None) | RespValue::Null => {
+                buf.extend_from_slice(b"_\r\n");
+            }
+            RespValue::Array(Some(elements))
+            | RespValue::Set(elements)
+            | RespValue::Push(elements) => {
+                buf.put_u8(match value {
+                    RespValue::Set(_) => b'~',
+                    RespValue::Push(_) => b'>',
+                    _ => b'*',
+                });
+                buf.extend_from_slice(elements.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                for elem in elements {
+                    Self::encode_resp3_into(elem, buf);
+                }
+            }
+            RespValue::Map(pairs) | RespValue::Attribute(pairs, _) => {
+                buf.put_u8(if matches!(value, RespValue::Map(_)) { b'%' } else { b'|' });
+                buf.extend_from_slice(pairs.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                for (key, value) in pairs {
+                    Self::encode_resp3_into(key, buf);
+                    Self::encode_resp3_into(value, buf);
+                }
+                // Attributes precede the reply they describe
+                if let RespValue::Attribute(_, reply) = value {
+                    Self::encode_resp3_into(reply, buf);
+                }
+            }
+            RespValue::Double(d) => {
+                buf.put_u8(b',');
+                buf.extend_from_slice(double_text(*d).as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespValue::Boolean(b) => {
+                buf.extend_from_slice(if *b { b"#t\r\n" } else { b"#f\r\n" });
+            }
+            RespValue::BigNumber(digits) => {
+                buf.put_u8(b'(');
+                buf.extend_from_slice(digits.as_bytes());
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespValue::Verbatim { format, text } => {
+                buf.put_u8(b'=');
+                buf.extend_from_slice((text.len() + 4).to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                buf.extend_from_slice(format);
+                buf.put_u8(b':');
+                buf.extend_from_slice(text);
+                buf.extend_from_slice(b"\r\n");
+            }
+            other => Self::encode_resp_into(other, buf),
         }
     }
 
@@ -1511,6 +2335,8 @@ enum CommandResult {
     Executed,
     NeedMoreData,
     ParseError(String),
+    /// SHUTDOWN was sent: nothing after it in the pipeline runs
+    Shutdown,
 }
 
 /// Result of attempting fast path execution
@@ -1523,17 +2349,908 @@ enum FastPathResult {
     NotFastPath,
 }
 
-/// Compare two RespValues for equality (used by WATCH/EXEC)
-fn resp_values_equal(a: &RespValue, b: &RespValue) -> bool {
-    match (a, b) {
-        (RespValue::BulkString(a), RespValue::BulkString(b)) => a == b,
-        (RespValue::Integer(a), RespValue::Integer(b)) => a == b,
-        (RespValue::SimpleString(a), RespValue::SimpleString(b)) => a == b,
-        (RespValue::Error(a), RespValue::Error(b)) => a == b,
-        (RespValue::Array(None), RespValue::Array(None)) => true,
-        (RespValue::Array(Some(a)), RespValue::Array(Some(b))) => {
-            a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| resp_values_equal(x, y))
-        }
-        _ => false,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::DatadogConfig;
+    use crate::production::ConnectionPool;
+    use std::time::Duration;
+    use tokio::io::DuplexStream;
+
+    fn spawn_client(state: &ShardedActorState) -> DuplexStream {
+        spawn_client_with_acl(state, Arc::new(RwLock::new(AclManager::new())))
+    }
+
+    fn spawn_client_with_acl(
+        state: &ShardedActorState,
+        acl_manager: Arc<RwLock<AclManager>>,
+    ) -> DuplexStream {
+        spawn_client_with_config(state, acl_manager, ConnectionConfig::default())
+    }
+
+    fn spawn_client_with_config(
+        state: &ShardedActorState,
+        acl_manager: Arc<RwLock<AclManager>>,
+        config: ConnectionConfig,
+    ) -> DuplexStream {
+        spawn_registered_client(
+            state,
+            acl_manager,
+            config,
+            Arc::new(ClientRegistry::new()),
+            "test:1",
+        )
+    }
+
+    fn spawn_registered_client(
+        state: &ShardedActorState,
+        acl_manager: Arc<RwLock<AclManager>>,
+        config: ConnectionConfig,
+        client_registry: Arc<ClientRegistry>,
+        addr: &str,
+    ) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let pool = ConnectionPool::new(16, 16);
+        let handler = OptimizedConnectionHandler::new(
+            server,
+            state.clone(),
+            addr.to_string(),
+            pool.buffer_pool(),
+            Arc::new(Metrics::new(&DatadogConfig::from_env())),
+            config,
+            acl_manager,
+            None,
+            client_registry,
+            None,
+        );
+        tokio::spawn(handler.run());
+        client
+    }
+
+    fn resp_command(args: &[&str]) -> Vec<u8> {
+        let mut out = format!("*{}\r\n", args.len()).into_bytes();
+        for arg in args {
+            out.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+        }
+        out
+    }
+
+    async fn send(client: &mut DuplexStream, args: &[&str]) {
+        client.write_all(&resp_command(args)).await.unwrap();
+    }
+
+    /// Read exactly `expected.len()` bytes and compare
+    async fn expect(client: &mut DuplexStream, expected: &str) {
+        let mut buf = vec![0u8; expected.len()];
+        tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut buf))
+            .await
+            .expect("reply timed out")
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&buf), expected);
+    }
+
+    /// Read one bulk string reply
+    async fn read_bulk(client: &mut DuplexStream) -> String {
+        let mut header = Vec::new();
+        while !header.ends_with(b"\r\n") {
+            let mut byte = [0u8; 1];
+            tokio::time::timeout(Duration::from_secs(5), client.read_exact(&mut byte))
+                .await
+                .expect("reply timed out")
+                .unwrap();
+            header.push(byte[0]);
+        }
+        let len: usize = std::str::from_utf8(&header[1..header.len() - 2])
+            .unwrap()
+            .parse()
+            .unwrap_or_else(|_| panic!("expected a bulk string, got {:?}", header));
+        let mut body = vec![0u8; len + 2];
+        client.read_exact(&mut body).await.unwrap();
+        body.truncate(len);
+        String::from_utf8(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_acl_genpass_follows_redis_bits() {
+        let state = ShardedActorState::with_shards(1);
+        let mut client = spawn_client(&state);
+
+        send(&mut client, &["ACL", "GENPASS"]).await;
+        let first = read_bulk(&mut client).await;
+        assert_eq!(first.len(), 64);
+        assert!(first.bytes().all(|b| b.is_ascii_hexdigit()));
+        send(&mut client, &["ACL", "GENPASS"]).await;
+        assert_ne!(read_bulk(&mut client).await, first);
+
+        send(&mut client, &["ACL", "GENPASS", "5"]).await;
+        assert_eq!(read_bulk(&mut client).await.len(), 2);
+        send(&mut client, &["ACL", "GENPASS", "4096"]).await;
+        assert_eq!(read_bulk(&mut client).await.len(), 1024);
+        send(&mut client, &["ACL", "GENPASS", "4097"]).await;
+        expect(
+            &mut client,
+            "-ERR ACL GENPASS argument must be the number of bits for the output password, a positive number up to 4096\r\n",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_receives_published_messages() {
+        let state = ShardedActorState::with_shards(4);
+        let mut subscriber = spawn_client(&state);
+        let mut publisher = spawn_client(&state);
+
+        send(&mut subscriber, &["SUBSCRIBE", "news", "sports"]).await;
+        expect(
+            &mut subscriber,
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n\
+             *3\r\n$9\r\nsubscribe\r\n$6\r\nsports\r\n:2\r\n",
+        )
+        .await;
+
+        send(&mut publisher, &["PUBLISH", "news", "hello"]).await;
+        expect(&mut publisher, ":1\r\n").await;
+        send(&mut publisher, &["PUBLISH", "weather", "rain"]).await;
+        expect(&mut publisher, ":0\r\n").await;
+        expect(
+            &mut subscriber,
+            "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n",
+        )
+        .await;
+
+        // Subscribed mode: only Pub/Sub commands and PING
+        send(&mut subscriber, &["GET", "k"]).await;
+        expect(
+            &mut subscriber,
+            "-ERR Can't execute 'get': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context\r\n",
+        )
+        .await;
+        send(&mut subscriber, &["PING"]).await;
+        expect(&mut subscriber, "*2\r\n$4\r\npong\r\n$0\r\n\r\n").await;
+
+        send(&mut subscriber, &["UNSUBSCRIBE"]).await;
+        expect(
+            &mut subscriber,
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:1\r\n\
+             *3\r\n$11\r\nunsubscribe\r\n$6\r\nsports\r\n:0\r\n",
+        )
+        .await;
+        send(&mut subscriber, &["PING"]).await;
+        expect(&mut subscriber, "+PONG\r\n").await;
+        send(&mut publisher, &["PUBLISH", "news", "again"]).await;
+        expect(&mut publisher, ":0\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_push_on_other_connection_unblocks_blpop() {
+        let state = ShardedActorState::with_shards(4);
+        let mut waiter = spawn_client(&state);
+        let mut pusher = spawn_client(&state);
+
+        send(&mut waiter, &["BLPOP", "empty", "jobs", "0"]).await;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while state.blocking().num_blocked() == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("BLPOP on empty lists must block");
+
+        send(&mut pusher, &["RPUSH", "jobs", "j1"]).await;
+        expect(&mut pusher, ":1\r\n").await;
+        expect(&mut waiter, "*2\r\n$4\r\njobs\r\n$2\r\nj1\r\n").await;
+        assert_eq!(state.blocking().num_blocked(), 0);
+
+        // Commands pipelined behind a blocked one run after it is served
+        send(&mut waiter, &["BRPOP", "jobs", "0.05"]).await;
+        send(&mut waiter, &["PING"]).await;
+        expect(&mut waiter, "*-1\r\n+PONG\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_xadd_unblocks_xread_block_dollar() {
+        let state = ShardedActorState::with_shards(4);
+        let mut reader = spawn_client(&state);
+        let mut writer = spawn_client(&state);
+        send(&mut writer, &["XADD", "s", "1-1", "f", "old"]).await;
+        expect(&mut writer, "$3\r\n1-1\r\n").await;
+
+        send(&mut reader, &["XREAD", "BLOCK", "0", "STREAMS", "s", "$"]).await;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while state.blocking().num_blocked() == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("XREAD BLOCK $ must block");
+
+        send(&mut writer, &["XADD", "s", "2-1", "f", "new"]).await;
+        expect(&mut writer, "$3\r\n2-1\r\n").await;
+        expect(
+            &mut reader,
+            "*1\r\n*2\r\n$1\r\ns\r\n*1\r\n*2\r\n$3\r\n2-1\r\n*2\r\n$1\r\nf\r\n$3\r\nnew\r\n",
+        )
+        .await;
+
+        // Each connection keeps its own deadline
+        send(&mut reader, &["BZPOPMIN", "z", "0.05"]).await;
+        send(&mut writer, &["BZPOPMAX", "z", "0"]).await;
+        expect(&mut reader, "*-1\r\n").await;
+        send(&mut reader, &["ZADD", "z", "1", "m"]).await;
+        expect(&mut reader, ":1\r\n").await;
+        expect(&mut writer, "*3\r\n$1\r\nz\r\n$1\r\nm\r\n$1\r\n1\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_closed_connection_drops_subscriptions() {
+        let state = ShardedActorState::with_shards(2);
+        let mut subscriber = spawn_client(&state);
+        send(&mut subscriber, &["SUBSCRIBE", "c"]).await;
+        expect(&mut subscriber, "*3\r\n$9\r\nsubscribe\r\n$1\r\nc\r\n:1\r\n").await;
+        assert_eq!(state.pubsub().num_subscribers("c"), 1);
+
+        drop(subscriber);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while state.pubsub().num_subscribers("c") > 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("closed connection must unsubscribe");
+    }
+
+    #[tokio::test]
+    async fn test_pattern_subscriber_receives_pmessages() {
+        let state = ShardedActorState::with_shards(4);
+        let mut subscriber = spawn_client(&state);
+        let mut publisher = spawn_client(&state);
+
+        send(&mut subscriber, &["SUBSCRIBE", "news.tech"]).await;
+        expect(&mut subscriber, "*3\r\n$9\r\nsubscribe\r\n$9\r\nnews.tech\r\n:1\r\n").await;
+        send(&mut subscriber, &["PSUBSCRIBE", "news.*"]).await;
+        expect(&mut subscriber, "*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:2\r\n").await;
+
+        send(&mut publisher, &["PUBSUB", "NUMPAT"]).await;
+        expect(&mut publisher, ":1\r\n").await;
+        send(&mut publisher, &["PUBSUB", "NUMSUB", "news.tech", "news.art"]).await;
+        expect(
+            &mut publisher,
+            "*4\r\n$9\r\nnews.tech\r\n:1\r\n$8\r\nnews.art\r\n:0\r\n",
+        )
+        .await;
+
+        // Channel and pattern subscriptions each get a copy, channel first
+        send(&mut publisher, &["PUBLISH", "news.tech", "hi"]).await;
+        expect(&mut publisher, ":2\r\n").await;
+        expect(
+            &mut subscriber,
+            "*3\r\n$7\r\nmessage\r\n$9\r\nnews.tech\r\n$2\r\nhi\r\n\
+             *4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$9\r\nnews.tech\r\n$2\r\nhi\r\n",
+        )
+        .await;
+
+        send(&mut subscriber, &["PUNSUBSCRIBE"]).await;
+        expect(&mut subscriber, "*3\r\n$12\r\npunsubscribe\r\n$6\r\nnews.*\r\n:1\r\n").await;
+        send(&mut publisher, &["PUBLISH", "news.art", "x"]).await;
+        expect(&mut publisher, ":0\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_large_gets_are_streamed() {
+        let state = ShardedActorState::with_shards(4);
+        let config = ConnectionConfig {
+            write_high_watermark: 16 * 1024,
+            ..ConnectionConfig::default()
+        };
+        let acl_manager = Arc::new(RwLock::new(AclManager::new()));
+        let value = "v".repeat(2 * 1024 * 1024);
+
+        let mut writer = spawn_client(&state);
+        send(&mut writer, &["SET", "big", &value]).await;
+        expect(&mut writer, "+OK\r\n").await;
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut client =
+                    spawn_client_with_config(&state, acl_manager.clone(), config.clone());
+                let value = value.clone();
+                tokio::spawn(async move {
+                    // Pipelined behind a small reply, which must arrive first
+                    send(&mut client, &["PING"]).await;
+                    send(&mut client, &["GET", "big"]).await;
+                    send(&mut client, &["GET", "missing"]).await;
+                    expect(&mut client, "+PONG\r\n").await;
+                    expect(&mut client, &format!("${}\r\n{}\r\n", value.len(), value)).await;
+                    expect(&mut client, "$-1\r\n").await;
+                })
+            })
+            .collect();
+        for reader in readers {
+            reader.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors_close_the_connection() {
+        let state = ShardedActorState::with_shards(4);
+        let config = ConnectionConfig {
+            protocol_limits: ProtocolLimits {
+                max_bulk_len: 1024,
+                ..ProtocolLimits::default()
+            },
+            ..ConnectionConfig::default()
+        };
+        let acl_manager = Arc::new(RwLock::new(AclManager::new()));
+
+        // Rejected on its header, before the value is sent
+        let mut client = spawn_client_with_config(&state, acl_manager.clone(), config.clone());
+        send(&mut client, &["SET", "k", "small"]).await;
+        client
+            .write_all(b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1025\r\n")
+            .await
+            .unwrap();
+        expect(&mut client, "+OK\r\n-ERR Protocol error: invalid bulk length\r\n").await;
+        let mut rest = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), client.read_to_end(&mut rest))
+            .await
+            .expect("connection left open")
+            .unwrap();
+        assert!(rest.is_empty());
+
+        // A length line that never ends
+        let mut client = spawn_client_with_config(&state, acl_manager, config);
+        let endless = format!("*2\r\n$3\r\nGET\r\n${}", "9".repeat(70 * 1024));
+        let _ = client.write_all(endless.as_bytes()).await;
+        expect(&mut client, "-ERR Protocol error: too big inline request\r\n").await;
+    }
+
+    #[cfg(feature = "acl")]
+    #[tokio::test]
+    async fn test_tenants_are_confined_to_their_keyspace() {
+        use crate::production::TENANT_COMMAND_DENIED;
+        use crate::security::acl::AclCommandHandler;
+
+        let acl_manager = Arc::new(RwLock::new(AclManager::new()));
+        for (user, prefix) in [("acme", "keyspace:acme:"), ("globex", "keyspace:globex:")] {
+            let rules = ["on", "nopass", "~*", "+@all", prefix];
+            AclCommandHandler::handle_setuser(&mut acl_manager.write(), user, &rules).unwrap();
+        }
+
+        let state = ShardedActorState::with_shards(4);
+        let mut acme = spawn_client_with_acl(&state, acl_manager.clone());
+        let mut globex = spawn_client_with_acl(&state, acl_manager.clone());
+        let mut admin = spawn_client_with_acl(&state, acl_manager);
+        for (client, user) in [(&mut acme, "acme"), (&mut globex, "globex")] {
+            send(client, &["AUTH", user, "x"]).await;
+            expect(client, "+OK\r\n").await;
+        }
+
+        send(&mut acme, &["SET", "k", "a"]).await;
+        expect(&mut acme, "+OK\r\n").await;
+        send(&mut globex, &["SET", "k", "g"]).await;
+        expect(&mut globex, "+OK\r\n").await;
+
+        send(&mut acme, &["GET", "k"]).await;
+        expect(&mut acme, "$1\r\na\r\n").await;
+        send(&mut globex, &["KEYS", "*"]).await;
+        expect(&mut globex, "*1\r\n$1\r\nk\r\n").await;
+
+        // The shared keyspace holds both, under their prefixes
+        send(&mut admin, &["GET", "acme:k"]).await;
+        expect(&mut admin, "$1\r\na\r\n").await;
+        send(&mut admin, &["DBSIZE"]).await;
+        expect(&mut admin, ":2\r\n").await;
+
+        send(&mut acme, &["FLUSHALL"]).await;
+        expect(&mut acme, &format!("-{}\r\n", TENANT_COMMAND_DENIED)).await;
+
+        // Queued commands are rewritten too
+        send(&mut acme, &["MULTI"]).await;
+        send(&mut acme, &["INCR", "n"]).await;
+        send(&mut acme, &["KEYS", "*"]).await;
+        send(&mut acme, &["EXEC"]).await;
+        expect(
+            &mut acme,
+            "+OK\r\n+QUEUED\r\n+QUEUED\r\n*2\r\n:1\r\n*2\r\n",
+        )
+        .await;
+        let mut keys = vec![0u8; "$1\r\nk\r\n$1\r\nn\r\n".len()];
+        acme.read_exact(&mut keys).await.unwrap();
+        let keys = String::from_utf8(keys).unwrap();
+        assert!(keys.contains("$1\r\nk\r\n") && keys.contains("$1\r\nn\r\n"));
+    }
+
+    /// HELLO's info as a map of 7 pairs (RESP3) or an array of 14 (RESP2)
+    fn hello_reply(proto: i64) -> String {
+        let header = if proto == 3 { "%7" } else { "*14" };
+        format!(
+            "{}\r\n$6\r\nserver\r\n$5\r\nredis\r\n$7\r\nversion\r\n$5\r\n7.0.0\r\n\
+             $5\r\nproto\r\n:{}\r\n$2\r\nid\r\n:1\r\n$4\r\nmode\r\n$10\r\nstandalone\r\n\
+             $4\r\nrole\r\n$6\r\nmaster\r\n$7\r\nmodules\r\n*0\r\n",
+            header, proto
+        )
+    }
+
+    #[tokio::test]
+    async fn test_resp3_replies_use_maps_and_sets() {
+        let state = ShardedActorState::with_shards(4);
+        let mut resp2 = spawn_client(&state);
+        let mut resp3 = spawn_client(&state);
+
+        send(&mut resp2, &["HSET", "h", "f", "v"]).await;
+        expect(&mut resp2, ":1\r\n").await;
+        send(&mut resp2, &["SADD", "s", "m"]).await;
+        expect(&mut resp2, ":1\r\n").await;
+
+        // RESP2 keeps flat arrays
+        send(&mut resp2, &["HELLO"]).await;
+        expect(&mut resp2, &hello_reply(2)).await;
+        send(&mut resp2, &["HGETALL", "h"]).await;
+        expect(&mut resp2, "*2\r\n$1\r\nf\r\n$1\r\nv\r\n").await;
+        send(&mut resp2, &["SMEMBERS", "s"]).await;
+        expect(&mut resp2, "*1\r\n$1\r\nm\r\n").await;
+        send(&mut resp2, &["CONFIG", "GET", "maxmemory"]).await;
+        expect(&mut resp2, "*2\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n").await;
+
+        // HELLO 3 answers in the protocol it switches to
+        send(&mut resp3, &["HELLO", "3"]).await;
+        expect(&mut resp3, &hello_reply(3)).await;
+        send(&mut resp3, &["HGETALL", "h"]).await;
+        expect(&mut resp3, "%1\r\n$1\r\nf\r\n$1\r\nv\r\n").await;
+        send(&mut resp3, &["HGETALL", "missing"]).await;
+        expect(&mut resp3, "%0\r\n").await;
+        send(&mut resp3, &["SMEMBERS", "s"]).await;
+        expect(&mut resp3, "~1\r\n$1\r\nm\r\n").await;
+        send(&mut resp3, &["CONFIG", "GET", "maxmemory"]).await;
+        expect(&mut resp3, "%1\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n").await;
+        send(&mut resp3, &["XINFO", "STREAM", "x"]).await;
+        expect(&mut resp3, "%0\r\n").await;
+
+        // Errors and other replies are the same in both protocols
+        send(&mut resp3, &["HGETALL", "s"]).await;
+        expect(
+            &mut resp3,
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+        )
+        .await;
+        send(&mut resp3, &["KEYS", "h"]).await;
+        expect(&mut resp3, "*1\r\n$1\r\nh\r\n").await;
+
+        send(&mut resp3, &["HELLO", "4"]).await;
+        expect(&mut resp3, "-NOPROTO unsupported protocol version\r\n").await;
+        send(&mut resp3, &["HELLO", "2"]).await;
+        expect(&mut resp3, &hello_reply(2)).await;
+        send(&mut resp3, &["HGETALL", "h"]).await;
+        expect(&mut resp3, "*2\r\n$1\r\nf\r\n$1\r\nv\r\n").await;
+    }
+
+    #[cfg(feature = "acl")]
+    #[tokio::test]
+    async fn test_hello_authenticates_and_names_the_connection() {
+        use crate::security::acl::AclCommandHandler;
+
+        let acl_manager = Arc::new(RwLock::new(AclManager::new_with_auth()));
+        let rules = ["on", ">secret", "~*", "+@all"];
+        AclCommandHandler::handle_setuser(&mut acl_manager.write(), "app", &rules).unwrap();
+        let state = ShardedActorState::with_shards(4);
+        let mut client = spawn_client_with_acl(&state, acl_manager);
+
+        send(&mut client, &["HELLO", "3"]).await;
+        let noauth = "-NOAUTH HELLO must be called with the client already authenticated, \
+                      otherwise the HELLO <proto> AUTH <user> <pass> option can be used to \
+                      authenticate the client and select the RESP protocol version at the same \
+                      time\r\n";
+        expect(&mut client, noauth).await;
+        send(&mut client, &["HELLO", "3", "AUTH", "app", "wrong"]).await;
+        expect(
+            &mut client,
+            "-WRONGPASS invalid username-password pair or user is disabled.\r\n",
+        )
+        .await;
+        send(&mut client, &["HELLO", "3", "SETNAME", "bad name"]).await;
+        expect(
+            &mut client,
+            "-ERR Client names cannot contain spaces, newlines or special characters.\r\n",
+        )
+        .await;
+        send(&mut client, &["HELLO", "3", "AUTH", "app"]).await;
+        expect(&mut client, "-ERR Syntax error in HELLO option 'AUTH'\r\n").await;
+
+        send(&mut client, &["HELLO", "3", "AUTH", "app", "secret", "SETNAME", "worker"]).await;
+        expect(&mut client, &hello_reply(3)).await;
+        send(&mut client, &["CLIENT", "GETNAME"]).await;
+        expect(&mut client, "$6\r\nworker\r\n").await;
+        send(&mut client, &["ACL", "WHOAMI"]).await;
+        expect(&mut client, "$3\r\napp\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_resp3_pushes_pubsub_and_sends_text_verbatim() {
+        let state = ShardedActorState::with_shards(4);
+        let mut subscriber = spawn_client(&state);
+        let mut publisher = spawn_client(&state);
+        send(&mut subscriber, &["HELLO", "3"]).await;
+        expect(&mut subscriber, &hello_reply(3)).await;
+
+        send(&mut subscriber, &["SUBSCRIBE", "news"]).await;
+        expect(&mut subscriber, ">3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n").await;
+        send(&mut publisher, &["PUBLISH", "news", "hello"]).await;
+        expect(&mut publisher, ":1\r\n").await;
+        expect(
+            &mut subscriber,
+            ">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n",
+        )
+        .await;
+        send(&mut subscriber, &["UNSUBSCRIBE"]).await;
+        expect(&mut subscriber, ">3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:0\r\n").await;
+
+        // Plain-text reports are verbatim strings; RESP2 keeps bulk strings
+        send(&mut subscriber, &["CLIENT", "SETNAME", "sub"]).await;
+        expect(&mut subscriber, "+OK\r\n").await;
+        send(&mut subscriber, &["CLIENT", "INFO"]).await;
+        let info = read_bulk(&mut subscriber).await;
+        assert!(info.starts_with("txt:") && info.contains(" name=sub "), "{}", info);
+        send(&mut publisher, &["CLIENT", "INFO"]).await;
+        assert!(read_bulk(&mut publisher).await.starts_with("id="));
+    }
+
+    #[tokio::test]
+    async fn test_resp3_replies_use_nulls_doubles_and_booleans() {
+        let state = ShardedActorState::with_shards(4);
+        let mut resp2 = spawn_client(&state);
+        let mut resp3 = spawn_client(&state);
+        send(&mut resp3, &["HELLO", "3"]).await;
+        expect(&mut resp3, &hello_reply(3)).await;
+
+        send(&mut resp2, &["ZADD", "z", "1.5", "m"]).await;
+        expect(&mut resp2, ":1\r\n").await;
+        send(&mut resp2, &["ZSCORE", "z", "m"]).await;
+        expect(&mut resp2, "$3\r\n1.5\r\n").await;
+        send(&mut resp2, &["GET", "missing"]).await;
+        expect(&mut resp2, "$-1\r\n").await;
+        send(&mut resp2, &["DEBUG", "PROTOCOL", "true"]).await;
+        expect(&mut resp2, ":1\r\n").await;
+
+        send(&mut resp3, &["ZSCORE", "z", "m"]).await;
+        expect(&mut resp3, ",1.5\r\n").await;
+        send(&mut resp3, &["ZINCRBY", "z", "1", "m"]).await;
+        expect(&mut resp3, ",2.5\r\n").await;
+        send(&mut resp3, &["ZMSCORE", "z", "m", "nope"]).await;
+        expect(&mut resp3, "*2\r\n,2.5\r\n_\r\n").await;
+        send(&mut resp3, &["ZSCORE", "z", "nope"]).await;
+        expect(&mut resp3, "_\r\n").await;
+        send(&mut resp3, &["GET", "missing"]).await;
+        expect(&mut resp3, "_\r\n").await;
+        send(&mut resp3, &["MGET", "missing", "z"]).await;
+        expect(&mut resp3, "*2\r\n_\r\n_\r\n").await;
+
+        // INCRBYFLOAT replies with the string it stored, as in Redis
+        send(&mut resp3, &["INCRBYFLOAT", "f", "0.5"]).await;
+        expect(&mut resp3, "$3\r\n0.5\r\n").await;
+
+        send(&mut resp3, &["DEBUG", "PROTOCOL", "double"]).await;
+        expect(&mut resp3, ",3.141\r\n").await;
+        send(&mut resp3, &["DEBUG", "PROTOCOL", "false"]).await;
+        expect(&mut resp3, "#f\r\n").await;
+        send(&mut resp3, &["DEBUG", "PROTOCOL", "map"]).await;
+        expect(&mut resp3, "%3\r\n:0\r\n#f\r\n:1\r\n#t\r\n:2\r\n#f\r\n").await;
+        send(&mut resp3, &["DEBUG", "PROTOCOL", "set"]).await;
+        expect(&mut resp3, "~3\r\n:0\r\n:1\r\n:2\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_scan_pages_merge_across_shards() {
+        let state = ShardedActorState::with_shards(4);
+        for i in 0..100 {
+            state
+                .execute(&Command::set(
+                    format!("key:{}", i),
+                    crate::redis::SDS::from_str("v"),
+                ))
+                .await;
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let reply = state
+                .execute(&Command::Scan {
+                    cursor,
+                    pattern: None,
+                    count: Some(7),
+                    type_filter: None,
+                })
+                .await;
+            let RespValue::Array(Some(parts)) = reply else {
+                panic!("SCAN returned {:?}", reply);
+            };
+            let (RespValue::BulkString(Some(next)), RespValue::Array(Some(keys))) =
+                (&parts[0], &parts[1])
+            else {
+                panic!("SCAN returned {:?}", parts);
+            };
+            let next: u64 = String::from_utf8_lossy(next).parse().unwrap();
+            seen.extend(keys.iter().cloned());
+            if next == 0 {
+                break;
+            }
+            assert!(next > cursor, "cursors only move forward");
+            cursor = next;
+        }
+
+        // Every key once, though each shard holds about a quarter of them
+        assert_eq!(seen.len(), 100);
+        seen.sort_by_key(|k| format!("{:?}", k));
+        seen.dedup();
+        assert_eq!(seen.len(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_client_list_pause_and_kill() {
+        let state = ShardedActorState::with_shards(4);
+        let registry = Arc::new(ClientRegistry::new());
+        let acl_manager = Arc::new(RwLock::new(AclManager::new()));
+        let spawn = |addr| {
+            spawn_registered_client(
+                &state,
+                acl_manager.clone(),
+                ConnectionConfig::default(),
+                registry.clone(),
+                addr,
+            )
+        };
+        let mut admin = spawn("10.0.0.1:1");
+        let mut worker = spawn("10.0.0.2:2");
+
+        send(&mut worker, &["CLIENT", "SETNAME", "worker"]).await;
+        expect(&mut worker, "+OK\r\n").await;
+        send(&mut admin, &["CLIENT", "LIST"]).await;
+        let list = read_bulk(&mut admin).await;
+        let lines: Vec<&str> = list.lines().collect();
+        assert_eq!(lines.len(), 2, "{}", list);
+        assert!(lines[0].starts_with("id=1 addr=10.0.0.1:1 name= "), "{}", list);
+        assert!(lines[0].contains(" cmd=client|list "), "{}", list);
+        assert!(lines[1].starts_with("id=2 addr=10.0.0.2:2 name=worker "), "{}", list);
+        assert!(lines[1].contains(" cmd=client|setname "), "{}", list);
+
+        // A WRITE pause holds SET until UNPAUSE, but not GET
+        send(&mut admin, &["CLIENT", "PAUSE", "10000", "WRITE"]).await;
+        expect(&mut admin, "+OK\r\n").await;
+        send(&mut worker, &["SET", "k", "v"]).await;
+        send(&mut admin, &["GET", "k"]).await;
+        expect(&mut admin, "$-1\r\n").await;
+        let mut early = [0u8; 1];
+        let held = tokio::time::timeout(Duration::from_millis(50), worker.read_exact(&mut early));
+        assert!(held.await.is_err(), "SET must wait for the pause");
+        send(&mut admin, &["CLIENT", "UNPAUSE"]).await;
+        expect(&mut admin, "+OK\r\n").await;
+        expect(&mut worker, "+OK\r\n").await;
+
+        // SKIPME (on by default) spares the caller; a killed client is closed
+        send(&mut admin, &["CLIENT", "KILL", "ID", "1"]).await;
+        expect(&mut admin, ":0\r\n").await;
+        send(&mut admin, &["CLIENT", "KILL", "ADDR", "10.0.0.2:2"]).await;
+        expect(&mut admin, ":1\r\n").await;
+        let mut rest = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), worker.read_to_end(&mut rest))
+            .await
+            .expect("a killed client must be disconnected")
+            .unwrap();
+        assert!(rest.is_empty());
+        send(&mut admin, &["CLIENT", "KILL", "10.0.0.2:2"]).await;
+        expect(&mut admin, "-ERR No such client\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_wakes_the_server_and_runs_nothing_after_it() {
+        let state = ShardedActorState::with_shards(2);
+        let registry = Arc::new(ClientRegistry::new());
+        let acl_manager = Arc::new(RwLock::new(AclManager::new()));
+        let mut client = spawn_registered_client(
+            &state,
+            acl_manager,
+            ConnectionConfig::default(),
+            registry.clone(),
+            "10.0.0.1:1",
+        );
+
+        send(&mut client, &["SHUTDOWN", "SAVE", "NOSAVE"]).await;
+        expect(&mut client, "-ERR syntax error\r\n").await;
+
+        send(&mut client, &["SET", "before", "1"]).await;
+        expect(&mut client, "+OK\r\n").await;
+
+        // One write: the SET pipelined after SHUTDOWN must not run
+        let mut pipeline = resp_command(&["SHUTDOWN", "NOSAVE"]);
+        pipeline.extend_from_slice(&resp_command(&["SET", "after", "1"]));
+        client.write_all(&pipeline).await.unwrap();
+        let request = tokio::time::timeout(Duration::from_secs(5), registry.shutdown_requested())
+            .await
+            .expect("SHUTDOWN must reach the server");
+        assert_eq!(request, ShutdownRequest { save: false });
+
+        registry.drain_all();
+        expect(&mut client, &format!("-{}\r\n", SHUTDOWN_NOTICE)).await;
+        assert_eq!(
+            state.execute(&Command::Exists(vec!["before".into(), "after".into()])).await,
+            RespValue::Integer(1)
+        );
+    }
+
+    fn invalidate_push(keys: &[&str]) -> String {
+        let mut push = format!(">2\r\n$10\r\ninvalidate\r\n*{}\r\n", keys.len());
+        for key in keys {
+            push.push_str(&format!("${}\r\n{}\r\n", key.len(), key));
+        }
+        push
+    }
+
+    #[tokio::test]
+    async fn test_client_tracking_pushes_invalidations() {
+        let state = ShardedActorState::with_shards(4);
+        let mut reader = spawn_client(&state);
+        let mut watcher = spawn_client(&state);
+        let mut writer = spawn_client(&state);
+        for client in [&mut reader, &mut watcher] {
+            send(client, &["HELLO", "3"]).await;
+            expect(client, &hello_reply(3)).await;
+        }
+        send(&mut writer, &["MSET", "user:1", "a", "cart:1", "b"]).await;
+        expect(&mut writer, "+OK\r\n").await;
+
+        send(&mut reader, &["CLIENT", "TRACKING", "ON"]).await;
+        expect(&mut reader, "+OK\r\n").await;
+        send(&mut reader, &["CLIENT", "TRACKING", "ON", "BCAST"]).await;
+        expect(
+            &mut reader,
+            "-ERR You can't switch BCAST mode on/off before disabling tracking for this client, and then re-enabling it with a different mode.\r\n",
+        )
+        .await;
+        send(
+            &mut watcher,
+            &["CLIENT", "TRACKING", "ON", "BCAST", "PREFIX", "user:"],
+        )
+        .await;
+        expect(&mut watcher, "+OK\r\n").await;
+        assert_eq!(state.tracking().num_clients(), 2);
+
+        // Default mode: keys the reader read, once per read
+        send(&mut reader, &["GET", "user:1"]).await;
+        expect(&mut reader, "$1\r\na\r\n").await;
+        send(&mut reader, &["GET", "cart:1"]).await;
+        expect(&mut reader, "$1\r\nb\r\n").await;
+        send(&mut writer, &["SET", "user:1", "x"]).await;
+        expect(&mut writer, "+OK\r\n").await;
+        expect(&mut reader, &invalidate_push(&["user:1"])).await;
+        expect(&mut watcher, &invalidate_push(&["user:1"])).await;
+        send(&mut writer, &["DEL", "user:1", "cart:1"]).await;
+        expect(&mut writer, ":2\r\n").await;
+        expect(&mut reader, &invalidate_push(&["cart:1"])).await;
+        expect(&mut watcher, &invalidate_push(&["user:1"])).await;
+        send(&mut reader, &["PING"]).await;
+        expect(&mut reader, "+PONG\r\n").await;
+
+        // Turned off, nothing more is pushed
+        send(&mut reader, &["CLIENT", "TRACKING", "OFF"]).await;
+        expect(&mut reader, "+OK\r\n").await;
+        send(&mut reader, &["GET", "cart:1"]).await;
+        expect(&mut reader, "_\r\n").await;
+        send(&mut writer, &["SET", "cart:1", "c"]).await;
+        expect(&mut writer, "+OK\r\n").await;
+        send(&mut reader, &["PING"]).await;
+        expect(&mut reader, "+PONG\r\n").await;
+        assert_eq!(state.tracking().num_clients(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reset_restores_a_fresh_connection() {
+        let state = ShardedActorState::with_shards(4);
+        let mut client = spawn_client(&state);
+        send(&mut client, &["HELLO", "3"]).await;
+        expect(&mut client, &hello_reply(3)).await;
+        send(&mut client, &["CLIENT", "SETNAME", "app"]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut client, &["CLIENT", "TRACKING", "ON"]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut client, &["WATCH", "k"]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut client, &["MULTI"]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut client, &["SET", "k", "v"]).await;
+        expect(&mut client, "+QUEUED\r\n").await;
+
+        // RESET is not queued; it ends the transaction at once
+        send(&mut client, &["RESET"]).await;
+        expect(&mut client, "+RESET\r\n").await;
+        assert_eq!(state.tracking().num_clients(), 0);
+        send(&mut client, &["EXEC"]).await;
+        expect(&mut client, "-ERR EXEC without MULTI\r\n").await;
+        // Back on RESP2 with no name
+        send(&mut client, &["CLIENT", "GETNAME"]).await;
+        expect(&mut client, "$-1\r\n").await;
+
+        // Allowed while subscribed, and unsubscribes
+        send(&mut client, &["SUBSCRIBE", "c"]).await;
+        expect(&mut client, "*3\r\n$9\r\nsubscribe\r\n$1\r\nc\r\n:1\r\n").await;
+        send(&mut client, &["RESET"]).await;
+        expect(&mut client, "+RESET\r\n").await;
+        assert_eq!(state.pubsub().num_subscribers("c"), 0);
+        send(&mut client, &["GET", "k"]).await;
+        expect(&mut client, "$-1\r\n").await;
+        send(&mut client, &["RESET", "extra"]).await;
+        expect(&mut client, "-ERR wrong number of arguments for 'reset' command\r\n").await;
+    }
+
+    #[tokio::test]
+    async fn test_watch_spans_shards_and_sees_reverted_writes() {
+        let state = ShardedActorState::with_shards(4);
+        let router = crate::production::ShardRouter::new(4);
+        let other = (0..)
+            .map(|i| format!("other:{}", i))
+            .find(|key| router.shard_for(key) != router.shard_for("acct"))
+            .expect("some key lives on another shard");
+        let mut client = spawn_client(&state);
+        let mut writer = spawn_client(&state);
+
+        // A write that puts the old value back still aborts EXEC
+        send(&mut client, &["WATCH", "acct", other.as_str()]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut writer, &["SET", other.as_str(), "x"]).await;
+        expect(&mut writer, "+OK\r\n").await;
+        send(&mut writer, &["DEL", other.as_str()]).await;
+        expect(&mut writer, ":1\r\n").await;
+        send(&mut client, &["MULTI"]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut client, &["SET", "acct", "1"]).await;
+        expect(&mut client, "+QUEUED\r\n").await;
+        send(&mut client, &["EXEC"]).await;
+        expect(&mut client, "*-1\r\n").await;
+
+        send(&mut client, &["WATCH", "acct", other.as_str()]).await;
+        expect(&mut client, "+OK\r\n").await;
+        send(&mut client, &["MULTI"]).await;
+        expect(&mut client, "+OK\r\n").await;
+        for key in ["acct", other.as_str()] {
+            send(&mut client, &["INCR", key]).await;
+            expect(&mut client, "+QUEUED\r\n").await;
+        }
+        send(&mut client, &["EXEC"]).await;
+        expect(&mut client, "*2\r\n:1\r\n:1\r\n").await;
+
+        // EXEC released both shards
+        send(&mut writer, &["MGET", "acct", other.as_str()]).await;
+        expect(&mut writer, "*2\r\n$1\r\n1\r\n$1\r\n1\r\n").await;
+    }
+
+    /// The script's shard is blocked, so this needs threads to answer on
+    #[cfg(feature = "lua")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_busy_script_gets_busy_replies_until_killed() {
+        let state = ShardedActorState::with_shards(2);
+        state.scripts().set_threshold_ms(0);
+        let mut scripted = spawn_client(&state);
+        let mut other = spawn_client(&state);
+
+        send(&mut scripted, &["EVAL", "while true do end", "0"]).await;
+        while state.scripts().num_running() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let busy = format!("-{}\r\n", crate::redis::BUSY_ERROR);
+        send(&mut other, &["GET", "k"]).await;
+        expect(&mut other, &busy).await;
+        send(&mut other, &["MULTI"]).await;
+        expect(&mut other, "+OK\r\n").await;
+        send(&mut other, &["SET", "k", "v"]).await;
+        expect(&mut other, &busy).await;
+        send(&mut other, &["DISCARD"]).await;
+        expect(&mut other, "+OK\r\n").await;
+
+        send(&mut other, &["SCRIPT", "KILL"]).await;
+        expect(&mut other, "+OK\r\n").await;
+        expect(&mut scripted, "-ERR Script killed by user with SCRIPT KILL...\r\n").await;
+        send(&mut other, &["GET", "k"]).await;
+        expect(&mut other, "$-1\r\n").await;
+        send(&mut other, &["SCRIPT", "KILL"]).await;
+        expect(&mut other, "-NOTBUSY No scripts in execution right now.\r\n").await;
     }
 }
@@ -21,6 +21,7 @@ use crate::replication::config::ReplicationConfig;
 use crate::replication::gossip::{GossipState, RoutedMessage};
 use crate::replication::gossip_router::GossipRouter;
 use crate::replication::state::ReplicationDelta;
+use crate::replication::ReplicaId;
 use tokio::sync::{mpsc, oneshot};
 
 /// Messages that can be sent to the GossipActor
@@ -52,6 +53,32 @@ pub enum GossipMessage {
     /// Get current epoch
     GetEpoch { response: oneshot::Sender<u64> },
 
+    /// A peer's broadcast batch holding its offsets `first..=last` arrived
+    RecordDelivery {
+        source: ReplicaId,
+        first: u64,
+        last: u64,
+    },
+
+    /// A peer acknowledged `target`'s offsets through `offset`
+    RecordAck {
+        source: ReplicaId,
+        target: ReplicaId,
+        offset: u64,
+    },
+
+    /// Re-acknowledge a peer that sent a heartbeat
+    AckHeartbeat(ReplicaId),
+
+    /// Get the current replication offset
+    GetOffset { response: oneshot::Sender<u64> },
+
+    /// Count the replicas that acknowledged `offset`
+    ReplicasAcked {
+        offset: u64,
+        response: oneshot::Sender<usize>,
+    },
+
     /// Graceful shutdown
     Shutdown { response: oneshot::Sender<()> },
 }
@@ -140,6 +167,58 @@ impl GossipActorHandle {
         rx.await.unwrap_or(0)
     }
 
+    /// Record a peer's broadcast batch holding its offsets `first..=last`
+    pub fn record_delivery(&self, source: ReplicaId, first: u64, last: u64) {
+        let _ = self.tx.send(GossipMessage::RecordDelivery {
+            source,
+            first,
+            last,
+        });
+    }
+
+    /// Record a peer's ack of `target`'s offsets through `offset`
+    pub fn record_ack(&self, source: ReplicaId, target: ReplicaId, offset: u64) {
+        let _ = self.tx.send(GossipMessage::RecordAck {
+            source,
+            target,
+            offset,
+        });
+    }
+
+    /// Re-acknowledge a peer that sent a heartbeat
+    pub fn ack_heartbeat(&self, source: ReplicaId) {
+        let _ = self.tx.send(GossipMessage::AckHeartbeat(source));
+    }
+
+    /// Get the current replication offset
+    pub async fn get_offset(&self) -> u64 {
+        let (tx, rx) = oneshot::channel();
+        if self
+            .tx
+            .send(GossipMessage::GetOffset { response: tx })
+            .is_err()
+        {
+            return 0;
+        }
+        rx.await.unwrap_or(0)
+    }
+
+    /// Count the replicas that acknowledged `offset`
+    pub async fn replicas_acked(&self, offset: u64) -> usize {
+        let (tx, rx) = oneshot::channel();
+        if self
+            .tx
+            .send(GossipMessage::ReplicasAcked {
+                offset,
+                response: tx,
+            })
+            .is_err()
+        {
+            return 0;
+        }
+        rx.await.unwrap_or(0)
+    }
+
     /// Graceful shutdown
     pub async fn shutdown(&self) {
         let (tx, rx) = oneshot::channel();
@@ -227,6 +306,34 @@ impl GossipActor {
                     let _ = response.send(self.state.epoch);
                 }
 
+                GossipMessage::RecordDelivery {
+                    source,
+                    first,
+                    last,
+                } => {
+                    self.state.record_delivery(source, first, last);
+                }
+
+                GossipMessage::RecordAck {
+                    source,
+                    target,
+                    offset,
+                } => {
+                    self.state.record_ack(source, target, offset);
+                }
+
+                GossipMessage::AckHeartbeat(source) => {
+                    self.state.ack_heartbeat(source);
+                }
+
+                GossipMessage::GetOffset { response } => {
+                    let _ = response.send(self.state.offsets.offset());
+                }
+
+                GossipMessage::ReplicasAcked { offset, response } => {
+                    let _ = response.send(self.state.offsets.replicas_acked(offset));
+                }
+
                 GossipMessage::Shutdown { response } => {
                     let _ = response.send(());
                     break;
@@ -310,6 +417,27 @@ mod tests {
         handle.shutdown().await;
     }
 
+    #[tokio::test]
+    async fn test_gossip_actor_counts_acks() {
+        let handle = GossipActor::spawn(test_config());
+        let peer = ReplicaId::new(2);
+
+        handle.record_ack(peer, ReplicaId::new(1), 0);
+        assert_eq!(handle.get_offset().await, 0);
+        assert_eq!(handle.replicas_acked(0).await, 1);
+
+        // A delivery from the peer is acknowledged back to it
+        handle.record_delivery(peer, 1, 3);
+        let messages = handle.drain_outbound().await;
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            messages[0].message,
+            crate::replication::gossip::GossipMessage::Ack { offset: 3, .. }
+        ));
+
+        handle.shutdown().await;
+    }
+
     #[tokio::test]
     async fn test_gossip_actor_multiple_handles() {
         let handle1 = GossipActor::spawn(test_config());
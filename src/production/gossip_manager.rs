@@ -1,4 +1,5 @@
 use super::gossip_actor::GossipActorHandle;
+use super::health::HEARTBEAT_INTERVAL;
 use crate::replication::gossip::{GossipMessage, GossipState, RoutedMessage};
 use crate::replication::state::ReplicationDelta;
 use crate::replication::{ReplicaId, ReplicationConfig};
@@ -6,6 +7,7 @@ use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
@@ -100,7 +102,7 @@ impl GossipManager {
                 "Postcondition: old peer handle must be removed before inserting new one"
             );
 
-            info!("Gossip connection from {} (active peers: {})", addr, active_peers.len().checked_add(1).unwrap_or(usize::MAX));
+            info!("Gossip connection from {} (active peers: {})", addr, active_peers.len().saturating_add(1));
             let callback = delta_callback.clone();
 
             let handle = tokio::spawn(async move {
@@ -166,12 +168,15 @@ impl GossipManager {
                             source_replica,
                             epoch,
                         } => {
-                            info!(
+                            debug!(
                                 "Heartbeat from replica {} epoch {}",
                                 source_replica.0, epoch
                             );
                         }
                         GossipMessage::SyncRequest { .. } => {}
+                        // Acks feed WAIT, which needs the replicated state's
+                        // gossip listener; this server only applies deltas
+                        GossipMessage::Ack { .. } => {}
                         GossipMessage::SyncResponse { deltas, .. } => {
                             delta_callback(deltas);
                         }
@@ -230,6 +235,7 @@ impl GossipManager {
         // send_to_peer() opened a new TCP connection and dropped it after one message,
         // causing ~20 new connections/sec with 2 peers at 100ms intervals.
         let mut peer_connections: HashMap<String, TcpStream> = HashMap::new();
+        let mut last_sent = Instant::now();
 
         loop {
             ticker.tick().await;
@@ -242,12 +248,19 @@ impl GossipManager {
                 let mut state = gossip_state.write();
                 state.advance_epoch();
                 state.queue_deltas(deltas);
-                routed_messages = state.drain_outbound();
+                let mut drained = state.drain_outbound();
+                // Idle: a heartbeat tells peers this replica is alive (DEBUG HEALTH)
+                if drained.is_empty() && last_sent.elapsed() >= HEARTBEAT_INTERVAL {
+                    state.queue_heartbeat();
+                    drained = state.drain_outbound();
+                }
+                routed_messages = drained;
             }
 
             if routed_messages.is_empty() {
                 continue;
             }
+            last_sent = Instant::now();
 
             // Send each routed message using persistent connections
             for routed in routed_messages {
@@ -376,6 +389,7 @@ impl GossipManager {
 
         // Persistent connection pool: reuse TCP connections across gossip rounds.
         let mut peer_connections: HashMap<String, TcpStream> = HashMap::new();
+        let mut last_sent = Instant::now();
 
         loop {
             ticker.tick().await;
@@ -385,11 +399,17 @@ impl GossipManager {
             // Use actor handle - no locks!
             gossip_handle.advance_epoch();
             gossip_handle.queue_deltas(deltas);
-            let routed_messages = gossip_handle.drain_outbound().await;
+            let mut routed_messages = gossip_handle.drain_outbound().await;
+            // Idle: a heartbeat tells peers this replica is alive (DEBUG HEALTH)
+            if routed_messages.is_empty() && last_sent.elapsed() >= HEARTBEAT_INTERVAL {
+                gossip_handle.queue_heartbeat();
+                routed_messages = gossip_handle.drain_outbound().await;
+            }
 
             if routed_messages.is_empty() {
                 continue;
             }
+            last_sent = Instant::now();
 
             // Send each routed message using persistent connections
             for routed in routed_messages {
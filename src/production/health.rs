@@ -0,0 +1,402 @@
+//! Health - component statuses for orchestration probes
+//!
+//! `HealthCheck` asks each component of the persistent server how it is
+//! doing and folds the answers into a `HealthReport`, served as
+//! `DEBUG HEALTH` on the RESP port and as `GET /healthz` on the health port
+//! (port + 1). Every component is ok, degraded or down:
+//!
+//! | Component | Degraded when | Down when |
+//! |-----------|---------------|-----------|
+//! | persistence | the object store is degraded (see `PersistenceHealth`) | a persistence worker exited |
+//! | wal | the oldest unsynced entry waited longer than `WAL_FSYNC_LAG_LIMIT` | the WAL actor exited |
+//! | replication | no gossip from any peer for `REPLICATION_SILENCE_LIMIT` | - |
+//! | event_loop | a timer fired later than `EVENT_LOOP_LAG_LIMIT` | - |
+//!
+//! The report is as bad as its worst component. A degraded server still
+//! answers clients, so `/healthz` replies 200 and names the degraded
+//! components in the body; only a down component makes it reply 503, the
+//! signal for a liveness probe to restart the process. Components that are
+//! not configured (no WAL, replication disabled) are left out.
+//!
+//! Peers send a gossip heartbeat when they have had nothing to send for
+//! `HEARTBEAT_INTERVAL`, so an idle cluster does not read as lagging.
+
+use crate::redis::RespValue;
+use crate::streaming::wal_actor::WalActorHandle;
+use crate::streaming::wal_config::FsyncPolicy;
+use crate::streaming::PersistenceHealth;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Fsync lag past which the WAL is degraded; EverySecond syncs every second
+pub const WAL_FSYNC_LAG_LIMIT: Duration = Duration::from_secs(2);
+
+/// Gossip silence past which replication is degraded
+pub const REPLICATION_SILENCE_LIMIT: Duration = Duration::from_secs(5);
+
+/// Timer lateness past which the event loop is degraded
+pub const EVENT_LOOP_LAG_LIMIT: Duration = Duration::from_millis(100);
+
+/// Idle time after which the gossip loop sends peers a heartbeat
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the event loop monitor samples timer lateness
+const EVENT_LOOP_SAMPLE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Status of one component, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+impl HealthStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HealthStatus::Ok => "ok",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Down => "down",
+        }
+    }
+}
+
+/// One line of a health report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHealth {
+    pub name: &'static str,
+    pub status: HealthStatus,
+    /// What was measured, e.g. `fsync lag 12ms`
+    pub detail: String,
+}
+
+impl ComponentHealth {
+    fn new(name: &'static str, status: HealthStatus, detail: String) -> Self {
+        ComponentHealth {
+            name,
+            status,
+            detail,
+        }
+    }
+
+    /// `ok: fsync lag 12ms`
+    fn summary(&self) -> String {
+        format!("{}: {}", self.status.as_str(), self.detail)
+    }
+}
+
+/// Every configured component's status
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HealthReport {
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    /// The worst component status; ok when nothing is configured
+    pub fn status(&self) -> HealthStatus {
+        self.components
+            .iter()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok)
+    }
+
+    /// DEBUG HEALTH: `status` then each component, as name and value pairs
+    pub fn to_resp(&self) -> RespValue {
+        let bulk = |s: &str| RespValue::BulkString(Some(s.as_bytes().to_vec()));
+        let mut reply = Vec::with_capacity(2 + self.components.len() * 2);
+        reply.push(bulk("status"));
+        reply.push(bulk(self.status().as_str()));
+        for component in &self.components {
+            reply.push(bulk(component.name));
+            reply.push(bulk(&component.summary()));
+        }
+        RespValue::Array(Some(reply))
+    }
+
+    /// The `/healthz` HTTP response: 503 when a component is down
+    pub fn to_http(&self) -> String {
+        let mut body = format!("status: {}\n", self.status().as_str());
+        for component in &self.components {
+            body.push_str(&format!("{}: {}\n", component.name, component.summary()));
+        }
+        let status_line = match self.status() {
+            HealthStatus::Down => "503 Service Unavailable",
+            HealthStatus::Ok | HealthStatus::Degraded => "200 OK",
+        };
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+            status_line,
+            body.len(),
+            body
+        )
+    }
+}
+
+/// Time since something last happened, shared between the task that sees
+/// it and the health check
+#[derive(Debug, Clone)]
+pub struct Activity {
+    epoch: Instant,
+    last_ms: Arc<AtomicU64>,
+}
+
+impl Activity {
+    /// Starts as if the activity just happened
+    pub fn new() -> Self {
+        Activity {
+            epoch: Instant::now(),
+            last_ms: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn record(&self) {
+        let now = self.epoch.elapsed().as_millis() as u64;
+        self.last_ms.fetch_max(now, Ordering::Relaxed);
+    }
+
+    pub fn silence(&self) -> Duration {
+        let last = Duration::from_millis(self.last_ms.load(Ordering::Relaxed));
+        self.epoch.elapsed().saturating_sub(last)
+    }
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How late a periodic timer fires on the tokio runtime: time a ready
+/// task waits for a worker thread
+#[derive(Debug, Clone, Default)]
+pub struct EventLoopLag {
+    lag_ms: Arc<AtomicU64>,
+}
+
+impl EventLoopLag {
+    /// Start sampling on the current runtime; abort the task to stop
+    pub fn spawn() -> (Self, JoinHandle<()>) {
+        let monitor = EventLoopLag::default();
+        let lag_ms = monitor.lag_ms.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                let start = Instant::now();
+                tokio::time::sleep(EVENT_LOOP_SAMPLE_PERIOD).await;
+                let late = start.elapsed().saturating_sub(EVENT_LOOP_SAMPLE_PERIOD);
+                lag_ms.store(late.as_millis() as u64, Ordering::Relaxed);
+            }
+        });
+        (monitor, task)
+    }
+
+    /// Lateness of the last sample
+    pub fn lag(&self) -> Duration {
+        Duration::from_millis(self.lag_ms.load(Ordering::Relaxed))
+    }
+}
+
+/// Closure naming the persistence workers that have exited
+type ExitedProbe = Box<dyn Fn() -> Vec<&'static str> + Send + Sync>;
+
+/// The components a server has, checked on demand
+#[derive(Default)]
+pub struct HealthCheck {
+    persistence: Option<(PersistenceHealth, ExitedProbe)>,
+    wal: Option<(WalActorHandle, AbortHandle)>,
+    replication: Option<Activity>,
+    event_loop: Option<EventLoopLag>,
+}
+
+impl HealthCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Streaming persistence: object store health and worker liveness
+    pub fn with_persistence(
+        mut self,
+        health: PersistenceHealth,
+        exited: impl Fn() -> Vec<&'static str> + Send + Sync + 'static,
+    ) -> Self {
+        self.persistence = Some((health, Box::new(exited)));
+        self
+    }
+
+    /// The WAL actor and its task
+    pub fn with_wal(mut self, handle: WalActorHandle, task: AbortHandle) -> Self {
+        self.wal = Some((handle, task));
+        self
+    }
+
+    /// Gossip received from peers
+    pub fn with_replication(mut self, received: Activity) -> Self {
+        self.replication = Some(received);
+        self
+    }
+
+    pub fn with_event_loop(mut self, lag: EventLoopLag) -> Self {
+        self.event_loop = Some(lag);
+        self
+    }
+
+    pub fn report(&self) -> HealthReport {
+        let mut components = Vec::with_capacity(4);
+        if let Some((health, exited)) = &self.persistence {
+            components.push(persistence_health(health, &exited()));
+        }
+        if let Some((handle, task)) = &self.wal {
+            components.push(wal_health(
+                handle.fsync_policy(),
+                handle.fsync_lag(),
+                task.is_finished(),
+            ));
+        }
+        if let Some(received) = &self.replication {
+            components.push(replication_health(received.silence()));
+        }
+        if let Some(lag) = &self.event_loop {
+            components.push(event_loop_health(lag.lag()));
+        }
+        HealthReport { components }
+    }
+}
+
+fn persistence_health(health: &PersistenceHealth, exited: &[&'static str]) -> ComponentHealth {
+    let (status, detail) = if !exited.is_empty() {
+        (HealthStatus::Down, format!("{} exited", exited.join(", ")))
+    } else if health.is_degraded() {
+        (
+            HealthStatus::Degraded,
+            format!(
+                "object store unreachable, {} segments unsynced",
+                health.unsynced_segments()
+            ),
+        )
+    } else {
+        (HealthStatus::Ok, "workers running".to_string())
+    };
+    ComponentHealth::new("persistence", status, detail)
+}
+
+fn wal_health(policy: FsyncPolicy, lag: Duration, exited: bool) -> ComponentHealth {
+    let (status, detail) = if exited {
+        (HealthStatus::Down, "WAL actor exited".to_string())
+    } else if policy == FsyncPolicy::No {
+        (HealthStatus::Ok, "fsync disabled".to_string())
+    } else if lag > WAL_FSYNC_LAG_LIMIT {
+        (
+            HealthStatus::Degraded,
+            format!("fsync lag {}ms", lag.as_millis()),
+        )
+    } else {
+        (HealthStatus::Ok, format!("fsync lag {}ms", lag.as_millis()))
+    };
+    ComponentHealth::new("wal", status, detail)
+}
+
+fn replication_health(silence: Duration) -> ComponentHealth {
+    let status = if silence > REPLICATION_SILENCE_LIMIT {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+    ComponentHealth::new(
+        "replication",
+        status,
+        format!("last gossip {}ms ago", silence.as_millis()),
+    )
+}
+
+fn event_loop_health(lag: Duration) -> ComponentHealth {
+    let status = if lag > EVENT_LOOP_LAG_LIMIT {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+    ComponentHealth::new("event_loop", status, format!("lag {}ms", lag.as_millis()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_is_as_bad_as_its_worst_component() {
+        let mut report = HealthReport::default();
+        assert_eq!(report.status(), HealthStatus::Ok);
+
+        report.components.push(wal_health(
+            FsyncPolicy::EverySecond,
+            Duration::from_millis(5),
+            false,
+        ));
+        report
+            .components
+            .push(replication_health(Duration::from_secs(30)));
+        assert_eq!(report.status(), HealthStatus::Degraded);
+        assert!(report.to_http().starts_with("HTTP/1.1 200 OK\r\n"));
+
+        report.components.push(persistence_health(
+            &PersistenceHealth::default(),
+            &["persistence"],
+        ));
+        assert_eq!(report.status(), HealthStatus::Down);
+        let http = report.to_http();
+        assert!(http.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(http.ends_with(
+            "status: down\n\
+             wal: ok: fsync lag 5ms\n\
+             replication: degraded: last gossip 30000ms ago\n\
+             persistence: down: persistence exited\n"
+        ));
+    }
+
+    #[test]
+    fn test_component_thresholds() {
+        let lagging = wal_health(FsyncPolicy::Always, Duration::from_secs(3), false);
+        assert_eq!(lagging.status, HealthStatus::Degraded);
+        // Without fsync there is no lag to report
+        let unsynced = wal_health(FsyncPolicy::No, Duration::ZERO, false);
+        assert_eq!(unsynced.summary(), "ok: fsync disabled");
+        let dead = wal_health(FsyncPolicy::Always, Duration::ZERO, true);
+        assert_eq!(dead.status, HealthStatus::Down);
+
+        assert_eq!(
+            event_loop_health(Duration::from_millis(250)).status,
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            event_loop_health(Duration::from_millis(3)).status,
+            HealthStatus::Ok
+        );
+    }
+
+    #[test]
+    fn test_debug_health_reply() {
+        let report = HealthReport {
+            components: vec![event_loop_health(Duration::from_millis(1))],
+        };
+        let bulk = |s: &str| RespValue::BulkString(Some(s.as_bytes().to_vec()));
+        assert_eq!(
+            report.to_resp(),
+            RespValue::Array(Some(vec![
+                bulk("status"),
+                bulk("ok"),
+                bulk("event_loop"),
+                bulk("ok: lag 1ms"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_activity_silence() {
+        let activity = Activity::new();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(activity.silence() >= Duration::from_millis(20));
+        activity.record();
+        assert!(activity.silence() < Duration::from_millis(20));
+    }
+}
@@ -1,40 +1,69 @@
 mod adaptive_actor;
 mod adaptive_replication;
+mod client_registry;
 mod connection_optimized;
 mod connection_pool;
 mod gossip_actor;
 mod gossip_manager;
+mod health;
 mod hotkey;
 mod load_balancer;
 mod perf_config;
+mod persistent_connection;
+mod rebalance;
 mod replicated_shard_actor;
 mod replicated_state;
+mod reply_shape;
+mod reply_stream;
 mod response_pool;
 mod server_config;
 mod server_optimized;
+mod shard_router;
 mod sharded_actor;
+mod shutdown;
+mod tenant_keyspace;
+mod transaction_coordinator;
 mod ttl_manager;
+mod watchdog;
 
 pub use adaptive_actor::{
     AdaptiveActor, AdaptiveActorConfig, AdaptiveActorHandle, AdaptiveActorStats, AdaptiveMessage,
 };
 pub use adaptive_replication::{AdaptiveConfig, AdaptiveReplicationManager, AdaptiveStats};
+pub use client_registry::{ClientRegistry, ClientSession};
 pub use connection_optimized::ConnectionConfig;
 pub use connection_pool::ConnectionPool;
 pub use gossip_actor::{GossipActor, GossipActorHandle, GossipMessage};
 pub use gossip_manager::GossipManager;
+pub use health::{
+    Activity, ComponentHealth, EventLoopLag, HealthCheck, HealthReport, HealthStatus,
+    EVENT_LOOP_LAG_LIMIT, HEARTBEAT_INTERVAL, REPLICATION_SILENCE_LIMIT, WAL_FSYNC_LAG_LIMIT,
+};
 pub use hotkey::{AccessMetrics, HotKeyConfig, HotKeyDetector};
 pub use load_balancer::{
     LoadBalancerConfig, LoadBalancerStats, ScalingDecision, ShardLoadBalancer, ShardMetrics,
 };
 pub use perf_config::{BatchingConfig, BufferConfig, PerformanceConfig, ResponsePoolConfig};
+pub use persistent_connection::handle_persistent_connection;
+pub use rebalance::{RebalanceMove, RebalancePlan, ShardLoad};
 pub use replicated_shard_actor::{
     ReplicatedShardActor, ReplicatedShardHandle, ReplicatedShardMessage,
 };
 pub use replicated_state::{GossipBackend, ReplicatedShardedState};
+pub(crate) use reply_shape::ReplyShape;
 pub use server_config::{AclServerConfig, ServerConfig, TlsServerConfig};
 pub use server_optimized::OptimizedRedisServer;
+pub use shard_router::ShardRouter;
 pub use sharded_actor::{ShardConfig, ShardedActorState};
+pub use shutdown::{
+    drain_clients, shutdown_trigger, termination_signal, DrainReport, ShutdownCause,
+    ShutdownConfig, ShutdownRequest, DEFAULT_DRAIN_TIMEOUT, SHUTDOWN_NOTICE,
+};
+pub use tenant_keyspace::{TenantKeyspace, TENANT_COMMAND_DENIED};
+pub use transaction_coordinator::{WatchGuard, WatchStamp};
 pub use ttl_manager::{TtlManagerActor, TtlManagerHandle, TtlMessage};
+pub use watchdog::{
+    ProgressSnapshot, ShardProgress, Watchdog, WatchdogConfig, WatchdogHandle, MIN_WATCHDOG_PERIOD,
+};
 
 pub use server_optimized::OptimizedRedisServer as ProductionRedisServer;
@@ -53,6 +53,11 @@ pub struct BufferConfig {
     /// Maximum buffer size per connection in bytes (default: 1MB)
     #[serde(default = "default_max_buffer")]
     pub max_size: usize,
+
+    /// Reply bytes buffered per connection before they are flushed; larger
+    /// replies are streamed in chunks (default: 1MB)
+    #[serde(default = "default_write_high_watermark")]
+    pub write_high_watermark: usize,
 }
 
 /// Batching parameters for pipeline optimization
@@ -95,6 +100,9 @@ fn default_read_buffer() -> usize {
 fn default_max_buffer() -> usize {
     512 * 1024 * 1024
 } // 512MB (matches Redis proto-max-bulk-len default)
+fn default_write_high_watermark() -> usize {
+    1024 * 1024
+}
 fn default_min_pipeline_buffer() -> usize {
     60
 }
@@ -143,6 +151,7 @@ impl Default for BufferConfig {
         Self {
             read_size: default_read_buffer(),
             max_size: default_max_buffer(),
+            write_high_watermark: default_write_high_watermark(),
         }
     }
 }
@@ -218,6 +227,9 @@ impl PerformanceConfig {
         if self.buffers.max_size < self.buffers.read_size {
             return Err("buffers.max_size must be >= read_size".to_string());
         }
+        if self.buffers.write_high_watermark == 0 {
+            return Err("buffers.write_high_watermark must be > 0".to_string());
+        }
         if self.connection_pool.max_connections == 0 {
             return Err("connection_pool.max_connections must be > 0".to_string());
         }
@@ -239,6 +251,7 @@ mod tests {
         assert_eq!(config.response_pool.capacity, 256);
         assert_eq!(config.response_pool.prewarm, 64);
         assert_eq!(config.buffers.read_size, 8192);
+        assert_eq!(config.buffers.write_high_watermark, 1024 * 1024);
         assert_eq!(config.batching.min_pipeline_buffer, 60);
         assert_eq!(config.connection_pool.max_connections, 10000);
         assert_eq!(config.connection_pool.buffer_pool_size, 64);
@@ -275,6 +288,7 @@ mod tests {
             [buffers]
             read_size = 16384
             max_size = 2097152
+            write_high_watermark = 65536
 
             [batching]
             min_pipeline_buffer = 100
@@ -286,6 +300,7 @@ mod tests {
         assert_eq!(config.response_pool.capacity, 512);
         assert_eq!(config.response_pool.prewarm, 128);
         assert_eq!(config.buffers.read_size, 16384);
+        assert_eq!(config.buffers.write_high_watermark, 65536);
         assert_eq!(config.batching.min_pipeline_buffer, 100);
         assert_eq!(config.batching.batch_threshold, 4);
     }
@@ -0,0 +1,225 @@
+//! Connection handler for the persistent server
+//!
+//! A plain pipelined RESP loop over `ReplicatedShardedState`. Every write is
+//! acknowledged only after `ReplicatedShardedState::execute` has handed its
+//! delta to the WAL and the delta sink.
+//!
+//! MULTI queues commands on the connection until EXEC, which runs them
+//! through `ReplicatedShardedState::execute_transaction` so the WAL holds
+//! the whole transaction as one batch record. WATCH is not supported here.
+//!
+//! When the session is asked to drain (graceful shutdown) the connection
+//! stops reading, finishes the batch it has already read, flushes the
+//! replies, sends a `-SHUTDOWN` notice and closes.
+//!
+//! A request that breaks the protocol or its limits (see `ProtocolLimits`)
+//! gets a `Protocol error` reply and the connection closes, since the rest
+//! of the stream can no longer be framed.
+//!
+//! SHUTDOWN (outside MULTI) asks the server to shut down through the
+//! registry. It gets no reply and nothing pipelined after it runs; the
+//! connection then waits to be drained like every other.
+
+use super::shutdown::{ShutdownRequest, SHUTDOWN_NOTICE};
+use super::{ClientRegistry, ClientSession, ReplicatedShardedState};
+use crate::redis::{Command, RespCodec, RespValue};
+use bytes::{BufMut, BytesMut};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Serve one client until it disconnects or its session is closed.
+///
+/// Unregisters `session` from `registry` on every exit path.
+pub async fn handle_persistent_connection(
+    stream: TcpStream,
+    state: Arc<ReplicatedShardedState>,
+    registry: Arc<ClientRegistry>,
+    session: Arc<ClientSession>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let result = serve(stream, &state, &registry, &session).await;
+    registry.unregister(session.id());
+    result
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    state: &ReplicatedShardedState,
+    registry: &ClientRegistry,
+    session: &ClientSession,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Enable TCP_NODELAY for lower latency
+    let _ = stream.set_nodelay(true);
+
+    let mut read_buf = [0u8; 8192];
+    let mut buffer = BytesMut::with_capacity(4096);
+    let mut write_buffer = BytesMut::with_capacity(4096);
+    let mut transaction = Transaction::default();
+    let mut protocol_error = false;
+
+    loop {
+        let n = tokio::select! {
+            biased;
+            _ = session.close_requested() => 0,
+            result = stream.read(&mut read_buf) => result?,
+        };
+        if n == 0 {
+            break;
+        }
+
+        buffer.extend_from_slice(&read_buf[..n]);
+
+        // Process all available commands (pipelining support)
+        loop {
+            match RespCodec::parse(&mut buffer) {
+                Ok(Some(resp_value)) => match Command::from_resp_zero_copy(&resp_value) {
+                    Ok(Command::Shutdown { save }) if transaction.queued.is_none() => {
+                        registry.request_shutdown(ShutdownRequest {
+                            save: save != Some(false),
+                        });
+                        buffer.clear();
+                        break;
+                    }
+                    Ok(cmd) => {
+                        let response = transaction.dispatch(state, cmd).await;
+                        encode_resp_into(&response, &mut write_buffer);
+                    }
+                    Err(e) => {
+                        transaction.reject();
+                        encode_error_into(&e, &mut write_buffer);
+                    }
+                },
+                Ok(None) => break, // Need more data
+                Err(e) => {
+                    encode_error_into(&e, &mut write_buffer);
+                    buffer.clear();
+                    protocol_error = true;
+                    break;
+                }
+            }
+        }
+
+        // Flush all responses
+        if !write_buffer.is_empty() {
+            stream.write_all(&write_buffer).await?;
+            stream.flush().await?;
+            write_buffer.clear();
+        }
+
+        if protocol_error || session.is_killed() || session.is_draining() {
+            break;
+        }
+    }
+
+    if session.is_draining() {
+        encode_resp_into(&RespValue::err(SHUTDOWN_NOTICE), &mut write_buffer);
+        stream.write_all(&write_buffer).await?;
+        stream.flush().await?;
+    }
+
+    Ok(())
+}
+
+/// The connection's MULTI state
+#[derive(Default)]
+struct Transaction {
+    /// Commands queued since MULTI, `None` outside a transaction
+    queued: Option<Vec<Command>>,
+    /// A command was rejected while queueing, so EXEC must abort
+    aborted: bool,
+}
+
+impl Transaction {
+    /// Run `cmd`, or queue it while a transaction is open
+    async fn dispatch(&mut self, state: &ReplicatedShardedState, cmd: Command) -> RespValue {
+        match (cmd, self.queued.as_mut()) {
+            (Command::Multi, None) => {
+                self.queued = Some(Vec::new());
+                self.aborted = false;
+                RespValue::simple("OK")
+            }
+            (Command::Multi, Some(_)) => RespValue::err("ERR MULTI calls can not be nested"),
+            (Command::Exec, None) => RespValue::err("ERR EXEC without MULTI"),
+            (Command::Discard, None) => RespValue::err("ERR DISCARD without MULTI"),
+            (Command::Exec, Some(_)) => {
+                let queued = self.queued.take().unwrap_or_default();
+                if std::mem::take(&mut self.aborted) {
+                    return RespValue::err(
+                        "EXECABORT Transaction discarded because of previous errors.",
+                    );
+                }
+                state.execute_transaction(queued).await
+            }
+            (Command::Discard, Some(_)) => {
+                self.queued = None;
+                self.aborted = false;
+                RespValue::simple("OK")
+            }
+            (Command::Watch(_) | Command::Unwatch, _) => {
+                self.reject();
+                RespValue::err("ERR WATCH is not supported by this server")
+            }
+            (cmd, Some(queued)) => {
+                queued.push(cmd);
+                RespValue::simple("QUEUED")
+            }
+            (cmd, None) => state.execute(cmd).await,
+        }
+    }
+
+    /// Note a command that could not be queued
+    fn reject(&mut self) {
+        if self.queued.is_some() {
+            self.aborted = true;
+        }
+    }
+}
+
+fn encode_resp_into(value: &RespValue, buf: &mut BytesMut) {
+    match value {
+        RespValue::SimpleString(s) => {
+            buf.put_u8(b'+');
+            buf.extend_from_slice(s.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        RespValue::Error(s) => {
+            buf.put_u8(b'-');
+            buf.extend_from_slice(s.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        RespValue::Integer(n) => {
+            buf.put_u8(b':');
+            buf.extend_from_slice(n.to_string().as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        RespValue::BulkString(None) => {
+            buf.extend_from_slice(b"$-1\r\n");
+        }
+        RespValue::BulkString(Some(data)) => {
+            buf.put_u8(b'$');
+            buf.extend_from_slice(data.len().to_string().as_bytes());
+            buf.extend_from_slice(b"\r\n");
+            buf.extend_from_slice(data);
+            buf.extend_from_slice(b"\r\n");
+        }
+        RespValue::Array(None) => {
+            buf.extend_from_slice(b"*-1\r\n");
+        }
+        RespValue::Array(Some(elements)) => {
+            buf.put_u8(b'*');
+            buf.extend_from_slice(elements.len().to_string().as_bytes());
+            buf.extend_from_slice(b"\r\n");
+            for elem in elements {
+                encode_resp_into(elem, buf);
+            }
+        }
+        resp3 => encode_resp_into(&resp3.to_resp2(), buf),
+    }
+}
+
+fn encode_error_into(msg: &str, buf: &mut BytesMut) {
+    buf.put_u8(b'-');
+    buf.extend_from_slice(b"ERR ");
+    buf.extend_from_slice(msg.as_bytes());
+    buf.extend_from_slice(b"\r\n");
+}
@@ -0,0 +1,254 @@
+//! Rebalancing planner over per-shard load
+//!
+//! Given each shard's key count and bytes (from `MEMORY STATS`), the planner
+//! works out how much data would have to move, and between which shards,
+//! for every shard to sit within a tolerance of the mean.
+//!
+//! - **Greedy.** Each move takes the most loaded shard's excess over the
+//!   mean to the least loaded shard's deficit, whichever is smaller, so one
+//!   of the two lands exactly on the mean and the plan has fewer moves than
+//!   there are shards.
+//! - **Bytes drive it, keys follow.** A move carries bytes; its key count
+//!   is estimated at the source shard's average key size.
+//!
+//! # Scope
+//!
+//! This module plans over whole shards and stops there. The requested
+//! per-slot plans and the `apply` mode that would carry one out are
+//! deferred to GAP-012: keys are routed by `hash(key) % num_shards`, so
+//! there are no slots to name and no migration to drive. A plan reports
+//! the volume a rebalance would copy; nothing in the tree applies it.
+
+use crate::redis::MemoryStats;
+
+/// One shard's load, as the planner sees it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShardLoad {
+    pub shard: usize,
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+impl ShardLoad {
+    pub fn from_memory_stats(shard: usize, stats: &MemoryStats) -> Self {
+        ShardLoad {
+            shard,
+            keys: stats.keys,
+            bytes: stats.total_bytes(),
+        }
+    }
+}
+
+/// Data a rebalance would copy from one shard to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebalanceMove {
+    pub from: usize,
+    pub to: usize,
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+/// The moves that bring every shard within `tolerance_pct` of the mean
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalancePlan {
+    pub tolerance_pct: u64,
+    pub mean_bytes: u64,
+    /// Load before the moves, by shard
+    pub before: Vec<ShardLoad>,
+    /// Load after the moves, by shard
+    pub after: Vec<ShardLoad>,
+    pub moves: Vec<RebalanceMove>,
+}
+
+impl RebalancePlan {
+    /// Plan the moves for `loads`, one entry per shard
+    pub fn build(loads: &[ShardLoad], tolerance_pct: u64) -> Self {
+        debug_assert!(
+            loads.iter().enumerate().all(|(i, load)| load.shard == i),
+            "Precondition: loads must be indexed by shard"
+        );
+
+        let total_bytes: u64 = loads.iter().map(|load| load.bytes).sum();
+        let total_keys: u64 = loads.iter().map(|load| load.keys).sum();
+        let mean_bytes = total_bytes.checked_div(loads.len() as u64).unwrap_or(0);
+        let band = mean_bytes.saturating_mul(tolerance_pct) / 100;
+
+        let mut after = loads.to_vec();
+        let mut moves = Vec::new();
+        while let (Some(from), Some(to)) = (
+            after.iter().max_by_key(|load| load.bytes).map(|l| l.shard),
+            after.iter().min_by_key(|load| load.bytes).map(|l| l.shard),
+        ) {
+            let excess = after[from].bytes.saturating_sub(mean_bytes);
+            let deficit = mean_bytes.saturating_sub(after[to].bytes);
+            if excess <= band && deficit <= band {
+                break;
+            }
+            let bytes = excess.min(deficit);
+            if bytes == 0 {
+                break;
+            }
+            let keys = (u128::from(bytes) * u128::from(after[from].keys)
+                / u128::from(after[from].bytes)) as u64;
+            after[from].bytes -= bytes;
+            after[from].keys -= keys;
+            after[to].bytes += bytes;
+            after[to].keys += keys;
+            moves.push(RebalanceMove {
+                from,
+                to,
+                keys,
+                bytes,
+            });
+            debug_assert!(
+                moves.len() < loads.len(),
+                "Invariant: each move settles a shard on the mean"
+            );
+        }
+
+        debug_assert_eq!(
+            after.iter().map(|load| load.bytes).sum::<u64>(),
+            total_bytes,
+            "Postcondition violated: moves must conserve bytes"
+        );
+        debug_assert_eq!(
+            after.iter().map(|load| load.keys).sum::<u64>(),
+            total_keys,
+            "Postcondition violated: moves must conserve keys"
+        );
+
+        RebalancePlan {
+            tolerance_pct,
+            mean_bytes,
+            before: loads.to_vec(),
+            after,
+            moves,
+        }
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn bytes_moved(&self) -> u64 {
+        self.moves.iter().map(|m| m.bytes).sum()
+    }
+
+    pub fn keys_moved(&self) -> u64 {
+        self.moves.iter().map(|m| m.keys).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::ProductionTimeSource;
+    use crate::production::ShardedActorState;
+    use crate::redis::{Command, SDS};
+
+    fn loads(bytes: &[u64]) -> Vec<ShardLoad> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(shard, &bytes)| ShardLoad {
+                shard,
+                keys: bytes / 100,
+                bytes,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_balanced_shards_need_no_moves() {
+        let plan = RebalancePlan::build(&loads(&[1000, 1050, 950, 1000]), 10);
+        assert!(plan.is_balanced());
+        assert_eq!(plan.mean_bytes, 1000);
+        assert_eq!(plan.after, plan.before);
+
+        assert!(RebalancePlan::build(&[], 10).is_balanced());
+        assert!(RebalancePlan::build(&loads(&[0, 0]), 0).is_balanced());
+    }
+
+    #[test]
+    fn test_hot_shard_spreads_to_the_others() {
+        let plan = RebalancePlan::build(&loads(&[7000, 1000, 1000, 3000]), 0);
+        assert_eq!(plan.mean_bytes, 3000);
+        assert_eq!(
+            plan.moves,
+            vec![
+                RebalanceMove {
+                    from: 0,
+                    to: 1,
+                    keys: 20,
+                    bytes: 2000
+                },
+                RebalanceMove {
+                    from: 0,
+                    to: 2,
+                    keys: 20,
+                    bytes: 2000
+                },
+            ]
+        );
+        assert!(plan.after.iter().all(|load| load.bytes == 3000));
+        assert_eq!(plan.bytes_moved(), 4000);
+        assert_eq!(plan.keys_moved(), 40);
+    }
+
+    #[test]
+    fn test_tolerance_stops_early() {
+        let uneven = loads(&[1300, 1000, 700]);
+        assert!(RebalancePlan::build(&uneven, 30).is_balanced());
+
+        let plan = RebalancePlan::build(&uneven, 10);
+        assert_eq!(plan.moves.len(), 1);
+        assert_eq!(plan.bytes_moved(), 300);
+    }
+
+    #[test]
+    fn test_keys_follow_the_source_average() {
+        // Shard 0 holds few large values, shard 1 many small ones
+        let plan = RebalancePlan::build(
+            &[
+                ShardLoad {
+                    shard: 0,
+                    keys: 10,
+                    bytes: 10_000,
+                },
+                ShardLoad {
+                    shard: 1,
+                    keys: 1000,
+                    bytes: 2000,
+                },
+            ],
+            0,
+        );
+        assert_eq!(plan.moves[0].bytes, 4000);
+        assert_eq!(plan.moves[0].keys, 4);
+    }
+
+    #[tokio::test]
+    async fn test_sharded_state_plans_from_memory_stats() {
+        let state = ShardedActorState::<ProductionTimeSource>::with_shards(4);
+        for i in 0..64 {
+            state
+                .execute(&Command::set(format!("key:{}", i), SDS::from_str("value")))
+                .await;
+        }
+
+        let loads = state.shard_loads().await.expect("every shard reports");
+        assert_eq!(loads.len(), 4);
+        assert_eq!(loads.iter().map(|load| load.keys).sum::<u64>(), 64);
+        let merged = MemoryStats::from_resp(&state.execute(&Command::MemoryStats).await)
+            .expect("expected MEMORY STATS fields");
+        assert_eq!(
+            loads.iter().map(|load| load.bytes).sum::<u64>(),
+            merged.total_bytes()
+        );
+
+        let plan = state.rebalance_plan(0).await.expect("every shard reports");
+        assert_eq!(plan.before, loads);
+        let spread = plan.after.iter().map(|load| load.bytes);
+        assert!(spread.clone().max().unwrap() - spread.min().unwrap() <= 4);
+    }
+}
@@ -11,25 +11,42 @@
 //! └─────────────────────────┘        └─────────────────────────┘
 //! ```
 
-use crate::redis::{Command, CommandExecutor, RespValue};
+use super::shard_router::ShardOwner;
+use super::watchdog::ShardProgress;
+use crate::redis::latency::command_event;
+use crate::redis::{
+    BulkEntry, BulkLoadReport, Command, CommandExecutor, LatencyMonitor, RespValue, Value,
+};
 use crate::replication::state::ShardReplicaState;
 use crate::replication::{ConsistencyLevel, ReplicaId, ReplicationDelta};
 use crate::simulator::VirtualTime;
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{mpsc, oneshot};
+use tracing::warn;
 
 /// Messages for controlling the ReplicatedShardActor
 #[derive(Debug)]
 pub enum ReplicatedShardMessage {
-    /// Execute a command and return result with optional delta
+    /// Execute a command and return result with its deltas: a DEL for each
+    /// key the command's write evicted, then the command's own delta (one
+    /// per declared key for EVAL/EVALSHA)
     Execute {
         cmd: Command,
-        response: oneshot::Sender<(RespValue, Option<ReplicationDelta>)>,
+        response: oneshot::Sender<(RespValue, Vec<ReplicationDelta>)>,
     },
     /// Execute a read-only command (no delta generation)
     ExecuteReadonly {
         cmd: Command,
         response: oneshot::Sender<RespValue>,
     },
+    /// One chunk of a chunked KEYS, answered with the next cursor
+    KeysChunk {
+        pattern: String,
+        cursor: u64,
+        chunk_size: usize,
+        response: oneshot::Sender<(u64, Vec<RespValue>)>,
+    },
     /// Apply a remote delta from another replica
     ApplyRemoteDelta { delta: ReplicationDelta },
     /// Drain all pending deltas for replication
@@ -47,6 +64,11 @@ pub enum ReplicatedShardMessage {
             std::collections::HashMap<String, crate::replication::state::ReplicatedValue>,
         >,
     },
+    /// Install imported keys directly (no per-key deltas)
+    BulkLoad {
+        entries: Vec<BulkEntry>,
+        response: oneshot::Sender<BulkLoadReport>,
+    },
     /// Apply recovered state from persistence
     ApplyRecoveredState {
         key: String,
@@ -56,27 +78,57 @@ pub enum ReplicatedShardMessage {
     Shutdown { response: oneshot::Sender<()> },
 }
 
+impl ReplicatedShardMessage {
+    /// Command name reported by the watchdog
+    fn command_name(&self) -> &'static str {
+        match self {
+            ReplicatedShardMessage::Execute { cmd, .. }
+            | ReplicatedShardMessage::ExecuteReadonly { cmd, .. } => cmd.name(),
+            ReplicatedShardMessage::KeysChunk { .. } => "KEYS (chunk)",
+            ReplicatedShardMessage::ApplyRemoteDelta { .. } => "(apply remote delta)",
+            ReplicatedShardMessage::DrainPendingDeltas { .. } => "(drain deltas)",
+            ReplicatedShardMessage::EvictExpired { .. } => "(expire cycle)",
+            ReplicatedShardMessage::GetSnapshot { .. } => "(snapshot)",
+            ReplicatedShardMessage::BulkLoad { .. } => "(bulk load)",
+            ReplicatedShardMessage::ApplyRecoveredState { .. } => "(apply recovered state)",
+            ReplicatedShardMessage::Shutdown { .. } => "(shutdown)",
+        }
+    }
+
+    /// Latency monitor event the message is timed as, if any
+    fn latency_event(&self) -> Option<&'static str> {
+        match self {
+            ReplicatedShardMessage::Execute { cmd, .. }
+            | ReplicatedShardMessage::ExecuteReadonly { cmd, .. } => Some(command_event(cmd)),
+            ReplicatedShardMessage::EvictExpired { .. } => Some("expire-cycle"),
+            ReplicatedShardMessage::GetSnapshot { .. } => Some("snapshot"),
+            _ => None,
+        }
+    }
+}
+
 /// Handle for communicating with the ReplicatedShardActor
 #[derive(Clone)]
 pub struct ReplicatedShardHandle {
     tx: mpsc::UnboundedSender<ReplicatedShardMessage>,
     shard_id: usize,
+    progress: Arc<ShardProgress>,
 }
 
 impl ReplicatedShardHandle {
-    /// Execute a command and return result with optional delta
+    /// Execute a command and return result with its deltas (evictions first)
     #[inline]
-    pub async fn execute(&self, cmd: Command) -> (RespValue, Option<ReplicationDelta>) {
+    pub async fn execute(&self, cmd: Command) -> (RespValue, Vec<ReplicationDelta>) {
         let (tx, rx) = oneshot::channel();
         if self
             .tx
             .send(ReplicatedShardMessage::Execute { cmd, response: tx })
             .is_err()
         {
-            return (RespValue::err("ERR shard unavailable"), None);
+            return (RespValue::err("ERR shard unavailable"), Vec::new());
         }
         rx.await
-            .unwrap_or_else(|_| (RespValue::err("ERR shard response failed"), None))
+            .unwrap_or_else(|_| (RespValue::err("ERR shard response failed"), Vec::new()))
     }
 
     /// Execute a read-only command
@@ -94,6 +146,36 @@ impl ReplicatedShardHandle {
             .unwrap_or_else(|_| RespValue::err("ERR shard response failed"))
     }
 
+    /// Run KEYS on this shard one chunk per message, so commands queued
+    /// behind it run between chunks instead of waiting for the whole scan
+    pub async fn keys_chunked(&self, pattern: &str, chunk_size: usize) -> Vec<RespValue> {
+        let mut keys = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (tx, rx) = oneshot::channel();
+            if self
+                .tx
+                .send(ReplicatedShardMessage::KeysChunk {
+                    pattern: pattern.to_string(),
+                    cursor,
+                    chunk_size,
+                    response: tx,
+                })
+                .is_err()
+            {
+                return keys;
+            }
+            let Ok((next, chunk)) = rx.await else {
+                return keys;
+            };
+            keys.extend(chunk);
+            if next == 0 {
+                return keys;
+            }
+            cursor = next;
+        }
+    }
+
     /// Apply a remote delta (fire-and-forget)
     #[inline]
     pub fn apply_remote_delta(&self, delta: ReplicationDelta) {
@@ -146,6 +228,22 @@ impl ReplicatedShardHandle {
         rx.await.unwrap_or_default()
     }
 
+    /// Install imported keys, bypassing per-command execution
+    pub async fn bulk_load(&self, entries: Vec<BulkEntry>) -> BulkLoadReport {
+        let (tx, rx) = oneshot::channel();
+        if self
+            .tx
+            .send(ReplicatedShardMessage::BulkLoad {
+                entries,
+                response: tx,
+            })
+            .is_err()
+        {
+            return BulkLoadReport::default();
+        }
+        rx.await.unwrap_or_default()
+    }
+
     /// Apply recovered state (fire-and-forget)
     pub fn apply_recovered_state(
         &self,
@@ -178,6 +276,11 @@ impl ReplicatedShardHandle {
     pub fn is_running(&self) -> bool {
         !self.tx.is_closed()
     }
+
+    /// Progress of the actor, for the watchdog
+    pub fn progress(&self) -> Arc<ShardProgress> {
+        self.progress.clone()
+    }
 }
 
 /// The ReplicatedShardActor owns its state exclusively - no Arc<RwLock<>> needed!
@@ -185,6 +288,10 @@ pub struct ReplicatedShardActor {
     executor: CommandExecutor,
     replica_state: ShardReplicaState,
     rx: mpsc::UnboundedReceiver<ReplicatedShardMessage>,
+    /// Current message and processed count, sampled by the watchdog
+    progress: Arc<ShardProgress>,
+    /// Debug check that only this actor's task touches `executor`
+    owner: ShardOwner,
     shard_id: usize,
 }
 
@@ -194,28 +301,61 @@ impl ReplicatedShardActor {
         replica_id: ReplicaId,
         consistency_level: ConsistencyLevel,
         shard_id: usize,
+    ) -> ReplicatedShardHandle {
+        Self::spawn_with_latency(replica_id, consistency_level, shard_id, LatencyMonitor::new())
+    }
+
+    /// Spawn an actor recording its spikes in `latency`, shared with the
+    /// other shards so LATENCY on any of them sees every spike
+    pub fn spawn_with_latency(
+        replica_id: ReplicaId,
+        consistency_level: ConsistencyLevel,
+        shard_id: usize,
+        latency: LatencyMonitor,
     ) -> ReplicatedShardHandle {
         let (tx, rx) = mpsc::unbounded_channel();
+        let mut executor = CommandExecutor::new();
+        executor.set_latency_monitor(latency);
         let actor = ReplicatedShardActor {
-            executor: CommandExecutor::new(),
+            executor,
             replica_state: ShardReplicaState::new(replica_id, consistency_level),
             rx,
+            progress: Arc::new(ShardProgress::new()),
+            owner: ShardOwner::default(),
             shard_id,
         };
+        let progress = actor.progress.clone();
 
         tokio::spawn(actor.run());
 
-        ReplicatedShardHandle { tx, shard_id }
+        ReplicatedShardHandle {
+            tx,
+            shard_id,
+            progress,
+        }
     }
 
     /// Run the actor's message loop
     async fn run(mut self) {
         while let Some(msg) = self.rx.recv().await {
+            self.owner.assert_owned(self.shard_id);
+            self.progress.begin(msg.command_name(), self.rx.len());
+            let latency_event = msg.latency_event();
+            let start = Instant::now();
             match msg {
                 ReplicatedShardMessage::Execute { cmd, response } => {
                     let result = self.executor.execute(&cmd);
-                    let delta = self.record_mutation_post_execute(&cmd);
-                    let _ = response.send((result, delta));
+                    let mut deltas = self.record_evictions();
+                    match &cmd {
+                        // A script that errors part-way keeps the writes it made
+                        Command::Eval { keys, .. } | Command::EvalSha { keys, .. } => {
+                            deltas.extend(self.record_script_effects(keys));
+                        }
+                        // A rejected write (OOM, WRONGTYPE) changed nothing to replicate
+                        _ if matches!(result, RespValue::Error(_)) => {}
+                        _ => deltas.extend(self.record_mutation_post_execute(&cmd)),
+                    }
+                    let _ = response.send((result, deltas));
 
                     #[cfg(debug_assertions)]
                     self.verify_invariants();
@@ -226,8 +366,20 @@ impl ReplicatedShardActor {
                     let _ = response.send(result);
                 }
 
+                ReplicatedShardMessage::KeysChunk {
+                    pattern,
+                    cursor,
+                    chunk_size,
+                    response,
+                } => {
+                    let chunk = self.executor.keys_chunk(&pattern, cursor, chunk_size);
+                    let _ = response.send(chunk);
+                }
+
                 ReplicatedShardMessage::ApplyRemoteDelta { delta } => {
                     self.apply_remote_delta_impl(delta);
+                    // Keys evicted to make room ship with the next drain
+                    self.record_evictions();
 
                     #[cfg(debug_assertions)]
                     self.verify_invariants();
@@ -243,6 +395,11 @@ impl ReplicatedShardActor {
                     response,
                 } => {
                     let evicted = self.executor.evict_expired_direct(current_time);
+                    // Free what UNLINK queued since the last cycle, off the shard's task
+                    let detached = self.executor.take_lazyfree_queue();
+                    if !detached.is_empty() {
+                        tokio::task::spawn_blocking(move || drop(detached));
+                    }
                     let _ = response.send(evicted);
                 }
 
@@ -251,6 +408,15 @@ impl ReplicatedShardActor {
                     let _ = response.send(snapshot);
                 }
 
+                ReplicatedShardMessage::BulkLoad { entries, response } => {
+                    self.record_imports(&entries);
+                    let report = self.executor.bulk_load(entries);
+                    let _ = response.send(report);
+
+                    #[cfg(debug_assertions)]
+                    self.verify_invariants();
+                }
+
                 ReplicatedShardMessage::ApplyRecoveredState { key, value } => {
                     // Insert into replica state
                     self.replica_state
@@ -286,11 +452,86 @@ impl ReplicatedShardActor {
                 }
 
                 ReplicatedShardMessage::Shutdown { response } => {
+                    self.progress.end();
                     let _ = response.send(());
                     break;
                 }
             }
+            if let Some(event) = latency_event {
+                self.executor
+                    .record_latency(event, start.elapsed().as_millis() as u64);
+            }
+            self.progress.end();
+        }
+    }
+
+    /// Track imported strings and hashes - the types replication carries -
+    /// so the import checkpoint includes them. Other types replace any
+    /// replicated value the key held.
+    fn record_imports(&mut self, entries: &[BulkEntry]) {
+        for (key, value, ttl_ms) in entries {
+            if *ttl_ms == Some(0) {
+                continue;
+            }
+            match value {
+                Value::String(s) => {
+                    self.replica_state
+                        .record_import(key.clone(), s.clone(), *ttl_ms);
+                }
+                Value::Hash(h) if !h.is_empty() => {
+                    let fields = h.iter().map(|(f, v)| (f.clone(), v.clone())).collect();
+                    self.replica_state.record_hash_import(key.clone(), fields);
+                }
+                _ => {
+                    self.replica_state.replicated_keys.remove(key);
+                }
+            }
+        }
+    }
+
+    /// Turn keys the executor evicted into DEL deltas (also queued in
+    /// pending deltas), so replicas and the WAL drop them too. A key the
+    /// command wrote again after evicting it is still live.
+    fn record_evictions(&mut self) -> Vec<ReplicationDelta> {
+        let evicted = self.executor.take_evicted_keys();
+        evicted
+            .into_iter()
+            .filter(|key| !self.executor.get_data().contains_key(key))
+            .filter_map(|key| self.replica_state.record_delete(key))
+            .collect()
+    }
+
+    /// Record what a script left in its declared keys: a string or hash as
+    /// its current value, a missing key as a DEL. Other types are not
+    /// replicated, as for the commands that write them.
+    fn record_script_effects(&mut self, keys: &[String]) -> Vec<ReplicationDelta> {
+        let mut deltas = Vec::with_capacity(keys.len());
+        for key in keys {
+            let delta = match self.executor.get_data().get(key) {
+                None => self.replica_state.record_delete(key.clone()),
+                Some(value) => {
+                    if let Some(sds) = value.as_string() {
+                        let sds = sds.clone();
+                        Some(self.replica_state.record_write(key.clone(), sds, None))
+                    } else if let Some(hash) = value.as_hash() {
+                        let fields: Vec<(String, crate::redis::SDS)> = hash
+                            .iter()
+                            .map(|(f, v)| (f.clone(), v.clone()))
+                            .collect();
+                        Some(self.replica_state.record_hash_write(key.clone(), fields))
+                    } else {
+                        None
+                    }
+                }
+            };
+            deltas.extend(delta);
         }
+
+        debug_assert!(
+            deltas.len() <= keys.len(),
+            "Postcondition violated: at most one delta per script key"
+        );
+        deltas
     }
 
     /// Record mutation after command execution
@@ -338,7 +579,7 @@ impl ReplicatedShardActor {
                         .record_write(key.clone(), value.clone(), expiry_ms),
                 )
             }
-            Command::Del(keys) => {
+            Command::Del(keys) | Command::Unlink(keys) => {
                 // Record deletion for each key
                 let mut result = None;
                 for key in keys {
@@ -441,15 +682,16 @@ impl ReplicatedShardActor {
     }
 
     /// Apply a remote delta from another replica
-    /// TigerStyle: Applies remote delta and syncs executor state
+    /// TigerStyle: Applies remote delta and syncs executor state. Deltas the
+    /// replica state refuses are logged and dropped.
     fn apply_remote_delta_impl(&mut self, delta: ReplicationDelta) {
-        // TigerStyle: Preconditions
-        debug_assert!(
-            !delta.key.is_empty(),
-            "Precondition: delta key must not be empty"
-        );
-
-        self.replica_state.apply_remote_delta(delta.clone());
+        if let Err(rejection) = self.replica_state.apply_remote_delta(delta.clone()) {
+            warn!(
+                "Rejected delta from replica {}: {}",
+                delta.source_replica.0, rejection
+            );
+            return;
+        }
 
         let Some(merged_value) = self.replica_state.replicated_keys.get(&delta.key).cloned() else {
             return;
@@ -570,7 +812,7 @@ mod tests {
         let handle = ReplicatedShardActor::spawn(ReplicaId::new(1), ConsistencyLevel::Eventual, 0);
 
         // Execute SET
-        let (result, delta) = handle
+        let (result, deltas) = handle
             .execute(Command::set(
                 "key1".to_string(),
                 crate::redis::SDS::from_str("value1"),
@@ -578,7 +820,7 @@ mod tests {
             .await;
 
         assert!(matches!(result, RespValue::SimpleString(_)));
-        assert!(delta.is_some());
+        assert_eq!(deltas.len(), 1);
 
         // Execute GET
         let result = handle
@@ -698,6 +940,54 @@ mod tests {
         handle.shutdown().await;
     }
 
+    #[tokio::test]
+    async fn test_replicated_shard_actor_eviction_yields_tombstone() {
+        let handle = ReplicatedShardActor::spawn(ReplicaId::new(1), ConsistencyLevel::Eventual, 0);
+
+        for (param, value) in [("maxmemory-policy", "volatile-ttl"), ("maxmemory", "150")] {
+            let (result, _) = handle
+                .execute(Command::ConfigSet(param.to_string(), value.to_string()))
+                .await;
+            assert_eq!(result, RespValue::ok());
+        }
+
+        // Each key costs 64 bytes plus its value. Eviction runs before a
+        // write, so the last of these takes the shard to 66 + 66 + 84 = 216
+        handle
+            .execute(Command::setex(
+                "old".to_string(),
+                100,
+                crate::redis::SDS::from_str("v1"),
+            ))
+            .await;
+        for (key, value) in [("keep", "v2".to_string()), ("big", "x".repeat(20))] {
+            handle
+                .execute(Command::set(
+                    key.to_string(),
+                    crate::redis::SDS::from_str(&value),
+                ))
+                .await;
+        }
+
+        // Over the limit: the next write evicts the only key with a TTL
+        let (result, deltas) = handle
+            .execute(Command::set(
+                "new".to_string(),
+                crate::redis::SDS::from_str("v2"),
+            ))
+            .await;
+        assert_eq!(result, RespValue::ok());
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].key, "old");
+        assert!(deltas[0].value.is_tombstone());
+        assert_eq!(deltas[1].key, "new");
+
+        let pending = handle.drain_pending_deltas().await;
+        assert!(pending.iter().any(|d| d.key == "old" && d.value.is_tombstone()));
+
+        handle.shutdown().await;
+    }
+
     #[tokio::test]
     async fn test_replicated_shard_actor_snapshot() {
         let handle = ReplicatedShardActor::spawn(ReplicaId::new(1), ConsistencyLevel::Eventual, 0);
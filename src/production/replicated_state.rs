@@ -1,17 +1,20 @@
 use super::gossip_actor::GossipActorHandle;
+use super::health::HealthCheck;
 use super::replicated_shard_actor::{ReplicatedShardActor, ReplicatedShardHandle};
+use super::shard_router::ShardRouter;
+use super::watchdog::ShardProgress;
 use crate::io::{ProductionTimeSource, TimeSource};
-use crate::redis::{Command, RespValue};
+use crate::redis::import::{ImportPlan, ImportStep};
+use crate::redis::{BulkEntry, BulkLoadReport, Command, LatencyMonitor, MemoryStats, RespValue};
+use crate::release::{info_server_fields, ServerMode};
 use crate::replication::gossip::GossipState;
 use crate::replication::{ReplicaId, ReplicationConfig, ReplicationDelta};
 use crate::simulator::VirtualTime;
 use crate::streaming::wal_actor::WalActorHandle;
 use crate::streaming::wal_config::FsyncPolicy;
-use crate::streaming::DeltaSinkSender;
+use crate::streaming::{DeltaSinkSender, PersistenceHealth};
 use parking_lot::RwLock;
-use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 /// Gossip backend for replication
@@ -29,11 +32,10 @@ pub enum GossipBackend {
 
 const NUM_SHARDS: usize = 16;
 
-fn hash_key(key: &str) -> usize {
-    let mut hasher = DefaultHasher::new();
-    key.hash(&mut hasher);
-    (hasher.finish() as usize) % NUM_SHARDS
-}
+const ROUTER: ShardRouter = ShardRouter::new(NUM_SHARDS);
+
+/// How often a blocked WAIT re-checks replica acks
+const WAIT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
 
 /// Replicated sharded state with configurable time source
 ///
@@ -63,6 +65,10 @@ pub struct ReplicatedShardedState<T: TimeSource = ProductionTimeSource> {
     delta_sink: Option<DeltaSinkSender>,
     /// Optional WAL actor handle for durable writes
     wal_handle: Option<WalActorHandle>,
+    /// Object store health from streaming persistence, for INFO
+    persistence_health: Option<PersistenceHealth>,
+    /// Component statuses for DEBUG HEALTH
+    health_check: Option<Arc<HealthCheck>>,
     /// Time source for getting current time
     time_source: T,
 }
@@ -90,9 +96,17 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
         let replica_id = ReplicaId::new(config.replica_id);
         let consistency_level = config.consistency_level;
 
-        // Spawn actor for each shard (no locks!)
+        // Spawn actor for each shard (no locks!), sharing one latency monitor
+        let latency = LatencyMonitor::new();
         let shards = (0..NUM_SHARDS)
-            .map(|shard_id| ReplicatedShardActor::spawn(replica_id, consistency_level, shard_id))
+            .map(|shard_id| {
+                ReplicatedShardActor::spawn_with_latency(
+                    replica_id,
+                    consistency_level,
+                    shard_id,
+                    latency.clone(),
+                )
+            })
             .collect();
 
         let gossip_state = Arc::new(RwLock::new(GossipState::new(config.clone())));
@@ -103,6 +117,8 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
             gossip_backend: GossipBackend::Locked(gossip_state),
             delta_sink: None,
             wal_handle: None,
+            persistence_health: None,
+            health_check: None,
             time_source,
         }
     }
@@ -119,9 +135,17 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
         let replica_id = ReplicaId::new(config.replica_id);
         let consistency_level = config.consistency_level;
 
-        // Spawn actor for each shard (no locks!)
+        // Spawn actor for each shard (no locks!), sharing one latency monitor
+        let latency = LatencyMonitor::new();
         let shards = (0..NUM_SHARDS)
-            .map(|shard_id| ReplicatedShardActor::spawn(replica_id, consistency_level, shard_id))
+            .map(|shard_id| {
+                ReplicatedShardActor::spawn_with_latency(
+                    replica_id,
+                    consistency_level,
+                    shard_id,
+                    latency.clone(),
+                )
+            })
             .collect();
 
         ReplicatedShardedState {
@@ -130,6 +154,8 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
             gossip_backend: GossipBackend::Actor(gossip_handle),
             delta_sink: None,
             wal_handle: None,
+            persistence_health: None,
+            health_check: None,
             time_source,
         }
     }
@@ -154,74 +180,188 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
         self.wal_handle = Some(handle);
     }
 
+    /// Report the object store's health in INFO persistence
+    pub fn set_persistence_health(&mut self, health: PersistenceHealth) {
+        self.persistence_health = Some(health);
+    }
+
     /// Clear the WAL handle (for shutdown)
     pub fn clear_wal_handle(&mut self) {
         self.wal_handle = None;
     }
 
+    /// Answer DEBUG HEALTH from these components
+    pub fn set_health_check(&mut self, health_check: Arc<HealthCheck>) {
+        self.health_check = Some(health_check);
+    }
+
     /// Execute a command (async - uses actor message passing)
     pub async fn execute(&self, cmd: Command) -> RespValue {
+        if let Some(refused) = self.refuse_write(&cmd) {
+            return refused;
+        }
+        let (result, deltas) = self.execute_unpublished(cmd).await;
+        self.publish_deltas(deltas).await;
+        result
+    }
+
+    /// Run EXEC's queued commands. Their deltas reach the WAL as one batch,
+    /// so recovery applies the whole transaction or none of it.
+    pub async fn execute_transaction(&self, cmds: Vec<Command>) -> RespValue {
+        if let Some(refused) = cmds.iter().find_map(|cmd| self.refuse_write(cmd)) {
+            return refused;
+        }
+        let mut results = Vec::with_capacity(cmds.len());
+        let mut deltas = Vec::new();
+        for cmd in cmds {
+            let (result, cmd_deltas) = self.execute_unpublished(cmd).await;
+            results.push(result);
+            deltas.extend(cmd_deltas);
+        }
+        self.publish_deltas(deltas).await;
+        RespValue::Array(Some(results))
+    }
+
+    /// The MISCONF error for a key write while streaming persistence refuses
+    /// writes. Its deltas would be acknowledged and then rejected by the
+    /// persistence actor, so they are refused before they are applied.
+    fn refuse_write(&self, cmd: &Command) -> Option<RespValue> {
+        let health = self.persistence_health.as_ref()?;
+        if !health.is_refusing_writes() || cmd.is_read_only() || cmd.get_keys().is_empty() {
+            return None;
+        }
+        Some(RespValue::err(
+            "MISCONF Errors writing to the object store. Commands that may modify the data set are disabled until parked segments are re-synced.",
+        ))
+    }
+
+    /// Execute a command and return its deltas without publishing them
+    async fn execute_unpublished(&self, cmd: Command) -> (RespValue, Vec<ReplicationDelta>) {
         if let Some(key) = cmd.get_primary_key() {
-            let shard_idx = hash_key(&key);
-            let (result, delta) = self.shards[shard_idx].execute(cmd).await;
-
-            if let Some(delta) = delta {
-                // Wrap in Arc to avoid cloning for each consumer
-                let delta = std::sync::Arc::new(delta);
-
-                // Write to WAL for local durability (before responding to client)
-                if let Some(ref wal) = self.wal_handle {
-                    let timestamp = delta.value.timestamp.time;
-                    match wal.fsync_policy() {
-                        FsyncPolicy::Always => {
-                            // Durable write: await fsync before client gets response.
-                            // Design choice: on WAL failure, we log and continue rather
-                            // than returning an error to the client. Rationale:
-                            // 1. The write succeeded in memory and will be replicated via gossip
-                            // 2. The delta is still sent to the streaming object store
-                            // 3. Failing the client response would require unwinding the
-                            //    in-memory state change, which is not supported
-                            // This means Always mode provides best-effort local durability,
-                            // not strict "fail client on WAL error" semantics.
-                            if let Err(e) = wal.write_durable(std::sync::Arc::clone(&delta), timestamp).await {
-                                tracing::error!("WAL durable write failed: {}", e);
-                            }
-                        }
-                        FsyncPolicy::EverySecond | FsyncPolicy::No => {
-                            // Fire-and-forget: client gets response before fsync
-                            wal.write_fire_and_forget(std::sync::Arc::clone(&delta), timestamp);
-                        }
+            let shard_idx = ROUTER.shard_for(&key);
+            return self.shards[shard_idx].execute(cmd).await;
+        }
+        match cmd {
+            Command::MSet(pairs) => {
+                // Execute all SETs concurrently
+                let futures: Vec<_> = pairs
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let shard_idx = ROUTER.shard_for(&key);
+                        self.shards[shard_idx].execute(Command::set(key, value))
+                    })
+                    .collect();
+                let deltas = futures::future::join_all(futures)
+                    .await
+                    .into_iter()
+                    .flat_map(|(_, deltas)| deltas)
+                    .collect();
+                (RespValue::simple("OK"), deltas)
+            }
+            cmd => (self.execute_global(cmd).await, Vec::new()),
+        }
+    }
+
+    /// Ship one command's deltas to the WAL, gossip and streaming
+    /// persistence. Several deltas (a multi-key write, a script, EXEC) are
+    /// one WAL batch entry.
+    async fn publish_deltas(&self, mut deltas: Vec<ReplicationDelta>) {
+        if deltas.len() <= 1 {
+            if let Some(delta) = deltas.pop() {
+                self.publish_delta(delta).await;
+            }
+            return;
+        }
+
+        let batch = std::sync::Arc::new(deltas);
+        if let Some(ref wal) = self.wal_handle {
+            // Kept until its newest delta has been streamed
+            let timestamp = batch
+                .iter()
+                .map(|delta| delta.value.timestamp.time)
+                .max()
+                .unwrap_or(0);
+            match wal.fsync_policy() {
+                FsyncPolicy::Always => {
+                    // Same best-effort durability as single deltas below
+                    if let Err(e) = wal
+                        .write_batch_durable(std::sync::Arc::clone(&batch), timestamp)
+                        .await
+                    {
+                        tracing::error!("WAL durable batch write failed: {}", e);
                     }
                 }
+                FsyncPolicy::EverySecond | FsyncPolicy::No => {
+                    wal.write_batch_fire_and_forget(std::sync::Arc::clone(&batch), timestamp);
+                }
+            }
+        }
 
-                // Unwrap Arc for gossip and streaming (they need owned values)
-                let delta = std::sync::Arc::try_unwrap(delta)
-                    .unwrap_or_else(|arc| (*arc).clone());
+        let deltas = std::sync::Arc::try_unwrap(batch).unwrap_or_else(|arc| (*arc).clone());
+        self.replicate(deltas);
+    }
 
-                // Send to gossip for replication
-                if self.config.enabled {
-                    match &self.gossip_backend {
-                        GossipBackend::Locked(gossip_state) => {
-                            let mut gossip = gossip_state.write();
-                            gossip.queue_deltas(vec![delta.clone()]);
-                        }
-                        GossipBackend::Actor(handle) => {
-                            // Actor-based: fire-and-forget, no locks!
-                            handle.queue_deltas(vec![delta.clone()]);
-                        }
+    /// Ship one delta to the WAL, gossip and streaming persistence.
+    ///
+    /// Eviction DELs go through here like any other write, so an evicted key
+    /// is not resurrected by replicas or by recovery.
+    async fn publish_delta(&self, delta: ReplicationDelta) {
+        // Wrap in Arc to avoid cloning for each consumer
+        let delta = std::sync::Arc::new(delta);
+
+        // Write to WAL for local durability (before responding to client)
+        if let Some(ref wal) = self.wal_handle {
+            let timestamp = delta.value.timestamp.time;
+            match wal.fsync_policy() {
+                FsyncPolicy::Always => {
+                    // Durable write: await fsync before client gets response.
+                    // Design choice: on WAL failure, we log and continue rather
+                    // than returning an error to the client. Rationale:
+                    // 1. The write succeeded in memory and will be replicated via gossip
+                    // 2. The delta is still sent to the streaming object store
+                    // 3. Failing the client response would require unwinding the
+                    //    in-memory state change, which is not supported
+                    // This means Always mode provides best-effort local durability,
+                    // not strict "fail client on WAL error" semantics.
+                    if let Err(e) = wal.write_durable(std::sync::Arc::clone(&delta), timestamp).await {
+                        tracing::error!("WAL durable write failed: {}", e);
                     }
                 }
+                FsyncPolicy::EverySecond | FsyncPolicy::No => {
+                    // Fire-and-forget: client gets response before fsync
+                    wal.write_fire_and_forget(std::sync::Arc::clone(&delta), timestamp);
+                }
+            }
+        }
+
+        // Unwrap Arc for gossip and streaming (they need owned values)
+        let delta = std::sync::Arc::try_unwrap(delta)
+            .unwrap_or_else(|arc| (*arc).clone());
+        self.replicate(vec![delta]);
+    }
 
-                // Send to streaming persistence if enabled
-                if let Some(ref sink) = self.delta_sink {
-                    // Best-effort send - don't block or error on persistence failures
-                    let _ = sink.send(delta);
+    /// Hand deltas already in the WAL to gossip and streaming persistence
+    fn replicate(&self, deltas: Vec<ReplicationDelta>) {
+        // Send to gossip for replication
+        if self.config.enabled {
+            match &self.gossip_backend {
+                GossipBackend::Locked(gossip_state) => {
+                    let mut gossip = gossip_state.write();
+                    gossip.queue_deltas(deltas.clone());
+                }
+                GossipBackend::Actor(handle) => {
+                    // Actor-based: fire-and-forget, no locks!
+                    handle.queue_deltas(deltas.clone());
                 }
             }
+        }
 
-            result
-        } else {
-            self.execute_global(cmd).await
+        // Send to streaming persistence if enabled
+        if let Some(ref sink) = self.delta_sink {
+            // Best-effort send - don't block or error on persistence failures
+            for delta in deltas {
+                let _ = sink.send(delta);
+            }
         }
     }
 
@@ -239,25 +379,12 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
                 futures::future::join_all(futures).await;
                 RespValue::simple("OK")
             }
-            Command::MSet(pairs) => {
-                // Execute all SETs concurrently
-                let futures: Vec<_> = pairs
-                    .iter()
-                    .map(|(key, value)| {
-                        let shard_idx = hash_key(key);
-                        let set_cmd = Command::set(key.clone(), value.clone());
-                        self.shards[shard_idx].execute(set_cmd)
-                    })
-                    .collect();
-                futures::future::join_all(futures).await;
-                RespValue::simple("OK")
-            }
             Command::MGet(keys) => {
                 // Execute all GETs concurrently
                 let futures: Vec<_> = keys
                     .iter()
                     .map(|key| {
-                        let shard_idx = hash_key(key);
+                        let shard_idx = ROUTER.shard_for(key);
                         let get_cmd = Command::Get(key.clone());
                         self.shards[shard_idx].execute_readonly(get_cmd)
                     })
@@ -270,7 +397,7 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
                 let futures: Vec<_> = keys
                     .iter()
                     .map(|key| {
-                        let shard_idx = hash_key(key);
+                        let shard_idx = ROUTER.shard_for(key);
                         let exists_cmd = Command::Exists(vec![key.clone()]);
                         self.shards[shard_idx].execute_readonly(exists_cmd)
                     })
@@ -288,30 +415,39 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
                     .sum();
                 RespValue::Integer(count)
             }
-            Command::Keys(_pattern) => {
-                // Execute on all shards concurrently
+            Command::Keys(pattern) => {
+                // With keys-chunk-size set, each shard answers in chunks so
+                // writes interleave with the scan
+                let chunk_size = self.keys_chunk_size().await;
                 let futures: Vec<_> = self
                     .shards
                     .iter()
-                    .map(|shard| shard.execute_readonly(cmd.clone()))
+                    .map(|shard| shard.keys_chunked(pattern, chunk_size))
+                    .collect();
+                let all_keys: Vec<RespValue> = futures::future::join_all(futures)
+                    .await
+                    .into_iter()
+                    .flatten()
                     .collect();
-                let results = futures::future::join_all(futures).await;
-                let mut all_keys = Vec::new();
-                for result in results {
-                    if let RespValue::Array(Some(keys)) = result {
-                        all_keys.extend(keys);
-                    }
-                }
                 RespValue::Array(Some(all_keys))
             }
             Command::Info => {
                 let pid = std::process::id();
+                let release = info_server_fields(ServerMode::Standalone);
+                // Degraded: the object store has been unreachable past the
+                // threshold and writes are held in memory and the WAL
+                let persistence = match &self.persistence_health {
+                    Some(health) => format!(
+                        "object_store_degraded:{}\r\n\
+                         object_store_unsynced_segments:{}\r\n",
+                        u8::from(health.is_degraded()),
+                        health.unsynced_segments()
+                    ),
+                    None => String::new(),
+                };
                 let info = format!(
                     "# Server\r\n\
-                     redis_version:7.0.0\r\n\
-                     redis_mode:standalone\r\n\
-                     os:Linux\r\n\
-                     arch_bits:64\r\n\
+                     {release}\
                      tcp_port:6379\r\n\
                      uptime_in_seconds:0\r\n\
                      uptime_in_days:0\r\n\
@@ -353,6 +489,11 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
                      num_shards:{num_shards}\r\n\
                      architecture:actor_per_shard\r\n\
                      \r\n\
+                     # Persistence\r\n\
+                     loading:0\r\n\
+                     aof_enabled:{aof_enabled}\r\n\
+                     {persistence}\
+                     \r\n\
                      # CPU\r\n\
                      used_cpu_sys:0.000000\r\n\
                      used_cpu_user:0.000000\r\n\
@@ -365,9 +506,17 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
                     consistency_level = self.config.consistency_level,
                     replication_enabled = self.config.enabled,
                     num_shards = NUM_SHARDS,
+                    aof_enabled = u8::from(self.wal_handle.is_some()),
+                    persistence = persistence,
                 );
                 RespValue::BulkString(Some(info.into_bytes()))
             }
+            Command::DebugHealth => match &self.health_check {
+                Some(health_check) => health_check.report().to_resp(),
+                None => HealthCheck::new().report().to_resp(),
+            },
+            // Connections here keep no MULTI, Pub/Sub or auth state to reset
+            Command::Reset => RespValue::simple("RESET"),
             Command::DbSize => {
                 // Fan out DBSIZE to all shards, sum per-shard key counts
                 let futures: Vec<_> = self
@@ -389,18 +538,188 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
                 debug_assert!(total >= 0, "Postcondition: DBSIZE must be non-negative");
                 RespValue::Integer(total)
             }
+            // Each shard estimates its own keys; DOCTOR judges the sum
+            Command::MemoryStats | Command::MemoryDoctor => {
+                let futures: Vec<_> = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.execute_readonly(Command::MemoryStats))
+                    .collect();
+                let mut merged = MemoryStats::default();
+                for result in futures::future::join_all(futures).await {
+                    match MemoryStats::from_resp(&result) {
+                        Some(stats) => merged.merge(&stats),
+                        None if matches!(result, RespValue::Error(_)) => return result,
+                        None => return RespValue::err("ERR unexpected MEMORY STATS reply from shard"),
+                    }
+                }
+                if matches!(cmd, Command::MemoryDoctor) {
+                    RespValue::BulkString(Some(merged.doctor().into_bytes()))
+                } else {
+                    merged.to_resp()
+                }
+            }
+            // The shards share one latency monitor
+            Command::LatencyLatest
+            | Command::LatencyHistory(_)
+            | Command::LatencyReset(_)
+            | Command::LatencyDoctor
+            | Command::LatencyHelp
+            | Command::MemoryHelp
+            | Command::DebugProtocol(_)
+            | Command::Lolwut { .. } => {
+                let (result, _) = self.shards[0].execute(cmd.clone()).await;
+                result
+            }
+            Command::ConfigGet(_) => {
+                // Every shard holds the same config
+                let (result, _) = self.shards[0].execute(cmd.clone()).await;
+                result
+            }
+            // Sleep on the runtime's timer: moving a shard's clock would
+            // expire its keys early
+            Command::DebugSleep(seconds) => {
+                tokio::time::sleep(std::time::Duration::from_secs_f64(*seconds)).await;
+                RespValue::ok()
+            }
+            // Every shard expires and persists its own keys
+            Command::DebugSetActiveExpire(_) | Command::DebugReload => {
+                let futures: Vec<_> = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.execute(cmd.clone()))
+                    .collect();
+                let mut reply = RespValue::ok();
+                for (result, deltas) in futures::future::join_all(futures).await {
+                    for delta in deltas {
+                        self.publish_delta(delta).await;
+                    }
+                    if matches!(result, RespValue::Error(_)) {
+                        reply = result;
+                    }
+                }
+                reply
+            }
+            Command::ConfigSet(_, _) => {
+                // Apply on all shards; lowering maxmemory evicts right away
+                let futures: Vec<_> = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.execute(cmd.clone()))
+                    .collect();
+                let mut reply = RespValue::ok();
+                for (result, deltas) in futures::future::join_all(futures).await {
+                    for delta in deltas {
+                        self.publish_delta(delta).await;
+                    }
+                    if matches!(result, RespValue::Error(_)) {
+                        reply = result;
+                    }
+                }
+                reply
+            }
+            Command::Wait(numreplicas, timeout_ms) => {
+                RespValue::Integer(self.wait_for_replicas(*numreplicas, *timeout_ms).await as i64)
+            }
             _ => RespValue::err("ERR unknown command"),
         }
     }
 
+    /// WAIT: block until `numreplicas` replicas acknowledged the current
+    /// replication offset or `timeout_ms` passed (0 waits forever). Returns
+    /// how many did.
+    async fn wait_for_replicas(&self, numreplicas: i64, timeout_ms: i64) -> usize {
+        debug_assert!(timeout_ms >= 0, "Precondition: WAIT timeout must not be negative");
+        if !self.config.enabled {
+            return 0;
+        }
+        let target = self.replication_offset().await;
+        let deadline = (timeout_ms > 0)
+            .then(|| self.time_source.now_millis().saturating_add(timeout_ms as u64));
+        loop {
+            let acked = self.replicas_acked(target).await;
+            if acked as i64 >= numreplicas {
+                return acked;
+            }
+            if deadline.is_some_and(|deadline| self.time_source.now_millis() >= deadline) {
+                return acked;
+            }
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Offset of the newest delta this replica has broadcast
+    async fn replication_offset(&self) -> u64 {
+        match &self.gossip_backend {
+            GossipBackend::Locked(gossip_state) => gossip_state.read().offsets.offset(),
+            GossipBackend::Actor(handle) => handle.get_offset().await,
+        }
+    }
+
+    async fn replicas_acked(&self, offset: u64) -> usize {
+        match &self.gossip_backend {
+            GossipBackend::Locked(gossip_state) => {
+                gossip_state.read().offsets.replicas_acked(offset)
+            }
+            GossipBackend::Actor(handle) => handle.replicas_acked(offset).await,
+        }
+    }
+
+    /// `keys-chunk-size`; every shard holds the same config
+    async fn keys_chunk_size(&self) -> usize {
+        let (reply, _) = self.shards[0]
+            .execute(Command::ConfigGet(vec!["keys-chunk-size".to_string()]))
+            .await;
+        match reply {
+            RespValue::Array(Some(pair)) => match pair.get(1) {
+                Some(RespValue::BulkString(Some(v))) => std::str::from_utf8(v)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                _ => 0,
+            },
+            _ => 0,
+        }
+    }
+
     /// Apply remote deltas from other replicas (fire-and-forget)
     pub fn apply_remote_deltas(&self, deltas: Vec<ReplicationDelta>) {
         for delta in deltas {
-            let shard_idx = hash_key(&delta.key);
+            let shard_idx = ROUTER.shard_for(&delta.key);
             self.shards[shard_idx].apply_remote_delta(delta);
         }
     }
 
+    /// A peer's broadcast batch holding its offsets `first..=last` was
+    /// applied; acknowledges it when this replica's progress moved
+    pub fn record_replication_delivery(&self, source: ReplicaId, first: u64, last: u64) {
+        match &self.gossip_backend {
+            GossipBackend::Locked(gossip_state) => {
+                gossip_state.write().record_delivery(source, first, last)
+            }
+            GossipBackend::Actor(handle) => handle.record_delivery(source, first, last),
+        }
+    }
+
+    /// A peer acknowledged `target`'s offsets through `offset`, for WAIT
+    pub fn record_replication_ack(&self, source: ReplicaId, target: ReplicaId, offset: u64) {
+        match &self.gossip_backend {
+            GossipBackend::Locked(gossip_state) => {
+                gossip_state.write().record_ack(source, target, offset)
+            }
+            GossipBackend::Actor(handle) => handle.record_ack(source, target, offset),
+        }
+    }
+
+    /// A peer's heartbeat arrived; acknowledging it lets WAIT count this
+    /// replica as connected before any writes
+    pub fn ack_heartbeat(&self, source: ReplicaId) {
+        match &self.gossip_backend {
+            GossipBackend::Locked(gossip_state) => gossip_state.write().ack_heartbeat(source),
+            GossipBackend::Actor(handle) => handle.ack_heartbeat(source),
+        }
+    }
+
     /// Collect pending deltas from all shards (async)
     pub async fn collect_pending_deltas(&self) -> Vec<ReplicationDelta> {
         let futures: Vec<_> = self
@@ -474,6 +793,11 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
         NUM_SHARDS
     }
 
+    /// Per-shard progress, for the watchdog
+    pub fn shard_progress(&self) -> Vec<Arc<ShardProgress>> {
+        self.shards.iter().map(|shard| shard.progress()).collect()
+    }
+
     /// Create a snapshot of all replicated state for checkpointing (async)
     ///
     /// Returns a HashMap of all keys to their ReplicatedValue across all shards.
@@ -508,7 +832,7 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
         // Step 1: Apply checkpoint state if present (fire-and-forget to actors)
         if let Some(state) = checkpoint_state {
             for (key, value) in state {
-                let shard_idx = hash_key(&key);
+                let shard_idx = ROUTER.shard_for(&key);
                 self.shards[shard_idx].apply_recovered_state(key, value);
             }
         }
@@ -517,6 +841,47 @@ impl<T: TimeSource> ReplicatedShardedState<T> {
         self.apply_remote_deltas(deltas);
     }
 
+    /// Install `entries` directly in their shards, bypassing per-command
+    /// execution. Imported strings and hashes queue no deltas: persist
+    /// them with one checkpoint afterwards.
+    pub async fn bulk_load(&self, entries: Vec<BulkEntry>) -> BulkLoadReport {
+        let mut per_shard: Vec<Vec<BulkEntry>> = vec![Vec::new(); self.shards.len()];
+        for entry in entries {
+            per_shard[ROUTER.shard_for(&entry.0)].push(entry);
+        }
+        let futures: Vec<_> = self
+            .shards
+            .iter()
+            .zip(per_shard)
+            .filter(|(_, entries)| !entries.is_empty())
+            .map(|(shard, entries)| shard.bulk_load(entries))
+            .collect();
+        let mut report = BulkLoadReport::default();
+        for shard_report in futures::future::join_all(futures).await {
+            report.merge(shard_report);
+        }
+        report
+    }
+
+    /// Apply an import plan in order: loads through `bulk_load`, other
+    /// commands through `execute`. Returns the combined load report and
+    /// the number of executed commands that replied with an error.
+    pub async fn import(&self, plan: ImportPlan) -> (BulkLoadReport, usize) {
+        let mut report = BulkLoadReport::default();
+        let mut failed = 0;
+        for step in plan.steps {
+            match step {
+                ImportStep::Load(entries) => report.merge(self.bulk_load(entries).await),
+                ImportStep::Execute(cmd) => {
+                    if matches!(self.execute(cmd).await, RespValue::Error(_)) {
+                        failed += 1;
+                    }
+                }
+            }
+        }
+        (report, failed)
+    }
+
     /// Get the total number of keys across all shards (async)
     pub async fn key_count(&self) -> usize {
         let futures: Vec<_> = self
@@ -537,6 +902,8 @@ impl<T: TimeSource> Clone for ReplicatedShardedState<T> {
             gossip_backend: self.gossip_backend.clone(),
             delta_sink: self.delta_sink.clone(),
             wal_handle: self.wal_handle.clone(),
+            persistence_health: self.persistence_health.clone(),
+            health_check: self.health_check.clone(),
             time_source: self.time_source.clone(),
         }
     }
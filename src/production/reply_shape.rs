@@ -0,0 +1,404 @@
+//! RESP3 types for replies the executor builds in RESP2 form.
+//!
+//! The executor answers every connection in RESP2: HGETALL, CONFIG GET,
+//! COMMAND DOCS and XINFO STREAM reply an array of alternating names and
+//! values, SMEMBERS an array of members, ZSCORE a bulk string holding a
+//! float. A connection that negotiated RESP3 with `HELLO 3` expects a map
+//! (`%`), a set (`~`) or a double (`,`) instead. The type depends only on
+//! the command, so each command declares it here and the connection maps
+//! it to the negotiated protocol while encoding:
+//!
+//! - `Map`, `Set`: the aggregate header is rewritten, the elements are
+//!   encoded as before
+//! - `Double` (ZSCORE, ZINCRBY, HINCRBYFLOAT): a float bulk string becomes
+//!   a double. INCRBYFLOAT stays a bulk string, as in Redis, which replies
+//!   with the string it stored
+//! - `Boolean`: an integer 0 or 1 becomes `#f` or `#t`
+//! - `Verbatim` (INFO, CLIENT INFO/LIST, the DOCTOR reports): a bulk string
+//!   of text becomes a verbatim string in the `txt` format
+//! - `DoubleArray`, `MapArray`, `BooleanMap`: one level of elements is
+//!   typed too (ZMSCORE scores, XINFO GROUPS entries, DEBUG PROTOCOL map
+//!   values, FUNCTION LIST libraries)
+//!
+//! Nil needs no declaration: the RESP3 encoder sends every nil bulk string
+//! and nil array as the null type (`_`), including inside EXEC. Otherwise
+//! replies queued in a transaction keep their RESP2 types inside the EXEC
+//! array, and scores in ZRANGE WITHSCORES and friends stay bulk strings.
+//!
+//! # TigerStyle Invariants
+//!
+//! - A map header is only written for an even number of elements
+//! - Errors, nil and replies not in the declared RESP2 form are never
+//!   retyped
+
+use crate::redis::{Command, RespValue};
+use bytes::{BufMut, BytesMut};
+
+/// RESP version a connection negotiated with HELLO
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+impl Protocol {
+    /// The version HELLO asked for, if the server speaks it
+    pub(crate) fn from_version(version: i64) -> Option<Self> {
+        match version {
+            2 => Some(Protocol::Resp2),
+            3 => Some(Protocol::Resp3),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn version(self) -> i64 {
+        match self {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        }
+    }
+}
+
+/// How a RESP3 connection sees a command's array reply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ReplyShape {
+    /// An array in both protocols
+    Flat,
+    /// Alternating names and values, sent as a map
+    Map,
+    /// Unordered unique members, sent as a set
+    Set,
+    /// An array whose elements are maps
+    MapArray,
+    /// A bulk string holding a float, sent as a double
+    Double,
+    /// An array of floats (or nil), sent as doubles
+    DoubleArray,
+    /// An integer 0 or 1, sent as a boolean
+    Boolean,
+    /// Alternating names and 0/1 flags, sent as a map of booleans
+    BooleanMap,
+    /// A bulk string of plain text, sent as a verbatim string
+    Verbatim,
+}
+
+impl ReplyShape {
+    /// The shape of `cmd`'s reply under RESP3
+    pub(crate) fn of(cmd: &Command) -> Self {
+        match cmd {
+            Command::HGetAll(_)
+            | Command::ConfigGet(_)
+            | Command::AclGetUser { .. }
+            | Command::CommandDocs(_)
+            | Command::DebugHealth
+            | Command::FunctionStats => ReplyShape::Map,
+            Command::SMembers(_) => ReplyShape::Set,
+            Command::Info
+            | Command::ClientInfo
+            | Command::ClientList
+            | Command::LatencyDoctor
+            | Command::MemoryDoctor => ReplyShape::Verbatim,
+            Command::ZScore(_, _) | Command::ZIncrBy(_, _, _) | Command::HIncrByFloat(_, _, _) => {
+                ReplyShape::Double
+            }
+            Command::ZMScore(_, _) => ReplyShape::DoubleArray,
+            Command::FunctionList { .. } => ReplyShape::MapArray,
+            Command::DebugProtocol(kind) => match kind.as_str() {
+                "double" => ReplyShape::Double,
+                "set" => ReplyShape::Set,
+                "map" => ReplyShape::BooleanMap,
+                "true" | "false" => ReplyShape::Boolean,
+                _ => ReplyShape::Flat,
+            },
+            Command::Unknown(name) => match name.as_str() {
+                "XINFO STREAM" => ReplyShape::Map,
+                "XINFO GROUPS" | "XINFO CONSUMERS" => ReplyShape::MapArray,
+                _ => ReplyShape::Flat,
+            },
+            _ => ReplyShape::Flat,
+        }
+    }
+
+    /// The shape of the element at `index` of a reply of this shape
+    pub(crate) fn element(self, index: usize) -> Self {
+        match self {
+            ReplyShape::MapArray => ReplyShape::Map,
+            ReplyShape::DoubleArray => ReplyShape::Double,
+            ReplyShape::BooleanMap if index % 2 == 1 => ReplyShape::Boolean,
+            _ => ReplyShape::Flat,
+        }
+    }
+
+    /// Write `value` as this shape's RESP3 scalar type, returning whether
+    /// it was written. Only the declared RESP2 form is converted: a bulk
+    /// string holding a float for `Double`, 0 or 1 for `Boolean`, any bulk
+    /// string for `Verbatim`.
+    pub(crate) fn write_scalar(self, value: &RespValue, buf: &mut BytesMut) -> bool {
+        match (self, value) {
+            (ReplyShape::Double, RespValue::BulkString(Some(data))) => {
+                let Some(text) = std::str::from_utf8(data).ok() else {
+                    return false;
+                };
+                let text = match text.parse::<f64>() {
+                    Ok(d) if d.is_nan() => "nan",
+                    Ok(d) if d == f64::INFINITY => "inf",
+                    Ok(d) if d == f64::NEG_INFINITY => "-inf",
+                    Ok(_) => text,
+                    Err(_) => return false,
+                };
+                buf.put_u8(b',');
+                buf.extend_from_slice(text.as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                true
+            }
+            (ReplyShape::Boolean, RespValue::Integer(0)) => {
+                buf.extend_from_slice(b"#f\r\n");
+                true
+            }
+            (ReplyShape::Boolean, RespValue::Integer(1)) => {
+                buf.extend_from_slice(b"#t\r\n");
+                true
+            }
+            (ReplyShape::Verbatim, RespValue::BulkString(Some(text))) => {
+                buf.put_u8(b'=');
+                buf.extend_from_slice((text.len() + 4).to_string().as_bytes());
+                buf.extend_from_slice(b"\r\ntxt:");
+                buf.extend_from_slice(text);
+                buf.extend_from_slice(b"\r\n");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Write the aggregate header `value` takes in this shape, returning
+    /// the elements still to encode. `None` means `value` is not reshaped
+    /// and is encoded as it is.
+    pub(crate) fn write_header<'a>(
+        self,
+        value: &'a RespValue,
+        buf: &mut BytesMut,
+    ) -> Option<&'a [RespValue]> {
+        let RespValue::Array(Some(elements)) = value else {
+            return None;
+        };
+        let (marker, len) = match self {
+            ReplyShape::Flat | ReplyShape::Double | ReplyShape::Boolean | ReplyShape::Verbatim => {
+                return None
+            }
+            ReplyShape::Map | ReplyShape::BooleanMap if elements.len() % 2 != 0 => return None,
+            ReplyShape::Map | ReplyShape::BooleanMap => (b'%', elements.len() / 2),
+            ReplyShape::Set => (b'~', elements.len()),
+            ReplyShape::MapArray | ReplyShape::DoubleArray => (b'*', elements.len()),
+        };
+        buf.put_u8(marker);
+        buf.extend_from_slice(len.to_string().as_bytes());
+        buf.extend_from_slice(b"\r\n");
+
+        // TigerStyle: Postcondition - a map pairs every element
+        debug_assert!(
+            marker != b'%' || len * 2 == elements.len(),
+            "Postcondition violated: a map header must count pairs of elements"
+        );
+        Some(elements)
+    }
+
+    /// `value` with this shape's RESP3 types, for replies handed on as
+    /// values instead of encoded (scripts after `redis.setresp(3)`). Only
+    /// the forms the writers above convert are retyped.
+    pub(crate) fn retype(self, value: RespValue) -> RespValue {
+        match (self, value) {
+            (ReplyShape::Double, RespValue::BulkString(Some(data))) => {
+                let parsed = std::str::from_utf8(&data).ok().and_then(|t| t.parse().ok());
+                match parsed {
+                    Some(d) => RespValue::Double(d),
+                    None => RespValue::BulkString(Some(data)),
+                }
+            }
+            (ReplyShape::Boolean, RespValue::Integer(n @ (0 | 1))) => RespValue::Boolean(n == 1),
+            (ReplyShape::Verbatim, RespValue::BulkString(Some(text))) => RespValue::Verbatim {
+                format: *b"txt",
+                text,
+            },
+            (ReplyShape::Map | ReplyShape::BooleanMap, RespValue::Array(Some(elements)))
+                if elements.len() % 2 == 0 =>
+            {
+                let mut pairs = Vec::with_capacity(elements.len() / 2);
+                let mut elements = elements.into_iter();
+                while let (Some(name), Some(value)) = (elements.next(), elements.next()) {
+                    pairs.push((name, self.element(1).retype(value)));
+                }
+                RespValue::Map(pairs)
+            }
+            (ReplyShape::Set, RespValue::Array(Some(members))) => RespValue::Set(members),
+            (ReplyShape::MapArray | ReplyShape::DoubleArray, RespValue::Array(Some(elements))) => {
+                RespValue::Array(Some(
+                    elements
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, element)| self.element(index).retype(element))
+                        .collect(),
+                ))
+            }
+            (_, value) => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis::SDS;
+
+    fn bulk(s: &str) -> RespValue {
+        RespValue::BulkString(Some(s.as_bytes().to_vec()))
+    }
+
+    fn scalar(shape: ReplyShape, value: &RespValue) -> Option<String> {
+        let mut buf = BytesMut::new();
+        shape
+            .write_scalar(value, &mut buf)
+            .then(|| String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    fn header(shape: ReplyShape, value: &RespValue) -> (String, Option<usize>) {
+        let mut buf = BytesMut::new();
+        let rest = shape.write_header(value, &mut buf).map(<[RespValue]>::len);
+        (String::from_utf8_lossy(&buf).into_owned(), rest)
+    }
+
+    #[test]
+    fn test_shapes_by_command() {
+        assert_eq!(
+            ReplyShape::of(&Command::HGetAll("h".to_string())),
+            ReplyShape::Map
+        );
+        assert_eq!(
+            ReplyShape::of(&Command::SMembers("s".to_string())),
+            ReplyShape::Set
+        );
+        assert_eq!(
+            ReplyShape::of(&Command::Unknown("XINFO GROUPS".to_string())),
+            ReplyShape::MapArray
+        );
+        assert_eq!(ReplyShape::MapArray.element(0), ReplyShape::Map);
+        assert_eq!(
+            ReplyShape::of(&Command::ZScore("z".to_string(), SDS::from_str("m"))),
+            ReplyShape::Double
+        );
+        assert_eq!(
+            ReplyShape::of(&Command::IncrByFloat("k".to_string(), 1.5)),
+            ReplyShape::Flat
+        );
+        assert_eq!(ReplyShape::BooleanMap.element(0), ReplyShape::Flat);
+        assert_eq!(ReplyShape::BooleanMap.element(1), ReplyShape::Boolean);
+        assert_eq!(
+            ReplyShape::of(&Command::Get("k".to_string())),
+            ReplyShape::Flat
+        );
+    }
+
+    #[test]
+    fn test_headers_count_pairs_and_members() {
+        let pairs = RespValue::Array(Some(vec![bulk("f1"), bulk("v1"), bulk("f2"), bulk("v2")]));
+        assert_eq!(
+            header(ReplyShape::Map, &pairs),
+            ("%2\r\n".to_string(), Some(4))
+        );
+        assert_eq!(
+            header(ReplyShape::Set, &pairs),
+            ("~4\r\n".to_string(), Some(4))
+        );
+        assert_eq!(header(ReplyShape::Flat, &pairs), (String::new(), None));
+
+        // Odd arrays, errors and nil are left to the plain encoder
+        let odd = RespValue::Array(Some(vec![bulk("f1")]));
+        assert_eq!(header(ReplyShape::Map, &odd), (String::new(), None));
+        let error =
+            RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value");
+        assert_eq!(header(ReplyShape::Map, &error), (String::new(), None));
+        assert_eq!(
+            header(ReplyShape::Set, &RespValue::Array(None)),
+            (String::new(), None)
+        );
+    }
+
+    #[test]
+    fn test_scalars_convert_only_their_resp2_form() {
+        assert_eq!(
+            scalar(ReplyShape::Double, &bulk("1.5")),
+            Some(",1.5\r\n".to_string())
+        );
+        assert_eq!(
+            scalar(ReplyShape::Double, &bulk("-inf")),
+            Some(",-inf\r\n".to_string())
+        );
+        assert_eq!(
+            scalar(ReplyShape::Double, &bulk("inf")),
+            Some(",inf\r\n".to_string())
+        );
+        assert_eq!(
+            scalar(ReplyShape::Boolean, &RespValue::Integer(1)),
+            Some("#t\r\n".to_string())
+        );
+        assert_eq!(
+            scalar(ReplyShape::Boolean, &RespValue::Integer(0)),
+            Some("#f\r\n".to_string())
+        );
+        assert_eq!(
+            scalar(ReplyShape::Verbatim, &bulk("# Server")),
+            Some("=12\r\ntxt:# Server\r\n".to_string())
+        );
+
+        // Nil, errors, other values and other shapes go to the plain encoder
+        assert_eq!(
+            scalar(ReplyShape::Double, &RespValue::BulkString(None)),
+            None
+        );
+        assert_eq!(scalar(ReplyShape::Double, &bulk("abc")), None);
+        assert_eq!(
+            scalar(ReplyShape::Double, &RespValue::err("ERR nope")),
+            None
+        );
+        assert_eq!(scalar(ReplyShape::Boolean, &RespValue::Integer(2)), None);
+        assert_eq!(scalar(ReplyShape::Flat, &bulk("1.5")), None);
+        assert_eq!(scalar(ReplyShape::Map, &RespValue::Integer(1)), None);
+        assert_eq!(
+            scalar(ReplyShape::Verbatim, &RespValue::BulkString(None)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_retype_builds_resp3_values() {
+        let pairs = RespValue::Array(Some(vec![bulk("f"), bulk("v")]));
+        assert_eq!(
+            ReplyShape::Map.retype(pairs.clone()),
+            RespValue::Map(vec![(bulk("f"), bulk("v"))])
+        );
+        assert_eq!(
+            ReplyShape::Set.retype(pairs.clone()),
+            RespValue::Set(vec![bulk("f"), bulk("v")])
+        );
+        assert_eq!(ReplyShape::Flat.retype(pairs.clone()), pairs);
+        assert_eq!(
+            ReplyShape::DoubleArray.retype(RespValue::Array(Some(vec![
+                bulk("2.5"),
+                RespValue::BulkString(None)
+            ]))),
+            RespValue::Array(Some(vec![
+                RespValue::Double(2.5),
+                RespValue::BulkString(None)
+            ]))
+        );
+        assert_eq!(
+            ReplyShape::Boolean.retype(RespValue::Integer(1)),
+            RespValue::Boolean(true)
+        );
+        assert_eq!(
+            ReplyShape::Double.retype(RespValue::err("ERR nope")),
+            RespValue::err("ERR nope")
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Streaming writes for replies too large to buffer whole.
+//!
+//! Replies are normally encoded into the connection's write buffer and
+//! flushed once per pipeline batch. A reply whose encoding would push that
+//! buffer past the write high-watermark is streamed instead: headers and
+//! small elements still go through the buffer, which is flushed whenever
+//! it reaches the watermark, and bulk payloads larger than one chunk are
+//! written straight from the reply in `STREAM_CHUNK_SIZE` pieces. Every
+//! write awaits the socket, so a slow reader holds the connection back
+//! rather than growing its buffer.
+//!
+//! The reply value itself is still materialized by the executor; what is
+//! bounded is the extra copy each connection would otherwise hold.
+//!
+//! # TigerStyle Invariants
+//!
+//! - The write buffer never grows past the watermark plus one element
+//!   smaller than a chunk
+//! - The bytes written equal `RespParser::encode` of the reply
+
+use crate::redis::RespValue;
+use bytes::{BufMut, BytesMut};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Bulk payloads above this size are written in pieces of this size
+pub(crate) const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+fn digits(mut n: usize) -> usize {
+    let mut count = 1;
+    while n >= 10 {
+        n /= 10;
+        count += 1;
+    }
+    count
+}
+
+/// Size of the RESP2 encoding of `value`. Attributes and verbatim strings
+/// count as their longer RESP3 encoding, so the result bounds both.
+pub(crate) fn encoded_len(value: &RespValue) -> usize {
+    match value {
+        RespValue::SimpleString(s) | RespValue::Error(s) => 1 + s.len() + 2,
+        RespValue::Integer(n) => {
+            let sign = usize::from(*n < 0);
+            1 + sign + digits(n.unsigned_abs() as usize) + 2
+        }
+        RespValue::BulkString(None) | RespValue::Array(None) => 5,
+        RespValue::BulkString(Some(data)) => 1 + digits(data.len()) + 2 + data.len() + 2,
+        RespValue::Array(Some(elements)) => {
+            1 + digits(elements.len()) + 2 + elements.iter().map(encoded_len).sum::<usize>()
+        }
+        RespValue::Attribute(attributes, reply) => {
+            let pairs = attributes
+                .iter()
+                .map(|(key, value)| encoded_len(key) + encoded_len(value))
+                .sum::<usize>();
+            1 + digits(attributes.len()) + 2 + pairs + encoded_len(reply)
+        }
+        RespValue::Verbatim { text, .. } => 1 + digits(text.len() + 4) + 2 + text.len() + 4 + 2,
+        resp3 => encoded_len(&resp3.to_resp2()),
+    }
+}
+
+async fn flush<W: AsyncWrite + Unpin>(stream: &mut W, buf: &mut BytesMut) -> std::io::Result<()> {
+    if !buf.is_empty() {
+        stream.write_all(buf).await?;
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Write `value` through `buf` without buffering more than `high_watermark`
+/// bytes of it. `encode` writes one non-streamed element into the buffer.
+///
+/// Whatever was already buffered is written first, so replies stay in order.
+/// The tail of the reply may be left in `buf` for the caller's next flush.
+pub(crate) async fn write_streamed<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    buf: &mut BytesMut,
+    value: &RespValue,
+    high_watermark: usize,
+    encode: fn(&RespValue, &mut BytesMut),
+) -> std::io::Result<()> {
+    debug_assert!(
+        high_watermark > 0,
+        "Precondition: watermark must be positive"
+    );
+
+    // Arrays are walked with an explicit stack of element iterators
+    let mut stack = vec![std::slice::from_ref(value).iter()];
+    while let Some(elements) = stack.last_mut() {
+        let Some(element) = elements.next() else {
+            stack.pop();
+            continue;
+        };
+        match element {
+            RespValue::BulkString(Some(data)) if data.len() > STREAM_CHUNK_SIZE => {
+                buf.put_u8(b'$');
+                buf.extend_from_slice(data.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                flush(stream, buf).await?;
+                for chunk in data.chunks(STREAM_CHUNK_SIZE) {
+                    stream.write_all(chunk).await?;
+                }
+                buf.extend_from_slice(b"\r\n");
+            }
+            RespValue::Array(Some(elements)) => {
+                buf.put_u8(b'*');
+                buf.extend_from_slice(elements.len().to_string().as_bytes());
+                buf.extend_from_slice(b"\r\n");
+                stack.push(elements.iter());
+            }
+            other => encode(other, buf),
+        }
+        if buf.len() >= high_watermark {
+            flush(stream, buf).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis::RespParser;
+    use tokio::io::AsyncReadExt;
+
+    fn encode(value: &RespValue, buf: &mut BytesMut) {
+        buf.extend_from_slice(&RespParser::encode(value));
+    }
+
+    fn bulk(len: usize, fill: u8) -> RespValue {
+        RespValue::BulkString(Some(vec![fill; len]))
+    }
+
+    /// Counts bytes written and the largest single write
+    #[derive(Default)]
+    struct Recorder {
+        bytes: Vec<u8>,
+        largest_write: usize,
+    }
+
+    impl AsyncWrite for Recorder {
+        fn poll_write(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            data: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.largest_write = self.largest_write.max(data.len());
+            self.bytes.extend_from_slice(data);
+            std::task::Poll::Ready(Ok(data.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_encoded_len_matches_encoder() {
+        for value in [
+            RespValue::SimpleString("OK".into()),
+            RespValue::err("ERR nope"),
+            RespValue::Integer(0),
+            RespValue::Integer(-1234),
+            RespValue::Integer(i64::MIN),
+            RespValue::BulkString(None),
+            bulk(0, b'x'),
+            bulk(12345, b'x'),
+            RespValue::Array(None),
+            RespValue::Array(Some(vec![
+                bulk(3, b'a'),
+                RespValue::Array(Some(vec![
+                    RespValue::Integer(7),
+                    RespValue::BulkString(None),
+                ])),
+            ])),
+        ] {
+            assert_eq!(
+                encoded_len(&value),
+                RespParser::encode(&value).len(),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_bytes_match_encoding() {
+        let reply = RespValue::Array(Some(vec![
+            bulk(3 * STREAM_CHUNK_SIZE + 17, b'a'),
+            RespValue::Integer(42),
+            RespValue::Array(Some((0..500).map(|i| bulk(i, b'b')).collect())),
+            bulk(STREAM_CHUNK_SIZE, b'c'),
+        ]));
+        let mut recorder = Recorder::default();
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"+PENDING\r\n");
+        let watermark = 4096;
+
+        write_streamed(&mut recorder, &mut buf, &reply, watermark, encode)
+            .await
+            .unwrap();
+        recorder.bytes.extend_from_slice(&buf);
+
+        let mut expected = b"+PENDING\r\n".to_vec();
+        expected.extend_from_slice(&RespParser::encode(&reply));
+        assert_eq!(recorder.bytes, expected);
+        assert!(recorder.largest_write <= STREAM_CHUNK_SIZE + watermark);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_large_replies_stay_under_ceiling() {
+        // Small pipes and readers that drain slowly: every writer is held
+        // back by its reader, and none may buffer past the ceiling
+        let value_len = 4 * 1024 * 1024;
+        let watermark = 16 * 1024;
+        let ceiling = watermark + STREAM_CHUNK_SIZE;
+        let mut tasks = Vec::new();
+        for client in 0..8u8 {
+            let (mut server, mut reader) = tokio::io::duplex(8 * 1024);
+            let writer = tokio::spawn(async move {
+                let reply = bulk(value_len, client);
+                let mut buf = BytesMut::new();
+                write_streamed(&mut server, &mut buf, &reply, watermark, encode)
+                    .await
+                    .unwrap();
+                server.write_all(&buf).await.unwrap();
+                buf.capacity()
+            });
+            let drain = tokio::spawn(async move {
+                let mut received = Vec::new();
+                let mut chunk = vec![0u8; 1024];
+                loop {
+                    let n = reader.read(&mut chunk).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    received.extend_from_slice(&chunk[..n]);
+                    tokio::task::yield_now().await;
+                }
+                received
+            });
+            tasks.push((client, writer, drain));
+        }
+        for (client, writer, drain) in tasks {
+            let capacity = writer.await.unwrap();
+            assert!(
+                capacity <= ceiling,
+                "client {} buffered {} bytes",
+                client,
+                capacity
+            );
+            let received = drain.await.unwrap();
+            assert_eq!(received, RespParser::encode(&bulk(value_len, client)));
+        }
+    }
+}
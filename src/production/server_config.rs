@@ -7,11 +7,26 @@
 //! - `TLS_KEY_PATH`: Path to server private key (PEM)
 //! - `TLS_CA_PATH`: Path to CA certificate for client verification (optional)
 //! - `TLS_REQUIRE_CLIENT_CERT`: Require client certificates (default: false)
+//! - `TLS_ALPN_PROTOCOLS`: Comma-separated ALPN protocols, in preference order (default: none)
+//! - `TLS_SESSION_CACHE_SIZE`: Stateful session cache capacity, 0 disables (default: 256)
+//! - `TLS_SESSION_TICKETS`: Issue stateless session tickets (default: true)
 //!
 //! ## ACL Configuration (requires `acl` feature)
 //! - `REDIS_REQUIRE_PASS`: Simple password for AUTH (optional)
 //! - `ACL_FILE`: Path to ACL configuration file (optional)
+//! - `ACL_PASSWORD_MIN_LENGTH`: Minimum length for `>password` rules (default: 0)
+//! - `ACL_PASSWORD_MIN_CLASSES`: Minimum character classes (lower/upper/digit/symbol)
+//!   for `>password` rules, 0-4 (default: 0)
+//! - `ACL_PASSWORD_HASH`: Scheme for stored password hashes, `sha256` or `argon2id`
+//!   (default: sha256; argon2id requires the `acl-argon2` feature)
+//!
+//! ## Protocol Limits
+//! - `PROTO_MAX_BULK_LEN`: Longest bulk string a client may send (default: 512MB)
+//! - `PROTO_MAX_MULTIBULK_LEN`: Most elements in one request array (default: 2147483647)
+//! - `PROTO_MAX_INLINE_LEN`: Longest protocol line (default: 64KB)
+//! - `PROTO_MAX_NESTING`: Deepest nesting of arrays (default: 32)
 
+use crate::redis::ProtocolLimits;
 use std::path::PathBuf;
 
 /// Server security configuration
@@ -21,6 +36,8 @@ pub struct ServerConfig {
     pub tls: Option<TlsServerConfig>,
     /// ACL configuration
     pub acl: AclServerConfig,
+    /// Request sizes beyond which a connection is closed with a protocol error
+    pub protocol: ProtocolLimits,
 }
 
 /// TLS server configuration
@@ -34,6 +51,12 @@ pub struct TlsServerConfig {
     pub ca_path: Option<PathBuf>,
     /// Whether to require client certificates
     pub require_client_cert: bool,
+    /// ALPN protocols offered, in preference order
+    pub alpn_protocols: Vec<String>,
+    /// Stateful session cache capacity (0 = disabled)
+    pub session_cache_size: usize,
+    /// Whether to issue stateless session tickets
+    pub session_tickets: bool,
 }
 
 /// ACL server configuration
@@ -45,6 +68,12 @@ pub struct AclServerConfig {
     pub acl_file: Option<PathBuf>,
     /// Whether to require authentication
     pub require_auth: bool,
+    /// Minimum length for passwords set via ACL SETUSER (0 = no policy)
+    pub password_min_length: usize,
+    /// Minimum character classes for passwords set via ACL SETUSER (0 = no policy)
+    pub password_min_classes: u8,
+    /// Scheme for newly stored password hashes: "sha256" (default) or "argon2id"
+    pub password_hash: Option<String>,
 }
 
 impl ServerConfig {
@@ -52,8 +81,9 @@ impl ServerConfig {
     pub fn from_env() -> Self {
         let tls = Self::load_tls_config();
         let acl = Self::load_acl_config();
+        let protocol = Self::load_protocol_limits();
 
-        Self { tls, acl }
+        Self { tls, acl, protocol }
     }
 
     fn load_tls_config() -> Option<TlsServerConfig> {
@@ -67,6 +97,22 @@ impl ServerConfig {
             require_client_cert: std::env::var("TLS_REQUIRE_CLIENT_CERT")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(false),
+            alpn_protocols: std::env::var("TLS_ALPN_PROTOCOLS")
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|p| !p.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            session_cache_size: std::env::var("TLS_SESSION_CACHE_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(256),
+            session_tickets: std::env::var("TLS_SESSION_TICKETS")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
         })
     }
 
@@ -74,11 +120,42 @@ impl ServerConfig {
         let require_pass = std::env::var("REDIS_REQUIRE_PASS").ok();
         let acl_file = std::env::var("ACL_FILE").ok().map(PathBuf::from);
         let require_auth = require_pass.is_some() || acl_file.is_some();
+        let password_min_length = std::env::var("ACL_PASSWORD_MIN_LENGTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let password_min_classes = std::env::var("ACL_PASSWORD_MIN_CLASSES")
+            .ok()
+            .and_then(|v| v.parse::<u8>().ok())
+            .map(|v| v.min(4))
+            .unwrap_or(0);
+        let password_hash = std::env::var("ACL_PASSWORD_HASH").ok();
 
         AclServerConfig {
             require_pass,
             acl_file,
             require_auth,
+            password_min_length,
+            password_min_classes,
+            password_hash,
+        }
+    }
+
+    fn load_protocol_limits() -> ProtocolLimits {
+        let defaults = ProtocolLimits::default();
+        let limit = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|&v| v > 0)
+                .unwrap_or(default)
+        };
+
+        ProtocolLimits {
+            max_bulk_len: limit("PROTO_MAX_BULK_LEN", defaults.max_bulk_len),
+            max_multibulk_len: limit("PROTO_MAX_MULTIBULK_LEN", defaults.max_multibulk_len),
+            max_inline_len: limit("PROTO_MAX_INLINE_LEN", defaults.max_inline_len),
+            max_depth: limit("PROTO_MAX_NESTING", defaults.max_depth),
         }
     }
 
@@ -111,7 +188,11 @@ impl TlsServerConfig {
             config = config.require_client_cert(true);
         }
 
-        config.build_acceptor()
+        config
+            .with_alpn(self.alpn_protocols.iter().cloned())
+            .with_session_cache_size(self.session_cache_size)
+            .with_session_tickets(self.session_tickets)
+            .build_acceptor()
     }
 }
 
@@ -134,8 +215,12 @@ mod tests {
                 key_path: PathBuf::from("/path/to/key"),
                 ca_path: None,
                 require_client_cert: false,
+                alpn_protocols: Vec::new(),
+                session_cache_size: 256,
+                session_tickets: true,
             }),
             acl: AclServerConfig::default(),
+            protocol: ProtocolLimits::default(),
         };
         assert!(config.tls_enabled());
     }
@@ -1,11 +1,15 @@
+use super::client_registry::ClientRegistry;
 use super::connection_optimized::{ConnectionConfig, OptimizedConnectionHandler};
+use super::shutdown::{drain_clients, shutdown_trigger, ShutdownConfig};
 use super::ttl_manager::TtlManagerActor;
+use super::watchdog::{Watchdog, WatchdogConfig};
 use super::{ConnectionPool, PerformanceConfig, ServerConfig, ShardedActorState};
 use crate::observability::{DatadogConfig, Metrics};
-use crate::security::AclManager;
+use crate::security::{AclManager, TlsStats};
 use parking_lot::RwLock;
 use std::sync::Arc;
 use tokio::net::TcpListener;
+use tokio::task::JoinSet;
 use tracing::{error, info, warn};
 
 #[cfg(feature = "tls")]
@@ -56,6 +60,12 @@ impl OptimizedRedisServer {
                     if tls_config.require_client_cert {
                         info!("Mutual TLS (mTLS) enabled - client certificates required");
                     }
+                    info!(
+                        "TLS resumption: session cache {}, tickets {}; ALPN {:?}",
+                        tls_config.session_cache_size,
+                        if tls_config.session_tickets { "on" } else { "off" },
+                        tls_config.alpn_protocols
+                    );
                     Some(acceptor)
                 }
                 Err(e) => {
@@ -78,6 +88,13 @@ impl OptimizedRedisServer {
         // Initialize ACL manager
         let acl_manager = Self::create_acl_manager(&server_config);
         let acl_manager = Arc::new(RwLock::new(acl_manager));
+        let client_registry = Arc::new(ClientRegistry::new());
+
+        // Handshake counters for INFO, present only when TLS is serving
+        #[cfg(feature = "tls")]
+        let tls_stats: Option<Arc<TlsStats>> = tls_acceptor.as_ref().map(|_| Arc::new(TlsStats::new()));
+        #[cfg(not(feature = "tls"))]
+        let tls_stats: Option<Arc<TlsStats>> = None;
 
         let state = ShardedActorState::with_perf_config(&perf_config);
         let connection_pool = Arc::new(ConnectionPool::new(
@@ -86,8 +103,10 @@ impl OptimizedRedisServer {
         ));
 
         // Create connection config from performance config
-        let conn_config =
-            ConnectionConfig::from_perf_config(&perf_config.buffers, &perf_config.batching);
+        let conn_config = ConnectionConfig {
+            protocol_limits: server_config.protocol,
+            ..ConnectionConfig::from_perf_config(&perf_config.buffers, &perf_config.batching)
+        };
 
         // Initialize metrics
         let dd_config = DatadogConfig::from_env();
@@ -102,11 +121,35 @@ impl OptimizedRedisServer {
         let _ttl_handle = TtlManagerActor::spawn(state.clone(), metrics.clone());
         info!("TTL manager started (100ms interval)");
 
+        // Dropped when run() returns, which stops the watchdog thread
+        let _watchdog = WatchdogConfig::from_env().period.and_then(|period| {
+            Watchdog::new(period, state.shard_progress())
+                .spawn()
+                .map_err(|e| error!("Failed to start watchdog: {}", e))
+                .ok()
+        });
+
         let listener = TcpListener::bind(&self.addr).await?;
         info!("Redis server listening on {}", self.addr);
 
+        let shutdown_config = ShutdownConfig::from_env();
+        let mut connections = JoinSet::new();
+        let trigger = shutdown_trigger(&client_registry);
+        tokio::pin!(trigger);
+
         loop {
-            match listener.accept().await {
+            // Reap finished connection tasks so the set stays bounded
+            while connections.try_join_next().is_some() {}
+
+            let accepted = tokio::select! {
+                result = listener.accept() => result,
+                cause = &mut trigger => {
+                    info!("{} received, shutting down", cause);
+                    break;
+                }
+            };
+
+            match accepted {
                 Ok((stream, addr)) => {
                     let client_addr = addr.to_string();
                     let state_clone = state.clone();
@@ -114,6 +157,8 @@ impl OptimizedRedisServer {
                     let metrics_clone = metrics.clone();
                     let conn_config_clone = conn_config.clone();
                     let acl_manager_clone = acl_manager.clone();
+                    let client_registry_clone = client_registry.clone();
+                    let tls_stats_clone = tls_stats.clone();
 
                     // Set TCP_NODELAY for lower latency before any wrapping
                     if let Err(e) = stream.set_nodelay(true) {
@@ -123,7 +168,7 @@ impl OptimizedRedisServer {
                     #[cfg(feature = "tls")]
                     let tls_acceptor_clone = tls_acceptor.clone();
 
-                    tokio::spawn(async move {
+                    connections.spawn(async move {
                         // TigerStyle: Handle Result instead of unwrap
                         let _permit = match pool.acquire_permit().await {
                             Ok(permit) => permit,
@@ -138,12 +183,23 @@ impl OptimizedRedisServer {
                         let (stream, client_cert_cn) = if let Some(acceptor) = tls_acceptor_clone {
                             match acceptor.accept(stream).await {
                                 Ok(tls_stream) => {
+                                    if let Some(stats) = &tls_stats_clone {
+                                        use tokio_rustls::rustls::HandshakeKind;
+                                        let conn = tls_stream.get_ref().1;
+                                        stats.record_handshake(
+                                            conn.handshake_kind() == Some(HandshakeKind::Resumed),
+                                            conn.alpn_protocol().is_some(),
+                                        );
+                                    }
                                     let stream = MaybeSecureStream::tls(tls_stream);
                                     // Extract client certificate CN for authentication
                                     let cn = stream.peer_certificate_cn();
                                     (stream, cn)
                                 }
                                 Err(e) => {
+                                    if let Some(stats) = &tls_stats_clone {
+                                        stats.record_failure();
+                                    }
                                     warn!("TLS handshake failed for {}: {}", client_addr, e);
                                     return;
                                 }
@@ -164,6 +220,8 @@ impl OptimizedRedisServer {
                             conn_config_clone,
                             acl_manager_clone,
                             client_cert_cn,
+                            client_registry_clone,
+                            tls_stats_clone,
                         );
                         handler.run().await;
                     });
@@ -173,6 +231,16 @@ impl OptimizedRedisServer {
                 }
             }
         }
+
+        // Stop accepting, then let clients finish what they have sent
+        drop(listener);
+        let report =
+            drain_clients(&client_registry, &mut connections, shutdown_config.drain_timeout).await;
+        info!(
+            "Client drain complete: {} drained, {} aborted",
+            report.drained, report.aborted
+        );
+        Ok(())
     }
 
     /// Create and configure ACL manager based on server configuration
@@ -216,6 +284,27 @@ impl OptimizedRedisServer {
                 info!("ACL authentication disabled (set REDIS_REQUIRE_PASS to enable)");
             }
 
+            let policy = crate::security::password::PasswordPolicy::new(
+                config.acl.password_min_length,
+                config.acl.password_min_classes,
+            );
+            if !policy.is_permissive() {
+                info!(
+                    "ACL password policy: min length {}, min character classes {}",
+                    policy.min_length, policy.min_classes
+                );
+            }
+            manager.set_password_policy(policy);
+
+            if let Some(name) = &config.acl.password_hash {
+                use crate::security::acl::PasswordScheme;
+                match PasswordScheme::from_name(name).map(|scheme| manager.set_hash_scheme(scheme)) {
+                    Some(Ok(())) => info!("ACL password hash scheme: {}", manager.hash_scheme().name()),
+                    Some(Err(e)) => error!("{}; keeping sha256", e),
+                    None => error!("Unknown ACL_PASSWORD_HASH '{}'; keeping sha256", name),
+                }
+            }
+
             manager
         }
 
@@ -0,0 +1,234 @@
+//! Key-to-shard routing and the multi-shard concurrency model
+//!
+//! `ShardRouter` is the one place a key is mapped to a shard. The sharded
+//! and replicated states both route through it, so a key reaches the same
+//! shard whichever path (fast GET/SET, batch pipeline, `Command`) it takes.
+//! A key routes by its bytes; `shard_for(&str)` and `shard_for_bytes` agree.
+//!
+//! ## Concurrency model
+//!
+//! 1. **One owner per executor.** Each shard's `CommandExecutor` is owned
+//!    by its actor task (`ShardActor`, `ReplicatedShardActor`) and is only
+//!    reached by message passing. There are no locks around keyspace data;
+//!    `ShardOwner` asserts in debug builds that every message is handled on
+//!    the owning task.
+//! 2. **Single-key commands touch one shard** and are atomic, because the
+//!    owner handles one message at a time.
+//! 3. **Fan-out commands touch several shards, each atomically, but not
+//!    all together.** MGET, MSET, multi-key DEL/UNLINK, EXISTS, TOUCH,
+//!    multi-key BLPOP/BRPOP/BZPOPMIN/BZPOPMAX, XREAD, KEYS, SCAN, DBSIZE,
+//!    FLUSHDB/FLUSHALL, MEMORY STATS, CONFIG SET and DEBUG RELOAD send one
+//!    message per shard. Another client can observe some shards before and
+//!    others after.
+//! 4. **Multi-key atomic commands assume co-located keys.** RENAME, COPY,
+//!    SMOVE, LMOVE, the *STORE commands, EVAL/EVALSHA and MULTI/EXEC run on
+//!    the shard of their first key. They only see the other keys when those
+//!    route to the same shard; nothing rejects them otherwise (no CROSSSLOT
+//!    check yet), so callers needing them should run a single shard.
+//! 5. **Shared, server-wide structures** (script cache, Pub/Sub, blocked
+//!    clients, tracking, latency, watchdog progress) are the only state
+//!    more than one task touches. Each guards itself with a lock or atomics
+//!    and never calls back into an executor while holding it. The script
+//!    cache and `ShardProgress` are model-checked with loom
+//!    (`RUSTFLAGS="--cfg redis_sim_loom" cargo test --release --test loom_test`).
+//!
+//! The router is fixed when the state is built: auto-scaling does not move
+//! keys between shards.
+
+use std::hash::{Hash, Hasher};
+
+// P4 optimization: Use AHash for faster shard routing
+#[cfg(feature = "opt-fxhash-routing")]
+use ahash::AHasher;
+
+#[cfg(not(feature = "opt-fxhash-routing"))]
+use std::collections::hash_map::DefaultHasher;
+
+/// Maps keys to one of a fixed number of shards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardRouter {
+    num_shards: usize,
+}
+
+impl ShardRouter {
+    pub const fn new(num_shards: usize) -> Self {
+        assert!(num_shards > 0, "Precondition: num_shards must be positive");
+        ShardRouter { num_shards }
+    }
+
+    #[inline]
+    pub fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
+    /// Shard owning `key`
+    #[inline]
+    pub fn shard_for(&self, key: &str) -> usize {
+        self.shard_for_bytes(key.as_bytes())
+    }
+
+    /// Shard owning `key`, without UTF-8 validation
+    /// P4 optimization: Use AHash when opt-fxhash-routing is enabled
+    #[inline]
+    pub fn shard_for_bytes(&self, key: &[u8]) -> usize {
+        #[cfg(feature = "opt-fxhash-routing")]
+        let mut hasher = AHasher::default();
+
+        #[cfg(not(feature = "opt-fxhash-routing"))]
+        let mut hasher = DefaultHasher::new();
+
+        key.hash(&mut hasher);
+        let idx = (hasher.finish() as usize) % self.num_shards;
+        debug_assert!(
+            idx < self.num_shards,
+            "Postcondition violated: hash produced invalid shard index"
+        );
+        idx
+    }
+
+    /// The shard all of `keys` route to, or None when they span several
+    /// (or there are none)
+    pub fn colocated<'a, I>(&self, keys: I) -> Option<usize>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut keys = keys.into_iter();
+        let shard = self.shard_for(keys.next()?);
+        keys.all(|key| self.shard_for(key) == shard)
+            .then_some(shard)
+    }
+}
+
+/// Debug-build check that a shard executor is only used by its owning task
+///
+/// The first message binds the owner to the current tokio task; any later
+/// message handled on another task is a bug (rule 1 above). Outside a
+/// task, and in release builds, it checks nothing.
+#[derive(Debug, Default)]
+pub(crate) struct ShardOwner {
+    #[cfg(debug_assertions)]
+    task: Option<tokio::task::Id>,
+}
+
+impl ShardOwner {
+    #[inline]
+    pub(crate) fn assert_owned(&mut self, shard_id: usize) {
+        #[cfg(debug_assertions)]
+        if let Some(current) = tokio::task::try_id() {
+            let owner = *self.task.get_or_insert(current);
+            debug_assert!(
+                owner == current,
+                "Shard {} executor used from task {} but owned by task {}",
+                shard_id,
+                current,
+                owner
+            );
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = shard_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_str_and_bytes_route_alike() {
+        let router = ShardRouter::new(8);
+        for i in 0..1000 {
+            let key = format!("key:{}", i);
+            assert_eq!(
+                router.shard_for(&key),
+                router.shard_for_bytes(key.as_bytes())
+            );
+            assert!(router.shard_for(&key) < 8);
+        }
+    }
+
+    #[test]
+    fn test_keys_spread_over_every_shard() {
+        let router = ShardRouter::new(16);
+        let mut hits = [0usize; 16];
+        for i in 0..1600 {
+            hits[router.shard_for(&format!("k{}", i))] += 1;
+        }
+        assert!(hits.iter().all(|&n| n > 0), "{:?}", hits);
+    }
+
+    #[test]
+    fn test_colocated() {
+        let router = ShardRouter::new(4);
+        assert_eq!(router.colocated([]), None);
+        assert_eq!(router.colocated(["a", "a"]), Some(router.shard_for("a")));
+
+        let other = (0..)
+            .map(|i| format!("b{}", i))
+            .find(|key| router.shard_for(key) != router.shard_for("a"))
+            .unwrap();
+        assert_eq!(router.colocated(["a", other.as_str()]), None);
+        assert_eq!(
+            ShardRouter::new(1).colocated(["a", other.as_str()]),
+            Some(0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fast_path_and_commands_reach_the_same_shard() {
+        use crate::production::ShardedActorState;
+        use crate::redis::{Command, RespValue};
+        use bytes::Bytes;
+
+        let state = ShardedActorState::with_shards(8);
+        for i in 0..64 {
+            let key = format!("k{}", i);
+            state
+                .pooled_fast_set(Bytes::from(key.clone()), Bytes::from_static(b"v"))
+                .await;
+            assert_eq!(
+                state.execute(&Command::Get(key)).await,
+                RespValue::BulkString(Some(b"v".to_vec()))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_owner_binds_to_the_first_task() {
+        let owner = tokio::spawn(async {
+            let mut owner = ShardOwner::default();
+            owner.assert_owned(0);
+            owner.assert_owned(0);
+            owner
+        })
+        .await
+        .unwrap();
+        #[cfg(debug_assertions)]
+        assert!(owner.task.is_some(), "a task must record itself as owner");
+
+        // Outside any task there is nothing to check
+        std::thread::spawn(move || {
+            let mut owner = owner;
+            owner.assert_owned(0);
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn test_owner_rejects_another_task() {
+        let owner = tokio::spawn(async {
+            let mut owner = ShardOwner::default();
+            owner.assert_owned(3);
+            owner
+        })
+        .await
+        .unwrap();
+        let moved = tokio::spawn(async move {
+            let mut owner = owner;
+            owner.assert_owned(3)
+        })
+        .await;
+        assert!(moved.is_err(), "a second task must trip the owner check");
+    }
+}
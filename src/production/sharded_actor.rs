@@ -1,21 +1,23 @@
 use crate::io::{ProductionTimeSource, TimeSource};
-use crate::redis::{Command, CommandExecutor, RespValue};
+use crate::redis::latency::command_event;
+use crate::redis::{
+    BlockingManager, ClientTracking, Command, CommandExecutor, ExpiryOutlook, FunctionLibraries,
+    KeyStatsReport, LatencyMonitor, MemoryStats, PubSubManager, RespValue, ScriptMonitor,
+};
+use crate::release::{info_server_fields, ServerMode};
 use crate::simulator::VirtualTime;
-use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{mpsc, oneshot};
 
-// P4 optimization: Use AHash for faster shard routing
-#[cfg(feature = "opt-fxhash-routing")]
-use ahash::AHasher;
-
-#[cfg(not(feature = "opt-fxhash-routing"))]
-use std::collections::hash_map::DefaultHasher;
-
 use super::adaptive_actor::{AdaptiveActor, AdaptiveActorConfig, AdaptiveActorHandle};
 use super::load_balancer::ScalingDecision;
 use super::perf_config::PerformanceConfig;
+use super::rebalance::{RebalancePlan, ShardLoad};
 use super::response_pool::{response_future, ResponsePool, ResponseSlot};
+use super::shard_router::{ShardOwner, ShardRouter};
+use super::transaction_coordinator::{shards_to_hold, WatchGuard, WatchStamp, WatchTable};
+use super::watchdog::ShardProgress;
 
 /// Configuration for dynamic sharding behavior
 #[derive(Clone, Debug)]
@@ -85,6 +87,19 @@ pub enum ShardMessage {
         virtual_time: VirtualTime,
         response_tx: oneshot::Sender<usize>,
     },
+    /// Keys expiring within each expiry horizon, for the TTL gauges
+    ExpiryOutlook {
+        virtual_time: VirtualTime,
+        response_tx: oneshot::Sender<ExpiryOutlook>,
+    },
+    /// One chunk of a chunked KEYS, answered with the next cursor
+    KeysChunk {
+        pattern: String,
+        cursor: u64,
+        chunk_size: usize,
+        virtual_time: VirtualTime,
+        response_tx: oneshot::Sender<(u64, Vec<RespValue>)>,
+    },
     /// Fast path for GET - avoids Command enum overhead
     FastGet {
         key: bytes::Bytes,
@@ -117,12 +132,81 @@ pub enum ShardMessage {
         value: bytes::Bytes,
         response_slot: Arc<ResponseSlot<RespValue>>,
     },
+    /// Register a WATCH on a key, answered with its stamp
+    Watch {
+        key: String,
+        virtual_time: VirtualTime,
+        response_tx: oneshot::Sender<WatchStamp>,
+    },
+    /// Release one WATCH on a key (sent when its guard drops)
+    Unwatch { key: String },
+    /// Serve only `rx` until every sender of it is dropped, so an EXEC runs
+    /// with no other client's command in between; acknowledged once held
+    Hold {
+        rx: mpsc::UnboundedReceiver<ShardMessage>,
+        held_tx: oneshot::Sender<()>,
+    },
+    /// Whether every stamp is still current, checked by a holding EXEC
+    CheckWatches {
+        stamps: Vec<(String, WatchStamp)>,
+        virtual_time: VirtualTime,
+        response_tx: oneshot::Sender<bool>,
+    },
+}
+
+impl ShardMessage {
+    /// Command name reported by the watchdog
+    fn command_name(&self) -> &'static str {
+        match self {
+            ShardMessage::Command { cmd, .. } | ShardMessage::BatchCommand { cmd, .. } => {
+                cmd.name()
+            }
+            ShardMessage::EvictExpired { .. } => "(expire cycle)",
+            ShardMessage::ExpiryOutlook { .. } => "(expiry outlook)",
+            ShardMessage::KeysChunk { .. } => "KEYS (chunk)",
+            ShardMessage::FastGet { .. } | ShardMessage::PooledFastGet { .. } => "GET",
+            ShardMessage::FastSet { .. } | ShardMessage::PooledFastSet { .. } => "SET",
+            ShardMessage::FastBatchGet { .. } => "GET (pipelined batch)",
+            ShardMessage::FastBatchSet { .. } => "SET (pipelined batch)",
+            ShardMessage::Watch { .. } => "WATCH",
+            ShardMessage::Unwatch { .. } => "UNWATCH",
+            ShardMessage::Hold { .. } => "EXEC (hold)",
+            ShardMessage::CheckWatches { .. } => "EXEC (watch check)",
+        }
+    }
+
+    /// Latency monitor event the message is timed as, if any
+    fn latency_event(&self) -> Option<&'static str> {
+        match self {
+            ShardMessage::Command { cmd, .. } | ShardMessage::BatchCommand { cmd, .. } => {
+                Some(command_event(cmd))
+            }
+            ShardMessage::EvictExpired { .. } => Some("expire-cycle"),
+            ShardMessage::FastGet { .. }
+            | ShardMessage::FastSet { .. }
+            | ShardMessage::FastBatchGet { .. }
+            | ShardMessage::FastBatchSet { .. }
+            | ShardMessage::PooledFastGet { .. }
+            | ShardMessage::PooledFastSet { .. } => Some("fast-command"),
+            ShardMessage::ExpiryOutlook { .. }
+            | ShardMessage::KeysChunk { .. }
+            | ShardMessage::Watch { .. }
+            | ShardMessage::Unwatch { .. }
+            | ShardMessage::Hold { .. }
+            | ShardMessage::CheckWatches { .. } => None,
+        }
+    }
 }
 
 pub struct ShardActor {
     executor: CommandExecutor,
     rx: mpsc::UnboundedReceiver<ShardMessage>,
-    #[allow(dead_code)]
+    /// Current message and processed count, sampled by the watchdog
+    progress: Arc<ShardProgress>,
+    /// Debug check that only this actor's task touches `executor`
+    owner: ShardOwner,
+    /// Keys connections WATCH on this shard, with their versions
+    watches: WatchTable,
     shard_id: usize,
     #[allow(dead_code)]
     num_shards: usize,
@@ -148,14 +232,24 @@ impl ShardActor {
         ShardActor {
             executor,
             rx,
+            progress: Arc::new(ShardProgress::new()),
+            owner: ShardOwner::default(),
+            watches: WatchTable::default(),
             shard_id,
             num_shards,
         }
     }
 
-    /// Create a new ShardActor with a shared script cache
+    /// Create a new ShardActor with a shared script cache, Pub/Sub registry,
+    /// blocked-client registry and latency monitor
     ///
-    /// This allows all shards to share a single script cache for multi-shard Lua support.
+    /// This allows all shards to share a single script cache for multi-shard Lua support,
+    /// lets PUBLISH reach subscribers no matter which shard executes it,
+    /// lets a push on any shard wake clients blocked on that key, lets a
+    /// write on any shard invalidate the key for tracking clients, lets
+    /// LATENCY on any shard see every shard's spikes, and lets FCALL on any
+    /// shard call a library loaded through another.
+    #[allow(clippy::too_many_arguments)]
     fn new_with_shared_scripts(
         rx: mpsc::UnboundedReceiver<ShardMessage>,
         simulation_start_epoch: i64,
@@ -163,6 +257,12 @@ impl ShardActor {
         shard_id: usize,
         num_shards: usize,
         shared_script_cache: crate::redis::lua::SharedScriptCache,
+        pubsub: PubSubManager,
+        blocking: BlockingManager,
+        tracking: ClientTracking,
+        latency: LatencyMonitor,
+        scripts: ScriptMonitor,
+        functions: FunctionLibraries,
     ) -> Self {
         debug_assert!(
             shard_id < num_shards,
@@ -171,11 +271,20 @@ impl ShardActor {
             num_shards
         );
         let mut executor = CommandExecutor::with_shared_script_cache(shared_script_cache);
+        executor.set_pubsub(pubsub);
+        executor.set_blocking(blocking);
+        executor.set_client_tracking(tracking);
+        executor.set_latency_monitor(latency);
+        executor.set_script_monitor(scripts);
+        executor.set_function_libraries(functions);
         executor.set_simulation_start_epoch(simulation_start_epoch);
         executor.set_simulation_start_epoch_ms(start_millis as i64);
         ShardActor {
             executor,
             rx,
+            progress: Arc::new(ShardProgress::new()),
+            owner: ShardOwner::default(),
+            watches: WatchTable::default(),
             shard_id,
             num_shards,
         }
@@ -184,79 +293,169 @@ impl ShardActor {
     async fn run(mut self) {
         while let Some(msg) = self.rx.recv().await {
             match msg {
-                ShardMessage::Command {
-                    cmd,
-                    virtual_time,
-                    response_tx,
-                } => {
-                    self.executor.set_time(virtual_time);
-                    let response = self.executor.execute(&cmd);
-                    let _ = response_tx.send(response);
-                }
-                ShardMessage::BatchCommand { cmd, virtual_time } => {
-                    // Fire-and-forget: execute without sending response
-                    self.executor.set_time(virtual_time);
-                    let _ = self.executor.execute(&cmd);
-                }
-                ShardMessage::EvictExpired {
-                    virtual_time,
-                    response_tx,
-                } => {
-                    let evicted = self.executor.evict_expired_direct(virtual_time);
-                    let _ = response_tx.send(evicted);
-                }
-                ShardMessage::FastGet { key, response_tx } => {
-                    // Fast path: direct GET without Command enum overhead
-                    let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
-                    let response = self.executor.get_direct(key_str);
-                    let _ = response_tx.send(response);
-                }
-                ShardMessage::FastSet {
-                    key,
-                    value,
-                    response_tx,
-                } => {
-                    // Fast path: direct SET without Command enum overhead
-                    let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
-                    let response = self.executor.set_direct(key_str, &value);
-                    let _ = response_tx.send(response);
+                ShardMessage::Hold { rx, held_tx } => self.serve_hold(rx, held_tx).await,
+                msg => {
+                    let queued = self.rx.len();
+                    self.handle(msg, queued);
                 }
-                ShardMessage::FastBatchGet { keys, response_tx } => {
-                    // Batch GET: process multiple keys in single message
-                    let mut results = Vec::with_capacity(keys.len());
-                    for key in keys {
-                        let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
-                        results.push(self.executor.get_direct(key_str));
-                    }
-                    let _ = response_tx.send(results);
-                }
-                ShardMessage::FastBatchSet { pairs, response_tx } => {
-                    // Batch SET: process multiple key-value pairs in single message
-                    let mut results = Vec::with_capacity(pairs.len());
-                    for (key, value) in pairs {
-                        let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
-                        results.push(self.executor.set_direct(key_str, &value));
-                    }
-                    let _ = response_tx.send(results);
+            }
+        }
+    }
+
+    /// Serve one EXEC's messages and nothing else, until EXEC drops its
+    /// end of `rx`
+    async fn serve_hold(
+        &mut self,
+        mut rx: mpsc::UnboundedReceiver<ShardMessage>,
+        held_tx: oneshot::Sender<()>,
+    ) {
+        // EXEC gave up waiting (its connection went away): nothing to serve
+        if held_tx.send(()).is_err() {
+            return;
+        }
+        while let Some(msg) = rx.recv().await {
+            let queued = rx.len();
+            self.handle(msg, queued);
+        }
+    }
+
+    fn handle(&mut self, msg: ShardMessage, queued: usize) {
+        self.owner.assert_owned(self.shard_id);
+        self.progress.begin(msg.command_name(), queued);
+        let latency_event = msg.latency_event();
+        let start = Instant::now();
+        match msg {
+            ShardMessage::Command {
+                cmd,
+                virtual_time,
+                response_tx,
+            } => {
+                self.executor.set_time(virtual_time);
+                let response = self.executor.execute(&cmd);
+                self.watches.touch_written(&cmd);
+                let _ = response_tx.send(response);
+            }
+            ShardMessage::BatchCommand { cmd, virtual_time } => {
+                // Fire-and-forget: execute without sending response
+                self.executor.set_time(virtual_time);
+                let _ = self.executor.execute(&cmd);
+                self.watches.touch_written(&cmd);
+            }
+            ShardMessage::EvictExpired {
+                virtual_time,
+                response_tx,
+            } => {
+                let evicted = self.executor.evict_expired_direct(virtual_time);
+                // Free what UNLINK queued since the last cycle, off the shard's task
+                let detached = self.executor.take_lazyfree_queue();
+                if !detached.is_empty() {
+                    tokio::task::spawn_blocking(move || drop(detached));
                 }
-                ShardMessage::PooledFastGet { key, response_slot } => {
-                    // Pooled fast GET: uses response slot instead of oneshot
+                let _ = response_tx.send(evicted);
+            }
+            ShardMessage::ExpiryOutlook {
+                virtual_time,
+                response_tx,
+            } => {
+                let _ = response_tx.send(self.executor.expiry_outlook(virtual_time));
+            }
+            ShardMessage::KeysChunk {
+                pattern,
+                cursor,
+                chunk_size,
+                virtual_time,
+                response_tx,
+            } => {
+                self.executor.set_time(virtual_time);
+                let chunk = self.executor.keys_chunk(&pattern, cursor, chunk_size);
+                let _ = response_tx.send(chunk);
+            }
+            ShardMessage::FastGet { key, response_tx } => {
+                // Fast path: direct GET without Command enum overhead
+                let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
+                let response = self.executor.get_direct(key_str);
+                let _ = response_tx.send(response);
+            }
+            ShardMessage::FastSet {
+                key,
+                value,
+                response_tx,
+            } => {
+                // Fast path: direct SET without Command enum overhead
+                let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
+                let response = self.executor.set_direct(key_str, &value);
+                self.watches.touch(key_str);
+                let _ = response_tx.send(response);
+            }
+            ShardMessage::FastBatchGet { keys, response_tx } => {
+                // Batch GET: process multiple keys in single message
+                let mut results = Vec::with_capacity(keys.len());
+                for key in keys {
                     let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
-                    let response = self.executor.get_direct(key_str);
-                    response_slot.send(response);
+                    results.push(self.executor.get_direct(key_str));
                 }
-                ShardMessage::PooledFastSet {
-                    key,
-                    value,
-                    response_slot,
-                } => {
-                    // Pooled fast SET: uses response slot instead of oneshot
+                let _ = response_tx.send(results);
+            }
+            ShardMessage::FastBatchSet { pairs, response_tx } => {
+                // Batch SET: process multiple key-value pairs in single message
+                let mut results = Vec::with_capacity(pairs.len());
+                for (key, value) in pairs {
                     let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
-                    let response = self.executor.set_direct(key_str, &value);
-                    response_slot.send(response);
+                    results.push(self.executor.set_direct(key_str, &value));
+                    self.watches.touch(key_str);
                 }
+                let _ = response_tx.send(results);
+            }
+            ShardMessage::PooledFastGet { key, response_slot } => {
+                // Pooled fast GET: uses response slot instead of oneshot
+                let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
+                let response = self.executor.get_direct(key_str);
+                response_slot.send(response);
             }
+            ShardMessage::PooledFastSet {
+                key,
+                value,
+                response_slot,
+            } => {
+                // Pooled fast SET: uses response slot instead of oneshot
+                let key_str = unsafe { std::str::from_utf8_unchecked(&key) };
+                let response = self.executor.set_direct(key_str, &value);
+                self.watches.touch(key_str);
+                response_slot.send(response);
+            }
+            ShardMessage::Watch {
+                key,
+                virtual_time,
+                response_tx,
+            } => {
+                self.executor.set_time(virtual_time);
+                let existed = self.executor.get_value(&key).is_some();
+                let version = self.watches.register(&key);
+                let _ = response_tx.send(WatchStamp { version, existed });
+            }
+            ShardMessage::Unwatch { key } => self.watches.release(&key),
+            ShardMessage::Hold { .. } => {
+                debug_assert!(false, "Precondition: a held shard is not held again");
+            }
+            ShardMessage::CheckWatches {
+                stamps,
+                virtual_time,
+                response_tx,
+            } => {
+                self.executor.set_time(virtual_time);
+                // Expired or evicted since WATCH counts as a change too
+                let current = stamps.iter().all(|(key, stamp)| {
+                    self.watches.version(key) == Some(stamp.version)
+                        && (!stamp.existed || self.executor.get_value(key).is_some())
+                });
+                let _ = response_tx.send(current);
+            }
+        }
+        if let Some(event) = latency_event {
+            self.executor
+                .record_latency(event, start.elapsed().as_millis() as u64);
         }
+        self.progress.end();
     }
 }
 
@@ -266,6 +465,7 @@ pub struct ShardHandle {
     shard_id: usize,
     /// Response pool for reducing channel allocation overhead
     response_pool: Arc<ResponsePool<RespValue>>,
+    progress: Arc<ShardProgress>,
 }
 
 impl ShardHandle {
@@ -289,6 +489,41 @@ impl ShardHandle {
         })
     }
 
+    /// Run KEYS on this shard one chunk per message, so commands queued
+    /// behind it run between chunks instead of waiting for the whole scan
+    async fn keys_chunked(
+        &self,
+        pattern: &str,
+        chunk_size: usize,
+        virtual_time: VirtualTime,
+    ) -> Vec<RespValue> {
+        let mut keys = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (response_tx, response_rx) = oneshot::channel();
+            let msg = ShardMessage::KeysChunk {
+                pattern: pattern.to_string(),
+                cursor,
+                chunk_size,
+                virtual_time,
+                response_tx,
+            };
+            if self.tx.send(msg).is_err() {
+                debug_assert!(false, "Shard {} channel closed unexpectedly", self.shard_id);
+                return keys;
+            }
+            let Ok((next, chunk)) = response_rx.await else {
+                debug_assert!(false, "Shard {} response channel dropped", self.shard_id);
+                return keys;
+            };
+            keys.extend(chunk);
+            if next == 0 {
+                return keys;
+            }
+            cursor = next;
+        }
+    }
+
     /// Fire-and-forget execution - no response channel allocation
     #[inline]
     #[allow(dead_code)]
@@ -420,42 +655,81 @@ impl ShardHandle {
 
         response_rx.await.unwrap_or(0)
     }
-}
 
-/// Hash key string to shard index
-/// P4 optimization: Use AHash when opt-fxhash-routing is enabled
-#[inline]
-fn hash_key(key: &str, num_shards: usize) -> usize {
-    debug_assert!(num_shards > 0, "num_shards must be positive");
-
-    #[cfg(feature = "opt-fxhash-routing")]
-    let mut hasher = AHasher::default();
-
-    #[cfg(not(feature = "opt-fxhash-routing"))]
-    let mut hasher = DefaultHasher::new();
+    async fn expiry_outlook(&self, virtual_time: VirtualTime) -> ExpiryOutlook {
+        let (response_tx, response_rx) = oneshot::channel();
+        let msg = ShardMessage::ExpiryOutlook {
+            virtual_time,
+            response_tx,
+        };
 
-    key.hash(&mut hasher);
-    let idx = (hasher.finish() as usize) % num_shards;
-    debug_assert!(idx < num_shards, "Hash produced invalid shard index");
-    idx
-}
+        if self.tx.send(msg).is_err() {
+            return ExpiryOutlook::default();
+        }
 
-/// Fast path: hash key bytes directly without UTF-8 validation overhead
-/// P4 optimization: Use AHash when opt-fxhash-routing is enabled
-#[inline]
-fn hash_key_bytes(key: &[u8], num_shards: usize) -> usize {
-    debug_assert!(num_shards > 0, "num_shards must be positive");
+        response_rx.await.unwrap_or_default()
+    }
 
-    #[cfg(feature = "opt-fxhash-routing")]
-    let mut hasher = AHasher::default();
+    /// Register a WATCH on `key`, which this shard owns
+    async fn watch(&self, key: String, virtual_time: VirtualTime) -> WatchGuard {
+        let (response_tx, response_rx) = oneshot::channel();
+        let msg = ShardMessage::Watch {
+            key: key.clone(),
+            virtual_time,
+            response_tx,
+        };
+        // Version 0 is never handed out, so a failed WATCH aborts EXEC
+        let stamp = if self.tx.send(msg).is_ok() {
+            response_rx.await.unwrap_or(WatchStamp {
+                version: 0,
+                existed: false,
+            })
+        } else {
+            debug_assert!(false, "Shard {} channel closed unexpectedly", self.shard_id);
+            WatchStamp {
+                version: 0,
+                existed: false,
+            }
+        };
+        WatchGuard::new(key, self.shard_id, stamp, self.tx.clone())
+    }
 
-    #[cfg(not(feature = "opt-fxhash-routing"))]
-    let mut hasher = DefaultHasher::new();
+    /// Wait until this shard serves only the returned handle. The shard
+    /// resumes its own mailbox once every clone of the handle is dropped.
+    async fn hold(&self) -> ShardHandle {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (held_tx, held_rx) = oneshot::channel();
+        let sent = self.tx.send(ShardMessage::Hold { rx, held_tx }).is_ok();
+        if !sent || held_rx.await.is_err() {
+            debug_assert!(false, "Shard {} channel closed unexpectedly", self.shard_id);
+            return self.clone();
+        }
+        ShardHandle {
+            tx,
+            shard_id: self.shard_id,
+            response_pool: self.response_pool.clone(),
+            progress: self.progress.clone(),
+        }
+    }
 
-    key.hash(&mut hasher);
-    let idx = (hasher.finish() as usize) % num_shards;
-    debug_assert!(idx < num_shards, "Hash produced invalid shard index");
-    idx
+    /// Whether every stamp is still current on this (held) shard
+    async fn check_watches(
+        &self,
+        stamps: Vec<(String, WatchStamp)>,
+        virtual_time: VirtualTime,
+    ) -> bool {
+        let (response_tx, response_rx) = oneshot::channel();
+        let msg = ShardMessage::CheckWatches {
+            stamps,
+            virtual_time,
+            response_tx,
+        };
+        if self.tx.send(msg).is_err() {
+            debug_assert!(false, "Shard {} channel closed unexpectedly", self.shard_id);
+            return false;
+        }
+        response_rx.await.unwrap_or(false)
+    }
 }
 
 /// Pool configuration defaults (used when no PerformanceConfig provided)
@@ -470,7 +744,8 @@ const DEFAULT_RESPONSE_POOL_PREWARM: usize = 64;
 #[derive(Clone)]
 pub struct ShardedActorState<T: TimeSource = ProductionTimeSource> {
     shards: Arc<Vec<ShardHandle>>,
-    num_shards: usize,
+    /// Key-to-shard mapping, fixed for the life of the state
+    router: ShardRouter,
     /// Timestamp (in millis) when this state was created
     start_millis: u64,
     /// Time source for getting current time
@@ -483,6 +758,18 @@ pub struct ShardedActorState<T: TimeSource = ProductionTimeSource> {
     /// Shared script cache for Lua scripts (allows SCRIPT LOAD to work across all shards)
     #[allow(dead_code)]
     shared_script_cache: crate::redis::lua::SharedScriptCache,
+    /// Server-wide Pub/Sub registry, shared by every shard and connection
+    pubsub: PubSubManager,
+    /// Clients blocked in BLPOP/BRPOP/BLMOVE, shared by every shard and connection
+    blocking: BlockingManager,
+    /// CLIENT TRACKING table, shared by every shard and connection
+    tracking: ClientTracking,
+    /// Latency spikes, shared by every shard
+    latency: LatencyMonitor,
+    /// Running scripts, shared by every shard and connection
+    scripts: ScriptMonitor,
+    /// Function libraries, shared by every shard
+    functions: FunctionLibraries,
 }
 
 /// Production-specific constructors (use ProductionTimeSource)
@@ -539,6 +826,12 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         // Create shared script cache for all shards (enables multi-shard Lua support)
         let shared_script_cache = crate::redis::lua::SharedScriptCache::new();
+        let pubsub = PubSubManager::new();
+        let blocking = BlockingManager::new();
+        let tracking = ClientTracking::new();
+        let latency = LatencyMonitor::new();
+        let scripts = ScriptMonitor::new();
+        let functions = FunctionLibraries::new();
 
         let shards: Vec<ShardHandle> = (0..num_shards)
             .map(|shard_id| {
@@ -551,12 +844,20 @@ impl<T: TimeSource> ShardedActorState<T> {
                     shard_id,
                     num_shards,
                     shared_script_cache.clone(),
+                    pubsub.clone(),
+                    blocking.clone(),
+                    tracking.clone(),
+                    latency.clone(),
+                    scripts.clone(),
+                    functions.clone(),
                 );
+                let progress = actor.progress.clone();
                 tokio::spawn(actor.run());
                 ShardHandle {
                     tx,
                     shard_id,
                     response_pool: response_pool.clone(),
+                    progress,
                 }
             })
             .collect();
@@ -580,13 +881,19 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         ShardedActorState {
             shards: Arc::new(shards),
-            num_shards,
+            router: ShardRouter::new(num_shards),
             start_millis,
             time_source,
             config,
             adaptive_handle,
             response_pool,
             shared_script_cache,
+            pubsub,
+            blocking,
+            tracking,
+            latency,
+            scripts,
+            functions,
         }
     }
 
@@ -616,6 +923,12 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         // Create shared script cache for all shards (enables multi-shard Lua support)
         let shared_script_cache = crate::redis::lua::SharedScriptCache::new();
+        let pubsub = PubSubManager::new();
+        let blocking = BlockingManager::new();
+        let tracking = ClientTracking::new();
+        let latency = LatencyMonitor::new();
+        let scripts = ScriptMonitor::new();
+        let functions = FunctionLibraries::new();
 
         let shards: Vec<ShardHandle> = (0..num_shards)
             .map(|shard_id| {
@@ -628,12 +941,20 @@ impl<T: TimeSource> ShardedActorState<T> {
                     shard_id,
                     num_shards,
                     shared_script_cache.clone(),
+                    pubsub.clone(),
+                    blocking.clone(),
+                    tracking.clone(),
+                    latency.clone(),
+                    scripts.clone(),
+                    functions.clone(),
                 );
+                let progress = actor.progress.clone();
                 tokio::spawn(actor.run());
                 ShardHandle {
                     tx,
                     shard_id,
                     response_pool: response_pool.clone(),
+                    progress,
                 }
             })
             .collect();
@@ -657,19 +978,60 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         ShardedActorState {
             shards: Arc::new(shards),
-            num_shards,
+            router: ShardRouter::new(num_shards),
             start_millis,
             time_source,
             config: shard_config,
             adaptive_handle,
             response_pool,
             shared_script_cache,
+            pubsub,
+            blocking,
+            tracking,
+            latency,
+            scripts,
+            functions,
         }
     }
 
+    /// Per-shard progress, for the watchdog
+    pub fn shard_progress(&self) -> Vec<Arc<ShardProgress>> {
+        self.shards.iter().map(|shard| shard.progress.clone()).collect()
+    }
+
+    /// Server-wide Pub/Sub registry
+    pub fn pubsub(&self) -> &PubSubManager {
+        &self.pubsub
+    }
+
+    /// Server-wide registry of clients blocked on keys
+    pub fn blocking(&self) -> &BlockingManager {
+        &self.blocking
+    }
+
+    /// Server-wide CLIENT TRACKING table
+    pub fn tracking(&self) -> &ClientTracking {
+        &self.tracking
+    }
+
+    /// Server-wide latency monitor
+    pub fn latency(&self) -> &LatencyMonitor {
+        &self.latency
+    }
+
+    /// Server-wide monitor of running scripts
+    pub fn scripts(&self) -> &ScriptMonitor {
+        &self.scripts
+    }
+
+    /// Server-wide function libraries
+    pub fn functions(&self) -> &FunctionLibraries {
+        &self.functions
+    }
+
     /// Get current number of shards
     pub fn num_shards(&self) -> usize {
-        self.num_shards
+        self.router.num_shards()
     }
 
     /// Get the time source
@@ -865,6 +1227,57 @@ impl<T: TimeSource> ShardedActorState<T> {
         VirtualTime::from_millis(elapsed_ms)
     }
 
+    /// WATCH `key` on its owning shard; dropping the guard unwatches it
+    pub async fn watch(&self, key: &str) -> WatchGuard {
+        let virtual_time = self.get_current_virtual_time();
+        let shard_idx = self.router.shard_for(key);
+        self.shards[shard_idx]
+            .watch(key.to_string(), virtual_time)
+            .await
+    }
+
+    /// EXEC: run `queued` with no other client's command in between on the
+    /// shards involved, or return None without running anything when a
+    /// watched key changed since WATCH (see `transaction_coordinator`)
+    pub async fn execute_transaction(
+        &self,
+        watched: &[WatchGuard],
+        queued: &[Command],
+    ) -> Option<Vec<RespValue>> {
+        let virtual_time = self.get_current_virtual_time();
+
+        // Ascending shard order, so concurrent EXECs never wait on each other
+        let mut held: Vec<ShardHandle> = self.shards.to_vec();
+        for shard_idx in shards_to_hold(&self.router, watched, queued) {
+            held[shard_idx] = self.shards[shard_idx].hold().await;
+        }
+
+        let mut stamps: std::collections::BTreeMap<usize, Vec<(String, WatchStamp)>> =
+            std::collections::BTreeMap::new();
+        for guard in watched {
+            stamps
+                .entry(guard.shard_id())
+                .or_default()
+                .push((guard.key().to_string(), guard.stamp()));
+        }
+        for (shard_idx, stamps) in stamps {
+            if !held[shard_idx].check_watches(stamps, virtual_time).await {
+                return None;
+            }
+        }
+
+        // Route through the held shards; dropping `view` releases them
+        let view = ShardedActorState {
+            shards: Arc::new(held),
+            ..self.clone()
+        };
+        let mut replies = Vec::with_capacity(queued.len());
+        for cmd in queued {
+            replies.push(view.execute(cmd).await);
+        }
+        Some(replies)
+    }
+
     pub async fn evict_expired_all_shards(&self) -> usize {
         let virtual_time = self.get_current_virtual_time();
         let mut total = 0usize;
@@ -876,13 +1289,72 @@ impl<T: TimeSource> ShardedActorState<T> {
         total
     }
 
+    /// Keys expiring within each expiry horizon, summed over all shards.
+    /// O(distinct deadlines within the longest horizon) per shard.
+    pub async fn expiry_outlook(&self) -> ExpiryOutlook {
+        let virtual_time = self.get_current_virtual_time();
+        let mut outlook = ExpiryOutlook::default();
+        for shard in self.shards.iter() {
+            outlook.merge(&shard.expiry_outlook(virtual_time).await);
+        }
+
+        debug_assert!(
+            outlook.is_consistent(),
+            "Postcondition: merged expiry outlook must stay cumulative"
+        );
+        outlook
+    }
+
+    /// `MEMORY STATS` for each shard, indexed by shard
+    async fn shard_memory_stats_at(
+        &self,
+        virtual_time: VirtualTime,
+    ) -> Result<Vec<MemoryStats>, RespValue> {
+        let mut futures = Vec::with_capacity(self.router.num_shards());
+        for shard in self.shards.iter() {
+            futures.push(shard.execute(Command::MemoryStats, virtual_time));
+        }
+        let mut per_shard = Vec::with_capacity(self.router.num_shards());
+        for result in futures::future::join_all(futures).await {
+            match MemoryStats::from_resp(&result) {
+                Some(stats) => per_shard.push(stats),
+                None if matches!(result, RespValue::Error(_)) => return Err(result),
+                None => {
+                    return Err(RespValue::err(
+                        "ERR unexpected MEMORY STATS reply from shard",
+                    ))
+                }
+            }
+        }
+        Ok(per_shard)
+    }
+
+    /// Key count and bytes per shard, from each shard's `MEMORY STATS`
+    pub async fn shard_loads(&self) -> Result<Vec<ShardLoad>, RespValue> {
+        let virtual_time = self.get_current_virtual_time();
+        let per_shard = self.shard_memory_stats_at(virtual_time).await?;
+        Ok(per_shard
+            .iter()
+            .enumerate()
+            .map(|(shard, stats)| ShardLoad::from_memory_stats(shard, stats))
+            .collect())
+    }
+
+    /// Plan for bringing every shard within `tolerance_pct` of the mean.
+    /// Planning only: there is no apply mode, since keys are routed by hash
+    /// with no slots to migrate (deferred to GAP-012).
+    pub async fn rebalance_plan(&self, tolerance_pct: u64) -> Result<RebalancePlan, RespValue> {
+        let loads = self.shard_loads().await?;
+        Ok(RebalancePlan::build(&loads, tolerance_pct))
+    }
+
     /// Fast path GET - bypasses Command enum for lower overhead
     ///
     /// Uses bytes::Bytes to avoid String allocation. The key is hashed
     /// directly from bytes and routed to the appropriate shard.
     #[inline]
     pub async fn fast_get(&self, key: bytes::Bytes) -> RespValue {
-        let shard_idx = hash_key_bytes(&key, self.num_shards);
+        let shard_idx = self.router.shard_for_bytes(&key);
         debug_assert!(shard_idx < self.shards.len(), "Shard index out of bounds");
         self.shards[shard_idx].fast_get(key).await
     }
@@ -893,7 +1365,7 @@ impl<T: TimeSource> ShardedActorState<T> {
     /// The key is hashed directly from bytes and routed to the appropriate shard.
     #[inline]
     pub async fn fast_set(&self, key: bytes::Bytes, value: bytes::Bytes) -> RespValue {
-        let shard_idx = hash_key_bytes(&key, self.num_shards);
+        let shard_idx = self.router.shard_for_bytes(&key);
         debug_assert!(shard_idx < self.shards.len(), "Shard index out of bounds");
         self.shards[shard_idx].fast_set(key, value).await
     }
@@ -904,7 +1376,7 @@ impl<T: TimeSource> ShardedActorState<T> {
     /// eliminates oneshot channel allocation overhead.
     #[inline]
     pub async fn pooled_fast_get(&self, key: bytes::Bytes) -> RespValue {
-        let shard_idx = hash_key_bytes(&key, self.num_shards);
+        let shard_idx = self.router.shard_for_bytes(&key);
         debug_assert!(shard_idx < self.shards.len(), "Shard index out of bounds");
         self.shards[shard_idx].pooled_fast_get(key).await
     }
@@ -915,7 +1387,7 @@ impl<T: TimeSource> ShardedActorState<T> {
     /// eliminates oneshot channel allocation overhead.
     #[inline]
     pub async fn pooled_fast_set(&self, key: bytes::Bytes, value: bytes::Bytes) -> RespValue {
-        let shard_idx = hash_key_bytes(&key, self.num_shards);
+        let shard_idx = self.router.shard_for_bytes(&key);
         debug_assert!(shard_idx < self.shards.len(), "Shard index out of bounds");
         self.shards[shard_idx].pooled_fast_set(key, value).await
     }
@@ -932,9 +1404,10 @@ impl<T: TimeSource> ShardedActorState<T> {
         }
 
         // Group keys by shard, tracking original indices for result reconstruction
-        let mut shard_batches: Vec<Vec<(usize, bytes::Bytes)>> = vec![Vec::new(); self.num_shards];
+        let mut shard_batches: Vec<Vec<(usize, bytes::Bytes)>> =
+            vec![Vec::new(); self.router.num_shards()];
         for (idx, key) in keys.iter().enumerate() {
-            let shard_idx = hash_key_bytes(key, self.num_shards);
+            let shard_idx = self.router.shard_for_bytes(key);
             shard_batches[shard_idx].push((idx, key.clone()));
         }
 
@@ -960,7 +1433,7 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         // Reconstruct results in original order
         for (indices, shard_results) in all_results {
-            for (i, resp) in indices.into_iter().zip(shard_results.into_iter()) {
+            for (i, resp) in indices.into_iter().zip(shard_results) {
                 results[i] = resp;
             }
         }
@@ -985,9 +1458,9 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         // Group pairs by shard, tracking original indices for result reconstruction
         let mut shard_batches: Vec<Vec<(usize, bytes::Bytes, bytes::Bytes)>> =
-            vec![Vec::new(); self.num_shards];
+            vec![Vec::new(); self.router.num_shards()];
         for (idx, (key, value)) in pairs.iter().enumerate() {
-            let shard_idx = hash_key_bytes(key, self.num_shards);
+            let shard_idx = self.router.shard_for_bytes(key);
             shard_batches[shard_idx].push((idx, key.clone(), value.clone()));
         }
 
@@ -1016,7 +1489,7 @@ impl<T: TimeSource> ShardedActorState<T> {
 
         // Reconstruct results in original order
         for (indices, shard_results) in all_results {
-            for (i, resp) in indices.into_iter().zip(shard_results.into_iter()) {
+            for (i, resp) in indices.into_iter().zip(shard_results) {
                 results[i] = resp;
             }
         }
@@ -1024,6 +1497,26 @@ impl<T: TimeSource> ShardedActorState<T> {
         results
     }
 
+    /// `keys-chunk-size`, read from shard 0 where keyless CONFIG SET lands
+    async fn keys_chunk_size(&self, virtual_time: VirtualTime) -> usize {
+        let reply = self.shards[0]
+            .execute(
+                Command::ConfigGet(vec!["keys-chunk-size".to_string()]),
+                virtual_time,
+            )
+            .await;
+        match reply {
+            RespValue::Array(Some(pair)) => match pair.get(1) {
+                Some(RespValue::BulkString(Some(v))) => std::str::from_utf8(v)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                _ => 0,
+            },
+            _ => 0,
+        }
+    }
+
     pub async fn execute(&self, cmd: &Command) -> RespValue {
         let virtual_time = self.get_current_virtual_time();
 
@@ -1040,7 +1533,7 @@ impl<T: TimeSource> ShardedActorState<T> {
                 let uptime_days = uptime_secs / 86400;
 
                 // Get total key count across all shards (same pattern as DbSize)
-                let mut dbsize_futures = Vec::with_capacity(self.num_shards);
+                let mut dbsize_futures = Vec::with_capacity(self.router.num_shards());
                 for shard in self.shards.iter() {
                     dbsize_futures.push(shard.execute(Command::DbSize, virtual_time));
                 }
@@ -1062,16 +1555,11 @@ impl<T: TimeSource> ShardedActorState<T> {
 
                 let pid = std::process::id();
                 let tcp_port = 6379; // Default Redis port (overridden by env in container)
+                let release = info_server_fields(ServerMode::Standalone);
 
                 let info = format!(
                     "# Server\r\n\
-                     redis_version:7.0.0\r\n\
-                     redis_git_sha1:00000000\r\n\
-                     redis_git_dirty:0\r\n\
-                     redis_build_id:0\r\n\
-                     redis_mode:standalone\r\n\
-                     os:Linux\r\n\
-                     arch_bits:64\r\n\
+                     {release}\
                      tcp_port:{tcp_port}\r\n\
                      uptime_in_seconds:{uptime_secs}\r\n\
                      uptime_in_days:{uptime_days}\r\n\
@@ -1084,8 +1572,8 @@ impl<T: TimeSource> ShardedActorState<T> {
                      \r\n\
                      # Clients\r\n\
                      connected_clients:1\r\n\
-                     blocked_clients:0\r\n\
-                     tracking_clients:0\r\n\
+                     blocked_clients:{blocked_clients}\r\n\
+                     tracking_clients:{tracking_clients}\r\n\
                      clients_in_timeout_table:0\r\n\
                      \r\n\
                      # Memory\r\n\
@@ -1133,7 +1621,9 @@ impl<T: TimeSource> ShardedActorState<T> {
                     uptime_secs = uptime_secs,
                     uptime_days = uptime_days,
                     pid = pid,
-                    num_shards = self.num_shards,
+                    num_shards = self.router.num_shards(),
+                    blocked_clients = self.blocking.num_blocked(),
+                    tracking_clients = self.tracking.num_clients(),
                     used_memory = used_memory,
                     used_memory_human = Self::format_bytes_human(used_memory),
                     used_memory_rss = used_memory_rss,
@@ -1153,7 +1643,7 @@ impl<T: TimeSource> ShardedActorState<T> {
             }
 
             Command::FlushDb | Command::FlushAll => {
-                let mut futures = Vec::with_capacity(self.num_shards);
+                let mut futures = Vec::with_capacity(self.router.num_shards());
                 for shard in self.shards.iter() {
                     futures.push(shard.execute(Command::FlushDb, virtual_time));
                 }
@@ -1163,30 +1653,52 @@ impl<T: TimeSource> ShardedActorState<T> {
                 RespValue::simple("OK")
             }
 
-            Command::Keys(pattern) => {
-                let mut futures = Vec::with_capacity(self.num_shards);
-                for shard in self.shards.iter() {
-                    futures.push(shard.execute(Command::Keys(pattern.clone()), virtual_time));
-                }
+            // Answered here: the shard running the busy script cannot
+            Command::ScriptKill => self.scripts.kill(),
 
-                let mut all_keys: Vec<RespValue> = Vec::new();
-                for future in futures {
-                    if let RespValue::Array(Some(keys)) = future.await {
-                        all_keys.extend(keys);
+            // Sleep on the runtime's timer: moving a shard's clock would
+            // expire its keys early
+            Command::DebugSleep(seconds) => {
+                tokio::time::sleep(std::time::Duration::from_secs_f64(*seconds)).await;
+                RespValue::ok()
+            }
+
+            // Every shard expires and persists its own keys
+            Command::DebugSetActiveExpire(_) | Command::DebugReload => {
+                let futures: Vec<_> = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.execute(cmd.clone(), virtual_time))
+                    .collect();
+                let mut reply = RespValue::ok();
+                for result in futures::future::join_all(futures).await {
+                    if matches!(result, RespValue::Error(_)) {
+                        reply = result;
                     }
                 }
+                reply
+            }
+
+            Command::Keys(pattern) => {
+                let chunk_size = self.keys_chunk_size(virtual_time).await;
+                let futures: Vec<_> = self
+                    .shards
+                    .iter()
+                    .map(|shard| shard.keys_chunked(pattern, chunk_size, virtual_time))
+                    .collect();
+                let all_keys: Vec<RespValue> =
+                    futures::future::join_all(futures).await.into_iter().flatten().collect();
                 RespValue::Array(Some(all_keys))
             }
 
             Command::MGet(keys) => {
                 // Optimization: Group keys by shard to reduce actor messages (Abseil Tip #5)
-                let num_shards = self.num_shards;
 
                 // Build shard batches: (shard_idx, original_indices, keys)
                 let mut shard_batches: std::collections::HashMap<usize, (Vec<usize>, Vec<String>)> =
                     std::collections::HashMap::new();
                 for (original_idx, key) in keys.iter().enumerate() {
-                    let shard_idx = hash_key(key, num_shards);
+                    let shard_idx = self.router.shard_for(key);
                     let entry = shard_batches
                         .entry(shard_idx)
                         .or_insert_with(|| (Vec::new(), Vec::new()));
@@ -1222,13 +1734,12 @@ impl<T: TimeSource> ShardedActorState<T> {
 
             Command::MSet(pairs) => {
                 // Group key-value pairs by target shard (using HashMap for dynamic shard count)
-                let num_shards = self.num_shards;
                 let mut shard_batches: std::collections::HashMap<
                     usize,
                     Vec<(String, crate::redis::SDS)>,
                 > = std::collections::HashMap::new();
                 for (key, value) in pairs {
-                    let shard_idx = hash_key(key, num_shards);
+                    let shard_idx = self.router.shard_for(key);
                     shard_batches
                         .entry(shard_idx)
                         .or_default()
@@ -1280,8 +1791,50 @@ impl<T: TimeSource> ShardedActorState<T> {
                 ]))
             }
 
+            Command::DebugKeyStats => {
+                let mut futures = Vec::with_capacity(self.router.num_shards());
+                for shard in self.shards.iter() {
+                    futures.push(shard.execute(Command::DebugKeyStats, virtual_time));
+                }
+                let results = futures::future::join_all(futures).await;
+                let mut merged = KeyStatsReport::default();
+                for result in results {
+                    match result {
+                        RespValue::BulkString(Some(bytes)) => {
+                            let parsed = std::str::from_utf8(&bytes)
+                                .ok()
+                                .and_then(KeyStatsReport::parse);
+                            match parsed {
+                                Some(report) => merged.merge(&report),
+                                None => return RespValue::err("ERR malformed KEYSTATS reply from shard"),
+                            }
+                        }
+                        err @ RespValue::Error(_) => return err,
+                        _ => return RespValue::err("ERR unexpected KEYSTATS reply from shard"),
+                    }
+                }
+                RespValue::BulkString(Some(merged.render().into_bytes()))
+            }
+
+            // Each shard estimates its own keys; DOCTOR judges the sum
+            Command::MemoryStats | Command::MemoryDoctor => {
+                let per_shard = match self.shard_memory_stats_at(virtual_time).await {
+                    Ok(per_shard) => per_shard,
+                    Err(err) => return err,
+                };
+                let mut merged = MemoryStats::default();
+                for stats in &per_shard {
+                    merged.merge(stats);
+                }
+                if matches!(cmd, Command::MemoryDoctor) {
+                    RespValue::BulkString(Some(merged.doctor().into_bytes()))
+                } else {
+                    merged.to_resp()
+                }
+            }
+
             Command::DbSize => {
-                let mut futures = Vec::with_capacity(self.num_shards);
+                let mut futures = Vec::with_capacity(self.router.num_shards());
                 for shard in self.shards.iter() {
                     futures.push(shard.execute(Command::DbSize, virtual_time));
                 }
@@ -1300,55 +1853,91 @@ impl<T: TimeSource> ShardedActorState<T> {
             }
 
             Command::Scan {
-                cursor: _,
-                ref pattern,
-                ref count,
+                cursor,
+                pattern,
+                count,
+                type_filter,
             } => {
-                // Fan out to all shards with cursor 0, merge results
-                let mut futures = Vec::with_capacity(self.num_shards);
+                // Page every shard from the same cursor. Below the smallest
+                // cursor a shard returns, every shard has sent all its
+                // keys, so that prefix of the merge is complete.
+                let mut futures = Vec::with_capacity(self.router.num_shards());
                 for shard in self.shards.iter() {
                     futures.push(shard.execute(
                         Command::Scan {
-                            cursor: 0,
+                            cursor: *cursor,
                             pattern: pattern.clone(),
                             count: *count,
+                            type_filter: type_filter.clone(),
                         },
                         virtual_time,
                     ));
                 }
                 let results = futures::future::join_all(futures).await;
-                let mut all_keys: Vec<RespValue> = Vec::new();
+                let mut entries: Vec<(u64, Vec<u8>)> = Vec::new();
+                let mut bound: Option<u64> = None;
                 for result in results {
-                    if let RespValue::Array(Some(parts)) = result {
-                        // SCAN returns [cursor, [keys...]]
-                        if parts.len() == 2 {
-                            if let RespValue::Array(Some(keys)) = &parts[1] {
-                                all_keys.extend(keys.iter().cloned());
-                            }
+                    // SCAN returns [cursor, [keys...]]
+                    let parts = match result {
+                        RespValue::Array(Some(parts)) => parts,
+                        error => return error,
+                    };
+                    let (
+                        Some(RespValue::BulkString(Some(next))),
+                        Some(RespValue::Array(Some(keys))),
+                    ) = (parts.first(), parts.get(1))
+                    else {
+                        continue;
+                    };
+                    let next: u64 = std::str::from_utf8(next)
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or(0);
+                    if next != 0 {
+                        bound = Some(bound.map_or(next, |b| b.min(next)));
+                    }
+                    for key in keys {
+                        if let RespValue::BulkString(Some(key)) = key {
+                            entries.push((CommandExecutor::scan_position(key), key.clone()));
                         }
                     }
                 }
-                // Return cursor 0 (done) with merged keys
+                if let Some(bound) = bound {
+                    entries.retain(|(position, _)| *position < bound);
+                }
+                entries.sort_unstable();
+                let (len, next) = CommandExecutor::scan_page(&entries, count.unwrap_or(10));
+                let next = if next == 0 { bound.unwrap_or(0) } else { next };
+                entries.truncate(len);
                 RespValue::Array(Some(vec![
-                    RespValue::BulkString(Some(b"0".to_vec())),
-                    RespValue::Array(Some(all_keys)),
+                    RespValue::BulkString(Some(next.to_string().into_bytes())),
+                    RespValue::Array(Some(
+                        entries
+                            .into_iter()
+                            .map(|(_, key)| RespValue::BulkString(Some(key)))
+                            .collect(),
+                    )),
                 ]))
             }
 
-            Command::Del(keys) if keys.len() > 1 => {
-                // Fan out multi-key DEL to correct shards
-                let num_shards = self.num_shards;
+            Command::Del(keys) | Command::Unlink(keys) if keys.len() > 1 => {
+                // Fan out multi-key DEL / UNLINK to correct shards
+                let unlink = matches!(cmd, Command::Unlink(_));
                 let mut shard_batches: std::collections::HashMap<usize, Vec<String>> =
                     std::collections::HashMap::new();
                 for key in keys {
-                    let shard_idx = hash_key(key, num_shards);
+                    let shard_idx = self.router.shard_for(key);
                     shard_batches.entry(shard_idx).or_default().push(key.clone());
                 }
                 let futures: Vec<_> = shard_batches
                     .into_iter()
                     .map(|(shard_idx, batch_keys)| {
-                        self.shards[shard_idx]
-                            .execute(Command::Del(batch_keys), virtual_time)
+                        let batch = if unlink {
+                            Command::Unlink(batch_keys)
+                        } else {
+                            Command::Del(batch_keys)
+                        };
+                        self.shards[shard_idx].execute(batch, virtual_time)
                     })
                     .collect();
                 let results = futures::future::join_all(futures).await;
@@ -1365,14 +1954,18 @@ impl<T: TimeSource> ShardedActorState<T> {
                 RespValue::Integer(count)
             }
 
-            Command::Exists(keys) => {
-                let num_shards = self.num_shards;
+            Command::Exists(keys) | Command::Touch(keys) => {
+                let touch = matches!(cmd, Command::Touch(_));
                 let futures: Vec<_> = keys
                     .iter()
                     .map(|key| {
-                        let shard_idx = hash_key(key, num_shards);
-                        self.shards[shard_idx]
-                            .execute(Command::Exists(vec![key.clone()]), virtual_time)
+                        let shard_idx = self.router.shard_for(key);
+                        let single = if touch {
+                            Command::Touch(vec![key.clone()])
+                        } else {
+                            Command::Exists(vec![key.clone()])
+                        };
+                        self.shards[shard_idx].execute(single, virtual_time)
                     })
                     .collect();
 
@@ -1391,10 +1984,110 @@ impl<T: TimeSource> ShardedActorState<T> {
                 RespValue::Integer(count)
             }
 
+            Command::BLPop { keys, timeout_ms }
+            | Command::BRPop { keys, timeout_ms }
+            | Command::BZPopMin { keys, timeout_ms }
+            | Command::BZPopMax { keys, timeout_ms }
+                if keys.len() > 1 =>
+            {
+                // Keys may live on different shards: try them in argument order,
+                // one shard at a time, so the first non-empty key wins
+                for key in keys {
+                    let keys = vec![key.clone()];
+                    let timeout_ms = *timeout_ms;
+                    let single = match cmd {
+                        Command::BLPop { .. } => Command::BLPop { keys, timeout_ms },
+                        Command::BRPop { .. } => Command::BRPop { keys, timeout_ms },
+                        Command::BZPopMin { .. } => Command::BZPopMin { keys, timeout_ms },
+                        _ => Command::BZPopMax { keys, timeout_ms },
+                    };
+                    let shard_idx = self.router.shard_for(key);
+                    match self.shards[shard_idx].execute(single, virtual_time).await {
+                        RespValue::Array(None) => continue,
+                        reply => return reply,
+                    }
+                }
+                RespValue::Array(None)
+            }
+
+            Command::XRead { .. } | Command::XReadGroup { .. } => {
+                let per_key: Vec<(&String, Command)> = match cmd {
+                    Command::XRead {
+                        count,
+                        block_ms,
+                        keys,
+                        ids,
+                    } => keys
+                        .iter()
+                        .zip(ids)
+                        .map(|(key, id)| {
+                            let single = Command::XRead {
+                                count: *count,
+                                block_ms: *block_ms,
+                                keys: vec![key.clone()],
+                                ids: vec![*id],
+                            };
+                            (key, single)
+                        })
+                        .collect(),
+                    Command::XReadGroup {
+                        group,
+                        consumer,
+                        count,
+                        block_ms,
+                        noack,
+                        keys,
+                        ids,
+                    } => keys
+                        .iter()
+                        .zip(ids)
+                        .map(|(key, id)| {
+                            let single = Command::XReadGroup {
+                                group: group.clone(),
+                                consumer: consumer.clone(),
+                                count: *count,
+                                block_ms: *block_ms,
+                                noack: *noack,
+                                keys: vec![key.clone()],
+                                ids: vec![*id],
+                            };
+                            (key, single)
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                let futures: Vec<_> = per_key
+                    .into_iter()
+                    .map(|(key, single)| {
+                        let shard_idx = self.router.shard_for(key);
+                        self.shards[shard_idx].execute(single, virtual_time)
+                    })
+                    .collect();
+
+                // Per-key replies are nil or a one-stream array; keep key order
+                let results = futures::future::join_all(futures).await;
+                let mut streams = Vec::new();
+                for resp in results {
+                    match resp {
+                        RespValue::Array(Some(items)) => streams.extend(items),
+                        RespValue::Array(None) => {}
+                        err => return err,
+                    }
+                }
+                if streams.is_empty() {
+                    RespValue::Array(None)
+                } else {
+                    RespValue::Array(Some(streams))
+                }
+            }
+
             _ => {
                 if let Some(key) = cmd.get_primary_key() {
-                    let shard_idx = hash_key(key, self.num_shards);
-                    debug_assert!(shard_idx < self.num_shards, "Invalid shard index for key");
+                    let shard_idx = self.router.shard_for(key);
+                    debug_assert!(
+                        shard_idx < self.router.num_shards(),
+                        "Invalid shard index for key"
+                    );
                     self.shards[shard_idx]
                         .execute(cmd.clone(), virtual_time)
                         .await
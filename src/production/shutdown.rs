@@ -0,0 +1,259 @@
+//! Graceful Shutdown
+//!
+//! Shared by both production servers. On SIGTERM, SIGINT or a SHUTDOWN
+//! command a server:
+//!
+//! 1. stops accepting connections
+//! 2. drains clients: each connection finishes the commands it has already
+//!    read, flushes the replies, sends a `-SHUTDOWN` notice and closes
+//! 3. aborts connections still running after the drain timeout
+//! 4. flushes persistence (persistent server only): final WAL fsync, write
+//!    buffer flush, shutdown checkpoint
+//!
+//! Step 3 runs before step 4, so no reply can be written once persistence
+//! has been flushed: every acknowledged write reached the WAL and the delta
+//! sink before the flush started.
+//!
+//! `SHUTDOWN NOSAVE` skips the shutdown checkpoint; the WAL and the write
+//! buffer are still flushed, so acknowledged writes stay durable. `SAVE`, or
+//! neither, writes it, as a signal does. The connection that sent SHUTDOWN
+//! gets no reply of its own, only the `-SHUTDOWN` notice, as Redis closes
+//! it without one.
+//!
+//! ## Environment Variables
+//!
+//! | Variable | Default | Description |
+//! |----------|---------|-------------|
+//! | REDIS_SHUTDOWN_DRAIN_TIMEOUT_MS | 10000 | Time clients get to drain before being aborted |
+
+use super::ClientRegistry;
+use std::fmt;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// Default time clients get to drain
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error sent to every client closed by a graceful shutdown
+pub const SHUTDOWN_NOTICE: &str = "SHUTDOWN Server is shutting down";
+
+/// Graceful shutdown configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownConfig {
+    /// Time clients get to drain before their connections are aborted
+    pub drain_timeout: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        ShutdownConfig {
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+}
+
+impl ShutdownConfig {
+    /// Load from `REDIS_SHUTDOWN_DRAIN_TIMEOUT_MS`
+    pub fn from_env() -> Self {
+        let drain_timeout = std::env::var("REDIS_SHUTDOWN_DRAIN_TIMEOUT_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+        ShutdownConfig { drain_timeout }
+    }
+}
+
+/// Outcome of draining clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Sessions that were live when the drain started
+    pub sessions: usize,
+    /// Connection tasks that closed on their own
+    pub drained: usize,
+    /// Connection tasks aborted at the drain timeout
+    pub aborted: usize,
+}
+
+/// What a SHUTDOWN command asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownRequest {
+    /// False for NOSAVE: skip the shutdown checkpoint
+    pub save: bool,
+}
+
+/// Why a server is shutting down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownCause {
+    /// SIGTERM or SIGINT
+    Signal(&'static str),
+    /// A client sent SHUTDOWN
+    Command(ShutdownRequest),
+}
+
+impl ShutdownCause {
+    /// Whether to write the shutdown checkpoint; signals always do
+    pub fn save(&self) -> bool {
+        match self {
+            ShutdownCause::Signal(_) => true,
+            ShutdownCause::Command(request) => request.save,
+        }
+    }
+}
+
+impl fmt::Display for ShutdownCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownCause::Signal(name) => f.write_str(name),
+            ShutdownCause::Command(request) if request.save => f.write_str("SHUTDOWN"),
+            ShutdownCause::Command(_) => f.write_str("SHUTDOWN NOSAVE"),
+        }
+    }
+}
+
+/// Resolve on the first SIGTERM or SIGINT, or the first SHUTDOWN sent to a
+/// client of `registry`
+pub async fn shutdown_trigger(registry: &ClientRegistry) -> ShutdownCause {
+    tokio::select! {
+        name = termination_signal() => ShutdownCause::Signal(name),
+        request = registry.shutdown_requested() => ShutdownCause::Command(request),
+    }
+}
+
+/// Resolve on the first SIGTERM or SIGINT (Ctrl+C elsewhere). Returns the
+/// signal name for logging.
+pub async fn termination_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => "SIGTERM",
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                }
+            }
+            Err(e) => {
+                warn!("Failed to install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Drain every client session and wait for the connection tasks in `tasks`
+/// to finish, aborting whatever is left after `timeout`.
+///
+/// On return no connection task is running, so nothing can be acknowledged
+/// from here on.
+pub async fn drain_clients(
+    registry: &ClientRegistry,
+    tasks: &mut JoinSet<()>,
+    timeout: Duration,
+) -> DrainReport {
+    let sessions = registry.drain_all();
+    info!(
+        "Draining {} client(s), {} connection task(s) (timeout {:?})",
+        sessions,
+        tasks.len(),
+        timeout
+    );
+
+    let mut drained = 0;
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        match tokio::time::timeout_at(deadline, tasks.join_next()).await {
+            Ok(Some(_)) => drained += 1,
+            Ok(None) => break,
+            Err(_) => break,
+        }
+    }
+
+    let aborted = tasks.len();
+    if aborted > 0 {
+        warn!(
+            "{} connection(s) did not drain within {:?}, aborting",
+            aborted, timeout
+        );
+        tasks.abort_all();
+        while tasks.join_next().await.is_some() {}
+    }
+
+    // TigerStyle: Postcondition
+    debug_assert!(
+        tasks.is_empty(),
+        "Postcondition violated: no connection task may outlive the drain"
+    );
+
+    DrainReport {
+        sessions,
+        drained,
+        aborted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_drain_clients_aborts_stragglers() {
+        let registry = Arc::new(ClientRegistry::new());
+        let mut tasks = JoinSet::new();
+
+        // Well-behaved connection: closes as soon as it is asked to
+        let session = registry.register("a:1", None);
+        let reg = registry.clone();
+        tasks.spawn(async move {
+            session.close_requested().await;
+            reg.unregister(session.id());
+        });
+
+        // Stuck connection: never looks at its session
+        tasks.spawn(std::future::pending::<()>());
+
+        let report = drain_clients(&registry, &mut tasks, Duration::from_millis(50)).await;
+        assert_eq!(
+            report,
+            DrainReport {
+                sessions: 1,
+                drained: 1,
+                aborted: 1
+            }
+        );
+        assert!(tasks.is_empty());
+        assert!(registry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_command_fires_the_trigger() {
+        let registry = Arc::new(ClientRegistry::new());
+        let trigger = {
+            let registry = registry.clone();
+            tokio::spawn(async move { shutdown_trigger(&registry).await })
+        };
+        tokio::task::yield_now().await;
+
+        registry.request_shutdown(ShutdownRequest { save: false });
+        // Only the first request counts
+        registry.request_shutdown(ShutdownRequest { save: true });
+
+        let cause = trigger.await.unwrap();
+        assert_eq!(
+            cause,
+            ShutdownCause::Command(ShutdownRequest { save: false })
+        );
+        assert!(!cause.save());
+        assert_eq!(cause.to_string(), "SHUTDOWN NOSAVE");
+        assert!(ShutdownCause::Signal("SIGTERM").save());
+    }
+}
@@ -0,0 +1,433 @@
+//! Tenant Keyspaces
+//!
+//! An ACL user with a `keyspace:<prefix>` rule is confined to a virtual
+//! keyspace: every key it names is stored as `<prefix><key>`, and key names
+//! in replies have the prefix stripped again. The rewrite happens at dispatch,
+//! after ACL checks (which therefore see the tenant's own key names), so
+//! shards and persistence never know tenants exist.
+//!
+//! Broad patterns stay inside the tenant: `KEYS *` and `SCAN MATCH *` are
+//! anchored to the prefix, and a SCAN without MATCH is given one. SORT's BY
+//! and GET patterns name keys too and are prefixed like keys; a prefix with
+//! a `*` in it would take their substitution, so such tenants may not SORT
+//! with lookups. Commands
+//! that cannot be confined to a prefix (FLUSHALL, DBSIZE, RANDOMKEY, Lua
+//! scripts that may name arbitrary keys, ...) are refused.
+//!
+//! Prefixes must not be prefixes of each other (`acme:` and `acme:eu:` would
+//! overlap); ending every prefix with a delimiter avoids that.
+//!
+//! # TigerStyle Invariants
+//!
+//! - The prefix is never empty
+//! - Every key of a confined command starts with the prefix, and so does
+//!   every SORT BY/GET pattern that names keys
+
+use crate::redis::{Command, RespValue};
+use std::borrow::Cow;
+
+/// Error for commands a tenant cannot run
+pub const TENANT_COMMAND_DENIED: &str =
+    "NOPERM this command is not available to users confined to a keyspace";
+
+/// A tenant's virtual keyspace, identified by its key prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantKeyspace {
+    prefix: String,
+}
+
+impl TenantKeyspace {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        debug_assert!(!prefix.is_empty(), "Precondition: prefix must not be empty");
+        TenantKeyspace { prefix }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Rewrite a command into the shared keyspace, or refuse it
+    pub fn confine(&self, cmd: &Command) -> Result<Command, &'static str> {
+        if Self::is_unconfinable(cmd) {
+            return Err(TENANT_COMMAND_DENIED);
+        }
+
+        let mut confined = cmd.clone();
+        match &mut confined {
+            Command::Keys(pattern) => *pattern = self.anchor_pattern(pattern),
+            Command::Scan { pattern, .. } => {
+                let anchored = self.anchor_pattern(pattern.as_deref().unwrap_or("*"));
+                *pattern = Some(anchored);
+            }
+            other => {
+                if let Command::Sort { by, get, .. } = other {
+                    self.confine_sort_patterns(by, get)?;
+                }
+                for key in other.keys_mut() {
+                    key.insert_str(0, &self.prefix);
+                }
+            }
+        }
+
+        self.verify_invariants(&confined);
+        Ok(confined)
+    }
+
+    /// Strip the prefix from key names in the reply to a confined command
+    pub fn unconfine_reply(&self, confined: &Command, reply: RespValue) -> RespValue {
+        match (confined, reply) {
+            (Command::Keys(_), RespValue::Array(Some(keys))) => {
+                RespValue::Array(Some(self.strip_keys(keys)))
+            }
+            (Command::Scan { .. }, RespValue::Array(Some(mut parts))) if parts.len() == 2 => {
+                if let RespValue::Array(Some(keys)) = parts.pop().expect("len checked") {
+                    parts.push(RespValue::Array(Some(self.strip_keys(keys))));
+                }
+                RespValue::Array(Some(parts))
+            }
+            // `[key, element]` and `[key, member, score]`
+            (
+                Command::BLPop { .. }
+                | Command::BRPop { .. }
+                | Command::BZPopMin { .. }
+                | Command::BZPopMax { .. },
+                RespValue::Array(Some(mut parts)),
+            ) if !parts.is_empty() => {
+                let rest = parts.split_off(1);
+                let mut parts = self.strip_keys(parts);
+                parts.extend(rest);
+                RespValue::Array(Some(parts))
+            }
+            // `[[key, entries], ...]`
+            (Command::XRead { .. } | Command::XReadGroup { .. }, RespValue::Array(Some(streams))) => {
+                let streams = streams
+                    .into_iter()
+                    .map(|stream| match stream {
+                        RespValue::Array(Some(mut parts)) if parts.len() == 2 => {
+                            let entries = parts.pop().expect("len checked");
+                            let mut parts = self.strip_keys(parts);
+                            parts.push(entries);
+                            RespValue::Array(Some(parts))
+                        }
+                        other => other,
+                    })
+                    .collect();
+                RespValue::Array(Some(streams))
+            }
+            (_, reply) => reply,
+        }
+    }
+
+    /// Commands whose effect or reply spans the whole keyspace
+    fn is_unconfinable(cmd: &Command) -> bool {
+        matches!(
+            cmd,
+            Command::FlushDb
+                | Command::FlushAll
+                | Command::DbSize
+                | Command::RandomKey
+                | Command::DebugKeyStats
+                | Command::MemoryStats
+                | Command::MemoryDoctor
+                | Command::Eval { .. }
+                | Command::EvalSha { .. }
+                | Command::EvalRo { .. }
+                | Command::EvalShaRo { .. }
+                | Command::FCall { .. }
+                | Command::FCallRo { .. }
+        )
+    }
+
+    /// Prefix the SORT BY/GET patterns that name keys; a pattern without
+    /// `*` (`#`, `nosort`) looks nothing up
+    fn confine_sort_patterns(
+        &self,
+        by: &mut Option<String>,
+        get: &mut [String],
+    ) -> Result<(), &'static str> {
+        let lookups: Vec<&mut String> = by
+            .iter_mut()
+            .chain(get.iter_mut())
+            .filter(|pattern| pattern.contains('*'))
+            .collect();
+        // The element would replace the prefix's `*` instead of the pattern's
+        if !lookups.is_empty() && self.prefix.contains('*') {
+            return Err(TENANT_COMMAND_DENIED);
+        }
+        for pattern in lookups {
+            pattern.insert_str(0, &self.prefix);
+        }
+        Ok(())
+    }
+
+    /// `<prefix><pattern>` with glob metacharacters in the prefix escaped
+    fn anchor_pattern(&self, pattern: &str) -> String {
+        let mut anchored = String::with_capacity(self.prefix.len() + pattern.len() + 8);
+        for c in self.prefix.chars() {
+            match c {
+                '*' | '?' | '[' => {
+                    anchored.push('[');
+                    anchored.push(c);
+                    anchored.push(']');
+                }
+                _ => anchored.push(c),
+            }
+        }
+        anchored.push_str(pattern);
+        anchored
+    }
+
+    fn strip_keys(&self, keys: Vec<RespValue>) -> Vec<RespValue> {
+        let prefix = self.prefix.as_bytes();
+        keys.into_iter()
+            .map(|key| match key {
+                RespValue::BulkString(Some(name)) if name.starts_with(prefix) => {
+                    RespValue::BulkString(Some(name[prefix.len()..].to_vec()))
+                }
+                other => other,
+            })
+            .collect()
+    }
+
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self, confined: &Command) {
+        debug_assert!(
+            !self.prefix.is_empty(),
+            "Invariant: prefix must not be empty"
+        );
+        if let Command::Sort { by, get, .. } = confined {
+            for pattern in by.iter().chain(get.iter()) {
+                debug_assert!(
+                    !pattern.contains('*') || pattern.starts_with(&self.prefix),
+                    "Invariant: SORT pattern '{}' must carry the tenant prefix",
+                    pattern
+                );
+            }
+        }
+        if !matches!(confined, Command::Keys(_) | Command::Scan { .. }) {
+            for key in confined.get_keys() {
+                debug_assert!(
+                    key.starts_with(&self.prefix),
+                    "Invariant: confined key '{}' must carry the tenant prefix",
+                    key
+                );
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn verify_invariants(&self, _confined: &Command) {}
+}
+
+/// Confine a command when the connection belongs to a tenant; borrows the
+/// command unchanged otherwise
+pub fn confine_command<'a>(
+    keyspace: Option<&TenantKeyspace>,
+    cmd: &'a Command,
+) -> Result<Cow<'a, Command>, &'static str> {
+    match keyspace {
+        Some(keyspace) => keyspace.confine(cmd).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(cmd)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis::{CommandExecutor, SDS};
+
+    fn bulk(s: &str) -> RespValue {
+        RespValue::BulkString(Some(s.as_bytes().to_vec()))
+    }
+
+    #[test]
+    fn test_confine_prefixes_every_key() {
+        let tenant = TenantKeyspace::new("acme:");
+
+        let confined = tenant.confine(&Command::Get("k".to_string())).unwrap();
+        assert_eq!(confined.get_keys(), vec!["acme:k".to_string()]);
+
+        let mset = Command::MSet(vec![
+            ("a".to_string(), SDS::from_str("1")),
+            ("b".to_string(), SDS::from_str("2")),
+        ]);
+        let confined = tenant.confine(&mset).unwrap();
+        assert_eq!(
+            confined.get_keys(),
+            vec!["acme:a".to_string(), "acme:b".to_string()]
+        );
+
+        let rename = Command::Rename("src".to_string(), "dst".to_string());
+        let confined = tenant.confine(&rename).unwrap();
+        assert_eq!(
+            confined.get_keys(),
+            vec!["acme:src".to_string(), "acme:dst".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_patterns_are_anchored_to_prefix() {
+        let tenant = TenantKeyspace::new("t*1:");
+
+        match tenant.confine(&Command::Keys("*".to_string())).unwrap() {
+            Command::Keys(pattern) => assert_eq!(pattern, "t[*]1:*"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let scan = Command::Scan {
+            cursor: 0,
+            pattern: None,
+            count: None,
+            type_filter: None,
+        };
+        match tenant.confine(&scan).unwrap() {
+            Command::Scan { pattern, .. } => assert_eq!(pattern.as_deref(), Some("t[*]1:*")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sort_lookup_patterns_stay_in_the_keyspace() {
+        let sort = Command::Sort {
+            key: "list".to_string(),
+            by: Some("weight_*".to_string()),
+            limit: None,
+            get: vec!["#".to_string(), "victim:*".to_string()],
+            desc: false,
+            alpha: false,
+            store: Some("out".to_string()),
+        };
+        match TenantKeyspace::new("acme:").confine(&sort).unwrap() {
+            Command::Sort {
+                key,
+                by,
+                get,
+                store,
+                ..
+            } => {
+                assert_eq!(key, "acme:list");
+                assert_eq!(by.as_deref(), Some("acme:weight_*"));
+                assert_eq!(get, vec!["#".to_string(), "acme:victim:*".to_string()]);
+                assert_eq!(store.as_deref(), Some("acme:out"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // A `*` in the prefix would swallow the substitution
+        let starred = TenantKeyspace::new("t*1:");
+        assert_eq!(starred.confine(&sort).unwrap_err(), TENANT_COMMAND_DENIED);
+        let plain = Command::Sort {
+            key: "list".to_string(),
+            by: Some("nosort".to_string()),
+            limit: None,
+            get: vec!["#".to_string()],
+            desc: false,
+            alpha: false,
+            store: None,
+        };
+        assert!(starred.confine(&plain).is_ok());
+    }
+
+    #[test]
+    fn test_sort_cannot_read_another_tenant() {
+        let mut executor = CommandExecutor::new();
+        let acme = TenantKeyspace::new("acme:");
+        let mut run = |tenant: Option<&TenantKeyspace>, args: &[&str]| {
+            let resp = RespValue::Array(Some(args.iter().map(|arg| bulk(arg)).collect()));
+            let cmd = Command::from_resp(&resp).unwrap();
+            let cmd = confine_command(tenant, &cmd).unwrap();
+            executor.execute(&cmd)
+        };
+        run(None, &["SET", "globex:victim:1", "secret"]);
+        run(Some(&acme), &["RPUSH", "mylist", "1"]);
+        run(Some(&acme), &["SET", "own:1", "mine"]);
+
+        let get = ["GET", "globex:victim:*", "GET", "own:*"];
+        let sort = [&["SORT", "mylist", "BY", "nosort"][..], &get].concat();
+        assert_eq!(
+            run(Some(&acme), &sort),
+            RespValue::Array(Some(vec![RespValue::BulkString(None), bulk("mine")]))
+        );
+    }
+
+    #[test]
+    fn test_unconfinable_commands_are_refused() {
+        let tenant = TenantKeyspace::new("acme:");
+        assert_eq!(
+            tenant.confine(&Command::FlushAll).unwrap_err(),
+            TENANT_COMMAND_DENIED
+        );
+        assert_eq!(
+            tenant.confine(&Command::DbSize).unwrap_err(),
+            TENANT_COMMAND_DENIED
+        );
+        assert!(tenant.confine(&Command::Ping(None)).is_ok());
+    }
+
+    #[test]
+    fn test_unconfine_reply_strips_prefix() {
+        let tenant = TenantKeyspace::new("acme:");
+
+        let keys = tenant.confine(&Command::Keys("*".to_string())).unwrap();
+        let reply = RespValue::Array(Some(vec![bulk("acme:a"), bulk("acme:b")]));
+        assert_eq!(
+            tenant.unconfine_reply(&keys, reply),
+            RespValue::Array(Some(vec![bulk("a"), bulk("b")]))
+        );
+
+        let scan = tenant
+            .confine(&Command::Scan {
+                cursor: 0,
+                pattern: Some("user:*".to_string()),
+                count: None,
+                type_filter: None,
+            })
+            .unwrap();
+        let reply = RespValue::Array(Some(vec![
+            bulk("0"),
+            RespValue::Array(Some(vec![bulk("acme:user:1")])),
+        ]));
+        assert_eq!(
+            tenant.unconfine_reply(&scan, reply),
+            RespValue::Array(Some(vec![
+                bulk("0"),
+                RespValue::Array(Some(vec![bulk("user:1")])),
+            ]))
+        );
+
+        let blpop = tenant
+            .confine(&Command::BLPop {
+                keys: vec!["q".to_string()],
+                timeout_ms: 0,
+            })
+            .unwrap();
+        let reply = RespValue::Array(Some(vec![bulk("acme:q"), bulk("acme:job")]));
+        assert_eq!(
+            tenant.unconfine_reply(&blpop, reply),
+            RespValue::Array(Some(vec![bulk("q"), bulk("acme:job")]))
+        );
+
+        let xread = tenant
+            .confine(&Command::XRead {
+                count: None,
+                block_ms: Some(0),
+                keys: vec!["s".to_string()],
+                ids: vec![None],
+            })
+            .unwrap();
+        let entries = RespValue::Array(Some(vec![]));
+        let reply = RespValue::Array(Some(vec![RespValue::Array(Some(vec![
+            bulk("acme:s"),
+            entries.clone(),
+        ]))]));
+        assert_eq!(
+            tenant.unconfine_reply(&xread, reply),
+            RespValue::Array(Some(vec![RespValue::Array(Some(vec![bulk("s"), entries]))]))
+        );
+
+        // Values are never rewritten
+        let get = tenant.confine(&Command::Get("k".to_string())).unwrap();
+        assert_eq!(tenant.unconfine_reply(&get, bulk("acme:v")), bulk("acme:v"));
+    }
+}
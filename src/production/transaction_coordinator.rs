@@ -0,0 +1,243 @@
+//! Cross-shard WATCH/MULTI/EXEC coordination
+//!
+//! A connection's watched keys and queued commands may live on different
+//! shards. The coordinator makes EXEC behave as on a single-threaded server:
+//!
+//! - WATCH registers the key on its owning shard and gets back a version
+//!   stamp. The shard bumps a watched key's version whenever a command
+//!   writes it, so a change that was reverted before EXEC still aborts it
+//! - EXEC holds every shard involved in ascending shard order, so two EXECs
+//!   never wait on each other. Once held, a shard serves only that EXEC: the
+//!   stamps are checked and the queued commands run with no other client's
+//!   command in between. A queued command without keys (DBSIZE, FLUSHALL,
+//!   KEYS, ...) holds every shard
+//! - dropping the hold releases the shards, including when the connection
+//!   goes away mid-EXEC
+//!
+//! As in Redis, a watched key that existed at WATCH time and has since
+//! expired or been evicted also aborts EXEC.
+//!
+//! Watches are reference counted per shard and released by dropping their
+//! `WatchGuard`, so UNWATCH, EXEC, DISCARD, RESET and disconnecting all
+//! unregister them the same way.
+//!
+//! # TigerStyle Invariants
+//!
+//! - A key is in a shard's table while at least one guard watches it
+//! - A version is never handed out twice on a shard
+
+use super::shard_router::ShardRouter;
+use super::sharded_actor::ShardMessage;
+use crate::redis::Command;
+use std::collections::{BTreeSet, HashMap};
+use tokio::sync::mpsc;
+
+/// A watched key's version on its shard, and whether the key existed, at
+/// WATCH time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchStamp {
+    pub(crate) version: u64,
+    pub(crate) existed: bool,
+}
+
+#[derive(Debug)]
+struct WatchEntry {
+    version: u64,
+    watchers: usize,
+}
+
+/// One shard's watched keys and their versions
+#[derive(Debug, Default)]
+pub(crate) struct WatchTable {
+    entries: HashMap<String, WatchEntry>,
+    /// Last version handed out
+    last_version: u64,
+}
+
+impl WatchTable {
+    fn next_version(&mut self) -> u64 {
+        self.last_version += 1;
+        self.last_version
+    }
+
+    /// Add a watcher of `key`; returns the key's current version
+    pub(crate) fn register(&mut self, key: &str) -> u64 {
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.watchers += 1;
+            return entry.version;
+        }
+        let version = self.next_version();
+        self.entries.insert(
+            key.to_string(),
+            WatchEntry {
+                version,
+                watchers: 1,
+            },
+        );
+        version
+    }
+
+    /// Drop a watcher of `key`, forgetting the key with its last watcher
+    pub(crate) fn release(&mut self, key: &str) {
+        let Some(entry) = self.entries.get_mut(key) else {
+            debug_assert!(false, "Precondition: releasing unwatched key '{}'", key);
+            return;
+        };
+        entry.watchers -= 1;
+        if entry.watchers == 0 {
+            self.entries.remove(key);
+        }
+    }
+
+    pub(crate) fn version(&self, key: &str) -> Option<u64> {
+        self.entries.get(key).map(|entry| entry.version)
+    }
+
+    /// `key` was written
+    pub(crate) fn touch(&mut self, key: &str) {
+        if self.entries.contains_key(key) {
+            let version = self.next_version();
+            if let Some(entry) = self.entries.get_mut(key) {
+                entry.version = version;
+            }
+        }
+    }
+
+    /// `cmd` ran on this shard: bump the keys it may have written
+    pub(crate) fn touch_written(&mut self, cmd: &Command) {
+        if self.entries.is_empty() || cmd.is_read_only() {
+            return;
+        }
+        match cmd {
+            Command::FlushDb | Command::FlushAll | Command::DebugReload => {
+                let keys: Vec<String> = self.entries.keys().cloned().collect();
+                for key in keys {
+                    self.touch(&key);
+                }
+            }
+            _ => {
+                for key in cmd.get_keys() {
+                    self.touch(&key);
+                }
+            }
+        }
+    }
+}
+
+/// One watched key, registered on its owning shard until dropped
+#[derive(Debug)]
+pub struct WatchGuard {
+    key: String,
+    shard_id: usize,
+    stamp: WatchStamp,
+    /// The owning shard's mailbox, for the release
+    tx: mpsc::UnboundedSender<ShardMessage>,
+}
+
+impl WatchGuard {
+    pub(crate) fn new(
+        key: String,
+        shard_id: usize,
+        stamp: WatchStamp,
+        tx: mpsc::UnboundedSender<ShardMessage>,
+    ) -> Self {
+        WatchGuard {
+            key,
+            shard_id,
+            stamp,
+            tx,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn shard_id(&self) -> usize {
+        self.shard_id
+    }
+
+    pub fn stamp(&self) -> WatchStamp {
+        self.stamp
+    }
+}
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        let key = std::mem::take(&mut self.key);
+        // A closed mailbox means the shard, and its table, are gone
+        let _ = self.tx.send(ShardMessage::Unwatch { key });
+    }
+}
+
+/// Shards an EXEC holds, in the order it takes them: the owners of the
+/// watched and queued keys, or every shard when a queued command has none
+pub(crate) fn shards_to_hold(
+    router: &ShardRouter,
+    watched: &[WatchGuard],
+    queued: &[Command],
+) -> Vec<usize> {
+    let mut shards: BTreeSet<usize> = watched.iter().map(WatchGuard::shard_id).collect();
+    for cmd in queued {
+        let keys = cmd.get_keys();
+        if keys.is_empty() {
+            return (0..router.num_shards()).collect();
+        }
+        shards.extend(keys.iter().map(|key| router.shard_for(key)));
+    }
+    shards.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis::SDS;
+
+    #[test]
+    fn test_writes_bump_only_watched_keys() {
+        let mut table = WatchTable::default();
+        let version = table.register("a");
+        assert_eq!(table.register("a"), version);
+
+        table.touch_written(&Command::Get("a".to_string()));
+        table.touch_written(&Command::set("b".to_string(), SDS::from_str("1")));
+        assert_eq!(table.version("a"), Some(version));
+        assert_eq!(table.version("b"), None);
+
+        table.touch_written(&Command::set("a".to_string(), SDS::from_str("1")));
+        let bumped = table.version("a").expect("still watched");
+        assert!(bumped > version);
+        table.touch_written(&Command::FlushAll);
+        assert!(table.version("a").expect("still watched") > bumped);
+    }
+
+    #[test]
+    fn test_last_release_forgets_the_key() {
+        let mut table = WatchTable::default();
+        let first = table.register("a");
+        table.register("a");
+        table.release("a");
+        assert_eq!(table.version("a"), Some(first));
+        table.release("a");
+        assert_eq!(table.version("a"), None);
+
+        // A new watch never sees an old version again
+        assert!(table.register("a") > first);
+    }
+
+    #[test]
+    fn test_keyless_commands_hold_every_shard() {
+        let router = ShardRouter::new(4);
+        let keyed = vec![
+            Command::Get("a".to_string()),
+            Command::Incr("b".to_string()),
+        ];
+        let mut expected: Vec<usize> = vec![router.shard_for("a"), router.shard_for("b")];
+        expected.sort_unstable();
+        expected.dedup();
+        assert_eq!(shards_to_hold(&router, &[], &keyed), expected);
+
+        let keyless = vec![Command::Get("a".to_string()), Command::DbSize];
+        assert_eq!(shards_to_hold(&router, &[], &keyless), vec![0, 1, 2, 3]);
+    }
+}
@@ -11,6 +11,23 @@
 //! │ (periodic eviction) │     │ (execute eviction)  │
 //! └─────────────────────┘     └─────────────────────┘
 //! ```
+//!
+//! ## Expiry outlook
+//!
+//! Every `EXPIRY_OUTLOOK_INTERVAL_MS` the actor also samples how many keys
+//! expire within the next 1m, 5m and 1h (`ExpiryOutlook`) as the gauges
+//! `ttl.expiring_keys{within:1m|5m|1h}`, next to `ttl.volatile_keys`. Each
+//! shard counts them from its deadline index, walking only the deadlines
+//! within the hour. Plotted over time they show an expiry wave coming before
+//! `ttl.evictions` spikes, e.g. with the `MetricsQuery`
+//!
+//! ```rust,ignore
+//! let wave = MetricsQuery::new("ttl.expiring_keys").by("within");
+//! // One series per horizon from a SimulatedMetrics recording
+//! let series = wave.run(&recorded);
+//! // max:redis_rust.ttl.expiring_keys{*} by {within}
+//! let widget = wave.to_datadog(&config.metric_prefix);
+//! ```
 
 use super::ShardedActorState;
 use crate::io::TimeSource;
@@ -22,6 +39,8 @@ use tracing::debug;
 
 const TTL_CHECK_INTERVAL_MS: u64 = 100;
 const SHUTDOWN_CHECK_INTERVAL_MS: u64 = 50;
+/// How often the expiry outlook is sampled
+const EXPIRY_OUTLOOK_INTERVAL_MS: u64 = 10_000;
 
 /// Messages for controlling the TtlManagerActor
 #[derive(Debug)]
@@ -65,6 +84,8 @@ pub struct TtlManagerActor<T: TimeSource> {
     interval_ms: u64,
     metrics: Arc<Metrics>,
     rx: mpsc::UnboundedReceiver<TtlMessage>,
+    /// Periodic eviction cycles run so far
+    ticks: u64,
 }
 
 impl<T: TimeSource + Clone + Send + 'static> TtlManagerActor<T> {
@@ -93,6 +114,7 @@ impl<T: TimeSource + Clone + Send + 'static> TtlManagerActor<T> {
             interval_ms,
             metrics,
             rx,
+            ticks: 0,
         };
 
         (handle, actor)
@@ -121,7 +143,8 @@ impl<T: TimeSource + Clone + Send + 'static> TtlManagerActor<T> {
     /// This loop:
     /// 1. Checks for messages (shutdown, manual tick)
     /// 2. Periodically triggers eviction on all shards
-    /// 3. Responds to shutdown within SHUTDOWN_CHECK_INTERVAL_MS
+    /// 3. Samples the expiry outlook every EXPIRY_OUTLOOK_INTERVAL_MS
+    /// 4. Responds to shutdown within SHUTDOWN_CHECK_INTERVAL_MS
     pub async fn run(mut self) {
         let mut tick_interval = interval(Duration::from_millis(self.interval_ms));
         let shutdown_check = Duration::from_millis(SHUTDOWN_CHECK_INTERVAL_MS);
@@ -150,6 +173,10 @@ impl<T: TimeSource + Clone + Send + 'static> TtlManagerActor<T> {
                 // Periodic eviction
                 _ = tick_interval.tick() => {
                     self.do_eviction().await;
+                    if self.ticks % self.outlook_every() == 0 {
+                        self.sample_expiry_outlook().await;
+                    }
+                    self.ticks += 1;
                 }
 
                 // Ensure we're responsive to shutdown even during long intervals
@@ -175,6 +202,19 @@ impl<T: TimeSource + Clone + Send + 'static> TtlManagerActor<T> {
             evicted
         );
     }
+
+    /// Eviction cycles between two expiry outlook samples
+    fn outlook_every(&self) -> u64 {
+        (EXPIRY_OUTLOOK_INTERVAL_MS / self.interval_ms).max(1)
+    }
+
+    /// Publish the expiry outlook gauges
+    async fn sample_expiry_outlook(&self) {
+        let outlook = self.state.expiry_outlook().await;
+        let points: Vec<(&str, u64)> = outlook.points().collect();
+        self.metrics
+            .record_expiry_outlook(outlook.volatile_keys, &points);
+    }
 }
 
 #[cfg(test)]
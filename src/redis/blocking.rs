@@ -0,0 +1,439 @@
+//! Blocking commands: the wakeup registry behind BLPOP, BRPOP, BLMOVE,
+//! BZPOPMIN, BZPOPMAX and XREAD/XREADGROUP BLOCK.
+//!
+//! The executor only ever runs the non-blocking half of a blocking command:
+//! it pops if a key has data and otherwise replies nil, which is also what
+//! Redis does inside MULTI and scripts. Waiting happens one layer up:
+//! - `BlockingManager`: the server-wide registry of blocked clients, keyed by
+//!   the keys they wait on. Cheap to clone; every shard executor and every
+//!   connection holds a handle to the same registry. Executors `signal` a
+//!   key whenever a push may have made it non-empty.
+//! - `BlockedClient`: one client's registration. A signal marks it ready and
+//!   wakes its task; the client re-runs its command and either gets a reply
+//!   or goes back to waiting. Dropping it unregisters.
+//!
+//! Wakeup model:
+//! - Register, then retry: a client registers *before* re-running its
+//!   command once more, so a push that lands between its first (nil) attempt
+//!   and the registration is never missed.
+//! - A signal wakes every client blocked on the key. All of them retry; the
+//!   ones that find the key empty again keep waiting.
+//! - Deadlines are not kept here. Production connections race the wakeup
+//!   against a tokio timer; the simulated `RedisServer` schedules a
+//!   `VirtualTime` timer per blocked request.
+//! - `XREAD BLOCK ... $` waits for entries added after the call, but a
+//!   retry of `$` would only ever see entries added after the retry. Drivers
+//!   pin each `$` to the stream's last ID before the first attempt
+//!   (`last_id_probes`, then `pin_last_ids`).
+//! - Waiters are ordered by registration (`BTreeSet` of increasing IDs), so
+//!   a driver that retries `ready` clients in that order serves the
+//!   longest-waiting client first, as Redis does.
+//!
+//! TigerStyle: All functions have precondition/postcondition assertions.
+
+use super::data::StreamId;
+use super::resp::RespValue;
+use super::Command;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Identifies one blocked client (one `BlockedClient`)
+pub type WaiterId = u64;
+
+/// Parse a blocking command timeout: seconds as a float, 0 = forever.
+/// Returns milliseconds.
+pub fn parse_timeout(arg: &str) -> Result<u64, String> {
+    let secs: f64 = arg
+        .parse()
+        .ok()
+        .filter(|s: &f64| s.is_finite())
+        .ok_or_else(|| "ERR timeout is not a float or out of range".to_string())?;
+    if secs < 0.0 {
+        return Err("ERR timeout is negative".to_string());
+    }
+    let millis = secs * 1000.0;
+    if millis >= u64::MAX as f64 {
+        return Err("ERR timeout is out of range".to_string());
+    }
+    Ok(millis as u64)
+}
+
+/// Parse an XREAD/XREADGROUP BLOCK timeout: integer milliseconds, 0 = forever
+pub fn parse_block_ms(arg: &str) -> Result<u64, String> {
+    let ms: i64 = arg
+        .parse()
+        .map_err(|_| "ERR timeout is not an integer or out of range".to_string())?;
+    u64::try_from(ms).map_err(|_| "ERR timeout is negative".to_string())
+}
+
+/// For an XREAD BLOCK with `$` IDs: one `XREVRANGE key + - COUNT 1` per `$`
+/// stream, in key order. Entries are never deleted, so the newest entry's
+/// ID is the stream's last ID. Empty for every other command.
+pub fn last_id_probes(cmd: &Command) -> Vec<Command> {
+    match cmd {
+        Command::XRead {
+            block_ms: Some(_),
+            keys,
+            ids,
+            ..
+        } => keys
+            .iter()
+            .zip(ids)
+            .filter(|(_, id)| id.is_none())
+            .map(|(key, _)| Command::XRevRange {
+                key: key.clone(),
+                start: StreamId::MIN,
+                end: StreamId::MAX,
+                count: Some(1),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Replace each `$` in `cmd` with the last ID reported by the matching
+/// probe reply (`0-0` for a missing or empty stream)
+pub fn pin_last_ids(cmd: &mut Command, probe_replies: &[RespValue]) {
+    let Command::XRead { ids, .. } = cmd else {
+        return;
+    };
+    debug_assert_eq!(
+        ids.iter().filter(|id| id.is_none()).count(),
+        probe_replies.len(),
+        "Precondition: one probe reply per `$`"
+    );
+
+    let mut replies = probe_replies.iter();
+    for id in ids.iter_mut().filter(|id| id.is_none()) {
+        *id = Some(replies.next().map_or(StreamId::MIN, newest_id));
+    }
+
+    debug_assert!(
+        ids.iter().all(Option::is_some),
+        "Postcondition: no `$` left after pinning"
+    );
+}
+
+/// ID of the single entry in an XREVRANGE reply
+fn newest_id(reply: &RespValue) -> StreamId {
+    let RespValue::Array(Some(entries)) = reply else {
+        return StreamId::MIN;
+    };
+    match entries.first() {
+        Some(RespValue::Array(Some(entry))) => match entry.first() {
+            Some(RespValue::BulkString(Some(id))) => std::str::from_utf8(id)
+                .ok()
+                .and_then(|id| StreamId::parse(id, 0))
+                .unwrap_or(StreamId::MIN),
+            _ => StreamId::MIN,
+        },
+        _ => StreamId::MIN,
+    }
+}
+
+#[derive(Debug)]
+struct Waiter {
+    keys: Vec<String>,
+    ready: bool,
+    notify: Arc<Notify>,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// key -> clients blocked on it; keys with no waiters are removed
+    keys: BTreeMap<String, BTreeSet<WaiterId>>,
+    waiters: BTreeMap<WaiterId, Waiter>,
+    next_id: WaiterId,
+}
+
+impl Registry {
+    /// Verify all invariants hold for this registry
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        // Invariant 1: Every indexed waiter is live and waits on that key
+        for (key, ids) in &self.keys {
+            debug_assert!(
+                !ids.is_empty(),
+                "Invariant violated: empty waiter set for '{}'",
+                key
+            );
+            for id in ids {
+                debug_assert!(
+                    self.waiters.get(id).is_some_and(|w| w.keys.contains(key)),
+                    "Invariant violated: waiter {} indexed under '{}' but not blocked on it",
+                    id,
+                    key
+                );
+            }
+        }
+
+        // Invariant 2: Every live waiter is indexed under each of its keys
+        for (id, waiter) in &self.waiters {
+            for key in &waiter.keys {
+                debug_assert!(
+                    self.keys.get(key).is_some_and(|ids| ids.contains(id)),
+                    "Invariant violated: waiter {} not indexed under '{}'",
+                    id,
+                    key
+                );
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline(always)]
+    fn verify_invariants(&self) {}
+}
+
+/// Server-wide registry of clients blocked on keys
+///
+/// Clones share the same registry, like `PubSubManager`.
+#[derive(Debug, Clone, Default)]
+pub struct BlockingManager {
+    inner: Arc<Mutex<Registry>>,
+    /// Live waiters, readable without the lock so `signal` is free when
+    /// nobody is blocked (the common case for every push)
+    blocked: Arc<AtomicUsize>,
+}
+
+impl BlockingManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a client blocked on `keys` (deduplicated, order kept)
+    pub fn block(&self, keys: &[String]) -> BlockedClient {
+        debug_assert!(
+            !keys.is_empty(),
+            "Precondition: a blocked client waits on at least one key"
+        );
+
+        let mut unique: Vec<String> = Vec::with_capacity(keys.len());
+        for key in keys {
+            if !unique.contains(key) {
+                unique.push(key.clone());
+            }
+        }
+
+        let notify = Arc::new(Notify::new());
+        let mut registry = self.inner.lock();
+        let id = registry.next_id;
+        registry.next_id += 1;
+        for key in &unique {
+            registry.keys.entry(key.clone()).or_default().insert(id);
+        }
+        registry.waiters.insert(
+            id,
+            Waiter {
+                keys: unique,
+                ready: false,
+                notify: notify.clone(),
+            },
+        );
+        self.blocked
+            .store(registry.waiters.len(), Ordering::Release);
+
+        registry.verify_invariants();
+        BlockedClient {
+            manager: self.clone(),
+            id,
+            notify,
+        }
+    }
+
+    /// A push may have made `key` non-empty: wake every client blocked on it.
+    /// Returns the number of clients woken.
+    pub fn signal(&self, key: &str) -> usize {
+        if self.blocked.load(Ordering::Acquire) == 0 {
+            return 0;
+        }
+        let mut registry = self.inner.lock();
+        let Registry { keys, waiters, .. } = &mut *registry;
+        let Some(ids) = keys.get(key) else {
+            return 0;
+        };
+        for id in ids {
+            if let Some(waiter) = waiters.get_mut(id) {
+                waiter.ready = true;
+                waiter.notify.notify_one();
+            }
+        }
+        ids.len()
+    }
+
+    /// Number of blocked clients (INFO `blocked_clients`)
+    pub fn num_blocked(&self) -> usize {
+        self.blocked.load(Ordering::Acquire)
+    }
+
+    /// Number of clients blocked on `key`
+    pub fn num_blocked_on(&self, key: &str) -> usize {
+        self.inner.lock().keys.get(key).map_or(0, BTreeSet::len)
+    }
+
+    fn take_ready(&self, id: WaiterId) -> bool {
+        let mut registry = self.inner.lock();
+        match registry.waiters.get_mut(&id) {
+            Some(waiter) => std::mem::take(&mut waiter.ready),
+            None => false,
+        }
+    }
+
+    fn remove(&self, id: WaiterId) {
+        let mut registry = self.inner.lock();
+        if let Some(waiter) = registry.waiters.remove(&id) {
+            for key in &waiter.keys {
+                if let Some(ids) = registry.keys.get_mut(key) {
+                    ids.remove(&id);
+                    if ids.is_empty() {
+                        registry.keys.remove(key);
+                    }
+                }
+            }
+        }
+        self.blocked
+            .store(registry.waiters.len(), Ordering::Release);
+
+        debug_assert!(
+            !registry.waiters.contains_key(&id),
+            "Postcondition: removed waiter must be gone"
+        );
+        registry.verify_invariants();
+    }
+}
+
+/// One client blocked on a set of keys.
+///
+/// Dropping it unregisters the client.
+#[derive(Debug)]
+pub struct BlockedClient {
+    manager: BlockingManager,
+    id: WaiterId,
+    notify: Arc<Notify>,
+}
+
+impl BlockedClient {
+    pub fn id(&self) -> WaiterId {
+        self.id
+    }
+
+    /// Whether one of the keys was signalled since the last check; clears
+    /// the flag. Simulation drivers poll this instead of awaiting `ready`.
+    pub fn take_ready(&self) -> bool {
+        self.manager.take_ready(self.id)
+    }
+
+    /// Wait until one of the keys is signalled. A signal sent before the
+    /// call is not lost.
+    pub async fn ready(&self) {
+        self.notify.notified().await;
+        self.take_ready();
+    }
+}
+
+impl Drop for BlockedClient {
+    fn drop(&mut self) {
+        self.manager.remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_signal_wakes_only_clients_on_that_key() {
+        let manager = BlockingManager::new();
+        let a = manager.block(&keys(&["q1", "q2", "q1"]));
+        let b = manager.block(&keys(&["q2"]));
+        assert_eq!(manager.num_blocked(), 2);
+        assert_eq!(manager.num_blocked_on("q1"), 1);
+
+        assert_eq!(manager.signal("q1"), 1);
+        assert_eq!(manager.signal("other"), 0);
+        assert!(a.take_ready());
+        assert!(!a.take_ready());
+        assert!(!b.take_ready());
+
+        drop(a);
+        assert_eq!(manager.num_blocked(), 1);
+        assert_eq!(manager.num_blocked_on("q1"), 0);
+        assert_eq!(manager.signal("q2"), 1);
+        assert!(b.take_ready());
+    }
+
+    #[tokio::test]
+    async fn test_signal_before_wait_is_not_lost() {
+        let manager = BlockingManager::new();
+        let client = manager.block(&keys(&["q"]));
+        manager.signal("q");
+        tokio::time::timeout(std::time::Duration::from_secs(1), client.ready())
+            .await
+            .expect("stored wakeup must complete ready()");
+    }
+
+    #[test]
+    fn test_pin_last_ids_replaces_only_dollar() {
+        let mut cmd = Command::XRead {
+            count: None,
+            block_ms: Some(0),
+            keys: keys(&["a", "b", "c"]),
+            ids: vec![None, Some(StreamId::new(5, 0)), None],
+        };
+        let probes = last_id_probes(&cmd);
+        assert_eq!(probes.len(), 2);
+        assert_eq!(probes[0].get_primary_key(), Some("a"));
+
+        let entry = RespValue::Array(Some(vec![RespValue::Array(Some(vec![
+            RespValue::BulkString(Some(b"7-1".to_vec())),
+            RespValue::Array(Some(vec![])),
+        ]))]));
+        pin_last_ids(&mut cmd, &[entry, RespValue::Array(Some(vec![]))]);
+        let Command::XRead { ids, .. } = &cmd else {
+            unreachable!()
+        };
+        assert_eq!(
+            ids,
+            &vec![
+                Some(StreamId::new(7, 1)),
+                Some(StreamId::new(5, 0)),
+                Some(StreamId::MIN)
+            ]
+        );
+        assert!(last_id_probes(&cmd).is_empty());
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("0"), Ok(0));
+        assert_eq!(parse_timeout("1.5"), Ok(1500));
+        assert_eq!(
+            parse_timeout("-1"),
+            Err("ERR timeout is negative".to_string())
+        );
+        assert_eq!(
+            parse_timeout("soon"),
+            Err("ERR timeout is not a float or out of range".to_string())
+        );
+        assert_eq!(
+            parse_timeout("inf"),
+            Err("ERR timeout is not a float or out of range".to_string())
+        );
+        assert_eq!(parse_block_ms("250"), Ok(250));
+        assert_eq!(
+            parse_block_ms("-5"),
+            Err("ERR timeout is negative".to_string())
+        );
+        assert_eq!(
+            parse_block_ms("0.5"),
+            Err("ERR timeout is not an integer or out of range".to_string())
+        );
+    }
+}
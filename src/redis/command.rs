@@ -6,13 +6,15 @@
 //! Parsing logic is in `parser.rs` and `parser_zero_copy.rs`.
 //! Execution logic is in the `executor/` module.
 
-use super::data::SDS;
+use super::data::{ClaimOptions, PendingRange, StreamId, StreamIdSpec, SDS};
+use super::declared_commands::DeclaredCommand;
 
 /// Represents a Redis command parsed from RESP protocol.
 ///
 /// # Categories
 ///
 /// - **String commands**: GET, SET, APPEND, etc.
+/// - **Bitmap commands**: SETBIT, GETBIT, BITCOUNT, BITPOS, BITOP, BITFIELD
 /// - **Counter commands**: INCR, DECR, INCRBY, DECRBY
 /// - **Key commands**: DEL, EXISTS, TYPE, KEYS, FLUSHDB, FLUSHALL
 /// - **Expiration commands**: EXPIRE, EXPIREAT, TTL, PTTL, PERSIST
@@ -20,11 +22,14 @@ use super::data::SDS;
 /// - **Set commands**: SADD, SREM, SMEMBERS, SISMEMBER, SCARD, SPOP
 /// - **Hash commands**: HSET, HGET, HDEL, HGETALL, HKEYS, HVALS, etc.
 /// - **Sorted set commands**: ZADD, ZREM, ZRANGE, ZREVRANGE, ZSCORE, etc.
+/// - **Stream commands**: XADD, XLEN, XRANGE, XREVRANGE, XREAD
+/// - **Consumer groups**: XGROUP, XREADGROUP, XACK, XPENDING, XCLAIM, XAUTOCLAIM
 /// - **Scan commands**: SCAN, HSCAN, ZSCAN
 /// - **Transaction commands**: MULTI, EXEC, DISCARD, WATCH, UNWATCH
-/// - **Script commands**: EVAL, EVALSHA, SCRIPT LOAD/EXISTS/FLUSH
+/// - **Script commands**: EVAL, EVALSHA, EVAL_RO, EVALSHA_RO, SCRIPT LOAD/EXISTS/FLUSH/KILL
+/// - **Function commands**: FUNCTION LOAD/DELETE/FLUSH/LIST/DUMP/RESTORE/STATS, FCALL, FCALL_RO
 /// - **Server commands**: INFO, PING, DBSIZE
-/// - **Auth/ACL commands**: AUTH, ACL WHOAMI/LIST/USERS/GETUSER/SETUSER/DELUSER/CAT/GENPASS
+/// - **Auth/ACL commands**: AUTH, HELLO, ACL WHOAMI/LIST/USERS/GETUSER/SETUSER/DELUSER/CAT/GENPASS
 #[derive(Debug, Clone)]
 pub enum Command {
     // String commands
@@ -60,6 +65,34 @@ pub enum Command {
     SetBit(String, u64, u8),
     /// GETBIT key offset
     GetBit(String, u64),
+    /// BITCOUNT key [start end [BYTE|BIT]]
+    BitCount {
+        key: String,
+        range: Option<BitRange>,
+    },
+    /// BITPOS key bit [start [end [BYTE|BIT]]]
+    BitPos {
+        key: String,
+        bit: u8,
+        range: Option<BitRange>,
+    },
+    /// BITOP AND|OR|XOR|NOT destkey key [key ...]
+    BitOp {
+        op: BitOperation,
+        dest: String,
+        keys: Vec<String>,
+    },
+    /// BITFIELD key [GET type offset] [SET type offset value]
+    /// [INCRBY type offset increment] [OVERFLOW WRAP|SAT|FAIL] ...
+    BitField {
+        key: String,
+        ops: Vec<BitFieldOp>,
+    },
+    /// BITFIELD_RO key [GET type offset] ...
+    BitFieldRo {
+        key: String,
+        ops: Vec<BitFieldOp>,
+    },
     /// GETEX key [EX s|PX ms|EXAT t|PXAT t|PERSIST]
     GetEx {
         key: String,
@@ -80,7 +113,11 @@ pub enum Command {
     IncrByFloat(String, f64),
     // Key commands
     Del(Vec<String>),
+    /// UNLINK key [key ...] - DEL that frees large values in the background
+    Unlink(Vec<String>),
     Exists(Vec<String>),
+    /// TOUCH key [key ...] - refresh the access time of existing keys
+    Touch(Vec<String>),
     TypeOf(String),
     Keys(String),
     FlushDb,
@@ -94,7 +131,6 @@ pub enum Command {
         gt: bool,
         lt: bool,
     },
-    ExpireAt(String, i64),
     PExpire {
         key: String,
         milliseconds: i64,
@@ -103,22 +139,28 @@ pub enum Command {
         gt: bool,
         lt: bool,
     },
-    PExpireAt(String, i64),
     Ttl(String),
     Pttl(String),
-    /// EXPIRETIME key - returns Unix timestamp (seconds) when key will expire
-    ExpireTime(String),
-    /// PEXPIRETIME key - returns Unix timestamp (milliseconds) when key will expire
-    PExpireTime(String),
     Persist(String),
     // Server commands (stubs)
     /// WAIT numreplicas timeout
     Wait(i64, i64),
+    /// SHUTDOWN [NOSAVE|SAVE] [NOW] [FORCE], answered by the server
+    /// connection; `save` is None when neither was given
+    Shutdown {
+        save: Option<bool>,
+    },
     /// TIME - returns [seconds, microseconds]
     Time,
-    /// SORT key [STORE dest] ... - minimal stub
+    /// SORT key [BY pattern] [LIMIT offset count] [GET pattern ...] [ASC|DESC] [ALPHA]
+    /// [STORE destination]
     Sort {
         key: String,
+        by: Option<String>,
+        limit: Option<(i64, i64)>,
+        get: Vec<String>,
+        desc: bool,
+        alpha: bool,
         store: Option<String>,
     },
     // List commands
@@ -138,6 +180,39 @@ pub enum Command {
         wherefrom: String, // LEFT or RIGHT
         whereto: String,   // LEFT or RIGHT
     },
+    LInsert {
+        key: String,
+        before: bool,
+        pivot: SDS,
+        value: SDS,
+    },
+    LRem(String, isize, SDS), // key, count, element
+    /// LPOS key element [RANK rank] [COUNT num] [MAXLEN len]
+    LPos {
+        key: String,
+        element: SDS,
+        rank: i64,
+        count: Option<usize>,
+        maxlen: usize,
+    },
+    LPushX(String, Vec<SDS>),
+    RPushX(String, Vec<SDS>),
+    // Blocking list commands; timeout in milliseconds, 0 = block forever
+    BLPop {
+        keys: Vec<String>,
+        timeout_ms: u64,
+    },
+    BRPop {
+        keys: Vec<String>,
+        timeout_ms: u64,
+    },
+    BLMove {
+        source: String,
+        dest: String,
+        wherefrom: String, // LEFT or RIGHT
+        whereto: String,   // LEFT or RIGHT
+        timeout_ms: u64,
+    },
     // Set commands
     SAdd(String, Vec<SDS>),
     SRem(String, Vec<SDS>),
@@ -145,16 +220,46 @@ pub enum Command {
     SIsMember(String, SDS),
     SCard(String),
     SPop(String, Option<usize>), // SPOP key [count]
+    SMIsMember(String, Vec<SDS>),
+    SRandMember(String, Option<i64>), // SRANDMEMBER key [count]
+    /// SINTERCARD numkeys key [key ...] [LIMIT limit] (limit 0 = no limit)
+    SInterCard {
+        keys: Vec<String>,
+        limit: usize,
+    },
     // Hash commands
     HSet(String, Vec<(SDS, SDS)>),
     HGet(String, SDS),
     HDel(String, Vec<SDS>),
     HGetAll(String),
-    HKeys(String),
-    HVals(String),
     HLen(String),
     HExists(String, SDS),
     HIncrBy(String, SDS, i64),
+    HIncrByFloat(String, SDS, f64),
+    HMGet(String, Vec<SDS>),
+    /// `count: None` replies one field (nil for a missing key)
+    HRandField {
+        key: String,
+        count: Option<i64>,
+        with_values: bool,
+    },
+    /// HEXPIRE/HPEXPIRE/HEXPIREAT/HPEXPIREAT key time [NX|XX|GT|LT]
+    /// FIELDS numfields field [field ...]
+    HExpire {
+        key: String,
+        time: i64,
+        form: FieldTtlForm,
+        condition: Option<ExpireCondition>,
+        fields: Vec<SDS>,
+    },
+    /// HTTL/HPTTL/HEXPIRETIME/HPEXPIRETIME key FIELDS numfields field [field ...]
+    HTtl {
+        key: String,
+        form: FieldTtlForm,
+        fields: Vec<SDS>,
+    },
+    /// HPERSIST key FIELDS numfields field [field ...]
+    HPersist(String, Vec<SDS>),
     // Sorted set commands
     /// ZADD with optional NX/XX/GT/LT/CH flags
     ZAdd {
@@ -167,10 +272,19 @@ pub enum Command {
         ch: bool, // Return number of elements changed (not just added)
     },
     ZRem(String, Vec<SDS>),
-    ZRange(String, isize, isize, bool), // bool = WITHSCORES
+    /// ZREMRANGEBYRANK/ZREMRANGEBYSCORE/ZREMRANGEBYLEX
+    ZRemRange(String, ZRangeSpec),
+    /// Unified ZRANGE, or ZRANGESTORE when `dest` is set
+    ZRange {
+        dest: Option<String>,
+        key: String,
+        range: ZRangeSpec,
+        rev: bool,
+        limit: Option<(i64, i64)>, // offset, count (negative = all)
+        with_scores: bool,
+    },
     ZRevRange(String, isize, isize, bool), // bool = WITHSCORES
     ZScore(String, SDS),
-    ZRank(String, SDS),
     ZCard(String),
     ZCount(String, String, String), // key, min, max (strings to support -inf, +inf, exclusive)
     ZRangeByScore {
@@ -180,11 +294,146 @@ pub enum Command {
         with_scores: bool,
         limit: Option<(isize, usize)>, // offset, count
     },
+    /// `count: None` replies one flat `[member, score]` pair at most
+    ZPopMin {
+        key: String,
+        count: Option<usize>,
+    },
+    ZPopMax {
+        key: String,
+        count: Option<usize>,
+    },
+    ZIncrBy(String, f64, SDS), // key, increment, member
+    ZMScore(String, Vec<SDS>),
+    /// `count: None` replies one member (nil for a missing key)
+    ZRandMember {
+        key: String,
+        count: Option<i64>,
+        with_scores: bool,
+    },
+    /// ZUNION/ZINTER/ZDIFF numkeys key [key ...] [WEIGHTS w ...]
+    /// [AGGREGATE SUM|MIN|MAX] [WITHSCORES], or the STORE form when `dest`
+    /// is set. `weights` has one entry per key; ZDIFF takes neither option.
+    ZSetOp {
+        op: ZSetOperation,
+        dest: Option<String>,
+        keys: Vec<String>,
+        weights: Option<Vec<f64>>,
+        aggregate: ZAggregate,
+        with_scores: bool,
+    },
+    // Blocking sorted set commands; timeout in milliseconds, 0 = block forever
+    BZPopMin {
+        keys: Vec<String>,
+        timeout_ms: u64,
+    },
+    BZPopMax {
+        keys: Vec<String>,
+        timeout_ms: u64,
+    },
+    // Stream commands
+    XAdd {
+        key: String,
+        id: StreamIdSpec,
+        fields: Vec<(SDS, SDS)>,
+        nomkstream: bool,
+        maxlen: Option<usize>,
+    },
+    /// Inclusive bounds; exclusive `(` bounds are resolved by the parser
+    XRange {
+        key: String,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    },
+    /// Same bounds as XRange (`start <= end`), entries returned newest first
+    XRevRange {
+        key: String,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    },
+    /// One ID per key; `None` is `$` (only entries added after the call).
+    /// `block_ms`: BLOCK timeout in milliseconds, 0 = block forever.
+    XRead {
+        count: Option<usize>,
+        block_ms: Option<u64>,
+        keys: Vec<String>,
+        ids: Vec<Option<StreamId>>,
+    },
+    // Stream consumer groups
+    /// XGROUP CREATE; `id: None` is `$`
+    XGroupCreate {
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+        mkstream: bool,
+    },
+    /// XGROUP SETID; `id: None` is `$`
+    XGroupSetId {
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+    },
+    XGroupDestroy {
+        key: String,
+        group: String,
+    },
+    XGroupCreateConsumer {
+        key: String,
+        group: String,
+        consumer: String,
+    },
+    XGroupDelConsumer {
+        key: String,
+        group: String,
+        consumer: String,
+    },
+    /// One ID per key; `None` is `>` (entries never delivered to the group)
+    XReadGroup {
+        group: String,
+        consumer: String,
+        count: Option<usize>,
+        block_ms: Option<u64>,
+        noack: bool,
+        keys: Vec<String>,
+        ids: Vec<Option<StreamId>>,
+    },
+    XAck {
+        key: String,
+        group: String,
+        ids: Vec<StreamId>,
+    },
+    /// Summary form when `range` is `None`
+    XPending {
+        key: String,
+        group: String,
+        range: Option<PendingRange>,
+    },
+    XClaim {
+        key: String,
+        group: String,
+        consumer: String,
+        min_idle_ms: u64,
+        ids: Vec<StreamId>,
+        opts: ClaimOptions,
+    },
+    XAutoClaim {
+        key: String,
+        group: String,
+        consumer: String,
+        min_idle_ms: u64,
+        start: StreamId,
+        count: usize,
+        justid: bool,
+    },
     // Scan commands
     Scan {
         cursor: u64,
         pattern: Option<String>,
         count: Option<usize>,
+        /// TYPE filter: only keys whose TYPE is this name
+        type_filter: Option<String>,
     },
     HScan {
         key: String,
@@ -204,6 +453,9 @@ pub enum Command {
     Discard,
     Watch(Vec<String>),
     Unwatch,
+    /// RESET: discard MULTI, unwatch, unsubscribe, stop tracking and
+    /// deauthenticate, as if the connection were new
+    Reset,
     // Script commands
     Eval {
         script: String,
@@ -215,15 +467,26 @@ pub enum Command {
         keys: Vec<String>,
         args: Vec<SDS>,
     },
+    /// EVAL_RO - EVAL for a script that only reads; redis.call refuses writes
+    EvalRo {
+        script: String,
+        keys: Vec<String>,
+        args: Vec<SDS>,
+    },
+    /// EVALSHA_RO - EVALSHA for a script that only reads
+    EvalShaRo {
+        sha1: String,
+        keys: Vec<String>,
+        args: Vec<SDS>,
+    },
     /// SCRIPT LOAD command - loads script and returns SHA1
     ScriptLoad(String),
     /// SCRIPT EXISTS command - checks if scripts exist by SHA1
     ScriptExists(Vec<String>),
     /// SCRIPT FLUSH command - clears script cache
     ScriptFlush,
-    // String commands (legacy)
-    /// SETNX key value - legacy command returning Integer(1)/Integer(0)
-    SetNx(String, SDS),
+    /// SCRIPT KILL command - stops a busy script that has not written
+    ScriptKill,
     // Server commands
     Info,
     Ping(Option<SDS>),
@@ -234,6 +497,13 @@ pub enum Command {
         username: Option<String>,
         password: String,
     },
+    /// HELLO [protover [AUTH username password] [SETNAME clientname]],
+    /// answered by the connection
+    Hello {
+        protover: Option<i64>,
+        auth: Option<(String, String)>,
+        setname: Option<String>,
+    },
     /// ACL WHOAMI
     AclWhoami,
     /// ACL LIST
@@ -274,42 +544,323 @@ pub enum Command {
     /// ACL LOG RESET
     AclLogReset,
     // CONFIG commands
-    ConfigGet(String),
+    /// CONFIG GET pattern [pattern ...]
+    ConfigGet(Vec<String>),
     ConfigSet(String, String),
     ConfigResetStat,
+    // LATENCY monitor (see latency.rs)
+    LatencyLatest,
+    /// LATENCY HISTORY event
+    LatencyHistory(String),
+    /// LATENCY RESET [event ...]; no events means every event
+    LatencyReset(Vec<String>),
+    LatencyDoctor,
+    LatencyHelp,
     // SELECT command
     Select(u64),
     // ECHO command
     Echo(SDS),
-    // COMMAND command (for Tcl test harness compatibility)
-    CommandCommand,   // COMMAND / COMMAND COUNT / COMMAND DOCS etc. - stub
+    /// LOLWUT [VERSION version] [param ...]
+    Lolwut {
+        version: Option<i64>,
+        params: Vec<i64>,
+    },
+    // Pub/Sub
+    Publish {
+        channel: String,
+        message: SDS,
+    },
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    PSubscribe(Vec<String>),
+    PUnsubscribe(Vec<String>),
+    PubSubChannels(Option<String>),
+    PubSubNumSub(Vec<String>),
+    PubSubNumPat,
+    // COMMAND introspection, answered from the command table
+    CommandCommand, // COMMAND: info for every command
     CommandCount,
-    // FUNCTION FLUSH (for Tcl test harness compatibility)
+    /// COMMAND INFO [name ...]; no names means every command
+    CommandInfo(Vec<String>),
+    /// COMMAND DOCS [name ...]; no names means every command
+    CommandDocs(Vec<String>),
+    /// COMMAND GETKEYS command [arg ...]
+    CommandGetKeys(Vec<String>),
+    /// FUNCTION LOAD [REPLACE] code - registers a library's functions
+    FunctionLoad {
+        code: String,
+        replace: bool,
+    },
+    /// FUNCTION DELETE library
+    FunctionDelete(String),
+    /// FUNCTION FLUSH [ASYNC|SYNC] - deletes every library
     FunctionFlush,
+    /// FUNCTION LIST [LIBRARYNAME pattern] [WITHCODE]
+    FunctionList {
+        pattern: Option<String>,
+        with_code: bool,
+    },
+    /// FUNCTION DUMP - every library as a FUNCTION RESTORE payload
+    FunctionDump,
+    /// FUNCTION RESTORE payload [FLUSH|APPEND|REPLACE]
+    FunctionRestore {
+        payload: Vec<u8>,
+        policy: FunctionRestorePolicy,
+    },
+    /// FUNCTION STATS
+    FunctionStats,
+    /// FCALL function numkeys [key ...] [arg ...]
+    FCall {
+        function: String,
+        keys: Vec<String>,
+        args: Vec<SDS>,
+    },
+    /// FCALL_RO - FCALL of a function flagged `no-writes`
+    FCallRo {
+        function: String,
+        keys: Vec<String>,
+        args: Vec<SDS>,
+    },
     // CLIENT command stubs (for Tcl test harness compatibility)
     ClientSetName(String),
     ClientGetName,
     ClientId,
     ClientInfo,
+    // CLIENT connection management, answered by the connection from its
+    // session registry
+    ClientList,
+    /// CLIENT KILL addr (`legacy`), or CLIENT KILL with ID/ADDR/USER/SKIPME
+    /// filters, all of which must match
+    ClientKill {
+        id: Option<u64>,
+        addr: Option<String>,
+        user: Option<String>,
+        skip_me: bool,
+        legacy: bool,
+    },
+    /// CLIENT PAUSE timeout [WRITE|ALL]
+    ClientPause {
+        timeout_ms: u64,
+        writes_only: bool,
+    },
+    ClientUnpause,
+    /// CLIENT TRACKING ON|OFF [BCAST] [PREFIX prefix ...]
+    ClientTracking {
+        on: bool,
+        bcast: bool,
+        prefixes: Vec<String>,
+    },
     // OBJECT command stubs
     ObjectHelp,
     ObjectEncoding(String),
     ObjectRefCount(String),
     ObjectIdleTime(String),
     ObjectFreq(String),
-    // DEBUG command stubs
+    // MEMORY (see executor/memory_ops.rs)
+    /// MEMORY USAGE key [SAMPLES count]; 0 samples every element, `None`
+    /// the default
+    MemoryUsage {
+        key: String,
+        samples: Option<usize>,
+    },
+    MemoryStats,
+    MemoryDoctor,
+    MemoryHelp,
+    /// DEBUG SLEEP <seconds> - block for the given (virtual) time
     DebugSleep(f64),
+    /// DEBUG SET-ACTIVE-EXPIRE <0|1> - switch the active expiry cycle off or on
+    DebugSetActiveExpire(bool),
+    /// DEBUG RELOAD - save the keyspace as an RDB file and load it back
+    DebugReload,
+    /// DEBUG subcommands accepted for compatibility and answered with OK
     DebugSet(String, String),
+    /// DEBUG OBJECT <key> - encoding, serialized length and LRU of a value
     DebugObject(String),
+    /// DEBUG KEYSTATS - per-type counts, sizes and TTL histogram
+    DebugKeyStats,
+    /// DEBUG STRINGMATCH-LEN - fuzz the glob matcher against its reference
+    DebugStringMatchLen,
+    /// DEBUG PROTOCOL <type> - a sample reply of a RESP3 type (lowercased),
+    /// sent in the connection's protocol
+    DebugProtocol(String),
+    /// DEBUG HEALTH - component statuses (persistence, WAL, replication, event loop)
+    DebugHealth,
+    /// DEBUG SNAPSHOT-READ <argc> <arg>... - read-only commands evaluated
+    /// against one snapshot of the keyspace
+    DebugSnapshotRead(Vec<Command>),
+    /// DEBUG BUGGIFY SET <fault> <prob> - change a fault probability mid-run (simulation builds)
+    DebugBuggifySet {
+        fault: String,
+        probability: f64,
+    },
+    /// DEBUG BUGGIFY STATS - per-fault checks, triggers and probability (simulation builds)
+    DebugBuggifyStats,
     // RANDOMKEY
     RandomKey,
     // RENAME
     Rename(String, String),
     RenameNx(String, String),
+    /// COPY source destination [REPLACE]
+    Copy {
+        source: String,
+        dest: String,
+        replace: bool,
+    },
+    /// RESTORE key ttl payload [REPLACE] [ABSTTL] [IDLETIME s] [FREQ f]
+    Restore {
+        key: String,
+        /// Milliseconds to live, or with `absttl` a Unix-ms deadline; 0 for none
+        ttl_ms: u64,
+        payload: Vec<u8>,
+        replace: bool,
+        absttl: bool,
+        idletime: Option<u64>,
+        freq: Option<u8>,
+    },
+    /// Fixed-argument commands defined in `declared_commands.rs`
+    Declared(DeclaredCommand),
     // OBJECT
     Unknown(String),
 }
 
+/// What FUNCTION RESTORE does with libraries already loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FunctionRestorePolicy {
+    /// Delete every library first
+    Flush,
+    /// Refuse the payload if a library exists (the default)
+    #[default]
+    Append,
+    /// Replace libraries of the same name
+    Replace,
+}
+
+/// Unit of a BITCOUNT/BITPOS range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitUnit {
+    Byte,
+    Bit,
+}
+
+/// Inclusive BITCOUNT/BITPOS range; negative positions count from the end.
+/// `end` is `None` only for BITPOS without an explicit end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitRange {
+    pub start: i64,
+    pub end: Option<i64>,
+    pub unit: BitUnit,
+}
+
+/// BITOP operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// Condition an expiry update must meet (EXPIRE-style NX/XX/GT/LT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireCondition {
+    /// Only when there is no TTL yet
+    Nx,
+    /// Only when there is a TTL already
+    Xx,
+    /// Only when the new deadline is later (no TTL counts as never)
+    Gt,
+    /// Only when the new deadline is earlier
+    Lt,
+}
+
+/// Which member of a hash field TTL family a command is: the unit of its
+/// time, and whether that time is a Unix timestamp rather than relative
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldTtlForm {
+    pub millis: bool,
+    pub absolute: bool,
+}
+
+/// ZUNION/ZINTER/ZDIFF operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZSetOperation {
+    Union,
+    Inter,
+    Diff,
+}
+
+/// How ZUNION/ZINTER combine the weighted scores of a member
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZAggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+/// What a unified ZRANGE selects. Score and lex bounds are stored as
+/// (min, max) even though REV takes them max first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZRangeSpec {
+    /// Start and stop indexes, negative from the end
+    Rank(isize, isize),
+    /// Score bounds such as `1`, `(1`, `-inf`
+    Score(String, String),
+    /// Member bounds such as `[a`, `(a`, `-`, `+`
+    Lex(String, String),
+}
+
+/// BITFIELD integer type: `i1`..`i64` or `u1`..`u63`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitFieldType {
+    pub signed: bool,
+    pub bits: u8,
+}
+
+impl BitFieldType {
+    /// Parse `i<bits>` / `u<bits>`; unsigned fields stop at 63 bits so
+    /// every value fits in an integer reply
+    pub fn parse(s: &str) -> Option<Self> {
+        let (signed, bits) = match s.as_bytes().first()? {
+            b'i' | b'I' => (true, &s[1..]),
+            b'u' | b'U' => (false, &s[1..]),
+            _ => return None,
+        };
+        let bits: u8 = bits.parse().ok()?;
+        let max_bits = if signed { 64 } else { 63 };
+        (1..=max_bits).contains(&bits).then_some(BitFieldType { signed, bits })
+    }
+}
+
+/// BITFIELD overflow policy for SET and INCRBY
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOverflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+/// One BITFIELD sub-operation; `offset` is in bits, with `#N` already
+/// multiplied out by the type width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOp {
+    Get {
+        ty: BitFieldType,
+        offset: u64,
+    },
+    Set {
+        ty: BitFieldType,
+        offset: u64,
+        value: i64,
+    },
+    IncrBy {
+        ty: BitFieldType,
+        offset: u64,
+        increment: i64,
+    },
+    /// Applies to the SET and INCRBY sub-operations that follow it
+    Overflow(BitFieldOverflow),
+}
+
 impl Command {
     // =========================================================================
     // Helper Constructors
@@ -349,7 +900,7 @@ impl Command {
 
     /// Helper constructor for SETNX (legacy command returning Integer)
     pub fn setnx(key: String, value: SDS) -> Self {
-        Command::SetNx(key, value)
+        Command::Declared(DeclaredCommand::SetNx { key, value })
     }
 
     /// Helper constructor for basic EXPIRE (no flags)
@@ -375,86 +926,180 @@ impl Command {
 
     /// Returns true if this command only reads data (no mutations)
     pub fn is_read_only(&self) -> bool {
+        if let Command::Declared(cmd) = self {
+            return cmd.is_read_only();
+        }
         matches!(
             self,
             Command::Get(_)
                 | Command::GetRange(_, _, _)
                 | Command::GetBit(_, _)
+                | Command::BitCount { .. }
+                | Command::BitPos { .. }
+                | Command::BitFieldRo { .. }
                 | Command::StrLen(_)
                 | Command::MGet(_)
                 | Command::Exists(_)
+                | Command::Touch(_)
                 | Command::TypeOf(_)
                 | Command::Keys(_)
                 | Command::Ttl(_)
                 | Command::Pttl(_)
-                | Command::ExpireTime(_)
-                | Command::PExpireTime(_)
                 | Command::LLen(_)
                 | Command::LIndex(_, _)
                 | Command::LRange(_, _, _)
+                | Command::LPos { .. }
                 | Command::SMembers(_)
                 | Command::SIsMember(_, _)
                 | Command::SCard(_)
+                | Command::SMIsMember(_, _)
+                | Command::SRandMember(_, _)
+                | Command::SInterCard { .. }
                 | Command::HGet(_, _)
                 | Command::HGetAll(_)
-                | Command::HKeys(_)
-                | Command::HVals(_)
                 | Command::HLen(_)
                 | Command::HExists(_, _)
-                | Command::ZRange(_, _, _, _)
+                | Command::HMGet(_, _)
+                | Command::HRandField { .. }
+                | Command::HTtl { .. }
+                | Command::ZRange { dest: None, .. }
                 | Command::ZRevRange(_, _, _, _)
                 | Command::ZScore(_, _)
-                | Command::ZRank(_, _)
+                | Command::ZMScore(_, _)
+                | Command::ZRandMember { .. }
+                | Command::ZSetOp { dest: None, .. }
                 | Command::ZCard(_)
                 | Command::ZCount(_, _, _)
                 | Command::ZRangeByScore { .. }
+                | Command::XRange { .. }
+                | Command::XRevRange { .. }
+                | Command::XRead { .. }
+                | Command::XPending { .. }
                 | Command::Scan { .. }
                 | Command::HScan { .. }
                 | Command::ZScan { .. }
                 | Command::Info
                 | Command::Ping(_)
                 | Command::ConfigGet(_)
+                | Command::LatencyLatest
+                | Command::LatencyHistory(_)
+                | Command::LatencyDoctor
+                | Command::LatencyHelp
                 | Command::Echo(_)
+                | Command::Lolwut { .. }
+                | Command::Publish { .. }
+                | Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::PubSubChannels(_)
+                | Command::PubSubNumSub(_)
+                | Command::PubSubNumPat
                 | Command::CommandCommand
                 | Command::CommandCount
+                | Command::CommandInfo(_)
+                | Command::CommandDocs(_)
+                | Command::CommandGetKeys(_)
                 | Command::ClientGetName
                 | Command::ClientId
                 | Command::ClientInfo
+                | Command::ClientList
                 | Command::ObjectHelp
                 | Command::ObjectEncoding(_)
                 | Command::ObjectRefCount(_)
                 | Command::ObjectIdleTime(_)
                 | Command::ObjectFreq(_)
+                | Command::MemoryUsage { .. }
+                | Command::MemoryStats
+                | Command::MemoryDoctor
+                | Command::MemoryHelp
+                | Command::DebugKeyStats
+                | Command::DebugStringMatchLen
+                | Command::DebugProtocol(_)
+                | Command::DebugHealth
+                | Command::DebugSnapshotRead(_)
+                | Command::DebugBuggifyStats
                 | Command::RandomKey
                 | Command::DbSize
                 | Command::Wait(_, _)
+                | Command::Shutdown { .. }
+                | Command::ScriptKill
+                | Command::EvalRo { .. }
+                | Command::EvalShaRo { .. }
+                | Command::FCallRo { .. }
+                | Command::FunctionList { .. }
+                | Command::FunctionDump
+                | Command::FunctionStats
                 | Command::Time
                 | Command::AclDryrun { .. }
                 | Command::AclLog { .. }
         )
     }
 
+    /// Returns true if this command's result can differ between two runs on
+    /// identical data (Redis' `random` command flag)
+    pub fn is_nondeterministic(&self) -> bool {
+        matches!(
+            self,
+            Command::RandomKey
+                | Command::Time
+                | Command::SPop(_, _)
+                | Command::SRandMember(_, _)
+                | Command::ZRandMember { .. }
+                | Command::HRandField { .. }
+        )
+    }
+
+    /// For blocking commands: the keys a nil reply waits on and the timeout
+    /// in milliseconds (0 = forever). The executor always answers without
+    /// blocking; connections use this to turn a nil into a wait.
+    pub fn blocking_keys(&self) -> Option<(&[String], u64)> {
+        match self {
+            Command::BLPop { keys, timeout_ms } | Command::BRPop { keys, timeout_ms } => {
+                Some((keys, *timeout_ms))
+            }
+            Command::BLMove {
+                source, timeout_ms, ..
+            } => Some((std::slice::from_ref(source), *timeout_ms)),
+            Command::BZPopMin { keys, timeout_ms } | Command::BZPopMax { keys, timeout_ms } => {
+                Some((keys, *timeout_ms))
+            }
+            // XREADGROUP history reads (explicit IDs) always reply, so only
+            // its `>` reads end up waiting
+            Command::XRead {
+                keys,
+                block_ms: Some(timeout_ms),
+                ..
+            }
+            | Command::XReadGroup {
+                keys,
+                block_ms: Some(timeout_ms),
+                ..
+            } => Some((keys, *timeout_ms)),
+            _ => None,
+        }
+    }
+
     /// Returns the key(s) this command operates on (for sharding)
     pub fn get_primary_key(&self) -> Option<&str> {
         match self {
             Command::Get(k)
             | Command::Set { key: k, .. }
-            | Command::SetNx(k, _)
             | Command::GetRange(k, _, _)
             | Command::SetRange(k, _, _)
             | Command::SetBit(k, _, _)
             | Command::GetBit(k, _)
+            | Command::BitCount { key: k, .. }
+            | Command::BitPos { key: k, .. }
+            | Command::BitField { key: k, .. }
+            | Command::BitFieldRo { key: k, .. }
             | Command::GetEx { key: k, .. }
             | Command::GetDel(k)
             | Command::TypeOf(k)
             | Command::Expire { key: k, .. }
-            | Command::ExpireAt(k, _)
             | Command::PExpire { key: k, .. }
-            | Command::PExpireAt(k, _)
             | Command::Ttl(k)
             | Command::Pttl(k)
-            | Command::ExpireTime(k)
-            | Command::PExpireTime(k)
             | Command::Persist(k)
             | Command::Incr(k)
             | Command::Decr(k)
@@ -473,45 +1118,90 @@ impl Command {
             | Command::LRange(k, _, _)
             | Command::LSet(k, _, _)
             | Command::LTrim(k, _, _)
+            | Command::LInsert { key: k, .. }
+            | Command::LRem(k, _, _)
+            | Command::LPos { key: k, .. }
+            | Command::LPushX(k, _)
+            | Command::RPushX(k, _)
             | Command::RPopLPush(k, _)
             | Command::LMove { source: k, .. }
+            | Command::BLMove { source: k, .. }
             | Command::SAdd(k, _)
             | Command::SRem(k, _)
             | Command::SMembers(k)
             | Command::SIsMember(k, _)
             | Command::SCard(k)
             | Command::SPop(k, _)
+            | Command::SMIsMember(k, _)
+            | Command::SRandMember(k, _)
             | Command::HSet(k, _)
             | Command::HGet(k, _)
             | Command::HDel(k, _)
             | Command::HGetAll(k)
-            | Command::HKeys(k)
-            | Command::HVals(k)
             | Command::HLen(k)
             | Command::HExists(k, _)
             | Command::HIncrBy(k, _, _)
+            | Command::HIncrByFloat(k, _, _)
+            | Command::HMGet(k, _)
+            | Command::HRandField { key: k, .. }
+            | Command::HExpire { key: k, .. }
+            | Command::HTtl { key: k, .. }
+            | Command::HPersist(k, _)
             | Command::ZAdd { key: k, .. }
             | Command::ZRem(k, _)
-            | Command::ZRange(k, _, _, _)
+            | Command::ZRemRange(k, _)
             | Command::ZRevRange(k, _, _, _)
             | Command::ZScore(k, _)
-            | Command::ZRank(k, _)
+            | Command::ZIncrBy(k, _, _)
+            | Command::ZMScore(k, _)
+            | Command::ZRandMember { key: k, .. }
             | Command::ZCard(k)
             | Command::ZCount(k, _, _)
             | Command::ZRangeByScore { key: k, .. }
+            | Command::ZPopMin { key: k, .. }
+            | Command::ZPopMax { key: k, .. }
+            | Command::XAdd { key: k, .. }
+            | Command::XRange { key: k, .. }
+            | Command::XRevRange { key: k, .. }
+            | Command::XGroupCreate { key: k, .. }
+            | Command::XGroupSetId { key: k, .. }
+            | Command::XGroupDestroy { key: k, .. }
+            | Command::XGroupCreateConsumer { key: k, .. }
+            | Command::XGroupDelConsumer { key: k, .. }
+            | Command::XAck { key: k, .. }
+            | Command::XPending { key: k, .. }
+            | Command::XClaim { key: k, .. }
+            | Command::XAutoClaim { key: k, .. }
             | Command::HScan { key: k, .. }
             | Command::ZScan { key: k, .. } => Some(k.as_str()),
-            Command::Del(keys) | Command::Exists(keys) => keys.first().map(|s| s.as_str()),
+            Command::Del(keys)
+            | Command::Unlink(keys)
+            | Command::Exists(keys)
+            | Command::Touch(keys) => keys.first().map(|s| s.as_str()),
             Command::MGet(keys) => keys.first().map(|s| s.as_str()),
+            Command::DebugSnapshotRead(cmds) => cmds.iter().find_map(Command::get_primary_key),
             Command::MSet(pairs) | Command::MSetNx(pairs) => {
                 pairs.first().map(|(k, _)| k.as_str())
             }
             Command::BatchSet(pairs) => pairs.first().map(|(k, _)| k.as_str()),
             Command::BatchGet(keys) => keys.first().map(|s| s.as_str()),
-            Command::Watch(keys) => keys.first().map(|s| s.as_str()),
-            Command::Eval { keys, .. } | Command::EvalSha { keys, .. } => {
+            Command::Watch(keys) | Command::SInterCard { keys, .. } => {
+                keys.first().map(|s| s.as_str())
+            }
+            Command::XRead { keys, .. } | Command::XReadGroup { keys, .. } => {
                 keys.first().map(|s| s.as_str())
             }
+            Command::BLPop { keys, .. }
+            | Command::BRPop { keys, .. }
+            | Command::BZPopMin { keys, .. }
+            | Command::BZPopMax { keys, .. } => keys.first().map(|s| s.as_str()),
+            Command::Declared(cmd) => cmd.keys().first().map(|k| k.as_str()),
+            Command::Eval { keys, .. }
+            | Command::EvalSha { keys, .. }
+            | Command::EvalRo { keys, .. }
+            | Command::EvalShaRo { keys, .. }
+            | Command::FCall { keys, .. }
+            | Command::FCallRo { keys, .. } => keys.first().map(|s| s.as_str()),
             Command::Scan { .. }
             | Command::Keys(_)
             | Command::FlushDb
@@ -520,15 +1210,19 @@ impl Command {
             | Command::Exec
             | Command::Discard
             | Command::Unwatch
+            | Command::Reset
             | Command::ScriptLoad(_)
             | Command::ScriptExists(_)
             | Command::ScriptFlush
+            | Command::ScriptKill
             | Command::Info
             | Command::Ping(_)
             | Command::DbSize
             | Command::Wait(_, _)
+            | Command::Shutdown { .. }
             | Command::Time
             | Command::Auth { .. }
+            | Command::Hello { .. }
             | Command::AclWhoami
             | Command::AclList
             | Command::AclUsers
@@ -543,18 +1237,57 @@ impl Command {
             | Command::ConfigGet(_)
             | Command::ConfigSet(_, _)
             | Command::ConfigResetStat
+            | Command::LatencyLatest
+            | Command::LatencyHistory(_)
+            | Command::LatencyReset(_)
+            | Command::LatencyDoctor
+            | Command::LatencyHelp
             | Command::Select(_)
             | Command::Echo(_)
+            | Command::Lolwut { .. }
+            | Command::Publish { .. }
+            | Command::Subscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PSubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::PubSubChannels(_)
+            | Command::PubSubNumSub(_)
+            | Command::PubSubNumPat
             | Command::CommandCommand
             | Command::CommandCount
+            | Command::CommandInfo(_)
+            | Command::CommandDocs(_)
+            | Command::CommandGetKeys(_)
+            | Command::FunctionLoad { .. }
+            | Command::FunctionDelete(_)
             | Command::FunctionFlush
+            | Command::FunctionList { .. }
+            | Command::FunctionDump
+            | Command::FunctionRestore { .. }
+            | Command::FunctionStats
             | Command::ClientSetName(_)
             | Command::ClientGetName
             | Command::ClientId
             | Command::ClientInfo
+            | Command::ClientList
+            | Command::ClientKill { .. }
+            | Command::ClientPause { .. }
+            | Command::ClientUnpause
+            | Command::ClientTracking { .. }
             | Command::ObjectHelp
+            | Command::MemoryStats
+            | Command::MemoryDoctor
+            | Command::MemoryHelp
             | Command::DebugSleep(_)
+            | Command::DebugSetActiveExpire(_)
+            | Command::DebugReload
             | Command::DebugSet(_, _)
+            | Command::DebugKeyStats
+            | Command::DebugStringMatchLen
+            | Command::DebugProtocol(_)
+            | Command::DebugHealth
+            | Command::DebugBuggifySet { .. }
+            | Command::DebugBuggifyStats
             | Command::RandomKey
             | Command::Unknown(_) => None,
 
@@ -562,10 +1295,16 @@ impl Command {
             | Command::ObjectRefCount(k)
             | Command::ObjectIdleTime(k)
             | Command::ObjectFreq(k)
+            | Command::MemoryUsage { key: k, .. }
             | Command::DebugObject(k) => Some(k.as_str()),
 
             Command::Sort { key: k, .. } => Some(k.as_str()),
             Command::Rename(k, _) | Command::RenameNx(k, _) => Some(k.as_str()),
+            Command::Copy { source, .. } => Some(source.as_str()),
+            Command::Restore { key: k, .. } => Some(k.as_str()),
+            Command::BitOp { dest, .. } => Some(dest.as_str()),
+            Command::ZSetOp { dest, keys, .. } => dest.as_ref().or(keys.first()).map(|k| k.as_str()),
+            Command::ZRange { dest, key, .. } => Some(dest.as_ref().unwrap_or(key).as_str()),
         }
     }
 
@@ -574,22 +1313,21 @@ impl Command {
         match self {
             Command::Get(k)
             | Command::Set { key: k, .. }
-            | Command::SetNx(k, _)
             | Command::GetRange(k, _, _)
             | Command::SetRange(k, _, _)
             | Command::SetBit(k, _, _)
             | Command::GetBit(k, _)
+            | Command::BitCount { key: k, .. }
+            | Command::BitPos { key: k, .. }
+            | Command::BitField { key: k, .. }
+            | Command::BitFieldRo { key: k, .. }
             | Command::GetEx { key: k, .. }
             | Command::GetDel(k)
             | Command::TypeOf(k)
             | Command::Expire { key: k, .. }
-            | Command::ExpireAt(k, _)
             | Command::PExpire { key: k, .. }
-            | Command::PExpireAt(k, _)
             | Command::Ttl(k)
             | Command::Pttl(k)
-            | Command::ExpireTime(k)
-            | Command::PExpireTime(k)
             | Command::Persist(k)
             | Command::Incr(k)
             | Command::Decr(k)
@@ -608,47 +1346,92 @@ impl Command {
             | Command::LRange(k, _, _)
             | Command::LSet(k, _, _)
             | Command::LTrim(k, _, _)
+            | Command::LInsert { key: k, .. }
+            | Command::LRem(k, _, _)
+            | Command::LPos { key: k, .. }
+            | Command::LPushX(k, _)
+            | Command::RPushX(k, _)
             | Command::SAdd(k, _)
             | Command::SRem(k, _)
             | Command::SMembers(k)
             | Command::SIsMember(k, _)
             | Command::SCard(k)
             | Command::SPop(k, _)
+            | Command::SMIsMember(k, _)
+            | Command::SRandMember(k, _)
             | Command::HSet(k, _)
             | Command::HGet(k, _)
             | Command::HDel(k, _)
             | Command::HGetAll(k)
-            | Command::HKeys(k)
-            | Command::HVals(k)
             | Command::HLen(k)
             | Command::HExists(k, _)
             | Command::HIncrBy(k, _, _)
+            | Command::HIncrByFloat(k, _, _)
+            | Command::HMGet(k, _)
+            | Command::HRandField { key: k, .. }
+            | Command::HExpire { key: k, .. }
+            | Command::HTtl { key: k, .. }
+            | Command::HPersist(k, _)
             | Command::ZAdd { key: k, .. }
             | Command::ZRem(k, _)
-            | Command::ZRange(k, _, _, _)
+            | Command::ZRemRange(k, _)
             | Command::ZRevRange(k, _, _, _)
             | Command::ZScore(k, _)
-            | Command::ZRank(k, _)
+            | Command::ZIncrBy(k, _, _)
+            | Command::ZMScore(k, _)
+            | Command::ZRandMember { key: k, .. }
             | Command::ZCard(k)
             | Command::ZCount(k, _, _)
             | Command::ZRangeByScore { key: k, .. }
+            | Command::ZPopMin { key: k, .. }
+            | Command::ZPopMax { key: k, .. }
+            | Command::XAdd { key: k, .. }
+            | Command::XRange { key: k, .. }
+            | Command::XRevRange { key: k, .. }
+            | Command::XGroupCreate { key: k, .. }
+            | Command::XGroupSetId { key: k, .. }
+            | Command::XGroupDestroy { key: k, .. }
+            | Command::XGroupCreateConsumer { key: k, .. }
+            | Command::XGroupDelConsumer { key: k, .. }
+            | Command::XAck { key: k, .. }
+            | Command::XPending { key: k, .. }
+            | Command::XClaim { key: k, .. }
+            | Command::XAutoClaim { key: k, .. }
             | Command::HScan { key: k, .. }
             | Command::ZScan { key: k, .. }
             | Command::Keys(k) => vec![k.clone()],
 
             // Commands with two keys (source, dest)
             Command::RPopLPush(src, dst) => vec![src.clone(), dst.clone()],
-            Command::LMove { source, dest, .. } => vec![source.clone(), dest.clone()],
+            Command::LMove { source, dest, .. } | Command::BLMove { source, dest, .. } => {
+                vec![source.clone(), dest.clone()]
+            }
 
             // Multi-key commands
-            Command::Del(keys) | Command::Exists(keys) | Command::MGet(keys) => keys.clone(),
+            Command::Del(keys)
+            | Command::Unlink(keys)
+            | Command::Exists(keys)
+            | Command::Touch(keys)
+            | Command::MGet(keys) => keys.clone(),
+            Command::DebugSnapshotRead(cmds) => cmds.iter().flat_map(Command::get_keys).collect(),
             Command::MSet(pairs) | Command::MSetNx(pairs) => {
                 pairs.iter().map(|(k, _)| k.clone()).collect()
             }
             Command::BatchSet(pairs) => pairs.iter().map(|(k, _)| k.clone()).collect(),
             Command::BatchGet(keys) => keys.clone(),
-            Command::Watch(keys) => keys.clone(),
-            Command::Eval { keys, .. } | Command::EvalSha { keys, .. } => keys.clone(),
+            Command::Watch(keys) | Command::SInterCard { keys, .. } => keys.clone(),
+            Command::XRead { keys, .. } | Command::XReadGroup { keys, .. } => keys.clone(),
+            Command::BLPop { keys, .. }
+            | Command::BRPop { keys, .. }
+            | Command::BZPopMin { keys, .. }
+            | Command::BZPopMax { keys, .. } => keys.clone(),
+            Command::Declared(cmd) => cmd.keys().into_iter().cloned().collect(),
+            Command::Eval { keys, .. }
+            | Command::EvalSha { keys, .. }
+            | Command::EvalRo { keys, .. }
+            | Command::EvalShaRo { keys, .. }
+            | Command::FCall { keys, .. }
+            | Command::FCallRo { keys, .. } => keys.clone(),
 
             // Commands with no keys
             Command::Scan { .. }
@@ -658,15 +1441,19 @@ impl Command {
             | Command::Exec
             | Command::Discard
             | Command::Unwatch
+            | Command::Reset
             | Command::ScriptLoad(_)
             | Command::ScriptExists(_)
             | Command::ScriptFlush
+            | Command::ScriptKill
             | Command::Info
             | Command::Ping(_)
             | Command::DbSize
             | Command::Wait(_, _)
+            | Command::Shutdown { .. }
             | Command::Time
             | Command::Auth { .. }
+            | Command::Hello { .. }
             | Command::AclWhoami
             | Command::AclList
             | Command::AclUsers
@@ -681,18 +1468,57 @@ impl Command {
             | Command::ConfigGet(_)
             | Command::ConfigSet(_, _)
             | Command::ConfigResetStat
+            | Command::LatencyLatest
+            | Command::LatencyHistory(_)
+            | Command::LatencyReset(_)
+            | Command::LatencyDoctor
+            | Command::LatencyHelp
             | Command::Select(_)
             | Command::Echo(_)
+            | Command::Lolwut { .. }
+            | Command::Publish { .. }
+            | Command::Subscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PSubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::PubSubChannels(_)
+            | Command::PubSubNumSub(_)
+            | Command::PubSubNumPat
             | Command::CommandCommand
             | Command::CommandCount
+            | Command::CommandInfo(_)
+            | Command::CommandDocs(_)
+            | Command::CommandGetKeys(_)
+            | Command::FunctionLoad { .. }
+            | Command::FunctionDelete(_)
             | Command::FunctionFlush
+            | Command::FunctionList { .. }
+            | Command::FunctionDump
+            | Command::FunctionRestore { .. }
+            | Command::FunctionStats
             | Command::ClientSetName(_)
             | Command::ClientGetName
             | Command::ClientId
             | Command::ClientInfo
+            | Command::ClientList
+            | Command::ClientKill { .. }
+            | Command::ClientPause { .. }
+            | Command::ClientUnpause
+            | Command::ClientTracking { .. }
             | Command::ObjectHelp
+            | Command::MemoryStats
+            | Command::MemoryDoctor
+            | Command::MemoryHelp
             | Command::DebugSleep(_)
+            | Command::DebugSetActiveExpire(_)
+            | Command::DebugReload
             | Command::DebugSet(_, _)
+            | Command::DebugKeyStats
+            | Command::DebugStringMatchLen
+            | Command::DebugProtocol(_)
+            | Command::DebugHealth
+            | Command::DebugBuggifySet { .. }
+            | Command::DebugBuggifyStats
             | Command::RandomKey
             | Command::Unknown(_) => vec![],
 
@@ -700,9 +1526,10 @@ impl Command {
             | Command::ObjectRefCount(k)
             | Command::ObjectIdleTime(k)
             | Command::ObjectFreq(k)
+            | Command::MemoryUsage { key: k, .. }
             | Command::DebugObject(k) => vec![k.clone()],
 
-            Command::Sort { key, store } => {
+            Command::Sort { key, store, .. } => {
                 let mut keys = vec![key.clone()];
                 if let Some(dest) = store {
                     keys.push(dest.clone());
@@ -712,6 +1539,259 @@ impl Command {
             Command::Rename(src, dst) | Command::RenameNx(src, dst) => {
                 vec![src.clone(), dst.clone()]
             }
+            Command::Copy { source, dest, .. } => vec![source.clone(), dest.clone()],
+            Command::Restore { key: k, .. } => vec![k.clone()],
+            Command::BitOp { dest, keys, .. } => {
+                std::iter::once(dest).chain(keys).cloned().collect()
+            }
+            Command::ZSetOp { dest, keys, .. } => dest.iter().chain(keys).cloned().collect(),
+            Command::ZRange { dest, key, .. } => {
+                dest.iter().chain(std::iter::once(key)).cloned().collect()
+            }
+        }
+    }
+
+    /// Mutable access to the same keys as `get_keys`, in the same order
+    /// (used to rewrite key names before dispatch, e.g. tenant keyspaces)
+    pub fn keys_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Command::Get(k)
+            | Command::Set { key: k, .. }
+            | Command::GetRange(k, _, _)
+            | Command::SetRange(k, _, _)
+            | Command::SetBit(k, _, _)
+            | Command::GetBit(k, _)
+            | Command::BitCount { key: k, .. }
+            | Command::BitPos { key: k, .. }
+            | Command::BitField { key: k, .. }
+            | Command::BitFieldRo { key: k, .. }
+            | Command::GetEx { key: k, .. }
+            | Command::GetDel(k)
+            | Command::TypeOf(k)
+            | Command::Expire { key: k, .. }
+            | Command::PExpire { key: k, .. }
+            | Command::Ttl(k)
+            | Command::Pttl(k)
+            | Command::Persist(k)
+            | Command::Incr(k)
+            | Command::Decr(k)
+            | Command::IncrBy(k, _)
+            | Command::DecrBy(k, _)
+            | Command::IncrByFloat(k, _)
+            | Command::Append(k, _)
+            | Command::GetSet(k, _)
+            | Command::StrLen(k)
+            | Command::LPush(k, _)
+            | Command::RPush(k, _)
+            | Command::LPop(k)
+            | Command::RPop(k)
+            | Command::LLen(k)
+            | Command::LIndex(k, _)
+            | Command::LRange(k, _, _)
+            | Command::LSet(k, _, _)
+            | Command::LTrim(k, _, _)
+            | Command::LInsert { key: k, .. }
+            | Command::LRem(k, _, _)
+            | Command::LPos { key: k, .. }
+            | Command::LPushX(k, _)
+            | Command::RPushX(k, _)
+            | Command::SAdd(k, _)
+            | Command::SRem(k, _)
+            | Command::SMembers(k)
+            | Command::SIsMember(k, _)
+            | Command::SCard(k)
+            | Command::SPop(k, _)
+            | Command::SMIsMember(k, _)
+            | Command::SRandMember(k, _)
+            | Command::HSet(k, _)
+            | Command::HGet(k, _)
+            | Command::HDel(k, _)
+            | Command::HGetAll(k)
+            | Command::HLen(k)
+            | Command::HExists(k, _)
+            | Command::HIncrBy(k, _, _)
+            | Command::HIncrByFloat(k, _, _)
+            | Command::HMGet(k, _)
+            | Command::HRandField { key: k, .. }
+            | Command::HExpire { key: k, .. }
+            | Command::HTtl { key: k, .. }
+            | Command::HPersist(k, _)
+            | Command::ZAdd { key: k, .. }
+            | Command::ZRem(k, _)
+            | Command::ZRemRange(k, _)
+            | Command::ZRevRange(k, _, _, _)
+            | Command::ZScore(k, _)
+            | Command::ZIncrBy(k, _, _)
+            | Command::ZMScore(k, _)
+            | Command::ZRandMember { key: k, .. }
+            | Command::ZCard(k)
+            | Command::ZCount(k, _, _)
+            | Command::ZRangeByScore { key: k, .. }
+            | Command::ZPopMin { key: k, .. }
+            | Command::ZPopMax { key: k, .. }
+            | Command::XAdd { key: k, .. }
+            | Command::XRange { key: k, .. }
+            | Command::XRevRange { key: k, .. }
+            | Command::XGroupCreate { key: k, .. }
+            | Command::XGroupSetId { key: k, .. }
+            | Command::XGroupDestroy { key: k, .. }
+            | Command::XGroupCreateConsumer { key: k, .. }
+            | Command::XGroupDelConsumer { key: k, .. }
+            | Command::XAck { key: k, .. }
+            | Command::XPending { key: k, .. }
+            | Command::XClaim { key: k, .. }
+            | Command::XAutoClaim { key: k, .. }
+            | Command::HScan { key: k, .. }
+            | Command::ZScan { key: k, .. }
+            | Command::Keys(k) => vec![k],
+
+            // Commands with two keys (source, dest)
+            Command::RPopLPush(src, dst) => vec![src, dst],
+            Command::LMove { source, dest, .. } | Command::BLMove { source, dest, .. } => {
+                vec![source, dest]
+            }
+
+            // Multi-key commands
+            Command::Del(keys)
+            | Command::Unlink(keys)
+            | Command::Exists(keys)
+            | Command::Touch(keys)
+            | Command::MGet(keys) => keys.iter_mut().collect(),
+            Command::DebugSnapshotRead(cmds) => {
+                cmds.iter_mut().flat_map(Command::keys_mut).collect()
+            }
+            Command::MSet(pairs) | Command::MSetNx(pairs) => {
+                pairs.iter_mut().map(|(k, _)| k).collect()
+            }
+            Command::BatchSet(pairs) => pairs.iter_mut().map(|(k, _)| k).collect(),
+            Command::BatchGet(keys) => keys.iter_mut().collect(),
+            Command::Watch(keys) | Command::SInterCard { keys, .. } => keys.iter_mut().collect(),
+            Command::XRead { keys, .. } | Command::XReadGroup { keys, .. } => {
+                keys.iter_mut().collect()
+            }
+            Command::BLPop { keys, .. }
+            | Command::BRPop { keys, .. }
+            | Command::BZPopMin { keys, .. }
+            | Command::BZPopMax { keys, .. } => keys.iter_mut().collect(),
+            Command::Declared(cmd) => cmd.keys_mut(),
+            Command::Eval { keys, .. }
+            | Command::EvalSha { keys, .. }
+            | Command::EvalRo { keys, .. }
+            | Command::EvalShaRo { keys, .. }
+            | Command::FCall { keys, .. }
+            | Command::FCallRo { keys, .. } => keys.iter_mut().collect(),
+
+            // Commands with no keys
+            Command::Scan { .. }
+            | Command::FlushDb
+            | Command::FlushAll
+            | Command::Multi
+            | Command::Exec
+            | Command::Discard
+            | Command::Unwatch
+            | Command::Reset
+            | Command::ScriptLoad(_)
+            | Command::ScriptExists(_)
+            | Command::ScriptFlush
+            | Command::ScriptKill
+            | Command::Info
+            | Command::Ping(_)
+            | Command::DbSize
+            | Command::Wait(_, _)
+            | Command::Shutdown { .. }
+            | Command::Time
+            | Command::Auth { .. }
+            | Command::Hello { .. }
+            | Command::AclWhoami
+            | Command::AclList
+            | Command::AclUsers
+            | Command::AclGetUser { .. }
+            | Command::AclSetUser { .. }
+            | Command::AclDelUser { .. }
+            | Command::AclCat { .. }
+            | Command::AclGenPass { .. }
+            | Command::AclDryrun { .. }
+            | Command::AclLog { .. }
+            | Command::AclLogReset
+            | Command::ConfigGet(_)
+            | Command::ConfigSet(_, _)
+            | Command::ConfigResetStat
+            | Command::LatencyLatest
+            | Command::LatencyHistory(_)
+            | Command::LatencyReset(_)
+            | Command::LatencyDoctor
+            | Command::LatencyHelp
+            | Command::Select(_)
+            | Command::Echo(_)
+            | Command::Lolwut { .. }
+            | Command::Publish { .. }
+            | Command::Subscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PSubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::PubSubChannels(_)
+            | Command::PubSubNumSub(_)
+            | Command::PubSubNumPat
+            | Command::CommandCommand
+            | Command::CommandCount
+            | Command::CommandInfo(_)
+            | Command::CommandDocs(_)
+            | Command::CommandGetKeys(_)
+            | Command::FunctionLoad { .. }
+            | Command::FunctionDelete(_)
+            | Command::FunctionFlush
+            | Command::FunctionList { .. }
+            | Command::FunctionDump
+            | Command::FunctionRestore { .. }
+            | Command::FunctionStats
+            | Command::ClientSetName(_)
+            | Command::ClientGetName
+            | Command::ClientId
+            | Command::ClientInfo
+            | Command::ClientList
+            | Command::ClientKill { .. }
+            | Command::ClientPause { .. }
+            | Command::ClientUnpause
+            | Command::ClientTracking { .. }
+            | Command::ObjectHelp
+            | Command::MemoryStats
+            | Command::MemoryDoctor
+            | Command::MemoryHelp
+            | Command::DebugSleep(_)
+            | Command::DebugSetActiveExpire(_)
+            | Command::DebugReload
+            | Command::DebugSet(_, _)
+            | Command::DebugKeyStats
+            | Command::DebugStringMatchLen
+            | Command::DebugProtocol(_)
+            | Command::DebugHealth
+            | Command::DebugBuggifySet { .. }
+            | Command::DebugBuggifyStats
+            | Command::RandomKey
+            | Command::Unknown(_) => vec![],
+
+            Command::ObjectEncoding(k)
+            | Command::ObjectRefCount(k)
+            | Command::ObjectIdleTime(k)
+            | Command::ObjectFreq(k)
+            | Command::MemoryUsage { key: k, .. }
+            | Command::DebugObject(k) => vec![k],
+
+            Command::Sort { key, store, .. } => {
+                let mut keys = vec![key];
+                if let Some(dest) = store {
+                    keys.push(dest);
+                }
+                keys
+            }
+            Command::Rename(src, dst) | Command::RenameNx(src, dst) => vec![src, dst],
+            Command::Copy { source, dest, .. } => vec![source, dest],
+            Command::Restore { key: k, .. } => vec![k],
+            Command::BitOp { dest, keys, .. } => std::iter::once(dest).chain(keys).collect(),
+            Command::ZSetOp { dest, keys, .. } => dest.iter_mut().chain(keys).collect(),
+            Command::ZRange { dest, key, .. } => {
+                dest.iter_mut().chain(std::iter::once(key)).collect()
+            }
         }
     }
 
@@ -733,6 +1813,11 @@ impl Command {
             Command::SetRange(_, _, _) => "SETRANGE",
             Command::SetBit(_, _, _) => "SETBIT",
             Command::GetBit(_, _) => "GETBIT",
+            Command::BitCount { .. } => "BITCOUNT",
+            Command::BitPos { .. } => "BITPOS",
+            Command::BitOp { .. } => "BITOP",
+            Command::BitField { .. } => "BITFIELD",
+            Command::BitFieldRo { .. } => "BITFIELD_RO",
             Command::GetEx { .. } => "GETEX",
             Command::GetDel(_) => "GETDEL",
             Command::Incr(_) => "INCR",
@@ -741,21 +1826,20 @@ impl Command {
             Command::DecrBy(_, _) => "DECRBY",
             Command::IncrByFloat(_, _) => "INCRBYFLOAT",
             Command::Del(_) => "DEL",
+            Command::Unlink(_) => "UNLINK",
             Command::Exists(_) => "EXISTS",
+            Command::Touch(_) => "TOUCH",
             Command::TypeOf(_) => "TYPE",
             Command::Keys(_) => "KEYS",
             Command::FlushDb => "FLUSHDB",
             Command::FlushAll => "FLUSHALL",
             Command::Expire { .. } => "EXPIRE",
-            Command::ExpireAt(_, _) => "EXPIREAT",
             Command::PExpire { .. } => "PEXPIRE",
-            Command::PExpireAt(_, _) => "PEXPIREAT",
             Command::Ttl(_) => "TTL",
             Command::Pttl(_) => "PTTL",
-            Command::ExpireTime(_) => "EXPIRETIME",
-            Command::PExpireTime(_) => "PEXPIRETIME",
             Command::Persist(_) => "PERSIST",
             Command::Wait(_, _) => "WAIT",
+            Command::Shutdown { .. } => "SHUTDOWN",
             Command::Time => "TIME",
             Command::Sort { .. } => "SORT",
             Command::LPush(_, _) => "LPUSH",
@@ -769,31 +1853,87 @@ impl Command {
             Command::LTrim(_, _, _) => "LTRIM",
             Command::RPopLPush(_, _) => "RPOPLPUSH",
             Command::LMove { .. } => "LMOVE",
+            Command::LInsert { .. } => "LINSERT",
+            Command::LRem(_, _, _) => "LREM",
+            Command::LPos { .. } => "LPOS",
+            Command::LPushX(_, _) => "LPUSHX",
+            Command::RPushX(_, _) => "RPUSHX",
+            Command::BLPop { .. } => "BLPOP",
+            Command::BRPop { .. } => "BRPOP",
+            Command::BLMove { .. } => "BLMOVE",
             Command::SAdd(_, _) => "SADD",
             Command::SRem(_, _) => "SREM",
             Command::SMembers(_) => "SMEMBERS",
             Command::SIsMember(_, _) => "SISMEMBER",
             Command::SCard(_) => "SCARD",
             Command::SPop(_, _) => "SPOP",
+            Command::SMIsMember(_, _) => "SMISMEMBER",
+            Command::SRandMember(_, _) => "SRANDMEMBER",
+            Command::SInterCard { .. } => "SINTERCARD",
             Command::HSet(_, _) => "HSET",
             Command::HGet(_, _) => "HGET",
             Command::HDel(_, _) => "HDEL",
             Command::HGetAll(_) => "HGETALL",
-            Command::HKeys(_) => "HKEYS",
-            Command::HVals(_) => "HVALS",
             Command::HLen(_) => "HLEN",
             Command::HExists(_, _) => "HEXISTS",
             Command::HIncrBy(_, _, _) => "HINCRBY",
+            Command::HIncrByFloat(_, _, _) => "HINCRBYFLOAT",
+            Command::HMGet(_, _) => "HMGET",
+            Command::HRandField { .. } => "HRANDFIELD",
+            Command::HExpire { form, .. } => match (form.millis, form.absolute) {
+                (false, false) => "HEXPIRE",
+                (true, false) => "HPEXPIRE",
+                (false, true) => "HEXPIREAT",
+                (true, true) => "HPEXPIREAT",
+            },
+            Command::HTtl { form, .. } => match (form.millis, form.absolute) {
+                (false, false) => "HTTL",
+                (true, false) => "HPTTL",
+                (false, true) => "HEXPIRETIME",
+                (true, true) => "HPEXPIRETIME",
+            },
+            Command::HPersist(_, _) => "HPERSIST",
             Command::ZAdd { .. } => "ZADD",
             Command::ZRem(_, _) => "ZREM",
-            Command::SetNx(_, _) => "SETNX",
-            Command::ZRange(_, _, _, _) => "ZRANGE",
+            Command::ZRemRange(_, ZRangeSpec::Rank(..)) => "ZREMRANGEBYRANK",
+            Command::ZRemRange(_, ZRangeSpec::Score(..)) => "ZREMRANGEBYSCORE",
+            Command::ZRemRange(_, ZRangeSpec::Lex(..)) => "ZREMRANGEBYLEX",
+            Command::ZRange { dest: None, .. } => "ZRANGE",
+            Command::ZRange { dest: Some(_), .. } => "ZRANGESTORE",
             Command::ZRevRange(_, _, _, _) => "ZREVRANGE",
             Command::ZScore(_, _) => "ZSCORE",
-            Command::ZRank(_, _) => "ZRANK",
+            Command::ZIncrBy(_, _, _) => "ZINCRBY",
+            Command::ZMScore(_, _) => "ZMSCORE",
+            Command::ZRandMember { .. } => "ZRANDMEMBER",
+            Command::ZSetOp { op, dest, .. } => match (op, dest.is_some()) {
+                (ZSetOperation::Union, false) => "ZUNION",
+                (ZSetOperation::Union, true) => "ZUNIONSTORE",
+                (ZSetOperation::Inter, false) => "ZINTER",
+                (ZSetOperation::Inter, true) => "ZINTERSTORE",
+                (ZSetOperation::Diff, false) => "ZDIFF",
+                (ZSetOperation::Diff, true) => "ZDIFFSTORE",
+            },
             Command::ZCard(_) => "ZCARD",
             Command::ZCount(_, _, _) => "ZCOUNT",
             Command::ZRangeByScore { .. } => "ZRANGEBYSCORE",
+            Command::ZPopMin { .. } => "ZPOPMIN",
+            Command::ZPopMax { .. } => "ZPOPMAX",
+            Command::BZPopMin { .. } => "BZPOPMIN",
+            Command::BZPopMax { .. } => "BZPOPMAX",
+            Command::XAdd { .. } => "XADD",
+            Command::XRange { .. } => "XRANGE",
+            Command::XRevRange { .. } => "XREVRANGE",
+            Command::XRead { .. } => "XREAD",
+            Command::XGroupCreate { .. }
+            | Command::XGroupSetId { .. }
+            | Command::XGroupDestroy { .. }
+            | Command::XGroupCreateConsumer { .. }
+            | Command::XGroupDelConsumer { .. } => "XGROUP",
+            Command::XReadGroup { .. } => "XREADGROUP",
+            Command::XAck { .. } => "XACK",
+            Command::XPending { .. } => "XPENDING",
+            Command::XClaim { .. } => "XCLAIM",
+            Command::XAutoClaim { .. } => "XAUTOCLAIM",
             Command::Scan { .. } => "SCAN",
             Command::HScan { .. } => "HSCAN",
             Command::ZScan { .. } => "ZSCAN",
@@ -802,15 +1942,20 @@ impl Command {
             Command::Discard => "DISCARD",
             Command::Watch(_) => "WATCH",
             Command::Unwatch => "UNWATCH",
+            Command::Reset => "RESET",
             Command::Eval { .. } => "EVAL",
             Command::EvalSha { .. } => "EVALSHA",
+            Command::EvalRo { .. } => "EVAL_RO",
+            Command::EvalShaRo { .. } => "EVALSHA_RO",
             Command::ScriptLoad(_) => "SCRIPT",
             Command::ScriptExists(_) => "SCRIPT",
             Command::ScriptFlush => "SCRIPT",
+            Command::ScriptKill => "SCRIPT",
             Command::Info => "INFO",
             Command::Ping(_) => "PING",
             Command::DbSize => "DBSIZE",
             Command::Auth { .. } => "AUTH",
+            Command::Hello { .. } => "HELLO",
             Command::AclWhoami => "ACL",
             Command::AclList => "ACL",
             Command::AclUsers => "ACL",
@@ -825,26 +1970,72 @@ impl Command {
             Command::ConfigGet(_) => "CONFIG",
             Command::ConfigSet(_, _) => "CONFIG",
             Command::ConfigResetStat => "CONFIG",
+            Command::LatencyLatest
+            | Command::LatencyHistory(_)
+            | Command::LatencyReset(_)
+            | Command::LatencyDoctor
+            | Command::LatencyHelp => "LATENCY",
             Command::Select(_) => "SELECT",
             Command::Echo(_) => "ECHO",
+            Command::Lolwut { .. } => "LOLWUT",
+            Command::Publish { .. } => "PUBLISH",
+            Command::Subscribe(_) => "SUBSCRIBE",
+            Command::Unsubscribe(_) => "UNSUBSCRIBE",
+            Command::PSubscribe(_) => "PSUBSCRIBE",
+            Command::PUnsubscribe(_) => "PUNSUBSCRIBE",
+            Command::PubSubChannels(_) | Command::PubSubNumSub(_) | Command::PubSubNumPat => {
+                "PUBSUB"
+            }
             Command::CommandCommand => "COMMAND",
             Command::CommandCount => "COMMAND",
-            Command::FunctionFlush => "FUNCTION",
+            Command::CommandInfo(_) | Command::CommandDocs(_) | Command::CommandGetKeys(_) => {
+                "COMMAND"
+            }
+            Command::FunctionLoad { .. }
+            | Command::FunctionDelete(_)
+            | Command::FunctionFlush
+            | Command::FunctionList { .. }
+            | Command::FunctionDump
+            | Command::FunctionRestore { .. }
+            | Command::FunctionStats => "FUNCTION",
+            Command::FCall { .. } => "FCALL",
+            Command::FCallRo { .. } => "FCALL_RO",
             Command::ClientSetName(_) => "CLIENT",
             Command::ClientGetName => "CLIENT",
             Command::ClientId => "CLIENT",
             Command::ClientInfo => "CLIENT",
+            Command::ClientList => "CLIENT",
+            Command::ClientKill { .. } => "CLIENT",
+            Command::ClientPause { .. } => "CLIENT",
+            Command::ClientUnpause => "CLIENT",
+            Command::ClientTracking { .. } => "CLIENT",
             Command::ObjectHelp => "OBJECT",
             Command::ObjectEncoding(_) => "OBJECT",
             Command::ObjectRefCount(_) => "OBJECT",
             Command::ObjectIdleTime(_) => "OBJECT",
             Command::ObjectFreq(_) => "OBJECT",
+            Command::MemoryUsage { .. }
+            | Command::MemoryStats
+            | Command::MemoryDoctor
+            | Command::MemoryHelp => "MEMORY",
             Command::DebugSleep(_) => "DEBUG",
+            Command::DebugSetActiveExpire(_) => "DEBUG",
+            Command::DebugReload => "DEBUG",
             Command::DebugSet(_, _) => "DEBUG",
             Command::DebugObject(_) => "DEBUG",
+            Command::DebugKeyStats => "DEBUG",
+            Command::DebugStringMatchLen => "DEBUG",
+            Command::DebugProtocol(_) => "DEBUG",
+            Command::DebugHealth => "DEBUG",
+            Command::DebugSnapshotRead(_) => "DEBUG",
+            Command::DebugBuggifySet { .. } => "DEBUG",
+            Command::DebugBuggifyStats => "DEBUG",
             Command::RandomKey => "RANDOMKEY",
             Command::Rename(_, _) => "RENAME",
             Command::RenameNx(_, _) => "RENAMENX",
+            Command::Copy { .. } => "COPY",
+            Command::Restore { .. } => "RESTORE",
+            Command::Declared(cmd) => cmd.name(),
             Command::Unknown(_) => "UNKNOWN",
         }
     }
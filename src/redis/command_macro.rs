@@ -0,0 +1,262 @@
+//! Declarative command definitions.
+//!
+//! `declare_commands!` defines a fixed-argument command in one place: its
+//! name, flags, typed arguments and executor handler. From that one entry it
+//! generates the `DeclaredCommand` variant, the parser arm shared by
+//! `from_resp`, `from_resp_zero_copy` and the Lua bridge, the key accessors
+//! used for routing and ACLs, the executor dispatch, and the `CommandSpec`
+//! row that drives arity validation.
+//!
+//! ```ignore
+//! declare_commands! {
+//!     /// HKEYS key
+//!     HKeys("hkeys", readonly) { key: Key } => execute_hkeys;
+//! }
+//! ```
+//!
+//! Argument kinds:
+//!
+//! | Kind    | Field type | Notes                                          |
+//! |---------|------------|------------------------------------------------|
+//! | `Key`   | `String`   | Reported by `get_keys` / routed on             |
+//! | `Sds`   | `SDS`      | Binary-safe value                              |
+//! | `Int`   | `i64`      | `ERR value is not an integer or out of range`  |
+//!
+//! Handlers are `pub(crate)` executor methods taking `&str` for `Key`,
+//! `&SDS` for `Sds` and `i64` for `Int`, in declaration order.
+//!
+//! Commands with options, variadic arguments or subcommands keep their
+//! hand-written variants in `Command`.
+
+use super::data::SDS;
+
+/// Argument kind of a declared command, in argv order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    Key,
+    Sds,
+    Int,
+}
+
+pub(crate) fn arg_string(bytes: Option<&[u8]>) -> Result<String, String> {
+    match bytes {
+        Some(b) => Ok(String::from_utf8_lossy(b).to_string()),
+        None => Err("Expected bulk string".to_string()),
+    }
+}
+
+pub(crate) fn arg_sds(bytes: Option<&[u8]>) -> Result<SDS, String> {
+    match bytes {
+        Some(b) => Ok(SDS::new(b.to_vec())),
+        None => Err("Expected bulk string".to_string()),
+    }
+}
+
+pub(crate) fn arg_int(bytes: Option<&[u8]>) -> Result<i64, String> {
+    arg_string(bytes)?
+        .parse()
+        .map_err(|_| "ERR value is not an integer or out of range".to_string())
+}
+
+/// ASCII-uppercase a name at compile time (`N` must be `name.len()`)
+pub(crate) const fn ascii_uppercase<const N: usize>(name: &str) -> [u8; N] {
+    let bytes = name.as_bytes();
+    assert!(bytes.len() == N, "ascii_uppercase: N must equal name.len()");
+    let mut out = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = bytes[i].to_ascii_uppercase();
+        i += 1;
+    }
+    out
+}
+
+macro_rules! declare_commands {
+    (
+        $(
+            $(#[doc = $doc:literal])*
+            $variant:ident($name:literal, $flag:ident) {
+                $($arg:ident: $kind:ident),* $(,)?
+            } => $handler:ident;
+        )*
+    ) => {
+        /// Fixed-argument commands generated by `declare_commands!`
+        #[derive(Debug, Clone)]
+        pub enum DeclaredCommand {
+            $(
+                $(#[doc = $doc])*
+                $variant { $($arg: declare_commands!(@ty $kind)),* },
+            )*
+        }
+
+        impl DeclaredCommand {
+            /// Validation rows, merged into the command table
+            pub const SPECS: &'static [$crate::redis::CommandSpec] = &[
+                $(
+                    $crate::redis::CommandSpec::declared(
+                        $name,
+                        &[$($crate::redis::command_macro::ArgKind::$kind),*],
+                    ),
+                )*
+            ];
+
+            /// Parse argv for a declared command. Returns `None` for names
+            /// that are not declared here; arity has already been checked by
+            /// `command_table::validate`.
+            pub fn parse<'a>(
+                name: &str,
+                argc: usize,
+                arg: impl Fn(usize) -> Option<&'a [u8]>,
+            ) -> Option<Result<Self, String>> {
+                $(
+                    if name.eq_ignore_ascii_case($name) {
+                        debug_assert_eq!(
+                            argc,
+                            1 + <[&str]>::len(&[$(stringify!($arg)),*]),
+                            "Precondition: argc validated against the table"
+                        );
+                        let parse = || -> Result<Self, String> {
+                            let mut _pos = 0;
+                            $(
+                                _pos += 1;
+                                let $arg = declare_commands!(@parse $kind, arg(_pos))?;
+                            )*
+                            Ok(DeclaredCommand::$variant { $($arg),* })
+                        };
+                        return Some(parse());
+                    }
+                )*
+                let _ = (argc, &arg);
+                None
+            }
+
+            /// Uppercase command name
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(
+                        DeclaredCommand::$variant { .. } => {
+                            const BYTES: [u8; $name.len()] =
+                                $crate::redis::command_macro::ascii_uppercase($name);
+                            const NAME: &str = match std::str::from_utf8(&BYTES) {
+                                Ok(name) => name,
+                                Err(_) => panic!("command names are ASCII"),
+                            };
+                            NAME
+                        }
+                    )*
+                }
+            }
+
+            pub fn is_read_only(&self) -> bool {
+                match self {
+                    $(DeclaredCommand::$variant { .. } => declare_commands!(@readonly $flag),)*
+                }
+            }
+
+            /// Key arguments in argv order
+            pub fn keys(&self) -> Vec<&String> {
+                #[allow(unused_mut)]
+                let mut keys = Vec::new();
+                match self {
+                    $(
+                        DeclaredCommand::$variant { $($arg),* } => {
+                            $(declare_commands!(@key $kind, keys, $arg);)*
+                        }
+                    )*
+                }
+                keys
+            }
+
+            pub fn keys_mut(&mut self) -> Vec<&mut String> {
+                #[allow(unused_mut)]
+                let mut keys = Vec::new();
+                match self {
+                    $(
+                        DeclaredCommand::$variant { $($arg),* } => {
+                            $(declare_commands!(@key $kind, keys, $arg);)*
+                        }
+                    )*
+                }
+                keys
+            }
+
+            pub(crate) fn execute(
+                &self,
+                executor: &mut $crate::redis::CommandExecutor,
+            ) -> $crate::redis::RespValue {
+                match self {
+                    $(
+                        DeclaredCommand::$variant { $($arg),* } => {
+                            executor.$handler($(declare_commands!(@pass $kind, $arg)),*)
+                        }
+                    )*
+                }
+            }
+        }
+    };
+
+    (@ty Key) => { String };
+    (@ty Sds) => { $crate::redis::SDS };
+    (@ty Int) => { i64 };
+
+    (@parse Key, $bytes:expr) => { $crate::redis::command_macro::arg_string($bytes) };
+    (@parse Sds, $bytes:expr) => { $crate::redis::command_macro::arg_sds($bytes) };
+    (@parse Int, $bytes:expr) => { $crate::redis::command_macro::arg_int($bytes) };
+
+    (@pass Key, $arg:ident) => { $arg.as_str() };
+    (@pass Sds, $arg:ident) => { $arg };
+    (@pass Int, $arg:ident) => { *$arg };
+
+    (@key Key, $keys:ident, $arg:ident) => { $keys.push($arg) };
+    (@key $kind:ident, $keys:ident, $arg:ident) => { let _ = $arg; };
+
+    (@readonly readonly) => { true };
+    (@readonly write) => { false };
+}
+
+pub(crate) use declare_commands;
+
+#[cfg(test)]
+mod tests {
+    use super::super::declared_commands::DeclaredCommand;
+    use super::super::CommandSpec;
+
+    fn parse(parts: &[&str]) -> Option<Result<DeclaredCommand, String>> {
+        DeclaredCommand::parse(parts[0], parts.len(), |i| Some(parts[i].as_bytes()))
+    }
+
+    #[test]
+    fn test_generated_metadata() {
+        let cmd = parse(&["zrank", "z", "m"]).unwrap().unwrap();
+        assert_eq!(cmd.name(), "ZRANK");
+        assert!(cmd.is_read_only());
+        assert_eq!(cmd.keys(), vec!["z"]);
+
+        let mut cmd = parse(&["EXPIREAT", "k", "100"]).unwrap().unwrap();
+        assert!(matches!(
+            cmd,
+            DeclaredCommand::ExpireAt { timestamp: 100, .. }
+        ));
+        assert!(!cmd.is_read_only());
+        cmd.keys_mut()[0].insert_str(0, "t:");
+        assert_eq!(cmd.keys(), vec!["t:k"]);
+
+        assert!(parse(&["NOTDECLARED", "k"]).is_none());
+    }
+
+    #[test]
+    fn test_generated_specs() {
+        let spec = CommandSpec::lookup("PEXPIREAT").unwrap();
+        assert_eq!(spec.arity(), 3);
+        assert_eq!(spec.key_positions(3), vec![1]);
+        assert!(DeclaredCommand::SPECS.iter().any(|s| s.name == "hkeys"));
+    }
+
+    #[test]
+    fn test_typed_argument_errors() {
+        assert_eq!(
+            parse(&["EXPIREAT", "k", "soon"]).unwrap().unwrap_err(),
+            "ERR value is not an integer or out of range"
+        );
+    }
+}
@@ -0,0 +1,872 @@
+//! Command validation table.
+//!
+//! One row per command the parsers understand: argument counts, key
+//! positions, and the argument positions that must parse as integers or
+//! floats. Both parsers (`from_resp` and `from_resp_zero_copy`) and the Lua
+//! bridge run `validate` before building a `Command`, so a malformed call
+//! fails with the same Redis error whichever way it arrives. A new command
+//! gets arity and numeric validation by adding its row here. Commands
+//! defined with `declare_commands!` contribute their rows from
+//! `DeclaredCommand::SPECS` instead.
+//!
+//! Positions count argv from the command name, as in Redis' own table:
+//! `argv[0]` is the name, `argv[1]` the first argument.
+//!
+//! `COMMAND_FLAGS` gives every command, declared ones included, a
+//! Redis-style flags string such as `"write denyoom fast @string"`: command
+//! flags, then ACL categories. COMMAND INFO reports them, adding the
+//! categories Redis implies from the flags (`@write`, `@read`, `@fast` or
+//! `@slow`, `@admin`, `@pubsub`, `@blocking`).
+//!
+//! # TigerStyle Invariants
+//!
+//! - `min_argc >= 1` and `max_argc`, when set, is at least `min_argc`
+//! - Names are lowercase and unique
+//! - Typed positions lie inside the allowed argument range
+//! - Every command has one flags row, made of known flags and categories
+
+use super::command_macro::ArgKind;
+use super::declared_commands::DeclaredCommand;
+use ahash::AHashMap;
+use std::sync::OnceLock;
+
+/// Validation rules for one command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    /// Lowercase command name, as shown in errors
+    pub name: &'static str,
+    /// Minimum argv length (including the name)
+    pub min_argc: usize,
+    /// Maximum argv length, `None` for variadic commands
+    pub max_argc: Option<usize>,
+    /// Position of the first key, 0 for keyless commands
+    pub first_key: usize,
+    /// Position of the last key; negative counts from the end (-1 = last)
+    pub last_key: isize,
+    /// Distance between keys (2 for MSET's key/value pairs)
+    pub key_step: usize,
+    /// Positions that must parse as a 64-bit integer
+    pub integer_args: &'static [usize],
+    /// Positions that must parse as a float
+    pub float_args: &'static [usize],
+}
+
+impl CommandSpec {
+    const fn new(name: &'static str, min_argc: usize, max_argc: Option<usize>) -> Self {
+        CommandSpec {
+            name,
+            min_argc,
+            max_argc,
+            first_key: 0,
+            last_key: 0,
+            key_step: 0,
+            integer_args: &[],
+            float_args: &[],
+        }
+    }
+
+    /// Exactly `argc` elements
+    const fn exact(name: &'static str, argc: usize) -> Self {
+        Self::new(name, argc, Some(argc))
+    }
+
+    /// At least `argc` elements
+    const fn at_least(name: &'static str, argc: usize) -> Self {
+        Self::new(name, argc, None)
+    }
+
+    /// Between `min` and `max` elements
+    const fn between(name: &'static str, min: usize, max: usize) -> Self {
+        Self::new(name, min, Some(max))
+    }
+
+    const fn keys(mut self, first: usize, last: isize, step: usize) -> Self {
+        self.first_key = first;
+        self.last_key = last;
+        self.key_step = step;
+        self
+    }
+
+    /// Single key at position 1
+    const fn key(self) -> Self {
+        self.keys(1, 1, 1)
+    }
+
+    const fn integers(mut self, positions: &'static [usize]) -> Self {
+        self.integer_args = positions;
+        self
+    }
+
+    const fn floats(mut self, positions: &'static [usize]) -> Self {
+        self.float_args = positions;
+        self
+    }
+
+    /// Row for a `declare_commands!` entry: exact arity from its argument
+    /// list, keys wherever `ArgKind::Key` appears (they must be adjacent).
+    /// Typed arguments are checked by the generated parser.
+    pub(crate) const fn declared(name: &'static str, kinds: &'static [ArgKind]) -> Self {
+        let mut spec = Self::exact(name, kinds.len() + 1);
+        let mut i = 0;
+        while i < kinds.len() {
+            if matches!(kinds[i], ArgKind::Key) {
+                assert!(
+                    spec.first_key == 0 || spec.last_key == i as isize,
+                    "declared command keys must be adjacent"
+                );
+                if spec.first_key == 0 {
+                    spec.first_key = i + 1;
+                }
+                spec.last_key = (i + 1) as isize;
+                spec.key_step = 1;
+            }
+            i += 1;
+        }
+        spec
+    }
+
+    /// Look up a command by name (any case)
+    pub fn lookup(name: &str) -> Option<&'static CommandSpec> {
+        static INDEX: OnceLock<AHashMap<String, &'static CommandSpec>> = OnceLock::new();
+        let index = INDEX.get_or_init(|| {
+            let index: AHashMap<_, _> = Self::all()
+                .map(|spec| (spec.name.to_ascii_uppercase(), spec))
+                .collect();
+            debug_assert_eq!(
+                index.len(),
+                COMMAND_TABLE.len() + DeclaredCommand::SPECS.len(),
+                "Invariant: command names must be unique across both tables"
+            );
+            index
+        });
+        match index.get(name) {
+            Some(spec) => Some(*spec),
+            None => index.get(&name.to_ascii_uppercase()).copied(),
+        }
+    }
+
+    /// Every row: the table, then the `declare_commands!` rows
+    pub fn all() -> impl Iterator<Item = &'static CommandSpec> {
+        COMMAND_TABLE.iter().chain(DeclaredCommand::SPECS)
+    }
+
+    /// The command's flags string from `COMMAND_FLAGS`
+    pub fn flags(&self) -> &'static str {
+        static INDEX: OnceLock<AHashMap<&'static str, &'static str>> = OnceLock::new();
+        let index = INDEX.get_or_init(|| COMMAND_FLAGS.iter().copied().collect());
+        index.get(self.name).copied().unwrap_or("")
+    }
+
+    /// Command flags, as COMMAND INFO lists them
+    pub fn flag_names(&self) -> impl Iterator<Item = &'static str> {
+        self.flags()
+            .split_whitespace()
+            .filter(|w| !w.starts_with('@'))
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flag_names().any(|f| f == flag)
+    }
+
+    /// ACL categories: those the flags imply, then the explicit ones
+    pub fn acl_categories(&self) -> Vec<&'static str> {
+        let mut categories = Vec::new();
+        if self.has_flag("write") {
+            categories.push("@write");
+        }
+        if self.has_flag("readonly") {
+            categories.push("@read");
+        }
+        if self.has_flag("admin") {
+            categories.extend(["@admin", "@dangerous"]);
+        }
+        if self.has_flag("pubsub") {
+            categories.push("@pubsub");
+        }
+        if self.has_flag("blocking") {
+            categories.push("@blocking");
+        }
+        categories.push(if self.has_flag("fast") {
+            "@fast"
+        } else {
+            "@slow"
+        });
+        for category in self
+            .flags()
+            .split_whitespace()
+            .filter(|w| w.starts_with('@'))
+        {
+            if !categories.contains(&category) {
+                categories.push(category);
+            }
+        }
+        categories
+    }
+
+    /// The group COMMAND DOCS reports, from the first data category
+    pub fn group(&self) -> &'static str {
+        let category = self
+            .flags()
+            .split_whitespace()
+            .find(|w| w.starts_with('@') && *w != "@dangerous");
+        match category {
+            Some("@keyspace") => "generic",
+            Some("@sortedset") => "sorted-set",
+            Some("@transaction") => "transactions",
+            Some(category) => &category[1..],
+            None if self.has_flag("pubsub") => "pubsub",
+            None => "server",
+        }
+    }
+
+    /// Redis-style arity: `N` for exactly N elements, `-N` for at least N
+    pub fn arity(&self) -> i64 {
+        match self.max_argc {
+            Some(max) if max == self.min_argc => max as i64,
+            _ => -(self.min_argc as i64),
+        }
+    }
+
+    /// Argv positions holding keys for a call with `argc` elements
+    pub fn key_positions(&self, argc: usize) -> Vec<usize> {
+        if self.first_key == 0 || argc <= self.first_key {
+            return Vec::new();
+        }
+        let last = if self.last_key < 0 {
+            argc as isize + self.last_key
+        } else {
+            self.last_key
+        };
+        if last < self.first_key as isize {
+            return Vec::new();
+        }
+        let last = (last as usize).min(argc - 1);
+        (self.first_key..=last).step_by(self.key_step).collect()
+    }
+
+    /// Check argument count and numeric arguments.
+    ///
+    /// `arg(i)` returns the bytes of `argv[i]`, or `None` when the element is
+    /// not a bulk string (the parser reports that itself).
+    pub fn validate<'a>(
+        &self,
+        argc: usize,
+        arg: impl Fn(usize) -> Option<&'a [u8]>,
+    ) -> Result<(), String> {
+        debug_assert!(
+            argc >= 1,
+            "Precondition: argv must contain the command name"
+        );
+
+        if argc < self.min_argc || self.max_argc.is_some_and(|max| argc > max) {
+            return Err(format!(
+                "ERR wrong number of arguments for '{}' command",
+                self.name
+            ));
+        }
+        for &pos in self.integer_args.iter().filter(|&&pos| pos < argc) {
+            if let Some(bytes) = arg(pos) {
+                if parse_str(bytes)
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .is_none()
+                {
+                    return Err("ERR value is not an integer or out of range".to_string());
+                }
+            }
+        }
+        for &pos in self.float_args.iter().filter(|&&pos| pos < argc) {
+            if let Some(bytes) = arg(pos) {
+                let valid = parse_str(bytes)
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .is_some_and(|f| !f.is_nan());
+                if !valid {
+                    return Err("ERR value is not a valid float".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        debug_assert!(self.min_argc >= 1, "Invariant: argv includes the name");
+        debug_assert!(
+            self.max_argc.is_none_or(|max| max >= self.min_argc),
+            "Invariant: '{}' max_argc must be at least min_argc",
+            self.name
+        );
+        debug_assert!(
+            self.name.bytes().all(|b| !b.is_ascii_uppercase()),
+            "Invariant: '{}' must be lowercase",
+            self.name
+        );
+        for &pos in self.integer_args.iter().chain(self.float_args) {
+            debug_assert!(
+                pos >= 1 && self.max_argc.is_none_or(|max| pos < max),
+                "Invariant: '{}' typed position {} out of range",
+                self.name,
+                pos
+            );
+        }
+        for word in self.flags().split_whitespace() {
+            debug_assert!(
+                FLAGS.contains(&word) || CATEGORIES.contains(&word),
+                "Invariant: '{}' has unknown flag {}",
+                self.name,
+                word
+            );
+        }
+        debug_assert!(
+            !(self.has_flag("write") && self.has_flag("readonly")),
+            "Invariant: '{}' cannot be both write and readonly",
+            self.name
+        );
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn verify_invariants(&self) {}
+}
+
+/// Validate argv against the table; commands without a row are left to the
+/// parser (which turns them into `Command::Unknown`)
+pub fn validate<'a>(
+    name: &str,
+    argc: usize,
+    arg: impl Fn(usize) -> Option<&'a [u8]>,
+) -> Result<(), String> {
+    match CommandSpec::lookup(name) {
+        Some(spec) => {
+            spec.verify_invariants();
+            spec.validate(argc, arg)
+        }
+        None => Ok(()),
+    }
+}
+
+fn parse_str(bytes: &[u8]) -> Option<&str> {
+    std::str::from_utf8(bytes).ok()
+}
+
+/// The validation table, grouped like `Command`
+pub static COMMAND_TABLE: &[CommandSpec] = &[
+    // Connection and server
+    CommandSpec::between("ping", 1, 2),
+    CommandSpec::at_least("info", 1),
+    CommandSpec::exact("time", 1),
+    CommandSpec::exact("dbsize", 1),
+    CommandSpec::at_least("config", 2),
+    CommandSpec::at_least("latency", 2),
+    CommandSpec::exact("select", 2).integers(&[1]),
+    CommandSpec::exact("echo", 2),
+    CommandSpec::at_least("lolwut", 1),
+    CommandSpec::at_least("auth", 2),
+    CommandSpec::at_least("acl", 2),
+    CommandSpec::at_least("flushdb", 1),
+    CommandSpec::at_least("flushall", 1),
+    CommandSpec::at_least("command", 1),
+    CommandSpec::at_least("client", 2),
+    CommandSpec::at_least("hello", 1).integers(&[1]),
+    CommandSpec::exact("reset", 1),
+    CommandSpec::at_least("debug", 2),
+    CommandSpec::exact("wait", 3).integers(&[1, 2]),
+    CommandSpec::at_least("shutdown", 1),
+    // Pub/Sub
+    CommandSpec::exact("publish", 3),
+    CommandSpec::at_least("subscribe", 2),
+    CommandSpec::at_least("unsubscribe", 1),
+    CommandSpec::at_least("psubscribe", 2),
+    CommandSpec::at_least("punsubscribe", 1),
+    CommandSpec::at_least("pubsub", 2),
+    // Transactions
+    CommandSpec::exact("multi", 1),
+    CommandSpec::exact("exec", 1),
+    CommandSpec::exact("discard", 1),
+    CommandSpec::at_least("watch", 2).keys(1, -1, 1),
+    CommandSpec::exact("unwatch", 1),
+    // Scripting
+    CommandSpec::at_least("eval", 3).integers(&[2]),
+    CommandSpec::at_least("evalsha", 3).integers(&[2]),
+    CommandSpec::at_least("eval_ro", 3).integers(&[2]),
+    CommandSpec::at_least("evalsha_ro", 3).integers(&[2]),
+    CommandSpec::at_least("script", 2),
+    CommandSpec::at_least("function", 2),
+    CommandSpec::at_least("fcall", 3).integers(&[2]),
+    CommandSpec::at_least("fcall_ro", 3).integers(&[2]),
+    // Strings
+    CommandSpec::exact("get", 2).key(),
+    CommandSpec::at_least("set", 3).key(),
+    CommandSpec::exact("setex", 4).key().integers(&[2]),
+    CommandSpec::exact("psetex", 4).key().integers(&[2]),
+    CommandSpec::exact("incr", 2).key(),
+    CommandSpec::exact("decr", 2).key(),
+    CommandSpec::exact("incrby", 3).key().integers(&[2]),
+    CommandSpec::exact("decrby", 3).key().integers(&[2]),
+    CommandSpec::exact("incrbyfloat", 3).key().floats(&[2]),
+    CommandSpec::exact("append", 3).key(),
+    CommandSpec::exact("getset", 3).key(),
+    CommandSpec::exact("strlen", 2).key(),
+    CommandSpec::at_least("mget", 2).keys(1, -1, 1),
+    CommandSpec::at_least("mset", 3).keys(1, -1, 2),
+    CommandSpec::at_least("msetnx", 3).keys(1, -1, 2),
+    CommandSpec::exact("getrange", 4).key().integers(&[2, 3]),
+    CommandSpec::exact("substr", 4).key().integers(&[2, 3]),
+    CommandSpec::exact("setrange", 4).key().integers(&[2]),
+    CommandSpec::exact("setbit", 4).key(),
+    CommandSpec::exact("getbit", 3).key(),
+    CommandSpec::between("bitcount", 2, 5).key(),
+    CommandSpec::between("bitpos", 3, 6)
+        .key()
+        .integers(&[2, 3, 4]),
+    CommandSpec::at_least("bitop", 4).keys(2, -1, 1),
+    CommandSpec::at_least("bitfield", 2).key(),
+    CommandSpec::at_least("bitfield_ro", 2).key(),
+    CommandSpec::at_least("getex", 2).key(),
+    CommandSpec::exact("getdel", 2).key(),
+    // Keys
+    CommandSpec::at_least("del", 2).keys(1, -1, 1),
+    CommandSpec::at_least("unlink", 2).keys(1, -1, 1),
+    CommandSpec::at_least("exists", 2).keys(1, -1, 1),
+    CommandSpec::at_least("touch", 2).keys(1, -1, 1),
+    CommandSpec::exact("type", 2).key(),
+    CommandSpec::exact("keys", 2),
+    CommandSpec::at_least("expire", 3).key().integers(&[2]),
+    CommandSpec::at_least("pexpire", 3).key().integers(&[2]),
+    CommandSpec::exact("ttl", 2).key(),
+    CommandSpec::exact("pttl", 2).key(),
+    CommandSpec::exact("persist", 2).key(),
+    CommandSpec::exact("rename", 3).keys(1, 2, 1),
+    CommandSpec::exact("renamenx", 3).keys(1, 2, 1),
+    CommandSpec::between("copy", 3, 4).keys(1, 2, 1),
+    CommandSpec::at_least("restore", 4).key().integers(&[2]),
+    CommandSpec::exact("randomkey", 1),
+    CommandSpec::at_least("sort", 2).key(),
+    CommandSpec::at_least("object", 2).keys(2, 2, 1),
+    CommandSpec::at_least("memory", 2).keys(2, 2, 1),
+    CommandSpec::at_least("scan", 2),
+    // Lists
+    CommandSpec::at_least("lpush", 3).key(),
+    CommandSpec::at_least("rpush", 3).key(),
+    CommandSpec::at_least("lpop", 2).key(),
+    CommandSpec::at_least("rpop", 2).key(),
+    CommandSpec::exact("lrange", 4).key().integers(&[2, 3]),
+    CommandSpec::exact("llen", 2).key(),
+    CommandSpec::exact("lindex", 3).key().integers(&[2]),
+    CommandSpec::exact("lset", 4).key().integers(&[2]),
+    CommandSpec::exact("ltrim", 4).key().integers(&[2, 3]),
+    CommandSpec::exact("rpoplpush", 3).keys(1, 2, 1),
+    CommandSpec::exact("lmove", 5).keys(1, 2, 1),
+    CommandSpec::exact("linsert", 5).key(),
+    CommandSpec::exact("lrem", 4).key().integers(&[2]),
+    CommandSpec::at_least("lpos", 3).key(),
+    CommandSpec::at_least("lpushx", 3).key(),
+    CommandSpec::at_least("rpushx", 3).key(),
+    CommandSpec::at_least("blpop", 3).keys(1, -2, 1),
+    CommandSpec::at_least("brpop", 3).keys(1, -2, 1),
+    CommandSpec::exact("blmove", 6).keys(1, 2, 1),
+    // Sets
+    CommandSpec::at_least("sadd", 3).key(),
+    CommandSpec::exact("smembers", 2).key(),
+    CommandSpec::exact("sismember", 3).key(),
+    CommandSpec::at_least("srem", 3).key(),
+    CommandSpec::exact("scard", 2).key(),
+    CommandSpec::between("spop", 2, 3).key(),
+    CommandSpec::at_least("smismember", 3).key(),
+    CommandSpec::between("srandmember", 2, 3)
+        .key()
+        .integers(&[2]),
+    CommandSpec::at_least("sintercard", 3),
+    // Hashes
+    CommandSpec::at_least("hset", 4).key(),
+    CommandSpec::exact("hget", 3).key(),
+    CommandSpec::exact("hgetall", 2).key(),
+    CommandSpec::exact("hincrby", 4).key().integers(&[3]),
+    CommandSpec::exact("hincrbyfloat", 4).key().floats(&[3]),
+    CommandSpec::at_least("hmget", 3).key(),
+    CommandSpec::between("hrandfield", 2, 4)
+        .key()
+        .integers(&[2]),
+    CommandSpec::at_least("hexpire", 6).key().integers(&[2]),
+    CommandSpec::at_least("hpexpire", 6).key().integers(&[2]),
+    CommandSpec::at_least("hexpireat", 6).key().integers(&[2]),
+    CommandSpec::at_least("hpexpireat", 6).key().integers(&[2]),
+    CommandSpec::at_least("httl", 5).key(),
+    CommandSpec::at_least("hpttl", 5).key(),
+    CommandSpec::at_least("hexpiretime", 5).key(),
+    CommandSpec::at_least("hpexpiretime", 5).key(),
+    CommandSpec::at_least("hpersist", 5).key(),
+    CommandSpec::at_least("hdel", 3).key(),
+    CommandSpec::exact("hlen", 2).key(),
+    CommandSpec::exact("hexists", 3).key(),
+    CommandSpec::at_least("hscan", 3).key(),
+    // Sorted sets
+    CommandSpec::at_least("zadd", 4).key(),
+    CommandSpec::at_least("zrange", 4).key(),
+    CommandSpec::at_least("zrangestore", 5).keys(1, 2, 1),
+    CommandSpec::at_least("zrevrange", 4)
+        .key()
+        .integers(&[2, 3]),
+    CommandSpec::exact("zscore", 3).key(),
+    CommandSpec::at_least("zrem", 3).key(),
+    CommandSpec::exact("zremrangebyrank", 4)
+        .key()
+        .integers(&[2, 3]),
+    CommandSpec::exact("zremrangebyscore", 4).key(),
+    CommandSpec::exact("zremrangebylex", 4).key(),
+    CommandSpec::exact("zcard", 2).key(),
+    CommandSpec::exact("zcount", 4).key(),
+    CommandSpec::at_least("zrangebyscore", 4).key(),
+    CommandSpec::between("zpopmin", 2, 3).key().integers(&[2]),
+    CommandSpec::between("zpopmax", 2, 3).key().integers(&[2]),
+    CommandSpec::exact("zincrby", 4).key().floats(&[2]),
+    CommandSpec::at_least("zmscore", 3).key(),
+    CommandSpec::between("zrandmember", 2, 4)
+        .key()
+        .integers(&[2]),
+    CommandSpec::at_least("zunion", 3).integers(&[1]),
+    CommandSpec::at_least("zinter", 3).integers(&[1]),
+    CommandSpec::at_least("zdiff", 3).integers(&[1]),
+    CommandSpec::at_least("zunionstore", 4).key().integers(&[2]),
+    CommandSpec::at_least("zinterstore", 4).key().integers(&[2]),
+    CommandSpec::at_least("zdiffstore", 4).key().integers(&[2]),
+    CommandSpec::at_least("bzpopmin", 3).keys(1, -2, 1),
+    CommandSpec::at_least("bzpopmax", 3).keys(1, -2, 1),
+    CommandSpec::at_least("zscan", 3).key(),
+    // Streams
+    CommandSpec::at_least("xadd", 5).key(),
+    CommandSpec::at_least("xrange", 4).key(),
+    CommandSpec::at_least("xrevrange", 4).key(),
+    CommandSpec::at_least("xread", 4),
+    CommandSpec::at_least("xinfo", 2).keys(2, 2, 1),
+    // Stream consumer groups
+    CommandSpec::at_least("xgroup", 2).keys(2, 2, 1),
+    CommandSpec::at_least("xreadgroup", 7),
+    CommandSpec::at_least("xack", 4).key(),
+    CommandSpec::at_least("xpending", 3).key(),
+    CommandSpec::at_least("xclaim", 6).key(),
+    CommandSpec::at_least("xautoclaim", 6).key(),
+];
+
+/// Flags COMMAND INFO reports, in Redis' spelling
+const FLAGS: &[&str] = &[
+    "write",
+    "readonly",
+    "denyoom",
+    "admin",
+    "pubsub",
+    "noscript",
+    "blocking",
+    "loading",
+    "stale",
+    "fast",
+    "no_auth",
+    "movablekeys",
+];
+
+/// ACL categories a flags string may name; the rest are implied by flags
+const CATEGORIES: &[&str] = &[
+    "@keyspace",
+    "@string",
+    "@bitmap",
+    "@list",
+    "@set",
+    "@sortedset",
+    "@hash",
+    "@stream",
+    "@connection",
+    "@transaction",
+    "@scripting",
+    "@dangerous",
+];
+
+/// Flags and explicit ACL categories per command, in table order
+static COMMAND_FLAGS: &[(&str, &str)] = &[
+    // Connection and server
+    ("ping", "fast @connection"),
+    ("info", "loading stale @dangerous"),
+    ("time", "loading stale fast"),
+    ("dbsize", "readonly fast @keyspace"),
+    ("config", "admin noscript loading stale"),
+    ("latency", "admin noscript loading stale"),
+    ("select", "loading stale fast @connection"),
+    ("echo", "fast @connection"),
+    ("lolwut", "readonly fast"),
+    ("auth", "noscript loading stale fast no_auth @connection"),
+    ("acl", "admin noscript loading stale"),
+    ("flushdb", "write @keyspace @dangerous"),
+    ("flushall", "write @keyspace @dangerous"),
+    ("command", "loading stale @connection"),
+    ("client", "noscript loading stale @connection"),
+    ("hello", "noscript loading stale fast no_auth @connection"),
+    ("reset", "noscript loading stale fast no_auth @connection"),
+    ("debug", "admin noscript loading stale"),
+    ("wait", "@connection"),
+    ("shutdown", "admin noscript loading stale"),
+    // Pub/Sub
+    ("publish", "pubsub loading stale fast"),
+    ("subscribe", "pubsub noscript loading stale"),
+    ("unsubscribe", "pubsub noscript loading stale"),
+    ("psubscribe", "pubsub noscript loading stale"),
+    ("punsubscribe", "pubsub noscript loading stale"),
+    ("pubsub", "pubsub loading stale"),
+    // Transactions
+    ("multi", "noscript loading stale fast @transaction"),
+    ("exec", "noscript loading stale @transaction"),
+    ("discard", "noscript loading stale fast @transaction"),
+    ("watch", "noscript loading stale fast @transaction"),
+    ("unwatch", "noscript loading stale fast @transaction"),
+    // Scripting
+    ("eval", "noscript stale movablekeys @scripting"),
+    ("evalsha", "noscript stale movablekeys @scripting"),
+    ("eval_ro", "readonly noscript stale movablekeys @scripting"),
+    ("evalsha_ro", "readonly noscript stale movablekeys @scripting"),
+    ("script", "noscript @scripting"),
+    ("function", "noscript @scripting"),
+    ("fcall", "noscript stale movablekeys @scripting"),
+    ("fcall_ro", "readonly noscript stale movablekeys @scripting"),
+    // Strings
+    ("get", "readonly fast @string"),
+    ("set", "write denyoom @string"),
+    ("setex", "write denyoom @string"),
+    ("psetex", "write denyoom @string"),
+    ("incr", "write denyoom fast @string"),
+    ("decr", "write denyoom fast @string"),
+    ("incrby", "write denyoom fast @string"),
+    ("decrby", "write denyoom fast @string"),
+    ("incrbyfloat", "write denyoom fast @string"),
+    ("append", "write denyoom fast @string"),
+    ("getset", "write denyoom fast @string"),
+    ("strlen", "readonly fast @string"),
+    ("mget", "readonly fast @string"),
+    ("mset", "write denyoom @string"),
+    ("msetnx", "write denyoom @string"),
+    ("getrange", "readonly @string"),
+    ("substr", "readonly @string"),
+    ("setrange", "write denyoom @string"),
+    ("setbit", "write denyoom @bitmap"),
+    ("getbit", "readonly fast @bitmap"),
+    ("bitcount", "readonly @bitmap"),
+    ("bitpos", "readonly @bitmap"),
+    ("bitop", "write denyoom @bitmap"),
+    ("bitfield", "write denyoom @bitmap"),
+    ("bitfield_ro", "readonly fast @bitmap"),
+    ("getex", "write fast @string"),
+    ("getdel", "write fast @string"),
+    // Keys
+    ("del", "write @keyspace"),
+    ("unlink", "write fast @keyspace"),
+    ("exists", "readonly fast @keyspace"),
+    ("touch", "readonly fast @keyspace"),
+    ("type", "readonly fast @keyspace"),
+    ("keys", "readonly @keyspace @dangerous"),
+    ("expire", "write fast @keyspace"),
+    ("pexpire", "write fast @keyspace"),
+    ("ttl", "readonly fast @keyspace"),
+    ("pttl", "readonly fast @keyspace"),
+    ("persist", "write fast @keyspace"),
+    ("rename", "write @keyspace"),
+    ("renamenx", "write fast @keyspace"),
+    ("copy", "write denyoom @keyspace"),
+    ("restore", "write denyoom @keyspace @dangerous"),
+    ("randomkey", "readonly @keyspace"),
+    (
+        "sort",
+        "write denyoom movablekeys @set @sortedset @list @dangerous",
+    ),
+    ("object", "readonly @keyspace"),
+    ("memory", "readonly @keyspace"),
+    ("scan", "readonly @keyspace"),
+    // Lists
+    ("lpush", "write denyoom fast @list"),
+    ("rpush", "write denyoom fast @list"),
+    ("lpop", "write fast @list"),
+    ("rpop", "write fast @list"),
+    ("lrange", "readonly @list"),
+    ("llen", "readonly fast @list"),
+    ("lindex", "readonly @list"),
+    ("lset", "write denyoom @list"),
+    ("ltrim", "write @list"),
+    ("rpoplpush", "write denyoom @list"),
+    ("lmove", "write denyoom @list"),
+    ("linsert", "write denyoom @list"),
+    ("lrem", "write @list"),
+    ("lpos", "readonly @list"),
+    ("lpushx", "write denyoom fast @list"),
+    ("rpushx", "write denyoom fast @list"),
+    ("blpop", "write blocking @list"),
+    ("brpop", "write blocking @list"),
+    ("blmove", "write denyoom blocking @list"),
+    // Sets
+    ("sadd", "write denyoom fast @set"),
+    ("smembers", "readonly @set"),
+    ("sismember", "readonly fast @set"),
+    ("srem", "write fast @set"),
+    ("scard", "readonly fast @set"),
+    ("spop", "write fast @set"),
+    ("smismember", "readonly fast @set"),
+    ("srandmember", "readonly @set"),
+    ("sintercard", "readonly movablekeys @set"),
+    // Hashes
+    ("hset", "write denyoom fast @hash"),
+    ("hget", "readonly fast @hash"),
+    ("hgetall", "readonly @hash"),
+    ("hincrby", "write denyoom fast @hash"),
+    ("hincrbyfloat", "write denyoom fast @hash"),
+    ("hmget", "readonly fast @hash"),
+    ("hrandfield", "readonly @hash"),
+    ("hexpire", "write fast @hash"),
+    ("hpexpire", "write fast @hash"),
+    ("hexpireat", "write fast @hash"),
+    ("hpexpireat", "write fast @hash"),
+    ("httl", "readonly fast @hash"),
+    ("hpttl", "readonly fast @hash"),
+    ("hexpiretime", "readonly fast @hash"),
+    ("hpexpiretime", "readonly fast @hash"),
+    ("hpersist", "write fast @hash"),
+    ("hdel", "write fast @hash"),
+    ("hlen", "readonly fast @hash"),
+    ("hexists", "readonly fast @hash"),
+    ("hscan", "readonly @hash"),
+    // Sorted sets
+    ("zadd", "write denyoom fast @sortedset"),
+    ("zrange", "readonly @sortedset"),
+    ("zrangestore", "write denyoom @sortedset"),
+    ("zrevrange", "readonly @sortedset"),
+    ("zscore", "readonly fast @sortedset"),
+    ("zrem", "write fast @sortedset"),
+    ("zremrangebyrank", "write @sortedset"),
+    ("zremrangebyscore", "write @sortedset"),
+    ("zremrangebylex", "write @sortedset"),
+    ("zcard", "readonly fast @sortedset"),
+    ("zcount", "readonly fast @sortedset"),
+    ("zrangebyscore", "readonly @sortedset"),
+    ("zpopmin", "write fast @sortedset"),
+    ("zpopmax", "write fast @sortedset"),
+    ("zincrby", "write denyoom fast @sortedset"),
+    ("zmscore", "readonly fast @sortedset"),
+    ("zrandmember", "readonly @sortedset"),
+    ("zunion", "readonly movablekeys @sortedset"),
+    ("zinter", "readonly movablekeys @sortedset"),
+    ("zdiff", "readonly movablekeys @sortedset"),
+    ("zunionstore", "write denyoom movablekeys @sortedset"),
+    ("zinterstore", "write denyoom movablekeys @sortedset"),
+    ("zdiffstore", "write denyoom movablekeys @sortedset"),
+    ("bzpopmin", "write blocking fast @sortedset"),
+    ("bzpopmax", "write blocking fast @sortedset"),
+    ("zscan", "readonly @sortedset"),
+    // Streams
+    ("xadd", "write denyoom fast @stream"),
+    ("xrange", "readonly @stream"),
+    ("xrevrange", "readonly @stream"),
+    ("xread", "readonly blocking movablekeys @stream"),
+    ("xinfo", "readonly @stream"),
+    // Stream consumer groups
+    ("xgroup", "write denyoom @stream"),
+    ("xreadgroup", "write blocking movablekeys @stream"),
+    ("xack", "write fast @stream"),
+    ("xpending", "readonly @stream"),
+    ("xclaim", "write fast @stream"),
+    ("xautoclaim", "write fast @stream"),
+    // declare_commands!
+    ("setnx", "write denyoom fast @string"),
+    ("expireat", "write fast @keyspace"),
+    ("pexpireat", "write fast @keyspace"),
+    ("expiretime", "readonly fast @keyspace"),
+    ("pexpiretime", "readonly fast @keyspace"),
+    ("dump", "readonly @keyspace"),
+    ("hkeys", "readonly @hash"),
+    ("hvals", "readonly @hash"),
+    ("hsetnx", "write denyoom fast @hash"),
+    ("hstrlen", "readonly fast @hash"),
+    ("zrank", "readonly fast @sortedset"),
+    ("xlen", "readonly fast @stream"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(parts: &[&str]) -> Vec<Vec<u8>> {
+        parts.iter().map(|p| p.as_bytes().to_vec()).collect()
+    }
+
+    fn check(parts: &[&str]) -> Result<(), String> {
+        let args = argv(parts);
+        validate(parts[0], args.len(), |i| Some(args[i].as_slice()))
+    }
+
+    #[test]
+    fn test_table_is_well_formed() {
+        let mut names = std::collections::HashSet::new();
+        for spec in CommandSpec::all() {
+            spec.verify_invariants();
+            assert!(names.insert(spec.name), "duplicate row for '{}'", spec.name);
+        }
+        let flagged: Vec<&str> = COMMAND_FLAGS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            flagged,
+            CommandSpec::all().map(|s| s.name).collect::<Vec<_>>()
+        );
+        for spec in DeclaredCommand::SPECS {
+            // The macro's readonly/write flag and the table must agree
+            let declared = DeclaredCommand::parse(spec.name, spec.min_argc, |_| Some(&b"0"[..]));
+            let read_only = declared.unwrap().unwrap().is_read_only();
+            assert_eq!(spec.has_flag("readonly"), read_only, "'{}'", spec.name);
+            assert_eq!(spec.has_flag("write"), !read_only, "'{}'", spec.name);
+        }
+        assert_eq!(CommandSpec::lookup("get").map(|s| s.arity()), Some(2));
+        assert_eq!(CommandSpec::lookup("SET").map(|s| s.arity()), Some(-3));
+    }
+
+    #[test]
+    fn test_validate_arity_and_numbers() {
+        assert!(check(&["GET", "k"]).is_ok());
+        assert_eq!(
+            check(&["GET"]).unwrap_err(),
+            "ERR wrong number of arguments for 'get' command"
+        );
+        assert_eq!(
+            check(&["spop", "s", "1", "2"]).unwrap_err(),
+            "ERR wrong number of arguments for 'spop' command"
+        );
+        assert_eq!(
+            check(&["EXPIRE", "k", "soon"]).unwrap_err(),
+            "ERR value is not an integer or out of range"
+        );
+        assert_eq!(
+            check(&["INCRBYFLOAT", "k", "nan"]).unwrap_err(),
+            "ERR value is not a valid float"
+        );
+        // Commands without a row are the parser's business
+        assert!(check(&["NOSUCHCOMMAND"]).is_ok());
+    }
+
+    #[test]
+    fn test_key_positions() {
+        let mset = CommandSpec::lookup("MSET").unwrap();
+        assert_eq!(mset.key_positions(5), vec![1, 3]);
+        let rename = CommandSpec::lookup("RENAME").unwrap();
+        assert_eq!(rename.key_positions(3), vec![1, 2]);
+        let keys = CommandSpec::lookup("KEYS").unwrap();
+        assert!(keys.key_positions(2).is_empty());
+    }
+
+    #[test]
+    fn test_flags_and_categories() {
+        let get = CommandSpec::lookup("GET").unwrap();
+        assert_eq!(get.flag_names().collect::<Vec<_>>(), ["readonly", "fast"]);
+        assert_eq!(get.acl_categories(), ["@read", "@fast", "@string"]);
+        assert_eq!(get.group(), "string");
+
+        let debug = CommandSpec::lookup("DEBUG").unwrap();
+        assert_eq!(debug.acl_categories(), ["@admin", "@dangerous", "@slow"]);
+        assert_eq!(debug.group(), "server");
+        let keys = CommandSpec::lookup("KEYS").unwrap();
+        assert_eq!(
+            keys.acl_categories(),
+            ["@read", "@slow", "@keyspace", "@dangerous"]
+        );
+        assert_eq!(keys.group(), "generic");
+        assert_eq!(CommandSpec::lookup("ZADD").unwrap().group(), "sorted-set");
+        assert_eq!(CommandSpec::lookup("PUBLISH").unwrap().group(), "pubsub");
+    }
+}
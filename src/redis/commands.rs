@@ -7,10 +7,17 @@
 //! The `CommandExecutor` is in the `executor/` module.
 //! The standard `from_resp` parser is in `parser.rs`.
 
-use super::command::Command;
-use super::data::SDS;
+use super::blocking;
+use super::command::{
+    BitOperation, BitRange, BitUnit, Command, FunctionRestorePolicy, ZRangeSpec, ZSetOperation,
+};
+use super::declared_commands::DeclaredCommand;
+use super::command_table;
+use super::data::{ClaimOptions, PendingRange, StreamId, StreamIdSpec, SDS};
 use super::resp_optimized::RespValueZeroCopy;
 
+const INVALID_STREAM_ID: &str = "ERR Invalid stream ID specified as stream command argument";
+
 // ============================================================================
 // Zero-Copy Parser
 // ============================================================================
@@ -25,6 +32,10 @@ impl Command {
                     }
                     _ => return Err("Invalid command format".to_string()),
                 };
+                command_table::validate(&cmd_name, elements.len(), |i| match &elements[i] {
+                    RespValueZeroCopy::BulkString(Some(data)) => Some(data.as_ref()),
+                    _ => None,
+                })?;
 
                 match cmd_name.as_str() {
                     "PING" => {
@@ -39,17 +50,17 @@ impl Command {
                     "TIME" => Ok(Command::Time),
                     "DBSIZE" => Ok(Command::DbSize),
                     "CONFIG" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'config' command".to_string());
-                        }
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "GET" => {
-                                if elements.len() != 3 {
+                                if elements.len() < 3 {
                                     return Err("ERR wrong number of arguments for 'config|get' command".to_string());
                                 }
-                                let pattern = Self::extract_string_zc(&elements[2])?;
-                                Ok(Command::ConfigGet(pattern))
+                                let patterns = elements[2..]
+                                    .iter()
+                                    .map(Self::extract_string_zc)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                Ok(Command::ConfigGet(patterns))
                             }
                             "SET" => {
                                 if elements.len() != 4 {
@@ -64,9 +75,6 @@ impl Command {
                         }
                     }
                     "SELECT" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'select' command".to_string());
-                        }
                         let db = Self::extract_u64_zc(&elements[1])?;
                         if db > 15 {
                             return Err("ERR DB index is out of range".to_string());
@@ -74,12 +82,69 @@ impl Command {
                         Ok(Command::Select(db))
                     }
                     "ECHO" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'echo' command".to_string());
-                        }
                         let msg = Self::extract_sds_zc(&elements[1])?;
                         Ok(Command::Echo(msg))
                     }
+                    "PUBLISH" => {
+                        let channel = Self::extract_string_zc(&elements[1])?;
+                        let message = Self::extract_sds_zc(&elements[2])?;
+                        Ok(Command::Publish { channel, message })
+                    }
+                    "SUBSCRIBE" => {
+                        let channels = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::Subscribe(channels))
+                    }
+                    "UNSUBSCRIBE" => {
+                        let channels = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::Unsubscribe(channels))
+                    }
+                    "PSUBSCRIBE" => {
+                        let patterns = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::PSubscribe(patterns))
+                    }
+                    "PUNSUBSCRIBE" => {
+                        let patterns = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::PUnsubscribe(patterns))
+                    }
+                    "PUBSUB" => {
+                        let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
+                        match subcommand.as_str() {
+                            "CHANNELS" => match elements.len() {
+                                2 => Ok(Command::PubSubChannels(None)),
+                                3 => Ok(Command::PubSubChannels(Some(Self::extract_string_zc(&elements[2])?))),
+                                _ => Err("ERR wrong number of arguments for 'pubsub|channels' command".to_string()),
+                            },
+                            "NUMSUB" => {
+                                let channels = elements[2..]
+                                    .iter()
+                                    .map(Self::extract_string_zc)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                Ok(Command::PubSubNumSub(channels))
+                            }
+                            "NUMPAT" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'pubsub|numpat' command".to_string());
+                                }
+                                Ok(Command::PubSubNumPat)
+                            }
+                            _ => Err(format!(
+                                "ERR unknown subcommand '{}'. Try PUBSUB HELP.",
+                                subcommand.to_lowercase()
+                            )),
+                        }
+                    }
                     "AUTH" => match elements.len() {
                         2 => {
                             let password = Self::extract_string_zc(&elements[1])?;
@@ -99,9 +164,6 @@ impl Command {
                         _ => Err("AUTH requires 1 or 2 arguments".to_string()),
                     },
                     "ACL" => {
-                        if elements.len() < 2 {
-                            return Err("ACL requires a subcommand".to_string());
-                        }
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "WHOAMI" => Ok(Command::AclWhoami),
@@ -152,7 +214,7 @@ impl Command {
                                     Some(
                                         Self::extract_string_zc(&elements[2])?
                                             .parse::<u32>()
-                                            .map_err(|_| "Invalid bits value")?,
+                                            .map_err(|_| "ERR ACL GENPASS argument must be the number of bits for the output password, a positive number up to 4096")?,
                                     )
                                 } else {
                                     None
@@ -214,9 +276,6 @@ impl Command {
                     "EXEC" => Ok(Command::Exec),
                     "DISCARD" => Ok(Command::Discard),
                     "WATCH" => {
-                        if elements.len() < 2 {
-                            return Err("WATCH requires at least 1 argument".to_string());
-                        }
                         let keys: Vec<String> = elements[1..]
                             .iter()
                             .map(Self::extract_string_zc)
@@ -224,15 +283,13 @@ impl Command {
                         Ok(Command::Watch(keys))
                     }
                     "UNWATCH" => Ok(Command::Unwatch),
-                    "EVAL" => {
-                        if elements.len() < 3 {
-                            return Err("EVAL requires at least 2 arguments".to_string());
-                        }
+                    "RESET" => Ok(Command::Reset),
+                    "EVAL" | "EVAL_RO" => {
                         let script = Self::extract_string_zc(&elements[1])?;
                         let numkeys = Self::extract_integer_zc(&elements[2])? as usize;
 
                         if elements.len() < 3 + numkeys {
-                            return Err("EVAL wrong number of keys".to_string());
+                            return Err(format!("{} wrong number of keys", cmd_name));
                         }
 
                         let keys: Vec<String> = elements[3..3 + numkeys]
@@ -245,17 +302,18 @@ impl Command {
                             .map(Self::extract_sds_zc)
                             .collect::<Result<Vec<_>, _>>()?;
 
-                        Ok(Command::Eval { script, keys, args })
-                    }
-                    "EVALSHA" => {
-                        if elements.len() < 3 {
-                            return Err("EVALSHA requires at least 2 arguments".to_string());
+                        if cmd_name == "EVAL_RO" {
+                            Ok(Command::EvalRo { script, keys, args })
+                        } else {
+                            Ok(Command::Eval { script, keys, args })
                         }
+                    }
+                    "EVALSHA" | "EVALSHA_RO" => {
                         let sha1 = Self::extract_string_zc(&elements[1])?;
                         let numkeys = Self::extract_integer_zc(&elements[2])? as usize;
 
                         if elements.len() < 3 + numkeys {
-                            return Err("EVALSHA wrong number of keys".to_string());
+                            return Err(format!("{} wrong number of keys", cmd_name));
                         }
 
                         let keys: Vec<String> = elements[3..3 + numkeys]
@@ -268,12 +326,51 @@ impl Command {
                             .map(Self::extract_sds_zc)
                             .collect::<Result<Vec<_>, _>>()?;
 
-                        Ok(Command::EvalSha { sha1, keys, args })
+                        if cmd_name == "EVALSHA_RO" {
+                            Ok(Command::EvalShaRo { sha1, keys, args })
+                        } else {
+                            Ok(Command::EvalSha { sha1, keys, args })
+                        }
                     }
-                    "SCRIPT" => {
-                        if elements.len() < 2 {
-                            return Err("SCRIPT requires a subcommand".to_string());
+                    "FCALL" | "FCALL_RO" => {
+                        let function = Self::extract_string_zc(&elements[1])?;
+                        let numkeys = Self::extract_integer_zc(&elements[2])?;
+                        if numkeys < 0 {
+                            return Err("ERR Number of keys can't be negative".to_string());
+                        }
+                        let numkeys = numkeys as usize;
+                        if numkeys > elements.len() - 3 {
+                            return Err(
+                                "ERR Number of keys can't be greater than number of args"
+                                    .to_string(),
+                            );
+                        }
+
+                        let keys: Vec<String> = elements[3..3 + numkeys]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        let args: Vec<SDS> = elements[3 + numkeys..]
+                            .iter()
+                            .map(Self::extract_sds_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        if cmd_name == "FCALL_RO" {
+                            Ok(Command::FCallRo {
+                                function,
+                                keys,
+                                args,
+                            })
+                        } else {
+                            Ok(Command::FCall {
+                                function,
+                                keys,
+                                args,
+                            })
                         }
+                    }
+                    "SCRIPT" => {
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "LOAD" => {
@@ -296,19 +393,14 @@ impl Command {
                                 Ok(Command::ScriptExists(sha1s))
                             }
                             "FLUSH" => Ok(Command::ScriptFlush),
+                            "KILL" => Ok(Command::ScriptKill),
                             _ => Err(format!("Unknown SCRIPT subcommand '{}'", subcommand)),
                         }
                     }
                     "GET" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'get' command".to_string());
-                        }
                         Ok(Command::Get(Self::extract_string_zc(&elements[1])?))
                     }
                     "SET" => {
-                        if elements.len() < 3 {
-                            return Err("SET requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let value = Self::extract_sds_zc(&elements[2])?;
 
@@ -377,6 +469,7 @@ impl Command {
                         if keepttl && (ex.is_some() || px.is_some() || exat.is_some() || pxat.is_some()) {
                             return Err("ERR syntax error".to_string());
                         }
+                        Self::check_expire("set", ex, px, exat, pxat)?;
 
                         Ok(Command::Set {
                             key,
@@ -392,11 +485,9 @@ impl Command {
                         })
                     }
                     "SETEX" => {
-                        if elements.len() != 4 {
-                            return Err("SETEX requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
-                        let seconds = Self::extract_integer_zc(&elements[2])? as i64;
+                        let seconds = Self::extract_i64_zc(&elements[2])?;
+                        Self::check_expire("setex", Some(seconds), None, None, None)?;
                         let value = Self::extract_sds_zc(&elements[3])?;
                         Ok(Command::Set {
                             key,
@@ -411,18 +502,7 @@ impl Command {
                             keepttl: false,
                         })
                     }
-                    "SETNX" => {
-                        if elements.len() != 3 {
-                            return Err("SETNX requires 2 arguments".to_string());
-                        }
-                        let key = Self::extract_string_zc(&elements[1])?;
-                        let value = Self::extract_sds_zc(&elements[2])?;
-                        Ok(Command::SetNx(key, value))
-                    }
                     "DEL" => {
-                        if elements.len() < 2 {
-                            return Err("DEL requires at least 1 argument".to_string());
-                        }
                         let keys: Vec<String> = elements[1..]
                             .iter()
                             .map(Self::extract_string_zc)
@@ -430,9 +510,6 @@ impl Command {
                         Ok(Command::Del(keys))
                     }
                     "EXISTS" => {
-                        if elements.len() < 2 {
-                            return Err("EXISTS requires at least 1 argument".to_string());
-                        }
                         Ok(Command::Exists(
                             elements[1..]
                                 .iter()
@@ -440,22 +517,21 @@ impl Command {
                                 .collect::<Result<Vec<_>, _>>()?,
                         ))
                     }
+                    "TOUCH" => {
+                        Ok(Command::Touch(
+                            elements[1..]
+                                .iter()
+                                .map(Self::extract_string_zc)
+                                .collect::<Result<Vec<_>, _>>()?,
+                        ))
+                    }
                     "TYPE" => {
-                        if elements.len() != 2 {
-                            return Err("TYPE requires 1 argument".to_string());
-                        }
                         Ok(Command::TypeOf(Self::extract_string_zc(&elements[1])?))
                     }
                     "KEYS" => {
-                        if elements.len() != 2 {
-                            return Err("KEYS requires 1 argument".to_string());
-                        }
                         Ok(Command::Keys(Self::extract_string_zc(&elements[1])?))
                     }
                     "EXPIRE" => {
-                        if elements.len() < 3 {
-                            return Err("EXPIRE requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let seconds = Self::extract_integer_zc(&elements[2])? as i64;
                         let mut nx = false;
@@ -483,9 +559,6 @@ impl Command {
                         Ok(Command::Expire { key, seconds, nx, xx, gt, lt })
                     }
                     "PEXPIRE" => {
-                        if elements.len() < 3 {
-                            return Err("PEXPIRE requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let milliseconds = Self::extract_integer_zc(&elements[2])? as i64;
                         let mut nx = false;
@@ -512,100 +585,49 @@ impl Command {
                         }
                         Ok(Command::PExpire { key, milliseconds, nx, xx, gt, lt })
                     }
-                    "EXPIREAT" => {
-                        if elements.len() != 3 {
-                            return Err("EXPIREAT requires 2 arguments".to_string());
-                        }
-                        Ok(Command::ExpireAt(
-                            Self::extract_string_zc(&elements[1])?,
-                            Self::extract_integer_zc(&elements[2])? as i64,
-                        ))
-                    }
-                    "PEXPIREAT" => {
-                        if elements.len() != 3 {
-                            return Err("PEXPIREAT requires 2 arguments".to_string());
-                        }
-                        Ok(Command::PExpireAt(
-                            Self::extract_string_zc(&elements[1])?,
-                            Self::extract_integer_zc(&elements[2])? as i64,
-                        ))
-                    }
                     "TTL" => {
-                        if elements.len() != 2 {
-                            return Err("TTL requires 1 argument".to_string());
-                        }
                         Ok(Command::Ttl(Self::extract_string_zc(&elements[1])?))
                     }
                     "PTTL" => {
-                        if elements.len() != 2 {
-                            return Err("PTTL requires 1 argument".to_string());
-                        }
                         Ok(Command::Pttl(Self::extract_string_zc(&elements[1])?))
                     }
                     "PERSIST" => {
-                        if elements.len() != 2 {
-                            return Err("PERSIST requires 1 argument".to_string());
-                        }
                         Ok(Command::Persist(Self::extract_string_zc(&elements[1])?))
                     }
                     "INCR" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'incr' command".to_string());
-                        }
                         Ok(Command::Incr(Self::extract_string_zc(&elements[1])?))
                     }
                     "DECR" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'decr' command".to_string());
-                        }
                         Ok(Command::Decr(Self::extract_string_zc(&elements[1])?))
                     }
                     "INCRBY" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'incrby' command".to_string());
-                        }
                         Ok(Command::IncrBy(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_integer_zc(&elements[2])? as i64,
                         ))
                     }
                     "DECRBY" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'decrby' command".to_string());
-                        }
                         Ok(Command::DecrBy(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_integer_zc(&elements[2])? as i64,
                         ))
                     }
                     "APPEND" => {
-                        if elements.len() != 3 {
-                            return Err("APPEND requires 2 arguments".to_string());
-                        }
                         Ok(Command::Append(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                         ))
                     }
                     "GETSET" => {
-                        if elements.len() != 3 {
-                            return Err("GETSET requires 2 arguments".to_string());
-                        }
                         Ok(Command::GetSet(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                         ))
                     }
                     "STRLEN" => {
-                        if elements.len() != 2 {
-                            return Err("STRLEN requires 1 argument".to_string());
-                        }
                         Ok(Command::StrLen(Self::extract_string_zc(&elements[1])?))
                     }
                     "MGET" => {
-                        if elements.len() < 2 {
-                            return Err("MGET requires at least 1 argument".to_string());
-                        }
                         Ok(Command::MGet(
                             elements[1..]
                                 .iter()
@@ -641,9 +663,6 @@ impl Command {
                         Ok(Command::MSetNx(pairs))
                     }
                     "LPUSH" => {
-                        if elements.len() < 3 {
-                            return Err("LPUSH requires key and values".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let values = elements[2..]
                             .iter()
@@ -652,9 +671,6 @@ impl Command {
                         Ok(Command::LPush(key, values))
                     }
                     "RPUSH" => {
-                        if elements.len() < 3 {
-                            return Err("RPUSH requires key and values".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let values = elements[2..]
                             .iter()
@@ -664,20 +680,17 @@ impl Command {
                     }
                     "LPOP" => {
                         if elements.len() != 2 {
-                            return Err("LPOP requires 1 argument".to_string());
+                            return Err("ERR wrong number of arguments for 'lpop' command".to_string());
                         }
                         Ok(Command::LPop(Self::extract_string_zc(&elements[1])?))
                     }
                     "RPOP" => {
                         if elements.len() != 2 {
-                            return Err("RPOP requires 1 argument".to_string());
+                            return Err("ERR wrong number of arguments for 'rpop' command".to_string());
                         }
                         Ok(Command::RPop(Self::extract_string_zc(&elements[1])?))
                     }
                     "LRANGE" => {
-                        if elements.len() != 4 {
-                            return Err("LRANGE requires 3 arguments".to_string());
-                        }
                         Ok(Command::LRange(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_integer_zc(&elements[2])?,
@@ -685,24 +698,15 @@ impl Command {
                         ))
                     }
                     "LLEN" => {
-                        if elements.len() != 2 {
-                            return Err("LLEN requires 1 argument".to_string());
-                        }
                         Ok(Command::LLen(Self::extract_string_zc(&elements[1])?))
                     }
                     "LINDEX" => {
-                        if elements.len() != 3 {
-                            return Err("LINDEX requires 2 arguments".to_string());
-                        }
                         Ok(Command::LIndex(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_integer_zc(&elements[2])?,
                         ))
                     }
                     "LSET" => {
-                        if elements.len() != 4 {
-                            return Err("LSET requires 3 arguments".to_string());
-                        }
                         Ok(Command::LSet(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_integer_zc(&elements[2])?,
@@ -710,9 +714,6 @@ impl Command {
                         ))
                     }
                     "LTRIM" => {
-                        if elements.len() != 4 {
-                            return Err("LTRIM requires 3 arguments".to_string());
-                        }
                         Ok(Command::LTrim(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_integer_zc(&elements[2])?,
@@ -720,18 +721,12 @@ impl Command {
                         ))
                     }
                     "RPOPLPUSH" => {
-                        if elements.len() != 3 {
-                            return Err("RPOPLPUSH requires 2 arguments".to_string());
-                        }
                         Ok(Command::RPopLPush(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_string_zc(&elements[2])?,
                         ))
                     }
                     "LMOVE" => {
-                        if elements.len() != 5 {
-                            return Err("LMOVE requires 4 arguments".to_string());
-                        }
                         let source = Self::extract_string_zc(&elements[1])?;
                         let dest = Self::extract_string_zc(&elements[2])?;
                         let wherefrom = Self::extract_string_zc(&elements[3])?.to_uppercase();
@@ -749,10 +744,82 @@ impl Command {
                             whereto,
                         })
                     }
-                    "SADD" => {
-                        if elements.len() < 3 {
-                            return Err("SADD requires key and members".to_string());
+                    "LINSERT" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let before = match Self::extract_string_zc(&elements[2])?.to_ascii_uppercase().as_str() {
+                            "BEFORE" => true,
+                            "AFTER" => false,
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        let pivot = Self::extract_sds_zc(&elements[3])?;
+                        let value = Self::extract_sds_zc(&elements[4])?;
+                        Ok(Command::LInsert {
+                            key,
+                            before,
+                            pivot,
+                            value,
+                        })
+                    }
+                    "LREM" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let count = Self::extract_integer_zc(&elements[2])?;
+                        let element = Self::extract_sds_zc(&elements[3])?;
+                        Ok(Command::LRem(key, count, element))
+                    }
+                    "LPOS" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let element = Self::extract_sds_zc(&elements[2])?;
+                        let options = elements[3..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_lpos(key, element, &options)
+                    }
+                    "LPUSHX" | "RPUSHX" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let values = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        if cmd_name == "LPUSHX" {
+                            Ok(Command::LPushX(key, values))
+                        } else {
+                            Ok(Command::RPushX(key, values))
+                        }
+                    }
+                    "BLPOP" | "BRPOP" => {
+                        let last = elements.len() - 1;
+                        let keys = elements[1..last]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let timeout_ms = blocking::parse_timeout(&Self::extract_string_zc(&elements[last])?)?;
+                        if cmd_name == "BLPOP" {
+                            Ok(Command::BLPop { keys, timeout_ms })
+                        } else {
+                            Ok(Command::BRPop { keys, timeout_ms })
+                        }
+                    }
+                    "BLMOVE" => {
+                        let source = Self::extract_string_zc(&elements[1])?;
+                        let dest = Self::extract_string_zc(&elements[2])?;
+                        let wherefrom = Self::extract_string_zc(&elements[3])?.to_uppercase();
+                        let whereto = Self::extract_string_zc(&elements[4])?.to_uppercase();
+                        for side in [&wherefrom, &whereto] {
+                            if side != "LEFT" && side != "RIGHT" {
+                                return Err("ERR syntax error".to_string());
+                            }
                         }
+                        let timeout_ms = blocking::parse_timeout(&Self::extract_string_zc(&elements[5])?)?;
+                        Ok(Command::BLMove {
+                            source,
+                            dest,
+                            wherefrom,
+                            whereto,
+                            timeout_ms,
+                        })
+                    }
+                    "SADD" => {
                         let key = Self::extract_string_zc(&elements[1])?;
                         let members = elements[2..]
                             .iter()
@@ -761,24 +828,15 @@ impl Command {
                         Ok(Command::SAdd(key, members))
                     }
                     "SMEMBERS" => {
-                        if elements.len() != 2 {
-                            return Err("SMEMBERS requires 1 argument".to_string());
-                        }
                         Ok(Command::SMembers(Self::extract_string_zc(&elements[1])?))
                     }
                     "SISMEMBER" => {
-                        if elements.len() != 3 {
-                            return Err("SISMEMBER requires 2 arguments".to_string());
-                        }
                         Ok(Command::SIsMember(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                         ))
                     }
                     "SREM" => {
-                        if elements.len() < 3 {
-                            return Err("SREM requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let members = elements[2..]
                             .iter()
@@ -787,16 +845,10 @@ impl Command {
                         Ok(Command::SRem(key, members))
                     }
                     "SCARD" => {
-                        if elements.len() != 2 {
-                            return Err("SCARD requires 1 argument".to_string());
-                        }
                         Ok(Command::SCard(Self::extract_string_zc(&elements[1])?))
                     }
                     "SPOP" => {
                         // SPOP key [count]
-                        if elements.len() < 2 || elements.len() > 3 {
-                            return Err("SPOP requires 1 or 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let count = if elements.len() == 3 {
                             let count_str = Self::extract_string_zc(&elements[2])?;
@@ -810,6 +862,34 @@ impl Command {
                         };
                         Ok(Command::SPop(key, count))
                     }
+                    "SMISMEMBER" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let members = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::SMIsMember(key, members))
+                    }
+                    "SRANDMEMBER" => {
+                        // SRANDMEMBER key [count]
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(arg) => Some(
+                                Self::extract_string_zc(arg)?
+                                    .parse::<i64>()
+                                    .map_err(|_| "ERR value is not an integer or out of range")?,
+                            ),
+                            None => None,
+                        };
+                        Ok(Command::SRandMember(key, count))
+                    }
+                    "SINTERCARD" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_sintercard(args)
+                    }
                     "HSET" => {
                         // HSET key field value [field value ...]
                         if elements.len() < 4 || (elements.len() - 2) % 2 != 0 {
@@ -825,72 +905,77 @@ impl Command {
                         Ok(Command::HSet(key, pairs))
                     }
                     "HGET" => {
-                        if elements.len() != 3 {
-                            return Err("HGET requires 2 arguments".to_string());
-                        }
                         Ok(Command::HGet(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                         ))
                     }
                     "HGETALL" => {
-                        if elements.len() != 2 {
-                            return Err("HGETALL requires 1 argument".to_string());
-                        }
                         Ok(Command::HGetAll(Self::extract_string_zc(&elements[1])?))
                     }
                     "HINCRBY" => {
-                        if elements.len() != 4 {
-                            return Err("HINCRBY requires 3 arguments".to_string());
-                        }
                         Ok(Command::HIncrBy(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                             Self::extract_i64_zc(&elements[3])?,
                         ))
                     }
-                    "HDEL" => {
-                        if elements.len() < 3 {
-                            return Err("HDEL requires at least 2 arguments".to_string());
+                    "HINCRBYFLOAT" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let field = Self::extract_sds_zc(&elements[2])?;
+                        let increment = Self::extract_float_zc(&elements[3])?;
+                        if increment.is_nan() || increment.is_infinite() {
+                            return Err("ERR value is NaN or Infinity".to_string());
                         }
+                        Ok(Command::HIncrByFloat(key, field, increment))
+                    }
+                    "HMGET" => {
                         let key = Self::extract_string_zc(&elements[1])?;
                         let fields = elements[2..]
                             .iter()
                             .map(Self::extract_sds_zc)
                             .collect::<Result<Vec<_>, _>>()?;
-                        Ok(Command::HDel(key, fields))
+                        Ok(Command::HMGet(key, fields))
                     }
-                    "HKEYS" => {
-                        if elements.len() != 2 {
-                            return Err("HKEYS requires 1 argument".to_string());
-                        }
-                        Ok(Command::HKeys(Self::extract_string_zc(&elements[1])?))
+                    "HRANDFIELD" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(e) => Some(Self::extract_i64_zc(e)?),
+                            None => None,
+                        };
+                        let with_values = match elements.get(3) {
+                            Some(e) if Self::extract_string_zc(e)?.eq_ignore_ascii_case("WITHVALUES") => true,
+                            Some(_) => return Err("ERR syntax error".to_string()),
+                            None => false,
+                        };
+                        Ok(Command::HRandField { key, count, with_values })
                     }
-                    "HVALS" => {
-                        if elements.len() != 2 {
-                            return Err("HVALS requires 1 argument".to_string());
-                        }
-                        Ok(Command::HVals(Self::extract_string_zc(&elements[1])?))
+                    "HEXPIRE" | "HPEXPIRE" | "HEXPIREAT" | "HPEXPIREAT" | "HTTL" | "HPTTL"
+                    | "HEXPIRETIME" | "HPEXPIRETIME" | "HPERSIST" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_field_ttl(&cmd_name, args)
+                    }
+                    "HDEL" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let fields = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::HDel(key, fields))
                     }
                     "HLEN" => {
-                        if elements.len() != 2 {
-                            return Err("HLEN requires 1 argument".to_string());
-                        }
                         Ok(Command::HLen(Self::extract_string_zc(&elements[1])?))
                     }
                     "HEXISTS" => {
-                        if elements.len() != 3 {
-                            return Err("HEXISTS requires 2 arguments".to_string());
-                        }
                         Ok(Command::HExists(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                         ))
                     }
                     "ZADD" => {
-                        if elements.len() < 4 {
-                            return Err("ZADD requires key and score-member pairs".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
 
                         // Parse optional flags (NX, XX, GT, LT, CH)
@@ -950,23 +1035,23 @@ impl Command {
                             ch,
                         })
                     }
-                    "ZRANGE" => {
-                        if elements.len() < 4 || elements.len() > 5 {
-                            return Err("ZRANGE requires 3 or 4 arguments".to_string());
+                    "ZRANGE" | "ZRANGESTORE" => {
+                        let store = cmd_name == "ZRANGESTORE";
+                        if elements.len() < 4 + usize::from(store) {
+                            return Err(format!(
+                                "ERR wrong number of arguments for '{}' command",
+                                cmd_name.to_ascii_lowercase()
+                            ));
                         }
-                        let key = Self::extract_string_zc(&elements[1])?;
-                        let start = Self::extract_integer_zc(&elements[2])?;
-                        let stop = Self::extract_integer_zc(&elements[3])?;
-                        let with_scores = if elements.len() == 5 {
-                            Self::extract_string_zc(&elements[4])?.to_uppercase() == "WITHSCORES"
-                        } else {
-                            false
-                        };
-                        Ok(Command::ZRange(key, start, stop, with_scores))
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_zrange(store, args)
                     }
                     "ZREVRANGE" => {
                         if elements.len() < 4 || elements.len() > 5 {
-                            return Err("ZREVRANGE requires 3 or 4 arguments".to_string());
+                            return Err("ERR wrong number of arguments for 'zrevrange' command".to_string());
                         }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let start = Self::extract_integer_zc(&elements[2])?;
@@ -979,18 +1064,12 @@ impl Command {
                         Ok(Command::ZRevRange(key, start, stop, with_scores))
                     }
                     "ZSCORE" => {
-                        if elements.len() != 3 {
-                            return Err("ZSCORE requires 2 arguments".to_string());
-                        }
                         Ok(Command::ZScore(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_sds_zc(&elements[2])?,
                         ))
                     }
                     "ZREM" => {
-                        if elements.len() < 3 {
-                            return Err("ZREM requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let members = elements[2..]
                             .iter()
@@ -998,25 +1077,26 @@ impl Command {
                             .collect::<Result<Vec<_>, _>>()?;
                         Ok(Command::ZRem(key, members))
                     }
-                    "ZRANK" => {
-                        if elements.len() != 3 {
-                            return Err("ZRANK requires 2 arguments".to_string());
-                        }
-                        Ok(Command::ZRank(
-                            Self::extract_string_zc(&elements[1])?,
-                            Self::extract_sds_zc(&elements[2])?,
-                        ))
+                    "ZREMRANGEBYRANK" | "ZREMRANGEBYSCORE" | "ZREMRANGEBYLEX" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let (min, max) = (Self::extract_string_zc(&elements[2])?, Self::extract_string_zc(&elements[3])?);
+                        let range = match cmd_name.as_str() {
+                            "ZREMRANGEBYRANK" => {
+                                let parse = |s: &str| {
+                                    s.parse::<isize>()
+                                        .map_err(|_| "ERR value is not an integer or out of range".to_string())
+                                };
+                                ZRangeSpec::Rank(parse(&min)?, parse(&max)?)
+                            }
+                            "ZREMRANGEBYSCORE" => ZRangeSpec::Score(min, max),
+                            _ => ZRangeSpec::Lex(min, max),
+                        };
+                        Ok(Command::ZRemRange(key, range))
                     }
                     "ZCARD" => {
-                        if elements.len() != 2 {
-                            return Err("ZCARD requires 1 argument".to_string());
-                        }
                         Ok(Command::ZCard(Self::extract_string_zc(&elements[1])?))
                     }
                     "ZCOUNT" => {
-                        if elements.len() != 4 {
-                            return Err("ZCOUNT requires 3 arguments".to_string());
-                        }
                         Ok(Command::ZCount(
                             Self::extract_string_zc(&elements[1])?,
                             Self::extract_string_zc(&elements[2])?,
@@ -1024,9 +1104,6 @@ impl Command {
                         ))
                     }
                     "ZRANGEBYSCORE" => {
-                        if elements.len() < 4 {
-                            return Err("ZRANGEBYSCORE requires at least 3 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let min = Self::extract_string_zc(&elements[2])?;
                         let max = Self::extract_string_zc(&elements[3])?;
@@ -1059,13 +1136,85 @@ impl Command {
                             limit,
                         })
                     }
-                    "SCAN" => {
-                        if elements.len() < 2 {
-                            return Err("SCAN requires at least 1 argument".to_string());
+                    "ZPOPMIN" | "ZPOPMAX" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(e) => {
+                                let n = Self::extract_integer_zc(e)?;
+                                if n < 0 {
+                                    return Err("ERR value is out of range, must be positive".to_string());
+                                }
+                                Some(n as usize)
+                            }
+                            None => None,
+                        };
+                        if cmd_name == "ZPOPMIN" {
+                            Ok(Command::ZPopMin { key, count })
+                        } else {
+                            Ok(Command::ZPopMax { key, count })
                         }
-                        let cursor = Self::extract_u64_zc(&elements[1])?;
-                        let mut pattern = None;
-                        let mut count = None;
+                    }
+                    "ZINCRBY" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let increment = Self::extract_float_zc(&elements[2])?;
+                        if increment.is_nan() {
+                            return Err("ERR value is not a valid float".to_string());
+                        }
+                        let member = Self::extract_sds_zc(&elements[3])?;
+                        Ok(Command::ZIncrBy(key, increment, member))
+                    }
+                    "ZMSCORE" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let members = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::ZMScore(key, members))
+                    }
+                    "ZRANDMEMBER" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(e) => Some(Self::extract_i64_zc(e)?),
+                            None => None,
+                        };
+                        let with_scores = match elements.get(3) {
+                            Some(e) if Self::extract_string_zc(e)?.eq_ignore_ascii_case("WITHSCORES") => true,
+                            Some(_) => return Err("ERR syntax error".to_string()),
+                            None => false,
+                        };
+                        Ok(Command::ZRandMember { key, count, with_scores })
+                    }
+                    "ZUNION" | "ZINTER" | "ZDIFF" | "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => {
+                        let op = match cmd_name.as_str() {
+                            "ZUNION" | "ZUNIONSTORE" => ZSetOperation::Union,
+                            "ZINTER" | "ZINTERSTORE" => ZSetOperation::Inter,
+                            _ => ZSetOperation::Diff,
+                        };
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let name = cmd_name.to_ascii_lowercase();
+                        Self::parse_zset_op(&name, op, name.ends_with("store"), args)
+                    }
+                    "BZPOPMIN" | "BZPOPMAX" => {
+                        let last = elements.len() - 1;
+                        let keys = elements[1..last]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let timeout_ms = blocking::parse_timeout(&Self::extract_string_zc(&elements[last])?)?;
+                        if cmd_name == "BZPOPMIN" {
+                            Ok(Command::BZPopMin { keys, timeout_ms })
+                        } else {
+                            Ok(Command::BZPopMax { keys, timeout_ms })
+                        }
+                    }
+                    "SCAN" => {
+                        let cursor = Self::extract_u64_zc(&elements[1])?;
+                        let mut pattern = None;
+                        let mut count = None;
+                        let mut type_filter = None;
                         let mut i = 2;
                         while i < elements.len() {
                             let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
@@ -1078,6 +1227,10 @@ impl Command {
                                     i += 1;
                                     count = Some(Self::extract_integer_zc(&elements[i])? as usize);
                                 }
+                                "TYPE" => {
+                                    i += 1;
+                                    type_filter = Some(Self::extract_string_zc(&elements[i])?);
+                                }
                                 _ => return Err(format!("Unknown SCAN option: {}", opt)),
                             }
                             i += 1;
@@ -1086,12 +1239,10 @@ impl Command {
                             cursor,
                             pattern,
                             count,
+                            type_filter,
                         })
                     }
                     "HSCAN" => {
-                        if elements.len() < 3 {
-                            return Err("HSCAN requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let cursor = Self::extract_u64_zc(&elements[2])?;
                         let mut pattern = None;
@@ -1120,9 +1271,6 @@ impl Command {
                         })
                     }
                     "ZSCAN" => {
-                        if elements.len() < 3 {
-                            return Err("ZSCAN requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let cursor = Self::extract_u64_zc(&elements[2])?;
                         let mut pattern = None;
@@ -1150,31 +1298,540 @@ impl Command {
                             count,
                         })
                     }
-                    "FUNCTION" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'function' command".to_string());
+                    "XADD" => {
+                        // XADD key [NOMKSTREAM] [MAXLEN [=|~] n] <* | id> field value [field value ...]
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let mut nomkstream = false;
+                        let mut maxlen = None;
+                        let mut i = 2;
+                        while i < elements.len() {
+                            let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "NOMKSTREAM" => nomkstream = true,
+                                "MAXLEN" => {
+                                    i += 1;
+                                    if i < elements.len()
+                                        && matches!(Self::extract_string_zc(&elements[i])?.as_str(), "=" | "~")
+                                    {
+                                        i += 1;
+                                    }
+                                    if i >= elements.len() {
+                                        return Err("ERR syntax error".to_string());
+                                    }
+                                    let n = Self::extract_integer_zc(&elements[i])?;
+                                    if n < 0 {
+                                        return Err("ERR The MAXLEN argument must be >= 0.".to_string());
+                                    }
+                                    maxlen = Some(n as usize);
+                                }
+                                _ => break,
+                            }
+                            i += 1;
+                        }
+                        let rest = elements.len().saturating_sub(i + 1);
+                        if rest == 0 || rest % 2 != 0 {
+                            return Err("ERR wrong number of arguments for 'xadd' command".to_string());
+                        }
+                        let id = StreamIdSpec::parse(&Self::extract_string_zc(&elements[i])?)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let mut fields = Vec::with_capacity(rest / 2);
+                        for j in ((i + 1)..elements.len()).step_by(2) {
+                            fields.push((Self::extract_sds_zc(&elements[j])?, Self::extract_sds_zc(&elements[j + 1])?));
+                        }
+                        Ok(Command::XAdd {
+                            key,
+                            id,
+                            fields,
+                            nomkstream,
+                            maxlen,
+                        })
+                    }
+                    "XRANGE" | "XREVRANGE" => {
+                        // XRANGE takes `start end`, XREVRANGE takes `end start`
+                        let rev = cmd_name == "XREVRANGE";
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let (low, high) = if rev { (3, 2) } else { (2, 3) };
+                        let start = StreamId::parse_bound(&Self::extract_string_zc(&elements[low])?, true)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let end = StreamId::parse_bound(&Self::extract_string_zc(&elements[high])?, false)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let count = match elements.len() {
+                            4 => None,
+                            6 if Self::extract_string_zc(&elements[4])?.eq_ignore_ascii_case("COUNT") => {
+                                Some(Self::extract_integer_zc(&elements[5])?.max(0) as usize)
+                            }
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        if rev {
+                            Ok(Command::XRevRange {
+                                key,
+                                start,
+                                end,
+                                count,
+                            })
+                        } else {
+                            Ok(Command::XRange {
+                                key,
+                                start,
+                                end,
+                                count,
+                            })
+                        }
+                    }
+                    "XREAD" => {
+                        // XREAD [COUNT n] [BLOCK ms] STREAMS key [key ...] id [id ...]
+                        let mut count = None;
+                        let mut block_ms = None;
+                        let mut i = 1;
+                        loop {
+                            if i >= elements.len() {
+                                return Err("ERR syntax error".to_string());
+                            }
+                            let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "COUNT" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    count = Some(Self::extract_integer_zc(&elements[i])?.max(0) as usize);
+                                }
+                                "BLOCK" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    block_ms = Some(blocking::parse_block_ms(&Self::extract_string_zc(&elements[i])?)?);
+                                }
+                                "STREAMS" => break,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        let rest = elements.len() - i - 1;
+                        if rest == 0 || rest % 2 != 0 {
+                            return Err("ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.".to_string());
+                        }
+                        let n = rest / 2;
+                        let mut keys = Vec::with_capacity(n);
+                        let mut ids = Vec::with_capacity(n);
+                        for j in 0..n {
+                            keys.push(Self::extract_string_zc(&elements[i + 1 + j])?);
+                            let id = Self::extract_string_zc(&elements[i + 1 + n + j])?;
+                            ids.push(if id == "$" {
+                                None
+                            } else {
+                                Some(
+                                    StreamId::parse(&id, 0)
+                                        .ok_or_else(|| INVALID_STREAM_ID.to_string())?,
+                                )
+                            });
+                        }
+                        Ok(Command::XRead {
+                            count,
+                            block_ms,
+                            keys,
+                            ids,
+                        })
+                    }
+                    "XGROUP" => {
+                        let sub = Self::extract_string_zc(&elements[1])?.to_uppercase();
+                        let argc_ok = match sub.as_str() {
+                            "CREATE" => (5..=6).contains(&elements.len()),
+                            "SETID" | "CREATECONSUMER" | "DELCONSUMER" => elements.len() == 5,
+                            "DESTROY" => elements.len() == 4,
+                            _ => {
+                                return Err(format!(
+                                    "ERR unknown subcommand '{}'. Try XGROUP HELP.",
+                                    Self::extract_string_zc(&elements[1])?
+                                ))
+                            }
+                        };
+                        if !argc_ok {
+                            return Err(format!(
+                                "ERR wrong number of arguments for 'xgroup|{}' command",
+                                sub.to_lowercase()
+                            ));
+                        }
+                        let key = Self::extract_string_zc(&elements[2])?;
+                        let group = Self::extract_string_zc(&elements[3])?;
+                        // `$` means the stream's current last ID
+                        let parse_id = |s: String| -> Result<Option<StreamId>, String> {
+                            if s == "$" {
+                                return Ok(None);
+                            }
+                            StreamId::parse(&s, 0)
+                                .map(Some)
+                                .ok_or_else(|| INVALID_STREAM_ID.to_string())
+                        };
+                        match sub.as_str() {
+                            "CREATE" => {
+                                let id = parse_id(Self::extract_string_zc(&elements[4])?)?;
+                                let mkstream = match elements.get(5) {
+                                    None => false,
+                                    Some(opt) if Self::extract_string_zc(opt)?.eq_ignore_ascii_case("MKSTREAM") => true,
+                                    Some(_) => return Err("ERR syntax error".to_string()),
+                                };
+                                Ok(Command::XGroupCreate {
+                                    key,
+                                    group,
+                                    id,
+                                    mkstream,
+                                })
+                            }
+                            "SETID" => Ok(Command::XGroupSetId {
+                                key,
+                                group,
+                                id: parse_id(Self::extract_string_zc(&elements[4])?)?,
+                            }),
+                            "DESTROY" => Ok(Command::XGroupDestroy { key, group }),
+                            "CREATECONSUMER" => Ok(Command::XGroupCreateConsumer {
+                                key,
+                                group,
+                                consumer: Self::extract_string_zc(&elements[4])?,
+                            }),
+                            _ => Ok(Command::XGroupDelConsumer {
+                                key,
+                                group,
+                                consumer: Self::extract_string_zc(&elements[4])?,
+                            }),
+                        }
+                    }
+                    "XREADGROUP" => {
+                        // XREADGROUP GROUP group consumer [COUNT n] [BLOCK ms] [NOACK] STREAMS key [key ...] id [id ...]
+                        let mut group = None;
+                        let mut count = None;
+                        let mut block_ms = None;
+                        let mut noack = false;
+                        let mut i = 1;
+                        loop {
+                            if i >= elements.len() {
+                                return Err("ERR syntax error".to_string());
+                            }
+                            let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "GROUP" if i + 2 < elements.len() => {
+                                    group = Some((Self::extract_string_zc(&elements[i + 1])?, Self::extract_string_zc(&elements[i + 2])?));
+                                    i += 2;
+                                }
+                                "COUNT" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    count = Some(Self::extract_integer_zc(&elements[i])?.max(0) as usize);
+                                }
+                                "NOACK" => noack = true,
+                                "BLOCK" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    block_ms = Some(blocking::parse_block_ms(&Self::extract_string_zc(&elements[i])?)?);
+                                }
+                                "STREAMS" => break,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        let Some((group, consumer)) = group else {
+                            return Err("ERR Missing GROUP option for XREADGROUP".to_string());
+                        };
+                        let rest = elements.len() - i - 1;
+                        if rest == 0 || rest % 2 != 0 {
+                            return Err("ERR Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified.".to_string());
+                        }
+                        let n = rest / 2;
+                        let mut keys = Vec::with_capacity(n);
+                        let mut ids = Vec::with_capacity(n);
+                        for j in 0..n {
+                            keys.push(Self::extract_string_zc(&elements[i + 1 + j])?);
+                            let id = Self::extract_string_zc(&elements[i + 1 + n + j])?;
+                            ids.push(if id == ">" {
+                                None
+                            } else {
+                                Some(
+                                    StreamId::parse(&id, 0)
+                                        .ok_or_else(|| INVALID_STREAM_ID.to_string())?,
+                                )
+                            });
+                        }
+                        Ok(Command::XReadGroup {
+                            group,
+                            consumer,
+                            count,
+                            block_ms,
+                            noack,
+                            keys,
+                            ids,
+                        })
+                    }
+                    "XACK" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let group = Self::extract_string_zc(&elements[2])?;
+                        let ids = elements[3..]
+                            .iter()
+                            .map(|e| {
+                                StreamId::parse(&Self::extract_string_zc(e)?, 0)
+                                    .ok_or_else(|| INVALID_STREAM_ID.to_string())
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::XAck { key, group, ids })
+                    }
+                    "XPENDING" => {
+                        // XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let group = Self::extract_string_zc(&elements[2])?;
+                        let range = if elements.len() == 3 {
+                            None
+                        } else {
+                            let mut i = 3;
+                            let mut min_idle_ms = None;
+                            if Self::extract_string_zc(&elements[3])?.eq_ignore_ascii_case("IDLE") && elements.len() > 4 {
+                                min_idle_ms = Some(Self::extract_integer_zc(&elements[4])?.max(0) as u64);
+                                i = 5;
+                            }
+                            if !(i + 3..=i + 4).contains(&elements.len()) {
+                                return Err("ERR syntax error".to_string());
+                            }
+                            let start = StreamId::parse_bound(&Self::extract_string_zc(&elements[i])?, true)
+                                .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                            let end = StreamId::parse_bound(&Self::extract_string_zc(&elements[i + 1])?, false)
+                                .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                            let count = Self::extract_integer_zc(&elements[i + 2])?.max(0) as usize;
+                            let consumer = match elements.get(i + 3) {
+                                Some(c) => Some(Self::extract_string_zc(c)?),
+                                None => None,
+                            };
+                            Some(PendingRange {
+                                min_idle_ms,
+                                start,
+                                end,
+                                count,
+                                consumer,
+                            })
+                        };
+                        Ok(Command::XPending { key, group, range })
+                    }
+                    "XCLAIM" => {
+                        // XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-ms]
+                        //        [RETRYCOUNT n] [FORCE] [JUSTID]
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let group = Self::extract_string_zc(&elements[2])?;
+                        let consumer = Self::extract_string_zc(&elements[3])?;
+                        let min_idle_ms = Self::extract_string_zc(&elements[4])?
+                            .parse::<i64>()
+                            .map_err(|_| "ERR Invalid min-idle-time argument for XCLAIM".to_string())?
+                            .max(0) as u64;
+                        let mut ids = Vec::new();
+                        let mut i = 5;
+                        while i < elements.len() {
+                            match StreamId::parse(&Self::extract_string_zc(&elements[i])?, 0) {
+                                Some(id) => ids.push(id),
+                                None => break,
+                            }
+                            i += 1;
+                        }
+                        if ids.is_empty() {
+                            return Err(INVALID_STREAM_ID.to_string());
+                        }
+                        let mut opts = ClaimOptions::default();
+                        while i < elements.len() {
+                            let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
+                            let has_arg = i + 1 < elements.len();
+                            let mut arg = |name: &str| -> Result<u64, String> {
+                                i += 1;
+                                Self::extract_string_zc(&elements[i])?
+                                    .parse::<i64>()
+                                    .map(|n| n.max(0) as u64)
+                                    .map_err(|_| format!("ERR Invalid {} option argument for XCLAIM", name))
+                            };
+                            match opt.as_str() {
+                                "IDLE" if has_arg => opts.idle_ms = Some(arg("IDLE")?),
+                                "TIME" if has_arg => opts.time_ms = Some(arg("TIME")?),
+                                "RETRYCOUNT" if has_arg => opts.retry_count = Some(arg("RETRYCOUNT")?),
+                                "FORCE" => opts.force = true,
+                                "JUSTID" => opts.justid = true,
+                                _ => {
+                                    return Err(format!(
+                                        "ERR Unrecognized XCLAIM option '{}'",
+                                        Self::extract_string_zc(&elements[i])?
+                                    ))
+                                }
+                            }
+                            i += 1;
+                        }
+                        Ok(Command::XClaim {
+                            key,
+                            group,
+                            consumer,
+                            min_idle_ms,
+                            ids,
+                            opts,
+                        })
+                    }
+                    "XAUTOCLAIM" => {
+                        // XAUTOCLAIM key group consumer min-idle-time start [COUNT n] [JUSTID]
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let group = Self::extract_string_zc(&elements[2])?;
+                        let consumer = Self::extract_string_zc(&elements[3])?;
+                        let min_idle_ms = Self::extract_string_zc(&elements[4])?
+                            .parse::<i64>()
+                            .map_err(|_| "ERR Invalid min-idle-time argument for XAUTOCLAIM".to_string())?
+                            .max(0) as u64;
+                        let start = StreamId::parse_bound(&Self::extract_string_zc(&elements[5])?, true)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let mut count = 100;
+                        let mut justid = false;
+                        let mut i = 6;
+                        while i < elements.len() {
+                            let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "COUNT" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    let n = Self::extract_integer_zc(&elements[i])?;
+                                    if n < 1 {
+                                        return Err("ERR COUNT must be > 0".to_string());
+                                    }
+                                    count = n as usize;
+                                }
+                                "JUSTID" => justid = true,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
                         }
+                        Ok(Command::XAutoClaim {
+                            key,
+                            group,
+                            consumer,
+                            min_idle_ms,
+                            start,
+                            count,
+                            justid,
+                        })
+                    }
+                    "FUNCTION" => {
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
+                        let arity_error = || {
+                            format!(
+                                "ERR wrong number of arguments for 'function|{}' command",
+                                subcommand.to_lowercase()
+                            )
+                        };
                         match subcommand.as_str() {
-                            "FLUSH" => Ok(Command::FunctionFlush),
+                            "LOAD" => match elements.len() {
+                                3 => Ok(Command::FunctionLoad {
+                                    code: Self::extract_string_zc(&elements[2])?,
+                                    replace: false,
+                                }),
+                                4 => {
+                                    let option = Self::extract_string_zc(&elements[2])?;
+                                    if !option.eq_ignore_ascii_case("REPLACE") {
+                                        return Err(format!("ERR Unknown option given: {}", option));
+                                    }
+                                    Ok(Command::FunctionLoad {
+                                        code: Self::extract_string_zc(&elements[3])?,
+                                        replace: true,
+                                    })
+                                }
+                                _ => Err(arity_error()),
+                            },
+                            "DELETE" => {
+                                if elements.len() != 3 {
+                                    return Err(arity_error());
+                                }
+                                let library = Self::extract_string_zc(&elements[2])?;
+                                Ok(Command::FunctionDelete(library))
+                            }
+                            "FLUSH" => {
+                                if elements.len() > 3 {
+                                    return Err(arity_error());
+                                }
+                                if let Some(mode) = elements.get(2) {
+                                    let mode = Self::extract_string_zc(mode)?.to_uppercase();
+                                    if mode != "ASYNC" && mode != "SYNC" {
+                                        return Err(
+                                            "ERR FUNCTION FLUSH only supports SYNC|ASYNC option"
+                                                .to_string(),
+                                        );
+                                    }
+                                }
+                                Ok(Command::FunctionFlush)
+                            }
+                            "LIST" => {
+                                let mut pattern = None;
+                                let mut with_code = false;
+                                let mut i = 2;
+                                while i < elements.len() {
+                                    let option =
+                                        Self::extract_string_zc(&elements[i])?.to_uppercase();
+                                    match option.as_str() {
+                                        "WITHCODE" => with_code = true,
+                                        "LIBRARYNAME" => {
+                                            if pattern.is_some() {
+                                                return Err("ERR library name can be given only once"
+                                                    .to_string());
+                                            }
+                                            i += 1;
+                                            if i >= elements.len() {
+                                                return Err("ERR library name argument was not given"
+                                                    .to_string());
+                                            }
+                                            pattern = Some(Self::extract_string_zc(&elements[i])?);
+                                        }
+                                        _ => return Err(format!("ERR Unknown argument {}", option)),
+                                    }
+                                    i += 1;
+                                }
+                                Ok(Command::FunctionList { pattern, with_code })
+                            }
+                            "DUMP" | "STATS" if elements.len() != 2 => Err(arity_error()),
+                            "DUMP" => Ok(Command::FunctionDump),
+                            "STATS" => Ok(Command::FunctionStats),
+                            "RESTORE" => {
+                                let policy = match elements.len() {
+                                    3 => FunctionRestorePolicy::default(),
+                                    4 => {
+                                        let policy =
+                                            Self::extract_string_zc(&elements[3])?.to_uppercase();
+                                        match policy.as_str() {
+                                            "FLUSH" => FunctionRestorePolicy::Flush,
+                                            "APPEND" => FunctionRestorePolicy::Append,
+                                            "REPLACE" => FunctionRestorePolicy::Replace,
+                                            _ => {
+                                                return Err("ERR Wrong restore policy given, \
+                                                    value should be either FLUSH, APPEND or \
+                                                    REPLACE."
+                                                    .to_string())
+                                            }
+                                        }
+                                    }
+                                    _ => return Err(arity_error()),
+                                };
+                                let payload =
+                                    Self::extract_sds_zc(&elements[2])?.as_bytes().to_vec();
+                                Ok(Command::FunctionRestore { payload, policy })
+                            }
                             _ => Ok(Command::Unknown(format!("FUNCTION {}", subcommand))),
                         }
                     }
                     "COMMAND" => {
-                        if elements.len() >= 2 {
-                            let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
-                            match subcommand.as_str() {
-                                "COUNT" => Ok(Command::CommandCount),
-                                _ => Ok(Command::CommandCommand),
-                            }
-                        } else {
-                            Ok(Command::CommandCommand)
-                        }
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_command_introspection(args)
+                    }
+                    "LATENCY" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_latency(args)
+                    }
+                    "MEMORY" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_memory(args)
+                    }
+                    "LOLWUT" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_lolwut(args)
                     }
                     "CLIENT" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'client' command".to_string());
-                        }
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "SETNAME" => {
@@ -1187,13 +1844,63 @@ impl Command {
                             "GETNAME" => Ok(Command::ClientGetName),
                             "ID" => Ok(Command::ClientId),
                             "INFO" => Ok(Command::ClientInfo),
+                            "LIST" if elements.len() == 2 => Ok(Command::ClientList),
+                            "LIST" => Err("ERR syntax error".to_string()),
+                            "KILL" | "PAUSE" | "TRACKING" => {
+                                let args = elements[2..]
+                                    .iter()
+                                    .map(Self::extract_string_zc)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                match subcommand.as_str() {
+                                    "KILL" => Self::parse_client_kill(args),
+                                    "PAUSE" => Self::parse_client_pause(args),
+                                    _ => Self::parse_client_tracking(args),
+                                }
+                            }
+                            "UNPAUSE" if elements.len() == 2 => Ok(Command::ClientUnpause),
+                            "UNPAUSE" => Err("ERR wrong number of arguments for 'client|unpause' command".to_string()),
                             _ => Ok(Command::Unknown(format!("CLIENT {}", subcommand))),
                         }
                     }
-                    "OBJECT" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'object' command".to_string());
+                    // Negotiated by the connection
+                    "HELLO" => {
+                        let protover = elements.get(1).map(Self::extract_i64_zc).transpose()?;
+                        let (mut auth, mut setname) = (None, None);
+                        let mut i = 2;
+                        while i < elements.len() {
+                            let option = Self::extract_string_zc(&elements[i])?;
+                            let more = elements.len() - i - 1;
+                            match option.to_uppercase().as_str() {
+                                "AUTH" if more >= 2 => {
+                                    auth = Some((
+                                        Self::extract_string_zc(&elements[i + 1])?,
+                                        Self::extract_string_zc(&elements[i + 2])?,
+                                    ));
+                                    i += 3;
+                                }
+                                "SETNAME" if more >= 1 => {
+                                    setname = Some(Self::extract_string_zc(&elements[i + 1])?);
+                                    i += 2;
+                                }
+                                _ => {
+                                    return Err(format!(
+                                        "ERR Syntax error in HELLO option '{}'",
+                                        option
+                                    ))
+                                }
+                            }
                         }
+                        Ok(Command::Hello {
+                            protover,
+                            auth,
+                            setname,
+                        })
+                    }
+                    "XINFO" => {
+                        let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
+                        Ok(Command::Unknown(format!("XINFO {}", subcommand)))
+                    }
+                    "OBJECT" => {
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "HELP" => Ok(Command::ObjectHelp),
@@ -1225,9 +1932,6 @@ impl Command {
                         }
                     }
                     "DEBUG" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'debug' command".to_string());
-                        }
                         let subcommand = Self::extract_string_zc(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "SLEEP" => {
@@ -1235,9 +1939,25 @@ impl Command {
                                     return Err("ERR wrong number of arguments for 'debug|sleep' command".to_string());
                                 }
                                 let seconds = Self::extract_float_zc(&elements[2])?;
+                                if !seconds.is_finite() || seconds < 0.0 {
+                                    return Err("ERR sleep time must be a non-negative number".to_string());
+                                }
                                 Ok(Command::DebugSleep(seconds))
                             }
-                            "SET-ACTIVE-EXPIRE" | "JMAP" | "RELOAD" | "LOADAOF" | "QUICKLIST-PACKED-THRESHOLD" => {
+                            "SET-ACTIVE-EXPIRE" => {
+                                if elements.len() != 3 {
+                                    return Err("ERR wrong number of arguments for 'debug|set-active-expire' command".to_string());
+                                }
+                                Ok(Command::DebugSetActiveExpire(Self::extract_integer_zc(&elements[2])? != 0))
+                            }
+                            "RELOAD" => {
+                                if elements.len() > 2 {
+                                    let option = Self::extract_string_zc(&elements[2])?;
+                                    return Err(format!("ERR DEBUG RELOAD option '{}' is not supported", option));
+                                }
+                                Ok(Command::DebugReload)
+                            }
+                            "JMAP" | "LOADAOF" | "QUICKLIST-PACKED-THRESHOLD" => {
                                 if elements.len() >= 3 {
                                     let val = Self::extract_string_zc(&elements[2])?;
                                     Ok(Command::DebugSet(subcommand, val))
@@ -1245,6 +1965,51 @@ impl Command {
                                     Ok(Command::DebugSet(subcommand, String::new()))
                                 }
                             }
+                            "KEYSTATS" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'debug|keystats' command".to_string());
+                                }
+                                Ok(Command::DebugKeyStats)
+                            }
+                            "STRINGMATCH-LEN" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'debug|stringmatch-len' command".to_string());
+                                }
+                                Ok(Command::DebugStringMatchLen)
+                            }
+                            "PROTOCOL" => {
+                                if elements.len() != 3 {
+                                    return Err("ERR wrong number of arguments for 'debug|protocol' command".to_string());
+                                }
+                                Ok(Command::DebugProtocol(Self::extract_string_zc(&elements[2])?.to_lowercase()))
+                            }
+                            "HEALTH" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'debug|health' command".to_string());
+                                }
+                                Ok(Command::DebugHealth)
+                            }
+                            "BUGGIFY" => {
+                                let action = if elements.len() >= 3 {
+                                    Self::extract_string_zc(&elements[2])?.to_uppercase()
+                                } else {
+                                    String::new()
+                                };
+                                match (action.as_str(), elements.len()) {
+                                    ("SET", 5) => {
+                                        let fault = Self::extract_string_zc(&elements[3])?;
+                                        let probability = Self::extract_float_zc(&elements[4])?;
+                                        Ok(Command::DebugBuggifySet { fault, probability })
+                                    }
+                                    ("STATS", 3) => Ok(Command::DebugBuggifyStats),
+                                    _ => Err("ERR wrong number of arguments for 'debug|buggify' command".to_string()),
+                                }
+                            }
+                            "SNAPSHOT-READ" => Self::parse_snapshot_reads(
+                                &elements[2..],
+                                Self::extract_integer_zc,
+                                |args| Self::from_resp_zero_copy(&RespValueZeroCopy::Array(Some(args.to_vec()))),
+                            ),
                             "OBJECT" => {
                                 if elements.len() != 3 {
                                     return Err("ERR wrong number of arguments for 'debug|object' command".to_string());
@@ -1262,18 +2027,12 @@ impl Command {
                         }
                     }
                     "GETRANGE" | "SUBSTR" => {
-                        if elements.len() != 4 {
-                            return Err("GETRANGE requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let start = Self::extract_integer_zc(&elements[2])?;
                         let end = Self::extract_integer_zc(&elements[3])?;
                         Ok(Command::GetRange(key, start, end))
                     }
                     "SETRANGE" => {
-                        if elements.len() != 4 {
-                            return Err("SETRANGE requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let offset = Self::extract_integer_zc(&elements[2])?;
                         if offset < 0 {
@@ -1283,9 +2042,6 @@ impl Command {
                         Ok(Command::SetRange(key, offset as usize, value))
                     }
                     "SETBIT" => {
-                        if elements.len() != 4 {
-                            return Err("ERR wrong number of arguments for 'setbit' command".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let offset = Self::extract_u64_zc(&elements[2])
                             .map_err(|_| "ERR bit offset is not an integer or out of range".to_string())?;
@@ -1297,18 +2053,81 @@ impl Command {
                         Ok(Command::SetBit(key, offset, value as u8))
                     }
                     "GETBIT" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'getbit' command".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let offset = Self::extract_u64_zc(&elements[2])
                             .map_err(|_| "ERR bit offset is not an integer or out of range".to_string())?;
                         Ok(Command::GetBit(key, offset))
                     }
-                    "GETEX" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'getex' command".to_string());
+                    "BITCOUNT" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let range = match elements.len() {
+                            2 => None,
+                            4 | 5 => Some(BitRange {
+                                start: Self::extract_integer_zc(&elements[2])? as i64,
+                                end: Some(Self::extract_integer_zc(&elements[3])? as i64),
+                                unit: match elements.get(4) {
+                                    Some(e) => Self::parse_bit_unit_zc(&Self::extract_string_zc(e)?)?,
+                                    None => BitUnit::Byte,
+                                },
+                            }),
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        Ok(Command::BitCount { key, range })
+                    }
+                    "BITPOS" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let bit = Self::extract_integer_zc(&elements[2])?;
+                        if bit != 0 && bit != 1 {
+                            return Err("ERR The bit argument must be 1 or 0.".to_string());
+                        }
+                        let range = match elements.get(3) {
+                            Some(start) => Some(BitRange {
+                                start: Self::extract_integer_zc(start)? as i64,
+                                end: match elements.get(4) {
+                                    Some(e) => Some(Self::extract_integer_zc(e)? as i64),
+                                    None => None,
+                                },
+                                unit: match elements.get(5) {
+                                    Some(e) => Self::parse_bit_unit_zc(&Self::extract_string_zc(e)?)?,
+                                    None => BitUnit::Byte,
+                                },
+                            }),
+                            None => None,
+                        };
+                        Ok(Command::BitPos { key, bit: bit as u8, range })
+                    }
+                    "BITOP" => {
+                        let op = match Self::extract_string_zc(&elements[1])?.to_uppercase().as_str() {
+                            "AND" => BitOperation::And,
+                            "OR" => BitOperation::Or,
+                            "XOR" => BitOperation::Xor,
+                            "NOT" => BitOperation::Not,
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        let dest = Self::extract_string_zc(&elements[2])?;
+                        let keys = elements[3..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        if op == BitOperation::Not && keys.len() != 1 {
+                            return Err("ERR BITOP NOT must be called with a single source key.".to_string());
+                        }
+                        Ok(Command::BitOp { op, dest, keys })
+                    }
+                    "BITFIELD" | "BITFIELD_RO" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let args = elements[2..]
+                            .iter()
+                            .map(Self::extract_string_zc)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let ops = Self::parse_bitfield_ops(&args, cmd_name == "BITFIELD_RO")?;
+                        if cmd_name == "BITFIELD_RO" {
+                            Ok(Command::BitFieldRo { key, ops })
+                        } else {
+                            Ok(Command::BitField { key, ops })
                         }
+                    }
+                    "GETEX" => {
                         let key = Self::extract_string_zc(&elements[1])?;
                         let mut ex = None;
                         let mut px = None;
@@ -1350,20 +2169,15 @@ impl Command {
                         if option_count > 1 {
                             return Err("ERR syntax error".to_string());
                         }
+                        Self::check_expire("getex", ex, px, exat, pxat)?;
 
                         Ok(Command::GetEx { key, ex, px, exat, pxat, persist })
                     }
                     "GETDEL" => {
-                        if elements.len() != 2 {
-                            return Err("GETDEL requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         Ok(Command::GetDel(key))
                     }
                     "INCRBYFLOAT" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'incrbyfloat' command".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
                         let increment = Self::extract_float_zc(&elements[2])?;
                         if increment.is_nan() || increment.is_infinite() {
@@ -1372,11 +2186,9 @@ impl Command {
                         Ok(Command::IncrByFloat(key, increment))
                     }
                     "PSETEX" => {
-                        if elements.len() != 4 {
-                            return Err("PSETEX requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string_zc(&elements[1])?;
-                        let millis = Self::extract_integer_zc(&elements[2])? as i64;
+                        let millis = Self::extract_i64_zc(&elements[2])?;
+                        Self::check_expire("psetex", None, Some(millis), None, None)?;
                         let value = Self::extract_sds_zc(&elements[3])?;
                         Ok(Command::Set {
                             key,
@@ -1391,81 +2203,182 @@ impl Command {
                             keepttl: false,
                         })
                     }
-                    "EXPIRETIME" => {
-                        if elements.len() != 2 {
-                            return Err("EXPIRETIME requires 1 argument".to_string());
-                        }
-                        let key = Self::extract_string_zc(&elements[1])?;
-                        Ok(Command::ExpireTime(key))
-                    }
-                    "PEXPIRETIME" => {
-                        if elements.len() != 2 {
-                            return Err("PEXPIRETIME requires 1 argument".to_string());
-                        }
-                        let key = Self::extract_string_zc(&elements[1])?;
-                        Ok(Command::PExpireTime(key))
-                    }
                     "UNLINK" => {
-                        if elements.len() < 2 {
-                            return Err("UNLINK requires at least 1 argument".to_string());
-                        }
                         let keys: Vec<String> = elements[1..]
                             .iter()
                             .map(Self::extract_string_zc)
                             .collect::<Result<Vec<_>, _>>()?;
-                        Ok(Command::Del(keys))
+                        Ok(Command::Unlink(keys))
                     }
                     "WAIT" => {
-                        if elements.len() != 3 {
-                            return Err("WAIT requires 2 arguments".to_string());
-                        }
                         let numreplicas = Self::extract_i64_zc(&elements[1])?;
                         let timeout = Self::extract_i64_zc(&elements[2])?;
+                        if timeout < 0 {
+                            return Err("ERR timeout is negative".to_string());
+                        }
                         Ok(Command::Wait(numreplicas, timeout))
                     }
-                    "SORT" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'sort' command".to_string());
+                    "SHUTDOWN" => {
+                        let mut save = None;
+                        for element in &elements[1..] {
+                            match Self::extract_string_zc(element)?.to_uppercase().as_str() {
+                                "NOSAVE" if save.is_none() => save = Some(false),
+                                "SAVE" if save.is_none() => save = Some(true),
+                                // Nothing waits for replicas and a failed save
+                                // never stops the shutdown, so these change nothing
+                                "NOW" | "FORCE" => {}
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
                         }
+                        Ok(Command::Shutdown { save })
+                    }
+                    "SORT" => {
                         let key = Self::extract_string_zc(&elements[1])?;
+                        let mut by = None;
+                        let mut limit = None;
+                        let mut get = Vec::new();
+                        let mut desc = false;
+                        let mut alpha = false;
                         let mut store = None;
                         let mut i = 2;
                         while i < elements.len() {
                             let opt = Self::extract_string_zc(&elements[i])?.to_uppercase();
-                            if opt == "STORE" {
-                                i += 1;
-                                if i < elements.len() {
-                                    store = Some(Self::extract_string_zc(&elements[i])?);
+                            let remaining = elements.len() - i - 1;
+                            match opt.as_str() {
+                                "ASC" => desc = false,
+                                "DESC" => desc = true,
+                                "ALPHA" => alpha = true,
+                                "LIMIT" if remaining >= 2 => {
+                                    let offset = Self::extract_i64_zc(&elements[i + 1])?;
+                                    let count = Self::extract_i64_zc(&elements[i + 2])?;
+                                    limit = Some((offset, count));
+                                    i += 2;
+                                }
+                                "BY" if remaining >= 1 => {
+                                    by = Some(Self::extract_string_zc(&elements[i + 1])?);
+                                    i += 1;
+                                }
+                                "GET" if remaining >= 1 => {
+                                    get.push(Self::extract_string_zc(&elements[i + 1])?);
+                                    i += 1;
+                                }
+                                "STORE" if remaining >= 1 => {
+                                    store = Some(Self::extract_string_zc(&elements[i + 1])?);
+                                    i += 1;
                                 }
+                                _ => return Err("ERR syntax error".to_string()),
                             }
                             i += 1;
                         }
-                        Ok(Command::Sort { key, store })
+                        Ok(Command::Sort {
+                            key,
+                            by,
+                            limit,
+                            get,
+                            desc,
+                            alpha,
+                            store,
+                        })
                     }
                     "RANDOMKEY" => Ok(Command::RandomKey),
                     "RENAME" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'rename' command".to_string());
-                        }
                         let src = Self::extract_string_zc(&elements[1])?;
                         let dst = Self::extract_string_zc(&elements[2])?;
                         Ok(Command::Rename(src, dst))
                     }
                     "RENAMENX" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'renamenx' command".to_string());
-                        }
                         let src = Self::extract_string_zc(&elements[1])?;
                         let dst = Self::extract_string_zc(&elements[2])?;
                         Ok(Command::RenameNx(src, dst))
                     }
-                    _ => Ok(Command::Unknown(cmd_name)),
+                    "COPY" => {
+                        let source = Self::extract_string_zc(&elements[1])?;
+                        let dest = Self::extract_string_zc(&elements[2])?;
+                        let mut replace = false;
+                        for arg in &elements[3..] {
+                            match Self::extract_string_zc(arg)?.to_ascii_uppercase().as_str() {
+                                "REPLACE" => replace = true,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                        }
+                        Ok(Command::Copy {
+                            source,
+                            dest,
+                            replace,
+                        })
+                    }
+                    "RESTORE" => {
+                        let key = Self::extract_string_zc(&elements[1])?;
+                        let ttl = Self::extract_i64_zc(&elements[2])?;
+                        if ttl < 0 {
+                            return Err("ERR Invalid TTL value, must be >= 0".to_string());
+                        }
+                        let payload = Self::extract_sds_zc(&elements[3])?.as_bytes().to_vec();
+                        let mut replace = false;
+                        let mut absttl = false;
+                        let mut idletime = None;
+                        let mut freq = None;
+                        let mut i = 4;
+                        while i < elements.len() {
+                            let opt = Self::extract_string_zc(&elements[i])?.to_ascii_uppercase();
+                            match opt.as_str() {
+                                "REPLACE" => replace = true,
+                                "ABSTTL" => absttl = true,
+                                "IDLETIME" if i + 1 < elements.len() && freq.is_none() => {
+                                    i += 1;
+                                    let secs = Self::extract_i64_zc(&elements[i])?;
+                                    if secs < 0 {
+                                        return Err(
+                                            "ERR Invalid IDLETIME value, must be >= 0".to_string()
+                                        );
+                                    }
+                                    idletime = Some(secs as u64);
+                                }
+                                "FREQ" if i + 1 < elements.len() && idletime.is_none() => {
+                                    i += 1;
+                                    let value = Self::extract_i64_zc(&elements[i])?;
+                                    freq = Some(u8::try_from(value).map_err(|_| {
+                                        "ERR Invalid FREQ value, must be >= 0 and <= 255"
+                                            .to_string()
+                                    })?);
+                                }
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        Ok(Command::Restore {
+                            key,
+                            ttl_ms: ttl as u64,
+                            payload,
+                            replace,
+                            absttl,
+                            idletime,
+                            freq,
+                        })
+                    }
+                    _ => match DeclaredCommand::parse(&cmd_name, elements.len(), |i| {
+                        match &elements[i] {
+                            RespValueZeroCopy::BulkString(Some(data)) => Some(data.as_ref()),
+                            _ => None,
+                        }
+                    }) {
+                        Some(parsed) => parsed.map(Command::Declared),
+                        None => Ok(Command::Unknown(cmd_name)),
+                    },
                 }
             }
             _ => Err("Invalid command format".to_string()),
         }
     }
 
+    fn parse_bit_unit_zc(unit: &str) -> Result<BitUnit, String> {
+        match unit.to_uppercase().as_str() {
+            "BYTE" => Ok(BitUnit::Byte),
+            "BIT" => Ok(BitUnit::Bit),
+            _ => Err("ERR syntax error".to_string()),
+        }
+    }
+
     fn extract_string_zc(value: &RespValueZeroCopy) -> Result<String, String> {
         match value {
             RespValueZeroCopy::BulkString(Some(data)) => {
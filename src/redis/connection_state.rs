@@ -0,0 +1,125 @@
+//! Per-connection state: MULTI queue, WATCH snapshots, Pub/Sub and tracking.
+//!
+//! Everything RESET throws away lives here, so both layers that own a
+//! connection reset it the same way:
+//!
+//! - `CommandExecutor` holds one for its single client, snapshotting
+//!   watched keys as `Option<Value>`
+//! - the production connection handler holds one per socket, watching keys
+//!   through a `WatchGuard` registered on the key's shard, since values live
+//!   behind shard actors. Dropping the guard unwatches the key. It
+//!   additionally resets its authenticated user, tenant, protocol and client
+//!   name, which only it knows about
+//!
+//! Dropping the Pub/Sub and tracking sessions unregisters them from their
+//! managers, so RESET unsubscribes and turns tracking off by dropping them.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Outside MULTI the queue is empty and no queueing error is recorded
+//! - A key is watched at most once
+
+use super::pubsub::PubSubSession;
+use super::tracking::TrackingSession;
+use super::Command;
+
+pub struct ConnectionState<W> {
+    /// Between MULTI and EXEC/DISCARD
+    pub(crate) in_transaction: bool,
+    /// Commands queued since MULTI
+    pub(crate) queued_commands: Vec<Command>,
+    /// A command failed to queue; EXEC replies EXECABORT
+    pub(crate) transaction_errors: bool,
+    /// Watched keys with their snapshot at WATCH time
+    pub(crate) watched_keys: Vec<(String, W)>,
+    /// Pub/Sub subscriptions and mailbox (created on first SUBSCRIBE)
+    pub(crate) pubsub: Option<PubSubSession>,
+    /// CLIENT TRACKING registration and invalidation mailbox (None = off)
+    pub(crate) tracking: Option<TrackingSession>,
+}
+
+impl<W> Default for ConnectionState<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> ConnectionState<W> {
+    pub fn new() -> Self {
+        ConnectionState {
+            in_transaction: false,
+            queued_commands: Vec::new(),
+            transaction_errors: false,
+            watched_keys: Vec::new(),
+            pubsub: None,
+            tracking: None,
+        }
+    }
+
+    /// Enter MULTI with an empty queue
+    pub fn begin_transaction(&mut self) {
+        debug_assert!(
+            !self.in_transaction,
+            "Precondition: MULTI calls can not be nested"
+        );
+        self.in_transaction = true;
+        self.queued_commands.clear();
+        self.transaction_errors = false;
+    }
+
+    /// Leave MULTI (EXEC, DISCARD or RESET), unwatching every key.
+    ///
+    /// Returns the queued commands for EXEC to run.
+    pub fn end_transaction(&mut self) -> Vec<Command> {
+        self.in_transaction = false;
+        self.transaction_errors = false;
+        self.watched_keys.clear();
+        let queued = std::mem::take(&mut self.queued_commands);
+
+        self.verify_invariants();
+        queued
+    }
+
+    /// Watch `key`, replacing an earlier snapshot of it
+    pub fn watch(&mut self, key: String, snapshot: W) {
+        match self.watched_keys.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = snapshot,
+            None => self.watched_keys.push((key, snapshot)),
+        }
+    }
+
+    pub fn is_watching(&self, key: &str) -> bool {
+        self.watched_keys.iter().any(|(k, _)| k == key)
+    }
+
+    /// RESET: discard MULTI, unwatch, unsubscribe and stop tracking
+    pub fn reset(&mut self) {
+        self.end_transaction();
+        self.pubsub = None;
+        self.tracking = None;
+
+        debug_assert!(
+            self.watched_keys.is_empty() && self.pubsub.is_none() && self.tracking.is_none(),
+            "Postcondition violated: RESET must leave a fresh connection"
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn verify_invariants(&self) {
+        debug_assert!(
+            self.in_transaction || (self.queued_commands.is_empty() && !self.transaction_errors),
+            "Invariant: commands are only queued inside MULTI"
+        );
+        debug_assert!(
+            self.watched_keys
+                .iter()
+                .enumerate()
+                .all(|(i, (key, _))| self.watched_keys[..i].iter().all(|(k, _)| k != key)),
+            "Invariant: a key is watched at most once"
+        );
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline(always)]
+    pub fn verify_invariants(&self) {}
+}
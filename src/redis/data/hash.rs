@@ -1,4 +1,8 @@
 //! Redis Hash data structure
+//!
+//! Fields may carry their own deadline (HEXPIRE and friends), kept in a
+//! side map in milliseconds on the executor's clock. Removing expired
+//! fields is the caller's job: the hash only knows deadlines, not the time.
 
 use super::SDS;
 use ahash::AHashMap;
@@ -6,15 +10,32 @@ use ahash::AHashMap;
 #[derive(Clone, Debug, PartialEq)]
 pub struct RedisHash {
     fields: AHashMap<String, SDS>,
+    /// Field deadlines; every entry names an existing field
+    field_expiry: AHashMap<String, u64>,
 }
 
 impl RedisHash {
     pub fn new() -> Self {
         RedisHash {
             fields: AHashMap::new(),
+            field_expiry: AHashMap::new(),
         }
     }
 
+    /// Build a hash of `pairs` (a repeated field keeps its last value),
+    /// checking invariants once instead of after every set
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (SDS, SDS)>) -> Self {
+        let hash = RedisHash {
+            fields: pairs
+                .into_iter()
+                .map(|(field, value)| (field.to_string(), value))
+                .collect(),
+            field_expiry: AHashMap::new(),
+        };
+        hash.verify_invariants();
+        hash
+    }
+
     /// Verify all invariants hold for this hash
     /// Called in debug builds after every mutation
     #[cfg(debug_assertions)]
@@ -54,6 +75,15 @@ impl RedisHash {
                 "Invariant violated: key roundtrip must be stable"
             );
         }
+
+        // Invariant 5: Only existing fields carry a deadline
+        for field in self.field_expiry.keys() {
+            debug_assert!(
+                self.fields.contains_key(field),
+                "Invariant violated: deadline for missing field '{}'",
+                field
+            );
+        }
     }
 
     #[cfg(not(debug_assertions))]
@@ -72,6 +102,8 @@ impl RedisHash {
         };
 
         self.fields.insert(field_str.clone(), value.clone());
+        // Overwriting a field clears its TTL, as in Redis
+        self.field_expiry.remove(&field_str);
 
         // TigerStyle: Postcondition - verify the set succeeded
         debug_assert!(
@@ -93,10 +125,26 @@ impl RedisHash {
         self.verify_invariants();
     }
 
+    /// `set` that keeps the field's TTL (HINCRBY, HINCRBYFLOAT)
+    pub fn set_keep_ttl(&mut self, field: SDS, value: SDS) {
+        let field_str = field.to_string();
+        let deadline = self.field_expiry.get(&field_str).copied();
+        self.set(field, value);
+        if let Some(deadline) = deadline {
+            self.field_expiry.insert(field_str, deadline);
+        }
+        self.verify_invariants();
+    }
+
     pub fn get(&self, field: &SDS) -> Option<&SDS> {
         self.fields.get(&field.to_string())
     }
 
+    /// `get` without building an SDS for the field
+    pub fn get_str(&self, field: &str) -> Option<&SDS> {
+        self.fields.get(field)
+    }
+
     pub fn delete(&mut self, field: &SDS) -> bool {
         let field_str = field.to_string();
 
@@ -107,6 +155,7 @@ impl RedisHash {
         let existed = self.fields.contains_key(&field_str);
 
         let removed = self.fields.remove(&field_str).is_some();
+        self.field_expiry.remove(&field_str);
 
         // TigerStyle: Postconditions
         debug_assert!(
@@ -162,6 +211,65 @@ impl RedisHash {
     pub fn iter(&self) -> impl Iterator<Item = (&String, &SDS)> {
         self.fields.iter()
     }
+
+    /// The field's deadline in milliseconds, if it has one
+    pub fn field_expiry(&self, field: &str) -> Option<u64> {
+        self.field_expiry.get(field).copied()
+    }
+
+    /// Give an existing field a deadline, replacing any earlier one
+    pub fn set_field_expiry(&mut self, field: &str, deadline_ms: u64) {
+        debug_assert!(
+            self.fields.contains_key(field),
+            "Precondition: only an existing field can expire"
+        );
+        self.field_expiry.insert(field.to_string(), deadline_ms);
+        self.verify_invariants();
+    }
+
+    /// Drop the field's deadline; false if it had none
+    pub fn persist_field(&mut self, field: &str) -> bool {
+        let removed = self.field_expiry.remove(field).is_some();
+        debug_assert!(
+            !self.field_expiry.contains_key(field),
+            "Postcondition violated: field must have no deadline after persist"
+        );
+        removed
+    }
+
+    /// Whether any field has a deadline
+    pub fn has_field_expiries(&self) -> bool {
+        !self.field_expiry.is_empty()
+    }
+
+    /// Delete every field whose deadline is at or before `now_ms`,
+    /// returning how many went
+    pub fn remove_expired_fields(&mut self, now_ms: u64) -> usize {
+        if self.field_expiry.is_empty() {
+            return 0;
+        }
+        let fields = &mut self.fields;
+        let pre_len = fields.len();
+        self.field_expiry.retain(|field, &mut deadline| {
+            if deadline <= now_ms {
+                fields.remove(field);
+                false
+            } else {
+                true
+            }
+        });
+        let removed = pre_len - self.fields.len();
+
+        // TigerStyle: Postcondition - no deadline at or before now remains
+        debug_assert!(
+            self.field_expiry
+                .values()
+                .all(|&deadline| deadline > now_ms),
+            "Postcondition violated: expired fields must be gone"
+        );
+        self.verify_invariants();
+        removed
+    }
 }
 
 #[cfg(test)]
@@ -254,4 +362,36 @@ mod tests {
         assert_eq!(hash.len(), 0);
         assert!(!hash.exists(&SDS::from_str("counter")));
     }
+
+    #[test]
+    fn test_hash_field_expiry() {
+        let mut hash = RedisHash::new();
+        for field in ["a", "b", "c"] {
+            hash.set(SDS::from_str(field), SDS::from_str("1"));
+        }
+        hash.set_field_expiry("a", 100);
+        hash.set_field_expiry("b", 200);
+        assert!(hash.has_field_expiries());
+        assert_eq!(hash.field_expiry("a"), Some(100));
+        assert_eq!(hash.field_expiry("c"), None);
+
+        // HINCRBY keeps the deadline, HSET clears it
+        hash.set_keep_ttl(SDS::from_str("a"), SDS::from_str("2"));
+        assert_eq!(hash.field_expiry("a"), Some(100));
+        hash.set(SDS::from_str("b"), SDS::from_str("2"));
+        assert_eq!(hash.field_expiry("b"), None);
+
+        assert_eq!(hash.remove_expired_fields(99), 0);
+        assert_eq!(hash.remove_expired_fields(100), 1);
+        assert!(!hash.exists(&SDS::from_str("a")));
+        assert!(!hash.has_field_expiries());
+        assert_eq!(hash.len(), 2);
+
+        hash.set_field_expiry("c", 300);
+        assert!(hash.persist_field("c"));
+        assert!(!hash.persist_field("c"));
+        hash.set_field_expiry("c", 300);
+        hash.delete(&SDS::from_str("c"));
+        assert!(!hash.has_field_expiries());
+    }
 }
@@ -15,6 +15,16 @@ impl RedisList {
         }
     }
 
+    /// Build a list holding `items` in order (RPUSH of each), checking
+    /// invariants once instead of after every push
+    pub fn from_items(items: impl IntoIterator<Item = SDS>) -> Self {
+        let list = RedisList {
+            items: items.into_iter().collect(),
+        };
+        list.verify_invariants();
+        list
+    }
+
     /// Verify all invariants hold for this list
     #[cfg(debug_assertions)]
     fn verify_invariants(&self) {
@@ -176,6 +186,11 @@ impl RedisList {
         self.items.is_empty()
     }
 
+    /// Iterate over the elements, head first
+    pub fn iter(&self) -> impl Iterator<Item = &SDS> {
+        self.items.iter()
+    }
+
     pub fn range(&self, start: isize, stop: isize) -> Vec<SDS> {
         let len = self.items.len() as isize;
         let start = if start < 0 {
@@ -301,6 +316,99 @@ impl RedisList {
 
         self.verify_invariants();
     }
+
+    /// LINSERT - insert `value` before or after the first `pivot`.
+    /// Returns false (leaving the list alone) when the pivot is absent.
+    pub fn insert(&mut self, pivot: &SDS, value: SDS, before: bool) -> bool {
+        let Some(at) = self.items.iter().position(|item| item == pivot) else {
+            return false;
+        };
+        let pre_len = self.items.len();
+        let index = if before { at } else { at + 1 };
+        self.items.insert(index, value);
+
+        // TigerStyle: Postcondition
+        debug_assert_eq!(
+            self.items.len(),
+            pre_len + 1,
+            "Postcondition violated: len must increase by 1 after insert"
+        );
+
+        self.verify_invariants();
+        true
+    }
+
+    /// LREM - remove up to `count` elements equal to `value`: from the head
+    /// when positive, from the tail when negative, every match when 0.
+    /// Returns the number removed.
+    pub fn remove(&mut self, count: isize, value: &SDS) -> usize {
+        let pre_len = self.items.len();
+        let limit = if count == 0 {
+            usize::MAX
+        } else {
+            count.unsigned_abs()
+        };
+        let mut removed = 0;
+        if count >= 0 {
+            self.items.retain(|item| {
+                if removed < limit && item == value {
+                    removed += 1;
+                    return false;
+                }
+                true
+            });
+        } else {
+            let mut index = self.items.len();
+            while index > 0 && removed < limit {
+                index -= 1;
+                if self.items[index] == *value {
+                    self.items.remove(index);
+                    removed += 1;
+                }
+            }
+        }
+
+        // TigerStyle: Postcondition
+        debug_assert_eq!(
+            self.items.len(),
+            pre_len - removed,
+            "Postcondition violated: len must decrease by the removed count"
+        );
+
+        self.verify_invariants();
+        removed
+    }
+
+    /// LPOS - indexes (from the head) of elements equal to `value`.
+    ///
+    /// `rank` picks the first match to report: 1 is the first from the head,
+    /// -1 the first from the tail. At most `count` matches are returned
+    /// (0 means all) and at most `maxlen` elements compared (0 means all).
+    pub fn positions(&self, value: &SDS, rank: i64, count: usize, maxlen: usize) -> Vec<usize> {
+        debug_assert!(rank != 0, "Precondition: LPOS rank must not be zero");
+
+        let len = self.items.len();
+        let maxlen = if maxlen == 0 { len } else { maxlen.min(len) };
+        let count = if count == 0 { len } else { count };
+        let skip = (rank.unsigned_abs() - 1) as usize;
+        let indexes: Box<dyn Iterator<Item = usize>> = if rank > 0 {
+            Box::new(0..maxlen)
+        } else {
+            Box::new((len - maxlen..len).rev())
+        };
+        let found: Vec<usize> = indexes
+            .filter(|&i| self.items[i] == *value)
+            .skip(skip)
+            .take(count)
+            .collect();
+
+        // TigerStyle: Postcondition
+        debug_assert!(
+            found.len() <= count,
+            "Postcondition violated: LPOS must return at most count matches"
+        );
+        found
+    }
 }
 
 #[cfg(test)]
@@ -423,4 +531,26 @@ mod tests {
         assert!(list.is_empty());
         assert_eq!(list.len(), 0);
     }
+
+    #[test]
+    fn test_list_insert_remove_positions() {
+        let mut list = RedisList::new();
+        for v in ["a", "b", "a", "c", "a"] {
+            list.rpush(SDS::from_str(v));
+        }
+        let a = SDS::from_str("a");
+
+        assert!(list.insert(&SDS::from_str("c"), SDS::from_str("x"), true));
+        assert!(!list.insert(&SDS::from_str("zz"), SDS::from_str("x"), true));
+        assert_eq!(list.positions(&a, 1, 0, 0), vec![0, 2, 5]);
+        assert_eq!(list.positions(&a, -1, 2, 0), vec![5, 2]);
+        assert_eq!(list.positions(&a, 2, 1, 0), vec![2]);
+        assert_eq!(list.positions(&a, 1, 0, 2), vec![0]);
+
+        assert_eq!(list.remove(-2, &a), 2);
+        let items: Vec<String> = list.range(0, -1).iter().map(|v| v.to_string()).collect();
+        assert_eq!(items, ["a", "b", "x", "c"]);
+        assert_eq!(list.remove(0, &SDS::from_str("b")), 1);
+        assert_eq!(list.len(), 3);
+    }
 }
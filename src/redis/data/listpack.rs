@@ -0,0 +1,506 @@
+//! Listpack - the compact encoding Redis uses for small collections in
+//! RDB files and DUMP payloads
+//!
+//! Layout: a 6-byte header (total bytes as u32 LE, element count as u16 LE,
+//! where 65535 means "count the entries"), the entries, then a 0xFF
+//! terminator. Each entry is an encoding byte, its data, and a backlen
+//! holding the size of encoding + data so the list can be walked backwards.
+//!
+//! Payloads come from the network (RESTORE) and from disk, so decoding
+//! never trusts a length: every read is bounds-checked and a corrupted
+//! payload is an error, never a panic. `sanitize-dump-payload` decides how
+//! much more is checked:
+//!
+//! - shallow (default): the header matches the payload and every entry fits
+//! - deep: additionally every backlen matches its entry and the element
+//!   count matches the header, catching corruption that would otherwise
+//!   decode to the wrong elements
+//!
+//! # TigerStyle Invariants
+//!
+//! - `decode(&encode(items), deep)` returns `items` for either depth
+//! - `decode` never reads outside the payload
+
+/// Header size: total bytes (u32) + element count (u16)
+const HEADER_SIZE: usize = 6;
+
+/// Terminator byte, also the first byte no entry may start with
+const EOF_BYTE: u8 = 0xFF;
+
+/// Element count stored when the real count does not fit in a u16
+const COUNT_UNKNOWN: u16 = u16::MAX;
+
+/// One decoded listpack element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListpackEntry {
+    Int(i64),
+    Str(Vec<u8>),
+}
+
+impl ListpackEntry {
+    /// The element as Redis returns it to clients
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            ListpackEntry::Int(n) => n.to_string().into_bytes(),
+            ListpackEntry::Str(bytes) => bytes.clone(),
+        }
+    }
+}
+
+/// `sanitize-dump-payload` values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizePayload {
+    /// Shallow checks only
+    No,
+    /// Deep checks for every payload
+    Yes,
+    /// Deep checks for payloads sent by clients, not by a master
+    Clients,
+}
+
+impl SanitizePayload {
+    pub const ALL: [SanitizePayload; 3] = [
+        SanitizePayload::No,
+        SanitizePayload::Yes,
+        SanitizePayload::Clients,
+    ];
+
+    /// Parse a config value (case-insensitive)
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(value))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SanitizePayload::No => "no",
+            SanitizePayload::Yes => "yes",
+            SanitizePayload::Clients => "clients",
+        }
+    }
+
+    /// Whether a payload needs deep checks, given who sent it
+    pub fn deep(self, from_client: bool) -> bool {
+        match self {
+            SanitizePayload::No => false,
+            SanitizePayload::Yes => true,
+            SanitizePayload::Clients => from_client,
+        }
+    }
+}
+
+/// Encode elements, storing canonical integers in the integer encodings
+/// the way Redis does
+pub fn encode<'a>(items: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut out = vec![0u8; HEADER_SIZE];
+    let mut count: usize = 0;
+    for item in items {
+        let start = out.len();
+        match canonical_int(item) {
+            Some(n) => encode_int(&mut out, n),
+            None => encode_str(&mut out, item),
+        }
+        let entry_len = out.len() - start;
+        encode_backlen(&mut out, entry_len);
+        count += 1;
+    }
+    out.push(EOF_BYTE);
+
+    let total = u32::try_from(out.len()).expect("listpack must fit in 4GB");
+    let stored_count = u16::try_from(count)
+        .ok()
+        .filter(|&c| c != COUNT_UNKNOWN)
+        .unwrap_or(COUNT_UNKNOWN);
+    out[0..4].copy_from_slice(&total.to_le_bytes());
+    out[4..6].copy_from_slice(&stored_count.to_le_bytes());
+
+    debug_assert!(
+        decode(&out, true).map(|entries| entries.len()) == Ok(count),
+        "Postcondition: encoded listpack must decode to its elements"
+    );
+    out
+}
+
+/// Decode a listpack payload. `deep` enables the sanitize-dump-payload
+/// checks described in the module docs.
+pub fn decode(payload: &[u8], deep: bool) -> Result<Vec<ListpackEntry>, &'static str> {
+    if payload.len() < HEADER_SIZE + 1 {
+        return Err("listpack shorter than its header");
+    }
+    let total = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+    if total as usize != payload.len() {
+        return Err("listpack total bytes does not match the payload");
+    }
+    if payload[payload.len() - 1] != EOF_BYTE {
+        return Err("listpack is not terminated");
+    }
+    let stored_count = u16::from_le_bytes([payload[4], payload[5]]);
+
+    let end = payload.len() - 1;
+    let mut entries = Vec::with_capacity(usize::from(stored_count.min(1024)));
+    let mut pos = HEADER_SIZE;
+    while pos < end {
+        let (entry, entry_len) = decode_entry(&payload[pos..end])?;
+        let (backlen, backlen_size) = decode_backlen(&payload[pos + entry_len..end], entry_len)?;
+        if deep && backlen != entry_len {
+            return Err("listpack entry backlen does not match its length");
+        }
+        entries.push(entry);
+        pos += entry_len + backlen_size;
+    }
+
+    if deep && stored_count != COUNT_UNKNOWN && usize::from(stored_count) != entries.len() {
+        return Err("listpack element count does not match its header");
+    }
+    debug_assert!(
+        pos == end,
+        "Postcondition: entries must end at the terminator"
+    );
+    Ok(entries)
+}
+
+/// Parse the integer an element would be stored as: only strings that are
+/// exactly the decimal form of an i64 (no sign on zero, no leading zeros)
+fn canonical_int(item: &[u8]) -> Option<i64> {
+    if item.is_empty() || item.len() > 20 {
+        return None;
+    }
+    let n: i64 = std::str::from_utf8(item).ok()?.parse().ok()?;
+    (n.to_string().as_bytes() == item).then_some(n)
+}
+
+fn encode_int(out: &mut Vec<u8>, n: i64) {
+    if (0..=127).contains(&n) {
+        out.push(n as u8);
+    } else if (-4096..=4095).contains(&n) {
+        let v = (n as u16) & 0x1FFF;
+        out.push(0xC0 | (v >> 8) as u8);
+        out.push(v as u8);
+    } else if let Ok(v) = i16::try_from(n) {
+        out.push(0xF1);
+        out.extend_from_slice(&v.to_le_bytes());
+    } else if (-(1 << 23)..(1 << 23)).contains(&n) {
+        out.push(0xF2);
+        out.extend_from_slice(&(n as i32).to_le_bytes()[..3]);
+    } else if let Ok(v) = i32::try_from(n) {
+        out.push(0xF3);
+        out.extend_from_slice(&v.to_le_bytes());
+    } else {
+        out.push(0xF4);
+        out.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+fn encode_str(out: &mut Vec<u8>, item: &[u8]) {
+    let len = item.len();
+    if len < 64 {
+        out.push(0x80 | len as u8);
+    } else if len < 4096 {
+        out.push(0xE0 | (len >> 8) as u8);
+        out.push(len as u8);
+    } else {
+        out.push(0xF0);
+        let len = u32::try_from(len).expect("listpack string must fit in 4GB");
+        out.extend_from_slice(&len.to_le_bytes());
+    }
+    out.extend_from_slice(item);
+}
+
+/// Backlen: 7 bits per byte, most significant first; every byte but the
+/// first has the high bit set, so it can be read from its last byte
+fn encode_backlen(out: &mut Vec<u8>, len: usize) {
+    let size = backlen_size(len);
+    for i in (0..size).rev() {
+        let group = ((len >> (7 * i)) & 0x7F) as u8;
+        out.push(if i == size - 1 { group } else { group | 0x80 });
+    }
+}
+
+fn backlen_size(len: usize) -> usize {
+    // Thresholds as in Redis lpEncodeBacklen
+    match len {
+        0..=127 => 1,
+        128..=16_382 => 2,
+        16_383..=2_097_150 => 3,
+        2_097_151..=268_435_454 => 4,
+        _ => 5,
+    }
+}
+
+/// Read the backlen of an entry of `entry_len` bytes from the start of
+/// `buf`. Returns (value, bytes used).
+fn decode_backlen(buf: &[u8], entry_len: usize) -> Result<(usize, usize), &'static str> {
+    let size = backlen_size(entry_len);
+    let bytes = buf
+        .get(..size)
+        .ok_or("listpack entry backlen extends past the payload")?;
+    let value = bytes.iter().fold(0usize, |value, &byte| {
+        (value << 7) | usize::from(byte & 0x7F)
+    });
+    Ok((value, size))
+}
+
+/// Fixed-size slice `buf[1..1 + len]` after an encoding byte
+fn data(buf: &[u8], len: usize) -> Result<&[u8], &'static str> {
+    buf.get(1..1 + len)
+        .ok_or("listpack entry extends past the payload")
+}
+
+/// Decode the entry at the start of `buf`. Returns (entry, encoding + data
+/// length).
+fn decode_entry(buf: &[u8]) -> Result<(ListpackEntry, usize), &'static str> {
+    let first = buf[0];
+    let (entry, len) = match first {
+        0x00..=0x7F => (ListpackEntry::Int(i64::from(first)), 1),
+        0x80..=0xBF => {
+            let len = usize::from(first & 0x3F);
+            (ListpackEntry::Str(data(buf, len)?.to_vec()), 1 + len)
+        }
+        0xC0..=0xDF => {
+            let low = *buf
+                .get(1)
+                .ok_or("listpack entry extends past the payload")?;
+            let v = (i64::from(first & 0x1F) << 8) | i64::from(low);
+            let n = if v >= 1 << 12 { v - (1 << 13) } else { v };
+            (ListpackEntry::Int(n), 2)
+        }
+        0xE0..=0xEF => {
+            let low = *buf
+                .get(1)
+                .ok_or("listpack entry extends past the payload")?;
+            let len = (usize::from(first & 0x0F) << 8) | usize::from(low);
+            let bytes = buf
+                .get(2..2 + len)
+                .ok_or("listpack entry extends past the payload")?;
+            (ListpackEntry::Str(bytes.to_vec()), 2 + len)
+        }
+        0xF0 => {
+            let l = data(buf, 4)?;
+            let len = u32::from_le_bytes([l[0], l[1], l[2], l[3]]) as usize;
+            let bytes = buf
+                .get(5..5usize.saturating_add(len))
+                .ok_or("listpack entry extends past the payload")?;
+            (ListpackEntry::Str(bytes.to_vec()), 5 + len)
+        }
+        0xF1 => {
+            let d = data(buf, 2)?;
+            (
+                ListpackEntry::Int(i64::from(i16::from_le_bytes([d[0], d[1]]))),
+                3,
+            )
+        }
+        0xF2 => {
+            let d = data(buf, 3)?;
+            // Sign-extend from 24 bits
+            let n = i32::from_le_bytes([0, d[0], d[1], d[2]]) >> 8;
+            (ListpackEntry::Int(i64::from(n)), 4)
+        }
+        0xF3 => {
+            let d = data(buf, 4)?;
+            let n = i32::from_le_bytes([d[0], d[1], d[2], d[3]]);
+            (ListpackEntry::Int(i64::from(n)), 5)
+        }
+        0xF4 => {
+            let d = data(buf, 8)?;
+            let mut raw = [0u8; 8];
+            raw.copy_from_slice(d);
+            (ListpackEntry::Int(i64::from_le_bytes(raw)), 9)
+        }
+        _ => return Err("listpack entry has an invalid encoding"),
+    };
+    debug_assert!(
+        len <= buf.len(),
+        "Postcondition: a decoded entry must lie within the payload"
+    );
+    Ok((entry, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::DeterministicRng;
+
+    fn entries(items: &[&[u8]]) -> Vec<ListpackEntry> {
+        decode(&encode(items.iter().copied()), true).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_covers_every_encoding() {
+        let long = vec![b'x'; 5000];
+        let medium = vec![b'y'; 300];
+        let items: Vec<Vec<u8>> = [
+            "0",
+            "127",
+            "128",
+            "-1",
+            "-4096",
+            "4095",
+            "-32768",
+            "32767",
+            "8388607",
+            "-8388608",
+            "2147483647",
+            "-2147483648",
+            "9223372036854775807",
+            "-9223372036854775808",
+            "",
+            "hello",
+            "007",
+            "-0",
+            "+5",
+            "1.5",
+        ]
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .chain([medium, long])
+        .collect();
+
+        let payload = encode(items.iter().map(Vec::as_slice));
+        for deep in [false, true] {
+            let decoded = decode(&payload, deep).unwrap();
+            let bytes: Vec<Vec<u8>> = decoded.iter().map(ListpackEntry::to_bytes).collect();
+            assert_eq!(bytes, items);
+        }
+        // Non-canonical integers stay strings
+        assert_eq!(
+            entries(&[b"12", b"012"]),
+            vec![ListpackEntry::Int(12), ListpackEntry::Str(b"012".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_matches_redis_byte_layout() {
+        // "a" is a 6-bit string, "1" a 7-bit integer, each with a 1-byte backlen
+        let expected = [
+            0x0C, 0x00, 0x00, 0x00, 0x02, 0x00, 0x81, 0x61, 0x02, 0x01, 0x01, 0xFF,
+        ];
+        assert_eq!(encode([b"a".as_slice(), b"1"]), expected);
+    }
+
+    #[test]
+    fn test_shallow_and_deep_checks() {
+        let good = encode([b"one".as_slice(), b"two", b"3"]);
+
+        let mut bad_total = good.clone();
+        bad_total[0] += 1;
+        let mut unterminated = good.clone();
+        *unterminated.last_mut().unwrap() = 0x00;
+        for payload in [&bad_total, &unterminated, &good[..5].to_vec()] {
+            assert!(decode(payload, false).is_err());
+        }
+
+        // A wrong count or backlen decodes the right bytes, so only deep
+        // checks catch it
+        let mut bad_count = good.clone();
+        bad_count[4] = 7;
+        let mut bad_backlen = good.clone();
+        bad_backlen[HEADER_SIZE + 4] = 9;
+        for payload in [&bad_count, &bad_backlen] {
+            assert!(decode(payload, false).is_ok());
+            assert!(decode(payload, true).is_err());
+        }
+
+        // The unknown count marker skips the count check
+        let mut unknown = good;
+        unknown[4..6].copy_from_slice(&COUNT_UNKNOWN.to_le_bytes());
+        assert_eq!(decode(&unknown, true).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_sanitize_payload_modes() {
+        assert_eq!(
+            SanitizePayload::parse("CLIENTS"),
+            Some(SanitizePayload::Clients)
+        );
+        assert_eq!(SanitizePayload::parse("maybe"), None);
+        assert!(!SanitizePayload::No.deep(true));
+        assert!(SanitizePayload::Yes.deep(false));
+        assert!(SanitizePayload::Clients.deep(true));
+        assert!(!SanitizePayload::Clients.deep(false));
+    }
+
+    #[test]
+    fn test_config_set_sanitize_dump_payload() {
+        use crate::redis::{Command, CommandExecutor, RespValue};
+
+        let mut executor = CommandExecutor::new();
+        assert_eq!(executor.sanitize_dump_payload(), SanitizePayload::No);
+        let set = |executor: &mut CommandExecutor, value: &str| {
+            executor.execute(&Command::ConfigSet(
+                "sanitize-dump-payload".to_string(),
+                value.to_string(),
+            ))
+        };
+        assert_eq!(set(&mut executor, "Clients"), RespValue::ok());
+        assert_eq!(executor.sanitize_dump_payload(), SanitizePayload::Clients);
+        assert_eq!(
+            set(&mut executor, "sometimes"),
+            RespValue::err(
+                "ERR CONFIG SET failed (possibly related to argument 'sanitize-dump-payload') - \
+                 argument(s) must be one of the following: no, yes, clients"
+            )
+        );
+        assert_eq!(executor.sanitize_dump_payload(), SanitizePayload::Clients);
+    }
+
+    /// Random payloads and corruptions of valid ones must decode or fail,
+    /// never panic or read out of bounds
+    #[test]
+    fn test_fuzz_corrupted_payloads() {
+        let mut rng = DeterministicRng::new(0x5A17);
+        for _ in 0..2000 {
+            let count = rng.gen_range(0, 12);
+            let items: Vec<Vec<u8>> = (0..count)
+                .map(|_| match rng.gen_range(0, 3) {
+                    0 => (rng.next_u64() as i64 >> rng.gen_range(0, 63))
+                        .to_string()
+                        .into_bytes(),
+                    1 => vec![b'v'; rng.gen_range(0, 80) as usize],
+                    _ => vec![b'w'; rng.gen_range(4000, 4200) as usize],
+                })
+                .collect();
+            let mut payload = encode(items.iter().map(Vec::as_slice));
+            let deep = rng.gen_bool(0.5);
+            let decoded: Vec<Vec<u8>> = decode(&payload, deep)
+                .unwrap()
+                .iter()
+                .map(ListpackEntry::to_bytes)
+                .collect();
+            assert_eq!(decoded, items);
+
+            // Corrupt: flip bytes, truncate, or extend, keeping the header
+            // consistent half of the time so the walk itself is exercised
+            match rng.gen_range(0, 3) {
+                0 => {
+                    for _ in 0..rng.gen_range(1, 4) {
+                        let at = rng.gen_range(0, payload.len() as u64) as usize;
+                        payload[at] = rng.next_u64() as u8;
+                    }
+                }
+                1 => payload.truncate(rng.gen_range(0, payload.len() as u64) as usize),
+                _ => {
+                    let at = rng.gen_range(HEADER_SIZE as u64, payload.len() as u64) as usize;
+                    payload.insert(at, rng.next_u64() as u8);
+                }
+            }
+            if rng.gen_bool(0.5) && payload.len() >= HEADER_SIZE {
+                let total = payload.len() as u32;
+                payload[0..4].copy_from_slice(&total.to_le_bytes());
+            }
+            let _ = decode(&payload, false);
+            let _ = decode(&payload, true);
+        }
+
+        // Pure noise
+        for _ in 0..2000 {
+            let len = rng.gen_range(0, 64) as usize;
+            let mut noise: Vec<u8> = (0..len).map(|_| rng.next_u64() as u8).collect();
+            if len > HEADER_SIZE {
+                noise[0..4].copy_from_slice(&(len as u32).to_le_bytes());
+                noise[len - 1] = EOF_BYTE;
+            }
+            let _ = decode(&noise, rng.gen_bool(0.5));
+        }
+    }
+}
@@ -7,21 +7,30 @@
 //! - `RedisSet`: Unordered set of unique strings
 //! - `RedisHash`: Hash table of field-value pairs
 //! - `RedisSortedSet`: Sorted set with scores (using skip list)
+//! - `listpack`: Compact collection encoding of RDB and DUMP payloads
+//! - `RedisStream`: Append-only log of entries with monotonic IDs
+//! - `ConsumerGroup`: Stream consumer group with its pending entries list
 //! - `SkipList`: Probabilistic data structure for sorted sets
 
 mod hash;
 mod list;
+pub mod listpack;
 mod sds;
 mod set;
 mod skiplist;
 mod sorted_set;
+mod stream;
+mod stream_group;
 mod value;
 
 // Re-export all public types
 pub use hash::RedisHash;
 pub use list::RedisList;
+pub use listpack::{ListpackEntry, SanitizePayload};
 pub use sds::SDS;
 pub use set::RedisSet;
 pub use skiplist::SkipList;
 pub use sorted_set::RedisSortedSet;
+pub use stream::{RedisStream, StreamFields, StreamId, StreamIdSpec};
+pub use stream_group::{ClaimOptions, PendingRange};
 pub use value::Value;
@@ -15,6 +15,16 @@ impl RedisSet {
         }
     }
 
+    /// Build a set of `members` (duplicates collapse), checking invariants
+    /// once instead of after every add
+    pub fn from_members(members: impl IntoIterator<Item = SDS>) -> Self {
+        let set = RedisSet {
+            members: members.into_iter().map(|m| m.to_string()).collect(),
+        };
+        set.verify_invariants();
+        set
+    }
+
     /// Verify all invariants hold for this set
     #[cfg(debug_assertions)]
     fn verify_invariants(&self) {
@@ -127,6 +137,11 @@ impl RedisSet {
         self.members.iter().map(|s| SDS::from_str(s)).collect()
     }
 
+    /// Iterate over the members without copying them
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|s| s.as_str())
+    }
+
     pub fn len(&self) -> usize {
         self.members.len()
     }
@@ -126,6 +126,13 @@ impl SkipList {
             .then_with(|| member1.cmp(member2))
     }
 
+    /// Whether two scores are the same. Exact equality covers the
+    /// infinities, whose difference is NaN.
+    #[inline]
+    pub(crate) fn same_score(a: f64, b: f64) -> bool {
+        a == b || (a - b).abs() < f64::EPSILON
+    }
+
     /// Insert a new element. Returns true if new element, false if updated.
     pub fn insert(&mut self, member: String, score: f64) -> bool {
         let mut update = [0usize; SKIPLIST_MAXLEVEL];
@@ -159,7 +166,7 @@ impl SkipList {
             let fwd_node = self.nodes[fwd].as_ref().expect("node must exist at valid index");
             if fwd_node.member == member {
                 // Update score - need to reposition if score changed
-                if (fwd_node.score - score).abs() > f64::EPSILON {
+                if !Self::same_score(fwd_node.score, score) {
                     // Remove and re-insert with new score
                     self.delete_node(fwd, &update);
                     self.insert_internal(member, score, &mut update, &mut rank);
@@ -350,7 +357,7 @@ impl SkipList {
         let node = self.nodes[x].as_ref().expect("node must exist at valid index");
         if let Some(fwd) = node.levels[0].forward {
             let fwd_node = self.nodes[fwd].as_ref().expect("node must exist at valid index");
-            if fwd_node.member == member && (fwd_node.score - score).abs() < f64::EPSILON {
+            if fwd_node.member == member && Self::same_score(fwd_node.score, score) {
                 self.delete_node(fwd, &update);
                 return true;
             }
@@ -386,7 +393,7 @@ impl SkipList {
         let node = self.nodes[x].as_ref().expect("node must exist at valid index");
         if let Some(fwd) = node.levels[0].forward {
             let fwd_node = self.nodes[fwd].as_ref().expect("node must exist at valid index");
-            if fwd_node.member == member && (fwd_node.score - score).abs() < f64::EPSILON {
+            if fwd_node.member == member && Self::same_score(fwd_node.score, score) {
                 return Some(rank);
             }
         }
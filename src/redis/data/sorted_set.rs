@@ -20,6 +20,25 @@ impl RedisSortedSet {
         }
     }
 
+    /// Build a sorted set of `pairs` (a repeated member keeps its last
+    /// score, as with ZADD), checking invariants once instead of after
+    /// every add
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (SDS, f64)>) -> Self {
+        let members: AHashMap<String, f64> = pairs
+            .into_iter()
+            .map(|(member, score)| (member.to_string(), score))
+            .collect();
+        let mut skiplist = SkipList::new();
+        for (member, &score) in &members {
+            skiplist.insert(member.clone(), score);
+        }
+        let zset = RedisSortedSet { members, skiplist };
+
+        #[cfg(debug_assertions)]
+        zset.verify_invariants();
+        zset
+    }
+
     /// Verify all invariants hold for this sorted set
     #[cfg(debug_assertions)]
     fn verify_invariants(&self) {
@@ -61,7 +80,7 @@ impl RedisSortedSet {
         match self.members.entry(key) {
             Entry::Occupied(mut entry) => {
                 let old_score = *entry.get();
-                if (old_score - score).abs() < f64::EPSILON {
+                if SkipList::same_score(old_score, score) {
                     return false; // Score unchanged
                 }
                 // Update score
@@ -320,12 +339,57 @@ impl RedisSortedSet {
         Ok(results)
     }
 
+    /// Parse a lex bound: `-`, `+`, `[member` (inclusive) or `(member`
+    fn parse_lex_bound(s: &str) -> Result<LexBound<'_>, String> {
+        match s.as_bytes().first() {
+            Some(b'-') if s.len() == 1 => Ok(LexBound::NegInf),
+            Some(b'+') if s.len() == 1 => Ok(LexBound::PosInf),
+            Some(b'[') => Ok(LexBound::Inclusive(&s[1..])),
+            Some(b'(') => Ok(LexBound::Exclusive(&s[1..])),
+            _ => Err("ERR min or max not valid string range item".to_string()),
+        }
+    }
+
+    /// ZRANGE BYLEX - members between lex bounds, in set order. Like Redis,
+    /// only meaningful when every member has the same score. O(n)
+    pub fn range_by_lex(&self, min: &str, max: &str) -> Result<Vec<(String, f64)>, String> {
+        let min = Self::parse_lex_bound(min)?;
+        let max = Self::parse_lex_bound(max)?;
+        let above_min = |member: &str| match min {
+            LexBound::NegInf => true,
+            LexBound::PosInf => false,
+            LexBound::Inclusive(m) => member >= m,
+            LexBound::Exclusive(m) => member > m,
+        };
+        let below_max = |member: &str| match max {
+            LexBound::NegInf => false,
+            LexBound::PosInf => true,
+            LexBound::Inclusive(m) => member <= m,
+            LexBound::Exclusive(m) => member < m,
+        };
+        Ok(self
+            .skiplist
+            .iter()
+            .filter(|(member, _)| above_min(member) && below_max(member))
+            .map(|(member, score)| (member.to_string(), score))
+            .collect())
+    }
+
     /// Iterate over member-score pairs in sorted order (for ZSCAN). O(n)
     pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
         self.skiplist.iter()
     }
 }
 
+/// One end of a BYLEX range
+#[derive(Clone, Copy)]
+enum LexBound<'a> {
+    NegInf,
+    PosInf,
+    Inclusive(&'a str),
+    Exclusive(&'a str),
+}
+
 impl PartialEq for RedisSortedSet {
     fn eq(&self, other: &Self) -> bool {
         self.members == other.members
@@ -0,0 +1,633 @@
+//! Redis Stream data structure
+//!
+//! An append-only log of field-value entries keyed by strictly increasing
+//! `<ms>-<seq>` IDs. `last_id` is remembered separately from the entries so
+//! that trimming a stream never lets an ID be handed out twice. Consumer
+//! groups (see `stream_group.rs`) hang off the stream and are dropped with it.
+
+use super::stream_group::{ClaimOptions, ConsumerGroup};
+use super::SDS;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Stream entry ID: milliseconds plus a sequence number within that millisecond
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+
+    /// Parse `<ms>-<seq>`, or `<ms>` with `missing_seq` as the sequence
+    pub fn parse(s: &str, missing_seq: u64) -> Option<StreamId> {
+        match s.split_once('-') {
+            Some((ms, seq)) => Some(StreamId {
+                ms: ms.parse().ok()?,
+                seq: seq.parse().ok()?,
+            }),
+            None => Some(StreamId {
+                ms: s.parse().ok()?,
+                seq: missing_seq,
+            }),
+        }
+    }
+
+    /// Parse an XRANGE/XREVRANGE bound: `-`, `+`, `<ms>[-<seq>]`, or any of
+    /// those after `(` for an exclusive bound. A bare `<ms>` covers the whole
+    /// millisecond, so it means `<ms>-0` as a lower bound and `<ms>-MAX` as an
+    /// upper one.
+    pub fn parse_bound(s: &str, lower: bool) -> Option<StreamId> {
+        let (exclusive, s) = match s.strip_prefix('(') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let id = match s {
+            "-" => StreamId::MIN,
+            "+" => StreamId::MAX,
+            _ => StreamId::parse(s, if lower { 0 } else { u64::MAX })?,
+        };
+        match (exclusive, lower) {
+            (false, _) => Some(id),
+            (true, true) => id.next(),
+            (true, false) => id.prev(),
+        }
+    }
+
+    /// The smallest ID greater than this one
+    pub fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self.ms.checked_add(1).map(|ms| StreamId { ms, seq: 0 }),
+        }
+    }
+
+    /// The largest ID smaller than this one
+    pub fn prev(self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId { ms: self.ms, seq }),
+            None => self
+                .ms
+                .checked_sub(1)
+                .map(|ms| StreamId { ms, seq: u64::MAX }),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// The ID argument of XADD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamIdSpec {
+    /// `*`: milliseconds from the clock, sequence generated
+    Auto,
+    /// `<ms>-*`: explicit milliseconds, sequence generated
+    AutoSeq(u64),
+    /// `<ms>-<seq>`
+    Explicit(StreamId),
+}
+
+impl StreamIdSpec {
+    /// Parse `*`, `<ms>-*`, `<ms>-<seq>` or `<ms>` (meaning `<ms>-0`)
+    pub fn parse(s: &str) -> Option<StreamIdSpec> {
+        if s == "*" {
+            return Some(StreamIdSpec::Auto);
+        }
+        if let Some(ms) = s.strip_suffix("-*") {
+            return ms.parse().ok().map(StreamIdSpec::AutoSeq);
+        }
+        StreamId::parse(s, 0).map(StreamIdSpec::Explicit)
+    }
+}
+
+/// Field-value pairs of one entry
+pub type StreamFields = Vec<(SDS, SDS)>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedisStream {
+    entries: BTreeMap<StreamId, StreamFields>,
+    /// Largest ID ever added, even if that entry has since been trimmed
+    last_id: StreamId,
+    /// Entries added over the stream's lifetime
+    entries_added: u64,
+    groups: BTreeMap<String, ConsumerGroup>,
+}
+
+/// Entries handed out by a group read or claim; `None` fields mean the entry
+/// is still pending but has been trimmed from the stream
+pub type DeliveredEntries<'a> = Vec<(StreamId, Option<&'a StreamFields>)>;
+
+/// XAUTOCLAIM outcome: the cursor to resume from (`0-0` when the PEL scan
+/// finished), the claimed entries, and pending IDs dropped because their
+/// entries no longer exist
+pub type AutoClaimed<'a> = (StreamId, Vec<(StreamId, &'a StreamFields)>, Vec<StreamId>);
+
+impl RedisStream {
+    pub fn new() -> Self {
+        RedisStream {
+            entries: BTreeMap::new(),
+            last_id: StreamId::MIN,
+            entries_added: 0,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    /// Verify all invariants hold for this stream
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        // Invariant 1: No entry is newer than last_id
+        if let Some((&newest, _)) = self.entries.iter().next_back() {
+            debug_assert!(
+                newest <= self.last_id,
+                "Invariant violated: entry {} is newer than last_id {}",
+                newest,
+                self.last_id
+            );
+        }
+
+        // Invariant 2: Every entry has at least one field
+        debug_assert!(
+            self.entries.values().all(|fields| !fields.is_empty()),
+            "Invariant violated: stream entry without fields"
+        );
+
+        // Invariant 3: Live entries were all counted as added
+        debug_assert!(
+            self.entries.len() as u64 <= self.entries_added,
+            "Invariant violated: more entries than were ever added"
+        );
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline(always)]
+    fn verify_invariants(&self) {}
+
+    /// Resolve an XADD ID against the stream's last ID and the clock.
+    ///
+    /// Returns the Redis error text when the ID would not be strictly greater
+    /// than every ID handed out so far.
+    pub fn next_id(&self, spec: StreamIdSpec, now_ms: u64) -> Result<StreamId, &'static str> {
+        const TOO_SMALL: &str =
+            "ERR The ID specified in XADD is equal or smaller than the target stream top item";
+
+        let id = match spec {
+            StreamIdSpec::Auto => {
+                if now_ms > self.last_id.ms {
+                    StreamId::new(now_ms, 0)
+                } else {
+                    self.last_id.next().ok_or(TOO_SMALL)?
+                }
+            }
+            StreamIdSpec::AutoSeq(ms) => {
+                if ms > self.last_id.ms {
+                    StreamId::new(ms, 0)
+                } else if ms == self.last_id.ms && self.last_id.seq < u64::MAX {
+                    StreamId::new(ms, self.last_id.seq + 1)
+                } else {
+                    return Err(TOO_SMALL);
+                }
+            }
+            StreamIdSpec::Explicit(id) => {
+                if id == StreamId::MIN {
+                    return Err("ERR The ID specified in XADD must be greater than 0-0");
+                }
+                if id <= self.last_id {
+                    return Err(TOO_SMALL);
+                }
+                id
+            }
+        };
+
+        debug_assert!(
+            id > self.last_id,
+            "Postcondition: generated ID must exceed last_id"
+        );
+        Ok(id)
+    }
+
+    /// Append an entry; `id` must come from `next_id`
+    pub fn add(&mut self, id: StreamId, fields: StreamFields) {
+        debug_assert!(id > self.last_id, "Precondition: IDs must increase");
+        debug_assert!(
+            !fields.is_empty(),
+            "Precondition: entry needs at least one field"
+        );
+
+        #[cfg(debug_assertions)]
+        let pre_len = self.entries.len();
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+
+        // TigerStyle: Postconditions
+        #[cfg(debug_assertions)]
+        debug_assert_eq!(
+            self.entries.len(),
+            pre_len + 1,
+            "Postcondition violated: add must grow the stream by one"
+        );
+        self.verify_invariants();
+    }
+
+    /// Drop the oldest entries until at most `maxlen` remain; returns how many
+    /// were removed
+    pub fn trim_maxlen(&mut self, maxlen: usize) -> usize {
+        let mut removed = 0;
+        while self.entries.len() > maxlen {
+            self.entries.pop_first();
+            removed += 1;
+        }
+
+        debug_assert!(
+            self.entries.len() <= maxlen,
+            "Postcondition violated: stream must not exceed maxlen after trim"
+        );
+        self.verify_invariants();
+        removed
+    }
+
+    /// Entries with `start <= id <= end`, oldest first
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    ) -> Vec<(StreamId, &StreamFields)> {
+        if start > end {
+            return Vec::new();
+        }
+        self.entries
+            .range(start..=end)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, fields))
+            .collect()
+    }
+
+    /// Entries with `start <= id <= end`, newest first
+    pub fn rev_range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+    ) -> Vec<(StreamId, &StreamFields)> {
+        if start > end {
+            return Vec::new();
+        }
+        self.entries
+            .range(start..=end)
+            .rev()
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, fields))
+            .collect()
+    }
+
+    /// Entries strictly newer than `id`, oldest first (XREAD)
+    pub fn read_after(&self, id: StreamId, count: Option<usize>) -> Vec<(StreamId, &StreamFields)> {
+        match id.next() {
+            Some(start) => self.range(start, StreamId::MAX, count),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub fn entries_added(&self) -> u64 {
+        self.entries_added
+    }
+
+    /// Set the ID generator and lifetime count of a stream being loaded (RDB,
+    /// RESTORE); false, changing nothing, if they are behind its entries
+    pub fn restore_history(&mut self, last_id: StreamId, entries_added: u64) -> bool {
+        let newest = self
+            .entries
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or(StreamId::MIN);
+        if last_id < newest || entries_added < self.entries.len() as u64 {
+            return false;
+        }
+        self.last_id = last_id;
+        self.entries_added = entries_added;
+        self.verify_invariants();
+        true
+    }
+
+    pub fn groups(&self) -> &BTreeMap<String, ConsumerGroup> {
+        &self.groups
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    /// Create a group delivering entries after `last_delivered`; returns
+    /// false if the name is taken
+    pub fn create_group(&mut self, name: &str, last_delivered: StreamId) -> bool {
+        if self.groups.contains_key(name) {
+            return false;
+        }
+        self.groups
+            .insert(name.to_string(), ConsumerGroup::new(last_delivered));
+        true
+    }
+
+    pub fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// XREADGROUP for one stream. `after: None` is `>`: new entries, which
+    /// move the group's last-delivered ID and (unless `noack`) join the PEL.
+    /// An explicit ID re-reads the consumer's own pending entries after it.
+    /// Returns `None` if the group does not exist.
+    pub fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        after: Option<StreamId>,
+        count: Option<usize>,
+        noack: bool,
+        now_ms: u64,
+    ) -> Option<DeliveredEntries<'_>> {
+        let RedisStream { entries, groups, .. } = self;
+        let g = groups.get_mut(group)?;
+        g.touch_consumer(consumer, now_ms);
+
+        let delivered: DeliveredEntries<'_> = match after {
+            None => {
+                let fresh: Vec<(StreamId, &StreamFields)> = match g.last_delivered().next() {
+                    Some(start) => entries
+                        .range(start..)
+                        .take(count.unwrap_or(usize::MAX))
+                        .map(|(id, fields)| (*id, fields))
+                        .collect(),
+                    None => Vec::new(),
+                };
+                for (id, _) in &fresh {
+                    g.set_last_delivered(*id);
+                    if !noack {
+                        g.deliver(*id, consumer, now_ms, true);
+                    }
+                }
+                fresh
+                    .into_iter()
+                    .map(|(id, fields)| (id, Some(fields)))
+                    .collect()
+            }
+            Some(after) => g
+                .consumer_pending_after(consumer, after, count)
+                .into_iter()
+                .map(|id| {
+                    g.deliver(id, consumer, now_ms, true);
+                    (id, entries.get(&id))
+                })
+                .collect(),
+        };
+
+        debug_assert!(
+            count.is_none_or(|c| delivered.len() <= c),
+            "Postcondition: XREADGROUP must honor COUNT"
+        );
+        Some(delivered)
+    }
+
+    /// XCLAIM: move pending `ids` idle for at least `min_idle_ms` to
+    /// `consumer`. Pending IDs whose entries were trimmed are dropped from the
+    /// PEL. Returns `None` if the group does not exist.
+    pub fn claim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        ids: &[StreamId],
+        opts: ClaimOptions,
+        now_ms: u64,
+    ) -> Option<Vec<(StreamId, &StreamFields)>> {
+        let RedisStream { entries, groups, .. } = self;
+        let g = groups.get_mut(group)?;
+        g.touch_consumer(consumer, now_ms);
+
+        let mut claimed = Vec::new();
+        for &id in ids {
+            let Some(fields) = entries.get(&id) else {
+                g.ack(id);
+                continue;
+            };
+            match g.pending().get(&id) {
+                Some(entry) => {
+                    let idle = now_ms.saturating_sub(entry.delivery_ms);
+                    if idle < min_idle_ms {
+                        continue;
+                    }
+                }
+                None if opts.force => {}
+                None => continue,
+            }
+
+            g.deliver(id, consumer, now_ms, !opts.justid);
+            let delivery_ms = match (opts.time_ms, opts.idle_ms) {
+                (Some(time), _) => time,
+                (None, Some(idle)) => now_ms.saturating_sub(idle),
+                (None, None) => now_ms,
+            };
+            g.set_delivery(id, delivery_ms, opts.retry_count);
+            claimed.push((id, fields));
+        }
+        Some(claimed)
+    }
+
+    /// XAUTOCLAIM: scan the PEL from `start`, claiming up to `count` entries
+    /// idle for at least `min_idle_ms`. Like Redis, at most `count * 10` PEL
+    /// entries are inspected per call. Returns `None` if the group does not
+    /// exist.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autoclaim(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        start: StreamId,
+        count: usize,
+        justid: bool,
+        now_ms: u64,
+    ) -> Option<AutoClaimed<'_>> {
+        debug_assert!(count > 0, "Precondition: XAUTOCLAIM COUNT must be positive");
+
+        let RedisStream { entries, groups, .. } = self;
+        let g = groups.get_mut(group)?;
+        g.touch_consumer(consumer, now_ms);
+
+        let attempts = count.saturating_mul(10);
+        let candidates: Vec<(StreamId, u64)> = g
+            .pending()
+            .range(start..)
+            .take(attempts + 1)
+            .map(|(id, entry)| (*id, entry.delivery_ms))
+            .collect();
+
+        let mut claimed = Vec::new();
+        let mut deleted = Vec::new();
+        let mut next = StreamId::MIN;
+        for (i, &(id, delivery_ms)) in candidates.iter().enumerate() {
+            if i == attempts || claimed.len() == count {
+                next = id;
+                break;
+            }
+            match entries.get(&id) {
+                None => {
+                    g.ack(id);
+                    deleted.push(id);
+                }
+                Some(fields) if now_ms.saturating_sub(delivery_ms) >= min_idle_ms => {
+                    g.deliver(id, consumer, now_ms, !justid);
+                    claimed.push((id, fields));
+                }
+                Some(_) => {}
+            }
+        }
+
+        debug_assert!(
+            claimed.len() <= count,
+            "Postcondition: XAUTOCLAIM must honor COUNT"
+        );
+        Some((next, claimed, deleted))
+    }
+}
+
+impl Default for RedisStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(v: &str) -> StreamFields {
+        vec![(SDS::from_str("f"), SDS::from_str(v))]
+    }
+
+    #[test]
+    fn test_auto_ids_are_monotonic() {
+        let mut stream = RedisStream::new();
+        let a = stream.next_id(StreamIdSpec::Auto, 1000).unwrap();
+        stream.add(a, fields("a"));
+        // Same millisecond, and a clock that went backwards
+        let b = stream.next_id(StreamIdSpec::Auto, 1000).unwrap();
+        stream.add(b, fields("b"));
+        let c = stream.next_id(StreamIdSpec::Auto, 900).unwrap();
+        stream.add(c, fields("c"));
+
+        assert_eq!(a, StreamId::new(1000, 0));
+        assert_eq!(b, StreamId::new(1000, 1));
+        assert_eq!(c, StreamId::new(1000, 2));
+        assert_eq!(stream.len(), 3);
+    }
+
+    #[test]
+    fn test_explicit_ids_must_increase() {
+        let mut stream = RedisStream::new();
+        assert!(stream
+            .next_id(StreamIdSpec::Explicit(StreamId::MIN), 0)
+            .is_err());
+        let id = stream
+            .next_id(StreamIdSpec::Explicit(StreamId::new(5, 1)), 0)
+            .unwrap();
+        stream.add(id, fields("x"));
+        assert!(stream
+            .next_id(StreamIdSpec::Explicit(StreamId::new(5, 1)), 0)
+            .is_err());
+        assert_eq!(
+            stream.next_id(StreamIdSpec::AutoSeq(5), 0),
+            Ok(StreamId::new(5, 2))
+        );
+        assert!(stream.next_id(StreamIdSpec::AutoSeq(4), 0).is_err());
+    }
+
+    #[test]
+    fn test_range_and_trim() {
+        let mut stream = RedisStream::new();
+        for ms in 1..=5 {
+            stream.add(StreamId::new(ms, 0), fields(&ms.to_string()));
+        }
+
+        let ids = |entries: Vec<(StreamId, &StreamFields)>| -> Vec<u64> {
+            entries.into_iter().map(|(id, _)| id.ms).collect()
+        };
+        assert_eq!(
+            ids(stream.range(StreamId::new(2, 0), StreamId::new(4, 0), None)),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            ids(stream.rev_range(StreamId::MIN, StreamId::MAX, Some(2))),
+            vec![5, 4]
+        );
+        assert_eq!(
+            ids(stream.read_after(StreamId::new(3, 0), None)),
+            vec![4, 5]
+        );
+
+        assert_eq!(stream.trim_maxlen(2), 3);
+        assert_eq!(
+            ids(stream.range(StreamId::MIN, StreamId::MAX, None)),
+            vec![4, 5]
+        );
+        // Trimming never rewinds the ID generator
+        assert_eq!(stream.last_id(), StreamId::new(5, 0));
+        assert_eq!(stream.entries_added(), 5);
+    }
+
+    #[test]
+    fn test_parse_ids() {
+        assert_eq!(StreamId::parse("5-3", 0), Some(StreamId::new(5, 3)));
+        assert_eq!(
+            StreamId::parse("5", u64::MAX),
+            Some(StreamId::new(5, u64::MAX))
+        );
+        assert_eq!(StreamId::parse("5-x", 0), None);
+        assert_eq!(StreamId::new(5, 0).prev(), Some(StreamId::new(4, u64::MAX)));
+        assert_eq!(StreamId::MAX.next(), None);
+
+        assert_eq!(StreamId::parse_bound("-", true), Some(StreamId::MIN));
+        assert_eq!(
+            StreamId::parse_bound("7", false),
+            Some(StreamId::new(7, u64::MAX))
+        );
+        assert_eq!(
+            StreamId::parse_bound("(7-2", true),
+            Some(StreamId::new(7, 3))
+        );
+        assert_eq!(StreamId::parse_bound("(-", false), None);
+        assert_eq!(StreamIdSpec::parse("*"), Some(StreamIdSpec::Auto));
+        assert_eq!(StreamIdSpec::parse("9-*"), Some(StreamIdSpec::AutoSeq(9)));
+        assert_eq!(
+            StreamIdSpec::parse("9"),
+            Some(StreamIdSpec::Explicit(StreamId::new(9, 0)))
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Stream consumer groups
+//!
+//! A group remembers the last ID it delivered and a pending entries list
+//! (PEL): every entry handed to a consumer that has not been acknowledged
+//! yet, with its owner, last delivery time and delivery count. Each consumer
+//! also indexes its own pending IDs so history reads and DELCONSUMER do not
+//! scan the whole group PEL.
+
+use super::stream::StreamId;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// XCLAIM modifiers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClaimOptions {
+    /// Set the idle time of claimed entries (IDLE ms)
+    pub idle_ms: Option<u64>,
+    /// Set the delivery time of claimed entries (TIME unix-ms)
+    pub time_ms: Option<u64>,
+    /// Set the delivery count of claimed entries (RETRYCOUNT n)
+    pub retry_count: Option<u64>,
+    /// Create PEL entries for IDs that exist in the stream but are not pending
+    pub force: bool,
+    /// Reply with IDs only and leave delivery counts alone
+    pub justid: bool,
+}
+
+/// Extended XPENDING form: `[IDLE ms] start end count [consumer]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingRange {
+    pub min_idle_ms: Option<u64>,
+    pub start: StreamId,
+    pub end: StreamId,
+    pub count: usize,
+    pub consumer: Option<String>,
+}
+
+/// One unacknowledged delivery
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    /// Unix milliseconds of the last delivery
+    pub delivery_ms: u64,
+    pub delivery_count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Consumer {
+    /// Unix milliseconds of the last interaction
+    pub seen_ms: u64,
+    pending: BTreeSet<StreamId>,
+}
+
+impl Consumer {
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsumerGroup {
+    last_delivered: StreamId,
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: BTreeMap<String, Consumer>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_delivered: StreamId) -> Self {
+        ConsumerGroup {
+            last_delivered,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    /// Verify all invariants hold for this group
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        // Invariant 1: Every pending entry is indexed by the consumer owning it
+        for (id, entry) in &self.pending {
+            debug_assert!(
+                self.consumers
+                    .get(&entry.consumer)
+                    .is_some_and(|c| c.pending.contains(id)),
+                "Invariant violated: pending {} not indexed under '{}'",
+                id,
+                entry.consumer
+            );
+        }
+
+        // Invariant 2: Consumer indexes hold nothing beyond the group PEL
+        let indexed: usize = self.consumers.values().map(|c| c.pending.len()).sum();
+        debug_assert_eq!(
+            indexed,
+            self.pending.len(),
+            "Invariant violated: consumer PEL indexes diverged from group PEL"
+        );
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline(always)]
+    fn verify_invariants(&self) {}
+
+    pub fn last_delivered(&self) -> StreamId {
+        self.last_delivered
+    }
+
+    pub fn set_last_delivered(&mut self, id: StreamId) {
+        self.last_delivered = id;
+    }
+
+    pub fn pending(&self) -> &BTreeMap<StreamId, PendingEntry> {
+        &self.pending
+    }
+
+    pub fn consumers(&self) -> &BTreeMap<String, Consumer> {
+        &self.consumers
+    }
+
+    /// Create `name` if missing (and mark it seen); returns true if created
+    pub fn touch_consumer(&mut self, name: &str, now_ms: u64) -> bool {
+        match self.consumers.get_mut(name) {
+            Some(consumer) => {
+                consumer.seen_ms = now_ms;
+                false
+            }
+            None => {
+                self.consumers.insert(
+                    name.to_string(),
+                    Consumer {
+                        seen_ms: now_ms,
+                        pending: BTreeSet::new(),
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    /// Remove a consumer and its pending entries; returns how many pending
+    /// entries it owned, or `None` if it did not exist
+    pub fn delete_consumer(&mut self, name: &str) -> Option<usize> {
+        let consumer = self.consumers.remove(name)?;
+        for id in &consumer.pending {
+            self.pending.remove(id);
+        }
+        self.verify_invariants();
+        Some(consumer.pending.len())
+    }
+
+    /// Record a delivery of `id` to `consumer`. A new PEL entry starts at one
+    /// delivery; an existing one changes owner and, when `count_delivery`,
+    /// gains a delivery.
+    pub fn deliver(&mut self, id: StreamId, consumer: &str, now_ms: u64, count_delivery: bool) {
+        self.touch_consumer(consumer, now_ms);
+
+        let previous = self.pending.get(&id).map(|e| e.consumer.clone());
+        if let Some(owner) = previous.as_deref().filter(|owner| *owner != consumer) {
+            if let Some(c) = self.consumers.get_mut(owner) {
+                c.pending.remove(&id);
+            }
+        }
+
+        let entry = self.pending.entry(id).or_insert_with(|| PendingEntry {
+            consumer: consumer.to_string(),
+            delivery_ms: now_ms,
+            delivery_count: 0,
+        });
+        entry.consumer = consumer.to_string();
+        entry.delivery_ms = now_ms;
+        if count_delivery || previous.is_none() {
+            entry.delivery_count += 1;
+        }
+        if let Some(c) = self.consumers.get_mut(consumer) {
+            c.pending.insert(id);
+        }
+
+        debug_assert_eq!(
+            self.pending.get(&id).map(|e| e.consumer.as_str()),
+            Some(consumer),
+            "Postcondition: delivered entry must belong to the consumer"
+        );
+        self.verify_invariants();
+    }
+
+    /// Acknowledge `id`; returns true if it was pending
+    pub fn ack(&mut self, id: StreamId) -> bool {
+        let Some(entry) = self.pending.remove(&id) else {
+            return false;
+        };
+        if let Some(c) = self.consumers.get_mut(&entry.consumer) {
+            c.pending.remove(&id);
+        }
+        self.verify_invariants();
+        true
+    }
+
+    /// Pending IDs of `consumer` strictly after `after`, oldest first
+    pub fn consumer_pending_after(
+        &self,
+        consumer: &str,
+        after: StreamId,
+        count: Option<usize>,
+    ) -> Vec<StreamId> {
+        let Some(start) = after.next() else {
+            return Vec::new();
+        };
+        match self.consumers.get(consumer) {
+            Some(c) => c
+                .pending
+                .range(start..)
+                .take(count.unwrap_or(usize::MAX))
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Explicitly set an entry's delivery metadata (XCLAIM IDLE/TIME/RETRYCOUNT)
+    pub fn set_delivery(&mut self, id: StreamId, delivery_ms: u64, delivery_count: Option<u64>) {
+        if let Some(entry) = self.pending.get_mut(&id) {
+            entry.delivery_ms = delivery_ms;
+            if let Some(count) = delivery_count {
+                entry.delivery_count = count;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deliver_ack_and_reassign() {
+        let mut group = ConsumerGroup::new(StreamId::MIN);
+        let id = StreamId::new(1, 0);
+
+        group.deliver(id, "alice", 100, true);
+        assert_eq!(group.pending()[&id].delivery_count, 1);
+
+        // Claiming moves ownership and counts another delivery
+        group.deliver(id, "bob", 200, true);
+        let entry = &group.pending()[&id];
+        assert_eq!(entry.consumer, "bob");
+        assert_eq!(entry.delivery_count, 2);
+        assert_eq!(group.consumers()["alice"].pending_count(), 0);
+        assert_eq!(group.consumers()["bob"].pending_count(), 1);
+
+        assert!(group.ack(id));
+        assert!(!group.ack(id));
+        assert!(group.pending().is_empty());
+    }
+
+    #[test]
+    fn test_delete_consumer_drops_its_pending() {
+        let mut group = ConsumerGroup::new(StreamId::MIN);
+        group.deliver(StreamId::new(1, 0), "alice", 0, true);
+        group.deliver(StreamId::new(2, 0), "alice", 0, true);
+        group.deliver(StreamId::new(3, 0), "bob", 0, true);
+
+        assert_eq!(group.delete_consumer("alice"), Some(2));
+        assert_eq!(group.delete_consumer("alice"), None);
+        assert_eq!(group.pending().len(), 1);
+        assert_eq!(
+            group.consumer_pending_after("bob", StreamId::MIN, None),
+            vec![StreamId::new(3, 0)]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Redis Value type enum
 
-use super::{RedisHash, RedisList, RedisSet, RedisSortedSet, SDS};
+use super::{RedisHash, RedisList, RedisSet, RedisSortedSet, RedisStream, SDS};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
@@ -9,6 +9,7 @@ pub enum Value {
     Set(RedisSet),
     Hash(RedisHash),
     SortedSet(RedisSortedSet),
+    Stream(RedisStream),
     Null,
 }
 
@@ -47,4 +48,11 @@ impl Value {
             _ => None,
         }
     }
+
+    pub fn as_stream(&self) -> Option<&RedisStream> {
+        match self {
+            Value::Stream(s) => Some(s),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,33 @@
+//! Commands defined with `declare_commands!`.
+//!
+//! Each entry is the whole definition of a fixed-argument command; see
+//! `command_macro.rs` for the generated code and the argument kinds.
+
+use super::command_macro::declare_commands;
+
+declare_commands! {
+    /// SETNX key value
+    SetNx("setnx", write) { key: Key, value: Sds } => execute_setnx;
+    /// EXPIREAT key unix-time-seconds
+    ExpireAt("expireat", write) { key: Key, timestamp: Int } => execute_expireat;
+    /// PEXPIREAT key unix-time-milliseconds
+    PExpireAt("pexpireat", write) { key: Key, timestamp_millis: Int } => execute_pexpireat;
+    /// EXPIRETIME key
+    ExpireTime("expiretime", readonly) { key: Key } => execute_expiretime;
+    /// PEXPIRETIME key
+    PExpireTime("pexpiretime", readonly) { key: Key } => execute_pexpiretime;
+    /// DUMP key
+    Dump("dump", readonly) { key: Key } => execute_dump;
+    /// HKEYS key
+    HKeys("hkeys", readonly) { key: Key } => execute_hkeys;
+    /// HVALS key
+    HVals("hvals", readonly) { key: Key } => execute_hvals;
+    /// HSETNX key field value
+    HSetNx("hsetnx", write) { key: Key, field: Sds, value: Sds } => execute_hsetnx;
+    /// HSTRLEN key field
+    HStrLen("hstrlen", readonly) { key: Key, field: Sds } => execute_hstrlen;
+    /// ZRANK key member
+    ZRank("zrank", readonly) { key: Key, member: Sds } => execute_zrank;
+    /// XLEN key
+    XLen("xlen", readonly) { key: Key } => execute_xlen;
+}
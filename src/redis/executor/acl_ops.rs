@@ -156,43 +156,22 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_acl_genpass(&self, bits: Option<u32>) -> RespValue {
-        use std::time::{SystemTime, UNIX_EPOCH};
-
-        let bits = bits.unwrap_or(256).min(1024);
-
-        // TigerStyle: Preconditions
-        debug_assert!(
-            bits > 0 && bits <= 1024,
-            "Precondition violated: ACL GENPASS bits must be in (0, 1024]"
-        );
-
-        let bytes = (bits as usize).div_ceil(8);
-        let expected_hex_len = bytes * 2;
-
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("System time must be after UNIX epoch")
-            .as_nanos();
-
-        let mut result = String::with_capacity(expected_hex_len);
-        let mut state = seed;
-        for _ in 0..bytes {
-            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
-            let byte = ((state >> 33) & 0xFF) as u8;
-            result.push_str(&format!("{:02x}", byte));
-        }
-
-        // TigerStyle: Postconditions
-        debug_assert_eq!(
-            result.len(),
-            expected_hex_len,
-            "Postcondition violated: ACL GENPASS output length must be 2*ceil(bits/8)"
-        );
-        debug_assert!(
-            result.chars().all(|c| c.is_ascii_hexdigit()),
-            "Postcondition violated: ACL GENPASS output must be valid hex"
-        );
+    pub(super) fn execute_acl_genpass(&mut self, bits: Option<u32>) -> RespValue {
+        use crate::security::password::{generate_password, genpass_bits};
+
+        let bits = match genpass_bits(bits) {
+            Ok(b) => b,
+            Err(e) => return RespValue::err(e),
+        };
+
+        // Replayable when a simulation seeded the RNG; from the OS otherwise,
+        // since a password must not be predictable from the clock
+        let result = if self.rng_seeded {
+            let mut rng = crate::io::simulation::SimulatedRng::new(self.rng.next_u64());
+            generate_password(&mut rng, bits)
+        } else {
+            generate_password(&mut crate::io::production::ProductionRng::new(), bits)
+        };
 
         RespValue::BulkString(Some(result.into_bytes()))
     }
@@ -1,17 +1,22 @@
 //! Bitmap command implementations for CommandExecutor.
 //!
-//! Handles: SETBIT, GETBIT
+//! Handles: SETBIT, GETBIT, BITCOUNT, BITPOS, BITOP
 //!
 //! Redis bitmaps are not a separate data type — they operate on string values
 //! at the bit level. Bit ordering is big-endian within each byte:
 //! bit 0 = MSB (0x80), bit 7 = LSB (0x01).
+//!
+//! BITCOUNT and BITPOS ranges use GETRANGE's index rules (negative counts
+//! from the end, clamped to the string), in bytes by default or in bits
+//! with `BIT`.
 
 use super::CommandExecutor;
+use crate::redis::command::{BitOperation, BitRange, BitUnit};
 use crate::redis::data::{Value, SDS};
 use crate::redis::resp::RespValue;
 
 /// Maximum bit offset: 512MB * 8 bits = 2^32 bits
-const MAX_BIT_OFFSET: u64 = 512 * 1024 * 1024 * 8;
+pub(super) const MAX_BIT_OFFSET: u64 = 512 * 1024 * 1024 * 8;
 
 impl CommandExecutor {
     pub(super) fn execute_setbit(&mut self, key: &str, offset: u64, value: u8) -> RespValue {
@@ -123,4 +128,182 @@ impl CommandExecutor {
             ),
         }
     }
+
+    pub(super) fn execute_bitcount(&mut self, key: &str, range: Option<BitRange>) -> RespValue {
+        let bytes = match self.get_value(key) {
+            None => return RespValue::Integer(0),
+            Some(Value::String(s)) => s.as_bytes(),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+        };
+
+        let range = range.unwrap_or(BitRange {
+            start: 0,
+            end: Some(-1),
+            unit: BitUnit::Byte,
+        });
+        let Some((first, last)) = bit_span(bytes.len(), range) else {
+            return RespValue::Integer(0);
+        };
+
+        let count: u64 = (first / 8..=last / 8)
+            .map(|i| (bytes[i] & span_mask(i, first, last)).count_ones() as u64)
+            .sum();
+
+        debug_assert!(
+            count <= (last - first + 1) as u64,
+            "Postcondition: BITCOUNT {} exceeds range width",
+            count
+        );
+        RespValue::Integer(count as i64)
+    }
+
+    pub(super) fn execute_bitpos(
+        &mut self,
+        key: &str,
+        bit: u8,
+        range: Option<BitRange>,
+    ) -> RespValue {
+        debug_assert!(bit <= 1, "Precondition: bit value must be 0 or 1");
+
+        let bytes = match self.get_value(key) {
+            None => return RespValue::Integer(if bit == 1 { -1 } else { 0 }),
+            Some(Value::String(s)) => s.as_bytes(),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+        };
+
+        let range = range.unwrap_or(BitRange {
+            start: 0,
+            end: None,
+            unit: BitUnit::Byte,
+        });
+        let Some((first, last)) = bit_span(bytes.len(), range) else {
+            return RespValue::Integer(-1);
+        };
+
+        for i in first / 8..=last / 8 {
+            let candidates = if bit == 1 { bytes[i] } else { !bytes[i] };
+            let hits = candidates & span_mask(i, first, last);
+            if hits != 0 {
+                let pos = i * 8 + hits.leading_zeros() as usize;
+                debug_assert!(
+                    (first..=last).contains(&pos),
+                    "Postcondition: BITPOS {} outside range",
+                    pos
+                );
+                return RespValue::Integer(pos as i64);
+            }
+        }
+
+        // Without an explicit end the string is treated as padded with
+        // zeros, so the first clear bit is the one just past it
+        if bit == 0 && range.end.is_none() {
+            RespValue::Integer(((last / 8 + 1) * 8) as i64)
+        } else {
+            RespValue::Integer(-1)
+        }
+    }
+
+    pub(super) fn execute_bitop(
+        &mut self,
+        op: BitOperation,
+        dest: &str,
+        keys: &[String],
+    ) -> RespValue {
+        debug_assert!(!keys.is_empty(), "Precondition: BITOP needs a source key");
+        debug_assert!(
+            op != BitOperation::Not || keys.len() == 1,
+            "Precondition: BITOP NOT takes exactly one source"
+        );
+
+        let mut sources: Vec<Vec<u8>> = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.get_value(key) {
+                None => sources.push(Vec::new()),
+                Some(Value::String(s)) => sources.push(s.as_bytes().to_vec()),
+                Some(_) => {
+                    return RespValue::err(
+                        "WRONGTYPE Operation against a key holding the wrong kind of value",
+                    )
+                }
+            }
+        }
+
+        // Shorter strings are zero-padded to the longest source
+        let len = sources.iter().map(Vec::len).max().unwrap_or(0);
+        let byte_at = |src: &Vec<u8>, i: usize| src.get(i).copied().unwrap_or(0);
+        let result: Vec<u8> = (0..len)
+            .map(|i| {
+                let mut bytes = sources.iter().map(|src| byte_at(src, i));
+                let first = bytes.next().unwrap_or(0);
+                match op {
+                    BitOperation::And => bytes.fold(first, |acc, b| acc & b),
+                    BitOperation::Or => bytes.fold(first, |acc, b| acc | b),
+                    BitOperation::Xor => bytes.fold(first, |acc, b| acc ^ b),
+                    BitOperation::Not => !first,
+                }
+            })
+            .collect();
+
+        // Like SORT ... STORE: an empty result deletes the destination
+        self.expirations.remove(dest);
+        if result.is_empty() {
+            self.data.remove(dest);
+        } else {
+            self.data
+                .insert(dest.to_string(), Value::String(SDS::new(result)));
+        }
+
+        debug_assert!(
+            len == 0 || matches!(self.data.get(dest), Some(Value::String(s)) if s.len() == len),
+            "Postcondition: BITOP destination must hold {} bytes",
+            len
+        );
+        RespValue::Integer(len as i64)
+    }
+}
+
+/// Bit positions `[first, last]` a BITCOUNT/BITPOS range covers in a string
+/// of `len` bytes, or `None` if the range is empty
+fn bit_span(len: usize, range: BitRange) -> Option<(usize, usize)> {
+    let total = match range.unit {
+        BitUnit::Byte => len as i64,
+        BitUnit::Bit => len as i64 * 8,
+    };
+    let resolve = |index: i64| if index < 0 { (total + index).max(0) } else { index };
+    let start = resolve(range.start);
+    let end = resolve(range.end.unwrap_or(-1)).min(total - 1);
+    if total == 0 || start > end {
+        return None;
+    }
+
+    let (first, last) = match range.unit {
+        BitUnit::Byte => (start * 8, end * 8 + 7),
+        BitUnit::Bit => (start, end),
+    };
+    debug_assert!(
+        last < len as i64 * 8,
+        "Postcondition: span end {} past string end",
+        last
+    );
+    Some((first as usize, last as usize))
+}
+
+/// Bits of byte `i` that fall inside the span `[first, last]`
+fn span_mask(i: usize, first: usize, last: usize) -> u8 {
+    let mut mask = 0xFFu8;
+    if i == first / 8 {
+        mask &= 0xFF >> (first % 8);
+    }
+    if i == last / 8 {
+        mask &= 0xFF << (7 - last % 8);
+    }
+    mask
 }
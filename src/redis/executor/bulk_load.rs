@@ -0,0 +1,103 @@
+//! Bulk loading for initial data import.
+//!
+//! `CommandExecutor::bulk_load` installs finished values - built with
+//! `RedisList::from_items`, `RedisSortedSet::from_pairs` and friends rather
+//! than one LPUSH or ZADD per element - straight into the keyspace. It skips
+//! what a client command pays for: parsing, transaction queueing, maxmemory
+//! checks, blocked-client wakeups, and the per-mutation invariant checks of
+//! the data structures and the executor, which run once per load instead.
+//!
+//! An entry replaces whatever the key held, TTL included, like RESTORE with
+//! REPLACE. Its TTL is in milliseconds from the executor's current time; an
+//! entry whose TTL is zero, or whose collection is empty, is skipped since
+//! Redis would never hold it.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Keyspace statistics and field-TTL tracking stay in sync, as after any
+//!   command
+//! - `loaded + skipped` equals the number of entries offered
+
+use super::keyspace_stats::KeyFootprint;
+use super::CommandExecutor;
+use crate::redis::data::Value;
+use crate::simulator::VirtualTime;
+
+/// One key for `bulk_load`: name, finished value, TTL in milliseconds
+pub type BulkEntry = (String, Value, Option<u64>);
+
+/// What a `bulk_load` call did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkLoadReport {
+    /// Entries now in the keyspace
+    pub loaded: usize,
+    /// Loaded entries that overwrote an existing key
+    pub replaced: usize,
+    /// Entries dropped: zero TTL or an empty collection
+    pub skipped: usize,
+}
+
+impl BulkLoadReport {
+    /// Fold another report into this one (one report per shard)
+    pub fn merge(&mut self, other: BulkLoadReport) {
+        self.loaded = self.loaded.saturating_add(other.loaded);
+        self.replaced = self.replaced.saturating_add(other.replaced);
+        self.skipped = self.skipped.saturating_add(other.skipped);
+    }
+}
+
+/// Whether Redis could hold `value` at all
+fn is_loadable(value: &Value) -> bool {
+    match value {
+        Value::List(l) => !l.is_empty(),
+        Value::Set(s) => !s.is_empty(),
+        Value::Hash(h) => !h.is_empty(),
+        Value::SortedSet(z) => !z.is_empty(),
+        Value::String(_) | Value::Stream(_) => true,
+        Value::Null => false,
+    }
+}
+
+impl CommandExecutor {
+    /// Install `entries` directly, bypassing per-command overhead. See the
+    /// module docs for what is skipped and how TTLs are read.
+    pub fn bulk_load(&mut self, entries: impl IntoIterator<Item = BulkEntry>) -> BulkLoadReport {
+        let mut report = BulkLoadReport::default();
+        let now_ms = self.current_time.as_millis();
+
+        for (key, value, ttl_ms) in entries {
+            if ttl_ms == Some(0) || !is_loadable(&value) {
+                report.skipped += 1;
+                continue;
+            }
+            let before = self.data.get(&key).and_then(KeyFootprint::of);
+            if before.is_some() {
+                report.replaced += 1;
+            }
+            if matches!(&value, Value::Hash(h) if h.has_field_expiries()) {
+                self.field_ttl_keys.insert(key.clone());
+            } else {
+                self.field_ttl_keys.remove(&key);
+            }
+            match ttl_ms {
+                Some(ttl) => {
+                    let deadline = VirtualTime::from_millis(now_ms.saturating_add(ttl));
+                    self.expirations.insert(key.clone(), deadline);
+                }
+                None => {
+                    self.expirations.remove(&key);
+                }
+            }
+            self.keyspace_stats.apply(before, KeyFootprint::of(&value));
+            self.data.insert(key, value);
+            report.loaded += 1;
+        }
+
+        debug_assert!(
+            report.replaced <= report.loaded,
+            "Postcondition: only loaded entries can replace a key"
+        );
+        self.verify_invariants();
+        report
+    }
+}
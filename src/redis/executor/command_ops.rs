@@ -0,0 +1,124 @@
+//! COMMAND introspection: COMMAND, COUNT, INFO, DOCS and GETKEYS.
+//!
+//! Every reply is built from the command table (`command_table.rs`), so it
+//! describes exactly what the parser accepts. COMMAND INFO replies the
+//! Redis 7 ten-element entry: name, arity, flags, first key, last key, key
+//! step, ACL categories, then tips, key specs and subcommands, which this
+//! server does not track and replies empty. An unknown name replies nil.
+//!
+//! COMMAND DOCS replies each known name with its documentation as name and
+//! value pairs; only the group is known. Unknown names are left out.
+//!
+//! COMMAND GETKEYS parses its arguments as a command and replies the keys
+//! that command touches, so commands whose keys move (EVAL, ZUNION, SORT)
+//! answer for the given call rather than from the table's key positions.
+//!
+//! # TigerStyle Invariants
+//!
+//! - COMMAND COUNT equals the number of entries COMMAND replies
+//! - GETKEYS never replies an empty array
+
+use super::CommandExecutor;
+use crate::redis::command_table::CommandSpec;
+use crate::redis::resp::RespValue;
+use crate::redis::Command;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+fn statuses<'a>(words: impl Iterator<Item = &'a str>) -> RespValue {
+    RespValue::Array(Some(
+        words
+            .map(|w| RespValue::SimpleString(w.to_string().into()))
+            .collect(),
+    ))
+}
+
+/// The COMMAND INFO entry for one command
+fn info_entry(spec: &CommandSpec) -> RespValue {
+    let empty = || RespValue::Array(Some(Vec::new()));
+    RespValue::Array(Some(vec![
+        bulk(&spec.name.to_ascii_lowercase()),
+        RespValue::Integer(spec.arity()),
+        statuses(spec.flag_names()),
+        RespValue::Integer(spec.first_key as i64),
+        RespValue::Integer(spec.last_key as i64),
+        RespValue::Integer(spec.key_step as i64),
+        statuses(spec.acl_categories().into_iter()),
+        empty(),
+        empty(),
+        empty(),
+    ]))
+}
+
+impl CommandExecutor {
+    /// COMMAND (no names) and COMMAND INFO
+    pub(super) fn execute_command_info(&self, names: &[String]) -> RespValue {
+        if names.is_empty() {
+            return RespValue::Array(Some(CommandSpec::all().map(info_entry).collect()));
+        }
+        RespValue::Array(Some(
+            names
+                .iter()
+                .map(|name| match CommandSpec::lookup(name) {
+                    Some(spec) => info_entry(spec),
+                    None => RespValue::Array(None),
+                })
+                .collect(),
+        ))
+    }
+
+    pub(super) fn execute_command_count(&self) -> RespValue {
+        let count = CommandSpec::all().count();
+
+        // TigerStyle: Invariant - COUNT agrees with COMMAND
+        debug_assert!(
+            count > 0 && CommandSpec::all().all(|spec| CommandSpec::lookup(spec.name).is_some()),
+            "Invariant: every command COMMAND replies must be found by name"
+        );
+        RespValue::Integer(count as i64)
+    }
+
+    pub(super) fn execute_command_docs(&self, names: &[String]) -> RespValue {
+        let specs: Vec<&CommandSpec> = if names.is_empty() {
+            CommandSpec::all().collect()
+        } else {
+            names
+                .iter()
+                .filter_map(|name| CommandSpec::lookup(name))
+                .collect()
+        };
+        let mut reply = Vec::with_capacity(specs.len() * 2);
+        for spec in specs {
+            reply.push(bulk(&spec.name.to_ascii_lowercase()));
+            reply.push(RespValue::Array(Some(vec![
+                bulk("group"),
+                bulk(spec.group()),
+            ])));
+        }
+        RespValue::Array(Some(reply))
+    }
+
+    pub(super) fn execute_command_getkeys(&self, args: &[String]) -> RespValue {
+        debug_assert!(
+            !args.is_empty(),
+            "Precondition: COMMAND GETKEYS needs a command to inspect"
+        );
+        let Some(spec) = CommandSpec::lookup(&args[0]) else {
+            return RespValue::err("ERR Invalid command specified");
+        };
+        if args.len() < spec.min_argc || spec.max_argc.is_some_and(|max| args.len() > max) {
+            return RespValue::err("ERR Invalid number of arguments specified for command");
+        }
+        let call = RespValue::Array(Some(args.iter().map(|a| bulk(a)).collect()));
+        let keys = match Command::from_resp(&call) {
+            Ok(cmd) => cmd.get_keys(),
+            Err(e) => return RespValue::err(e),
+        };
+        if keys.is_empty() {
+            return RespValue::err("ERR The command has no key arguments");
+        }
+        RespValue::Array(Some(keys.iter().map(|k| bulk(k)).collect()))
+    }
+}
@@ -1,90 +1,130 @@
 //! CONFIG command implementation.
 //!
 //! Provides CONFIG GET (with glob matching), CONFIG SET, and CONFIG RESETSTAT.
-//! The `ServerConfig` struct holds a map of configuration parameters seeded with
-//! Redis 7 defaults for the ~40 parameters the official Tcl test suite requires.
+//! CONFIG SET validates `maxmemory` and `maxmemory-policy` and applies them
+//! to eviction (see `eviction.rs`), and validates `sanitize-dump-payload`
+//! (see `data/listpack.rs`) and `keys-chunk-size` (see `scan_ops.rs`).
+//! `loglevel` takes a level spec with per-subsystem overrides and swaps the
+//! running log filter (see `crate::logging`). `latency-monitor-threshold`
+//! turns the latency monitor on (see `latency_ops.rs`). `busy-reply-threshold`
+//! sets when a running script starts getting other clients BUSY replies (see
+//! `script_monitor.rs`).
+//! `ServerConfig` holds the parameters CONFIG SET changed; every other
+//! parameter reads as its Redis 7 default from `CONFIG_DEFAULTS`, which
+//! covers the ~40 parameters the official Tcl test suite requires.
+//!
+//! CONFIG GET follows Redis 7: it takes several patterns and replies each
+//! matching parameter once. A pattern without glob characters is a lookup
+//! and replies the name as given; a glob matches names without case and
+//! matches aliases too, so `*ziplist*` finds the old names of the listpack
+//! parameters (`CONFIG_ALIASES`). An alias reads and writes its parameter.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Changed parameters are stored under their canonical, lowercase name
+//! - Every alias names a parameter with a default, and no default is an alias
 
+use super::eviction::{self, EvictionPolicy};
 use super::CommandExecutor;
+use crate::logging::{self, LogLevels};
+use crate::redis::data::SanitizePayload;
 use crate::redis::resp::RespValue;
 use ahash::AHashMap;
 
+/// Every parameter with its Redis 7 default, in CONFIG GET order
+const CONFIG_DEFAULTS: &[(&str, &str)] = &[
+    // Encoding thresholds
+    ("list-max-listpack-size", "-2"),
+    ("list-compress-depth", "0"),
+    ("set-max-listpack-entries", "128"),
+    ("set-max-intset-entries", "512"),
+    ("hash-max-listpack-entries", "128"),
+    ("hash-max-listpack-value", "64"),
+    ("zset-max-listpack-entries", "128"),
+    ("zset-max-listpack-value", "64"),
+    // Memory
+    ("maxmemory", "0"),
+    ("maxmemory-policy", "noeviction"),
+    ("active-expire-enabled", "yes"),
+    // Persistence
+    ("save", ""),
+    ("appendonly", "no"),
+    ("rdbcompression", "yes"),
+    ("sanitize-dump-payload", "no"),
+    // Networking
+    ("hz", "10"),
+    ("dynamic-hz", "yes"),
+    ("timeout", "0"),
+    ("tcp-keepalive", "300"),
+    ("maxclients", "10000"),
+    // Limits
+    ("proto-max-bulk-len", "512000000"),
+    ("client-query-buffer-limit", "1073741824"),
+    ("keys-chunk-size", "0"),
+    // Scripting
+    ("busy-reply-threshold", "5000"),
+    // Lazy free
+    ("lazyfree-lazy-eviction", "no"),
+    ("lazyfree-lazy-expire", "no"),
+    ("lazyfree-lazy-server-del", "no"),
+    // Replication
+    ("min-replicas-to-write", "0"),
+    ("min-replicas-max-lag", "10"),
+    ("replica-serve-stale-data", "yes"),
+    ("replica-read-only", "yes"),
+    ("replica-lazy-flush", "no"),
+    ("replica-priority", "100"),
+    // Additional params the Tcl harness commonly reads
+    ("bind", ""),
+    ("port", "6379"),
+    ("databases", "16"),
+    ("loglevel", "notice"),
+    ("logfile", ""),
+    ("dir", "."),
+    ("dbfilename", "dump.rdb"),
+    ("requirepass", ""),
+    ("activedefrag", "no"),
+    ("no-appendfsync-on-rewrite", "no"),
+    ("tracking-table-max-keys", "0"),
+    ("close-on-oom", "no"),
+    ("repl-min-slaves-to-write", "0"),
+    ("latency-tracking", "yes"),
+    ("latency-monitor-threshold", "0"),
+    ("close-files-after-invoked-defer", "no"),
+    ("slowlog-log-slower-than", "10000"),
+    ("slowlog-max-len", "128"),
+    ("lfu-log-factor", "10"),
+    ("lfu-decay-time", "1"),
+];
+
+/// Old names Redis still accepts, and the parameter each one names
+const CONFIG_ALIASES: &[(&str, &str)] = &[
+    ("list-max-ziplist-size", "list-max-listpack-size"),
+    ("hash-max-ziplist-entries", "hash-max-listpack-entries"),
+    ("hash-max-ziplist-value", "hash-max-listpack-value"),
+    ("zset-max-ziplist-entries", "zset-max-listpack-entries"),
+    ("zset-max-ziplist-value", "zset-max-listpack-value"),
+    ("min-slaves-to-write", "min-replicas-to-write"),
+    ("min-slaves-max-lag", "min-replicas-max-lag"),
+    ("slave-serve-stale-data", "replica-serve-stale-data"),
+    ("slave-read-only", "replica-read-only"),
+    ("slave-lazy-flush", "replica-lazy-flush"),
+    ("slave-priority", "replica-priority"),
+    ("lua-time-limit", "busy-reply-threshold"),
+];
+
 /// Server configuration store for CONFIG GET/SET.
+#[derive(Clone)]
 pub struct ServerConfig {
+    /// Parameters CONFIG SET changed, by canonical name
     params: AHashMap<String, String>,
 }
 
 impl ServerConfig {
     pub fn new() -> Self {
-        let mut params = AHashMap::new();
-
-        // Encoding thresholds
-        params.insert("list-max-listpack-size".into(), "-2".into());
-        params.insert("list-compress-depth".into(), "0".into());
-        params.insert("set-max-listpack-entries".into(), "128".into());
-        params.insert("set-max-intset-entries".into(), "512".into());
-        params.insert("hash-max-listpack-entries".into(), "128".into());
-        params.insert("hash-max-listpack-value".into(), "64".into());
-        params.insert("zset-max-listpack-entries".into(), "128".into());
-        params.insert("zset-max-listpack-value".into(), "64".into());
-
-        // Memory
-        params.insert("maxmemory".into(), "0".into());
-        params.insert("maxmemory-policy".into(), "noeviction".into());
-        params.insert("active-expire-enabled".into(), "yes".into());
-
-        // Persistence
-        params.insert("save".into(), "".into());
-        params.insert("appendonly".into(), "no".into());
-        params.insert("rdbcompression".into(), "yes".into());
-
-        // Networking
-        params.insert("hz".into(), "10".into());
-        params.insert("dynamic-hz".into(), "yes".into());
-        params.insert("timeout".into(), "0".into());
-        params.insert("tcp-keepalive".into(), "300".into());
-        params.insert("maxclients".into(), "10000".into());
-
-        // Limits
-        params.insert("proto-max-bulk-len".into(), "512000000".into());
-        params.insert("client-query-buffer-limit".into(), "1073741824".into());
-
-        // Scripting
-        params.insert("lua-time-limit".into(), "5000".into());
-
-        // Lazy free
-        params.insert("lazyfree-lazy-eviction".into(), "no".into());
-        params.insert("lazyfree-lazy-expire".into(), "no".into());
-        params.insert("lazyfree-lazy-server-del".into(), "no".into());
-
-        // Replication
-        params.insert("min-replicas-to-write".into(), "0".into());
-        params.insert("replica-serve-stale-data".into(), "yes".into());
-        params.insert("replica-read-only".into(), "yes".into());
-
-        // Additional params the Tcl harness commonly reads
-        params.insert("bind".into(), "".into());
-        params.insert("port".into(), "6379".into());
-        params.insert("databases".into(), "16".into());
-        params.insert("loglevel".into(), "notice".into());
-        params.insert("logfile".into(), "".into());
-        params.insert("dir".into(), ".".into());
-        params.insert("dbfilename".into(), "dump.rdb".into());
-        params.insert("requirepass".into(), "".into());
-        params.insert("activedefrag".into(), "no".into());
-        params.insert("no-appendfsync-on-rewrite".into(), "no".into());
-        params.insert("slave-lazy-flush".into(), "no".into());
-        params.insert("tracking-table-max-keys".into(), "0".into());
-        params.insert("close-on-oom".into(), "no".into());
-        params.insert("repl-min-slaves-to-write".into(), "0".into());
-        params.insert("latency-tracking".into(), "yes".into());
-        params.insert("close-files-after-invoked-defer".into(), "no".into());
-        params.insert("slowlog-log-slower-than".into(), "10000".into());
-        params.insert("slowlog-max-len".into(), "128".into());
-        params.insert("list-max-ziplist-size".into(), "-2".into());
-        params.insert("lfu-log-factor".into(), "10".into());
-        params.insert("lfu-decay-time".into(), "1".into());
-
-        let config = ServerConfig { params };
+        let config = ServerConfig {
+            params: AHashMap::new(),
+        };
 
         #[cfg(debug_assertions)]
         config.verify_invariants();
@@ -92,25 +132,87 @@ impl ServerConfig {
         config
     }
 
-    /// Return all (key, value) pairs whose key matches the given glob pattern.
-    pub fn get_matching(&self, pattern: &str) -> Vec<(&str, &str)> {
-        self.params
-            .iter()
-            .filter(|(k, _)| glob_match(k.as_bytes(), pattern.as_bytes()))
-            .map(|(k, v)| (k.as_str(), v.as_str()))
-            .collect()
+    /// The parameter `name` refers to: itself, or the one it is an alias of
+    fn canonical(name: &str) -> String {
+        let name = name.to_ascii_lowercase();
+        match CONFIG_ALIASES.iter().find(|(alias, _)| *alias == name) {
+            Some((_, canonical)) => canonical.to_string(),
+            None => name,
+        }
+    }
+
+    /// Every (name, value) pair matching any of `patterns`, each name once.
+    /// A pattern without glob characters replies the name as given.
+    pub fn get_matching(&self, patterns: &[String]) -> Vec<(String, &str)> {
+        fn add<'a>(matches: &mut Vec<(String, &'a str)>, name: &str, value: &'a str) {
+            if !matches.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                matches.push((name.to_string(), value));
+            }
+        }
+
+        let mut matches = Vec::new();
+        for pattern in patterns {
+            if !pattern.contains(['*', '?', '[']) {
+                if let Some(value) = self.get(pattern) {
+                    add(&mut matches, pattern, value);
+                }
+                continue;
+            }
+            let pattern = pattern.to_ascii_lowercase();
+            let mut names: Vec<&str> = CONFIG_DEFAULTS.iter().map(|(name, _)| *name).collect();
+            names.extend(CONFIG_ALIASES.iter().map(|(alias, _)| *alias));
+            let mut extra: Vec<&str> = self
+                .params
+                .keys()
+                .map(String::as_str)
+                .filter(|name| !CONFIG_DEFAULTS.iter().any(|(d, _)| d == name))
+                .collect();
+            extra.sort_unstable();
+            names.extend(extra);
+            for name in names {
+                if glob_match(name.as_bytes(), pattern.as_bytes()) {
+                    let value = self.get(name).expect("listed parameters have values");
+                    add(&mut matches, name, value);
+                }
+            }
+        }
+        matches
+    }
+
+    /// Value of one parameter, by name or alias
+    pub fn get(&self, param: &str) -> Option<&str> {
+        let canonical = Self::canonical(param);
+        match self.params.get(&canonical) {
+            Some(value) => Some(value.as_str()),
+            None => CONFIG_DEFAULTS
+                .iter()
+                .find(|(name, _)| *name == canonical)
+                .map(|(_, default)| *default),
+        }
     }
 
-    /// Upsert a configuration parameter.
+    /// Upsert a configuration parameter, by name or alias.
     pub fn set(&mut self, param: &str, value: &str) {
-        self.params
-            .insert(param.to_lowercase(), value.to_string());
+        self.params.insert(Self::canonical(param), value.to_string());
     }
 
     #[cfg(debug_assertions)]
     pub(crate) fn verify_invariants(&self) {
         for key in self.params.keys() {
             debug_assert!(!key.is_empty(), "Config key must not be empty");
+            debug_assert!(
+                *key == Self::canonical(key),
+                "Invariant violated: config key '{}' must be stored canonical",
+                key
+            );
+        }
+        for (alias, canonical) in CONFIG_ALIASES {
+            debug_assert!(
+                CONFIG_DEFAULTS.iter().any(|(name, _)| name == canonical)
+                    && !CONFIG_DEFAULTS.iter().any(|(name, _)| name == alias),
+                "Invariant violated: alias '{}' must name a parameter with a default",
+                alias
+            );
         }
     }
 }
@@ -191,18 +293,42 @@ fn glob_match_inner(text: &[u8], pattern: &[u8], t: usize, p: usize) -> bool {
     }
 }
 
+fn config_set_error(param: &str, reason: &str) -> RespValue {
+    RespValue::err(format!(
+        "ERR CONFIG SET failed (possibly related to argument '{}') - {}",
+        param, reason
+    ))
+}
+
 // ============================================================================
 // Executor methods
 // ============================================================================
 
 impl CommandExecutor {
-    pub(super) fn execute_config_get(&self, pattern: &str) -> RespValue {
+    /// How much of a DUMP payload to check before loading it
+    pub fn sanitize_dump_payload(&self) -> SanitizePayload {
+        self.config
+            .get("sanitize-dump-payload")
+            .and_then(SanitizePayload::parse)
+            .unwrap_or(SanitizePayload::No)
+    }
+
+    /// Keys per chunk when production shards run KEYS as a chunked scan;
+    /// 0 runs it in one pass
+    pub fn keys_chunk_size(&self) -> usize {
+        self.config
+            .get("keys-chunk-size")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub(super) fn execute_config_get(&self, patterns: &[String]) -> RespValue {
         debug_assert!(
-            !pattern.is_empty(),
-            "Precondition: CONFIG GET pattern must not be empty"
+            !patterns.is_empty(),
+            "Precondition: CONFIG GET needs at least one pattern"
         );
 
-        let matches = self.config.get_matching(pattern);
+        let matches = self.config.get_matching(patterns);
         let mut result = Vec::with_capacity(matches.len() * 2);
         for (k, v) in &matches {
             result.push(RespValue::BulkString(Some(k.as_bytes().to_vec())));
@@ -221,7 +347,71 @@ impl CommandExecutor {
             !param.is_empty(),
             "Precondition: CONFIG SET param must not be empty"
         );
-        self.config.set(param, value);
+
+        // Eviction settings are validated and applied; other params are stored as given
+        match ServerConfig::canonical(param).as_str() {
+            "maxmemory" => {
+                let Some(bytes) = eviction::parse_memory(value) else {
+                    return config_set_error(param, "argument must be a memory value");
+                };
+                self.config.set(param, &bytes.to_string());
+                self.set_maxmemory(bytes);
+            }
+            "maxmemory-policy" => {
+                let Some(policy) = EvictionPolicy::parse(value) else {
+                    let names: Vec<&str> = EvictionPolicy::ALL.iter().map(|p| p.name()).collect();
+                    let reason = format!(
+                        "argument(s) must be one of the following: {}",
+                        names.join(", ")
+                    );
+                    return config_set_error(param, &reason);
+                };
+                self.config.set(param, policy.name());
+                self.set_eviction_policy(policy);
+            }
+            "sanitize-dump-payload" => {
+                let Some(mode) = SanitizePayload::parse(value) else {
+                    let names: Vec<&str> = SanitizePayload::ALL.iter().map(|m| m.name()).collect();
+                    let reason = format!(
+                        "argument(s) must be one of the following: {}",
+                        names.join(", ")
+                    );
+                    return config_set_error(param, &reason);
+                };
+                self.config.set(param, mode.name());
+            }
+            "loglevel" => {
+                let levels = match LogLevels::parse(value) {
+                    Ok(levels) => levels,
+                    Err(reason) => return config_set_error(param, &reason),
+                };
+                if let Err(reason) = logging::set_levels(&levels) {
+                    return config_set_error(param, &reason);
+                }
+                self.config.set(param, &levels.to_string());
+            }
+            "latency-monitor-threshold" => {
+                let Ok(threshold_ms) = value.parse::<u64>() else {
+                    return config_set_error(param, "argument couldn't be parsed into an integer");
+                };
+                self.config.set(param, &threshold_ms.to_string());
+                self.latency.set_threshold_ms(threshold_ms);
+            }
+            "busy-reply-threshold" => {
+                let Ok(threshold_ms) = value.parse::<u64>() else {
+                    return config_set_error(param, "argument couldn't be parsed into an integer");
+                };
+                self.config.set(param, &threshold_ms.to_string());
+                self.script_monitor.set_threshold_ms(threshold_ms);
+            }
+            "keys-chunk-size" => {
+                let Ok(size) = value.parse::<usize>() else {
+                    return config_set_error(param, "argument couldn't be parsed into an integer");
+                };
+                self.config.set(param, &size.to_string());
+            }
+            _ => self.config.set(param, value),
+        }
 
         #[cfg(debug_assertions)]
         self.config.verify_invariants();
@@ -0,0 +1,183 @@
+//! DEBUG SLEEP, SET-ACTIVE-EXPIRE, OBJECT, RELOAD, PROTOCOL and BUGGIFY.
+//!
+//! - SLEEP moves the executor's virtual clock forward; the sharded servers
+//!   sleep on the runtime's timer instead, without reaching a shard
+//! - SET-ACTIVE-EXPIRE 0 stops clock advances and the TTL manager from
+//!   evicting keys, leaving only lazy expiry on access, as the Tcl suite
+//!   uses it to test lazy expiry
+//! - OBJECT reports the encoding OBJECT ENCODING would, the length of the
+//!   value's RDB encoding and its LRU clock
+//! - RELOAD writes the keyspace as an RDB file, flushes and loads the file
+//!   back through the same path as startup, so a value that does not survive
+//!   persistence changes or disappears
+//! - PROTOCOL replies a fixed sample of one RESP3 type in its RESP2 form;
+//!   the connection sends it in the negotiated protocol (see the server's
+//!   `reply_shape`), so clients and the Tcl suite can check each type
+//!
+//! BUGGIFY lets scenario scripts and the Tcl harness steer fault injection
+//! mid-run: `DEBUG BUGGIFY SET <fault> <prob>` changes one fault's base
+//! probability and `DEBUG BUGGIFY STATS` reports what has been checked and
+//! triggered so far.
+//!
+//! Buggify state is thread-local and the simulation is single-threaded, so a
+//! change applies to every executor in the run. Both commands are errors
+//! outside `simulation` builds, where no buggify site is compiled in.
+
+use super::string_ops::parse_integer_value;
+use super::CommandExecutor;
+use crate::redis::data::Value;
+use crate::redis::rdb;
+use crate::redis::resp::RespValue;
+use crate::simulator::Duration;
+
+/// Redis's LRU clock is 24 bits of seconds
+const LRU_CLOCK_MAX: u64 = (1 << 24) - 1;
+
+/// Collections above this many elements leave their compact encoding
+const COMPACT_MAX_ELEMENTS: usize = 128;
+
+/// Strings up to this many bytes are embedded in their object
+const EMBSTR_MAX_BYTES: usize = 44;
+
+/// The encoding OBJECT ENCODING reports, or None for `Value::Null`
+pub(super) fn object_encoding(value: &Value) -> Option<&'static str> {
+    let encoding = match value {
+        Value::String(s) if parse_integer_value(s.as_bytes()).is_some() => "int",
+        Value::String(s) if s.len() <= EMBSTR_MAX_BYTES => "embstr",
+        Value::String(_) => "raw",
+        Value::List(l) if l.len() <= COMPACT_MAX_ELEMENTS => "listpack",
+        Value::List(_) => "quicklist",
+        Value::Set(s) if s.len() <= COMPACT_MAX_ELEMENTS => "listpack",
+        Value::Hash(h) if h.len() <= COMPACT_MAX_ELEMENTS => "listpack",
+        Value::Set(_) | Value::Hash(_) => "hashtable",
+        Value::SortedSet(z) if z.len() <= COMPACT_MAX_ELEMENTS => "listpack",
+        Value::SortedSet(_) => "skiplist",
+        Value::Stream(_) => "stream",
+        Value::Null => return None,
+    };
+    Some(encoding)
+}
+
+#[cfg(not(feature = "simulation"))]
+const SIMULATION_ONLY: &str = "ERR DEBUG BUGGIFY is only available in simulation builds";
+
+impl CommandExecutor {
+    pub(super) fn execute_debug_sleep(&mut self, seconds: f64) -> RespValue {
+        debug_assert!(
+            seconds.is_finite() && seconds >= 0.0,
+            "Precondition: sleep time must be a non-negative number"
+        );
+        let millis = (seconds * 1000.0).round() as u64;
+        self.set_time(self.current_time + Duration::from_millis(millis));
+        RespValue::ok()
+    }
+
+    pub(super) fn execute_debug_object(&mut self, key: &str) -> RespValue {
+        let epoch_ms = self.simulation_start_epoch_ms;
+        let Some(value) = self.get_value(key) else {
+            return RespValue::err("ERR no such key");
+        };
+        let Some(encoding) = object_encoding(value) else {
+            return RespValue::err("ERR no such key");
+        };
+        let serialized_length = rdb::serialized_length(value, epoch_ms);
+        let lru = self
+            .access_times
+            .get(key)
+            .map_or(0, |at| (at.as_millis() / 1000) & LRU_CLOCK_MAX);
+        let report = format!(
+            "Value at:0x0 refcount:1 encoding:{} serializedlength:{} lru:{} lru_seconds_idle:{}",
+            encoding,
+            serialized_length,
+            lru,
+            self.idle_time_secs(key)
+        );
+        RespValue::BulkString(Some(report.into_bytes()))
+    }
+
+    pub(super) fn execute_debug_reload(&mut self) -> RespValue {
+        let snapshot = self.rdb_snapshot();
+        self.execute_flush();
+        match self.load_rdb_file(&snapshot) {
+            Ok(report) => {
+                // TigerStyle: Postcondition - the clock has not moved, so no
+                // saved key has expired on the way back
+                debug_assert_eq!(
+                    report.skipped, 0,
+                    "Postcondition violated: DEBUG RELOAD must restore every key it saved"
+                );
+                RespValue::ok()
+            }
+            Err(e) => RespValue::err(format!("ERR Error trying to load the RDB dump: {}", e)),
+        }
+    }
+
+    /// Redis's samples: booleans are integers in RESP2, and the map pairs
+    /// 0..3 with whether the key is 1
+    pub(super) fn execute_debug_protocol(&self, kind: &str) -> RespValue {
+        match kind {
+            "string" => RespValue::BulkString(Some(b"Hello World".to_vec())),
+            "integer" => RespValue::Integer(12345),
+            "double" => RespValue::BulkString(Some(b"3.141".to_vec())),
+            "null" => RespValue::BulkString(None),
+            "array" | "set" => RespValue::Array(Some((0..3).map(RespValue::Integer).collect())),
+            "map" => RespValue::Array(Some(
+                (0..3)
+                    .flat_map(|j| [RespValue::Integer(j), RespValue::Integer(i64::from(j == 1))])
+                    .collect(),
+            )),
+            "true" => RespValue::Integer(1),
+            "false" => RespValue::Integer(0),
+            _ => RespValue::err(
+                "ERR Wrong protocol type name. Please use one of the following: \
+                 string|integer|double|null|array|set|map|true|false",
+            ),
+        }
+    }
+
+    #[cfg(feature = "simulation")]
+    pub(super) fn execute_debug_buggify_set(&self, fault: &str, probability: f64) -> RespValue {
+        let Some(fault_id) = crate::buggify::faults::lookup(fault) else {
+            return RespValue::err(format!("ERR unknown fault '{}'", fault));
+        };
+        if !(0.0..=1.0).contains(&probability) {
+            return RespValue::err("ERR probability must be between 0 and 1");
+        }
+
+        crate::buggify::set_fault_probability(fault_id, probability);
+        RespValue::ok()
+    }
+
+    #[cfg(not(feature = "simulation"))]
+    pub(super) fn execute_debug_buggify_set(&self, _fault: &str, _probability: f64) -> RespValue {
+        RespValue::err(SIMULATION_ONLY)
+    }
+
+    /// One line per fault that is armed or has been checked:
+    /// `<fault>:probability=<p>,checks=<n>,triggers=<n>`
+    #[cfg(feature = "simulation")]
+    pub(super) fn execute_debug_buggify_stats(&self) -> RespValue {
+        let stats = crate::buggify::get_stats();
+        let mut out = String::with_capacity(1024);
+        for fault_id in crate::buggify::ALL_FAULTS {
+            let probability = crate::buggify::fault_probability(fault_id);
+            let checks = stats.checks.get(*fault_id).copied().unwrap_or(0);
+            let triggers = stats.triggers.get(*fault_id).copied().unwrap_or(0);
+            if probability == 0.0 && checks == 0 {
+                continue;
+            }
+
+            debug_assert!(triggers <= checks, "Invariant: a trigger is always a check");
+            out.push_str(&format!(
+                "{}:probability={:.6},checks={},triggers={}\r\n",
+                fault_id, probability, checks, triggers
+            ));
+        }
+        RespValue::BulkString(Some(out.into_bytes()))
+    }
+
+    #[cfg(not(feature = "simulation"))]
+    pub(super) fn execute_debug_buggify_stats(&self) -> RespValue {
+        RespValue::err(SIMULATION_ONLY)
+    }
+}
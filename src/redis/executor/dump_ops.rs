@@ -0,0 +1,97 @@
+//! DUMP and RESTORE.
+//!
+//! DUMP serializes a key's value in the RDB value encoding with the RDB
+//! version and a CRC64 appended (see `rdb.rs`), so payloads move between
+//! this server and redis-server in either direction. RESTORE checks the
+//! footer, decodes the value as deeply as `sanitize-dump-payload` asks, and
+//! installs it with an optional TTL.
+//!
+//! IDLETIME and FREQ are accepted and validated, but not applied: RESTORE
+//! is itself an access, and the executor keeps no LFU counter.
+//!
+//! # TigerStyle Invariants
+//!
+//! - A failed RESTORE leaves the keyspace untouched
+//! - After RESTORE with a future deadline, the key holds exactly that deadline
+
+use super::CommandExecutor;
+use crate::redis::data::Value;
+use crate::redis::rdb;
+use crate::redis::resp::RespValue;
+use crate::simulator::VirtualTime;
+
+impl CommandExecutor {
+    pub(crate) fn execute_dump(&mut self, key: &str) -> RespValue {
+        let epoch_ms = self.simulation_start_epoch_ms;
+        match self.get_value(key) {
+            Some(value) => RespValue::BulkString(rdb::encode_dump_payload(value, epoch_ms)),
+            None => RespValue::BulkString(None),
+        }
+    }
+
+    pub(super) fn execute_restore(
+        &mut self,
+        key: &str,
+        ttl_ms: u64,
+        payload: &[u8],
+        replace: bool,
+        absttl: bool,
+    ) -> RespValue {
+        if !replace && self.get_value(key).is_some() {
+            return RespValue::err("BUSYKEY Target key name already exists.");
+        }
+
+        let deep = self.sanitize_dump_payload().deep(true);
+        let mut value =
+            match rdb::decode_dump_payload(payload, deep, self.simulation_start_epoch_ms) {
+                Ok(value) => value,
+                Err(e) if e == rdb::BAD_DUMP_PAYLOAD => {
+                    return RespValue::err(format!("ERR {}", rdb::BAD_DUMP_PAYLOAD))
+                }
+                Err(_) => return RespValue::err("ERR Bad data format"),
+            };
+
+        let now_ms = self.current_time.as_millis();
+        let deadline_ms = match (ttl_ms, absttl) {
+            (0, _) => None,
+            (unix_ms, true) => {
+                Some((unix_ms as i128 - self.simulation_start_epoch_ms as i128).max(0) as u64)
+            }
+            (ttl_ms, false) => Some(now_ms.saturating_add(ttl_ms)),
+        };
+
+        // A deadline already past, or a hash whose every field has expired,
+        // restores a key that is gone at once
+        let expired_hash = match &mut value {
+            Value::Hash(h) => {
+                h.remove_expired_fields(now_ms);
+                h.is_empty()
+            }
+            _ => false,
+        };
+        if expired_hash || deadline_ms.is_some_and(|deadline| deadline <= now_ms) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+            return RespValue::ok();
+        }
+
+        self.data.insert(key.to_string(), value);
+        match deadline_ms {
+            Some(deadline) => {
+                self.expirations
+                    .insert(key.to_string(), VirtualTime::from_millis(deadline));
+            }
+            None => {
+                self.expirations.remove(key);
+            }
+        }
+
+        // TigerStyle: Postcondition - the key holds the restored deadline
+        debug_assert_eq!(
+            self.expirations.get(key).map(|t| t.as_millis()),
+            deadline_ms,
+            "Postcondition violated: RESTORE must leave exactly the requested deadline"
+        );
+        RespValue::ok()
+    }
+}
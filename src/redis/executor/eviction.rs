@@ -0,0 +1,316 @@
+//! maxmemory eviction (CONFIG SET maxmemory / maxmemory-policy).
+//!
+//! Memory use is estimated from the incrementally maintained keyspace stats,
+//! so checking the limit is O(1): a fixed overhead per key, plus the byte
+//! length of strings and a fixed overhead per collection element. It is an
+//! estimate of the dataset, not of allocator usage.
+//!
+//! Before a write runs, `execute()` (or a direct fast path such as
+//! `incr_direct`) evicts keys until the estimate fits.
+//! Writes that may grow the dataset are rejected with the Redis OOM error
+//! when nothing more can be evicted; removals (DEL, LPOP, EXPIRE, ...) still
+//! run so clients can free memory themselves.
+//!
+//! Victims are picked deterministically so simulations replay exactly: the
+//! random policies take the key with the lowest seeded FNV-1a hash, and
+//! volatile-ttl the key with the nearest deadline. Access times (kept for
+//! OBJECT IDLETIME) are not sampled and no LFU counter is kept (OBJECT FREQ
+//! is always 0), so the LRU and LFU policies pick like their random
+//! counterparts.
+//!
+//! Every evicted key is queued as an event. Replicated callers drain the
+//! queue with `take_evicted_keys()` after each command and turn each key into
+//! a DEL delta, so replicas and the WAL never resurrect an evicted key. A
+//! write may evict the very key it then stores; callers skip keys that exist
+//! again by the time they drain.
+//!
+//! # TigerStyle Invariants
+//!
+//! - After a successful eviction pass the estimate is within `maxmemory`
+//! - The event queue never exceeds `MAX_PENDING_EVICTIONS`
+
+use super::keyspace_stats::ValueKind;
+use super::CommandExecutor;
+use crate::redis::command::Command;
+use crate::redis::resp::RespValue;
+
+/// Estimated bytes per key (dict entry, object header, key name)
+const KEY_OVERHEAD_BYTES: u64 = 64;
+
+/// Estimated bytes per collection element
+const ELEMENT_OVERHEAD_BYTES: u64 = 32;
+
+/// Evicted keys kept for `take_evicted_keys()`. Executors nobody drains
+/// (non-replicated shards) drop the oldest events past this bound.
+const MAX_PENDING_EVICTIONS: usize = 10_000;
+
+/// maxmemory-policy values, in the order Redis lists them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    VolatileLru,
+    VolatileLfu,
+    VolatileRandom,
+    VolatileTtl,
+    AllKeysLru,
+    AllKeysLfu,
+    AllKeysRandom,
+    NoEviction,
+}
+
+impl EvictionPolicy {
+    pub const ALL: [EvictionPolicy; 8] = [
+        EvictionPolicy::VolatileLru,
+        EvictionPolicy::VolatileLfu,
+        EvictionPolicy::VolatileRandom,
+        EvictionPolicy::VolatileTtl,
+        EvictionPolicy::AllKeysLru,
+        EvictionPolicy::AllKeysLfu,
+        EvictionPolicy::AllKeysRandom,
+        EvictionPolicy::NoEviction,
+    ];
+
+    /// Parse a policy name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Name used by CONFIG GET and INFO
+    pub fn name(self) -> &'static str {
+        match self {
+            EvictionPolicy::VolatileLru => "volatile-lru",
+            EvictionPolicy::VolatileLfu => "volatile-lfu",
+            EvictionPolicy::VolatileRandom => "volatile-random",
+            EvictionPolicy::VolatileTtl => "volatile-ttl",
+            EvictionPolicy::AllKeysLru => "allkeys-lru",
+            EvictionPolicy::AllKeysLfu => "allkeys-lfu",
+            EvictionPolicy::AllKeysRandom => "allkeys-random",
+            EvictionPolicy::NoEviction => "noeviction",
+        }
+    }
+
+    /// Whether only keys with a TTL may be evicted
+    fn volatile_only(self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::VolatileLru
+                | EvictionPolicy::VolatileLfu
+                | EvictionPolicy::VolatileRandom
+                | EvictionPolicy::VolatileTtl
+        )
+    }
+}
+
+/// Limit, policy and pending eviction events of one executor
+#[derive(Debug)]
+pub(crate) struct EvictionState {
+    /// 0 means unlimited
+    maxmemory: u64,
+    policy: EvictionPolicy,
+    pending: Vec<String>,
+    evicted_total: u64,
+}
+
+impl EvictionState {
+    pub(crate) fn new() -> Self {
+        EvictionState {
+            maxmemory: 0,
+            policy: EvictionPolicy::NoEviction,
+            pending: Vec::new(),
+            evicted_total: 0,
+        }
+    }
+
+    fn record(&mut self, key: String) {
+        self.pending.push(key);
+        self.evicted_total += 1;
+        if self.pending.len() > MAX_PENDING_EVICTIONS {
+            let overflow = self.pending.len() - MAX_PENDING_EVICTIONS;
+            self.pending.drain(..overflow);
+        }
+        debug_assert!(
+            self.pending.len() <= MAX_PENDING_EVICTIONS,
+            "Postcondition: pending evictions must be within capacity"
+        );
+    }
+}
+
+/// Parse a memory amount the way Redis `memtoull` does: digits with an
+/// optional b/k/kb/m/mb/g/gb suffix (k = 1000, kb = 1024).
+pub(super) fn parse_memory(value: &str) -> Option<u64> {
+    let digits_end = value
+        .bytes()
+        .position(|b| !b.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(digits_end);
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" => 1_000,
+        "kb" => 1 << 10,
+        "m" => 1_000_000,
+        "mb" => 1 << 20,
+        "g" => 1_000_000_000,
+        "gb" => 1 << 30,
+        _ => return None,
+    };
+    // Like strtoull, an empty digit string reads as 0
+    let amount: u64 = if digits.is_empty() {
+        0
+    } else {
+        digits.parse().ok()?
+    };
+    amount.checked_mul(multiplier)
+}
+
+/// Seeded FNV-1a, used to rank eviction candidates
+fn sample_rank(seed: u64, key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in seed.to_le_bytes().iter().chain(key.as_bytes()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Writes that never grow the dataset and so still run when out of memory
+fn frees_memory(cmd: &Command) -> bool {
+    matches!(
+        cmd,
+        Command::Del(_)
+            | Command::Unlink(_)
+            | Command::GetDel(_)
+            | Command::Expire { .. }
+            | Command::PExpire { .. }
+            | Command::Persist(_)
+            | Command::LPop(_)
+            | Command::RPop(_)
+            | Command::LTrim(..)
+            | Command::SRem(..)
+            | Command::SPop(..)
+            | Command::HDel(..)
+            | Command::ZRem(..)
+            | Command::ZPopMin { .. }
+            | Command::ZPopMax { .. }
+    )
+}
+
+impl CommandExecutor {
+    /// Estimated dataset size in bytes (see the module docs)
+    pub fn used_memory(&self) -> u64 {
+        let stats = &self.keyspace_stats;
+        let elements: u64 = ValueKind::ALL
+            .into_iter()
+            .filter(|&kind| kind != ValueKind::String)
+            .map(|kind| stats.total_size(kind))
+            .sum();
+        stats.total_keys() * KEY_OVERHEAD_BYTES
+            + stats.total_size(ValueKind::String)
+            + elements * ELEMENT_OVERHEAD_BYTES
+    }
+
+    pub fn maxmemory(&self) -> u64 {
+        self.eviction.maxmemory
+    }
+
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.eviction.policy
+    }
+
+    /// Keys evicted since the last call, oldest first
+    pub fn take_evicted_keys(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.eviction.pending)
+    }
+
+    /// Keys evicted since the executor was created (INFO evicted_keys)
+    pub fn evicted_keys_total(&self) -> u64 {
+        self.eviction.evicted_total
+    }
+
+    pub(super) fn set_maxmemory(&mut self, bytes: u64) {
+        self.eviction.maxmemory = bytes;
+        // Lowering the limit takes effect at once, not on the next write
+        self.evict_to_fit();
+    }
+
+    pub(super) fn set_eviction_policy(&mut self, policy: EvictionPolicy) {
+        self.eviction.policy = policy;
+    }
+
+    /// Evict ahead of a write. Returns the OOM error to reply with when the
+    /// command may grow the dataset and the limit can't be met.
+    pub(super) fn evict_for_write(&mut self, cmd: &Command) -> Option<RespValue> {
+        let limit = self.eviction.maxmemory;
+        if limit == 0 || cmd.is_read_only() || self.used_memory() <= limit {
+            return None;
+        }
+        if self.evict_to_fit() || cmd.get_primary_key().is_none() || frees_memory(cmd) {
+            return None;
+        }
+        Some(RespValue::err(
+            "OOM command not allowed when used memory > 'maxmemory'.",
+        ))
+    }
+
+    /// `evict_for_write` for a single-key write that may grow the dataset,
+    /// for the direct fast paths that have no `Command` to inspect
+    pub(super) fn evict_for_direct_write(&mut self) -> Option<RespValue> {
+        let limit = self.eviction.maxmemory;
+        if limit == 0 || self.used_memory() <= limit || self.evict_to_fit() {
+            return None;
+        }
+        Some(RespValue::err(
+            "OOM command not allowed when used memory > 'maxmemory'.",
+        ))
+    }
+
+    /// Evict until the estimate is within `maxmemory`. Returns false when the
+    /// policy has no candidate left.
+    fn evict_to_fit(&mut self) -> bool {
+        if self.eviction.maxmemory == 0 {
+            return true;
+        }
+        while self.used_memory() > self.eviction.maxmemory {
+            let Some(victim) = self.pick_victim() else {
+                return false;
+            };
+            debug_assert!(
+                self.data.contains_key(&victim),
+                "Precondition: eviction victim '{}' must exist",
+                victim
+            );
+            self.remove_tracked(&victim);
+            self.expirations.remove(&victim);
+            self.eviction.record(victim);
+        }
+
+        debug_assert!(
+            self.used_memory() <= self.eviction.maxmemory,
+            "Postcondition: eviction must bring memory within maxmemory"
+        );
+        true
+    }
+
+    fn pick_victim(&self) -> Option<String> {
+        let policy = self.eviction.policy;
+        let seed = self.eviction.evicted_total;
+        match policy {
+            EvictionPolicy::NoEviction => None,
+            EvictionPolicy::VolatileTtl => self
+                .expirations
+                .iter()
+                .min_by_key(|&(key, &deadline)| (deadline, key))
+                .map(|(key, _)| key.clone()),
+            _ if policy.volatile_only() => self
+                .expirations
+                .keys()
+                .min_by_key(|&key| (sample_rank(seed, key), key))
+                .cloned(),
+            _ => self
+                .data
+                .keys()
+                .min_by_key(|&key| (sample_rank(seed, key), key))
+                .cloned(),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Key deadlines with a deadline-ordered index.
+//!
+//! `Expirations` is the executor's key -> deadline map. Next to it, every
+//! insert and remove keeps a count of keys per deadline in a `BTreeMap`, so
+//! "how many keys expire before t" is a range walk over the distinct
+//! deadlines before t rather than a scan of every key with a TTL. The
+//! expiry outlook and the DEBUG KEYSTATS TTL histogram read it that way.
+//!
+//! # TigerStyle Invariants
+//!
+//! - The per-deadline counts sum to the number of keys with a deadline
+//! - No deadline in the index has a count of zero
+//! - Both are checked in debug builds by `CommandExecutor::verify_invariants`
+
+use crate::simulator::VirtualTime;
+use ahash::AHashMap;
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use std::ops::RangeTo;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Expirations {
+    deadlines: AHashMap<String, VirtualTime>,
+    /// Keys per deadline
+    by_deadline: BTreeMap<VirtualTime, u64>,
+}
+
+impl Expirations {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.deadlines.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.deadlines.is_empty()
+    }
+
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<&VirtualTime>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.deadlines.get(key)
+    }
+
+    pub(crate) fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.deadlines.contains_key(key)
+    }
+
+    /// Set `key`'s deadline, returning the one it replaces
+    pub(crate) fn insert(&mut self, key: String, deadline: VirtualTime) -> Option<VirtualTime> {
+        let previous = self.deadlines.insert(key, deadline);
+        if let Some(previous) = previous {
+            self.unindex(previous);
+        }
+        *self.by_deadline.entry(deadline).or_insert(0) += 1;
+        previous
+    }
+
+    pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<VirtualTime>
+    where
+        String: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let removed = self.deadlines.remove(key);
+        if let Some(deadline) = removed {
+            self.unindex(deadline);
+        }
+        removed
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.deadlines.clear();
+        self.by_deadline.clear();
+    }
+
+    /// Keep the keys for which `keep` returns true
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&String, VirtualTime) -> bool) {
+        let by_deadline = &mut self.by_deadline;
+        self.deadlines.retain(|key, &mut deadline| {
+            let kept = keep(key, deadline);
+            if !kept {
+                Self::unindex_in(by_deadline, deadline);
+            }
+            kept
+        });
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &String> {
+        self.deadlines.keys()
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &VirtualTime> {
+        self.deadlines.values()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&String, &VirtualTime)> {
+        self.deadlines.iter()
+    }
+
+    /// (deadline, keys) for every distinct deadline in `range`, earliest first
+    pub(crate) fn counts(
+        &self,
+        range: RangeTo<VirtualTime>,
+    ) -> impl Iterator<Item = (VirtualTime, u64)> + '_ {
+        self.by_deadline
+            .range(range)
+            .map(|(&deadline, &count)| (deadline, count))
+    }
+
+    /// The index agrees with the map
+    pub(crate) fn is_consistent(&self) -> bool {
+        self.by_deadline.values().all(|&count| count > 0)
+            && self.by_deadline.values().sum::<u64>() == self.deadlines.len() as u64
+    }
+
+    fn unindex(&mut self, deadline: VirtualTime) {
+        Self::unindex_in(&mut self.by_deadline, deadline);
+    }
+
+    fn unindex_in(by_deadline: &mut BTreeMap<VirtualTime, u64>, deadline: VirtualTime) {
+        let count = by_deadline
+            .get_mut(&deadline)
+            .expect("Invariant: an indexed key's deadline has a count");
+        *count -= 1;
+        if *count == 0 {
+            by_deadline.remove(&deadline);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Expirations {
+    type Item = (&'a String, &'a VirtualTime);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, VirtualTime>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.deadlines.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_follows_inserts_and_removes() {
+        let at = VirtualTime::from_millis;
+        let mut expirations = Expirations::new();
+        expirations.insert("a".to_string(), at(100));
+        expirations.insert("b".to_string(), at(100));
+        expirations.insert("c".to_string(), at(300));
+        assert_eq!(
+            expirations.counts(..at(1000)).collect::<Vec<_>>(),
+            vec![(at(100), 2), (at(300), 1)]
+        );
+
+        // A new deadline moves the key; removing the last key drops its deadline
+        assert_eq!(expirations.insert("a".to_string(), at(300)), Some(at(100)));
+        assert_eq!(expirations.remove("b"), Some(at(100)));
+        assert_eq!(expirations.remove("b"), None);
+        assert_eq!(
+            expirations.counts(..at(1000)).collect::<Vec<_>>(),
+            vec![(at(300), 2)]
+        );
+        assert!(expirations.counts(..at(300)).next().is_none());
+        assert!(expirations.is_consistent());
+
+        expirations.retain(|key, _| key == "c");
+        assert_eq!(expirations.len(), 1);
+        assert_eq!(
+            expirations.counts(..at(1000)).collect::<Vec<_>>(),
+            vec![(at(300), 1)]
+        );
+        expirations.clear();
+        assert!(expirations.is_empty());
+        assert!(expirations.is_consistent());
+    }
+}
@@ -0,0 +1,367 @@
+//! Function command implementations for CommandExecutor.
+//!
+//! Handles: FUNCTION LOAD, DELETE, FLUSH, LIST, DUMP, RESTORE, STATS, and
+//! FCALL, FCALL_RO
+//!
+//! Libraries live in the executor's `FunctionLibraries` (see `functions`):
+//! - loading a library runs its code in a sandbox whose `redis` table only
+//!   has `register_function`, `log` and the `LOG_*` constants, so the code
+//!   cannot touch the keyspace while it loads. A budget of instruction hooks
+//!   stands in for Redis's load timeout: a library that never returns from
+//!   its top level fails to load instead of hanging the executor
+//! - FCALL runs the library again through the EVAL path (see `script_ops`)
+//!   and calls the function it registers, so BUSY replies, SCRIPT KILL,
+//!   `redis.setresp` and the replication checks apply to functions too
+//! - a function flagged `no-writes` may only read: the `redis.call` bridge
+//!   refuses its writes, and FCALL_RO calls no other function
+//!
+//! FUNCTION RESTORE and snapshot loading load every library in the payload
+//! before storing any, so a bad library leaves the store as it was.
+
+use super::CommandExecutor;
+use crate::redis::command::FunctionRestorePolicy;
+use crate::redis::data::SDS;
+use crate::redis::functions::LUA_ENGINE;
+#[cfg(feature = "lua")]
+use crate::redis::functions::{self, FunctionInfo, Library, LibraryHeader, FUNCTION_FLAGS};
+use crate::redis::rdb;
+use crate::redis::resp::RespValue;
+
+/// Instruction hooks a library may take to load (Redis's 500ms load timeout)
+#[cfg(feature = "lua")]
+const FUNCTION_LOAD_HOOKS: u32 = 50;
+
+#[cfg(feature = "lua")]
+const INVALID_FUNCTION_NAME: &str = "Function names can only contain letters, numbers, or underscores(_) and must be at least one character long";
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+impl CommandExecutor {
+    /// FUNCTION LOAD: the library's name once its functions are stored
+    pub(super) fn execute_function_load(&self, code: &str, replace: bool) -> RespValue {
+        #[cfg(feature = "lua")]
+        {
+            let stored = self.load_library(code).and_then(|library| {
+                let name = library.name.clone();
+                self.functions.load(library, replace).map(|()| name)
+            });
+            match stored {
+                Ok(name) => RespValue::BulkString(Some(name.into_bytes())),
+                Err(e) => RespValue::err(e),
+            }
+        }
+        #[cfg(not(feature = "lua"))]
+        {
+            let _ = (code, replace);
+            RespValue::err("ERR Lua scripting not compiled in")
+        }
+    }
+
+    pub(super) fn execute_function_delete(&self, library: &str) -> RespValue {
+        if self.functions.delete(library) {
+            RespValue::ok()
+        } else {
+            RespValue::err("ERR Library not found")
+        }
+    }
+
+    pub(super) fn execute_function_flush(&self) -> RespValue {
+        self.functions.flush();
+        RespValue::ok()
+    }
+
+    /// FUNCTION LIST: per library its name, engine, functions (name,
+    /// description and flags) and, WITHCODE, its code
+    pub(super) fn execute_function_list(
+        &self,
+        pattern: Option<&str>,
+        with_code: bool,
+    ) -> RespValue {
+        let libraries = self
+            .functions
+            .libraries()
+            .into_iter()
+            .filter(|library| {
+                pattern.is_none_or(|pattern| {
+                    super::glob_match(library.name.as_bytes(), pattern.as_bytes())
+                })
+            })
+            .map(|library| {
+                let functions = library
+                    .functions
+                    .iter()
+                    .map(|function| {
+                        let description = function
+                            .description
+                            .as_deref()
+                            .map_or(RespValue::BulkString(None), bulk);
+                        let flags = function.flags.iter().map(|flag| bulk(flag)).collect();
+                        RespValue::Array(Some(vec![
+                            bulk("name"),
+                            bulk(&function.name),
+                            bulk("description"),
+                            description,
+                            bulk("flags"),
+                            RespValue::Array(Some(flags)),
+                        ]))
+                    })
+                    .collect();
+                let mut entry = vec![
+                    bulk("library_name"),
+                    bulk(&library.name),
+                    bulk("engine"),
+                    bulk(&library.engine),
+                    bulk("functions"),
+                    RespValue::Array(Some(functions)),
+                ];
+                if with_code {
+                    entry.push(bulk("library_code"));
+                    entry.push(bulk(&library.code));
+                }
+                RespValue::Array(Some(entry))
+            })
+            .collect();
+        RespValue::Array(Some(libraries))
+    }
+
+    pub(super) fn execute_function_dump(&self) -> RespValue {
+        RespValue::BulkString(Some(self.functions.dump()))
+    }
+
+    pub(super) fn execute_function_restore(
+        &self,
+        payload: &[u8],
+        policy: FunctionRestorePolicy,
+    ) -> RespValue {
+        let restored = rdb::decode_function_payload(payload)
+            .map_err(|e| format!("ERR {}", e))
+            .and_then(|codes| self.restore_libraries(&codes, policy));
+        match restored {
+            Ok(()) => RespValue::ok(),
+            Err(e) => RespValue::err(e),
+        }
+    }
+
+    /// FUNCTION STATS. A running function holds its executor, so none is
+    /// ever running when the executor answers
+    pub(super) fn execute_function_stats(&self) -> RespValue {
+        let lua = RespValue::Array(Some(vec![
+            bulk("libraries_count"),
+            RespValue::Integer(self.functions.num_libraries() as i64),
+            bulk("functions_count"),
+            RespValue::Integer(self.functions.num_functions() as i64),
+        ]));
+        RespValue::Array(Some(vec![
+            bulk("running_script"),
+            RespValue::BulkString(None),
+            bulk("engines"),
+            RespValue::Array(Some(vec![bulk(LUA_ENGINE), lua])),
+        ]))
+    }
+
+    /// FCALL and FCALL_RO (`read_only`)
+    pub(super) fn execute_fcall(
+        &mut self,
+        function: &str,
+        keys: &[String],
+        args: &[SDS],
+        read_only: bool,
+    ) -> RespValue {
+        #[cfg(feature = "lua")]
+        {
+            let Some((info, code)) = self.functions.function(function) else {
+                return RespValue::err("ERR Function not found");
+            };
+            if read_only && !info.is_read_only() {
+                return RespValue::err(
+                    "ERR Can not execute a script with write flag using *_ro command.",
+                );
+            }
+            let entry = super::script_ops::LuaEntry::Function {
+                code: functions::library_body(&code),
+                function,
+            };
+            self.run_lua(entry, keys, args, read_only || info.is_read_only())
+        }
+        #[cfg(not(feature = "lua"))]
+        {
+            let _ = (function, keys, args, read_only);
+            RespValue::err("ERR Lua scripting not compiled in")
+        }
+    }
+
+    /// Load the libraries of a FUNCTION RESTORE payload or a snapshot, and
+    /// store them all under `policy`, or none if one fails
+    pub(crate) fn restore_libraries(
+        &self,
+        codes: &[String],
+        policy: FunctionRestorePolicy,
+    ) -> Result<(), String> {
+        #[cfg(feature = "lua")]
+        {
+            let libraries = codes
+                .iter()
+                .map(|code| self.load_library(code))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.functions.restore(libraries, policy)
+        }
+        #[cfg(not(feature = "lua"))]
+        {
+            let _ = policy;
+            if codes.is_empty() {
+                return Ok(());
+            }
+            Err("ERR Lua scripting not compiled in".to_string())
+        }
+    }
+
+    /// Run a library's code to learn the functions it registers
+    #[cfg(feature = "lua")]
+    fn load_library(&self, code: &str) -> Result<Library, String> {
+        use mlua::MultiValue;
+        use std::cell::{Cell, RefCell};
+
+        let header = LibraryHeader::parse(code)?;
+        let lua = self.sandboxed_lua()?;
+
+        let hooks = Cell::new(0u32);
+        lua.set_hook(
+            mlua::HookTriggers::new()
+                .every_nth_instruction(crate::redis::script_monitor::SCRIPT_HOOK_INSTRUCTIONS),
+            move |_, _| {
+                hooks.set(hooks.get() + 1);
+                if hooks.get() > FUNCTION_LOAD_HOOKS {
+                    return Err(mlua::Error::RuntimeError(
+                        "FUNCTION LOAD timeout".to_string(),
+                    ));
+                }
+                Ok(mlua::VmState::Continue)
+            },
+        );
+
+        let registered: RefCell<Vec<FunctionInfo>> = RefCell::new(Vec::new());
+        let result = lua.scope(|scope| {
+            let register_fn = scope.create_function(|_, args: MultiValue| {
+                let (info, _) = Self::function_registration(args)?;
+                let mut registered = registered.borrow_mut();
+                if registered.iter().any(|function| function.name == info.name) {
+                    return Err(mlua::Error::RuntimeError(
+                        "Function already exists in the library".to_string(),
+                    ));
+                }
+                registered.push(info);
+                Ok(())
+            })?;
+
+            let redis_table = lua.create_table()?;
+            redis_table.set("register_function", register_fn)?;
+            redis_table.set(
+                "log",
+                lua.create_function(|_, args: MultiValue| Self::script_log(args))?,
+            )?;
+            for (name, value) in super::script_ops::SCRIPT_CONSTANTS {
+                redis_table.set(name, value)?;
+            }
+            lua.globals().set("redis", redis_table)?;
+            lua.load(functions::library_body(code)).exec()
+        });
+        if let Err(e) = result {
+            return Err(format!("ERR Error registering functions: {}", e));
+        }
+
+        let functions = registered.into_inner();
+        if functions.is_empty() {
+            return Err("ERR No functions registered".to_string());
+        }
+        Ok(Library {
+            name: header.name,
+            engine: header.engine,
+            code: code.to_string(),
+            functions,
+        })
+    }
+
+    /// The function and callback of one `redis.register_function` call:
+    /// `(name, callback)`, or a table with `function_name`, `callback` and
+    /// optionally `flags` and `description`
+    #[cfg(feature = "lua")]
+    pub(super) fn function_registration(
+        args: mlua::MultiValue,
+    ) -> mlua::Result<(FunctionInfo, mlua::Function)> {
+        use mlua::Value as LuaValue;
+
+        let error = |message: &str| mlua::Error::RuntimeError(message.to_string());
+        let args: Vec<LuaValue> = args.into_iter().collect();
+        let (name, callback, flags, description) = match args.as_slice() {
+            [LuaValue::String(name), LuaValue::Function(callback)] => {
+                (name.to_string_lossy(), callback.clone(), Vec::new(), None)
+            }
+            [LuaValue::Table(table)] => {
+                let mut name = None;
+                let mut callback = None;
+                let mut flags = Vec::new();
+                let mut description = None;
+                for pair in table.clone().pairs::<String, LuaValue>() {
+                    let (key, value) = pair?;
+                    match (key.as_str(), value) {
+                        ("function_name", LuaValue::String(s)) => name = Some(s.to_string_lossy()),
+                        ("callback", LuaValue::Function(f)) => callback = Some(f),
+                        ("description", LuaValue::String(s)) => {
+                            description = Some(s.to_string_lossy())
+                        }
+                        ("flags", LuaValue::Table(list)) => {
+                            for flag in list.sequence_values::<String>() {
+                                flags.push(flag?);
+                            }
+                        }
+                        ("function_name" | "callback" | "description" | "flags", _) => {
+                            return Err(error(&format!(
+                                "{} argument given to redis.register_function has a wrong type",
+                                key
+                            )))
+                        }
+                        _ => {
+                            return Err(error("unknown argument given to redis.register_function"))
+                        }
+                    }
+                }
+                let name = name.ok_or_else(|| {
+                    error("redis.register_function must get a function name argument")
+                })?;
+                let callback = callback
+                    .ok_or_else(|| error("redis.register_function must get a callback argument"))?;
+                (name, callback, flags, description)
+            }
+            [_, _] => {
+                return Err(error(
+                    "redis.register_function takes a function name and a callback",
+                ))
+            }
+            _ => {
+                return Err(error(
+                    "wrong number of arguments to redis.register_function",
+                ))
+            }
+        };
+
+        if !functions::is_valid_name(&name) {
+            return Err(error(INVALID_FUNCTION_NAME));
+        }
+        if let Some(flag) = flags
+            .iter()
+            .find(|flag| !FUNCTION_FLAGS.contains(&flag.as_str()))
+        {
+            return Err(error(&format!("unknown flag given: {}", flag)));
+        }
+        Ok((
+            FunctionInfo {
+                name,
+                description,
+                flags,
+            },
+            callback,
+        ))
+    }
+}
@@ -0,0 +1,390 @@
+//! Glob patterns for KEYS, SCAN/HSCAN/ZSCAN MATCH and pattern subscriptions.
+//!
+//! A pattern is compiled once into tokens (byte classes become 256-bit
+//! sets) and matched iteratively with single-star backtracking, so a match
+//! is O(key × pattern) instead of exponential in the number of `*`.
+//! The executor keeps recently used patterns in a small LRU `GlobCache`,
+//! so a SCAN loop issuing the same MATCH every call compiles it once.
+//!
+//! Syntax: `*`, `?`, `[abc]`, `[^abc]` and ranges `[a-z]`. A pattern with
+//! an unclosed `[` matches nothing.
+//!
+//! `fuzz_against_reference` checks the matcher against the recursive
+//! definition over random pattern/key pairs, plus long star-heavy pairs
+//! that take the recursion exponential time. DEBUG STRINGMATCH-LEN runs it
+//! with the executor RNG, as Redis runs its own matcher fuzzer.
+//!
+//! # TigerStyle Invariants
+//!
+//! - `GlobPattern::compile(p).matches(k)` agrees with the recursive
+//!   definition for every pattern and key
+//! - The cache never holds more than `GLOB_CACHE_CAPACITY` patterns
+
+use crate::simulator::DeterministicRng;
+use ahash::AHashMap;
+use std::sync::Arc;
+
+/// Patterns kept by one executor's cache
+pub(crate) const GLOB_CACHE_CAPACITY: usize = 64;
+
+/// Random pairs one DEBUG STRINGMATCH-LEN checks
+pub(crate) const STRINGMATCH_FUZZ_ROUNDS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Byte(u8),
+    AnyByte,
+    Star,
+    /// Bitset of matching bytes, negation already applied
+    Class(Box<[u64; 4]>),
+}
+
+/// A compiled glob pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobPattern {
+    /// `None` when the pattern can never match (unclosed `[`)
+    tokens: Option<Vec<Token>>,
+}
+
+impl GlobPattern {
+    pub fn compile(pattern: &[u8]) -> Self {
+        let mut tokens = Vec::with_capacity(pattern.len());
+        let mut p = 0;
+        while p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    // Consecutive stars match like one
+                    if tokens.last() != Some(&Token::Star) {
+                        tokens.push(Token::Star);
+                    }
+                    p += 1;
+                }
+                b'?' => {
+                    tokens.push(Token::AnyByte);
+                    p += 1;
+                }
+                b'[' => {
+                    let Some(len) = pattern[p + 1..].iter().position(|&b| b == b']') else {
+                        return GlobPattern { tokens: None };
+                    };
+                    let end = p + 1 + len;
+                    tokens.push(Token::Class(Box::new(compile_class(&pattern[p + 1..end]))));
+                    p = end + 1;
+                }
+                byte => {
+                    tokens.push(Token::Byte(byte));
+                    p += 1;
+                }
+            }
+        }
+        GlobPattern {
+            tokens: Some(tokens),
+        }
+    }
+
+    pub fn matches(&self, text: &[u8]) -> bool {
+        let Some(tokens) = &self.tokens else {
+            return false;
+        };
+        if let [Token::Star] = tokens.as_slice() {
+            return true;
+        }
+
+        let (mut t, mut p) = (0, 0);
+        // Last star seen: (token after it, text position it now covers up to)
+        let mut backtrack: Option<(usize, usize)> = None;
+        while t < text.len() {
+            match tokens.get(p) {
+                Some(Token::Star) => {
+                    p += 1;
+                    backtrack = Some((p, t));
+                    continue;
+                }
+                Some(token) if token_matches(token, text[t]) => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            // Mismatch: let the last star swallow one more byte
+            match backtrack {
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p;
+                    t = star_t + 1;
+                }
+                None => return false,
+            }
+        }
+        // Only stars may remain
+        tokens[p..].iter().all(|token| *token == Token::Star)
+    }
+}
+
+fn token_matches(token: &Token, byte: u8) -> bool {
+    match token {
+        Token::Byte(b) => *b == byte,
+        Token::AnyByte => true,
+        Token::Class(set) => set[usize::from(byte >> 6)] & (1 << (byte & 63)) != 0,
+        Token::Star => unreachable!("stars are handled by the matcher"),
+    }
+}
+
+fn compile_class(class: &[u8]) -> [u64; 4] {
+    let (negate, class) = match class.split_first() {
+        Some((b'^', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut set = [0u64; 4];
+    let mut add = |byte: u8| set[usize::from(byte >> 6)] |= 1 << (byte & 63);
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            // A reversed range like [z-a] matches nothing
+            for byte in class[i]..=class[i + 2] {
+                add(byte);
+            }
+            i += 3;
+        } else {
+            add(class[i]);
+            i += 1;
+        }
+    }
+    if negate {
+        for word in &mut set {
+            *word = !*word;
+        }
+    }
+    set
+}
+
+/// Uncached one-off match
+pub(crate) fn glob_match(key: &[u8], pattern: &[u8]) -> bool {
+    GlobPattern::compile(pattern).matches(key)
+}
+
+/// The recursive definition the compiled matcher must agree with.
+/// Exponential in the number of `*`, so only short pairs are checked.
+fn reference_match(key: &[u8], pattern: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => key.is_empty(),
+        Some((b'*', rest)) => (0..=key.len()).any(|i| reference_match(&key[i..], rest)),
+        Some((b'?', rest)) => !key.is_empty() && reference_match(&key[1..], rest),
+        Some((b'[', rest)) => match rest.iter().position(|&b| b == b']') {
+            None => false,
+            Some(end) => {
+                let set = compile_class(&rest[..end]);
+                !key.is_empty()
+                    && token_matches(&Token::Class(Box::new(set)), key[0])
+                    && reference_match(&key[1..], &rest[end + 1..])
+            }
+        },
+        Some((&byte, rest)) => key.first() == Some(&byte) && reference_match(&key[1..], rest),
+    }
+}
+
+fn random_bytes(rng: &mut DeterministicRng, alphabet: &[u8], max_len: u64) -> Vec<u8> {
+    (0..rng.gen_range(0, max_len))
+        .map(|_| alphabet[rng.gen_range(0, alphabet.len() as u64) as usize])
+        .collect()
+}
+
+/// Check the compiled matcher against `reference_match` over `rounds`
+/// random short pattern/key pairs, each followed by a long pair only the
+/// compiled matcher can answer quickly (it must not match). Returns the
+/// first `(pattern, key)` that fails.
+pub(crate) fn fuzz_against_reference(
+    rng: &mut DeterministicRng,
+    rounds: usize,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    for _ in 0..rounds {
+        // Few letters, so that classes and ranges hit often
+        let pattern = random_bytes(rng, b"ab*?[]^-", 8);
+        let key = random_bytes(rng, b"ab-", 8);
+        if GlobPattern::compile(&pattern).matches(&key) != reference_match(&key, &pattern) {
+            return Some((pattern, key));
+        }
+
+        // `a*a*...*b` against a run of `a`: no match, after exponential
+        // work for a backtracking matcher without the single-star rule
+        let stars = rng.gen_range(1, 33) as usize;
+        let mut pattern = b"a*".repeat(stars);
+        pattern.push(b'b');
+        let key = vec![b'a'; 64 + stars];
+        if GlobPattern::compile(&pattern).matches(&key) {
+            return Some((pattern, key));
+        }
+    }
+    None
+}
+
+/// Bounded LRU of compiled patterns
+#[derive(Debug, Default)]
+pub(crate) struct GlobCache {
+    /// Pattern -> (compiled, last use)
+    entries: AHashMap<String, (Arc<GlobPattern>, u64)>,
+    clock: u64,
+}
+
+impl GlobCache {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// The compiled pattern, compiling and caching it on a miss
+    pub(crate) fn get(&mut self, pattern: &str) -> Arc<GlobPattern> {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some((compiled, last_use)) = self.entries.get_mut(pattern) {
+            *last_use = clock;
+            return compiled.clone();
+        }
+
+        if self.entries.len() >= GLOB_CACHE_CAPACITY {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_use))| *last_use)
+                .map(|(pattern, _)| pattern.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        let compiled = Arc::new(GlobPattern::compile(pattern.as_bytes()));
+        self.entries
+            .insert(pattern.to_string(), (compiled.clone(), clock));
+
+        debug_assert!(
+            self.entries.len() <= GLOB_CACHE_CAPACITY,
+            "Postcondition: glob cache must stay within capacity"
+        );
+        compiled
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn contains(&self, pattern: &str) -> bool {
+        self.entries.contains_key(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_syntax() {
+        for (pattern, key, expected) in [
+            ("*", "", true),
+            ("user:*", "user:42", true),
+            ("user:*", "admin:1", false),
+            ("*:*:name", "a:b:name", true),
+            ("h?llo", "hello", true),
+            ("h?llo", "hllo", false),
+            ("h[ae]llo", "hallo", true),
+            ("h[^e]llo", "hello", false),
+            ("h[a-c]x", "hbx", true),
+            ("h[z-a]x", "hbx", false),
+            ("h[^]x", "hqx", true),
+            ("a[bc", "ab", false),
+            ("**a**", "xa", true),
+        ] {
+            assert_eq!(
+                GlobPattern::compile(pattern.as_bytes()).matches(key.as_bytes()),
+                expected,
+                "{} against {}",
+                pattern,
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_compiled_matcher_agrees_with_reference() {
+        for seed in [0x610B, 1, 2, 3] {
+            let mismatch = fuzz_against_reference(&mut DeterministicRng::new(seed), 5_000);
+            if let Some((pattern, key)) = mismatch {
+                panic!(
+                    "seed {}: {:?} against {:?}",
+                    seed,
+                    String::from_utf8_lossy(&pattern),
+                    String::from_utf8_lossy(&key)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_debug_stringmatch_len_runs_the_fuzzer() {
+        use crate::redis::{Command, CommandExecutor, RespValue};
+
+        let mut executor = CommandExecutor::new();
+        for seed in 0..3 {
+            executor.set_rng_seed(seed);
+            assert_eq!(
+                executor.execute(&Command::DebugStringMatchLen),
+                RespValue::simple("Apparently Redis did not crash: test passed")
+            );
+        }
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = GlobCache::new();
+        for i in 0..GLOB_CACHE_CAPACITY {
+            cache.get(&format!("p{}*", i));
+        }
+        // Touch the oldest so the second oldest goes first
+        cache.get("p0*");
+        cache.get("new*");
+        assert_eq!(cache.len(), GLOB_CACHE_CAPACITY);
+        assert!(cache.contains("p0*"));
+        assert!(!cache.contains("p1*"));
+        assert!(cache.contains("new*"));
+
+        let first = cache.get("new*");
+        assert!(Arc::ptr_eq(&first, &cache.get("new*")));
+    }
+
+    #[test]
+    fn test_scan_and_keys_share_the_executor_cache() {
+        use crate::redis::{Command, CommandExecutor, RespValue, SDS};
+
+        let mut executor = CommandExecutor::new();
+        for key in ["user:1", "user:2", "order:1"] {
+            executor.execute(&Command::set(key.to_string(), SDS::from_str("v")));
+        }
+        let scan = Command::Scan {
+            cursor: 0,
+            pattern: Some("user:*".to_string()),
+            count: Some(10),
+            type_filter: None,
+        };
+        match executor.execute(&scan) {
+            RespValue::Array(Some(mut reply)) => {
+                // SCAN pages in position order, not name order
+                let Some(RespValue::Array(Some(mut keys))) = reply.pop() else {
+                    panic!("SCAN returned no key array");
+                };
+                keys.sort_by_key(|k| format!("{:?}", k));
+                assert_eq!(
+                    keys,
+                    vec![
+                        RespValue::BulkString(Some(b"user:1".to_vec())),
+                        RespValue::BulkString(Some(b"user:2".to_vec())),
+                    ]
+                );
+            }
+            other => panic!("SCAN returned {:?}", other),
+        }
+        executor.execute(&Command::Keys("user:*".to_string()));
+        executor.execute(&Command::Keys("order:?".to_string()));
+
+        let cache = executor.glob_cache.lock();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains("user:*") && cache.contains("order:?"));
+    }
+}
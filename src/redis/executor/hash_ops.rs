@@ -1,7 +1,11 @@
 //! Hash command implementations for CommandExecutor.
 //!
-//! Handles: HSET, HGET, HDEL, HGETALL, HKEYS, HVALS, HLEN, HEXISTS, HINCRBY
+//! Handles: HSET, HSETNX, HGET, HMGET, HDEL, HGETALL, HKEYS, HVALS, HLEN,
+//! HEXISTS, HSTRLEN, HINCRBY, HINCRBYFLOAT, HRANDFIELD. Field TTLs are in
+//! `hash_ttl_ops.rs`; writes here drop expired fields first, and only the
+//! increments keep a field's TTL.
 
+use super::string_ops::format_float;
 use super::CommandExecutor;
 use crate::redis::data::{RedisHash, Value, SDS};
 use crate::redis::resp::RespValue;
@@ -12,6 +16,7 @@ impl CommandExecutor {
             self.data.remove(key);
             self.expirations.remove(key);
         }
+        self.expire_hash_fields(key);
         let hash = self
             .data
             .entry(key.to_string())
@@ -113,7 +118,7 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_hkeys(&mut self, key: &str) -> RespValue {
+    pub(crate) fn execute_hkeys(&mut self, key: &str) -> RespValue {
         match self.get_value(key) {
             Some(Value::Hash(h)) => {
                 let hash_keys = h.keys();
@@ -137,7 +142,7 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_hvals(&mut self, key: &str) -> RespValue {
+    pub(crate) fn execute_hvals(&mut self, key: &str) -> RespValue {
         match self.get_value(key) {
             Some(Value::Hash(h)) => {
                 let hash_vals = h.values();
@@ -204,6 +209,7 @@ impl CommandExecutor {
             self.data.remove(key);
             self.expirations.remove(key);
         }
+        self.expire_hash_fields(key);
 
         // Check if key exists and is wrong type before inserting
         if let Some(existing) = self.data.get(key) {
@@ -239,7 +245,7 @@ impl CommandExecutor {
                     None => return RespValue::err("ERR increment or decrement would overflow"),
                 };
 
-                h.set(field.clone(), SDS::from_str(&new_value.to_string()));
+                h.set_keep_ttl(field.clone(), SDS::from_str(&new_value.to_string()));
 
                 // TigerStyle: Assert invariants after mutation
                 debug_assert!(
@@ -261,4 +267,161 @@ impl CommandExecutor {
             }
         }
     }
+
+    /// The hash at `key` for a write, created empty if missing; `None` when
+    /// the key holds another type
+    fn hash_for_write(&mut self, key: &str) -> Option<&mut RedisHash> {
+        if self.is_expired(key) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+        self.expire_hash_fields(key);
+        match self
+            .data
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Hash(RedisHash::new()))
+        {
+            Value::Hash(h) => Some(h),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn execute_hsetnx(&mut self, key: &str, field: &SDS, value: &SDS) -> RespValue {
+        let Some(h) = self.hash_for_write(key) else {
+            return RespValue::err(
+                "WRONGTYPE Operation against a key holding the wrong kind of value",
+            );
+        };
+        if h.exists(field) {
+            return RespValue::Integer(0);
+        }
+        h.set(field.clone(), value.clone());
+        debug_assert!(
+            h.get(field) == Some(value),
+            "Postcondition: HSETNX must store a new field"
+        );
+        RespValue::Integer(1)
+    }
+
+    pub(super) fn execute_hmget(&mut self, key: &str, fields: &[SDS]) -> RespValue {
+        match self.get_value(key) {
+            Some(Value::Hash(h)) => RespValue::Array(Some(
+                fields
+                    .iter()
+                    .map(|field| RespValue::BulkString(h.get(field).map(|v| v.as_bytes().to_vec())))
+                    .collect(),
+            )),
+            Some(_) => {
+                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+            }
+            None => RespValue::Array(Some(vec![RespValue::BulkString(None); fields.len()])),
+        }
+    }
+
+    pub(crate) fn execute_hstrlen(&mut self, key: &str, field: &SDS) -> RespValue {
+        match self.get_value(key) {
+            Some(Value::Hash(h)) => RespValue::Integer(h.get(field).map_or(0, |v| v.len()) as i64),
+            Some(_) => {
+                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+            }
+            None => RespValue::Integer(0),
+        }
+    }
+
+    pub(super) fn execute_hincrbyfloat(
+        &mut self,
+        key: &str,
+        field: &SDS,
+        increment: f64,
+    ) -> RespValue {
+        debug_assert!(
+            increment.is_finite(),
+            "Precondition: increment must be finite"
+        );
+
+        let Some(h) = self.hash_for_write(key) else {
+            return RespValue::err(
+                "WRONGTYPE Operation against a key holding the wrong kind of value",
+            );
+        };
+        let current = match h.get(field) {
+            Some(v) => match v.to_string().parse::<f64>() {
+                Ok(n) if !n.is_nan() => n,
+                _ => return RespValue::err("ERR hash value is not a float"),
+            },
+            None => 0.0,
+        };
+        let new_value = current + increment;
+        if !new_value.is_finite() {
+            return RespValue::err("ERR increment would produce NaN or Infinity");
+        }
+        let formatted = format_float(new_value);
+        h.set_keep_ttl(field.clone(), SDS::from_str(&formatted));
+
+        debug_assert!(
+            h.get(field)
+                .is_some_and(|v| v.to_string().parse::<f64>().is_ok()),
+            "Postcondition: HINCRBYFLOAT must store a float"
+        );
+        RespValue::BulkString(Some(formatted.into_bytes()))
+    }
+
+    /// HRANDFIELD: a random field without a count; with one, SRANDMEMBER
+    /// count semantics (distinct when positive, repeats when negative),
+    /// optionally followed by each field's value.
+    pub(super) fn execute_hrandfield(
+        &mut self,
+        key: &str,
+        count: Option<i64>,
+        with_values: bool,
+    ) -> RespValue {
+        debug_assert!(
+            count.is_some() || !with_values,
+            "Precondition: WITHVALUES requires a count"
+        );
+        if count == Some(i64::MIN) {
+            return RespValue::err(format!(
+                "ERR value is out of range, value must between {} and {}",
+                -i64::MAX,
+                i64::MAX
+            ));
+        }
+        // The reply holds two entries per pick
+        if with_values && count.is_some_and(|n| n < -(i64::MAX / 2)) {
+            return RespValue::err("ERR value is out of range");
+        }
+        // Field order, so picks replay under a seed
+        let mut fields = match self.get_value(key) {
+            Some(Value::Hash(h)) => h.get_all(),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None if count.is_some() => return RespValue::Array(Some(Vec::new())),
+            None => return RespValue::BulkString(None),
+        };
+        debug_assert!(
+            !fields.is_empty(),
+            "Invariant: stored hashes are never empty"
+        );
+        fields.sort_unstable_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+        let picked = match count {
+            None => {
+                let i = self.rng.gen_range(0, fields.len() as u64) as usize;
+                let (field, _) = fields.swap_remove(i);
+                return RespValue::BulkString(Some(field.as_bytes().to_vec()));
+            }
+            Some(n) => self.sample_with_count(fields, n),
+        };
+        let mut elements = Vec::with_capacity(picked.len() * if with_values { 2 } else { 1 });
+        for (field, value) in picked {
+            elements.push(RespValue::BulkString(Some(field.as_bytes().to_vec())));
+            if with_values {
+                elements.push(RespValue::BulkString(Some(value.as_bytes().to_vec())));
+            }
+        }
+        RespValue::Array(Some(elements))
+    }
 }
@@ -0,0 +1,253 @@
+//! Hash field TTL commands for CommandExecutor.
+//!
+//! Handles: HEXPIRE, HPEXPIRE, HEXPIREAT, HPEXPIREAT, HTTL, HPTTL,
+//! HEXPIRETIME, HPEXPIRETIME, HPERSIST. Field deadlines live in the hash
+//! itself (see `RedisHash`), in milliseconds on the executor clock like the
+//! key deadlines in `expirations`.
+//!
+//! Expired fields go lazily, whenever their hash is read or written, and
+//! actively, when an eviction pass walks `field_ttl_keys`. A hash emptied
+//! either way is deleted like any other empty collection.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Replies hold one entry per requested field, in request order
+//! - Every hash with field deadlines is in `field_ttl_keys`; the set may
+//!   still name keys that lost theirs until the next eviction pass
+
+use super::keyspace_stats::KeyFootprint;
+use super::CommandExecutor;
+use crate::redis::command::{ExpireCondition, FieldTtlForm};
+use crate::redis::data::{Value, SDS};
+use crate::redis::resp::RespValue;
+
+/// Latest field deadline Redis accepts, in Unix milliseconds
+const MAX_FIELD_DEADLINE_MS: i64 = (1 << 46) - 1;
+
+/// Per-field reply for a field (or key) that does not exist
+const NO_FIELD: i64 = -2;
+
+fn no_fields(fields: &[SDS]) -> RespValue {
+    RespValue::Array(Some(vec![RespValue::Integer(NO_FIELD); fields.len()]))
+}
+
+impl CommandExecutor {
+    /// Delete the expired fields of the hash at `key`, and the key itself if
+    /// that empties it, returning how many fields went. Keyspace stats are
+    /// the caller's job; `execute()` accounts for every key a command names.
+    pub(super) fn expire_hash_fields(&mut self, key: &str) -> usize {
+        if self.field_ttl_keys.is_empty() || !self.field_ttl_keys.contains(key) {
+            return 0;
+        }
+        let now = self.current_time.as_millis();
+        let Some(Value::Hash(h)) = self.data.get_mut(key) else {
+            return 0;
+        };
+        let removed = h.remove_expired_fields(now);
+        if h.is_empty() {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+        if removed > 0 {
+            self.client_tracking.invalidate_key(key);
+        }
+        removed
+    }
+
+    /// Active expiry for hash fields: delete expired fields across every
+    /// tracked hash, keeping the stats in sync, and stop tracking keys left
+    /// without deadlines. Returns how many keys were deleted for being
+    /// emptied.
+    pub(super) fn evict_expired_hash_fields(&mut self) -> usize {
+        let now = self.current_time.as_millis();
+        let data = &mut self.data;
+        let expirations = &mut self.expirations;
+        let access_times = &mut self.access_times;
+        let stats = &mut self.keyspace_stats;
+        let tracking = &self.client_tracking;
+        let mut emptied = 0;
+        self.field_ttl_keys.retain(|key| {
+            let Some(value) = data.get_mut(key) else {
+                return false;
+            };
+            let before = KeyFootprint::of(value);
+            let Value::Hash(h) = value else {
+                return false;
+            };
+            if h.remove_expired_fields(now) == 0 {
+                return h.has_field_expiries();
+            }
+            tracking.invalidate_key(key);
+            if h.is_empty() {
+                data.remove(key);
+                expirations.remove(key);
+                access_times.remove(key);
+                stats.apply(before, None);
+                emptied += 1;
+                return false;
+            }
+            let tracked = h.has_field_expiries();
+            stats.apply(before, KeyFootprint::of(value));
+            tracked
+        });
+
+        debug_assert!(
+            self.field_ttl_keys.iter().all(
+                |key| matches!(self.data.get(key), Some(Value::Hash(h)) if h.has_field_expiries())
+            ),
+            "Postcondition violated: only hashes with field deadlines stay tracked"
+        );
+        emptied
+    }
+
+    pub(super) fn execute_hexpire(
+        &mut self,
+        key: &str,
+        time: i64,
+        form: FieldTtlForm,
+        condition: Option<ExpireCondition>,
+        fields: &[SDS],
+    ) -> RespValue {
+        debug_assert!(!fields.is_empty(), "Precondition: at least one field");
+        let name = match (form.millis, form.absolute) {
+            (false, false) => "hexpire",
+            (true, false) => "hpexpire",
+            (false, true) => "hexpireat",
+            (true, true) => "hpexpireat",
+        };
+        let invalid = || RespValue::err(format!("ERR invalid expire time in '{}' command", name));
+
+        if time < 0 {
+            return RespValue::err("ERR invalid expire time, must be >= 0");
+        }
+        let time_ms = if form.millis {
+            time
+        } else if time > MAX_FIELD_DEADLINE_MS / 1000 {
+            return invalid();
+        } else {
+            time * 1000
+        };
+        let now_ms = self.current_time.as_millis() as i64;
+        let basetime_ms = if form.absolute {
+            0
+        } else {
+            self.simulation_start_epoch_ms.saturating_add(now_ms)
+        };
+        if time_ms > MAX_FIELD_DEADLINE_MS.saturating_sub(basetime_ms) {
+            return invalid();
+        }
+        // The deadline on the executor clock, possibly already past
+        let deadline = time_ms + basetime_ms - self.simulation_start_epoch_ms;
+
+        let h = match self.get_value_mut(key) {
+            None => return no_fields(fields),
+            Some(Value::Hash(h)) => h,
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+        };
+        let replies = fields
+            .iter()
+            .map(|field| {
+                let field_str = field.to_string();
+                if !h.exists(field) {
+                    return RespValue::Integer(NO_FIELD);
+                }
+                let current = h.field_expiry(&field_str).map(|d| d as i64);
+                let allowed = match condition {
+                    None => true,
+                    Some(ExpireCondition::Nx) => current.is_none(),
+                    Some(ExpireCondition::Xx) => current.is_some(),
+                    // No TTL counts as never expiring
+                    Some(ExpireCondition::Gt) => current.is_some_and(|c| deadline > c),
+                    Some(ExpireCondition::Lt) => current.is_none_or(|c| deadline < c),
+                };
+                if !allowed {
+                    RespValue::Integer(0)
+                } else if deadline <= now_ms {
+                    h.delete(field);
+                    RespValue::Integer(2)
+                } else {
+                    h.set_field_expiry(&field_str, deadline as u64);
+                    RespValue::Integer(1)
+                }
+            })
+            .collect();
+
+        // Redis auto-deletes empty hashes
+        if matches!(self.data.get(key), Some(Value::Hash(h)) if h.is_empty()) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+        RespValue::Array(Some(replies))
+    }
+
+    pub(super) fn execute_httl(
+        &mut self,
+        key: &str,
+        form: FieldTtlForm,
+        fields: &[SDS],
+    ) -> RespValue {
+        let now_ms = self.current_time.as_millis() as i64;
+        let epoch_ms = self.simulation_start_epoch_ms;
+        let h = match self.get_value(key) {
+            None => return no_fields(fields),
+            Some(Value::Hash(h)) => h,
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+        };
+        let replies = fields
+            .iter()
+            .map(|field| {
+                if !h.exists(field) {
+                    return RespValue::Integer(NO_FIELD);
+                }
+                let Some(deadline) = h.field_expiry(&field.to_string()) else {
+                    return RespValue::Integer(-1);
+                };
+                let deadline = deadline as i64;
+                debug_assert!(
+                    deadline > now_ms,
+                    "Invariant: expired fields are gone before reads"
+                );
+                RespValue::Integer(match (form.millis, form.absolute) {
+                    (false, false) => (deadline - now_ms + 999) / 1000,
+                    (true, false) => deadline - now_ms,
+                    (false, true) => epoch_ms.saturating_add(deadline) / 1000,
+                    (true, true) => epoch_ms.saturating_add(deadline),
+                })
+            })
+            .collect();
+        RespValue::Array(Some(replies))
+    }
+
+    pub(super) fn execute_hpersist(&mut self, key: &str, fields: &[SDS]) -> RespValue {
+        let h = match self.get_value_mut(key) {
+            None => return no_fields(fields),
+            Some(Value::Hash(h)) => h,
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+        };
+        let replies = fields
+            .iter()
+            .map(|field| {
+                RespValue::Integer(if !h.exists(field) {
+                    NO_FIELD
+                } else if h.persist_field(&field.to_string()) {
+                    1
+                } else {
+                    -1
+                })
+            })
+            .collect();
+        RespValue::Array(Some(replies))
+    }
+}
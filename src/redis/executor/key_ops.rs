@@ -1,20 +1,44 @@
 //! Key command implementations for CommandExecutor.
 //!
-//! Handles: DEL, EXISTS, TYPE, KEYS, FLUSHDB, FLUSHALL, EXPIRE, EXPIREAT,
-//! PEXPIREAT, TTL, PTTL, PERSIST
+//! Handles: DEL, EXISTS, TOUCH, TYPE, KEYS, FLUSHDB, FLUSHALL, EXPIRE,
+//! EXPIREAT, PEXPIREAT, TTL, PTTL, PERSIST
+//!
+//! Access times: `execute()` stamps every key a command looks up with the
+//! virtual clock, except for the commands Redis looks keys up with
+//! LOOKUP_NOTOUCH (`is_notouch`), so OBJECT IDLETIME reports the time since
+//! the last real access. TOUCH does nothing but that lookup. Keys installed
+//! without a command (bulk loads, fixtures) are idle 0 until first accessed.
 //!
 //! # TigerStyle Invariants
 //!
 //! - DEL removes keys from data and expirations
-//! - EXISTS count is always in range [0, keys.len()]
+//! - EXISTS and TOUCH counts are always in range [0, keys.len()]
 //! - TTL/PTTL returns -2 (not exists), -1 (no expiry), or >= 0 (remaining)
 //! - FLUSH clears data and expirations completely
 
 use super::CommandExecutor;
+use crate::redis::command::Command;
 use crate::redis::data::Value;
 use crate::redis::resp::RespValue;
 use crate::simulator::VirtualTime;
 
+/// Commands that read keys without counting as an access
+pub(super) fn is_notouch(cmd: &Command) -> bool {
+    matches!(
+        cmd,
+        Command::Exists(_)
+            | Command::TypeOf(_)
+            | Command::Ttl(_)
+            | Command::Pttl(_)
+            | Command::ObjectEncoding(_)
+            | Command::ObjectRefCount(_)
+            | Command::ObjectIdleTime(_)
+            | Command::ObjectFreq(_)
+            | Command::MemoryUsage { .. }
+            | Command::DebugObject(_)
+    )
+}
+
 impl CommandExecutor {
     pub(super) fn execute_del(&mut self, keys: &[String]) -> RespValue {
         // TigerStyle: Capture pre-state for postcondition
@@ -71,6 +95,43 @@ impl CommandExecutor {
         RespValue::Integer(count as i64)
     }
 
+    pub(super) fn execute_touch(&mut self, keys: &[String]) -> RespValue {
+        let mut count = 0;
+        for key in keys {
+            if self.get_value(key).is_some() {
+                self.record_access(key);
+                count += 1;
+            }
+        }
+
+        // TigerStyle: Postcondition - count must be valid
+        debug_assert!(
+            count <= keys.len(),
+            "Postcondition violated: TOUCH count cannot exceed input keys count"
+        );
+
+        RespValue::Integer(count as i64)
+    }
+
+    /// Stamp `key` as accessed now
+    pub(crate) fn record_access(&mut self, key: &str) {
+        let now = self.current_time;
+        match self.access_times.get_mut(key) {
+            Some(at) => *at = now,
+            None => {
+                self.access_times.insert(key.to_string(), now);
+            }
+        }
+    }
+
+    /// Whole seconds since `key` was last accessed (OBJECT IDLETIME)
+    pub(super) fn idle_time_secs(&self, key: &str) -> u64 {
+        let now = self.current_time.as_millis();
+        self.access_times
+            .get(key)
+            .map_or(0, |at| now.saturating_sub(at.as_millis()) / 1000)
+    }
+
     pub(super) fn execute_typeof(&mut self, key: &str) -> RespValue {
         match self.get_value(key) {
             Some(Value::String(_)) => RespValue::simple("string"),
@@ -78,16 +139,18 @@ impl CommandExecutor {
             Some(Value::Set(_)) => RespValue::simple("set"),
             Some(Value::Hash(_)) => RespValue::simple("hash"),
             Some(Value::SortedSet(_)) => RespValue::simple("zset"),
+            Some(Value::Stream(_)) => RespValue::simple("stream"),
             Some(Value::Null) => RespValue::simple("none"),
             None => RespValue::simple("none"),
         }
     }
 
     pub(super) fn execute_keys(&self, pattern: &str) -> RespValue {
+        let glob = self.glob_pattern(pattern);
         let keys: Vec<RespValue> = self
             .data
             .keys()
-            .filter(|k| !self.is_expired(k) && self.matches_glob_pattern(k, pattern))
+            .filter(|k| !self.is_expired(k) && glob.matches(k.as_bytes()))
             .map(|k| RespValue::BulkString(Some(k.as_bytes().to_vec())))
             .collect();
         RespValue::Array(Some(keys))
@@ -96,6 +159,8 @@ impl CommandExecutor {
     pub(super) fn execute_flush(&mut self) -> RespValue {
         self.data.clear();
         self.expirations.clear();
+        self.access_times.clear();
+        self.keyspace_stats.clear();
 
         // TigerStyle: Postconditions - all state must be cleared
         debug_assert!(
@@ -125,12 +190,8 @@ impl CommandExecutor {
             return RespValue::err("ERR invalid expire time in 'expire' command");
         }
         // Also check if seconds*1000 + basetime_ms overflows
-        let expire_ms = seconds.saturating_mul(1000);
-        if expire_ms > 0 {
-            let basetime_ms = self.simulation_start_epoch_ms.saturating_add(self.current_time.as_millis() as i64);
-            if expire_ms > i64::MAX - basetime_ms {
-                return RespValue::err("ERR invalid expire time in 'expire' command");
-            }
+        if self.relative_expire_overflows(seconds.saturating_mul(1000)) {
+            return RespValue::err("ERR invalid expire time in 'expire' command");
         }
         if self.is_expired(key) || !self.data.contains_key(key) {
             return RespValue::Integer(0);
@@ -194,11 +255,8 @@ impl CommandExecutor {
         lt: bool,
     ) -> RespValue {
         // Reject values that would overflow when adding basetime
-        if milliseconds > 0 {
-            let basetime_ms = self.simulation_start_epoch_ms.saturating_add(self.current_time.as_millis() as i64);
-            if milliseconds > i64::MAX - basetime_ms {
-                return RespValue::err("ERR invalid expire time in 'pexpire' command");
-            }
+        if self.relative_expire_overflows(milliseconds) {
+            return RespValue::err("ERR invalid expire time in 'pexpire' command");
         } else if milliseconds < i64::MIN / 2 {
             return RespValue::err("ERR invalid expire time in 'pexpire' command");
         }
@@ -244,7 +302,7 @@ impl CommandExecutor {
         RespValue::Integer(1)
     }
 
-    pub(super) fn execute_expiretime(&self, key: &str) -> RespValue {
+    pub(crate) fn execute_expiretime(&self, key: &str) -> RespValue {
         if self.is_expired(key) || !self.data.contains_key(key) {
             return RespValue::Integer(-2);
         }
@@ -260,7 +318,7 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_pexpiretime(&self, key: &str) -> RespValue {
+    pub(crate) fn execute_pexpiretime(&self, key: &str) -> RespValue {
         if self.is_expired(key) || !self.data.contains_key(key) {
             return RespValue::Integer(-2);
         }
@@ -275,7 +333,7 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_expireat(&mut self, key: &str, timestamp: i64) -> RespValue {
+    pub(crate) fn execute_expireat(&mut self, key: &str, timestamp: i64) -> RespValue {
         if self.is_expired(key) || !self.data.contains_key(key) {
             RespValue::Integer(0)
         } else {
@@ -300,7 +358,7 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_pexpireat(&mut self, key: &str, timestamp_millis: i64) -> RespValue {
+    pub(crate) fn execute_pexpireat(&mut self, key: &str, timestamp_millis: i64) -> RespValue {
         if self.is_expired(key) || !self.data.contains_key(key) {
             RespValue::Integer(0)
         } else {
@@ -323,12 +381,22 @@ impl CommandExecutor {
         }
     }
 
+    /// Whether an expire `ms` from now lands past the largest Unix time in
+    /// ms, which Redis rejects as an invalid expire time
+    pub(super) fn relative_expire_overflows(&self, ms: i64) -> bool {
+        let basetime_ms = self
+            .simulation_start_epoch_ms
+            .saturating_add(self.current_time.as_millis() as i64);
+        ms > 0 && basetime_ms.checked_add(ms).is_none()
+    }
+
     pub(super) fn execute_ttl(&self, key: &str) -> RespValue {
         let result = if self.is_expired(key) || !self.data.contains_key(key) {
             -2i64 // Key does not exist
         } else if let Some(expiration) = self.expirations.get(key) {
-            let remaining_ms = expiration.as_millis() as i64 - self.current_time.as_millis() as i64;
-            ((remaining_ms + 999) / 1000).max(0)
+            let remaining_ms = (expiration.as_millis() as i64)
+                .saturating_sub(self.current_time.as_millis() as i64);
+            (remaining_ms.saturating_add(999) / 1000).max(0)
         } else {
             -1i64 // Key exists but has no associated expire
         };
@@ -343,7 +411,8 @@ impl CommandExecutor {
         let result = if self.is_expired(key) || !self.data.contains_key(key) {
             -2i64 // Key does not exist
         } else if let Some(expiration) = self.expirations.get(key) {
-            let remaining = expiration.as_millis() as i64 - self.current_time.as_millis() as i64;
+            let remaining = (expiration.as_millis() as i64)
+                .saturating_sub(self.current_time.as_millis() as i64);
             remaining.max(0)
         } else {
             -1i64 // Key exists but has no associated expire
@@ -0,0 +1,460 @@
+//! Incrementally maintained keyspace statistics (DEBUG KEYSTATS).
+//!
+//! `CommandExecutor::execute()` snapshots the footprint of every key a command
+//! touches before dispatch and applies the delta afterwards, so per-type
+//! counts and size totals are O(keys touched) per command instead of a full
+//! scan at query time. The TTL histogram and the expiry outlook (the TTL
+//! manager's `ttl.expiring_keys` gauges) are derived when they are built,
+//! because remaining TTLs move with the clock, by walking the deadline index
+//! in `expirations` up to the furthest bound rather than every key.
+//!
+//! "Size" is the byte length for strings and the element count for
+//! collections, both of which are O(1) to read.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Counts and size totals never underflow
+//! - Stats always equal a full recount of `data` (checked in debug builds by
+//!   `CommandExecutor::verify_invariants`)
+
+use crate::redis::data::Value;
+use super::expirations::Expirations;
+use crate::simulator::VirtualTime;
+use ahash::AHashMap;
+
+/// Value types tracked by the keyspace statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    List,
+    Set,
+    Hash,
+    SortedSet,
+    Stream,
+}
+
+impl ValueKind {
+    pub const ALL: [ValueKind; 6] = [
+        ValueKind::String,
+        ValueKind::List,
+        ValueKind::Set,
+        ValueKind::Hash,
+        ValueKind::SortedSet,
+        ValueKind::Stream,
+    ];
+
+    /// Name used by TYPE and in the DEBUG KEYSTATS report
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueKind::String => "string",
+            ValueKind::List => "list",
+            ValueKind::Set => "set",
+            ValueKind::Hash => "hash",
+            ValueKind::SortedSet => "zset",
+            ValueKind::Stream => "stream",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Type and size of a single stored value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyFootprint {
+    kind: ValueKind,
+    size: u64,
+}
+
+impl KeyFootprint {
+    /// Footprint of a value, or `None` for `Value::Null` (not a real key)
+    pub(crate) fn of(value: &Value) -> Option<Self> {
+        let (kind, size) = match value {
+            Value::String(s) => (ValueKind::String, s.len()),
+            Value::List(l) => (ValueKind::List, l.len()),
+            Value::Set(s) => (ValueKind::Set, s.len()),
+            Value::Hash(h) => (ValueKind::Hash, h.len()),
+            Value::SortedSet(z) => (ValueKind::SortedSet, z.len()),
+            Value::Stream(s) => (ValueKind::Stream, s.len()),
+            Value::Null => return None,
+        };
+        Some(KeyFootprint {
+            kind,
+            size: size as u64,
+        })
+    }
+
+    pub(crate) fn kind(self) -> ValueKind {
+        self.kind
+    }
+}
+
+/// Per-type key counts and size totals
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyspaceStats {
+    counts: [u64; 6],
+    sizes: [u64; 6],
+}
+
+impl KeyspaceStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recount from scratch (used to construct and to verify the incremental stats)
+    pub(crate) fn from_data(data: &AHashMap<String, Value>) -> Self {
+        let mut stats = KeyspaceStats::new();
+        for value in data.values() {
+            stats.apply(None, KeyFootprint::of(value));
+        }
+        stats
+    }
+
+    /// Replace `before` with `after` for a single key
+    pub(crate) fn apply(&mut self, before: Option<KeyFootprint>, after: Option<KeyFootprint>) {
+        if before == after {
+            return;
+        }
+        if let Some(fp) = before {
+            let i = fp.kind.index();
+            debug_assert!(self.counts[i] > 0, "Precondition: {} count underflow", fp.kind.name());
+            debug_assert!(self.sizes[i] >= fp.size, "Precondition: {} size underflow", fp.kind.name());
+            self.counts[i] = self.counts[i].saturating_sub(1);
+            self.sizes[i] = self.sizes[i].saturating_sub(fp.size);
+        }
+        if let Some(fp) = after {
+            let i = fp.kind.index();
+            self.counts[i] = self.counts[i].saturating_add(1);
+            self.sizes[i] = self.sizes[i].saturating_add(fp.size);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = KeyspaceStats::new();
+    }
+
+    /// Number of keys holding the given type
+    pub fn count(&self, kind: ValueKind) -> u64 {
+        self.counts[kind.index()]
+    }
+
+    /// Sum of sizes (bytes for strings, elements for collections) for the given type
+    pub fn total_size(&self, kind: ValueKind) -> u64 {
+        self.sizes[kind.index()]
+    }
+
+    /// Total number of keys across all types
+    pub fn total_keys(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// Upper bounds (exclusive, in ms) of the TTL histogram buckets. Keys with a
+/// remaining TTL at or above the last bound fall into a final overflow bucket.
+pub const TTL_BUCKET_BOUNDS_MS: [u64; 6] = [1_000, 10_000, 60_000, 600_000, 3_600_000, 86_400_000];
+
+const TTL_BUCKET_NAMES: [&str; 7] = [
+    "ttl_lt_1s",
+    "ttl_lt_10s",
+    "ttl_lt_1m",
+    "ttl_lt_10m",
+    "ttl_lt_1h",
+    "ttl_lt_1d",
+    "ttl_ge_1d",
+];
+
+/// Point-in-time DEBUG KEYSTATS report.
+///
+/// Rendered as `name:value` lines so the sharded server can parse, sum and
+/// re-render per-shard reports without a dedicated message type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyStatsReport {
+    pub counts: [u64; 6],
+    pub sizes: [u64; 6],
+    pub volatile_keys: u64,
+    pub ttl_buckets: [u64; 7],
+}
+
+impl KeyStatsReport {
+    pub(crate) fn build(
+        stats: &KeyspaceStats,
+        expirations: &Expirations,
+        now: VirtualTime,
+    ) -> Self {
+        let mut report = KeyStatsReport {
+            counts: stats.counts,
+            sizes: stats.sizes,
+            volatile_keys: expirations.len() as u64,
+            ttl_buckets: [0; 7],
+        };
+        // Walk the deadlines below the last bound; the rest overflow
+        let last_bound = TTL_BUCKET_BOUNDS_MS[TTL_BUCKET_BOUNDS_MS.len() - 1];
+        let mut bounded = 0;
+        for (deadline, keys) in expirations.counts(..horizon_end(now, last_bound)) {
+            let remaining = deadline.as_millis().saturating_sub(now.as_millis());
+            let bucket = TTL_BUCKET_BOUNDS_MS
+                .iter()
+                .position(|&bound| remaining < bound)
+                .expect("Invariant: deadlines below the last bound fall in a bounded bucket");
+            report.ttl_buckets[bucket] += keys;
+            bounded += keys;
+        }
+        report.ttl_buckets[TTL_BUCKET_BOUNDS_MS.len()] = report.volatile_keys - bounded;
+
+        debug_assert_eq!(
+            report.ttl_buckets.iter().sum::<u64>(),
+            report.volatile_keys,
+            "Postcondition: every volatile key lands in exactly one TTL bucket"
+        );
+        report
+    }
+
+    pub fn total_keys(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Fold another shard's report into this one
+    pub fn merge(&mut self, other: &KeyStatsReport) {
+        for i in 0..self.counts.len() {
+            self.counts[i] = self.counts[i].saturating_add(other.counts[i]);
+            self.sizes[i] = self.sizes[i].saturating_add(other.sizes[i]);
+        }
+        self.volatile_keys = self.volatile_keys.saturating_add(other.volatile_keys);
+        for i in 0..self.ttl_buckets.len() {
+            self.ttl_buckets[i] = self.ttl_buckets[i].saturating_add(other.ttl_buckets[i]);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::with_capacity(512);
+        out.push_str(&format!("keys:{}\r\n", self.total_keys()));
+        out.push_str(&format!("volatile_keys:{}\r\n", self.volatile_keys));
+        for kind in ValueKind::ALL {
+            let i = kind.index();
+            let avg = if self.counts[i] == 0 {
+                0.0
+            } else {
+                self.sizes[i] as f64 / self.counts[i] as f64
+            };
+            out.push_str(&format!("{}_keys:{}\r\n", kind.name(), self.counts[i]));
+            out.push_str(&format!("{}_size_total:{}\r\n", kind.name(), self.sizes[i]));
+            out.push_str(&format!("{}_size_avg:{:.2}\r\n", kind.name(), avg));
+        }
+        for (name, count) in TTL_BUCKET_NAMES.iter().zip(self.ttl_buckets.iter()) {
+            out.push_str(&format!("{}:{}\r\n", name, count));
+        }
+        out
+    }
+
+    /// Parse a report produced by `render()`. Derived lines (`keys`, `*_avg`)
+    /// are recomputed rather than read back.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut report = KeyStatsReport::default();
+        for line in text.lines() {
+            let (name, value) = line.split_once(':')?;
+            if name == "keys" || name.ends_with("_size_avg") {
+                continue;
+            }
+            let value: u64 = value.trim().parse().ok()?;
+            if name == "volatile_keys" {
+                report.volatile_keys = value;
+            } else if let Some(i) = TTL_BUCKET_NAMES.iter().position(|n| *n == name) {
+                report.ttl_buckets[i] = value;
+            } else if let Some(kind) = ValueKind::ALL
+                .iter()
+                .find(|k| name.strip_prefix(k.name()) == Some("_keys"))
+            {
+                report.counts[kind.index()] = value;
+            } else if let Some(kind) = ValueKind::ALL
+                .iter()
+                .find(|k| name.strip_prefix(k.name()) == Some("_size_total"))
+            {
+                report.sizes[kind.index()] = value;
+            } else {
+                return None;
+            }
+        }
+        Some(report)
+    }
+}
+
+/// End of the window `horizon_ms` long from `now`; keys due before it are inside
+fn horizon_end(now: VirtualTime, horizon_ms: u64) -> VirtualTime {
+    VirtualTime::from_millis(now.as_millis().saturating_add(horizon_ms))
+}
+
+/// Horizons of the expiry outlook, as (label, ms). Unlike the histogram
+/// buckets these are cumulative: a key expiring within 1m also counts
+/// towards 5m and 1h.
+pub const EXPIRY_HORIZONS: [(&str, u64); 3] = [("1m", 60_000), ("5m", 300_000), ("1h", 3_600_000)];
+
+/// How many keys expire within each of `EXPIRY_HORIZONS` from now.
+///
+/// Sampled periodically as gauges, a series per horizon shows an expiry
+/// wave (keys written together with the same TTL) minutes before it lands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExpiryOutlook {
+    pub volatile_keys: u64,
+    pub expiring: [u64; 3],
+}
+
+impl ExpiryOutlook {
+    /// O(distinct deadlines within the longest horizon), via the deadline
+    /// index rather than a scan of every key with a TTL
+    pub(crate) fn build(expirations: &Expirations, now: VirtualTime) -> Self {
+        let mut outlook = ExpiryOutlook {
+            volatile_keys: expirations.len() as u64,
+            expiring: [0; 3],
+        };
+        let longest = EXPIRY_HORIZONS[EXPIRY_HORIZONS.len() - 1].1;
+        for (deadline, keys) in expirations.counts(..horizon_end(now, longest)) {
+            let remaining = deadline.as_millis().saturating_sub(now.as_millis());
+            for (count, &(_, horizon)) in outlook.expiring.iter_mut().zip(&EXPIRY_HORIZONS) {
+                if remaining < horizon {
+                    *count += keys;
+                }
+            }
+        }
+
+        debug_assert!(
+            outlook.is_consistent(),
+            "Postcondition: expiry outlook must be cumulative and bounded by volatile keys"
+        );
+        outlook
+    }
+
+    /// Horizons are cumulative and no horizon counts more keys than have a TTL
+    pub fn is_consistent(&self) -> bool {
+        self.expiring.windows(2).all(|pair| pair[0] <= pair[1])
+            && self.expiring.iter().all(|&count| count <= self.volatile_keys)
+    }
+
+    /// Fold another shard's outlook into this one
+    pub fn merge(&mut self, other: &ExpiryOutlook) {
+        self.volatile_keys = self.volatile_keys.saturating_add(other.volatile_keys);
+        for (count, other) in self.expiring.iter_mut().zip(other.expiring) {
+            *count = count.saturating_add(other);
+        }
+    }
+
+    /// (label, count) per horizon, in `EXPIRY_HORIZONS` order
+    pub fn points(&self) -> impl Iterator<Item = (&'static str, u64)> {
+        EXPIRY_HORIZONS
+            .iter()
+            .map(|&(label, _)| label)
+            .zip(self.expiring)
+    }
+}
+
+/// Keys touched by a command, paired with their footprint before dispatch
+pub(crate) type StatsSnapshot = Vec<(String, Option<KeyFootprint>)>;
+
+impl super::CommandExecutor {
+    fn key_footprint(&self, key: &str) -> Option<KeyFootprint> {
+        self.data.get(key).and_then(KeyFootprint::of)
+    }
+
+    /// Capture the footprint of every key `cmd` may touch.
+    ///
+    /// EXEC, scripts and functions run their inner commands through `execute()`, which
+    /// tracks each one, so the wrapper itself must not be tracked again.
+    pub(crate) fn stats_snapshot(&self, cmd: &super::Command) -> StatsSnapshot {
+        use super::Command;
+        if matches!(
+            cmd,
+            Command::Exec
+                | Command::Eval { .. }
+                | Command::EvalSha { .. }
+                | Command::EvalRo { .. }
+                | Command::EvalShaRo { .. }
+                | Command::FCall { .. }
+                | Command::FCallRo { .. }
+        ) {
+            return Vec::new();
+        }
+        let mut keys = cmd.get_keys();
+        if keys.len() > 1 {
+            // DEL a a / MSET a 1 a 2 must only count the key once
+            keys.sort_unstable();
+            keys.dedup();
+        }
+        keys.into_iter()
+            .map(|key| {
+                let before = self.key_footprint(&key);
+                (key, before)
+            })
+            .collect()
+    }
+
+    /// Apply the delta between a snapshot and the current state, note
+    /// hashes with field deadlines for the eviction passes, and stamp the
+    /// access time of the keys that remain when `stamp_access` is set
+    pub(crate) fn stats_commit(&mut self, snapshot: StatsSnapshot, stamp_access: bool) {
+        let now = self.current_time;
+        for (key, before) in snapshot {
+            let value = self.data.get(&key);
+            self.keyspace_stats.apply(before, value.and_then(KeyFootprint::of));
+            match (value.is_some(), self.access_times.get_mut(&key)) {
+                (false, Some(_)) => {
+                    self.access_times.remove(&key);
+                }
+                (true, Some(at)) if stamp_access => *at = now,
+                (true, None) if stamp_access => {
+                    self.access_times.insert(key.clone(), now);
+                }
+                _ => {}
+            }
+            // A hash that gained field deadlines, or arrived with them
+            // (RENAME), joins the eviction passes
+            if matches!(value, Some(Value::Hash(h)) if h.has_field_expiries()) {
+                self.field_ttl_keys.insert(key);
+            }
+        }
+    }
+
+    /// Track a single-key change made outside `execute()` (fast paths)
+    pub(crate) fn stats_track_key(&mut self, key: &str, before: Option<KeyFootprint>) {
+        let after = self.key_footprint(key);
+        self.keyspace_stats.apply(before, after);
+        if self.data.contains_key(key) {
+            self.record_access(key);
+        } else {
+            self.access_times.remove(key);
+        }
+    }
+
+    /// Remove a key from data, keeping the stats and access times in sync
+    /// and invalidating it for CLIENT TRACKING (eviction paths)
+    pub(crate) fn remove_tracked(&mut self, key: &str) -> Option<Value> {
+        self.access_times.remove(key);
+        self.client_tracking.invalidate_key(key);
+        let removed = self.data.remove(key);
+        if let Some(value) = &removed {
+            self.keyspace_stats.apply(KeyFootprint::of(value), None);
+        }
+        removed
+    }
+
+    /// Incrementally maintained per-type counts and sizes
+    pub fn keyspace_stats(&self) -> &KeyspaceStats {
+        &self.keyspace_stats
+    }
+
+    /// Keys expiring within each of `EXPIRY_HORIZONS` of `now`
+    pub fn expiry_outlook(&self, now: VirtualTime) -> ExpiryOutlook {
+        ExpiryOutlook::build(&self.expirations, now)
+    }
+
+    pub(super) fn execute_debug_keystats(&self) -> crate::redis::resp::RespValue {
+        let report = KeyStatsReport::build(&self.keyspace_stats, &self.expirations, self.current_time);
+
+        debug_assert_eq!(
+            report.total_keys(),
+            self.data.values().filter(|v| !matches!(v, Value::Null)).count() as u64,
+            "Postcondition: KEYSTATS key count must match data"
+        );
+
+        crate::redis::resp::RespValue::BulkString(Some(report.render().into_bytes()))
+    }
+}
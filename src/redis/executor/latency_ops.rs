@@ -0,0 +1,187 @@
+//! LATENCY LATEST, HISTORY, RESET, DOCTOR and HELP over the shared
+//! `LatencyMonitor` (see `redis/latency.rs`).
+//!
+//! Replies follow Redis 7:
+//! - LATEST: `[event, time of the latest spike, its latency, worst latency]`
+//!   per event
+//! - HISTORY: `[time, latency]` per spike of one event, oldest first
+//! - RESET: how many events had a history
+//! - DOCTOR: a report with per-event statistics and advice, as a bulk string
+//!
+//! `record_latency` is how callers feed the monitor; it stamps spikes with
+//! the executor's clock.
+
+use super::CommandExecutor;
+use crate::redis::latency::{LatencyMonitor, LatencySeries};
+use crate::redis::resp::RespValue;
+
+const HELP: &[&str] = &[
+    "LATENCY <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "DOCTOR",
+    "    Return a human readable latency analysis report.",
+    "HISTORY <event>",
+    "    Return time-latency samples for the <event> class.",
+    "LATEST",
+    "    Return the latest latency samples for all events.",
+    "RESET [<event> ...]",
+    "    Reset latency data of one or more <event> classes.",
+    "    (default: reset all data for all event classes)",
+    "HELP",
+    "    Print this help.",
+];
+
+/// What to look at when an event spikes
+fn advice(event: &str) -> Option<&'static str> {
+    match event {
+        "command" => Some(
+            "Check for slow commands: KEYS, SORT and set algebra over big keys run in \
+             one go. Setting keys-chunk-size lets production shards run KEYS in chunks.",
+        ),
+        "fast-command" => Some(
+            "GET and SET are O(1), so spikes on the fast path point at the host: \
+             CPU contention, swapping or a noisy neighbour.",
+        ),
+        "expire-cycle" => Some(
+            "Many keys expiring in the same instant make the expire cycle slow. \
+             Add some randomness to the TTLs to spread expiries out.",
+        ),
+        "snapshot" => {
+            Some("Snapshots copy the whole keyspace. Take them less often, or from a replica.")
+        }
+        _ => None,
+    }
+}
+
+/// One DOCTOR line: spikes, their average and mean deviation, how often
+/// they came and the worst ever
+fn describe(n: usize, event: &str, series: &LatencySeries) -> String {
+    let samples = &series.samples;
+    let count = samples.len() as u64;
+    debug_assert!(
+        count > 0,
+        "Precondition: only events with spikes are described"
+    );
+
+    let avg = samples.iter().map(|s| s.latency_ms).sum::<u64>() / count;
+    let mad = samples
+        .iter()
+        .map(|s| s.latency_ms.abs_diff(avg))
+        .sum::<u64>()
+        / count;
+    let span = match (samples.front(), samples.back()) {
+        (Some(first), Some(last)) => (last.time - first.time).max(0) as u64,
+        _ => 0,
+    };
+    format!(
+        "{}. {}: {} latency spikes (average {}ms, mean deviation {}ms, period {} sec). \
+         Worst all time event {}ms.",
+        n,
+        event,
+        count,
+        avg,
+        mad,
+        span / count,
+        series.max_ms
+    )
+}
+
+fn doctor(monitor: &LatencyMonitor) -> String {
+    let events = monitor.events();
+    if events.is_empty() {
+        return if monitor.threshold_ms() == 0 {
+            "I'm sorry, Dave, I can't do that. Latency monitoring is disabled in this \
+             Redis instance. You may use \"CONFIG SET latency-monitor-threshold \
+             <milliseconds>.\" in order to enable it.\n"
+                .to_string()
+        } else {
+            "Dave, no latency spike was observed during the lifetime of this Redis \
+             instance, not in the slightest bit. I honestly think you ought to sleep \
+             tonight.\n"
+                .to_string()
+        };
+    }
+
+    let mut report = String::from(
+        "Dave, I have observed latency spikes in this Redis instance. \
+         You don't mind talking about it, do you Dave?\n\n",
+    );
+    for (i, (event, series)) in events.iter().enumerate() {
+        report.push_str(&describe(i + 1, event, series));
+        report.push('\n');
+    }
+    let advices: Vec<&str> = events
+        .iter()
+        .filter_map(|(event, _)| advice(event))
+        .collect();
+    if !advices.is_empty() {
+        report.push_str("\nI have a few advices for you:\n\n");
+        for advice in advices {
+            report.push_str("- ");
+            report.push_str(advice);
+            report.push('\n');
+        }
+    }
+    report
+}
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+impl CommandExecutor {
+    /// Report that `event` took `latency_ms`; kept if it is a spike
+    pub fn record_latency(&self, event: &str, latency_ms: u64) -> bool {
+        let now_ms = self
+            .simulation_start_epoch_ms
+            .saturating_add(self.current_time.as_millis() as i64);
+        self.latency.record(event, latency_ms, now_ms / 1000)
+    }
+
+    pub(super) fn execute_latency_latest(&self) -> RespValue {
+        let latest = self
+            .latency
+            .events()
+            .into_iter()
+            .filter_map(|(event, series)| {
+                let sample = series.latest()?;
+                Some(RespValue::Array(Some(vec![
+                    bulk(&event),
+                    RespValue::Integer(sample.time),
+                    RespValue::Integer(sample.latency_ms as i64),
+                    RespValue::Integer(series.max_ms as i64),
+                ])))
+            })
+            .collect();
+        RespValue::Array(Some(latest))
+    }
+
+    pub(super) fn execute_latency_history(&self, event: &str) -> RespValue {
+        let samples = self
+            .latency
+            .history(event)
+            .map(|series| series.samples)
+            .unwrap_or_default();
+        let history = samples
+            .iter()
+            .map(|sample| {
+                RespValue::Array(Some(vec![
+                    RespValue::Integer(sample.time),
+                    RespValue::Integer(sample.latency_ms as i64),
+                ]))
+            })
+            .collect();
+        RespValue::Array(Some(history))
+    }
+
+    pub(super) fn execute_latency_reset(&self, events: &[String]) -> RespValue {
+        RespValue::Integer(self.latency.reset(events) as i64)
+    }
+
+    pub(super) fn execute_latency_doctor(&self) -> RespValue {
+        bulk(&doctor(&self.latency))
+    }
+
+    pub(super) fn execute_latency_help(&self) -> RespValue {
+        RespValue::Array(Some(HELP.iter().map(|line| bulk(line)).collect()))
+    }
+}
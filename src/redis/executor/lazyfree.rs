@@ -0,0 +1,126 @@
+//! UNLINK and lazy free.
+//!
+//! UNLINK removes its keys at once, exactly as DEL does: the keyspace, the
+//! stats and the memory estimate lose them before the reply. Only the
+//! dropping of the values is deferred. A value whose free effort (its
+//! element count; 1 for a string) exceeds `LAZYFREE_THRESHOLD` is queued for
+//! the lazy-free worker, as Redis hands it to its lazyfree thread; smaller
+//! values cost less to drop than to queue, and are dropped inline.
+//!
+//! The executor only queues. Its host runs the worker: the simulated
+//! `RedisServer` frees a batch per timer tick, so runs replay exactly, and
+//! the production shard actors hand the queue to a blocking task on each
+//! expire cycle.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Every queued value is freed at most once: queued = freed + pending
+//! - Only values above `LAZYFREE_THRESHOLD` are queued
+
+use super::CommandExecutor;
+use crate::redis::data::Value;
+use crate::redis::resp::RespValue;
+use std::collections::VecDeque;
+
+/// Free effort above which UNLINK defers the free (Redis' LAZYFREE_THRESHOLD)
+pub const LAZYFREE_THRESHOLD: usize = 64;
+
+/// Values detached by UNLINK and not yet freed
+#[derive(Debug, Default)]
+pub(crate) struct LazyFreeState {
+    queue: VecDeque<Value>,
+    queued_total: u64,
+    freed_total: u64,
+}
+
+impl LazyFreeState {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn check(&self) {
+        debug_assert_eq!(
+            self.queued_total,
+            self.freed_total + self.queue.len() as u64,
+            "Invariant violated: lazy free must account for every queued value"
+        );
+    }
+}
+
+/// Work to free a value: its element count, 1 for a string
+fn free_effort(value: &Value) -> usize {
+    match value {
+        Value::String(_) | Value::Null => 1,
+        Value::List(l) => l.len(),
+        Value::Set(s) => s.len(),
+        Value::Hash(h) => h.len(),
+        Value::SortedSet(z) => z.len(),
+        Value::Stream(s) => s.len(),
+    }
+}
+
+impl CommandExecutor {
+    pub(super) fn execute_unlink(&mut self, keys: &[String]) -> RespValue {
+        let mut count = 0i64;
+        for key in keys {
+            if let Some(value) = self.data.remove(key) {
+                count += 1;
+                self.lazy_free(value);
+            }
+            self.expirations.remove(key);
+        }
+
+        // TigerStyle: Postconditions - unlinked keys are gone at once
+        debug_assert!(
+            count >= 0 && count <= keys.len() as i64,
+            "Postcondition violated: UNLINK count must be in [0, keys.len()]"
+        );
+        debug_assert!(
+            keys.iter()
+                .all(|k| !self.data.contains_key(k) && !self.expirations.contains_key(k)),
+            "Postcondition violated: unlinked keys must leave data and expirations"
+        );
+        self.lazyfree.check();
+
+        RespValue::Integer(count)
+    }
+
+    /// Drop a detached value, or queue it for the worker if it is large
+    fn lazy_free(&mut self, value: Value) {
+        if free_effort(&value) > LAZYFREE_THRESHOLD {
+            self.lazyfree.queue.push_back(value);
+            self.lazyfree.queued_total += 1;
+        }
+    }
+
+    /// The lazy-free worker's step: free up to `max_objects` queued values,
+    /// oldest first. Returns how many were freed.
+    pub fn lazyfree_step(&mut self, max_objects: usize) -> usize {
+        let state = &mut self.lazyfree;
+        let freed = max_objects.min(state.queue.len());
+        state.queue.drain(..freed);
+        state.freed_total += freed as u64;
+        state.check();
+        freed
+    }
+
+    /// Hand every queued value to the caller, which frees them off the
+    /// command path; they count as freed
+    pub fn take_lazyfree_queue(&mut self) -> Vec<Value> {
+        let state = &mut self.lazyfree;
+        let taken: Vec<Value> = state.queue.drain(..).collect();
+        state.freed_total += taken.len() as u64;
+        state.check();
+        taken
+    }
+
+    /// Values waiting for the worker (INFO lazyfree_pending_objects)
+    pub fn lazyfree_pending_objects(&self) -> usize {
+        self.lazyfree.queue.len()
+    }
+
+    /// Values the worker has freed (INFO lazyfreed_objects)
+    pub fn lazyfreed_objects(&self) -> u64 {
+        self.lazyfree.freed_total
+    }
+}
@@ -1,7 +1,9 @@
 //! List command implementations for CommandExecutor.
 //!
-//! Handles: LPUSH, RPUSH, LPOP, RPOP, LLEN, LINDEX, LRANGE, LSET, LTRIM,
-//! RPOPLPUSH, LMOVE
+//! Handles: LPUSH, RPUSH, LPUSHX, RPUSHX, LPOP, RPOP, LLEN, LINDEX, LRANGE,
+//! LSET, LTRIM, LINSERT, LREM, LPOS, RPOPLPUSH, LMOVE, and the non-blocking
+//! half of BLPOP, BRPOP, BLMOVE (see `redis::blocking`). Every push signals
+//! the key so blocked clients retry.
 //!
 //! # TigerStyle Invariants
 //!
@@ -10,6 +12,7 @@
 //! - LLEN: always returns non-negative
 //! - LINDEX: actual_index must be < list.len() when accessing
 //! - LRANGE: result.len() <= end - start + 1
+//! - LREM: an emptied list is deleted, like a pop
 
 use super::CommandExecutor;
 use crate::redis::data::{RedisList, Value, SDS};
@@ -43,6 +46,7 @@ impl CommandExecutor {
                     "Postcondition violated: LPUSH length must increase by pushed count"
                 );
 
+                self.blocking.signal(key);
                 RespValue::Integer(new_len)
             }
             _ => {
@@ -78,6 +82,7 @@ impl CommandExecutor {
                     "Postcondition violated: RPUSH length must increase by pushed count"
                 );
 
+                self.blocking.signal(key);
                 RespValue::Integer(new_len)
             }
             _ => {
@@ -86,6 +91,29 @@ impl CommandExecutor {
         }
     }
 
+    /// LPUSHX/RPUSHX: push only onto an existing list, replying 0 otherwise
+    pub(super) fn execute_pushx(&mut self, key: &str, values: &[SDS], left: bool) -> RespValue {
+        match self.get_value(key) {
+            Some(Value::List(_)) => {}
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => return RespValue::Integer(0),
+        }
+        let resp = if left {
+            self.execute_lpush(key, values)
+        } else {
+            self.execute_rpush(key, values)
+        };
+        debug_assert!(
+            matches!(resp, RespValue::Integer(n) if n as usize >= values.len()),
+            "Postcondition: LPUSHX/RPUSHX on a list must reply its new length"
+        );
+        resp
+    }
+
     pub(super) fn execute_lpop(&mut self, key: &str) -> RespValue {
         let result = match self.get_value_mut(key) {
             Some(Value::List(l)) => match l.lpop() {
@@ -132,6 +160,38 @@ impl CommandExecutor {
         result
     }
 
+    /// BLPOP/BRPOP without blocking: pop from the first non-empty list,
+    /// replying `[key, value]`, or nil when every key is empty
+    pub(super) fn execute_blpop(&mut self, keys: &[String], left: bool) -> RespValue {
+        debug_assert!(!keys.is_empty(), "Precondition: BLPOP/BRPOP needs at least one key");
+
+        for key in keys {
+            match self.get_value(key) {
+                Some(Value::List(l)) if !l.is_empty() => {}
+                Some(Value::List(_)) | None => continue,
+                Some(_) => {
+                    return RespValue::err(
+                        "WRONGTYPE Operation against a key holding the wrong kind of value",
+                    )
+                }
+            }
+            let popped = if left {
+                self.execute_lpop(key)
+            } else {
+                self.execute_rpop(key)
+            };
+            debug_assert!(
+                matches!(popped, RespValue::BulkString(Some(_))),
+                "Postcondition: a non-empty list must yield an element"
+            );
+            return RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(key.as_bytes().to_vec())),
+                popped,
+            ]));
+        }
+        RespValue::Array(None)
+    }
+
     pub(super) fn execute_llen(&mut self, key: &str) -> RespValue {
         match self.get_value(key) {
             Some(Value::List(l)) => {
@@ -251,6 +311,82 @@ impl CommandExecutor {
         }
     }
 
+    /// LINSERT: -1 when the pivot is absent, 0 when the key is
+    pub(super) fn execute_linsert(
+        &mut self,
+        key: &str,
+        before: bool,
+        pivot: &SDS,
+        value: &SDS,
+    ) -> RespValue {
+        match self.get_value_mut(key) {
+            Some(Value::List(list)) => {
+                if !list.insert(pivot, value.clone(), before) {
+                    return RespValue::Integer(-1);
+                }
+                RespValue::Integer(list.len() as i64)
+            }
+            Some(_) => {
+                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+            }
+            None => RespValue::Integer(0),
+        }
+    }
+
+    pub(super) fn execute_lrem(&mut self, key: &str, count: isize, element: &SDS) -> RespValue {
+        let removed = match self.get_value_mut(key) {
+            Some(Value::List(list)) => list.remove(count, element),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => 0,
+        };
+        // Redis auto-deletes empty lists
+        if matches!(self.data.get(key), Some(Value::List(l)) if l.is_empty()) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+        #[cfg(debug_assertions)]
+        if matches!(self.data.get(key), Some(Value::List(l)) if l.is_empty()) {
+            panic!("Invariant violated: empty list should have been deleted after LREM");
+        }
+        RespValue::Integer(removed as i64)
+    }
+
+    /// LPOS: one index (or nil) without COUNT, an array of indexes with it
+    pub(super) fn execute_lpos(
+        &mut self,
+        key: &str,
+        element: &SDS,
+        rank: i64,
+        count: Option<usize>,
+        maxlen: usize,
+    ) -> RespValue {
+        let found = match self.get_value(key) {
+            Some(Value::List(list)) => list.positions(element, rank, count.unwrap_or(1), maxlen),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => Vec::new(),
+        };
+        match count {
+            Some(_) => RespValue::Array(Some(
+                found
+                    .into_iter()
+                    .map(|index| RespValue::Integer(index as i64))
+                    .collect(),
+            )),
+            None => match found.first() {
+                Some(&index) => RespValue::Integer(index as i64),
+                None => RespValue::BulkString(None),
+            },
+        }
+    }
+
     pub(super) fn execute_rpoplpush(&mut self, source: &str, dest: &str) -> RespValue {
         if self.is_expired(source) {
             self.data.remove(source);
@@ -291,6 +427,7 @@ impl CommandExecutor {
                         list.lpush(value.clone());
                         #[cfg(debug_assertions)]
                         debug_assert!(self.data.contains_key(dest), "Postcondition: RPOPLPUSH dest must exist after push");
+                        self.blocking.signal(dest);
                         RespValue::BulkString(Some(value.as_bytes().to_vec()))
                     }
                     _ => RespValue::err(
@@ -358,6 +495,7 @@ impl CommandExecutor {
                         }
                         #[cfg(debug_assertions)]
                         debug_assert!(self.data.contains_key(dest), "Postcondition: LMOVE dest must exist after push");
+                        self.blocking.signal(dest);
                         RespValue::BulkString(Some(value.as_bytes().to_vec()))
                     }
                     _ => RespValue::err(
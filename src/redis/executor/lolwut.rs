@@ -0,0 +1,152 @@
+//! LOLWUT: computer art followed by the server version.
+//!
+//! Each call draws a seed from the executor's RNG, so a simulation run with
+//! a fixed seed replays the same pictures while successive calls differ.
+//! Versions follow Redis:
+//!
+//! - 5: Schotter (Georg Nees, 1968), rows of squares that scatter more the
+//!   further down they are. Params: canvas columns, squares per row,
+//!   squares per column
+//! - 6 and no VERSION: a city skyline at night. Params: columns and rows
+//! - anything else: only the version line (see `crate::release`)
+//!
+//! Params are clamped as Redis clamps them, so no call can ask for a huge
+//! canvas.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Every line of a picture is exactly as wide as the canvas
+
+use super::CommandExecutor;
+use crate::redis::resp::RespValue;
+use crate::release::lolwut_version_line;
+use crate::simulator::DeterministicRng;
+
+/// A grid of ASCII cells; drawing outside it is clipped
+struct Canvas {
+    cols: usize,
+    rows: usize,
+    cells: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(cols: usize, rows: usize) -> Self {
+        Canvas {
+            cols,
+            rows,
+            cells: vec![b' '; cols * rows],
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, c: u8) {
+        if (0..self.cols as i64).contains(&x) && (0..self.rows as i64).contains(&y) {
+            self.cells[y as usize * self.cols + x as usize] = c;
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::with_capacity((self.cols + 1) * self.rows);
+        for line in self.cells.chunks(self.cols) {
+            out.push_str(std::str::from_utf8(line).expect("canvas cells are ASCII"));
+            out.push('\n');
+        }
+
+        // TigerStyle: Postcondition - a line per row, each a full row wide
+        debug_assert_eq!(
+            out.len(),
+            (self.cols + 1) * self.rows,
+            "Postcondition violated: every canvas line must be {} wide",
+            self.cols
+        );
+        out
+    }
+}
+
+fn param(params: &[i64], i: usize, default: i64, max: i64) -> usize {
+    params.get(i).copied().unwrap_or(default).clamp(1, max) as usize
+}
+
+/// A value in `-spread..=spread`
+fn jitter(rng: &mut DeterministicRng, spread: i64) -> i64 {
+    rng.gen_range(0, (2 * spread + 1) as u64) as i64 - spread
+}
+
+/// LOLWUT VERSION 5: squares that fall out of line row by row
+fn schotter(rng: &mut DeterministicRng, params: &[i64]) -> String {
+    let cols = param(params, 0, 66, 1000);
+    let per_row = param(params, 1, 8, 200);
+    let per_col = param(params, 2, 12, 200);
+    // Terminal cells are about twice as tall as wide
+    let width = (cols / per_row).max(2);
+    let height = (width / 2).max(2);
+    let mut canvas = Canvas::new(cols, per_col * height + 1);
+
+    for row in 0..per_col {
+        // No disorder in the first row, up to half a square in the last
+        let spread = (row * width / 2 / per_col) as i64;
+        for col in 0..per_row {
+            let left = (col * width) as i64 + jitter(rng, spread);
+            let top = (row * height) as i64 + jitter(rng, spread / 2);
+            let (right, bottom) = (left + width as i64 - 1, top + height as i64);
+            for x in left..=right {
+                canvas.set(x, top, b'-');
+                canvas.set(x, bottom, b'-');
+            }
+            for y in top..=bottom {
+                canvas.set(left, y, b'|');
+                canvas.set(right, y, b'|');
+            }
+            for (x, y) in [(left, top), (right, top), (left, bottom), (right, bottom)] {
+                canvas.set(x, y, b'+');
+            }
+        }
+    }
+    canvas.render() + "\nGeorg Nees - schotter, plotter on paper, 1968. "
+}
+
+/// LOLWUT VERSION 6: lit windows in a row of towers under a starry sky
+fn skyline(rng: &mut DeterministicRng, params: &[i64]) -> String {
+    let cols = param(params, 0, 80, 1000);
+    let rows = param(params, 1, 20, 1000);
+    let mut canvas = Canvas::new(cols, rows);
+
+    for y in 0..rows as i64 {
+        for x in 0..cols as i64 {
+            if rng.gen_bool(0.02) {
+                canvas.set(x, y, b'*');
+            }
+        }
+    }
+    let mut left = 0;
+    while left < cols {
+        let width = rng.gen_range(3, 9) as usize;
+        let height = rng.gen_range(1, rows as u64 + 1) as usize;
+        for x in left..(left + width).min(cols) {
+            for y in rows - height..rows {
+                let window = x > left && x % 2 == 0 && y % 2 == 1 && rng.gen_bool(0.5);
+                canvas.set(x as i64, y as i64, if window { b'.' } else { b'#' });
+            }
+        }
+        // Gaps between towers are an alley or none at all
+        left += width + rng.gen_range(0, 2) as usize;
+    }
+    canvas.render() + "\nA city that never sleeps. "
+}
+
+impl CommandExecutor {
+    pub(super) fn execute_lolwut(&mut self, version: Option<i64>, params: &[i64]) -> RespValue {
+        let mut rng = DeterministicRng::new(self.rng.next_u64());
+        let version_line = lolwut_version_line();
+        let art = match version.unwrap_or(6) {
+            5 => schotter(&mut rng, params) + &version_line,
+            6 => skyline(&mut rng, params) + &version_line,
+            _ => version_line.clone(),
+        };
+
+        debug_assert!(
+            art.ends_with(&version_line),
+            "Postcondition violated: LOLWUT must end with the version line"
+        );
+        RespValue::BulkString(Some(art.into_bytes()))
+    }
+}
@@ -0,0 +1,293 @@
+//! MEMORY USAGE, STATS, DOCTOR and HELP.
+//!
+//! Sizes are modelled on Redis's own layout rather than measured from the
+//! allocator, so they answer "how big would this be in Redis" the same way
+//! on every platform:
+//! - a string is an SDS: the smallest header that holds its length, the
+//!   payload and a terminator (integers are stored in the object itself)
+//! - every value has an object header
+//! - list elements pay a list node, set and hash elements a hash table
+//!   entry, sorted set members a skiplist node and a hash table entry,
+//!   stream entries a fixed bookkeeping cost; consumer groups are not counted
+//!
+//! MEMORY USAGE adds the key's SDS and its keyspace entry. Collections
+//! bigger than SAMPLES (default 5, 0 means all) are estimated from their
+//! first elements, as in Redis.
+//!
+//! MEMORY STATS reports the dataset as `MemoryStats`, which the sharded
+//! servers sum across shards. DOCTOR only tells an empty instance from one
+//! with data; it has no detectors.
+
+use super::string_ops::parse_integer_value;
+use super::CommandExecutor;
+use crate::redis::data::{StreamId, Value};
+use crate::redis::resp::RespValue;
+
+/// Object header: type, encoding, LRU clock, refcount and value pointer
+const OBJECT_HEADER_BYTES: u64 = 16;
+
+/// Hash table entry: key, value and next pointers
+const DICT_ENTRY_BYTES: u64 = 24;
+
+/// Hash table bucket: one pointer
+const DICT_BUCKET_BYTES: u64 = 8;
+
+/// List node: prev, next and value pointers
+const LIST_NODE_BYTES: u64 = 24;
+
+/// Skiplist node: member, score, backward pointer and 1.33 levels on
+/// average (Redis's p = 1/4)
+const SKIPLIST_NODE_BYTES: u64 = 48;
+
+/// Stream entry: its ID and its share of the radix tree and listpack
+const STREAM_ENTRY_BYTES: u64 = 32;
+
+/// Below this an instance is too small for DOCTOR to judge (as in Redis)
+const DOCTOR_MIN_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Elements MEMORY USAGE samples when SAMPLES is not given
+pub const MEMORY_USAGE_DEFAULT_SAMPLES: usize = 5;
+
+const HELP: &[&str] = &[
+    "MEMORY <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "DOCTOR",
+    "    Return memory problems reports.",
+    "STATS",
+    "    Return information about the memory usage of the server.",
+    "USAGE <key> [SAMPLES <count>]",
+    "    Return memory in bytes used by <key> and its value. Nested values are",
+    "    sampled up to <count> times (default: 5, 0 means sample all).",
+    "HELP",
+    "    Print this help.",
+];
+
+/// Bytes an SDS string of `len` bytes takes
+pub fn sds_bytes(len: usize) -> u64 {
+    let header = match len {
+        0..=0x1f => 1,
+        0x20..=0xff => 3,
+        0x100..=0xffff => 5,
+        0x1_0000..=0xffff_ffff => 9,
+        _ => 17,
+    };
+    header + len as u64 + 1
+}
+
+/// Sum `cost` over the first `samples` of `len` elements (all of them when
+/// `samples` is 0) and scale the sum up to the whole collection
+fn sampled<I, F>(len: usize, elements: I, samples: usize, cost: F) -> u64
+where
+    I: Iterator,
+    F: Fn(I::Item) -> u64,
+{
+    let take = if samples == 0 { len } else { samples.min(len) };
+    if take == 0 {
+        return 0;
+    }
+    let sum: u64 = elements.take(take).map(cost).sum();
+    sum.saturating_mul(len as u64) / take as u64
+}
+
+/// Estimated bytes of a value, without its key
+pub fn value_memory_usage(value: &Value, samples: usize) -> u64 {
+    let payload = match value {
+        Value::String(s) => {
+            if parse_integer_value(s.as_bytes()).is_some() {
+                0
+            } else {
+                sds_bytes(s.len())
+            }
+        }
+        Value::List(l) => sampled(l.len(), l.iter(), samples, |item| {
+            LIST_NODE_BYTES + sds_bytes(item.len())
+        }),
+        Value::Set(s) => sampled(s.len(), s.iter(), samples, |member| {
+            DICT_ENTRY_BYTES + sds_bytes(member.len())
+        }),
+        Value::Hash(h) => sampled(h.len(), h.iter(), samples, |(field, value)| {
+            DICT_ENTRY_BYTES + sds_bytes(field.len()) + sds_bytes(value.len())
+        }),
+        Value::SortedSet(z) => sampled(z.len(), z.iter(), samples, |(member, _)| {
+            SKIPLIST_NODE_BYTES + DICT_ENTRY_BYTES + sds_bytes(member.len())
+        }),
+        Value::Stream(st) => {
+            let count = (samples != 0).then_some(samples);
+            let entries = st.range(StreamId::MIN, StreamId::MAX, count);
+            sampled(st.len(), entries.into_iter(), samples, |(_, fields)| {
+                STREAM_ENTRY_BYTES
+                    + fields
+                        .iter()
+                        .map(|(f, v)| sds_bytes(f.len()) + sds_bytes(v.len()))
+                        .sum::<u64>()
+            })
+        }
+        Value::Null => return 0,
+    };
+    OBJECT_HEADER_BYTES + payload
+}
+
+/// Estimated bytes of a key, its keyspace entry and its value (MEMORY USAGE)
+pub fn key_memory_usage(key: &str, value: &Value, samples: usize) -> u64 {
+    sds_bytes(key.len()) + DICT_ENTRY_BYTES + value_memory_usage(value, samples)
+}
+
+/// Bytes of a hash table holding `entries`: entries plus a power-of-two
+/// bucket array
+fn dict_bytes(entries: u64) -> u64 {
+    if entries == 0 {
+        return 0;
+    }
+    entries * DICT_ENTRY_BYTES + entries.next_power_of_two() * DICT_BUCKET_BYTES
+}
+
+/// MEMORY STATS for one shard, or several summed with `merge`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub keys: u64,
+    /// Keys and values, as MEMORY USAGE counts them less the keyspace entry
+    pub dataset_bytes: u64,
+    /// The keyspace hash table
+    pub main_overhead_bytes: u64,
+    /// The hash table of keys with a TTL
+    pub expires_overhead_bytes: u64,
+}
+
+impl MemoryStats {
+    pub fn overhead_bytes(&self) -> u64 {
+        self.main_overhead_bytes
+            .saturating_add(self.expires_overhead_bytes)
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.dataset_bytes.saturating_add(self.overhead_bytes())
+    }
+
+    /// Fold another shard's stats into these
+    pub fn merge(&mut self, other: &MemoryStats) {
+        self.keys = self.keys.saturating_add(other.keys);
+        self.dataset_bytes = self.dataset_bytes.saturating_add(other.dataset_bytes);
+        self.main_overhead_bytes = self
+            .main_overhead_bytes
+            .saturating_add(other.main_overhead_bytes);
+        self.expires_overhead_bytes = self
+            .expires_overhead_bytes
+            .saturating_add(other.expires_overhead_bytes);
+    }
+
+    /// The MEMORY STATS reply: name/value pairs, as Redis sends in RESP2
+    pub fn to_resp(&self) -> RespValue {
+        let total = self.total_bytes();
+        let bytes_per_key = total.checked_div(self.keys).unwrap_or(0);
+        let dataset_percentage = if total == 0 {
+            0.0
+        } else {
+            self.dataset_bytes as f64 * 100.0 / total as f64
+        };
+        let integers = [
+            ("total.allocated", total),
+            ("overhead.hashtable.main", self.main_overhead_bytes),
+            ("overhead.hashtable.expires", self.expires_overhead_bytes),
+            ("overhead.total", self.overhead_bytes()),
+            ("keys.count", self.keys),
+            ("keys.bytes-per-key", bytes_per_key),
+            ("dataset.bytes", self.dataset_bytes),
+        ];
+        let mut reply = Vec::with_capacity(integers.len() * 2 + 2);
+        for (name, value) in integers {
+            reply.push(bulk(name));
+            reply.push(RespValue::Integer(value.min(i64::MAX as u64) as i64));
+        }
+        reply.push(bulk("dataset.percentage"));
+        reply.push(bulk(&format!("{:.2}", dataset_percentage)));
+        RespValue::Array(Some(reply))
+    }
+
+    /// Read back a shard's MEMORY STATS reply
+    pub fn from_resp(reply: &RespValue) -> Option<MemoryStats> {
+        let RespValue::Array(Some(items)) = reply else {
+            return None;
+        };
+        let mut stats = MemoryStats::default();
+        for pair in items.chunks(2) {
+            let (RespValue::BulkString(Some(name)), Some(value)) = (&pair[0], pair.get(1)) else {
+                return None;
+            };
+            let RespValue::Integer(n) = value else {
+                continue;
+            };
+            let n = u64::try_from(*n).ok()?;
+            match name.as_slice() {
+                b"keys.count" => stats.keys = n,
+                b"dataset.bytes" => stats.dataset_bytes = n,
+                b"overhead.hashtable.main" => stats.main_overhead_bytes = n,
+                b"overhead.hashtable.expires" => stats.expires_overhead_bytes = n,
+                _ => {}
+            }
+        }
+        Some(stats)
+    }
+
+    /// The MEMORY DOCTOR report
+    pub fn doctor(&self) -> String {
+        if self.total_bytes() < DOCTOR_MIN_BYTES {
+            "Hi Sam, this instance is empty or is using very little memory, my issues \
+             detector can't be used in these conditions. Please, leave for your mission \
+             on Earth and fill it with some data. The new Sam and I will be back to our \
+             programming as soon as I finished rebooting."
+                .to_string()
+        } else {
+            "Hi Sam, I can't find any memory issue in your instance. I can only account \
+             for what occurs on this base."
+                .to_string()
+        }
+    }
+}
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+impl CommandExecutor {
+    pub(super) fn execute_memory_usage(&mut self, key: &str, samples: Option<usize>) -> RespValue {
+        let samples = samples.unwrap_or(MEMORY_USAGE_DEFAULT_SAMPLES);
+        match self.get_value(key) {
+            Some(value) => RespValue::Integer(key_memory_usage(key, value, samples) as i64),
+            None => RespValue::BulkString(None),
+        }
+    }
+
+    /// This executor's dataset, with MEMORY USAGE's default sampling
+    pub fn memory_stats(&self) -> MemoryStats {
+        let dataset_bytes = self
+            .data
+            .iter()
+            .map(|(key, value)| {
+                sds_bytes(key.len()) + value_memory_usage(value, MEMORY_USAGE_DEFAULT_SAMPLES)
+            })
+            .sum();
+        let stats = MemoryStats {
+            keys: self.data.len() as u64,
+            dataset_bytes,
+            main_overhead_bytes: dict_bytes(self.data.len() as u64),
+            expires_overhead_bytes: dict_bytes(self.expirations.len() as u64),
+        };
+
+        debug_assert!(
+            stats.keys == 0 || stats.dataset_bytes > 0,
+            "Postcondition violated: keys must account for some dataset bytes"
+        );
+        stats
+    }
+
+    pub(super) fn execute_memory_stats(&self) -> RespValue {
+        self.memory_stats().to_resp()
+    }
+
+    pub(super) fn execute_memory_doctor(&self) -> RespValue {
+        RespValue::BulkString(Some(self.memory_stats().doctor().into_bytes()))
+    }
+
+    pub(super) fn execute_memory_help(&self) -> RespValue {
+        RespValue::Array(Some(HELP.iter().map(|line| bulk(line)).collect()))
+    }
+}
@@ -9,30 +9,80 @@
 //! - `list_ops.rs`: List command implementations (LPUSH, RPUSH, LRANGE, etc.)
 //! - `set_ops.rs`: Set command implementations (SADD, SREM, SMEMBERS, etc.)
 //! - `hash_ops.rs`: Hash command implementations (HSET, HGET, HGETALL, etc.)
+//! - `hash_ttl_ops.rs`: Hash field TTLs (HEXPIRE, HTTL, HPERSIST, etc.)
 //! - `sorted_set_ops.rs`: Sorted set implementations (ZADD, ZRANGE, ZSCORE, etc.)
+//! - `zset_algebra_ops.rs`: ZUNION/ZINTER/ZDIFF and their STORE forms
+//! - `stream_ops.rs`: Stream implementations (XADD, XRANGE, XREAD, etc.)
 //! - `scan_ops.rs`: Scan command implementations (SCAN, HSCAN, ZSCAN)
-//! - `transaction_ops.rs`: Transaction implementations (MULTI, EXEC, DISCARD)
-//! - `script_ops.rs`: Lua scripting implementations (EVAL, EVALSHA, SCRIPT)
+//! - `transaction_ops.rs`: Transaction implementations (MULTI, EXEC, DISCARD, RESET)
+//! - `script_ops.rs`: Lua scripting implementations (EVAL, EVALSHA, their _RO forms, SCRIPT)
+//! - `function_ops.rs`: Redis 7 Functions (FUNCTION LOAD/LIST/DUMP/RESTORE/STATS, FCALL)
 //! - `acl_ops.rs`: ACL command implementations
+//! - `command_ops.rs`: COMMAND INFO, DOCS, COUNT and GETKEYS from the command table
+//! - `debug_ops.rs`: DEBUG BUGGIFY (runtime fault injection control)
+//! - `latency_ops.rs`: LATENCY LATEST, HISTORY, RESET and DOCTOR
+//! - `memory_ops.rs`: MEMORY USAGE, STATS and DOCTOR over per-value size estimates
+//! - `lolwut.rs`: LOLWUT art drawn from the simulation RNG
+//! - `keyspace_stats.rs`: Incremental per-type statistics (DEBUG KEYSTATS)
+//! - `eviction.rs`: maxmemory eviction and eviction events
+//! - `lazyfree.rs`: UNLINK and the queue of values awaiting lazy free
+//! - `sort_ops.rs`: SORT with BY/GET patterns, LIMIT and STORE
+//! - `glob.rs`: Compiled glob patterns and their cache (KEYS, SCAN MATCH)
+//! - `snapshot_read.rs`: Read-only batches against a frozen keyspace (DEBUG SNAPSHOT-READ)
+//! - `bulk_load.rs`: Direct installation of finished values for data import
+//! - `dump_ops.rs`: DUMP and RESTORE in the RDB value encoding
+//! - `rdb_file.rs`: Whole-keyspace RDB snapshots and loading them at startup
 
 mod acl_ops;
 mod bitmap_ops;
+mod bulk_load;
+mod command_ops;
 mod config_ops;
+mod debug_ops;
+mod dump_ops;
+mod eviction;
+mod expirations;
+mod function_ops;
+mod glob;
 mod hash_ops;
+mod hash_ttl_ops;
 mod key_ops;
+mod keyspace_stats;
+mod latency_ops;
+mod lazyfree;
 mod list_ops;
+mod lolwut;
+mod memory_ops;
+mod rdb_file;
 mod scan_ops;
 mod script_ops;
 mod set_ops;
+mod snapshot_read;
+mod sort_ops;
 mod sorted_set_ops;
+mod stream_ops;
 mod string_ops;
 mod transaction_ops;
+mod zset_algebra_ops;
 
 use super::command::Command;
+use super::connection_state::ConnectionState;
 use super::data::*;
 use super::resp::RespValue;
-use crate::simulator::VirtualTime;
-use ahash::AHashMap;
+use crate::release::{info_server_fields, ServerMode};
+use crate::simulator::{DeterministicRng, VirtualTime};
+use ahash::{AHashMap, AHashSet};
+use std::sync::Arc;
+
+pub use bulk_load::{BulkEntry, BulkLoadReport};
+pub use eviction::EvictionPolicy;
+use expirations::Expirations;
+pub use glob::GlobPattern;
+pub(crate) use glob::glob_match;
+pub use keyspace_stats::{ExpiryOutlook, KeyStatsReport, KeyspaceStats, ValueKind, EXPIRY_HORIZONS};
+pub use lazyfree::LAZYFREE_THRESHOLD;
+pub use memory_ops::MemoryStats;
+pub use snapshot_read::KeyspaceSnapshot;
 
 /// Redis command executor - the state machine that processes commands.
 ///
@@ -43,39 +93,81 @@ use ahash::AHashMap;
 /// - Script cache for Lua scripting
 pub struct CommandExecutor {
     pub(crate) data: AHashMap<String, Value>,
-    pub(crate) expirations: AHashMap<String, VirtualTime>,
+    pub(crate) expirations: Expirations,
+    // Hashes with field deadlines, walked by the eviction passes
+    pub(crate) field_ttl_keys: AHashSet<String>,
+    // Last access per key, for OBJECT IDLETIME (see key_ops.rs)
+    pub(crate) access_times: AHashMap<String, VirtualTime>,
     pub(crate) current_time: VirtualTime,
+    // Whether clock advances and the TTL manager evict expired keys; DEBUG
+    // SET-ACTIVE-EXPIRE 0 leaves only lazy expiry on access
+    pub(crate) active_expire: bool,
     pub(crate) commands_processed: usize,
     pub(crate) simulation_start_epoch: i64,
     /// Exact server start time in milliseconds (for precise PEXPIREAT/PXAT)
     pub(crate) simulation_start_epoch_ms: i64,
-    // Transaction state
-    pub(crate) in_transaction: bool,
-    pub(crate) queued_commands: Vec<Command>,
-    pub(crate) watched_keys: AHashMap<String, Option<Value>>,
+    // Transaction state (MULTI queue and WATCH snapshots), cleared by RESET
+    pub(crate) conn: ConnectionState<Option<Value>>,
     // Lua scripting - local cache for single-shard mode
     pub(crate) script_cache: super::lua::ScriptCache,
     // Shared script cache for multi-shard mode (all shards share one cache)
     pub(crate) shared_script_cache: Option<super::lua::SharedScriptCache>,
     // Server configuration for CONFIG GET/SET
     pub(crate) config: config_ops::ServerConfig,
+    // Per-type counts and sizes, maintained incrementally by execute()
+    pub(crate) keyspace_stats: KeyspaceStats,
+    // Channel registry for PUBLISH (shared by all shards in multi-shard mode)
+    pub(crate) pubsub: super::pubsub::PubSubManager,
+    // Clients blocked on keys, woken by pushes (shared like `pubsub`)
+    pub(crate) blocking: super::blocking::BlockingManager,
+    // CLIENT TRACKING table, told about every modified key (shared like `pubsub`)
+    pub(crate) client_tracking: super::tracking::ClientTracking,
+    // Latency spikes for LATENCY (shared like `pubsub`)
+    pub(crate) latency: super::latency::LatencyMonitor,
+    // Running scripts, for BUSY replies and SCRIPT KILL (shared like `pubsub`)
+    pub(crate) script_monitor: super::script_monitor::ScriptMonitor,
+    // Function libraries for FUNCTION and FCALL (shared like `pubsub`)
+    pub(crate) functions: super::functions::FunctionLibraries,
+    // maxmemory limit, policy and evicted keys not yet drained
+    pub(crate) eviction: eviction::EvictionState,
+    // Values detached by UNLINK, awaiting the lazy-free worker
+    pub(crate) lazyfree: lazyfree::LazyFreeState,
+    // Randomness for SRANDMEMBER, seeded so runs replay exactly
+    pub(crate) rng: DeterministicRng,
+    // Set by `set_rng_seed`: a simulation wants secrets (GENPASS) to replay too
+    pub(crate) rng_seeded: bool,
+    // Recently used MATCH patterns, compiled (behind a lock for &self reads)
+    pub(crate) glob_cache: parking_lot::Mutex<glob::GlobCache>,
 }
 
 impl CommandExecutor {
     pub fn new() -> Self {
         CommandExecutor {
             data: AHashMap::new(),
-            expirations: AHashMap::new(),
+            expirations: Expirations::new(),
+            field_ttl_keys: AHashSet::new(),
+            access_times: AHashMap::new(),
             current_time: VirtualTime::from_millis(0),
+            active_expire: true,
             commands_processed: 0,
             simulation_start_epoch: 0,
             simulation_start_epoch_ms: 0,
-            in_transaction: false,
-            queued_commands: Vec::new(),
-            watched_keys: AHashMap::new(),
+            conn: ConnectionState::new(),
             script_cache: super::lua::ScriptCache::new(),
             shared_script_cache: None,
             config: config_ops::ServerConfig::new(),
+            keyspace_stats: KeyspaceStats::new(),
+            pubsub: super::pubsub::PubSubManager::new(),
+            blocking: super::blocking::BlockingManager::new(),
+            client_tracking: super::tracking::ClientTracking::new(),
+            latency: super::latency::LatencyMonitor::new(),
+            script_monitor: super::script_monitor::ScriptMonitor::new(),
+            functions: super::functions::FunctionLibraries::new(),
+            eviction: eviction::EvictionState::new(),
+            lazyfree: lazyfree::LazyFreeState::new(),
+            rng: DeterministicRng::new(0),
+            rng_seeded: false,
+            glob_cache: parking_lot::Mutex::new(glob::GlobCache::new()),
         }
     }
 
@@ -83,17 +175,30 @@ impl CommandExecutor {
     pub fn with_shared_script_cache(shared_cache: super::lua::SharedScriptCache) -> Self {
         CommandExecutor {
             data: AHashMap::new(),
-            expirations: AHashMap::new(),
+            expirations: Expirations::new(),
+            field_ttl_keys: AHashSet::new(),
+            access_times: AHashMap::new(),
             current_time: VirtualTime::from_millis(0),
+            active_expire: true,
             commands_processed: 0,
             simulation_start_epoch: 0,
             simulation_start_epoch_ms: 0,
-            in_transaction: false,
-            queued_commands: Vec::new(),
-            watched_keys: AHashMap::new(),
+            conn: ConnectionState::new(),
             script_cache: super::lua::ScriptCache::new(),
             shared_script_cache: Some(shared_cache),
             config: config_ops::ServerConfig::new(),
+            keyspace_stats: KeyspaceStats::new(),
+            pubsub: super::pubsub::PubSubManager::new(),
+            blocking: super::blocking::BlockingManager::new(),
+            client_tracking: super::tracking::ClientTracking::new(),
+            latency: super::latency::LatencyMonitor::new(),
+            script_monitor: super::script_monitor::ScriptMonitor::new(),
+            functions: super::functions::FunctionLibraries::new(),
+            eviction: eviction::EvictionState::new(),
+            lazyfree: lazyfree::LazyFreeState::new(),
+            rng: DeterministicRng::new(0),
+            rng_seeded: false,
+            glob_cache: parking_lot::Mutex::new(glob::GlobCache::new()),
         }
     }
 
@@ -102,6 +207,97 @@ impl CommandExecutor {
         self.shared_script_cache = Some(shared_cache);
     }
 
+    /// Set the Pub/Sub registry PUBLISH delivers to (shared with connections)
+    pub fn set_pubsub(&mut self, pubsub: super::pubsub::PubSubManager) {
+        self.pubsub = pubsub;
+    }
+
+    pub fn pubsub(&self) -> &super::pubsub::PubSubManager {
+        &self.pubsub
+    }
+
+    /// Set the registry of blocked clients that pushes wake (shared with connections)
+    pub fn set_blocking(&mut self, blocking: super::blocking::BlockingManager) {
+        self.blocking = blocking;
+    }
+
+    pub fn blocking(&self) -> &super::blocking::BlockingManager {
+        &self.blocking
+    }
+
+    /// Set the CLIENT TRACKING table writes invalidate (shared with connections)
+    pub fn set_client_tracking(&mut self, tracking: super::tracking::ClientTracking) {
+        self.client_tracking = tracking;
+    }
+
+    pub fn client_tracking(&self) -> &super::tracking::ClientTracking {
+        &self.client_tracking
+    }
+
+    /// Set the latency monitor spikes are recorded in (shared with every shard)
+    pub fn set_latency_monitor(&mut self, latency: super::latency::LatencyMonitor) {
+        self.latency = latency;
+    }
+
+    pub fn latency_monitor(&self) -> &super::latency::LatencyMonitor {
+        &self.latency
+    }
+
+    /// Set the monitor scripts register with (shared with every shard and connection)
+    pub fn set_script_monitor(&mut self, monitor: super::script_monitor::ScriptMonitor) {
+        self.script_monitor = monitor;
+    }
+
+    pub fn script_monitor(&self) -> &super::script_monitor::ScriptMonitor {
+        &self.script_monitor
+    }
+
+    /// Set the function libraries FCALL calls (shared with every shard)
+    pub fn set_function_libraries(&mut self, functions: super::functions::FunctionLibraries) {
+        self.functions = functions;
+    }
+
+    pub fn function_libraries(&self) -> &super::functions::FunctionLibraries {
+        &self.functions
+    }
+
+    /// Reseed the RNG behind random replies (RANDOMKEY, SPOP, SRANDMEMBER,
+    /// HRANDFIELD, ZRANDMEMBER) and ACL GENPASS
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng = DeterministicRng::new(seed);
+        self.rng_seeded = true;
+    }
+
+    /// Pick from `items` with SRANDMEMBER/ZRANDMEMBER count semantics: a
+    /// positive count picks distinct items (at most all of them), a negative
+    /// one |count| independent picks with repeats. `items` must be in a
+    /// deterministic order for the result to replay under a seed.
+    pub(super) fn sample_with_count<T: Clone>(&mut self, mut items: Vec<T>, count: i64) -> Vec<T> {
+        let len = items.len() as u64;
+        if len == 0 {
+            return items;
+        }
+        let picked: Vec<T> = if count >= 0 {
+            // A partial Fisher-Yates shuffle of the first n
+            let n = (count as u64).min(len) as usize;
+            for i in 0..n {
+                let j = self.rng.gen_range(i as u64, len) as usize;
+                items.swap(i, j);
+            }
+            items.truncate(n);
+            items
+        } else {
+            (0..count.unsigned_abs())
+                .map(|_| items[self.rng.gen_range(0, len) as usize].clone())
+                .collect()
+        };
+        debug_assert!(
+            picked.len() as u64 <= count.unsigned_abs(),
+            "Postcondition: at most |count| items are picked"
+        );
+        picked
+    }
+
     pub fn set_simulation_start_epoch(&mut self, epoch: i64) {
         self.simulation_start_epoch = epoch;
         // Default ms value from seconds if not set separately
@@ -140,19 +336,27 @@ impl CommandExecutor {
     #[inline]
     pub fn get_direct(&mut self, key: &str) -> RespValue {
         self.commands_processed += 1;
-        match self.get_value(key) {
+        if self.is_expired(key) {
+            self.remove_tracked(key);
+            self.expirations.remove(key);
+            return RespValue::BulkString(None);
+        }
+        let response = match self.data.get(key) {
             Some(Value::String(s)) => RespValue::BulkString(Some(s.as_bytes().to_vec())),
             Some(_) => {
                 RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
             }
-            None => RespValue::BulkString(None),
-        }
+            None => return RespValue::BulkString(None),
+        };
+        self.record_access(key);
+        response
     }
 
     /// Fast path SET - avoids Command enum overhead
     #[inline]
     pub fn set_direct(&mut self, key: &str, value: &[u8]) -> RespValue {
         self.commands_processed += 1;
+        let before = self.data.get(key).and_then(keyspace_stats::KeyFootprint::of);
 
         #[cfg(feature = "opt-single-key-alloc")]
         {
@@ -175,9 +379,110 @@ impl CommandExecutor {
             debug_assert!(!self.expirations.contains_key(key), "Postcondition: set_direct must clear expiration");
         }
 
+        self.stats_track_key(key, before);
+        self.client_tracking.invalidate_key(key);
+
         RespValue::ok()
     }
 
+    /// Fast path INCR - avoids Command enum overhead
+    #[inline]
+    pub fn incr_direct(&mut self, key: &str) -> RespValue {
+        if self.conn.in_transaction {
+            return self.execute(&Command::Incr(key.to_string()));
+        }
+        self.commands_processed += 1;
+        if let Some(oom) = self.evict_for_direct_write() {
+            return oom;
+        }
+        let before = self.data.get(key).and_then(keyspace_stats::KeyFootprint::of);
+        let response = self.incr_by_impl(key, 1);
+        self.stats_track_key(key, before);
+        self.client_tracking.invalidate_key(key);
+        self.verify_invariants();
+        response
+    }
+
+    /// Fast path MGET - avoids Command enum overhead
+    #[inline]
+    pub fn mget_direct(&mut self, keys: &[&str]) -> RespValue {
+        if self.conn.in_transaction {
+            let keys = keys.iter().map(|k| k.to_string()).collect();
+            return self.execute(&Command::MGet(keys));
+        }
+        self.commands_processed += 1;
+        let values: Vec<RespValue> = keys
+            .iter()
+            .map(|&key| {
+                if self.is_expired(key) {
+                    self.remove_tracked(key);
+                    self.expirations.remove(key);
+                    return RespValue::BulkString(None);
+                }
+                let value = match self.data.get(key) {
+                    Some(Value::String(s)) => RespValue::BulkString(Some(s.as_bytes().to_vec())),
+                    Some(_) => RespValue::BulkString(None),
+                    None => return RespValue::BulkString(None),
+                };
+                self.record_access(key);
+                value
+            })
+            .collect();
+        debug_assert_eq!(
+            values.len(),
+            keys.len(),
+            "Postcondition: mget_direct must reply once per key"
+        );
+        RespValue::Array(Some(values))
+    }
+
+    /// Fast path HGET - avoids Command enum overhead
+    #[inline]
+    pub fn hget_direct(&mut self, key: &str, field: &str) -> RespValue {
+        if self.conn.in_transaction {
+            return self.execute(&Command::HGet(key.to_string(), SDS::from_str(field)));
+        }
+        self.commands_processed += 1;
+        if self.is_expired(key) {
+            self.remove_tracked(key);
+            self.expirations.remove(key);
+            return RespValue::BulkString(None);
+        }
+        if !self.field_ttl_keys.is_empty() {
+            let before = self.data.get(key).and_then(keyspace_stats::KeyFootprint::of);
+            if self.expire_hash_fields(key) > 0 {
+                self.stats_track_key(key, before);
+            }
+        }
+        let response = match self.data.get(key) {
+            Some(Value::Hash(h)) => match h.get_str(field) {
+                Some(v) => RespValue::BulkString(Some(v.as_bytes().to_vec())),
+                None => RespValue::BulkString(None),
+            },
+            Some(_) => {
+                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+            }
+            None => return RespValue::BulkString(None),
+        };
+        self.record_access(key);
+        response
+    }
+
+    /// Fast path EXISTS - avoids Command enum overhead
+    #[inline]
+    pub fn exists_direct(&mut self, keys: &[&str]) -> RespValue {
+        if self.conn.in_transaction {
+            let keys = keys.iter().map(|k| k.to_string()).collect();
+            return self.execute(&Command::Exists(keys));
+        }
+        self.commands_processed += 1;
+        let count = keys
+            .iter()
+            .filter(|&&key| !self.is_expired(key) && self.data.contains_key(key))
+            .count();
+        RespValue::Integer(count as i64)
+    }
+
     /// Direct expiration eviction - call this from TTL manager
     pub fn evict_expired_direct(&mut self, current_time: VirtualTime) -> usize {
         #[cfg(debug_assertions)]
@@ -186,10 +491,13 @@ impl CommandExecutor {
         let pre_exp_len = self.expirations.len();
 
         self.current_time = current_time;
+        if !self.active_expire {
+            return 0;
+        }
 
         // Single-pass: retain unexpired keys, collect expired ones for data removal
         let mut expired_keys = Vec::with_capacity(self.expirations.len() / 4);
-        self.expirations.retain(|k, &mut exp_time| {
+        self.expirations.retain(|k, exp_time| {
             if exp_time <= self.current_time {
                 expired_keys.push(k.clone());
                 false
@@ -200,7 +508,7 @@ impl CommandExecutor {
 
         let count = expired_keys.len();
         for key in &expired_keys {
-            self.data.remove(key);
+            self.remove_tracked(key);
         }
 
         #[cfg(debug_assertions)]
@@ -215,15 +523,21 @@ impl CommandExecutor {
                 pre_exp_len.saturating_sub(count),
                 "Postcondition: expirations size must decrease by evicted count"
             );
-            self.verify_invariants();
         }
 
-        count
+        // Hashes whose last fields expired count as evicted keys too
+        let emptied = self.evict_expired_hash_fields();
+        self.verify_invariants();
+
+        count + emptied
     }
 
     pub(crate) fn evict_expired_keys(&mut self) {
+        if !self.active_expire {
+            return;
+        }
         let mut expired_keys = Vec::with_capacity(self.expirations.len() / 4);
-        self.expirations.retain(|k, &mut exp_time| {
+        self.expirations.retain(|k, exp_time| {
             if exp_time <= self.current_time {
                 expired_keys.push(k.clone());
                 false
@@ -233,17 +547,29 @@ impl CommandExecutor {
         });
 
         for key in &expired_keys {
-            self.data.remove(key);
+            self.remove_tracked(key);
         }
+        self.evict_expired_hash_fields();
 
-        #[cfg(debug_assertions)]
+        // TigerStyle: Postcondition - eviction leaves no deadline at or before now
+        debug_assert!(
+            self.expirations.values().all(|&t| t > self.current_time),
+            "Postcondition violated: eviction must remove every key with exp <= now"
+        );
         self.verify_invariants();
     }
 
     /// Verify all CommandExecutor invariants hold.
-    /// Call in debug builds after mutations to catch consistency bugs early.
+    ///
+    /// Called after every `execute()` and after every eviction pass, so any
+    /// command that leaves the keyspace inconsistent fails at the point of
+    /// damage rather than on some later read. No-op in release builds.
+    ///
+    /// Expiry is lazy, so a key whose deadline equals `current_time` may still
+    /// be present until it is next touched or evicted; a deadline strictly in
+    /// the past can only exist if time advanced without an eviction pass.
     #[cfg(debug_assertions)]
-    pub(crate) fn verify_invariants(&self) {
+    pub fn verify_invariants(&self) {
         // Invariant 1: Every key in expirations must exist in data
         for key in self.expirations.keys() {
             debug_assert!(
@@ -253,10 +579,16 @@ impl CommandExecutor {
             );
         }
 
-        // Invariant 2: No expired keys should remain in expirations after eviction
+        debug_assert!(
+            self.expirations.is_consistent(),
+            "Invariant violated: deadline index must agree with expirations"
+        );
+
+        // Invariant 2: No key may carry an expiration strictly in the past
+        // (unless active expiry is off, when keys wait to be accessed)
         for (key, &exp_time) in &self.expirations {
             debug_assert!(
-                exp_time > self.current_time,
+                !self.active_expire || exp_time >= self.current_time,
                 "Invariant violated: expired key '{}' (exp={}, now={}) still in expirations",
                 key,
                 exp_time.as_millis(),
@@ -290,14 +622,46 @@ impl CommandExecutor {
                 _ => {}
             }
         }
+
+        // Invariant 4: Incremental keyspace stats match a full recount
+        debug_assert_eq!(
+            self.keyspace_stats,
+            KeyspaceStats::from_data(&self.data),
+            "Invariant violated: incremental keyspace stats diverged from data"
+        );
+
+        // Invariant 5: Every hash with field deadlines is tracked for eviction
+        for (key, value) in &self.data {
+            if let Value::Hash(h) = value {
+                debug_assert!(
+                    !h.has_field_expiries() || self.field_ttl_keys.contains(key),
+                    "Invariant violated: hash '{}' has field deadlines but is not tracked",
+                    key
+                );
+            }
+        }
+
+        // Invariant 6: Every key with an access time must exist in data
+        for key in self.access_times.keys() {
+            debug_assert!(
+                self.data.contains_key(key),
+                "Invariant violated: access time for key '{}' has no corresponding data entry",
+                key
+            );
+        }
     }
 
+    #[cfg(not(debug_assertions))]
+    pub fn verify_invariants(&self) {}
+
     pub(crate) fn get_value(&mut self, key: &str) -> Option<&Value> {
         if self.is_expired(key) {
             self.data.remove(key);
             self.expirations.remove(key);
+            self.client_tracking.invalidate_key(key);
             None
         } else {
+            self.expire_hash_fields(key);
             self.data.get(key)
         }
     }
@@ -306,8 +670,10 @@ impl CommandExecutor {
         if self.is_expired(key) {
             self.data.remove(key);
             self.expirations.remove(key);
+            self.client_tracking.invalidate_key(key);
             None
         } else {
+            self.expire_hash_fields(key);
             self.data.get_mut(key)
         }
     }
@@ -344,16 +710,18 @@ impl CommandExecutor {
                 RespValue::Integer(count as i64)
             }
             Command::Keys(pattern) => {
+                let glob = self.glob_pattern(pattern);
                 let matching: Vec<RespValue> = self
                     .data
                     .keys()
-                    .filter(|k| !self.is_expired(k) && self.matches_glob_pattern(k, pattern))
+                    .filter(|k| !self.is_expired(k) && glob.matches(k.as_bytes()))
                     .map(|k| RespValue::BulkString(Some(k.as_bytes().to_vec())))
                     .collect();
                 RespValue::Array(Some(matching))
             }
             Command::Ping(None) => RespValue::simple("PONG"),
             Command::Ping(Some(msg)) => RespValue::BulkString(Some(msg.as_bytes().to_vec())),
+            Command::MemoryStats => self.execute_memory_stats(),
             _ => RespValue::err("ERR command not supported in readonly mode"),
         }
     }
@@ -389,22 +757,56 @@ impl CommandExecutor {
         }
 
         // Handle command queueing when in transaction
-        if self.in_transaction {
+        if self.conn.in_transaction {
             match cmd {
                 // These commands are executed immediately even in transaction
-                Command::Exec | Command::Discard | Command::Multi => {}
+                Command::Exec | Command::Discard | Command::Multi | Command::Reset => {}
                 // WATCH inside MULTI is an error (not queued)
                 Command::Watch(_) => {
                     return RespValue::err("ERR WATCH inside MULTI is not allowed");
                 }
+                // Unknown commands are refused and doom the transaction;
+                // unknown subcommands (`CLIENT FOO`) fail when EXEC runs them
+                Command::Unknown(name) if !name.contains(' ') => {
+                    return self.reject(format!("ERR unknown command '{}'", name));
+                }
                 // All other commands get queued
                 _ => {
-                    self.queued_commands.push(cmd.clone());
+                    self.conn.queued_commands.push(cmd.clone());
                     return RespValue::simple("QUEUED");
                 }
             }
         }
 
+        if let Some(oom) = self.evict_for_write(cmd) {
+            return oom;
+        }
+
+        let snapshot = self.stats_snapshot(cmd);
+        let response = self.dispatch(cmd);
+        self.stats_commit(snapshot, !key_ops::is_notouch(cmd));
+        self.invalidate_tracked(cmd);
+
+        // TigerStyle: every command must leave the keyspace consistent
+        self.verify_invariants();
+
+        response
+    }
+
+    /// Tell CLIENT TRACKING about the keys a write may have modified
+    fn invalidate_tracked(&self, cmd: &Command) {
+        if cmd.is_read_only() || !self.client_tracking.is_active() {
+            return;
+        }
+        match cmd {
+            Command::FlushDb | Command::FlushAll => self.client_tracking.invalidate_all(),
+            _ => self.client_tracking.invalidate(&cmd.get_keys()),
+        }
+    }
+
+    /// Route a command to its implementation. Transaction queueing and
+    /// fault injection are handled by `execute()` before we get here.
+    fn dispatch(&mut self, cmd: &Command) -> RespValue {
         match cmd {
             // Server commands
             Command::Ping(None) => RespValue::simple("PONG"),
@@ -436,7 +838,6 @@ impl CommandExecutor {
                 get,
                 keepttl,
             } => self.execute_set(key, value, ex, px, exat, pxat, nx, xx, get, keepttl),
-            Command::SetNx(key, value) => self.execute_setnx(key, value),
             Command::Append(key, value) => self.execute_append(key, value),
             Command::GetSet(key, value) => self.execute_getset(key, value),
             Command::StrLen(key) => self.execute_strlen(key),
@@ -449,6 +850,12 @@ impl CommandExecutor {
             Command::SetRange(key, offset, value) => self.execute_setrange(key, *offset, value),
             Command::SetBit(key, offset, value) => self.execute_setbit(key, *offset, *value),
             Command::GetBit(key, offset) => self.execute_getbit(key, *offset),
+            Command::BitCount { key, range } => self.execute_bitcount(key, *range),
+            Command::BitPos { key, bit, range } => self.execute_bitpos(key, *bit, *range),
+            Command::BitOp { op, dest, keys } => self.execute_bitop(*op, dest, keys),
+            Command::BitField { key, ops } | Command::BitFieldRo { key, ops } => {
+                self.execute_bitfield(key, ops)
+            }
             Command::GetEx {
                 key,
                 ex,
@@ -469,7 +876,9 @@ impl CommandExecutor {
 
             // Key commands
             Command::Del(keys) => self.execute_del(keys),
+            Command::Unlink(keys) => self.execute_unlink(keys),
             Command::Exists(keys) => self.execute_exists(keys),
+            Command::Touch(keys) => self.execute_touch(keys),
             Command::TypeOf(key) => self.execute_typeof(key),
             Command::Keys(pattern) => self.execute_keys(pattern),
             Command::FlushDb | Command::FlushAll => self.execute_flush(),
@@ -481,7 +890,6 @@ impl CommandExecutor {
                 gt,
                 lt,
             } => self.execute_expire(key, *seconds, *nx, *xx, *gt, *lt),
-            Command::ExpireAt(key, timestamp) => self.execute_expireat(key, *timestamp),
             Command::PExpire {
                 key,
                 milliseconds,
@@ -490,13 +898,8 @@ impl CommandExecutor {
                 gt,
                 lt,
             } => self.execute_pexpire(key, *milliseconds, *nx, *xx, *gt, *lt),
-            Command::PExpireAt(key, timestamp_millis) => {
-                self.execute_pexpireat(key, *timestamp_millis)
-            }
             Command::Ttl(key) => self.execute_ttl(key),
             Command::Pttl(key) => self.execute_pttl(key),
-            Command::ExpireTime(key) => self.execute_expiretime(key),
-            Command::PExpireTime(key) => self.execute_pexpiretime(key),
             Command::Persist(key) => self.execute_persist(key),
 
             // List commands
@@ -516,6 +919,31 @@ impl CommandExecutor {
                 wherefrom,
                 whereto,
             } => self.execute_lmove(source, dest, wherefrom, whereto),
+            Command::LInsert {
+                key,
+                before,
+                pivot,
+                value,
+            } => self.execute_linsert(key, *before, pivot, value),
+            Command::LRem(key, count, element) => self.execute_lrem(key, *count, element),
+            Command::LPos {
+                key,
+                element,
+                rank,
+                count,
+                maxlen,
+            } => self.execute_lpos(key, element, *rank, *count, *maxlen),
+            Command::LPushX(key, values) => self.execute_pushx(key, values, true),
+            Command::RPushX(key, values) => self.execute_pushx(key, values, false),
+            Command::BLPop { keys, .. } => self.execute_blpop(keys, true),
+            Command::BRPop { keys, .. } => self.execute_blpop(keys, false),
+            Command::BLMove {
+                source,
+                dest,
+                wherefrom,
+                whereto,
+                ..
+            } => self.execute_lmove(source, dest, wherefrom, whereto),
 
             // Set commands
             Command::SAdd(key, members) => self.execute_sadd(key, members),
@@ -523,6 +951,9 @@ impl CommandExecutor {
             Command::SMembers(key) => self.execute_smembers(key),
             Command::SIsMember(key, member) => self.execute_sismember(key, member),
             Command::SCard(key) => self.execute_scard(key),
+            Command::SMIsMember(key, members) => self.execute_smismember(key, members),
+            Command::SRandMember(key, count) => self.execute_srandmember(key, *count),
+            Command::SInterCard { keys, limit } => self.execute_sintercard(keys, *limit),
             Command::SPop(key, count) => self.execute_spop(key, *count),
 
             // Hash commands
@@ -530,11 +961,27 @@ impl CommandExecutor {
             Command::HGet(key, field) => self.execute_hget(key, field),
             Command::HDel(key, fields) => self.execute_hdel(key, fields),
             Command::HGetAll(key) => self.execute_hgetall(key),
-            Command::HKeys(key) => self.execute_hkeys(key),
-            Command::HVals(key) => self.execute_hvals(key),
             Command::HLen(key) => self.execute_hlen(key),
             Command::HExists(key, field) => self.execute_hexists(key, field),
             Command::HIncrBy(key, field, increment) => self.execute_hincrby(key, field, *increment),
+            Command::HIncrByFloat(key, field, increment) => {
+                self.execute_hincrbyfloat(key, field, *increment)
+            }
+            Command::HMGet(key, fields) => self.execute_hmget(key, fields),
+            Command::HExpire {
+                key,
+                time,
+                form,
+                condition,
+                fields,
+            } => self.execute_hexpire(key, *time, *form, *condition, fields),
+            Command::HTtl { key, form, fields } => self.execute_httl(key, *form, fields),
+            Command::HPersist(key, fields) => self.execute_hpersist(key, fields),
+            Command::HRandField {
+                key,
+                count,
+                with_values,
+            } => self.execute_hrandfield(key, *count, *with_values),
 
             // Sorted set commands
             Command::ZAdd {
@@ -547,14 +994,19 @@ impl CommandExecutor {
                 ch,
             } => self.execute_zadd(key, pairs, *nx, *xx, *gt, *lt, *ch),
             Command::ZRem(key, members) => self.execute_zrem(key, members),
-            Command::ZRange(key, start, stop, with_scores) => {
-                self.execute_zrange(key, *start, *stop, *with_scores)
-            }
+            Command::ZRemRange(key, range) => self.execute_zremrange(key, range),
+            Command::ZRange {
+                dest,
+                key,
+                range,
+                rev,
+                limit,
+                with_scores,
+            } => self.execute_zrange(dest.as_deref(), key, range, *rev, *limit, *with_scores),
             Command::ZRevRange(key, start, stop, with_scores) => {
                 self.execute_zrevrange(key, *start, *stop, *with_scores)
             }
             Command::ZScore(key, member) => self.execute_zscore(key, member),
-            Command::ZRank(key, member) => self.execute_zrank(key, member),
             Command::ZCard(key) => self.execute_zcard(key),
             Command::ZCount(key, min, max) => self.execute_zcount(key, min, max),
             Command::ZRangeByScore {
@@ -564,13 +1016,117 @@ impl CommandExecutor {
                 with_scores,
                 limit,
             } => self.execute_zrangebyscore(key, min, max, *with_scores, limit),
+            Command::ZPopMin { key, count } => self.execute_zpop(key, *count, true),
+            Command::ZPopMax { key, count } => self.execute_zpop(key, *count, false),
+            Command::ZIncrBy(key, increment, member) => self.execute_zincrby(key, *increment, member),
+            Command::ZMScore(key, members) => self.execute_zmscore(key, members),
+            Command::ZRandMember {
+                key,
+                count,
+                with_scores,
+            } => self.execute_zrandmember(key, *count, *with_scores),
+            Command::ZSetOp {
+                op,
+                dest,
+                keys,
+                weights,
+                aggregate,
+                with_scores,
+            } => self.execute_zset_op(
+                *op,
+                dest.as_deref(),
+                keys,
+                weights.as_deref(),
+                *aggregate,
+                *with_scores,
+            ),
+            Command::BZPopMin { keys, .. } => self.execute_bzpop(keys, true),
+            Command::BZPopMax { keys, .. } => self.execute_bzpop(keys, false),
+
+            // Stream commands
+            Command::XAdd {
+                key,
+                id,
+                fields,
+                nomkstream,
+                maxlen,
+            } => self.execute_xadd(key, *id, fields, *nomkstream, *maxlen),
+            Command::XRange {
+                key,
+                start,
+                end,
+                count,
+            } => self.execute_xrange(key, *start, *end, *count, false),
+            Command::XRevRange {
+                key,
+                start,
+                end,
+                count,
+            } => self.execute_xrange(key, *start, *end, *count, true),
+            Command::XRead {
+                count, keys, ids, ..
+            } => self.execute_xread(*count, keys, ids),
+            Command::XGroupCreate {
+                key,
+                group,
+                id,
+                mkstream,
+            } => self.execute_xgroup_create(key, group, *id, *mkstream),
+            Command::XGroupSetId { key, group, id } => self.execute_xgroup_setid(key, group, *id),
+            Command::XGroupDestroy { key, group } => self.execute_xgroup_destroy(key, group),
+            Command::XGroupCreateConsumer {
+                key,
+                group,
+                consumer,
+            } => self.execute_xgroup_consumer(key, group, consumer, true),
+            Command::XGroupDelConsumer {
+                key,
+                group,
+                consumer,
+            } => self.execute_xgroup_consumer(key, group, consumer, false),
+            Command::XReadGroup {
+                group,
+                consumer,
+                count,
+                noack,
+                keys,
+                ids,
+                ..
+            } => self.execute_xreadgroup(group, consumer, *count, *noack, keys, ids),
+            Command::XAck { key, group, ids } => self.execute_xack(key, group, ids),
+            Command::XPending { key, group, range } => {
+                self.execute_xpending(key, group, range.as_ref())
+            }
+            Command::XClaim {
+                key,
+                group,
+                consumer,
+                min_idle_ms,
+                ids,
+                opts,
+            } => self.execute_xclaim(key, group, consumer, *min_idle_ms, ids, *opts),
+            Command::XAutoClaim {
+                key,
+                group,
+                consumer,
+                min_idle_ms,
+                start,
+                count,
+                justid,
+            } => self.execute_xautoclaim(key, group, consumer, *min_idle_ms, *start, *count, *justid),
 
             // Scan commands
             Command::Scan {
                 cursor,
                 pattern,
                 count,
-            } => self.execute_scan(*cursor, pattern.as_deref(), *count),
+                type_filter,
+            } => self.execute_scan(
+                *cursor,
+                pattern.as_deref(),
+                *count,
+                type_filter.as_deref(),
+            ),
             Command::HScan {
                 key,
                 cursor,
@@ -590,13 +1146,17 @@ impl CommandExecutor {
             Command::Discard => self.execute_discard(),
             Command::Watch(keys) => self.execute_watch(keys),
             Command::Unwatch => self.execute_unwatch(),
+            Command::Reset => self.execute_reset(),
 
             // Script commands
-            Command::Eval { script, keys, args } => self.execute_eval(script, keys, args),
-            Command::EvalSha { sha1, keys, args } => self.execute_evalsha(sha1, keys, args),
+            Command::Eval { script, keys, args } => self.execute_eval(script, keys, args, false),
+            Command::EvalSha { sha1, keys, args } => self.execute_evalsha(sha1, keys, args, false),
+            Command::EvalRo { script, keys, args } => self.execute_eval(script, keys, args, true),
+            Command::EvalShaRo { sha1, keys, args } => self.execute_evalsha(sha1, keys, args, true),
             Command::ScriptLoad(script) => self.execute_script_load(script),
             Command::ScriptExists(sha1s) => self.execute_script_exists(sha1s),
             Command::ScriptFlush => self.execute_script_flush(),
+            Command::ScriptKill => self.execute_script_kill(),
 
             // ACL commands
             Command::Auth { .. } => self.execute_auth(),
@@ -610,16 +1170,31 @@ impl CommandExecutor {
             Command::AclGenPass { bits } => self.execute_acl_genpass(*bits),
 
             // Config commands
-            Command::ConfigGet(pattern) => self.execute_config_get(pattern),
+            Command::ConfigGet(patterns) => self.execute_config_get(patterns),
             Command::ConfigSet(param, value) => self.execute_config_set(param, value),
             Command::ConfigResetStat => self.execute_config_resetstat(),
 
+            // Latency monitor
+            Command::LatencyLatest => self.execute_latency_latest(),
+            Command::LatencyHistory(event) => self.execute_latency_history(event),
+            Command::LatencyReset(events) => self.execute_latency_reset(events),
+            Command::LatencyDoctor => self.execute_latency_doctor(),
+            Command::LatencyHelp => self.execute_latency_help(),
+
+            // Memory introspection
+            Command::MemoryUsage { key, samples } => self.execute_memory_usage(key, *samples),
+            Command::MemoryStats => self.execute_memory_stats(),
+            Command::MemoryDoctor => self.execute_memory_doctor(),
+            Command::MemoryHelp => self.execute_memory_help(),
+
             // Select command
             Command::Select(_db) => {
                 debug_assert!(*_db <= 15, "Precondition: database index must be 0-15");
                 RespValue::ok()
             }
 
+            Command::Lolwut { version, params } => self.execute_lolwut(*version, params),
+
             // Echo command
             Command::Echo(msg) => {
                 let resp = RespValue::BulkString(Some(msg.as_bytes().to_vec()));
@@ -630,26 +1205,81 @@ impl CommandExecutor {
                 resp
             }
 
-            // Function commands (stubs for Tcl harness)
-            Command::FunctionFlush => RespValue::ok(),
-
-            // Command introspection (stubs for Tcl harness)
-            Command::CommandCommand => {
-                // Return empty array — tests just need it to not crash
-                RespValue::Array(Some(vec![]))
+            // Pub/Sub: subscriptions live on the connection, not the keyspace
+            Command::Publish { channel, message } => {
+                RespValue::Integer(self.pubsub.publish(channel, message.as_bytes()) as i64)
             }
-            Command::CommandCount => {
-                // Return a plausible count
-                RespValue::Integer(200)
+            Command::Subscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PSubscribe(_)
+            | Command::PUnsubscribe(_) => RespValue::err(format!(
+                "ERR {} is only available on client connections",
+                cmd.name()
+            )),
+            Command::PubSubChannels(pattern) => RespValue::Array(Some(
+                self.pubsub
+                    .channels(pattern.as_deref())
+                    .into_iter()
+                    .map(|channel| RespValue::BulkString(Some(channel.into_bytes())))
+                    .collect(),
+            )),
+            Command::PubSubNumSub(channels) => {
+                let mut reply = Vec::with_capacity(channels.len() * 2);
+                for channel in channels {
+                    let subscribers = self.pubsub.num_subscribers(channel);
+                    reply.push(RespValue::BulkString(Some(channel.as_bytes().to_vec())));
+                    reply.push(RespValue::Integer(subscribers as i64));
+                }
+                RespValue::Array(Some(reply))
             }
+            Command::PubSubNumPat => RespValue::Integer(self.pubsub.num_patterns() as i64),
 
-            // Client commands (stubs)
+            // Function commands (stubs for Tcl harness)
+            Command::FunctionLoad { code, replace } => self.execute_function_load(code, *replace),
+            Command::FunctionDelete(library) => self.execute_function_delete(library),
+            Command::FunctionFlush => self.execute_function_flush(),
+            Command::FunctionList { pattern, with_code } => {
+                self.execute_function_list(pattern.as_deref(), *with_code)
+            }
+            Command::FunctionDump => self.execute_function_dump(),
+            Command::FunctionRestore { payload, policy } => {
+                self.execute_function_restore(payload, *policy)
+            }
+            Command::FunctionStats => self.execute_function_stats(),
+            Command::FCall {
+                function,
+                keys,
+                args,
+            } => self.execute_fcall(function, keys, args, false),
+            Command::FCallRo {
+                function,
+                keys,
+                args,
+            } => self.execute_fcall(function, keys, args, true),
+
+            // Command introspection, answered from the command table
+            Command::CommandCommand => self.execute_command_info(&[]),
+            Command::CommandCount => self.execute_command_count(),
+            Command::CommandInfo(names) => self.execute_command_info(names),
+            Command::CommandDocs(names) => self.execute_command_docs(names),
+            Command::CommandGetKeys(args) => self.execute_command_getkeys(args),
+
+            // Client commands (stubs: production connections answer them from
+            // their session registry)
             Command::ClientSetName(_) => RespValue::ok(),
             Command::ClientGetName => RespValue::BulkString(None),
             Command::ClientId => RespValue::Integer(1),
             Command::ClientInfo => {
                 RespValue::BulkString(Some(b"id=1 fd=5 name= db=0 flags=N".to_vec()))
             }
+            Command::ClientList => {
+                RespValue::BulkString(Some(b"id=1 fd=5 name= db=0 flags=N\n".to_vec()))
+            }
+            Command::ClientKill { legacy: true, .. } => RespValue::err("ERR No such client"),
+            Command::ClientKill { .. } => RespValue::Integer(0),
+            Command::ClientPause { .. }
+            | Command::ClientUnpause
+            | Command::ClientTracking { .. } => RespValue::ok(),
 
             // Object commands
             Command::ObjectHelp => {
@@ -663,49 +1293,9 @@ impl CommandExecutor {
                 RespValue::Array(Some(help))
             }
             Command::ObjectEncoding(key) => {
-                match self.get_value(key) {
-                    Some(Value::String(s)) => {
-                        if std::str::from_utf8(s.as_bytes())
-                            .unwrap_or("")
-                            .parse::<i64>()
-                            .is_ok()
-                        {
-                            RespValue::BulkString(Some(b"int".to_vec()))
-                        } else if s.as_bytes().len() <= 44 {
-                            RespValue::BulkString(Some(b"embstr".to_vec()))
-                        } else {
-                            RespValue::BulkString(Some(b"raw".to_vec()))
-                        }
-                    }
-                    Some(Value::List(l)) => {
-                        if l.len() <= 128 {
-                            RespValue::BulkString(Some(b"listpack".to_vec()))
-                        } else {
-                            RespValue::BulkString(Some(b"quicklist".to_vec()))
-                        }
-                    }
-                    Some(Value::Set(s)) => {
-                        if s.len() <= 128 {
-                            RespValue::BulkString(Some(b"listpack".to_vec()))
-                        } else {
-                            RespValue::BulkString(Some(b"hashtable".to_vec()))
-                        }
-                    }
-                    Some(Value::Hash(h)) => {
-                        if h.len() <= 128 {
-                            RespValue::BulkString(Some(b"listpack".to_vec()))
-                        } else {
-                            RespValue::BulkString(Some(b"hashtable".to_vec()))
-                        }
-                    }
-                    Some(Value::SortedSet(z)) => {
-                        if z.len() <= 128 {
-                            RespValue::BulkString(Some(b"listpack".to_vec()))
-                        } else {
-                            RespValue::BulkString(Some(b"skiplist".to_vec()))
-                        }
-                    }
-                    None | Some(Value::Null) => RespValue::err("ERR no such key"),
+                match self.get_value(key).and_then(debug_ops::object_encoding) {
+                    Some(encoding) => RespValue::BulkString(Some(encoding.as_bytes().to_vec())),
+                    None => RespValue::err("ERR no such key"),
                 }
             }
             Command::ObjectRefCount(key) => {
@@ -717,7 +1307,7 @@ impl CommandExecutor {
             }
             Command::ObjectIdleTime(key) => {
                 if self.get_value(key).is_some() {
-                    RespValue::Integer(0)
+                    RespValue::Integer(self.idle_time_secs(key) as i64)
                 } else {
                     RespValue::err("ERR no such key")
                 }
@@ -730,28 +1320,51 @@ impl CommandExecutor {
                 }
             }
 
-            // Debug commands (stubs)
-            Command::DebugSleep(_) => RespValue::ok(),
+            // Debug commands
+            Command::DebugSleep(seconds) => self.execute_debug_sleep(*seconds),
+            Command::DebugSetActiveExpire(enabled) => {
+                self.active_expire = *enabled;
+                RespValue::ok()
+            }
+            Command::DebugReload => self.execute_debug_reload(),
             Command::DebugSet(_, _) => RespValue::ok(),
-            Command::DebugObject(key) => {
-                match self.get_value(key) {
-                    Some(_) => {
-                        RespValue::BulkString(Some(
-                            b"Value at:0x0 refcount:1 encoding:raw serializedlength:0 lru:0 lru_seconds_idle:0 type:string".to_vec()
-                        ))
-                    }
-                    None => RespValue::err("ERR no such key"),
+            Command::DebugKeyStats => self.execute_debug_keystats(),
+            Command::DebugProtocol(kind) => self.execute_debug_protocol(kind),
+            Command::DebugStringMatchLen => {
+                match glob::fuzz_against_reference(&mut self.rng, glob::STRINGMATCH_FUZZ_ROUNDS) {
+                    None => RespValue::simple("Apparently Redis did not crash: test passed"),
+                    Some((pattern, key)) => RespValue::err(format!(
+                        "ERR glob matcher disagrees with its reference: pattern {:?}, key {:?}",
+                        String::from_utf8_lossy(&pattern),
+                        String::from_utf8_lossy(&key)
+                    )),
                 }
             }
+            // A bare executor has no components to check; the persistent
+            // server answers from its HealthCheck (production/health.rs)
+            Command::DebugHealth => RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(b"status".to_vec())),
+                RespValue::BulkString(Some(b"ok".to_vec())),
+            ])),
+            Command::DebugSnapshotRead(cmds) => self.execute_debug_snapshot_read(cmds),
+            Command::DebugBuggifySet { fault, probability } => {
+                self.execute_debug_buggify_set(fault, *probability)
+            }
+            Command::DebugBuggifyStats => self.execute_debug_buggify_stats(),
+            Command::DebugObject(key) => self.execute_debug_object(key),
 
             // RANDOMKEY
             Command::RandomKey => {
-                // Return a random non-expired key, or nil
-                let key = self.data.keys().find(|k| !self.is_expired(k)).cloned();
-                match key {
-                    Some(k) => RespValue::BulkString(Some(k.into_bytes())),
-                    None => RespValue::BulkString(None),
+                // The i-th smallest live key: hash order never leaks into
+                // the reply, so a seed replays it
+                let mut live: Vec<&String> =
+                    self.data.keys().filter(|k| !self.is_expired(k)).collect();
+                if live.is_empty() {
+                    return RespValue::BulkString(None);
                 }
+                let i = self.rng.gen_range(0, live.len() as u64) as usize;
+                let (_, key, _) = live.select_nth_unstable(i);
+                RespValue::BulkString(Some(key.as_bytes().to_vec()))
             }
 
             // RENAME
@@ -797,54 +1410,85 @@ impl CommandExecutor {
                 RespValue::Integer(1)
             }
 
-            // WAIT - no replicas in simulation
-            Command::Wait(_, _) => RespValue::Integer(0),
-
-            // SORT - minimal stub (returns sorted elements, stores if STORE)
-            Command::Sort { key, store } => {
-                let members: Vec<SDS> = match self.get_value(key) {
-                    Some(Value::List(l)) => {
-                        l.range(0, l.len() as isize - 1)
-                    }
-                    Some(Value::Set(s)) => s.members(),
-                    None => vec![],
-                    Some(_) => {
-                        return RespValue::err(
-                            "WRONGTYPE Operation against a key holding the wrong kind of value",
-                        )
-                    }
+            // COPY
+            Command::Copy {
+                source,
+                dest,
+                replace,
+            } => {
+                if source == dest {
+                    return RespValue::err("ERR source and destination objects are the same");
+                }
+                let Some(val) = self.get_value(source).cloned() else {
+                    return RespValue::Integer(0);
                 };
-                // Sort lexicographically (basic sort; Redis sorts numerically by default but this is a stub)
-                let mut sorted = members;
-                sorted.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
-                let items: Vec<RespValue> = sorted
-                    .iter()
-                    .map(|s| RespValue::BulkString(Some(s.as_bytes().to_vec())))
-                    .collect();
-                if let Some(dest) = store {
-                    let count = items.len() as i64;
-                    if sorted.is_empty() {
-                        self.data.remove(dest);
-                        self.expirations.remove(dest);
-                    } else {
-                        use crate::redis::data::RedisList;
-                        let mut list = RedisList::new();
-                        for s in &sorted {
-                            list.rpush(s.clone());
-                        }
-                        self.data.insert(dest.clone(), Value::List(list));
-                        self.expirations.remove(dest);
-                    }
-                    RespValue::Integer(count)
+                if !*replace && self.get_value(dest).is_some() {
+                    return RespValue::Integer(0);
+                }
+                let exp = self.expirations.get(source).copied();
+                self.data.insert(dest.clone(), val);
+                if let Some(exp_time) = exp {
+                    self.expirations.insert(dest.clone(), exp_time);
                 } else {
-                    RespValue::Array(Some(items))
+                    self.expirations.remove(dest);
                 }
+                #[cfg(debug_assertions)]
+                {
+                    debug_assert!(self.data.contains_key(dest.as_str()), "Postcondition: COPY dst must exist");
+                    debug_assert!(self.data.contains_key(source.as_str()), "Postcondition: COPY src must survive");
+                    debug_assert_eq!(
+                        self.expirations.get(source),
+                        self.expirations.get(dest),
+                        "Postcondition: COPY dst must share the src deadline"
+                    );
+                }
+                RespValue::Integer(1)
             }
 
-            // Stubs for stream commands (return minimal valid responses)
-            Command::Unknown(cmd) if cmd.eq_ignore_ascii_case("XADD") => {
-                RespValue::BulkString(Some(b"0-1".to_vec()))
+            // DUMP / RESTORE
+            Command::Restore {
+                key,
+                ttl_ms,
+                payload,
+                replace,
+                absttl,
+                ..
+            } => self.execute_restore(key, *ttl_ms, payload, *replace, *absttl),
+
+            // WAIT - a lone executor has no replicas; the replicated state
+            // and the multi-node simulator answer it from replication acks
+            Command::Wait(_, _) => RespValue::Integer(0),
+
+            // SHUTDOWN - stops the server, so only a server connection can
+            // answer it
+            Command::Shutdown { .. } => {
+                RespValue::err("ERR SHUTDOWN is handled at connection level, not executor")
             }
+
+            // HELLO - negotiates the connection's protocol
+            Command::Hello { .. } => {
+                RespValue::err("ERR HELLO is handled at connection level, not executor")
+            }
+
+            Command::Sort {
+                key,
+                by,
+                limit,
+                get,
+                desc,
+                alpha,
+                store,
+            } => self.execute_sort(
+                key,
+                by.as_deref(),
+                *limit,
+                get,
+                *desc,
+                *alpha,
+                store.as_deref(),
+            ),
+
+            // Stub for XINFO (returns a minimal valid response)
             Command::Unknown(cmd) if cmd.starts_with("XINFO") => {
                 RespValue::Array(Some(Vec::new()))
             }
@@ -854,6 +1498,9 @@ impl CommandExecutor {
                 RespValue::err("ERR ACL commands are handled at the connection level")
             }
 
+            // Commands from declare_commands!
+            Command::Declared(cmd) => cmd.execute(self),
+
             // Unknown
             Command::Unknown(cmd) => RespValue::err(format!("ERR unknown command '{}'", cmd)),
         }
@@ -863,16 +1510,23 @@ impl CommandExecutor {
     fn execute_info(&self) -> RespValue {
         let info = format!(
             "# Server\r\n\
-             redis_mode:simulator\r\n\
+             {}\
              \r\n\
              # Stats\r\n\
              total_commands_processed:{}\r\n\
              total_keys:{}\r\n\
              keys_with_expiration:{}\r\n\
+             evicted_keys:{}\r\n\
+             lazyfree_pending_objects:{}\r\n\
+             lazyfreed_objects:{}\r\n\
              current_time_ms:{}\r\n",
+            info_server_fields(ServerMode::Simulator),
             self.commands_processed,
             self.data.len(),
             self.expirations.len(),
+            self.evicted_keys_total(),
+            self.lazyfree_pending_objects(),
+            self.lazyfreed_objects(),
             self.current_time.as_millis()
         );
         RespValue::BulkString(Some(info.into_bytes()))
@@ -883,102 +1537,9 @@ impl CommandExecutor {
         RespValue::Integer(valid_keys as i64)
     }
 
-    /// Helper for glob pattern matching
-    pub(crate) fn matches_glob_pattern(&self, key: &str, pattern: &str) -> bool {
-        let key_bytes = key.as_bytes();
-        let pattern_bytes = pattern.as_bytes();
-        self.glob_match(key_bytes, pattern_bytes, 0, 0)
-    }
-
-    fn glob_match(&self, key: &[u8], pattern: &[u8], k_idx: usize, p_idx: usize) -> bool {
-        if p_idx >= pattern.len() {
-            return k_idx >= key.len();
-        }
-
-        let p_char = pattern[p_idx];
-
-        if p_char == b'*' {
-            // Try matching zero or more characters
-            for i in k_idx..=key.len() {
-                if self.glob_match(key, pattern, i, p_idx + 1) {
-                    return true;
-                }
-            }
-            false
-        } else if p_char == b'?' {
-            if k_idx >= key.len() {
-                false
-            } else {
-                self.glob_match(key, pattern, k_idx + 1, p_idx + 1)
-            }
-        } else if p_char == b'[' {
-            // Character class
-            let mut bracket_end = p_idx + 1;
-            while bracket_end < pattern.len() && pattern[bracket_end] != b']' {
-                bracket_end += 1;
-            }
-            if bracket_end >= pattern.len() {
-                return false;
-            }
-
-            let char_set = &pattern[p_idx + 1..bracket_end];
-            let (negate, char_set) = if !char_set.is_empty() && char_set[0] == b'^' {
-                (true, &char_set[1..])
-            } else {
-                (false, char_set)
-            };
-
-            if k_idx >= key.len() {
-                return false;
-            }
-
-            // Handle ranges like [a-z]
-            let mut chars_to_check: Vec<u8> = Vec::new();
-            let mut i = 0;
-            while i < char_set.len() {
-                if i + 2 < char_set.len() && char_set[i + 1] == b'-' {
-                    let start = char_set[i];
-                    let end = char_set[i + 2];
-                    for c in start..=end {
-                        chars_to_check.push(c);
-                    }
-                    i += 3;
-                } else {
-                    chars_to_check.push(char_set[i]);
-                    i += 1;
-                }
-            }
-
-            let chars_to_check = if chars_to_check.is_empty() {
-                char_set.to_vec()
-            } else {
-                chars_to_check
-            };
-
-            let mut matched = false;
-            for c in &chars_to_check {
-                if *c == key[k_idx] {
-                    matched = true;
-                    break;
-                }
-            }
-
-            if negate {
-                matched = !matched;
-            }
-
-            if matched {
-                self.glob_match(key, pattern, k_idx + 1, bracket_end + 1)
-            } else {
-                false
-            }
-        } else {
-            if k_idx >= key.len() || key[k_idx] != p_char {
-                false
-            } else {
-                self.glob_match(key, pattern, k_idx + 1, p_idx + 1)
-            }
-        }
+    /// Compiled glob for KEYS and SCAN MATCH, cached across calls
+    pub(crate) fn glob_pattern(&self, pattern: &str) -> Arc<GlobPattern> {
+        self.glob_cache.lock().get(pattern)
     }
 }
 
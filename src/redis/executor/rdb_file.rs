@@ -0,0 +1,57 @@
+//! Whole-keyspace RDB files: a SAVE-style snapshot of the live keys, and
+//! loading one back into an executor at startup.
+//!
+//! Deadlines are written as Unix milliseconds (the executor clock plus
+//! `simulation_start_epoch_ms`) and read back as TTLs from the loading
+//! executor's clock, so a key keeps its deadline across a restart however
+//! long the executor was down; one that passed meanwhile is not loaded.
+//!
+//! Function libraries are saved with the keys. Loading replaces libraries of
+//! the same name and keeps the others, since shards sharing one library
+//! store each load their own file.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Snapshots are deterministic: keys are written in sorted order
+//! - Loading a snapshot taken at the same instant reproduces every live key
+
+use super::{BulkLoadReport, CommandExecutor};
+use crate::redis::command::FunctionRestorePolicy;
+use crate::redis::rdb;
+
+impl CommandExecutor {
+    /// Encode every live key and every function library as an RDB file
+    pub fn rdb_snapshot(&self) -> Vec<u8> {
+        let epoch_ms = self.simulation_start_epoch_ms;
+        let mut keys: Vec<&String> = self
+            .data
+            .keys()
+            .filter(|key| !self.is_expired(key))
+            .collect();
+        keys.sort();
+        let entries = keys.into_iter().map(|key| {
+            let deadline = self.expirations.get(key).map(|deadline| {
+                (epoch_ms as i128 + deadline.as_millis() as i128).clamp(0, u64::MAX as i128) as u64
+            });
+            (key.as_str(), &self.data[key], deadline)
+        });
+        rdb::write_file_with_functions(&self.functions.codes(), entries, epoch_ms)
+    }
+
+    /// Install the keys and function libraries of an RDB file, replacing
+    /// those of the same name; keys whose deadline has passed are skipped
+    pub fn load_rdb_file(&mut self, bytes: &[u8]) -> Result<BulkLoadReport, String> {
+        let epoch_ms = self.simulation_start_epoch_ms;
+        let file = rdb::read_file(bytes, true, epoch_ms)?;
+        self.restore_libraries(&file.functions, FunctionRestorePolicy::Replace)?;
+        let now_unix_ms = (epoch_ms as i128 + self.current_time.as_millis() as i128).max(0) as u64;
+        let entries = file.keys.into_iter().map(|key| {
+            // A passed deadline becomes a zero TTL, which bulk_load skips
+            let ttl_ms = key
+                .expire_at_ms
+                .map(|deadline| deadline.saturating_sub(now_unix_ms));
+            (key.key, key.value, ttl_ms)
+        });
+        Ok(self.bulk_load(entries))
+    }
+}
@@ -1,69 +1,180 @@
 //! Scan command implementations for CommandExecutor.
 //!
-//! Handles: SCAN, HSCAN, ZSCAN
+//! Handles: SCAN, HSCAN, ZSCAN, and chunked KEYS
+//!
+//! Chunked KEYS lets a production shard answer KEYS without holding its
+//! actor for the whole keyspace. With `keys-chunk-size` set, the keyspace
+//! is split by a fixed hash into as many chunks as that size needs, and
+//! each `keys_chunk` call matches one chunk; the coordinator sends one
+//! message per chunk, so other commands run in between. A key's chunk never
+//! changes, so a key that exists for the whole scan is returned exactly
+//! once however the keyspace is written meanwhile. Each chunk still walks
+//! every key to hash it, but the expiry check, the glob match and the reply
+//! are paid only for the chunk's own keys.
+//!
+//! SCAN gives the same guarantee without chunks: every key has a fixed
+//! position (the same seeded hash, never 0), and the cursor is the position
+//! to resume from. A page is the keys at or past the cursor in position
+//! order, so writes between calls cannot shift a key behind the cursor the
+//! way an index into the current key order would: a key that exists for
+//! the whole scan is returned at least once, and a key is returned twice
+//! only if it is deleted and re-added. The sharded server pages every
+//! shard from the same cursor and merges by position (`scan_page`).
 //!
 //! # TigerStyle Invariants
 //!
 //! - SCAN returns [cursor, keys_array] where cursor is "0" when complete
-//! - Result count never exceeds requested COUNT
-//! - Keys are returned in sorted order for deterministic iteration
+//! - A page exceeds COUNT only to finish keys sharing the last position
+//! - Cursors strictly increase until the final 0
+//! - Every key falls in exactly one KEYS chunk
 
+use super::keyspace_stats::KeyFootprint;
 use super::CommandExecutor;
 use crate::redis::data::Value;
 use crate::redis::resp::RespValue;
 
+/// Most chunks one chunked KEYS splits a shard into. Every chunk hashes the
+/// whole keyspace, so past this the repeated hashing outweighs the work
+/// each chunk saves.
+const MAX_KEYS_CHUNKS: u64 = 1024;
+
+/// Fixed seeds, so a key's chunk and SCAN position are the same on every call
+const KEY_HASH_SEEDS: [u64; 4] = [
+    0x243f_6a88_85a3_08d3,
+    0x1319_8a2e_0370_7344,
+    0xa409_3822_299f_31d0,
+    0x082e_fa98_ec4e_6c89,
+];
+
+fn key_hasher() -> ahash::RandomState {
+    ahash::RandomState::with_seeds(
+        KEY_HASH_SEEDS[0],
+        KEY_HASH_SEEDS[1],
+        KEY_HASH_SEEDS[2],
+        KEY_HASH_SEEDS[3],
+    )
+}
+
 impl CommandExecutor {
+    /// Where SCAN visits `key`. Never 0, so cursor 0 only ever starts a scan.
+    pub fn scan_position(key: &[u8]) -> u64 {
+        key_hasher().hash_one(key).max(1)
+    }
+
+    /// Cut one SCAN page from `entries`, sorted by position: the first
+    /// `count`, plus any more at the last one's position so the next
+    /// cursor moves past it. Returns the page length and the cursor that
+    /// resumes after it, 0 when nothing is left.
+    pub fn scan_page<T>(entries: &[(u64, T)], count: usize) -> (usize, u64) {
+        debug_assert!(count > 0, "Precondition: SCAN count must be positive");
+        debug_assert!(
+            entries.windows(2).all(|w| w[0].0 <= w[1].0),
+            "Precondition: SCAN entries must be sorted by position"
+        );
+
+        if entries.len() <= count {
+            return (entries.len(), 0);
+        }
+        let last = entries[count - 1].0;
+        let len = count + entries[count..].iter().take_while(|e| e.0 == last).count();
+        let next_cursor = entries.get(len).map_or(0, |e| e.0);
+
+        // TigerStyle: Postcondition - the next page starts past this one
+        debug_assert!(
+            next_cursor == 0 || next_cursor > last,
+            "Postcondition violated: SCAN cursor must move past the page"
+        );
+        (len, next_cursor)
+    }
+
+    /// One chunk of a chunked KEYS. Cursor 0 starts a scan, splitting the
+    /// keyspace into `len / chunk_size` chunks (one when `chunk_size` is 0);
+    /// the returned cursor continues it and is 0 once every chunk is done.
+    /// The cursor carries the chunk count, so chunks agree even if the
+    /// keyspace grows or shrinks between calls.
+    pub fn keys_chunk(
+        &self,
+        pattern: &str,
+        cursor: u64,
+        chunk_size: usize,
+    ) -> (u64, Vec<RespValue>) {
+        let (chunks, chunk) = if cursor == 0 {
+            let chunks = match chunk_size {
+                0 => 1,
+                size => (self.data.len() as u64)
+                    .div_ceil(size as u64)
+                    .clamp(1, MAX_KEYS_CHUNKS),
+            };
+            (chunks, 0)
+        } else {
+            (cursor >> 32, cursor & 0xFFFF_FFFF)
+        };
+        debug_assert!(
+            (1..=MAX_KEYS_CHUNKS).contains(&chunks) && chunk < chunks,
+            "Precondition: KEYS cursor {} is not one this executor issued",
+            cursor
+        );
+
+        let glob = self.glob_pattern(pattern);
+        let hasher = key_hasher();
+        let keys: Vec<RespValue> = self
+            .data
+            .keys()
+            .filter(|k| chunks == 1 || hasher.hash_one(k.as_bytes()) % chunks == chunk)
+            .filter(|k| !self.is_expired(k) && glob.matches(k.as_bytes()))
+            .map(|k| RespValue::BulkString(Some(k.as_bytes().to_vec())))
+            .collect();
+
+        let next = chunk + 1;
+        let next_cursor = if next == chunks {
+            0
+        } else {
+            (chunks << 32) | next
+        };
+        (next_cursor, keys)
+    }
+
     pub(super) fn execute_scan(
         &mut self,
         cursor: u64,
         pattern: Option<&str>,
         count: Option<usize>,
+        type_filter: Option<&str>,
     ) -> RespValue {
         let count = count.unwrap_or(10);
 
         // TigerStyle: Precondition - count must be reasonable
         debug_assert!(count > 0, "Precondition: SCAN count must be positive");
 
-        // Collect all non-expired keys
-        let mut keys: Vec<String> = self
+        // Live matching keys at or past the cursor, in position order
+        let glob = pattern.map(|p| self.glob_pattern(p));
+        let mut keys: Vec<(u64, &String)> = self
             .data
-            .keys()
-            .filter(|k| !self.is_expired(k))
-            .filter(|k| pattern.map_or(true, |p| self.matches_glob_pattern(k, p)))
-            .cloned()
-            .collect();
-        // Sort for deterministic iteration
-        keys.sort();
-
-        // Skip to cursor position and take count+1 to know if there's more
-        let results: Vec<String> = keys
-            .into_iter()
-            .skip(cursor as usize)
-            .take(count + 1)
+            .iter()
+            .filter(|(_, value)| {
+                type_filter.is_none_or(|t| {
+                    KeyFootprint::of(value).is_some_and(|f| f.kind().name().eq_ignore_ascii_case(t))
+                })
+            })
+            .map(|(k, _)| (Self::scan_position(k.as_bytes()), k))
+            .filter(|&(position, k)| position >= cursor && !self.is_expired(k))
+            .filter(|(_, k)| glob.as_ref().is_none_or(|g| g.matches(k.as_bytes())))
             .collect();
+        keys.sort_unstable();
+        let (len, next_cursor) = Self::scan_page(&keys, count);
 
-        let (next_cursor, result_keys) = if results.len() > count {
-            (cursor + count as u64, &results[..count])
-        } else {
-            (0u64, &results[..])
-        };
-
-        // TigerStyle: Postconditions
-        debug_assert!(
-            result_keys.len() <= count,
-            "Postcondition violated: SCAN result count must not exceed requested count"
-        );
+        // TigerStyle: Postcondition - a later page never revisits this one
         debug_assert!(
-            next_cursor == 0 || !result_keys.is_empty(),
-            "Postcondition violated: non-zero cursor implies non-empty results"
+            next_cursor == 0 || next_cursor > cursor,
+            "Postcondition violated: SCAN cursor must increase"
         );
 
         RespValue::Array(Some(vec![
             RespValue::BulkString(Some(next_cursor.to_string().into_bytes())),
             RespValue::Array(Some(
-                result_keys
+                keys[..len]
                     .iter()
-                    .map(|k| RespValue::BulkString(Some(k.as_bytes().to_vec())))
+                    .map(|(_, k)| RespValue::BulkString(Some(k.as_bytes().to_vec())))
                     .collect(),
             )),
         ]))
@@ -103,9 +214,10 @@ impl CommandExecutor {
                 debug_assert!(count > 0, "Precondition: HSCAN count must be positive");
 
                 // Filter by pattern
+                let glob = pattern.map(|p| self.glob_pattern(p));
                 let mut fields: Vec<(String, String)> = all_fields
                     .into_iter()
-                    .filter(|(f, _)| pattern.map_or(true, |p| self.matches_glob_pattern(f, p)))
+                    .filter(|(f, _)| glob.as_ref().is_none_or(|g| g.matches(f.as_bytes())))
                     .collect();
                 // Sort for deterministic iteration
                 fields.sort_by(|a, b| a.0.cmp(&b.0));
@@ -196,9 +308,10 @@ impl CommandExecutor {
                 debug_assert!(count > 0, "Precondition: ZSCAN count must be positive");
 
                 // Filter by pattern
+                let glob = pattern.map(|p| self.glob_pattern(p));
                 let mut members: Vec<(String, f64)> = all_members
                     .into_iter()
-                    .filter(|(m, _)| pattern.map_or(true, |p| self.matches_glob_pattern(m, p)))
+                    .filter(|(m, _)| glob.as_ref().is_none_or(|g| g.matches(m.as_bytes())))
                     .collect();
                 // Sort by member for deterministic iteration
                 members.sort_by(|a, b| a.0.cmp(&b.0));
@@ -1,11 +1,114 @@
 //! Script command implementations for CommandExecutor.
 //!
-//! Handles: EVAL, EVALSHA, SCRIPT LOAD, SCRIPT EXISTS, SCRIPT FLUSH
+//! Handles: EVAL, EVALSHA, EVAL_RO, EVALSHA_RO, SCRIPT LOAD, SCRIPT EXISTS,
+//! SCRIPT FLUSH, SCRIPT KILL
+//!
+//! Scripts are replicated verbatim by default, so a script must do the same
+//! thing on every replica. As in Redis, a write after a nondeterministic
+//! command (RANDOMKEY, SPOP, TIME) is refused unless the script first calls
+//! `redis.replicate_commands()` to switch to effect replication.
+//!
+//! EVAL_RO and EVALSHA_RO run a script that may only read, so replicas and
+//! snapshot reads can serve them. The bridge behind `redis.call` and
+//! `redis.pcall` refuses the script's write commands; the script itself is
+//! not inspected.
+//!
+//! The `redis` table also carries `error_reply`, `status_reply`, `sha1hex`,
+//! `log` (to the server's tracing output), `setresp`, a no-op `breakpoint`
+//! and the `LOG_*` and `REPL_*` constants. After `redis.setresp(3)`,
+//! `redis.call` replies carry their RESP3 types (see `reply_shape`) and
+//! convert to Lua as Redis does: `{map=...}`, `{set=...}`, `{double=...}`,
+//! `{big_number=...}`, `{verbatim_string=...}` and booleans. A script may
+//! return those tables under either protocol; map and set entries are
+//! sorted, since Lua iterates tables in hash order.
+//!
+//! Every script registers with the executor's `ScriptMonitor` while it runs,
+//! and an instruction hook looks every `SCRIPT_HOOK_INSTRUCTIONS` for a
+//! SCRIPT KILL from another connection (see `script_monitor`).
+//!
+//! FCALL runs library functions through the same path (see `function_ops`),
+//! so everything above applies to functions too.
 
 use super::CommandExecutor;
-use crate::redis::command::Command;
+use crate::redis::command::{Command, ZRangeSpec};
+#[cfg(feature = "lua")]
+use crate::redis::command_table;
+#[cfg(feature = "lua")]
+use crate::redis::declared_commands::DeclaredCommand;
 use crate::redis::data::SDS;
 use crate::redis::resp::RespValue;
+#[cfg(feature = "lua")]
+use crate::redis::resp::RespParser;
+#[cfg(feature = "lua")]
+use crate::redis::script_monitor::{SCRIPT_HOOK_INSTRUCTIONS, SCRIPT_KILLED_ERROR};
+
+/// `redis.LOG_*` levels and `redis.REPL_*` flags, with Redis's values
+#[cfg(feature = "lua")]
+pub(super) const SCRIPT_CONSTANTS: [(&str, i64); 9] = [
+    ("LOG_DEBUG", 0),
+    ("LOG_VERBOSE", 1),
+    ("LOG_NOTICE", 2),
+    ("LOG_WARNING", 3),
+    ("REPL_NONE", 0),
+    ("REPL_AOF", 1),
+    ("REPL_SLAVE", 2),
+    ("REPL_REPLICA", 2),
+    ("REPL_ALL", 3),
+];
+
+#[cfg(feature = "lua")]
+const READ_ONLY_SCRIPT_WRITE: &str = "Write commands are not allowed from read-only scripts.";
+
+#[cfg(feature = "lua")]
+const NONDETERMINISTIC_WRITE: &str = "Write commands not allowed after non deterministic commands. Call redis.replicate_commands() at the start of your script in order to switch to single commands replication mode.";
+
+/// What a Lua run evaluates
+#[cfg(feature = "lua")]
+#[derive(Debug, Clone, Copy)]
+pub(super) enum LuaEntry<'a> {
+    /// An EVAL script, with KEYS and ARGV as globals
+    Script(&'a str),
+    /// A library's `function`, called with the keys and arguments as its
+    /// two parameters; `code` is the library without its metadata line
+    Function { code: &'a str, function: &'a str },
+}
+
+/// Replication state of the running script
+#[cfg(feature = "lua")]
+#[derive(Debug, Default)]
+struct ScriptReplication {
+    /// `redis.replicate_commands()` succeeded: effects are replicated, not the script
+    effects: bool,
+    /// A nondeterministic command has run
+    random_dirty: bool,
+    /// A write command has run
+    write_dirty: bool,
+}
+
+#[cfg(feature = "lua")]
+impl ScriptReplication {
+    /// Refuse writes that would make verbatim replication diverge
+    fn check(&self, cmd: &Command) -> Result<(), String> {
+        if self.random_dirty && !self.effects && !cmd.is_read_only() {
+            return Err(NONDETERMINISTIC_WRITE.to_string());
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, cmd: &Command) {
+        self.random_dirty |= cmd.is_nondeterministic();
+        self.write_dirty |= !cmd.is_read_only();
+    }
+
+    /// Switching is only possible before the first write
+    fn replicate_commands(&mut self) -> bool {
+        if self.write_dirty {
+            return false;
+        }
+        self.effects = true;
+        true
+    }
+}
 
 impl CommandExecutor {
     // Script cache helper methods
@@ -51,14 +154,15 @@ impl CommandExecutor {
         script: &str,
         keys: &[String],
         args: &[SDS],
+        read_only: bool,
     ) -> RespValue {
         #[cfg(feature = "lua")]
         {
-            self.execute_lua_script(script, keys, args)
+            self.execute_lua_script(script, keys, args, read_only)
         }
         #[cfg(not(feature = "lua"))]
         {
-            let _ = (script, keys, args);
+            let _ = (script, keys, args, read_only);
             RespValue::err("ERR Lua scripting not compiled in")
         }
     }
@@ -68,6 +172,7 @@ impl CommandExecutor {
         sha1: &str,
         keys: &[String],
         args: &[SDS],
+        read_only: bool,
     ) -> RespValue {
         #[cfg(feature = "lua")]
         {
@@ -75,14 +180,14 @@ impl CommandExecutor {
             match self.get_script_internal(sha1) {
                 Some(script) => {
                     let script = script.clone();
-                    self.execute_lua_script(&script, keys, args)
+                    self.execute_lua_script(&script, keys, args, read_only)
                 }
                 None => RespValue::err("NOSCRIPT No matching script. Please use EVAL."),
             }
         }
         #[cfg(not(feature = "lua"))]
         {
-            let _ = (sha1, keys, args);
+            let _ = (sha1, keys, args, read_only);
             RespValue::err("ERR Lua scripting not compiled in")
         }
     }
@@ -142,17 +247,21 @@ impl CommandExecutor {
         }
     }
 
-    /// Execute a Lua script with KEYS and ARGV
+    /// SCRIPT KILL: stop the busy scripts of any shard
+    pub(super) fn execute_script_kill(&self) -> RespValue {
+        self.script_monitor.kill()
+    }
+
+    /// Execute a Lua script with KEYS and ARGV; a `read_only` script may not
+    /// write
     #[cfg(feature = "lua")]
     pub(crate) fn execute_lua_script(
         &mut self,
         script: &str,
         keys: &[String],
         args: &[SDS],
+        read_only: bool,
     ) -> RespValue {
-        use mlua::{Lua, MultiValue, Result as LuaResult, Value as LuaValue};
-        use std::cell::RefCell;
-
         // TigerStyle: Preconditions
         debug_assert!(!script.is_empty(), "Precondition: script must not be empty");
 
@@ -160,13 +269,21 @@ impl CommandExecutor {
         #[allow(unused_variables)]
         let script_sha = self.cache_script_internal(script);
 
+        self.run_lua(LuaEntry::Script(script), keys, args, read_only)
+    }
+
+    /// A fresh Lua state, seeded from the executor clock and sandboxed
+    #[cfg(feature = "lua")]
+    pub(super) fn sandboxed_lua(&self) -> Result<mlua::Lua, String> {
+        use mlua::{Lua, Result as LuaResult, Value as LuaValue};
+
         // Create a new Lua instance for this execution
         let lua = Lua::new();
 
         // DST: Seed math.random deterministically using current_time
         let seed = self.current_time.as_millis();
         if let Err(e) = lua.load(format!("math.randomseed({})", seed)).exec() {
-            return RespValue::err(format!("ERR Failed to seed RNG: {}", e));
+            return Err(format!("ERR Failed to seed RNG: {}", e));
         }
 
         // Sandbox: Remove dangerous/non-deterministic functions
@@ -178,41 +295,81 @@ impl CommandExecutor {
             lua.globals().set("debug", LuaValue::Nil)?;
             Ok(())
         })() {
-            return RespValue::err(format!("ERR Lua sandbox error: {}", e));
+            return Err(format!("ERR Lua sandbox error: {}", e));
         }
+        Ok(lua)
+    }
 
-        // Create KEYS table
-        if let Err(e) = (|| -> LuaResult<()> {
+    /// Run a script or a library function with the full `redis` table; a
+    /// `read_only` run may not write
+    #[cfg(feature = "lua")]
+    pub(super) fn run_lua(
+        &mut self,
+        entry: LuaEntry<'_>,
+        keys: &[String],
+        args: &[SDS],
+        read_only: bool,
+    ) -> RespValue {
+        use mlua::{MultiValue, Result as LuaResult, Value as LuaValue};
+        use std::cell::{Cell, RefCell};
+
+        let lua = match self.sandboxed_lua() {
+            Ok(lua) => lua,
+            Err(e) => return RespValue::err(e),
+        };
+
+        // KEYS and ARGV (binary-safe Lua strings): globals of a script, the
+        // parameters of a function
+        let tables = (|| -> LuaResult<(mlua::Table, mlua::Table)> {
             let keys_table = lua.create_table()?;
             for (i, key) in keys.iter().enumerate() {
                 keys_table.set(i + 1, key.as_str())?;
             }
-            lua.globals().set("KEYS", keys_table)?;
-            Ok(())
-        })() {
-            return RespValue::err(format!("ERR Failed to set KEYS: {}", e));
-        }
-
-        // Create ARGV table - use binary-safe Lua strings
-        if let Err(e) = (|| -> LuaResult<()> {
             let argv_table = lua.create_table()?;
             for (i, arg) in args.iter().enumerate() {
                 let lua_str = lua.create_string(arg.as_bytes())?;
                 argv_table.set(i + 1, lua_str)?;
             }
-            lua.globals().set("ARGV", argv_table)?;
-            Ok(())
-        })() {
-            return RespValue::err(format!("ERR Failed to set ARGV: {}", e));
-        }
+            if let LuaEntry::Script(_) = entry {
+                lua.globals().set("KEYS", keys_table.clone())?;
+                lua.globals().set("ARGV", argv_table.clone())?;
+            }
+            Ok((keys_table, argv_table))
+        })();
+        let (keys_table, argv_table) = match tables {
+            Ok(tables) => tables,
+            Err(e) => return RespValue::err(format!("ERR Failed to set KEYS and ARGV: {}", e)),
+        };
+
+        // Registered until the script ends; SCRIPT KILL from another
+        // connection stops it at the next hook
+        let run = self.script_monitor.start();
+        let killed = run.kill_check();
+        lua.set_hook(
+            mlua::HookTriggers::new().every_nth_instruction(SCRIPT_HOOK_INSTRUCTIONS),
+            move |_, _| {
+                if killed() {
+                    return Err(mlua::Error::RuntimeError(SCRIPT_KILLED_ERROR.to_string()));
+                }
+                Ok(mlua::VmState::Continue)
+            },
+        );
 
         // Use RefCell to allow mutable borrow from within Lua callbacks
         let executor = RefCell::new(&mut *self);
+        let replication = RefCell::new(ScriptReplication::default());
+        // Protocol of the replies redis.call/pcall hand the script
+        let resp_version = Cell::new(2);
+        // The callback a library registers for the function being called
+        let callback = RefCell::new(None);
 
         // Execute script within a scope that allows borrowing executor
         let result = lua.scope(|scope| {
             // Create redis.call - executes command immediately, propagates errors
             let executor_call = &executor;
+            let replication_call = &replication;
+            let resp_call = &resp_version;
+            let run_call = &run;
             let call_fn = scope.create_function_mut(|lua, args: MultiValue| {
                 let cmd_parts = Self::parse_multivalue_to_bytes(args)?;
                 if cmd_parts.is_empty() {
@@ -222,21 +379,29 @@ impl CommandExecutor {
                 }
 
                 let mut exec = executor_call.borrow_mut();
-                match exec.parse_lua_command_bytes(&cmd_parts) {
-                    Ok(cmd) => {
-                        let resp = exec.execute(&cmd);
-                        // redis.call propagates errors
-                        if let RespValue::Error(e) = &resp {
-                            return Err(mlua::Error::RuntimeError(e.to_string()));
-                        }
-                        Self::resp_to_lua_value(lua, resp)
-                    }
-                    Err(e) => Err(mlua::Error::RuntimeError(e)),
+                let cmd = exec
+                    .parse_lua_command_bytes(&cmd_parts)
+                    .map_err(mlua::Error::RuntimeError)?;
+                Self::check_script_write(read_only, &cmd).map_err(mlua::Error::RuntimeError)?;
+                let mut replication = replication_call.borrow_mut();
+                replication.check(&cmd).map_err(mlua::Error::RuntimeError)?;
+                let resp = exec.execute(&cmd);
+                replication.record(&cmd);
+                if !cmd.is_read_only() {
+                    run_call.record_write();
                 }
+                // redis.call propagates errors
+                if let RespValue::Error(e) = &resp {
+                    return Err(mlua::Error::RuntimeError(e.to_string()));
+                }
+                Self::resp_to_lua_value(lua, Self::script_reply(&cmd, resp, resp_call.get()))
             })?;
 
             // Create redis.pcall - executes command immediately, returns errors as tables
             let executor_pcall = &executor;
+            let replication_pcall = &replication;
+            let resp_pcall = &resp_version;
+            let run_pcall = &run;
             let pcall_fn = scope.create_function_mut(|lua, args: MultiValue| {
                 let cmd_parts = Self::parse_multivalue_to_bytes(args)?;
                 if cmd_parts.is_empty() {
@@ -246,15 +411,25 @@ impl CommandExecutor {
                 }
 
                 let mut exec = executor_pcall.borrow_mut();
-                match exec.parse_lua_command_bytes(&cmd_parts) {
+                let mut replication = replication_pcall.borrow_mut();
+                match exec
+                    .parse_lua_command_bytes(&cmd_parts)
+                    .and_then(|cmd| Self::check_script_write(read_only, &cmd).map(|()| cmd))
+                    .and_then(|cmd| replication.check(&cmd).map(|()| cmd))
+                {
                     Ok(cmd) => {
                         let resp = exec.execute(&cmd);
+                        replication.record(&cmd);
+                        if !cmd.is_read_only() {
+                            run_pcall.record_write();
+                        }
                         // redis.pcall returns errors as {err = "message"} tables
                         if let RespValue::Error(e) = &resp {
                             let err_table = lua.create_table()?;
                             err_table.set("err", e.as_ref())?;
                             return Ok(LuaValue::Table(err_table));
                         }
+                        let resp = Self::script_reply(&cmd, resp, resp_pcall.get());
                         Self::resp_to_lua_value(lua, resp)
                     }
                     Err(e) => {
@@ -265,23 +440,115 @@ impl CommandExecutor {
                 }
             })?;
 
-            // Set up redis table with call/pcall
+            // redis.replicate_commands - switch to effect replication before the first write
+            let replication_switch = &replication;
+            let replicate_commands_fn = scope.create_function_mut(|_, ()| {
+                Ok(replication_switch.borrow_mut().replicate_commands())
+            })?;
+
+            // redis.setresp - protocol of the replies redis.call/pcall return
+            let resp_switch = &resp_version;
+            let setresp_fn = scope.create_function(|_, args: MultiValue| {
+                let args: Vec<LuaValue> = args.into_iter().collect();
+                let version = match args.as_slice() {
+                    [LuaValue::Integer(version @ (2 | 3))] => *version,
+                    [_] => {
+                        return Err(mlua::Error::RuntimeError(
+                            "RESP version must be 2 or 3.".to_string(),
+                        ))
+                    }
+                    _ => {
+                        return Err(mlua::Error::RuntimeError(
+                            "redis.setresp() requires one argument.".to_string(),
+                        ))
+                    }
+                };
+                resp_switch.set(version);
+                Ok(())
+            })?;
+
+            // Set up the redis table
             let redis_table = lua.create_table()?;
             redis_table.set("call", call_fn)?;
             redis_table.set("pcall", pcall_fn)?;
+            redis_table.set("replicate_commands", replicate_commands_fn)?;
+            redis_table.set("setresp", setresp_fn)?;
+            redis_table.set(
+                "error_reply",
+                lua.create_function(|lua, message: String| {
+                    let reply = lua.create_table()?;
+                    reply.set("err", message.strip_prefix('-').unwrap_or(&message))?;
+                    Ok(reply)
+                })?,
+            )?;
+            redis_table.set(
+                "status_reply",
+                lua.create_function(|lua, message: String| {
+                    let reply = lua.create_table()?;
+                    reply.set("ok", message)?;
+                    Ok(reply)
+                })?,
+            )?;
+            redis_table.set(
+                "sha1hex",
+                lua.create_function(|_, data: mlua::String| {
+                    Ok(crate::redis::lua::sha1_hex(&data.as_bytes()))
+                })?,
+            )?;
+            redis_table.set(
+                "log",
+                lua.create_function(|_, args: MultiValue| Self::script_log(args))?,
+            )?;
+            // Nothing debugs scripts here, so there is never a breakpoint to stop at
+            redis_table.set("breakpoint", lua.create_function(|_, ()| Ok(false))?)?;
+            for (name, value) in SCRIPT_CONSTANTS {
+                redis_table.set(name, value)?;
+            }
+            if let LuaEntry::Function { function, .. } = entry {
+                let callback_register = &callback;
+                let register_fn = scope.create_function(move |_, args: MultiValue| {
+                    let (info, registered) = Self::function_registration(args)?;
+                    if info.name == function {
+                        *callback_register.borrow_mut() = Some(registered);
+                    }
+                    Ok(())
+                })?;
+                redis_table.set("register_function", register_fn)?;
+            }
             lua.globals().set("redis", redis_table)?;
 
-            // Execute the script
-            lua.load(script).eval::<LuaValue>()
+            match entry {
+                // Execute the script
+                LuaEntry::Script(script) => lua.load(script).eval::<LuaValue>(),
+                // Run the library, then call the function it registered
+                LuaEntry::Function { code, function } => {
+                    lua.load(code).exec()?;
+                    let registered: Option<mlua::Function> = callback.borrow_mut().take();
+                    let registered = registered.ok_or_else(|| {
+                        mlua::Error::RuntimeError(format!("Function {} not registered", function))
+                    })?;
+                    registered.call::<LuaValue>((keys_table, argv_table))
+                }
+            }
         });
 
         // Convert result
         match result {
-            Ok(lua_value) => self.lua_to_resp(&lua, lua_value),
+            Ok(lua_value) => self.lua_to_resp(&lua, lua_value, resp_version.get() == 3),
+            Err(_) if run.is_killed() => RespValue::err(SCRIPT_KILLED_ERROR),
             Err(e) => RespValue::err(format!("ERR {}", e)),
         }
     }
 
+    /// A read-only script (EVAL_RO, EVALSHA_RO) may not run `cmd` if it writes
+    #[cfg(feature = "lua")]
+    fn check_script_write(read_only: bool, cmd: &Command) -> Result<(), String> {
+        if read_only && !cmd.is_read_only() {
+            return Err(READ_ONLY_SCRIPT_WRITE.to_string());
+        }
+        Ok(())
+    }
+
     /// Parse MultiValue arguments to bytes for redis.call/pcall
     #[cfg(feature = "lua")]
     fn parse_multivalue_to_bytes(args: mlua::MultiValue) -> mlua::Result<Vec<Vec<u8>>> {
@@ -303,6 +570,58 @@ impl CommandExecutor {
         Ok(cmd_parts)
     }
 
+    /// A command's reply as a script sees it: with its RESP3 types after
+    /// `redis.setresp(3)`
+    #[cfg(feature = "lua")]
+    fn script_reply(cmd: &Command, resp: RespValue, resp_version: i64) -> RespValue {
+        if resp_version == 3 {
+            crate::production::ReplyShape::of(cmd).retype(resp)
+        } else {
+            resp
+        }
+    }
+
+    /// redis.log(level, message, ...): the strings and numbers after the
+    /// level, joined by spaces, at the matching tracing level
+    #[cfg(feature = "lua")]
+    pub(super) fn script_log(args: mlua::MultiValue) -> mlua::Result<()> {
+        use mlua::Value as LuaValue;
+
+        let args: Vec<LuaValue> = args.into_iter().collect();
+        if args.len() < 2 {
+            return Err(mlua::Error::RuntimeError(
+                "redis.log() requires two arguments or more.".to_string(),
+            ));
+        }
+        let level = match &args[0] {
+            LuaValue::Integer(level @ 0..=3) => *level,
+            LuaValue::Integer(_) => {
+                return Err(mlua::Error::RuntimeError("Invalid debug level.".to_string()))
+            }
+            _ => {
+                return Err(mlua::Error::RuntimeError(
+                    "First argument must be a number (log level).".to_string(),
+                ))
+            }
+        };
+        let message = args[1..]
+            .iter()
+            .filter_map(|arg| match arg {
+                LuaValue::String(s) => Some(String::from_utf8_lossy(&s.as_bytes()).into_owned()),
+                LuaValue::Integer(i) => Some(i.to_string()),
+                LuaValue::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        match level {
+            0 | 1 => tracing::debug!("script: {}", message),
+            2 => tracing::info!("script: {}", message),
+            _ => tracing::warn!("script: {}", message),
+        }
+        Ok(())
+    }
+
     /// Convert RespValue to Lua Value
     #[cfg(feature = "lua")]
     fn resp_to_lua_value(lua: &mlua::Lua, resp: RespValue) -> mlua::Result<mlua::Value> {
@@ -330,10 +649,50 @@ impl CommandExecutor {
                 }
                 LuaValue::Table(t)
             }
-            RespValue::Array(None) => LuaValue::Nil,
+            RespValue::Array(None) | RespValue::Null => LuaValue::Nil,
+            // RESP3 types, only seen after redis.setresp(3)
+            RespValue::Map(pairs) => {
+                let map = lua.create_table()?;
+                for (key, value) in pairs {
+                    map.set(
+                        Self::resp_to_lua_value(lua, key)?,
+                        Self::resp_to_lua_value(lua, value)?,
+                    )?;
+                }
+                Self::single_field_table(lua, "map", map)?
+            }
+            RespValue::Set(members) => {
+                let set = lua.create_table()?;
+                for member in members {
+                    set.set(Self::resp_to_lua_value(lua, member)?, true)?;
+                }
+                Self::single_field_table(lua, "set", set)?
+            }
+            RespValue::Double(d) => Self::single_field_table(lua, "double", d)?,
+            RespValue::Boolean(b) => LuaValue::Boolean(b),
+            RespValue::BigNumber(digits) => Self::single_field_table(lua, "big_number", digits)?,
+            RespValue::Verbatim { format, text } => {
+                let verbatim = lua.create_table()?;
+                verbatim.set("format", lua.create_string(format)?)?;
+                verbatim.set("string", lua.create_string(&text)?)?;
+                Self::single_field_table(lua, "verbatim_string", verbatim)?
+            }
+            resp3 => Self::resp_to_lua_value(lua, resp3.to_resp2())?,
         })
     }
 
+    /// `{name = value}`, the shape RESP3 replies take in Lua
+    #[cfg(feature = "lua")]
+    fn single_field_table(
+        lua: &mlua::Lua,
+        name: &str,
+        value: impl mlua::IntoLua,
+    ) -> mlua::Result<mlua::Value> {
+        let t = lua.create_table()?;
+        t.set(name, value)?;
+        Ok(mlua::Value::Table(t))
+    }
+
     /// Parse a command from Lua script arguments (bytes version)
     #[cfg(feature = "lua")]
     fn parse_lua_command_bytes(&self, parts: &[Vec<u8>]) -> Result<Command, String> {
@@ -342,21 +701,16 @@ impl CommandExecutor {
         }
 
         let cmd_name = String::from_utf8_lossy(&parts[0]).to_uppercase();
+        command_table::validate(&cmd_name, parts.len(), |i| Some(parts[i].as_slice()))?;
         let args = &parts[1..];
         let to_string = |b: &[u8]| String::from_utf8_lossy(b).to_string();
         let to_sds = |b: &[u8]| SDS::new(b.to_vec());
 
         match cmd_name.as_str() {
             "GET" => {
-                if args.len() != 1 {
-                    return Err("GET requires 1 argument".to_string());
-                }
                 Ok(Command::Get(to_string(&args[0])))
             }
             "SET" => {
-                if args.len() < 2 {
-                    return Err("SET requires at least 2 arguments".to_string());
-                }
                 let key = to_string(&args[0]);
                 let value = to_sds(&args[1]);
                 let mut ex = None;
@@ -412,36 +766,24 @@ impl CommandExecutor {
                 })
             }
             "DEL" => {
-                if args.is_empty() {
-                    return Err("DEL requires at least 1 argument".to_string());
-                }
                 Ok(Command::Del(args.iter().map(|a| to_string(a)).collect()))
             }
+            "UNLINK" => {
+                Ok(Command::Unlink(args.iter().map(|a| to_string(a)).collect()))
+            }
             "INCR" => {
-                if args.len() != 1 {
-                    return Err("INCR requires 1 argument".to_string());
-                }
                 Ok(Command::Incr(to_string(&args[0])))
             }
             "DECR" => {
-                if args.len() != 1 {
-                    return Err("DECR requires 1 argument".to_string());
-                }
                 Ok(Command::Decr(to_string(&args[0])))
             }
             "INCRBY" => {
-                if args.len() != 2 {
-                    return Err("INCRBY requires 2 arguments".to_string());
-                }
                 let incr: i64 = to_string(&args[1])
                     .parse()
                     .map_err(|_| "INCRBY increment must be integer")?;
                 Ok(Command::IncrBy(to_string(&args[0]), incr))
             }
             "HGET" => {
-                if args.len() != 2 {
-                    return Err("HGET requires 2 arguments".to_string());
-                }
                 Ok(Command::HGet(to_string(&args[0]), to_sds(&args[1])))
             }
             "HSET" => {
@@ -456,78 +798,54 @@ impl CommandExecutor {
                 Ok(Command::HSet(key, pairs))
             }
             "HDEL" => {
-                if args.len() < 2 {
-                    return Err("HDEL requires key and at least 1 field".to_string());
-                }
                 let key = to_string(&args[0]);
                 let fields: Vec<SDS> = args[1..].iter().map(|a| to_sds(a)).collect();
                 Ok(Command::HDel(key, fields))
             }
             "LPUSH" => {
-                if args.len() < 2 {
-                    return Err("LPUSH requires key and at least 1 value".to_string());
-                }
                 let key = to_string(&args[0]);
                 let values: Vec<SDS> = args[1..].iter().map(|a| to_sds(a)).collect();
                 Ok(Command::LPush(key, values))
             }
             "RPUSH" => {
-                if args.len() < 2 {
-                    return Err("RPUSH requires key and at least 1 value".to_string());
-                }
                 let key = to_string(&args[0]);
                 let values: Vec<SDS> = args[1..].iter().map(|a| to_sds(a)).collect();
                 Ok(Command::RPush(key, values))
             }
             "LPOP" => {
                 if args.len() != 1 {
-                    return Err("LPOP requires 1 argument".to_string());
+                    return Err("ERR wrong number of arguments for 'lpop' command".to_string());
                 }
                 Ok(Command::LPop(to_string(&args[0])))
             }
             "RPOP" => {
                 if args.len() != 1 {
-                    return Err("RPOP requires 1 argument".to_string());
+                    return Err("ERR wrong number of arguments for 'rpop' command".to_string());
                 }
                 Ok(Command::RPop(to_string(&args[0])))
             }
             "LLEN" => {
-                if args.len() != 1 {
-                    return Err("LLEN requires 1 argument".to_string());
-                }
                 Ok(Command::LLen(to_string(&args[0])))
             }
             "SADD" => {
-                if args.len() < 2 {
-                    return Err("SADD requires key and at least 1 member".to_string());
-                }
                 let key = to_string(&args[0]);
                 let members: Vec<SDS> = args[1..].iter().map(|a| to_sds(a)).collect();
                 Ok(Command::SAdd(key, members))
             }
             "SREM" => {
-                if args.len() < 2 {
-                    return Err("SREM requires key and at least 1 member".to_string());
-                }
                 let key = to_string(&args[0]);
                 let members: Vec<SDS> = args[1..].iter().map(|a| to_sds(a)).collect();
                 Ok(Command::SRem(key, members))
             }
             "SMEMBERS" => {
-                if args.len() != 1 {
-                    return Err("SMEMBERS requires 1 argument".to_string());
-                }
                 Ok(Command::SMembers(to_string(&args[0])))
             }
             "EXISTS" => {
-                if args.is_empty() {
-                    return Err("EXISTS requires at least 1 argument".to_string());
-                }
                 Ok(Command::Exists(args.iter().map(|a| to_string(a)).collect()))
             }
             "EXPIRE" => {
                 if args.len() != 2 {
-                    return Err("EXPIRE requires 2 arguments".to_string());
+                    return Err("ERR wrong number of arguments for 'expire' command".to_string());
                 }
                 let seconds: i64 = to_string(&args[1])
                     .parse()
@@ -535,21 +853,12 @@ impl CommandExecutor {
                 Ok(Command::expire(to_string(&args[0]), seconds))
             }
             "TTL" => {
-                if args.len() != 1 {
-                    return Err("TTL requires 1 argument".to_string());
-                }
                 Ok(Command::Ttl(to_string(&args[0])))
             }
             "TYPE" => {
-                if args.len() != 1 {
-                    return Err("TYPE requires 1 argument".to_string());
-                }
                 Ok(Command::TypeOf(to_string(&args[0])))
             }
             "HINCRBY" => {
-                if args.len() != 3 {
-                    return Err("HINCRBY requires 3 arguments".to_string());
-                }
                 let incr: i64 = to_string(&args[2])
                     .parse()
                     .map_err(|_| "HINCRBY increment must be integer")?;
@@ -560,9 +869,6 @@ impl CommandExecutor {
                 ))
             }
             "LRANGE" => {
-                if args.len() != 3 {
-                    return Err("LRANGE requires 3 arguments".to_string());
-                }
                 let start: isize = to_string(&args[1])
                     .parse()
                     .map_err(|_| "LRANGE start must be integer")?;
@@ -572,15 +878,9 @@ impl CommandExecutor {
                 Ok(Command::LRange(to_string(&args[0]), start, stop))
             }
             "RPOPLPUSH" => {
-                if args.len() != 2 {
-                    return Err("RPOPLPUSH requires 2 arguments".to_string());
-                }
                 Ok(Command::RPopLPush(to_string(&args[0]), to_string(&args[1])))
             }
             "LMOVE" => {
-                if args.len() != 4 {
-                    return Err("LMOVE requires 4 arguments".to_string());
-                }
                 let wherefrom = to_string(&args[2]).to_uppercase();
                 let whereto = to_string(&args[3]).to_uppercase();
                 if wherefrom != "LEFT" && wherefrom != "RIGHT" {
@@ -596,22 +896,36 @@ impl CommandExecutor {
                     whereto,
                 })
             }
-            "HGETALL" => {
-                if args.len() != 1 {
-                    return Err("HGETALL requires 1 argument".to_string());
+            "RANDOMKEY" => {
+                if !args.is_empty() {
+                    return Err("RANDOMKEY takes no arguments".to_string());
+                }
+                Ok(Command::RandomKey)
+            }
+            "TIME" => {
+                if !args.is_empty() {
+                    return Err("TIME takes no arguments".to_string());
                 }
+                Ok(Command::Time)
+            }
+            "SPOP" => {
+                let count = match args.get(1) {
+                    Some(count) => Some(
+                        to_string(count)
+                            .parse()
+                            .map_err(|_| "SPOP count must be integer")?,
+                    ),
+                    None => None,
+                };
+                Ok(Command::SPop(to_string(&args[0]), count))
+            }
+            "HGETALL" => {
                 Ok(Command::HGetAll(to_string(&args[0])))
             }
             "SISMEMBER" => {
-                if args.len() != 2 {
-                    return Err("SISMEMBER requires 2 arguments".to_string());
-                }
                 Ok(Command::SIsMember(to_string(&args[0]), to_sds(&args[1])))
             }
             "ZADD" => {
-                if args.len() < 3 {
-                    return Err("ZADD requires key and score-member pairs".to_string());
-                }
                 let key = to_string(&args[0]);
                 let mut nx = false;
                 let mut xx = false;
@@ -670,16 +984,13 @@ impl CommandExecutor {
                 })
             }
             "ZREM" => {
-                if args.len() < 2 {
-                    return Err("ZREM requires key and at least 1 member".to_string());
-                }
                 let key = to_string(&args[0]);
                 let members: Vec<SDS> = args[1..].iter().map(|a| to_sds(a)).collect();
                 Ok(Command::ZRem(key, members))
             }
             "ZRANGE" => {
                 if args.len() != 3 {
-                    return Err("ZRANGE requires 3 arguments".to_string());
+                    return Err("ERR wrong number of arguments for 'zrange' command".to_string());
                 }
                 let start: isize = to_string(&args[1])
                     .parse()
@@ -687,24 +998,22 @@ impl CommandExecutor {
                 let stop: isize = to_string(&args[2])
                     .parse()
                     .map_err(|_| "ZRANGE stop must be integer")?;
-                Ok(Command::ZRange(to_string(&args[0]), start, stop, false))
+                Ok(Command::ZRange {
+                    dest: None,
+                    key: to_string(&args[0]),
+                    range: ZRangeSpec::Rank(start, stop),
+                    rev: false,
+                    limit: None,
+                    with_scores: false,
+                })
             }
             "ZSCORE" => {
-                if args.len() != 2 {
-                    return Err("ZSCORE requires 2 arguments".to_string());
-                }
                 Ok(Command::ZScore(to_string(&args[0]), to_sds(&args[1])))
             }
             "ZCARD" => {
-                if args.len() != 1 {
-                    return Err("ZCARD requires 1 argument".to_string());
-                }
                 Ok(Command::ZCard(to_string(&args[0])))
             }
             "ZCOUNT" => {
-                if args.len() != 3 {
-                    return Err("ZCOUNT requires 3 arguments".to_string());
-                }
                 Ok(Command::ZCount(
                     to_string(&args[0]),
                     to_string(&args[1]),
@@ -712,9 +1021,6 @@ impl CommandExecutor {
                 ))
             }
             "ZRANGEBYSCORE" => {
-                if args.len() < 3 {
-                    return Err("ZRANGEBYSCORE requires at least 3 arguments".to_string());
-                }
                 let key = to_string(&args[0]);
                 let min = to_string(&args[1]);
                 let max = to_string(&args[2]);
@@ -753,20 +1059,26 @@ impl CommandExecutor {
                     limit,
                 })
             }
-            _ => Err(format!(
-                "ERR Unknown Redis command '{}' called from Lua",
-                cmd_name
-            )),
+            _ => match DeclaredCommand::parse(&cmd_name, parts.len(), |i| {
+                Some(parts[i].as_slice())
+            }) {
+                Some(parsed) => parsed.map(Command::Declared),
+                None => Err(format!(
+                    "ERR Unknown Redis command '{}' called from Lua",
+                    cmd_name
+                )),
+            },
         }
     }
 
     /// Convert Lua value to RespValue
     #[cfg(feature = "lua")]
-    fn lua_to_resp(&self, lua: &mlua::Lua, value: mlua::Value) -> RespValue {
+    fn lua_to_resp(&self, lua: &mlua::Lua, value: mlua::Value, resp3: bool) -> RespValue {
         use mlua::Value as LuaValue;
 
         match value {
             LuaValue::Nil => RespValue::BulkString(None),
+            LuaValue::Boolean(b) if resp3 => RespValue::Boolean(b),
             LuaValue::Boolean(b) => {
                 if b {
                     RespValue::Integer(1)
@@ -785,6 +1097,9 @@ impl CommandExecutor {
                 if let Ok(ok) = t.get::<String>("ok") {
                     return RespValue::simple(ok);
                 }
+                if let Some(reply) = self.lua_resp3_to_resp(lua, &t, resp3) {
+                    return reply;
+                }
 
                 // Otherwise treat as array
                 let mut elements = Vec::new();
@@ -792,7 +1107,7 @@ impl CommandExecutor {
                 loop {
                     match t.get::<mlua::Value>(i) {
                         Ok(val) if !matches!(val, LuaValue::Nil) => {
-                            elements.push(self.lua_to_resp(lua, val));
+                            elements.push(self.lua_to_resp(lua, val, resp3));
                             i += 1;
                         }
                         _ => break,
@@ -803,4 +1118,56 @@ impl CommandExecutor {
             _ => RespValue::BulkString(None),
         }
     }
+
+    /// A `{map=...}`, `{set=...}`, `{double=...}`, `{big_number=...}` or
+    /// `{verbatim_string=...}` table as its RESP3 reply
+    #[cfg(feature = "lua")]
+    fn lua_resp3_to_resp(
+        &self,
+        lua: &mlua::Lua,
+        t: &mlua::Table,
+        resp3: bool,
+    ) -> Option<RespValue> {
+        use mlua::Value as LuaValue;
+
+        if let Ok(LuaValue::Table(map)) = t.get::<LuaValue>("map") {
+            let mut pairs: Vec<(RespValue, RespValue)> = map
+                .pairs::<LuaValue, LuaValue>()
+                .filter_map(Result::ok)
+                .map(|(key, value)| {
+                    (self.lua_to_resp(lua, key, resp3), self.lua_to_resp(lua, value, resp3))
+                })
+                .collect();
+            pairs.sort_by_cached_key(|(key, _)| RespParser::encode(key));
+            return Some(RespValue::Map(pairs));
+        }
+        if let Ok(LuaValue::Table(set)) = t.get::<LuaValue>("set") {
+            let mut members: Vec<RespValue> = set
+                .pairs::<LuaValue, LuaValue>()
+                .filter_map(Result::ok)
+                .map(|(member, _)| self.lua_to_resp(lua, member, resp3))
+                .collect();
+            members.sort_by_cached_key(RespParser::encode);
+            return Some(RespValue::Set(members));
+        }
+        match t.get::<LuaValue>("double") {
+            Ok(LuaValue::Number(d)) => return Some(RespValue::Double(d)),
+            Ok(LuaValue::Integer(i)) => return Some(RespValue::Double(i as f64)),
+            _ => {}
+        }
+        if let Ok(LuaValue::String(digits)) = t.get::<LuaValue>("big_number") {
+            let digits = String::from_utf8_lossy(&digits.as_bytes()).into_owned();
+            return Some(RespValue::BigNumber(digits));
+        }
+        if let Ok(LuaValue::Table(verbatim)) = t.get::<LuaValue>("verbatim_string") {
+            let format = verbatim.get::<mlua::String>("format").ok()?;
+            let format = <[u8; 3]>::try_from(&format.as_bytes()[..]).ok()?;
+            let text = verbatim.get::<mlua::String>("string").ok()?;
+            return Some(RespValue::Verbatim {
+                format,
+                text: text.as_bytes().to_vec(),
+            });
+        }
+        None
+    }
 }
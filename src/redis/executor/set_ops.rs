@@ -1,6 +1,7 @@
 //! Set command implementations for CommandExecutor.
 //!
-//! Handles: SADD, SREM, SMEMBERS, SISMEMBER, SCARD, SPOP
+//! Handles: SADD, SREM, SMEMBERS, SISMEMBER, SMISMEMBER, SCARD, SPOP,
+//! SRANDMEMBER, SINTERCARD
 
 use super::CommandExecutor;
 use crate::redis::data::{RedisSet, Value, SDS};
@@ -126,39 +127,36 @@ impl CommandExecutor {
         }
     }
 
+    /// SPOP key [count]. As with SRANDMEMBER, members are sorted and the
+    /// executor RNG alone decides which ones leave.
     pub(super) fn execute_spop(&mut self, key: &str, count: Option<usize>) -> RespValue {
-        let result = match self.get_value_mut(key) {
-            Some(Value::Set(s)) => match count {
-                None => {
-                    // SPOP key - return single element or nil
-                    match s.pop() {
-                        Some(member) => {
-                            RespValue::BulkString(Some(member.to_string().into_bytes()))
-                        }
-                        None => RespValue::BulkString(None),
-                    }
-                }
-                Some(n) => {
-                    // SPOP key count - return array of elements
-                    let members = s.pop_count(n);
-                    RespValue::Array(Some(
-                        members
-                            .into_iter()
-                            .map(|m| RespValue::BulkString(Some(m.to_string().into_bytes())))
-                            .collect(),
-                    ))
-                }
-            },
+        let mut members = match self.get_value(key) {
+            Some(Value::Set(s)) => s.members(),
             Some(_) => {
-                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
             }
-            None => {
-                // Key doesn't exist
-                match count {
-                    None => RespValue::BulkString(None),
-                    Some(_) => RespValue::Array(Some(vec![])),
-                }
+            None if count.is_some() => return RespValue::Array(Some(Vec::new())),
+            None => return RespValue::BulkString(None),
+        };
+        members.sort_unstable_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        let n = count.unwrap_or(1).min(i64::MAX as usize) as i64;
+        let picked = self.sample_with_count(members, n);
+        if let Some(Value::Set(s)) = self.data.get_mut(key) {
+            for member in &picked {
+                s.remove(member);
             }
+        }
+
+        let result = match count {
+            None => RespValue::BulkString(picked.first().map(|m| m.as_bytes().to_vec())),
+            Some(_) => RespValue::Array(Some(
+                picked
+                    .into_iter()
+                    .map(|m| RespValue::BulkString(Some(m.as_bytes().to_vec())))
+                    .collect(),
+            )),
         };
         // Redis auto-deletes empty sets
         if matches!(self.data.get(key), Some(Value::Set(s)) if s.is_empty()) {
@@ -171,4 +169,103 @@ impl CommandExecutor {
         }
         result
     }
+
+    pub(super) fn execute_smismember(&mut self, key: &str, members: &[SDS]) -> RespValue {
+        debug_assert!(!members.is_empty(), "Precondition: SMISMEMBER needs a member");
+        let flags: Vec<RespValue> = match self.get_value(key) {
+            Some(Value::Set(s)) => members
+                .iter()
+                .map(|m| RespValue::Integer(i64::from(s.contains(m))))
+                .collect(),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => vec![RespValue::Integer(0); members.len()],
+        };
+        debug_assert_eq!(flags.len(), members.len(), "Postcondition: one flag per member");
+        RespValue::Array(Some(flags))
+    }
+
+    /// SRANDMEMBER key [count]. Members are sorted before sampling so the
+    /// executor RNG alone decides the result, whatever the hash order.
+    pub(super) fn execute_srandmember(&mut self, key: &str, count: Option<i64>) -> RespValue {
+        if count == Some(i64::MIN) {
+            return RespValue::err(format!(
+                "ERR value is out of range, value must between {} and {}",
+                -i64::MAX,
+                i64::MAX
+            ));
+        }
+        let mut members = match self.get_value(key) {
+            Some(Value::Set(s)) => s.members(),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None if count.is_some() => return RespValue::Array(Some(Vec::new())),
+            None => return RespValue::BulkString(None),
+        };
+        debug_assert!(!members.is_empty(), "Invariant: stored sets are never empty");
+        members.sort_unstable_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+        let picked = match count {
+            None => {
+                let i = self.rng.gen_range(0, members.len() as u64) as usize;
+                return RespValue::BulkString(Some(members.swap_remove(i).as_bytes().to_vec()));
+            }
+            Some(n) => self.sample_with_count(members, n),
+        };
+        RespValue::Array(Some(
+            picked
+                .into_iter()
+                .map(|m| RespValue::BulkString(Some(m.as_bytes().to_vec())))
+                .collect(),
+        ))
+    }
+
+    /// SINTERCARD: cardinality of the intersection, stopping at `limit`
+    /// (0 = no limit). A missing key empties the intersection before later
+    /// keys are type-checked, as in Redis.
+    pub(super) fn execute_sintercard(&mut self, keys: &[String], limit: usize) -> RespValue {
+        debug_assert!(!keys.is_empty(), "Precondition: SINTERCARD needs a key");
+        for key in keys {
+            match self.get_value(key) {
+                Some(Value::Set(_)) => {}
+                Some(_) => {
+                    return RespValue::err(
+                        "WRONGTYPE Operation against a key holding the wrong kind of value",
+                    )
+                }
+                None => return RespValue::Integer(0),
+            }
+        }
+
+        let mut sets: Vec<&RedisSet> = keys
+            .iter()
+            .filter_map(|key| match self.data.get(key) {
+                Some(Value::Set(s)) => Some(s),
+                _ => None,
+            })
+            .collect();
+        debug_assert_eq!(sets.len(), keys.len(), "Precondition: every key holds a set");
+        // Probe from the smallest set: it bounds the result
+        sets.sort_by_key(|s| s.len());
+
+        let mut count = 0usize;
+        for member in sets[0].members() {
+            if sets[1..].iter().all(|s| s.contains(&member)) {
+                count += 1;
+                if count == limit {
+                    break;
+                }
+            }
+        }
+
+        debug_assert!(count <= sets[0].len(), "Postcondition: bounded by the smallest set");
+        debug_assert!(limit == 0 || count <= limit, "Postcondition: bounded by LIMIT");
+        RespValue::Integer(count as i64)
+    }
 }
@@ -0,0 +1,94 @@
+//! Read-only snapshot queries.
+//!
+//! `CommandExecutor::snapshot` freezes the keyspace - data, expirations,
+//! access times, keyspace statistics, config and the clock - into a
+//! `KeyspaceSnapshot` that answers read-only commands on its own. A batch of
+//! reads against one snapshot sees a single point in time however many
+//! writes the live executor applies meanwhile, so analytics-style multi-key
+//! reads can run off the write path: taking the snapshot is the only step
+//! that holds up writers.
+//!
+//! The snapshot is a point-in-time copy, so taking one costs O(keyspace).
+//! `DEBUG SNAPSHOT-READ <argc> <arg>... [<argc> <arg>...]` exercises the
+//! same path from a client and replies with one reply per command.
+//!
+//! # TigerStyle Invariants
+//!
+//! - A snapshot never changes the executor it was taken from
+//! - Only read-only commands run against a snapshot; its clock never moves
+
+use super::CommandExecutor;
+use crate::redis::command::Command;
+use crate::redis::resp::RespValue;
+use crate::simulator::VirtualTime;
+
+/// A frozen view of one executor's keyspace, answering read-only commands
+pub struct KeyspaceSnapshot {
+    frozen: CommandExecutor,
+    taken_at: VirtualTime,
+}
+
+impl CommandExecutor {
+    /// Freeze the keyspace as it is now
+    pub fn snapshot(&self) -> KeyspaceSnapshot {
+        let mut frozen = CommandExecutor::new();
+        frozen.data = self.data.clone();
+        frozen.expirations = self.expirations.clone();
+        frozen.field_ttl_keys = self.field_ttl_keys.clone();
+        frozen.access_times = self.access_times.clone();
+        frozen.keyspace_stats = self.keyspace_stats.clone();
+        frozen.config = self.config.clone();
+        frozen.current_time = self.current_time;
+        frozen.simulation_start_epoch = self.simulation_start_epoch;
+        frozen.simulation_start_epoch_ms = self.simulation_start_epoch_ms;
+
+        debug_assert_eq!(
+            frozen.data.len(),
+            self.data.len(),
+            "Postcondition: snapshot must hold every key"
+        );
+        KeyspaceSnapshot {
+            frozen,
+            taken_at: self.current_time,
+        }
+    }
+
+    pub(super) fn execute_debug_snapshot_read(&self, cmds: &[Command]) -> RespValue {
+        debug_assert!(!cmds.is_empty(), "Precondition: SNAPSHOT-READ needs a command");
+        RespValue::Array(Some(self.snapshot().read_batch(cmds)))
+    }
+}
+
+impl KeyspaceSnapshot {
+    /// Time the snapshot was taken; reads see keys as of this instant
+    pub fn taken_at(&self) -> VirtualTime {
+        self.taken_at
+    }
+
+    /// Run one read-only command against the snapshot. Writes are refused.
+    pub fn read(&mut self, cmd: &Command) -> RespValue {
+        if !cmd.is_read_only() {
+            return RespValue::err(format!(
+                "ERR '{}' is not a read-only command",
+                cmd.name().to_ascii_lowercase()
+            ));
+        }
+        let reply = self.frozen.execute(cmd);
+        debug_assert_eq!(
+            self.frozen.current_time, self.taken_at,
+            "Invariant: a snapshot's clock never moves"
+        );
+        reply
+    }
+
+    /// Run `cmds` in order against the snapshot, one reply per command
+    pub fn read_batch(&mut self, cmds: &[Command]) -> Vec<RespValue> {
+        let replies: Vec<RespValue> = cmds.iter().map(|cmd| self.read(cmd)).collect();
+        debug_assert_eq!(
+            replies.len(),
+            cmds.len(),
+            "Postcondition: one reply per command"
+        );
+        replies
+    }
+}
@@ -0,0 +1,218 @@
+//! SORT.
+//!
+//! Elements come from a list (in list order), a set, or a sorted set (in
+//! score order). They sort as numbers by default, and a value that is not a
+//! number fails the whole command with Redis' error; ALPHA sorts them as
+//! byte strings instead. Equal numbers fall back to comparing the elements
+//! and the sort is stable, so the order never depends on how a set happens
+//! to iterate.
+//!
+//! BY and GET patterns name other keys: the first `*` is replaced by the
+//! element, and a `->field` suffix reads that hash field rather than a
+//! string. GET `#` is the element itself. A missing key or field weighs 0
+//! (or sorts first with ALPHA), and projects as nil. A BY pattern without a
+//! `*` skips sorting, except that set elements still sort as byte strings,
+//! as Redis does when storing them. Pattern keys are read here only, never
+//! expired or stamped: under the sharded server they must live on the
+//! source key's shard, as Redis Cluster requires.
+//!
+//! # TigerStyle Invariants
+//!
+//! - The reply holds at most `count` elements per GET pattern (one without)
+//! - STORE leaves a list without TTL at the destination, or no key if empty
+
+use super::CommandExecutor;
+use crate::redis::data::{RedisList, Value, SDS};
+use crate::redis::resp::RespValue;
+use std::cmp::Ordering;
+
+/// What an element sorts by
+enum Weight {
+    Score(f64),
+    /// A missing BY key sorts before every value
+    Bytes(Option<SDS>),
+}
+
+fn parse_score(value: &SDS) -> Option<f64> {
+    let score: f64 = std::str::from_utf8(value.as_bytes()).ok()?.parse().ok()?;
+    (!score.is_nan()).then_some(score)
+}
+
+fn compare(a: &(SDS, Weight), b: &(SDS, Weight)) -> Ordering {
+    match (&a.1, &b.1) {
+        (Weight::Score(x), Weight::Score(y)) => x
+            .partial_cmp(y)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.as_bytes().cmp(b.0.as_bytes())),
+        (Weight::Bytes(x), Weight::Bytes(y)) => x
+            .as_ref()
+            .map(SDS::as_bytes)
+            .cmp(&y.as_ref().map(SDS::as_bytes)),
+        _ => unreachable!("one SORT weighs every element the same way"),
+    }
+}
+
+/// The `[start, end)` window of `len` elements that LIMIT selects, clamped
+/// the way Redis clamps it
+fn limit_window(len: usize, limit: Option<(i64, i64)>) -> (usize, usize) {
+    let Some((offset, count)) = limit else {
+        return (0, len);
+    };
+    let start = (offset.max(0) as u64).min(len as u64) as usize;
+    let end = if count < 0 {
+        len
+    } else {
+        (start as u64).saturating_add(count as u64).min(len as u64) as usize
+    };
+    (start, end)
+}
+
+impl CommandExecutor {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn execute_sort(
+        &mut self,
+        key: &str,
+        by: Option<&str>,
+        limit: Option<(i64, i64)>,
+        get: &[String],
+        desc: bool,
+        alpha: bool,
+        store: Option<&str>,
+    ) -> RespValue {
+        let (elements, from_set) = match self.get_value(key) {
+            Some(Value::List(l)) => (l.range(0, -1), false),
+            Some(Value::Set(s)) => (s.members(), true),
+            Some(Value::SortedSet(z)) => {
+                (z.range(0, -1).into_iter().map(|(m, _)| m).collect(), false)
+            }
+            None => (Vec::new(), false),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+        };
+
+        let (sorted, by, alpha) = match by {
+            Some(pattern) if !pattern.contains('*') => (from_set, None, true),
+            by => (true, by, alpha),
+        };
+        let mut elements = if sorted {
+            let mut weighted = Vec::with_capacity(elements.len());
+            for element in elements {
+                let value = match by {
+                    Some(pattern) => self.lookup_sort_pattern(pattern, &element),
+                    None => Some(element.clone()),
+                };
+                let weight = if alpha {
+                    Weight::Bytes(value)
+                } else {
+                    match value.map_or(Some(0.0), |v| parse_score(&v)) {
+                        Some(score) => Weight::Score(score),
+                        None => {
+                            return RespValue::err(
+                                "ERR One or more scores can't be converted into double",
+                            )
+                        }
+                    }
+                };
+                weighted.push((element, weight));
+            }
+            weighted.sort_by(|a, b| {
+                let ordering = compare(a, b);
+                if desc {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+            weighted.into_iter().map(|(element, _)| element).collect()
+        } else {
+            elements
+        };
+
+        let (start, end) = limit_window(elements.len(), limit);
+        elements.truncate(end);
+        elements.drain(..start);
+        let values: Vec<Option<SDS>> = if get.is_empty() {
+            elements.into_iter().map(Some).collect()
+        } else {
+            elements
+                .iter()
+                .flat_map(|element| get.iter().map(move |pattern| (pattern, element)))
+                .map(|(pattern, element)| self.lookup_sort_pattern(pattern, element))
+                .collect()
+        };
+
+        // TigerStyle: Postcondition - LIMIT bounds the reply
+        debug_assert!(
+            values.len() <= (end - start) * get.len().max(1),
+            "Postcondition violated: SORT must reply at most one value per element and GET"
+        );
+
+        let Some(dest) = store else {
+            return RespValue::Array(Some(
+                values
+                    .into_iter()
+                    .map(|v| RespValue::BulkString(v.map(|v| v.as_bytes().to_vec())))
+                    .collect(),
+            ));
+        };
+        let count = values.len();
+        self.expirations.remove(dest);
+        if values.is_empty() {
+            self.data.remove(dest);
+        } else {
+            let mut list = RedisList::new();
+            for value in values {
+                list.rpush(value.unwrap_or_else(|| SDS::from_str("")));
+            }
+            self.data.insert(dest.to_string(), Value::List(list));
+        }
+
+        // TigerStyle: Postcondition - STORE replaces the destination
+        debug_assert!(
+            !self.expirations.contains_key(dest),
+            "Postcondition violated: SORT STORE must leave no TTL on the destination"
+        );
+        RespValue::Integer(count as i64)
+    }
+
+    /// Resolve a BY or GET pattern for one element (see the module docs)
+    fn lookup_sort_pattern(&self, pattern: &str, element: &SDS) -> Option<SDS> {
+        if pattern == "#" {
+            return Some(element.clone());
+        }
+        let star = pattern.find('*')?;
+        let (key_pattern, field) = match pattern[star + 1..].find("->") {
+            Some(arrow) if star + 1 + arrow + 2 < pattern.len() => {
+                let arrow = star + 1 + arrow;
+                (&pattern[..arrow], Some(&pattern[arrow + 2..]))
+            }
+            _ => (pattern, None),
+        };
+
+        let mut name = Vec::with_capacity(key_pattern.len() + element.len());
+        name.extend_from_slice(&key_pattern.as_bytes()[..star]);
+        name.extend_from_slice(element.as_bytes());
+        name.extend_from_slice(&key_pattern.as_bytes()[star + 1..]);
+        let name = String::from_utf8_lossy(&name);
+        if self.is_expired(&name) {
+            return None;
+        }
+
+        let now_ms = self.current_time.as_millis();
+        match (self.data.get(name.as_ref())?, field) {
+            (Value::String(s), None) => Some(s.clone()),
+            (Value::Hash(h), Some(field)) => {
+                if h.field_expiry(field)
+                    .is_some_and(|deadline| deadline <= now_ms)
+                {
+                    return None;
+                }
+                h.get_str(field).cloned()
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,8 +1,14 @@
 //! Sorted set command implementations for CommandExecutor.
 //!
-//! Handles: ZADD, ZREM, ZRANGE, ZREVRANGE, ZSCORE, ZRANK, ZCARD, ZCOUNT, ZRANGEBYSCORE
+//! Handles: ZADD, ZINCRBY, ZREM, ZREMRANGEBYRANK/BYSCORE/BYLEX, ZRANGE,
+//! ZRANGESTORE, ZREVRANGE, ZSCORE, ZMSCORE, ZRANK,
+//! ZCARD, ZCOUNT, ZRANGEBYSCORE, ZRANDMEMBER, ZPOPMIN, ZPOPMAX, and the
+//! non-blocking half of BZPOPMIN/BZPOPMAX (see `redis::blocking`). ZADD and
+//! ZINCRBY signal the key so blocked clients retry. ZRANDMEMBER draws from
+//! the executor's seeded RNG, so its replies replay under a seed.
 
 use super::CommandExecutor;
+use crate::redis::command::ZRangeSpec;
 use crate::redis::data::{RedisSortedSet, Value, SDS};
 use crate::redis::resp::RespValue;
 
@@ -37,6 +43,9 @@ impl CommandExecutor {
                             added += 1;
                         }
                     }
+                    if added > 0 {
+                        self.blocking.signal(key);
+                    }
                     #[cfg(debug_assertions)]
                     debug_assert!(self.data.contains_key(key), "Postcondition: ZADD key must exist after adding members");
                     return RespValue::Integer(added);
@@ -82,6 +91,9 @@ impl CommandExecutor {
                         changed += 1;
                     }
                 }
+                if added > 0 {
+                    self.blocking.signal(key);
+                }
                 #[cfg(debug_assertions)]
                 debug_assert!(self.data.contains_key(key), "Postcondition: ZADD key must exist after adding members");
                 // CH: return number changed, not just added
@@ -97,6 +109,51 @@ impl CommandExecutor {
         }
     }
 
+    /// ZINCRBY: add `increment` to the member's score (0 when absent),
+    /// creating the key if needed. Replies with the new score.
+    pub(super) fn execute_zincrby(&mut self, key: &str, increment: f64, member: &SDS) -> RespValue {
+        debug_assert!(
+            !increment.is_nan(),
+            "Precondition: the parser rejects a NaN increment"
+        );
+        let current = match self.get_value(key) {
+            Some(Value::SortedSet(zs)) => zs.score(member),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => None,
+        };
+        // inf + -inf
+        let score = current.unwrap_or(0.0) + increment;
+        if score.is_nan() {
+            return RespValue::err("ERR resulting score is not a number (NaN)");
+        }
+
+        if self.is_expired(key) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+        let Value::SortedSet(zs) = self
+            .data
+            .entry(key.to_string())
+            .or_insert_with(|| Value::SortedSet(RedisSortedSet::new()))
+        else {
+            unreachable!("type checked above");
+        };
+        if zs.add(member.clone(), score) {
+            self.blocking.signal(key);
+        }
+
+        debug_assert_eq!(
+            zs.score(member),
+            Some(score),
+            "Postcondition: ZINCRBY must store the new score"
+        );
+        RespValue::BulkString(Some(score.to_string().into_bytes()))
+    }
+
     pub(super) fn execute_zrem(&mut self, key: &str, members: &[SDS]) -> RespValue {
         let result = match self.get_value_mut(key) {
             Some(Value::SortedSet(zs)) => {
@@ -144,36 +201,88 @@ impl CommandExecutor {
         result
     }
 
+    /// Unified ZRANGE and ZRANGESTORE: select by rank, score or lex, in
+    /// either direction, then apply LIMIT (a negative count takes the rest).
     pub(super) fn execute_zrange(
         &mut self,
+        dest: Option<&str>,
         key: &str,
-        start: isize,
-        stop: isize,
+        range: &ZRangeSpec,
+        rev: bool,
+        limit: Option<(i64, i64)>,
         with_scores: bool,
     ) -> RespValue {
-        match self.get_value(key) {
-            Some(Value::SortedSet(zs)) => {
-                let range = zs.range(start, stop);
-                if with_scores {
-                    let mut elements = Vec::with_capacity(range.len() * 2);
-                    for (m, s) in range {
-                        elements.push(RespValue::BulkString(Some(m.as_bytes().to_vec())));
-                        elements.push(RespValue::BulkString(Some(s.to_string().into_bytes())));
-                    }
-                    RespValue::Array(Some(elements))
-                } else {
-                    let elements: Vec<RespValue> = range
-                        .iter()
-                        .map(|(m, _)| RespValue::BulkString(Some(m.as_bytes().to_vec())))
-                        .collect();
-                    RespValue::Array(Some(elements))
-                }
+        debug_assert!(
+            limit.is_none() || !matches!(range, ZRangeSpec::Rank(..)),
+            "Precondition: LIMIT needs BYSCORE or BYLEX"
+        );
+
+        let mut selected = match self.get_value(key) {
+            None => Vec::new(),
+            Some(Value::SortedSet(zs)) => match select_range(zs, range, rev) {
+                Ok(selected) => selected,
+                Err(e) => return RespValue::err(e),
+            },
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
             }
+        };
+
+        if let Some((offset, count)) = limit {
+            let count = usize::try_from(count).unwrap_or(usize::MAX);
+            selected = match usize::try_from(offset) {
+                Ok(offset) => selected.into_iter().skip(offset).take(count).collect(),
+                Err(_) => Vec::new(),
+            };
+        }
+
+        if let Some(dest) = dest {
+            return self.store_zset_result(dest, selected);
+        }
+        let mut elements = Vec::with_capacity(selected.len() * if with_scores { 2 } else { 1 });
+        for (member, score) in selected {
+            elements.push(RespValue::BulkString(Some(member.into_bytes())));
+            if with_scores {
+                elements.push(RespValue::BulkString(Some(score.to_string().into_bytes())));
+            }
+        }
+        RespValue::Array(Some(elements))
+    }
+
+    /// ZREMRANGEBYRANK/BYSCORE/BYLEX: remove the selected members, deleting
+    /// the key once it is empty, and reply with how many went.
+    pub(super) fn execute_zremrange(&mut self, key: &str, range: &ZRangeSpec) -> RespValue {
+        let doomed = match self.get_value(key) {
+            None => return RespValue::Integer(0),
+            Some(Value::SortedSet(zs)) => match select_range(zs, range, false) {
+                Ok(selected) => selected,
+                Err(e) => return RespValue::err(e),
+            },
             Some(_) => {
-                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
             }
-            None => RespValue::Array(Some(Vec::new())),
+        };
+        let Some(Value::SortedSet(zs)) = self.data.get_mut(key) else {
+            unreachable!("key was just read as a sorted set");
+        };
+        let pre_len = zs.len();
+        for (member, _) in &doomed {
+            zs.remove(&SDS::from_str(member));
         }
+        debug_assert_eq!(
+            zs.len(),
+            pre_len - doomed.len(),
+            "Postcondition: every selected member must be removed"
+        );
+        if zs.is_empty() {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+        RespValue::Integer(doomed.len() as i64)
     }
 
     pub(super) fn execute_zrevrange(
@@ -224,7 +333,37 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_zrank(&mut self, key: &str, member: &SDS) -> RespValue {
+    /// ZMSCORE: one score (or nil) per member; a missing key is all nils
+    pub(super) fn execute_zmscore(&mut self, key: &str, members: &[SDS]) -> RespValue {
+        debug_assert!(!members.is_empty(), "Precondition: ZMSCORE needs a member");
+        let zs = match self.get_value(key) {
+            Some(Value::SortedSet(zs)) => Some(zs),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => None,
+        };
+        let scores: Vec<RespValue> = members
+            .iter()
+            .map(|member| {
+                RespValue::BulkString(
+                    zs.and_then(|zs| zs.score(member))
+                        .map(|score| score.to_string().into_bytes()),
+                )
+            })
+            .collect();
+
+        debug_assert_eq!(
+            scores.len(),
+            members.len(),
+            "Postcondition: ZMSCORE replies once per member"
+        );
+        RespValue::Array(Some(scores))
+    }
+
+    pub(crate) fn execute_zrank(&mut self, key: &str, member: &SDS) -> RespValue {
         match self.get_value(key) {
             Some(Value::SortedSet(zs)) => match zs.rank(member) {
                 Some(rank) => {
@@ -307,4 +446,162 @@ impl CommandExecutor {
             None => RespValue::Array(Some(vec![])),
         }
     }
+
+    /// ZRANDMEMBER: a random member without a count; with one, SRANDMEMBER
+    /// count semantics (distinct when positive, repeats when negative),
+    /// optionally followed by each member's score.
+    pub(super) fn execute_zrandmember(
+        &mut self,
+        key: &str,
+        count: Option<i64>,
+        with_scores: bool,
+    ) -> RespValue {
+        debug_assert!(
+            count.is_some() || !with_scores,
+            "Precondition: WITHSCORES requires a count"
+        );
+        if count == Some(i64::MIN) {
+            return RespValue::err(format!(
+                "ERR value is out of range, value must between {} and {}",
+                -i64::MAX,
+                i64::MAX
+            ));
+        }
+        // The reply holds two entries per pick
+        if with_scores && count.is_some_and(|n| n < -(i64::MAX / 2)) {
+            return RespValue::err("ERR value is out of range");
+        }
+        // Score order, so picks replay under a seed
+        let mut members = match self.get_value(key) {
+            Some(Value::SortedSet(zs)) => zs.range(0, -1),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None if count.is_some() => return RespValue::Array(Some(Vec::new())),
+            None => return RespValue::BulkString(None),
+        };
+        debug_assert!(!members.is_empty(), "Invariant: stored sorted sets are never empty");
+
+        let picked = match count {
+            None => {
+                let i = self.rng.gen_range(0, members.len() as u64) as usize;
+                let (member, _) = members.swap_remove(i);
+                return RespValue::BulkString(Some(member.as_bytes().to_vec()));
+            }
+            Some(n) => self.sample_with_count(members, n),
+        };
+        let mut elements = Vec::with_capacity(picked.len() * if with_scores { 2 } else { 1 });
+        for (member, score) in picked {
+            elements.push(RespValue::BulkString(Some(member.as_bytes().to_vec())));
+            if with_scores {
+                elements.push(RespValue::BulkString(Some(score.to_string().into_bytes())));
+            }
+        }
+        RespValue::Array(Some(elements))
+    }
+
+    /// ZPOPMIN/ZPOPMAX: remove the lowest (or highest) scored members.
+    /// Without a count at most one `[member, score]` pair is returned.
+    pub(super) fn execute_zpop(&mut self, key: &str, count: Option<usize>, min: bool) -> RespValue {
+        let n = count.unwrap_or(1);
+        let popped = match self.get_value_mut(key) {
+            Some(Value::SortedSet(zs)) => {
+                let pre_len = zs.len();
+                let stop = n.min(pre_len) as isize - 1;
+                let members = if stop < 0 {
+                    Vec::new()
+                } else if min {
+                    zs.range(0, stop)
+                } else {
+                    zs.rev_range(0, stop)
+                };
+                for (member, _) in &members {
+                    zs.remove(member);
+                }
+
+                debug_assert_eq!(
+                    zs.len(),
+                    pre_len - members.len(),
+                    "Postcondition: ZPOP must remove every returned member"
+                );
+                members
+            }
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => Vec::new(),
+        };
+        // Redis auto-deletes empty sorted sets
+        if matches!(self.data.get(key), Some(Value::SortedSet(zs)) if zs.len() == 0) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+
+        let mut elements = Vec::with_capacity(popped.len() * 2);
+        for (member, score) in popped {
+            elements.push(RespValue::BulkString(Some(member.as_bytes().to_vec())));
+            elements.push(RespValue::BulkString(Some(score.to_string().into_bytes())));
+        }
+        RespValue::Array(Some(elements))
+    }
+
+    /// BZPOPMIN/BZPOPMAX without blocking: pop from the first non-empty
+    /// sorted set, replying `[key, member, score]`, or nil when all are empty
+    pub(super) fn execute_bzpop(&mut self, keys: &[String], min: bool) -> RespValue {
+        debug_assert!(!keys.is_empty(), "Precondition: BZPOPMIN/BZPOPMAX needs at least one key");
+
+        for key in keys {
+            match self.get_value(key) {
+                Some(Value::SortedSet(zs)) if zs.len() > 0 => {}
+                Some(Value::SortedSet(_)) | None => continue,
+                Some(_) => {
+                    return RespValue::err(
+                        "WRONGTYPE Operation against a key holding the wrong kind of value",
+                    )
+                }
+            }
+            let RespValue::Array(Some(mut pair)) = self.execute_zpop(key, None, min) else {
+                unreachable!("ZPOP on a sorted set replies with an array");
+            };
+            debug_assert_eq!(pair.len(), 2, "Postcondition: a non-empty set yields one pair");
+            pair.insert(0, RespValue::BulkString(Some(key.as_bytes().to_vec())));
+            return RespValue::Array(Some(pair));
+        }
+        RespValue::Array(None)
+    }
+}
+
+/// Members selected by a rank, score or lex range, in reply order
+fn select_range(
+    zs: &RedisSortedSet,
+    range: &ZRangeSpec,
+    rev: bool,
+) -> Result<Vec<(String, f64)>, String> {
+    let mut selected: Vec<(String, f64)> = match range {
+        ZRangeSpec::Rank(start, stop) => {
+            let ranked = if rev {
+                zs.rev_range(*start, *stop)
+            } else {
+                zs.range(*start, *stop)
+            };
+            return Ok(ranked
+                .into_iter()
+                .map(|(m, s)| (m.to_string(), s))
+                .collect());
+        }
+        ZRangeSpec::Score(min, max) => zs
+            .range_by_score(min, max, true, None)?
+            .into_iter()
+            .map(|(m, s)| (m, s.expect("scores requested")))
+            .collect(),
+        ZRangeSpec::Lex(min, max) => zs.range_by_lex(min, max)?,
+    };
+    if rev {
+        selected.reverse();
+    }
+    Ok(selected)
 }
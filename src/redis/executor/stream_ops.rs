@@ -0,0 +1,450 @@
+//! Stream command implementations for CommandExecutor.
+//!
+//! Handles: XADD, XLEN, XRANGE, XREVRANGE, XREAD, and the consumer group
+//! commands XGROUP, XREADGROUP, XACK, XPENDING, XCLAIM, XAUTOCLAIM. XREAD
+//! and XREADGROUP never block here; with BLOCK their nil reply is turned
+//! into a wait one layer up (see `redis::blocking`), and XADD signals the
+//! key to wake those readers.
+
+use super::CommandExecutor;
+use crate::redis::data::{
+    ClaimOptions, PendingRange, RedisStream, StreamFields, StreamId, StreamIdSpec, Value, SDS,
+};
+use crate::redis::resp::RespValue;
+
+const WRONGTYPE: &str = "WRONGTYPE Operation against a key holding the wrong kind of value";
+const XGROUP_NO_KEY: &str = "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.";
+
+fn bulk_id(id: StreamId) -> RespValue {
+    RespValue::BulkString(Some(id.to_string().into_bytes()))
+}
+
+fn no_group(key: &str, group: &str) -> RespValue {
+    RespValue::err(format!(
+        "NOGROUP No such key '{}' or consumer group '{}'",
+        key, group
+    ))
+}
+
+/// `[id, [field, value, ...]]`
+fn entry_reply(id: StreamId, fields: &StreamFields) -> RespValue {
+    let mut flat = Vec::with_capacity(fields.len() * 2);
+    for (field, value) in fields {
+        flat.push(RespValue::BulkString(Some(field.as_bytes().to_vec())));
+        flat.push(RespValue::BulkString(Some(value.as_bytes().to_vec())));
+    }
+    RespValue::Array(Some(vec![bulk_id(id), RespValue::Array(Some(flat))]))
+}
+
+fn entries_reply(entries: Vec<(StreamId, &StreamFields)>) -> RespValue {
+    RespValue::Array(Some(
+        entries
+            .into_iter()
+            .map(|(id, fields)| entry_reply(id, fields))
+            .collect(),
+    ))
+}
+
+impl CommandExecutor {
+    /// Wall-clock milliseconds used for `*` IDs and consumer group idle times
+    pub(crate) fn stream_now_ms(&self) -> u64 {
+        self.simulation_start_epoch_ms
+            .saturating_add(self.current_time.as_millis() as i64)
+            .max(0) as u64
+    }
+
+    pub(super) fn execute_xadd(
+        &mut self,
+        key: &str,
+        spec: StreamIdSpec,
+        fields: &[(SDS, SDS)],
+        nomkstream: bool,
+        maxlen: Option<usize>,
+    ) -> RespValue {
+        debug_assert!(
+            !fields.is_empty(),
+            "Precondition: XADD needs at least one field"
+        );
+
+        if self.is_expired(key) {
+            self.data.remove(key);
+            self.expirations.remove(key);
+        }
+
+        // Resolve the ID before touching the keyspace so a rejected ID never
+        // creates an empty stream
+        let now_ms = self.stream_now_ms();
+        let id = match self.data.get(key) {
+            Some(Value::Stream(s)) => s.next_id(spec, now_ms),
+            Some(_) => return RespValue::err(WRONGTYPE),
+            None if nomkstream => return RespValue::BulkString(None),
+            None => RedisStream::new().next_id(spec, now_ms),
+        };
+        let id = match id {
+            Ok(id) => id,
+            Err(e) => return RespValue::err(e),
+        };
+
+        let value = self
+            .data
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Stream(RedisStream::new()));
+        if let Value::Stream(s) = value {
+            s.add(id, fields.to_vec());
+            if let Some(maxlen) = maxlen {
+                s.trim_maxlen(maxlen);
+            }
+            debug_assert_eq!(s.last_id(), id, "Postcondition: XADD must advance last_id");
+        }
+        self.blocking.signal(key);
+
+        bulk_id(id)
+    }
+
+    pub(crate) fn execute_xlen(&mut self, key: &str) -> RespValue {
+        match self.get_value(key) {
+            Some(Value::Stream(s)) => RespValue::Integer(s.len() as i64),
+            Some(_) => RespValue::err(WRONGTYPE),
+            None => RespValue::Integer(0),
+        }
+    }
+
+    pub(super) fn execute_xrange(
+        &mut self,
+        key: &str,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    ) -> RespValue {
+        match self.get_value(key) {
+            Some(Value::Stream(s)) => {
+                let entries = if rev {
+                    s.rev_range(start, end, count)
+                } else {
+                    s.range(start, end, count)
+                };
+                debug_assert!(
+                    count.map_or(true, |c| entries.len() <= c),
+                    "Postcondition: XRANGE must honor COUNT"
+                );
+                entries_reply(entries)
+            }
+            Some(_) => RespValue::err(WRONGTYPE),
+            None => RespValue::Array(Some(Vec::new())),
+        }
+    }
+
+    /// Non-blocking XREAD. Replies nil when no stream has newer entries,
+    /// otherwise `[[key, [entry, ...]], ...]` for the streams that do.
+    pub(super) fn execute_xread(
+        &mut self,
+        count: Option<usize>,
+        keys: &[String],
+        ids: &[Option<StreamId>],
+    ) -> RespValue {
+        debug_assert_eq!(keys.len(), ids.len(), "Precondition: one ID per XREAD key");
+
+        let mut results = Vec::new();
+        for (key, id) in keys.iter().zip(ids) {
+            match self.get_value(key) {
+                Some(Value::Stream(s)) => {
+                    // `$` only sees entries added after this call, so a
+                    // non-blocking read never returns anything for it
+                    let after = id.unwrap_or_else(|| s.last_id());
+                    let entries = s.read_after(after, count);
+                    if !entries.is_empty() {
+                        results.push(RespValue::Array(Some(vec![
+                            RespValue::BulkString(Some(key.as_bytes().to_vec())),
+                            entries_reply(entries),
+                        ])));
+                    }
+                }
+                Some(_) => return RespValue::err(WRONGTYPE),
+                None => {}
+            }
+        }
+
+        if results.is_empty() {
+            RespValue::Array(None)
+        } else {
+            RespValue::Array(Some(results))
+        }
+    }
+
+    /// The stream at `key`, or the error reply for a missing key / wrong type
+    fn stream_mut(&mut self, key: &str, missing: RespValue) -> Result<&mut RedisStream, RespValue> {
+        match self.get_value_mut(key) {
+            Some(Value::Stream(s)) => Ok(s),
+            Some(_) => Err(RespValue::err(WRONGTYPE)),
+            None => Err(missing),
+        }
+    }
+
+    pub(super) fn execute_xgroup_create(
+        &mut self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+        mkstream: bool,
+    ) -> RespValue {
+        if mkstream && self.get_value(key).is_none() {
+            self.data
+                .insert(key.to_string(), Value::Stream(RedisStream::new()));
+        }
+        let stream = match self.stream_mut(key, RespValue::err(XGROUP_NO_KEY)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let last_delivered = id.unwrap_or_else(|| stream.last_id());
+        if stream.create_group(group, last_delivered) {
+            RespValue::ok()
+        } else {
+            RespValue::err("BUSYGROUP Consumer Group name already exists")
+        }
+    }
+
+    pub(super) fn execute_xgroup_setid(
+        &mut self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+    ) -> RespValue {
+        let stream = match self.stream_mut(key, RespValue::err(XGROUP_NO_KEY)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let id = id.unwrap_or_else(|| stream.last_id());
+        match stream.group_mut(group) {
+            Some(g) => {
+                g.set_last_delivered(id);
+                RespValue::ok()
+            }
+            None => RespValue::err(format!(
+                "NOGROUP No such consumer group '{}' for key name '{}'",
+                group, key
+            )),
+        }
+    }
+
+    pub(super) fn execute_xgroup_destroy(&mut self, key: &str, group: &str) -> RespValue {
+        match self.stream_mut(key, RespValue::err(XGROUP_NO_KEY)) {
+            Ok(stream) => RespValue::Integer(stream.destroy_group(group) as i64),
+            Err(e) => e,
+        }
+    }
+
+    /// XGROUP CREATECONSUMER (`create`) and DELCONSUMER
+    pub(super) fn execute_xgroup_consumer(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        create: bool,
+    ) -> RespValue {
+        let now_ms = self.stream_now_ms();
+        let stream = match self.stream_mut(key, RespValue::err(XGROUP_NO_KEY)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let Some(g) = stream.group_mut(group) else {
+            return RespValue::err(format!(
+                "NOGROUP No such consumer group '{}' for key name '{}'",
+                group, key
+            ));
+        };
+        if create {
+            RespValue::Integer(g.touch_consumer(consumer, now_ms) as i64)
+        } else {
+            RespValue::Integer(g.delete_consumer(consumer).unwrap_or(0) as i64)
+        }
+    }
+
+    /// XREADGROUP. `>` reads are omitted for streams with nothing new (nil
+    /// when no stream has anything); history reads always report the stream.
+    pub(super) fn execute_xreadgroup(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        count: Option<usize>,
+        noack: bool,
+        keys: &[String],
+        ids: &[Option<StreamId>],
+    ) -> RespValue {
+        debug_assert_eq!(keys.len(), ids.len(), "Precondition: one ID per XREADGROUP key");
+
+        let now_ms = self.stream_now_ms();
+        let mut results = Vec::new();
+        for (key, id) in keys.iter().zip(ids) {
+            let missing = RespValue::err(format!(
+                "NOGROUP No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                key, group
+            ));
+            let stream = match self.stream_mut(key, missing.clone()) {
+                Ok(s) => s,
+                Err(e) => return e,
+            };
+            let Some(delivered) = stream.read_group(group, consumer, *id, count, noack, now_ms)
+            else {
+                return missing;
+            };
+            if id.is_none() && delivered.is_empty() {
+                continue;
+            }
+            let entries = delivered
+                .into_iter()
+                .map(|(id, fields)| match fields {
+                    Some(fields) => entry_reply(id, fields),
+                    None => RespValue::Array(Some(vec![bulk_id(id), RespValue::Array(None)])),
+                })
+                .collect();
+            results.push(RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(key.as_bytes().to_vec())),
+                RespValue::Array(Some(entries)),
+            ])));
+        }
+
+        if results.is_empty() {
+            RespValue::Array(None)
+        } else {
+            RespValue::Array(Some(results))
+        }
+    }
+
+    pub(super) fn execute_xack(&mut self, key: &str, group: &str, ids: &[StreamId]) -> RespValue {
+        let stream = match self.stream_mut(key, RespValue::Integer(0)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        match stream.group_mut(group) {
+            Some(g) => RespValue::Integer(ids.iter().filter(|id| g.ack(**id)).count() as i64),
+            None => RespValue::Integer(0),
+        }
+    }
+
+    /// XPENDING summary (`[count, min, max, [[consumer, count], ...]]`) or,
+    /// with a range, `[[id, consumer, idle-ms, deliveries], ...]`
+    pub(super) fn execute_xpending(
+        &mut self,
+        key: &str,
+        group: &str,
+        range: Option<&PendingRange>,
+    ) -> RespValue {
+        let now_ms = self.stream_now_ms();
+        let stream = match self.stream_mut(key, no_group(key, group)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let Some(g) = stream.groups().get(group) else {
+            return no_group(key, group);
+        };
+        let pending = g.pending();
+
+        let Some(range) = range else {
+            let (Some((min, _)), Some((max, _))) =
+                (pending.iter().next(), pending.iter().next_back())
+            else {
+                return RespValue::Array(Some(vec![
+                    RespValue::Integer(0),
+                    RespValue::BulkString(None),
+                    RespValue::BulkString(None),
+                    RespValue::Array(None),
+                ]));
+            };
+            let consumers = g
+                .consumers()
+                .iter()
+                .filter(|(_, c)| c.pending_count() > 0)
+                .map(|(name, c)| {
+                    RespValue::Array(Some(vec![
+                        RespValue::BulkString(Some(name.as_bytes().to_vec())),
+                        RespValue::BulkString(Some(c.pending_count().to_string().into_bytes())),
+                    ]))
+                })
+                .collect();
+            return RespValue::Array(Some(vec![
+                RespValue::Integer(pending.len() as i64),
+                bulk_id(*min),
+                bulk_id(*max),
+                RespValue::Array(Some(consumers)),
+            ]));
+        };
+
+        if range.start > range.end {
+            return RespValue::Array(Some(Vec::new()));
+        }
+        let rows = pending
+            .range(range.start..=range.end)
+            .filter(|(_, e)| range.consumer.as_ref().is_none_or(|c| *c == e.consumer))
+            .map(|(id, e)| (id, e, now_ms.saturating_sub(e.delivery_ms)))
+            .filter(|(_, _, idle)| range.min_idle_ms.is_none_or(|min| *idle >= min))
+            .take(range.count)
+            .map(|(id, e, idle)| {
+                RespValue::Array(Some(vec![
+                    bulk_id(*id),
+                    RespValue::BulkString(Some(e.consumer.as_bytes().to_vec())),
+                    RespValue::Integer(idle as i64),
+                    RespValue::Integer(e.delivery_count as i64),
+                ]))
+            })
+            .collect();
+        RespValue::Array(Some(rows))
+    }
+
+    pub(super) fn execute_xclaim(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        ids: &[StreamId],
+        opts: ClaimOptions,
+    ) -> RespValue {
+        let now_ms = self.stream_now_ms();
+        let stream = match self.stream_mut(key, no_group(key, group)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        match stream.claim(group, consumer, min_idle_ms, ids, opts, now_ms) {
+            Some(claimed) if opts.justid => RespValue::Array(Some(
+                claimed.into_iter().map(|(id, _)| bulk_id(id)).collect(),
+            )),
+            Some(claimed) => entries_reply(claimed),
+            None => no_group(key, group),
+        }
+    }
+
+    /// XAUTOCLAIM reply: `[next-cursor, [entry | id, ...], [deleted-id, ...]]`
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn execute_xautoclaim(
+        &mut self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        start: StreamId,
+        count: usize,
+        justid: bool,
+    ) -> RespValue {
+        let now_ms = self.stream_now_ms();
+        let stream = match self.stream_mut(key, no_group(key, group)) {
+            Ok(s) => s,
+            Err(e) => return e,
+        };
+        let Some((next, claimed, deleted)) =
+            stream.autoclaim(group, consumer, min_idle_ms, start, count, justid, now_ms)
+        else {
+            return no_group(key, group);
+        };
+        let claimed = if justid {
+            RespValue::Array(Some(claimed.into_iter().map(|(id, _)| bulk_id(id)).collect()))
+        } else {
+            entries_reply(claimed)
+        };
+        RespValue::Array(Some(vec![
+            bulk_id(next),
+            claimed,
+            RespValue::Array(Some(deleted.into_iter().map(bulk_id).collect())),
+        ]))
+    }
+}
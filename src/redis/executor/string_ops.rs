@@ -1,9 +1,11 @@
 //! String command implementations for CommandExecutor.
 //!
 //! Handles: GET, SET, APPEND, GETSET, STRLEN, MGET, MSET, BATCHSET, BATCHGET,
-//! INCR, DECR, INCRBY, DECRBY
+//! INCR, DECR, INCRBY, DECRBY, BITFIELD, BITFIELD_RO
 
+use super::bitmap_ops::MAX_BIT_OFFSET;
 use super::CommandExecutor;
+use crate::redis::command::{BitFieldOp, BitFieldOverflow, BitFieldType};
 use crate::redis::data::{Value, SDS};
 use crate::redis::resp::RespValue;
 
@@ -18,7 +20,7 @@ impl CommandExecutor {
         }
     }
 
-    pub(super) fn execute_setnx(&mut self, key: &str, value: &SDS) -> RespValue {
+    pub(crate) fn execute_setnx(&mut self, key: &str, value: &SDS) -> RespValue {
         // Check if key exists (not expired)
         let key_exists = !self.is_expired(key) && self.data.contains_key(key);
         if key_exists {
@@ -51,12 +53,15 @@ impl CommandExecutor {
     ) -> RespValue {
         // Validate expiration values
         if let Some(seconds) = ex {
-            if *seconds <= 0 || *seconds > i64::MAX / 1000 {
+            if *seconds <= 0
+                || *seconds > i64::MAX / 1000
+                || self.relative_expire_overflows(seconds * 1000)
+            {
                 return RespValue::err("ERR invalid expire time in 'set' command");
             }
         }
         if let Some(millis) = px {
-            if *millis <= 0 {
+            if *millis <= 0 || self.relative_expire_overflows(*millis) {
                 return RespValue::err("ERR invalid expire time in 'set' command");
             }
         }
@@ -335,7 +340,10 @@ impl CommandExecutor {
             Some(Value::String(s)) => {
                 let bytes = s.as_bytes();
                 let len = bytes.len() as isize;
-                if len == 0 {
+                // Redis checks an inverted negative range before clamping,
+                // so GETRANGE k -5 -10 on a short string is empty rather than
+                // the first byte
+                if len == 0 || (start < 0 && end < 0 && start > end) {
                     return RespValue::BulkString(Some(vec![]));
                 }
                 // Normalize negative indices
@@ -354,58 +362,134 @@ impl CommandExecutor {
             }
             None => RespValue::BulkString(Some(vec![])),
         };
-        debug_assert!(matches!(&result, RespValue::BulkString(Some(_)) | RespValue::BulkString(None)), "Postcondition: GETRANGE must return bulk string");
+        debug_assert!(
+            matches!(&result, RespValue::BulkString(Some(_)) | RespValue::Error(_)),
+            "Postcondition: GETRANGE must return a bulk string or WRONGTYPE"
+        );
         result
     }
 
     pub(super) fn execute_setrange(&mut self, key: &str, offset: usize, value: &SDS) -> RespValue {
         let val_bytes = value.as_bytes();
+        // Redis order: type check, then an empty value is a no-op that
+        // reports the current length (0 for a missing key, which is NOT
+        // created), and only then the 512MB size limit
+        let current_len = match self.get_value(key) {
+            Some(Value::String(s)) => Some(s.len()),
+            Some(_) => {
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
+            }
+            None => None,
+        };
+        if val_bytes.is_empty() {
+            return RespValue::Integer(current_len.unwrap_or(0) as i64);
+        }
         // Checked arithmetic: offset + value length could overflow usize
         let needed = match offset.checked_add(val_bytes.len()) {
-            Some(n) => n,
-            None => return RespValue::err("ERR string exceeds maximum allowed size"),
+            Some(n) if n <= 512 * 1024 * 1024 => n,
+            _ => return RespValue::err("ERR string exceeds maximum allowed size"),
+        };
+
+        let mut bytes = match self.get_value(key) {
+            Some(Value::String(s)) => s.as_bytes().to_vec(),
+            _ => Vec::new(),
         };
-        // Redis limits strings to 512MB
-        if needed > 512 * 1024 * 1024 {
-            return RespValue::err("ERR string exceeds maximum allowed size");
+        if needed > bytes.len() {
+            // Gaps are padded with NUL bytes
+            bytes.resize(needed, 0);
         }
-        match self.get_value_mut(key) {
-            Some(Value::String(s)) => {
-                let mut bytes = s.as_bytes().to_vec();
-                if needed > bytes.len() {
-                    bytes.resize(needed, 0);
+        bytes[offset..needed].copy_from_slice(val_bytes);
+        let new_len = bytes.len() as i64;
+        self.data.insert(key.to_string(), Value::String(SDS::new(bytes)));
+        debug_assert!(
+            new_len as usize >= current_len.unwrap_or(0).max(needed),
+            "Postcondition: SETRANGE never shrinks the string"
+        );
+        RespValue::Integer(new_len)
+    }
+
+    /// BITFIELD / BITFIELD_RO. Sub-operations run in order against one
+    /// copy of the string; SET and INCRBY zero-pad it to cover their field
+    /// (even when OVERFLOW FAIL then rejects the write), and the result is
+    /// stored back only if some sub-operation could write.
+    pub(super) fn execute_bitfield(&mut self, key: &str, ops: &[BitFieldOp]) -> RespValue {
+        for op in ops {
+            if let BitFieldOp::Get { offset, .. }
+            | BitFieldOp::Set { offset, .. }
+            | BitFieldOp::IncrBy { offset, .. } = op
+            {
+                if *offset >= MAX_BIT_OFFSET {
+                    return RespValue::err("ERR bit offset is not an integer or out of range");
                 }
-                bytes[offset..needed].copy_from_slice(val_bytes);
-                let new_len = bytes.len() as i64;
-                self.data.insert(key.to_string(), Value::String(SDS::new(bytes)));
-                #[cfg(debug_assertions)]
-                debug_assert!(
-                    self.data.contains_key(key),
-                    "Postcondition: SETRANGE must store result"
-                );
-                RespValue::Integer(new_len)
             }
+        }
+
+        let mut bytes = match self.get_value(key) {
+            Some(Value::String(s)) => s.as_bytes().to_vec(),
             Some(_) => {
-                RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+                return RespValue::err(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value",
+                )
             }
-            None => {
-                // Redis: SETRANGE on non-existing key with empty value returns 0
-                // and does NOT create the key
-                if needed == 0 {
-                    return RespValue::Integer(0);
+            None => Vec::new(),
+        };
+
+        let mut overflow = BitFieldOverflow::Wrap;
+        let mut writes = false;
+        let mut replies = Vec::with_capacity(ops.len());
+        for op in ops {
+            let (ty, offset, result) = match *op {
+                BitFieldOp::Overflow(policy) => {
+                    overflow = policy;
+                    continue;
                 }
-                let mut bytes = vec![0u8; needed];
-                bytes[offset..needed].copy_from_slice(val_bytes);
-                let new_len = bytes.len() as i64;
-                self.data.insert(key.to_string(), Value::String(SDS::new(bytes)));
-                #[cfg(debug_assertions)]
-                debug_assert!(
-                    self.data.contains_key(key),
-                    "Postcondition: SETRANGE must store result"
-                );
-                RespValue::Integer(new_len)
+                BitFieldOp::Get { ty, offset } => {
+                    replies.push(RespValue::Integer(bitfield_read(&bytes, ty, offset)));
+                    continue;
+                }
+                BitFieldOp::Set { ty, offset, value } => {
+                    // Unsigned fields take the argument as its 64-bit pattern,
+                    // so SET u8 0 -1 overflows like Redis rather than clamping to 0
+                    let value = if ty.signed { value as i128 } else { value as u64 as i128 };
+                    (ty, offset, value)
+                }
+                BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment,
+                } => (ty, offset, bitfield_read(&bytes, ty, offset) as i128 + increment as i128),
+            };
+
+            writes = true;
+            let needed = ((offset + ty.bits as u64).div_ceil(8)) as usize;
+            if bytes.len() < needed {
+                bytes.resize(needed, 0);
+            }
+
+            let old = bitfield_read(&bytes, ty, offset);
+            match bitfield_overflow(ty, result, overflow) {
+                Some(value) => {
+                    bitfield_write(&mut bytes, ty, offset, value);
+                    debug_assert_eq!(
+                        bitfield_read(&bytes, ty, offset),
+                        value,
+                        "Postcondition: BITFIELD field must read back the written value"
+                    );
+                    // SET replies with the old value, INCRBY with the new one
+                    let reply = if matches!(op, BitFieldOp::Set { .. }) { old } else { value };
+                    replies.push(RespValue::Integer(reply));
+                }
+                None => replies.push(RespValue::BulkString(None)),
             }
         }
+
+        if writes {
+            self.data
+                .insert(key.to_string(), Value::String(SDS::new(bytes)));
+        }
+        RespValue::Array(Some(replies))
     }
 
     pub(super) fn execute_getex(
@@ -430,14 +514,17 @@ impl CommandExecutor {
 
         // Key exists as string — apply expiry changes
         if let Some(seconds) = ex {
-            if *seconds <= 0 || *seconds > i64::MAX / 1000 {
+            if *seconds <= 0
+                || *seconds > i64::MAX / 1000
+                || self.relative_expire_overflows(seconds * 1000)
+            {
                 return RespValue::err("ERR invalid expire time in 'getex' command");
             }
             let expiration =
                 self.current_time + crate::simulator::Duration::from_secs(*seconds as u64);
             self.expirations.insert(key.to_string(), expiration);
         } else if let Some(millis) = px {
-            if *millis <= 0 {
+            if *millis <= 0 || self.relative_expire_overflows(*millis) {
                 return RespValue::err("ERR invalid expire time in 'getex' command");
             }
             let expiration =
@@ -546,9 +633,9 @@ impl CommandExecutor {
 
         let response = match self.get_value_mut(key) {
             Some(Value::String(s)) => {
-                let current = match s.to_string().parse::<i64>() {
-                    Ok(n) => n,
-                    Err(_) => return RespValue::err("ERR value is not an integer or out of range"),
+                let current = match parse_integer_value(s.as_bytes()) {
+                    Some(n) => n,
+                    None => return RespValue::err("ERR value is not an integer or out of range"),
                 };
                 let new_value = match current.checked_add(increment) {
                     Some(n) => n,
@@ -586,29 +673,88 @@ impl CommandExecutor {
     }
 }
 
-/// Format a float value the way Redis does:
-/// - 17 significant digits
-/// - Trailing zeros kept (Redis uses `%.17Lg` C format)
-fn format_float(value: f64) -> String {
-    // Redis uses %.17Lg which prints 17 significant digits, removing trailing zeros
-    // Handle negative zero → positive zero
-    let value = if value == 0.0 { 0.0 } else { value };
+/// Parse a string value as an integer the way Redis' `string2ll` does:
+/// optional `-`, no `+`, no leading zeros, no whitespace. Values like
+/// "007" (e.g. left behind by SETRANGE) are strings, not integers.
+pub(super) fn parse_integer_value(bytes: &[u8]) -> Option<i64> {
+    let digits = bytes.strip_prefix(b"-").unwrap_or(bytes);
+    match digits {
+        [b'0'] if digits.len() == bytes.len() => return Some(0),
+        [b'1'..=b'9', rest @ ..] if rest.iter().all(u8::is_ascii_digit) => {}
+        _ => return None,
+    }
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
 
-    // Format with 17 significant digits
-    let s = format!("{:.17e}", value);
-    // Parse to get the clean representation
-    let reparsed: f64 = s.parse().unwrap_or(value);
+/// Read a BITFIELD integer; bits past the end of the string read as zero
+fn bitfield_read(bytes: &[u8], ty: BitFieldType, offset: u64) -> i64 {
+    let mut raw: u64 = 0;
+    for pos in offset..offset + ty.bits as u64 {
+        let bit = bytes
+            .get((pos / 8) as usize)
+            .is_some_and(|b| b & (0x80 >> (pos % 8)) != 0);
+        raw = (raw << 1) | bit as u64;
+    }
+    if ty.signed && ty.bits < 64 && raw >> (ty.bits - 1) == 1 {
+        // Sign-extend
+        (raw | (u64::MAX << ty.bits)) as i64
+    } else {
+        raw as i64
+    }
+}
 
-    // Use ryu or manual: format with enough precision then trim
-    // Redis %.17Lg: up to 17 significant digits, no trailing zeros
-    let formatted = format!("{:.17}", reparsed);
+/// Write the low `ty.bits` bits of `value`; `bytes` must cover the field
+fn bitfield_write(bytes: &mut [u8], ty: BitFieldType, offset: u64, value: i64) {
+    debug_assert!(
+        bytes.len() as u64 * 8 >= offset + ty.bits as u64,
+        "Precondition: string must cover the field"
+    );
+    let raw = value as u64;
+    for (i, pos) in (offset..offset + ty.bits as u64).enumerate() {
+        let mask = 0x80u8 >> (pos % 8);
+        let byte = &mut bytes[(pos / 8) as usize];
+        if raw >> (ty.bits as usize - 1 - i) & 1 == 1 {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+}
 
-    // Trim trailing zeros after decimal point (like %g)
-    if formatted.contains('.') {
-        let trimmed = formatted.trim_end_matches('0');
-        let trimmed = trimmed.trim_end_matches('.');
-        trimmed.to_string()
+/// Value to store for `result` under `overflow`, or `None` when FAIL
+/// rejects it
+fn bitfield_overflow(ty: BitFieldType, result: i128, overflow: BitFieldOverflow) -> Option<i64> {
+    let (min, max) = if ty.signed {
+        (-(1i128 << (ty.bits - 1)), (1i128 << (ty.bits - 1)) - 1)
     } else {
-        formatted
+        (0, (1i128 << ty.bits) - 1)
+    };
+    if (min..=max).contains(&result) {
+        return Some(result as i64);
+    }
+    match overflow {
+        BitFieldOverflow::Fail => None,
+        BitFieldOverflow::Sat => Some(if result > max { max } else { min } as i64),
+        BitFieldOverflow::Wrap => {
+            let modulus = 1i128 << ty.bits;
+            let wrapped = result.rem_euclid(modulus);
+            Some(if wrapped > max { wrapped - modulus } else { wrapped } as i64)
+        }
     }
 }
+
+/// Format a float value the way Redis does for INCRBYFLOAT/HINCRBYFLOAT:
+/// plain decimal notation, no exponent, no trailing zeros. Redis computes
+/// in long double and prints 17 significant digits, which hides the binary
+/// error in sums like 10.5 + 0.1; the shortest representation that
+/// round-trips is the f64 equivalent.
+pub(super) fn format_float(value: f64) -> String {
+    // Negative zero prints as 0
+    let value = if value == 0.0 { 0.0 } else { value };
+    let formatted = value.to_string();
+    debug_assert!(
+        formatted.parse::<f64>() == Ok(value),
+        "Postcondition: formatted float must round-trip"
+    );
+    formatted
+}
@@ -1,12 +1,19 @@
 //! Transaction command implementations for CommandExecutor.
 //!
-//! Handles: MULTI, EXEC, DISCARD, WATCH, UNWATCH
+//! Handles: MULTI, EXEC, DISCARD, WATCH, UNWATCH, RESET
+//!
+//! The state lives in `self.conn` (see `connection_state.rs`).
+//!
+//! Errors follow Redis: a command refused while queueing (unknown, or one
+//! that failed to parse, see `reject`) dooms the transaction, and EXEC
+//! answers EXECABORT without running anything. Errors raised while EXEC
+//! runs are replies in its array; the commands after them still run.
 //!
 //! # TigerStyle Invariants
 //!
 //! - `in_transaction` and `queued_commands` are always in sync:
 //!   - If `in_transaction == false`, then `queued_commands.is_empty()`
-//! - `watched_keys` is cleared when transaction ends (EXEC/DISCARD)
+//! - `watched_keys` is cleared when transaction ends (EXEC/DISCARD/RESET)
 
 use super::CommandExecutor;
 use crate::redis::resp::RespValue;
@@ -15,20 +22,19 @@ impl CommandExecutor {
     pub(super) fn execute_multi(&mut self) -> RespValue {
         // TigerStyle: Precondition - not already in transaction
         // (This is enforced by returning an error, which is correct Redis behavior)
-        if self.in_transaction {
+        if self.conn.in_transaction {
             return RespValue::err("ERR MULTI calls can not be nested");
         }
 
-        self.in_transaction = true;
-        self.queued_commands.clear();
+        self.conn.begin_transaction();
 
         // TigerStyle: Postconditions
         debug_assert!(
-            self.in_transaction,
+            self.conn.in_transaction,
             "Postcondition violated: in_transaction must be true after MULTI"
         );
         debug_assert!(
-            self.queued_commands.is_empty(),
+            self.conn.queued_commands.is_empty(),
             "Postcondition violated: queued_commands must be empty after MULTI"
         );
 
@@ -37,36 +43,39 @@ impl CommandExecutor {
 
     pub(super) fn execute_exec(&mut self) -> RespValue {
         // TigerStyle: Precondition - must be in transaction
-        if !self.in_transaction {
+        if !self.conn.in_transaction {
             return RespValue::err("ERR EXEC without MULTI");
         }
 
+        if self.conn.transaction_errors {
+            self.conn.end_transaction();
+            return RespValue::err("EXECABORT Transaction discarded because of previous errors.");
+        }
+
         // TigerStyle: Capture pre-state for postcondition verification
         #[cfg(debug_assertions)]
-        let queued_count = self.queued_commands.len();
+        let queued_count = self.conn.queued_commands.len();
 
         // Check if any watched keys have changed
-        let watch_violated = self.watched_keys.iter().any(|(key, original_value)| {
+        let watch_violated = self.conn.watched_keys.iter().any(|(key, original_value)| {
             let current_value = self.data.get(key).cloned();
             &current_value != original_value
         });
 
         // Clear transaction state
-        self.in_transaction = false;
-        let commands = std::mem::take(&mut self.queued_commands);
-        self.watched_keys.clear();
+        let commands = self.conn.end_transaction();
 
         // TigerStyle: Postconditions - transaction state must be reset
         debug_assert!(
-            !self.in_transaction,
+            !self.conn.in_transaction,
             "Postcondition violated: in_transaction must be false after EXEC"
         );
         debug_assert!(
-            self.queued_commands.is_empty(),
+            self.conn.queued_commands.is_empty(),
             "Postcondition violated: queued_commands must be empty after EXEC"
         );
         debug_assert!(
-            self.watched_keys.is_empty(),
+            self.conn.watched_keys.is_empty(),
             "Postcondition violated: watched_keys must be empty after EXEC"
         );
 
@@ -89,27 +98,44 @@ impl CommandExecutor {
         RespValue::Array(Some(results))
     }
 
+    /// Reply to a request that failed to parse (wrong arity, bad arguments,
+    /// not a command). Inside MULTI it also dooms the transaction.
+    pub fn reject(&mut self, error: impl Into<String>) -> RespValue {
+        let error = error.into();
+        if self.conn.in_transaction {
+            self.conn.transaction_errors = true;
+        }
+
+        let has_code = error
+            .split(' ')
+            .next()
+            .is_some_and(|code| !code.is_empty() && code.bytes().all(|b| b.is_ascii_uppercase()));
+        if has_code {
+            RespValue::err(error)
+        } else {
+            RespValue::err(format!("ERR {}", error))
+        }
+    }
+
     pub(super) fn execute_discard(&mut self) -> RespValue {
         // TigerStyle: Precondition - must be in transaction
-        if !self.in_transaction {
+        if !self.conn.in_transaction {
             return RespValue::err("ERR DISCARD without MULTI");
         }
 
-        self.in_transaction = false;
-        self.queued_commands.clear();
-        self.watched_keys.clear();
+        self.conn.end_transaction();
 
         // TigerStyle: Postconditions - all transaction state must be reset
         debug_assert!(
-            !self.in_transaction,
+            !self.conn.in_transaction,
             "Postcondition violated: in_transaction must be false after DISCARD"
         );
         debug_assert!(
-            self.queued_commands.is_empty(),
+            self.conn.queued_commands.is_empty(),
             "Postcondition violated: queued_commands must be empty after DISCARD"
         );
         debug_assert!(
-            self.watched_keys.is_empty(),
+            self.conn.watched_keys.is_empty(),
             "Postcondition violated: watched_keys must be empty after DISCARD"
         );
 
@@ -118,18 +144,18 @@ impl CommandExecutor {
 
     pub(super) fn execute_watch(&mut self, keys: &[String]) -> RespValue {
         // TigerStyle: Precondition - cannot WATCH inside a transaction
-        if self.in_transaction {
+        if self.conn.in_transaction {
             return RespValue::err("ERR WATCH inside MULTI is not allowed");
         }
 
         // TigerStyle: Capture pre-state
         #[cfg(debug_assertions)]
-        let pre_watch_count = self.watched_keys.len();
+        let pre_watch_count = self.conn.watched_keys.len();
 
         // Store current values of watched keys
         for key in keys {
             let current_value = self.data.get(key).cloned();
-            self.watched_keys.insert(key.clone(), current_value);
+            self.conn.watch(key.clone(), current_value);
         }
 
         // TigerStyle: Postcondition - all requested keys must be watched
@@ -137,13 +163,13 @@ impl CommandExecutor {
         {
             for key in keys {
                 debug_assert!(
-                    self.watched_keys.contains_key(key),
+                    self.conn.is_watching(key),
                     "Postcondition violated: WATCH must add all requested keys"
                 );
             }
             // Watch count should have increased (unless keys were already watched)
             debug_assert!(
-                self.watched_keys.len() >= pre_watch_count,
+                self.conn.watched_keys.len() >= pre_watch_count,
                 "Postcondition violated: WATCH must not decrease watched key count"
             );
         }
@@ -152,14 +178,26 @@ impl CommandExecutor {
     }
 
     pub(super) fn execute_unwatch(&mut self) -> RespValue {
-        self.watched_keys.clear();
+        self.conn.watched_keys.clear();
 
         // TigerStyle: Postcondition - all watches must be cleared
         debug_assert!(
-            self.watched_keys.is_empty(),
+            self.conn.watched_keys.is_empty(),
             "Postcondition violated: watched_keys must be empty after UNWATCH"
         );
 
         RespValue::simple("OK")
     }
+
+    /// RESET: this executor's only per-client state is the transaction
+    pub(super) fn execute_reset(&mut self) -> RespValue {
+        self.conn.reset();
+
+        debug_assert!(
+            !self.conn.in_transaction && self.conn.watched_keys.is_empty(),
+            "Postcondition violated: RESET must leave no MULTI or WATCH state"
+        );
+
+        RespValue::simple("RESET")
+    }
 }
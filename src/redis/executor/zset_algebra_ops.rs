@@ -0,0 +1,172 @@
+//! Sorted set algebra for CommandExecutor.
+//!
+//! Handles: ZUNION, ZINTER, ZDIFF and their STORE forms. Like Redis, plain
+//! sets are accepted as inputs with every member scored 1. Weights multiply
+//! each input's scores before aggregation (a NaN product such as 0 * inf
+//! counts as 0, as does a NaN sum such as inf + -inf); ZDIFF keeps the
+//! first input's scores as they are.
+//!
+//! # TigerStyle Invariants
+//!
+//! - A STORE form replaces the destination, deleting it for an empty result
+//! - Replies list members by score, then member, like ZRANGE
+
+use super::CommandExecutor;
+use crate::redis::command::{ZAggregate, ZSetOperation};
+use crate::redis::data::{RedisSortedSet, Value, SDS};
+use crate::redis::resp::RespValue;
+use ahash::AHashMap;
+
+fn aggregate(how: ZAggregate, current: f64, score: f64) -> f64 {
+    match how {
+        ZAggregate::Sum => {
+            let sum = current + score;
+            if sum.is_nan() {
+                0.0
+            } else {
+                sum
+            }
+        }
+        ZAggregate::Min => current.min(score),
+        ZAggregate::Max => current.max(score),
+    }
+}
+
+impl CommandExecutor {
+    pub(super) fn execute_zset_op(
+        &mut self,
+        op: ZSetOperation,
+        dest: Option<&str>,
+        keys: &[String],
+        weights: Option<&[f64]>,
+        how: ZAggregate,
+        with_scores: bool,
+    ) -> RespValue {
+        debug_assert!(!keys.is_empty(), "Precondition: at least one input key");
+        debug_assert!(
+            weights.is_none_or(|w| w.len() == keys.len()),
+            "Precondition: one weight per input key"
+        );
+
+        // Read every input first, so a WRONGTYPE leaves the destination alone
+        let mut inputs: Vec<AHashMap<String, f64>> = Vec::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            let weight = match op {
+                ZSetOperation::Diff => 1.0,
+                _ => weights.map_or(1.0, |w| w[i]),
+            };
+            let weighted = |score: f64| {
+                let product = score * weight;
+                if product.is_nan() {
+                    0.0
+                } else {
+                    product
+                }
+            };
+            let input = match self.get_value(key) {
+                None => AHashMap::new(),
+                Some(Value::SortedSet(zs)) => zs
+                    .iter()
+                    .map(|(member, score)| (member.to_string(), weighted(score)))
+                    .collect(),
+                Some(Value::Set(set)) => set
+                    .members()
+                    .iter()
+                    .map(|member| {
+                        let member = String::from_utf8_lossy(member.as_bytes()).into_owned();
+                        (member, weighted(1.0))
+                    })
+                    .collect(),
+                Some(_) => {
+                    return RespValue::err(
+                        "WRONGTYPE Operation against a key holding the wrong kind of value",
+                    )
+                }
+            };
+            inputs.push(input);
+        }
+
+        let result: AHashMap<String, f64> = match op {
+            ZSetOperation::Union => {
+                let mut result = AHashMap::new();
+                for input in &inputs {
+                    for (member, &score) in input {
+                        result
+                            .entry(member.clone())
+                            .and_modify(|current| *current = aggregate(how, *current, score))
+                            .or_insert(score);
+                    }
+                }
+                result
+            }
+            ZSetOperation::Inter => {
+                // Walk the smallest input; aggregate in key order
+                let smallest = inputs
+                    .iter()
+                    .min_by_key(|input| input.len())
+                    .expect("at least one input");
+                smallest
+                    .keys()
+                    .filter_map(|member| {
+                        let mut scores = inputs.iter().map(|input| input.get(member).copied());
+                        let first = scores.next().flatten()?;
+                        scores
+                            .try_fold(first, |acc, score| Some(aggregate(how, acc, score?)))
+                            .map(|score| (member.clone(), score))
+                    })
+                    .collect()
+            }
+            ZSetOperation::Diff => inputs[0]
+                .iter()
+                .filter(|(member, _)| inputs[1..].iter().all(|other| !other.contains_key(*member)))
+                .map(|(member, &score)| (member.clone(), score))
+                .collect(),
+        };
+
+        let mut sorted: Vec<(String, f64)> = result.into_iter().collect();
+        sorted.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+        let Some(dest) = dest else {
+            let mut reply = Vec::with_capacity(sorted.len() * if with_scores { 2 } else { 1 });
+            for (member, score) in sorted {
+                reply.push(RespValue::BulkString(Some(member.into_bytes())));
+                if with_scores {
+                    reply.push(RespValue::BulkString(Some(score.to_string().into_bytes())));
+                }
+            }
+            return RespValue::Array(Some(reply));
+        };
+
+        self.store_zset_result(dest, sorted)
+    }
+
+    /// Replace `dest` with the given members (deleting it when there are
+    /// none), clearing any TTL, and reply with the member count. Shared by
+    /// the STORE forms and ZRANGESTORE.
+    pub(super) fn store_zset_result(
+        &mut self,
+        dest: &str,
+        members: Vec<(String, f64)>,
+    ) -> RespValue {
+        let len = members.len();
+        self.expirations.remove(dest);
+        if members.is_empty() {
+            self.data.remove(dest);
+        } else {
+            let mut zs = RedisSortedSet::new();
+            for (member, score) in members {
+                zs.add(SDS::from_str(&member), score);
+            }
+            self.data.insert(dest.to_string(), Value::SortedSet(zs));
+            self.blocking.signal(dest);
+        }
+
+        debug_assert!(
+            len == 0
+                || matches!(self.data.get(dest), Some(Value::SortedSet(zs)) if zs.len() == len),
+            "Postcondition: destination must hold the {} result members",
+            len
+        );
+        RespValue::Integer(len as i64)
+    }
+}
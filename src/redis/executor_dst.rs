@@ -25,13 +25,17 @@
 //! }
 //! ```
 
-use super::command::Command;
+use super::command::{
+    BitFieldOp, BitFieldOverflow, BitFieldType, BitOperation, BitRange, BitUnit, Command,
+    ZAggregate, ZRangeSpec, ZSetOperation,
+};
 use super::data::SDS;
-use super::executor::CommandExecutor;
+use super::declared_commands::DeclaredCommand;
+use super::executor::{CommandExecutor, EXPIRY_HORIZONS};
 use super::resp::RespValue;
 use crate::io::simulation::SimulatedRng;
 use crate::io::Rng;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 /// Configuration for Executor DST
 #[derive(Debug, Clone)]
@@ -55,6 +59,12 @@ pub struct ExecutorDSTConfig {
     pub weight_hash: u64,
     pub weight_sorted_set: u64,
     pub weight_expiry: u64,
+    pub weight_bitfield: u64,
+
+    /// Stop the clock 1ms either side of every key's expiry instead of only
+    /// jumping it forward at random, and check the key is live before its
+    /// deadline and gone after it
+    pub precise_expiry: bool,
 }
 
 impl Default for ExecutorDSTConfig {
@@ -72,6 +82,8 @@ impl Default for ExecutorDSTConfig {
             weight_hash: 15,
             weight_sorted_set: 10,
             weight_expiry: 10,
+            weight_bitfield: 5,
+            precise_expiry: false,
         }
     }
 }
@@ -123,6 +135,40 @@ impl ExecutorDSTConfig {
         }
     }
 
+    /// Expiry-heavy workload in precise-expiry mode: every EXPIRE and
+    /// PEXPIRE deadline is observed at the millisecond either side
+    pub fn precise_expiry(seed: u64) -> Self {
+        ExecutorDSTConfig {
+            seed,
+            weight_string: 25,
+            weight_key: 10,
+            weight_list: 10,
+            weight_set: 10,
+            weight_hash: 10,
+            weight_sorted_set: 5,
+            weight_expiry: 30,
+            precise_expiry: true,
+            ..Default::default()
+        }
+    }
+
+    /// BITFIELD-heavy workload: narrow fields, boundary values and all three
+    /// overflow policies
+    pub fn bitfield_heavy(seed: u64) -> Self {
+        ExecutorDSTConfig {
+            seed,
+            weight_string: 20,
+            weight_key: 5,
+            weight_list: 5,
+            weight_set: 5,
+            weight_hash: 5,
+            weight_sorted_set: 5,
+            weight_expiry: 5,
+            weight_bitfield: 50,
+            ..Default::default()
+        }
+    }
+
     fn total_weight(&self) -> u64 {
         self.weight_string
             + self.weight_key
@@ -131,6 +177,7 @@ impl ExecutorDSTConfig {
             + self.weight_hash
             + self.weight_sorted_set
             + self.weight_expiry
+            + self.weight_bitfield
     }
 }
 
@@ -144,6 +191,7 @@ pub enum ExecutorOp {
     Hash(String),
     SortedSet(String),
     Expiry(String),
+    BitField(String),
 }
 
 /// Result of an Executor DST run
@@ -158,6 +206,12 @@ pub struct ExecutorDSTResult {
     pub hash_ops: u64,
     pub sorted_set_ops: u64,
     pub expiry_ops: u64,
+    pub bitfield_ops: u64,
+    /// BITFIELD SET/INCRBY results outside their type, resolved by the
+    /// overflow policy
+    pub bitfield_overflows: u64,
+    /// Expiry deadlines observed from both sides (precise-expiry mode)
+    pub expiry_boundary_checks: u64,
     pub invariant_violations: Vec<String>,
     pub last_op: Option<ExecutorOp>,
 }
@@ -174,6 +228,9 @@ impl ExecutorDSTResult {
             hash_ops: 0,
             sorted_set_ops: 0,
             expiry_ops: 0,
+            bitfield_ops: 0,
+            bitfield_overflows: 0,
+            expiry_boundary_checks: 0,
             invariant_violations: Vec::new(),
             last_op: None,
         }
@@ -185,7 +242,7 @@ impl ExecutorDSTResult {
 
     pub fn summary(&self) -> String {
         format!(
-            "Seed {}: {} ops (str:{}, key:{}, list:{}, set:{}, hash:{}, zset:{}, exp:{}), {} violations",
+            "Seed {}: {} ops (str:{}, key:{}, list:{}, set:{}, hash:{}, zset:{}, exp:{}, bitfield:{}), {} violations",
             self.seed,
             self.total_operations,
             self.string_ops,
@@ -195,6 +252,7 @@ impl ExecutorDSTResult {
             self.hash_ops,
             self.sorted_set_ops,
             self.expiry_ops,
+            self.bitfield_ops,
             self.invariant_violations.len()
         )
     }
@@ -281,6 +339,124 @@ impl ShadowState {
     }
 }
 
+/// Integer value of a string under Redis' `string2ll` rules (no `+`, no
+/// leading zeros, no `-0`), written out digit by digit
+fn ref_parse_int(v: &[u8]) -> Option<i64> {
+    let (negative, digits) = match v.split_first() {
+        Some((b'-', rest)) => (true, rest),
+        _ => (false, v),
+    };
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    if digits[0] == b'0' && (digits.len() > 1 || negative) {
+        return None;
+    }
+    let mut n: i128 = 0;
+    for &d in digits {
+        n = n * 10 + (d - b'0') as i128;
+        if n > i64::MAX as i128 + 1 {
+            return None;
+        }
+    }
+    let n = if negative { -n } else { n };
+    i64::try_from(n).ok()
+}
+
+/// GETRANGE by Redis' rules: an inverted all-negative range is empty, then
+/// negative indices count from the end and both ends clamp to the string
+fn ref_getrange(v: &[u8], start: isize, end: isize) -> Vec<u8> {
+    let len = v.len() as i128;
+    let (mut start, mut end) = (start as i128, end as i128);
+    if start < 0 && end < 0 && start > end {
+        return Vec::new();
+    }
+    if start < 0 {
+        start = (len + start).max(0);
+    }
+    if end < 0 {
+        end = (len + end).max(0);
+    }
+    end = end.min(len - 1);
+    if len == 0 || start > end {
+        return Vec::new();
+    }
+    v[start as usize..=end as usize].to_vec()
+}
+
+/// Bit positions a BITCOUNT/BITPOS range covers, resolved one bit at a time
+fn ref_bit_span(len: usize, range: BitRange) -> impl Iterator<Item = usize> {
+    let total = match range.unit {
+        BitUnit::Byte => len as i64,
+        BitUnit::Bit => len as i64 * 8,
+    };
+    let resolve = |i: i64| if i < 0 { (total + i).max(0) } else { i };
+    let start = resolve(range.start);
+    let end = resolve(range.end.unwrap_or(-1)).min(total - 1);
+    let scale = match range.unit {
+        BitUnit::Byte => 8,
+        BitUnit::Bit => 1,
+    };
+    (0..len * 8).filter(move |&p| {
+        let index = (p / scale) as i64;
+        index >= start && index <= end
+    })
+}
+
+fn ref_bit(v: &[u8], pos: usize) -> bool {
+    v.get(pos / 8).is_some_and(|b| b & (0x80 >> (pos % 8)) != 0)
+}
+
+/// Smallest and largest value a BITFIELD type holds
+fn ref_bitfield_range(ty: BitFieldType) -> (i128, i128) {
+    if ty.signed {
+        (-(1i128 << (ty.bits - 1)), (1i128 << (ty.bits - 1)) - 1)
+    } else {
+        (0, (1i128 << ty.bits) - 1)
+    }
+}
+
+/// BITFIELD field value, assembled bit by bit; bits past the end read as 0
+fn ref_bitfield_get(v: &[u8], ty: BitFieldType, offset: u64) -> i128 {
+    let raw = (offset..offset + ty.bits as u64)
+        .fold(0i128, |acc, pos| (acc << 1) | ref_bit(v, pos as usize) as i128);
+    let (_, max) = ref_bitfield_range(ty);
+    if raw > max {
+        raw - (1i128 << ty.bits)
+    } else {
+        raw
+    }
+}
+
+/// Store `value`, already within the type's range, as a two's complement
+/// field; the string grows with zero bytes to cover it
+fn ref_bitfield_put(v: &mut Vec<u8>, ty: BitFieldType, offset: u64, value: i128) {
+    let raw = value.rem_euclid(1i128 << ty.bits);
+    for i in 0..ty.bits as u64 {
+        let pos = (offset + i) as usize;
+        let mask = 0x80u8 >> (pos % 8);
+        if raw >> (ty.bits as u64 - 1 - i) & 1 == 1 {
+            v[pos / 8] |= mask;
+        } else {
+            v[pos / 8] &= !mask;
+        }
+    }
+}
+
+/// The exact result of a SET or INCRBY under an overflow policy, or `None`
+/// when FAIL refuses it
+fn ref_bitfield_overflow(ty: BitFieldType, exact: i128, policy: BitFieldOverflow) -> Option<i128> {
+    let (min, max) = ref_bitfield_range(ty);
+    if (min..=max).contains(&exact) {
+        return Some(exact);
+    }
+    match policy {
+        BitFieldOverflow::Wrap => Some((exact - min).rem_euclid(max - min + 1) + min),
+        BitFieldOverflow::Sat => Some(exact.clamp(min, max)),
+        BitFieldOverflow::Fail => None,
+    }
+}
+
 // =============================================================================
 // DST Harness
 // =============================================================================
@@ -293,6 +469,11 @@ pub struct ExecutorDSTHarness {
     shadow: ShadowState,
     result: ExecutorDSTResult,
     current_time_ms: u64,
+    /// Millisecond timer wheel for precise-expiry mode: deadline -> keys
+    /// whose expiry was set to it. Entries go stale when a key is deleted,
+    /// persisted or given a new TTL; they are checked against the shadow
+    /// when they fire.
+    expiry_timers: BTreeMap<u64, BTreeSet<String>>,
     /// All keys that have been created via SCAN liveness check
     all_keys_ever: HashSet<String>,
 }
@@ -300,13 +481,16 @@ pub struct ExecutorDSTHarness {
 impl ExecutorDSTHarness {
     pub fn new(config: ExecutorDSTConfig) -> Self {
         let rng = SimulatedRng::new(config.seed);
+        let mut executor = CommandExecutor::new();
+        executor.set_rng_seed(config.seed);
         ExecutorDSTHarness {
             result: ExecutorDSTResult::new(config.seed),
             config,
             rng,
-            executor: CommandExecutor::new(),
+            executor,
             shadow: ShadowState::new(),
             current_time_ms: 1_000_000, // Start at 1 second to allow expiry math
+            expiry_timers: BTreeMap::new(),
             all_keys_ever: HashSet::new(),
         }
     }
@@ -428,6 +612,11 @@ impl ExecutorDSTHarness {
             self.run_sorted_set_op();
             return;
         }
+        threshold += self.config.weight_bitfield;
+        if roll < threshold {
+            self.run_bitfield_op();
+            return;
+        }
         // Remaining = expiry
         self.run_expiry_op();
     }
@@ -491,7 +680,7 @@ impl ExecutorDSTHarness {
             self.assert_ok(&set_resp, "CONFIG SET should return OK");
 
             // Verify with CONFIG GET
-            let get_resp = self.executor.execute(&Command::ConfigGet(param.clone()));
+            let get_resp = self.executor.execute(&Command::ConfigGet(vec![param.clone()]));
             if let RespValue::Array(Some(elements)) = &get_resp {
                 if elements.len() != 2 {
                     self.violation(&format!(
@@ -514,7 +703,7 @@ impl ExecutorDSTHarness {
             let desc = "CONFIG GET *max*".to_string();
             self.result.last_op = Some(ExecutorOp::Key(desc));
 
-            let resp = self.executor.execute(&Command::ConfigGet("*max*".to_string()));
+            let resp = self.executor.execute(&Command::ConfigGet(vec!["*max*".to_string()]));
             if let RespValue::Array(Some(elements)) = &resp {
                 if elements.len() % 2 != 0 {
                     self.violation(&format!(
@@ -617,7 +806,7 @@ impl ExecutorDSTHarness {
                     .rev()
                     .collect()
             };
-            let mget_resp = self.executor.execute(&Command::MGet(unique_keys.clone()));
+            let mget_resp = self.execute_routed(&Command::MGet(unique_keys.clone()));
             if let RespValue::Array(Some(values)) = &mget_resp {
                 for (i, key) in unique_keys.iter().enumerate() {
                     if i < values.len() {
@@ -647,7 +836,7 @@ impl ExecutorDSTHarness {
             }
             let expect = match self.shadow.get(&key) {
                 Some(RefValue::String(v)) => {
-                    match std::str::from_utf8(v).ok().and_then(|s| s.parse::<i64>().ok()) {
+                    match ref_parse_int(v) {
                         Some(n) => IncrExpect::Value(n),
                         None => IncrExpect::NotInteger,
                     }
@@ -658,7 +847,7 @@ impl ExecutorDSTHarness {
 
             match expect {
                 IncrExpect::Value(current) => {
-                    let resp = self.executor.execute(&Command::Incr(key.clone()));
+                    let resp = self.execute_routed(&Command::Incr(key.clone()));
                     let expected = current + 1;
                     // Invariant 3: INCR produces correct arithmetic
                     self.assert_integer(&resp, expected, &format!("INCR {} should be {}", key, expected));
@@ -666,12 +855,12 @@ impl ExecutorDSTHarness {
                 }
                 IncrExpect::NotInteger => {
                     // Key holds non-integer string - expect ERR not WRONGTYPE
-                    let resp = self.executor.execute(&Command::Incr(key.clone()));
+                    let resp = self.execute_routed(&Command::Incr(key.clone()));
                     self.assert_error_contains(&resp, "ERR", "INCR on non-integer string");
                 }
                 IncrExpect::WrongType => {
                     // Key holds wrong data type (list, set, etc.)
-                    let resp = self.executor.execute(&Command::Incr(key.clone()));
+                    let resp = self.execute_routed(&Command::Incr(key.clone()));
                     self.assert_error_contains(&resp, "WRONGTYPE", "INCR on wrong type");
                 }
             }
@@ -681,7 +870,7 @@ impl ExecutorDSTHarness {
             let desc = format!("GET {}", key);
             self.result.last_op = Some(ExecutorOp::String(desc));
 
-            let resp = self.executor.execute(&Command::Get(key.clone()));
+            let resp = self.execute_routed(&Command::Get(key.clone()));
             enum GetExpect {
                 Value(Vec<u8>),
                 Null,
@@ -761,90 +950,27 @@ impl ExecutorDSTHarness {
                 }
             }
         } else if sub < 63 {
-            // GETRANGE - only on string-or-none keys to avoid executor debug_assert on WRONGTYPE
+            // GETRANGE: indices well past either end, inverted ranges, and
+            // non-string keys
             let key = self.random_key();
-            let is_string_or_none = matches!(
-                self.shadow.get(&key),
-                Some(RefValue::String(_)) | None
-            );
-
-            if is_string_or_none {
-                // Random start/end indices including negative values
-                let start = (self.rng.gen_range(0, 20) as isize) - 10;
-                let end = (self.rng.gen_range(0, 20) as isize) - 5;
-                let desc = format!("GETRANGE {} {} {}", key, start, end);
-                self.result.last_op = Some(ExecutorOp::String(desc));
-
-                let resp = self.executor.execute(&Command::GetRange(key.clone(), start, end));
-
-                let expected = match self.shadow.get(&key) {
-                    Some(RefValue::String(v)) => {
-                        let bytes = v.as_slice();
-                        let len = bytes.len() as isize;
-                        if len == 0 {
-                            vec![]
-                        } else {
-                            let s_idx = if start < 0 { (len + start).max(0) } else { start.min(len) };
-                            let e_idx = if end < 0 { (len + end).max(0) } else { end.min(len - 1) };
-                            if s_idx > e_idx || s_idx >= len {
-                                vec![]
-                            } else {
-                                let s_idx = s_idx.max(0);
-                                let e_idx = e_idx.min(len - 1);
-                                bytes[s_idx as usize..=e_idx as usize].to_vec()
-                            }
-                        }
-                    }
-                    None => vec![],
-                    _ => unreachable!(),
-                };
-                self.assert_bulk_eq(
-                    &resp,
-                    &expected,
-                    &format!("GETRANGE {} {} {}", key, start, end),
-                );
-            }
-        } else if sub < 69 {
-            // SETRANGE
-            let key = self.random_key();
-            // Use small offsets to avoid enormous strings
-            let offset = self.rng.gen_range(0, 20) as usize;
-            let value = self.random_value();
-            let desc = format!("SETRANGE {} {} <value>", key, offset);
+            let start = self.random_range_index();
+            let end = self.random_range_index();
+            let desc = format!("GETRANGE {} {} {}", key, start, end);
             self.result.last_op = Some(ExecutorOp::String(desc));
 
-            let is_string_or_none = matches!(
-                self.shadow.get(&key),
-                Some(RefValue::String(_)) | None
-            );
-
-            if is_string_or_none {
-                let resp = self.executor.execute(&Command::SetRange(
-                    key.clone(),
-                    offset,
-                    SDS::new(value.clone()),
-                ));
+            let resp = self.executor.execute(&Command::GetRange(key.clone(), start, end));
 
-                // Shadow: pad with zeros if needed, then overwrite at offset
-                let mut bytes = match self.shadow.get(&key) {
-                    Some(RefValue::String(existing)) => existing.clone(),
-                    None => vec![],
-                    _ => unreachable!(),
-                };
-                let needed = offset + value.len();
-                if needed > bytes.len() {
-                    bytes.resize(needed, 0);
+            match self.shadow.get(&key) {
+                Some(RefValue::String(v)) => {
+                    let expected = ref_getrange(v, start, end);
+                    let context = format!("GETRANGE {} {} {}", key, start, end);
+                    self.assert_bulk_eq(&resp, &expected, &context);
                 }
-                bytes[offset..needed].copy_from_slice(&value);
-                let expected_len = bytes.len() as i64;
-                self.shadow.set_string(&key, bytes);
-
-                self.assert_integer(
-                    &resp,
-                    expected_len,
-                    &format!("SETRANGE {} should return new length {}", key, expected_len),
-                );
+                None => self.assert_bulk_eq(&resp, b"", "GETRANGE on missing key"),
+                Some(_) => self.assert_error_contains(&resp, "WRONGTYPE", "GETRANGE on wrong type"),
             }
+        } else if sub < 69 {
+            self.run_setrange_op();
         } else if sub < 75 {
             // GETDEL
             let key = self.random_key();
@@ -978,7 +1104,7 @@ impl ExecutorDSTHarness {
             }
             let expect = match self.shadow.get(&key) {
                 Some(RefValue::String(v)) => {
-                    match std::str::from_utf8(v).ok().and_then(|s| s.parse::<i64>().ok()) {
+                    match ref_parse_int(v) {
                         Some(n) => DecrByExpect::Value(n),
                         None => DecrByExpect::NotInteger,
                     }
@@ -1022,7 +1148,7 @@ impl ExecutorDSTHarness {
             }
             let expect = match self.shadow.get(&key) {
                 Some(RefValue::String(v)) => {
-                    match std::str::from_utf8(v).ok().and_then(|s| s.parse::<i64>().ok()) {
+                    match ref_parse_int(v) {
                         Some(n) => IncrByExpect::Value(n),
                         None => IncrByExpect::NotInteger,
                     }
@@ -1065,7 +1191,7 @@ impl ExecutorDSTHarness {
             }
             let expect = match self.shadow.get(&key) {
                 Some(RefValue::String(v)) => {
-                    match std::str::from_utf8(v).ok().and_then(|s| s.parse::<i64>().ok()) {
+                    match ref_parse_int(v) {
                         Some(n) => DecrExpect::Value(n),
                         None => DecrExpect::NotInteger,
                     }
@@ -1102,7 +1228,7 @@ impl ExecutorDSTHarness {
             let offset: u64 = self.rng.gen_range(0, 2048);
             let bit_sub = self.rng.gen_range(0, 100);
 
-            if bit_sub < 60 {
+            if bit_sub < 45 {
                 // SETBIT
                 let value = self.rng.gen_range(0, 2) as u8;
                 let desc = format!("SETBIT {} {} {}", key, offset, value);
@@ -1159,7 +1285,7 @@ impl ExecutorDSTHarness {
                         self.assert_error_contains(&resp, "WRONGTYPE", "SETBIT on wrong type");
                     }
                 }
-            } else {
+            } else if bit_sub < 60 {
                 // GETBIT
                 let desc = format!("GETBIT {} {}", key, offset);
                 self.result.last_op = Some(ExecutorOp::String(desc));
@@ -1185,6 +1311,242 @@ impl ExecutorDSTHarness {
                 } else {
                     self.assert_integer(&resp, expected, &format!("GETBIT {} at {}", key, offset));
                 }
+            } else {
+                self.run_bitmap_analytics_op(key);
+            }
+        }
+    }
+
+    /// GETRANGE index: mostly small, sometimes far past either end
+    fn random_range_index(&mut self) -> isize {
+        match self.rng.gen_range(0, 10) {
+            0 => isize::MIN + self.rng.gen_range(0, 2) as isize,
+            1 => isize::MAX - self.rng.gen_range(0, 2) as isize,
+            2 | 3 => self.rng.gen_range(0, 2000) as isize - 1000,
+            _ => self.rng.gen_range(0, 20) as isize - 10,
+        }
+    }
+
+    /// SETRANGE covering NUL padding past the end, empty values, offsets
+    /// past the 512MB limit, and integer values that stop (or start)
+    /// parsing as integers
+    fn run_setrange_op(&mut self) {
+        const MAX_STRING: usize = 512 * 1024 * 1024;
+
+        let key = self.random_key();
+        if self.rng.gen_bool(0.3) && matches!(self.shadow.get(&key), Some(RefValue::String(_)) | None) {
+            // Start from an integer so the write can change its encoding
+            let value = self.random_integer_string();
+            self.executor.execute(&Command::set(key.clone(), SDS::new(value.clone())));
+            self.shadow.set_string(&key, value);
+            self.shadow.expirations.remove(&key);
+        }
+
+        let current = match self.shadow.get(&key) {
+            Some(RefValue::String(v)) => Some(v.clone()),
+            None => None,
+            Some(_) => {
+                let desc = format!("SETRANGE {} on wrong type", key);
+                self.result.last_op = Some(ExecutorOp::String(desc));
+                let resp = self.executor.execute(&Command::SetRange(key.clone(), 0, SDS::from_str("x")));
+                self.assert_error_contains(&resp, "WRONGTYPE", "SETRANGE on wrong type");
+                return;
+            }
+        };
+        let len = current.as_ref().map_or(0, Vec::len);
+
+        let offset = match self.rng.gen_range(0, 10) {
+            0..=3 => self.rng.gen_range(0, 20) as usize,
+            4 | 5 => self.rng.gen_range(0, len as u64 + 1) as usize,
+            6 | 7 => len + self.rng.gen_range(1, 64) as usize,
+            8 => MAX_STRING + self.rng.gen_range(0, 3) as usize,
+            _ => usize::MAX - self.rng.gen_range(0, 3) as usize,
+        };
+        let value = match self.rng.gen_range(0, 10) {
+            0 | 1 => Vec::new(),
+            2..=4 => self.rng.gen_range(0, 100).to_string().into_bytes(),
+            _ => self.random_value(),
+        };
+        let desc = format!("SETRANGE {} {} {:?}", key, offset, String::from_utf8_lossy(&value));
+        self.result.last_op = Some(ExecutorOp::String(desc.clone()));
+
+        let cmd = Command::SetRange(key.clone(), offset, SDS::new(value.clone()));
+        let resp = self.executor.execute(&cmd);
+
+        if value.is_empty() {
+            // No-op reporting the current length; never creates the key
+            self.assert_integer(&resp, len as i64, &format!("{} with empty value", desc));
+        } else if offset.checked_add(value.len()).is_none_or(|n| n > MAX_STRING) {
+            self.assert_error_contains(&resp, "maximum allowed size", &desc);
+        } else {
+            let mut bytes = current.unwrap_or_default();
+            if bytes.len() < offset + value.len() {
+                bytes.resize(offset + value.len(), 0);
+            }
+            bytes[offset..offset + value.len()].copy_from_slice(&value);
+            self.assert_integer(&resp, bytes.len() as i64, &format!("{} new length", desc));
+            self.shadow.set_string(&key, bytes);
+        }
+
+        // The stored bytes (including NUL padding) and their integer-ness
+        // must match the shadow whichever branch ran
+        let get_resp = self.execute_routed(&Command::Get(key.clone()));
+        match self.shadow.get(&key) {
+            Some(RefValue::String(v)) => {
+                let v = v.clone();
+                self.assert_bulk_eq(&get_resp, &v, &format!("GET after {}", desc));
+                let encoding = self.executor.execute(&Command::ObjectEncoding(key.clone()));
+                let is_int = matches!(&encoding, RespValue::BulkString(Some(e)) if e == b"int");
+                if is_int != ref_parse_int(&v).is_some() {
+                    let msg = format!("OBJECT ENCODING {} after {}: got {:?}", key, desc, encoding);
+                    self.violation(&msg);
+                }
+            }
+            _ => self.assert_null(&get_resp, &format!("{} must not create the key", desc)),
+        }
+    }
+
+    /// BITCOUNT / BITPOS / BITOP, checked against a bit-by-bit shadow model
+    fn run_bitmap_analytics_op(&mut self, key: String) {
+        let sub = self.rng.gen_range(0, 100);
+
+        if sub < 70 {
+            let range = if self.rng.gen_bool(0.7) {
+                let unit = if self.rng.gen_bool(0.5) { BitUnit::Byte } else { BitUnit::Bit };
+                let start = self.rng.gen_range(0, 600) as i64 - 300;
+                let end = if sub < 35 || self.rng.gen_bool(0.5) {
+                    Some(self.rng.gen_range(0, 600) as i64 - 300)
+                } else {
+                    None
+                };
+                Some(BitRange { start, end, unit })
+            } else {
+                None
+            };
+
+            if sub < 35 {
+                // BITCOUNT needs both ends when a range is given
+                let range = range.map(|r| BitRange { end: r.end.or(Some(-1)), ..r });
+                let desc = format!("BITCOUNT {} {:?}", key, range);
+                self.result.last_op = Some(ExecutorOp::String(desc));
+
+                let expected = match self.shadow.get(&key) {
+                    Some(RefValue::String(v)) => {
+                        let r = range.unwrap_or(BitRange {
+                            start: 0,
+                            end: Some(-1),
+                            unit: BitUnit::Byte,
+                        });
+                        Some(ref_bit_span(v.len(), r).filter(|&p| ref_bit(v, p)).count() as i64)
+                    }
+                    None => Some(0),
+                    Some(_) => None,
+                };
+
+                let resp = self.executor.execute(&Command::BitCount { key: key.clone(), range });
+                match expected {
+                    Some(count) => {
+                        self.assert_integer(&resp, count, &format!("BITCOUNT {} {:?}", key, range))
+                    }
+                    None => {
+                        self.assert_error_contains(&resp, "WRONGTYPE", "BITCOUNT on wrong type")
+                    }
+                }
+            } else {
+                let bit = self.rng.gen_range(0, 2) as u8;
+                let desc = format!("BITPOS {} {} {:?}", key, bit, range);
+                self.result.last_op = Some(ExecutorOp::String(desc));
+
+                let expected = match self.shadow.get(&key) {
+                    Some(RefValue::String(v)) => {
+                        let r = range.unwrap_or(BitRange {
+                            start: 0,
+                            end: None,
+                            unit: BitUnit::Byte,
+                        });
+                        let mut span = ref_bit_span(v.len(), r).peekable();
+                        if span.peek().is_none() {
+                            Some(-1)
+                        } else {
+                            let hit = span.find(|&p| ref_bit(v, p) == (bit == 1));
+                            Some(match hit {
+                                Some(pos) => pos as i64,
+                                None if bit == 0 && r.end.is_none() => v.len() as i64 * 8,
+                                None => -1,
+                            })
+                        }
+                    }
+                    None => Some(if bit == 1 { -1 } else { 0 }),
+                    Some(_) => None,
+                };
+
+                let resp = self.executor.execute(&Command::BitPos { key: key.clone(), bit, range });
+                match expected {
+                    Some(pos) => {
+                        let context = format!("BITPOS {} {} {:?}", key, bit, range);
+                        self.assert_integer(&resp, pos, &context)
+                    }
+                    None => self.assert_error_contains(&resp, "WRONGTYPE", "BITPOS on wrong type"),
+                }
+            }
+        } else {
+            let op = match self.rng.gen_range(0, 4) {
+                0 => BitOperation::And,
+                1 => BitOperation::Or,
+                2 => BitOperation::Xor,
+                _ => BitOperation::Not,
+            };
+            let source_count = if op == BitOperation::Not { 1 } else { self.rng.gen_range(1, 4) };
+            let keys: Vec<String> = (0..source_count).map(|_| self.random_key()).collect();
+            let desc = format!("BITOP {:?} {} {:?}", op, key, keys);
+            self.result.last_op = Some(ExecutorOp::String(desc));
+
+            let mut sources = Vec::with_capacity(keys.len());
+            for k in &keys {
+                match self.shadow.get(k) {
+                    Some(RefValue::String(v)) => sources.push(Some(v.clone())),
+                    None => sources.push(Some(Vec::new())),
+                    Some(_) => sources.push(None),
+                }
+            }
+            let sources: Option<Vec<Vec<u8>>> = sources.into_iter().collect();
+
+            let resp = self.executor.execute(&Command::BitOp {
+                op,
+                dest: key.clone(),
+                keys: keys.clone(),
+            });
+            let Some(sources) = sources else {
+                self.assert_error_contains(&resp, "WRONGTYPE", "BITOP on wrong type");
+                return;
+            };
+
+            let len = sources.iter().map(Vec::len).max().unwrap_or(0);
+            let mut result = vec![0u8; len];
+            for pos in 0..len * 8 {
+                let bits: Vec<bool> = sources.iter().map(|v| ref_bit(v, pos)).collect();
+                let set = match op {
+                    BitOperation::And => bits.iter().all(|&b| b),
+                    BitOperation::Or => bits.iter().any(|&b| b),
+                    BitOperation::Xor => bits.iter().filter(|&&b| b).count() % 2 == 1,
+                    BitOperation::Not => !bits[0],
+                };
+                if set {
+                    result[pos / 8] |= 0x80 >> (pos % 8);
+                }
+            }
+            self.assert_integer(&resp, len as i64, &format!("BITOP {:?} {} length", op, key));
+
+            // BITOP overwrites the destination and clears its TTL
+            self.shadow.del(&key);
+            if !result.is_empty() {
+                self.shadow.set_string(&key, result.clone());
+            }
+            let get_resp = self.execute_routed(&Command::Get(key.clone()));
+            if result.is_empty() {
+                self.assert_null(&get_resp, "BITOP with empty result deletes destination");
+            } else {
+                self.assert_bulk_eq(&get_resp, &result, &format!("BITOP {:?} {} result", op, key));
             }
         }
     }
@@ -1210,7 +1572,7 @@ impl ExecutorDSTHarness {
             self.shadow.del(&key);
 
             // Verify key is gone
-            let get_resp = self.executor.execute(&Command::Get(key.clone()));
+            let get_resp = self.execute_routed(&Command::Get(key.clone()));
             self.assert_null(&get_resp, &format!("GET {} after DEL should be nil", key));
         } else if sub < 35 {
             // EXISTS
@@ -1218,7 +1580,7 @@ impl ExecutorDSTHarness {
             let desc = format!("EXISTS {}", key);
             self.result.last_op = Some(ExecutorOp::Key(desc));
 
-            let resp = self.executor.execute(&Command::Exists(vec![key.clone()]));
+            let resp = self.execute_routed(&Command::Exists(vec![key.clone()]));
             let expected = if self.shadow.exists(&key) { 1 } else { 0 };
             self.assert_integer(&resp, expected, &format!("EXISTS {}", key));
         } else if sub < 50 {
@@ -1300,14 +1662,14 @@ impl ExecutorDSTHarness {
                     let exp = self.shadow.expirations.remove(&src);
                     self.shadow.data.insert(dst.clone(), val);
                     if let Some(exp_time) = exp {
-                        self.shadow.expirations.insert(dst.clone(), exp_time);
+                        self.track_expiry(&dst, exp_time);
                     } else {
                         self.shadow.expirations.remove(&dst);
                     }
                 }
 
                 // Verify src is gone, dst exists
-                let src_exists_after = self.executor.execute(&Command::Exists(vec![src.clone()]));
+                let src_exists_after = self.execute_routed(&Command::Exists(vec![src.clone()]));
                 // If src == dst, key still exists at that name
                 if src != dst {
                     self.assert_integer(&src_exists_after, 0, &format!("RENAME src {} should not exist", src));
@@ -1424,7 +1786,7 @@ impl ExecutorDSTHarness {
             };
             // Clean up empty lists
             if matches!(self.shadow.data.get(&key), Some(RefValue::List(l)) if l.is_empty()) {
-                self.shadow.data.remove(&key);
+                self.shadow.del(&key);
             }
             match expect {
                 PopExpect::Value(expected) => {
@@ -1458,7 +1820,7 @@ impl ExecutorDSTHarness {
                 Some(_) => RPopExpect::WrongType,
             };
             if matches!(self.shadow.data.get(&key), Some(RefValue::List(l)) if l.is_empty()) {
-                self.shadow.data.remove(&key);
+                self.shadow.del(&key);
             }
             match expect {
                 RPopExpect::Value(expected) => {
@@ -1518,7 +1880,7 @@ impl ExecutorDSTHarness {
                     self.assert_error_contains(&resp, "WRONGTYPE", "LRANGE on wrong type");
                 }
             }
-        } else {
+        } else if sub < 97 {
             // LINDEX
             let index = (self.rng.gen_range(0, 10) as isize) - 3; // range: -3 to 6
             let desc = format!("LINDEX {} {}", key, index);
@@ -1555,6 +1917,71 @@ impl ExecutorDSTHarness {
                     self.assert_error_contains(&resp, "WRONGTYPE", "LINDEX on wrong type");
                 }
             }
+        } else if self.rng.gen_bool(0.5) {
+            // LREM
+            let count = (self.rng.gen_range(0, 5) as isize) - 2; // range: -2 to 2
+            let value = self.random_value();
+            let desc = format!("LREM {} {}", key, count);
+            self.result.last_op = Some(ExecutorOp::List(desc));
+
+            let resp = self
+                .executor
+                .execute(&Command::LRem(key.clone(), count, SDS::new(value.clone())));
+
+            let expected: Result<i64, &str> = match self.shadow.data.get_mut(&key) {
+                Some(RefValue::List(l)) => {
+                    let limit = if count == 0 { l.len() } else { count.unsigned_abs() };
+                    let mut matches: Vec<usize> = (0..l.len()).filter(|&i| l[i] == value).collect();
+                    if count < 0 {
+                        matches.reverse();
+                    }
+                    matches.truncate(limit);
+                    matches.sort_unstable();
+                    for &i in matches.iter().rev() {
+                        l.remove(i);
+                    }
+                    Ok(matches.len() as i64)
+                }
+                None => Ok(0),
+                Some(_) => Err("WRONGTYPE"),
+            };
+            if matches!(self.shadow.data.get(&key), Some(RefValue::List(l)) if l.is_empty()) {
+                self.shadow.del(&key);
+            }
+            match expected {
+                Ok(n) => self.assert_integer(&resp, n, &format!("LREM {} result", key)),
+                Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "LREM on wrong type"),
+            }
+        } else {
+            // LINSERT
+            let before = self.rng.gen_bool(0.5);
+            let pivot = self.random_value();
+            let value = self.random_value();
+            let desc = format!("LINSERT {} {}", key, if before { "BEFORE" } else { "AFTER" });
+            self.result.last_op = Some(ExecutorOp::List(desc));
+
+            let resp = self.executor.execute(&Command::LInsert {
+                key: key.clone(),
+                before,
+                pivot: SDS::new(pivot.clone()),
+                value: SDS::new(value.clone()),
+            });
+
+            let expected: Result<i64, &str> = match self.shadow.data.get_mut(&key) {
+                Some(RefValue::List(l)) => match l.iter().position(|v| *v == pivot) {
+                    Some(at) => {
+                        l.insert(if before { at } else { at + 1 }, value);
+                        Ok(l.len() as i64)
+                    }
+                    None => Ok(-1),
+                },
+                None => Ok(0),
+                Some(_) => Err("WRONGTYPE"),
+            };
+            match expected {
+                Ok(n) => self.assert_integer(&resp, n, &format!("LINSERT {} result", key)),
+                Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "LINSERT on wrong type"),
+            }
         }
     }
 
@@ -1566,7 +1993,7 @@ impl ExecutorDSTHarness {
         let key = self.random_key();
         let is_set_or_none = matches!(self.shadow.get(&key), Some(RefValue::Set(_)) | None);
 
-        if sub < 35 && is_set_or_none {
+        if sub < 30 && is_set_or_none {
             // SADD
             let member = self.random_value();
             let desc = format!("SADD {} member", key);
@@ -1587,7 +2014,7 @@ impl ExecutorDSTHarness {
                 // Invariant 5: Set cardinality matches SADD/SREM
                 self.assert_integer(&resp, expected, &format!("SADD {} result", key));
             }
-        } else if sub < 55 {
+        } else if sub < 45 {
             // SREM
             let member = self.random_value();
             let desc = format!("SREM {} member", key);
@@ -1607,13 +2034,13 @@ impl ExecutorDSTHarness {
                 Some(_) => Err("WRONGTYPE"),
             };
             if matches!(self.shadow.data.get(&key), Some(RefValue::Set(s)) if s.is_empty()) {
-                self.shadow.data.remove(&key);
+                self.shadow.del(&key);
             }
             match expected {
                 Ok(n) => self.assert_integer(&resp, n, &format!("SREM {} result", key)),
                 Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "SREM on wrong type"),
             }
-        } else if sub < 75 {
+        } else if sub < 58 {
             // SCARD
             let desc = format!("SCARD {}", key);
             self.result.last_op = Some(ExecutorOp::Set(desc));
@@ -1629,7 +2056,7 @@ impl ExecutorDSTHarness {
                 Ok(n) => self.assert_integer(&resp, n, &format!("SCARD {}", key)),
                 Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "SCARD on wrong type"),
             }
-        } else if sub < 88 {
+        } else if sub < 68 {
             // SISMEMBER
             let member = self.random_value();
             let desc = format!("SISMEMBER {}", key);
@@ -1651,6 +2078,129 @@ impl ExecutorDSTHarness {
                     self.assert_error_contains(&resp, "WRONGTYPE", "SISMEMBER on wrong type")
                 }
             }
+        } else if sub < 76 {
+            // SMISMEMBER
+            let members = [self.random_value(), self.random_value()];
+            let desc = format!("SMISMEMBER {}", key);
+            self.result.last_op = Some(ExecutorOp::Set(desc));
+
+            let resp = self.executor.execute(&Command::SMIsMember(
+                key.clone(),
+                members.iter().map(|m| SDS::new(m.clone())).collect(),
+            ));
+
+            let expected: Result<Vec<RespValue>, &str> = match self.shadow.get(&key) {
+                Some(RefValue::Set(s)) => Ok(members
+                    .iter()
+                    .map(|m| RespValue::Integer(if s.contains(m) { 1 } else { 0 }))
+                    .collect()),
+                None => Ok(vec![RespValue::Integer(0); members.len()]),
+                Some(_) => Err("WRONGTYPE"),
+            };
+            match expected {
+                Ok(flags) => {
+                    if resp != RespValue::Array(Some(flags.clone())) {
+                        self.violation(&format!(
+                            "SMISMEMBER {} mismatch: got {:?}, expected {:?}",
+                            key, resp, flags
+                        ));
+                    }
+                }
+                Err(_) => {
+                    self.assert_error_contains(&resp, "WRONGTYPE", "SMISMEMBER on wrong type")
+                }
+            }
+        } else if sub < 86 {
+            // SRANDMEMBER, with no count, a positive count or a negative count
+            let count = match self.rng.gen_range(0, 3) {
+                0 => None,
+                1 => Some(self.rng.gen_range(0, 6) as i64),
+                _ => Some(-(self.rng.gen_range(1, 6) as i64)),
+            };
+            let desc = format!("SRANDMEMBER {} {:?}", key, count);
+            self.result.last_op = Some(ExecutorOp::Set(desc));
+
+            let resp = self.executor.execute(&Command::SRandMember(key.clone(), count));
+
+            let members = match self.shadow.get(&key) {
+                Some(RefValue::Set(s)) => s.clone(),
+                None => HashSet::new(),
+                Some(_) => {
+                    self.assert_error_contains(&resp, "WRONGTYPE", "SRANDMEMBER on wrong type");
+                    return;
+                }
+            };
+            let picked: Vec<Vec<u8>> = match (&resp, count) {
+                (RespValue::BulkString(None), None) if members.is_empty() => Vec::new(),
+                (RespValue::BulkString(Some(m)), None) => vec![m.clone()],
+                (RespValue::Array(Some(items)), Some(_)) => items
+                    .iter()
+                    .filter_map(|item| match item {
+                        RespValue::BulkString(Some(m)) => Some(m.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => {
+                    self.violation(&format!("SRANDMEMBER {} unexpected reply {:?}", key, resp));
+                    return;
+                }
+            };
+            // Invariant: SRANDMEMBER only returns members, honouring the count
+            let expected_len = match count {
+                None => members.len().min(1),
+                Some(_) if members.is_empty() => 0,
+                Some(n) if n >= 0 => members.len().min(n as usize),
+                Some(n) => n.unsigned_abs() as usize,
+            };
+            let distinct: HashSet<&Vec<u8>> = picked.iter().collect();
+            if picked.len() != expected_len
+                || picked.iter().any(|m| !members.contains(m))
+                || (matches!(count, Some(n) if n > 0) && distinct.len() != picked.len())
+            {
+                self.violation(&format!(
+                    "SRANDMEMBER {} {:?} returned {:?} for a set of {}",
+                    key, count, picked, members.len()
+                ));
+            }
+        } else if sub < 93 {
+            // SINTERCARD over two keys, with an optional LIMIT
+            let other = self.random_key();
+            let limit = self.rng.gen_range(0, 4) as usize;
+            let desc = format!("SINTERCARD 2 {} {} LIMIT {}", key, other, limit);
+            self.result.last_op = Some(ExecutorOp::Set(desc));
+
+            let resp = self.executor.execute(&Command::SInterCard {
+                keys: vec![key.clone(), other.clone()],
+                limit,
+            });
+
+            // A missing key answers 0 before a later key can raise WRONGTYPE
+            let mut sets = Vec::new();
+            let mut expected: Result<i64, &str> = Ok(-1);
+            for k in [&key, &other] {
+                match self.shadow.get(k) {
+                    Some(RefValue::Set(s)) => sets.push(s),
+                    None => {
+                        expected = Ok(0);
+                        break;
+                    }
+                    Some(_) => {
+                        expected = Err("WRONGTYPE");
+                        break;
+                    }
+                }
+            }
+            if expected == Ok(-1) {
+                let count = sets[0].iter().filter(|m| sets[1].contains(*m)).count();
+                let capped = if limit == 0 { count } else { count.min(limit) };
+                expected = Ok(capped as i64);
+            }
+            match expected {
+                Ok(n) => self.assert_integer(&resp, n, &format!("SINTERCARD {} {}", key, other)),
+                Err(_) => {
+                    self.assert_error_contains(&resp, "WRONGTYPE", "SINTERCARD on wrong type")
+                }
+            }
         } else {
             // SMEMBERS
             let desc = format!("SMEMBERS {}", key);
@@ -1732,9 +2282,7 @@ impl ExecutorDSTHarness {
             let desc = format!("HGET {} field", key);
             self.result.last_op = Some(ExecutorOp::Hash(desc));
 
-            let resp = self
-                .executor
-                .execute(&Command::HGet(key.clone(), SDS::new(field.clone())));
+            let resp = self.execute_routed(&Command::HGet(key.clone(), SDS::new(field.clone())));
 
             enum HGetExpect {
                 Value(Vec<u8>),
@@ -1781,7 +2329,7 @@ impl ExecutorDSTHarness {
                 Some(_) => Err("WRONGTYPE"),
             };
             if matches!(self.shadow.data.get(&key), Some(RefValue::Hash(h)) if h.is_empty()) {
-                self.shadow.data.remove(&key);
+                self.shadow.del(&key);
             }
             match expected {
                 Ok(n) => self.assert_integer(&resp, n, &format!("HDEL {} result", key)),
@@ -1803,7 +2351,7 @@ impl ExecutorDSTHarness {
                 Ok(n) => self.assert_integer(&resp, n, &format!("HLEN {}", key)),
                 Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "HLEN on wrong type"),
             }
-        } else if sub < 90 {
+        } else if sub < 86 {
             // HEXISTS
             let field = self.random_field();
             let desc = format!("HEXISTS {} field", key);
@@ -1825,6 +2373,70 @@ impl ExecutorDSTHarness {
                     self.assert_error_contains(&resp, "WRONGTYPE", "HEXISTS on wrong type")
                 }
             }
+        } else if sub < 88 {
+            // HSETNX
+            let field = self.random_field();
+            let value = self.random_value();
+            let desc = format!("HSETNX {} field", key);
+            self.result.last_op = Some(ExecutorOp::Hash(desc));
+
+            let resp = self
+                .executor
+                .execute(&Command::Declared(DeclaredCommand::HSetNx {
+                    key: key.clone(),
+                    field: SDS::new(field.clone()),
+                    value: SDS::new(value.clone()),
+                }));
+
+            match self.shadow.get(&key) {
+                Some(RefValue::Hash(h)) if h.contains_key(&field) => {
+                    self.assert_integer(&resp, 0, &format!("HSETNX {} existing", key))
+                }
+                Some(RefValue::Hash(_)) | None => {
+                    self.assert_integer(&resp, 1, &format!("HSETNX {} new", key));
+                    let hash = self
+                        .shadow
+                        .data
+                        .entry(key.clone())
+                        .or_insert_with(|| RefValue::Hash(HashMap::new()));
+                    if let RefValue::Hash(ref mut h) = hash {
+                        h.insert(field, value);
+                    }
+                }
+                Some(_) => self.assert_error_contains(&resp, "WRONGTYPE", "HSETNX on wrong type"),
+            }
+        } else if sub < 90 {
+            // HMGET
+            let fields = [self.random_field(), self.random_field()];
+            let desc = format!("HMGET {} 2 fields", key);
+            self.result.last_op = Some(ExecutorOp::Hash(desc));
+
+            let resp = self.executor.execute(&Command::HMGet(
+                key.clone(),
+                fields.iter().map(|f| SDS::new(f.clone())).collect(),
+            ));
+
+            let expected = match self.shadow.get(&key) {
+                Some(RefValue::Hash(h)) => Some(
+                    fields
+                        .iter()
+                        .map(|f| RespValue::BulkString(h.get(f).cloned()))
+                        .collect(),
+                ),
+                None => Some(vec![RespValue::BulkString(None); fields.len()]),
+                Some(_) => None,
+            };
+            match expected {
+                Some(values) => {
+                    if resp != RespValue::Array(Some(values.clone())) {
+                        self.violation(&format!(
+                            "HMGET {}: expected {:?}, got {:?}",
+                            key, values, resp
+                        ));
+                    }
+                }
+                None => self.assert_error_contains(&resp, "WRONGTYPE", "HMGET on wrong type"),
+            }
         } else {
             // HINCRBY
             let field = self.random_field();
@@ -1891,7 +2503,7 @@ impl ExecutorDSTHarness {
         let key = self.random_key();
         let is_zset_or_none = matches!(self.shadow.get(&key), Some(RefValue::SortedSet(_)) | None);
 
-        if sub < 35 && is_zset_or_none {
+        if sub < 30 && is_zset_or_none {
             // ZADD
             let member = self.random_field();
             let score = self.random_score();
@@ -1920,7 +2532,40 @@ impl ExecutorDSTHarness {
                 // Invariant 7: Sorted set cardinality matches ZADD/ZREM
                 self.assert_integer(&resp, expected, &format!("ZADD {} result", key));
             }
-        } else if sub < 55 {
+        } else if sub < 38 {
+            // ZINCRBY
+            let member = self.random_field();
+            let increment = self.random_score() - 500.0;
+            let desc = format!("ZINCRBY {} {} member", key, increment);
+            self.result.last_op = Some(ExecutorOp::SortedSet(desc));
+
+            let resp = self.executor.execute(&Command::ZIncrBy(
+                key.clone(),
+                increment,
+                SDS::new(member.clone()),
+            ));
+
+            if !is_zset_or_none {
+                self.assert_error_contains(&resp, "WRONGTYPE", "ZINCRBY on wrong type");
+                return;
+            }
+            let zset = self
+                .shadow
+                .data
+                .entry(key.clone())
+                .or_insert_with(|| RefValue::SortedSet(BTreeMap::new()));
+            if let RefValue::SortedSet(ref mut z) = zset {
+                let score = z.get(&member).copied().unwrap_or(0.0) + increment;
+                z.insert(member, score);
+                let expected = RespValue::BulkString(Some(score.to_string().into_bytes()));
+                if resp != expected {
+                    self.violation(&format!(
+                        "ZINCRBY {} returned {:?}, expected score {}",
+                        key, resp, score
+                    ));
+                }
+            }
+        } else if sub < 52 {
             // ZREM
             let member = self.random_field();
             let desc = format!("ZREM {} member", key);
@@ -1940,13 +2585,13 @@ impl ExecutorDSTHarness {
                 Some(_) => Err("WRONGTYPE"),
             };
             if matches!(self.shadow.data.get(&key), Some(RefValue::SortedSet(z)) if z.is_empty()) {
-                self.shadow.data.remove(&key);
+                self.shadow.del(&key);
             }
             match expected {
                 Ok(n) => self.assert_integer(&resp, n, &format!("ZREM {} result", key)),
                 Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "ZREM on wrong type"),
             }
-        } else if sub < 70 {
+        } else if sub < 64 {
             // ZCARD
             let desc = format!("ZCARD {}", key);
             self.result.last_op = Some(ExecutorOp::SortedSet(desc));
@@ -1962,7 +2607,7 @@ impl ExecutorDSTHarness {
                 Ok(n) => self.assert_integer(&resp, n, &format!("ZCARD {}", key)),
                 Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "ZCARD on wrong type"),
             }
-        } else if sub < 85 {
+        } else if sub < 74 {
             // ZSCORE
             let member = self.random_field();
             let desc = format!("ZSCORE {} member", key);
@@ -2008,6 +2653,326 @@ impl ExecutorDSTHarness {
                     self.assert_error_contains(&resp, "WRONGTYPE", "ZSCORE on wrong type");
                 }
             }
+        } else if sub < 81 {
+            // ZMSCORE
+            let members = [self.random_field(), self.random_field()];
+            let desc = format!("ZMSCORE {} member member", key);
+            self.result.last_op = Some(ExecutorOp::SortedSet(desc));
+
+            let resp = self.executor.execute(&Command::ZMScore(
+                key.clone(),
+                members.iter().map(|m| SDS::new(m.clone())).collect(),
+            ));
+
+            let expected: Result<Vec<RespValue>, &str> = match self.shadow.get(&key) {
+                Some(RefValue::SortedSet(z)) => Ok(members
+                    .iter()
+                    .map(|m| RespValue::BulkString(z.get(m).map(|s| s.to_string().into_bytes())))
+                    .collect()),
+                None => Ok(vec![RespValue::BulkString(None); members.len()]),
+                Some(_) => Err("WRONGTYPE"),
+            };
+            match expected {
+                Ok(scores) => {
+                    if resp != RespValue::Array(Some(scores.clone())) {
+                        self.violation(&format!(
+                            "ZMSCORE {} mismatch: got {:?}, expected {:?}",
+                            key, resp, scores
+                        ));
+                    }
+                }
+                Err(_) => self.assert_error_contains(&resp, "WRONGTYPE", "ZMSCORE on wrong type"),
+            }
+        } else if sub < 86 {
+            // ZRANDMEMBER, with no count, a positive count or a negative count
+            let count = match self.rng.gen_range(0, 3) {
+                0 => None,
+                1 => Some(self.rng.gen_range(0, 6) as i64),
+                _ => Some(-(self.rng.gen_range(1, 6) as i64)),
+            };
+            let with_scores = count.is_some() && self.rng.gen_bool(0.5);
+            let desc = format!("ZRANDMEMBER {} {:?} withscores={}", key, count, with_scores);
+            self.result.last_op = Some(ExecutorOp::SortedSet(desc));
+
+            let resp = self.executor.execute(&Command::ZRandMember {
+                key: key.clone(),
+                count,
+                with_scores,
+            });
+
+            let zset = match self.shadow.get(&key) {
+                Some(RefValue::SortedSet(z)) => z.clone(),
+                None => BTreeMap::new(),
+                Some(_) => {
+                    self.assert_error_contains(&resp, "WRONGTYPE", "ZRANDMEMBER on wrong type");
+                    return;
+                }
+            };
+            let elements: Vec<Vec<u8>> = match (&resp, count) {
+                (RespValue::BulkString(None), None) if zset.is_empty() => Vec::new(),
+                (RespValue::BulkString(Some(m)), None) => vec![m.clone()],
+                (RespValue::Array(Some(items)), Some(_)) => items
+                    .iter()
+                    .filter_map(|item| match item {
+                        RespValue::BulkString(Some(m)) => Some(m.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => {
+                    self.violation(&format!("ZRANDMEMBER {} unexpected reply {:?}", key, resp));
+                    return;
+                }
+            };
+            // Invariant: ZRANDMEMBER only returns members (with their scores)
+            let stride = if with_scores { 2 } else { 1 };
+            let picked: Vec<&[Vec<u8>]> = elements.chunks(stride).collect();
+            let expected_len = match count {
+                None => zset.len().min(1),
+                Some(_) if zset.is_empty() => 0,
+                Some(n) if n >= 0 => zset.len().min(n as usize),
+                Some(n) => n.unsigned_abs() as usize,
+            };
+            let members_ok = picked.iter().all(|pick| match zset.get(&pick[0]) {
+                Some(score) => !with_scores || pick.get(1) == Some(&score.to_string().into_bytes()),
+                None => false,
+            });
+            let distinct: HashSet<&Vec<u8>> = picked.iter().map(|pick| &pick[0]).collect();
+            if elements.len() != expected_len * stride
+                || !members_ok
+                || (matches!(count, Some(n) if n > 0) && distinct.len() != picked.len())
+            {
+                self.violation(&format!(
+                    "ZRANDMEMBER {} {:?} returned {:?} for a sorted set of {}",
+                    key, count, elements, zset.len()
+                ));
+            }
+        } else if sub < 90 {
+            // ZUNION/ZINTER/ZDIFF, sometimes STORE, against a shadow aggregation
+            let op = match self.rng.gen_range(0, 3) {
+                0 => ZSetOperation::Union,
+                1 => ZSetOperation::Inter,
+                _ => ZSetOperation::Diff,
+            };
+            let keys = vec![key.clone(), self.random_key()];
+            let dest = if self.rng.gen_bool(0.5) {
+                Some(self.random_key())
+            } else {
+                None
+            };
+            let weights = if op != ZSetOperation::Diff && self.rng.gen_bool(0.5) {
+                Some(vec![
+                    self.rng.gen_range(0, 5) as f64 - 2.0,
+                    self.rng.gen_range(0, 5) as f64 - 2.0,
+                ])
+            } else {
+                None
+            };
+            let aggregate = match self.rng.gen_range(0, 3) {
+                0 => ZAggregate::Sum,
+                1 => ZAggregate::Min,
+                _ => ZAggregate::Max,
+            };
+            let with_scores = dest.is_none() && self.rng.gen_bool(0.5);
+            let desc = format!(
+                "{:?} dest={:?} {:?} weights={:?} {:?}",
+                op, dest, keys, weights, aggregate
+            );
+            self.result.last_op = Some(ExecutorOp::SortedSet(desc));
+
+            let resp = self.executor.execute(&Command::ZSetOp {
+                op,
+                dest: dest.clone(),
+                keys: keys.clone(),
+                weights: weights.clone(),
+                aggregate,
+                with_scores,
+            });
+
+            // Shadow aggregation over BTreeMaps; plain sets score 1
+            let mut inputs: Vec<BTreeMap<Vec<u8>, f64>> = Vec::new();
+            for (i, k) in keys.iter().enumerate() {
+                let weight = weights.as_ref().map_or(1.0, |w| w[i]);
+                let scale = |s: f64| if (s * weight).is_nan() { 0.0 } else { s * weight };
+                match self.shadow.get(k) {
+                    None => inputs.push(BTreeMap::new()),
+                    Some(RefValue::SortedSet(z)) => {
+                        inputs.push(z.iter().map(|(m, &s)| (m.clone(), scale(s))).collect())
+                    }
+                    Some(RefValue::Set(set)) => {
+                        inputs.push(set.iter().map(|m| (m.clone(), scale(1.0))).collect())
+                    }
+                    Some(_) => {
+                        self.assert_error_contains(&resp, "WRONGTYPE", "ZSET op on wrong type");
+                        return;
+                    }
+                }
+            }
+            let combine = |a: f64, b: f64| match aggregate {
+                ZAggregate::Sum if (a + b).is_nan() => 0.0,
+                ZAggregate::Sum => a + b,
+                ZAggregate::Min => a.min(b),
+                ZAggregate::Max => a.max(b),
+            };
+            let mut expected: BTreeMap<Vec<u8>, f64> = BTreeMap::new();
+            match op {
+                ZSetOperation::Union => {
+                    for input in &inputs {
+                        for (m, &s) in input {
+                            let merged = expected.get(m).map_or(s, |&cur| combine(cur, s));
+                            expected.insert(m.clone(), merged);
+                        }
+                    }
+                }
+                ZSetOperation::Inter => {
+                    for (m, &s) in &inputs[0] {
+                        if let Some(&other) = inputs[1].get(m) {
+                            expected.insert(m.clone(), combine(s, other));
+                        }
+                    }
+                }
+                ZSetOperation::Diff => {
+                    for (m, &s) in &inputs[0] {
+                        if !inputs[1].contains_key(m) {
+                            expected.insert(m.clone(), s);
+                        }
+                    }
+                }
+            }
+
+            match dest {
+                Some(dest) => {
+                    self.assert_integer(&resp, expected.len() as i64, "ZSET op STORE cardinality");
+                    self.shadow.del(&dest);
+                    if !expected.is_empty() {
+                        self.shadow.data.insert(dest, RefValue::SortedSet(expected));
+                    }
+                }
+                None => {
+                    let mut ordered: Vec<(&Vec<u8>, &f64)> = expected.iter().collect();
+                    ordered.sort_by(|a, b| a.1.total_cmp(b.1).then_with(|| a.0.cmp(b.0)));
+                    let mut items = Vec::new();
+                    for (m, s) in ordered {
+                        items.push(RespValue::BulkString(Some(m.clone())));
+                        if with_scores {
+                            items.push(RespValue::BulkString(Some(s.to_string().into_bytes())));
+                        }
+                    }
+                    if resp != RespValue::Array(Some(items)) {
+                        self.violation(&format!("{:?} {:?} mismatch: got {:?}", op, keys, resp));
+                    }
+                }
+            }
+        } else if sub < 95 {
+            // ZREMRANGEBYRANK/BYSCORE/BYLEX against the shadow's sorted order
+            let range = match self.rng.gen_range(0, 3) {
+                0 => ZRangeSpec::Rank(
+                    self.rng.gen_range(0, 12) as isize - 6,
+                    self.rng.gen_range(0, 12) as isize - 6,
+                ),
+                1 => {
+                    let bound = |rng: &mut SimulatedRng, score: f64, inf: &str| {
+                        match rng.gen_range(0, 5) {
+                            0 => inf.to_string(),
+                            1 | 2 => format!("({}", score),
+                            _ => score.to_string(),
+                        }
+                    };
+                    let (lo, hi) = (self.random_score(), self.random_score());
+                    ZRangeSpec::Score(
+                        bound(&mut self.rng, lo.min(hi), "-inf"),
+                        bound(&mut self.rng, lo.max(hi), "+inf"),
+                    )
+                }
+                _ => {
+                    let bound = |rng: &mut SimulatedRng, member: Vec<u8>, inf: &str| {
+                        let member = String::from_utf8(member).expect("fields are ASCII");
+                        match rng.gen_range(0, 5) {
+                            0 => inf.to_string(),
+                            1 | 2 => format!("({}", member),
+                            _ => format!("[{}", member),
+                        }
+                    };
+                    let (lo, hi) = (self.random_field(), self.random_field());
+                    ZRangeSpec::Lex(
+                        bound(&mut self.rng, lo.clone().min(hi.clone()), "-"),
+                        bound(&mut self.rng, lo.max(hi), "+"),
+                    )
+                }
+            };
+            let desc = format!("ZREMRANGE {} {:?}", key, range);
+            self.result.last_op = Some(ExecutorOp::SortedSet(desc));
+
+            let resp = self
+                .executor
+                .execute(&Command::ZRemRange(key.clone(), range.clone()));
+
+            let zset = match self.shadow.get(&key) {
+                None => {
+                    self.assert_integer(&resp, 0, "ZREMRANGE on a missing key");
+                    return;
+                }
+                Some(RefValue::SortedSet(z)) => z.clone(),
+                Some(_) => {
+                    self.assert_error_contains(&resp, "WRONGTYPE", "ZREMRANGE on wrong type");
+                    return;
+                }
+            };
+            let mut ordered: Vec<(&Vec<u8>, f64)> = zset.iter().map(|(m, &s)| (m, s)).collect();
+            ordered.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+            let in_score = |bound: &str, score: f64, is_min: bool| match bound {
+                "-inf" => is_min,
+                "+inf" => !is_min,
+                _ => match bound.strip_prefix('(') {
+                    Some(b) if is_min => score > b.parse::<f64>().unwrap(),
+                    Some(b) => score < b.parse::<f64>().unwrap(),
+                    None if is_min => score >= bound.parse::<f64>().unwrap(),
+                    None => score <= bound.parse::<f64>().unwrap(),
+                },
+            };
+            let in_lex = |bound: &str, member: &[u8], is_min: bool| match bound {
+                "-" => is_min,
+                "+" => !is_min,
+                _ => match (bound.as_bytes()[0], &bound.as_bytes()[1..]) {
+                    (b'(', b) if is_min => member > b,
+                    (b'(', b) => member < b,
+                    (_, b) if is_min => member >= b,
+                    (_, b) => member <= b,
+                },
+            };
+            let doomed: Vec<Vec<u8>> = match &range {
+                ZRangeSpec::Rank(start, stop) => {
+                    let len = ordered.len() as isize;
+                    let start = if *start < 0 { (start + len).max(0) } else { *start };
+                    let stop = if *stop < 0 { stop + len } else { (*stop).min(len - 1) };
+                    if start > stop || start >= len {
+                        Vec::new()
+                    } else {
+                        ordered[start as usize..=stop as usize]
+                            .iter()
+                            .map(|(m, _)| (*m).clone())
+                            .collect()
+                    }
+                }
+                ZRangeSpec::Score(min, max) => ordered
+                    .iter()
+                    .filter(|(_, s)| in_score(min, *s, true) && in_score(max, *s, false))
+                    .map(|(m, _)| (*m).clone())
+                    .collect(),
+                ZRangeSpec::Lex(min, max) => ordered
+                    .iter()
+                    .filter(|(m, _)| in_lex(min, m, true) && in_lex(max, m, false))
+                    .map(|(m, _)| (*m).clone())
+                    .collect(),
+            };
+            self.assert_integer(&resp, doomed.len() as i64, &format!("ZREMRANGE {} count", key));
+            if let Some(RefValue::SortedSet(z)) = self.shadow.data.get_mut(&key) {
+                for member in &doomed {
+                    z.remove(member);
+                }
+                if z.is_empty() {
+                    self.shadow.del(&key);
+                }
+            }
         } else {
             // ZRANGE - verify ordering invariant, sometimes with WITHSCORES
             let with_scores = self.rng.gen_range(0, 100) < 40;
@@ -2020,7 +2985,14 @@ impl ExecutorDSTHarness {
 
             let resp = self
                 .executor
-                .execute(&Command::ZRange(key.clone(), 0, -1, with_scores));
+                .execute(&Command::ZRange {
+                    dest: None,
+                    key: key.clone(),
+                    range: ZRangeSpec::Rank(0, -1),
+                    rev: false,
+                    limit: None,
+                    with_scores,
+                });
 
             // Invariant 8: ZRANGE returns ascending order with correct count
             let expected_len = match self.shadow.get(&key) {
@@ -2070,6 +3042,183 @@ impl ExecutorDSTHarness {
         }
     }
 
+    // --- BITFIELD operations ---
+    /// BITFIELD sequences under WRAP, SAT and FAIL. The shadow computes
+    /// every SET and INCRBY result exactly in i128, then applies the policy.
+    fn run_bitfield_op(&mut self) {
+        self.result.bitfield_ops += 1;
+        let key = self.random_key();
+        let read_only = self.rng.gen_bool(0.1);
+
+        let mut ops = Vec::new();
+        for _ in 0..self.rng.gen_range(1, 5) {
+            let ty = self.random_bitfield_type();
+            let offset = if self.rng.gen_bool(0.5) {
+                // `#N` offsets, multiplied out by the width
+                self.rng.gen_range(0, 8) * ty.bits as u64
+            } else {
+                self.rng.gen_range(0, 64)
+            };
+            let op = match self.rng.gen_range(0, 10) {
+                _ if read_only => BitFieldOp::Get { ty, offset },
+                0 | 1 => BitFieldOp::Get { ty, offset },
+                2 => BitFieldOp::Overflow(match self.rng.gen_range(0, 3) {
+                    0 => BitFieldOverflow::Wrap,
+                    1 => BitFieldOverflow::Sat,
+                    _ => BitFieldOverflow::Fail,
+                }),
+                3..=5 => BitFieldOp::Set {
+                    ty,
+                    offset,
+                    value: self.random_bitfield_value(ty),
+                },
+                _ => BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment: self.random_bitfield_increment(ty),
+                },
+            };
+            ops.push(op);
+        }
+        let name = if read_only { "BITFIELD_RO" } else { "BITFIELD" };
+        let desc = format!("{} {} {:?}", name, key, ops);
+        self.result.last_op = Some(ExecutorOp::BitField(desc.clone()));
+
+        let cmd = if read_only {
+            Command::BitFieldRo {
+                key: key.clone(),
+                ops: ops.clone(),
+            }
+        } else {
+            Command::BitField {
+                key: key.clone(),
+                ops: ops.clone(),
+            }
+        };
+        let resp = self.executor.execute(&cmd);
+
+        let mut bytes = match self.shadow.get(&key) {
+            Some(RefValue::String(v)) => v.clone(),
+            None => Vec::new(),
+            Some(_) => {
+                self.assert_error_contains(&resp, "WRONGTYPE", &desc);
+                return;
+            }
+        };
+        let mut policy = BitFieldOverflow::Wrap;
+        let mut writes = false;
+        let mut expected = Vec::with_capacity(ops.len());
+        for op in &ops {
+            let (ty, offset, exact, is_set) = match *op {
+                BitFieldOp::Overflow(p) => {
+                    policy = p;
+                    continue;
+                }
+                BitFieldOp::Get { ty, offset } => {
+                    let value = ref_bitfield_get(&bytes, ty, offset);
+                    expected.push(RespValue::Integer(value as i64));
+                    continue;
+                }
+                // Unsigned fields take the argument's 64-bit pattern
+                BitFieldOp::Set { ty, offset, value } if ty.signed => {
+                    (ty, offset, value as i128, true)
+                }
+                BitFieldOp::Set { ty, offset, value } => {
+                    (ty, offset, value as u64 as i128, true)
+                }
+                BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment,
+                } => (
+                    ty,
+                    offset,
+                    ref_bitfield_get(&bytes, ty, offset) + increment as i128,
+                    false,
+                ),
+            };
+
+            // The field is padded in even when FAIL refuses the write
+            writes = true;
+            let needed = (offset + ty.bits as u64).div_ceil(8) as usize;
+            if bytes.len() < needed {
+                bytes.resize(needed, 0);
+            }
+            let (min, max) = ref_bitfield_range(ty);
+            if !(min..=max).contains(&exact) {
+                self.result.bitfield_overflows += 1;
+            }
+            let old = ref_bitfield_get(&bytes, ty, offset);
+            match ref_bitfield_overflow(ty, exact, policy) {
+                Some(stored) => {
+                    ref_bitfield_put(&mut bytes, ty, offset, stored);
+                    let reply = if is_set { old } else { stored };
+                    expected.push(RespValue::Integer(reply as i64));
+                }
+                None => expected.push(RespValue::BulkString(None)),
+            }
+        }
+
+        let expected = RespValue::Array(Some(expected));
+        if resp != expected {
+            self.violation(&format!("{}: expected {:?}, got {:?}", desc, expected, resp));
+            return;
+        }
+        if writes {
+            let stored = self.executor.execute(&Command::Get(key.clone()));
+            self.assert_bulk_eq(&stored, &bytes, &format!("GET {} after {}", key, desc));
+            self.shadow.set_string(&key, bytes);
+        }
+    }
+
+    /// Mostly narrow widths, where small increments overflow, plus the two
+    /// widest of each signedness
+    fn random_bitfield_type(&mut self) -> BitFieldType {
+        let signed = self.rng.gen_bool(0.5);
+        let widest = if signed { 64 } else { 63 };
+        let bits = match self.rng.gen_range(0, 10) {
+            0..=5 => self.rng.gen_range(1, 9),
+            6 | 7 => self.rng.gen_range(9, widest + 1),
+            _ => widest - self.rng.gen_range(0, 2),
+        };
+        BitFieldType {
+            signed,
+            bits: bits as u8,
+        }
+    }
+
+    /// SET values at and just past the type's bounds, the i64 extremes, and
+    /// small values of either sign
+    fn random_bitfield_value(&mut self, ty: BitFieldType) -> i64 {
+        let (min, max) = ref_bitfield_range(ty);
+        let value = match self.rng.gen_range(0, 8) {
+            0 => min,
+            1 => max,
+            2 => min - 1,
+            3 => max + 1,
+            4 => i64::MIN as i128,
+            5 => i64::MAX as i128,
+            _ => self.rng.gen_range(0, 512) as i128 - 256,
+        };
+        value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// INCRBY increments: a whole period of the type (a no-op under WRAP),
+    /// the type's bounds, the i64 extremes, and small steps
+    fn random_bitfield_increment(&mut self, ty: BitFieldType) -> i64 {
+        let (min, max) = ref_bitfield_range(ty);
+        let increment = match self.rng.gen_range(0, 8) {
+            0 => max - min + 1,
+            1 => min - max - 1,
+            2 => max,
+            3 => -max,
+            4 => i64::MIN as i128,
+            5 => i64::MAX as i128,
+            _ => self.rng.gen_range(0, 512) as i128 - 256,
+        };
+        increment.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
     // --- Expiry operations ---
     fn run_expiry_op(&mut self) {
         let sub = self.rng.gen_range(0, 100);
@@ -2104,7 +3253,7 @@ impl ExecutorDSTHarness {
                     }
                     // Sync shadow: track the expiry
                     let expiry_ms = self.current_time_ms + (seconds as u64 * 1000);
-                    self.shadow.expirations.insert(key.clone(), expiry_ms);
+                    self.track_expiry(&key, expiry_ms);
                 }
                 RespValue::Integer(0) => {
                     // Key didn't exist; make sure shadow agrees it doesn't exist
@@ -2162,7 +3311,7 @@ impl ExecutorDSTHarness {
                 RespValue::Integer(1) => {
                     // Key existed and got an expiry
                     let expiry_ms = self.current_time_ms + milliseconds as u64;
-                    self.shadow.expirations.insert(key.clone(), expiry_ms);
+                    self.track_expiry(&key, expiry_ms);
                 }
                 RespValue::Integer(0) => {
                     // Key didn't exist; make sure shadow agrees
@@ -2203,10 +3352,12 @@ impl ExecutorDSTHarness {
         } else {
             // Advance time slightly (simulate passage of time for expiry testing)
             let advance_ms = self.rng.gen_range(100, 5000);
-            self.current_time_ms += advance_ms;
-            let time = crate::simulator::VirtualTime::from_millis(self.current_time_ms);
-            self.executor.set_time(time);
-            self.shadow.evict_expired(self.current_time_ms);
+            let target_ms = self.current_time_ms + advance_ms;
+            if self.config.precise_expiry {
+                self.fire_expiry_timers(target_ms);
+            }
+            // The last deadline can leave the clock 1ms past the target
+            self.set_time_ms(target_ms.max(self.current_time_ms));
 
             let desc = format!("TIME_ADVANCE +{}ms", advance_ms);
             self.result.last_op = Some(ExecutorOp::Expiry(desc));
@@ -2223,6 +3374,94 @@ impl ExecutorDSTHarness {
             }
             // Keys in executor but not shadow: created by executor (shouldn't happen normally)
             // Don't add them to shadow - these represent a tracking gap we should fix
+
+            self.check_expiry_outlook();
+        }
+    }
+
+    /// Record that `key` expires at `expiry_ms`, in the shadow and, in
+    /// precise-expiry mode, on the timer wheel
+    fn track_expiry(&mut self, key: &str, expiry_ms: u64) {
+        debug_assert!(
+            expiry_ms > self.current_time_ms,
+            "Precondition: expiry must be in the future"
+        );
+        self.shadow.expirations.insert(key.to_string(), expiry_ms);
+        if self.config.precise_expiry {
+            self.expiry_timers
+                .entry(expiry_ms)
+                .or_default()
+                .insert(key.to_string());
+        }
+    }
+
+    /// Move the executor's and the shadow's clocks forward to `time_ms`
+    fn set_time_ms(&mut self, time_ms: u64) {
+        debug_assert!(
+            time_ms >= self.current_time_ms,
+            "Precondition: time cannot go backwards"
+        );
+        self.current_time_ms = time_ms;
+        let time = crate::simulator::VirtualTime::from_millis(time_ms);
+        self.executor.set_time(time);
+        self.shadow.evict_expired(time_ms);
+    }
+
+    /// Fire every timer due by `until_ms`. Each still-current deadline is
+    /// observed at deadline-1ms, where the key must be live with PTTL 1, and
+    /// at deadline+1ms, where it must be gone. Stepping around the deadline
+    /// rather than onto it keeps the check independent of which side of the
+    /// exact millisecond the TTL comparison puts the key.
+    fn fire_expiry_timers(&mut self, until_ms: u64) {
+        while let Some(timer) = self.expiry_timers.first_entry() {
+            let deadline = *timer.key();
+            if deadline > until_ms {
+                break;
+            }
+            let keys = timer.remove();
+            if deadline <= self.current_time_ms {
+                // The previous deadline stepped over this one's "before"
+                continue;
+            }
+
+            self.set_time_ms(deadline - 1);
+            let mut armed = Vec::new();
+            for key in keys {
+                let current = self.shadow.exists(&key)
+                    && self.shadow.expirations.get(&key) == Some(&deadline);
+                if !current {
+                    continue;
+                }
+                self.result.last_op = Some(ExecutorOp::Expiry(format!(
+                    "EXPIRY_BOUNDARY {} @{}ms",
+                    key, deadline
+                )));
+                match self.executor.execute(&Command::Pttl(key.clone())) {
+                    RespValue::Integer(1) => {}
+                    // The executor lost the TTL some other way: shadow
+                    // drift, which the serve-time checks cover
+                    RespValue::Integer(-1) => continue,
+                    other => {
+                        self.violation(&format!(
+                            "PTTL {} 1ms before its expiry should be 1, got {:?}",
+                            key, other
+                        ));
+                        continue;
+                    }
+                }
+                let exists = self.execute_routed(&Command::Exists(vec![key.clone()]));
+                self.assert_integer(&exists, 1, &format!("EXISTS {} 1ms before its expiry", key));
+                armed.push(key);
+            }
+
+            self.set_time_ms(deadline + 1);
+            for key in armed {
+                let exists = self.execute_routed(&Command::Exists(vec![key.clone()]));
+                self.assert_integer(&exists, 0, &format!("EXISTS {} 1ms after its expiry", key));
+                let pttl = self.executor.execute(&Command::Pttl(key.clone()));
+                self.assert_integer(&pttl, -2, &format!("PTTL {} 1ms after its expiry", key));
+                self.result.expiry_boundary_checks += 1;
+            }
         }
     }
 
@@ -2230,6 +3469,133 @@ impl ExecutorDSTHarness {
     // Invariant Assertion Helpers
     // =========================================================================
 
+    /// The expiry outlook behind the TTL gauges: horizons are cumulative and
+    /// bounded by the keys with a TTL, the deadline index agrees with a full
+    /// scan, and every key seen expiring within the hour is, an hour later,
+    /// expiring within the minute.
+    fn check_expiry_outlook(&mut self) {
+        let now = crate::simulator::VirtualTime::from_millis(self.current_time_ms);
+        let outlook = self.executor.expiry_outlook(now);
+        if !outlook.is_consistent() {
+            self.violation(&format!("Expiry outlook is inconsistent: {:?}", outlook));
+            return;
+        }
+
+        // The deadline index must count what a scan of every deadline counts
+        let mut scanned = [0u64; 3];
+        for deadline in self.executor.expirations.values() {
+            let remaining = deadline.as_millis().saturating_sub(self.current_time_ms);
+            for (count, &(_, horizon)) in scanned.iter_mut().zip(&EXPIRY_HORIZONS) {
+                if remaining < horizon {
+                    *count += 1;
+                }
+            }
+        }
+        if outlook.expiring != scanned {
+            self.violation(&format!(
+                "Expiry outlook {:?} disagrees with a scan of every deadline {:?}",
+                outlook.expiring, scanned
+            ));
+            return;
+        }
+
+        let an_hour_on =
+            crate::simulator::VirtualTime::from_millis(self.current_time_ms + 3_600_000);
+        let arrived = self.executor.expiry_outlook(an_hour_on);
+        if arrived.expiring[0] < outlook.expiring[2] {
+            self.violation(&format!(
+                "Expiry outlook saw {} keys expiring within 1h, but only {} an hour later",
+                outlook.expiring[2], arrived.expiring[0]
+            ));
+        }
+    }
+
+    /// Expire-at-serve-time consistency: EXISTS, TTL, PTTL, TYPE and GET must
+    /// all agree on whether a key is live, no matter how lazily it expires.
+    fn check_serve_time_agreement(&mut self) {
+        let key = self.random_key();
+
+        let exists = self.execute_routed(&Command::Exists(vec![key.clone()]));
+        let ttl = self.executor.execute(&Command::Ttl(key.clone()));
+        let pttl = self.executor.execute(&Command::Pttl(key.clone()));
+        let type_resp = self.executor.execute(&Command::TypeOf(key.clone()));
+
+        let live = match exists {
+            RespValue::Integer(1) => true,
+            RespValue::Integer(0) => false,
+            other => {
+                self.violation(&format!("EXISTS {} returned unexpected: {:?}", key, other));
+                return;
+            }
+        };
+
+        let is_none = matches!(&type_resp, RespValue::SimpleString(s) if s.as_ref() == "none");
+        if live == is_none {
+            self.violation(&format!(
+                "EXISTS {} = {} disagrees with TYPE {:?}",
+                key, live, type_resp
+            ));
+        }
+
+        match (&ttl, &pttl) {
+            (RespValue::Integer(t), RespValue::Integer(pt)) => {
+                if live != (*t != -2) || live != (*pt != -2) {
+                    self.violation(&format!(
+                        "EXISTS {} = {} disagrees with TTL {} / PTTL {}",
+                        key, live, t, pt
+                    ));
+                } else if (*t == -1) != (*pt == -1) {
+                    self.violation(&format!(
+                        "TTL {} = {} and PTTL = {} disagree on persistence",
+                        key, t, pt
+                    ));
+                }
+            }
+            _ => self.violation(&format!(
+                "TTL/PTTL {} returned non-integers: {:?} / {:?}",
+                key, ttl, pttl
+            )),
+        }
+
+        if !live {
+            let get_resp = self.execute_routed(&Command::Get(key.clone()));
+            self.assert_null(&get_resp, &format!("GET {} on a key EXISTS reports missing", key));
+        }
+
+        // Internal maps must be consistent too (panics in debug builds)
+        self.executor.verify_invariants();
+    }
+
+    /// Run `cmd` through `execute()` or, for commands with a direct fast
+    /// path, through that path at random: both must behave identically
+    fn execute_routed(&mut self, cmd: &Command) -> RespValue {
+        let routable = matches!(
+            cmd,
+            Command::Get(_)
+                | Command::Incr(_)
+                | Command::MGet(_)
+                | Command::HGet(..)
+                | Command::Exists(_)
+        );
+        if !routable || self.rng.gen_bool(0.5) {
+            return self.executor.execute(cmd);
+        }
+        match cmd {
+            Command::Get(key) => self.executor.get_direct(key),
+            Command::Incr(key) => self.executor.incr_direct(key),
+            Command::MGet(keys) => {
+                let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+                self.executor.mget_direct(&keys)
+            }
+            Command::HGet(key, field) => self.executor.hget_direct(key, &field.to_string()),
+            Command::Exists(keys) => {
+                let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+                self.executor.exists_direct(&keys)
+            }
+            _ => unreachable!("only routable commands reach here"),
+        }
+    }
+
     fn violation(&mut self, msg: &str) {
         self.result.invariant_violations.push(format!(
             "Op #{}: {:?} - {}",
@@ -2306,12 +3672,12 @@ impl ExecutorDSTHarness {
     /// Run specified number of operations
     pub fn run(&mut self, operations: usize) {
         // Set initial time
-        let time = crate::simulator::VirtualTime::from_millis(self.current_time_ms);
-        self.executor.set_time(time);
+        self.set_time_ms(self.current_time_ms);
 
         for _ in 0..operations {
             self.result.total_operations += 1;
             self.run_single_op();
+            self.check_serve_time_agreement();
 
             // Stop early if we hit a violation
             if !self.result.invariant_violations.is_empty() {
@@ -2433,6 +3799,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_executor_dst_precise_expiry() {
+        let config = ExecutorDSTConfig::precise_expiry(2043);
+        let mut harness = ExecutorDSTHarness::new(config);
+        harness.run(1000);
+        let result = harness.result();
+        println!("Precise expiry: {}", result.summary());
+        for v in &result.invariant_violations {
+            println!("  VIOLATION: {}", v);
+        }
+        assert!(result.is_success());
+        assert!(
+            result.expiry_boundary_checks > 0,
+            "Precise mode should observe some expiry deadlines"
+        );
+    }
+
+    #[test]
+    fn test_executor_dst_bitfield_heavy() {
+        let config = ExecutorDSTConfig::bitfield_heavy(1990);
+        let mut harness = ExecutorDSTHarness::new(config);
+        harness.run(1000);
+        let result = harness.result();
+        println!("BITFIELD heavy: {}", result.summary());
+        for v in &result.invariant_violations {
+            println!("  VIOLATION: {}", v);
+        }
+        assert!(result.is_success());
+        assert!(
+            result.bitfield_overflows > 0,
+            "BITFIELD-heavy runs should overflow some fields"
+        );
+    }
+
     #[test]
     fn test_executor_dst_10_seeds() {
         let results = run_executor_batch(0, 10, 500, ExecutorDSTConfig::new);
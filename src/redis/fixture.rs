@@ -0,0 +1,500 @@
+//! Keyspace fixtures - an executor's keys as readable YAML, for tests and
+//! DST scenarios that start from a curated state
+//!
+//! `Fixture::capture` records every live key of a `CommandExecutor`;
+//! `Fixture::apply` installs a fixture through `CommandExecutor::bulk_load`,
+//! so keyspace statistics and field-TTL tracking come out as if commands had
+//! written the keys. `Fixture::generate` builds a seeded keyspace of mixed
+//! types and TTLs, to use directly or to write out and curate by hand.
+//!
+//! ```yaml
+//! keys:
+//!   greeting:
+//!     type: string
+//!     value: hello
+//!     ttl_ms: 5000
+//!   queue:
+//!     type: list
+//!     items: [a, b, c]
+//!   user:1:
+//!     type: hash
+//!     fields: {name: alice, visits: "3"}
+//!     field_ttl_ms: {visits: 60000}
+//!   scores:
+//!     type: zset
+//!     members: {alice: 10.5, bob: 7}
+//!   events:
+//!     type: stream
+//!     entries:
+//!       - id: 1-0
+//!         fields: [temp, "21"]
+//!     groups:
+//!       workers:
+//!         last_delivered: 1-0
+//!         consumers:
+//!           alice:
+//!             pending: [{id: 1-0, delivery_count: 2}]
+//! ```
+//!
+//! Times are relative, so a fixture loads the same at any clock: `ttl_ms`
+//! and `field_ttl_ms` count forward from the executor's current time, and a
+//! consumer's or pending entry's `idle_ms` back from it. Keys, fields and
+//! set members are written sorted, so capturing the same keyspace twice
+//! gives the same file. Values are text: a key or element that is not
+//! UTF-8 cannot be captured, and a string that looks like a number must be
+//! quoted, like `"3"` above.
+//!
+//! # TigerStyle Invariants
+//!
+//! - `Fixture::capture` after `apply` of a captured fixture returns it again
+//! - A fixture that fails to build leaves the executor untouched
+
+use super::data::{
+    RedisHash, RedisList, RedisSet, RedisSortedSet, RedisStream, StreamId, Value, SDS,
+};
+use super::executor::{BulkEntry, BulkLoadReport, CommandExecutor};
+use crate::simulator::DeterministicRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A keyspace: each key's value and TTL
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub keys: BTreeMap<String, FixtureKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureKey {
+    #[serde(flatten)]
+    pub value: FixtureValue,
+    /// Milliseconds to live from load time; none for a persistent key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FixtureValue {
+    String {
+        value: String,
+    },
+    List {
+        items: Vec<String>,
+    },
+    Set {
+        members: Vec<String>,
+    },
+    Hash {
+        fields: BTreeMap<String, String>,
+        /// Milliseconds to live of the fields that expire
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        field_ttl_ms: BTreeMap<String, u64>,
+    },
+    Zset {
+        members: BTreeMap<String, f64>,
+    },
+    Stream(FixtureStream),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureStream {
+    pub entries: Vec<FixtureEntry>,
+    /// Largest ID ever added; the newest entry's when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_id: Option<String>,
+    /// Entries added over the stream's lifetime; the entry count when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entries_added: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, FixtureGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureEntry {
+    pub id: String,
+    /// Field, value, field, value... as XADD takes them
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureGroup {
+    pub last_delivered: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub consumers: BTreeMap<String, FixtureConsumer>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureConsumer {
+    #[serde(default)]
+    pub idle_ms: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<FixturePending>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixturePending {
+    pub id: String,
+    #[serde(default)]
+    pub idle_ms: u64,
+    #[serde(default = "one_delivery")]
+    pub delivery_count: u64,
+}
+
+fn one_delivery() -> u64 {
+    1
+}
+
+impl Fixture {
+    pub fn from_yaml(text: &str) -> Result<Fixture, String> {
+        serde_yaml::from_str(text).map_err(|e| format!("invalid fixture: {}", e))
+    }
+
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("a fixture is always representable as YAML")
+    }
+
+    pub fn read(path: &Path) -> Result<Fixture, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Self::from_yaml(&text)
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_yaml())
+    }
+
+    /// Record every live key of `executor`
+    pub fn capture(executor: &CommandExecutor) -> Result<Fixture, String> {
+        let now_ms = executor.current_time.as_millis();
+        let stream_now_ms = executor.stream_now_ms();
+        let mut keys = BTreeMap::new();
+        for (key, value) in &executor.data {
+            if executor.is_expired(key) {
+                continue;
+            }
+            let Some(value) = capture_value(value, now_ms, stream_now_ms)
+                .map_err(|e| format!("key '{}': {}", key, e))?
+            else {
+                continue;
+            };
+            let ttl_ms = executor
+                .expirations
+                .get(key)
+                .map(|deadline| deadline.as_millis().saturating_sub(now_ms));
+            keys.insert(key.clone(), FixtureKey { value, ttl_ms });
+        }
+        Ok(Fixture { keys })
+    }
+
+    /// Install every key, replacing what the executor held under the same
+    /// names. Nothing is installed if any key fails to build.
+    pub fn apply(&self, executor: &mut CommandExecutor) -> Result<BulkLoadReport, String> {
+        let now_ms = executor.current_time.as_millis();
+        let stream_now_ms = executor.stream_now_ms();
+        let entries = self
+            .keys
+            .iter()
+            .map(|(key, entry)| {
+                let value = build_value(&entry.value, now_ms, stream_now_ms)
+                    .map_err(|e| format!("key '{}': {}", key, e))?;
+                Ok((key.clone(), value, entry.ttl_ms))
+            })
+            .collect::<Result<Vec<BulkEntry>, String>>()?;
+        let report = executor.bulk_load(entries);
+
+        debug_assert_eq!(
+            report.loaded + report.skipped,
+            self.keys.len(),
+            "Postcondition: every key is loaded or skipped"
+        );
+        Ok(report)
+    }
+
+    /// `count` keys of every type, a third of them with TTLs, named
+    /// `<type>:<n>` and all derived from `seed`
+    pub fn generate(seed: u64, count: usize) -> Fixture {
+        let mut rng = DeterministicRng::new(seed);
+        let mut keys = BTreeMap::new();
+        for n in 0..count {
+            let len = rng.gen_range(1, 8) as usize;
+            let (kind, value) = match rng.gen_range(0, 6) {
+                0 => (
+                    "string",
+                    FixtureValue::String {
+                        value: word(&mut rng),
+                    },
+                ),
+                1 => (
+                    "list",
+                    FixtureValue::List {
+                        items: (0..len).map(|_| word(&mut rng)).collect(),
+                    },
+                ),
+                2 => {
+                    let mut members: Vec<String> = (0..len).map(|_| word(&mut rng)).collect();
+                    members.sort();
+                    members.dedup();
+                    ("set", FixtureValue::Set { members })
+                }
+                3 => {
+                    let fields: BTreeMap<String, String> = (0..len)
+                        .map(|i| (format!("f{}", i), word(&mut rng)))
+                        .collect();
+                    let field_ttl_ms = if rng.gen_bool(0.25) {
+                        BTreeMap::from([("f0".to_string(), rng.gen_range(1_000, 3_600_000))])
+                    } else {
+                        BTreeMap::new()
+                    };
+                    (
+                        "hash",
+                        FixtureValue::Hash {
+                            fields,
+                            field_ttl_ms,
+                        },
+                    )
+                }
+                4 => (
+                    "zset",
+                    FixtureValue::Zset {
+                        members: (0..len)
+                            .map(|_| (word(&mut rng), rng.gen_range(0, 100) as f64))
+                            .collect(),
+                    },
+                ),
+                _ => (
+                    "stream",
+                    FixtureValue::Stream(FixtureStream {
+                        entries: (1..=len)
+                            .map(|i| FixtureEntry {
+                                id: format!("{}-0", i),
+                                fields: vec!["f".to_string(), word(&mut rng)],
+                            })
+                            .collect(),
+                        last_id: None,
+                        entries_added: None,
+                        groups: BTreeMap::new(),
+                    }),
+                ),
+            };
+            let ttl_ms = rng
+                .gen_bool(1.0 / 3.0)
+                .then(|| rng.gen_range(1_000, 3_600_000));
+            keys.insert(format!("{}:{}", kind, n), FixtureKey { value, ttl_ms });
+        }
+
+        debug_assert_eq!(keys.len(), count, "Postcondition: one key per index");
+        Fixture { keys }
+    }
+}
+
+fn word(rng: &mut DeterministicRng) -> String {
+    format!("v{}", rng.gen_range(0, 1000))
+}
+
+fn text(bytes: &SDS) -> Result<String, String> {
+    String::from_utf8(bytes.as_bytes().to_vec()).map_err(|_| "value is not UTF-8".to_string())
+}
+
+/// None for a hash whose fields have all expired but not yet been removed
+fn capture_value(
+    value: &Value,
+    now_ms: u64,
+    stream_now_ms: u64,
+) -> Result<Option<FixtureValue>, String> {
+    let captured = match value {
+        Value::String(s) => FixtureValue::String { value: text(s)? },
+        Value::List(list) => FixtureValue::List {
+            items: list
+                .range(0, -1)
+                .iter()
+                .map(text)
+                .collect::<Result<_, _>>()?,
+        },
+        Value::Set(set) => {
+            let mut members = set
+                .members()
+                .iter()
+                .map(text)
+                .collect::<Result<Vec<_>, _>>()?;
+            members.sort();
+            FixtureValue::Set { members }
+        }
+        Value::Hash(hash) => {
+            let mut fields = BTreeMap::new();
+            let mut field_ttl_ms = BTreeMap::new();
+            for (field, value) in hash.iter() {
+                match hash.field_expiry(field) {
+                    Some(deadline) if deadline <= now_ms => continue,
+                    Some(deadline) => {
+                        field_ttl_ms.insert(field.clone(), deadline - now_ms);
+                    }
+                    None => {}
+                }
+                fields.insert(field.clone(), text(value)?);
+            }
+            if fields.is_empty() {
+                return Ok(None);
+            }
+            FixtureValue::Hash {
+                fields,
+                field_ttl_ms,
+            }
+        }
+        Value::SortedSet(zset) => FixtureValue::Zset {
+            members: zset
+                .iter()
+                .map(|(member, score)| (member.to_string(), score))
+                .collect(),
+        },
+        Value::Stream(stream) => FixtureValue::Stream(capture_stream(stream, stream_now_ms)?),
+        Value::Null => return Ok(None),
+    };
+    Ok(Some(captured))
+}
+
+fn capture_stream(stream: &RedisStream, stream_now_ms: u64) -> Result<FixtureStream, String> {
+    let mut entries = Vec::with_capacity(stream.len());
+    for (id, fields) in stream.range(StreamId::MIN, StreamId::MAX, None) {
+        let mut flat = Vec::with_capacity(fields.len() * 2);
+        for (field, value) in fields {
+            flat.push(text(field)?);
+            flat.push(text(value)?);
+        }
+        entries.push(FixtureEntry {
+            id: id.to_string(),
+            fields: flat,
+        });
+    }
+
+    let mut groups = BTreeMap::new();
+    for (name, group) in stream.groups() {
+        let mut consumers: BTreeMap<String, FixtureConsumer> = group
+            .consumers()
+            .iter()
+            .map(|(consumer, state)| {
+                let idle_ms = stream_now_ms.saturating_sub(state.seen_ms);
+                (
+                    consumer.clone(),
+                    FixtureConsumer {
+                        idle_ms,
+                        pending: Vec::new(),
+                    },
+                )
+            })
+            .collect();
+        for (id, entry) in group.pending() {
+            let owner = consumers
+                .get_mut(&entry.consumer)
+                .ok_or("pending entry without its consumer")?;
+            owner.pending.push(FixturePending {
+                id: id.to_string(),
+                idle_ms: stream_now_ms.saturating_sub(entry.delivery_ms),
+                delivery_count: entry.delivery_count,
+            });
+        }
+        groups.insert(
+            name.clone(),
+            FixtureGroup {
+                last_delivered: group.last_delivered().to_string(),
+                consumers,
+            },
+        );
+    }
+
+    Ok(FixtureStream {
+        entries,
+        last_id: Some(stream.last_id().to_string()),
+        entries_added: Some(stream.entries_added()),
+        groups,
+    })
+}
+
+fn build_value(value: &FixtureValue, now_ms: u64, stream_now_ms: u64) -> Result<Value, String> {
+    let sds = |s: &String| SDS::from_str(s);
+    let built = match value {
+        FixtureValue::String { value } => Value::String(sds(value)),
+        FixtureValue::List { items } => Value::List(RedisList::from_items(items.iter().map(sds))),
+        FixtureValue::Set { members } => {
+            Value::Set(RedisSet::from_members(members.iter().map(sds)))
+        }
+        FixtureValue::Hash {
+            fields,
+            field_ttl_ms,
+        } => {
+            let mut hash =
+                RedisHash::from_pairs(fields.iter().map(|(field, value)| (sds(field), sds(value))));
+            for (field, ttl_ms) in field_ttl_ms {
+                if !fields.contains_key(field) {
+                    return Err(format!("TTL for missing field '{}'", field));
+                }
+                if *ttl_ms == 0 {
+                    hash.delete(&sds(field));
+                } else {
+                    hash.set_field_expiry(field, now_ms.saturating_add(*ttl_ms));
+                }
+            }
+            Value::Hash(hash)
+        }
+        FixtureValue::Zset { members } => {
+            if members.values().any(|score| score.is_nan()) {
+                return Err("score is not a number".to_string());
+            }
+            Value::SortedSet(RedisSortedSet::from_pairs(
+                members.iter().map(|(member, score)| (sds(member), *score)),
+            ))
+        }
+        FixtureValue::Stream(stream) => Value::Stream(build_stream(stream, stream_now_ms)?),
+    };
+    Ok(built)
+}
+
+fn parse_id(id: &str) -> Result<StreamId, String> {
+    StreamId::parse(id, 0).ok_or_else(|| format!("invalid stream ID '{}'", id))
+}
+
+fn build_stream(fixture: &FixtureStream, stream_now_ms: u64) -> Result<RedisStream, String> {
+    let mut stream = RedisStream::new();
+    for entry in &fixture.entries {
+        let id = parse_id(&entry.id)?;
+        if id <= stream.last_id() {
+            return Err(format!("stream ID {} is not above the previous one", id));
+        }
+        if entry.fields.is_empty() || entry.fields.len() % 2 != 0 {
+            return Err(format!("entry {} needs field-value pairs", id));
+        }
+        let fields = entry
+            .fields
+            .chunks_exact(2)
+            .map(|pair| (SDS::from_str(&pair[0]), SDS::from_str(&pair[1])))
+            .collect();
+        stream.add(id, fields);
+    }
+
+    let last_id = match &fixture.last_id {
+        Some(id) => parse_id(id)?,
+        None => stream.last_id(),
+    };
+    let entries_added = fixture.entries_added.unwrap_or(stream.len() as u64);
+    if !stream.restore_history(last_id, entries_added) {
+        return Err("last_id or entries_added is behind the entries".to_string());
+    }
+
+    for (name, fixture_group) in &fixture.groups {
+        stream.create_group(name, parse_id(&fixture_group.last_delivered)?);
+        let group = stream.group_mut(name).expect("group was created above");
+        for (consumer, state) in &fixture_group.consumers {
+            for pending in &state.pending {
+                let id = parse_id(&pending.id)?;
+                if group.pending().contains_key(&id) {
+                    return Err(format!("{} is pending twice in group '{}'", id, name));
+                }
+                let delivery_ms = stream_now_ms.saturating_sub(pending.idle_ms);
+                group.deliver(id, consumer, delivery_ms, true);
+                group.set_delivery(id, delivery_ms, Some(pending.delivery_count));
+            }
+            group.touch_consumer(consumer, stream_now_ms.saturating_sub(state.idle_ms));
+        }
+    }
+    Ok(stream)
+}
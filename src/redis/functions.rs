@@ -0,0 +1,364 @@
+//! Redis 7 Functions: the library store behind FUNCTION and FCALL.
+//!
+//! A library is Lua code whose first line names it, `#!lua name=<library>`;
+//! running the code registers its functions with `redis.register_function`:
+//! - FUNCTION LOAD runs the code once to learn the functions it registers,
+//!   then stores the library here (see `function_ops`)
+//! - FCALL finds the function's library, runs the code again and calls the
+//!   registered callback with the keys and arguments, the way EVAL runs a
+//!   script
+//! - FUNCTION DUMP and RESTORE carry each library's code in an RDB function
+//!   record; RDB snapshots (the simulated server's disk, DEBUG RELOAD) hold
+//!   the same records ahead of the keys, so libraries persist with the data.
+//!   The streaming persistence of production servers records keys only
+//!
+//! Libraries are part of the dataset, not a cache: FLUSHALL keeps them, and
+//! only FUNCTION DELETE, FUNCTION FLUSH and RESTORE with FLUSH remove them.
+//!
+//! `FunctionLibraries` is cheap to clone; every shard executor holds a handle
+//! to the same store, so a library loaded through any shard can be called on
+//! whichever shard FCALL's keys route to.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Every function belongs to exactly one library, and no two libraries
+//!   register a function of the same name
+//! - A stored library registers at least one function
+
+use super::command::FunctionRestorePolicy;
+use super::rdb;
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The engine libraries are written for, as FUNCTION LIST names it
+pub const LUA_ENGINE: &str = "LUA";
+
+/// Flags `redis.register_function` accepts
+pub const FUNCTION_FLAGS: [&str; 5] = [
+    "no-writes",
+    "allow-oom",
+    "allow-stale",
+    "no-cluster",
+    "allow-cross-slot-keys",
+];
+
+const INVALID_LIBRARY_NAME: &str = "ERR Library names can only contain letters, numbers, or underscores(_) and must be at least one character long";
+
+/// One function a library registers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// Flags from `FUNCTION_FLAGS`
+    pub flags: Vec<String>,
+}
+
+impl FunctionInfo {
+    /// Flagged `no-writes`: FCALL_RO may call it, and it may not write
+    pub fn is_read_only(&self) -> bool {
+        self.flags.iter().any(|flag| flag == "no-writes")
+    }
+}
+
+/// A loaded library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    pub name: String,
+    pub engine: String,
+    /// The code as loaded, metadata line included
+    pub code: String,
+    /// Functions in registration order
+    pub functions: Vec<FunctionInfo>,
+}
+
+/// The metadata line a library starts with: `#!<engine> name=<library>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryHeader {
+    pub engine: String,
+    pub name: String,
+}
+
+impl LibraryHeader {
+    /// Read the metadata line of `code`, with Redis's errors
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let first_line = code.lines().next().unwrap_or("");
+        let Some(shebang) = first_line.strip_prefix("#!") else {
+            return Err("ERR Missing library metadata".to_string());
+        };
+        let mut parts = shebang.split(' ').filter(|part| !part.is_empty());
+        let engine = parts.next().unwrap_or("");
+        let mut name = None;
+        for part in parts {
+            let Some(value) = part.strip_prefix("name=") else {
+                return Err(format!("ERR Invalid metadata value given: {}", part));
+            };
+            if name.replace(value).is_some() {
+                return Err(
+                    "ERR Invalid metadata value, name argument was given multiple times"
+                        .to_string(),
+                );
+            }
+        }
+        let name = name.ok_or("ERR Library name was not given")?;
+        if !engine.eq_ignore_ascii_case(LUA_ENGINE) {
+            return Err(format!("ERR Engine '{}' not found", engine));
+        }
+        if !is_valid_name(name) {
+            return Err(INVALID_LIBRARY_NAME.to_string());
+        }
+        Ok(LibraryHeader {
+            engine: LUA_ENGINE.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+/// `code` without its metadata line; the newline stays, so line numbers in
+/// Lua errors match the code as loaded
+pub fn library_body(code: &str) -> &str {
+    code.find('\n').map_or("", |newline| &code[newline..])
+}
+
+/// Library and function names are letters, digits and underscores
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
+
+#[derive(Debug, Clone, Default)]
+struct LibraryStore {
+    libraries: BTreeMap<String, Library>,
+    /// Function name to the library registering it
+    functions: BTreeMap<String, String>,
+}
+
+impl LibraryStore {
+    fn insert(&mut self, library: Library, replace: bool) -> Result<(), String> {
+        if !replace && self.libraries.contains_key(&library.name) {
+            return Err(format!("ERR Library '{}' already exists", library.name));
+        }
+        for function in &library.functions {
+            if let Some(owner) = self.functions.get(&function.name) {
+                if *owner != library.name {
+                    return Err(format!("ERR Function {} already exists", function.name));
+                }
+            }
+        }
+        self.remove(&library.name);
+        for function in &library.functions {
+            self.functions
+                .insert(function.name.clone(), library.name.clone());
+        }
+        self.libraries.insert(library.name.clone(), library);
+
+        debug_assert_eq!(
+            self.functions.len(),
+            self.libraries
+                .values()
+                .map(|library| library.functions.len())
+                .sum::<usize>(),
+            "Postcondition violated: every function belongs to exactly one library"
+        );
+        Ok(())
+    }
+
+    fn remove(&mut self, name: &str) -> Option<Library> {
+        let library = self.libraries.remove(name)?;
+        for function in &library.functions {
+            self.functions.remove(&function.name);
+        }
+        Some(library)
+    }
+}
+
+/// Function libraries, shared by every executor holding a handle
+#[derive(Debug, Clone, Default)]
+pub struct FunctionLibraries {
+    inner: Arc<RwLock<LibraryStore>>,
+}
+
+impl FunctionLibraries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `library`. A library of the same name is an error unless
+    /// `replace`; a function another library registers always is
+    pub fn load(&self, library: Library, replace: bool) -> Result<(), String> {
+        debug_assert!(
+            !library.functions.is_empty(),
+            "Precondition: a library registers at least one function"
+        );
+        self.inner.write().insert(library, replace)
+    }
+
+    /// Store the libraries of a FUNCTION RESTORE payload: all of them, or
+    /// none when one conflicts
+    pub fn restore(
+        &self,
+        libraries: Vec<Library>,
+        policy: FunctionRestorePolicy,
+    ) -> Result<(), String> {
+        let mut store = self.inner.write();
+        let mut restored = match policy {
+            FunctionRestorePolicy::Flush => LibraryStore::default(),
+            FunctionRestorePolicy::Append | FunctionRestorePolicy::Replace => store.clone(),
+        };
+        for library in libraries {
+            restored.insert(library, policy == FunctionRestorePolicy::Replace)?;
+        }
+        *store = restored;
+        Ok(())
+    }
+
+    /// Remove a library and its functions; false if there is none
+    pub fn delete(&self, name: &str) -> bool {
+        self.inner.write().remove(name).is_some()
+    }
+
+    pub fn flush(&self) {
+        *self.inner.write() = LibraryStore::default();
+    }
+
+    /// `name`'s function, and the code of the library registering it
+    pub fn function(&self, name: &str) -> Option<(FunctionInfo, String)> {
+        let store = self.inner.read();
+        let library = store.libraries.get(store.functions.get(name)?)?;
+        let function = library.functions.iter().find(|f| f.name == name)?;
+        Some((function.clone(), library.code.clone()))
+    }
+
+    /// Every library, by name
+    pub fn libraries(&self) -> Vec<Library> {
+        self.inner.read().libraries.values().cloned().collect()
+    }
+
+    pub fn num_libraries(&self) -> usize {
+        self.inner.read().libraries.len()
+    }
+
+    pub fn num_functions(&self) -> usize {
+        self.inner.read().functions.len()
+    }
+
+    /// The code of every library, by name, as snapshots store it
+    pub fn codes(&self) -> Vec<String> {
+        let store = self.inner.read();
+        store
+            .libraries
+            .values()
+            .map(|library| library.code.clone())
+            .collect()
+    }
+
+    /// The FUNCTION DUMP payload of every library
+    pub fn dump(&self) -> Vec<u8> {
+        let store = self.inner.read();
+        rdb::encode_function_payload(
+            store
+                .libraries
+                .values()
+                .map(|library| library.code.as_str()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(name: &str, functions: &[&str]) -> Library {
+        Library {
+            name: name.to_string(),
+            engine: LUA_ENGINE.to_string(),
+            code: format!("#!lua name={}\n", name),
+            functions: functions
+                .iter()
+                .map(|function| FunctionInfo {
+                    name: function.to_string(),
+                    description: None,
+                    flags: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_library_header() {
+        let header = LibraryHeader::parse("#!lua name=mylib\nreturn 1").expect("valid header");
+        assert_eq!(header.name, "mylib");
+        assert_eq!(header.engine, LUA_ENGINE);
+        assert_eq!(library_body("#!lua name=mylib\nreturn 1"), "\nreturn 1");
+
+        assert_eq!(
+            LibraryHeader::parse("return 1"),
+            Err("ERR Missing library metadata".to_string())
+        );
+        assert_eq!(
+            LibraryHeader::parse("#!js name=mylib\n"),
+            Err("ERR Engine 'js' not found".to_string())
+        );
+        assert_eq!(
+            LibraryHeader::parse("#!lua\n"),
+            Err("ERR Library name was not given".to_string())
+        );
+        assert!(LibraryHeader::parse("#!lua name=my-lib\n").is_err());
+    }
+
+    #[test]
+    fn test_function_names_are_unique_across_libraries() {
+        let functions = FunctionLibraries::new();
+        functions
+            .load(library("a", &["f", "g"]), false)
+            .expect("load a");
+        assert_eq!(
+            functions.load(library("a", &["h"]), false),
+            Err("ERR Library 'a' already exists".to_string())
+        );
+        assert_eq!(
+            functions.load(library("b", &["g"]), false),
+            Err("ERR Function g already exists".to_string())
+        );
+
+        // Replacing a library drops the functions it no longer registers
+        functions
+            .load(library("a", &["h"]), true)
+            .expect("replace a");
+        functions
+            .load(library("b", &["g"]), false)
+            .expect("g is free again");
+        assert_eq!(functions.num_functions(), 2);
+        assert!(functions.function("f").is_none());
+        assert!(functions.delete("a"));
+        assert!(!functions.delete("a"));
+    }
+
+    #[test]
+    fn test_restore_is_all_or_nothing() {
+        let functions = FunctionLibraries::new();
+        functions.load(library("a", &["f"]), false).expect("load a");
+        let payload = vec![library("b", &["g"]), library("a", &["f"])];
+
+        let appended = functions.restore(payload.clone(), FunctionRestorePolicy::Append);
+        assert!(appended.is_err());
+        assert_eq!(
+            functions.num_libraries(),
+            1,
+            "a failed restore changes nothing"
+        );
+
+        functions
+            .restore(payload.clone(), FunctionRestorePolicy::Replace)
+            .expect("replace");
+        assert_eq!(functions.num_libraries(), 2);
+
+        functions
+            .restore(vec![library("c", &["h"])], FunctionRestorePolicy::Flush)
+            .expect("flush");
+        assert_eq!(functions.num_libraries(), 1);
+        assert_eq!(functions.num_functions(), 1);
+    }
+}
@@ -0,0 +1,370 @@
+//! Import files - RDB snapshots and RESP command streams - turned into an
+//! `ImportPlan` for the bulk-load path
+//!
+//! An RDB file becomes a single load of every key in database 0. A file of
+//! RESP commands (what `redis-cli --pipe` takes) is folded: runs of plain
+//! SET, RPUSH/LPUSH, SADD, HSET, ZADD, EXPIRE/PEXPIRE and RESTORE build
+//! finished values that load in one batch, and any other command becomes an
+//! `Execute` step between batches, so the file's order is kept.
+//!
+//! A key's first write in a batch replaces what the keyspace held, the way
+//! loading an RDB file or RESTORE ... REPLACE does. So a push or add only
+//! folds into a batch when that batch created the key; one that extends a
+//! key from an earlier step runs as a normal command instead.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Steps apply in file order; no two consecutive steps are both loads
+//! - Every loaded collection is non-empty
+
+use super::command::Command;
+use super::data::{RedisHash, RedisList, RedisSet, RedisSortedSet, Value, SDS};
+use super::executor::BulkEntry;
+use super::rdb;
+use super::resp::{RespParser, RespValue};
+use ahash::{AHashMap, AHashSet};
+use std::collections::VecDeque;
+
+/// Executor clock origin for hash field deadlines: production executors
+/// keep Unix milliseconds, so their clock reads zero at the Unix epoch
+const IMPORT_EPOCH_MS: i64 = 0;
+
+/// One step of an import, applied in order
+#[derive(Debug, Clone)]
+pub enum ImportStep {
+    /// Finished values for `CommandExecutor::bulk_load`
+    Load(Vec<BulkEntry>),
+    /// A command with no fast path, executed as a client would send it
+    Execute(Command),
+}
+
+/// An import file, ready to apply
+#[derive(Debug, Clone, Default)]
+pub struct ImportPlan {
+    pub steps: Vec<ImportStep>,
+    /// RDB keys outside database 0, which the single keyspace cannot hold
+    pub skipped_other_dbs: usize,
+    /// RDB keys whose deadline had already passed
+    pub skipped_expired: usize,
+}
+
+impl ImportPlan {
+    /// Keys across every load step
+    pub fn loaded_keys(&self) -> usize {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                ImportStep::Load(entries) => entries.len(),
+                ImportStep::Execute(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Commands that run through the executor
+    pub fn executed_commands(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| matches!(step, ImportStep::Execute(_)))
+            .count()
+    }
+}
+
+/// Read an import file, RDB or RESP by its first bytes. `now_unix_ms`
+/// turns absolute deadlines (RDB expiries, RESTORE ABSTTL) into TTLs.
+pub fn read_import(bytes: &[u8], now_unix_ms: u64) -> Result<ImportPlan, String> {
+    if rdb::is_rdb(bytes) {
+        read_rdb(bytes, now_unix_ms)
+    } else {
+        read_resp(bytes, now_unix_ms)
+    }
+}
+
+fn read_rdb(bytes: &[u8], now_unix_ms: u64) -> Result<ImportPlan, String> {
+    let file = rdb::read_file(bytes, true, IMPORT_EPOCH_MS)?;
+    let mut plan = ImportPlan {
+        skipped_other_dbs: file.other_db_keys,
+        ..ImportPlan::default()
+    };
+    let mut entries = Vec::with_capacity(file.keys.len());
+    for key in file.keys {
+        let ttl_ms = match key.expire_at_ms {
+            Some(deadline) if deadline <= now_unix_ms => {
+                plan.skipped_expired += 1;
+                continue;
+            }
+            Some(deadline) => Some(deadline - now_unix_ms),
+            None => None,
+        };
+        entries.push((key.key, key.value, ttl_ms));
+    }
+    if !entries.is_empty() {
+        plan.steps.push(ImportStep::Load(entries));
+    }
+    Ok(plan)
+}
+
+fn read_resp(bytes: &[u8], now_unix_ms: u64) -> Result<ImportPlan, String> {
+    let mut folder = Folder::default();
+    let mut pos = 0;
+    let mut index = 0usize;
+    while pos < bytes.len() {
+        // Tolerate blank lines between commands
+        if bytes[pos] == b'\r' || bytes[pos] == b'\n' {
+            pos += 1;
+            continue;
+        }
+        let (value, consumed) = RespParser::parse(&bytes[pos..])
+            .map_err(|e| format!("command {}: {}", index + 1, e))?;
+        debug_assert!(consumed > 0, "Invariant: the parser always makes progress");
+        pos += consumed;
+        index += 1;
+        folder
+            .push(&value, now_unix_ms)
+            .map_err(|e| format!("command {}: {}", index, e))?;
+    }
+    Ok(folder.finish())
+}
+
+/// A value being built from folded commands
+enum Staged {
+    String(SDS),
+    List(VecDeque<SDS>),
+    Set(Vec<SDS>),
+    Hash(Vec<(SDS, SDS)>),
+    SortedSet(Vec<(SDS, f64)>),
+    Restored(Value),
+}
+
+impl Staged {
+    fn build(self) -> Value {
+        match self {
+            Staged::String(s) => Value::String(s),
+            Staged::List(items) => Value::List(RedisList::from_items(items)),
+            Staged::Set(members) => Value::Set(RedisSet::from_members(members)),
+            Staged::Hash(pairs) => Value::Hash(RedisHash::from_pairs(pairs)),
+            Staged::SortedSet(pairs) => Value::SortedSet(RedisSortedSet::from_pairs(pairs)),
+            Staged::Restored(value) => value,
+        }
+    }
+}
+
+/// Folds a command stream into load batches and executed commands
+#[derive(Default)]
+struct Folder {
+    steps: Vec<ImportStep>,
+    /// Keys of the open batch, in first-write order, with their TTLs
+    batch: Vec<(String, Staged, Option<u64>)>,
+    batch_index: AHashMap<String, usize>,
+    /// Keys written by earlier steps
+    sealed: AHashSet<String>,
+}
+
+impl Folder {
+    fn push(&mut self, value: &RespValue, now_unix_ms: u64) -> Result<(), String> {
+        let cmd = Command::from_resp(value)?;
+        if let Command::Restore {
+            key,
+            ttl_ms,
+            payload,
+            absttl,
+            ..
+        } = &cmd
+        {
+            // Decoded here since the import applies RESTORE as a load
+            let restored = rdb::decode_dump_payload(payload, true, IMPORT_EPOCH_MS)
+                .map_err(|e| format!("ERR {}", e))?;
+            let ttl_ms = match (*ttl_ms, *absttl) {
+                (0, _) => None,
+                (deadline, true) => Some(deadline.saturating_sub(now_unix_ms)),
+                (ttl, false) => Some(ttl),
+            };
+            self.replace(key.clone(), Staged::Restored(restored), ttl_ms);
+            return Ok(());
+        }
+        if !self.fold(&cmd) {
+            self.seal();
+            self.sealed.extend(cmd.get_keys());
+            self.steps.push(ImportStep::Execute(cmd));
+        }
+        Ok(())
+    }
+
+    /// Fold `cmd` into the open batch; false if it must execute instead
+    fn fold(&mut self, cmd: &Command) -> bool {
+        match cmd {
+            Command::Set {
+                key,
+                value,
+                ex,
+                px,
+                exat: None,
+                pxat: None,
+                nx: false,
+                xx: false,
+                get: false,
+                keepttl: false,
+            } => {
+                let ttl_ms = match (ex, px) {
+                    (Some(secs), _) => Some((*secs as u64).saturating_mul(1000)),
+                    (_, Some(ms)) => Some(*ms as u64),
+                    _ => None,
+                };
+                self.replace(key.clone(), Staged::String(value.clone()), ttl_ms);
+                true
+            }
+            Command::RPush(key, values) => self.extend(
+                key,
+                |staged| match staged {
+                    Staged::List(items) => {
+                        items.extend(values.iter().cloned());
+                        true
+                    }
+                    _ => false,
+                },
+                || Staged::List(values.iter().cloned().collect()),
+            ),
+            Command::LPush(key, values) => self.extend(
+                key,
+                |staged| match staged {
+                    Staged::List(items) => {
+                        values.iter().for_each(|v| items.push_front(v.clone()));
+                        true
+                    }
+                    _ => false,
+                },
+                || Staged::List(values.iter().rev().cloned().collect()),
+            ),
+            Command::SAdd(key, members) => self.extend(
+                key,
+                |staged| match staged {
+                    Staged::Set(existing) => {
+                        existing.extend(members.iter().cloned());
+                        true
+                    }
+                    _ => false,
+                },
+                || Staged::Set(members.clone()),
+            ),
+            Command::HSet(key, pairs) => self.extend(
+                key,
+                |staged| match staged {
+                    Staged::Hash(existing) => {
+                        existing.extend(pairs.iter().cloned());
+                        true
+                    }
+                    _ => false,
+                },
+                || Staged::Hash(pairs.clone()),
+            ),
+            Command::ZAdd {
+                key,
+                pairs,
+                nx: false,
+                xx: false,
+                gt: false,
+                lt: false,
+                ch: false,
+            } => {
+                let members = || pairs.iter().map(|(score, m)| (m.clone(), *score));
+                self.extend(
+                    key,
+                    |staged| match staged {
+                        Staged::SortedSet(existing) => {
+                            existing.extend(members());
+                            true
+                        }
+                        _ => false,
+                    },
+                    || Staged::SortedSet(members().collect()),
+                )
+            }
+            Command::Expire {
+                key,
+                seconds,
+                nx: false,
+                xx: false,
+                gt: false,
+                lt: false,
+            } if *seconds > 0 => self.set_ttl(key, (*seconds as u64).saturating_mul(1000)),
+            Command::PExpire {
+                key,
+                milliseconds,
+                nx: false,
+                xx: false,
+                gt: false,
+                lt: false,
+            } if *milliseconds > 0 => self.set_ttl(key, *milliseconds as u64),
+            _ => false,
+        }
+    }
+
+    /// Start `key` afresh in the open batch (SET, RESTORE)
+    fn replace(&mut self, key: String, staged: Staged, ttl_ms: Option<u64>) {
+        match self.batch_index.get(&key) {
+            Some(&i) => self.batch[i] = (key, staged, ttl_ms),
+            None => {
+                self.batch_index.insert(key.clone(), self.batch.len());
+                self.batch.push((key, staged, ttl_ms));
+            }
+        }
+    }
+
+    /// Add to `key`: in place if the open batch created it with the right
+    /// type, as a new key if no step has written it yet
+    fn extend(
+        &mut self,
+        key: &str,
+        add: impl FnOnce(&mut Staged) -> bool,
+        create: impl FnOnce() -> Staged,
+    ) -> bool {
+        if let Some(&i) = self.batch_index.get(key) {
+            return add(&mut self.batch[i].1);
+        }
+        if self.sealed.contains(key) {
+            return false;
+        }
+        self.replace(key.to_string(), create(), None);
+        true
+    }
+
+    fn set_ttl(&mut self, key: &str, ttl_ms: u64) -> bool {
+        match self.batch_index.get(key) {
+            Some(&i) => {
+                self.batch[i].2 = Some(ttl_ms);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close the open batch into a load step
+    fn seal(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        let entries: Vec<BulkEntry> = self
+            .batch
+            .drain(..)
+            .map(|(key, staged, ttl_ms)| (key, staged.build(), ttl_ms))
+            .collect();
+        self.sealed
+            .extend(entries.iter().map(|(key, _, _)| key.clone()));
+        self.batch_index.clear();
+        self.steps.push(ImportStep::Load(entries));
+    }
+
+    fn finish(mut self) -> ImportPlan {
+        self.seal();
+        debug_assert!(
+            !self
+                .steps
+                .windows(2)
+                .any(|w| matches!(w, [ImportStep::Load(_), ImportStep::Load(_)])),
+            "Postcondition: consecutive loads are one batch"
+        );
+        ImportPlan {
+            steps: self.steps,
+            ..ImportPlan::default()
+        }
+    }
+}
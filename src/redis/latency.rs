@@ -0,0 +1,167 @@
+//! Latency monitor: the spikes behind LATENCY LATEST/HISTORY/RESET/DOCTOR.
+//!
+//! Callers time the operations and report them as named events:
+//! - `command`: a command through the regular path
+//! - `fast-command`: GET/SET through the production fast path
+//! - `expire-cycle`: an active expiry pass over a shard
+//! - `snapshot`: a point-in-time copy of the keyspace, this server's
+//!   counterpart of Redis's `fork`
+//!
+//! A sample is kept only when monitoring is on (`latency-monitor-threshold`
+//! above 0) and it took at least the threshold, as in Redis. Each event has
+//! a ring of the last `LATENCY_HISTORY_LEN` spikes; spikes in the same second
+//! are merged into one holding the worst of them. Production shards time
+//! with the wall clock, the simulated server with the sampled execution time
+//! of its `LatencyProfile`, and timestamps come from the executor's clock, so
+//! a seeded simulation replays the same history.
+//!
+//! `LatencyMonitor` is cheap to clone; every shard executor holds a handle to
+//! the same monitor, so LATENCY on any shard sees spikes from all of them.
+//! The threshold is read without the lock, so nothing is locked while
+//! monitoring is off.
+//!
+//! TigerStyle: All functions have precondition/postcondition assertions.
+
+use super::command::Command;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Spikes kept per event (Redis `LATENCY_TS_LEN`)
+pub const LATENCY_HISTORY_LEN: usize = 160;
+
+/// The event a command is timed as
+pub fn command_event(cmd: &Command) -> &'static str {
+    match cmd {
+        Command::DebugSnapshotRead(_) => "snapshot",
+        _ => "command",
+    }
+}
+
+/// One spike: when it happened (unix seconds) and how long it took
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySample {
+    pub time: i64,
+    pub latency_ms: u64,
+}
+
+/// The history of one event
+#[derive(Debug, Clone, Default)]
+pub struct LatencySeries {
+    /// Oldest first, at most `LATENCY_HISTORY_LEN`
+    pub samples: VecDeque<LatencySample>,
+    /// Worst spike since the event was last reset
+    pub max_ms: u64,
+}
+
+impl LatencySeries {
+    fn add(&mut self, sample: LatencySample) {
+        self.max_ms = self.max_ms.max(sample.latency_ms);
+        match self.samples.back_mut() {
+            Some(last) if last.time == sample.time => {
+                last.latency_ms = last.latency_ms.max(sample.latency_ms);
+            }
+            _ => {
+                if self.samples.len() == LATENCY_HISTORY_LEN {
+                    self.samples.pop_front();
+                }
+                self.samples.push_back(sample);
+            }
+        }
+
+        debug_assert!(
+            self.samples.len() <= LATENCY_HISTORY_LEN,
+            "Postcondition violated: a series holds at most {} samples",
+            LATENCY_HISTORY_LEN
+        );
+    }
+
+    pub fn latest(&self) -> Option<LatencySample> {
+        self.samples.back().copied()
+    }
+}
+
+/// Server-wide latency history, shared by every shard
+#[derive(Clone, Default)]
+pub struct LatencyMonitor {
+    /// Series by event name, so LATENCY LATEST lists them in a stable order
+    events: Arc<Mutex<BTreeMap<String, LatencySeries>>>,
+    /// `latency-monitor-threshold` in milliseconds; 0 turns monitoring off
+    threshold_ms: Arc<AtomicU64>,
+}
+
+impl LatencyMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn threshold_ms(&self) -> u64 {
+        self.threshold_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn set_threshold_ms(&self, threshold_ms: u64) {
+        self.threshold_ms.store(threshold_ms, Ordering::Relaxed);
+    }
+
+    /// Whether a spike of `latency_ms` would be kept
+    #[inline]
+    pub fn is_spike(&self, latency_ms: u64) -> bool {
+        let threshold = self.threshold_ms();
+        threshold > 0 && latency_ms >= threshold
+    }
+
+    /// Record `event` taking `latency_ms` at unix second `time`, if it is a
+    /// spike. Returns whether it was kept.
+    pub fn record(&self, event: &str, latency_ms: u64, time: i64) -> bool {
+        debug_assert!(
+            !event.is_empty(),
+            "Precondition: event name must not be empty"
+        );
+
+        if !self.is_spike(latency_ms) {
+            return false;
+        }
+        self.events
+            .lock()
+            .entry(event.to_string())
+            .or_default()
+            .add(LatencySample { time, latency_ms });
+        true
+    }
+
+    /// Every event with a spike, by name
+    pub fn events(&self) -> Vec<(String, LatencySeries)> {
+        self.events
+            .lock()
+            .iter()
+            .map(|(name, series)| (name.clone(), series.clone()))
+            .collect()
+    }
+
+    pub fn history(&self, event: &str) -> Option<LatencySeries> {
+        self.events.lock().get(event).cloned()
+    }
+
+    /// Forget the named events, or every event when `events` is empty.
+    /// Returns how many had a history.
+    pub fn reset(&self, events: &[String]) -> usize {
+        let mut table = self.events.lock();
+        let reset = if events.is_empty() {
+            let n = table.len();
+            table.clear();
+            n
+        } else {
+            events
+                .iter()
+                .filter(|event| table.remove(event.as_str()).is_some())
+                .count()
+        };
+
+        debug_assert!(
+            !events.is_empty() || table.is_empty(),
+            "Postcondition violated: a full reset leaves no history"
+        );
+        reset
+    }
+}
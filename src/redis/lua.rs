@@ -7,9 +7,9 @@
 //!
 //! TigerStyle: All functions have precondition/postcondition assertions.
 
+use crate::sync::{Arc, RwLock};
 use ahash::AHashMap;
 use sha1::{Digest, Sha1};
-use std::sync::{Arc, RwLock};
 
 /// Script cache for EVALSHA - maps SHA1 -> script source
 #[derive(Debug, Default)]
@@ -26,10 +26,7 @@ impl ScriptCache {
 
     /// Compute SHA1 hash of a script
     pub fn compute_sha1(script: &str) -> String {
-        let mut hasher = Sha1::new();
-        hasher.update(script.as_bytes());
-        let result = hasher.finalize();
-        hex_encode(&result)
+        sha1_hex(script.as_bytes())
     }
 
     /// Cache a script and return its SHA1
@@ -62,6 +59,13 @@ impl ScriptCache {
     }
 }
 
+/// Lowercase hex SHA1 of `bytes`, as script SHA1s and `redis.sha1hex` give it
+pub fn sha1_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    hex_encode(&hasher.finalize())
+}
+
 /// Simple hex encoding (avoid external dependency)
 fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
@@ -1,43 +1,83 @@
+pub mod blocking;
 mod command;
+mod command_macro;
+mod command_table;
 mod commands;
+mod connection_state;
 mod data;
+mod declared_commands;
 mod executor;
 pub mod executor_dst;
+pub mod fixture;
+pub mod functions;
 pub mod hash_dst;
+pub mod import;
+pub mod latency;
 pub mod list_dst;
 pub mod lua;
 mod parser;
+pub mod pubsub;
+pub mod pubsub_dst;
+pub mod rdb;
 mod resp;
 mod resp_optimized;
+pub mod script_monitor;
 mod server;
 pub mod set_dst;
 pub mod sorted_set_dst;
+pub mod stream_dst;
+pub mod tracking;
 pub mod transaction_dst;
 #[cfg(test)]
 mod tests;
 
-pub use command::Command;
-pub use data::{RedisHash, RedisList, RedisSet, RedisSortedSet, Value, SDS};
-pub use executor::CommandExecutor;
+pub use blocking::{BlockedClient, BlockingManager};
+pub use command::{
+    BitFieldOp, BitFieldOverflow, BitFieldType, BitOperation, BitRange, BitUnit, Command,
+    ExpireCondition, FieldTtlForm, FunctionRestorePolicy, ZAggregate, ZRangeSpec, ZSetOperation,
+};
+pub use command_table::{CommandSpec, COMMAND_TABLE};
+pub use connection_state::ConnectionState;
+pub use declared_commands::DeclaredCommand;
+pub use data::{
+    listpack, ListpackEntry, RedisHash, RedisList, RedisSet, RedisSortedSet, RedisStream,
+    SanitizePayload, Value, SDS,
+};
+pub use executor::{
+    BulkEntry, BulkLoadReport, CommandExecutor, EvictionPolicy, ExpiryOutlook, GlobPattern,
+    KeyStatsReport, KeyspaceSnapshot, KeyspaceStats, MemoryStats, ValueKind, EXPIRY_HORIZONS,
+    LAZYFREE_THRESHOLD,
+};
 pub use executor_dst::{
     run_executor_batch, summarize_executor_batch, ExecutorDSTConfig, ExecutorDSTHarness,
     ExecutorDSTResult,
 };
+pub use functions::{FunctionInfo, FunctionLibraries, Library};
 pub use hash_dst::{
     run_hash_batch, summarize_hash_batch, HashDSTConfig, HashDSTHarness, HashDSTResult,
 };
 pub use list_dst::{
     run_list_batch, summarize_list_batch, ListDSTConfig, ListDSTHarness, ListDSTResult,
 };
+pub use latency::{LatencyMonitor, LatencySample, LatencySeries, LATENCY_HISTORY_LEN};
 pub use lua::ScriptCache;
-pub use resp::{RespParser, RespValue};
+pub use pubsub::{PubSubManager, PubSubMessage, PubSubSession};
+pub use pubsub_dst::{
+    run_pubsub_batch, summarize_pubsub_batch, PubSubDSTConfig, PubSubDSTHarness, PubSubDSTResult,
+};
+pub use resp::{double_text, ProtocolLimits, RespParser, RespValue};
 pub use resp_optimized::{BufferPool, RespCodec, RespValueZeroCopy};
-pub use server::{RedisClient, RedisServer};
+pub use script_monitor::{ScriptMonitor, ScriptRun, BUSY_ERROR};
+pub use server::{QueueStats, RedisClient, RedisServer};
 pub use set_dst::{run_set_batch, summarize_set_batch, SetDSTConfig, SetDSTHarness, SetDSTResult};
 pub use sorted_set_dst::{
     run_sorted_set_batch, summarize_batch, SortedSetDSTConfig, SortedSetDSTHarness,
     SortedSetDSTResult,
 };
+pub use stream_dst::{
+    run_stream_batch, summarize_stream_batch, StreamDSTConfig, StreamDSTHarness, StreamDSTResult,
+};
+pub use tracking::{ClientTracking, Invalidation, TrackingMode, TrackingSession};
 pub use transaction_dst::{
     run_transaction_batch, summarize_transaction_batch, TransactionDSTConfig,
     TransactionDSTHarness, TransactionDSTResult,
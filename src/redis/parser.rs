@@ -10,10 +10,18 @@
 //! Splitting this match statement would reduce readability without meaningful
 //! benefit. See DEV-001 for file size deviation tracking.
 
-use super::command::Command;
-use super::data::SDS;
+use super::blocking;
+use super::command::{
+    BitFieldOp, BitFieldOverflow, BitFieldType, BitOperation, BitRange, BitUnit, Command,
+    ExpireCondition, FieldTtlForm, FunctionRestorePolicy, ZAggregate, ZRangeSpec, ZSetOperation,
+};
+use super::declared_commands::DeclaredCommand;
+use super::command_table;
+use super::data::{ClaimOptions, PendingRange, StreamId, StreamIdSpec, SDS};
 use super::resp::RespValue;
 
+const INVALID_STREAM_ID: &str = "ERR Invalid stream ID specified as stream command argument";
+
 impl Command {
     /// Parse a RESP protocol value into a Command.
     ///
@@ -33,6 +41,10 @@ impl Command {
                     }
                     _ => return Err("Invalid command format".to_string()),
                 };
+                command_table::validate(&cmd_name, elements.len(), |i| match &elements[i] {
+                    RespValue::BulkString(Some(data)) => Some(data.as_slice()),
+                    _ => None,
+                })?;
 
                 match cmd_name.as_str() {
                     "PING" => {
@@ -47,17 +59,17 @@ impl Command {
                     "TIME" => Ok(Command::Time),
                     "DBSIZE" => Ok(Command::DbSize),
                     "CONFIG" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'config' command".to_string());
-                        }
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "GET" => {
-                                if elements.len() != 3 {
+                                if elements.len() < 3 {
                                     return Err("ERR wrong number of arguments for 'config|get' command".to_string());
                                 }
-                                let pattern = Self::extract_string(&elements[2])?;
-                                Ok(Command::ConfigGet(pattern))
+                                let patterns = elements[2..]
+                                    .iter()
+                                    .map(Self::extract_string)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                Ok(Command::ConfigGet(patterns))
                             }
                             "SET" => {
                                 if elements.len() != 4 {
@@ -72,9 +84,6 @@ impl Command {
                         }
                     }
                     "SELECT" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'select' command".to_string());
-                        }
                         let db = Self::extract_u64(&elements[1])?;
                         if db > 15 {
                             return Err("ERR DB index is out of range".to_string());
@@ -82,12 +91,69 @@ impl Command {
                         Ok(Command::Select(db))
                     }
                     "ECHO" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'echo' command".to_string());
-                        }
                         let msg = Self::extract_sds(&elements[1])?;
                         Ok(Command::Echo(msg))
                     }
+                    "PUBLISH" => {
+                        let channel = Self::extract_string(&elements[1])?;
+                        let message = Self::extract_sds(&elements[2])?;
+                        Ok(Command::Publish { channel, message })
+                    }
+                    "SUBSCRIBE" => {
+                        let channels = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::Subscribe(channels))
+                    }
+                    "UNSUBSCRIBE" => {
+                        let channels = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::Unsubscribe(channels))
+                    }
+                    "PSUBSCRIBE" => {
+                        let patterns = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::PSubscribe(patterns))
+                    }
+                    "PUNSUBSCRIBE" => {
+                        let patterns = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::PUnsubscribe(patterns))
+                    }
+                    "PUBSUB" => {
+                        let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
+                        match subcommand.as_str() {
+                            "CHANNELS" => match elements.len() {
+                                2 => Ok(Command::PubSubChannels(None)),
+                                3 => Ok(Command::PubSubChannels(Some(Self::extract_string(&elements[2])?))),
+                                _ => Err("ERR wrong number of arguments for 'pubsub|channels' command".to_string()),
+                            },
+                            "NUMSUB" => {
+                                let channels = elements[2..]
+                                    .iter()
+                                    .map(Self::extract_string)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                Ok(Command::PubSubNumSub(channels))
+                            }
+                            "NUMPAT" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'pubsub|numpat' command".to_string());
+                                }
+                                Ok(Command::PubSubNumPat)
+                            }
+                            _ => Err(format!(
+                                "ERR unknown subcommand '{}'. Try PUBSUB HELP.",
+                                subcommand.to_lowercase()
+                            )),
+                        }
+                    }
                     "AUTH" => {
                         match elements.len() {
                             2 => {
@@ -111,9 +177,6 @@ impl Command {
                         }
                     }
                     "ACL" => {
-                        if elements.len() < 2 {
-                            return Err("ACL requires a subcommand".to_string());
-                        }
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "WHOAMI" => Ok(Command::AclWhoami),
@@ -164,7 +227,7 @@ impl Command {
                                     Some(
                                         Self::extract_string(&elements[2])?
                                             .parse::<u32>()
-                                            .map_err(|_| "Invalid bits value")?,
+                                            .map_err(|_| "ERR ACL GENPASS argument must be the number of bits for the output password, a positive number up to 4096")?,
                                     )
                                 } else {
                                     None
@@ -222,9 +285,6 @@ impl Command {
                     "EXEC" => Ok(Command::Exec),
                     "DISCARD" => Ok(Command::Discard),
                     "WATCH" => {
-                        if elements.len() < 2 {
-                            return Err("WATCH requires at least 1 argument".to_string());
-                        }
                         let keys: Vec<String> = elements[1..]
                             .iter()
                             .map(Self::extract_string)
@@ -232,16 +292,14 @@ impl Command {
                         Ok(Command::Watch(keys))
                     }
                     "UNWATCH" => Ok(Command::Unwatch),
-                    "EVAL" => {
-                        if elements.len() < 3 {
-                            return Err("EVAL requires at least 2 arguments".to_string());
-                        }
+                    "RESET" => Ok(Command::Reset),
+                    "EVAL" | "EVAL_RO" => {
                         let script = Self::extract_string(&elements[1])?;
                         let numkeys = Self::extract_integer(&elements[2])? as usize;
 
                         // Validate we have enough arguments
                         if elements.len() < 3 + numkeys {
-                            return Err("EVAL wrong number of keys".to_string());
+                            return Err(format!("{} wrong number of keys", cmd_name));
                         }
 
                         let keys: Vec<String> = elements[3..3 + numkeys]
@@ -254,18 +312,19 @@ impl Command {
                             .map(Self::extract_sds)
                             .collect::<Result<Vec<_>, _>>()?;
 
-                        Ok(Command::Eval { script, keys, args })
-                    }
-                    "EVALSHA" => {
-                        if elements.len() < 3 {
-                            return Err("EVALSHA requires at least 2 arguments".to_string());
+                        if cmd_name == "EVAL_RO" {
+                            Ok(Command::EvalRo { script, keys, args })
+                        } else {
+                            Ok(Command::Eval { script, keys, args })
                         }
+                    }
+                    "EVALSHA" | "EVALSHA_RO" => {
                         let sha1 = Self::extract_string(&elements[1])?;
                         let numkeys = Self::extract_integer(&elements[2])? as usize;
 
                         // Validate we have enough arguments
                         if elements.len() < 3 + numkeys {
-                            return Err("EVALSHA wrong number of keys".to_string());
+                            return Err(format!("{} wrong number of keys", cmd_name));
                         }
 
                         let keys: Vec<String> = elements[3..3 + numkeys]
@@ -278,12 +337,51 @@ impl Command {
                             .map(Self::extract_sds)
                             .collect::<Result<Vec<_>, _>>()?;
 
-                        Ok(Command::EvalSha { sha1, keys, args })
+                        if cmd_name == "EVALSHA_RO" {
+                            Ok(Command::EvalShaRo { sha1, keys, args })
+                        } else {
+                            Ok(Command::EvalSha { sha1, keys, args })
+                        }
                     }
-                    "SCRIPT" => {
-                        if elements.len() < 2 {
-                            return Err("SCRIPT requires a subcommand".to_string());
+                    "FCALL" | "FCALL_RO" => {
+                        let function = Self::extract_string(&elements[1])?;
+                        let numkeys = Self::extract_integer(&elements[2])?;
+                        if numkeys < 0 {
+                            return Err("ERR Number of keys can't be negative".to_string());
+                        }
+                        let numkeys = numkeys as usize;
+                        if numkeys > elements.len() - 3 {
+                            return Err(
+                                "ERR Number of keys can't be greater than number of args"
+                                    .to_string(),
+                            );
+                        }
+
+                        let keys: Vec<String> = elements[3..3 + numkeys]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        let args: Vec<SDS> = elements[3 + numkeys..]
+                            .iter()
+                            .map(Self::extract_sds)
+                            .collect::<Result<Vec<_>, _>>()?;
+
+                        if cmd_name == "FCALL_RO" {
+                            Ok(Command::FCallRo {
+                                function,
+                                keys,
+                                args,
+                            })
+                        } else {
+                            Ok(Command::FCall {
+                                function,
+                                keys,
+                                args,
+                            })
                         }
+                    }
+                    "SCRIPT" => {
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "LOAD" => {
@@ -306,20 +404,15 @@ impl Command {
                                 Ok(Command::ScriptExists(sha1s))
                             }
                             "FLUSH" => Ok(Command::ScriptFlush),
+                            "KILL" => Ok(Command::ScriptKill),
                             _ => Err(format!("Unknown SCRIPT subcommand '{}'", subcommand)),
                         }
                     }
                     "GET" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'get' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::Get(key))
                     }
                     "SET" => {
-                        if elements.len() < 3 {
-                            return Err("SET requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let value = Self::extract_sds(&elements[2])?;
 
@@ -388,6 +481,7 @@ impl Command {
                         if keepttl && (ex.is_some() || px.is_some() || exat.is_some() || pxat.is_some()) {
                             return Err("ERR syntax error".to_string());
                         }
+                        Self::check_expire("set", ex, px, exat, pxat)?;
 
                         Ok(Command::Set {
                             key,
@@ -403,11 +497,9 @@ impl Command {
                         })
                     }
                     "SETEX" => {
-                        if elements.len() != 4 {
-                            return Err("SETEX requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
-                        let seconds = Self::extract_integer(&elements[2])? as i64;
+                        let seconds = Self::extract_i64(&elements[2])?;
+                        Self::check_expire("setex", Some(seconds), None, None, None)?;
                         let value = Self::extract_sds(&elements[3])?;
                         Ok(Command::Set {
                             key,
@@ -422,18 +514,7 @@ impl Command {
                             keepttl: false,
                         })
                     }
-                    "SETNX" => {
-                        if elements.len() != 3 {
-                            return Err("SETNX requires 2 arguments".to_string());
-                        }
-                        let key = Self::extract_string(&elements[1])?;
-                        let value = Self::extract_sds(&elements[2])?;
-                        Ok(Command::SetNx(key, value))
-                    }
                     "DEL" => {
-                        if elements.len() < 2 {
-                            return Err("DEL requires at least 1 argument".to_string());
-                        }
                         let keys: Vec<String> = elements[1..]
                             .iter()
                             .map(Self::extract_string)
@@ -441,33 +522,28 @@ impl Command {
                         Ok(Command::Del(keys))
                     }
                     "EXISTS" => {
-                        if elements.len() < 2 {
-                            return Err("EXISTS requires at least 1 argument".to_string());
-                        }
                         let keys = elements[1..]
                             .iter()
                             .map(Self::extract_string)
                             .collect::<Result<Vec<_>, _>>()?;
                         Ok(Command::Exists(keys))
                     }
+                    "TOUCH" => {
+                        let keys = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::Touch(keys))
+                    }
                     "TYPE" => {
-                        if elements.len() != 2 {
-                            return Err("TYPE requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::TypeOf(key))
                     }
                     "KEYS" => {
-                        if elements.len() != 2 {
-                            return Err("KEYS requires 1 argument".to_string());
-                        }
                         let pattern = Self::extract_string(&elements[1])?;
                         Ok(Command::Keys(pattern))
                     }
                     "EXPIRE" => {
-                        if elements.len() < 3 {
-                            return Err("EXPIRE requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let seconds = Self::extract_integer(&elements[2])? as i64;
                         let mut nx = false;
@@ -495,9 +571,6 @@ impl Command {
                         Ok(Command::Expire { key, seconds, nx, xx, gt, lt })
                     }
                     "PEXPIRE" => {
-                        if elements.len() < 3 {
-                            return Err("PEXPIRE requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let milliseconds = Self::extract_integer(&elements[2])? as i64;
                         let mut nx = false;
@@ -524,100 +597,51 @@ impl Command {
                         }
                         Ok(Command::PExpire { key, milliseconds, nx, xx, gt, lt })
                     }
-                    "EXPIREAT" => {
-                        if elements.len() != 3 {
-                            return Err("EXPIREAT requires 2 arguments".to_string());
-                        }
-                        let key = Self::extract_string(&elements[1])?;
-                        let timestamp = Self::extract_integer(&elements[2])? as i64;
-                        Ok(Command::ExpireAt(key, timestamp))
-                    }
-                    "PEXPIREAT" => {
-                        if elements.len() != 3 {
-                            return Err("PEXPIREAT requires 2 arguments".to_string());
-                        }
-                        let key = Self::extract_string(&elements[1])?;
-                        let timestamp_millis = Self::extract_integer(&elements[2])? as i64;
-                        Ok(Command::PExpireAt(key, timestamp_millis))
-                    }
                     "TTL" => {
-                        if elements.len() != 2 {
-                            return Err("TTL requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::Ttl(key))
                     }
                     "PTTL" => {
-                        if elements.len() != 2 {
-                            return Err("PTTL requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::Pttl(key))
                     }
                     "PERSIST" => {
-                        if elements.len() != 2 {
-                            return Err("PERSIST requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::Persist(key))
                     }
                     "INCR" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'incr' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::Incr(key))
                     }
                     "DECR" => {
-                        if elements.len() != 2 {
-                            return Err("ERR wrong number of arguments for 'decr' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::Decr(key))
                     }
                     "INCRBY" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'incrby' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let increment = Self::extract_integer(&elements[2])? as i64;
                         Ok(Command::IncrBy(key, increment))
                     }
                     "DECRBY" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'decrby' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let decrement = Self::extract_integer(&elements[2])? as i64;
                         Ok(Command::DecrBy(key, decrement))
                     }
                     "APPEND" => {
-                        if elements.len() != 3 {
-                            return Err("APPEND requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let value = Self::extract_sds(&elements[2])?;
                         Ok(Command::Append(key, value))
                     }
                     "GETSET" => {
-                        if elements.len() != 3 {
-                            return Err("GETSET requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let value = Self::extract_sds(&elements[2])?;
                         Ok(Command::GetSet(key, value))
                     }
                     "STRLEN" => {
-                        if elements.len() != 2 {
-                            return Err("STRLEN requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::StrLen(key))
                     }
                     "MGET" => {
-                        if elements.len() < 2 {
-                            return Err("MGET requires at least 1 argument".to_string());
-                        }
                         let keys = elements[1..]
                             .iter()
                             .map(Self::extract_string)
@@ -650,9 +674,6 @@ impl Command {
                         Ok(Command::MSetNx(pairs))
                     }
                     "LPUSH" => {
-                        if elements.len() < 3 {
-                            return Err("LPUSH requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let values = elements[2..]
                             .iter()
@@ -661,9 +682,6 @@ impl Command {
                         Ok(Command::LPush(key, values))
                     }
                     "RPUSH" => {
-                        if elements.len() < 3 {
-                            return Err("RPUSH requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let values = elements[2..]
                             .iter()
@@ -673,72 +691,51 @@ impl Command {
                     }
                     "LPOP" => {
                         if elements.len() != 2 {
-                            return Err("LPOP requires 1 argument".to_string());
+                            return Err("ERR wrong number of arguments for 'lpop' command".to_string());
                         }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::LPop(key))
                     }
                     "RPOP" => {
                         if elements.len() != 2 {
-                            return Err("RPOP requires 1 argument".to_string());
+                            return Err("ERR wrong number of arguments for 'rpop' command".to_string());
                         }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::RPop(key))
                     }
                     "LRANGE" => {
-                        if elements.len() != 4 {
-                            return Err("LRANGE requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let start = Self::extract_integer(&elements[2])?;
                         let stop = Self::extract_integer(&elements[3])?;
                         Ok(Command::LRange(key, start, stop))
                     }
                     "LLEN" => {
-                        if elements.len() != 2 {
-                            return Err("LLEN requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::LLen(key))
                     }
                     "LINDEX" => {
-                        if elements.len() != 3 {
-                            return Err("LINDEX requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let index = Self::extract_integer(&elements[2])?;
                         Ok(Command::LIndex(key, index))
                     }
                     "LSET" => {
-                        if elements.len() != 4 {
-                            return Err("LSET requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let index = Self::extract_integer(&elements[2])?;
                         let value = Self::extract_sds(&elements[3])?;
                         Ok(Command::LSet(key, index, value))
                     }
                     "LTRIM" => {
-                        if elements.len() != 4 {
-                            return Err("LTRIM requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let start = Self::extract_integer(&elements[2])?;
                         let stop = Self::extract_integer(&elements[3])?;
                         Ok(Command::LTrim(key, start, stop))
                     }
                     "RPOPLPUSH" => {
-                        if elements.len() != 3 {
-                            return Err("RPOPLPUSH requires 2 arguments".to_string());
-                        }
                         let source = Self::extract_string(&elements[1])?;
                         let dest = Self::extract_string(&elements[2])?;
                         Ok(Command::RPopLPush(source, dest))
                     }
                     "LMOVE" => {
-                        if elements.len() != 5 {
-                            return Err("LMOVE requires 4 arguments".to_string());
-                        }
                         let source = Self::extract_string(&elements[1])?;
                         let dest = Self::extract_string(&elements[2])?;
                         let wherefrom = Self::extract_string(&elements[3])?.to_uppercase();
@@ -756,10 +753,82 @@ impl Command {
                             whereto,
                         })
                     }
-                    "SADD" => {
-                        if elements.len() < 3 {
-                            return Err("SADD requires at least 2 arguments".to_string());
+                    "LINSERT" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let before = match Self::extract_string(&elements[2])?.to_ascii_uppercase().as_str() {
+                            "BEFORE" => true,
+                            "AFTER" => false,
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        let pivot = Self::extract_sds(&elements[3])?;
+                        let value = Self::extract_sds(&elements[4])?;
+                        Ok(Command::LInsert {
+                            key,
+                            before,
+                            pivot,
+                            value,
+                        })
+                    }
+                    "LREM" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let count = Self::extract_integer(&elements[2])?;
+                        let element = Self::extract_sds(&elements[3])?;
+                        Ok(Command::LRem(key, count, element))
+                    }
+                    "LPOS" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let element = Self::extract_sds(&elements[2])?;
+                        let options = elements[3..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_lpos(key, element, &options)
+                    }
+                    "LPUSHX" | "RPUSHX" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let values = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        if cmd_name == "LPUSHX" {
+                            Ok(Command::LPushX(key, values))
+                        } else {
+                            Ok(Command::RPushX(key, values))
+                        }
+                    }
+                    "BLPOP" | "BRPOP" => {
+                        let last = elements.len() - 1;
+                        let keys = elements[1..last]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let timeout_ms = blocking::parse_timeout(&Self::extract_string(&elements[last])?)?;
+                        if cmd_name == "BLPOP" {
+                            Ok(Command::BLPop { keys, timeout_ms })
+                        } else {
+                            Ok(Command::BRPop { keys, timeout_ms })
+                        }
+                    }
+                    "BLMOVE" => {
+                        let source = Self::extract_string(&elements[1])?;
+                        let dest = Self::extract_string(&elements[2])?;
+                        let wherefrom = Self::extract_string(&elements[3])?.to_uppercase();
+                        let whereto = Self::extract_string(&elements[4])?.to_uppercase();
+                        for side in [&wherefrom, &whereto] {
+                            if side != "LEFT" && side != "RIGHT" {
+                                return Err("ERR syntax error".to_string());
+                            }
                         }
+                        let timeout_ms = blocking::parse_timeout(&Self::extract_string(&elements[5])?)?;
+                        Ok(Command::BLMove {
+                            source,
+                            dest,
+                            wherefrom,
+                            whereto,
+                            timeout_ms,
+                        })
+                    }
+                    "SADD" => {
                         let key = Self::extract_string(&elements[1])?;
                         let members = elements[2..]
                             .iter()
@@ -768,24 +837,15 @@ impl Command {
                         Ok(Command::SAdd(key, members))
                     }
                     "SMEMBERS" => {
-                        if elements.len() != 2 {
-                            return Err("SMEMBERS requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::SMembers(key))
                     }
                     "SISMEMBER" => {
-                        if elements.len() != 3 {
-                            return Err("SISMEMBER requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let member = Self::extract_sds(&elements[2])?;
                         Ok(Command::SIsMember(key, member))
                     }
                     "SREM" => {
-                        if elements.len() < 3 {
-                            return Err("SREM requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let members = elements[2..]
                             .iter()
@@ -794,17 +854,11 @@ impl Command {
                         Ok(Command::SRem(key, members))
                     }
                     "SCARD" => {
-                        if elements.len() != 2 {
-                            return Err("SCARD requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::SCard(key))
                     }
                     "SPOP" => {
                         // SPOP key [count]
-                        if elements.len() < 2 || elements.len() > 3 {
-                            return Err("SPOP requires 1 or 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let count = if elements.len() == 3 {
                             let count_str = Self::extract_string(&elements[2])?;
@@ -818,6 +872,34 @@ impl Command {
                         };
                         Ok(Command::SPop(key, count))
                     }
+                    "SMISMEMBER" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let members = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::SMIsMember(key, members))
+                    }
+                    "SRANDMEMBER" => {
+                        // SRANDMEMBER key [count]
+                        let key = Self::extract_string(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(arg) => Some(
+                                Self::extract_string(arg)?
+                                    .parse::<i64>()
+                                    .map_err(|_| "ERR value is not an integer or out of range")?,
+                            ),
+                            None => None,
+                        };
+                        Ok(Command::SRandMember(key, count))
+                    }
+                    "SINTERCARD" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_sintercard(args)
+                    }
                     "HSET" => {
                         // HSET key field value [field value ...]
                         if elements.len() < 4 || (elements.len() - 2) % 2 != 0 {
@@ -833,73 +915,76 @@ impl Command {
                         Ok(Command::HSet(key, pairs))
                     }
                     "HGET" => {
-                        if elements.len() != 3 {
-                            return Err("HGET requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let field = Self::extract_sds(&elements[2])?;
                         Ok(Command::HGet(key, field))
                     }
                     "HGETALL" => {
-                        if elements.len() != 2 {
-                            return Err("HGETALL requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::HGetAll(key))
                     }
                     "HINCRBY" => {
-                        if elements.len() != 4 {
-                            return Err("HINCRBY requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let field = Self::extract_sds(&elements[2])?;
                         let increment = Self::extract_i64(&elements[3])?;
                         Ok(Command::HIncrBy(key, field, increment))
                     }
-                    "HDEL" => {
-                        if elements.len() < 3 {
-                            return Err("HDEL requires at least 2 arguments".to_string());
+                    "HINCRBYFLOAT" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let field = Self::extract_sds(&elements[2])?;
+                        let increment = Self::extract_float(&elements[3])?;
+                        if increment.is_nan() || increment.is_infinite() {
+                            return Err("ERR value is NaN or Infinity".to_string());
                         }
+                        Ok(Command::HIncrByFloat(key, field, increment))
+                    }
+                    "HMGET" => {
                         let key = Self::extract_string(&elements[1])?;
                         let fields = elements[2..]
                             .iter()
                             .map(Self::extract_sds)
                             .collect::<Result<Vec<_>, _>>()?;
-                        Ok(Command::HDel(key, fields))
+                        Ok(Command::HMGet(key, fields))
                     }
-                    "HKEYS" => {
-                        if elements.len() != 2 {
-                            return Err("HKEYS requires 1 argument".to_string());
-                        }
+                    "HRANDFIELD" => {
                         let key = Self::extract_string(&elements[1])?;
-                        Ok(Command::HKeys(key))
+                        let count = match elements.get(2) {
+                            Some(e) => Some(Self::extract_i64(e)?),
+                            None => None,
+                        };
+                        let with_values = match elements.get(3) {
+                            Some(e) if Self::extract_string(e)?.eq_ignore_ascii_case("WITHVALUES") => true,
+                            Some(_) => return Err("ERR syntax error".to_string()),
+                            None => false,
+                        };
+                        Ok(Command::HRandField { key, count, with_values })
                     }
-                    "HVALS" => {
-                        if elements.len() != 2 {
-                            return Err("HVALS requires 1 argument".to_string());
-                        }
+                    "HEXPIRE" | "HPEXPIRE" | "HEXPIREAT" | "HPEXPIREAT" | "HTTL" | "HPTTL"
+                    | "HEXPIRETIME" | "HPEXPIRETIME" | "HPERSIST" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_field_ttl(&cmd_name, args)
+                    }
+                    "HDEL" => {
                         let key = Self::extract_string(&elements[1])?;
-                        Ok(Command::HVals(key))
+                        let fields = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::HDel(key, fields))
                     }
                     "HLEN" => {
-                        if elements.len() != 2 {
-                            return Err("HLEN requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::HLen(key))
                     }
                     "HEXISTS" => {
-                        if elements.len() != 3 {
-                            return Err("HEXISTS requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let field = Self::extract_sds(&elements[2])?;
                         Ok(Command::HExists(key, field))
                     }
                     "ZADD" => {
-                        if elements.len() < 4 {
-                            return Err("ZADD requires key and score-member pairs".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
 
                         // Parse optional flags (NX, XX, GT, LT, CH)
@@ -959,24 +1044,23 @@ impl Command {
                             ch,
                         })
                     }
-                    "ZRANGE" => {
-                        if elements.len() < 4 || elements.len() > 5 {
-                            return Err("ZRANGE requires 3 or 4 arguments".to_string());
+                    "ZRANGE" | "ZRANGESTORE" => {
+                        let store = cmd_name == "ZRANGESTORE";
+                        if elements.len() < 4 + usize::from(store) {
+                            return Err(format!(
+                                "ERR wrong number of arguments for '{}' command",
+                                cmd_name.to_ascii_lowercase()
+                            ));
                         }
-                        let key = Self::extract_string(&elements[1])?;
-                        let start = Self::extract_integer(&elements[2])?;
-                        let stop = Self::extract_integer(&elements[3])?;
-                        let with_scores = if elements.len() == 5 {
-                            let opt = Self::extract_string(&elements[4])?.to_uppercase();
-                            opt == "WITHSCORES"
-                        } else {
-                            false
-                        };
-                        Ok(Command::ZRange(key, start, stop, with_scores))
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_zrange(store, args)
                     }
                     "ZREVRANGE" => {
                         if elements.len() < 4 || elements.len() > 5 {
-                            return Err("ZREVRANGE requires 3 or 4 arguments".to_string());
+                            return Err("ERR wrong number of arguments for 'zrevrange' command".to_string());
                         }
                         let key = Self::extract_string(&elements[1])?;
                         let start = Self::extract_integer(&elements[2])?;
@@ -990,17 +1074,11 @@ impl Command {
                         Ok(Command::ZRevRange(key, start, stop, with_scores))
                     }
                     "ZSCORE" => {
-                        if elements.len() != 3 {
-                            return Err("ZSCORE requires 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let member = Self::extract_sds(&elements[2])?;
                         Ok(Command::ZScore(key, member))
                     }
                     "ZREM" => {
-                        if elements.len() < 3 {
-                            return Err("ZREM requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let members = elements[2..]
                             .iter()
@@ -1008,34 +1086,33 @@ impl Command {
                             .collect::<Result<Vec<_>, _>>()?;
                         Ok(Command::ZRem(key, members))
                     }
-                    "ZRANK" => {
-                        if elements.len() != 3 {
-                            return Err("ZRANK requires 2 arguments".to_string());
-                        }
+                    "ZREMRANGEBYRANK" | "ZREMRANGEBYSCORE" | "ZREMRANGEBYLEX" => {
                         let key = Self::extract_string(&elements[1])?;
-                        let member = Self::extract_sds(&elements[2])?;
-                        Ok(Command::ZRank(key, member))
+                        let (min, max) = (Self::extract_string(&elements[2])?, Self::extract_string(&elements[3])?);
+                        let range = match cmd_name.as_str() {
+                            "ZREMRANGEBYRANK" => {
+                                let parse = |s: &str| {
+                                    s.parse::<isize>()
+                                        .map_err(|_| "ERR value is not an integer or out of range".to_string())
+                                };
+                                ZRangeSpec::Rank(parse(&min)?, parse(&max)?)
+                            }
+                            "ZREMRANGEBYSCORE" => ZRangeSpec::Score(min, max),
+                            _ => ZRangeSpec::Lex(min, max),
+                        };
+                        Ok(Command::ZRemRange(key, range))
                     }
                     "ZCARD" => {
-                        if elements.len() != 2 {
-                            return Err("ZCARD requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::ZCard(key))
                     }
                     "ZCOUNT" => {
-                        if elements.len() != 4 {
-                            return Err("ZCOUNT requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let min = Self::extract_string(&elements[2])?;
                         let max = Self::extract_string(&elements[3])?;
                         Ok(Command::ZCount(key, min, max))
                     }
                     "ZRANGEBYSCORE" => {
-                        if elements.len() < 4 {
-                            return Err("ZRANGEBYSCORE requires at least 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let min = Self::extract_string(&elements[2])?;
                         let max = Self::extract_string(&elements[3])?;
@@ -1067,39 +1144,113 @@ impl Command {
                             limit,
                         })
                     }
-                    "SCAN" => {
-                        if elements.len() < 2 {
-                            return Err("SCAN requires at least 1 argument".to_string());
-                        }
-                        let cursor = Self::extract_u64(&elements[1])?;
-                        let mut pattern = None;
-                        let mut count = None;
-                        let mut i = 2;
-                        while i < elements.len() {
-                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
-                            match opt.as_str() {
-                                "MATCH" => {
-                                    i += 1;
-                                    pattern = Some(Self::extract_string(&elements[i])?);
-                                }
-                                "COUNT" => {
-                                    i += 1;
-                                    count = Some(Self::extract_integer(&elements[i])? as usize);
+                    "ZPOPMIN" | "ZPOPMAX" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(e) => {
+                                let n = Self::extract_integer(e)?;
+                                if n < 0 {
+                                    return Err("ERR value is out of range, must be positive".to_string());
                                 }
-                                _ => return Err(format!("Unknown SCAN option: {}", opt)),
+                                Some(n as usize)
                             }
-                            i += 1;
-                        }
+                            None => None,
+                        };
+                        if cmd_name == "ZPOPMIN" {
+                            Ok(Command::ZPopMin { key, count })
+                        } else {
+                            Ok(Command::ZPopMax { key, count })
+                        }
+                    }
+                    "ZINCRBY" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let increment = Self::extract_float(&elements[2])?;
+                        if increment.is_nan() {
+                            return Err("ERR value is not a valid float".to_string());
+                        }
+                        let member = Self::extract_sds(&elements[3])?;
+                        Ok(Command::ZIncrBy(key, increment, member))
+                    }
+                    "ZMSCORE" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let members = elements[2..]
+                            .iter()
+                            .map(Self::extract_sds)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::ZMScore(key, members))
+                    }
+                    "ZRANDMEMBER" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let count = match elements.get(2) {
+                            Some(e) => Some(Self::extract_i64(e)?),
+                            None => None,
+                        };
+                        let with_scores = match elements.get(3) {
+                            Some(e) if Self::extract_string(e)?.eq_ignore_ascii_case("WITHSCORES") => true,
+                            Some(_) => return Err("ERR syntax error".to_string()),
+                            None => false,
+                        };
+                        Ok(Command::ZRandMember { key, count, with_scores })
+                    }
+                    "ZUNION" | "ZINTER" | "ZDIFF" | "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => {
+                        let op = match cmd_name.as_str() {
+                            "ZUNION" | "ZUNIONSTORE" => ZSetOperation::Union,
+                            "ZINTER" | "ZINTERSTORE" => ZSetOperation::Inter,
+                            _ => ZSetOperation::Diff,
+                        };
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let name = cmd_name.to_ascii_lowercase();
+                        Self::parse_zset_op(&name, op, name.ends_with("store"), args)
+                    }
+                    "BZPOPMIN" | "BZPOPMAX" => {
+                        let last = elements.len() - 1;
+                        let keys = elements[1..last]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let timeout_ms = blocking::parse_timeout(&Self::extract_string(&elements[last])?)?;
+                        if cmd_name == "BZPOPMIN" {
+                            Ok(Command::BZPopMin { keys, timeout_ms })
+                        } else {
+                            Ok(Command::BZPopMax { keys, timeout_ms })
+                        }
+                    }
+                    "SCAN" => {
+                        let cursor = Self::extract_u64(&elements[1])?;
+                        let mut pattern = None;
+                        let mut count = None;
+                        let mut type_filter = None;
+                        let mut i = 2;
+                        while i < elements.len() {
+                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "MATCH" => {
+                                    i += 1;
+                                    pattern = Some(Self::extract_string(&elements[i])?);
+                                }
+                                "COUNT" => {
+                                    i += 1;
+                                    count = Some(Self::extract_integer(&elements[i])? as usize);
+                                }
+                                "TYPE" => {
+                                    i += 1;
+                                    type_filter = Some(Self::extract_string(&elements[i])?);
+                                }
+                                _ => return Err(format!("Unknown SCAN option: {}", opt)),
+                            }
+                            i += 1;
+                        }
                         Ok(Command::Scan {
                             cursor,
                             pattern,
                             count,
+                            type_filter,
                         })
                     }
                     "HSCAN" => {
-                        if elements.len() < 3 {
-                            return Err("HSCAN requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let cursor = Self::extract_u64(&elements[2])?;
                         let mut pattern = None;
@@ -1128,9 +1279,6 @@ impl Command {
                         })
                     }
                     "ZSCAN" => {
-                        if elements.len() < 3 {
-                            return Err("ZSCAN requires at least 2 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let cursor = Self::extract_u64(&elements[2])?;
                         let mut pattern = None;
@@ -1158,31 +1306,540 @@ impl Command {
                             count,
                         })
                     }
-                    "FUNCTION" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'function' command".to_string());
+                    "XADD" => {
+                        // XADD key [NOMKSTREAM] [MAXLEN [=|~] n] <* | id> field value [field value ...]
+                        let key = Self::extract_string(&elements[1])?;
+                        let mut nomkstream = false;
+                        let mut maxlen = None;
+                        let mut i = 2;
+                        while i < elements.len() {
+                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "NOMKSTREAM" => nomkstream = true,
+                                "MAXLEN" => {
+                                    i += 1;
+                                    if i < elements.len()
+                                        && matches!(Self::extract_string(&elements[i])?.as_str(), "=" | "~")
+                                    {
+                                        i += 1;
+                                    }
+                                    if i >= elements.len() {
+                                        return Err("ERR syntax error".to_string());
+                                    }
+                                    let n = Self::extract_integer(&elements[i])?;
+                                    if n < 0 {
+                                        return Err("ERR The MAXLEN argument must be >= 0.".to_string());
+                                    }
+                                    maxlen = Some(n as usize);
+                                }
+                                _ => break,
+                            }
+                            i += 1;
+                        }
+                        let rest = elements.len().saturating_sub(i + 1);
+                        if rest == 0 || rest % 2 != 0 {
+                            return Err("ERR wrong number of arguments for 'xadd' command".to_string());
                         }
+                        let id = StreamIdSpec::parse(&Self::extract_string(&elements[i])?)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let mut fields = Vec::with_capacity(rest / 2);
+                        for j in ((i + 1)..elements.len()).step_by(2) {
+                            fields.push((Self::extract_sds(&elements[j])?, Self::extract_sds(&elements[j + 1])?));
+                        }
+                        Ok(Command::XAdd {
+                            key,
+                            id,
+                            fields,
+                            nomkstream,
+                            maxlen,
+                        })
+                    }
+                    "XRANGE" | "XREVRANGE" => {
+                        // XRANGE takes `start end`, XREVRANGE takes `end start`
+                        let rev = cmd_name == "XREVRANGE";
+                        let key = Self::extract_string(&elements[1])?;
+                        let (low, high) = if rev { (3, 2) } else { (2, 3) };
+                        let start = StreamId::parse_bound(&Self::extract_string(&elements[low])?, true)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let end = StreamId::parse_bound(&Self::extract_string(&elements[high])?, false)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let count = match elements.len() {
+                            4 => None,
+                            6 if Self::extract_string(&elements[4])?.eq_ignore_ascii_case("COUNT") => {
+                                Some(Self::extract_integer(&elements[5])?.max(0) as usize)
+                            }
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        if rev {
+                            Ok(Command::XRevRange {
+                                key,
+                                start,
+                                end,
+                                count,
+                            })
+                        } else {
+                            Ok(Command::XRange {
+                                key,
+                                start,
+                                end,
+                                count,
+                            })
+                        }
+                    }
+                    "XREAD" => {
+                        // XREAD [COUNT n] [BLOCK ms] STREAMS key [key ...] id [id ...]
+                        let mut count = None;
+                        let mut block_ms = None;
+                        let mut i = 1;
+                        loop {
+                            if i >= elements.len() {
+                                return Err("ERR syntax error".to_string());
+                            }
+                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "COUNT" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    count = Some(Self::extract_integer(&elements[i])?.max(0) as usize);
+                                }
+                                "BLOCK" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    block_ms = Some(blocking::parse_block_ms(&Self::extract_string(&elements[i])?)?);
+                                }
+                                "STREAMS" => break,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        let rest = elements.len() - i - 1;
+                        if rest == 0 || rest % 2 != 0 {
+                            return Err("ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.".to_string());
+                        }
+                        let n = rest / 2;
+                        let mut keys = Vec::with_capacity(n);
+                        let mut ids = Vec::with_capacity(n);
+                        for j in 0..n {
+                            keys.push(Self::extract_string(&elements[i + 1 + j])?);
+                            let id = Self::extract_string(&elements[i + 1 + n + j])?;
+                            ids.push(if id == "$" {
+                                None
+                            } else {
+                                Some(
+                                    StreamId::parse(&id, 0)
+                                        .ok_or_else(|| INVALID_STREAM_ID.to_string())?,
+                                )
+                            });
+                        }
+                        Ok(Command::XRead {
+                            count,
+                            block_ms,
+                            keys,
+                            ids,
+                        })
+                    }
+                    "XGROUP" => {
+                        let sub = Self::extract_string(&elements[1])?.to_uppercase();
+                        let argc_ok = match sub.as_str() {
+                            "CREATE" => (5..=6).contains(&elements.len()),
+                            "SETID" | "CREATECONSUMER" | "DELCONSUMER" => elements.len() == 5,
+                            "DESTROY" => elements.len() == 4,
+                            _ => {
+                                return Err(format!(
+                                    "ERR unknown subcommand '{}'. Try XGROUP HELP.",
+                                    Self::extract_string(&elements[1])?
+                                ))
+                            }
+                        };
+                        if !argc_ok {
+                            return Err(format!(
+                                "ERR wrong number of arguments for 'xgroup|{}' command",
+                                sub.to_lowercase()
+                            ));
+                        }
+                        let key = Self::extract_string(&elements[2])?;
+                        let group = Self::extract_string(&elements[3])?;
+                        // `$` means the stream's current last ID
+                        let parse_id = |s: String| -> Result<Option<StreamId>, String> {
+                            if s == "$" {
+                                return Ok(None);
+                            }
+                            StreamId::parse(&s, 0)
+                                .map(Some)
+                                .ok_or_else(|| INVALID_STREAM_ID.to_string())
+                        };
+                        match sub.as_str() {
+                            "CREATE" => {
+                                let id = parse_id(Self::extract_string(&elements[4])?)?;
+                                let mkstream = match elements.get(5) {
+                                    None => false,
+                                    Some(opt) if Self::extract_string(opt)?.eq_ignore_ascii_case("MKSTREAM") => true,
+                                    Some(_) => return Err("ERR syntax error".to_string()),
+                                };
+                                Ok(Command::XGroupCreate {
+                                    key,
+                                    group,
+                                    id,
+                                    mkstream,
+                                })
+                            }
+                            "SETID" => Ok(Command::XGroupSetId {
+                                key,
+                                group,
+                                id: parse_id(Self::extract_string(&elements[4])?)?,
+                            }),
+                            "DESTROY" => Ok(Command::XGroupDestroy { key, group }),
+                            "CREATECONSUMER" => Ok(Command::XGroupCreateConsumer {
+                                key,
+                                group,
+                                consumer: Self::extract_string(&elements[4])?,
+                            }),
+                            _ => Ok(Command::XGroupDelConsumer {
+                                key,
+                                group,
+                                consumer: Self::extract_string(&elements[4])?,
+                            }),
+                        }
+                    }
+                    "XREADGROUP" => {
+                        // XREADGROUP GROUP group consumer [COUNT n] [BLOCK ms] [NOACK] STREAMS key [key ...] id [id ...]
+                        let mut group = None;
+                        let mut count = None;
+                        let mut block_ms = None;
+                        let mut noack = false;
+                        let mut i = 1;
+                        loop {
+                            if i >= elements.len() {
+                                return Err("ERR syntax error".to_string());
+                            }
+                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "GROUP" if i + 2 < elements.len() => {
+                                    group = Some((Self::extract_string(&elements[i + 1])?, Self::extract_string(&elements[i + 2])?));
+                                    i += 2;
+                                }
+                                "COUNT" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    count = Some(Self::extract_integer(&elements[i])?.max(0) as usize);
+                                }
+                                "NOACK" => noack = true,
+                                "BLOCK" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    block_ms = Some(blocking::parse_block_ms(&Self::extract_string(&elements[i])?)?);
+                                }
+                                "STREAMS" => break,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        let Some((group, consumer)) = group else {
+                            return Err("ERR Missing GROUP option for XREADGROUP".to_string());
+                        };
+                        let rest = elements.len() - i - 1;
+                        if rest == 0 || rest % 2 != 0 {
+                            return Err("ERR Unbalanced 'xreadgroup' list of streams: for each stream key an ID or '>' must be specified.".to_string());
+                        }
+                        let n = rest / 2;
+                        let mut keys = Vec::with_capacity(n);
+                        let mut ids = Vec::with_capacity(n);
+                        for j in 0..n {
+                            keys.push(Self::extract_string(&elements[i + 1 + j])?);
+                            let id = Self::extract_string(&elements[i + 1 + n + j])?;
+                            ids.push(if id == ">" {
+                                None
+                            } else {
+                                Some(
+                                    StreamId::parse(&id, 0)
+                                        .ok_or_else(|| INVALID_STREAM_ID.to_string())?,
+                                )
+                            });
+                        }
+                        Ok(Command::XReadGroup {
+                            group,
+                            consumer,
+                            count,
+                            block_ms,
+                            noack,
+                            keys,
+                            ids,
+                        })
+                    }
+                    "XACK" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let group = Self::extract_string(&elements[2])?;
+                        let ids = elements[3..]
+                            .iter()
+                            .map(|e| {
+                                StreamId::parse(&Self::extract_string(e)?, 0)
+                                    .ok_or_else(|| INVALID_STREAM_ID.to_string())
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(Command::XAck { key, group, ids })
+                    }
+                    "XPENDING" => {
+                        // XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+                        let key = Self::extract_string(&elements[1])?;
+                        let group = Self::extract_string(&elements[2])?;
+                        let range = if elements.len() == 3 {
+                            None
+                        } else {
+                            let mut i = 3;
+                            let mut min_idle_ms = None;
+                            if Self::extract_string(&elements[3])?.eq_ignore_ascii_case("IDLE") && elements.len() > 4 {
+                                min_idle_ms = Some(Self::extract_integer(&elements[4])?.max(0) as u64);
+                                i = 5;
+                            }
+                            if !(i + 3..=i + 4).contains(&elements.len()) {
+                                return Err("ERR syntax error".to_string());
+                            }
+                            let start = StreamId::parse_bound(&Self::extract_string(&elements[i])?, true)
+                                .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                            let end = StreamId::parse_bound(&Self::extract_string(&elements[i + 1])?, false)
+                                .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                            let count = Self::extract_integer(&elements[i + 2])?.max(0) as usize;
+                            let consumer = match elements.get(i + 3) {
+                                Some(c) => Some(Self::extract_string(c)?),
+                                None => None,
+                            };
+                            Some(PendingRange {
+                                min_idle_ms,
+                                start,
+                                end,
+                                count,
+                                consumer,
+                            })
+                        };
+                        Ok(Command::XPending { key, group, range })
+                    }
+                    "XCLAIM" => {
+                        // XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-ms]
+                        //        [RETRYCOUNT n] [FORCE] [JUSTID]
+                        let key = Self::extract_string(&elements[1])?;
+                        let group = Self::extract_string(&elements[2])?;
+                        let consumer = Self::extract_string(&elements[3])?;
+                        let min_idle_ms = Self::extract_string(&elements[4])?
+                            .parse::<i64>()
+                            .map_err(|_| "ERR Invalid min-idle-time argument for XCLAIM".to_string())?
+                            .max(0) as u64;
+                        let mut ids = Vec::new();
+                        let mut i = 5;
+                        while i < elements.len() {
+                            match StreamId::parse(&Self::extract_string(&elements[i])?, 0) {
+                                Some(id) => ids.push(id),
+                                None => break,
+                            }
+                            i += 1;
+                        }
+                        if ids.is_empty() {
+                            return Err(INVALID_STREAM_ID.to_string());
+                        }
+                        let mut opts = ClaimOptions::default();
+                        while i < elements.len() {
+                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
+                            let has_arg = i + 1 < elements.len();
+                            let mut arg = |name: &str| -> Result<u64, String> {
+                                i += 1;
+                                Self::extract_string(&elements[i])?
+                                    .parse::<i64>()
+                                    .map(|n| n.max(0) as u64)
+                                    .map_err(|_| format!("ERR Invalid {} option argument for XCLAIM", name))
+                            };
+                            match opt.as_str() {
+                                "IDLE" if has_arg => opts.idle_ms = Some(arg("IDLE")?),
+                                "TIME" if has_arg => opts.time_ms = Some(arg("TIME")?),
+                                "RETRYCOUNT" if has_arg => opts.retry_count = Some(arg("RETRYCOUNT")?),
+                                "FORCE" => opts.force = true,
+                                "JUSTID" => opts.justid = true,
+                                _ => {
+                                    return Err(format!(
+                                        "ERR Unrecognized XCLAIM option '{}'",
+                                        Self::extract_string(&elements[i])?
+                                    ))
+                                }
+                            }
+                            i += 1;
+                        }
+                        Ok(Command::XClaim {
+                            key,
+                            group,
+                            consumer,
+                            min_idle_ms,
+                            ids,
+                            opts,
+                        })
+                    }
+                    "XAUTOCLAIM" => {
+                        // XAUTOCLAIM key group consumer min-idle-time start [COUNT n] [JUSTID]
+                        let key = Self::extract_string(&elements[1])?;
+                        let group = Self::extract_string(&elements[2])?;
+                        let consumer = Self::extract_string(&elements[3])?;
+                        let min_idle_ms = Self::extract_string(&elements[4])?
+                            .parse::<i64>()
+                            .map_err(|_| "ERR Invalid min-idle-time argument for XAUTOCLAIM".to_string())?
+                            .max(0) as u64;
+                        let start = StreamId::parse_bound(&Self::extract_string(&elements[5])?, true)
+                            .ok_or_else(|| INVALID_STREAM_ID.to_string())?;
+                        let mut count = 100;
+                        let mut justid = false;
+                        let mut i = 6;
+                        while i < elements.len() {
+                            let opt = Self::extract_string(&elements[i])?.to_uppercase();
+                            match opt.as_str() {
+                                "COUNT" if i + 1 < elements.len() => {
+                                    i += 1;
+                                    let n = Self::extract_integer(&elements[i])?;
+                                    if n < 1 {
+                                        return Err("ERR COUNT must be > 0".to_string());
+                                    }
+                                    count = n as usize;
+                                }
+                                "JUSTID" => justid = true,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        Ok(Command::XAutoClaim {
+                            key,
+                            group,
+                            consumer,
+                            min_idle_ms,
+                            start,
+                            count,
+                            justid,
+                        })
+                    }
+                    "FUNCTION" => {
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
+                        let arity_error = || {
+                            format!(
+                                "ERR wrong number of arguments for 'function|{}' command",
+                                subcommand.to_lowercase()
+                            )
+                        };
                         match subcommand.as_str() {
-                            "FLUSH" => Ok(Command::FunctionFlush),
+                            "LOAD" => match elements.len() {
+                                3 => Ok(Command::FunctionLoad {
+                                    code: Self::extract_string(&elements[2])?,
+                                    replace: false,
+                                }),
+                                4 => {
+                                    let option = Self::extract_string(&elements[2])?;
+                                    if !option.eq_ignore_ascii_case("REPLACE") {
+                                        return Err(format!("ERR Unknown option given: {}", option));
+                                    }
+                                    Ok(Command::FunctionLoad {
+                                        code: Self::extract_string(&elements[3])?,
+                                        replace: true,
+                                    })
+                                }
+                                _ => Err(arity_error()),
+                            },
+                            "DELETE" => {
+                                if elements.len() != 3 {
+                                    return Err(arity_error());
+                                }
+                                let library = Self::extract_string(&elements[2])?;
+                                Ok(Command::FunctionDelete(library))
+                            }
+                            "FLUSH" => {
+                                if elements.len() > 3 {
+                                    return Err(arity_error());
+                                }
+                                if let Some(mode) = elements.get(2) {
+                                    let mode = Self::extract_string(mode)?.to_uppercase();
+                                    if mode != "ASYNC" && mode != "SYNC" {
+                                        return Err(
+                                            "ERR FUNCTION FLUSH only supports SYNC|ASYNC option"
+                                                .to_string(),
+                                        );
+                                    }
+                                }
+                                Ok(Command::FunctionFlush)
+                            }
+                            "LIST" => {
+                                let mut pattern = None;
+                                let mut with_code = false;
+                                let mut i = 2;
+                                while i < elements.len() {
+                                    let option =
+                                        Self::extract_string(&elements[i])?.to_uppercase();
+                                    match option.as_str() {
+                                        "WITHCODE" => with_code = true,
+                                        "LIBRARYNAME" => {
+                                            if pattern.is_some() {
+                                                return Err("ERR library name can be given only once"
+                                                    .to_string());
+                                            }
+                                            i += 1;
+                                            if i >= elements.len() {
+                                                return Err("ERR library name argument was not given"
+                                                    .to_string());
+                                            }
+                                            pattern = Some(Self::extract_string(&elements[i])?);
+                                        }
+                                        _ => return Err(format!("ERR Unknown argument {}", option)),
+                                    }
+                                    i += 1;
+                                }
+                                Ok(Command::FunctionList { pattern, with_code })
+                            }
+                            "DUMP" | "STATS" if elements.len() != 2 => Err(arity_error()),
+                            "DUMP" => Ok(Command::FunctionDump),
+                            "STATS" => Ok(Command::FunctionStats),
+                            "RESTORE" => {
+                                let policy = match elements.len() {
+                                    3 => FunctionRestorePolicy::default(),
+                                    4 => {
+                                        let policy =
+                                            Self::extract_string(&elements[3])?.to_uppercase();
+                                        match policy.as_str() {
+                                            "FLUSH" => FunctionRestorePolicy::Flush,
+                                            "APPEND" => FunctionRestorePolicy::Append,
+                                            "REPLACE" => FunctionRestorePolicy::Replace,
+                                            _ => {
+                                                return Err("ERR Wrong restore policy given, \
+                                                    value should be either FLUSH, APPEND or \
+                                                    REPLACE."
+                                                    .to_string())
+                                            }
+                                        }
+                                    }
+                                    _ => return Err(arity_error()),
+                                };
+                                let payload =
+                                    Self::extract_sds(&elements[2])?.as_bytes().to_vec();
+                                Ok(Command::FunctionRestore { payload, policy })
+                            }
                             _ => Ok(Command::Unknown(format!("FUNCTION {}", subcommand))),
                         }
                     }
                     "COMMAND" => {
-                        if elements.len() >= 2 {
-                            let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
-                            match subcommand.as_str() {
-                                "COUNT" => Ok(Command::CommandCount),
-                                _ => Ok(Command::CommandCommand),
-                            }
-                        } else {
-                            Ok(Command::CommandCommand)
-                        }
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_command_introspection(args)
+                    }
+                    "LATENCY" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_latency(args)
+                    }
+                    "MEMORY" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_memory(args)
+                    }
+                    "LOLWUT" => {
+                        let args = elements[1..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Self::parse_lolwut(args)
                     }
                     "CLIENT" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'client' command".to_string());
-                        }
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "SETNAME" => {
@@ -1195,13 +1852,63 @@ impl Command {
                             "GETNAME" => Ok(Command::ClientGetName),
                             "ID" => Ok(Command::ClientId),
                             "INFO" => Ok(Command::ClientInfo),
+                            "LIST" if elements.len() == 2 => Ok(Command::ClientList),
+                            "LIST" => Err("ERR syntax error".to_string()),
+                            "KILL" | "PAUSE" | "TRACKING" => {
+                                let args = elements[2..]
+                                    .iter()
+                                    .map(Self::extract_string)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                match subcommand.as_str() {
+                                    "KILL" => Self::parse_client_kill(args),
+                                    "PAUSE" => Self::parse_client_pause(args),
+                                    _ => Self::parse_client_tracking(args),
+                                }
+                            }
+                            "UNPAUSE" if elements.len() == 2 => Ok(Command::ClientUnpause),
+                            "UNPAUSE" => Err("ERR wrong number of arguments for 'client|unpause' command".to_string()),
                             _ => Ok(Command::Unknown(format!("CLIENT {}", subcommand))),
                         }
                     }
-                    "OBJECT" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'object' command".to_string());
+                    // Negotiated by the connection
+                    "HELLO" => {
+                        let protover = elements.get(1).map(Self::extract_i64).transpose()?;
+                        let (mut auth, mut setname) = (None, None);
+                        let mut i = 2;
+                        while i < elements.len() {
+                            let option = Self::extract_string(&elements[i])?;
+                            let more = elements.len() - i - 1;
+                            match option.to_uppercase().as_str() {
+                                "AUTH" if more >= 2 => {
+                                    auth = Some((
+                                        Self::extract_string(&elements[i + 1])?,
+                                        Self::extract_string(&elements[i + 2])?,
+                                    ));
+                                    i += 3;
+                                }
+                                "SETNAME" if more >= 1 => {
+                                    setname = Some(Self::extract_string(&elements[i + 1])?);
+                                    i += 2;
+                                }
+                                _ => {
+                                    return Err(format!(
+                                        "ERR Syntax error in HELLO option '{}'",
+                                        option
+                                    ))
+                                }
+                            }
                         }
+                        Ok(Command::Hello {
+                            protover,
+                            auth,
+                            setname,
+                        })
+                    }
+                    "XINFO" => {
+                        let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
+                        Ok(Command::Unknown(format!("XINFO {}", subcommand)))
+                    }
+                    "OBJECT" => {
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "HELP" => Ok(Command::ObjectHelp),
@@ -1233,9 +1940,6 @@ impl Command {
                         }
                     }
                     "DEBUG" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'debug' command".to_string());
-                        }
                         let subcommand = Self::extract_string(&elements[1])?.to_uppercase();
                         match subcommand.as_str() {
                             "SLEEP" => {
@@ -1243,9 +1947,25 @@ impl Command {
                                     return Err("ERR wrong number of arguments for 'debug|sleep' command".to_string());
                                 }
                                 let seconds = Self::extract_float(&elements[2])?;
+                                if !seconds.is_finite() || seconds < 0.0 {
+                                    return Err("ERR sleep time must be a non-negative number".to_string());
+                                }
                                 Ok(Command::DebugSleep(seconds))
                             }
-                            "SET-ACTIVE-EXPIRE" | "JMAP" | "RELOAD" | "LOADAOF" | "QUICKLIST-PACKED-THRESHOLD" => {
+                            "SET-ACTIVE-EXPIRE" => {
+                                if elements.len() != 3 {
+                                    return Err("ERR wrong number of arguments for 'debug|set-active-expire' command".to_string());
+                                }
+                                Ok(Command::DebugSetActiveExpire(Self::extract_integer(&elements[2])? != 0))
+                            }
+                            "RELOAD" => {
+                                if elements.len() > 2 {
+                                    let option = Self::extract_string(&elements[2])?;
+                                    return Err(format!("ERR DEBUG RELOAD option '{}' is not supported", option));
+                                }
+                                Ok(Command::DebugReload)
+                            }
+                            "JMAP" | "LOADAOF" | "QUICKLIST-PACKED-THRESHOLD" => {
                                 if elements.len() >= 3 {
                                     let val = Self::extract_string(&elements[2])?;
                                     Ok(Command::DebugSet(subcommand, val))
@@ -1253,6 +1973,51 @@ impl Command {
                                     Ok(Command::DebugSet(subcommand, String::new()))
                                 }
                             }
+                            "KEYSTATS" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'debug|keystats' command".to_string());
+                                }
+                                Ok(Command::DebugKeyStats)
+                            }
+                            "STRINGMATCH-LEN" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'debug|stringmatch-len' command".to_string());
+                                }
+                                Ok(Command::DebugStringMatchLen)
+                            }
+                            "PROTOCOL" => {
+                                if elements.len() != 3 {
+                                    return Err("ERR wrong number of arguments for 'debug|protocol' command".to_string());
+                                }
+                                Ok(Command::DebugProtocol(Self::extract_string(&elements[2])?.to_lowercase()))
+                            }
+                            "HEALTH" => {
+                                if elements.len() != 2 {
+                                    return Err("ERR wrong number of arguments for 'debug|health' command".to_string());
+                                }
+                                Ok(Command::DebugHealth)
+                            }
+                            "BUGGIFY" => {
+                                let action = if elements.len() >= 3 {
+                                    Self::extract_string(&elements[2])?.to_uppercase()
+                                } else {
+                                    String::new()
+                                };
+                                match (action.as_str(), elements.len()) {
+                                    ("SET", 5) => {
+                                        let fault = Self::extract_string(&elements[3])?;
+                                        let probability = Self::extract_float(&elements[4])?;
+                                        Ok(Command::DebugBuggifySet { fault, probability })
+                                    }
+                                    ("STATS", 3) => Ok(Command::DebugBuggifyStats),
+                                    _ => Err("ERR wrong number of arguments for 'debug|buggify' command".to_string()),
+                                }
+                            }
+                            "SNAPSHOT-READ" => Self::parse_snapshot_reads(
+                                &elements[2..],
+                                Self::extract_integer,
+                                |args| Self::from_resp(&RespValue::Array(Some(args.to_vec()))),
+                            ),
                             "OBJECT" => {
                                 if elements.len() != 3 {
                                     return Err("ERR wrong number of arguments for 'debug|object' command".to_string());
@@ -1271,18 +2036,12 @@ impl Command {
                         }
                     }
                     "GETRANGE" | "SUBSTR" => {
-                        if elements.len() != 4 {
-                            return Err("GETRANGE requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let start = Self::extract_integer(&elements[2])?;
                         let end = Self::extract_integer(&elements[3])?;
                         Ok(Command::GetRange(key, start, end))
                     }
                     "SETRANGE" => {
-                        if elements.len() != 4 {
-                            return Err("SETRANGE requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let offset = Self::extract_integer(&elements[2])?;
                         if offset < 0 {
@@ -1292,9 +2051,6 @@ impl Command {
                         Ok(Command::SetRange(key, offset as usize, value))
                     }
                     "SETBIT" => {
-                        if elements.len() != 4 {
-                            return Err("ERR wrong number of arguments for 'setbit' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let offset = Self::extract_u64(&elements[2])
                             .map_err(|_| "ERR bit offset is not an integer or out of range".to_string())?;
@@ -1306,18 +2062,81 @@ impl Command {
                         Ok(Command::SetBit(key, offset, value as u8))
                     }
                     "GETBIT" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'getbit' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let offset = Self::extract_u64(&elements[2])
                             .map_err(|_| "ERR bit offset is not an integer or out of range".to_string())?;
                         Ok(Command::GetBit(key, offset))
                     }
-                    "GETEX" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'getex' command".to_string());
+                    "BITCOUNT" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let range = match elements.len() {
+                            2 => None,
+                            4 | 5 => Some(BitRange {
+                                start: Self::extract_integer(&elements[2])? as i64,
+                                end: Some(Self::extract_integer(&elements[3])? as i64),
+                                unit: match elements.get(4) {
+                                    Some(e) => Self::parse_bit_unit(&Self::extract_string(e)?)?,
+                                    None => BitUnit::Byte,
+                                },
+                            }),
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        Ok(Command::BitCount { key, range })
+                    }
+                    "BITPOS" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let bit = Self::extract_integer(&elements[2])?;
+                        if bit != 0 && bit != 1 {
+                            return Err("ERR The bit argument must be 1 or 0.".to_string());
+                        }
+                        let range = match elements.get(3) {
+                            Some(start) => Some(BitRange {
+                                start: Self::extract_integer(start)? as i64,
+                                end: match elements.get(4) {
+                                    Some(e) => Some(Self::extract_integer(e)? as i64),
+                                    None => None,
+                                },
+                                unit: match elements.get(5) {
+                                    Some(e) => Self::parse_bit_unit(&Self::extract_string(e)?)?,
+                                    None => BitUnit::Byte,
+                                },
+                            }),
+                            None => None,
+                        };
+                        Ok(Command::BitPos { key, bit: bit as u8, range })
+                    }
+                    "BITOP" => {
+                        let op = match Self::extract_string(&elements[1])?.to_uppercase().as_str() {
+                            "AND" => BitOperation::And,
+                            "OR" => BitOperation::Or,
+                            "XOR" => BitOperation::Xor,
+                            "NOT" => BitOperation::Not,
+                            _ => return Err("ERR syntax error".to_string()),
+                        };
+                        let dest = Self::extract_string(&elements[2])?;
+                        let keys = elements[3..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        if op == BitOperation::Not && keys.len() != 1 {
+                            return Err("ERR BITOP NOT must be called with a single source key.".to_string());
                         }
+                        Ok(Command::BitOp { op, dest, keys })
+                    }
+                    "BITFIELD" | "BITFIELD_RO" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let args = elements[2..]
+                            .iter()
+                            .map(Self::extract_string)
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let ops = Self::parse_bitfield_ops(&args, cmd_name == "BITFIELD_RO")?;
+                        if cmd_name == "BITFIELD_RO" {
+                            Ok(Command::BitFieldRo { key, ops })
+                        } else {
+                            Ok(Command::BitField { key, ops })
+                        }
+                    }
+                    "GETEX" => {
                         let key = Self::extract_string(&elements[1])?;
                         let mut ex = None;
                         let mut px = None;
@@ -1359,20 +2178,15 @@ impl Command {
                         if option_count > 1 {
                             return Err("ERR syntax error".to_string());
                         }
+                        Self::check_expire("getex", ex, px, exat, pxat)?;
 
                         Ok(Command::GetEx { key, ex, px, exat, pxat, persist })
                     }
                     "GETDEL" => {
-                        if elements.len() != 2 {
-                            return Err("GETDEL requires 1 argument".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         Ok(Command::GetDel(key))
                     }
                     "INCRBYFLOAT" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'incrbyfloat' command".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
                         let increment = Self::extract_float(&elements[2])?;
                         if increment.is_nan() || increment.is_infinite() {
@@ -1381,11 +2195,9 @@ impl Command {
                         Ok(Command::IncrByFloat(key, increment))
                     }
                     "PSETEX" => {
-                        if elements.len() != 4 {
-                            return Err("PSETEX requires 3 arguments".to_string());
-                        }
                         let key = Self::extract_string(&elements[1])?;
-                        let millis = Self::extract_integer(&elements[2])? as i64;
+                        let millis = Self::extract_i64(&elements[2])?;
+                        Self::check_expire("psetex", None, Some(millis), None, None)?;
                         let value = Self::extract_sds(&elements[3])?;
                         Ok(Command::Set {
                             key,
@@ -1400,81 +2212,797 @@ impl Command {
                             keepttl: false,
                         })
                     }
-                    "EXPIRETIME" => {
-                        if elements.len() != 2 {
-                            return Err("EXPIRETIME requires 1 argument".to_string());
-                        }
-                        let key = Self::extract_string(&elements[1])?;
-                        Ok(Command::ExpireTime(key))
-                    }
-                    "PEXPIRETIME" => {
-                        if elements.len() != 2 {
-                            return Err("PEXPIRETIME requires 1 argument".to_string());
-                        }
-                        let key = Self::extract_string(&elements[1])?;
-                        Ok(Command::PExpireTime(key))
-                    }
                     "UNLINK" => {
-                        if elements.len() < 2 {
-                            return Err("UNLINK requires at least 1 argument".to_string());
-                        }
                         let keys: Vec<String> = elements[1..]
                             .iter()
                             .map(Self::extract_string)
                             .collect::<Result<Vec<_>, _>>()?;
-                        Ok(Command::Del(keys))
+                        Ok(Command::Unlink(keys))
                     }
                     "WAIT" => {
-                        if elements.len() != 3 {
-                            return Err("WAIT requires 2 arguments".to_string());
-                        }
                         let numreplicas = Self::extract_i64(&elements[1])?;
                         let timeout = Self::extract_i64(&elements[2])?;
+                        if timeout < 0 {
+                            return Err("ERR timeout is negative".to_string());
+                        }
                         Ok(Command::Wait(numreplicas, timeout))
                     }
-                    "SORT" => {
-                        if elements.len() < 2 {
-                            return Err("ERR wrong number of arguments for 'sort' command".to_string());
+                    "SHUTDOWN" => {
+                        let mut save = None;
+                        for element in &elements[1..] {
+                            match Self::extract_string(element)?.to_uppercase().as_str() {
+                                "NOSAVE" if save.is_none() => save = Some(false),
+                                "SAVE" if save.is_none() => save = Some(true),
+                                // Nothing waits for replicas and a failed save
+                                // never stops the shutdown, so these change nothing
+                                "NOW" | "FORCE" => {}
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
                         }
+                        Ok(Command::Shutdown { save })
+                    }
+                    "SORT" => {
                         let key = Self::extract_string(&elements[1])?;
+                        let mut by = None;
+                        let mut limit = None;
+                        let mut get = Vec::new();
+                        let mut desc = false;
+                        let mut alpha = false;
                         let mut store = None;
                         let mut i = 2;
                         while i < elements.len() {
                             let opt = Self::extract_string(&elements[i])?.to_uppercase();
-                            if opt == "STORE" {
-                                i += 1;
-                                if i < elements.len() {
-                                    store = Some(Self::extract_string(&elements[i])?);
+                            let remaining = elements.len() - i - 1;
+                            match opt.as_str() {
+                                "ASC" => desc = false,
+                                "DESC" => desc = true,
+                                "ALPHA" => alpha = true,
+                                "LIMIT" if remaining >= 2 => {
+                                    let offset = Self::extract_i64(&elements[i + 1])?;
+                                    let count = Self::extract_i64(&elements[i + 2])?;
+                                    limit = Some((offset, count));
+                                    i += 2;
+                                }
+                                "BY" if remaining >= 1 => {
+                                    by = Some(Self::extract_string(&elements[i + 1])?);
+                                    i += 1;
+                                }
+                                "GET" if remaining >= 1 => {
+                                    get.push(Self::extract_string(&elements[i + 1])?);
+                                    i += 1;
+                                }
+                                "STORE" if remaining >= 1 => {
+                                    store = Some(Self::extract_string(&elements[i + 1])?);
+                                    i += 1;
                                 }
+                                _ => return Err("ERR syntax error".to_string()),
                             }
                             i += 1;
                         }
-                        Ok(Command::Sort { key, store })
+                        Ok(Command::Sort {
+                            key,
+                            by,
+                            limit,
+                            get,
+                            desc,
+                            alpha,
+                            store,
+                        })
                     }
                     "RANDOMKEY" => Ok(Command::RandomKey),
                     "RENAME" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'rename' command".to_string());
-                        }
                         let src = Self::extract_string(&elements[1])?;
                         let dst = Self::extract_string(&elements[2])?;
                         Ok(Command::Rename(src, dst))
                     }
                     "RENAMENX" => {
-                        if elements.len() != 3 {
-                            return Err("ERR wrong number of arguments for 'renamenx' command".to_string());
-                        }
                         let src = Self::extract_string(&elements[1])?;
                         let dst = Self::extract_string(&elements[2])?;
                         Ok(Command::RenameNx(src, dst))
                     }
-                    _ => Ok(Command::Unknown(cmd_name)),
+                    "COPY" => {
+                        let source = Self::extract_string(&elements[1])?;
+                        let dest = Self::extract_string(&elements[2])?;
+                        let mut replace = false;
+                        for arg in &elements[3..] {
+                            match Self::extract_string(arg)?.to_ascii_uppercase().as_str() {
+                                "REPLACE" => replace = true,
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                        }
+                        Ok(Command::Copy {
+                            source,
+                            dest,
+                            replace,
+                        })
+                    }
+                    "RESTORE" => {
+                        let key = Self::extract_string(&elements[1])?;
+                        let ttl = Self::extract_i64(&elements[2])?;
+                        if ttl < 0 {
+                            return Err("ERR Invalid TTL value, must be >= 0".to_string());
+                        }
+                        let payload = Self::extract_sds(&elements[3])?.as_bytes().to_vec();
+                        let mut replace = false;
+                        let mut absttl = false;
+                        let mut idletime = None;
+                        let mut freq = None;
+                        let mut i = 4;
+                        while i < elements.len() {
+                            let opt = Self::extract_string(&elements[i])?.to_ascii_uppercase();
+                            match opt.as_str() {
+                                "REPLACE" => replace = true,
+                                "ABSTTL" => absttl = true,
+                                "IDLETIME" if i + 1 < elements.len() && freq.is_none() => {
+                                    i += 1;
+                                    let secs = Self::extract_i64(&elements[i])?;
+                                    if secs < 0 {
+                                        return Err(
+                                            "ERR Invalid IDLETIME value, must be >= 0".to_string()
+                                        );
+                                    }
+                                    idletime = Some(secs as u64);
+                                }
+                                "FREQ" if i + 1 < elements.len() && idletime.is_none() => {
+                                    i += 1;
+                                    let value = Self::extract_i64(&elements[i])?;
+                                    freq = Some(u8::try_from(value).map_err(|_| {
+                                        "ERR Invalid FREQ value, must be >= 0 and <= 255"
+                                            .to_string()
+                                    })?);
+                                }
+                                _ => return Err("ERR syntax error".to_string()),
+                            }
+                            i += 1;
+                        }
+                        Ok(Command::Restore {
+                            key,
+                            ttl_ms: ttl as u64,
+                            payload,
+                            replace,
+                            absttl,
+                            idletime,
+                            freq,
+                        })
+                    }
+                    _ => match DeclaredCommand::parse(&cmd_name, elements.len(), |i| {
+                        match &elements[i] {
+                            RespValue::BulkString(Some(data)) => Some(data.as_slice()),
+                            _ => None,
+                        }
+                    }) {
+                        Some(parsed) => parsed.map(Command::Declared),
+                        None => Ok(Command::Unknown(cmd_name)),
+                    },
                 }
             }
             _ => Err("Invalid command format".to_string()),
         }
     }
 
+    // =========================================================================
+    // Argument helpers shared by both parsers
+    //
+    // Both parsers extract a command's arguments as strings first and hand
+    // them to these.
+    // =========================================================================
+
+    fn parse_bit_unit(unit: &str) -> Result<BitUnit, String> {
+        match unit.to_uppercase().as_str() {
+            "BYTE" => Ok(BitUnit::Byte),
+            "BIT" => Ok(BitUnit::Bit),
+            _ => Err("ERR syntax error".to_string()),
+        }
+    }
+
+    /// Validate SET-family expire arguments at parse time, as Redis does
+    /// before touching the key: each must be positive, and one given in
+    /// seconds (EX, EXAT) must not overflow once converted to milliseconds.
+    /// `cmd` is the lowercase command name embedded in the error.
+    pub(super) fn check_expire(
+        cmd: &str,
+        ex: Option<i64>,
+        px: Option<i64>,
+        exat: Option<i64>,
+        pxat: Option<i64>,
+    ) -> Result<(), String> {
+        let bad_seconds = [ex, exat]
+            .into_iter()
+            .flatten()
+            .any(|s| s <= 0 || s > i64::MAX / 1000);
+        let bad_millis = [px, pxat].into_iter().flatten().any(|ms| ms <= 0);
+        if bad_seconds || bad_millis {
+            return Err(format!("ERR invalid expire time in '{}' command", cmd));
+        }
+        Ok(())
+    }
+
+    /// Parse SINTERCARD arguments (everything after the name).
+    pub(super) fn parse_sintercard(mut args: Vec<String>) -> Result<Command, String> {
+        let numkeys = args[0]
+            .parse::<i64>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or("ERR numkeys should be greater than 0")? as usize;
+        if numkeys > args.len() - 1 {
+            return Err("ERR Number of keys can't be greater than number of args".to_string());
+        }
+
+        let mut limit = 0;
+        let mut i = 1 + numkeys;
+        while i < args.len() {
+            if args[i].eq_ignore_ascii_case("LIMIT") && i + 1 < args.len() {
+                limit = args[i + 1]
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|&n| n >= 0)
+                    .ok_or("ERR LIMIT can't be negative")? as usize;
+                i += 2;
+            } else {
+                return Err("ERR syntax error".to_string());
+            }
+        }
+
+        args.truncate(1 + numkeys);
+        let keys = args.split_off(1);
+        debug_assert_eq!(keys.len(), numkeys, "Postcondition: SINTERCARD key count");
+        Ok(Command::SInterCard { keys, limit })
+    }
+
+    /// Parse CLIENT KILL arguments (everything after the subcommand).
+    pub(super) fn parse_client_kill(args: Vec<String>) -> Result<Command, String> {
+        let mut id = None;
+        let mut addr = None;
+        let mut user = None;
+        let mut skip_me = true;
+        match args.len() {
+            0 => {
+                return Err("ERR wrong number of arguments for 'client|kill' command".to_string())
+            }
+            // The old form: CLIENT KILL addr
+            1 => {
+                return Ok(Command::ClientKill {
+                    id: None,
+                    addr: args.into_iter().next(),
+                    user: None,
+                    skip_me: false,
+                    legacy: true,
+                })
+            }
+            n if n % 2 != 0 => return Err("ERR syntax error".to_string()),
+            _ => {}
+        }
+        for pair in args.chunks(2) {
+            let value = &pair[1];
+            match pair[0].to_uppercase().as_str() {
+                "ID" => {
+                    id = Some(
+                        value
+                            .parse::<u64>()
+                            .ok()
+                            .filter(|&n| n > 0)
+                            .ok_or("ERR client-id should be greater than 0")?,
+                    )
+                }
+                "ADDR" => addr = Some(value.clone()),
+                "USER" => user = Some(value.clone()),
+                "SKIPME" => {
+                    skip_me = match value.to_lowercase().as_str() {
+                        "yes" => true,
+                        "no" => false,
+                        _ => return Err("ERR syntax error".to_string()),
+                    }
+                }
+                _ => return Err("ERR syntax error".to_string()),
+            }
+        }
+        Ok(Command::ClientKill {
+            id,
+            addr,
+            user,
+            skip_me,
+            legacy: false,
+        })
+    }
+
+    /// Parse CLIENT PAUSE arguments (everything after the subcommand).
+    pub(super) fn parse_client_pause(args: Vec<String>) -> Result<Command, String> {
+        if args.is_empty() || args.len() > 2 {
+            return Err("ERR wrong number of arguments for 'client|pause' command".to_string());
+        }
+        let timeout_ms = args[0]
+            .parse::<i64>()
+            .ok()
+            .filter(|&n| n >= 0)
+            .ok_or("ERR timeout is not an integer or out of range")? as u64;
+        let writes_only = match args.get(1).map(|mode| mode.to_uppercase()).as_deref() {
+            None | Some("ALL") => false,
+            Some("WRITE") => true,
+            Some(_) => return Err("ERR syntax error".to_string()),
+        };
+        Ok(Command::ClientPause {
+            timeout_ms,
+            writes_only,
+        })
+    }
+
+    /// Parse CLIENT TRACKING (everything after the subcommand). Only BCAST
+    /// and PREFIX are supported; any other option is a syntax error.
+    pub(super) fn parse_client_tracking(args: Vec<String>) -> Result<Command, String> {
+        let Some((switch, options)) = args.split_first() else {
+            return Err("ERR wrong number of arguments for 'client|tracking' command".to_string());
+        };
+        let on = match switch.to_uppercase().as_str() {
+            "ON" => true,
+            "OFF" => false,
+            _ => return Err("ERR syntax error".to_string()),
+        };
+        let mut bcast = false;
+        let mut prefixes = Vec::new();
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match option.to_uppercase().as_str() {
+                "BCAST" => bcast = true,
+                "PREFIX" => match options.next() {
+                    Some(prefix) => prefixes.push(prefix.clone()),
+                    None => return Err("ERR syntax error".to_string()),
+                },
+                _ => return Err("ERR syntax error".to_string()),
+            }
+        }
+        if !prefixes.is_empty() && !bcast {
+            return Err("ERR PREFIX option requires BCAST mode to be enabled".to_string());
+        }
+        Ok(Command::ClientTracking {
+            on,
+            bcast,
+            prefixes,
+        })
+    }
+
+    /// Parse COMMAND and its subcommands (everything after the name)
+    pub(super) fn parse_command_introspection(mut args: Vec<String>) -> Result<Command, String> {
+        if args.is_empty() {
+            return Ok(Command::CommandCommand);
+        }
+        let subcommand = args.remove(0).to_uppercase();
+        match subcommand.as_str() {
+            "COUNT" if args.is_empty() => Ok(Command::CommandCount),
+            "INFO" => Ok(Command::CommandInfo(args)),
+            "DOCS" => Ok(Command::CommandDocs(args)),
+            "GETKEYS" if !args.is_empty() => Ok(Command::CommandGetKeys(args)),
+            "COUNT" | "GETKEYS" => Err(format!(
+                "ERR wrong number of arguments for 'command|{}' command",
+                subcommand.to_lowercase()
+            )),
+            _ => Err(format!(
+                "ERR unknown subcommand '{}'. Try COMMAND HELP.",
+                subcommand.to_lowercase()
+            )),
+        }
+    }
+
+    /// Parse LATENCY LATEST|HISTORY|RESET|DOCTOR|HELP (everything after the name)
+    pub(super) fn parse_latency(mut args: Vec<String>) -> Result<Command, String> {
+        let subcommand = args.remove(0).to_uppercase();
+        match subcommand.as_str() {
+            "LATEST" if args.is_empty() => Ok(Command::LatencyLatest),
+            "HISTORY" if args.len() == 1 => Ok(Command::LatencyHistory(args.remove(0))),
+            "RESET" => Ok(Command::LatencyReset(args)),
+            "DOCTOR" if args.is_empty() => Ok(Command::LatencyDoctor),
+            "HELP" if args.is_empty() => Ok(Command::LatencyHelp),
+            "LATEST" | "HISTORY" | "DOCTOR" | "HELP" => Err(format!(
+                "ERR wrong number of arguments for 'latency|{}' command",
+                subcommand.to_lowercase()
+            )),
+            _ => Err(format!(
+                "ERR unknown subcommand '{}'. Try LATENCY HELP.",
+                subcommand.to_lowercase()
+            )),
+        }
+    }
+
+    /// Parse MEMORY USAGE|STATS|DOCTOR|HELP (everything after the name)
+    pub(super) fn parse_memory(mut args: Vec<String>) -> Result<Command, String> {
+        let subcommand = args.remove(0).to_uppercase();
+        match subcommand.as_str() {
+            "USAGE" if args.len() == 1 => Ok(Command::MemoryUsage {
+                key: args.remove(0),
+                samples: None,
+            }),
+            "USAGE" if args.len() == 3 && args[1].eq_ignore_ascii_case("SAMPLES") => {
+                let samples: i64 = args[2]
+                    .parse()
+                    .map_err(|_| "ERR value is not an integer or out of range".to_string())?;
+                if samples < 0 {
+                    return Err("ERR syntax error".to_string());
+                }
+                Ok(Command::MemoryUsage {
+                    key: args.remove(0),
+                    samples: Some(samples as usize),
+                })
+            }
+            "USAGE" if args.len() > 1 => Err("ERR syntax error".to_string()),
+            "STATS" if args.is_empty() => Ok(Command::MemoryStats),
+            "DOCTOR" if args.is_empty() => Ok(Command::MemoryDoctor),
+            "HELP" if args.is_empty() => Ok(Command::MemoryHelp),
+            "USAGE" | "STATS" | "DOCTOR" | "HELP" => Err(format!(
+                "ERR wrong number of arguments for 'memory|{}' command",
+                subcommand.to_lowercase()
+            )),
+            _ => Err(format!(
+                "ERR unknown subcommand '{}'. Try MEMORY HELP.",
+                subcommand.to_lowercase()
+            )),
+        }
+    }
+
+    /// Parse LOLWUT [VERSION version] [param ...] (everything after the name).
+    /// The params are integers whose meaning depends on the version.
+    pub(super) fn parse_lolwut(args: Vec<String>) -> Result<Command, String> {
+        let integer = |s: &String| {
+            s.parse::<i64>()
+                .map_err(|_| "ERR value is not an integer or out of range".to_string())
+        };
+        let mut rest = args.as_slice();
+        let mut version = None;
+        if rest.len() >= 2 && rest[0].eq_ignore_ascii_case("VERSION") {
+            version = Some(integer(&rest[1])?);
+            rest = &rest[2..];
+        }
+        let params = rest.iter().map(integer).collect::<Result<Vec<_>, _>>()?;
+        Ok(Command::Lolwut { version, params })
+    }
+
+    /// Parse ZUNION/ZINTER/ZDIFF and their STORE forms (everything after the
+    /// name). `name` is the lowercase command name used in errors.
+    pub(super) fn parse_zset_op(
+        name: &str,
+        op: ZSetOperation,
+        store: bool,
+        mut args: Vec<String>,
+    ) -> Result<Command, String> {
+        let dest = if store { Some(args.remove(0)) } else { None };
+        let numkeys = args[0]
+            .parse::<i64>()
+            .map_err(|_| "ERR value is not an integer or out of range")?;
+        if numkeys < 1 {
+            return Err(format!(
+                "ERR at least 1 input key is needed for '{}' command",
+                name
+            ));
+        }
+        let numkeys = numkeys as usize;
+        if numkeys > args.len() - 1 {
+            return Err("ERR syntax error".to_string());
+        }
+
+        let mut weights = None;
+        let mut aggregate = ZAggregate::Sum;
+        let mut with_scores = false;
+        let mut i = 1 + numkeys;
+        while i < args.len() {
+            let remaining = args.len() - i - 1;
+            let option = args[i].to_ascii_uppercase();
+            match option.as_str() {
+                "WEIGHTS" if op != ZSetOperation::Diff && remaining >= numkeys => {
+                    let parsed = args[i + 1..i + 1 + numkeys]
+                        .iter()
+                        .map(|w| w.parse::<f64>().ok().filter(|w| !w.is_nan()))
+                        .collect::<Option<Vec<f64>>>()
+                        .ok_or("ERR weight value is not a float")?;
+                    weights = Some(parsed);
+                    i += 1 + numkeys;
+                }
+                "AGGREGATE" if op != ZSetOperation::Diff && remaining >= 1 => {
+                    aggregate = match args[i + 1].to_ascii_uppercase().as_str() {
+                        "SUM" => ZAggregate::Sum,
+                        "MIN" => ZAggregate::Min,
+                        "MAX" => ZAggregate::Max,
+                        _ => return Err("ERR syntax error".to_string()),
+                    };
+                    i += 2;
+                }
+                "WITHSCORES" if !store => {
+                    with_scores = true;
+                    i += 1;
+                }
+                _ => return Err("ERR syntax error".to_string()),
+            }
+        }
+
+        args.truncate(1 + numkeys);
+        let keys = args.split_off(1);
+        debug_assert!(
+            weights.as_ref().is_none_or(|w: &Vec<f64>| w.len() == keys.len()),
+            "Postcondition: one weight per key"
+        );
+        Ok(Command::ZSetOp {
+            op,
+            dest,
+            keys,
+            weights,
+            aggregate,
+            with_scores,
+        })
+    }
+
+    /// Parse the hash field TTL commands (everything after the name): the
+    /// HEXPIRE family takes `key time [NX|XX|GT|LT]`, the HTTL family and
+    /// HPERSIST just `key`, and all of them end in `FIELDS numfields field
+    /// [field ...]`. `name` is the uppercase command name.
+    pub(super) fn parse_field_ttl(name: &str, mut args: Vec<String>) -> Result<Command, String> {
+        let form = FieldTtlForm {
+            millis: name.starts_with("HP"),
+            absolute: name.ends_with("AT") || name.ends_with("TIME"),
+        };
+        let setter = name.contains("EXPIRE") && !name.ends_with("TIME");
+
+        let mut at = 1;
+        let mut time = 0;
+        let mut condition = None;
+        if setter {
+            time = args[1]
+                .parse::<i64>()
+                .map_err(|_| "ERR value is not an integer or out of range")?;
+            at = 2;
+            condition = match args.get(2).map(|a| a.to_ascii_uppercase()).as_deref() {
+                Some("NX") => Some(ExpireCondition::Nx),
+                Some("XX") => Some(ExpireCondition::Xx),
+                Some("GT") => Some(ExpireCondition::Gt),
+                Some("LT") => Some(ExpireCondition::Lt),
+                _ => None,
+            };
+            if condition.is_some() {
+                at = 3;
+            }
+        }
+        if !args.get(at).is_some_and(|a| a.eq_ignore_ascii_case("FIELDS")) {
+            return Err(
+                "ERR Mandatory argument FIELDS is missing or not at the right position".to_string(),
+            );
+        }
+        let numfields = args
+            .get(at + 1)
+            .and_then(|n| n.parse::<i64>().ok())
+            .filter(|&n| n > 0)
+            .ok_or("ERR Number of fields must be a positive integer")? as usize;
+        if numfields != args.len() - at - 2 {
+            return Err("ERR The `numfields` parameter must match the number of arguments".to_string());
+        }
+
+        let fields: Vec<SDS> = args.split_off(at + 2).iter().map(|f| SDS::from_str(f)).collect();
+        let key = args.swap_remove(0);
+        debug_assert_eq!(fields.len(), numfields, "Postcondition: one field per numfields");
+        Ok(if setter {
+            Command::HExpire { key, time, form, condition, fields }
+        } else if name == "HPERSIST" {
+            Command::HPersist(key, fields)
+        } else {
+            Command::HTtl { key, form, fields }
+        })
+    }
+
+    /// Parse the unified ZRANGE and ZRANGESTORE (everything after the name).
+    pub(super) fn parse_zrange(store: bool, mut args: Vec<String>) -> Result<Command, String> {
+        const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+        let dest = if store { Some(args.remove(0)) } else { None };
+        let (start, stop) = (args[1].clone(), args[2].clone());
+
+        #[derive(PartialEq)]
+        enum By {
+            Rank,
+            Score,
+            Lex,
+        }
+        let mut by = By::Rank;
+        let mut rev = false;
+        let mut limit = None;
+        let mut with_scores = false;
+        let mut i = 3;
+        while i < args.len() {
+            let remaining = args.len() - i - 1;
+            match args[i].to_ascii_uppercase().as_str() {
+                "BYSCORE" => by = By::Score,
+                "BYLEX" => by = By::Lex,
+                "REV" => rev = true,
+                "WITHSCORES" if !store => with_scores = true,
+                "LIMIT" if remaining >= 2 => {
+                    let offset = args[i + 1].parse::<i64>().map_err(|_| NOT_AN_INTEGER)?;
+                    let count = args[i + 2].parse::<i64>().map_err(|_| NOT_AN_INTEGER)?;
+                    limit = Some((offset, count));
+                    i += 2;
+                }
+                _ => return Err("ERR syntax error".to_string()),
+            }
+            i += 1;
+        }
+
+        if limit.is_some() && by == By::Rank {
+            return Err(
+                "ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+                    .to_string(),
+            );
+        }
+        if with_scores && by == By::Lex {
+            return Err(
+                "ERR syntax error, WITHSCORES not supported in combination with BYLEX".to_string(),
+            );
+        }
+        // REV takes score and lex bounds max first
+        let (min, max) = if rev {
+            (stop.clone(), start.clone())
+        } else {
+            (start.clone(), stop.clone())
+        };
+        let range = match by {
+            By::Rank => ZRangeSpec::Rank(
+                start.parse().map_err(|_| NOT_AN_INTEGER)?,
+                stop.parse().map_err(|_| NOT_AN_INTEGER)?,
+            ),
+            By::Score => ZRangeSpec::Score(min, max),
+            By::Lex => ZRangeSpec::Lex(min, max),
+        };
+        Ok(Command::ZRange {
+            dest,
+            key: args.swap_remove(0),
+            range,
+            rev,
+            limit,
+            with_scores,
+        })
+    }
+
+    /// Split the argc-prefixed commands of DEBUG SNAPSHOT-READ and parse
+    /// each with `parse`. Generic over the argument type, so each parser
+    /// passes its own values.
+    pub(super) fn parse_snapshot_reads<T>(
+        args: &[T],
+        argc: impl Fn(&T) -> Result<isize, String>,
+        parse: impl Fn(&[T]) -> Result<Command, String>,
+    ) -> Result<Command, String> {
+        const WRONG_ARGS: &str = "ERR wrong number of arguments for 'debug|snapshot-read' command";
+        let mut cmds = Vec::new();
+        let mut i = 0;
+        while i < args.len() {
+            let n = argc(&args[i])?;
+            if n < 1 || i + 1 + n as usize > args.len() {
+                return Err(WRONG_ARGS.to_string());
+            }
+            let cmd = parse(&args[i + 1..i + 1 + n as usize])?;
+            if !cmd.is_read_only() {
+                return Err(format!(
+                    "ERR DEBUG SNAPSHOT-READ only runs read-only commands, got '{}'",
+                    cmd.name().to_ascii_lowercase()
+                ));
+            }
+            cmds.push(cmd);
+            i += 1 + n as usize;
+        }
+        if cmds.is_empty() {
+            return Err(WRONG_ARGS.to_string());
+        }
+        Ok(Command::DebugSnapshotRead(cmds))
+    }
+
+    /// Parse the LPOS options after the key and element.
+    pub(super) fn parse_lpos(key: String, element: SDS, options: &[String]) -> Result<Command, String> {
+        const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+        let mut rank = 1;
+        let mut count = None;
+        let mut maxlen = 0;
+        let mut i = 0;
+        while i < options.len() {
+            let Some(value) = options.get(i + 1) else {
+                return Err("ERR syntax error".to_string());
+            };
+            match options[i].to_ascii_uppercase().as_str() {
+                "RANK" => {
+                    rank = value.parse::<i64>().map_err(|_| NOT_AN_INTEGER.to_string())?;
+                    if rank == i64::MIN {
+                        return Err(format!(
+                            "ERR value is out of range, value must between {} and {}",
+                            -i64::MAX,
+                            i64::MAX
+                        ));
+                    }
+                    if rank == 0 {
+                        return Err("ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list".to_string());
+                    }
+                }
+                "COUNT" => {
+                    let n = value.parse::<i64>().map_err(|_| NOT_AN_INTEGER.to_string())?;
+                    if n < 0 {
+                        return Err("ERR COUNT can't be negative".to_string());
+                    }
+                    count = Some(n as usize);
+                }
+                "MAXLEN" => {
+                    let n = value.parse::<i64>().map_err(|_| NOT_AN_INTEGER.to_string())?;
+                    if n < 0 {
+                        return Err("ERR MAXLEN can't be negative".to_string());
+                    }
+                    maxlen = n as usize;
+                }
+                _ => return Err("ERR syntax error".to_string()),
+            }
+            i += 2;
+        }
+        debug_assert!(rank != 0, "Postcondition: LPOS rank must not be zero");
+        Ok(Command::LPos {
+            key,
+            element,
+            rank,
+            count,
+            maxlen,
+        })
+    }
+
+    /// Parse BITFIELD/BITFIELD_RO sub-operations (everything after the key).
+    pub(super) fn parse_bitfield_ops(
+        args: &[String],
+        read_only: bool,
+    ) -> Result<Vec<BitFieldOp>, String> {
+        let mut ops = Vec::new();
+        let mut i = 0;
+        while i < args.len() {
+            let remaining = args.len() - i - 1;
+            let sub = args[i].to_uppercase();
+            match sub.as_str() {
+                "OVERFLOW" if remaining >= 1 => {
+                    let overflow = match args[i + 1].to_uppercase().as_str() {
+                        "WRAP" => BitFieldOverflow::Wrap,
+                        "SAT" => BitFieldOverflow::Sat,
+                        "FAIL" => BitFieldOverflow::Fail,
+                        _ => return Err("ERR Invalid OVERFLOW type specified".to_string()),
+                    };
+                    ops.push(BitFieldOp::Overflow(overflow));
+                    i += 2;
+                    continue;
+                }
+                "GET" if remaining >= 2 => {}
+                "SET" | "INCRBY" if remaining >= 3 => {}
+                _ => return Err("ERR syntax error".to_string()),
+            }
+
+            let ty = BitFieldType::parse(&args[i + 1]).ok_or_else(|| {
+                "ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.".to_string()
+            })?;
+            let offset_err = || "ERR bit offset is not an integer or out of range".to_string();
+            let offset = match args[i + 2].strip_prefix('#') {
+                Some(index) => index
+                    .parse::<u64>()
+                    .ok()
+                    .and_then(|n| n.checked_mul(ty.bits as u64))
+                    .ok_or_else(offset_err)?,
+                None => args[i + 2].parse::<u64>().map_err(|_| offset_err())?,
+            };
+            if sub == "GET" {
+                ops.push(BitFieldOp::Get { ty, offset });
+                i += 3;
+                continue;
+            }
+
+            if read_only {
+                return Err("ERR BITFIELD_RO only supports the GET subcommand".to_string());
+            }
+            let value = args[i + 3]
+                .parse::<i64>()
+                .map_err(|_| "ERR value is not an integer or out of range".to_string())?;
+            ops.push(if sub == "SET" {
+                BitFieldOp::Set { ty, offset, value }
+            } else {
+                BitFieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment: value,
+                }
+            });
+            i += 4;
+        }
+        Ok(ops)
+    }
+
     // =========================================================================
     // Extract helpers for RespValue
     // =========================================================================
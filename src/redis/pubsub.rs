@@ -0,0 +1,607 @@
+//! Pub/Sub messaging: PUBLISH, (P)SUBSCRIBE/(P)UNSUBSCRIBE and PUBSUB
+//! CHANNELS/NUMSUB/NUMPAT.
+//!
+//! This module provides:
+//! - `PubSubManager`: the server-wide channel registry. Cheap to clone; every
+//!   shard executor and every connection holds a handle to the same registry.
+//! - `PubSubSession`: one client's subscription state and message mailbox.
+//!
+//! Delivery model:
+//! - Each session owns a bounded mailbox. `publish` pushes into the mailbox of
+//!   every subscriber of the channel while holding the registry lock, so all
+//!   subscribers observe messages on a channel in the same order.
+//! - A subscriber whose mailbox is full is disconnected rather than allowed to
+//!   block publishers (Redis' `client-output-buffer-limit pubsub`). Its
+//!   session drains what was already queued and then reports the disconnect.
+//! - A message goes first to the channel's subscribers, then to each matching
+//!   pattern's subscribers in pattern order (as a `pmessage`). A client
+//!   subscribed both ways receives it once per subscription, as in Redis.
+//! - Subscriber sets are ordered (`BTreeMap`/`BTreeSet`), so delivery order is
+//!   deterministic under simulation.
+//!
+//! TigerStyle: All functions have precondition/postcondition assertions.
+
+use super::executor::glob_match;
+use super::resp::RespValue;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Default number of undelivered messages a subscriber may have queued
+pub const DEFAULT_MAILBOX_CAPACITY: usize = 1024;
+
+/// Identifies one subscriber (one `PubSubSession`)
+pub type SubscriberId = u64;
+
+/// A message delivered to a subscriber
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PubSubMessage {
+    /// The PSUBSCRIBE pattern that matched (None = channel subscription)
+    pub pattern: Option<String>,
+    pub channel: String,
+    pub payload: Vec<u8>,
+}
+
+impl PubSubMessage {
+    /// The push frame: `["message", channel, payload]`, or
+    /// `["pmessage", pattern, channel, payload]` for pattern subscriptions
+    pub fn to_resp(&self) -> RespValue {
+        let mut frame = Vec::with_capacity(4);
+        match &self.pattern {
+            Some(pattern) => {
+                frame.push(RespValue::BulkString(Some(b"pmessage".to_vec())));
+                frame.push(RespValue::BulkString(Some(pattern.as_bytes().to_vec())));
+            }
+            None => frame.push(RespValue::BulkString(Some(b"message".to_vec()))),
+        }
+        frame.push(RespValue::BulkString(Some(self.channel.as_bytes().to_vec())));
+        frame.push(RespValue::BulkString(Some(self.payload.clone())));
+        RespValue::Push(frame)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// channel -> subscribers; channels with no subscribers are removed
+    channels: BTreeMap<String, BTreeSet<SubscriberId>>,
+    /// glob pattern -> subscribers; likewise never empty
+    patterns: BTreeMap<String, BTreeSet<SubscriberId>>,
+    /// Live subscribers' mailboxes
+    mailboxes: BTreeMap<SubscriberId, mpsc::Sender<PubSubMessage>>,
+    next_id: SubscriberId,
+    /// Subscribers disconnected because their mailbox overflowed
+    dropped_subscribers: u64,
+}
+
+impl Registry {
+    /// Forget a subscriber: drop its mailbox sender and all its subscriptions
+    fn remove(&mut self, id: SubscriberId) {
+        self.mailboxes.remove(&id);
+        for map in [&mut self.channels, &mut self.patterns] {
+            map.retain(|_, subscribers| {
+                subscribers.remove(&id);
+                !subscribers.is_empty()
+            });
+        }
+
+        debug_assert!(
+            !self
+                .channels
+                .values()
+                .chain(self.patterns.values())
+                .any(|s| s.contains(&id)),
+            "Postcondition: removed subscriber must not remain subscribed"
+        );
+    }
+
+    fn map(&mut self, kind: SubscriptionKind) -> &mut BTreeMap<String, BTreeSet<SubscriberId>> {
+        match kind {
+            SubscriptionKind::Channel => &mut self.channels,
+            SubscriptionKind::Pattern => &mut self.patterns,
+        }
+    }
+
+    /// Verify the registry is internally consistent
+    ///
+    /// No-op in release builds.
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        for (channel, subscribers) in self.channels.iter().chain(&self.patterns) {
+            // Invariant 1: No empty channel or pattern entries
+            debug_assert!(
+                !subscribers.is_empty(),
+                "Invariant violated: channel '{}' has no subscribers",
+                channel
+            );
+            // Invariant 2: Every subscriber has a live mailbox
+            for id in subscribers {
+                debug_assert!(
+                    self.mailboxes.contains_key(id),
+                    "Invariant violated: subscriber {} of '{}' has no mailbox",
+                    id,
+                    channel
+                );
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn verify_invariants(&self) {}
+}
+
+/// Server-wide Pub/Sub registry
+///
+/// Clones share the same registry, like `SharedScriptCache`.
+#[derive(Debug, Clone)]
+pub struct PubSubManager {
+    inner: Arc<Mutex<Registry>>,
+    mailbox_capacity: usize,
+}
+
+impl Default for PubSubManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PubSubManager {
+    pub fn new() -> Self {
+        Self::with_mailbox_capacity(DEFAULT_MAILBOX_CAPACITY)
+    }
+
+    /// Create a manager whose subscribers may queue at most `capacity` messages
+    pub fn with_mailbox_capacity(capacity: usize) -> Self {
+        debug_assert!(capacity > 0, "Precondition: mailbox capacity must be > 0");
+        PubSubManager {
+            inner: Arc::new(Mutex::new(Registry::default())),
+            mailbox_capacity: capacity,
+        }
+    }
+
+    /// Register a new subscriber with an empty subscription set
+    pub fn session(&self) -> PubSubSession {
+        let (tx, rx) = mpsc::channel(self.mailbox_capacity);
+        let mut registry = self.inner.lock();
+        let id = registry.next_id;
+        registry.next_id += 1;
+        registry.mailboxes.insert(id, tx);
+
+        debug_assert!(
+            registry.mailboxes.contains_key(&id),
+            "Postcondition: new subscriber must have a mailbox"
+        );
+        PubSubSession {
+            manager: self.clone(),
+            id,
+            receiver: rx,
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+        }
+    }
+
+    /// Deliver `payload` to every subscriber of `channel` and of every
+    /// pattern matching it.
+    ///
+    /// Returns the number of messages queued (a client subscribed both to
+    /// the channel and to matching patterns counts once per subscription).
+    /// Subscribers whose mailbox is full (or whose session is gone) are
+    /// disconnected and not counted further.
+    pub fn publish(&self, channel: &str, payload: &[u8]) -> usize {
+        let mut registry = self.inner.lock();
+
+        let mut deliveries: Vec<(SubscriberId, PubSubMessage)> = Vec::new();
+        if let Some(subscribers) = registry.channels.get(channel) {
+            let message = PubSubMessage {
+                pattern: None,
+                channel: channel.to_string(),
+                payload: payload.to_vec(),
+            };
+            deliveries.extend(subscribers.iter().map(|id| (*id, message.clone())));
+        }
+        for (pattern, subscribers) in &registry.patterns {
+            if !glob_match(channel.as_bytes(), pattern.as_bytes()) {
+                continue;
+            }
+            let message = PubSubMessage {
+                pattern: Some(pattern.clone()),
+                channel: channel.to_string(),
+                payload: payload.to_vec(),
+            };
+            deliveries.extend(subscribers.iter().map(|id| (*id, message.clone())));
+        }
+
+        let mut delivered = 0;
+        let mut overflowed = BTreeSet::new();
+        for (id, message) in deliveries {
+            if overflowed.contains(&id) {
+                continue;
+            }
+            let sent = registry
+                .mailboxes
+                .get(&id)
+                .map(|tx| tx.try_send(message).is_ok())
+                .unwrap_or(false);
+            if sent {
+                delivered += 1;
+            } else {
+                overflowed.insert(id);
+            }
+        }
+
+        for id in &overflowed {
+            registry.remove(*id);
+            registry.dropped_subscribers += 1;
+        }
+
+        registry.verify_invariants();
+        delivered
+    }
+
+    /// Number of channels with at least one subscriber
+    pub fn num_channels(&self) -> usize {
+        self.inner.lock().channels.len()
+    }
+
+    /// PUBSUB CHANNELS: active channels, optionally filtered by a glob
+    pub fn channels(&self, pattern: Option<&str>) -> Vec<String> {
+        self.inner
+            .lock()
+            .channels
+            .keys()
+            .filter(|c| pattern.is_none_or(|p| glob_match(c.as_bytes(), p.as_bytes())))
+            .cloned()
+            .collect()
+    }
+
+    /// PUBSUB NUMPAT: number of distinct patterns with at least one subscriber
+    pub fn num_patterns(&self) -> usize {
+        self.inner.lock().patterns.len()
+    }
+
+    /// Number of subscribers of `channel`
+    pub fn num_subscribers(&self, channel: &str) -> usize {
+        self.inner
+            .lock()
+            .channels
+            .get(channel)
+            .map_or(0, BTreeSet::len)
+    }
+
+    /// Subscribers disconnected so far because their mailbox overflowed
+    pub fn dropped_subscribers(&self) -> u64 {
+        self.inner.lock().dropped_subscribers
+    }
+
+    /// Add `id` to a channel or pattern. Returns false if the subscriber
+    /// was disconnected.
+    fn subscribe(&self, id: SubscriberId, kind: SubscriptionKind, name: &str) -> bool {
+        let mut registry = self.inner.lock();
+        if !registry.mailboxes.contains_key(&id) {
+            return false;
+        }
+        registry
+            .map(kind)
+            .entry(name.to_string())
+            .or_default()
+            .insert(id);
+
+        registry.verify_invariants();
+        true
+    }
+
+    fn unsubscribe(&self, id: SubscriberId, kind: SubscriptionKind, name: &str) {
+        let mut registry = self.inner.lock();
+        let map = registry.map(kind);
+        if let Some(subscribers) = map.get_mut(name) {
+            subscribers.remove(&id);
+            if subscribers.is_empty() {
+                map.remove(name);
+            }
+        }
+
+        registry.verify_invariants();
+    }
+
+    fn remove(&self, id: SubscriberId) {
+        let mut registry = self.inner.lock();
+        registry.remove(id);
+        registry.verify_invariants();
+    }
+}
+
+/// One client's Pub/Sub state: its subscriptions and its message mailbox.
+///
+/// Dropping the session unsubscribes it from everything.
+#[derive(Debug)]
+pub struct PubSubSession {
+    manager: PubSubManager,
+    id: SubscriberId,
+    receiver: mpsc::Receiver<PubSubMessage>,
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+}
+
+impl PubSubSession {
+    pub fn id(&self) -> SubscriberId {
+        self.id
+    }
+
+    /// Number of channels and patterns this session is subscribed to
+    pub fn subscription_count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    /// A subscribed client may only issue subscription commands (RESP2)
+    pub fn is_subscribed(&self) -> bool {
+        self.subscription_count() > 0
+    }
+
+    /// SUBSCRIBE: one `["subscribe", channel, count]` reply per channel
+    pub fn subscribe(&mut self, channels: &[String]) -> Vec<RespValue> {
+        self.add(SubscriptionKind::Channel, channels)
+    }
+
+    /// UNSUBSCRIBE: one `["unsubscribe", channel, count]` reply per channel.
+    ///
+    /// With no channels, unsubscribes from all of them; if there were none,
+    /// replies once with a nil channel.
+    pub fn unsubscribe(&mut self, channels: &[String]) -> Vec<RespValue> {
+        self.remove(SubscriptionKind::Channel, channels)
+    }
+
+    /// PSUBSCRIBE: one `["psubscribe", pattern, count]` reply per pattern
+    pub fn psubscribe(&mut self, patterns: &[String]) -> Vec<RespValue> {
+        self.add(SubscriptionKind::Pattern, patterns)
+    }
+
+    /// PUNSUBSCRIBE: like UNSUBSCRIBE, for patterns
+    pub fn punsubscribe(&mut self, patterns: &[String]) -> Vec<RespValue> {
+        self.remove(SubscriptionKind::Pattern, patterns)
+    }
+
+    fn names_mut(&mut self, kind: SubscriptionKind) -> &mut BTreeSet<String> {
+        match kind {
+            SubscriptionKind::Channel => &mut self.channels,
+            SubscriptionKind::Pattern => &mut self.patterns,
+        }
+    }
+
+    fn add(&mut self, kind: SubscriptionKind, names: &[String]) -> Vec<RespValue> {
+        debug_assert!(
+            !names.is_empty(),
+            "Precondition: {} needs at least one argument",
+            kind.subscribe_reply()
+        );
+
+        let replies: Vec<RespValue> = names
+            .iter()
+            .map(|name| {
+                if self.manager.subscribe(self.id, kind, name) {
+                    self.names_mut(kind).insert(name.clone());
+                }
+                subscription_reply(kind.subscribe_reply(), Some(name), self.subscription_count())
+            })
+            .collect();
+
+        debug_assert_eq!(replies.len(), names.len());
+        replies
+    }
+
+    fn remove(&mut self, kind: SubscriptionKind, names: &[String]) -> Vec<RespValue> {
+        let targets: Vec<String> = if names.is_empty() {
+            self.names_mut(kind).iter().cloned().collect()
+        } else {
+            names.to_vec()
+        };
+        if targets.is_empty() {
+            return vec![subscription_reply(
+                kind.unsubscribe_reply(),
+                None,
+                self.subscription_count(),
+            )];
+        }
+
+        let replies: Vec<RespValue> = targets
+            .iter()
+            .map(|name| {
+                self.names_mut(kind).remove(name);
+                self.manager.unsubscribe(self.id, kind, name);
+                subscription_reply(kind.unsubscribe_reply(), Some(name), self.subscription_count())
+            })
+            .collect();
+
+        debug_assert!(
+            !names.is_empty() || self.names_mut(kind).is_empty(),
+            "Postcondition: {} without arguments must leave none",
+            kind.unsubscribe_reply()
+        );
+        replies
+    }
+
+    /// Wait for the next message.
+    ///
+    /// Returns `None` once the session has been disconnected for falling
+    /// behind and every message queued before that has been received.
+    pub async fn recv(&mut self) -> Option<PubSubMessage> {
+        self.receiver.recv().await
+    }
+
+    /// Take the next already-queued message without waiting
+    pub fn try_recv(&mut self) -> Option<PubSubMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for PubSubSession {
+    fn drop(&mut self) {
+        self.manager.remove(self.id);
+    }
+}
+
+/// Whether a subscription names a channel or a glob pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubscriptionKind {
+    Channel,
+    Pattern,
+}
+
+impl SubscriptionKind {
+    fn subscribe_reply(self) -> &'static str {
+        match self {
+            SubscriptionKind::Channel => "subscribe",
+            SubscriptionKind::Pattern => "psubscribe",
+        }
+    }
+
+    fn unsubscribe_reply(self) -> &'static str {
+        match self {
+            SubscriptionKind::Channel => "unsubscribe",
+            SubscriptionKind::Pattern => "punsubscribe",
+        }
+    }
+}
+
+/// Subscription changes are pushed, like the messages they start
+fn subscription_reply(kind: &str, channel: Option<&str>, count: usize) -> RespValue {
+    RespValue::Push(vec![
+        RespValue::BulkString(Some(kind.as_bytes().to_vec())),
+        RespValue::BulkString(channel.map(|c| c.as_bytes().to_vec())),
+        RespValue::Integer(count as i64),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_publish_reaches_only_subscribers() {
+        let manager = PubSubManager::new();
+        let mut a = manager.session();
+        let mut b = manager.session();
+        a.subscribe(&channels(&["news", "sports"]));
+        b.subscribe(&channels(&["news"]));
+
+        assert_eq!(manager.publish("news", b"hello"), 2);
+        assert_eq!(manager.publish("sports", b"goal"), 1);
+        assert_eq!(manager.publish("weather", b"rain"), 0);
+
+        assert_eq!(a.try_recv().unwrap().payload, b"hello");
+        assert_eq!(a.try_recv().unwrap().payload, b"goal");
+        assert_eq!(a.try_recv(), None);
+        assert_eq!(b.try_recv().unwrap().channel, "news");
+        assert_eq!(b.try_recv(), None);
+    }
+
+    #[test]
+    fn test_subscription_replies_count_channels() {
+        let manager = PubSubManager::new();
+        let mut session = manager.session();
+        let replies = session.subscribe(&channels(&["a", "b", "a"]));
+        assert_eq!(replies[0], subscription_reply("subscribe", Some("a"), 1));
+        assert_eq!(replies[1], subscription_reply("subscribe", Some("b"), 2));
+        assert_eq!(replies[2], subscription_reply("subscribe", Some("a"), 2));
+
+        let replies = session.unsubscribe(&[]);
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[1], subscription_reply("unsubscribe", Some("b"), 0));
+        assert!(!session.is_subscribed());
+        assert_eq!(manager.num_channels(), 0);
+
+        assert_eq!(
+            session.unsubscribe(&[]),
+            vec![subscription_reply("unsubscribe", None, 0)]
+        );
+    }
+
+    #[test]
+    fn test_dropping_session_unsubscribes() {
+        let manager = PubSubManager::new();
+        let mut session = manager.session();
+        session.subscribe(&channels(&["c"]));
+        assert_eq!(manager.num_subscribers("c"), 1);
+        drop(session);
+        assert_eq!(manager.num_subscribers("c"), 0);
+        assert_eq!(manager.publish("c", b"x"), 0);
+    }
+
+    #[test]
+    fn test_pattern_subscriptions() {
+        let manager = PubSubManager::new();
+        let mut session = manager.session();
+        session.subscribe(&channels(&["news.tech"]));
+        let replies = session.psubscribe(&channels(&["news.*", "sport?"]));
+        assert_eq!(replies[1], subscription_reply("psubscribe", Some("sport?"), 3));
+        assert_eq!(manager.num_patterns(), 2);
+
+        // Once for the channel, once for the matching pattern
+        assert_eq!(manager.publish("news.tech", b"rust"), 2);
+        assert_eq!(manager.publish("sports", b"goal"), 1);
+        assert_eq!(manager.publish("weather", b"rain"), 0);
+
+        assert_eq!(session.try_recv().unwrap().pattern, None);
+        let pmessage = session.try_recv().unwrap();
+        assert_eq!(pmessage.pattern.as_deref(), Some("news.*"));
+        assert_eq!(
+            pmessage.to_resp(),
+            RespValue::Push(vec![
+                RespValue::BulkString(Some(b"pmessage".to_vec())),
+                RespValue::BulkString(Some(b"news.*".to_vec())),
+                RespValue::BulkString(Some(b"news.tech".to_vec())),
+                RespValue::BulkString(Some(b"rust".to_vec())),
+            ])
+        );
+        assert_eq!(session.try_recv().unwrap().channel, "sports");
+
+        // PUNSUBSCRIBE without arguments leaves channel subscriptions alone
+        let replies = session.punsubscribe(&[]);
+        assert_eq!(replies[1], subscription_reply("punsubscribe", Some("sport?"), 1));
+        assert_eq!(manager.num_patterns(), 0);
+        assert!(session.is_subscribed());
+    }
+
+    #[test]
+    fn test_channels_introspection() {
+        let manager = PubSubManager::new();
+        let mut a = manager.session();
+        let mut b = manager.session();
+        a.subscribe(&channels(&["news", "sports"]));
+        b.subscribe(&channels(&["news"]));
+        b.psubscribe(&channels(&["n*"]));
+
+        assert_eq!(manager.channels(None), channels(&["news", "sports"]));
+        assert_eq!(manager.channels(Some("s*")), channels(&["sports"]));
+        assert_eq!(manager.num_subscribers("news"), 2);
+        // Pattern subscribers are not channel subscribers
+        assert_eq!(manager.num_subscribers("nothing"), 0);
+        assert_eq!(manager.num_patterns(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mailbox_overflow_disconnects_subscriber() {
+        let manager = PubSubManager::with_mailbox_capacity(2);
+        let mut slow = manager.session();
+        let mut fast = manager.session();
+        slow.subscribe(&channels(&["c"]));
+        fast.subscribe(&channels(&["c"]));
+
+        assert_eq!(manager.publish("c", b"1"), 2);
+        fast.try_recv().unwrap();
+        assert_eq!(manager.publish("c", b"2"), 2);
+        fast.try_recv().unwrap();
+        // slow's mailbox is full: it is disconnected, fast still gets it
+        assert_eq!(manager.publish("c", b"3"), 1);
+        assert_eq!(manager.dropped_subscribers(), 1);
+        assert_eq!(fast.try_recv().unwrap().payload, b"3");
+
+        // slow drains what it had, then sees the disconnect
+        assert_eq!(slow.recv().await.unwrap().payload, b"1");
+        assert_eq!(slow.recv().await.unwrap().payload, b"2");
+        assert_eq!(slow.recv().await, None);
+    }
+}
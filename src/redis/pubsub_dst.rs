@@ -0,0 +1,642 @@
+//! Deterministic Simulation Testing for Pub/Sub
+//!
+//! Shadow-state testing harness for `PubSubManager` that enables:
+//! - Deterministic interleaving of N publishers and M subscribers, mixing
+//!   channel and glob pattern subscriptions
+//! - Slow consumers: small mailboxes, so subscribers regularly fall behind and
+//!   get disconnected, the server's only message-loss mechanism
+//! - Shadow log of every published `(publisher, channel, seq)` tuple
+//! - Seed-based reproducibility for debugging
+//!
+//! Each subscriber's shadow is the exact queue of messages it must receive.
+//! Comparing every received message against the head of that queue checks:
+//! - per publisher/subscriber/channel FIFO ordering (sequence numbers
+//!   strictly increase)
+//! - no phantom messages (every delivered message is in the shadow log)
+//! - no delivery to a subscriber that was not subscribed at publish time
+//! - no loss for subscribers that kept up
+//! - one delivery per matching subscription (channel first, then patterns in
+//!   order) for clients subscribed several ways
+
+use super::executor::glob_match;
+use super::pubsub::{PubSubManager, PubSubMessage, PubSubSession};
+use crate::io::simulation::SimulatedRng;
+use crate::io::Rng;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+/// Configuration for Pub/Sub DST
+#[derive(Debug, Clone)]
+pub struct PubSubDSTConfig {
+    /// Random seed for reproducibility
+    pub seed: u64,
+    pub num_publishers: usize,
+    pub num_subscribers: usize,
+    pub num_channels: usize,
+    /// Undelivered messages a subscriber may queue before it is disconnected
+    pub mailbox_capacity: usize,
+    /// Probability of a publish (vs. subscription changes and reads)
+    pub publish_prob: f64,
+    /// Probability a subscriber reads its mailbox on a given step
+    pub drain_prob: f64,
+    /// Probability a subscription change targets a pattern (vs. a channel)
+    pub pattern_prob: f64,
+}
+
+impl Default for PubSubDSTConfig {
+    fn default() -> Self {
+        PubSubDSTConfig {
+            seed: 0,
+            num_publishers: 4,
+            num_subscribers: 6,
+            num_channels: 5,
+            mailbox_capacity: 16,
+            publish_prob: 0.5,
+            drain_prob: 0.25,
+            pattern_prob: 0.3,
+        }
+    }
+}
+
+impl PubSubDSTConfig {
+    pub fn new(seed: u64) -> Self {
+        PubSubDSTConfig {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    /// Tiny mailboxes and rare reads: most subscribers get disconnected
+    pub fn slow_consumers(seed: u64) -> Self {
+        PubSubDSTConfig {
+            seed,
+            mailbox_capacity: 3,
+            publish_prob: 0.7,
+            drain_prob: 0.1,
+            ..Default::default()
+        }
+    }
+
+    /// Few channels, many subscribers: heavy fan-out per publish
+    pub fn fan_out(seed: u64) -> Self {
+        PubSubDSTConfig {
+            seed,
+            num_subscribers: 16,
+            num_channels: 2,
+            mailbox_capacity: 64,
+            ..Default::default()
+        }
+    }
+
+    /// Mostly pattern subscriptions: overlapping patterns multiply deliveries
+    pub fn patterns(seed: u64) -> Self {
+        PubSubDSTConfig {
+            seed,
+            mailbox_capacity: 32,
+            pattern_prob: 0.8,
+            ..Default::default()
+        }
+    }
+}
+
+/// Patterns the harness subscribes to: all channels, a range, single-digit
+/// suffixes and one that matches nothing
+const PATTERNS: &[&str] = &["channel:*", "channel:[0-1]", "channel:?", "chan*:3", "other:*"];
+
+/// Operation type for logging
+#[derive(Debug, Clone)]
+pub enum PubSubOp {
+    Publish {
+        publisher: usize,
+        channel: String,
+        seq: u64,
+    },
+    Subscribe {
+        subscriber: usize,
+        channel: String,
+    },
+    Unsubscribe {
+        subscriber: usize,
+        channel: Option<String>,
+    },
+    PSubscribe {
+        subscriber: usize,
+        pattern: String,
+    },
+    PUnsubscribe {
+        subscriber: usize,
+        pattern: Option<String>,
+    },
+    Drain {
+        subscriber: usize,
+    },
+    Reconnect {
+        subscriber: usize,
+    },
+}
+
+/// Result of a Pub/Sub DST run
+#[derive(Debug, Clone)]
+pub struct PubSubDSTResult {
+    pub seed: u64,
+    pub total_operations: u64,
+    pub publishes: u64,
+    pub deliveries: u64,
+    pub subscriptions: u64,
+    pub disconnects: u64,
+    pub invariant_violations: Vec<String>,
+    pub last_op: Option<PubSubOp>,
+}
+
+impl PubSubDSTResult {
+    pub fn new(seed: u64) -> Self {
+        PubSubDSTResult {
+            seed,
+            total_operations: 0,
+            publishes: 0,
+            deliveries: 0,
+            subscriptions: 0,
+            disconnects: 0,
+            invariant_violations: Vec::new(),
+            last_op: None,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.invariant_violations.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Seed {}: {} ops (publishes:{}, deliveries:{}, subscriptions:{}, disconnects:{}), {} violations",
+            self.seed,
+            self.total_operations,
+            self.publishes,
+            self.deliveries,
+            self.subscriptions,
+            self.disconnects,
+            self.invariant_violations.len()
+        )
+    }
+}
+
+/// Shadow state for one subscriber connection
+#[derive(Debug, Default)]
+struct ShadowSubscriber {
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+    /// Messages queued for this subscriber, in delivery order
+    expected: VecDeque<PubSubMessage>,
+    /// Dropped by the server for falling behind; still owed `expected`
+    disconnected: bool,
+}
+
+/// DST harness for Pub/Sub
+pub struct PubSubDSTHarness {
+    config: PubSubDSTConfig,
+    rng: SimulatedRng,
+    manager: PubSubManager,
+    sessions: Vec<PubSubSession>,
+    shadows: Vec<ShadowSubscriber>,
+    /// Next sequence number per publisher
+    next_seq: Vec<u64>,
+    /// Every payload ever published
+    published: HashSet<Vec<u8>>,
+    result: PubSubDSTResult,
+}
+
+impl PubSubDSTHarness {
+    pub fn new(config: PubSubDSTConfig) -> Self {
+        debug_assert!(config.num_publishers > 0, "Precondition: need publishers");
+        debug_assert!(config.num_subscribers > 0, "Precondition: need subscribers");
+        debug_assert!(config.num_channels > 0, "Precondition: need channels");
+
+        let rng = SimulatedRng::new(config.seed);
+        let manager = PubSubManager::with_mailbox_capacity(config.mailbox_capacity);
+        let sessions = (0..config.num_subscribers)
+            .map(|_| manager.session())
+            .collect();
+        let shadows = (0..config.num_subscribers)
+            .map(|_| ShadowSubscriber::default())
+            .collect();
+        PubSubDSTHarness {
+            result: PubSubDSTResult::new(config.seed),
+            next_seq: vec![0; config.num_publishers],
+            config,
+            rng,
+            manager,
+            sessions,
+            shadows,
+            published: HashSet::new(),
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(PubSubDSTConfig::new(seed))
+    }
+
+    fn random_channel(&mut self) -> String {
+        let idx = self.rng.gen_range(0, self.config.num_channels as u64);
+        format!("channel:{}", idx)
+    }
+
+    fn random_pattern(&mut self) -> String {
+        let idx = self.rng.gen_range(0, PATTERNS.len() as u64) as usize;
+        PATTERNS[idx].to_string()
+    }
+
+    fn random_subscriber(&mut self) -> usize {
+        self.rng.gen_range(0, self.config.num_subscribers as u64) as usize
+    }
+
+    fn violation(&mut self, msg: String) {
+        self.result.invariant_violations.push(format!(
+            "Op #{}: {:?} - {}",
+            self.result.total_operations, self.result.last_op, msg
+        ));
+    }
+
+    fn run_single_op(&mut self) {
+        let roll = self.rng.gen_range(0, 1000) as f64 / 1000.0;
+        if roll < self.config.publish_prob {
+            self.op_publish();
+        } else if roll < self.config.publish_prob + self.config.drain_prob {
+            let subscriber = self.random_subscriber();
+            self.op_drain(subscriber);
+        } else {
+            let pattern = self.rng.gen_bool(self.config.pattern_prob);
+            match (self.rng.gen_range(0, 10), pattern) {
+                (0..=5, false) => self.op_subscribe(),
+                (0..=5, true) => self.op_psubscribe(),
+                (6..=8, false) => self.op_unsubscribe(),
+                (6..=8, true) => self.op_punsubscribe(),
+                _ => {
+                    let subscriber = self.random_subscriber();
+                    self.op_reconnect(subscriber);
+                }
+            }
+        }
+
+        self.result.total_operations += 1;
+
+        if let Err(violation) = self.check_invariants() {
+            self.violation(violation);
+        }
+    }
+
+    fn op_publish(&mut self) {
+        let publisher = self.rng.gen_range(0, self.config.num_publishers as u64) as usize;
+        let channel = self.random_channel();
+        let seq = self.next_seq[publisher];
+        self.next_seq[publisher] += 1;
+        self.result.last_op = Some(PubSubOp::Publish {
+            publisher,
+            channel: channel.clone(),
+            seq,
+        });
+
+        let payload = format!("p{}:{}", publisher, seq).into_bytes();
+        self.published.insert(payload.clone());
+
+        // Shadow: every connected subscriber gets one message per matching
+        // subscription (channel first, then patterns in order) until its
+        // mailbox is full, at which point it is disconnected
+        let capacity = self.config.mailbox_capacity;
+        let mut expected_count = 0;
+        for shadow in &mut self.shadows {
+            if shadow.disconnected {
+                continue;
+            }
+            let via_channel = shadow.channels.contains(&channel).then_some(None);
+            let via_patterns = shadow
+                .patterns
+                .iter()
+                .filter(|p| glob_match(channel.as_bytes(), p.as_bytes()))
+                .map(|p| Some(p.clone()));
+            for pattern in via_channel.into_iter().chain(via_patterns) {
+                if shadow.expected.len() == capacity {
+                    shadow.disconnected = true;
+                    self.result.disconnects += 1;
+                    break;
+                }
+                shadow.expected.push_back(PubSubMessage {
+                    pattern,
+                    channel: channel.clone(),
+                    payload: payload.clone(),
+                });
+                expected_count += 1;
+            }
+        }
+
+        let delivered = self.manager.publish(&channel, &payload);
+        self.result.publishes += 1;
+        if delivered != expected_count {
+            self.violation(format!(
+                "PUBLISH {} reached {} subscribers, expected {}",
+                channel, delivered, expected_count
+            ));
+        }
+    }
+
+    fn op_subscribe(&mut self) {
+        let subscriber = self.random_subscriber();
+        let channel = self.random_channel();
+        self.result.last_op = Some(PubSubOp::Subscribe {
+            subscriber,
+            channel: channel.clone(),
+        });
+
+        self.sessions[subscriber].subscribe(std::slice::from_ref(&channel));
+        let shadow = &mut self.shadows[subscriber];
+        // A disconnected subscriber's SUBSCRIBE never reaches the registry
+        if !shadow.disconnected {
+            shadow.channels.insert(channel);
+        }
+        self.result.subscriptions += 1;
+    }
+
+    fn op_unsubscribe(&mut self) {
+        let subscriber = self.random_subscriber();
+        let channel = if self.rng.gen_bool(0.2) {
+            None
+        } else {
+            Some(self.random_channel())
+        };
+        self.result.last_op = Some(PubSubOp::Unsubscribe {
+            subscriber,
+            channel: channel.clone(),
+        });
+
+        let shadow = &mut self.shadows[subscriber];
+        let args: Vec<String> = match channel {
+            Some(channel) => {
+                shadow.channels.remove(&channel);
+                vec![channel]
+            }
+            None => {
+                shadow.channels.clear();
+                Vec::new()
+            }
+        };
+        self.sessions[subscriber].unsubscribe(&args);
+    }
+
+    fn op_psubscribe(&mut self) {
+        let subscriber = self.random_subscriber();
+        let pattern = self.random_pattern();
+        self.result.last_op = Some(PubSubOp::PSubscribe {
+            subscriber,
+            pattern: pattern.clone(),
+        });
+
+        self.sessions[subscriber].psubscribe(std::slice::from_ref(&pattern));
+        let shadow = &mut self.shadows[subscriber];
+        if !shadow.disconnected {
+            shadow.patterns.insert(pattern);
+        }
+        self.result.subscriptions += 1;
+    }
+
+    fn op_punsubscribe(&mut self) {
+        let subscriber = self.random_subscriber();
+        let pattern = if self.rng.gen_bool(0.2) {
+            None
+        } else {
+            Some(self.random_pattern())
+        };
+        self.result.last_op = Some(PubSubOp::PUnsubscribe {
+            subscriber,
+            pattern: pattern.clone(),
+        });
+
+        let shadow = &mut self.shadows[subscriber];
+        let args: Vec<String> = match pattern {
+            Some(pattern) => {
+                shadow.patterns.remove(&pattern);
+                vec![pattern]
+            }
+            None => {
+                shadow.patterns.clear();
+                Vec::new()
+            }
+        };
+        self.sessions[subscriber].punsubscribe(&args);
+    }
+
+    /// Read everything queued for `subscriber` and compare with the shadow
+    fn op_drain(&mut self, subscriber: usize) {
+        self.result.last_op = Some(PubSubOp::Drain { subscriber });
+
+        while let Some(message) = self.sessions[subscriber].try_recv() {
+            self.result.deliveries += 1;
+            if !self.published.contains(&message.payload) {
+                self.violation(format!(
+                    "subscriber {} received phantom message {:?}",
+                    subscriber, message
+                ));
+                return;
+            }
+            match self.shadows[subscriber].expected.pop_front() {
+                Some(expected) if expected == message => {}
+                expected => {
+                    self.violation(format!(
+                        "subscriber {} received {:?}, expected {:?}",
+                        subscriber, message, expected
+                    ));
+                    return;
+                }
+            }
+        }
+
+        let missing = self.shadows[subscriber].expected.len();
+        if missing > 0 {
+            self.violation(format!(
+                "subscriber {} is missing {} queued messages",
+                subscriber, missing
+            ));
+        }
+
+        // The server dropped this client; it has now read all it was owed
+        if self.shadows[subscriber].disconnected {
+            self.op_reconnect(subscriber);
+        }
+    }
+
+    /// Replace a subscriber's connection with a fresh one
+    fn op_reconnect(&mut self, subscriber: usize) {
+        self.result.last_op = Some(PubSubOp::Reconnect { subscriber });
+        self.sessions[subscriber] = self.manager.session();
+        self.shadows[subscriber] = ShadowSubscriber::default();
+    }
+
+    fn check_invariants(&self) -> Result<(), String> {
+        // Invariant 1: Registry subscriber counts match the shadow
+        for idx in 0..self.config.num_channels {
+            let channel = format!("channel:{}", idx);
+            let expected = self
+                .shadows
+                .iter()
+                .filter(|s| !s.disconnected && s.channels.contains(&channel))
+                .count();
+            let actual = self.manager.num_subscribers(&channel);
+            if actual != expected {
+                return Err(format!(
+                    "{} has {} subscribers, expected {}",
+                    channel, actual, expected
+                ));
+            }
+        }
+
+        // Invariant 2: PUBSUB NUMPAT counts distinct live patterns
+        let live_patterns: BTreeSet<&String> = self
+            .shadows
+            .iter()
+            .filter(|s| !s.disconnected)
+            .flat_map(|s| &s.patterns)
+            .collect();
+        if self.manager.num_patterns() != live_patterns.len() {
+            return Err(format!(
+                "registry has {} patterns, expected {}",
+                self.manager.num_patterns(),
+                live_patterns.len()
+            ));
+        }
+
+        // Invariant 3: No subscriber is owed more than its mailbox holds
+        for (idx, shadow) in self.shadows.iter().enumerate() {
+            if shadow.expected.len() > self.config.mailbox_capacity {
+                return Err(format!(
+                    "subscriber {} owed {} messages, mailbox holds {}",
+                    idx,
+                    shadow.expected.len(),
+                    self.config.mailbox_capacity
+                ));
+            }
+        }
+
+        // Invariant 4: Disconnects counted by the registry match the shadow
+        if self.manager.dropped_subscribers() != self.result.disconnects {
+            return Err(format!(
+                "registry dropped {} subscribers, expected {}",
+                self.manager.dropped_subscribers(),
+                self.result.disconnects
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn run(&mut self, operations: usize) {
+        for _ in 0..operations {
+            self.run_single_op();
+            if !self.result.invariant_violations.is_empty() {
+                return;
+            }
+        }
+
+        // Final read: everything still queued must arrive
+        for subscriber in 0..self.config.num_subscribers {
+            self.op_drain(subscriber);
+            if !self.result.invariant_violations.is_empty() {
+                return;
+            }
+        }
+    }
+
+    pub fn result(&self) -> &PubSubDSTResult {
+        &self.result
+    }
+}
+
+/// Run a batch of DST tests
+pub fn run_pubsub_batch(
+    start_seed: u64,
+    num_seeds: usize,
+    ops_per_seed: usize,
+    config_fn: fn(u64) -> PubSubDSTConfig,
+) -> Vec<PubSubDSTResult> {
+    (0..num_seeds)
+        .map(|i| {
+            let seed = start_seed + i as u64;
+            let config = config_fn(seed);
+            let mut harness = PubSubDSTHarness::new(config);
+            harness.run(ops_per_seed);
+            harness.result().clone()
+        })
+        .collect()
+}
+
+/// Summarize batch results
+pub fn summarize_pubsub_batch(results: &[PubSubDSTResult]) -> String {
+    let total = results.len();
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    let failed = total - passed;
+    let total_ops: u64 = results.iter().map(|r| r.total_operations).sum();
+    let total_deliveries: u64 = results.iter().map(|r| r.deliveries).sum();
+    let total_disconnects: u64 = results.iter().map(|r| r.disconnects).sum();
+
+    let mut summary = format!(
+        "Pub/Sub DST Summary\n\
+         ===================\n\
+         Seeds: {} total, {} passed, {} failed\n\
+         Total operations: {}\n\
+         Deliveries: {}, disconnects: {}\n",
+        total, passed, failed, total_ops, total_deliveries, total_disconnects
+    );
+
+    if failed > 0 {
+        summary.push_str("\nFailed seeds:\n");
+        for result in results.iter().filter(|r| !r.is_success()) {
+            summary.push_str(&format!("  Seed {}: {}\n", result.seed, result.summary()));
+            for violation in &result.invariant_violations {
+                summary.push_str(&format!("    - {}\n", violation));
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pubsub_dst_single_seed() {
+        let mut harness = PubSubDSTHarness::with_seed(12345);
+        harness.run(500);
+        let result = harness.result();
+        println!("{}", result.summary());
+        assert!(result.is_success(), "Seed 12345 failed");
+        assert!(result.deliveries > 0, "no message was ever delivered");
+    }
+
+    #[test]
+    fn test_pubsub_dst_slow_consumers_disconnect() {
+        let mut harness = PubSubDSTHarness::new(PubSubDSTConfig::slow_consumers(42));
+        harness.run(1000);
+        let result = harness.result();
+        println!("{}", result.summary());
+        assert!(result.is_success());
+        assert!(result.disconnects > 0, "slow consumers were never dropped");
+    }
+
+    #[test]
+    fn test_pubsub_dst_patterns_deliver_pmessages() {
+        let mut harness = PubSubDSTHarness::new(PubSubDSTConfig::patterns(7));
+        harness.run(1000);
+        let result = harness.result();
+        println!("{}", result.summary());
+        assert!(result.is_success());
+        assert!(result.deliveries > 0, "no pattern message was ever delivered");
+    }
+
+    #[test]
+    fn test_pubsub_dst_10_seeds() {
+        let results = run_pubsub_batch(0, 10, 500, PubSubDSTConfig::new);
+        let summary = summarize_pubsub_batch(&results);
+        println!("{}", summary);
+
+        let passed = results.iter().filter(|r| r.is_success()).count();
+        assert_eq!(passed, 10, "All 10 seeds should pass");
+    }
+}
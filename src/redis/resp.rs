@@ -12,109 +12,390 @@ pub enum RespValue {
     Integer(i64),
     BulkString(Option<Vec<u8>>),
     Array(Option<Vec<RespValue>>),
+    /// RESP3 map (`%`); a flat array of keys and values in RESP2
+    Map(Vec<(RespValue, RespValue)>),
+    /// RESP3 set (`~`); an array in RESP2
+    Set(Vec<RespValue>),
+    /// RESP3 double (`,`); a bulk string in RESP2
+    Double(f64),
+    /// RESP3 boolean (`#`); the integer 1 or 0 in RESP2
+    Boolean(bool),
+    /// RESP3 big number (`(`), as decimal digits; a bulk string in RESP2
+    BigNumber(String),
+    /// RESP3 null (`_`); a nil bulk string in RESP2
+    Null,
+    /// RESP3 push (`>`): data sent without a request, such as Pub/Sub
+    /// messages and invalidations; an array in RESP2
+    Push(Vec<RespValue>),
+    /// RESP3 attributes (`|`) describing the reply after them; a RESP2
+    /// client gets the reply alone
+    Attribute(Vec<(RespValue, RespValue)>, Box<RespValue>),
+    /// RESP3 verbatim string (`=`): text in a three-letter format such as
+    /// `txt` or `mkd`; a bulk string of the text in RESP2
+    Verbatim {
+        format: [u8; 3],
+        text: Vec<u8>,
+    },
+}
+
+/// Sizes a peer may declare in the protocol, checked as each header is
+/// read so a length alone can never make the parser allocate or recurse
+/// without bound (Redis `proto-max-bulk-len` and friends). Violations fail
+/// with a `Protocol error: ...` message, after which a server closes the
+/// connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolLimits {
+    /// Longest bulk or verbatim string payload
+    pub max_bulk_len: usize,
+    /// Most elements in one array, set, push or map (counting pairs)
+    pub max_multibulk_len: usize,
+    /// Longest line: a type header, simple string, error or number
+    pub max_inline_len: usize,
+    /// Deepest nesting of aggregates; a command is an array at depth 1
+    pub max_depth: usize,
+}
+
+impl Default for ProtocolLimits {
+    fn default() -> Self {
+        Self {
+            max_bulk_len: 512 * 1024 * 1024,
+            max_multibulk_len: i32::MAX as usize,
+            max_inline_len: 64 * 1024,
+            max_depth: 32,
+        }
+    }
+}
+
+// Errors for requests over the limits, in Redis's words where it has them
+const INVALID_BULK_LENGTH: &str = "Protocol error: invalid bulk length";
+const INVALID_MULTIBULK_LENGTH: &str = "Protocol error: invalid multibulk length";
+const TOO_BIG_INLINE_REQUEST: &str = "Protocol error: too big inline request";
+const TOO_DEEP_NESTING: &str = "Protocol error: too deep nesting";
+
+/// Elements reserved up front for an aggregate; the rest grow as they
+/// arrive, so a large header costs nothing until its elements are sent
+const MAX_PREALLOCATED_ELEMENTS: usize = 1024;
+
+impl ProtocolLimits {
+    /// Limits small enough for ordinary DST traffic to cross, so the
+    /// rejection and disconnect paths run under buggify
+    pub fn shrunk() -> Self {
+        Self {
+            max_bulk_len: 256,
+            max_multibulk_len: 16,
+            max_inline_len: 32,
+            max_depth: 2,
+        }
+    }
+
+    /// Where the line after the type byte ends: None while its CRLF has not
+    /// arrived, an error as soon as the buffered line no longer fits, so a
+    /// peer that never sends CRLF cannot grow the buffer past the limit
+    pub(crate) fn line_end(&self, input: &[u8]) -> Result<Option<usize>, String> {
+        // The type byte, up to max_inline_len bytes of line, then CRLF
+        let longest = self.max_inline_len.saturating_add(3);
+        let window = &input[..input.len().min(longest)];
+        if let Some(pos) = memchr::memmem::find(window, b"\r\n") {
+            return Ok(Some(pos));
+        }
+        // Past a full-length line, only the CR of its CRLF may follow
+        let past_line = self.max_inline_len.saturating_add(1);
+        match input.get(past_line) {
+            Some(b'\r') if input.len() < longest => Ok(None),
+            Some(_) => Err(TOO_BIG_INLINE_REQUEST.to_string()),
+            None => Ok(None),
+        }
+    }
+
+    /// A bulk string header's length; None for the nil `-1`
+    pub(crate) fn bulk_len(&self, digits: &[u8]) -> Result<Option<usize>, String> {
+        match Self::parse_len(digits) {
+            Some(-1) => Ok(None),
+            Some(len) if len >= 0 && len as u64 <= self.max_bulk_len as u64 => {
+                Ok(Some(len as usize))
+            }
+            _ => Err(INVALID_BULK_LENGTH.to_string()),
+        }
+    }
+
+    /// An aggregate header's element count; None for the nil `-1`
+    pub(crate) fn multibulk_len(&self, digits: &[u8]) -> Result<Option<usize>, String> {
+        match Self::parse_len(digits) {
+            Some(-1) => Ok(None),
+            Some(len) if len >= 0 && len as u64 <= self.max_multibulk_len as u64 => {
+                Ok(Some(len as usize))
+            }
+            _ => Err(INVALID_MULTIBULK_LENGTH.to_string()),
+        }
+    }
+
+    /// Fails when an aggregate at `depth` nests deeper than allowed
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<(), String> {
+        if depth > self.max_depth {
+            return Err(TOO_DEEP_NESTING.to_string());
+        }
+        Ok(())
+    }
+
+    /// Capacity to reserve for an aggregate of `len` elements
+    pub(crate) fn capacity_hint(len: usize) -> usize {
+        len.min(MAX_PREALLOCATED_ELEMENTS)
+    }
+
+    fn parse_len(digits: &[u8]) -> Option<i64> {
+        std::str::from_utf8(digits).ok()?.parse().ok()
+    }
 }
 
 pub struct RespParser;
 
 impl RespParser {
     pub fn parse(input: &[u8]) -> Result<(RespValue, usize), String> {
+        Self::parse_with_limits(input, &ProtocolLimits::default())
+    }
+
+    /// Parse one value, failing with a `Protocol error` as soon as a header
+    /// declares more than `limits` allow
+    pub fn parse_with_limits(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValue, usize), String> {
+        Self::parse_value(input, limits, 1)
+    }
+
+    fn parse_value(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValue, usize), String> {
         if input.is_empty() {
             return Err("Empty input".to_string());
         }
 
         match input[0] {
-            b'+' => Self::parse_simple_string(input),
-            b'-' => Self::parse_error(input),
-            b':' => Self::parse_integer(input),
-            b'$' => Self::parse_bulk_string(input),
-            b'*' => Self::parse_array(input),
+            b'+' => Self::parse_simple_string(input, limits),
+            b'-' => Self::parse_error(input, limits),
+            b':' => Self::parse_integer(input, limits),
+            b'$' => Self::parse_bulk_string(input, limits),
+            b'*' => Self::parse_array(input, limits, depth),
+            b'%' => Self::parse_map(input, limits, depth),
+            b'~' => Self::parse_set(input, limits, depth),
+            b',' => Self::parse_double(input, limits),
+            b'#' => Self::parse_boolean(input, limits),
+            b'(' => Self::parse_big_number(input, limits),
+            b'_' => Self::parse_null(input, limits),
+            b'>' => Self::parse_push(input, limits, depth),
+            b'|' => Self::parse_attribute(input, limits, depth),
+            b'=' => Self::parse_verbatim(input, limits),
             _ => Err(format!("Unknown RESP type: {}", input[0] as char)),
         }
     }
 
-    fn parse_simple_string(input: &[u8]) -> Result<(RespValue, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let s = String::from_utf8_lossy(&input[1..pos]).into_owned();
-            Ok((RespValue::SimpleString(Cow::Owned(s)), pos + 2))
-        } else {
-            Err("No CRLF found".to_string())
-        }
+    fn parse_simple_string(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValue, usize), String> {
+        let (line, consumed) = Self::parse_line(input, limits)?;
+        Ok((RespValue::SimpleString(Cow::Owned(line)), consumed))
     }
 
-    fn parse_error(input: &[u8]) -> Result<(RespValue, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let s = String::from_utf8_lossy(&input[1..pos]).into_owned();
-            Ok((RespValue::Error(Cow::Owned(s)), pos + 2))
-        } else {
-            Err("No CRLF found".to_string())
-        }
+    fn parse_error(input: &[u8], limits: &ProtocolLimits) -> Result<(RespValue, usize), String> {
+        let (line, consumed) = Self::parse_line(input, limits)?;
+        Ok((RespValue::Error(Cow::Owned(line)), consumed))
     }
 
-    fn parse_integer(input: &[u8]) -> Result<(RespValue, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let s = String::from_utf8_lossy(&input[1..pos]);
-            let n = s.parse::<i64>().map_err(|e| e.to_string())?;
-            Ok((RespValue::Integer(n), pos + 2))
-        } else {
-            Err("No CRLF found".to_string())
+    fn parse_integer(input: &[u8], limits: &ProtocolLimits) -> Result<(RespValue, usize), String> {
+        let (line, consumed) = Self::parse_line(input, limits)?;
+        let n = line.parse::<i64>().map_err(|e| e.to_string())?;
+        Ok((RespValue::Integer(n), consumed))
+    }
+
+    fn parse_bulk_string(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValue, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let Some(len) = limits.bulk_len(&input[1..pos])? else {
+            return Ok((RespValue::BulkString(None), pos + 2));
+        };
+
+        let start = pos + 2;
+        let end = start + len;
+        if end + 2 > input.len() {
+            return Err("Incomplete bulk string".to_string());
         }
+
+        let data = input[start..end].to_vec();
+        Ok((RespValue::BulkString(Some(data)), end + 2))
     }
 
-    fn parse_bulk_string(input: &[u8]) -> Result<(RespValue, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let len_str = String::from_utf8_lossy(&input[1..pos]);
-            let len = len_str.parse::<i64>().map_err(|e| e.to_string())?;
+    fn parse_array(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValue, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let Some(len) = limits.multibulk_len(&input[1..pos])? else {
+            return Ok((RespValue::Array(None), pos + 2));
+        };
+        let (elements, consumed) = Self::parse_elements(input, len, pos + 2, limits, depth)?;
+        Ok((RespValue::Array(Some(elements)), consumed))
+    }
 
-            if len == -1 {
-                return Ok((RespValue::BulkString(None), pos + 2));
-            }
+    /// End of the line after the type byte, or an error if it never ends
+    /// within the inline limit
+    fn line_end(input: &[u8], limits: &ProtocolLimits) -> Result<usize, String> {
+        limits
+            .line_end(input)?
+            .ok_or_else(|| "No CRLF found".to_string())
+    }
 
-            let len = len as usize;
-            let start = pos + 2;
-            let end = start + len;
+    /// The line after the type byte, and the bytes it takes with its CRLF
+    fn parse_line(input: &[u8], limits: &ProtocolLimits) -> Result<(String, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        Ok((
+            String::from_utf8_lossy(&input[1..pos]).into_owned(),
+            pos + 2,
+        ))
+    }
 
-            if end + 2 > input.len() {
-                return Err("Incomplete bulk string".to_string());
-            }
+    /// The element count in a RESP3 aggregate header, which has no nil form
+    fn aggregate_len(input: &[u8], limits: &ProtocolLimits) -> Result<(usize, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let len = limits
+            .multibulk_len(&input[1..pos])?
+            .ok_or_else(|| INVALID_MULTIBULK_LENGTH.to_string())?;
+        Ok((len, pos + 2))
+    }
 
-            let data = input[start..end].to_vec();
-            Ok((RespValue::BulkString(Some(data)), end + 2))
-        } else {
-            Err("No CRLF found".to_string())
+    /// `len` values following the header, `offset` bytes long, of an
+    /// aggregate at `depth`
+    fn parse_elements(
+        input: &[u8],
+        len: usize,
+        mut offset: usize,
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(Vec<RespValue>, usize), String> {
+        limits.check_depth(depth)?;
+        let mut elements = Vec::with_capacity(ProtocolLimits::capacity_hint(len));
+        for _ in 0..len {
+            let (value, consumed) = Self::parse_value(&input[offset..], limits, depth + 1)?;
+            elements.push(value);
+            offset += consumed;
         }
+        Ok((elements, offset))
     }
 
-    fn parse_array(input: &[u8]) -> Result<(RespValue, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let len_str = String::from_utf8_lossy(&input[1..pos]);
-            let len = len_str.parse::<i64>().map_err(|e| e.to_string())?;
+    /// The key-value pairs of a map or attribute header
+    fn parse_pairs(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(Vec<(RespValue, RespValue)>, usize), String> {
+        let (len, header) = Self::aggregate_len(input, limits)?;
+        let count = len
+            .checked_mul(2)
+            .filter(|&count| count <= limits.max_multibulk_len)
+            .ok_or_else(|| INVALID_MULTIBULK_LENGTH.to_string())?;
+        let (elements, consumed) = Self::parse_elements(input, count, header, limits, depth)?;
+        let mut elements = elements.into_iter();
+        let mut pairs = Vec::with_capacity(len);
+        while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+            pairs.push((key, value));
+        }
+        Ok((pairs, consumed))
+    }
 
-            if len == -1 {
-                return Ok((RespValue::Array(None), pos + 2));
-            }
+    fn parse_map(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValue, usize), String> {
+        let (pairs, consumed) = Self::parse_pairs(input, limits, depth)?;
+        Ok((RespValue::Map(pairs), consumed))
+    }
 
-            let mut elements = Vec::new();
-            let mut offset = pos + 2;
+    fn parse_set(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValue, usize), String> {
+        let (len, header) = Self::aggregate_len(input, limits)?;
+        let (elements, consumed) = Self::parse_elements(input, len, header, limits, depth)?;
+        Ok((RespValue::Set(elements), consumed))
+    }
 
-            for _ in 0..len {
-                let (value, consumed) = Self::parse(&input[offset..])?;
-                elements.push(value);
-                offset += consumed;
-            }
+    fn parse_double(input: &[u8], limits: &ProtocolLimits) -> Result<(RespValue, usize), String> {
+        let (line, consumed) = Self::parse_line(input, limits)?;
+        let d = line.parse::<f64>().map_err(|e| e.to_string())?;
+        Ok((RespValue::Double(d), consumed))
+    }
 
-            Ok((RespValue::Array(Some(elements)), offset))
-        } else {
-            Err("No CRLF found".to_string())
+    fn parse_boolean(input: &[u8], limits: &ProtocolLimits) -> Result<(RespValue, usize), String> {
+        let (line, consumed) = Self::parse_line(input, limits)?;
+        match line.as_str() {
+            "t" => Ok((RespValue::Boolean(true), consumed)),
+            "f" => Ok((RespValue::Boolean(false), consumed)),
+            other => Err(format!("Invalid boolean: {}", other)),
         }
     }
 
-    fn find_crlf(input: &[u8]) -> Option<usize> {
-        for i in 0..input.len().saturating_sub(1) {
-            if input[i] == b'\r' && input[i + 1] == b'\n' {
-                return Some(i);
-            }
+    fn parse_big_number(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValue, usize), String> {
+        let (line, consumed) = Self::parse_line(input, limits)?;
+        Ok((RespValue::BigNumber(line), consumed))
+    }
+
+    fn parse_null(input: &[u8], limits: &ProtocolLimits) -> Result<(RespValue, usize), String> {
+        let (_, consumed) = Self::parse_line(input, limits)?;
+        Ok((RespValue::Null, consumed))
+    }
+
+    fn parse_push(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValue, usize), String> {
+        let (len, header) = Self::aggregate_len(input, limits)?;
+        let (elements, consumed) = Self::parse_elements(input, len, header, limits, depth)?;
+        Ok((RespValue::Push(elements), consumed))
+    }
+
+    /// An attribute map and the reply it describes, parsed as one value;
+    /// the reply counts one level deeper, so chained attributes are bounded
+    fn parse_attribute(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValue, usize), String> {
+        let (attributes, header) = Self::parse_pairs(input, limits, depth)?;
+        let (reply, consumed) = Self::parse_value(&input[header..], limits, depth + 1)?;
+        Ok((
+            RespValue::Attribute(attributes, Box::new(reply)),
+            header + consumed,
+        ))
+    }
+
+    fn parse_verbatim(input: &[u8], limits: &ProtocolLimits) -> Result<(RespValue, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let len = limits
+            .bulk_len(&input[1..pos])?
+            .ok_or_else(|| INVALID_BULK_LENGTH.to_string())?;
+        let header = pos + 2;
+        let end = header + len;
+        if end + 2 > input.len() {
+            return Err("Incomplete verbatim string".to_string());
         }
-        None
+        let data = &input[header..end];
+        if len < 4 || data[3] != b':' {
+            return Err("Verbatim string without a format".to_string());
+        }
+        let format = [data[0], data[1], data[2]];
+        let text = data[4..].to_vec();
+        Ok((RespValue::Verbatim { format, text }, end + 2))
     }
 
     pub fn encode(value: &RespValue) -> Vec<u8> {
@@ -137,6 +418,54 @@ impl RespParser {
                 }
                 result
             }
+            resp3 => Self::encode(&resp3.to_resp2()),
+        }
+    }
+
+    /// Encode for a client that negotiated RESP3. Nil bulk strings and
+    /// arrays are sent as the null type.
+    pub fn encode_resp3(value: &RespValue) -> Vec<u8> {
+        let header = |marker: char, len: usize| format!("{}{}\r\n", marker, len).into_bytes();
+        let aggregate = |marker: char, elements: &[RespValue]| {
+            let mut result = header(marker, elements.len());
+            for element in elements {
+                result.extend_from_slice(&Self::encode_resp3(element));
+            }
+            result
+        };
+        let pairs = |marker: char, pairs: &[(RespValue, RespValue)]| {
+            let mut result = header(marker, pairs.len());
+            for (key, value) in pairs {
+                result.extend_from_slice(&Self::encode_resp3(key));
+                result.extend_from_slice(&Self::encode_resp3(value));
+            }
+            result
+        };
+        match value {
+            RespValue::BulkString(None) | RespValue::Array(None) | RespValue::Null => {
+                b"_\r\n".to_vec()
+            }
+            RespValue::Array(Some(elements)) => aggregate('*', elements),
+            RespValue::Set(members) => aggregate('~', members),
+            RespValue::Push(elements) => aggregate('>', elements),
+            RespValue::Map(entries) => pairs('%', entries),
+            RespValue::Attribute(attributes, reply) => {
+                let mut result = pairs('|', attributes);
+                result.extend_from_slice(&Self::encode_resp3(reply));
+                result
+            }
+            RespValue::Double(d) => format!(",{}\r\n", double_text(*d)).into_bytes(),
+            RespValue::Boolean(b) => if *b { b"#t\r\n" } else { b"#f\r\n" }.to_vec(),
+            RespValue::BigNumber(digits) => format!("({}\r\n", digits).into_bytes(),
+            RespValue::Verbatim { format, text } => {
+                let mut result = header('=', text.len() + 4);
+                result.extend_from_slice(format);
+                result.push(b':');
+                result.extend_from_slice(text);
+                result.extend_from_slice(b"\r\n");
+                result
+            }
+            resp2 => Self::encode(resp2),
         }
     }
 }
@@ -191,4 +520,60 @@ impl RespValue {
     pub fn empty_array() -> Self {
         RespValue::Array(Some(Vec::new()))
     }
+
+    /// Whether this is one of the types only RESP3 has
+    pub fn is_resp3(&self) -> bool {
+        matches!(
+            self,
+            RespValue::Map(_)
+                | RespValue::Set(_)
+                | RespValue::Double(_)
+                | RespValue::Boolean(_)
+                | RespValue::BigNumber(_)
+                | RespValue::Null
+                | RespValue::Push(_)
+                | RespValue::Attribute(_, _)
+                | RespValue::Verbatim { .. }
+        )
+    }
+
+    /// The value a RESP2 client gets instead. Only this value is converted:
+    /// elements of a map or set keep their types, and encoders downgrade
+    /// them as they reach them.
+    pub fn to_resp2(&self) -> RespValue {
+        let downgraded = match self {
+            RespValue::Map(pairs) => RespValue::Array(Some(
+                pairs
+                    .iter()
+                    .flat_map(|(key, value)| [key.clone(), value.clone()])
+                    .collect(),
+            )),
+            RespValue::Set(members) => RespValue::Array(Some(members.clone())),
+            RespValue::Double(d) => RespValue::BulkString(Some(double_text(*d).into_bytes())),
+            RespValue::Boolean(b) => RespValue::Integer(i64::from(*b)),
+            RespValue::BigNumber(digits) => {
+                RespValue::BulkString(Some(digits.clone().into_bytes()))
+            }
+            RespValue::Null => RespValue::BulkString(None),
+            RespValue::Push(elements) => RespValue::Array(Some(elements.clone())),
+            RespValue::Attribute(_, reply) => reply.to_resp2(),
+            RespValue::Verbatim { text, .. } => RespValue::BulkString(Some(text.clone())),
+            resp2 => resp2.clone(),
+        };
+        debug_assert!(
+            !downgraded.is_resp3(),
+            "Postcondition violated: the RESP2 form must be a RESP2 type"
+        );
+        downgraded
+    }
+}
+
+/// A double as Redis writes it, in a RESP3 double or a RESP2 bulk string
+pub fn double_text(d: f64) -> String {
+    if d.is_nan() {
+        "nan".to_string()
+    } else {
+        // Rust already writes the infinities as inf and -inf
+        d.to_string()
+    }
 }
@@ -1,3 +1,4 @@
+use super::resp::ProtocolLimits;
 use bytes::{Buf, Bytes, BytesMut};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,123 +14,130 @@ pub struct RespCodec;
 
 impl RespCodec {
     pub fn parse(input: &mut BytesMut) -> Result<Option<RespValueZeroCopy>, String> {
+        Self::parse_with_limits(input, &ProtocolLimits::default())
+    }
+
+    /// Parse one value, failing with a `Protocol error` on malformed input
+    /// or as soon as a header declares more than `limits` allow, before
+    /// waiting for its payload
+    pub fn parse_with_limits(
+        input: &mut BytesMut,
+        limits: &ProtocolLimits,
+    ) -> Result<Option<RespValueZeroCopy>, String> {
         if input.is_empty() {
             return Ok(None);
         }
 
-        match Self::try_parse(input) {
+        match Self::try_parse(input, limits, 1) {
             Ok((value, consumed)) => {
                 input.advance(consumed);
                 Ok(Some(value))
             }
             Err(e) if e == "Incomplete" => Ok(None),
-            Err(e) => Err(e),
+            Err(e) if e.starts_with("Protocol error") => Err(e),
+            Err(e) => Err(format!("Protocol error: {}", e)),
         }
     }
 
-    fn try_parse(input: &[u8]) -> Result<(RespValueZeroCopy, usize), String> {
+    fn try_parse(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValueZeroCopy, usize), String> {
         if input.is_empty() {
             return Err("Incomplete".to_string());
         }
 
         match input[0] {
-            b'+' => Self::parse_simple_string(input),
-            b'-' => Self::parse_error(input),
-            b':' => Self::parse_integer(input),
-            b'$' => Self::parse_bulk_string(input),
-            b'*' => Self::parse_array(input),
+            b'+' => Self::parse_simple_string(input, limits),
+            b'-' => Self::parse_error(input, limits),
+            b':' => Self::parse_integer(input, limits),
+            b'$' => Self::parse_bulk_string(input, limits),
+            b'*' => Self::parse_array(input, limits, depth),
             _ => Err(format!("Unknown RESP type: {}", input[0] as char)),
         }
     }
 
-    fn parse_simple_string(input: &[u8]) -> Result<(RespValueZeroCopy, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let data = Bytes::copy_from_slice(&input[1..pos]);
-            Ok((RespValueZeroCopy::SimpleString(data), pos + 2))
-        } else {
-            Err("Incomplete".to_string())
-        }
+    fn parse_simple_string(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValueZeroCopy, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let data = Bytes::copy_from_slice(&input[1..pos]);
+        Ok((RespValueZeroCopy::SimpleString(data), pos + 2))
     }
 
-    fn parse_error(input: &[u8]) -> Result<(RespValueZeroCopy, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let data = Bytes::copy_from_slice(&input[1..pos]);
-            Ok((RespValueZeroCopy::Error(data), pos + 2))
-        } else {
-            Err("Incomplete".to_string())
-        }
+    fn parse_error(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValueZeroCopy, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let data = Bytes::copy_from_slice(&input[1..pos]);
+        Ok((RespValueZeroCopy::Error(data), pos + 2))
     }
 
-    fn parse_integer(input: &[u8]) -> Result<(RespValueZeroCopy, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let s = std::str::from_utf8(&input[1..pos]).map_err(|e| e.to_string())?;
-            let n = s.parse::<i64>().map_err(|e| e.to_string())?;
-            Ok((RespValueZeroCopy::Integer(n), pos + 2))
-        } else {
-            Err("Incomplete".to_string())
-        }
+    fn parse_integer(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValueZeroCopy, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let s = std::str::from_utf8(&input[1..pos]).map_err(|e| e.to_string())?;
+        let n = s.parse::<i64>().map_err(|e| e.to_string())?;
+        Ok((RespValueZeroCopy::Integer(n), pos + 2))
     }
 
-    fn parse_bulk_string(input: &[u8]) -> Result<(RespValueZeroCopy, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let len_str = std::str::from_utf8(&input[1..pos]).map_err(|e| e.to_string())?;
-            let len = len_str.parse::<i64>().map_err(|e| e.to_string())?;
-
-            if len == -1 {
-                return Ok((RespValueZeroCopy::BulkString(None), pos + 2));
-            }
+    fn parse_bulk_string(
+        input: &[u8],
+        limits: &ProtocolLimits,
+    ) -> Result<(RespValueZeroCopy, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let Some(len) = limits.bulk_len(&input[1..pos])? else {
+            return Ok((RespValueZeroCopy::BulkString(None), pos + 2));
+        };
+
+        let start = pos + 2;
+        let end = start + len;
+        if end + 2 > input.len() {
+            return Err("Incomplete".to_string());
+        }
 
-            let len = len as usize;
-            let start = pos + 2;
-            let end = start + len;
+        let data = Bytes::copy_from_slice(&input[start..end]);
+        Ok((RespValueZeroCopy::BulkString(Some(data)), end + 2))
+    }
 
-            if end + 2 > input.len() {
+    fn parse_array(
+        input: &[u8],
+        limits: &ProtocolLimits,
+        depth: usize,
+    ) -> Result<(RespValueZeroCopy, usize), String> {
+        let pos = Self::line_end(input, limits)?;
+        let Some(len) = limits.multibulk_len(&input[1..pos])? else {
+            return Ok((RespValueZeroCopy::Array(None), pos + 2));
+        };
+        limits.check_depth(depth)?;
+
+        let mut elements = Vec::with_capacity(ProtocolLimits::capacity_hint(len));
+        let mut offset = pos + 2;
+
+        for _ in 0..len {
+            if offset >= input.len() {
                 return Err("Incomplete".to_string());
             }
-
-            let data = Bytes::copy_from_slice(&input[start..end]);
-            Ok((RespValueZeroCopy::BulkString(Some(data)), end + 2))
-        } else {
-            Err("Incomplete".to_string())
+            let (value, consumed) = Self::try_parse(&input[offset..], limits, depth + 1)?;
+            elements.push(value);
+            offset += consumed;
         }
-    }
-
-    fn parse_array(input: &[u8]) -> Result<(RespValueZeroCopy, usize), String> {
-        if let Some(pos) = Self::find_crlf(input) {
-            let len_str = std::str::from_utf8(&input[1..pos]).map_err(|e| e.to_string())?;
-            let len = len_str.parse::<i64>().map_err(|e| e.to_string())?;
-
-            if len == -1 {
-                return Ok((RespValueZeroCopy::Array(None), pos + 2));
-            }
-
-            let mut elements = Vec::with_capacity(len as usize);
-            let mut offset = pos + 2;
 
-            for _ in 0..len {
-                if offset >= input.len() {
-                    return Err("Incomplete".to_string());
-                }
-                let (value, consumed) = Self::try_parse(&input[offset..])?;
-                elements.push(value);
-                offset += consumed;
-            }
-
-            Ok((RespValueZeroCopy::Array(Some(elements)), offset))
-        } else {
-            Err("Incomplete".to_string())
-        }
+        Ok((RespValueZeroCopy::Array(Some(elements)), offset))
     }
 
+    /// End of the line after the type byte; "Incomplete" until its CRLF
+    /// arrives, a protocol error once it can no longer fit the inline limit
     #[inline]
-    fn find_crlf(input: &[u8]) -> Option<usize> {
-        memchr::memchr(b'\r', input).and_then(|pos| {
-            if pos + 1 < input.len() && input[pos + 1] == b'\n' {
-                Some(pos)
-            } else {
-                None
-            }
-        })
+    fn line_end(input: &[u8], limits: &ProtocolLimits) -> Result<usize, String> {
+        limits
+            .line_end(input)?
+            .ok_or_else(|| "Incomplete".to_string())
     }
 
     pub fn encode(value: &RespValueZeroCopy) -> BytesMut {
@@ -0,0 +1,267 @@
+//! Running scripts: the state behind BUSY replies and SCRIPT KILL.
+//!
+//! A script runs to completion on its executor, so while it runs nothing
+//! else reaches that executor. As in Redis, `busy-reply-threshold` (alias
+//! `lua-time-limit`) bounds how long that stays invisible:
+//! - a script that has run longer than the threshold is busy; while any
+//!   script is busy, connections answer every command except SCRIPT KILL and
+//!   SHUTDOWN NOSAVE with a BUSY error
+//! - SCRIPT KILL marks busy scripts as killed; the Lua instruction hook sees
+//!   the mark and aborts the script with an error
+//! - a script that has already written is unkillable: stopping it halfway
+//!   would leave a partial write, so SCRIPT KILL refuses with UNKILLABLE
+//!
+//! Durations are wall-clock time. Nothing the executor replies depends on
+//! them, only whether other connections get BUSY, so a simulated server
+//! still replays exactly.
+//!
+//! `ScriptMonitor` is cheap to clone; every shard executor holds a handle to
+//! the same monitor, so SCRIPT KILL on any connection reaches scripts on all
+//! shards, and a threshold set through one shard applies to all of them.
+//! The number of running scripts is read without the lock, so connections
+//! check for a busy script at no cost while none runs.
+//!
+//! TigerStyle: All functions have precondition/postcondition assertions.
+
+use super::resp::RespValue;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Redis's default `busy-reply-threshold`
+pub const DEFAULT_BUSY_REPLY_THRESHOLD_MS: u64 = 5000;
+
+/// Lua instructions between two looks at the kill mark (Redis's hook interval)
+pub const SCRIPT_HOOK_INSTRUCTIONS: u32 = 100_000;
+
+/// Reply to commands sent while a script is busy
+pub const BUSY_ERROR: &str =
+    "BUSY Redis is busy running a script. You can only call SCRIPT KILL or SHUTDOWN NOSAVE.";
+
+/// Error a killed script ends with
+pub const SCRIPT_KILLED_ERROR: &str = "ERR Script killed by user with SCRIPT KILL...";
+
+const NOTBUSY_ERROR: &str = "NOTBUSY No scripts in execution right now.";
+
+const UNKILLABLE_ERROR: &str = "UNKILLABLE Sorry the script already executed write commands against the dataset. You can either wait the script termination or kill the server in a hard way using the SHUTDOWN NOSAVE command.";
+
+#[derive(Debug)]
+struct RunningScript {
+    id: u64,
+    started: Instant,
+    /// The script has run a write command
+    wrote: bool,
+    /// SCRIPT KILL asked the script to stop
+    killed: bool,
+}
+
+#[derive(Debug)]
+struct MonitorState {
+    threshold_ms: AtomicU64,
+    next_id: AtomicU64,
+    /// `running.len()`, readable without the lock
+    num_running: AtomicUsize,
+    running: Mutex<Vec<RunningScript>>,
+}
+
+/// Scripts running on any executor sharing this monitor
+#[derive(Debug, Clone)]
+pub struct ScriptMonitor {
+    inner: Arc<MonitorState>,
+}
+
+impl Default for ScriptMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptMonitor {
+    pub fn new() -> Self {
+        ScriptMonitor {
+            inner: Arc::new(MonitorState {
+                threshold_ms: AtomicU64::new(DEFAULT_BUSY_REPLY_THRESHOLD_MS),
+                next_id: AtomicU64::new(0),
+                num_running: AtomicUsize::new(0),
+                running: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn threshold_ms(&self) -> u64 {
+        self.inner.threshold_ms.load(Ordering::Relaxed)
+    }
+
+    /// Set how long a script runs before it is busy
+    pub fn set_threshold_ms(&self, threshold_ms: u64) {
+        self.inner
+            .threshold_ms
+            .store(threshold_ms, Ordering::Relaxed);
+    }
+
+    /// Register a script that is starting; it is forgotten when the
+    /// returned run is dropped
+    pub fn start(&self) -> ScriptRun {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut running = self.inner.running.lock();
+        running.push(RunningScript {
+            id,
+            started: Instant::now(),
+            wrote: false,
+            killed: false,
+        });
+        self.inner
+            .num_running
+            .store(running.len(), Ordering::Release);
+        ScriptRun {
+            monitor: self.clone(),
+            id,
+        }
+    }
+
+    fn is_over_threshold(&self, script: &RunningScript) -> bool {
+        script.started.elapsed() >= Duration::from_millis(self.threshold_ms())
+    }
+
+    /// A script has run past the threshold and has not finished
+    pub fn is_busy(&self) -> bool {
+        if self.num_running() == 0 {
+            return false;
+        }
+        let running = self.inner.running.lock();
+        running.iter().any(|script| self.is_over_threshold(script))
+    }
+
+    /// The BUSY error for `allowed == false`, while a script is busy;
+    /// `allowed` says whether the command may run anyway
+    pub fn busy_reply(&self, allowed: bool) -> Option<RespValue> {
+        if allowed || !self.is_busy() {
+            return None;
+        }
+        Some(RespValue::err(BUSY_ERROR))
+    }
+
+    /// SCRIPT KILL: mark every busy script that has not written as killed.
+    /// Replies NOTBUSY when no script is busy and UNKILLABLE when a busy
+    /// script has written
+    pub fn kill(&self) -> RespValue {
+        let mut running = self.inner.running.lock();
+        let mut busy = 0;
+        let mut unkillable = 0;
+        for script in running.iter_mut() {
+            if !self.is_over_threshold(script) {
+                continue;
+            }
+            busy += 1;
+            if script.wrote {
+                unkillable += 1;
+            } else {
+                script.killed = true;
+            }
+        }
+
+        debug_assert!(
+            unkillable <= busy,
+            "Postcondition violated: only busy scripts can be unkillable"
+        );
+        if busy == 0 {
+            RespValue::err(NOTBUSY_ERROR)
+        } else if unkillable > 0 {
+            RespValue::err(UNKILLABLE_ERROR)
+        } else {
+            RespValue::ok()
+        }
+    }
+
+    /// Scripts currently running
+    pub fn num_running(&self) -> usize {
+        self.inner.num_running.load(Ordering::Acquire)
+    }
+
+    fn update<R>(&self, id: u64, f: impl FnOnce(&mut RunningScript) -> R) -> Option<R> {
+        let mut running = self.inner.running.lock();
+        running.iter_mut().find(|script| script.id == id).map(f)
+    }
+}
+
+/// One running script, registered with its monitor until dropped
+#[derive(Debug)]
+pub struct ScriptRun {
+    monitor: ScriptMonitor,
+    id: u64,
+}
+
+impl ScriptRun {
+    /// The script ran a write command: from now on it cannot be killed
+    pub fn record_write(&self) {
+        self.monitor.update(self.id, |script| script.wrote = true);
+    }
+
+    /// SCRIPT KILL asked this script to stop
+    pub fn is_killed(&self) -> bool {
+        self.kill_check()()
+    }
+
+    /// `is_killed` as a check that does not borrow the run, for the Lua
+    /// instruction hook
+    pub fn kill_check(&self) -> impl Fn() -> bool + Send + 'static {
+        let monitor = self.monitor.clone();
+        let id = self.id;
+        move || monitor.update(id, |script| script.killed).unwrap_or(false)
+    }
+}
+
+impl Drop for ScriptRun {
+    fn drop(&mut self) {
+        let mut running = self.monitor.inner.running.lock();
+        let before = running.len();
+        running.retain(|script| script.id != self.id);
+        self.monitor
+            .inner
+            .num_running
+            .store(running.len(), Ordering::Release);
+        debug_assert_eq!(
+            running.len() + 1,
+            before,
+            "Postcondition violated: a finished script is forgotten exactly once"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_busy_scripts_are_killed() {
+        let monitor = ScriptMonitor::new();
+        assert_eq!(monitor.kill(), RespValue::err(NOTBUSY_ERROR));
+
+        let run = monitor.start();
+        assert!(!monitor.is_busy(), "a fresh script is under the threshold");
+        assert_eq!(monitor.kill(), RespValue::err(NOTBUSY_ERROR));
+        assert!(!run.is_killed());
+
+        monitor.set_threshold_ms(0);
+        assert!(monitor.is_busy());
+        assert_eq!(monitor.busy_reply(false), Some(RespValue::err(BUSY_ERROR)));
+        assert_eq!(monitor.busy_reply(true), None);
+        assert_eq!(monitor.kill(), RespValue::ok());
+        assert!(run.is_killed());
+
+        drop(run);
+        assert_eq!(monitor.num_running(), 0);
+        assert!(!monitor.is_busy());
+    }
+
+    #[test]
+    fn test_scripts_that_wrote_are_unkillable() {
+        let monitor = ScriptMonitor::new();
+        monitor.set_threshold_ms(0);
+        let run = monitor.start();
+        run.record_write();
+        assert_eq!(monitor.kill(), RespValue::err(UNKILLABLE_ERROR));
+        assert!(!run.is_killed());
+    }
+}
@@ -1,7 +1,13 @@
+use super::blocking::{self, BlockedClient};
+use super::latency::command_event;
 use super::resp::RespValue;
-use super::{Command, CommandExecutor, RespParser};
-use crate::simulator::{Event, EventType, HostId, Simulation};
-use std::collections::HashMap;
+use super::{BulkLoadReport, Command, CommandExecutor, RespParser};
+use crate::simulator::{
+    Duration, Event, EventType, HostId, LatencyProfile, SimulatedDisk, Simulation, StorageMode,
+    TimerId, VirtualTime,
+};
+use std::collections::{HashMap, VecDeque};
+use tracing::info;
 
 fn encode_with_request_id(request_id: u64, payload: Vec<u8>) -> Vec<u8> {
     let mut framed = Vec::with_capacity(8 + payload.len());
@@ -20,18 +26,188 @@ fn decode_request_id(data: &[u8]) -> Option<(u64, &[u8])> {
     Some((request_id, &data[8..]))
 }
 
+/// A request waiting for the server to finish earlier ones
+struct QueuedRequest {
+    client: HostId,
+    request_id: u64,
+    /// Err: the request did not parse; rejected in its turn, so a
+    /// rejection inside MULTI dooms the transaction
+    cmd: Result<Command, String>,
+    arrived: VirtualTime,
+}
+
+/// A blocking command waiting for a push or its virtual-time deadline
+struct ParkedRequest {
+    client: HostId,
+    request_id: u64,
+    cmd: Command,
+    /// Nil reply sent if the deadline passes
+    timeout_reply: RespValue,
+    /// Deadline timer (None = block forever)
+    timer: Option<TimerId>,
+    blocked: BlockedClient,
+}
+
+fn is_nil(reply: &RespValue) -> bool {
+    matches!(reply, RespValue::Array(None) | RespValue::BulkString(None))
+}
+
+/// How long requests waited behind slower ones (see `with_latency`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats {
+    pub executed: u64,
+    pub max_depth: usize,
+    pub max_wait: Duration,
+    pub total_wait: Duration,
+}
+
+impl Default for QueueStats {
+    fn default() -> Self {
+        QueueStats {
+            executed: 0,
+            max_depth: 0,
+            max_wait: Duration::ZERO,
+            total_wait: Duration::ZERO,
+        }
+    }
+}
+
+/// Single-threaded simulated server: requests run one at a time, in
+/// arrival order. With a `LatencyProfile` each command occupies the server
+/// for its sampled execution time and its effects and reply land when it
+/// finishes, so a slow command delays everything queued behind it. That
+/// time is also what the latency monitor (LATENCY) sees.
+/// Parked clients served by a push and blocking timeouts cost nothing,
+/// as in Redis, which serves them while finishing the pushing command.
+///
+/// The server can crash and restart. A crash loses everything in memory:
+/// the keyspace, queued and blocked requests (their clients never get a
+/// reply). Its `SimulatedDisk` keeps the last synced snapshot, or nothing
+/// with `StorageMode::Ephemeral`, and `restart` rebuilds the keyspace from
+/// it, as redis-server loads `dump.rdb` at startup.
+///
+/// Values UNLINK leaves for lazy free are dropped by a worker step every
+/// `LAZYFREE_STEP_MS`, up to `LAZYFREE_STEP_OBJECTS` at a time, scheduled
+/// only while some are queued.
 pub struct RedisServer {
     host_id: HostId,
     executor: CommandExecutor,
     epoch_initialized: bool,
+    latency: LatencyProfile,
+    /// Arrived requests not yet started, oldest first
+    queue: VecDeque<QueuedRequest>,
+    /// The request being executed, the timer that completes it and the
+    /// execution time it was charged
+    executing: Option<(TimerId, Duration, QueuedRequest)>,
+    stats: QueueStats,
+    /// Blocked requests (BLPOP, BZPOPMIN, XREAD BLOCK, ...), oldest first
+    parked: Vec<ParkedRequest>,
+    disk: SimulatedDisk,
+    /// Interval between snapshots, and the timer of the next one
+    snapshot_every: Option<Duration>,
+    snapshot_timer: Option<TimerId>,
+    /// The lazy-free worker's next step, while values are queued
+    lazyfree_timer: Option<TimerId>,
+    /// Down between `crash` and `restart`: events are dropped
+    crashed: bool,
 }
 
+/// Snapshot file on the server's disk
+pub const SNAPSHOT_FILE: &str = "dump.rdb";
+
+/// Interval between lazy-free worker steps
+pub const LAZYFREE_STEP_MS: u64 = 1;
+
+/// Values freed per lazy-free worker step
+pub const LAZYFREE_STEP_OBJECTS: usize = 16;
+
 impl RedisServer {
     pub fn new(host_id: HostId) -> Self {
         RedisServer {
             host_id,
             executor: CommandExecutor::new(),
             epoch_initialized: false,
+            latency: LatencyProfile::default(),
+            queue: VecDeque::new(),
+            executing: None,
+            stats: QueueStats::default(),
+            parked: Vec::new(),
+            disk: SimulatedDisk::new(),
+            snapshot_every: None,
+            snapshot_timer: None,
+            lazyfree_timer: None,
+            crashed: false,
+        }
+    }
+
+    /// Charge each command an artificial execution time
+    pub fn with_latency(mut self, latency: LatencyProfile) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Snapshot the keyspace to disk every `every`, from host start on
+    pub fn with_snapshots(mut self, every: Duration) -> Self {
+        assert!(every > Duration::ZERO, "snapshot interval must be positive");
+        self.snapshot_every = Some(every);
+        self
+    }
+
+    pub fn disk(&self) -> &SimulatedDisk {
+        &self.disk
+    }
+
+    pub fn is_crashed(&self) -> bool {
+        self.crashed
+    }
+
+    /// Write a snapshot of the live keys and sync it, as SAVE does
+    pub fn save(&mut self, sim: &Simulation) {
+        self.ensure_epoch_initialized(sim);
+        self.executor.set_time(sim.current_time());
+        self.disk.write(SNAPSHOT_FILE, self.executor.rdb_snapshot());
+        self.disk.sync(SNAPSHOT_FILE);
+    }
+
+    /// Kill the server: memory is gone, the disk keeps what `mode` keeps
+    pub fn crash(&mut self, sim: &mut Simulation, mode: StorageMode) {
+        self.executor = CommandExecutor::new();
+        self.epoch_initialized = false;
+        self.queue.clear();
+        self.executing = None;
+        self.parked.clear();
+        self.snapshot_timer = None;
+        self.lazyfree_timer = None;
+        self.disk.crash(mode);
+        self.crashed = true;
+        sim.set_waiting(self.host_id, Vec::new());
+    }
+
+    /// Start a crashed server: load the snapshot left on disk, if any, and
+    /// resume snapshotting
+    pub fn restart(&mut self, sim: &mut Simulation) -> Result<BulkLoadReport, String> {
+        debug_assert!(self.crashed, "Precondition: only a crashed server restarts");
+        self.crashed = false;
+        self.ensure_epoch_initialized(sim);
+        self.executor.set_time(sim.current_time());
+        let report = match self.disk.read(SNAPSHOT_FILE) {
+            Some(snapshot) => self.executor.load_rdb_file(snapshot)?,
+            None => BulkLoadReport::default(),
+        };
+        self.schedule_snapshot(sim);
+        Ok(report)
+    }
+
+    fn schedule_snapshot(&mut self, sim: &mut Simulation) {
+        if let Some(every) = self.snapshot_every {
+            self.snapshot_timer = Some(sim.schedule_timer(self.host_id, every));
+        }
+    }
+
+    fn schedule_lazyfree(&mut self, sim: &mut Simulation) {
+        if self.lazyfree_timer.is_none() && self.executor.lazyfree_pending_objects() > 0 {
+            let step = Duration::from_millis(LAZYFREE_STEP_MS);
+            self.lazyfree_timer = Some(sim.schedule_timer(self.host_id, step));
         }
     }
 
@@ -39,49 +215,227 @@ impl RedisServer {
         if !self.epoch_initialized {
             self.executor
                 .set_simulation_start_epoch(sim.simulation_start_epoch());
+            // Random replies replay with the run; each host draws its own stream
+            self.executor
+                .set_rng_seed(sim.seed() ^ (self.host_id.0 as u64).rotate_left(32));
             self.epoch_initialized = true;
         }
     }
 
     pub fn handle_event(&mut self, sim: &mut Simulation, event: &Event) {
-        if event.host_id != self.host_id {
+        if event.host_id != self.host_id || self.crashed {
             return;
         }
 
         match &event.event_type {
             EventType::NetworkMessage(msg) => {
                 self.ensure_epoch_initialized(sim);
-                self.executor.set_time(sim.current_time());
                 if let Some((request_id, payload)) = decode_request_id(&msg.payload) {
                     if let Ok((resp_value, _)) = RespParser::parse(payload) {
-                        if let Ok(cmd) = Command::from_resp(&resp_value) {
-                            let response = self.executor.execute(&cmd);
-                            let response_bytes = RespParser::encode(&response);
-                            let framed_response =
-                                encode_with_request_id(request_id, response_bytes);
-                            sim.send_message(self.host_id, msg.from, framed_response);
-                        }
+                        self.queue.push_back(QueuedRequest {
+                            client: msg.from,
+                            request_id,
+                            cmd: Command::from_resp(&resp_value),
+                            arrived: sim.current_time(),
+                        });
+                        self.stats.max_depth = self.stats.max_depth.max(self.queue.len());
+                        self.start_next(sim);
                     }
                 }
             }
+            EventType::Timer(timer_id) => {
+                if self.snapshot_timer == Some(*timer_id) {
+                    self.save(sim);
+                    self.schedule_snapshot(sim);
+                } else if self.lazyfree_timer == Some(*timer_id) {
+                    self.lazyfree_timer = None;
+                    self.executor.lazyfree_step(LAZYFREE_STEP_OBJECTS);
+                    self.schedule_lazyfree(sim);
+                } else if matches!(&self.executing, Some((id, _, _)) if id == timer_id) {
+                    if let Some((_, latency, request)) = self.executing.take() {
+                        self.complete(sim, request, latency);
+                    }
+                    self.start_next(sim);
+                } else if let Some(pos) =
+                    self.parked.iter().position(|p| p.timer == Some(*timer_id))
+                {
+                    let parked = self.parked.remove(pos);
+                    self.reply(sim, parked.client, parked.request_id, &parked.timeout_reply);
+                    self.publish_waits(sim);
+                }
+            }
+            EventType::Idle => {}
             EventType::HostStart => {
                 self.ensure_epoch_initialized(sim);
                 self.executor.set_time(sim.current_time());
-                println!(
-                    "[{:?}] Redis server started on host {:?}",
-                    sim.current_time(),
-                    self.host_id
+                self.schedule_snapshot(sim);
+                info!(
+                    time = ?sim.current_time(),
+                    host = ?self.host_id,
+                    "Redis server started"
                 );
             }
-            _ => {}
         }
     }
+
+    /// Number of requests parked by a blocking command
+    pub fn num_blocked(&self) -> usize {
+        self.parked.len()
+    }
+
+    /// Requests that arrived but have not started executing
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn queue_stats(&self) -> QueueStats {
+        self.stats
+    }
+
+    /// Start queued requests until one takes virtual time (or the queue
+    /// empties). Zero-latency requests complete immediately.
+    fn start_next(&mut self, sim: &mut Simulation) {
+        while self.executing.is_none() {
+            let Some(request) = self.queue.pop_front() else {
+                break;
+            };
+            let wait = sim.current_time() - request.arrived;
+            self.stats.max_wait = self.stats.max_wait.max(wait);
+            self.stats.total_wait =
+                Duration::from_millis(self.stats.total_wait.as_millis() + wait.as_millis());
+
+            let latency = match &request.cmd {
+                Ok(cmd) if !self.latency.is_zero() => self.latency.sample(cmd, sim.rng()),
+                _ => Duration::ZERO,
+            };
+            if latency == Duration::ZERO {
+                self.complete(sim, request, latency);
+            } else {
+                let timer = sim.schedule_timer(self.host_id, latency);
+                self.executing = Some((timer, latency, request));
+            }
+        }
+
+        debug_assert!(
+            self.executing.is_some() || self.queue.is_empty(),
+            "Invariant: an idle server has no queued requests"
+        );
+    }
+
+    /// Execute a request and reply, or park it if it blocks. `latency` is
+    /// the execution time it was charged, reported to the latency monitor.
+    fn complete(&mut self, sim: &mut Simulation, request: QueuedRequest, latency: Duration) {
+        let QueuedRequest {
+            client,
+            request_id,
+            cmd,
+            ..
+        } = request;
+        self.executor.set_time(sim.current_time());
+        let mut cmd = match cmd {
+            Ok(cmd) => cmd,
+            Err(error) => {
+                let response = self.executor.reject(error);
+                self.reply(sim, client, request_id, &response);
+                return;
+            }
+        };
+        self.stats.executed += 1;
+        self.executor
+            .record_latency(command_event(&cmd), latency.as_millis());
+
+        // XREAD BLOCK `$` means "after the last entry now", not at each retry
+        let probes = blocking::last_id_probes(&cmd);
+        if !probes.is_empty() {
+            let replies: Vec<RespValue> = probes.iter().map(|p| self.executor.execute(p)).collect();
+            blocking::pin_last_ids(&mut cmd, &replies);
+        }
+
+        let response = self.executor.execute(&cmd);
+        match cmd.blocking_keys() {
+            Some((keys, timeout_ms)) if is_nil(&response) => {
+                let blocked = self.executor.blocking().block(keys);
+                let timer = (timeout_ms > 0).then(|| {
+                    sim.schedule_timer(self.host_id, Duration::from_millis(timeout_ms))
+                });
+                self.parked.push(ParkedRequest {
+                    client,
+                    request_id,
+                    cmd,
+                    timeout_reply: response,
+                    timer,
+                    blocked,
+                });
+            }
+            _ => self.reply(sim, client, request_id, &response),
+        }
+        self.schedule_lazyfree(sim);
+        self.serve_unblocked(sim);
+        self.publish_waits(sim);
+    }
+
+    /// Declare the parked requests to the simulation, so a run that stops
+    /// with clients still blocked says on what (see `RunOutcome::waits`)
+    fn publish_waits(&self, sim: &mut Simulation) {
+        let reasons = self
+            .parked
+            .iter()
+            .map(|p| {
+                let keys = p
+                    .cmd
+                    .blocking_keys()
+                    .map(|(keys, _)| keys.join(" "))
+                    .unwrap_or_default();
+                let deadline = if p.timer.is_some() { "" } else { " forever" };
+                format!("{} {} for {:?}{}", p.cmd.name(), keys, p.client, deadline)
+            })
+            .collect();
+        sim.set_waiting(self.host_id, reasons);
+    }
+
+    fn reply(&self, sim: &mut Simulation, client: HostId, request_id: u64, response: &RespValue) {
+        let response_bytes = RespParser::encode(response);
+        let framed_response = encode_with_request_id(request_id, response_bytes);
+        sim.send_message(self.host_id, client, framed_response);
+    }
+
+    /// Retry parked requests whose keys were signalled, oldest first, until
+    /// none makes progress (a served BLMOVE may wake others on its dest)
+    fn serve_unblocked(&mut self, sim: &mut Simulation) {
+        loop {
+            let mut served = false;
+            let mut i = 0;
+            while i < self.parked.len() {
+                if self.parked[i].blocked.take_ready() {
+                    let response = self.executor.execute(&self.parked[i].cmd);
+                    if !is_nil(&response) {
+                        let parked = self.parked.remove(i);
+                        self.reply(sim, parked.client, parked.request_id, &response);
+                        served = true;
+                        continue;
+                    }
+                }
+                i += 1;
+            }
+            if !served {
+                break;
+            }
+        }
+
+        debug_assert_eq!(
+            self.parked.len(),
+            self.executor.blocking().num_blocked(),
+            "Invariant: every parked request is registered with the executor"
+        );
+    }
 }
 
 pub struct RedisClient {
     host_id: HostId,
     server_id: HostId,
     responses: HashMap<u64, RespValue>,
+    /// When each response arrived
+    response_times: HashMap<u64, VirtualTime>,
     next_request_id: u64,
 }
 
@@ -91,6 +445,7 @@ impl RedisClient {
             host_id,
             server_id,
             responses: HashMap::new(),
+            response_times: HashMap::new(),
             next_request_id: 0,
         }
     }
@@ -114,6 +469,7 @@ impl RedisClient {
                 if let Some((request_id, payload)) = decode_request_id(&msg.payload) {
                     if let Ok((resp_value, _)) = RespParser::parse(payload) {
                         self.responses.insert(request_id, resp_value);
+                        self.response_times.insert(request_id, event.time);
                     }
                 }
             }
@@ -124,4 +480,8 @@ impl RedisClient {
     pub fn get_response(&self, request_id: u64) -> Option<&RespValue> {
         self.responses.get(&request_id)
     }
+
+    pub fn response_time(&self, request_id: u64) -> Option<VirtualTime> {
+        self.response_times.get(&request_id).copied()
+    }
 }
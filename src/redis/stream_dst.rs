@@ -0,0 +1,800 @@
+//! Deterministic Simulation Testing for Stream Consumer Groups
+//!
+//! Shadow-state testing harness for one consumer group on one stream that
+//! enables:
+//! - Several consumers interleaving XREADGROUP, XACK and XAUTOCLAIM on a
+//!   seeded `SimulatedRng`, with virtual time advancing between steps
+//! - Consumer crashes between XREADGROUP and XACK that drop the consumer's
+//!   local state
+//! - Recovery either by the restarted consumer re-reading its own history
+//!   (`XREADGROUP ... 0`) or by another consumer's XAUTOCLAIM once the entry
+//!   has been idle for the timeout
+//! - Seed-based reproducibility for debugging
+//!
+//! The shadow holds every added entry, the pending entry list (owner,
+//! delivery count, delivery time) and the consumer that acknowledged each
+//! entry. After every operation:
+//! - XREADGROUP, XACK and XAUTOCLAIM replies match the shadow exactly
+//! - XPENDING, both the summary and the extended form, matches the shadow
+//!   PEL; the summary counts come from each consumer's own PEL and the rows
+//!   from the group's, so an entry with two owners shows up as a mismatch
+//! - no entry is both pending and acknowledged
+//!
+//! At the end of a run one consumer reads, claims and acknowledges whatever
+//! is left; every entry must then have been acknowledged exactly once.
+
+use super::data::{PendingRange, StreamId, StreamIdSpec, SDS};
+use super::{Command, CommandExecutor, RespValue};
+use crate::io::simulation::SimulatedRng;
+use crate::io::Rng;
+use crate::simulator::VirtualTime;
+use std::collections::{BTreeMap, BTreeSet};
+
+const KEY: &str = "jobs";
+const GROUP: &str = "workers";
+
+/// Configuration for stream consumer group DST
+#[derive(Debug, Clone)]
+pub struct StreamDSTConfig {
+    /// Random seed for reproducibility
+    pub seed: u64,
+    pub num_consumers: usize,
+    /// Probability of an XADD (vs. a consumer step)
+    pub add_prob: f64,
+    /// Probability a live consumer crashes on its step
+    pub crash_prob: f64,
+    /// Probability a crashed consumer restarts on its step
+    pub restart_prob: f64,
+    /// Probability a live consumer runs XAUTOCLAIM on its step
+    pub claim_prob: f64,
+    /// XAUTOCLAIM min-idle-time in milliseconds
+    pub idle_timeout_ms: u64,
+    /// Largest virtual time step between operations, in milliseconds
+    pub max_step_ms: u64,
+}
+
+impl Default for StreamDSTConfig {
+    fn default() -> Self {
+        StreamDSTConfig {
+            seed: 0,
+            num_consumers: 4,
+            add_prob: 0.35,
+            crash_prob: 0.05,
+            restart_prob: 0.3,
+            claim_prob: 0.1,
+            idle_timeout_ms: 1_000,
+            max_step_ms: 50,
+        }
+    }
+}
+
+impl StreamDSTConfig {
+    pub fn new(seed: u64) -> Self {
+        StreamDSTConfig {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    /// Frequent crashes and slow restarts: most entries are recovered by
+    /// XAUTOCLAIM rather than by their first consumer
+    pub fn crash_heavy(seed: u64) -> Self {
+        StreamDSTConfig {
+            seed,
+            crash_prob: 0.2,
+            restart_prob: 0.1,
+            claim_prob: 0.2,
+            ..Default::default()
+        }
+    }
+
+    /// Many consumers and a short idle timeout: live consumers lose entries
+    /// to XAUTOCLAIM and acknowledge them after the new owner
+    pub fn contended(seed: u64) -> Self {
+        StreamDSTConfig {
+            seed,
+            num_consumers: 8,
+            claim_prob: 0.3,
+            idle_timeout_ms: 100,
+            ..Default::default()
+        }
+    }
+}
+
+/// Operation type for logging
+#[derive(Debug, Clone)]
+pub enum StreamOp {
+    Add,
+    ReadNew {
+        consumer: usize,
+        count: usize,
+    },
+    Ack {
+        consumer: usize,
+        ids: Vec<StreamId>,
+    },
+    Crash {
+        consumer: usize,
+    },
+    Restart {
+        consumer: usize,
+    },
+    AutoClaim {
+        consumer: usize,
+        start: StreamId,
+        count: usize,
+    },
+}
+
+/// Result of a stream consumer group DST run
+#[derive(Debug, Clone)]
+pub struct StreamDSTResult {
+    pub seed: u64,
+    pub total_operations: u64,
+    pub added: u64,
+    /// First deliveries, by `XREADGROUP ... >`
+    pub delivered: u64,
+    /// Deliveries of pending entries, by history reads and XAUTOCLAIM
+    pub redelivered: u64,
+    pub claimed: u64,
+    pub acked: u64,
+    /// Acknowledgements by a consumer that no longer owned the entry
+    pub stale_acks: u64,
+    pub crashes: u64,
+    pub invariant_violations: Vec<String>,
+    pub last_op: Option<StreamOp>,
+}
+
+impl StreamDSTResult {
+    pub fn new(seed: u64) -> Self {
+        StreamDSTResult {
+            seed,
+            total_operations: 0,
+            added: 0,
+            delivered: 0,
+            redelivered: 0,
+            claimed: 0,
+            acked: 0,
+            stale_acks: 0,
+            crashes: 0,
+            invariant_violations: Vec::new(),
+            last_op: None,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.invariant_violations.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Seed {}: {} ops (added:{}, delivered:{}, redelivered:{}, claimed:{}, acked:{}, stale_acks:{}, crashes:{}), {} violations",
+            self.seed,
+            self.total_operations,
+            self.added,
+            self.delivered,
+            self.redelivered,
+            self.claimed,
+            self.acked,
+            self.stale_acks,
+            self.crashes,
+            self.invariant_violations.len()
+        )
+    }
+}
+
+/// Shadow of one PEL entry
+#[derive(Debug, Clone)]
+struct ShadowPending {
+    owner: usize,
+    delivery_count: u64,
+    /// Harness time of the last delivery
+    delivery_ms: u64,
+}
+
+/// A consumer's local state, lost when it crashes
+#[derive(Debug, Default)]
+struct ShadowConsumer {
+    /// Entries it has been handed and not yet acknowledged
+    held: BTreeSet<StreamId>,
+    crashed: bool,
+    /// Where its next XAUTOCLAIM scan starts
+    cursor: StreamId,
+}
+
+fn consumer_name(consumer: usize) -> String {
+    format!("consumer:{}", consumer)
+}
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+fn parse_id(value: &RespValue) -> Option<StreamId> {
+    match value {
+        RespValue::BulkString(Some(id)) => StreamId::parse(std::str::from_utf8(id).ok()?, 0),
+        _ => None,
+    }
+}
+
+/// IDs of `[[id, fields], ...]`
+fn entry_ids(value: &RespValue) -> Option<Vec<StreamId>> {
+    match value {
+        RespValue::Array(Some(entries)) => entries
+            .iter()
+            .map(|entry| match entry {
+                RespValue::Array(Some(parts)) => parse_id(parts.first()?),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// IDs of an XREADGROUP reply for the one stream; nil means nothing new
+fn read_group_ids(reply: &RespValue) -> Option<Vec<StreamId>> {
+    match reply {
+        RespValue::Array(None) => Some(Vec::new()),
+        RespValue::Array(Some(streams)) => match streams.as_slice() {
+            [RespValue::Array(Some(parts))] if parts.len() == 2 => entry_ids(&parts[1]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// DST harness for stream consumer groups
+pub struct StreamDSTHarness {
+    config: StreamDSTConfig,
+    rng: SimulatedRng,
+    executor: CommandExecutor,
+    /// Virtual milliseconds since the start of the run
+    now_ms: u64,
+    /// Every entry ever added
+    entries: BTreeSet<StreamId>,
+    /// The group's last-delivered-id
+    last_delivered: StreamId,
+    pel: BTreeMap<StreamId, ShadowPending>,
+    /// Consumer whose XACK removed each entry from the PEL
+    acked_by: BTreeMap<StreamId, usize>,
+    consumers: Vec<ShadowConsumer>,
+    result: StreamDSTResult,
+}
+
+impl StreamDSTHarness {
+    pub fn new(config: StreamDSTConfig) -> Self {
+        debug_assert!(config.num_consumers > 0, "Precondition: need consumers");
+        debug_assert!(
+            config.idle_timeout_ms > 0,
+            "Precondition: idle timeout must be positive"
+        );
+
+        let mut executor = CommandExecutor::new();
+        let created = executor.execute(&Command::XGroupCreate {
+            key: KEY.to_string(),
+            group: GROUP.to_string(),
+            id: Some(StreamId::MIN),
+            mkstream: true,
+        });
+        debug_assert_eq!(created, RespValue::ok(), "Invariant: group must be created");
+
+        StreamDSTHarness {
+            rng: SimulatedRng::new(config.seed),
+            executor,
+            now_ms: 0,
+            entries: BTreeSet::new(),
+            last_delivered: StreamId::MIN,
+            pel: BTreeMap::new(),
+            acked_by: BTreeMap::new(),
+            consumers: (0..config.num_consumers)
+                .map(|_| ShadowConsumer::default())
+                .collect(),
+            result: StreamDSTResult::new(config.seed),
+            config,
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(StreamDSTConfig::new(seed))
+    }
+
+    fn violation(&mut self, msg: String) {
+        self.result.invariant_violations.push(format!(
+            "Op #{}: {:?} - {}",
+            self.result.total_operations, self.result.last_op, msg
+        ));
+    }
+
+    fn advance(&mut self, ms: u64) {
+        self.now_ms += ms;
+        self.executor
+            .set_time(VirtualTime::from_millis(self.now_ms));
+    }
+
+    fn run_single_op(&mut self) {
+        let step = self.rng.gen_range(0, self.config.max_step_ms + 1);
+        self.advance(step);
+
+        if self.rng.gen_bool(self.config.add_prob) {
+            self.op_add();
+        } else {
+            let consumer = self.rng.gen_range(0, self.config.num_consumers as u64) as usize;
+            if self.consumers[consumer].crashed {
+                if self.rng.gen_bool(self.config.restart_prob) {
+                    self.op_restart(consumer);
+                }
+            } else if self.rng.gen_bool(self.config.crash_prob) {
+                self.op_crash(consumer);
+            } else if self.rng.gen_bool(self.config.claim_prob) {
+                let count = self.rng.gen_range(1, 5) as usize;
+                self.op_autoclaim(consumer, count);
+            } else if self.consumers[consumer].held.is_empty() || self.rng.gen_bool(0.5) {
+                let count = self.rng.gen_range(1, 4) as usize;
+                self.op_read_new(consumer, count);
+            } else {
+                let held = &self.consumers[consumer].held;
+                let count = self.rng.gen_range(1, held.len().min(3) as u64 + 1) as usize;
+                let ids: Vec<StreamId> = held.iter().copied().take(count).collect();
+                self.op_ack(consumer, ids);
+            }
+        }
+
+        self.result.total_operations += 1;
+
+        if let Err(violation) = self.check_invariants() {
+            self.violation(violation);
+        }
+    }
+
+    fn op_add(&mut self) {
+        self.result.last_op = Some(StreamOp::Add);
+
+        let reply = self.executor.execute(&Command::XAdd {
+            key: KEY.to_string(),
+            id: StreamIdSpec::Auto,
+            fields: vec![(
+                SDS::from_str("job"),
+                SDS::from_str(&self.result.added.to_string()),
+            )],
+            nomkstream: false,
+            maxlen: None,
+        });
+        let Some(id) = parse_id(&reply) else {
+            self.violation(format!("XADD replied {:?}", reply));
+            return;
+        };
+        if self.entries.last().is_some_and(|last| *last >= id) {
+            self.violation(format!(
+                "XADD returned {} after {:?}",
+                id,
+                self.entries.last()
+            ));
+            return;
+        }
+        self.entries.insert(id);
+        self.result.added += 1;
+    }
+
+    /// `XREADGROUP ... COUNT count STREAMS jobs >`
+    fn op_read_new(&mut self, consumer: usize, count: usize) {
+        self.result.last_op = Some(StreamOp::ReadNew { consumer, count });
+
+        let reply = self.executor.execute(&Command::XReadGroup {
+            group: GROUP.to_string(),
+            consumer: consumer_name(consumer),
+            count: Some(count),
+            block_ms: None,
+            noack: false,
+            keys: vec![KEY.to_string()],
+            ids: vec![None],
+        });
+        let expected: Vec<StreamId> = match self.last_delivered.next() {
+            Some(start) => self.entries.range(start..).take(count).copied().collect(),
+            None => Vec::new(),
+        };
+        if read_group_ids(&reply).as_ref() != Some(&expected) {
+            self.violation(format!(
+                "consumer {} read {:?}, expected {:?}",
+                consumer, reply, expected
+            ));
+            return;
+        }
+
+        for id in expected {
+            self.pel.insert(
+                id,
+                ShadowPending {
+                    owner: consumer,
+                    delivery_count: 1,
+                    delivery_ms: self.now_ms,
+                },
+            );
+            self.consumers[consumer].held.insert(id);
+            self.last_delivered = id;
+            self.result.delivered += 1;
+        }
+    }
+
+    fn op_ack(&mut self, consumer: usize, ids: Vec<StreamId>) {
+        self.result.last_op = Some(StreamOp::Ack {
+            consumer,
+            ids: ids.clone(),
+        });
+
+        let reply = self.executor.execute(&Command::XAck {
+            key: KEY.to_string(),
+            group: GROUP.to_string(),
+            ids: ids.clone(),
+        });
+        // XACK does not check the owner: whoever acknowledges first wins
+        let mut expected = 0;
+        for id in &ids {
+            self.consumers[consumer].held.remove(id);
+            if let Some(pending) = self.pel.remove(id) {
+                if pending.owner != consumer {
+                    self.result.stale_acks += 1;
+                }
+                self.acked_by.insert(*id, consumer);
+                expected += 1;
+            }
+        }
+        self.result.acked += expected;
+        if reply != RespValue::Integer(expected as i64) {
+            self.violation(format!(
+                "consumer {} acknowledged {:?}: {:?}, expected {}",
+                consumer, ids, reply, expected
+            ));
+        }
+    }
+
+    /// The consumer dies between reading and acknowledging; what it held is
+    /// still pending under its name
+    fn op_crash(&mut self, consumer: usize) {
+        self.result.last_op = Some(StreamOp::Crash { consumer });
+        self.consumers[consumer] = ShadowConsumer {
+            crashed: true,
+            ..ShadowConsumer::default()
+        };
+        self.result.crashes += 1;
+    }
+
+    /// A restarted consumer first re-reads its own pending history
+    fn op_restart(&mut self, consumer: usize) {
+        self.result.last_op = Some(StreamOp::Restart { consumer });
+        self.consumers[consumer].crashed = false;
+
+        let reply = self.executor.execute(&Command::XReadGroup {
+            group: GROUP.to_string(),
+            consumer: consumer_name(consumer),
+            count: None,
+            block_ms: None,
+            noack: false,
+            keys: vec![KEY.to_string()],
+            ids: vec![Some(StreamId::MIN)],
+        });
+        let expected: Vec<StreamId> = self
+            .pel
+            .iter()
+            .filter(|(_, pending)| pending.owner == consumer)
+            .map(|(id, _)| *id)
+            .collect();
+        if read_group_ids(&reply).as_ref() != Some(&expected) {
+            self.violation(format!(
+                "consumer {} history read {:?}, expected {:?}",
+                consumer, reply, expected
+            ));
+            return;
+        }
+
+        for id in expected {
+            let pending = self.pel.get_mut(&id).expect("listed from the PEL");
+            pending.delivery_count += 1;
+            pending.delivery_ms = self.now_ms;
+            self.consumers[consumer].held.insert(id);
+            self.result.redelivered += 1;
+        }
+    }
+
+    /// `XAUTOCLAIM jobs workers <consumer> <idle-timeout> <cursor> COUNT count`
+    fn op_autoclaim(&mut self, consumer: usize, count: usize) {
+        let start = self.consumers[consumer].cursor;
+        self.result.last_op = Some(StreamOp::AutoClaim {
+            consumer,
+            start,
+            count,
+        });
+
+        let reply = self.executor.execute(&Command::XAutoClaim {
+            key: KEY.to_string(),
+            group: GROUP.to_string(),
+            consumer: consumer_name(consumer),
+            min_idle_ms: self.config.idle_timeout_ms,
+            start,
+            count,
+            justid: false,
+        });
+
+        // Same scan as Redis: at most count * 10 PEL entries per call, and
+        // the cursor is the first entry left unscanned (0-0 when done)
+        let attempts = count * 10;
+        let mut expected = Vec::new();
+        let mut next = StreamId::MIN;
+        for (i, (id, pending)) in self.pel.range(start..).enumerate() {
+            if i == attempts || expected.len() == count {
+                next = *id;
+                break;
+            }
+            if self.now_ms - pending.delivery_ms >= self.config.idle_timeout_ms {
+                expected.push(*id);
+            }
+        }
+
+        let parsed = match &reply {
+            RespValue::Array(Some(parts)) if parts.len() == 3 => {
+                match (parse_id(&parts[0]), entry_ids(&parts[1])) {
+                    (Some(cursor), Some(ids)) => {
+                        Some((cursor, ids, parts[2] == RespValue::Array(Some(Vec::new()))))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if parsed != Some((next, expected.clone(), true)) {
+            self.violation(format!(
+                "consumer {} XAUTOCLAIM from {} replied {:?}, expected cursor {} and {:?}",
+                consumer, start, reply, next, expected
+            ));
+            return;
+        }
+
+        // The previous owner, if still alive, keeps believing it holds them
+        for id in expected {
+            let pending = self.pel.get_mut(&id).expect("scanned from the PEL");
+            pending.owner = consumer;
+            pending.delivery_count += 1;
+            pending.delivery_ms = self.now_ms;
+            self.consumers[consumer].held.insert(id);
+            self.result.claimed += 1;
+            self.result.redelivered += 1;
+        }
+        self.consumers[consumer].cursor = next;
+    }
+
+    fn check_invariants(&mut self) -> Result<(), String> {
+        // Invariant 1: XPENDING summary matches the shadow PEL
+        let mut per_consumer: BTreeMap<String, usize> = BTreeMap::new();
+        for pending in self.pel.values() {
+            *per_consumer
+                .entry(consumer_name(pending.owner))
+                .or_default() += 1;
+        }
+        let expected = match (self.pel.keys().next(), self.pel.keys().next_back()) {
+            (Some(min), Some(max)) => RespValue::Array(Some(vec![
+                RespValue::Integer(self.pel.len() as i64),
+                bulk(&min.to_string()),
+                bulk(&max.to_string()),
+                RespValue::Array(Some(
+                    per_consumer
+                        .iter()
+                        .map(|(name, count)| {
+                            RespValue::Array(Some(vec![bulk(name), bulk(&count.to_string())]))
+                        })
+                        .collect(),
+                )),
+            ])),
+            _ => RespValue::Array(Some(vec![
+                RespValue::Integer(0),
+                RespValue::BulkString(None),
+                RespValue::BulkString(None),
+                RespValue::Array(None),
+            ])),
+        };
+        let summary = self.executor.execute(&Command::XPending {
+            key: KEY.to_string(),
+            group: GROUP.to_string(),
+            range: None,
+        });
+        if summary != expected {
+            return Err(format!(
+                "XPENDING summary {:?}, expected {:?}",
+                summary, expected
+            ));
+        }
+
+        // Invariant 2: every PEL row has the shadow's owner, idle time and
+        // delivery count
+        let expected = RespValue::Array(Some(
+            self.pel
+                .iter()
+                .map(|(id, pending)| {
+                    RespValue::Array(Some(vec![
+                        bulk(&id.to_string()),
+                        bulk(&consumer_name(pending.owner)),
+                        RespValue::Integer((self.now_ms - pending.delivery_ms) as i64),
+                        RespValue::Integer(pending.delivery_count as i64),
+                    ]))
+                })
+                .collect(),
+        ));
+        let rows = self.executor.execute(&Command::XPending {
+            key: KEY.to_string(),
+            group: GROUP.to_string(),
+            range: Some(PendingRange {
+                min_idle_ms: None,
+                start: StreamId::MIN,
+                end: StreamId::MAX,
+                count: usize::MAX,
+                consumer: None,
+            }),
+        });
+        if rows != expected {
+            return Err(format!("XPENDING rows {:?}, expected {:?}", rows, expected));
+        }
+
+        // Invariant 3: an entry is pending or acknowledged, never both, and
+        // only entries already delivered are either
+        if let Some(id) = self.pel.keys().find(|id| self.acked_by.contains_key(id)) {
+            return Err(format!("{} is pending after being acknowledged", id));
+        }
+        if let Some(id) = self
+            .pel
+            .keys()
+            .chain(self.acked_by.keys())
+            .find(|id| **id > self.last_delivered || !self.entries.contains(id))
+        {
+            return Err(format!("{} is tracked but was never delivered", id));
+        }
+
+        Ok(())
+    }
+
+    /// One consumer reads, claims and acknowledges everything left
+    fn drain(&mut self) {
+        if self.consumers[0].crashed {
+            self.op_restart(0);
+        }
+        self.op_read_new(0, self.entries.len().max(1));
+        self.advance(self.config.idle_timeout_ms);
+        self.consumers[0].cursor = StreamId::MIN;
+        self.op_autoclaim(0, self.pel.len().max(1));
+        let held: Vec<StreamId> = self.consumers[0].held.iter().copied().collect();
+        if !held.is_empty() {
+            self.op_ack(0, held);
+        }
+        if let Err(violation) = self.check_invariants() {
+            self.violation(violation);
+            return;
+        }
+
+        // Every entry was acknowledged exactly once
+        if !self.pel.is_empty() {
+            self.violation(format!(
+                "{} entries still pending after drain",
+                self.pel.len()
+            ));
+        }
+        let acked: BTreeSet<StreamId> = self.acked_by.keys().copied().collect();
+        if acked != self.entries || self.result.acked != self.result.added {
+            self.violation(format!(
+                "{} of {} entries acknowledged ({} acks)",
+                acked.len(),
+                self.entries.len(),
+                self.result.acked
+            ));
+        }
+    }
+
+    pub fn run(&mut self, operations: usize) {
+        for _ in 0..operations {
+            self.run_single_op();
+            if !self.result.invariant_violations.is_empty() {
+                return;
+            }
+        }
+        self.drain();
+    }
+
+    pub fn result(&self) -> &StreamDSTResult {
+        &self.result
+    }
+}
+
+/// Run a batch of DST tests
+pub fn run_stream_batch(
+    start_seed: u64,
+    num_seeds: usize,
+    ops_per_seed: usize,
+    config_fn: fn(u64) -> StreamDSTConfig,
+) -> Vec<StreamDSTResult> {
+    (0..num_seeds)
+        .map(|i| {
+            let seed = start_seed + i as u64;
+            let config = config_fn(seed);
+            let mut harness = StreamDSTHarness::new(config);
+            harness.run(ops_per_seed);
+            harness.result().clone()
+        })
+        .collect()
+}
+
+/// Summarize batch results
+pub fn summarize_stream_batch(results: &[StreamDSTResult]) -> String {
+    let total = results.len();
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    let failed = total - passed;
+    let total_ops: u64 = results.iter().map(|r| r.total_operations).sum();
+    let total_acked: u64 = results.iter().map(|r| r.acked).sum();
+    let total_claimed: u64 = results.iter().map(|r| r.claimed).sum();
+    let total_crashes: u64 = results.iter().map(|r| r.crashes).sum();
+
+    let mut summary = format!(
+        "Stream Consumer Group DST Summary\n\
+         =================================\n\
+         Seeds: {} total, {} passed, {} failed\n\
+         Total operations: {}\n\
+         Acked: {}, claimed: {}, crashes: {}\n",
+        total, passed, failed, total_ops, total_acked, total_claimed, total_crashes
+    );
+
+    if failed > 0 {
+        summary.push_str("\nFailed seeds:\n");
+        for result in results.iter().filter(|r| !r.is_success()) {
+            summary.push_str(&format!("  Seed {}: {}\n", result.seed, result.summary()));
+            for violation in &result.invariant_violations {
+                summary.push_str(&format!("    - {}\n", violation));
+            }
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_dst_single_seed() {
+        let mut harness = StreamDSTHarness::with_seed(12345);
+        harness.run(500);
+        let result = harness.result();
+        println!("{}", result.summary());
+        assert!(result.is_success(), "Seed 12345 failed");
+        assert!(result.acked > 0, "nothing was ever acknowledged");
+    }
+
+    #[test]
+    fn test_stream_dst_crashes_are_recovered_by_autoclaim() {
+        let mut harness = StreamDSTHarness::new(StreamDSTConfig::crash_heavy(42));
+        harness.run(1000);
+        let result = harness.result();
+        println!("{}", result.summary());
+        assert!(result.is_success());
+        assert!(result.crashes > 0, "no consumer ever crashed");
+        assert!(result.claimed > 0, "no abandoned entry was ever claimed");
+    }
+
+    #[test]
+    fn test_stream_dst_contended_claims_produce_stale_acks() {
+        let mut harness = StreamDSTHarness::new(StreamDSTConfig::contended(7));
+        harness.run(1000);
+        let result = harness.result();
+        println!("{}", result.summary());
+        assert!(result.is_success());
+        assert!(
+            result.stale_acks > 0,
+            "no consumer ever lost an entry it held"
+        );
+    }
+
+    #[test]
+    fn test_stream_dst_10_seeds() {
+        let results = run_stream_batch(0, 10, 500, StreamDSTConfig::new);
+        let summary = summarize_stream_batch(&results);
+        println!("{}", summary);
+
+        let passed = results.iter().filter(|r| r.is_success()).count();
+        assert_eq!(passed, 10, "All 10 seeds should pass");
+    }
+}
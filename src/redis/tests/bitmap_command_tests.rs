@@ -0,0 +1,348 @@
+//! Bitmap command tests - BITCOUNT, BITPOS, BITOP, BITFIELD
+
+use super::super::{Command, CommandExecutor, RespValue};
+use super::run;
+
+fn set_bytes(executor: &mut CommandExecutor, key: &str, bytes: &[u8]) {
+    let resp = RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"SET".to_vec())),
+        RespValue::BulkString(Some(key.as_bytes().to_vec())),
+        RespValue::BulkString(Some(bytes.to_vec())),
+    ]));
+    let cmd = Command::from_resp(&resp).unwrap();
+    assert_eq!(executor.execute(&cmd), RespValue::simple("OK"));
+}
+
+fn int(n: i64) -> RespValue {
+    RespValue::Integer(n)
+}
+
+fn ints(values: &[i64]) -> RespValue {
+    RespValue::Array(Some(values.iter().map(|&n| int(n)).collect()))
+}
+
+#[test]
+fn test_bitcount_byte_and_bit_ranges() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "foobar"]);
+
+    // Examples from the Redis docs
+    assert_eq!(run(&mut executor, &["BITCOUNT", "k"]), int(26));
+    assert_eq!(run(&mut executor, &["BITCOUNT", "k", "0", "0"]), int(4));
+    assert_eq!(run(&mut executor, &["BITCOUNT", "k", "1", "1"]), int(6));
+    assert_eq!(
+        run(&mut executor, &["BITCOUNT", "k", "1", "1", "BYTE"]),
+        int(6)
+    );
+    assert_eq!(
+        run(&mut executor, &["BITCOUNT", "k", "5", "30", "BIT"]),
+        int(17)
+    );
+
+    // Negative indexes count from the end; out-of-range ends clamp
+    assert_eq!(run(&mut executor, &["BITCOUNT", "k", "-2", "-1"]), int(7));
+    assert_eq!(run(&mut executor, &["BITCOUNT", "k", "0", "100"]), int(26));
+    assert_eq!(
+        run(&mut executor, &["BITCOUNT", "k", "-8", "-1", "bit"]),
+        int(4)
+    );
+    assert_eq!(run(&mut executor, &["BITCOUNT", "k", "4", "2"]), int(0));
+    assert_eq!(run(&mut executor, &["BITCOUNT", "missing"]), int(0));
+
+    run(&mut executor, &["LPUSH", "list", "a"]);
+    assert!(matches!(
+        run(&mut executor, &["BITCOUNT", "list"]),
+        RespValue::Error(e) if e.starts_with("WRONGTYPE")
+    ));
+}
+
+#[test]
+fn test_bitpos_finds_first_bit() {
+    let mut executor = CommandExecutor::new();
+    set_bytes(&mut executor, "k", &[0xFF, 0xF0, 0x00]);
+
+    assert_eq!(run(&mut executor, &["BITPOS", "k", "0"]), int(12));
+    assert_eq!(run(&mut executor, &["BITPOS", "k", "1"]), int(0));
+    assert_eq!(run(&mut executor, &["BITPOS", "k", "1", "1"]), int(8));
+    assert_eq!(run(&mut executor, &["BITPOS", "k", "1", "2"]), int(-1));
+    assert_eq!(
+        run(&mut executor, &["BITPOS", "k", "1", "2", "-1", "BIT"]),
+        int(2)
+    );
+    assert_eq!(
+        run(&mut executor, &["BITPOS", "k", "0", "0", "11", "BIT"]),
+        int(-1)
+    );
+
+    // All ones: without an end the string is treated as zero-padded
+    set_bytes(&mut executor, "ones", &[0xFF, 0xFF]);
+    assert_eq!(run(&mut executor, &["BITPOS", "ones", "0"]), int(16));
+    assert_eq!(run(&mut executor, &["BITPOS", "ones", "0", "1"]), int(16));
+    assert_eq!(
+        run(&mut executor, &["BITPOS", "ones", "0", "0", "-1"]),
+        int(-1)
+    );
+
+    assert_eq!(run(&mut executor, &["BITPOS", "missing", "1"]), int(-1));
+    assert_eq!(run(&mut executor, &["BITPOS", "missing", "0"]), int(0));
+    assert_eq!(
+        run(&mut executor, &["BITPOS", "k", "2"]),
+        RespValue::err("ERR The bit argument must be 1 or 0.")
+    );
+}
+
+#[test]
+fn test_bitop_pads_and_writes_destination() {
+    let mut executor = CommandExecutor::new();
+    set_bytes(&mut executor, "a", &[0b1100_1100, 0xFF]);
+    set_bytes(&mut executor, "b", &[0b1010_1010]);
+
+    assert_eq!(run(&mut executor, &["BITOP", "AND", "d", "a", "b"]), int(2));
+    assert_eq!(
+        run(&mut executor, &["GET", "d"]),
+        RespValue::BulkString(Some(vec![0b1000_1000, 0x00]))
+    );
+    assert_eq!(run(&mut executor, &["BITOP", "or", "d", "a", "b"]), int(2));
+    assert_eq!(
+        run(&mut executor, &["GET", "d"]),
+        RespValue::BulkString(Some(vec![0b1110_1110, 0xFF]))
+    );
+    assert_eq!(run(&mut executor, &["BITOP", "XOR", "d", "a", "b"]), int(2));
+    assert_eq!(
+        run(&mut executor, &["GET", "d"]),
+        RespValue::BulkString(Some(vec![0b0110_0110, 0xFF]))
+    );
+    assert_eq!(run(&mut executor, &["BITOP", "NOT", "d", "b"]), int(1));
+    assert_eq!(
+        run(&mut executor, &["GET", "d"]),
+        RespValue::BulkString(Some(vec![0b0101_0101]))
+    );
+
+    // Destination TTL is cleared; an all-missing source deletes it
+    run(&mut executor, &["EXPIRE", "d", "100"]);
+    assert_eq!(run(&mut executor, &["BITOP", "OR", "d", "a"]), int(2));
+    assert_eq!(run(&mut executor, &["TTL", "d"]), int(-1));
+    assert_eq!(run(&mut executor, &["BITOP", "OR", "d", "nope"]), int(0));
+    assert_eq!(run(&mut executor, &["EXISTS", "d"]), int(0));
+
+    run(&mut executor, &["LPUSH", "list", "a"]);
+    assert!(matches!(
+        run(&mut executor, &["BITOP", "AND", "d", "a", "list"]),
+        RespValue::Error(e) if e.starts_with("WRONGTYPE")
+    ));
+}
+
+#[test]
+fn test_bitmap_parse_errors() {
+    let mut executor = CommandExecutor::new();
+    let cases: &[(&[&str], &str)] = &[
+        (&["BITCOUNT", "k", "0"], "ERR syntax error"),
+        (&["BITCOUNT", "k", "0", "1", "WORD"], "ERR syntax error"),
+        (
+            &["BITCOUNT", "k", "x", "1"],
+            "ERR value is not an integer or out of range",
+        ),
+        (
+            &["BITPOS", "k", "1", "0", "1", "NIBBLE"],
+            "ERR syntax error",
+        ),
+        (&["BITOP", "NAND", "d", "a"], "ERR syntax error"),
+        (
+            &["BITOP", "NOT", "d", "a", "b"],
+            "ERR BITOP NOT must be called with a single source key.",
+        ),
+        (
+            &["BITOP", "AND", "d"],
+            "ERR wrong number of arguments for 'bitop' command",
+        ),
+    ];
+    for (parts, expected) in cases {
+        assert_eq!(
+            run(&mut executor, parts),
+            RespValue::err(*expected),
+            "{:?}",
+            parts
+        );
+    }
+}
+
+#[test]
+fn test_bitfield_get_set_incrby() {
+    let mut executor = CommandExecutor::new();
+
+    // Examples from the Redis docs
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "k", "INCRBY", "i5", "100", "1", "GET", "u4", "0"]
+        ),
+        ints(&[1, 0])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "f", "SET", "i8", "0", "-100", "GET", "u8", "0"]
+        ),
+        ints(&[0, 156])
+    );
+    assert_eq!(
+        run(&mut executor, &["BITFIELD", "f", "GET", "i8", "0"]),
+        ints(&[-100])
+    );
+
+    // `#N` offsets are multiplied by the type width
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "h", "SET", "u8", "#1", "200", "GET", "u8", "8"]
+        ),
+        ints(&[0, 200])
+    );
+    assert_eq!(run(&mut executor, &["STRLEN", "h"]), int(2));
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "h", "GET", "u4", "#3", "GET", "u16", "100"]
+        ),
+        ints(&[8, 0])
+    );
+
+    // 64-bit signed fields wrap like two's complement
+    let max = i64::MAX.to_string();
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "w", "SET", "i64", "3", &max, "INCRBY", "i64", "3", "1"]
+        ),
+        ints(&[0, i64::MIN])
+    );
+    assert_eq!(run(&mut executor, &["BITFIELD", "w"]), ints(&[]));
+
+    // TTL survives; BITFIELD_RO reads the same fields
+    run(&mut executor, &["EXPIRE", "h", "100"]);
+    run(&mut executor, &["BITFIELD", "h", "INCRBY", "u8", "#1", "5"]);
+    assert_eq!(run(&mut executor, &["TTL", "h"]), int(100));
+    assert_eq!(
+        run(&mut executor, &["BITFIELD_RO", "h", "GET", "u8", "#1"]),
+        ints(&[205])
+    );
+    assert_eq!(
+        run(&mut executor, &["BITFIELD_RO", "missing", "GET", "i8", "0"]),
+        ints(&[0])
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "missing"]), int(0));
+}
+
+#[test]
+fn test_bitfield_overflow_policies() {
+    let mut executor = CommandExecutor::new();
+    let incr = [
+        "BITFIELD", "k", "INCRBY", "u2", "100", "1", "OVERFLOW", "SAT", "INCRBY", "u2", "102", "1",
+    ];
+    assert_eq!(run(&mut executor, &incr), ints(&[1, 1]));
+    assert_eq!(run(&mut executor, &incr), ints(&[2, 2]));
+    assert_eq!(run(&mut executor, &incr), ints(&[3, 3]));
+    assert_eq!(run(&mut executor, &incr), ints(&[0, 3]));
+
+    // FAIL replies nil and leaves the field alone
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "k", "OVERFLOW", "FAIL", "INCRBY", "u2", "102", "1"]
+        ),
+        RespValue::Array(Some(vec![RespValue::BulkString(None)]))
+    );
+    assert_eq!(
+        run(&mut executor, &["BITFIELD", "k", "GET", "u2", "102"]),
+        ints(&[3])
+    );
+
+    // SET applies the policy to the new value
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "s", "SET", "u8", "0", "-1", "GET", "u8", "0"]
+        ),
+        ints(&[0, 255])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "s", "OVERFLOW", "SAT", "SET", "i8", "0", "200", "GET", "i8", "0"]
+        ),
+        ints(&[-1, 127])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "s", "OVERFLOW", "SAT", "INCRBY", "i8", "0", "-300"]
+        ),
+        ints(&[-128])
+    );
+    assert_eq!(
+        run(&mut executor, &["BITFIELD", "s", "INCRBY", "i8", "0", "-1"]),
+        ints(&[127])
+    );
+
+    // A rejected write still pads the string, as in Redis
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BITFIELD", "n", "OVERFLOW", "FAIL", "SET", "u2", "12", "7"]
+        ),
+        RespValue::Array(Some(vec![RespValue::BulkString(None)]))
+    );
+    assert_eq!(run(&mut executor, &["STRLEN", "n"]), int(2));
+}
+
+#[test]
+fn test_bitfield_errors() {
+    let mut executor = CommandExecutor::new();
+    let bad_type = "ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.";
+    let bad_offset = "ERR bit offset is not an integer or out of range";
+    let cases: &[(&[&str], &str)] = &[
+        (&["BITFIELD", "k", "GET", "u64", "0"], bad_type),
+        (&["BITFIELD", "k", "GET", "i65", "0"], bad_type),
+        (&["BITFIELD", "k", "GET", "x8", "0"], bad_type),
+        (&["BITFIELD", "k", "GET", "i0", "0"], bad_type),
+        (&["BITFIELD", "k", "GET", "u8", "-1"], bad_offset),
+        (&["BITFIELD", "k", "GET", "u8", "#x"], bad_offset),
+        (
+            &["BITFIELD", "k", "SET", "u8", "4294967296", "1"],
+            bad_offset,
+        ),
+        (
+            &["BITFIELD", "k", "OVERFLOW", "BOGUS"],
+            "ERR Invalid OVERFLOW type specified",
+        ),
+        (&["BITFIELD", "k", "GET", "u8"], "ERR syntax error"),
+        (&["BITFIELD", "k", "FROB", "u8", "0"], "ERR syntax error"),
+        (
+            &["BITFIELD", "k", "INCRBY", "u8", "0", "x"],
+            "ERR value is not an integer or out of range",
+        ),
+        (
+            &["BITFIELD_RO", "k", "GET", "u8", "0", "SET", "u8", "0", "1"],
+            "ERR BITFIELD_RO only supports the GET subcommand",
+        ),
+        (
+            &["BITFIELD_RO"],
+            "ERR wrong number of arguments for 'bitfield_ro' command",
+        ),
+    ];
+    for (parts, expected) in cases {
+        assert_eq!(
+            run(&mut executor, parts),
+            RespValue::err(*expected),
+            "{:?}",
+            parts
+        );
+    }
+    assert_eq!(run(&mut executor, &["EXISTS", "k"]), int(0));
+
+    run(&mut executor, &["LPUSH", "list", "a"]);
+    assert!(matches!(
+        run(&mut executor, &["BITFIELD", "list", "GET", "u8", "0"]),
+        RespValue::Error(e) if e.starts_with("WRONGTYPE")
+    ));
+}
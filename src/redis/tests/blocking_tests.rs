@@ -0,0 +1,505 @@
+//! Blocking command tests - BLPOP, BRPOP, BLMOVE, BZPOPMIN, BZPOPMAX,
+//! XREAD/XREADGROUP BLOCK
+//!
+//! The executor half never blocks; the `RedisServer` half parks requests and
+//! serves them on pushes or virtual-time deadlines.
+
+use super::super::{Command, CommandExecutor, RedisClient, RedisServer, RespParser, RespValue};
+use crate::simulator::{
+    CommandFamily, Duration, EventType, HostId, LatencyDistribution, LatencyProfile, RunEnd,
+    RunOutcome, Simulation, SimulationConfig, TimerId, VirtualTime,
+};
+
+fn resp(parts: &[&str]) -> RespValue {
+    RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+            .collect(),
+    ))
+}
+
+fn run(executor: &mut CommandExecutor, parts: &[&str]) -> RespValue {
+    match Command::from_resp(&resp(parts)) {
+        Ok(cmd) => executor.execute(&cmd),
+        Err(e) => RespValue::err(e),
+    }
+}
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+fn popped(key: &str, value: &str) -> RespValue {
+    RespValue::Array(Some(vec![bulk(key), bulk(value)]))
+}
+
+// ============================================
+// Executor (non-blocking half) Tests
+// ============================================
+
+#[test]
+fn test_blpop_pops_first_non_empty_key() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["RPUSH", "b", "1", "2"]);
+    run(&mut executor, &["RPUSH", "c", "3"]);
+
+    assert_eq!(
+        run(&mut executor, &["BLPOP", "a", "b", "c", "0"]),
+        popped("b", "1")
+    );
+    assert_eq!(
+        run(&mut executor, &["BRPOP", "a", "b", "c", "0"]),
+        popped("b", "2")
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "b"]), RespValue::Integer(0));
+    assert_eq!(
+        run(&mut executor, &["BRPOP", "a", "b", "0.5"]),
+        RespValue::Array(None)
+    );
+
+    run(&mut executor, &["SET", "s", "x"]);
+    assert_eq!(
+        run(&mut executor, &["BLPOP", "a", "s", "c", "0"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+}
+
+#[test]
+fn test_blmove_moves_or_replies_nil() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BLMOVE", "src", "dst", "LEFT", "RIGHT", "1"]
+        ),
+        RespValue::BulkString(None)
+    );
+
+    run(&mut executor, &["RPUSH", "src", "a", "b"]);
+    assert_eq!(
+        run(
+            &mut executor,
+            &["BLMOVE", "src", "dst", "RIGHT", "LEFT", "1"]
+        ),
+        bulk("b")
+    );
+    assert_eq!(
+        run(&mut executor, &["LRANGE", "dst", "0", "-1"]),
+        RespValue::Array(Some(vec![bulk("b")]))
+    );
+}
+
+#[test]
+fn test_blocking_parse_errors() {
+    let cases: &[(&[&str], &str)] = &[
+        (
+            &["BLPOP", "q"],
+            "ERR wrong number of arguments for 'blpop' command",
+        ),
+        (&["BLPOP", "q", "-1"], "ERR timeout is negative"),
+        (
+            &["BRPOP", "q", "soon"],
+            "ERR timeout is not a float or out of range",
+        ),
+        (&["BLMOVE", "a", "b", "UP", "LEFT", "0"], "ERR syntax error"),
+        (&["BZPOPMAX", "z", "-0.5"], "ERR timeout is negative"),
+        (
+            &["ZPOPMIN", "z", "-1"],
+            "ERR value is out of range, must be positive",
+        ),
+        (
+            &["XREAD", "BLOCK", "1.5", "STREAMS", "s", "$"],
+            "ERR timeout is not an integer or out of range",
+        ),
+        (
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "g",
+                "c",
+                "BLOCK",
+                "-1",
+                "STREAMS",
+                "s",
+                ">",
+            ],
+            "ERR timeout is negative",
+        ),
+    ];
+    for (parts, expected) in cases {
+        assert_eq!(
+            Command::from_resp(&resp(parts)).unwrap_err(),
+            *expected,
+            "from_resp {:?}",
+            parts
+        );
+    }
+
+    let cmd = Command::from_resp(&resp(&["BLPOP", "a", "b", "1.5"])).unwrap();
+    assert!(!cmd.is_read_only());
+    assert_eq!(cmd.get_keys(), vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(
+        cmd.blocking_keys(),
+        Some((&["a".to_string(), "b".to_string()][..], 1500))
+    );
+
+    // Without BLOCK, XREAD is an ordinary read
+    let cmd = Command::from_resp(&resp(&["XREAD", "STREAMS", "s", "$"])).unwrap();
+    assert_eq!(cmd.blocking_keys(), None);
+    let cmd = Command::from_resp(&resp(&["XREAD", "BLOCK", "250", "STREAMS", "s", "$"])).unwrap();
+    assert!(cmd.is_read_only());
+    assert_eq!(cmd.blocking_keys(), Some((&["s".to_string()][..], 250)));
+}
+
+#[test]
+fn test_bzpopmin_pops_first_non_empty_key() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["ZADD", "b", "2", "x", "1", "y"]);
+
+    assert_eq!(
+        run(&mut executor, &["BZPOPMIN", "a", "b", "0"]),
+        RespValue::Array(Some(vec![bulk("b"), bulk("y"), bulk("1")]))
+    );
+    assert_eq!(
+        run(&mut executor, &["BZPOPMAX", "a", "b", "0"]),
+        RespValue::Array(Some(vec![bulk("b"), bulk("x"), bulk("2")]))
+    );
+    assert_eq!(
+        run(&mut executor, &["BZPOPMAX", "a", "b", "0"]),
+        RespValue::Array(None)
+    );
+}
+
+// ============================================
+// Simulated Server (virtual-time) Tests
+// ============================================
+
+/// One server and a few clients; commands leave from client timers so a
+/// test decides when each one is sent
+struct World {
+    sim: Simulation,
+    server: RedisServer,
+    hosts: Vec<HostId>,
+    clients: Vec<RedisClient>,
+    /// Scheduled sends not yet fired: timer -> label
+    pending: Vec<(TimerId, usize)>,
+    /// label -> (client index, command bytes, request id once sent)
+    sends: Vec<(usize, Vec<u8>, Option<u64>)>,
+}
+
+impl World {
+    fn new(num_clients: usize) -> Self {
+        Self::with_latency(num_clients, LatencyProfile::default())
+    }
+
+    fn with_latency(num_clients: usize, latency: LatencyProfile) -> Self {
+        let mut sim = Simulation::new(SimulationConfig {
+            seed: 7,
+            max_time: VirtualTime::from_secs(60),
+            simulation_start_epoch: 0,
+            ..Default::default()
+        });
+        let server_host = sim.add_host("server".to_string());
+        let hosts: Vec<HostId> = (0..num_clients)
+            .map(|i| sim.add_host(format!("client-{}", i)))
+            .collect();
+        let clients = hosts
+            .iter()
+            .map(|h| RedisClient::new(*h, server_host))
+            .collect();
+        World {
+            sim,
+            server: RedisServer::new(server_host).with_latency(latency),
+            hosts,
+            clients,
+            pending: Vec::new(),
+            sends: Vec::new(),
+        }
+    }
+
+    /// Send `parts` from `client` at virtual time `at_ms`; returns a label
+    fn send_at(&mut self, at_ms: u64, client: usize, parts: &[&str]) -> usize {
+        let delay = at_ms - self.sim.current_time().as_millis();
+        let timer = self
+            .sim
+            .schedule_timer(self.hosts[client], Duration::from_millis(delay));
+        self.sends
+            .push((client, RespParser::encode(&resp(parts)), None));
+        let label = self.sends.len() - 1;
+        self.pending.push((timer, label));
+        label
+    }
+
+    fn run_until(&mut self, ms: u64) -> RunOutcome {
+        let World {
+            sim,
+            server,
+            clients,
+            pending,
+            sends,
+            ..
+        } = self;
+        sim.run_until(VirtualTime::from_millis(ms), |sim, event| {
+            server.handle_event(sim, event);
+            for client in clients.iter_mut() {
+                client.handle_event(event);
+            }
+            if let EventType::Timer(id) = event.event_type {
+                if let Some(pos) = pending.iter().position(|(t, _)| *t == id) {
+                    let (_, label) = pending.remove(pos);
+                    let (client, bytes, request_id) = &mut sends[label];
+                    *request_id = Some(clients[*client].send_command(sim, bytes.clone()));
+                }
+            }
+        })
+    }
+
+    fn reply(&self, label: usize) -> Option<&RespValue> {
+        let (client, _, request_id) = &self.sends[label];
+        self.clients[*client].get_response((*request_id)?)
+    }
+
+    fn replied_at(&self, label: usize) -> Option<u64> {
+        let (client, _, request_id) = &self.sends[label];
+        self.clients[*client]
+            .response_time((*request_id)?)
+            .map(|t| t.as_millis())
+    }
+}
+
+#[test]
+fn test_push_from_another_client_wakes_waiters_in_order() {
+    let mut world = World::new(3);
+    let first = world.send_at(0, 0, &["BLPOP", "q", "0"]);
+    let second = world.send_at(20, 1, &["BLPOP", "q", "other", "0"]);
+    world.run_until(100);
+    assert_eq!(world.reply(first), None);
+    assert_eq!(world.server.num_blocked(), 2);
+
+    // One push with two elements serves both waiters, longest-waiting first
+    let push = world.send_at(200, 2, &["RPUSH", "q", "x", "y"]);
+    world.run_until(300);
+    assert_eq!(world.reply(push), Some(&RespValue::Integer(2)));
+    assert_eq!(world.reply(first), Some(&popped("q", "x")));
+    assert_eq!(world.reply(second), Some(&popped("q", "y")));
+    assert_eq!(world.server.num_blocked(), 0);
+}
+
+#[test]
+fn test_timeout_fires_on_virtual_time() {
+    let mut world = World::new(2);
+    let waiter = world.send_at(0, 0, &["BRPOP", "q", "1.5"]);
+    let mover = world.send_at(0, 1, &["BLMOVE", "src", "q", "LEFT", "LEFT", "0.5"]);
+
+    // Deadlines count from arrival (at most 10ms of network latency)
+    world.run_until(490);
+    assert_eq!(world.reply(mover), None);
+    world.run_until(530);
+    assert_eq!(world.reply(mover), Some(&RespValue::BulkString(None)));
+    assert_eq!(world.reply(waiter), None);
+
+    world.run_until(1_490);
+    assert_eq!(world.reply(waiter), None);
+    world.run_until(1_530);
+    assert_eq!(world.reply(waiter), Some(&RespValue::Array(None)));
+    assert_eq!(world.server.num_blocked(), 0);
+}
+
+#[test]
+fn test_blmove_chain_wakes_waiter_on_destination() {
+    let mut world = World::new(3);
+    let tail = world.send_at(0, 0, &["BLPOP", "done", "0"]);
+    let worker = world.send_at(10, 1, &["BLMOVE", "jobs", "done", "LEFT", "RIGHT", "0"]);
+    world.run_until(100);
+
+    world.send_at(150, 2, &["LPUSH", "jobs", "job-1"]);
+    world.run_until(250);
+    assert_eq!(world.reply(worker), Some(&bulk("job-1")));
+    assert_eq!(world.reply(tail), Some(&popped("done", "job-1")));
+}
+
+#[test]
+fn test_run_outcome_names_clients_blocked_forever() {
+    let mut world = World::new(2);
+    world.send_at(0, 0, &["BLPOP", "q", "other", "0"]);
+    world.send_at(0, 1, &["BRPOP", "q", "5"]);
+
+    let outcome = world.run_until(1_000);
+    assert_eq!(outcome.end, RunEnd::MaxTime);
+    let server = &outcome.waits[0];
+    assert_eq!(server.name, "server");
+    assert_eq!(server.timers, 1, "only the BRPOP has a deadline");
+    assert_eq!(
+        server.waiting_on,
+        vec![
+            format!("BLPOP q other for {:?} forever", world.hosts[0]),
+            format!("BRPOP q for {:?}", world.hosts[1]),
+        ]
+    );
+
+    // With the BRPOP timed out, nothing will ever wake the BLPOP
+    let outcome = world.run_until(10_000);
+    assert_eq!(outcome.end, RunEnd::Drained);
+    assert!(outcome.is_stuck());
+    assert_eq!(outcome.waits.len(), 1);
+    assert_eq!(outcome.waits[0].waiting_on.len(), 1);
+}
+
+// ============================================
+// Declared Execution Latency Tests
+// ============================================
+
+#[test]
+fn test_slow_command_queues_requests_behind_it() {
+    let profile = LatencyProfile::new(LatencyDistribution::fixed_ms(1)).with_family(
+        CommandFamily::command("ZRANGEBYSCORE"),
+        LatencyDistribution::fixed_ms(100),
+    );
+    let mut world = World::with_latency(2, profile);
+    let slow = world.send_at(0, 0, &["ZRANGEBYSCORE", "z", "-inf", "+inf"]);
+    let fast = world.send_at(15, 1, &["GET", "k"]);
+
+    world.run_until(60);
+    assert_eq!(world.reply(slow), None);
+    assert_eq!(
+        world.server.queue_len(),
+        1,
+        "GET waits behind ZRANGEBYSCORE"
+    );
+
+    world.run_until(200);
+    assert_eq!(world.reply(slow), Some(&RespValue::Array(Some(vec![]))));
+    assert_eq!(world.reply(fast), Some(&RespValue::BulkString(None)));
+    assert!(world.replied_at(fast).unwrap() >= 101);
+
+    let stats = world.server.queue_stats();
+    assert_eq!(stats.executed, 2);
+    assert_eq!(stats.max_depth, 1);
+    assert!(
+        stats.max_wait.as_millis() >= 80,
+        "GET arrived at most 25ms in and waited for the 100ms command: {:?}",
+        stats
+    );
+}
+
+#[test]
+fn test_slow_push_lands_after_blocked_client_times_out() {
+    let profile = LatencyProfile::default()
+        .with_family(CommandFamily::Write, LatencyDistribution::fixed_ms(300));
+    let mut world = World::with_latency(3, profile);
+    // BLPOP is a write too, so it only parks after its own 300ms
+    let early = world.send_at(0, 0, &["BLPOP", "q", "0.1"]);
+    let push = world.send_at(20, 1, &["RPUSH", "q", "x"]);
+    world.run_until(350);
+    assert_eq!(world.server.num_blocked(), 1);
+    assert_eq!(world.server.queue_len(), 0);
+
+    // The push completes around 600ms, after the 100ms deadline (~400ms)
+    world.run_until(500);
+    assert_eq!(world.reply(early), Some(&RespValue::Array(None)));
+    assert_eq!(world.reply(push), None);
+    world.run_until(700);
+    assert_eq!(world.reply(push), Some(&RespValue::Integer(1)));
+    assert_eq!(world.server.num_blocked(), 0);
+}
+
+// ============================================
+// Sorted Set and Stream Blocking Tests
+// ============================================
+
+#[test]
+fn test_zadd_wakes_bzpopmin() {
+    let mut world = World::new(2);
+    let waiter = world.send_at(0, 0, &["BZPOPMIN", "z", "0"]);
+    world.run_until(100);
+    assert_eq!(world.server.num_blocked(), 1);
+
+    world.send_at(150, 1, &["ZADD", "z", "5", "late", "3", "early"]);
+    world.run_until(250);
+    assert_eq!(
+        world.reply(waiter),
+        Some(&RespValue::Array(Some(vec![
+            bulk("z"),
+            bulk("early"),
+            bulk("3")
+        ])))
+    );
+}
+
+/// Entry IDs in an XREAD/XREADGROUP reply, in order
+fn read_ids(reply: &RespValue) -> Vec<String> {
+    let RespValue::Array(Some(streams)) = reply else {
+        return Vec::new();
+    };
+    let mut ids = Vec::new();
+    for stream in streams {
+        if let RespValue::Array(Some(parts)) = stream {
+            if let Some(RespValue::Array(Some(entries))) = parts.get(1) {
+                for entry in entries {
+                    if let RespValue::Array(Some(fields)) = entry {
+                        if let Some(RespValue::BulkString(Some(id))) = fields.first() {
+                            ids.push(String::from_utf8_lossy(id).into_owned());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    ids
+}
+
+#[test]
+fn test_xread_block_dollar_sees_only_later_entries() {
+    let mut world = World::new(3);
+    world.send_at(0, 2, &["XADD", "s", "1-1", "f", "old"]);
+    // `$` is pinned to 1-1 when the read arrives, not at each retry
+    let reader = world.send_at(50, 0, &["XREAD", "BLOCK", "0", "STREAMS", "s", "$"]);
+    let timed = world.send_at(50, 1, &["XREAD", "BLOCK", "300", "STREAMS", "other", "$"]);
+    world.run_until(100);
+    assert_eq!(world.server.num_blocked(), 2);
+
+    world.send_at(150, 2, &["XADD", "s", "2-1", "f", "new"]);
+    world.run_until(250);
+    assert_eq!(read_ids(world.reply(reader).unwrap()), vec!["2-1"]);
+
+    world.run_until(340);
+    assert_eq!(world.reply(timed), None);
+    world.run_until(400);
+    assert_eq!(world.reply(timed), Some(&RespValue::Array(None)));
+    assert_eq!(world.server.num_blocked(), 0);
+}
+
+#[test]
+fn test_xreadgroup_block_delivers_each_entry_once() {
+    let mut world = World::new(3);
+    world.send_at(0, 2, &["XGROUP", "CREATE", "s", "g", "$", "MKSTREAM"]);
+    let read = |consumer| {
+        [
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            consumer,
+            "BLOCK",
+            "0",
+            "STREAMS",
+            "s",
+            ">",
+        ]
+    };
+    let alice = world.send_at(50, 0, &read("alice"));
+    let bob = world.send_at(60, 1, &read("bob"));
+    world.run_until(100);
+    assert_eq!(world.server.num_blocked(), 2);
+
+    // Both wake; alice registered first and takes the entry, bob waits on
+    world.send_at(150, 2, &["XADD", "s", "5-1", "job", "1"]);
+    world.run_until(250);
+    assert_eq!(read_ids(world.reply(alice).unwrap()), vec!["5-1"]);
+    assert_eq!(world.reply(bob), None);
+    assert_eq!(world.server.num_blocked(), 1);
+
+    world.send_at(300, 2, &["XADD", "s", "6-1", "job", "2"]);
+    world.run_until(400);
+    assert_eq!(read_ids(world.reply(bob).unwrap()), vec!["6-1"]);
+}
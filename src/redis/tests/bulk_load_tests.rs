@@ -0,0 +1,261 @@
+//! Bulk loading - CommandExecutor::bulk_load, import plans, RDB decoding
+
+use super::super::import::{read_import, ImportStep};
+use super::super::rdb;
+use super::super::{
+    Command, CommandExecutor, RedisList, RedisSet, RedisSortedSet, RespParser, RespValue, Value,
+    SDS,
+};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+fn sds(items: &[&str]) -> Vec<SDS> {
+    items.iter().map(|s| SDS::from_str(s)).collect()
+}
+
+/// Encode commands the way `redis-cli --pipe` expects them
+fn resp_file(cmds: &[&[&str]]) -> Vec<u8> {
+    cmds.iter()
+        .flat_map(|parts| {
+            RespParser::encode(&RespValue::Array(Some(
+                parts.iter().map(|p| bulk(p)).collect(),
+            )))
+        })
+        .collect()
+}
+
+/// A 6-bit length-prefixed RDB string
+fn rdb_string(s: &[u8]) -> Vec<u8> {
+    assert!(s.len() < 64);
+    let mut out = vec![s.len() as u8];
+    out.extend_from_slice(s);
+    out
+}
+
+#[test]
+fn test_bulk_load_installs_values_and_ttls() {
+    let mut executor = CommandExecutor::new();
+    executor.set_time(VirtualTime::from_millis(1_000));
+    run(&mut executor, &["SET", "old", "x"]);
+
+    let report = executor.bulk_load(vec![
+        (
+            "list".to_string(),
+            Value::List(RedisList::from_items(sds(&["a", "b", "c"]))),
+            None,
+        ),
+        (
+            "zset".to_string(),
+            Value::SortedSet(RedisSortedSet::from_pairs(vec![
+                (SDS::from_str("m1"), 2.0),
+                (SDS::from_str("m2"), 1.0),
+                (SDS::from_str("m1"), 3.0),
+            ])),
+            Some(5_000),
+        ),
+        ("old".to_string(), Value::String(SDS::from_str("new")), None),
+    ]);
+
+    assert_eq!(report.loaded, 3);
+    assert_eq!(report.replaced, 1);
+    assert_eq!(report.skipped, 0);
+    assert_eq!(
+        run(&mut executor, &["LRANGE", "list", "0", "-1"]),
+        RespValue::Array(Some(vec![bulk("a"), bulk("b"), bulk("c")]))
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANGE", "zset", "0", "-1"]),
+        RespValue::Array(Some(vec![bulk("m2"), bulk("m1")]))
+    );
+    assert_eq!(
+        run(&mut executor, &["PTTL", "zset"]),
+        RespValue::Integer(5_000)
+    );
+    assert_eq!(run(&mut executor, &["GET", "old"]), bulk("new"));
+    assert_eq!(executor.keyspace_stats().total_keys(), 3);
+}
+
+#[test]
+fn test_bulk_load_skips_empty_and_expired_entries() {
+    let mut executor = CommandExecutor::new();
+    let report = executor.bulk_load(vec![
+        (
+            "empty".to_string(),
+            Value::Set(RedisSet::from_members(vec![])),
+            None,
+        ),
+        (
+            "gone".to_string(),
+            Value::String(SDS::from_str("v")),
+            Some(0),
+        ),
+    ]);
+
+    assert_eq!(report.loaded, 0);
+    assert_eq!(report.skipped, 2);
+    assert_eq!(run(&mut executor, &["DBSIZE"]), RespValue::Integer(0));
+}
+
+#[test]
+fn test_bulk_load_clears_previous_ttl() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "v", "EX", "100"]);
+    executor.bulk_load(vec![(
+        "k".to_string(),
+        Value::String(SDS::from_str("w")),
+        None,
+    )]);
+    assert_eq!(run(&mut executor, &["TTL", "k"]), RespValue::Integer(-1));
+}
+
+#[test]
+fn test_resp_import_folds_writes_into_one_load() {
+    let file = resp_file(&[
+        &["RPUSH", "l", "a", "b"],
+        &["LPUSH", "l", "z"],
+        &["SADD", "s", "x", "y", "x"],
+        &["HSET", "h", "f", "1"],
+        &["ZADD", "z", "1", "one", "2", "two"],
+        &["SET", "k", "v"],
+        &["PEXPIRE", "k", "2500"],
+    ]);
+    let plan = read_import(&file, 0).unwrap();
+    assert_eq!(plan.steps.len(), 1);
+    assert_eq!(plan.loaded_keys(), 5);
+
+    let mut executor = CommandExecutor::new();
+    for step in plan.steps {
+        match step {
+            ImportStep::Load(entries) => {
+                executor.bulk_load(entries);
+            }
+            ImportStep::Execute(cmd) => {
+                executor.execute(&cmd);
+            }
+        }
+    }
+    assert_eq!(
+        run(&mut executor, &["LRANGE", "l", "0", "-1"]),
+        RespValue::Array(Some(vec![bulk("z"), bulk("a"), bulk("b")]))
+    );
+    assert_eq!(run(&mut executor, &["SCARD", "s"]), RespValue::Integer(2));
+    assert_eq!(run(&mut executor, &["HGET", "h", "f"]), bulk("1"));
+    assert_eq!(run(&mut executor, &["ZSCORE", "z", "two"]), bulk("2"));
+    assert_eq!(run(&mut executor, &["PTTL", "k"]), RespValue::Integer(2500));
+}
+
+#[test]
+fn test_resp_import_keeps_order_around_other_commands() {
+    let file = resp_file(&[
+        &["RPUSH", "l", "a"],
+        &["INCR", "counter"],
+        &["RPUSH", "l", "b"],
+    ]);
+    let plan = read_import(&file, 0).unwrap();
+
+    // The second RPUSH extends a key loaded by an earlier step, so it must
+    // execute rather than start a new load that would replace the list
+    assert_eq!(plan.steps.len(), 3);
+    assert!(matches!(plan.steps[0], ImportStep::Load(_)));
+    assert!(matches!(
+        plan.steps[1],
+        ImportStep::Execute(Command::Incr(_))
+    ));
+    assert!(matches!(
+        plan.steps[2],
+        ImportStep::Execute(Command::RPush(..))
+    ));
+}
+
+#[test]
+fn test_resp_import_rejects_malformed_commands() {
+    let err = read_import(b"*2\r\n$3\r\nGET\r\n", 0).unwrap_err();
+    assert!(err.starts_with("command 1"), "{}", err);
+}
+
+#[test]
+fn test_crc64_matches_redis() {
+    assert_eq!(rdb::crc64(0, b"123456789"), 0xe9c6_d914_c4b8_d9ca);
+}
+
+#[test]
+fn test_rdb_file_import() {
+    let mut file = b"REDIS0011".to_vec();
+    file.push(0xFA);
+    file.extend(rdb_string(b"redis-ver"));
+    file.extend(rdb_string(b"7.2.0"));
+    file.extend([0xFE, 0x00, 0xFB, 0x03, 0x01]);
+    // String with a millisecond deadline
+    file.push(0xFC);
+    file.extend(10_000u64.to_le_bytes());
+    file.push(0);
+    file.extend(rdb_string(b"str"));
+    file.extend(rdb_string(b"hello"));
+    // Integer-encoded string (int8)
+    file.push(0);
+    file.extend(rdb_string(b"num"));
+    file.extend([0xC0, 0x2A]);
+    // Quicklist v2 with one packed listpack node
+    let lp = super::super::listpack::encode([b"a".as_slice(), b"7".as_slice()]);
+    file.push(18);
+    file.extend(rdb_string(b"list"));
+    file.extend([0x01, 0x02]);
+    file.extend(rdb_string(&lp));
+    // Key in another database
+    file.extend([0xFE, 0x01, 0x00]);
+    file.extend(rdb_string(b"elsewhere"));
+    file.extend(rdb_string(b"v"));
+    file.push(0xFF);
+    let checksum = rdb::crc64(0, &file);
+    file.extend(checksum.to_le_bytes());
+
+    let plan = read_import(&file, 4_000).unwrap();
+    assert_eq!(plan.skipped_other_dbs, 1);
+    let ImportStep::Load(entries) = &plan.steps[0] else {
+        panic!("RDB import must be a single load");
+    };
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].0, "str");
+    assert_eq!(entries[0].2, Some(6_000));
+    assert_eq!(entries[1].1, Value::String(SDS::from_str("42")));
+    assert_eq!(
+        entries[2].1,
+        Value::List(RedisList::from_items(sds(&["a", "7"])))
+    );
+
+    // A flipped byte fails the checksum
+    let mut corrupt = file.clone();
+    corrupt[20] ^= 0xFF;
+    assert!(read_import(&corrupt, 0).is_err());
+}
+
+#[test]
+fn test_restore_payload_import() {
+    let mut payload = vec![0u8];
+    payload.extend(rdb_string(b"restored"));
+    payload.extend(11u16.to_le_bytes());
+    let crc = rdb::crc64(0, &payload);
+    payload.extend(crc.to_le_bytes());
+
+    let mut file = RespParser::encode(&RespValue::Array(Some(vec![
+        bulk("RESTORE"),
+        bulk("k"),
+        bulk("0"),
+        RespValue::BulkString(Some(payload)),
+        bulk("REPLACE"),
+    ])));
+    let plan = read_import(&file, 0).unwrap();
+    let ImportStep::Load(entries) = &plan.steps[0] else {
+        panic!("RESTORE must load");
+    };
+    assert_eq!(entries[0].1, Value::String(SDS::from_str("restored")));
+
+    // Damage the stored checksum: the payload no longer verifies
+    let last = file.len() - 20;
+    file[last] ^= 0x01;
+    assert!(read_import(&file, 0).is_err());
+}
@@ -0,0 +1,133 @@
+//! COMMAND, COMMAND INFO, DOCS, COUNT and GETKEYS against Redis 7 replies
+
+use super::super::{CommandExecutor, CommandSpec, RespValue};
+use super::run;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+fn status(s: &'static str) -> RespValue {
+    RespValue::SimpleString(s.into())
+}
+
+fn array(items: Vec<RespValue>) -> RespValue {
+    RespValue::Array(Some(items))
+}
+
+fn items(reply: RespValue) -> Vec<RespValue> {
+    match reply {
+        RespValue::Array(Some(items)) => items,
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_command_info_entries() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "INFO", "get"]),
+        array(vec![array(vec![
+            bulk("get"),
+            RespValue::Integer(2),
+            array(vec![status("readonly"), status("fast")]),
+            RespValue::Integer(1),
+            RespValue::Integer(1),
+            RespValue::Integer(1),
+            array(vec![status("@read"), status("@fast"), status("@string")]),
+            array(vec![]),
+            array(vec![]),
+            array(vec![]),
+        ])])
+    );
+
+    // MSET's keys run to the last argument, two apart; unknown names are nil
+    let reply = items(run(&mut executor, &["COMMAND", "INFO", "MSET", "nosuch"]));
+    assert_eq!(reply.len(), 2);
+    let mset = items(reply[0].clone());
+    assert_eq!(mset[0], bulk("mset"));
+    assert_eq!(mset[1], RespValue::Integer(-3));
+    assert_eq!(
+        mset[3..6],
+        [
+            RespValue::Integer(1),
+            RespValue::Integer(-1),
+            RespValue::Integer(2)
+        ]
+    );
+    assert_eq!(reply[1], RespValue::Array(None));
+}
+
+#[test]
+fn test_command_lists_every_command() {
+    let mut executor = CommandExecutor::new();
+    let count = CommandSpec::all().count() as i64;
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "COUNT"]),
+        RespValue::Integer(count)
+    );
+    assert_eq!(items(run(&mut executor, &["COMMAND"])).len() as i64, count);
+    assert_eq!(
+        items(run(&mut executor, &["COMMAND", "INFO"])).len() as i64,
+        count
+    );
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "COUNT", "extra"]),
+        RespValue::err("ERR wrong number of arguments for 'command|count' command")
+    );
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "BOGUS"]),
+        RespValue::err("ERR unknown subcommand 'bogus'. Try COMMAND HELP.")
+    );
+}
+
+#[test]
+fn test_command_docs_groups() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "DOCS", "zadd", "nosuch", "del"]),
+        array(vec![
+            bulk("zadd"),
+            array(vec![bulk("group"), bulk("sorted-set")]),
+            bulk("del"),
+            array(vec![bulk("group"), bulk("generic")]),
+        ])
+    );
+}
+
+#[test]
+fn test_command_getkeys() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(
+            &mut executor,
+            &["COMMAND", "GETKEYS", "MSET", "a", "1", "b", "2"]
+        ),
+        array(vec![bulk("a"), bulk("b")])
+    );
+    // Keys that move with the call come from parsing it, not the table
+    assert_eq!(
+        run(
+            &mut executor,
+            &["COMMAND", "GETKEYS", "EVAL", "return 1", "2", "k1", "k2", "arg"]
+        ),
+        array(vec![bulk("k1"), bulk("k2")])
+    );
+
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "GETKEYS", "PING"]),
+        RespValue::err("ERR The command has no key arguments")
+    );
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "GETKEYS", "NOSUCH", "k"]),
+        RespValue::err("ERR Invalid command specified")
+    );
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "GETKEYS", "GET"]),
+        RespValue::err("ERR Invalid number of arguments specified for command")
+    );
+    assert_eq!(
+        run(&mut executor, &["COMMAND", "GETKEYS"]),
+        RespValue::err("ERR wrong number of arguments for 'command|getkeys' command")
+    );
+}
@@ -262,3 +262,617 @@ fn test_get_from_both_parsers() {
         _ => panic!("Commands don't match"),
     }
 }
+
+#[test]
+fn test_pubsub_from_both_parsers() {
+    let old_resp = RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"PUBLISH".to_vec())),
+        RespValue::BulkString(Some(b"news".to_vec())),
+        RespValue::BulkString(Some(b"hello".to_vec())),
+    ]));
+    let new_resp = RespValueZeroCopy::Array(Some(vec![
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"PUBLISH"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"news"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"hello"))),
+    ]));
+
+    let old_cmd = Command::from_resp(&old_resp).unwrap();
+    let new_cmd = Command::from_resp_zero_copy(&new_resp).unwrap();
+
+    match (old_cmd, new_cmd) {
+        (
+            Command::Publish {
+                channel: c1,
+                message: m1,
+            },
+            Command::Publish {
+                channel: c2,
+                message: m2,
+            },
+        ) => {
+            assert_eq!(c1, c2);
+            assert_eq!(c1, "news");
+            assert_eq!(m1, m2);
+            assert_eq!(m1, SDS::from_str("hello"));
+        }
+        _ => panic!("Commands don't match"),
+    }
+
+    // UNSUBSCRIBE takes zero or more channels; SUBSCRIBE needs one
+    let unsubscribe = RespValueZeroCopy::Array(Some(vec![RespValueZeroCopy::BulkString(Some(
+        Bytes::from_static(b"UNSUBSCRIBE"),
+    ))]));
+    assert!(matches!(
+        Command::from_resp_zero_copy(&unsubscribe),
+        Ok(Command::Unsubscribe(channels)) if channels.is_empty()
+    ));
+    let subscribe = RespValue::Array(Some(vec![RespValue::BulkString(Some(
+        b"SUBSCRIBE".to_vec(),
+    ))]));
+    assert_eq!(
+        Command::from_resp(&subscribe).unwrap_err(),
+        "ERR wrong number of arguments for 'subscribe' command"
+    );
+}
+
+#[test]
+fn test_publish_execution_counts_subscribers() {
+    let mut executor = CommandExecutor::new();
+    let mut session = executor.pubsub().session();
+    session.subscribe(&["news".to_string()]);
+
+    let publish = Command::Publish {
+        channel: "news".to_string(),
+        message: SDS::from_str("hello"),
+    };
+    assert_eq!(executor.execute(&publish), RespValue::Integer(1));
+    assert_eq!(session.try_recv().unwrap().payload, b"hello");
+
+    // Subscriptions belong to connections, not the executor
+    assert!(matches!(
+        executor.execute(&Command::Subscribe(vec!["news".to_string()])),
+        RespValue::Error(_)
+    ));
+}
+
+#[test]
+fn test_pattern_pubsub_parsing() {
+    let psubscribe = RespValueZeroCopy::Array(Some(vec![
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"PSUBSCRIBE"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"news.*"))),
+    ]));
+    assert!(matches!(
+        Command::from_resp_zero_copy(&psubscribe),
+        Ok(Command::PSubscribe(patterns)) if patterns == vec!["news.*".to_string()]
+    ));
+
+    let channels = RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"PUBSUB".to_vec())),
+        RespValue::BulkString(Some(b"channels".to_vec())),
+        RespValue::BulkString(Some(b"n*".to_vec())),
+    ]));
+    assert!(matches!(
+        Command::from_resp(&channels),
+        Ok(Command::PubSubChannels(Some(pattern))) if pattern == "n*"
+    ));
+
+    let numpat = RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"PUBSUB".to_vec())),
+        RespValue::BulkString(Some(b"NUMPAT".to_vec())),
+        RespValue::BulkString(Some(b"extra".to_vec())),
+    ]));
+    assert_eq!(
+        Command::from_resp(&numpat).unwrap_err(),
+        "ERR wrong number of arguments for 'pubsub|numpat' command"
+    );
+
+    let unknown = RespValueZeroCopy::Array(Some(vec![
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"PUBSUB"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"SHARDCHANNELS"))),
+    ]));
+    assert_eq!(
+        Command::from_resp_zero_copy(&unknown).unwrap_err(),
+        "ERR unknown subcommand 'shardchannels'. Try PUBSUB HELP."
+    );
+}
+
+#[test]
+fn test_pubsub_introspection_execution() {
+    let mut executor = CommandExecutor::new();
+    let mut session = executor.pubsub().session();
+    session.subscribe(&["news".to_string(), "sports".to_string()]);
+    session.psubscribe(&["n*".to_string()]);
+
+    assert_eq!(
+        executor.execute(&Command::PubSubChannels(Some("n*".to_string()))),
+        RespValue::Array(Some(vec![RespValue::BulkString(Some(b"news".to_vec()))]))
+    );
+    assert_eq!(
+        executor.execute(&Command::PubSubNumSub(vec![
+            "news".to_string(),
+            "weather".to_string(),
+        ])),
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(Some(b"news".to_vec())),
+            RespValue::Integer(1),
+            RespValue::BulkString(Some(b"weather".to_vec())),
+            RespValue::Integer(0),
+        ]))
+    );
+    assert_eq!(executor.execute(&Command::PubSubNumPat), RespValue::Integer(1));
+
+    // A channel subscription and a matching pattern each receive a copy
+    let publish = Command::Publish {
+        channel: "news".to_string(),
+        message: SDS::from_str("hello"),
+    };
+    assert_eq!(executor.execute(&publish), RespValue::Integer(2));
+}
+
+#[test]
+fn test_debug_buggify_parsing() {
+    let old_resp = RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"DEBUG".to_vec())),
+        RespValue::BulkString(Some(b"buggify".to_vec())),
+        RespValue::BulkString(Some(b"set".to_vec())),
+        RespValue::BulkString(Some(b"network.packet_drop".to_vec())),
+        RespValue::BulkString(Some(b"0.25".to_vec())),
+    ]));
+    let new_resp = RespValueZeroCopy::Array(Some(vec![
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"DEBUG"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"buggify"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"set"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"network.packet_drop"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"0.25"))),
+    ]));
+
+    let old_cmd = Command::from_resp(&old_resp).unwrap();
+    let new_cmd = Command::from_resp_zero_copy(&new_resp).unwrap();
+
+    match (old_cmd, new_cmd) {
+        (
+            Command::DebugBuggifySet {
+                fault: f1,
+                probability: p1,
+            },
+            Command::DebugBuggifySet {
+                fault: f2,
+                probability: p2,
+            },
+        ) => {
+            assert_eq!(f1, f2);
+            assert_eq!(f1, "network.packet_drop");
+            assert_eq!(p1, p2);
+            assert_eq!(p1, 0.25);
+        }
+        _ => panic!("Commands don't match"),
+    }
+
+    let stats = RespValueZeroCopy::Array(Some(vec![
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"DEBUG"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"BUGGIFY"))),
+        RespValueZeroCopy::BulkString(Some(Bytes::from_static(b"STATS"))),
+    ]));
+    assert!(matches!(
+        Command::from_resp_zero_copy(&stats),
+        Ok(Command::DebugBuggifyStats)
+    ));
+
+    let missing_prob = RespValue::Array(Some(vec![
+        RespValue::BulkString(Some(b"DEBUG".to_vec())),
+        RespValue::BulkString(Some(b"BUGGIFY".to_vec())),
+        RespValue::BulkString(Some(b"SET".to_vec())),
+        RespValue::BulkString(Some(b"network.packet_drop".to_vec())),
+    ]));
+    assert_eq!(
+        Command::from_resp(&missing_prob).unwrap_err(),
+        "ERR wrong number of arguments for 'debug|buggify' command"
+    );
+}
+
+fn both_parsers(parts: &[&str]) -> (Result<Command, String>, Result<Command, String>) {
+    let old_resp = RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+            .collect(),
+    ));
+    let new_resp = RespValueZeroCopy::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValueZeroCopy::BulkString(Some(Bytes::copy_from_slice(p.as_bytes()))))
+            .collect(),
+    ));
+    (
+        Command::from_resp(&old_resp),
+        Command::from_resp_zero_copy(&new_resp),
+    )
+}
+
+#[test]
+fn test_malformed_commands_fail_identically() {
+    let cases: &[(&[&str], &str)] = &[
+        (&["GET"], "ERR wrong number of arguments for 'get' command"),
+        (&["hget", "h"], "ERR wrong number of arguments for 'hget' command"),
+        (&["LMOVE", "a", "b", "LEFT"], "ERR wrong number of arguments for 'lmove' command"),
+        (&["EXEC", "extra"], "ERR wrong number of arguments for 'exec' command"),
+        (&["EXPIRE", "k", "soon"], "ERR value is not an integer or out of range"),
+        (&["LRANGE", "l", "0", "x"], "ERR value is not an integer or out of range"),
+        (&["INCRBYFLOAT", "k", "abc"], "ERR value is not a valid float"),
+    ];
+    for (parts, expected) in cases {
+        let (old, new) = both_parsers(parts);
+        assert_eq!(old.unwrap_err(), *expected, "from_resp {:?}", parts);
+        assert_eq!(new.unwrap_err(), *expected, "from_resp_zero_copy {:?}", parts);
+    }
+
+    // The Lua bridge validates against the same table
+    let mut executor = CommandExecutor::new();
+    let result = executor.execute(&Command::Eval {
+        script: "return redis.pcall('LRANGE', 'l', '0')".to_string(),
+        keys: vec![],
+        args: vec![],
+    });
+    assert_eq!(
+        result,
+        RespValue::err("ERR wrong number of arguments for 'lrange' command")
+    );
+}
+
+#[test]
+fn test_table_key_positions_match_parsed_keys() {
+    let cases: &[&[&str]] = &[
+        &["GET", "k"],
+        &["MSET", "a", "1", "b", "2"],
+        &["DEL", "a", "b", "c"],
+        &["RENAME", "src", "dst"],
+        &["COPY", "src", "dst", "REPLACE"],
+        &["LMOVE", "src", "dst", "LEFT", "RIGHT"],
+        &["HSET", "h", "f", "v"],
+        &["ZADD", "z", "1", "m"],
+    ];
+    for parts in cases {
+        let (cmd, _) = both_parsers(parts);
+        let spec = super::super::CommandSpec::lookup(parts[0]).unwrap();
+        let from_table: Vec<String> = spec
+            .key_positions(parts.len())
+            .into_iter()
+            .map(|i| parts[i].to_string())
+            .collect();
+        assert_eq!(from_table, cmd.unwrap().get_keys(), "{:?}", parts);
+    }
+}
+
+#[test]
+fn test_stream_parsing() {
+    use super::super::data::{StreamId, StreamIdSpec};
+
+    for (old, new) in [
+        both_parsers(&["XADD", "s", "NOMKSTREAM", "MAXLEN", "=", "10", "5-*", "f", "v"]),
+        both_parsers(&["xadd", "s", "nomkstream", "maxlen", "10", "5-*", "f", "v"]),
+    ] {
+        for cmd in [old.unwrap(), new.unwrap()] {
+            assert!(matches!(
+                cmd,
+                Command::XAdd {
+                    id: StreamIdSpec::AutoSeq(5),
+                    nomkstream: true,
+                    maxlen: Some(10),
+                    ref fields,
+                    ..
+                } if fields.len() == 1
+            ));
+        }
+    }
+
+    // XREVRANGE lists the upper bound first; both parse to start <= end
+    let (old, new) = both_parsers(&["XREVRANGE", "s", "(5-0", "3", "COUNT", "2"]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert!(matches!(
+            cmd,
+            Command::XRevRange { start, end, count: Some(2), .. }
+                if start == StreamId::new(3, 0) && end == StreamId::new(4, u64::MAX)
+        ));
+    }
+
+    let (old, new) = both_parsers(&["XREAD", "COUNT", "3", "STREAMS", "a", "b", "$", "7"]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert!(cmd.is_read_only());
+        assert_eq!(cmd.get_keys(), vec!["a".to_string(), "b".to_string()]);
+        assert!(matches!(
+            cmd,
+            Command::XRead { count: Some(3), ref ids, .. }
+                if ids == &vec![None, Some(StreamId::new(7, 0))]
+        ));
+    }
+
+    let cases: &[(&[&str], &str)] = &[
+        (&["XADD", "s", "*", "f"], "ERR wrong number of arguments for 'xadd' command"),
+        (&["XADD", "s", "1-x", "f", "v"], "ERR Invalid stream ID specified as stream command argument"),
+        (&["XRANGE", "s", "-", "+", "LIMIT", "1"], "ERR syntax error"),
+        (&["XREAD", "BLOCK", "-1", "STREAMS", "s", "$"], "ERR timeout is negative"),
+    ];
+    for (parts, expected) in cases {
+        let (old, new) = both_parsers(parts);
+        assert_eq!(old.unwrap_err(), *expected, "from_resp {:?}", parts);
+        assert_eq!(new.unwrap_err(), *expected, "from_resp_zero_copy {:?}", parts);
+    }
+}
+
+#[test]
+fn test_stream_group_parsing() {
+    use super::super::data::StreamId;
+
+    let (old, new) = both_parsers(&["XGROUP", "CREATE", "s", "g", "$", "MKSTREAM"]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert_eq!(cmd.name(), "XGROUP");
+        assert_eq!(cmd.get_primary_key(), Some("s"));
+        assert!(matches!(cmd, Command::XGroupCreate { id: None, mkstream: true, .. }));
+    }
+
+    let (old, new) = both_parsers(&[
+        "XREADGROUP", "GROUP", "g", "c", "COUNT", "2", "NOACK", "STREAMS", "a", "b", ">", "0",
+    ]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert!(!cmd.is_read_only());
+        assert_eq!(cmd.get_keys(), vec!["a".to_string(), "b".to_string()]);
+        assert!(matches!(
+            cmd,
+            Command::XReadGroup { count: Some(2), noack: true, ref ids, .. }
+                if ids == &vec![None, Some(StreamId::MIN)]
+        ));
+    }
+
+    let (old, new) = both_parsers(&[
+        "XCLAIM", "s", "g", "c", "10", "1-0", "2-0", "RETRYCOUNT", "5", "FORCE", "JUSTID",
+    ]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert!(matches!(
+            cmd,
+            Command::XClaim { min_idle_ms: 10, ref ids, opts, .. }
+                if ids.len() == 2 && opts.retry_count == Some(5) && opts.force && opts.justid
+        ));
+    }
+
+    let (old, new) = both_parsers(&["XPENDING", "s", "g", "IDLE", "5", "-", "+", "10", "c"]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert!(cmd.is_read_only());
+        assert!(matches!(
+            cmd,
+            Command::XPending { range: Some(ref r), .. }
+                if r.min_idle_ms == Some(5) && r.count == 10 && r.consumer.as_deref() == Some("c")
+        ));
+    }
+
+    let cases: &[(&[&str], &str)] = &[
+        (&["XGROUP", "NOPE", "s", "g"], "ERR unknown subcommand 'NOPE'. Try XGROUP HELP."),
+        (&["XREADGROUP", "GROUP", "g", "c", "STREAMS", "s"], "ERR wrong number of arguments for 'xreadgroup' command"),
+        (&["XACK", "s", "g", "bad"], "ERR Invalid stream ID specified as stream command argument"),
+        (&["XAUTOCLAIM", "s", "g", "c", "0", "0", "COUNT", "0"], "ERR COUNT must be > 0"),
+    ];
+    for (parts, expected) in cases {
+        let (old, new) = both_parsers(parts);
+        assert_eq!(old.unwrap_err(), *expected, "from_resp {:?}", parts);
+        assert_eq!(new.unwrap_err(), *expected, "from_resp_zero_copy {:?}", parts);
+    }
+}
+
+#[test]
+fn test_declared_commands_parse_everywhere() {
+    use super::super::DeclaredCommand;
+
+    let (old, new) = both_parsers(&["setnx", "k", "v"]);
+    for cmd in [old.unwrap(), new.unwrap()] {
+        assert_eq!(cmd.name(), "SETNX");
+        assert_eq!(cmd.get_primary_key(), Some("k"));
+        assert!(matches!(
+            cmd,
+            Command::Declared(DeclaredCommand::SetNx { ref value, .. }) if value.as_bytes() == b"v"
+        ));
+    }
+
+    let (old, new) = both_parsers(&["HKEYS"]);
+    assert_eq!(old.unwrap_err(), "ERR wrong number of arguments for 'hkeys' command");
+    assert_eq!(new.unwrap_err(), "ERR wrong number of arguments for 'hkeys' command");
+
+    // Scripts reach declared commands through the same generated parser
+    let mut executor = CommandExecutor::new();
+    let result = executor.execute(&Command::Eval {
+        script: "redis.call('SETNX', 'k', 'v'); return redis.call('SETNX', 'k', 'w')".to_string(),
+        keys: vec![],
+        args: vec![],
+    });
+    assert_eq!(result, RespValue::Integer(0));
+}
+
+#[test]
+fn test_client_admin_parsing() {
+    let parsed = |parts: &[&str]| {
+        let (old, new) = both_parsers(parts);
+        assert_eq!(format!("{:?}", old), format!("{:?}", new), "{:?}", parts);
+        old
+    };
+
+    assert!(matches!(
+        parsed(&["client", "kill", "10.0.0.1:6000"]),
+        Ok(Command::ClientKill { legacy: true, skip_me: false, addr: Some(ref a), .. })
+            if a == "10.0.0.1:6000"
+    ));
+    assert!(matches!(
+        parsed(&["CLIENT", "KILL", "ID", "7", "USER", "alice", "SKIPME", "no"]),
+        Ok(Command::ClientKill {
+            id: Some(7),
+            user: Some(ref u),
+            skip_me: false,
+            legacy: false,
+            ..
+        }) if u == "alice"
+    ));
+    assert!(matches!(
+        parsed(&["CLIENT", "PAUSE", "500", "write"]),
+        Ok(Command::ClientPause {
+            timeout_ms: 500,
+            writes_only: true
+        })
+    ));
+    assert!(matches!(
+        parsed(&["CLIENT", "PAUSE", "500"]),
+        Ok(Command::ClientPause {
+            writes_only: false,
+            ..
+        })
+    ));
+    assert!(matches!(
+        parsed(&["CLIENT", "LIST"]),
+        Ok(Command::ClientList)
+    ));
+    assert!(matches!(
+        parsed(&["CLIENT", "UNPAUSE"]),
+        Ok(Command::ClientUnpause)
+    ));
+    assert!(matches!(
+        parsed(&["client", "tracking", "on", "bcast", "prefix", "a:", "PREFIX", "b:"]),
+        Ok(Command::ClientTracking { on: true, bcast: true, ref prefixes })
+            if prefixes == &["a:", "b:"]
+    ));
+    assert!(matches!(
+        parsed(&["CLIENT", "TRACKING", "OFF"]),
+        Ok(Command::ClientTracking { on: false, bcast: false, .. })
+    ));
+
+    let cases: [(&[&str], &str); 10] = [
+        (
+            &["CLIENT", "KILL"],
+            "ERR wrong number of arguments for 'client|kill' command",
+        ),
+        (
+            &["CLIENT", "KILL", "ID", "0"],
+            "ERR client-id should be greater than 0",
+        ),
+        (&["CLIENT", "KILL", "ID", "1", "ADDR"], "ERR syntax error"),
+        (&["CLIENT", "KILL", "SKIPME", "maybe"], "ERR syntax error"),
+        (
+            &["CLIENT", "PAUSE", "-1"],
+            "ERR timeout is not an integer or out of range",
+        ),
+        (&["CLIENT", "PAUSE", "10", "READ"], "ERR syntax error"),
+        (
+            &["CLIENT", "UNPAUSE", "x"],
+            "ERR wrong number of arguments for 'client|unpause' command",
+        ),
+        (
+            &["CLIENT", "TRACKING"],
+            "ERR wrong number of arguments for 'client|tracking' command",
+        ),
+        (
+            &["CLIENT", "TRACKING", "ON", "PREFIX", "a:"],
+            "ERR PREFIX option requires BCAST mode to be enabled",
+        ),
+        (&["CLIENT", "TRACKING", "ON", "OPTIN"], "ERR syntax error"),
+    ];
+    for (parts, error) in cases {
+        assert_eq!(parsed(parts).unwrap_err(), error, "{:?}", parts);
+    }
+}
+
+#[test]
+fn test_debug_health_parsing() {
+    let (old, new) = both_parsers(&["debug", "health"]);
+    assert!(matches!(old, Ok(Command::DebugHealth)));
+    assert!(matches!(new, Ok(Command::DebugHealth)));
+    let (old, new) = both_parsers(&["DEBUG", "HEALTH", "x"]);
+    assert_eq!(old.unwrap_err(), new.unwrap_err());
+
+    // A bare executor has no components, so it is healthy
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        executor.execute(&Command::DebugHealth),
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(Some(b"status".to_vec())),
+            RespValue::BulkString(Some(b"ok".to_vec())),
+        ]))
+    );
+}
+
+#[test]
+fn test_reset_parsing() {
+    let (old, new) = both_parsers(&["reset"]);
+    assert!(matches!(old, Ok(Command::Reset)));
+    assert!(matches!(new, Ok(Command::Reset)));
+    let (old, new) = both_parsers(&["RESET", "x"]);
+    assert_eq!(old.unwrap_err(), "ERR wrong number of arguments for 'reset' command");
+    assert_eq!(new.unwrap_err(), "ERR wrong number of arguments for 'reset' command");
+}
+
+#[test]
+fn test_shutdown_parsing() {
+    for (parts, expected) in [
+        (&["shutdown"][..], None),
+        (&["SHUTDOWN", "nosave", "NOW"], Some(false)),
+        (&["SHUTDOWN", "FORCE", "save"], Some(true)),
+    ] {
+        let (old, new) = both_parsers(parts);
+        assert!(matches!(old, Ok(Command::Shutdown { save }) if save == expected));
+        assert!(matches!(new, Ok(Command::Shutdown { save }) if save == expected));
+    }
+    for parts in [&["SHUTDOWN", "SAVE", "NOSAVE"][..], &["SHUTDOWN", "ABORT"]] {
+        let (old, new) = both_parsers(parts);
+        assert_eq!(old.unwrap_err(), "ERR syntax error");
+        assert_eq!(new.unwrap_err(), "ERR syntax error");
+    }
+}
+
+#[test]
+fn test_hello_parsing() {
+    let (old, new) = both_parsers(&["hello"]);
+    for parsed in [old, new] {
+        assert!(matches!(
+            parsed,
+            Ok(Command::Hello { protover: None, auth: None, setname: None })
+        ));
+    }
+    let (old, new) = both_parsers(&["HELLO", "3", "setname", "w", "AUTH", "u", "p"]);
+    for parsed in [old, new] {
+        let Ok(Command::Hello { protover, auth, setname }) = parsed else {
+            panic!("expected HELLO");
+        };
+        assert_eq!(protover, Some(3));
+        assert_eq!(auth, Some(("u".to_string(), "p".to_string())));
+        assert_eq!(setname.as_deref(), Some("w"));
+    }
+    let (old, new) = both_parsers(&["HELLO", "3", "AUTH", "u"]);
+    assert_eq!(old.unwrap_err(), "ERR Syntax error in HELLO option 'AUTH'");
+    assert_eq!(new.unwrap_err(), "ERR Syntax error in HELLO option 'AUTH'");
+    let (old, new) = both_parsers(&["HELLO", "three"]);
+    assert!(old.is_err() && new.is_err());
+}
+
+#[test]
+fn test_function_parsing() {
+    let (old, new) = both_parsers(&["FCALL_RO", "f", "1", "k", "a"]);
+    for parsed in [old, new] {
+        let Ok(Command::FCallRo { function, keys, args }) = parsed else {
+            panic!("expected FCALL_RO");
+        };
+        assert_eq!(function, "f");
+        assert_eq!(keys, vec!["k".to_string()]);
+        assert_eq!(args, vec![SDS::from_str("a")]);
+    }
+    let (old, new) = both_parsers(&["FUNCTION", "load", "replace", "#!lua name=l"]);
+    for parsed in [old, new] {
+        assert!(matches!(parsed, Ok(Command::FunctionLoad { replace: true, .. })));
+    }
+    let (old, new) = both_parsers(&["FUNCTION", "LIST", "WITHCODE", "LIBRARYNAME", "l*"]);
+    for parsed in [old, new] {
+        let Ok(Command::FunctionList { pattern, with_code }) = parsed else {
+            panic!("expected FUNCTION LIST");
+        };
+        assert_eq!(pattern.as_deref(), Some("l*"));
+        assert!(with_code);
+    }
+    let (old, new) = both_parsers(&["FCALL", "f", "2", "k"]);
+    assert_eq!(old.unwrap_err(), "ERR Number of keys can't be greater than number of args");
+    assert_eq!(new.unwrap_err(), "ERR Number of keys can't be greater than number of args");
+    let (old, new) = both_parsers(&["FUNCTION", "RESTORE", "payload", "MERGE"]);
+    assert!(old.is_err() && new.is_err());
+}
@@ -0,0 +1,151 @@
+//! CONFIG GET patterns, aliases and defaults, checked against Redis 7 replies
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+/// CONFIG GET's reply as sorted (name, value) pairs; Redis does not order it
+fn config_get(executor: &mut CommandExecutor, patterns: &[&str]) -> Vec<(String, String)> {
+    let mut parts = vec!["CONFIG", "GET"];
+    parts.extend_from_slice(patterns);
+    let RespValue::Array(Some(items)) = run(executor, &parts) else {
+        panic!("CONFIG GET {:?} did not reply an array", patterns);
+    };
+    let text = |item: &RespValue| match item {
+        RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes.clone()).unwrap(),
+        other => panic!("CONFIG GET replied {:?}", other),
+    };
+    let mut pairs: Vec<(String, String)> = items
+        .chunks(2)
+        .map(|pair| (text(&pair[0]), text(&pair[1])))
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = expected
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+#[test]
+fn test_config_get_takes_several_patterns() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        config_get(&mut executor, &["maxmemory", "hz"]),
+        pairs(&[("maxmemory", "0"), ("hz", "10")])
+    );
+    // A parameter matched by several patterns is replied once
+    assert_eq!(
+        config_get(&mut executor, &["maxmemory*", "maxmemory", "max*y"]),
+        pairs(&[("maxmemory", "0"), ("maxmemory-policy", "noeviction")])
+    );
+    assert_eq!(config_get(&mut executor, &["no-such-param"]), pairs(&[]));
+    assert_eq!(
+        run(&mut executor, &["CONFIG", "GET"]),
+        RespValue::err("ERR wrong number of arguments for 'config|get' command")
+    );
+}
+
+#[test]
+fn test_config_get_names_and_case() {
+    let mut executor = CommandExecutor::new();
+    // A lookup replies the name as given; a glob replies the real names
+    assert_eq!(
+        config_get(&mut executor, &["MAXMEMORY"]),
+        pairs(&[("MAXMEMORY", "0")])
+    );
+    assert_eq!(
+        config_get(&mut executor, &["MAXMEMORY-P*"]),
+        pairs(&[("maxmemory-policy", "noeviction")])
+    );
+}
+
+#[test]
+fn test_config_aliases_read_and_write_their_parameter() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        config_get(&mut executor, &["hash-max-ziplist-entries"]),
+        pairs(&[("hash-max-ziplist-entries", "128")])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["CONFIG", "SET", "hash-max-ziplist-entries", "256"]
+        ),
+        RespValue::ok()
+    );
+    assert_eq!(
+        config_get(&mut executor, &["hash-max-listpack-entries"]),
+        pairs(&[("hash-max-listpack-entries", "256")])
+    );
+
+    // Globs match the old names as well as the new ones
+    assert_eq!(
+        config_get(&mut executor, &["*max-*-entries*"]),
+        pairs(&[
+            ("hash-max-listpack-entries", "256"),
+            ("hash-max-ziplist-entries", "256"),
+            ("set-max-intset-entries", "512"),
+            ("set-max-listpack-entries", "128"),
+            ("zset-max-listpack-entries", "128"),
+            ("zset-max-ziplist-entries", "128"),
+        ])
+    );
+    assert_eq!(
+        config_get(&mut executor, &["slave-*"]),
+        pairs(&[
+            ("slave-lazy-flush", "no"),
+            ("slave-priority", "100"),
+            ("slave-read-only", "yes"),
+            ("slave-serve-stale-data", "yes"),
+        ])
+    );
+}
+
+#[test]
+fn test_config_set_params_without_defaults() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["CONFIG", "SET", "Custom-Param", "v"]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        config_get(&mut executor, &["custom-*"]),
+        pairs(&[("custom-param", "v")])
+    );
+    // Changing one parameter leaves the others at their defaults
+    assert_eq!(
+        config_get(&mut executor, &["replica-priority"]),
+        pairs(&[("replica-priority", "100")])
+    );
+}
+
+#[test]
+fn test_config_set_loglevel_with_subsystem_overrides() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(
+            &mut executor,
+            &["CONFIG", "SET", "loglevel", "WARNING, replication=debug"]
+        ),
+        RespValue::ok()
+    );
+    assert_eq!(
+        config_get(&mut executor, &["loglevel"]),
+        pairs(&[("loglevel", "warning,replication=debug")])
+    );
+    assert_eq!(
+        run(&mut executor, &["CONFIG", "SET", "loglevel", "chatty"]),
+        RespValue::err(
+            "ERR CONFIG SET failed (possibly related to argument 'loglevel') - invalid log level 'chatty'"
+        )
+    );
+    assert_eq!(
+        config_get(&mut executor, &["loglevel"]),
+        pairs(&[("loglevel", "warning,replication=debug")])
+    );
+}
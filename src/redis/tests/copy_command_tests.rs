@@ -0,0 +1,112 @@
+//! COPY - deep copies across value types, REPLACE and TTL preservation
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+#[test]
+fn test_copy_every_type_is_independent() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "s", "v"]);
+    run(&mut executor, &["RPUSH", "l", "a", "b"]);
+    run(&mut executor, &["SADD", "set", "x"]);
+    run(&mut executor, &["HSET", "h", "f", "1"]);
+    run(&mut executor, &["ZADD", "z", "1", "m"]);
+    run(&mut executor, &["XADD", "st", "1-1", "f", "v"]);
+    for key in ["s", "l", "set", "h", "z", "st"] {
+        let copy = format!("{}-copy", key);
+        assert_eq!(
+            run(&mut executor, &["COPY", key, &copy]),
+            RespValue::Integer(1),
+            "{}",
+            key
+        );
+        assert_eq!(
+            run(&mut executor, &["TYPE", key]),
+            run(&mut executor, &["TYPE", &copy])
+        );
+    }
+
+    // Writes to the copy never reach the source
+    run(&mut executor, &["RPUSH", "l-copy", "c"]);
+    run(&mut executor, &["HSET", "h-copy", "f", "2"]);
+    assert_eq!(run(&mut executor, &["LLEN", "l"]), RespValue::Integer(2));
+    assert_eq!(run(&mut executor, &["HGET", "h", "f"]), bulk("1"));
+    assert_eq!(executor.keyspace_stats().total_keys(), 12);
+}
+
+#[test]
+fn test_copy_refuses_existing_destination_without_replace() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "a", "1"]);
+    run(&mut executor, &["SET", "b", "2"]);
+
+    assert_eq!(
+        run(&mut executor, &["COPY", "a", "b"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(run(&mut executor, &["GET", "b"]), bulk("2"));
+    assert_eq!(
+        run(&mut executor, &["COPY", "a", "b", "replace"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(run(&mut executor, &["GET", "b"]), bulk("1"));
+    assert_eq!(
+        run(&mut executor, &["COPY", "missing", "b", "REPLACE"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(run(&mut executor, &["GET", "b"]), bulk("1"));
+}
+
+#[test]
+fn test_copy_carries_the_source_ttl() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "ttl", "v", "PX", "500"]);
+    run(&mut executor, &["SET", "dst", "old", "EX", "100"]);
+    run(&mut executor, &["SET", "plain", "p"]);
+
+    run(&mut executor, &["COPY", "ttl", "copy"]);
+    assert_eq!(
+        run(&mut executor, &["PTTL", "copy"]),
+        RespValue::Integer(500)
+    );
+
+    // Replacing a volatile key with a persistent one drops its deadline
+    run(&mut executor, &["COPY", "plain", "dst", "REPLACE"]);
+    assert_eq!(run(&mut executor, &["TTL", "dst"]), RespValue::Integer(-1));
+
+    executor.set_time(VirtualTime::from_millis(600));
+    assert_eq!(
+        run(&mut executor, &["EXISTS", "ttl", "copy"]),
+        RespValue::Integer(0)
+    );
+    // An expired destination counts as absent
+    run(&mut executor, &["SET", "gone", "x", "PX", "10"]);
+    executor.set_time(VirtualTime::from_millis(700));
+    assert_eq!(
+        run(&mut executor, &["COPY", "plain", "gone"]),
+        RespValue::Integer(1)
+    );
+}
+
+#[test]
+fn test_copy_errors() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "a", "1"]);
+    assert_eq!(
+        run(&mut executor, &["COPY", "a", "a"]),
+        RespValue::err("ERR source and destination objects are the same")
+    );
+    assert_eq!(
+        run(&mut executor, &["COPY", "a", "b", "NOW"]),
+        RespValue::err("ERR syntax error")
+    );
+    assert_eq!(
+        run(&mut executor, &["COPY", "a"]),
+        RespValue::err("ERR wrong number of arguments for 'copy' command")
+    );
+}
@@ -0,0 +1,99 @@
+//! DEBUG BUGGIFY tests - runtime fault probability control and stats
+
+use super::super::{Command, CommandExecutor, RespValue};
+
+#[cfg(feature = "simulation")]
+mod simulation {
+    use super::*;
+    use crate::buggify::{self, faults, FaultConfig};
+    use crate::io::simulation::SimulatedRng;
+
+    fn stats(executor: &mut CommandExecutor) -> String {
+        match executor.execute(&Command::DebugBuggifyStats) {
+            RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).expect("utf8 stats"),
+            other => panic!("DEBUG BUGGIFY STATS returned {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_buggify_set_arms_fault() {
+        buggify::set_config(FaultConfig::new());
+        buggify::reset_stats();
+        let mut executor = CommandExecutor::new();
+
+        let resp = executor.execute(&Command::DebugBuggifySet {
+            fault: faults::network::PACKET_DROP.to_string(),
+            probability: 1.0,
+        });
+        assert_eq!(resp, RespValue::ok());
+        assert_eq!(
+            buggify::fault_probability(faults::network::PACKET_DROP),
+            1.0
+        );
+
+        let mut rng = SimulatedRng::new(42);
+        assert!(buggify::should_buggify(
+            &mut rng,
+            faults::network::PACKET_DROP
+        ));
+
+        let report = stats(&mut executor);
+        assert!(report.contains("network.packet_drop:probability=1.000000,checks=1,triggers=1\r\n"));
+        assert!(!report.contains("network.reorder"));
+
+        // Disarming keeps the line while the fault has recorded checks
+        executor.execute(&Command::DebugBuggifySet {
+            fault: faults::network::PACKET_DROP.to_string(),
+            probability: 0.0,
+        });
+        assert!(!buggify::should_buggify(
+            &mut rng,
+            faults::network::PACKET_DROP
+        ));
+        let report = stats(&mut executor);
+        assert!(report.contains("network.packet_drop:probability=0.000000,checks=2,triggers=1\r\n"));
+    }
+
+    #[test]
+    fn test_buggify_set_rejects_bad_input() {
+        buggify::set_config(FaultConfig::new());
+        let mut executor = CommandExecutor::new();
+
+        let resp = executor.execute(&Command::DebugBuggifySet {
+            fault: "network.no_such_fault".to_string(),
+            probability: 0.5,
+        });
+        assert_eq!(
+            resp,
+            RespValue::err("ERR unknown fault 'network.no_such_fault'")
+        );
+
+        let resp = executor.execute(&Command::DebugBuggifySet {
+            fault: faults::network::PACKET_DROP.to_string(),
+            probability: 1.5,
+        });
+        assert_eq!(
+            resp,
+            RespValue::err("ERR probability must be between 0 and 1")
+        );
+        assert_eq!(
+            buggify::fault_probability(faults::network::PACKET_DROP),
+            0.0
+        );
+    }
+}
+
+#[cfg(not(feature = "simulation"))]
+#[test]
+fn test_buggify_requires_simulation_build() {
+    let mut executor = CommandExecutor::new();
+    let expected = RespValue::err("ERR DEBUG BUGGIFY is only available in simulation builds");
+    assert_eq!(executor.execute(&Command::DebugBuggifyStats), expected);
+    assert_eq!(
+        executor.execute(&Command::DebugBuggifySet {
+            fault: "network.packet_drop".to_string(),
+            probability: 0.5,
+        }),
+        expected
+    );
+}
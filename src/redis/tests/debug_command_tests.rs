@@ -0,0 +1,199 @@
+//! DEBUG SLEEP, SET-ACTIVE-EXPIRE, OBJECT, RELOAD and PROTOCOL
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn debug_object(executor: &mut CommandExecutor, key: &str) -> String {
+    match run(executor, &["DEBUG", "OBJECT", key]) {
+        RespValue::BulkString(Some(report)) => String::from_utf8(report).unwrap(),
+        other => panic!("expected a report for {}, got {:?}", key, other),
+    }
+}
+
+#[test]
+fn test_sleep_moves_the_virtual_clock() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "v", "PX", "1500"]);
+
+    assert_eq!(
+        run(&mut executor, &["DEBUG", "SLEEP", "1"]),
+        RespValue::ok()
+    );
+    assert_eq!(executor.get_current_time(), VirtualTime::from_millis(1000));
+    assert_eq!(run(&mut executor, &["PTTL", "k"]), RespValue::Integer(500));
+
+    assert_eq!(
+        run(&mut executor, &["debug", "sleep", "0.5"]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["GET", "k"]),
+        RespValue::BulkString(None)
+    );
+
+    // Zero is allowed and changes nothing
+    run(&mut executor, &["DEBUG", "SLEEP", "0"]);
+    assert_eq!(executor.get_current_time(), VirtualTime::from_millis(1500));
+}
+
+#[test]
+fn test_active_expire_off_leaves_only_lazy_expiry() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "v", "PX", "100"]);
+    assert_eq!(
+        run(&mut executor, &["DEBUG", "SET-ACTIVE-EXPIRE", "0"]),
+        RespValue::ok()
+    );
+
+    // Neither the clock nor the TTL manager removes the key...
+    executor.set_time(VirtualTime::from_millis(200));
+    assert_eq!(
+        executor.evict_expired_direct(VirtualTime::from_millis(300)),
+        0
+    );
+    assert!(executor.get_data().contains_key("k"));
+
+    // ...but an access still sees it expired
+    assert_eq!(
+        run(&mut executor, &["GET", "k"]),
+        RespValue::BulkString(None)
+    );
+    assert!(!executor.get_data().contains_key("k"));
+
+    run(&mut executor, &["DEBUG", "SET-ACTIVE-EXPIRE", "1"]);
+    run(&mut executor, &["SET", "k", "v", "PX", "100"]);
+    executor.set_time(VirtualTime::from_millis(400));
+    assert!(!executor.get_data().contains_key("k"));
+}
+
+#[test]
+fn test_object_reports_encoding_length_and_idle_time() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "n", "12345"]);
+    run(&mut executor, &["SET", "s", "hello"]);
+    run(&mut executor, &["SET", "big", &"x".repeat(100)]);
+    run(&mut executor, &["RPUSH", "l", "a", "b", "c"]);
+
+    assert!(debug_object(&mut executor, "n").contains("encoding:int"));
+    // One length byte and the payload
+    assert!(
+        debug_object(&mut executor, "s").contains("encoding:embstr serializedlength:6 "),
+        "{}",
+        debug_object(&mut executor, "s")
+    );
+    assert!(debug_object(&mut executor, "big").contains("encoding:raw"));
+    assert!(debug_object(&mut executor, "l").contains("encoding:listpack"));
+
+    // DEBUG OBJECT is not an access
+    executor.set_time(VirtualTime::from_millis(5_000));
+    assert!(debug_object(&mut executor, "s").ends_with("lru_seconds_idle:5"));
+    assert!(debug_object(&mut executor, "s").ends_with("lru_seconds_idle:5"));
+
+    assert_eq!(
+        run(&mut executor, &["DEBUG", "OBJECT", "missing"]),
+        RespValue::err("ERR no such key")
+    );
+}
+
+#[test]
+fn test_reload_round_trips_the_keyspace() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "s", "hello"]);
+    run(&mut executor, &["SET", "t", "ttl", "PX", "5000"]);
+    run(&mut executor, &["RPUSH", "l", "a", "b", "c"]);
+    run(&mut executor, &["HSET", "h", "f", "v", "g", "w"]);
+    run(&mut executor, &["SADD", "set", "x", "y"]);
+    run(&mut executor, &["ZADD", "z", "1", "a", "2.5", "b"]);
+    executor.set_time(VirtualTime::from_millis(1_000));
+
+    let reads: [&[&str]; 6] = [
+        &["GET", "s"],
+        &["PTTL", "t"],
+        &["LRANGE", "l", "0", "-1"],
+        &["HGET", "h", "g"],
+        &["SCARD", "set"],
+        &["ZRANGE", "z", "0", "-1", "WITHSCORES"],
+    ];
+    let before: Vec<RespValue> = reads.iter().map(|r| run(&mut executor, r)).collect();
+
+    assert_eq!(run(&mut executor, &["DEBUG", "RELOAD"]), RespValue::ok());
+    let after: Vec<RespValue> = reads.iter().map(|r| run(&mut executor, r)).collect();
+    assert_eq!(before, after);
+    assert_eq!(run(&mut executor, &["DBSIZE"]), RespValue::Integer(6));
+    assert_eq!(after[1], RespValue::Integer(4_000));
+}
+
+#[test]
+fn test_reload_drops_keys_past_their_deadline() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "stale", "v", "PX", "100"]);
+    run(&mut executor, &["SET", "kept", "v"]);
+    run(&mut executor, &["DEBUG", "SET-ACTIVE-EXPIRE", "0"]);
+    executor.set_time(VirtualTime::from_millis(200));
+    assert_eq!(executor.get_data().len(), 2);
+
+    run(&mut executor, &["DEBUG", "RELOAD"]);
+    assert!(!executor.get_data().contains_key("stale"));
+    assert!(executor.get_data().contains_key("kept"));
+}
+
+#[test]
+fn test_protocol_samples_in_resp2_form() {
+    let mut executor = CommandExecutor::new();
+    let int = RespValue::Integer;
+    assert_eq!(
+        run(&mut executor, &["DEBUG", "PROTOCOL", "double"]),
+        RespValue::BulkString(Some(b"3.141".to_vec()))
+    );
+    assert_eq!(
+        run(&mut executor, &["DEBUG", "PROTOCOL", "NULL"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(run(&mut executor, &["DEBUG", "PROTOCOL", "true"]), int(1));
+    assert_eq!(
+        run(&mut executor, &["DEBUG", "PROTOCOL", "map"]),
+        RespValue::Array(Some(vec![int(0), int(0), int(1), int(1), int(2), int(0)]))
+    );
+}
+
+#[test]
+fn test_argument_errors() {
+    let mut executor = CommandExecutor::new();
+    let cases: [(&[&str], &str); 7] = [
+        (
+            &["DEBUG", "SLEEP", "-1"],
+            "ERR sleep time must be a non-negative number",
+        ),
+        (
+            &["DEBUG", "SLEEP", "soon"],
+            "ERR value is not a valid float",
+        ),
+        (
+            &["DEBUG", "SET-ACTIVE-EXPIRE"],
+            "ERR wrong number of arguments for 'debug|set-active-expire'",
+        ),
+        (
+            &["DEBUG", "SET-ACTIVE-EXPIRE", "yes"],
+            "ERR value is not an integer",
+        ),
+        (
+            &["DEBUG", "RELOAD", "NOSAVE"],
+            "ERR DEBUG RELOAD option 'NOSAVE' is not supported",
+        ),
+        (
+            &["DEBUG", "PROTOCOL"],
+            "ERR wrong number of arguments for 'debug|protocol'",
+        ),
+        (
+            &["DEBUG", "PROTOCOL", "bignum"],
+            "ERR Wrong protocol type name",
+        ),
+    ];
+    for (parts, expected) in cases {
+        match run(&mut executor, parts) {
+            RespValue::Error(e) => assert!(e.starts_with(expected), "{:?}: {}", parts, e),
+            other => panic!("{:?}: expected an error, got {:?}", parts, other),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Direct fast paths - get_direct, incr_direct, mget_direct, hget_direct,
+//! exists_direct must match `execute()` reply for reply and state for state
+
+use super::super::{Command, CommandExecutor, RespValue, SDS};
+use crate::simulator::{DeterministicRng, VirtualTime};
+
+const KEYS: [&str; 4] = ["a", "b", "c", "d"];
+
+/// Run `cmd` on `dispatched` through `execute()` and on `direct` through
+/// the matching direct path, and compare the replies
+fn both(dispatched: &mut CommandExecutor, direct: &mut CommandExecutor, cmd: &Command) {
+    let expected = dispatched.execute(cmd);
+    let got = match cmd {
+        Command::Get(key) => direct.get_direct(key),
+        Command::Incr(key) => direct.incr_direct(key),
+        Command::MGet(keys) => {
+            direct.mget_direct(&keys.iter().map(String::as_str).collect::<Vec<_>>())
+        }
+        Command::HGet(key, field) => direct.hget_direct(key, &field.to_string()),
+        Command::Exists(keys) => {
+            direct.exists_direct(&keys.iter().map(String::as_str).collect::<Vec<_>>())
+        }
+        other => direct.execute(other),
+    };
+    assert_eq!(got, expected, "{:?}", cmd);
+}
+
+fn random_keys(rng: &mut DeterministicRng) -> Vec<String> {
+    (0..rng.gen_range(1, 4))
+        .map(|_| KEYS[rng.gen_range(0, 4) as usize].to_string())
+        .collect()
+}
+
+#[test]
+fn test_direct_paths_match_dispatch() {
+    for seed in 0..20 {
+        let mut rng = DeterministicRng::new(seed);
+        let mut dispatched = CommandExecutor::new();
+        let mut direct = CommandExecutor::new();
+        let mut now = 0;
+        for _ in 0..300 {
+            let key = KEYS[rng.gen_range(0, 4) as usize].to_string();
+            let cmd = match rng.gen_range(0, 11) {
+                0 => Command::set(key, SDS::from_str(&rng.gen_range(0, 50).to_string())),
+                1 => Command::set(key, SDS::from_str("text")),
+                2 => Command::HSet(key, vec![(SDS::from_str("f"), SDS::from_str("v"))]),
+                3 => Command::LPush(key, vec![SDS::from_str("x")]),
+                4 => Command::PExpire {
+                    key,
+                    milliseconds: rng.gen_range(1, 20) as i64,
+                    nx: false,
+                    xx: false,
+                    gt: false,
+                    lt: false,
+                },
+                5 | 6 => Command::Incr(key),
+                7 => Command::Get(key),
+                8 => Command::MGet(random_keys(&mut rng)),
+                9 => Command::HGet(key, SDS::from_str(["f", "g"][rng.gen_range(0, 2) as usize])),
+                _ => Command::Exists(random_keys(&mut rng)),
+            };
+            both(&mut dispatched, &mut direct, &cmd);
+
+            now += rng.gen_range(0, 5);
+            dispatched.set_time(VirtualTime::from_millis(now));
+            direct.set_time(VirtualTime::from_millis(now));
+        }
+        assert_eq!(direct.get_data(), dispatched.get_data(), "seed {}", seed);
+        assert_eq!(
+            direct.keyspace_stats(),
+            dispatched.keyspace_stats(),
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn test_incr_direct_respects_maxmemory() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::set("big".to_string(), SDS::new(vec![b'x'; 4096])));
+    for (param, value) in [("maxmemory", "100"), ("maxmemory-policy", "noeviction")] {
+        executor.execute(&Command::ConfigSet(param.to_string(), value.to_string()));
+    }
+    assert_eq!(
+        executor.incr_direct("n"),
+        RespValue::err("OOM command not allowed when used memory > 'maxmemory'.")
+    );
+    assert_eq!(executor.get_direct("n"), RespValue::BulkString(None));
+}
+
+#[test]
+fn test_direct_paths_queue_inside_multi() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::Multi);
+    assert_eq!(executor.incr_direct("n"), RespValue::simple("QUEUED"));
+    assert_eq!(executor.mget_direct(&["n"]), RespValue::simple("QUEUED"));
+    assert_eq!(
+        executor.execute(&Command::Exec),
+        RespValue::Array(Some(vec![
+            RespValue::Integer(1),
+            RespValue::Array(Some(vec![RespValue::BulkString(Some(b"1".to_vec()))])),
+        ]))
+    );
+}
@@ -0,0 +1,319 @@
+//! DUMP and RESTORE - round trips of every type, TTL options, BUSYKEY, and
+//! payload checks
+
+use super::super::{rdb, CommandExecutor, RespValue};
+use super::run;
+use super::run_bytes;
+use crate::simulator::VirtualTime;
+
+fn dump(executor: &mut CommandExecutor, key: &str) -> Vec<u8> {
+    match run(executor, &["DUMP", key]) {
+        RespValue::BulkString(Some(payload)) => payload,
+        other => panic!("DUMP {} returned {:?}", key, other),
+    }
+}
+
+fn restore(
+    executor: &mut CommandExecutor,
+    key: &str,
+    ttl: &str,
+    payload: &[u8],
+    opts: &[&str],
+) -> RespValue {
+    let mut parts: Vec<&[u8]> = vec![
+        b"RESTORE".as_slice(),
+        key.as_bytes(),
+        ttl.as_bytes(),
+        payload,
+    ];
+    parts.extend(opts.iter().map(|o| o.as_bytes()));
+    run_bytes(executor, &parts)
+}
+
+fn err_text(resp: RespValue) -> String {
+    match resp {
+        RespValue::Error(e) => e.into_owned(),
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_dump_restore_round_trips_every_type() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "str", "hello"]);
+    run(&mut executor, &["SET", "num", "-12345"]);
+    let items: Vec<String> = (0..300).map(|i| format!("item{}", i % 7)).collect();
+    let mut rpush = vec!["RPUSH", "list"];
+    rpush.extend(items.iter().map(String::as_str));
+    run(&mut executor, &rpush);
+    run(&mut executor, &["SADD", "set", "a", "b", "7", "c"]);
+    run(&mut executor, &["HSET", "hash", "f1", "v1", "f2", "2"]);
+    run(
+        &mut executor,
+        &["ZADD", "zset", "1.5", "a", "-inf", "b", "3", "c"],
+    );
+
+    let keys = ["str", "num", "list", "set", "hash", "zset"];
+    let mut copy = CommandExecutor::new();
+    for key in keys {
+        let payload = dump(&mut executor, key);
+        assert_eq!(restore(&mut copy, key, "0", &payload, &[]), RespValue::ok());
+        assert_eq!(
+            dump(&mut copy, key),
+            payload,
+            "{} must dump identically",
+            key
+        );
+    }
+    assert_eq!(
+        run(&mut copy, &["LRANGE", "list", "0", "-1"]),
+        run(&mut executor, &["LRANGE", "list", "0", "-1"])
+    );
+    assert_eq!(
+        run(&mut copy, &["ZRANGE", "zset", "0", "-1", "WITHSCORES"]),
+        run(&mut executor, &["ZRANGE", "zset", "0", "-1", "WITHSCORES"])
+    );
+    assert_eq!(run(&mut copy, &["SCARD", "set"]), RespValue::Integer(4));
+    assert_eq!(
+        run(&mut copy, &["HGET", "hash", "f2"]),
+        RespValue::BulkString(Some(b"2".to_vec()))
+    );
+    assert_eq!(
+        run(&mut copy, &["DUMP", "missing"]),
+        RespValue::BulkString(None)
+    );
+}
+
+#[test]
+fn test_dump_restore_stream_with_groups() {
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(1_700_000_000_000);
+    for i in 1..=250 {
+        let id = format!("{}-{}", i / 3 + 1, i % 3);
+        let field = if i % 10 == 0 { "other" } else { "f" };
+        run(&mut executor, &["XADD", "s", &id, field, &i.to_string()]);
+    }
+    run(&mut executor, &["XGROUP", "CREATE", "s", "g", "0"]);
+    run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "alice",
+            "COUNT",
+            "3",
+            "STREAMS",
+            "s",
+            ">",
+        ],
+    );
+    run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "bob",
+            "COUNT",
+            "2",
+            "STREAMS",
+            "s",
+            ">",
+        ],
+    );
+    run(&mut executor, &["XACK", "s", "g", "1-1"]);
+    run(
+        &mut executor,
+        &["XGROUP", "CREATECONSUMER", "s", "g", "idle"],
+    );
+    run(&mut executor, &["XGROUP", "CREATE", "s", "late", "$"]);
+    run(
+        &mut executor,
+        &["XADD", "s", "MAXLEN", "200", "300-0", "f", "last"],
+    );
+
+    let payload = dump(&mut executor, "s");
+    let mut copy = CommandExecutor::new();
+    copy.set_simulation_start_epoch_ms(1_700_000_000_000);
+    assert_eq!(restore(&mut copy, "s", "0", &payload, &[]), RespValue::ok());
+
+    for cmd in [
+        &["XRANGE", "s", "-", "+"][..],
+        &["XLEN", "s"],
+        &["XPENDING", "s", "g"],
+        &["XPENDING", "s", "g", "-", "+", "10"],
+    ] {
+        assert_eq!(run(&mut copy, cmd), run(&mut executor, cmd), "{:?}", cmd);
+    }
+    // The ID generator survives, so a trimmed or acked ID is never reused
+    assert!(matches!(
+        run(&mut copy, &["XADD", "s", "1-1", "f", "x"]),
+        RespValue::Error(_)
+    ));
+    assert_eq!(dump(&mut copy, "s"), payload);
+}
+
+#[test]
+fn test_dump_restore_hash_field_ttls() {
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(1_700_000_000_000);
+    executor.set_time(VirtualTime::from_millis(1_000));
+    run(
+        &mut executor,
+        &["HSET", "h", "keep", "1", "short", "2", "long", "3"],
+    );
+    run(
+        &mut executor,
+        &["HPEXPIRE", "h", "500", "FIELDS", "1", "short"],
+    );
+    run(
+        &mut executor,
+        &["HPEXPIRE", "h", "90000", "FIELDS", "1", "long"],
+    );
+    let payload = dump(&mut executor, "h");
+
+    // Another server whose clock started elsewhere sees the same Unix deadlines
+    let mut copy = CommandExecutor::new();
+    copy.set_simulation_start_epoch_ms(1_700_000_000_500);
+    copy.set_time(VirtualTime::from_millis(0));
+    assert_eq!(restore(&mut copy, "h", "0", &payload, &[]), RespValue::ok());
+    assert_eq!(
+        run(
+            &mut copy,
+            &["HPTTL", "h", "FIELDS", "3", "keep", "short", "long"]
+        ),
+        RespValue::Array(Some(vec![
+            RespValue::Integer(-1),
+            RespValue::Integer(1_000),
+            RespValue::Integer(90_500),
+        ]))
+    );
+
+    // Restored after every field deadline: nothing is left to hold
+    run(
+        &mut executor,
+        &["HPEXPIRE", "h", "100", "FIELDS", "1", "keep"],
+    );
+    run(&mut executor, &["HDEL", "h", "long"]);
+    let payload = dump(&mut executor, "h");
+    copy.set_time(VirtualTime::from_millis(10_000));
+    assert_eq!(
+        restore(&mut copy, "h", "0", &payload, &["REPLACE"]),
+        RespValue::ok()
+    );
+    assert_eq!(run(&mut copy, &["EXISTS", "h"]), RespValue::Integer(0));
+}
+
+#[test]
+fn test_restore_ttl_replace_and_busykey() {
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(1_000_000);
+    executor.set_time(VirtualTime::from_millis(2_000));
+    run(&mut executor, &["SET", "k", "v"]);
+    let payload = dump(&mut executor, "k");
+
+    assert_eq!(
+        err_text(restore(&mut executor, "k", "0", &payload, &[])),
+        "BUSYKEY Target key name already exists."
+    );
+    assert_eq!(
+        restore(&mut executor, "k", "5000", &payload, &["REPLACE"]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["PTTL", "k"]),
+        RespValue::Integer(5_000)
+    );
+
+    // ABSTTL is a Unix deadline (now is 1_002_000); one already past
+    // restores nothing
+    assert_eq!(
+        restore(&mut executor, "abs", "1010000", &payload, &["ABSTTL"]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["PTTL", "abs"]),
+        RespValue::Integer(8_000)
+    );
+    assert_eq!(
+        restore(&mut executor, "past", "1001000", &payload, &["ABSTTL"]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["EXISTS", "past"]),
+        RespValue::Integer(0)
+    );
+
+    // REPLACE without a TTL clears the old deadline
+    assert_eq!(
+        restore(
+            &mut executor,
+            "k",
+            "0",
+            &payload,
+            &["REPLACE", "IDLETIME", "10"]
+        ),
+        RespValue::ok()
+    );
+    assert_eq!(run(&mut executor, &["PTTL", "k"]), RespValue::Integer(-1));
+}
+
+#[test]
+fn test_restore_rejects_bad_arguments_and_payloads() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "v"]);
+    let payload = dump(&mut executor, "k");
+
+    assert_eq!(
+        err_text(restore(&mut executor, "x", "-1", &payload, &[])),
+        "ERR Invalid TTL value, must be >= 0"
+    );
+    assert_eq!(
+        err_text(restore(&mut executor, "x", "0", &payload, &["FREQ", "256"])),
+        "ERR Invalid FREQ value, must be >= 0 and <= 255"
+    );
+    assert_eq!(
+        err_text(restore(
+            &mut executor,
+            "x",
+            "0",
+            &payload,
+            &["IDLETIME", "1", "FREQ", "1"]
+        )),
+        "ERR syntax error"
+    );
+
+    let mut corrupt = payload.clone();
+    corrupt[1] ^= 0x01;
+    assert_eq!(
+        err_text(restore(&mut executor, "x", "0", &corrupt, &[])),
+        "ERR DUMP payload version or checksum are wrong"
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "x"]), RespValue::Integer(0));
+
+    // A well-formed footer around a body that is not a value
+    let mut garbage = vec![2u8, 5];
+    garbage.extend(rdb::DUMP_VERSION.to_le_bytes());
+    let crc = rdb::crc64(0, &garbage);
+    garbage.extend(crc.to_le_bytes());
+    assert_eq!(
+        err_text(restore(&mut executor, "x", "0", &garbage, &[])),
+        "ERR Bad data format"
+    );
+}
+
+#[test]
+fn test_restore_payload_from_redis_server() {
+    // DUMP of `SET mykey 10` on redis-server (RDB version 9)
+    let payload = b"\x00\xc0\n\t\x00\xbem\x06\x89Z(\x00\n";
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        restore(&mut executor, "mykey", "0", payload, &[]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["GET", "mykey"]),
+        RespValue::BulkString(Some(b"10".to_vec()))
+    );
+}
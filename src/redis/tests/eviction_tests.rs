@@ -0,0 +1,208 @@
+//! maxmemory eviction tests - CONFIG SET validation, policies, OOM, events
+
+use super::super::{Command, CommandExecutor, EvictionPolicy, RespValue, SDS};
+
+fn config_set(executor: &mut CommandExecutor, param: &str, value: &str) -> RespValue {
+    executor.execute(&Command::ConfigSet(param.to_string(), value.to_string()))
+}
+
+fn config_get(executor: &mut CommandExecutor, param: &str) -> String {
+    match executor.execute(&Command::ConfigGet(vec![param.to_string()])) {
+        RespValue::Array(Some(items)) => match &items[1] {
+            RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes.clone()).unwrap(),
+            other => panic!("CONFIG GET value was {:?}", other),
+        },
+        other => panic!("CONFIG GET returned {:?}", other),
+    }
+}
+
+fn set(executor: &mut CommandExecutor, key: &str, value: &str) -> RespValue {
+    executor.execute(&Command::set(key.to_string(), SDS::from_str(value)))
+}
+
+/// Executor limited to `maxmemory` bytes under `policy`
+fn limited(policy: &str, maxmemory: &str) -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        config_set(&mut executor, "maxmemory-policy", policy),
+        RespValue::ok()
+    );
+    assert_eq!(
+        config_set(&mut executor, "maxmemory", maxmemory),
+        RespValue::ok()
+    );
+    executor
+}
+
+#[test]
+fn test_config_set_maxmemory_units() {
+    let mut executor = CommandExecutor::new();
+    for (value, bytes) in [
+        ("0", 0),
+        ("100", 100),
+        ("100b", 100),
+        ("2k", 2_000),
+        ("2KB", 2_048),
+        ("3m", 3_000_000),
+        ("3mb", 3 << 20),
+        ("1g", 1_000_000_000),
+        ("1Gb", 1 << 30),
+    ] {
+        assert_eq!(
+            config_set(&mut executor, "maxmemory", value),
+            RespValue::ok()
+        );
+        assert_eq!(executor.maxmemory(), bytes, "maxmemory {}", value);
+        assert_eq!(config_get(&mut executor, "maxmemory"), bytes.to_string());
+    }
+
+    for value in ["-1", "1.5mb", "10tb", "abc", "99999999999999999999"] {
+        assert_eq!(
+            config_set(&mut executor, "maxmemory", value),
+            RespValue::err(
+                "ERR CONFIG SET failed (possibly related to argument 'maxmemory') - \
+                 argument must be a memory value"
+            ),
+            "maxmemory {}",
+            value
+        );
+    }
+    assert_eq!(executor.maxmemory(), 1 << 30);
+}
+
+#[test]
+fn test_config_set_maxmemory_policy() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(executor.eviction_policy(), EvictionPolicy::NoEviction);
+    for policy in EvictionPolicy::ALL {
+        let upper = policy.name().to_uppercase();
+        assert_eq!(
+            config_set(&mut executor, "maxmemory-policy", &upper),
+            RespValue::ok()
+        );
+        assert_eq!(executor.eviction_policy(), policy);
+        assert_eq!(config_get(&mut executor, "maxmemory-policy"), policy.name());
+    }
+
+    assert_eq!(
+        config_set(&mut executor, "maxmemory-policy", "lru"),
+        RespValue::err(
+            "ERR CONFIG SET failed (possibly related to argument 'maxmemory-policy') - \
+             argument(s) must be one of the following: volatile-lru, volatile-lfu, \
+             volatile-random, volatile-ttl, allkeys-lru, allkeys-lfu, allkeys-random, noeviction"
+        )
+    );
+    assert_eq!(executor.eviction_policy(), EvictionPolicy::NoEviction);
+}
+
+#[test]
+fn test_noeviction_rejects_growth_but_allows_removal() {
+    // Each key costs 64 bytes plus its value
+    let mut executor = limited("noeviction", "150");
+    assert_eq!(set(&mut executor, "a", "1"), RespValue::ok());
+    assert_eq!(set(&mut executor, "b", "2"), RespValue::ok());
+    assert_eq!(set(&mut executor, "c", "3"), RespValue::ok());
+    assert!(executor.used_memory() > executor.maxmemory());
+
+    let oom = RespValue::err("OOM command not allowed when used memory > 'maxmemory'.");
+    assert_eq!(set(&mut executor, "d", "4"), oom);
+    assert_eq!(
+        executor.execute(&Command::RPush("l".to_string(), vec![SDS::from_str("x")])),
+        oom
+    );
+    // Reads and removals still run
+    assert_eq!(
+        executor.execute(&Command::Get("a".to_string())),
+        RespValue::BulkString(Some(b"1".to_vec()))
+    );
+    assert_eq!(
+        executor.execute(&Command::del("a".to_string())),
+        RespValue::Integer(1)
+    );
+    assert_eq!(executor.evicted_keys_total(), 0);
+
+    // Back under the limit, writes succeed again
+    assert_eq!(set(&mut executor, "d", "4"), RespValue::ok());
+
+    // Raising the limit is always allowed
+    assert_eq!(config_set(&mut executor, "maxmemory", "0"), RespValue::ok());
+    assert_eq!(set(&mut executor, "e", "5"), RespValue::ok());
+}
+
+#[test]
+fn test_allkeys_eviction_queues_events() {
+    let mut executor = limited("allkeys-random", "1kb");
+    for i in 0..50 {
+        assert_eq!(
+            set(&mut executor, &format!("key{}", i), "value"),
+            RespValue::ok()
+        );
+        assert!(executor.used_memory() <= executor.maxmemory() + 69);
+    }
+
+    let evicted = executor.take_evicted_keys();
+    assert!(!evicted.is_empty());
+    assert_eq!(executor.evicted_keys_total(), evicted.len() as u64);
+    for key in &evicted {
+        assert_eq!(
+            executor.execute(&Command::Exists(vec![key.clone()])),
+            RespValue::Integer(0),
+            "{} was reported evicted but still exists",
+            key
+        );
+    }
+    assert!(executor.take_evicted_keys().is_empty());
+
+    match executor.execute(&Command::Info) {
+        RespValue::BulkString(Some(bytes)) => {
+            let info = String::from_utf8(bytes).unwrap();
+            assert!(info.contains(&format!("evicted_keys:{}\r\n", evicted.len())));
+        }
+        other => panic!("INFO returned {:?}", other),
+    }
+}
+
+#[test]
+fn test_volatile_ttl_evicts_nearest_deadline_only() {
+    let mut executor = limited("volatile-ttl", "300");
+    executor.execute(&Command::setex("late".to_string(), 500, SDS::from_str("v")));
+    executor.execute(&Command::setex("soon".to_string(), 10, SDS::from_str("v")));
+    set(&mut executor, "persistent", "v");
+    set(&mut executor, "big", &"x".repeat(100));
+
+    // Over the limit: the next write evicts the nearest deadline first
+    assert_eq!(set(&mut executor, "next", "v"), RespValue::ok());
+    assert_eq!(executor.take_evicted_keys(), vec!["soon".to_string()]);
+
+    // Lowering the limit evicts at once, but never keys without a TTL
+    assert_eq!(
+        config_set(&mut executor, "maxmemory", "10"),
+        RespValue::ok()
+    );
+    assert_eq!(executor.take_evicted_keys(), vec!["late".to_string()]);
+    assert_eq!(
+        set(&mut executor, "more", "v"),
+        RespValue::err("OOM command not allowed when used memory > 'maxmemory'.")
+    );
+    assert_eq!(
+        executor.execute(&Command::Exists(vec![
+            "persistent".to_string(),
+            "big".to_string()
+        ])),
+        RespValue::Integer(2)
+    );
+}
+
+#[test]
+fn test_eviction_may_take_the_written_key() {
+    let mut executor = limited("allkeys-random", "100");
+    set(&mut executor, "only", &"x".repeat(60));
+
+    // The only candidate is the key being overwritten: it is evicted, then stored
+    assert_eq!(set(&mut executor, "only", "small"), RespValue::ok());
+    assert_eq!(executor.take_evicted_keys(), vec!["only".to_string()]);
+    assert_eq!(
+        executor.execute(&Command::Get("only".to_string())),
+        RespValue::BulkString(Some(b"small".to_vec()))
+    );
+}
@@ -0,0 +1,167 @@
+//! Expire argument parsing - SET, SETEX, PSETEX, GETEX, EXPIRE, PEXPIRE
+//!
+//! Every case runs through both parsers, which must agree.
+
+use super::super::{Command, CommandExecutor, RespValue, RespValueZeroCopy};
+use bytes::Bytes;
+
+/// Parse with both parsers and return the shared result
+fn parse(parts: &[&str]) -> Result<Command, String> {
+    let resp = RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+            .collect(),
+    ));
+    let zero_copy = RespValueZeroCopy::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValueZeroCopy::BulkString(Some(Bytes::copy_from_slice(p.as_bytes()))))
+            .collect(),
+    ));
+    let old = Command::from_resp(&resp);
+    let new = Command::from_resp_zero_copy(&zero_copy);
+    assert_eq!(
+        format!("{:?}", old),
+        format!("{:?}", new),
+        "parsers disagree on {:?}",
+        parts
+    );
+    old
+}
+
+fn invalid_expire(cmd: &str) -> Result<Command, String> {
+    Err(format!("ERR invalid expire time in '{}' command", cmd))
+}
+
+fn not_an_integer() -> Result<Command, String> {
+    Err("ERR value is not an integer or out of range".to_string())
+}
+
+/// Seconds just past the largest value that converts to milliseconds
+const SECONDS_OVERFLOW: &str = "9223372036854776";
+
+#[test]
+fn test_setex_expire_validation() {
+    match parse(&["SETEX", "k", "10", "v"]) {
+        Ok(Command::Set { ex, px, .. }) => assert_eq!((ex, px), (Some(10), None)),
+        other => panic!("SETEX parsed as {:?}", other),
+    }
+    for seconds in ["0", "-1", SECONDS_OVERFLOW] {
+        assert_eq!(
+            format!("{:?}", parse(&["SETEX", "k", seconds, "v"])),
+            format!("{:?}", invalid_expire("setex")),
+            "SETEX {}",
+            seconds
+        );
+    }
+    assert_eq!(
+        format!("{:?}", parse(&["SETEX", "k", "ten", "v"])),
+        format!("{:?}", not_an_integer())
+    );
+}
+
+#[test]
+fn test_psetex_expire_validation() {
+    match parse(&["PSETEX", "k", "1500", "v"]) {
+        Ok(Command::Set { ex, px, .. }) => assert_eq!((ex, px), (None, Some(1500))),
+        other => panic!("PSETEX parsed as {:?}", other),
+    }
+    for millis in ["0", "-100"] {
+        assert_eq!(
+            format!("{:?}", parse(&["PSETEX", "k", millis, "v"])),
+            format!("{:?}", invalid_expire("psetex")),
+            "PSETEX {}",
+            millis
+        );
+    }
+    // Milliseconds never need scaling, so the largest value is accepted
+    assert!(parse(&["PSETEX", "k", SECONDS_OVERFLOW, "v"]).is_ok());
+    assert_eq!(
+        format!("{:?}", parse(&["PSETEX", "k", "1.5", "v"])),
+        format!("{:?}", not_an_integer())
+    );
+}
+
+#[test]
+fn test_set_expire_options_validation() {
+    for (option, value) in [
+        ("EX", "0"),
+        ("EX", "-5"),
+        ("EX", SECONDS_OVERFLOW),
+        ("PX", "0"),
+        ("PX", "-5"),
+        ("EXAT", "0"),
+        ("EXAT", SECONDS_OVERFLOW),
+        ("PXAT", "-1"),
+    ] {
+        assert_eq!(
+            format!("{:?}", parse(&["SET", "k", "v", option, value])),
+            format!("{:?}", invalid_expire("set")),
+            "SET {} {}",
+            option,
+            value
+        );
+    }
+    assert!(parse(&["SET", "k", "v", "EX", "1"]).is_ok());
+    assert!(parse(&["SET", "k", "v", "PXAT", "1"]).is_ok());
+
+    // Syntax errors are reported before the expire is checked
+    assert_eq!(
+        format!("{:?}", parse(&["SET", "k", "v", "EX", "0", "BOGUS"])),
+        format!("{:?}", Err::<Command, _>("ERR syntax error".to_string()))
+    );
+}
+
+#[test]
+fn test_getex_expire_validation() {
+    for (option, value) in [
+        ("EX", "0"),
+        ("EX", SECONDS_OVERFLOW),
+        ("PX", "-1"),
+        ("EXAT", "-1"),
+        ("PXAT", "0"),
+    ] {
+        assert_eq!(
+            format!("{:?}", parse(&["GETEX", "k", option, value])),
+            format!("{:?}", invalid_expire("getex")),
+            "GETEX {} {}",
+            option,
+            value
+        );
+    }
+    assert!(parse(&["GETEX", "k", "PX", "250"]).is_ok());
+    assert!(parse(&["GETEX", "k", "PERSIST"]).is_ok());
+}
+
+#[test]
+fn test_expire_and_pexpire_accept_non_positive() {
+    // A non-positive EXPIRE deletes the key rather than being rejected
+    for cmd in ["EXPIRE", "PEXPIRE"] {
+        for value in ["0", "-10"] {
+            assert!(parse(&[cmd, "k", value]).is_ok(), "{} {}", cmd, value);
+        }
+        assert_eq!(
+            format!("{:?}", parse(&[cmd, "k", "soon"])),
+            format!("{:?}", not_an_integer())
+        );
+    }
+}
+
+#[test]
+fn test_rejected_setex_leaves_key_untouched() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&parse(&["SET", "k", "old"]).unwrap());
+
+    // The error comes from the parser, so no command ever reaches the key
+    assert!(parse(&["SETEX", "k", "0", "new"]).is_err());
+    assert!(parse(&["PSETEX", "k", "-1", "new"]).is_err());
+    assert_eq!(
+        executor.execute(&Command::Get("k".to_string())),
+        RespValue::BulkString(Some(b"old".to_vec()))
+    );
+    assert_eq!(
+        executor.execute(&Command::Ttl("k".to_string())),
+        RespValue::Integer(-1)
+    );
+}
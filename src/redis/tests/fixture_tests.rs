@@ -0,0 +1,192 @@
+//! Keyspace fixtures - capture, YAML round trips, curated files, generated
+//! keyspaces
+
+use super::super::fixture::Fixture;
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use crate::simulator::VirtualTime;
+
+const MIXED_KEYSPACE: &str = include_str!("../../../tests/fixtures/mixed_keyspace.yaml");
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+#[test]
+fn test_fixture_capture_yaml_apply_round_trip() {
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(1_700_000_000_000);
+    executor.set_time(VirtualTime::from_millis(1_000));
+    run(&mut executor, &["SET", "str", "hello", "PX", "5000"]);
+    run(&mut executor, &["RPUSH", "list", "a", "b", "a"]);
+    run(&mut executor, &["SADD", "set", "x", "y"]);
+    run(&mut executor, &["HSET", "hash", "f1", "1", "f2", "2"]);
+    run(
+        &mut executor,
+        &["HPEXPIRE", "hash", "800", "FIELDS", "1", "f2"],
+    );
+    run(&mut executor, &["ZADD", "zset", "1.5", "a", "-inf", "b"]);
+    run(&mut executor, &["XADD", "s", "1-1", "f", "v1"]);
+    run(&mut executor, &["XADD", "s", "2-0", "f", "v2"]);
+    run(&mut executor, &["XGROUP", "CREATE", "s", "g", "0"]);
+    run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "alice",
+            "COUNT",
+            "1",
+            "STREAMS",
+            "s",
+            ">",
+        ],
+    );
+    run(&mut executor, &["SET", "gone", "x", "PX", "10"]);
+    executor.set_time(VirtualTime::from_millis(1_200));
+
+    let fixture = Fixture::capture(&executor).unwrap();
+    assert!(
+        !fixture.keys.contains_key("gone"),
+        "expired keys are left out"
+    );
+    assert_eq!(fixture.keys["str"].ttl_ms, Some(4_800));
+    let yaml = fixture.to_yaml();
+    assert_eq!(Fixture::from_yaml(&yaml).unwrap(), fixture);
+
+    // Relative times load the same on an executor at another clock
+    let mut copy = CommandExecutor::new();
+    copy.set_simulation_start_epoch_ms(1_800_000_000_000);
+    copy.set_time(VirtualTime::from_millis(50_000));
+    let report = fixture.apply(&mut copy).unwrap();
+    assert_eq!(report.loaded, 6);
+    assert_eq!(Fixture::capture(&copy).unwrap(), fixture);
+
+    for cmd in [
+        &["LRANGE", "list", "0", "-1"][..],
+        &["PTTL", "str"],
+        &["HPTTL", "hash", "FIELDS", "2", "f1", "f2"],
+        &["ZRANGE", "zset", "0", "-1", "WITHSCORES"],
+        &["XPENDING", "s", "g"],
+    ] {
+        assert_eq!(run(&mut copy, cmd), run(&mut executor, cmd), "{:?}", cmd);
+    }
+}
+
+#[test]
+fn test_fixture_loads_curated_file() {
+    let fixture = Fixture::from_yaml(MIXED_KEYSPACE).unwrap();
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(1_700_000_010_000);
+    let report = fixture.apply(&mut executor).unwrap();
+    assert_eq!((report.loaded, report.skipped), (7, 0));
+
+    assert_eq!(run(&mut executor, &["GET", "counter"]), bulk("17"));
+    assert_eq!(
+        run(&mut executor, &["PTTL", "session:42"]),
+        RespValue::Integer(30_000)
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HPTTL", "user:1", "FIELDS", "2", "otp", "name"]
+        ),
+        RespValue::Array(Some(vec![
+            RespValue::Integer(60_000),
+            RespValue::Integer(-1)
+        ]))
+    );
+    assert_eq!(
+        run(&mut executor, &["ZSCORE", "leaderboard", "carol"]),
+        bulk("-inf")
+    );
+    match run(
+        &mut executor,
+        &["XPENDING", "events", "workers", "-", "+", "10"],
+    ) {
+        RespValue::Array(Some(entries)) => assert_eq!(
+            entries,
+            vec![RespValue::Array(Some(vec![
+                bulk("1700000000000-0"),
+                bulk("alice"),
+                RespValue::Integer(2_000),
+                RespValue::Integer(2),
+            ]))]
+        ),
+        other => panic!("XPENDING returned {:?}", other),
+    }
+}
+
+#[test]
+fn test_fixture_generate_is_seeded_and_loads() {
+    let fixture = Fixture::generate(7, 1_000);
+    assert_eq!(fixture, Fixture::generate(7, 1_000));
+    assert_ne!(fixture, Fixture::generate(8, 1_000));
+    assert_eq!(fixture.keys.len(), 1_000);
+    let with_ttl = fixture
+        .keys
+        .values()
+        .filter(|key| key.ttl_ms.is_some())
+        .count();
+    assert!(
+        (200..500).contains(&with_ttl),
+        "{} keys with TTLs",
+        with_ttl
+    );
+    for kind in ["string", "list", "set", "hash", "zset", "stream"] {
+        assert!(
+            fixture.keys.keys().any(|key| key.starts_with(kind)),
+            "no {} keys",
+            kind
+        );
+    }
+
+    let mut executor = CommandExecutor::new();
+    let report = fixture.apply(&mut executor).unwrap();
+    assert_eq!(report.loaded, 1_000);
+    assert_eq!(run(&mut executor, &["DBSIZE"]), RespValue::Integer(1_000));
+
+    let captured = Fixture::capture(&executor).unwrap();
+    let mut again = CommandExecutor::new();
+    captured.apply(&mut again).unwrap();
+    assert_eq!(Fixture::capture(&again).unwrap(), captured);
+}
+
+#[test]
+fn test_fixture_rejects_bad_values_without_loading() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "keep", "1"]);
+
+    let cases = [
+        (
+            "keys: {s: {type: stream, entries: [{id: 2-0, fields: [f, v]}, {id: 1-0, fields: [f, v]}]}}",
+            "key 's': stream ID 1-0 is not above the previous one",
+        ),
+        (
+            "keys: {s: {type: stream, entries: [{id: 1-0, fields: [f]}]}}",
+            "key 's': entry 1-0 needs field-value pairs",
+        ),
+        (
+            "keys: {h: {type: hash, fields: {a: b}, field_ttl_ms: {c: 10}}}",
+            "key 'h': TTL for missing field 'c'",
+        ),
+        (
+            "keys: {s: {type: stream, entries: [{id: 5-0, fields: [f, v]}], last_id: 1-0}}",
+            "key 's': last_id or entries_added is behind the entries",
+        ),
+    ];
+    for (yaml, expected) in cases {
+        let fixture = Fixture::from_yaml(yaml).unwrap();
+        assert_eq!(fixture.apply(&mut executor).unwrap_err(), expected);
+    }
+    assert!(Fixture::from_yaml("keys: {k: {type: bitmap, value: x}}").is_err());
+    assert_eq!(run(&mut executor, &["DBSIZE"]), RespValue::Integer(1));
+
+    // A field whose TTL is zero is dropped, and a hash left empty is skipped
+    let fixture =
+        Fixture::from_yaml("keys: {h: {type: hash, fields: {a: b}, field_ttl_ms: {a: 0}}}")
+            .unwrap();
+    assert_eq!(fixture.apply(&mut executor).unwrap().skipped, 1);
+    assert_eq!(run(&mut executor, &["EXISTS", "h"]), RespValue::Integer(0));
+}
@@ -0,0 +1,209 @@
+//! Hash commands - HSETNX, HMGET, HRANDFIELD, HSTRLEN, HINCRBYFLOAT
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+fn strings(reply: RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_hsetnx_hmget_hstrlen() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["HSETNX", "h", "a", "apple"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(
+        run(&mut executor, &["HSETNX", "h", "a", "avocado"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(run(&mut executor, &["HGET", "h", "a"]), bulk("apple"));
+
+    assert_eq!(
+        run(&mut executor, &["HMGET", "h", "a", "missing", "a"]),
+        RespValue::Array(Some(vec![
+            bulk("apple"),
+            RespValue::BulkString(None),
+            bulk("apple")
+        ]))
+    );
+    assert_eq!(
+        run(&mut executor, &["HMGET", "nokey", "a", "b"]),
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(None),
+            RespValue::BulkString(None)
+        ]))
+    );
+
+    assert_eq!(
+        run(&mut executor, &["HSTRLEN", "h", "a"]),
+        RespValue::Integer(5)
+    );
+    assert_eq!(
+        run(&mut executor, &["HSTRLEN", "h", "missing"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["HSTRLEN", "nokey", "a"]),
+        RespValue::Integer(0)
+    );
+
+    run(&mut executor, &["SET", "s", "v"]);
+    for cmd in [
+        &["HSETNX", "s", "a", "v"][..],
+        &["HMGET", "s", "a"],
+        &["HSTRLEN", "s", "a"],
+        &["HINCRBYFLOAT", "s", "a", "1"],
+        &["HRANDFIELD", "s"],
+    ] {
+        assert!(
+            matches!(run(&mut executor, cmd), RespValue::Error(e) if e.starts_with("WRONGTYPE")),
+            "{:?}",
+            cmd
+        );
+    }
+    assert_eq!(run(&mut executor, &["GET", "s"]), bulk("v"));
+}
+
+#[test]
+fn test_hincrbyfloat() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "f", "10.5"]),
+        bulk("10.5")
+    );
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "f", "0.1"]),
+        bulk("10.6")
+    );
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "f", "-5"]),
+        bulk("5.6")
+    );
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "f", "5.0e3"]),
+        bulk("5005.6")
+    );
+    assert_eq!(run(&mut executor, &["HGET", "h", "f"]), bulk("5005.6"));
+
+    run(&mut executor, &["HSET", "h", "word", "abc"]);
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "word", "1"]),
+        RespValue::err("ERR hash value is not a float")
+    );
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "f", "abc"]),
+        RespValue::err("ERR value is not a valid float")
+    );
+    run(&mut executor, &["HSET", "h", "big", "1.7e308"]);
+    assert_eq!(
+        run(&mut executor, &["HINCRBYFLOAT", "h", "big", "1.7e308"]),
+        RespValue::err("ERR increment would produce NaN or Infinity")
+    );
+    assert_eq!(run(&mut executor, &["HGET", "h", "big"]), bulk("1.7e308"));
+}
+
+#[test]
+fn test_hrandfield_counts() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["HRANDFIELD", "h"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(
+        run(&mut executor, &["HRANDFIELD", "h", "3"]),
+        RespValue::Array(Some(vec![]))
+    );
+    run(&mut executor, &["HSET", "h", "a", "1", "b", "2", "c", "3"]);
+
+    let one = strings(RespValue::Array(Some(vec![run(
+        &mut executor,
+        &["HRANDFIELD", "h"],
+    )])));
+    assert!(["a", "b", "c"].contains(&one[0].as_str()));
+
+    // Positive counts are distinct and capped at the hash size
+    let mut distinct = strings(run(&mut executor, &["HRANDFIELD", "h", "10"]));
+    distinct.sort();
+    assert_eq!(distinct, vec!["a", "b", "c"]);
+    let two = strings(run(&mut executor, &["HRANDFIELD", "h", "2"]));
+    assert_eq!(two.len(), 2);
+    assert_ne!(two[0], two[1]);
+    assert_eq!(
+        run(&mut executor, &["HRANDFIELD", "h", "0"]),
+        RespValue::Array(Some(vec![]))
+    );
+
+    // Negative counts may repeat and return exactly |count| fields
+    let repeats = strings(run(&mut executor, &["HRANDFIELD", "h", "-20"]));
+    assert_eq!(repeats.len(), 20);
+    assert!(repeats
+        .iter()
+        .all(|f| ["a", "b", "c"].contains(&f.as_str())));
+
+    // WITHVALUES pairs each field with its value
+    let pairs = strings(run(&mut executor, &["HRANDFIELD", "h", "-6", "WITHVALUES"]));
+    assert_eq!(pairs.len(), 12);
+    for pair in pairs.chunks(2) {
+        let expected = match pair[0].as_str() {
+            "a" => "1",
+            "b" => "2",
+            _ => "3",
+        };
+        assert_eq!(pair[1], expected);
+    }
+
+    assert_eq!(
+        run(&mut executor, &["HRANDFIELD", "h", "1", "WITHSCORES"]),
+        RespValue::err("ERR syntax error")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HRANDFIELD", "h", "-9223372036854775807", "WITHVALUES"]
+        ),
+        RespValue::err("ERR value is out of range")
+    );
+}
+
+#[test]
+fn test_hrandfield_replays_under_a_seed() {
+    let picks = |seed: u64| {
+        let mut executor = CommandExecutor::new();
+        executor.set_rng_seed(seed);
+        // Insertion order differs from run to run; picks must not
+        let fields: Vec<String> = (0..50)
+            .map(|i| format!("f{}", (i * 37 + seed) % 50))
+            .collect();
+        for field in &fields {
+            run(&mut executor, &["HSET", "h", field, "v"]);
+        }
+        (0..5)
+            .map(|_| strings(run(&mut executor, &["HRANDFIELD", "h", "-8", "WITHVALUES"])))
+            .collect::<Vec<_>>()
+    };
+    let mut replay = CommandExecutor::new();
+    replay.set_rng_seed(7);
+    for i in (0..50).rev() {
+        run(&mut replay, &["HSET", "h", &format!("f{}", i), "v"]);
+    }
+    let replayed: Vec<Vec<String>> = (0..5)
+        .map(|_| strings(run(&mut replay, &["HRANDFIELD", "h", "-8", "WITHVALUES"])))
+        .collect();
+    assert_eq!(picks(7), replayed);
+    assert_ne!(picks(7), picks(8));
+}
@@ -0,0 +1,280 @@
+//! Hash field TTLs - HEXPIRE family, HTTL family, HPERSIST and expiry
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn ints(values: &[i64]) -> RespValue {
+    RespValue::Array(Some(
+        values.iter().map(|&v| RespValue::Integer(v)).collect(),
+    ))
+}
+
+/// A hash `h` with fields a, b and c
+fn with_hash() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["HSET", "h", "a", "1", "b", "2", "c", "3"]);
+    executor
+}
+
+#[test]
+fn test_hexpire_conditions_and_replies() {
+    let mut executor = with_hash();
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HEXPIRE", "h", "100", "FIELDS", "2", "a", "zz"]
+        ),
+        ints(&[1, -2])
+    );
+    assert_eq!(
+        run(&mut executor, &["HTTL", "h", "FIELDS", "3", "a", "b", "zz"]),
+        ints(&[100, -1, -2])
+    );
+    assert_eq!(
+        run(&mut executor, &["HPTTL", "h", "FIELDS", "1", "a"]),
+        ints(&[100_000])
+    );
+
+    // NX/XX look at whether there is a TTL, GT/LT compare deadlines with
+    // no TTL counting as never
+    let cases: [(&str, &str, &str, i64); 8] = [
+        ("NX", "50", "a", 0),
+        ("NX", "50", "b", 1),
+        ("XX", "50", "c", 0),
+        ("XX", "200", "a", 1),
+        ("GT", "100", "a", 0),
+        ("GT", "100", "c", 0),
+        ("LT", "100", "a", 1),
+        ("LT", "300", "c", 1),
+    ];
+    for (condition, seconds, field, expected) in cases {
+        assert_eq!(
+            run(
+                &mut executor,
+                &["HEXPIRE", "h", seconds, condition, "FIELDS", "1", field]
+            ),
+            ints(&[expected]),
+            "{} {} {}",
+            condition,
+            seconds,
+            field
+        );
+    }
+    assert_eq!(
+        run(&mut executor, &["HTTL", "h", "FIELDS", "3", "a", "b", "c"]),
+        ints(&[100, 50, 300])
+    );
+
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HPERSIST", "h", "FIELDS", "3", "a", "a", "zz"]
+        ),
+        ints(&[1, -1, -2])
+    );
+    // A time of 0 deletes the field, then the emptied key
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HPEXPIRE", "h", "0", "FIELDS", "3", "a", "b", "c"]
+        ),
+        ints(&[2, 2, 2])
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "h"]), RespValue::Integer(0));
+    assert_eq!(
+        run(&mut executor, &["HEXPIRE", "h", "10", "FIELDS", "1", "a"]),
+        ints(&[-2])
+    );
+    assert_eq!(
+        run(&mut executor, &["HPERSIST", "h", "FIELDS", "1", "a"]),
+        ints(&[-2])
+    );
+}
+
+#[test]
+fn test_absolute_forms_use_unix_time() {
+    let mut executor = with_hash();
+    executor.set_simulation_start_epoch_ms(1_700_000_000_000);
+    executor.set_time(VirtualTime::from_millis(5_000));
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HPEXPIREAT", "h", "1700000010500", "FIELDS", "1", "a"]
+        ),
+        ints(&[1])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HEXPIREAT", "h", "1700000060", "FIELDS", "1", "b"]
+        ),
+        ints(&[1])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HPEXPIRETIME", "h", "FIELDS", "2", "a", "c"]
+        ),
+        ints(&[1_700_000_010_500, -1])
+    );
+    assert_eq!(
+        run(&mut executor, &["HEXPIRETIME", "h", "FIELDS", "1", "b"]),
+        ints(&[1_700_000_060])
+    );
+    assert_eq!(
+        run(&mut executor, &["HTTL", "h", "FIELDS", "2", "a", "b"]),
+        ints(&[6, 55])
+    );
+    // A timestamp already past deletes
+    assert_eq!(
+        run(
+            &mut executor,
+            &["HEXPIREAT", "h", "1700000001", "FIELDS", "1", "c"]
+        ),
+        ints(&[2])
+    );
+    assert_eq!(run(&mut executor, &["HLEN", "h"]), RespValue::Integer(2));
+}
+
+#[test]
+fn test_expired_fields_go_on_access() {
+    let mut executor = with_hash();
+    run(
+        &mut executor,
+        &["HPEXPIRE", "h", "100", "FIELDS", "2", "a", "b"],
+    );
+    run(&mut executor, &["HPEXPIRE", "h", "300", "FIELDS", "1", "c"]);
+
+    // Past the first deadline without an eviction pass
+    executor.update_time_readonly(VirtualTime::from_millis(100));
+    assert_eq!(run(&mut executor, &["HLEN", "h"]), RespValue::Integer(1));
+    assert_eq!(
+        run(&mut executor, &["HGET", "h", "a"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(executor.hget_direct("h", "b"), RespValue::BulkString(None));
+
+    // A write sees the field as missing too: HSETNX sets it, without a TTL
+    assert_eq!(
+        run(&mut executor, &["HSETNX", "h", "a", "new"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(
+        run(&mut executor, &["HTTL", "h", "FIELDS", "1", "a"]),
+        ints(&[-1])
+    );
+
+    // HINCRBY keeps a field's TTL, HSET clears it
+    run(&mut executor, &["HSET", "h", "n", "1", "m", "1"]);
+    run(
+        &mut executor,
+        &["HPEXPIRE", "h", "500", "FIELDS", "2", "n", "m"],
+    );
+    run(&mut executor, &["HINCRBY", "h", "n", "1"]);
+    run(&mut executor, &["HSET", "h", "m", "2"]);
+    assert_eq!(
+        run(&mut executor, &["HPTTL", "h", "FIELDS", "2", "n", "m"]),
+        ints(&[500, -1])
+    );
+}
+
+#[test]
+fn test_eviction_pass_removes_fields_and_emptied_hashes() {
+    let mut executor = with_hash();
+    run(&mut executor, &["HSET", "other", "x", "1"]);
+    run(
+        &mut executor,
+        &["HPEXPIRE", "h", "100", "FIELDS", "3", "a", "b", "c"],
+    );
+    run(
+        &mut executor,
+        &["HPEXPIRE", "other", "200", "FIELDS", "1", "x"],
+    );
+    run(&mut executor, &["HSET", "other", "y", "1"]);
+
+    assert_eq!(
+        executor.evict_expired_direct(VirtualTime::from_millis(99)),
+        0
+    );
+    assert_eq!(
+        executor.evict_expired_direct(VirtualTime::from_millis(100)),
+        1
+    );
+    assert!(!executor.get_data().contains_key("h"));
+    assert_eq!(
+        executor.evict_expired_direct(VirtualTime::from_millis(200)),
+        0
+    );
+    assert_eq!(
+        run(&mut executor, &["HLEN", "other"]),
+        RespValue::Integer(1)
+    );
+    assert!(executor.field_ttl_keys.is_empty());
+
+    // A renamed hash keeps its field TTLs and is still evicted
+    run(
+        &mut executor,
+        &["HPEXPIRE", "other", "100", "FIELDS", "1", "y"],
+    );
+    run(&mut executor, &["RENAME", "other", "moved"]);
+    executor.set_time(VirtualTime::from_millis(300));
+    assert!(executor.get_data().is_empty());
+    assert_eq!(executor.keyspace_stats().total_keys(), 0);
+}
+
+#[test]
+fn test_field_ttl_argument_errors() {
+    let mut executor = with_hash();
+    run(&mut executor, &["SET", "s", "v"]);
+    let cases: [(&[&str], &str); 9] = [
+        (
+            &["HEXPIRE", "h", "10", "FIELDS", "0", "a"],
+            "ERR Number of fields must be a positive integer",
+        ),
+        (
+            &["HEXPIRE", "h", "10", "FIELDS", "2", "a"],
+            "ERR The `numfields` parameter must match the number of arguments",
+        ),
+        (
+            &["HEXPIRE", "h", "10", "BOGUS", "FIELDS", "1", "a"],
+            "ERR Mandatory argument FIELDS is missing or not at the right position",
+        ),
+        (
+            &["HTTL", "h", "FIELD", "1", "a"],
+            "ERR Mandatory argument FIELDS is missing or not at the right position",
+        ),
+        (
+            &["HEXPIRE", "h", "ten", "FIELDS", "1", "a"],
+            "ERR value is not an integer or out of range",
+        ),
+        (
+            &["HEXPIRE", "h", "-1", "FIELDS", "1", "a"],
+            "ERR invalid expire time, must be >= 0",
+        ),
+        (
+            &["HEXPIRE", "h", "9223372036854775807", "FIELDS", "1", "a"],
+            "ERR invalid expire time in 'hexpire' command",
+        ),
+        (
+            &["HPEXPIREAT", "h", "70368744177664", "FIELDS", "1", "a"],
+            "ERR invalid expire time in 'hpexpireat' command",
+        ),
+        (
+            &["HPERSIST", "s", "FIELDS", "1", "a"],
+            "WRONGTYPE Operation against a key holding the wrong kind of value",
+        ),
+    ];
+    for (parts, expected) in cases {
+        assert_eq!(
+            run(&mut executor, parts),
+            RespValue::err(expected),
+            "{:?}",
+            parts
+        );
+    }
+    assert_eq!(
+        run(&mut executor, &["HTTL", "h", "FIELDS", "1"]),
+        RespValue::err("ERR wrong number of arguments for 'httl' command")
+    );
+}
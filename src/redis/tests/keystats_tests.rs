@@ -0,0 +1,162 @@
+//! DEBUG KEYSTATS tests - incremental per-type counts, sizes, TTL histogram,
+//! and the expiry outlook behind the TTL gauges
+
+use super::super::{
+    Command, CommandExecutor, ExpiryOutlook, KeyStatsReport, RespValue, ValueKind, SDS,
+};
+use crate::simulator::VirtualTime;
+
+fn keystats(executor: &mut CommandExecutor) -> KeyStatsReport {
+    match executor.execute(&Command::DebugKeyStats) {
+        RespValue::BulkString(Some(bytes)) => {
+            KeyStatsReport::parse(std::str::from_utf8(&bytes).expect("utf8 report"))
+                .expect("well-formed report")
+        }
+        other => panic!("DEBUG KEYSTATS returned {:?}", other),
+    }
+}
+
+#[test]
+fn test_keystats_counts_per_type() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::set("s1".to_string(), SDS::from_str("hello")));
+    executor.execute(&Command::set("s2".to_string(), SDS::from_str("abc")));
+    executor.execute(&Command::LPush(
+        "l".to_string(),
+        vec![SDS::from_str("a"), SDS::from_str("b")],
+    ));
+    executor.execute(&Command::SAdd("set".to_string(), vec![SDS::from_str("m")]));
+    executor.execute(&Command::HSet(
+        "h".to_string(),
+        vec![(SDS::from_str("f"), SDS::from_str("v"))],
+    ));
+
+    let stats = executor.keyspace_stats();
+    assert_eq!(stats.count(ValueKind::String), 2);
+    assert_eq!(stats.total_size(ValueKind::String), 8);
+    assert_eq!(stats.count(ValueKind::List), 1);
+    assert_eq!(stats.total_size(ValueKind::List), 2);
+    assert_eq!(stats.count(ValueKind::Set), 1);
+    assert_eq!(stats.count(ValueKind::Hash), 1);
+    assert_eq!(stats.count(ValueKind::SortedSet), 0);
+
+    let report = keystats(&mut executor);
+    assert_eq!(report.total_keys(), 5);
+    assert_eq!(report.volatile_keys, 0);
+}
+
+#[test]
+fn test_keystats_tracks_overwrite_delete_and_flush() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::set("k".to_string(), SDS::from_str("abc")));
+    executor.execute(&Command::Append("k".to_string(), SDS::from_str("de")));
+    assert_eq!(executor.keyspace_stats().total_size(ValueKind::String), 5);
+
+    // Duplicate keys in one DEL must only be counted once
+    executor.execute(&Command::Del(vec!["k".to_string(), "k".to_string()]));
+    assert_eq!(executor.keyspace_stats().total_keys(), 0);
+
+    executor.execute(&Command::RPush("l".to_string(), vec![SDS::from_str("x")]));
+    executor.execute(&Command::LPop("l".to_string()));
+    assert_eq!(executor.keyspace_stats().count(ValueKind::List), 0);
+
+    executor.execute(&Command::set("a".to_string(), SDS::from_str("1")));
+    executor.execute(&Command::FlushDb);
+    assert_eq!(executor.keyspace_stats().total_keys(), 0);
+}
+
+#[test]
+fn test_keystats_ttl_histogram_and_expiry() {
+    let mut executor = CommandExecutor::new();
+    executor.set_time(VirtualTime::from_millis(0));
+    executor.execute(&Command::setex("short".to_string(), 5, SDS::from_str("v")));
+    executor.execute(&Command::setex("long".to_string(), 7200, SDS::from_str("v")));
+    executor.execute(&Command::set("forever".to_string(), SDS::from_str("v")));
+
+    let report = keystats(&mut executor);
+    assert_eq!(report.volatile_keys, 2);
+    assert_eq!(report.ttl_buckets[1], 1, "5s TTL lands in the <10s bucket");
+    assert_eq!(report.ttl_buckets[5], 1, "2h TTL lands in the <1d bucket");
+
+    // Eviction must keep the incremental stats in sync
+    executor.set_time(VirtualTime::from_millis(6_000));
+    let report = keystats(&mut executor);
+    assert_eq!(report.total_keys(), 2);
+    assert_eq!(report.volatile_keys, 1);
+}
+
+#[test]
+fn test_keystats_report_merge_roundtrip() {
+    let mut a = CommandExecutor::new();
+    let mut b = CommandExecutor::new();
+    a.execute(&Command::set("x".to_string(), SDS::from_str("1234")));
+    b.execute(&Command::set("y".to_string(), SDS::from_str("12")));
+
+    let mut merged = keystats(&mut a);
+    merged.merge(&keystats(&mut b));
+    assert_eq!(merged.total_keys(), 2);
+
+    let rendered = merged.render();
+    assert!(rendered.contains("string_size_avg:3.00\r\n"), "{}", rendered);
+    assert_eq!(KeyStatsReport::parse(&rendered), Some(merged));
+}
+
+#[test]
+fn test_expiry_outlook_is_cumulative_and_moves_with_the_clock() {
+    let mut executor = CommandExecutor::new();
+    executor.set_time(VirtualTime::from_millis(0));
+    for (key, ttl) in [("a", 30), ("b", 200), ("c", 200), ("d", 1800), ("e", 7200)] {
+        executor.execute(&Command::setex(key.to_string(), ttl, SDS::from_str("v")));
+    }
+    executor.execute(&Command::set("forever".to_string(), SDS::from_str("v")));
+
+    let outlook = executor.expiry_outlook(VirtualTime::from_millis(0));
+    assert_eq!(
+        outlook,
+        ExpiryOutlook {
+            volatile_keys: 5,
+            expiring: [1, 3, 4],
+        }
+    );
+    assert_eq!(
+        outlook.points().collect::<Vec<_>>(),
+        vec![("1m", 1), ("5m", 3), ("1h", 4)]
+    );
+
+    // Two minutes on, the 200s wave is inside the next minute
+    let later = executor.expiry_outlook(VirtualTime::from_millis(150_000));
+    assert_eq!(later.expiring, [3, 3, 4]);
+
+    let mut merged = outlook;
+    merged.merge(&later);
+    assert_eq!(merged.volatile_keys, 10);
+    assert!(merged.is_consistent());
+}
+
+#[test]
+fn test_expiry_outlook_follows_ttl_changes() {
+    let mut executor = CommandExecutor::new();
+    executor.set_time(VirtualTime::from_millis(0));
+    for key in ["a", "b", "c"] {
+        executor.execute(&Command::setex(key.to_string(), 30, SDS::from_str("v")));
+    }
+    let now = VirtualTime::from_millis(0);
+    assert_eq!(executor.expiry_outlook(now).expiring, [3, 3, 3]);
+
+    // A pushed-out deadline, a removed one and a deleted key all leave
+    // the index
+    executor.execute(&Command::expire("a".to_string(), 300));
+    executor.execute(&Command::Persist("b".to_string()));
+    executor.execute(&Command::Del(vec!["c".to_string()]));
+    assert_eq!(
+        executor.expiry_outlook(now),
+        ExpiryOutlook {
+            volatile_keys: 1,
+            expiring: [0, 0, 1],
+        }
+    );
+    assert_eq!(keystats(&mut executor).ttl_buckets, [0, 0, 0, 1, 0, 0, 0]);
+
+    executor.execute(&Command::FlushAll);
+    assert_eq!(executor.expiry_outlook(now), ExpiryOutlook::default());
+}
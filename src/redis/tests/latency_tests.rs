@@ -0,0 +1,135 @@
+//! LATENCY: spikes above latency-monitor-threshold and the reports on them
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn text(reply: RespValue) -> String {
+    match reply {
+        RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+        other => panic!("expected a bulk string, got {:?}", other),
+    }
+}
+
+fn pair(time: i64, latency_ms: i64) -> RespValue {
+    RespValue::Array(Some(vec![
+        RespValue::Integer(time),
+        RespValue::Integer(latency_ms),
+    ]))
+}
+
+const EPOCH: i64 = 1_700_000_000;
+
+fn executor() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(EPOCH * 1000);
+    executor
+}
+
+#[test]
+fn test_latency_records_spikes_above_the_threshold() {
+    let mut executor = executor();
+
+    // Off by default: nothing is kept and DOCTOR says how to turn it on
+    assert!(!executor.record_latency("command", 5000));
+    assert!(text(run(&mut executor, &["LATENCY", "DOCTOR"])).contains("latency-monitor-threshold"));
+
+    run(
+        &mut executor,
+        &["CONFIG", "SET", "latency-monitor-threshold", "100"],
+    );
+    assert!(text(run(&mut executor, &["LATENCY", "DOCTOR"])).contains("no latency spike"));
+    assert!(!executor.record_latency("command", 99));
+    assert!(executor.record_latency("command", 250));
+    // Spikes in the same second merge into the worst of them
+    assert!(executor.record_latency("command", 400));
+    executor.set_time(VirtualTime::from_millis(3000));
+    assert!(executor.record_latency("command", 120));
+    assert!(executor.record_latency("expire-cycle", 150));
+
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "HISTORY", "command"]),
+        RespValue::Array(Some(vec![pair(EPOCH, 400), pair(EPOCH + 3, 120)]))
+    );
+    assert_eq!(
+        run(&mut executor, &["latency", "latest"]),
+        RespValue::Array(Some(vec![
+            RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(b"command".to_vec())),
+                RespValue::Integer(EPOCH + 3),
+                RespValue::Integer(120),
+                RespValue::Integer(400),
+            ])),
+            RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(b"expire-cycle".to_vec())),
+                RespValue::Integer(EPOCH + 3),
+                RespValue::Integer(150),
+                RespValue::Integer(150),
+            ])),
+        ]))
+    );
+
+    let report = text(run(&mut executor, &["LATENCY", "DOCTOR"]));
+    assert!(report.contains(
+        "1. command: 2 latency spikes (average 260ms, mean deviation 140ms, period 1 sec). \
+         Worst all time event 400ms."
+    ));
+    assert!(report.contains("2. expire-cycle: 1 latency spikes"));
+    assert!(report.contains("expire cycle slow"));
+}
+
+#[test]
+fn test_latency_reset_and_subcommand_errors() {
+    let mut executor = executor();
+    run(
+        &mut executor,
+        &["CONFIG", "SET", "latency-monitor-threshold", "10"],
+    );
+    executor.record_latency("command", 20);
+    executor.record_latency("snapshot", 30);
+    executor.record_latency("fast-command", 40);
+
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "RESET", "snapshot", "nope"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "HISTORY", "snapshot"]),
+        RespValue::Array(Some(vec![]))
+    );
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "RESET"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "LATEST"]),
+        RespValue::Array(Some(vec![]))
+    );
+
+    assert_eq!(
+        run(
+            &mut executor,
+            &["CONFIG", "SET", "latency-monitor-threshold", "soon"]
+        ),
+        RespValue::err(
+            "ERR CONFIG SET failed (possibly related to argument 'latency-monitor-threshold') - \
+             argument couldn't be parsed into an integer"
+        )
+    );
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "GRAPH", "command"]),
+        RespValue::err("ERR unknown subcommand 'graph'. Try LATENCY HELP.")
+    );
+    assert_eq!(
+        run(&mut executor, &["LATENCY", "HISTORY"]),
+        RespValue::err("ERR wrong number of arguments for 'latency|history' command")
+    );
+    assert_eq!(
+        run(&mut executor, &["LATENCY"]),
+        RespValue::err("ERR wrong number of arguments for 'latency' command")
+    );
+    assert!(matches!(
+        run(&mut executor, &["LATENCY", "HELP"]),
+        RespValue::Array(Some(lines)) if lines.len() == 12
+    ));
+}
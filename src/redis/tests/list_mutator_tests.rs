@@ -0,0 +1,183 @@
+//! List mutators - LINSERT, LREM, LPOS, LPUSHX, RPUSHX
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn list(executor: &mut CommandExecutor, key: &str) -> Vec<String> {
+    match run(executor, &["LRANGE", key, "0", "-1"]) {
+        RespValue::Array(Some(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+fn ints(values: &[i64]) -> RespValue {
+    RespValue::Array(Some(
+        values.iter().map(|&n| RespValue::Integer(n)).collect(),
+    ))
+}
+
+#[test]
+fn test_linsert_before_and_after_pivot() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["RPUSH", "l", "a", "b", "b"]);
+
+    assert_eq!(
+        run(&mut executor, &["LINSERT", "l", "BEFORE", "b", "x"]),
+        RespValue::Integer(4)
+    );
+    assert_eq!(
+        run(&mut executor, &["LINSERT", "l", "after", "b", "y"]),
+        RespValue::Integer(5)
+    );
+    assert_eq!(list(&mut executor, "l"), ["a", "x", "b", "y", "b"]);
+
+    assert_eq!(
+        run(&mut executor, &["LINSERT", "l", "BEFORE", "zz", "x"]),
+        RespValue::Integer(-1)
+    );
+    assert_eq!(
+        run(&mut executor, &["LINSERT", "missing", "BEFORE", "a", "x"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["LINSERT", "l", "MIDDLE", "a", "x"]),
+        RespValue::err("ERR syntax error")
+    );
+}
+
+#[test]
+fn test_lrem_count_direction() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["RPUSH", "l", "a", "b", "a", "c", "a"]);
+
+    assert_eq!(
+        run(&mut executor, &["LREM", "l", "-2", "a"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(list(&mut executor, "l"), ["a", "b", "c"]);
+    assert_eq!(
+        run(&mut executor, &["LREM", "l", "1", "a"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(list(&mut executor, "l"), ["b", "c"]);
+    assert_eq!(
+        run(&mut executor, &["LREM", "l", "0", "zz"]),
+        RespValue::Integer(0)
+    );
+
+    // Removing the last elements deletes the key and its TTL
+    run(&mut executor, &["EXPIRE", "l", "100"]);
+    run(&mut executor, &["LREM", "l", "0", "b"]);
+    assert_eq!(
+        run(&mut executor, &["LREM", "l", "0", "c"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "l"]), RespValue::Integer(0));
+
+    run(&mut executor, &["SET", "s", "v"]);
+    assert!(matches!(
+        run(&mut executor, &["LREM", "s", "0", "v"]),
+        RespValue::Error(e) if e.starts_with("WRONGTYPE")
+    ));
+}
+
+#[test]
+fn test_lpos_rank_count_maxlen() {
+    let mut executor = CommandExecutor::new();
+    run(
+        &mut executor,
+        &["RPUSH", "l", "a", "b", "c", "1", "2", "3", "c", "c"],
+    );
+
+    assert_eq!(
+        run(&mut executor, &["LPOS", "l", "c"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(
+        run(&mut executor, &["LPOS", "l", "c", "RANK", "2"]),
+        RespValue::Integer(6)
+    );
+    assert_eq!(
+        run(&mut executor, &["LPOS", "l", "c", "RANK", "-1"]),
+        RespValue::Integer(7)
+    );
+    assert_eq!(
+        run(&mut executor, &["LPOS", "l", "c", "COUNT", "0"]),
+        ints(&[2, 6, 7])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["LPOS", "l", "c", "RANK", "-1", "COUNT", "2"]
+        ),
+        ints(&[7, 6])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["LPOS", "l", "c", "COUNT", "0", "MAXLEN", "3"]
+        ),
+        ints(&[2])
+    );
+    assert_eq!(
+        run(&mut executor, &["LPOS", "l", "zz"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(
+        run(&mut executor, &["LPOS", "missing", "c", "COUNT", "1"]),
+        ints(&[])
+    );
+}
+
+#[test]
+fn test_lpos_option_errors() {
+    let mut executor = CommandExecutor::new();
+    for (args, error) in [
+        (
+            &["RANK", "0"][..],
+            "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list",
+        ),
+        (&["COUNT", "-1"], "ERR COUNT can't be negative"),
+        (&["MAXLEN", "-1"], "ERR MAXLEN can't be negative"),
+        (&["RANK", "x"], "ERR value is not an integer or out of range"),
+        (&["RANK"], "ERR syntax error"),
+        (&["BOGUS", "1"], "ERR syntax error"),
+    ] {
+        let mut parts = vec!["LPOS", "l", "a"];
+        parts.extend_from_slice(args);
+        assert_eq!(run(&mut executor, &parts), RespValue::err(error), "{:?}", args);
+    }
+}
+
+#[test]
+fn test_pushx_only_onto_existing_lists() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        run(&mut executor, &["LPUSHX", "l", "a"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "l"]), RespValue::Integer(0));
+
+    run(&mut executor, &["RPUSH", "l", "m"]);
+    assert_eq!(
+        run(&mut executor, &["LPUSHX", "l", "a", "b"]),
+        RespValue::Integer(3)
+    );
+    assert_eq!(
+        run(&mut executor, &["RPUSHX", "l", "z"]),
+        RespValue::Integer(4)
+    );
+    assert_eq!(list(&mut executor, "l"), ["b", "a", "m", "z"]);
+
+    run(&mut executor, &["SET", "s", "v"]);
+    assert!(matches!(
+        run(&mut executor, &["RPUSHX", "s", "z"]),
+        RespValue::Error(e) if e.starts_with("WRONGTYPE")
+    ));
+}
@@ -0,0 +1,59 @@
+//! LOLWUT: art replayed from the simulation seed, and its version argument
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn text(reply: RespValue) -> String {
+    match reply {
+        RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+        other => panic!("expected a bulk string, got {:?}", other),
+    }
+}
+
+fn seeded(seed: u64) -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    executor.set_rng_seed(seed);
+    executor
+}
+
+#[test]
+fn test_lolwut_replays_from_the_seed() {
+    let (mut a, mut b) = (seeded(7), seeded(7));
+    let first = text(run(&mut a, &["LOLWUT"]));
+    assert_eq!(first, text(run(&mut b, &["LOLWUT"])));
+    // The next call draws a new seed
+    assert_ne!(first, text(run(&mut a, &["LOLWUT"])));
+
+    // Default skyline: 80 columns by 20 rows
+    let lines: Vec<&str> = first.lines().collect();
+    assert!(lines[..20].iter().all(|line| line.len() == 80));
+    assert!(first.ends_with("Redis ver. 7.0.0\n"));
+}
+
+#[test]
+fn test_lolwut_versions_and_params() {
+    let mut executor = seeded(1);
+    let schotter = text(run(
+        &mut executor,
+        &["lolwut", "version", "5", "20", "4", "3"],
+    ));
+    assert!(schotter.lines().next().unwrap().starts_with("+---+"));
+    assert!(schotter.contains("Georg Nees"));
+    assert_eq!(
+        run(&mut executor, &["LOLWUT", "VERSION", "1"]),
+        RespValue::BulkString(Some(b"Redis ver. 7.0.0\n".to_vec()))
+    );
+
+    // Oversized canvases are clamped
+    let huge = text(run(&mut executor, &["LOLWUT", "VERSION", "6", "5000", "1"]));
+    assert_eq!(huge.lines().next().unwrap().len(), 1000);
+
+    assert_eq!(
+        run(&mut executor, &["LOLWUT", "VERSION", "x"]),
+        RespValue::err("ERR value is not an integer or out of range")
+    );
+    assert_eq!(
+        run(&mut executor, &["LOLWUT", "wide"]),
+        RespValue::err("ERR value is not an integer or out of range")
+    );
+}
@@ -0,0 +1,276 @@
+//! Redis 7 Functions: FUNCTION LOAD/LIST/DUMP/RESTORE/STATS and FCALL
+
+use super::super::data::SDS;
+use super::super::{Command, CommandExecutor, FunctionLibraries, FunctionRestorePolicy, RespValue};
+
+const LIBRARY: &str = r#"#!lua name=counters
+redis.register_function('incr_by', function(keys, args)
+    return redis.call('INCRBY', keys[1], args[1])
+end)
+redis.register_function{
+    function_name = 'peek',
+    callback = function(keys) return redis.call('GET', keys[1]) end,
+    flags = {'no-writes'},
+    description = 'read a counter',
+}
+redis.register_function{
+    function_name = 'sneaky',
+    callback = function(keys) return redis.call('SET', keys[1], '0') end,
+    flags = {'no-writes'},
+}
+"#;
+
+fn load(executor: &mut CommandExecutor, code: &str, replace: bool) -> RespValue {
+    executor.execute(&Command::FunctionLoad {
+        code: code.to_string(),
+        replace,
+    })
+}
+
+fn fcall(
+    executor: &mut CommandExecutor,
+    function: &str,
+    keys: &[&str],
+    args: &[&str],
+) -> RespValue {
+    executor.execute(&Command::FCall {
+        function: function.to_string(),
+        keys: keys.iter().map(|key| key.to_string()).collect(),
+        args: args.iter().map(|arg| SDS::from_str(arg)).collect(),
+    })
+}
+
+fn fcall_ro(executor: &mut CommandExecutor, function: &str, key: &str) -> RespValue {
+    executor.execute(&Command::FCallRo {
+        function: function.to_string(),
+        keys: vec![key.to_string()],
+        args: vec![],
+    })
+}
+
+fn error_text(reply: RespValue) -> String {
+    match reply {
+        RespValue::Error(e) => e.into_owned(),
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_fcall_runs_registered_functions() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        load(&mut executor, LIBRARY, false),
+        RespValue::BulkString(Some(b"counters".to_vec()))
+    );
+
+    assert_eq!(
+        fcall(&mut executor, "incr_by", &["c"], &["5"]),
+        RespValue::Integer(5)
+    );
+    assert_eq!(
+        fcall(&mut executor, "incr_by", &["c"], &["2"]),
+        RespValue::Integer(7)
+    );
+    assert_eq!(
+        fcall_ro(&mut executor, "peek", "c"),
+        RespValue::BulkString(Some(b"7".to_vec()))
+    );
+
+    // FCALL_RO only calls no-writes functions, and those cannot write
+    assert_eq!(
+        error_text(fcall_ro(&mut executor, "incr_by", "c")),
+        "ERR Can not execute a script with write flag using *_ro command."
+    );
+    assert!(error_text(fcall(&mut executor, "sneaky", &["c"], &[]))
+        .contains("Write commands are not allowed from read-only scripts"));
+    assert_eq!(
+        error_text(fcall(&mut executor, "missing", &[], &[])),
+        "ERR Function not found"
+    );
+
+    // Functions are not part of the keyspace
+    assert_eq!(executor.execute(&Command::FlushAll), RespValue::ok());
+    assert_eq!(
+        fcall(&mut executor, "incr_by", &["c"], &["1"]),
+        RespValue::Integer(1)
+    );
+}
+
+#[test]
+fn test_function_load_errors() {
+    let mut executor = CommandExecutor::new();
+    let cases = [
+        ("return 1", "ERR Missing library metadata"),
+        (
+            "#!lua name=empty\nlocal x = 1",
+            "ERR No functions registered",
+        ),
+        ("#!python name=py\n", "ERR Engine 'python' not found"),
+    ];
+    for (code, expected) in cases {
+        assert_eq!(error_text(load(&mut executor, code, false)), expected);
+    }
+
+    // The keyspace is out of reach while a library loads
+    let touches_keys = "#!lua name=bad\nredis.call('SET', 'k', 'v')";
+    assert!(error_text(load(&mut executor, touches_keys, false))
+        .starts_with("ERR Error registering functions"));
+    let bad_flag = "#!lua name=bad\n\
+        redis.register_function{function_name='f', callback=function() end, flags={'fast'}}";
+    assert!(error_text(load(&mut executor, bad_flag, false)).contains("unknown flag given"));
+    let endless = "#!lua name=bad\nwhile true do end";
+    assert!(error_text(load(&mut executor, endless, false)).contains("FUNCTION LOAD timeout"));
+
+    load(&mut executor, LIBRARY, false);
+    assert_eq!(
+        error_text(load(&mut executor, LIBRARY, false)),
+        "ERR Library 'counters' already exists"
+    );
+    let clash = "#!lua name=other\nredis.register_function('peek', function() return 1 end)";
+    assert_eq!(
+        error_text(load(&mut executor, clash, false)),
+        "ERR Function peek already exists"
+    );
+
+    // REPLACE swaps the library's functions
+    let replacement =
+        "#!lua name=counters\nredis.register_function('one', function() return 1 end)";
+    assert!(!matches!(
+        load(&mut executor, replacement, true),
+        RespValue::Error(_)
+    ));
+    assert_eq!(fcall(&mut executor, "one", &[], &[]), RespValue::Integer(1));
+    assert_eq!(
+        error_text(fcall(&mut executor, "incr_by", &["c"], &["1"])),
+        "ERR Function not found"
+    );
+}
+
+#[test]
+fn test_function_list_stats_and_delete() {
+    let mut executor = CommandExecutor::new();
+    load(&mut executor, LIBRARY, false);
+    load(
+        &mut executor,
+        "#!lua name=misc\nredis.register_function('noop', function() end)",
+        false,
+    );
+
+    let list = |executor: &mut CommandExecutor, pattern: Option<&str>| match executor.execute(
+        &Command::FunctionList {
+            pattern: pattern.map(str::to_string),
+            with_code: true,
+        },
+    ) {
+        RespValue::Array(Some(libraries)) => libraries,
+        other => panic!("expected libraries, got {:?}", other),
+    };
+    let libraries = list(&mut executor, Some("count*"));
+    assert_eq!(libraries.len(), 1);
+    let RespValue::Array(Some(fields)) = &libraries[0] else {
+        panic!("expected a library entry");
+    };
+    assert_eq!(fields[1], RespValue::BulkString(Some(b"counters".to_vec())));
+    assert_eq!(fields[3], RespValue::BulkString(Some(b"LUA".to_vec())));
+    let RespValue::Array(Some(functions)) = &fields[5] else {
+        panic!("expected the functions");
+    };
+    assert_eq!(functions.len(), 3);
+    assert_eq!(
+        fields[7],
+        RespValue::BulkString(Some(LIBRARY.as_bytes().to_vec()))
+    );
+    assert_eq!(list(&mut executor, None).len(), 2);
+
+    let stats = executor.execute(&Command::FunctionStats);
+    let RespValue::Array(Some(stats)) = stats else {
+        panic!("expected stats");
+    };
+    assert_eq!(
+        stats[3],
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(Some(b"LUA".to_vec())),
+            RespValue::Array(Some(vec![
+                RespValue::BulkString(Some(b"libraries_count".to_vec())),
+                RespValue::Integer(2),
+                RespValue::BulkString(Some(b"functions_count".to_vec())),
+                RespValue::Integer(4),
+            ])),
+        ]))
+    );
+
+    let delete = |name: &str| Command::FunctionDelete(name.to_string());
+    assert_eq!(executor.execute(&delete("misc")), RespValue::ok());
+    assert_eq!(
+        error_text(executor.execute(&delete("misc"))),
+        "ERR Library not found"
+    );
+    assert_eq!(executor.execute(&Command::FunctionFlush), RespValue::ok());
+    assert!(list(&mut executor, None).is_empty());
+}
+
+#[test]
+fn test_function_dump_and_restore() {
+    let mut executor = CommandExecutor::new();
+    load(&mut executor, LIBRARY, false);
+    let RespValue::BulkString(Some(payload)) = executor.execute(&Command::FunctionDump) else {
+        panic!("expected a payload");
+    };
+    let restore = |policy| Command::FunctionRestore {
+        payload: payload.clone(),
+        policy,
+    };
+
+    assert_eq!(
+        error_text(executor.execute(&restore(FunctionRestorePolicy::Append))),
+        "ERR Library 'counters' already exists"
+    );
+    assert_eq!(
+        executor.execute(&restore(FunctionRestorePolicy::Replace)),
+        RespValue::ok()
+    );
+
+    let mut other = CommandExecutor::new();
+    assert_eq!(
+        other.execute(&restore(FunctionRestorePolicy::Flush)),
+        RespValue::ok()
+    );
+    assert_eq!(
+        fcall(&mut other, "incr_by", &["c"], &["3"]),
+        RespValue::Integer(3)
+    );
+
+    let mut corrupt = payload.clone();
+    corrupt[0] ^= 0xFF;
+    assert_eq!(
+        error_text(other.execute(&Command::FunctionRestore {
+            payload: corrupt,
+            policy: FunctionRestorePolicy::Append,
+        })),
+        "ERR payload version or checksum are wrong"
+    );
+}
+
+#[test]
+fn test_libraries_are_shared_and_survive_snapshots() {
+    let functions = FunctionLibraries::new();
+    let mut loader = CommandExecutor::new();
+    let mut caller = CommandExecutor::new();
+    loader.set_function_libraries(functions.clone());
+    caller.set_function_libraries(functions.clone());
+
+    load(&mut loader, LIBRARY, false);
+    assert_eq!(
+        fcall(&mut caller, "incr_by", &["c"], &["4"]),
+        RespValue::Integer(4)
+    );
+
+    let snapshot = caller.rdb_snapshot();
+    let mut restarted = CommandExecutor::new();
+    restarted.load_rdb_file(&snapshot).expect("snapshot loads");
+    assert_eq!(restarted.function_libraries().num_libraries(), 1);
+    assert_eq!(
+        fcall(&mut restarted, "incr_by", &["c"], &["1"]),
+        RespValue::Integer(5)
+    );
+}
@@ -228,3 +228,232 @@ fn test_redis_call_hash_operations() {
         panic!("Expected array result");
     }
 }
+
+#[test]
+fn test_write_after_nondeterministic_command_is_refused() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::set("k".to_string(), SDS::from_str("v")));
+
+    let eval = |script: &str| Command::Eval {
+        script: script.to_string(),
+        keys: vec![],
+        args: vec![],
+    };
+
+    // Reads after RANDOMKEY are fine; the first write is refused
+    let result = executor.execute(&eval(
+        r#"
+            local k = redis.call("RANDOMKEY")
+            redis.call("GET", k)
+            redis.call("SET", "copy", k)
+        "#,
+    ));
+    match result {
+        RespValue::Error(e) => assert!(
+            e.contains("Write commands not allowed after non deterministic commands"),
+            "unexpected error: {}",
+            e
+        ),
+        other => panic!("expected error, got {:?}", other),
+    }
+    assert_eq!(
+        executor.execute(&Command::Get("copy".to_string())),
+        RespValue::BulkString(None)
+    );
+
+    // redis.pcall reports the refusal as an error table
+    let result = executor.execute(&eval(
+        r#"
+            redis.call("TIME")
+            local r = redis.pcall("SET", "copy", "x")
+            return r["err"] ~= nil
+        "#,
+    ));
+    assert_eq!(result, RespValue::Integer(1));
+}
+
+#[test]
+fn test_replicate_commands_allows_writes_after_nondeterministic_command() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::set("k".to_string(), SDS::from_str("v")));
+
+    let cmd = Command::Eval {
+        script: r#"
+            redis.replicate_commands()
+            local k = redis.call("RANDOMKEY")
+            redis.call("SET", "copy", k)
+            return redis.call("GET", "copy")
+        "#
+        .to_string(),
+        keys: vec![],
+        args: vec![],
+    };
+    assert_eq!(
+        executor.execute(&cmd),
+        RespValue::BulkString(Some(b"k".to_vec()))
+    );
+
+    // Too late to switch once the script has written
+    let cmd = Command::Eval {
+        script: r#"
+            redis.call("SET", "a", "1")
+            return redis.replicate_commands()
+        "#
+        .to_string(),
+        keys: vec![],
+        args: vec![],
+    };
+    assert_eq!(executor.execute(&cmd), RespValue::BulkString(None));
+}
+
+fn eval(executor: &mut CommandExecutor, script: &str) -> RespValue {
+    executor.execute(&Command::Eval {
+        script: script.to_string(),
+        keys: vec![],
+        args: vec![],
+    })
+}
+
+#[test]
+fn test_redis_helpers_and_constants() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        eval(
+            &mut executor,
+            "return redis.error_reply('-MYERR went wrong')"
+        ),
+        RespValue::err("MYERR went wrong")
+    );
+    assert_eq!(
+        eval(&mut executor, "return redis.status_reply('FINE')"),
+        RespValue::simple("FINE")
+    );
+    assert_eq!(
+        eval(&mut executor, "return redis.sha1hex('')"),
+        RespValue::BulkString(Some(b"da39a3ee5e6b4b0d3255bfef95601890afd80709".to_vec()))
+    );
+    assert_eq!(
+        eval(
+            &mut executor,
+            "redis.log(redis.LOG_WARNING, 'from', 'a script')
+             return {redis.LOG_NOTICE, redis.REPL_ALL, redis.REPL_REPLICA, redis.breakpoint()}"
+        ),
+        RespValue::Array(Some(vec![
+            RespValue::Integer(2),
+            RespValue::Integer(3),
+            RespValue::Integer(2),
+            RespValue::BulkString(None),
+        ]))
+    );
+    assert!(matches!(
+        eval(&mut executor, "redis.log(9, 'x')"),
+        RespValue::Error(e) if e.contains("Invalid debug level")
+    ));
+    assert!(matches!(
+        eval(&mut executor, "redis.setresp(4)"),
+        RespValue::Error(e) if e.contains("RESP version must be 2 or 3")
+    ));
+}
+
+#[test]
+fn test_setresp_3_converts_replies_both_ways() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::HSet(
+        "h".to_string(),
+        vec![(SDS::from_str("f"), SDS::from_str("v"))],
+    ));
+    eval(&mut executor, "redis.call('ZADD', 'z', '1.5', 'm')");
+
+    // RESP2 replies are plain arrays and strings
+    assert_eq!(
+        eval(&mut executor, "return redis.call('HGETALL', 'h')[2]"),
+        RespValue::BulkString(Some(b"v".to_vec()))
+    );
+    assert_eq!(
+        eval(
+            &mut executor,
+            "redis.setresp(3)
+             local h = redis.call('HGETALL', 'h')
+             local score = redis.call('ZSCORE', 'z', 'm')
+             return {h.map.f, tostring(score.double)}"
+        ),
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(Some(b"v".to_vec())),
+            RespValue::BulkString(Some(b"1.5".to_vec())),
+        ]))
+    );
+
+    // RESP3 tables returned from a script become RESP3 replies
+    assert_eq!(
+        eval(&mut executor, "return {double = 2.5}"),
+        RespValue::Double(2.5)
+    );
+    assert_eq!(
+        eval(&mut executor, "return {map = {b = 2, a = 1}}"),
+        RespValue::Map(vec![
+            (
+                RespValue::BulkString(Some(b"a".to_vec())),
+                RespValue::Integer(1)
+            ),
+            (
+                RespValue::BulkString(Some(b"b".to_vec())),
+                RespValue::Integer(2)
+            ),
+        ])
+    );
+    assert_eq!(
+        eval(&mut executor, "redis.setresp(3) return false"),
+        RespValue::Boolean(false)
+    );
+    assert_eq!(
+        eval(&mut executor, "return false"),
+        RespValue::BulkString(None)
+    );
+}
+
+#[test]
+fn test_read_only_scripts_refuse_writes() {
+    let mut executor = CommandExecutor::new();
+    eval(&mut executor, "return redis.call('SET', 'k', 'v')");
+    let eval_ro = |script: &str| Command::EvalRo {
+        script: script.to_string(),
+        keys: vec!["k".to_string()],
+        args: vec![],
+    };
+    assert!(eval_ro("return 1").is_read_only());
+
+    assert_eq!(
+        executor.execute(&eval_ro("return redis.call('GET', KEYS[1])")),
+        RespValue::BulkString(Some(b"v".to_vec()))
+    );
+    let RespValue::Error(e) = executor.execute(&eval_ro("return redis.call('DEL', KEYS[1])"))
+    else {
+        panic!("a write from EVAL_RO must fail");
+    };
+    assert!(e.contains("Write commands are not allowed from read-only scripts."));
+    assert_eq!(
+        executor.execute(&eval_ro(
+            "return redis.pcall('INCR', KEYS[1])['err'] .. ' ' .. redis.call('GET', KEYS[1])"
+        )),
+        RespValue::BulkString(Some(
+            b"Write commands are not allowed from read-only scripts. v".to_vec()
+        ))
+    );
+
+    // EVALSHA_RO runs a cached script under the same rule
+    let RespValue::BulkString(Some(sha1)) = executor.execute(&Command::ScriptLoad(
+        "return redis.call('DEL', KEYS[1])".into(),
+    )) else {
+        panic!("SCRIPT LOAD replies the SHA1");
+    };
+    let evalsha_ro = Command::EvalShaRo {
+        sha1: String::from_utf8(sha1).expect("hex"),
+        keys: vec!["k".to_string()],
+        args: vec![],
+    };
+    assert!(matches!(executor.execute(&evalsha_ro), RespValue::Error(_)));
+    assert_eq!(
+        executor.execute(&Command::Get("k".to_string())),
+        RespValue::BulkString(Some(b"v".to_vec()))
+    );
+}
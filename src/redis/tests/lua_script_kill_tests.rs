@@ -0,0 +1,78 @@
+//! SCRIPT KILL and busy-reply-threshold tests
+//!
+//! A script only stops between instructions, when the hook sees a kill from
+//! another thread; these tests kill from a second thread, as a second
+//! connection would.
+
+use super::super::script_monitor::SCRIPT_KILLED_ERROR;
+use super::super::{Command, CommandExecutor, RespValue};
+
+fn eval(script: &str) -> Command {
+    Command::Eval {
+        script: script.to_string(),
+        keys: vec![],
+        args: vec![],
+    }
+}
+
+#[test]
+fn test_script_kill_without_a_script_is_notbusy() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(
+        executor.execute(&Command::ScriptKill),
+        RespValue::err("NOTBUSY No scripts in execution right now.")
+    );
+}
+
+#[test]
+fn test_lua_time_limit_sets_the_busy_threshold() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(executor.script_monitor().threshold_ms(), 5000);
+    assert_eq!(
+        executor.execute(&Command::ConfigSet(
+            "lua-time-limit".to_string(),
+            "250".to_string()
+        )),
+        RespValue::ok()
+    );
+    assert_eq!(executor.script_monitor().threshold_ms(), 250);
+    assert_eq!(
+        executor.execute(&Command::ConfigGet(
+            vec!["busy-reply-threshold".to_string()]
+        )),
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(Some(b"busy-reply-threshold".to_vec())),
+            RespValue::BulkString(Some(b"250".to_vec())),
+        ]))
+    );
+    assert!(matches!(
+        executor.execute(&Command::ConfigSet(
+            "busy-reply-threshold".to_string(),
+            "soon".to_string()
+        )),
+        RespValue::Error(_)
+    ));
+}
+
+#[test]
+fn test_script_kill_stops_an_endless_script() {
+    let mut executor = CommandExecutor::new();
+    let monitor = executor.script_monitor().clone();
+    monitor.set_threshold_ms(0);
+
+    let killer = std::thread::spawn(move || loop {
+        if monitor.kill() == RespValue::ok() {
+            return;
+        }
+        std::thread::yield_now();
+    });
+    assert_eq!(
+        executor.execute(&eval("while true do end")),
+        RespValue::err(SCRIPT_KILLED_ERROR)
+    );
+    killer.join().expect("killer thread");
+
+    // The kill ends with the script; the next one runs normally
+    assert_eq!(executor.script_monitor().num_running(), 0);
+    assert_eq!(executor.execute(&eval("return 1")), RespValue::Integer(1));
+}
@@ -0,0 +1,161 @@
+//! MEMORY USAGE, STATS and DOCTOR over the per-value size estimates
+
+use super::super::{CommandExecutor, MemoryStats, RespValue};
+use super::run;
+
+fn usage(executor: &mut CommandExecutor, parts: &[&str]) -> i64 {
+    match run(executor, parts) {
+        RespValue::Integer(n) => n,
+        other => panic!("expected an integer from {:?}, got {:?}", parts, other),
+    }
+}
+
+#[test]
+fn test_usage_follows_the_sds_and_object_layout() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "hello"]);
+    run(&mut executor, &["SET", "n", "12345"]);
+    run(&mut executor, &["SET", "big", &"x".repeat(300)]);
+
+    // key SDS (1+1+1) + keyspace entry 24 + object 16 + value SDS (1+5+1)
+    assert_eq!(
+        usage(&mut executor, &["MEMORY", "USAGE", "k"]),
+        3 + 24 + 16 + 7
+    );
+    // Integers live in the object itself
+    assert_eq!(usage(&mut executor, &["MEMORY", "USAGE", "n"]), 3 + 24 + 16);
+    // 300 bytes need the 16-bit length header
+    assert_eq!(
+        usage(&mut executor, &["memory", "usage", "big"]),
+        5 + 24 + 16 + (5 + 300 + 1)
+    );
+    assert_eq!(
+        run(&mut executor, &["MEMORY", "USAGE", "missing"]),
+        RespValue::BulkString(None)
+    );
+}
+
+#[test]
+fn test_usage_charges_collection_nodes() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["RPUSH", "l", "a", "b", "c"]);
+    run(&mut executor, &["SADD", "s", "a", "b", "c"]);
+    run(&mut executor, &["HSET", "h", "f", "v"]);
+    run(&mut executor, &["ZADD", "z", "1", "a"]);
+
+    let key = 2 + 1 + 24; // one-byte key name plus its keyspace entry
+    assert_eq!(
+        usage(&mut executor, &["MEMORY", "USAGE", "l"]),
+        key + 16 + 3 * (24 + 3)
+    );
+    assert_eq!(
+        usage(&mut executor, &["MEMORY", "USAGE", "s"]),
+        key + 16 + 3 * (24 + 3)
+    );
+    assert_eq!(
+        usage(&mut executor, &["MEMORY", "USAGE", "h"]),
+        key + 16 + 24 + 3 + 3
+    );
+    assert_eq!(
+        usage(&mut executor, &["MEMORY", "USAGE", "z"]),
+        key + 16 + 48 + 24 + 3
+    );
+}
+
+#[test]
+fn test_samples_extrapolate_from_the_first_elements() {
+    let mut executor = CommandExecutor::new();
+    let mut push = vec!["RPUSH", "l", "aaaaaaaaaa"];
+    push.extend(["a"; 9]);
+    run(&mut executor, &push);
+
+    let all = usage(&mut executor, &["MEMORY", "USAGE", "l", "SAMPLES", "0"]);
+    let one = usage(&mut executor, &["MEMORY", "USAGE", "l", "SAMPLES", "1"]);
+    let default = usage(&mut executor, &["MEMORY", "USAGE", "l"]);
+
+    let key = 2 + 1 + 24 + 16;
+    assert_eq!(all, key + 24 + 12 + 9 * (24 + 3));
+    // The long head element stands in for all ten
+    assert_eq!(one, key + 10 * (24 + 12));
+    assert!(
+        all < default && default < one,
+        "{} {} {}",
+        all,
+        default,
+        one
+    );
+}
+
+#[test]
+fn test_usage_argument_errors() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "v"]);
+
+    let cases: [(&[&str], &str); 5] = [
+        (
+            &["MEMORY", "USAGE", "k", "SAMPLES", "x"],
+            "ERR value is not an integer",
+        ),
+        (
+            &["MEMORY", "USAGE", "k", "SAMPLES", "-1"],
+            "ERR syntax error",
+        ),
+        (&["MEMORY", "USAGE", "k", "COUNT", "1"], "ERR syntax error"),
+        (
+            &["MEMORY", "USAGE"],
+            "ERR wrong number of arguments for 'memory|usage'",
+        ),
+        (&["MEMORY", "PURGE"], "ERR unknown subcommand 'purge'"),
+    ];
+    for (parts, expected) in cases {
+        match run(&mut executor, parts) {
+            RespValue::Error(e) => assert!(e.starts_with(expected), "{:?}: {}", parts, e),
+            other => panic!("{:?}: expected an error, got {:?}", parts, other),
+        }
+    }
+}
+
+#[test]
+fn test_stats_sum_the_dataset_and_merge_across_shards() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(executor.memory_stats(), MemoryStats::default());
+
+    run(&mut executor, &["SET", "a", "hello"]);
+    run(&mut executor, &["RPUSH", "b", "x", "y"]);
+    run(&mut executor, &["EXPIRE", "a", "100"]);
+
+    let stats = executor.memory_stats();
+    assert_eq!(stats.keys, 2);
+    assert_eq!(stats.dataset_bytes, (3 + 16 + 7) + (3 + 16 + 2 * (24 + 3)));
+    // Two entries and two buckets; one entry and one bucket
+    assert_eq!(stats.main_overhead_bytes, 2 * 24 + 2 * 8);
+    assert_eq!(stats.expires_overhead_bytes, 24 + 8);
+
+    // The reply reads back as the same stats, so shards can be summed
+    let reply = run(&mut executor, &["MEMORY", "STATS"]);
+    assert_eq!(MemoryStats::from_resp(&reply), Some(stats));
+    let mut merged = stats;
+    merged.merge(&stats);
+    assert_eq!(merged.keys, 4);
+    assert_eq!(merged.total_bytes(), 2 * stats.total_bytes());
+}
+
+#[test]
+fn test_doctor_and_help() {
+    let mut executor = CommandExecutor::new();
+    match run(&mut executor, &["MEMORY", "DOCTOR"]) {
+        RespValue::BulkString(Some(report)) => {
+            let report = String::from_utf8(report).unwrap();
+            assert!(
+                report.contains("empty or is using very little memory"),
+                "{}",
+                report
+            );
+        }
+        other => panic!("expected a report, got {:?}", other),
+    }
+    match run(&mut executor, &["MEMORY", "HELP"]) {
+        RespValue::Array(Some(lines)) => assert!(lines.len() > 4),
+        other => panic!("expected help lines, got {:?}", other),
+    }
+}
@@ -3,13 +3,43 @@
 //! Split from the original monolithic tests.rs for better organization
 //! and to comply with 500-line file limit.
 
+mod acl_command_tests;
+mod bitmap_command_tests;
+mod blocking_tests;
+mod bulk_load_tests;
+mod command_info_tests;
 mod command_parser_tests;
+mod config_tests;
+mod copy_command_tests;
+mod debug_buggify_tests;
+mod debug_command_tests;
+mod direct_path_tests;
+mod dump_restore_tests;
+mod eviction_tests;
+mod fixture_tests;
+mod expire_parser_tests;
+mod hash_command_tests;
+mod hash_ttl_tests;
+mod keystats_tests;
+mod latency_tests;
 mod list_command_tests;
+mod list_mutator_tests;
+mod lolwut_tests;
+mod memory_tests;
 mod resp_parser_tests;
 mod scan_tests;
+mod server_restart_tests;
+mod set_command_tests;
 mod set_option_tests;
+mod snapshot_read_tests;
+mod sort_tests;
 mod sorted_set_command_tests;
+mod stream_command_tests;
+mod stream_group_tests;
 mod transaction_tests;
+mod unlink_touch_tests;
+mod zrange_tests;
+mod zset_algebra_tests;
 
 // Lua scripting tests (feature-gated)
 #[cfg(feature = "lua")]
@@ -17,4 +47,44 @@ mod lua_basic_tests;
 #[cfg(feature = "lua")]
 mod lua_command_tests;
 #[cfg(feature = "lua")]
+mod lua_function_tests;
+#[cfg(feature = "lua")]
 mod lua_redis_call_tests;
+#[cfg(feature = "lua")]
+mod lua_script_kill_tests;
+
+use super::{Command, CommandExecutor, RespValue, RespValueZeroCopy};
+use bytes::Bytes;
+
+/// Parse with both parsers, which must agree, then execute
+fn run_bytes(executor: &mut CommandExecutor, parts: &[&[u8]]) -> RespValue {
+    let resp = RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.to_vec())))
+            .collect(),
+    ));
+    let zero_copy = RespValueZeroCopy::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValueZeroCopy::BulkString(Some(Bytes::copy_from_slice(p))))
+            .collect(),
+    ));
+    let parsed = Command::from_resp(&resp);
+    assert_eq!(
+        format!("{:?}", parsed),
+        format!("{:?}", Command::from_resp_zero_copy(&zero_copy)),
+        "parsers disagree on {:?}",
+        parts
+    );
+    match parsed {
+        Ok(cmd) => executor.execute(&cmd),
+        Err(e) => RespValue::err(e),
+    }
+}
+
+/// `run_bytes` for UTF-8 arguments
+fn run(executor: &mut CommandExecutor, parts: &[&str]) -> RespValue {
+    let parts: Vec<&[u8]> = parts.iter().map(|p| p.as_bytes()).collect();
+    run_bytes(executor, &parts)
+}
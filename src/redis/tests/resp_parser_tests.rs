@@ -1,6 +1,6 @@
 //! RESP parser tests - verify zero-copy parser equivalence with original
 
-use super::super::{RespCodec, RespParser, RespValue, RespValueZeroCopy};
+use super::super::{ProtocolLimits, RespCodec, RespParser, RespValue, RespValueZeroCopy};
 use bytes::BytesMut;
 
 fn test_parse_equivalence(input: &[u8]) {
@@ -89,3 +89,113 @@ fn test_set_get_commands() {
     test_parse_equivalence(b"*3\r\n$3\r\nSET\r\n$5\r\nmykey\r\n$7\r\nmyvalue\r\n");
     test_parse_equivalence(b"*2\r\n$3\r\nGET\r\n$5\r\nmykey\r\n");
 }
+
+#[test]
+fn test_resp3_types_parse_and_encode_as_resp2() {
+    let bulk = |s: &str| RespValue::BulkString(Some(s.as_bytes().to_vec()));
+    let input = b"%2\r\n$1\r\na\r\n,1.5\r\n$1\r\nb\r\n#t\r\n";
+    let (map, consumed) = RespParser::parse(input).unwrap();
+    assert_eq!(consumed, input.len());
+    assert_eq!(
+        map,
+        RespValue::Map(vec![
+            (bulk("a"), RespValue::Double(1.5)),
+            (bulk("b"), RespValue::Boolean(true)),
+        ])
+    );
+    assert_eq!(
+        RespParser::encode(&map),
+        b"*4\r\n$1\r\na\r\n$3\r\n1.5\r\n$1\r\nb\r\n:1\r\n".to_vec()
+    );
+
+    let (set, _) = RespParser::parse(b"~3\r\n_\r\n(12345678901234567890\r\n,-inf\r\n").unwrap();
+    assert_eq!(
+        RespParser::encode(&set),
+        b"*3\r\n$-1\r\n$20\r\n12345678901234567890\r\n$4\r\n-inf\r\n".to_vec()
+    );
+    assert!(RespParser::parse(b"#x\r\n").is_err());
+}
+
+#[test]
+fn test_resp3_push_attribute_and_verbatim_round_trip() {
+    let bulk = |s: &str| RespValue::BulkString(Some(s.as_bytes().to_vec()));
+    let values = [
+        RespValue::Push(vec![bulk("message"), bulk("news"), bulk("hi")]),
+        RespValue::Attribute(
+            vec![(bulk("ttl"), RespValue::Integer(3600))],
+            Box::new(RespValue::Array(Some(vec![RespValue::Double(2.5)]))),
+        ),
+        RespValue::Verbatim {
+            format: *b"txt",
+            text: b"Some text\r\nover two lines".to_vec(),
+        },
+        RespValue::Push(vec![bulk("invalidate"), RespValue::Null]),
+    ];
+    for value in values {
+        let encoded = RespParser::encode_resp3(&value);
+        assert_eq!(RespParser::parse(&encoded), Ok((value, encoded.len())));
+    }
+
+    let (push, _) = RespParser::parse(b">2\r\n$10\r\ninvalidate\r\n*1\r\n$1\r\nk\r\n").unwrap();
+    assert_eq!(
+        RespParser::encode(&push),
+        b"*2\r\n$10\r\ninvalidate\r\n*1\r\n$1\r\nk\r\n".to_vec()
+    );
+    // RESP2 clients see the reply without its attributes
+    let (attributed, consumed) = RespParser::parse(b"|1\r\n+key\r\n+v\r\n:7\r\n").unwrap();
+    assert_eq!(consumed, 18);
+    assert_eq!(RespParser::encode(&attributed), b":7\r\n".to_vec());
+    let (verbatim, _) = RespParser::parse(b"=8\r\nmkd:# hi\r\n").unwrap();
+    assert_eq!(RespParser::encode(&verbatim), b"$4\r\n# hi\r\n".to_vec());
+
+    assert!(RespParser::parse(b"=3\r\ntxt\r\n").is_err());
+    assert!(RespParser::parse(b"|1\r\n+key\r\n+v\r\n").is_err());
+}
+
+#[test]
+fn test_protocol_limits_reject_headers_before_their_payload() {
+    let limits = ProtocolLimits {
+        max_bulk_len: 16,
+        max_multibulk_len: 4,
+        max_inline_len: 8,
+        max_depth: 2,
+    };
+    let codec = |input: &[u8]| RespCodec::parse_with_limits(&mut BytesMut::from(input), &limits);
+    let error = |message: &str| format!("Protocol error: {}", message);
+
+    assert_eq!(codec(b"$17\r\n"), Err(error("invalid bulk length")));
+    assert_eq!(codec(b"$-2\r\n"), Err(error("invalid bulk length")));
+    assert_eq!(codec(b"*5\r\n"), Err(error("invalid multibulk length")));
+    assert_eq!(codec(b"*1\r\n*1\r\n*1\r\n"), Err(error("too deep nesting")));
+    assert_eq!(codec(b"+012345678"), Err(error("too big inline request")));
+    assert_eq!(codec(b"+01234567"), Ok(None));
+    assert_eq!(codec(b"+01234567\r"), Ok(None));
+    assert_eq!(codec(b"+01234567\rx"), Err(error("too big inline request")));
+    assert_eq!(codec(b"#t\r\n"), Err(error("Unknown RESP type: #")));
+    assert!(matches!(
+        codec(b"*1\r\n*1\r\n$16\r\n0123456789abcdef\r\n"),
+        Ok(Some(_))
+    ));
+
+    // The defaults allow what Redis allows; a large header alone allocates nothing
+    let parse = |input: &[u8]| RespCodec::parse(&mut BytesMut::from(input));
+    assert_eq!(parse(b"*2147483647\r\n"), Ok(None));
+    assert_eq!(
+        parse(b"*2147483648\r\n"),
+        Err(error("invalid multibulk length"))
+    );
+    assert_eq!(parse(b"$536870912\r\n"), Ok(None));
+    assert_eq!(parse(b"$536870913\r\n"), Err(error("invalid bulk length")));
+
+    let strict = |input: &[u8]| RespParser::parse_with_limits(input, &limits).map(|_| ());
+    assert_eq!(strict(b"$17\r\n"), Err(error("invalid bulk length")));
+    assert_eq!(strict(b"%3\r\n"), Err(error("invalid multibulk length")));
+    assert_eq!(
+        strict(b"|1\r\n+k\r\n+v\r\n|1\r\n+k\r\n+v\r\n:1\r\n"),
+        Ok(())
+    );
+    assert_eq!(
+        strict(b"|1\r\n+k\r\n+v\r\n|1\r\n+k\r\n+v\r\n|1\r\n+k\r\n+v\r\n:1\r\n"),
+        Err(error("too deep nesting"))
+    );
+}
@@ -29,6 +29,7 @@ fn test_scan_basic() {
         cursor: 0,
         pattern: None,
         count: Some(10),
+        type_filter: None,
     };
     let result = executor.execute(&cmd);
 
@@ -89,6 +90,7 @@ fn test_scan_with_pattern() {
         cursor: 0,
         pattern: Some("user:*".to_string()),
         count: Some(100),
+        type_filter: None,
     };
     let result = executor.execute(&cmd);
 
@@ -101,6 +103,108 @@ fn test_scan_with_pattern() {
     }
 }
 
+/// SCAN through the parser, returning the next cursor and the page
+fn scan(executor: &mut CommandExecutor, cursor: u64, options: &[&str]) -> (u64, Vec<String>) {
+    let cursor = cursor.to_string();
+    let mut parts = vec!["SCAN", cursor.as_str()];
+    parts.extend_from_slice(options);
+    let resp = RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+            .collect(),
+    ));
+    let cmd = Command::from_resp(&resp).unwrap();
+    match executor.execute(&cmd) {
+        RespValue::Array(Some(mut reply)) if reply.len() == 2 => {
+            let RespValue::Array(Some(keys)) = reply.pop().unwrap() else {
+                panic!("SCAN returned no key array");
+            };
+            let RespValue::BulkString(Some(next)) = reply.pop().unwrap() else {
+                panic!("SCAN returned no cursor");
+            };
+            let next = String::from_utf8(next).unwrap().parse().unwrap();
+            (next, key_names(keys))
+        }
+        other => panic!("SCAN returned {:?}", other),
+    }
+}
+
+#[test]
+fn test_scan_type_filter() {
+    let mut executor = CommandExecutor::new();
+    executor.set_direct("str", b"v");
+    executor.execute(&Command::RPush(
+        "list".to_string(),
+        vec![SDS::from_str("a")],
+    ));
+    executor.execute(&Command::HSet(
+        "hash".to_string(),
+        vec![(SDS::from_str("f"), SDS::from_str("v"))],
+    ));
+
+    assert_eq!(
+        scan(&mut executor, 0, &["TYPE", "list", "COUNT", "100"]),
+        (0, vec!["list".to_string()])
+    );
+    // Type names compare without case, and combine with MATCH
+    assert_eq!(
+        scan(&mut executor, 0, &["MATCH", "h*", "TYPE", "HASH"]),
+        (0, vec!["hash".to_string()])
+    );
+    assert_eq!(
+        scan(&mut executor, 0, &["MATCH", "s*", "TYPE", "hash"])
+            .1
+            .len(),
+        0
+    );
+    assert_eq!(scan(&mut executor, 0, &["TYPE", "nosuchtype"]).1.len(), 0);
+}
+
+#[test]
+fn test_scan_returns_every_stable_key_despite_writes() {
+    let mut executor = CommandExecutor::new();
+    for i in 0..300 {
+        executor.set_direct(&format!("stable:{}", i), b"v");
+        executor.set_direct(&format!("churn:{}", i), b"v");
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor = 0;
+    let mut round = 0;
+    loop {
+        let (next, keys) = scan(&mut executor, cursor, &["COUNT", "20"]);
+        assert!(keys.len() >= 20 || next == 0, "only the last page is short");
+        seen.extend(keys);
+        // Delete keys and grow the keyspace between pages, forcing rehashes
+        for i in 0..10 {
+            executor.execute(&Command::del(format!("churn:{}", round * 10 + i)));
+        }
+        for i in 0..50 {
+            executor.set_direct(&format!("new:{}:{}", round, i), b"v");
+        }
+        round += 1;
+        if next == 0 {
+            break;
+        }
+        assert!(next > cursor, "cursors only move forward");
+        cursor = next;
+    }
+
+    let mut stable: Vec<&String> = seen.iter().filter(|k| k.starts_with("stable:")).collect();
+    stable.sort();
+    stable.dedup();
+    assert_eq!(stable.len(), 300, "every stable key at least once");
+    let mut unique = seen.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(
+        unique.len(),
+        seen.len(),
+        "no key deleted and re-added, so none twice"
+    );
+}
+
 // ============================================
 // HSCAN Tests
 // ============================================
@@ -177,3 +281,99 @@ fn test_zscan_basic() {
         panic!("Expected array");
     }
 }
+
+// ============================================
+// Chunked KEYS Tests
+// ============================================
+
+fn key_names(reply: Vec<RespValue>) -> Vec<String> {
+    reply
+        .into_iter()
+        .map(|k| match k {
+            RespValue::BulkString(Some(b)) => String::from_utf8(b).unwrap(),
+            other => panic!("KEYS chunk returned {:?}", other),
+        })
+        .collect()
+}
+
+/// Every chunk of one chunked KEYS, in order
+fn keys_chunked(executor: &CommandExecutor, pattern: &str, chunk_size: usize) -> Vec<Vec<String>> {
+    let mut chunks = Vec::new();
+    let mut cursor = 0;
+    loop {
+        let (next, keys) = executor.keys_chunk(pattern, cursor, chunk_size);
+        chunks.push(key_names(keys));
+        if next == 0 {
+            return chunks;
+        }
+        cursor = next;
+    }
+}
+
+#[test]
+fn test_keys_chunks_cover_the_keyspace_once() {
+    let mut executor = CommandExecutor::new();
+    for i in 0..1000 {
+        executor.set_direct(&format!("key:{}", i), b"v");
+    }
+    executor.set_direct("other", b"v");
+
+    let chunks = keys_chunked(&executor, "key:*", 100);
+    assert_eq!(chunks.len(), 11);
+    let mut all: Vec<String> = chunks.into_iter().flatten().collect();
+    all.sort();
+    let mut expected: Vec<String> = (0..1000).map(|i| format!("key:{}", i)).collect();
+    expected.sort();
+    assert_eq!(all, expected);
+
+    // Chunking off: one pass
+    assert_eq!(keys_chunked(&executor, "*", 0).len(), 1);
+}
+
+#[test]
+fn test_keys_chunks_survive_writes_between_chunks() {
+    let mut executor = CommandExecutor::new();
+    for i in 0..500 {
+        executor.set_direct(&format!("stable:{}", i), b"v");
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor = 0;
+    let mut round = 0;
+    loop {
+        let (next, keys) = executor.keys_chunk("*", cursor, 50);
+        seen.extend(key_names(keys));
+        // Grow the keyspace enough to force rehashing between chunks
+        for i in 0..200 {
+            executor.set_direct(&format!("new:{}:{}", round, i), b"v");
+        }
+        round += 1;
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+
+    let stable: Vec<&String> = seen.iter().filter(|k| k.starts_with("stable:")).collect();
+    assert_eq!(stable.len(), 500, "every stable key exactly once");
+    let mut unique = seen.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), seen.len(), "no key returned twice");
+}
+
+#[test]
+fn test_keys_chunk_size_config() {
+    let mut executor = CommandExecutor::new();
+    assert_eq!(executor.keys_chunk_size(), 0);
+    assert_eq!(
+        executor.execute(&Command::ConfigSet("keys-chunk-size".into(), "5000".into())),
+        RespValue::ok()
+    );
+    assert_eq!(executor.keys_chunk_size(), 5000);
+    assert!(matches!(
+        executor.execute(&Command::ConfigSet("keys-chunk-size".into(), "-1".into())),
+        RespValue::Error(_)
+    ));
+    assert_eq!(executor.keys_chunk_size(), 5000);
+}
@@ -0,0 +1,133 @@
+//! Simulated server crashes and restarts - memory is lost, the disk keeps
+//! the last synced snapshot (or nothing, on ephemeral storage), and the
+//! restart loads it back
+
+use super::super::{RedisClient, RedisServer, RespParser, RespValue};
+use crate::simulator::{
+    Duration, IdlePolicy, Simulation, SimulationConfig, StorageMode, VirtualTime,
+};
+
+struct World {
+    sim: Simulation,
+    server: RedisServer,
+    client: RedisClient,
+}
+
+impl World {
+    fn new() -> Self {
+        // Tick while idle so the clock reaches each `run_until` target, as
+        // a restart's view of expired keys depends on it
+        let mut sim = Simulation::new(SimulationConfig {
+            idle_policy: IdlePolicy::AdvanceBy(Duration::from_millis(100)),
+            ..Default::default()
+        });
+        let server_host = sim.add_host("server".to_string());
+        let client_host = sim.add_host("client".to_string());
+        World {
+            sim,
+            server: RedisServer::new(server_host).with_snapshots(Duration::from_millis(1_000)),
+            client: RedisClient::new(client_host, server_host),
+        }
+    }
+
+    fn send(&mut self, parts: &[&str]) -> u64 {
+        let resp = RespValue::Array(Some(
+            parts
+                .iter()
+                .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+                .collect(),
+        ));
+        self.client
+            .send_command(&mut self.sim, RespParser::encode(&resp))
+    }
+
+    fn run_until(&mut self, ms: u64) {
+        let World {
+            sim,
+            server,
+            client,
+        } = self;
+        sim.run_until(VirtualTime::from_millis(ms), |sim, event| {
+            server.handle_event(sim, event);
+            client.handle_event(event);
+        });
+    }
+
+    fn reply(&self, request_id: u64) -> Option<&RespValue> {
+        self.client.get_response(request_id)
+    }
+}
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+/// Two keys snapshotted at 1000ms, then a third written after it
+fn world_with_snapshot() -> World {
+    let mut world = World::new();
+    world.send(&["SET", "a", "1"]);
+    world.send(&["SET", "b", "2", "PX", "5000"]);
+    world.run_until(1_100);
+    let late = world.send(&["SET", "c", "3"]);
+    world.run_until(1_200);
+    assert_eq!(world.reply(late), Some(&RespValue::ok()));
+    world
+}
+
+#[test]
+fn test_restart_recovers_the_last_snapshot() {
+    let mut world = world_with_snapshot();
+    world.server.crash(&mut world.sim, StorageMode::Persistent);
+    let lost = world.send(&["GET", "a"]);
+    world.run_until(1_300);
+    assert_eq!(world.reply(lost), None, "a crashed server never replies");
+
+    let report = world.server.restart(&mut world.sim).unwrap();
+    assert_eq!(report.loaded, 2);
+    let a = world.send(&["GET", "a"]);
+    let c = world.send(&["GET", "c"]);
+    let ttl = world.send(&["PTTL", "b"]);
+    world.run_until(1_400);
+    assert_eq!(world.reply(a), Some(&bulk("1")));
+    assert_eq!(
+        world.reply(c),
+        Some(&RespValue::BulkString(None)),
+        "written after the snapshot, lost with memory"
+    );
+    // The deadline survives the restart rather than starting over
+    match world.reply(ttl) {
+        Some(RespValue::Integer(ms)) => assert!((3_000..4_000).contains(ms), "PTTL {}", ms),
+        other => panic!("PTTL returned {:?}", other),
+    }
+}
+
+#[test]
+fn test_restart_drops_keys_that_expired_while_down() {
+    let mut world = world_with_snapshot();
+    world.server.crash(&mut world.sim, StorageMode::Persistent);
+    world.run_until(8_000);
+
+    let report = world.server.restart(&mut world.sim).unwrap();
+    assert_eq!((report.loaded, report.skipped), (1, 1));
+
+    // Snapshotting resumes after the restart
+    let write = world.send(&["SET", "d", "4"]);
+    world.run_until(9_500);
+    assert_eq!(world.reply(write), Some(&RespValue::ok()));
+    world.server.crash(&mut world.sim, StorageMode::Persistent);
+    assert_eq!(world.server.restart(&mut world.sim).unwrap().loaded, 2);
+}
+
+#[test]
+fn test_ephemeral_storage_restarts_empty() {
+    let mut world = world_with_snapshot();
+    assert!(world.server.disk().read("dump.rdb").is_some());
+    world.server.crash(&mut world.sim, StorageMode::Ephemeral);
+    assert!(world.server.is_crashed());
+
+    let report = world.server.restart(&mut world.sim).unwrap();
+    assert_eq!(report.loaded, 0);
+    let size = world.send(&["DBSIZE"]);
+    world.run_until(1_300);
+    assert_eq!(world.reply(size), Some(&RespValue::Integer(0)));
+}
@@ -0,0 +1,247 @@
+//! Set command tests - SMISMEMBER, SRANDMEMBER, SPOP, SINTERCARD, plus
+//! RANDOMKEY, which draws from the same seeded RNG
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use std::collections::HashSet;
+
+fn ints(values: &[i64]) -> RespValue {
+    RespValue::Array(Some(
+        values.iter().map(|&n| RespValue::Integer(n)).collect(),
+    ))
+}
+
+fn members(reply: RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                other => panic!("expected a member, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+/// Executor holding `s1 = {a..e}`, `s2 = {c..g}` and the string `str`
+fn with_sets() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SADD", "s1", "a", "b", "c", "d", "e"]);
+    run(&mut executor, &["SADD", "s2", "c", "d", "e", "f", "g"]);
+    run(&mut executor, &["SET", "str", "x"]);
+    executor
+}
+
+#[test]
+fn test_smismember() {
+    let mut executor = with_sets();
+    assert_eq!(
+        run(&mut executor, &["SMISMEMBER", "s1", "a", "z", "e", "a"]),
+        ints(&[1, 0, 1, 1])
+    );
+    assert_eq!(
+        run(&mut executor, &["SMISMEMBER", "missing", "a", "b"]),
+        ints(&[0, 0])
+    );
+    assert_eq!(
+        run(&mut executor, &["SMISMEMBER", "str", "a"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+    assert_eq!(
+        run(&mut executor, &["SMISMEMBER", "s1"]),
+        RespValue::err("ERR wrong number of arguments for 'smismember' command")
+    );
+}
+
+#[test]
+fn test_srandmember_counts() {
+    let mut executor = with_sets();
+    let all: HashSet<String> = ["a", "b", "c", "d", "e"].map(String::from).into();
+
+    match run(&mut executor, &["SRANDMEMBER", "s1"]) {
+        RespValue::BulkString(Some(bytes)) => {
+            assert!(all.contains(&String::from_utf8(bytes).unwrap()))
+        }
+        other => panic!("SRANDMEMBER returned {:?}", other),
+    }
+
+    // Positive counts are distinct and clamped to the cardinality
+    let three = members(run(&mut executor, &["SRANDMEMBER", "s1", "3"]));
+    assert_eq!(three.len(), 3);
+    assert_eq!(three.iter().collect::<HashSet<_>>().len(), 3);
+    assert!(three.iter().all(|m| all.contains(m)));
+    let clamped: HashSet<String> = members(run(&mut executor, &["SRANDMEMBER", "s1", "50"]))
+        .into_iter()
+        .collect();
+    assert_eq!(clamped, all);
+
+    // Negative counts return exactly |count| members, repeats allowed
+    let repeated = members(run(&mut executor, &["SRANDMEMBER", "s1", "-20"]));
+    assert_eq!(repeated.len(), 20);
+    assert!(repeated.iter().all(|m| all.contains(m)));
+
+    assert_eq!(
+        run(&mut executor, &["SRANDMEMBER", "s1", "0"]),
+        RespValue::Array(Some(vec![]))
+    );
+    // SRANDMEMBER never modifies the set
+    assert_eq!(run(&mut executor, &["SCARD", "s1"]), RespValue::Integer(5));
+}
+
+#[test]
+fn test_srandmember_missing_key_and_errors() {
+    let mut executor = with_sets();
+    assert_eq!(
+        run(&mut executor, &["SRANDMEMBER", "missing"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(
+        run(&mut executor, &["SRANDMEMBER", "missing", "-3"]),
+        RespValue::Array(Some(vec![]))
+    );
+    assert_eq!(
+        run(&mut executor, &["SRANDMEMBER", "str", "2"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+    assert_eq!(
+        run(&mut executor, &["SRANDMEMBER", "s1", "two"]),
+        RespValue::err("ERR value is not an integer or out of range")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["SRANDMEMBER", "s1", "-9223372036854775808"]
+        ),
+        RespValue::err(
+            "ERR value is out of range, value must between \
+             -9223372036854775807 and 9223372036854775807"
+        )
+    );
+}
+
+#[test]
+fn test_srandmember_is_reproducible_under_a_seed() {
+    let sample = |seed: u64| {
+        let mut executor = with_sets();
+        executor.set_rng_seed(seed);
+        (0..5)
+            .map(|_| members(run(&mut executor, &["SRANDMEMBER", "s1", "-4"])))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sample(7), sample(7));
+    assert_ne!(sample(7), sample(8));
+}
+
+#[test]
+fn test_spop_is_reproducible_under_a_seed() {
+    let pop = |seed: u64| {
+        let mut executor = with_sets();
+        executor.set_rng_seed(seed);
+        let single = match run(&mut executor, &["SPOP", "s1"]) {
+            RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+            other => panic!("SPOP replied {:?}", other),
+        };
+        let rest = members(run(&mut executor, &["SPOP", "s1", "2"]));
+        (
+            single,
+            rest,
+            members(run(&mut executor, &["SMEMBERS", "s1"])).len(),
+        )
+    };
+    let (single, rest, left) = pop(7);
+    assert_eq!(pop(7), (single.clone(), rest.clone(), left));
+    assert_eq!(left, 2);
+    assert!(!rest.contains(&single), "popped members leave the set");
+    assert!((0..16).any(|seed| pop(seed).0 != single));
+}
+
+#[test]
+fn test_randomkey_is_reproducible_under_a_seed() {
+    let picks = |seed: u64| {
+        let mut executor = with_sets();
+        executor.set_rng_seed(seed);
+        (0..8)
+            .map(|_| run(&mut executor, &["RANDOMKEY"]))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(picks(3), picks(3));
+    let seen: HashSet<String> = (0..4)
+        .flat_map(picks)
+        .map(|pick| format!("{:?}", pick))
+        .collect();
+    assert_eq!(seen.len(), 3, "every key can be picked");
+
+    let mut empty = CommandExecutor::new();
+    assert_eq!(run(&mut empty, &["RANDOMKEY"]), RespValue::BulkString(None));
+}
+
+#[test]
+fn test_sintercard_with_limit() {
+    let mut executor = with_sets();
+    assert_eq!(
+        run(&mut executor, &["SINTERCARD", "2", "s1", "s2"]),
+        RespValue::Integer(3)
+    );
+    assert_eq!(
+        run(&mut executor, &["SINTERCARD", "1", "s1"]),
+        RespValue::Integer(5)
+    );
+    for (limit, expected) in [("0", 3), ("2", 2), ("3", 3), ("10", 3)] {
+        assert_eq!(
+            run(
+                &mut executor,
+                &["SINTERCARD", "2", "s1", "s2", "limit", limit]
+            ),
+            RespValue::Integer(expected),
+            "LIMIT {}",
+            limit
+        );
+    }
+}
+
+#[test]
+fn test_sintercard_missing_and_wrong_type_keys() {
+    let mut executor = with_sets();
+    assert_eq!(
+        run(&mut executor, &["SINTERCARD", "2", "s1", "missing"]),
+        RespValue::Integer(0)
+    );
+    // Keys are checked in order: a missing key answers before a later WRONGTYPE
+    assert_eq!(
+        run(&mut executor, &["SINTERCARD", "2", "missing", "str"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["SINTERCARD", "2", "str", "missing"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+}
+
+#[test]
+fn test_sintercard_parse_errors() {
+    let mut executor = with_sets();
+    for (args, error) in [
+        (vec!["0", "s1"], "ERR numkeys should be greater than 0"),
+        (vec!["-1", "s1"], "ERR numkeys should be greater than 0"),
+        (
+            vec!["3", "s1", "s2"],
+            "ERR Number of keys can't be greater than number of args",
+        ),
+        (
+            vec!["2", "s1", "s2", "LIMIT", "-1"],
+            "ERR LIMIT can't be negative",
+        ),
+        (vec!["2", "s1", "s2", "LIMIT"], "ERR syntax error"),
+        (vec!["1", "s1", "s2"], "ERR syntax error"),
+    ] {
+        let mut parts = vec!["SINTERCARD"];
+        parts.extend(args.iter().copied());
+        assert_eq!(
+            run(&mut executor, &parts),
+            RespValue::err(error),
+            "{:?}",
+            args
+        );
+    }
+}
@@ -271,3 +271,44 @@ fn test_set_px_sets_expiration_milliseconds() {
     executor.set_time(VirtualTime::from_millis(600));
     assert_eq!(executor.execute(&get_cmd), RespValue::BulkString(None));
 }
+
+#[test]
+fn test_set_rejects_expire_overflowing_now() {
+    let mut executor = CommandExecutor::new();
+    executor.set_time(VirtualTime::from_millis(1_000));
+    let set = |ex, px| Command::Set {
+        key: "mykey".to_string(),
+        value: SDS::from_str("v"),
+        ex,
+        px,
+        exat: None,
+        pxat: None,
+        nx: false,
+        xx: false,
+        get: false,
+        keepttl: false,
+    };
+
+    // now + the expire overflows i64, as Redis's getExpireMillisecondsOrReply checks
+    let invalid = RespValue::err("ERR invalid expire time in 'set' command");
+    assert_eq!(executor.execute(&set(None, Some(i64::MAX))), invalid);
+    assert_eq!(executor.execute(&set(Some(i64::MAX / 1000), None)), invalid);
+    assert_eq!(
+        executor.execute(&Command::Pttl("mykey".to_string())),
+        RespValue::Integer(-2)
+    );
+
+    // The largest accepted deadline reports its TTL without overflowing
+    assert_eq!(
+        executor.execute(&set(None, Some(i64::MAX - 1_000))),
+        RespValue::simple("OK")
+    );
+    assert_eq!(
+        executor.execute(&Command::Pttl("mykey".to_string())),
+        RespValue::Integer(i64::MAX - 1_000)
+    );
+    assert_eq!(
+        executor.execute(&Command::Ttl("mykey".to_string())),
+        RespValue::Integer((i64::MAX - 1_000) / 1000 + 1)
+    );
+}
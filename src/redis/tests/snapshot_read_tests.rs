@@ -0,0 +1,108 @@
+//! Read-only snapshot queries - KeyspaceSnapshot and DEBUG SNAPSHOT-READ
+
+use super::super::{Command, CommandExecutor, RespValue, SDS};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+#[test]
+fn test_snapshot_is_unaffected_by_later_writes() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["MSET", "a", "1", "b", "2"]);
+    run(&mut executor, &["RPUSH", "l", "x", "y"]);
+    let mut snapshot = executor.snapshot();
+
+    run(&mut executor, &["SET", "a", "changed"]);
+    run(&mut executor, &["DEL", "b", "l"]);
+    run(&mut executor, &["SET", "c", "new"]);
+
+    let mget = Command::MGet(vec!["a".into(), "b".into(), "c".into()]);
+    assert_eq!(
+        snapshot.read_batch(&[mget.clone(), Command::LLen("l".into()), Command::DbSize]),
+        vec![
+            RespValue::Array(Some(vec![
+                bulk("1"),
+                bulk("2"),
+                RespValue::BulkString(None)
+            ])),
+            RespValue::Integer(2),
+            RespValue::Integer(3),
+        ]
+    );
+    // The live executor moved on
+    assert_eq!(
+        executor.execute(&mget),
+        RespValue::Array(Some(vec![
+            bulk("changed"),
+            RespValue::BulkString(None),
+            bulk("new")
+        ]))
+    );
+}
+
+#[test]
+fn test_snapshot_refuses_writes_and_keeps_its_clock() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "k", "v", "PX", "100"]);
+    let mut snapshot = executor.snapshot();
+    assert_eq!(snapshot.taken_at(), VirtualTime::from_millis(0));
+
+    executor.set_time(VirtualTime::from_millis(500));
+    assert_eq!(run(&mut executor, &["EXISTS", "k"]), RespValue::Integer(0));
+    // The key had not expired when the snapshot was taken
+    assert_eq!(snapshot.read(&Command::Get("k".into())), bulk("v"));
+
+    assert_eq!(
+        snapshot.read(&Command::set("k".into(), SDS::from_str("w"))),
+        RespValue::err("ERR 'set' is not a read-only command")
+    );
+    assert_eq!(snapshot.read(&Command::Get("k".into())), bulk("v"));
+}
+
+#[test]
+fn test_debug_snapshot_read_command() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "a", "1"]);
+    run(&mut executor, &["HSET", "h", "f", "v"]);
+
+    assert_eq!(
+        run(
+            &mut executor,
+            &[
+                "DEBUG",
+                "SNAPSHOT-READ",
+                "2",
+                "GET",
+                "a",
+                "3",
+                "HGET",
+                "h",
+                "f",
+                "1",
+                "DBSIZE"
+            ]
+        ),
+        RespValue::Array(Some(vec![bulk("1"), bulk("v"), RespValue::Integer(2)]))
+    );
+
+    assert_eq!(
+        run(
+            &mut executor,
+            &["DEBUG", "SNAPSHOT-READ", "3", "SET", "a", "2"]
+        ),
+        RespValue::err("ERR DEBUG SNAPSHOT-READ only runs read-only commands, got 'set'")
+    );
+    for args in [
+        &["DEBUG", "SNAPSHOT-READ"][..],
+        &["DEBUG", "SNAPSHOT-READ", "3", "GET", "a"],
+    ] {
+        assert_eq!(
+            run(&mut executor, args),
+            RespValue::err("ERR wrong number of arguments for 'debug|snapshot-read' command")
+        );
+    }
+    assert_eq!(run(&mut executor, &["GET", "a"]), bulk("1"));
+}
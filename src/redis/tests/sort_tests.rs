@@ -0,0 +1,152 @@
+//! SORT: numeric and ALPHA order, LIMIT, BY and GET patterns, STORE
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn bulks(values: &[Option<&str>]) -> RespValue {
+    RespValue::Array(Some(
+        values
+            .iter()
+            .map(|v| RespValue::BulkString(v.map(|v| v.as_bytes().to_vec())))
+            .collect(),
+    ))
+}
+
+fn strings(values: &[&str]) -> RespValue {
+    bulks(&values.iter().map(|v| Some(*v)).collect::<Vec<_>>())
+}
+
+#[test]
+fn test_sort_numeric_by_default_and_rejects_non_numbers() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["RPUSH", "nums", "10", "2", "-1.5", "2"]);
+    assert_eq!(
+        run(&mut executor, &["SORT", "nums"]),
+        strings(&["-1.5", "2", "2", "10"])
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "nums", "DESC", "LIMIT", "1", "2"]),
+        strings(&["2", "2"])
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "nums", "LIMIT", "3", "-1"]),
+        strings(&["10"])
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "nums", "LIMIT", "9", "2"]),
+        strings(&[])
+    );
+
+    run(&mut executor, &["RPUSH", "nums", "abc"]);
+    assert_eq!(
+        run(&mut executor, &["SORT", "nums"]),
+        RespValue::err("ERR One or more scores can't be converted into double")
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "nums", "ALPHA"]),
+        strings(&["-1.5", "10", "2", "2", "abc"])
+    );
+}
+
+#[test]
+fn test_sort_rejects_bad_options_and_types() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "s", "v"]);
+    assert_eq!(
+        run(&mut executor, &["SORT", "s"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "missing", "LIMIT", "0"]),
+        RespValue::err("ERR syntax error")
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "missing", "SIDEWAYS"]),
+        RespValue::err("ERR syntax error")
+    );
+    assert_eq!(run(&mut executor, &["SORT", "missing"]), strings(&[]));
+}
+
+#[test]
+fn test_sort_by_and_get_patterns() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SADD", "ids", "1", "2", "3"]);
+    run(&mut executor, &["SET", "weight_1", "30"]);
+    run(&mut executor, &["SET", "weight_2", "10"]);
+    run(&mut executor, &["SET", "weight_3", "20"]);
+    run(&mut executor, &["HSET", "user:1", "name", "ann"]);
+    run(&mut executor, &["HSET", "user:2", "name", "bob"]);
+
+    assert_eq!(
+        run(&mut executor, &["SORT", "ids", "BY", "weight_*"]),
+        strings(&["2", "3", "1"])
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &[
+                "SORT",
+                "ids",
+                "BY",
+                "user:*->name",
+                "ALPHA",
+                "GET",
+                "#",
+                "GET",
+                "user:*->name"
+            ]
+        ),
+        bulks(&[
+            Some("3"),
+            None,
+            Some("1"),
+            Some("ann"),
+            Some("2"),
+            Some("bob")
+        ])
+    );
+    // Without a `*` nothing is looked up; set elements still sort as bytes
+    assert_eq!(
+        run(&mut executor, &["SORT", "ids", "BY", "nosort", "DESC"]),
+        strings(&["3", "2", "1"])
+    );
+}
+
+#[test]
+fn test_sort_store_writes_a_list() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["RPUSH", "src", "3", "1", "2"]);
+    run(&mut executor, &["SET", "dest", "old", "EX", "100"]);
+
+    assert_eq!(
+        run(
+            &mut executor,
+            &["SORT", "src", "GET", "missing_*", "STORE", "dest"]
+        ),
+        RespValue::Integer(3)
+    );
+    assert_eq!(
+        run(&mut executor, &["LRANGE", "dest", "0", "-1"]),
+        strings(&["", "", ""])
+    );
+    assert_eq!(
+        run(&mut executor, &["SORT", "src", "DESC", "STORE", "dest"]),
+        RespValue::Integer(3)
+    );
+    assert_eq!(
+        run(&mut executor, &["LRANGE", "dest", "0", "-1"]),
+        strings(&["3", "2", "1"])
+    );
+    assert_eq!(run(&mut executor, &["TTL", "dest"]), RespValue::Integer(-1));
+
+    // An empty result deletes the destination
+    assert_eq!(
+        run(&mut executor, &["SORT", "missing", "STORE", "dest"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["EXISTS", "dest"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(executor.keyspace_stats().total_keys(), 1);
+}
@@ -1,4 +1,5 @@
-//! Sorted set command tests - ZCOUNT, ZRANGEBYSCORE
+//! Sorted set command tests - ZCOUNT, ZRANGEBYSCORE, ZPOPMIN, ZPOPMAX, ZINCRBY,
+//! ZMSCORE, ZRANDMEMBER
 
 use super::super::{Command, CommandExecutor, RespValue, SDS};
 
@@ -185,3 +186,255 @@ fn test_zrangebyscore_with_limit() {
         panic!("Expected array");
     }
 }
+
+// ============================================
+// ZPOPMIN / ZPOPMAX Tests
+// ============================================
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+#[test]
+fn test_zpopmin_zpopmax() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::ZAdd {
+        key: "myzset".to_string(),
+        pairs: vec![
+            (1.0, SDS::from_str("a")),
+            (2.5, SDS::from_str("b")),
+            (3.0, SDS::from_str("c")),
+        ],
+        nx: false,
+        xx: false,
+        gt: false,
+        lt: false,
+        ch: false,
+    });
+
+    let popmin = |count| Command::ZPopMin {
+        key: "myzset".to_string(),
+        count,
+    };
+    assert_eq!(
+        executor.execute(&popmin(None)),
+        RespValue::Array(Some(vec![bulk("a"), bulk("1")]))
+    );
+    assert_eq!(
+        executor.execute(&Command::ZPopMax {
+            key: "myzset".to_string(),
+            count: Some(5),
+        }),
+        RespValue::Array(Some(vec![bulk("c"), bulk("3"), bulk("b"), bulk("2.5")]))
+    );
+
+    // Popping the last member deletes the key
+    assert_eq!(
+        executor.execute(&Command::Exists(vec!["myzset".to_string()])),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        executor.execute(&popmin(Some(1))),
+        RespValue::Array(Some(vec![]))
+    );
+}
+
+// ============================================
+// ZINCRBY / ZMSCORE / ZRANDMEMBER Tests
+// ============================================
+
+fn run(executor: &mut CommandExecutor, parts: &[&str]) -> RespValue {
+    let resp = RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+            .collect(),
+    ));
+    match Command::from_resp(&resp) {
+        Ok(cmd) => executor.execute(&cmd),
+        Err(e) => RespValue::err(e),
+    }
+}
+
+fn strings(reply: RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+/// Executor holding `z = {a:1, b:2, c:3}` and the string `str`
+fn with_zset() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["ZADD", "z", "1", "a", "2", "b", "3", "c"]);
+    run(&mut executor, &["SET", "str", "x"]);
+    executor
+}
+
+#[test]
+fn test_zincrby() {
+    let mut executor = with_zset();
+    assert_eq!(
+        run(&mut executor, &["ZINCRBY", "z", "2.5", "a"]),
+        bulk("3.5")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZINCRBY", "z", "-1", "new"]),
+        bulk("-1")
+    );
+    assert_eq!(run(&mut executor, &["ZSCORE", "z", "new"]), bulk("-1"));
+    // The new score moves the member in the ordering
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "z", "0", "-1"])),
+        vec!["new", "b", "c", "a"]
+    );
+
+    // A missing key is created
+    assert_eq!(
+        run(&mut executor, &["ZINCRBY", "fresh", "4", "m"]),
+        bulk("4")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZCARD", "fresh"]),
+        RespValue::Integer(1)
+    );
+}
+
+#[test]
+fn test_zincrby_errors() {
+    let mut executor = with_zset();
+    run(&mut executor, &["ZADD", "inf", "inf", "m"]);
+    assert_eq!(
+        run(&mut executor, &["ZINCRBY", "inf", "-inf", "m"]),
+        RespValue::err("ERR resulting score is not a number (NaN)")
+    );
+    // The failed increment leaves the score alone
+    assert_eq!(run(&mut executor, &["ZSCORE", "inf", "m"]), bulk("inf"));
+
+    for increment in ["nan", "one"] {
+        assert_eq!(
+            run(&mut executor, &["ZINCRBY", "z", increment, "a"]),
+            RespValue::err("ERR value is not a valid float"),
+            "ZINCRBY {}",
+            increment
+        );
+    }
+    assert_eq!(
+        run(&mut executor, &["ZINCRBY", "str", "1", "a"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+}
+
+#[test]
+fn test_zmscore() {
+    let mut executor = with_zset();
+    assert_eq!(
+        run(&mut executor, &["ZMSCORE", "z", "c", "missing", "a"]),
+        RespValue::Array(Some(vec![
+            bulk("3"),
+            RespValue::BulkString(None),
+            bulk("1")
+        ]))
+    );
+    assert_eq!(
+        run(&mut executor, &["ZMSCORE", "nokey", "a", "b"]),
+        RespValue::Array(Some(vec![
+            RespValue::BulkString(None),
+            RespValue::BulkString(None)
+        ]))
+    );
+    assert_eq!(
+        run(&mut executor, &["ZMSCORE", "str", "a"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZMSCORE", "z"]),
+        RespValue::err("ERR wrong number of arguments for 'zmscore' command")
+    );
+}
+
+#[test]
+fn test_zrandmember_counts() {
+    let mut executor = with_zset();
+    let all = ["a", "b", "c"];
+
+    match run(&mut executor, &["ZRANDMEMBER", "z"]) {
+        RespValue::BulkString(Some(bytes)) => {
+            assert!(all.contains(&String::from_utf8(bytes).unwrap().as_str()))
+        }
+        other => panic!("ZRANDMEMBER returned {:?}", other),
+    }
+
+    // Positive counts are distinct and clamped to the cardinality
+    let mut clamped = strings(run(&mut executor, &["ZRANDMEMBER", "z", "10"]));
+    clamped.sort();
+    assert_eq!(clamped, all);
+
+    // Negative counts return exactly |count| members, repeats allowed
+    let repeated = strings(run(&mut executor, &["ZRANDMEMBER", "z", "-7"]));
+    assert_eq!(repeated.len(), 7);
+    assert!(repeated.iter().all(|m| all.contains(&m.as_str())));
+
+    // WITHSCORES pairs each member with its own score
+    let pairs = strings(run(
+        &mut executor,
+        &["ZRANDMEMBER", "z", "-4", "WITHSCORES"],
+    ));
+    assert_eq!(pairs.len(), 8);
+    for pair in pairs.chunks(2) {
+        assert_eq!(
+            run(&mut executor, &["ZSCORE", "z", &pair[0]]),
+            bulk(&pair[1])
+        );
+    }
+
+    assert_eq!(
+        run(&mut executor, &["ZRANDMEMBER", "z", "0"]),
+        RespValue::Array(Some(vec![]))
+    );
+    assert_eq!(run(&mut executor, &["ZCARD", "z"]), RespValue::Integer(3));
+}
+
+#[test]
+fn test_zrandmember_missing_key_and_errors() {
+    let mut executor = with_zset();
+    assert_eq!(
+        run(&mut executor, &["ZRANDMEMBER", "nokey"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANDMEMBER", "nokey", "2", "WITHSCORES"]),
+        RespValue::Array(Some(vec![]))
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANDMEMBER", "str"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANDMEMBER", "z", "2", "SCORES"]),
+        RespValue::err("ERR syntax error")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANDMEMBER", "z", "two"]),
+        RespValue::err("ERR value is not an integer or out of range")
+    );
+}
+
+#[test]
+fn test_zrandmember_is_reproducible_under_a_seed() {
+    let sample = |seed: u64| {
+        let mut executor = with_zset();
+        executor.set_rng_seed(seed);
+        (0..5)
+            .map(|_| strings(run(&mut executor, &["ZRANDMEMBER", "z", "-4"])))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sample(3), sample(3));
+    assert_ne!(sample(3), sample(4));
+}
@@ -0,0 +1,203 @@
+//! Stream command tests - XADD, XLEN, XRANGE, XREVRANGE, XREAD
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+/// IDs of an XRANGE-shaped reply
+fn ids(reply: &RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(entries)) => entries
+            .iter()
+            .map(|entry| match entry {
+                RespValue::Array(Some(parts)) => match &parts[0] {
+                    RespValue::BulkString(Some(id)) => String::from_utf8_lossy(id).into_owned(),
+                    other => panic!("unexpected id {:?}", other),
+                },
+                other => panic!("unexpected entry {:?}", other),
+            })
+            .collect(),
+        other => panic!("unexpected reply {:?}", other),
+    }
+}
+
+fn seeded() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    for id in ["1-0", "1-1", "2-0", "3-5"] {
+        run(&mut executor, &["XADD", "s", id, "f", id]);
+    }
+    executor
+}
+
+// ============================================
+// XADD / XLEN Tests
+// ============================================
+
+#[test]
+fn test_xadd_auto_id_uses_clock_and_stays_monotonic() {
+    let mut executor = CommandExecutor::new();
+    executor.set_simulation_start_epoch_ms(1_700_000_000_000);
+
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "*", "f", "v"]),
+        bulk("1700000000000-0")
+    );
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "*", "f", "v"]),
+        bulk("1700000000000-1")
+    );
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "1700000000000-*", "f", "v"]),
+        bulk("1700000000000-2")
+    );
+    assert_eq!(run(&mut executor, &["XLEN", "s"]), RespValue::Integer(3));
+    assert_eq!(
+        run(&mut executor, &["TYPE", "s"]),
+        RespValue::simple("stream")
+    );
+}
+
+#[test]
+fn test_xadd_rejects_stale_ids_without_creating_key() {
+    let mut executor = CommandExecutor::new();
+
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "0-0", "f", "v"]),
+        RespValue::err("ERR The ID specified in XADD must be greater than 0-0")
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "s"]), RespValue::Integer(0));
+
+    run(&mut executor, &["XADD", "s", "5-5", "f", "v"]);
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "5-5", "f", "v"]),
+        RespValue::err(
+            "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+        )
+    );
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "abc", "f", "v"]),
+        RespValue::err("ERR Invalid stream ID specified as stream command argument")
+    );
+}
+
+#[test]
+fn test_xadd_options() {
+    let mut executor = CommandExecutor::new();
+
+    assert_eq!(
+        run(&mut executor, &["XADD", "s", "NOMKSTREAM", "*", "f", "v"]),
+        RespValue::BulkString(None)
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "s"]), RespValue::Integer(0));
+
+    for id in ["1", "2", "3", "4"] {
+        run(
+            &mut executor,
+            &["XADD", "s", "MAXLEN", "~", "2", id, "f", "v"],
+        );
+    }
+    assert_eq!(run(&mut executor, &["XLEN", "s"]), RespValue::Integer(2));
+    assert_eq!(
+        ids(&run(&mut executor, &["XRANGE", "s", "-", "+"])),
+        vec!["3-0", "4-0"]
+    );
+
+    run(&mut executor, &["SET", "str", "x"]);
+    assert_eq!(
+        run(&mut executor, &["XADD", "str", "*", "f", "v"]),
+        RespValue::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+    );
+}
+
+// ============================================
+// XRANGE / XREVRANGE Tests
+// ============================================
+
+#[test]
+fn test_xrange_bounds() {
+    let mut executor = seeded();
+
+    assert_eq!(
+        ids(&run(&mut executor, &["XRANGE", "s", "-", "+"])),
+        vec!["1-0", "1-1", "2-0", "3-5"]
+    );
+    // A bare millisecond covers every sequence in it
+    assert_eq!(
+        ids(&run(&mut executor, &["XRANGE", "s", "1", "1"])),
+        vec!["1-0", "1-1"]
+    );
+    assert_eq!(
+        ids(&run(
+            &mut executor,
+            &["XRANGE", "s", "(1-0", "+", "COUNT", "2"]
+        )),
+        vec!["1-1", "2-0"]
+    );
+    assert_eq!(
+        ids(&run(&mut executor, &["XRANGE", "missing", "-", "+"])),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_xrevrange_takes_end_first() {
+    let mut executor = seeded();
+
+    assert_eq!(
+        ids(&run(
+            &mut executor,
+            &["XREVRANGE", "s", "+", "-", "COUNT", "3"]
+        )),
+        vec!["3-5", "2-0", "1-1"]
+    );
+    assert_eq!(
+        ids(&run(&mut executor, &["XREVRANGE", "s", "(3-5", "(1-0"])),
+        vec!["2-0", "1-1"]
+    );
+}
+
+// ============================================
+// XREAD Tests
+// ============================================
+
+#[test]
+fn test_xread_returns_entries_after_id() {
+    let mut executor = seeded();
+    run(&mut executor, &["XADD", "t", "9-0", "g", "w"]);
+
+    let reply = run(
+        &mut executor,
+        &["XREAD", "COUNT", "1", "STREAMS", "s", "t", "1-1", "0"],
+    );
+    let RespValue::Array(Some(streams)) = reply else {
+        panic!("expected streams, got {:?}", reply);
+    };
+    assert_eq!(streams.len(), 2);
+    let RespValue::Array(Some(first)) = &streams[0] else {
+        panic!("expected [key, entries]");
+    };
+    assert_eq!(first[0], bulk("s"));
+    assert_eq!(ids(&first[1]), vec!["2-0"]);
+}
+
+#[test]
+fn test_xread_nil_when_nothing_new() {
+    let mut executor = seeded();
+
+    assert_eq!(
+        run(
+            &mut executor,
+            &["XREAD", "STREAMS", "s", "missing", "$", "0"]
+        ),
+        RespValue::Array(None)
+    );
+    assert_eq!(
+        run(&mut executor, &["XREAD", "STREAMS", "s", "t", "0"]),
+        RespValue::err(
+            "ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified."
+        )
+    );
+}
@@ -0,0 +1,366 @@
+//! Stream consumer group tests - XGROUP, XREADGROUP, XACK, XPENDING, XCLAIM,
+//! XAUTOCLAIM
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+use crate::simulator::VirtualTime;
+
+fn bulk(s: &str) -> RespValue {
+    RespValue::BulkString(Some(s.as_bytes().to_vec()))
+}
+
+/// IDs of an XRANGE-shaped reply
+fn ids(reply: &RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(entries)) => entries
+            .iter()
+            .map(|entry| match entry {
+                RespValue::Array(Some(parts)) => match &parts[0] {
+                    RespValue::BulkString(Some(id)) => String::from_utf8_lossy(id).into_owned(),
+                    other => panic!("unexpected id {:?}", other),
+                },
+                other => panic!("unexpected entry {:?}", other),
+            })
+            .collect(),
+        other => panic!("unexpected reply {:?}", other),
+    }
+}
+
+fn seeded() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    for id in ["1-0", "1-1", "2-0", "3-5"] {
+        run(&mut executor, &["XADD", "s", id, "f", id]);
+    }
+    executor
+}
+
+/// Entries of the single stream in an XREADGROUP reply
+fn group_entries(reply: &RespValue) -> &RespValue {
+    match reply {
+        RespValue::Array(Some(streams)) if streams.len() == 1 => match &streams[0] {
+            RespValue::Array(Some(parts)) => &parts[1],
+            other => panic!("expected [key, entries], got {:?}", other),
+        },
+        other => panic!("expected one stream, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_xgroup_create_and_errors() {
+    let mut executor = seeded();
+
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "CREATE", "missing", "g", "$"]),
+        RespValue::err(
+            "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically."
+        )
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["XGROUP", "CREATE", "fresh", "g", "$", "MKSTREAM"]
+        ),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["XLEN", "fresh"]),
+        RespValue::Integer(0)
+    );
+
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "CREATE", "s", "g", "0"]),
+        RespValue::ok()
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "CREATE", "s", "g", "0"]),
+        RespValue::err("BUSYGROUP Consumer Group name already exists")
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "SETID", "s", "nope", "0"]),
+        RespValue::err("NOGROUP No such consumer group 'nope' for key name 's'")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["XREADGROUP", "GROUP", "nope", "c", "STREAMS", "s", ">"]
+        ),
+        RespValue::err(
+            "NOGROUP No such key 's' or consumer group 'nope' in XREADGROUP with GROUP option"
+        )
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "CREATECONSUMER", "s", "g", "c"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "CREATECONSUMER", "s", "g", "c"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "DESTROY", "s", "g"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "DESTROY", "s", "g"]),
+        RespValue::Integer(0)
+    );
+}
+
+#[test]
+fn test_xreadgroup_tracks_pending_until_ack() {
+    let mut executor = seeded();
+    run(&mut executor, &["XGROUP", "CREATE", "s", "g", "0"]);
+
+    let reply = run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "alice",
+            "COUNT",
+            "3",
+            "STREAMS",
+            "s",
+            ">",
+        ],
+    );
+    assert_eq!(ids(group_entries(&reply)), vec!["1-0", "1-1", "2-0"]);
+
+    // Only undelivered entries are new; history replays alice's PEL
+    let reply = run(
+        &mut executor,
+        &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"],
+    );
+    assert_eq!(ids(group_entries(&reply)), vec!["3-5"]);
+    assert_eq!(
+        run(
+            &mut executor,
+            &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"]
+        ),
+        RespValue::Array(None)
+    );
+    let reply = run(
+        &mut executor,
+        &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "0"],
+    );
+    assert_eq!(ids(group_entries(&reply)), vec!["1-0", "1-1", "2-0"]);
+
+    assert_eq!(
+        run(&mut executor, &["XPENDING", "s", "g"]),
+        RespValue::Array(Some(vec![
+            RespValue::Integer(4),
+            bulk("1-0"),
+            bulk("3-5"),
+            RespValue::Array(Some(vec![
+                RespValue::Array(Some(vec![bulk("alice"), bulk("3")])),
+                RespValue::Array(Some(vec![bulk("bob"), bulk("1")])),
+            ])),
+        ]))
+    );
+
+    assert_eq!(
+        run(&mut executor, &["XACK", "s", "g", "1-0", "1-1", "9-9"]),
+        RespValue::Integer(2)
+    );
+    // The history read counted a second delivery of 2-0
+    assert_eq!(
+        run(
+            &mut executor,
+            &["XPENDING", "s", "g", "-", "+", "10", "alice"]
+        ),
+        RespValue::Array(Some(vec![RespValue::Array(Some(vec![
+            bulk("2-0"),
+            bulk("alice"),
+            RespValue::Integer(0),
+            RespValue::Integer(2),
+        ]))]))
+    );
+
+    // NOACK deliveries never enter the PEL
+    run(&mut executor, &["XADD", "s", "4-0", "f", "v"]);
+    run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "carol",
+            "NOACK",
+            "STREAMS",
+            "s",
+            ">",
+        ],
+    );
+    assert_eq!(
+        run(&mut executor, &["XGROUP", "DELCONSUMER", "s", "g", "alice"]),
+        RespValue::Integer(1)
+    );
+    assert_eq!(
+        run(&mut executor, &["XPENDING", "s", "g"]),
+        RespValue::Array(Some(vec![
+            RespValue::Integer(1),
+            bulk("3-5"),
+            bulk("3-5"),
+            RespValue::Array(Some(vec![RespValue::Array(Some(vec![
+                bulk("bob"),
+                bulk("1")
+            ]))])),
+        ]))
+    );
+}
+
+#[test]
+fn test_at_least_once_redelivery_via_autoclaim() {
+    let mut executor = seeded();
+    run(&mut executor, &["XGROUP", "CREATE", "s", "jobs", "0"]);
+
+    // worker-1 takes two jobs and crashes before acknowledging them
+    executor.set_time(VirtualTime::from_millis(0));
+    run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "jobs",
+            "worker-1",
+            "COUNT",
+            "2",
+            "STREAMS",
+            "s",
+            ">",
+        ],
+    );
+
+    // Not idle long enough yet: nothing to claim
+    executor.set_time(VirtualTime::from_millis(500));
+    let reply = run(
+        &mut executor,
+        &["XAUTOCLAIM", "s", "jobs", "worker-2", "1000", "0"],
+    );
+    assert_eq!(
+        reply,
+        RespValue::Array(Some(vec![
+            bulk("0-0"),
+            RespValue::Array(Some(vec![])),
+            RespValue::Array(Some(vec![])),
+        ]))
+    );
+
+    executor.set_time(VirtualTime::from_millis(1_500));
+    let reply = run(
+        &mut executor,
+        &[
+            "XAUTOCLAIM",
+            "s",
+            "jobs",
+            "worker-2",
+            "1000",
+            "0",
+            "COUNT",
+            "1",
+        ],
+    );
+    let RespValue::Array(Some(parts)) = &reply else {
+        panic!("expected autoclaim reply, got {:?}", reply);
+    };
+    assert_eq!(parts[0], bulk("1-1"));
+    assert_eq!(ids(&parts[1]), vec!["1-0"]);
+
+    // The second job is claimed explicitly; a fresh claim resets idle time
+    let reply = run(
+        &mut executor,
+        &["XCLAIM", "s", "jobs", "worker-2", "1000", "1-1", "JUSTID"],
+    );
+    assert_eq!(reply, RespValue::Array(Some(vec![bulk("1-1")])));
+    assert_eq!(
+        run(
+            &mut executor,
+            &["XCLAIM", "s", "jobs", "worker-3", "1000", "1-1"]
+        ),
+        RespValue::Array(Some(vec![]))
+    );
+
+    assert_eq!(
+        run(&mut executor, &["XPENDING", "s", "jobs", "-", "+", "10"]),
+        RespValue::Array(Some(vec![
+            RespValue::Array(Some(vec![
+                bulk("1-0"),
+                bulk("worker-2"),
+                RespValue::Integer(0),
+                RespValue::Integer(2),
+            ])),
+            // JUSTID does not count a delivery
+            RespValue::Array(Some(vec![
+                bulk("1-1"),
+                bulk("worker-2"),
+                RespValue::Integer(0),
+                RespValue::Integer(1),
+            ])),
+        ]))
+    );
+
+    assert_eq!(
+        run(&mut executor, &["XACK", "s", "jobs", "1-0", "1-1"]),
+        RespValue::Integer(2)
+    );
+}
+
+#[test]
+fn test_autoclaim_reports_deleted_entries() {
+    let mut executor = seeded();
+    run(&mut executor, &["XGROUP", "CREATE", "s", "g", "0"]);
+    run(
+        &mut executor,
+        &["XREADGROUP", "GROUP", "g", "c", "STREAMS", "s", ">"],
+    );
+    run(
+        &mut executor,
+        &["XADD", "s", "MAXLEN", "2", "4-0", "f", "v"],
+    );
+
+    // Trimmed entries stay pending; history shows them with nil fields
+    let reply = run(
+        &mut executor,
+        &[
+            "XREADGROUP",
+            "GROUP",
+            "g",
+            "c",
+            "COUNT",
+            "1",
+            "STREAMS",
+            "s",
+            "0",
+        ],
+    );
+    assert_eq!(
+        group_entries(&reply),
+        &RespValue::Array(Some(vec![RespValue::Array(Some(vec![
+            bulk("1-0"),
+            RespValue::Array(None),
+        ]))]))
+    );
+
+    let reply = run(
+        &mut executor,
+        &["XAUTOCLAIM", "s", "g", "d", "0", "-", "JUSTID"],
+    );
+    assert_eq!(
+        reply,
+        RespValue::Array(Some(vec![
+            bulk("0-0"),
+            RespValue::Array(Some(vec![bulk("3-5")])),
+            RespValue::Array(Some(vec![bulk("1-0"), bulk("1-1"), bulk("2-0")])),
+        ]))
+    );
+    assert_eq!(
+        run(&mut executor, &["XPENDING", "s", "g", "-", "+", "10"]),
+        RespValue::Array(Some(vec![RespValue::Array(Some(vec![
+            bulk("3-5"),
+            bulk("d"),
+            RespValue::Integer(0),
+            RespValue::Integer(1),
+        ]))]))
+    );
+}
@@ -154,6 +154,90 @@ fn test_unwatch() {
     assert_eq!(result, RespValue::simple("OK"));
 }
 
+#[test]
+fn test_reset_discards_multi_and_watch() {
+    let mut executor = CommandExecutor::new();
+
+    executor.execute(&Command::Watch(vec!["watched".to_string()]));
+    executor.execute(&Command::Multi);
+    executor.execute(&Command::Incr("counter".to_string()));
+
+    // RESET runs immediately rather than being queued
+    let result = executor.execute(&Command::Reset);
+    assert_eq!(result, RespValue::simple("RESET"));
+    assert_eq!(
+        executor.execute(&Command::Exec),
+        RespValue::err("ERR EXEC without MULTI")
+    );
+    assert_eq!(
+        executor.execute(&Command::Get("counter".to_string())),
+        RespValue::BulkString(None)
+    );
+
+    // The old WATCH no longer aborts a later transaction
+    executor.execute(&Command::Incr("watched".to_string()));
+    executor.execute(&Command::Multi);
+    executor.execute(&Command::Incr("counter".to_string()));
+    assert_eq!(
+        executor.execute(&Command::Exec),
+        RespValue::Array(Some(vec![RespValue::Integer(1)]))
+    );
+}
+
+#[test]
+fn test_queueing_errors_abort_exec() {
+    let mut executor = CommandExecutor::new();
+
+    executor.execute(&Command::Multi);
+    executor.execute(&Command::set("key".to_string(), SDS::from_str("v")));
+    assert_eq!(
+        executor.execute(&Command::Unknown("NOSUCH".to_string())),
+        RespValue::err("ERR unknown command 'NOSUCH'")
+    );
+    assert_eq!(
+        executor.reject("ERR wrong number of arguments for 'get' command"),
+        RespValue::err("ERR wrong number of arguments for 'get' command")
+    );
+    assert_eq!(
+        executor.execute(&Command::Exec),
+        RespValue::err("EXECABORT Transaction discarded because of previous errors.")
+    );
+    // Nothing queued ran
+    assert_eq!(
+        executor.execute(&Command::Get("key".to_string())),
+        RespValue::BulkString(None)
+    );
+
+    // Outside MULTI a rejection is only a reply
+    assert_eq!(
+        executor.reject("Invalid command format"),
+        RespValue::err("ERR Invalid command format")
+    );
+    executor.execute(&Command::Multi);
+    executor.execute(&Command::Incr("counter".to_string()));
+    assert_eq!(
+        executor.execute(&Command::Exec),
+        RespValue::Array(Some(vec![RespValue::Integer(1)]))
+    );
+}
+
+#[test]
+fn test_runtime_errors_do_not_stop_exec() {
+    let mut executor = CommandExecutor::new();
+    executor.execute(&Command::set("text".to_string(), SDS::from_str("abc")));
+
+    executor.execute(&Command::Multi);
+    executor.execute(&Command::Incr("text".to_string()));
+    executor.execute(&Command::Incr("counter".to_string()));
+    assert_eq!(
+        executor.execute(&Command::Exec),
+        RespValue::Array(Some(vec![
+            RespValue::err("ERR value is not an integer or out of range"),
+            RespValue::Integer(1),
+        ]))
+    );
+}
+
 // ============================================
 // HINCRBY Error Handling Tests
 // ============================================
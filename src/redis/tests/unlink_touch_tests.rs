@@ -0,0 +1,194 @@
+//! UNLINK and lazy free, TOUCH and OBJECT IDLETIME
+
+use super::super::{
+    CommandExecutor, RedisClient, RedisServer, RespParser, RespValue, LAZYFREE_THRESHOLD,
+};
+use super::run;
+use crate::simulator::{Simulation, SimulationConfig, VirtualTime};
+
+/// A list just large enough to be freed lazily
+fn push_large_list(executor: &mut CommandExecutor, key: &str) {
+    let mut parts = vec!["RPUSH", key];
+    parts.extend(std::iter::repeat_n("x", LAZYFREE_THRESHOLD + 1));
+    assert_eq!(
+        run(executor, &parts),
+        RespValue::Integer(LAZYFREE_THRESHOLD as i64 + 1)
+    );
+}
+
+fn info_field(info: &RespValue, name: &str) -> u64 {
+    let RespValue::BulkString(Some(bytes)) = info else {
+        panic!("INFO returned {:?}", info);
+    };
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .unwrap_or_else(|| panic!("INFO has no {}", name))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_unlink_removes_keys_at_once() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "small", "v"]);
+    run(&mut executor, &["EXPIRE", "small", "100"]);
+    push_large_list(&mut executor, "large");
+
+    assert_eq!(
+        run(&mut executor, &["UNLINK", "small", "large", "missing"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(run(&mut executor, &["DBSIZE"]), RespValue::Integer(0));
+    assert_eq!(
+        run(&mut executor, &["TTL", "small"]),
+        RespValue::Integer(-2)
+    );
+    assert_eq!(executor.keyspace_stats().total_keys(), 0);
+    assert_eq!(executor.used_memory(), 0);
+
+    // Only the list was large enough to queue
+    assert_eq!(executor.lazyfree_pending_objects(), 1);
+    assert_eq!(executor.lazyfreed_objects(), 0);
+}
+
+#[test]
+fn test_lazyfree_step_frees_oldest_first_and_reports_in_info() {
+    let mut executor = CommandExecutor::new();
+    for key in ["a", "b", "c"] {
+        push_large_list(&mut executor, key);
+    }
+    run(&mut executor, &["UNLINK", "a", "b", "c"]);
+
+    assert_eq!(executor.lazyfree_step(2), 2);
+    let info = run(&mut executor, &["INFO"]);
+    assert_eq!(info_field(&info, "lazyfree_pending_objects"), 1);
+    assert_eq!(info_field(&info, "lazyfreed_objects"), 2);
+
+    assert_eq!(executor.lazyfree_step(2), 1);
+    assert_eq!(executor.lazyfree_step(2), 0);
+    assert_eq!(executor.take_lazyfree_queue().len(), 0);
+    assert_eq!(executor.lazyfreed_objects(), 3);
+}
+
+#[test]
+fn test_touch_counts_live_keys_and_resets_idle_time() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["SET", "a", "1"]);
+    run(&mut executor, &["SET", "b", "2", "PX", "500"]);
+
+    executor.set_time(VirtualTime::from_millis(3_000));
+    assert_eq!(
+        run(&mut executor, &["OBJECT", "IDLETIME", "a"]),
+        RespValue::Integer(3)
+    );
+    // EXISTS and OBJECT are not accesses
+    run(&mut executor, &["EXISTS", "a"]);
+    assert_eq!(
+        run(&mut executor, &["OBJECT", "IDLETIME", "a"]),
+        RespValue::Integer(3)
+    );
+
+    assert_eq!(
+        run(&mut executor, &["TOUCH", "a", "b", "missing"]),
+        RespValue::Integer(1),
+        "b expired at 500ms"
+    );
+    assert_eq!(
+        run(&mut executor, &["OBJECT", "IDLETIME", "a"]),
+        RespValue::Integer(0)
+    );
+
+    // A read through the GET fast path is an access too
+    executor.set_time(VirtualTime::from_millis(5_000));
+    executor.get_direct("a");
+    assert_eq!(
+        run(&mut executor, &["OBJECT", "IDLETIME", "a"]),
+        RespValue::Integer(0)
+    );
+}
+
+#[test]
+fn test_access_times_leave_with_deleted_and_expired_keys() {
+    let mut executor = CommandExecutor::new();
+    for key in [
+        "del", "unlink", "getdel", "renamed", "active", "passive", "kept",
+    ] {
+        run(&mut executor, &["SET", key, "v"]);
+    }
+    run(&mut executor, &["HSET", "hash", "f", "v"]);
+    run(
+        &mut executor,
+        &["HPEXPIRE", "hash", "500", "FIELDS", "1", "f"],
+    );
+    run(&mut executor, &["PEXPIRE", "active", "500"]);
+    run(&mut executor, &["PEXPIRE", "passive", "1500"]);
+    assert_eq!(executor.access_times.len(), 8);
+
+    run(&mut executor, &["DEL", "del"]);
+    run(&mut executor, &["UNLINK", "unlink"]);
+    run(&mut executor, &["GETDEL", "getdel"]);
+    run(&mut executor, &["RENAME", "renamed", "target"]);
+    executor.get_direct("missing");
+
+    // Active expiry takes "active" and the hash its last field left empty
+    executor.set_time(VirtualTime::from_millis(1_000));
+    // With it off, "passive" goes when it is next read
+    run(&mut executor, &["DEBUG", "SET-ACTIVE-EXPIRE", "0"]);
+    executor.set_time(VirtualTime::from_millis(2_000));
+    assert_eq!(
+        run(&mut executor, &["GET", "passive"]),
+        RespValue::BulkString(None)
+    );
+
+    let mut tracked: Vec<&str> = executor.access_times.keys().map(String::as_str).collect();
+    tracked.sort_unstable();
+    assert_eq!(tracked, vec!["kept", "target"]);
+    executor.verify_invariants();
+}
+
+fn send(sim: &mut Simulation, client: &mut RedisClient, parts: &[&str]) -> u64 {
+    let resp = RespValue::Array(Some(
+        parts
+            .iter()
+            .map(|p| RespValue::BulkString(Some(p.as_bytes().to_vec())))
+            .collect(),
+    ));
+    client.send_command(sim, RespParser::encode(&resp))
+}
+
+#[test]
+fn test_server_lazyfree_worker_drains_the_queue() {
+    let mut sim = Simulation::new(SimulationConfig::default());
+    let server_host = sim.add_host("server".to_string());
+    let client_host = sim.add_host("client".to_string());
+    let mut server = RedisServer::new(server_host);
+    let mut client = RedisClient::new(client_host, server_host);
+    let mut run_until = |sim: &mut Simulation, client: &mut RedisClient, ms: u64| {
+        sim.run_until(VirtualTime::from_millis(ms), |sim, event| {
+            server.handle_event(sim, event);
+            client.handle_event(event);
+        });
+    };
+
+    // The network may reorder requests, so each phase runs to completion
+    let keys: Vec<String> = (0..40).map(|i| format!("list:{}", i)).collect();
+    for key in &keys {
+        let mut push = vec!["RPUSH", key.as_str()];
+        push.extend(std::iter::repeat_n("x", LAZYFREE_THRESHOLD + 1));
+        send(&mut sim, &mut client, &push);
+    }
+    run_until(&mut sim, &mut client, 1_000);
+
+    let mut unlink = vec!["UNLINK"];
+    unlink.extend(keys.iter().map(String::as_str));
+    let unlinked = send(&mut sim, &mut client, &unlink);
+    run_until(&mut sim, &mut client, 2_000);
+    assert_eq!(client.get_response(unlinked), Some(&RespValue::Integer(40)));
+
+    let info = send(&mut sim, &mut client, &["INFO"]);
+    run_until(&mut sim, &mut client, 3_000);
+    let info = client.get_response(info).unwrap();
+    assert_eq!(info_field(info, "lazyfree_pending_objects"), 0);
+    assert_eq!(info_field(info, "lazyfreed_objects"), 40);
+}
@@ -0,0 +1,283 @@
+//! Sorted set ranges - unified ZRANGE (BYSCORE, BYLEX, REV, LIMIT),
+//! ZRANGESTORE and ZREMRANGEBYRANK/BYSCORE/BYLEX
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn strings(reply: RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+/// Executor holding `z = {a:1, b:2, c:3, d:4}` and `lex = {a..e}`, all score 0
+fn with_zsets() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    run(
+        &mut executor,
+        &["ZADD", "z", "1", "a", "2", "b", "3", "c", "4", "d"],
+    );
+    run(
+        &mut executor,
+        &[
+            "ZADD", "lex", "0", "a", "0", "b", "0", "c", "0", "d", "0", "e",
+        ],
+    );
+    executor
+}
+
+#[test]
+fn test_legacy_rank_form() {
+    let mut executor = with_zsets();
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "z", "0", "-1"])),
+        ["a", "b", "c", "d"]
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "z", "1", "2", "WITHSCORES"])),
+        ["b", "2", "c", "3"]
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "z", "0", "1", "REV"])),
+        ["d", "c"]
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "missing", "0", "-1"])),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_byscore() {
+    let mut executor = with_zsets();
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "z", "(1", "3", "BYSCORE", "WITHSCORES"]
+        )),
+        ["b", "2", "c", "3"]
+    );
+    // REV takes max first
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "z", "+inf", "2", "BYSCORE", "REV"]
+        )),
+        ["d", "c", "b"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "z", "-inf", "+inf", "BYSCORE", "LIMIT", "1", "2"]
+        )),
+        ["b", "c"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "z", "+inf", "-inf", "BYSCORE", "REV", "LIMIT", "1", "-1"]
+        )),
+        ["c", "b", "a"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "z", "0", "9", "BYSCORE", "LIMIT", "-1", "5"]
+        )),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANGE", "z", "x", "2", "BYSCORE"]),
+        RespValue::err("ERR min or max is not a float")
+    );
+}
+
+#[test]
+fn test_bylex() {
+    let mut executor = with_zsets();
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "lex", "[b", "(d", "BYLEX"])),
+        ["b", "c"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "lex", "+", "(c", "BYLEX", "REV"]
+        )),
+        ["e", "d"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "lex", "-", "+", "BYLEX", "LIMIT", "3", "10"]
+        )),
+        ["d", "e"]
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANGE", "lex", "b", "+", "BYLEX"]),
+        RespValue::err("ERR min or max not valid string range item")
+    );
+}
+
+#[test]
+fn test_option_errors() {
+    let mut executor = with_zsets();
+    assert_eq!(
+        run(&mut executor, &["ZRANGE", "z", "0", "1", "LIMIT", "0", "1"]),
+        RespValue::err(
+            "ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+        )
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZRANGE", "lex", "-", "+", "BYLEX", "WITHSCORES"]
+        ),
+        RespValue::err("ERR syntax error, WITHSCORES not supported in combination with BYLEX")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANGE", "z", "0", "1", "BOGUS"]),
+        RespValue::err("ERR syntax error")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZRANGE", "z", "a", "1"]),
+        RespValue::err("ERR value is not an integer or out of range")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZRANGESTORE", "dst", "z", "0", "1", "WITHSCORES"]
+        ),
+        RespValue::err("ERR syntax error")
+    );
+}
+
+#[test]
+fn test_zrangestore() {
+    let mut executor = with_zsets();
+    run(&mut executor, &["SET", "dst", "old", "EX", "100"]);
+    assert_eq!(
+        run(
+            &mut executor,
+            &[
+                "ZRANGESTORE",
+                "dst",
+                "z",
+                "2",
+                "+inf",
+                "BYSCORE",
+                "LIMIT",
+                "0",
+                "2"
+            ]
+        ),
+        RespValue::Integer(2)
+    );
+    assert_eq!(run(&mut executor, &["TTL", "dst"]), RespValue::Integer(-1));
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "dst", "0", "-1", "WITHSCORES"]
+        )),
+        ["b", "2", "c", "3"]
+    );
+
+    // An empty range deletes the destination
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZRANGESTORE", "dst", "z", "10", "20", "BYSCORE"]
+        ),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["EXISTS", "dst"]),
+        RespValue::Integer(0)
+    );
+
+    run(&mut executor, &["SET", "str", "x"]);
+    match run(&mut executor, &["ZRANGESTORE", "dst", "str", "0", "-1"]) {
+        RespValue::Error(e) => assert!(e.starts_with("WRONGTYPE"), "{}", e),
+        other => panic!("ZRANGESTORE returned {:?}", other),
+    }
+}
+
+#[test]
+fn test_zremrangebyrank() {
+    let mut executor = with_zsets();
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYRANK", "z", "-2", "-1"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "z", "0", "-1"])),
+        ["a", "b"]
+    );
+    // Out-of-range indexes clamp; an inverted range removes nothing
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYRANK", "z", "1", "0"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYRANK", "z", "-100", "100"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "z"]), RespValue::Integer(0));
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYRANK", "z", "x", "1"]),
+        RespValue::err("ERR value is not an integer or out of range")
+    );
+}
+
+#[test]
+fn test_zremrangebyscore_and_bylex() {
+    let mut executor = with_zsets();
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYSCORE", "z", "(1", "3"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "z", "0", "-1"])),
+        ["a", "d"]
+    );
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYSCORE", "z", "(1", "(4"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYSCORE", "z", "one", "4"]),
+        RespValue::err("ERR min or max is not a float")
+    );
+
+    run(&mut executor, &["EXPIRE", "lex", "100"]);
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYLEX", "lex", "(a", "[d"]),
+        RespValue::Integer(3)
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "lex", "0", "-1"])),
+        ["a", "e"]
+    );
+    assert_eq!(
+        run(&mut executor, &["ZREMRANGEBYLEX", "lex", "-", "+"]),
+        RespValue::Integer(2)
+    );
+    assert_eq!(
+        run(&mut executor, &["EXISTS", "lex"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(run(&mut executor, &["TTL", "lex"]), RespValue::Integer(-2));
+
+    run(&mut executor, &["SET", "str", "x"]);
+    match run(&mut executor, &["ZREMRANGEBYLEX", "str", "-", "+"]) {
+        RespValue::Error(e) => assert!(e.starts_with("WRONGTYPE"), "{}", e),
+        other => panic!("ZREMRANGEBYLEX returned {:?}", other),
+    }
+}
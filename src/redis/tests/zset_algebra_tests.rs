@@ -0,0 +1,241 @@
+//! Sorted set algebra tests - ZUNION, ZINTER, ZDIFF and their STORE forms
+
+use super::super::{CommandExecutor, RespValue};
+use super::run;
+
+fn strings(reply: RespValue) -> Vec<String> {
+    match reply {
+        RespValue::Array(Some(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                RespValue::BulkString(Some(bytes)) => String::from_utf8(bytes).unwrap(),
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected an array, got {:?}", other),
+    }
+}
+
+/// Executor holding `z1 = {a:1, b:2, c:3}`, `z2 = {b:10, c:20, d:30}`,
+/// the plain set `s = {a, d}` and the string `str`
+fn with_inputs() -> CommandExecutor {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["ZADD", "z1", "1", "a", "2", "b", "3", "c"]);
+    run(
+        &mut executor,
+        &["ZADD", "z2", "10", "b", "20", "c", "30", "d"],
+    );
+    run(&mut executor, &["SADD", "s", "a", "d"]);
+    run(&mut executor, &["SET", "str", "x"]);
+    executor
+}
+
+#[test]
+fn test_zunion_aggregates() {
+    let mut executor = with_inputs();
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZUNION", "2", "z1", "z2", "WITHSCORES"]
+        )),
+        ["a", "1", "b", "12", "c", "23", "d", "30"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZUNION", "2", "z1", "z2", "AGGREGATE", "MIN", "WITHSCORES"]
+        )),
+        ["a", "1", "b", "2", "c", "3", "d", "30"]
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &[
+                "ZUNION",
+                "2",
+                "z1",
+                "z2",
+                "WEIGHTS",
+                "10",
+                "1",
+                "AGGREGATE",
+                "MAX",
+                "WITHSCORES"
+            ]
+        )),
+        ["a", "10", "b", "20", "c", "30", "d", "30"]
+    );
+    // Missing keys are empty inputs
+    assert_eq!(
+        strings(run(&mut executor, &["ZUNION", "2", "z1", "nope"])),
+        ["a", "b", "c"]
+    );
+}
+
+#[test]
+fn test_zinter_and_zdiff() {
+    let mut executor = with_inputs();
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &[
+                "ZINTER",
+                "2",
+                "z1",
+                "z2",
+                "WEIGHTS",
+                "2",
+                "0.5",
+                "WITHSCORES"
+            ]
+        )),
+        ["b", "9", "c", "16"]
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZINTER", "2", "z1", "nope"])),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZDIFF", "2", "z1", "z2", "WITHSCORES"]
+        )),
+        ["a", "1"]
+    );
+    // Plain set members score 1
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZINTER", "2", "s", "z2", "WITHSCORES"]
+        )),
+        ["d", "31"]
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZDIFF", "2", "s", "z1", "WITHSCORES"])),
+        ["d", "1"]
+    );
+}
+
+#[test]
+fn test_store_forms_replace_destination() {
+    let mut executor = with_inputs();
+    run(&mut executor, &["SET", "dest", "old", "EX", "100"]);
+    assert_eq!(
+        run(&mut executor, &["ZUNIONSTORE", "dest", "2", "z1", "s"]),
+        RespValue::Integer(4)
+    );
+    assert_eq!(run(&mut executor, &["TTL", "dest"]), RespValue::Integer(-1));
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZRANGE", "dest", "0", "-1", "WITHSCORES"]
+        )),
+        ["d", "1", "a", "2", "b", "2", "c", "3"]
+    );
+
+    // The destination may also be an input
+    assert_eq!(
+        run(&mut executor, &["ZINTERSTORE", "dest", "2", "dest", "z2"]),
+        RespValue::Integer(3)
+    );
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "dest", "0", "-1"])),
+        ["b", "c", "d"]
+    );
+
+    // An empty result deletes the destination
+    assert_eq!(
+        run(&mut executor, &["ZDIFFSTORE", "dest", "2", "z2", "z2"]),
+        RespValue::Integer(0)
+    );
+    assert_eq!(
+        run(&mut executor, &["EXISTS", "dest"]),
+        RespValue::Integer(0)
+    );
+}
+
+#[test]
+fn test_wrong_type_leaves_destination_untouched() {
+    let mut executor = with_inputs();
+    run(&mut executor, &["ZADD", "dest", "5", "keep"]);
+    for cmd in ["ZUNIONSTORE", "ZINTERSTORE", "ZDIFFSTORE"] {
+        match run(&mut executor, &[cmd, "dest", "2", "z1", "str"]) {
+            RespValue::Error(e) => assert!(e.starts_with("WRONGTYPE"), "{}: {}", cmd, e),
+            other => panic!("{} returned {:?}", cmd, other),
+        }
+    }
+    assert_eq!(
+        strings(run(&mut executor, &["ZRANGE", "dest", "0", "-1"])),
+        ["keep"]
+    );
+}
+
+#[test]
+fn test_nan_scores_become_zero() {
+    let mut executor = CommandExecutor::new();
+    run(&mut executor, &["ZADD", "pos", "inf", "m"]);
+    run(&mut executor, &["ZADD", "neg", "-inf", "m"]);
+    // inf + -inf
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZUNION", "2", "pos", "neg", "WITHSCORES"]
+        )),
+        ["m", "0"]
+    );
+    // inf * 0
+    assert_eq!(
+        strings(run(
+            &mut executor,
+            &["ZUNION", "1", "pos", "WEIGHTS", "0", "WITHSCORES"]
+        )),
+        ["m", "0"]
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    let mut executor = with_inputs();
+    let err = |msg: &str| RespValue::err(msg.to_string());
+    assert_eq!(
+        run(&mut executor, &["ZUNION", "0", "z1"]),
+        err("ERR at least 1 input key is needed for 'zunion' command")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZINTERSTORE", "d", "0", "z1"]),
+        err("ERR at least 1 input key is needed for 'zinterstore' command")
+    );
+    assert_eq!(
+        run(&mut executor, &["ZUNION", "3", "z1", "z2"]),
+        err("ERR syntax error")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZUNION", "2", "z1", "z2", "WEIGHTS", "1", "x"]
+        ),
+        err("ERR weight value is not a float")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZUNION", "2", "z1", "z2", "AGGREGATE", "AVG"]
+        ),
+        err("ERR syntax error")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZDIFF", "2", "z1", "z2", "WEIGHTS", "1", "1"]
+        ),
+        err("ERR syntax error")
+    );
+    assert_eq!(
+        run(
+            &mut executor,
+            &["ZUNIONSTORE", "d", "1", "z1", "WITHSCORES"]
+        ),
+        err("ERR syntax error")
+    );
+    assert_eq!(run(&mut executor, &["EXISTS", "d"]), RespValue::Integer(0));
+}
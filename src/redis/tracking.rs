@@ -0,0 +1,503 @@
+//! Client-side caching: the invalidation table behind CLIENT TRACKING.
+//!
+//! A client that enables tracking is told when keys it may have cached
+//! change, so its library can drop them from a local cache:
+//! - `ClientTracking`: the server-wide table. Cheap to clone; every shard
+//!   executor and every connection holds a handle to the same table.
+//!   Executors `invalidate` every key a write touches, expires or evicts,
+//!   and `invalidate_all` on FLUSHALL/FLUSHDB.
+//! - `TrackingSession`: one client's registration and its mailbox of
+//!   pending invalidations. Dropping it (CLIENT TRACKING OFF, disconnect)
+//!   stops tracking and forgets every key remembered for it.
+//!
+//! Modes, as in Redis:
+//! - Default: the server remembers which keys each client read (the keys of
+//!   its read-only commands) and invalidates a key once, for the clients that
+//!   read it since it last changed. An invalidated key is no longer tracked
+//!   for them until they read it again.
+//! - BCAST: nothing is remembered. The client hears about every modified key
+//!   starting with one of its prefixes, or every key without prefixes.
+//!
+//! Delivery:
+//! - Invalidations are queued in a bounded mailbox. A client that falls
+//!   behind is not disconnected (unlike a Pub/Sub subscriber): its queue is
+//!   replaced by a single flush-everything invalidation, which is always
+//!   safe for a cache.
+//! - Consecutive flush-everything invalidations are received as one, so a
+//!   FLUSHALL fanned out to every shard reaches the client once.
+//! - The connection sends them as RESP3 `invalidate` pushes. Like Redis
+//!   without REDIRECT, a RESP2 connection receives nothing.
+//!
+//! NOLOOP, OPTIN, OPTOUT and REDIRECT are not supported.
+//!
+//! TigerStyle: All functions have precondition/postcondition assertions.
+
+use super::resp::RespValue;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Identifies one tracking client (one `TrackingSession`)
+pub type TrackingId = u64;
+
+/// Invalidations a client may have queued before it is told to flush
+/// everything instead
+pub const DEFAULT_MAILBOX_CAPACITY: usize = 1024;
+
+/// Which keys a client hears about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackingMode {
+    /// Keys the client read
+    Default,
+    /// Keys starting with one of the prefixes; all keys if there are none
+    Bcast { prefixes: BTreeSet<String> },
+}
+
+impl TrackingMode {
+    fn is_bcast(&self) -> bool {
+        matches!(self, TrackingMode::Bcast { .. })
+    }
+}
+
+/// One message for a tracking client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invalidation {
+    /// These keys changed
+    Keys(Vec<String>),
+    /// Drop the whole cache (FLUSHALL, or the client fell behind)
+    All,
+}
+
+impl Invalidation {
+    /// The RESP3 push frame: `["invalidate", keys]`, with a null instead of
+    /// keys for a flush
+    pub fn to_resp(&self) -> RespValue {
+        let keys = match self {
+            Invalidation::All => RespValue::Null,
+            Invalidation::Keys(keys) => {
+                debug_assert!(
+                    !keys.is_empty(),
+                    "Precondition: a key invalidation names at least one key"
+                );
+                RespValue::Array(Some(
+                    keys.iter()
+                        .map(|key| RespValue::BulkString(Some(key.as_bytes().to_vec())))
+                        .collect(),
+                ))
+            }
+        };
+        RespValue::Push(vec![
+            RespValue::BulkString(Some(b"invalidate".to_vec())),
+            keys,
+        ])
+    }
+}
+
+#[derive(Debug)]
+struct Client {
+    mode: TrackingMode,
+    mailbox: mpsc::Sender<Invalidation>,
+    /// Set when an invalidation did not fit; the session then flushes
+    overflowed: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    /// key -> default-mode clients that read it; never empty
+    keys: BTreeMap<String, BTreeSet<TrackingId>>,
+    clients: BTreeMap<TrackingId, Client>,
+    next_id: TrackingId,
+}
+
+impl Table {
+    /// Queue `message` for `id`, flagging the client if its mailbox is full
+    fn deliver(&self, id: TrackingId, message: Invalidation) {
+        let Some(client) = self.clients.get(&id) else {
+            return;
+        };
+        if client.mailbox.try_send(message).is_err() {
+            client.overflowed.store(true, Ordering::Release);
+        }
+    }
+
+    /// Forget a client and every key remembered for it
+    fn remove(&mut self, id: TrackingId) {
+        if let Some(client) = self.clients.remove(&id) {
+            if !client.mode.is_bcast() {
+                self.keys.retain(|_, readers| {
+                    readers.remove(&id);
+                    !readers.is_empty()
+                });
+            }
+        }
+
+        debug_assert!(
+            !self.keys.values().any(|readers| readers.contains(&id)),
+            "Postcondition: removed client must not track any key"
+        );
+    }
+
+    /// Verify the table is internally consistent
+    ///
+    /// No-op in release builds.
+    #[cfg(debug_assertions)]
+    fn verify_invariants(&self) {
+        for (key, readers) in &self.keys {
+            // Invariant 1: No empty key entries
+            debug_assert!(
+                !readers.is_empty(),
+                "Invariant violated: key '{}' is tracked for no client",
+                key
+            );
+            // Invariant 2: Only live default-mode clients track keys
+            for id in readers {
+                debug_assert!(
+                    self.clients.get(id).is_some_and(|c| !c.mode.is_bcast()),
+                    "Invariant violated: key '{}' tracked for client {} not in default mode",
+                    key,
+                    id
+                );
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn verify_invariants(&self) {}
+}
+
+/// Server-wide tracking table
+///
+/// Clones share the same table, like `PubSubManager`.
+#[derive(Debug, Clone)]
+pub struct ClientTracking {
+    inner: Arc<Mutex<Table>>,
+    /// Tracking clients, read without the lock so writes skip the table
+    /// while nobody tracks
+    active: Arc<AtomicUsize>,
+    mailbox_capacity: usize,
+}
+
+impl Default for ClientTracking {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientTracking {
+    pub fn new() -> Self {
+        Self::with_mailbox_capacity(DEFAULT_MAILBOX_CAPACITY)
+    }
+
+    /// Create a table whose clients may queue at most `capacity` invalidations
+    pub fn with_mailbox_capacity(capacity: usize) -> Self {
+        debug_assert!(capacity > 0, "Precondition: mailbox capacity must be > 0");
+        ClientTracking {
+            inner: Arc::new(Mutex::new(Table::default())),
+            active: Arc::new(AtomicUsize::new(0)),
+            mailbox_capacity: capacity,
+        }
+    }
+
+    /// CLIENT TRACKING ON: register a client in `mode`
+    pub fn session(&self, mode: TrackingMode) -> TrackingSession {
+        let (tx, rx) = mpsc::channel(self.mailbox_capacity);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        let mut table = self.inner.lock();
+        let id = table.next_id;
+        table.next_id += 1;
+        table.clients.insert(
+            id,
+            Client {
+                mode,
+                mailbox: tx,
+                overflowed: overflowed.clone(),
+            },
+        );
+        self.active.store(table.clients.len(), Ordering::Release);
+
+        debug_assert!(
+            table.clients.contains_key(&id),
+            "Postcondition: new tracking client must be registered"
+        );
+        TrackingSession {
+            tracking: self.clone(),
+            id,
+            receiver: rx,
+            overflowed,
+        }
+    }
+
+    /// Whether any client tracks keys; invalidating is a no-op otherwise
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire) > 0
+    }
+
+    /// Number of clients with tracking enabled (INFO `tracking_clients`)
+    pub fn num_clients(&self) -> usize {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// Number of keys remembered for default-mode clients
+    pub fn num_keys(&self) -> usize {
+        self.inner.lock().keys.len()
+    }
+
+    /// Tell the clients tracking any of `keys` that they changed
+    pub fn invalidate<S: AsRef<str>>(&self, keys: &[S]) {
+        if keys.is_empty() || !self.is_active() {
+            return;
+        }
+        let mut table = self.inner.lock();
+
+        let mut pending: BTreeMap<TrackingId, Vec<String>> = BTreeMap::new();
+        for key in keys {
+            let key = key.as_ref();
+            if let Some(readers) = table.keys.remove(key) {
+                for id in readers {
+                    pending.entry(id).or_default().push(key.to_string());
+                }
+            }
+        }
+        for (id, client) in &table.clients {
+            let TrackingMode::Bcast { prefixes } = &client.mode else {
+                continue;
+            };
+            let matching = keys
+                .iter()
+                .map(|key| key.as_ref())
+                .filter(|key| {
+                    prefixes.is_empty() || prefixes.iter().any(|p| key.starts_with(p.as_str()))
+                })
+                .map(str::to_string);
+            pending.entry(*id).or_default().extend(matching);
+        }
+
+        for (id, keys) in pending {
+            if !keys.is_empty() {
+                table.deliver(id, Invalidation::Keys(keys));
+            }
+        }
+
+        debug_assert!(
+            keys.iter()
+                .all(|key| !table.keys.contains_key(key.as_ref())),
+            "Postcondition: invalidated keys must no longer be tracked"
+        );
+        table.verify_invariants();
+    }
+
+    /// Tell the clients tracking `key` that it changed
+    pub fn invalidate_key(&self, key: &str) {
+        self.invalidate(&[key]);
+    }
+
+    /// FLUSHALL/FLUSHDB: every tracking client drops its whole cache
+    pub fn invalidate_all(&self) {
+        if !self.is_active() {
+            return;
+        }
+        let mut table = self.inner.lock();
+        table.keys.clear();
+        let ids: Vec<TrackingId> = table.clients.keys().copied().collect();
+        for id in ids {
+            table.deliver(id, Invalidation::All);
+        }
+
+        debug_assert!(
+            table.keys.is_empty(),
+            "Postcondition: a flush must leave no key tracked"
+        );
+        table.verify_invariants();
+    }
+
+    fn remember(&self, id: TrackingId, keys: &[String]) {
+        let mut table = self.inner.lock();
+        debug_assert!(
+            table.clients.get(&id).is_some_and(|c| !c.mode.is_bcast()),
+            "Precondition: only default-mode clients remember keys"
+        );
+        for key in keys {
+            table.keys.entry(key.clone()).or_default().insert(id);
+        }
+        table.verify_invariants();
+    }
+
+    fn add_prefixes(&self, id: TrackingId, new: &[String]) {
+        let mut table = self.inner.lock();
+        if let Some(TrackingMode::Bcast { prefixes }) =
+            table.clients.get_mut(&id).map(|c| &mut c.mode)
+        {
+            prefixes.extend(new.iter().cloned());
+        }
+    }
+
+    fn mode(&self, id: TrackingId) -> Option<TrackingMode> {
+        self.inner.lock().clients.get(&id).map(|c| c.mode.clone())
+    }
+
+    fn remove(&self, id: TrackingId) {
+        let mut table = self.inner.lock();
+        table.remove(id);
+        self.active.store(table.clients.len(), Ordering::Release);
+        table.verify_invariants();
+    }
+}
+
+/// One client's tracking registration and its invalidation mailbox.
+///
+/// Dropping the session turns tracking off.
+#[derive(Debug)]
+pub struct TrackingSession {
+    tracking: ClientTracking,
+    id: TrackingId,
+    receiver: mpsc::Receiver<Invalidation>,
+    overflowed: Arc<AtomicBool>,
+}
+
+impl TrackingSession {
+    pub fn id(&self) -> TrackingId {
+        self.id
+    }
+
+    pub fn is_bcast(&self) -> bool {
+        self.tracking
+            .mode(self.id)
+            .is_some_and(|mode| mode.is_bcast())
+    }
+
+    /// Default mode: remember that this client read `keys`
+    pub fn remember(&self, keys: &[String]) {
+        if !keys.is_empty() {
+            self.tracking.remember(self.id, keys);
+        }
+    }
+
+    /// CLIENT TRACKING ON BCAST again: listen to more prefixes
+    pub fn add_prefixes(&self, prefixes: &[String]) {
+        debug_assert!(
+            self.is_bcast(),
+            "Precondition: only BCAST clients have prefixes"
+        );
+        self.tracking.add_prefixes(self.id, prefixes);
+    }
+
+    /// Wait for the next invalidation
+    pub async fn recv(&mut self) -> Option<Invalidation> {
+        let message = self.receiver.recv().await?;
+        Some(self.coalesce(message))
+    }
+
+    /// Take the next already-queued invalidation without waiting
+    pub fn try_recv(&mut self) -> Option<Invalidation> {
+        let message = self.receiver.try_recv().ok()?;
+        Some(self.coalesce(message))
+    }
+
+    /// After an overflow, everything queued collapses into one flush; so do
+    /// flushes queued back to back
+    fn coalesce(&mut self, message: Invalidation) -> Invalidation {
+        if self.overflowed.swap(false, Ordering::AcqRel) {
+            while self.receiver.try_recv().is_ok() {}
+            return Invalidation::All;
+        }
+        if message == Invalidation::All {
+            while let Ok(Invalidation::All) = self.receiver.try_recv() {}
+        }
+        message
+    }
+}
+
+impl Drop for TrackingSession {
+    fn drop(&mut self) {
+        self.tracking.remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis::RespParser;
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn bcast(prefixes: &[&str]) -> TrackingMode {
+        TrackingMode::Bcast {
+            prefixes: prefixes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_default_mode_invalidates_read_keys_once() {
+        let tracking = ClientTracking::new();
+        let mut a = tracking.session(TrackingMode::Default);
+        let mut b = tracking.session(TrackingMode::Default);
+        a.remember(&keys(&["x", "y"]));
+        b.remember(&keys(&["y"]));
+
+        tracking.invalidate(&keys(&["y", "z"]));
+        assert_eq!(a.try_recv(), Some(Invalidation::Keys(keys(&["y"]))));
+        assert_eq!(b.try_recv(), Some(Invalidation::Keys(keys(&["y"]))));
+
+        // Untracked until read again
+        tracking.invalidate(&keys(&["y"]));
+        assert_eq!(a.try_recv(), None);
+        assert_eq!(tracking.num_keys(), 1);
+
+        drop(a);
+        assert_eq!(tracking.num_keys(), 0);
+        assert_eq!(tracking.num_clients(), 1);
+    }
+
+    #[test]
+    fn test_bcast_matches_prefixes() {
+        let tracking = ClientTracking::new();
+        let mut all = tracking.session(bcast(&[]));
+        let mut users = tracking.session(bcast(&["user:"]));
+        users.add_prefixes(&keys(&["session:"]));
+
+        tracking.invalidate(&keys(&["user:1", "cart:1", "session:9"]));
+        assert_eq!(
+            all.try_recv(),
+            Some(Invalidation::Keys(keys(&["user:1", "cart:1", "session:9"])))
+        );
+        assert_eq!(
+            users.try_recv(),
+            Some(Invalidation::Keys(keys(&["user:1", "session:9"])))
+        );
+        tracking.invalidate(&keys(&["cart:2"]));
+        assert_eq!(users.try_recv(), None);
+    }
+
+    #[test]
+    fn test_flushes_and_overflow_collapse() {
+        let tracking = ClientTracking::with_mailbox_capacity(4);
+        let mut session = tracking.session(bcast(&[]));
+        for _ in 0..3 {
+            tracking.invalidate_all();
+        }
+        assert_eq!(session.try_recv(), Some(Invalidation::All));
+        assert_eq!(session.try_recv(), None);
+
+        for key in ["a", "b", "c", "d", "e"] {
+            tracking.invalidate_key(key);
+        }
+        assert_eq!(session.try_recv(), Some(Invalidation::All));
+        assert_eq!(session.try_recv(), None);
+    }
+
+    #[test]
+    fn test_push_frames() {
+        assert_eq!(
+            RespParser::encode_resp3(&Invalidation::Keys(keys(&["k"])).to_resp()),
+            b">2\r\n$10\r\ninvalidate\r\n*1\r\n$1\r\nk\r\n".to_vec()
+        );
+        assert_eq!(
+            RespParser::encode_resp3(&Invalidation::All.to_resp()),
+            b">2\r\n$10\r\ninvalidate\r\n_\r\n".to_vec()
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Release metadata: the Redis version this server answers as, its own
+//! build, and the startup banner.
+//!
+//! Clients and the Tcl test suite key behaviour off the Redis version, so
+//! every place that reports one - INFO server, HELLO, LOLWUT - reads it from
+//! here rather than spelling it out. The crate's own version and commit are
+//! reported next to it (`redis_rust_version`, and `redis_git_sha1` from the
+//! `REDIS_RUST_GIT_SHA1` build-time variable, zeros when unset).
+//!
+//! `StartupBanner` logs the block a server writes before accepting
+//! connections: versions and process, where the configuration came from,
+//! the running mode and port, and the persistence status.
+
+use tracing::info;
+
+/// The Redis version clients see
+pub const REDIS_VERSION: &str = "7.0.0";
+
+/// This crate's version
+pub const BUILD_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the binary was built from; zeros when the build did not say
+pub const GIT_SHA1: &str = match option_env!("REDIS_RUST_GIT_SHA1") {
+    Some(sha) => sha,
+    None => "00000000",
+};
+
+/// Pointer width of the build, as INFO's `arch_bits`
+pub const ARCH_BITS: u32 = usize::BITS;
+
+/// The last line of every LOLWUT reply
+pub fn lolwut_version_line() -> String {
+    format!("Redis ver. {}\n", REDIS_VERSION)
+}
+
+/// How a server runs, as INFO's `redis_mode` and HELLO's `mode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerMode {
+    /// A production server. Replicas gossiping with peers are standalone
+    /// too: to clients they are not Redis Cluster nodes.
+    Standalone,
+    /// A deterministic executor under the simulator
+    Simulator,
+}
+
+impl ServerMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            ServerMode::Standalone => "standalone",
+            ServerMode::Simulator => "simulator",
+        }
+    }
+}
+
+/// The lines of INFO's `# Server` section that come from the build
+pub fn info_server_fields(mode: ServerMode) -> String {
+    format!(
+        "redis_version:{}\r\n\
+         redis_git_sha1:{}\r\n\
+         redis_git_dirty:0\r\n\
+         redis_build_id:0\r\n\
+         redis_rust_version:{}\r\n\
+         redis_mode:{}\r\n\
+         os:{} {}\r\n\
+         arch_bits:{}\r\n",
+        REDIS_VERSION,
+        GIT_SHA1,
+        BUILD_VERSION,
+        mode.name(),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        ARCH_BITS
+    )
+}
+
+/// What a server logs as it starts
+#[derive(Debug, Clone)]
+pub struct StartupBanner {
+    pub mode: ServerMode,
+    pub port: u16,
+    /// Where the configuration came from, e.g. `environment`
+    pub config_source: String,
+    /// Persistence in a few words, e.g. `disabled` or `s3 (bucket/prefix)`
+    pub persistence: String,
+}
+
+impl StartupBanner {
+    /// Log the banner, as structured events for JSON logs
+    pub fn log(&self) {
+        debug_assert!(
+            !self.config_source.is_empty() && !self.persistence.is_empty(),
+            "Precondition: the banner needs a config source and persistence status"
+        );
+
+        info!(
+            version = REDIS_VERSION,
+            build = BUILD_VERSION,
+            bits = ARCH_BITS,
+            commit = GIT_SHA1,
+            pid = std::process::id(),
+            "oO0OoO0OoO0Oo Redis is starting oO0OoO0OoO0Oo"
+        );
+        info!(source = %self.config_source, "Configuration loaded");
+        info!(mode = self.mode.name(), port = self.port, "Running");
+        info!(persistence = %self.persistence, "Persistence");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_info_fields_carry_the_version_and_mode() {
+        let fields = info_server_fields(ServerMode::Simulator);
+        assert!(fields.starts_with("redis_version:7.0.0\r\n"));
+        assert!(fields.contains("redis_mode:simulator\r\n"));
+        assert!(fields.contains(&format!("redis_rust_version:{}\r\n", BUILD_VERSION)));
+        assert!(fields.lines().all(|line| line.contains(':')));
+        assert_eq!(lolwut_version_line(), "Redis ver. 7.0.0\n");
+    }
+}
@@ -13,9 +13,13 @@
 //! - Efficient O(log n) divergence detection via Merkle trees
 //! - Bandwidth-efficient: only sync divergent keys
 //! - Crdt-aware: merges values rather than overwriting
+//! - Bandwidth metering: bytes and keys exchanged are counted per peer
+//! - Adaptive scheduling: peers that keep diverging are synced more often,
+//!   quiet peers less often, within configured bounds
 
 use super::lattice::ReplicaId;
 use super::state::{ReplicatedValue, ReplicationDelta};
+use serde::Serialize;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
@@ -31,6 +35,15 @@ pub struct AntiEntropyConfig {
     pub merkle_tree_depth: usize,
     /// Whether to enable automatic sync on partition heal
     pub auto_sync_on_heal: bool,
+    /// Adapt each peer's sync interval to its observed divergence rate
+    /// instead of using `sync_interval_ms` for every peer
+    pub adaptive_sync: bool,
+    /// Shortest adaptive interval (hot peers)
+    pub min_sync_interval_ms: u64,
+    /// Longest adaptive interval (peers that never diverge)
+    pub max_sync_interval_ms: u64,
+    /// Adaptive scheduling aims for this many keys exchanged per round
+    pub target_keys_per_sync: usize,
 }
 
 impl Default for AntiEntropyConfig {
@@ -40,10 +53,73 @@ impl Default for AntiEntropyConfig {
             max_keys_per_sync: 1000,
             merkle_tree_depth: 8, // 256 buckets
             auto_sync_on_heal: true,
+            adaptive_sync: false,
+            min_sync_interval_ms: 100,
+            max_sync_interval_ms: 10_000,
+            target_keys_per_sync: 64,
         }
     }
 }
 
+/// Weight of the newest sample in a peer's divergence rate average
+const DIVERGENCE_RATE_ALPHA: f64 = 0.5;
+
+/// Traffic exchanged with a peer over one or more anti-entropy rounds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncBandwidth {
+    pub rounds: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub keys_sent: u64,
+    pub keys_received: u64,
+}
+
+impl SyncBandwidth {
+    /// Traffic of a single round
+    pub fn round(bytes_sent: u64, bytes_received: u64, keys_sent: u64, keys_received: u64) -> Self {
+        SyncBandwidth {
+            rounds: 1,
+            bytes_sent,
+            bytes_received,
+            keys_sent,
+            keys_received,
+        }
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes_sent + self.bytes_received
+    }
+
+    pub fn add(&mut self, other: &SyncBandwidth) {
+        self.rounds += other.rounds;
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.keys_sent += other.keys_sent;
+        self.keys_received += other.keys_received;
+    }
+}
+
+/// Adaptive sync schedule for one peer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerSchedule {
+    /// Current interval between rounds with this peer
+    pub interval_ms: u64,
+    /// Moving average of keys exchanged per second of elapsed time
+    pub divergence_rate: f64,
+    /// Time of the last round, if any
+    pub last_round_at: Option<u64>,
+}
+
+/// Encoded size of a value on the wire
+fn wire_size<T: Serialize + ?Sized>(value: &T) -> u64 {
+    bincode::serialized_size(value).expect("anti-entropy messages always serialize")
+}
+
+/// Encoded size of the deltas a round ships
+pub fn deltas_wire_size(deltas: &[ReplicationDelta]) -> u64 {
+    wire_size(deltas)
+}
+
 /// A simple hash-based digest of a key-value pair
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct KeyDigest {
@@ -81,7 +157,7 @@ impl KeyDigest {
 }
 
 /// Merkle tree node for efficient state comparison
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct MerkleNode {
     /// Hash of this node (combines children or leaf digests)
     pub hash: u64,
@@ -139,7 +215,7 @@ impl MerkleNode {
 }
 
 /// State digest for efficient comparison
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct StateDigest {
     /// Root hash of merkle tree
     pub root_hash: u64,
@@ -173,7 +249,11 @@ impl StateDigest {
             bucket_digests[bucket].push(digest);
         }
 
-        // Build merkle nodes for each bucket
+        // Build merkle nodes for each bucket, hashing digests in key order
+        // so equal states hash equally whatever the map's iteration order
+        for digests in &mut bucket_digests {
+            digests.sort_unstable_by_key(|d| (d.key_hash, d.value_hash));
+        }
         let buckets: Vec<MerkleNode> = bucket_digests
             .iter()
             .map(|digests| MerkleNode::from_digests(digests))
@@ -200,6 +280,11 @@ impl StateDigest {
         }
     }
 
+    /// Encoded size of this digest on the wire
+    pub fn wire_size(&self) -> u64 {
+        wire_size(self)
+    }
+
     /// Check if this digest differs from another
     pub fn differs_from(&self, other: &StateDigest) -> bool {
         self.root_hash != other.root_hash
@@ -279,10 +364,25 @@ pub struct AntiEntropyManager {
     pub pending_requests: Vec<SyncRequest>,
     /// Pending sync responses
     pub pending_responses: Vec<SyncResponse>,
+    /// Traffic exchanged with each peer
+    pub peer_bandwidth: HashMap<ReplicaId, SyncBandwidth>,
+    /// Traffic exchanged with all peers
+    pub total_bandwidth: SyncBandwidth,
+    /// Adaptive schedule for each peer (only kept when `adaptive_sync` is on)
+    pub peer_schedules: HashMap<ReplicaId, PeerSchedule>,
 }
 
 impl AntiEntropyManager {
     pub fn new(replica_id: ReplicaId, config: AntiEntropyConfig) -> Self {
+        debug_assert!(
+            config.min_sync_interval_ms <= config.max_sync_interval_ms,
+            "Precondition: min sync interval must not exceed the max"
+        );
+        debug_assert!(
+            config.target_keys_per_sync > 0,
+            "Precondition: target keys per sync must be positive"
+        );
+
         AntiEntropyManager {
             config,
             replica_id,
@@ -292,6 +392,9 @@ impl AntiEntropyManager {
             last_sync_time: HashMap::new(),
             pending_requests: Vec::new(),
             pending_responses: Vec::new(),
+            peer_bandwidth: HashMap::new(),
+            total_bandwidth: SyncBandwidth::default(),
+            peer_schedules: HashMap::new(),
         }
     }
 
@@ -310,10 +413,82 @@ impl AntiEntropyManager {
         )
     }
 
+    /// Interval between rounds with a peer: the fixed interval, or the
+    /// peer's adaptive one (starting from the fixed interval, within bounds)
+    pub fn sync_interval(&self, peer: ReplicaId) -> u64 {
+        if !self.config.adaptive_sync {
+            return self.config.sync_interval_ms;
+        }
+        match self.peer_schedules.get(&peer) {
+            Some(schedule) => schedule.interval_ms,
+            None => self.config.sync_interval_ms.clamp(
+                self.config.min_sync_interval_ms,
+                self.config.max_sync_interval_ms,
+            ),
+        }
+    }
+
+    /// Record a finished round with a peer: meter its traffic and, when
+    /// adaptive, re-derive the peer's interval from the keys it exchanged.
+    ///
+    /// The interval is the time expected to accumulate
+    /// `target_keys_per_sync` divergent keys at the peer's average rate;
+    /// a peer with nothing to exchange drifts to the max interval.
+    /// Returns the interval until the next round with this peer.
+    pub fn record_round(
+        &mut self,
+        peer: ReplicaId,
+        round: SyncBandwidth,
+        current_time: u64,
+    ) -> u64 {
+        debug_assert_eq!(round.rounds, 1, "Precondition: one round at a time");
+
+        self.last_sync_time.insert(peer, current_time);
+        self.peer_bandwidth.entry(peer).or_default().add(&round);
+        self.total_bandwidth.add(&round);
+
+        if self.config.adaptive_sync {
+            let initial = self.sync_interval(peer);
+            let (min, max) = (
+                self.config.min_sync_interval_ms,
+                self.config.max_sync_interval_ms,
+            );
+            let target = self.config.target_keys_per_sync as f64;
+            let schedule = self.peer_schedules.entry(peer).or_insert(PeerSchedule {
+                interval_ms: initial,
+                divergence_rate: 0.0,
+                last_round_at: None,
+            });
+
+            // The first round only starts the clock: there is no elapsed
+            // time to turn its keys into a rate
+            if let Some(last) = schedule.last_round_at {
+                let elapsed_ms = current_time.saturating_sub(last).max(1);
+                let keys = (round.keys_sent + round.keys_received) as f64;
+                let sample = keys * 1000.0 / elapsed_ms as f64;
+                schedule.divergence_rate = DIVERGENCE_RATE_ALPHA * sample
+                    + (1.0 - DIVERGENCE_RATE_ALPHA) * schedule.divergence_rate;
+                schedule.interval_ms = if schedule.divergence_rate > 0.0 {
+                    ((target * 1000.0 / schedule.divergence_rate) as u64).clamp(min, max)
+                } else {
+                    max
+                };
+            }
+            schedule.last_round_at = Some(current_time);
+
+            debug_assert!(
+                (min..=max).contains(&schedule.interval_ms),
+                "Postcondition: adaptive interval must stay within bounds"
+            );
+        }
+
+        self.sync_interval(peer)
+    }
+
     /// Check if we should sync with a peer
     pub fn should_sync(&self, peer: ReplicaId, current_time: u64) -> bool {
         if let Some(&last_sync) = self.last_sync_time.get(&peer) {
-            current_time - last_sync >= self.config.sync_interval_ms
+            current_time.saturating_sub(last_sync) >= self.sync_interval(peer)
         } else {
             true // Never synced, should sync
         }
@@ -435,7 +610,7 @@ impl AntiEntropyManager {
 
         // Add peers we haven't synced with recently
         for (&peer, &last_sync) in &self.last_sync_time {
-            if current_time - last_sync >= self.config.sync_interval_ms {
+            if current_time.saturating_sub(last_sync) >= self.sync_interval(peer) {
                 if !peers.contains(&peer) {
                     peers.push(peer);
                 }
@@ -595,4 +770,62 @@ mod tests {
         let peers = manager.peers_needing_sync(0);
         assert!(peers.contains(&r2));
     }
+
+    fn adaptive_config() -> AntiEntropyConfig {
+        AntiEntropyConfig {
+            adaptive_sync: true,
+            sync_interval_ms: 1000,
+            min_sync_interval_ms: 100,
+            max_sync_interval_ms: 8000,
+            target_keys_per_sync: 10,
+            ..AntiEntropyConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_adaptive_interval_follows_divergence_rate() {
+        let hot = ReplicaId::new(2);
+        let quiet = ReplicaId::new(3);
+        let mut manager = AntiEntropyManager::new(ReplicaId::new(1), adaptive_config());
+        assert_eq!(manager.sync_interval(hot), 1000);
+
+        let mut now = 0;
+        for _ in 0..10 {
+            let interval = manager.record_round(hot, SyncBandwidth::round(100, 100, 50, 50), now);
+            manager.record_round(quiet, SyncBandwidth::round(100, 100, 0, 0), now);
+            assert!(manager.should_sync(hot, now + interval));
+            now += interval;
+        }
+        // 100 keys a round converges on 10 keys per round: the floor
+        assert_eq!(manager.sync_interval(hot), 100);
+        assert_eq!(manager.sync_interval(quiet), 8000);
+        assert!(!manager.should_sync(quiet, now + 100));
+
+        let hot_traffic = manager.peer_bandwidth[&hot];
+        assert_eq!(hot_traffic.rounds, 10);
+        assert_eq!(hot_traffic.keys_received, 500);
+        assert_eq!(manager.total_bandwidth.total_bytes(), 20 * 200);
+    }
+
+    #[test]
+    fn test_fixed_schedule_meters_without_adapting() {
+        let peer = ReplicaId::new(2);
+        let mut manager = AntiEntropyManager::new(ReplicaId::new(1), AntiEntropyConfig::default());
+        manager.record_round(peer, SyncBandwidth::round(10, 20, 1, 2), 0);
+        assert_eq!(manager.record_round(peer, SyncBandwidth::round(10, 20, 500, 500), 5), 1000);
+        assert!(manager.peer_schedules.is_empty());
+        assert!(!manager.should_sync(peer, 999));
+        assert!(manager.should_sync(peer, 1005));
+        assert_eq!(manager.peer_bandwidth[&peer].bytes_received, 40);
+    }
+
+    #[test]
+    fn test_digest_wire_size_counts_buckets() {
+        let r1 = ReplicaId::new(1);
+        let keys = HashMap::new();
+        let shallow = StateDigest::from_state(&keys, r1, 0, 4);
+        let deep = StateDigest::from_state(&keys, r1, 0, 8);
+        assert!(deep.wire_size() > shallow.wire_size());
+        assert_eq!(deltas_wire_size(&[]), 8);
+    }
 }
@@ -5,6 +5,9 @@ use std::time::Duration;
 pub enum ConsistencyLevel {
     Eventual,
     Causal,
+    /// Writes go through a single leader chosen by quorum election
+    /// (`election.rs`); other nodes refuse them
+    Strong,
 }
 
 impl Default for ConsistencyLevel {
@@ -0,0 +1,692 @@
+//! Quorum Leader Election (Raft-lite) for the Strongly-Consistent Mode
+//!
+//! Raft's election half without its log. Nodes count terms; a follower that
+//! hears no leader for a randomized timeout stands as candidate for the next
+//! term, and a candidate holding votes from a majority leads that term. With
+//! no log to compare, any candidate may win: the election only decides who
+//! leads, not what they hold.
+//!
+//! A node grants at most one vote per term. The vote (and every term change)
+//! is appended to the node's WAL and synced before it takes effect, so a
+//! node that crashes and restarts cannot vote again in a term it voted in.
+//!
+//! Followers acknowledge heartbeats, and a leader whose heartbeat a majority
+//! acknowledged holds a lease for `lease_ms` from sending it. A node that
+//! heard from its leader less than the minimum election timeout ago ignores
+//! RequestVote, neither adopting the term nor voting (Raft's check against
+//! disruptive servers); so does a leader, and a node that just restarted and
+//! may have acknowledged a lease it no longer remembers. No majority can
+//! elect anyone else before the lease runs out, even with every clock off by
+//! `max_clock_drift_percent`, so the leader may serve reads from its own
+//! state without a round of messages.
+//!
+//! `Election` does no I/O of its own beyond the WAL: the caller feeds it time
+//! (`tick`) and messages (`handle`) and delivers the messages they return.
+//! Timeouts come from the `Rng` it owns, so a seeded RNG makes elections
+//! deterministic.
+//!
+//! # TigerStyle Invariants
+//!
+//! - At most one leader per term (a majority votes once per term)
+//! - The term never decreases, across restarts too
+//! - A vote is durable before it is granted
+//! - A lease ends before any node that acknowledged it can vote
+
+use super::lattice::ReplicaId;
+use crate::io::Rng;
+use crate::streaming::wal::{WalEntry, WalRotator};
+use crate::streaming::wal_store::{WalError, WalStore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Vote WAL entries are tiny; rotate rarely
+const VOTE_WAL_MAX_FILE_SIZE: usize = 64 * 1024;
+
+/// Election timing, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElectionConfig {
+    /// A follower stands for election after hearing no leader for a random
+    /// time in `election_timeout_min_ms..=election_timeout_max_ms`
+    pub election_timeout_min_ms: u64,
+    pub election_timeout_max_ms: u64,
+    /// How often a leader sends heartbeats; well under the minimum timeout
+    pub heartbeat_interval_ms: u64,
+    /// How far any node's clock may run fast or slow against real time, in
+    /// percent; leases shrink to stay safe under it
+    pub max_clock_drift_percent: u64,
+}
+
+impl Default for ElectionConfig {
+    fn default() -> Self {
+        ElectionConfig {
+            election_timeout_min_ms: 150,
+            election_timeout_max_ms: 300,
+            heartbeat_interval_ms: 50,
+            max_clock_drift_percent: 10,
+        }
+    }
+}
+
+impl ElectionConfig {
+    /// How long an acknowledged heartbeat lets the leader serve alone, from
+    /// sending it. A voter waits out the minimum timeout (less a millisecond
+    /// of rounding) on a clock that may run fast, while the lease runs on a
+    /// clock that may run slow.
+    pub fn lease_ms(&self) -> u64 {
+        (self.election_timeout_min_ms - 1) * (100 - self.max_clock_drift_percent)
+            / (100 + self.max_clock_drift_percent)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+/// What a node must remember across a crash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct HardState {
+    pub term: u64,
+    /// The candidate voted for in `term`, if any
+    pub voted_for: Option<ReplicaId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElectionMessage {
+    /// A candidate asks for a vote in `term`
+    RequestVote { term: u64 },
+    /// Answer to `RequestVote`, at the voter's term
+    Vote { term: u64, granted: bool },
+    /// A leader asserts its leadership of `term`, at `sent_ms` on its clock
+    Heartbeat { term: u64, sent_ms: u64 },
+    /// Answer to `Heartbeat`, at the follower's term
+    HeartbeatAck { term: u64, sent_ms: u64 },
+}
+
+impl ElectionMessage {
+    pub fn term(&self) -> u64 {
+        match *self {
+            ElectionMessage::RequestVote { term }
+            | ElectionMessage::Vote { term, .. }
+            | ElectionMessage::Heartbeat { term, .. }
+            | ElectionMessage::HeartbeatAck { term, .. } => term,
+        }
+    }
+}
+
+/// Messages to send, by recipient
+pub type Outbox = Vec<(ReplicaId, ElectionMessage)>;
+
+/// One node's view of the election
+pub struct Election<S: WalStore, R: Rng> {
+    id: ReplicaId,
+    peers: Vec<ReplicaId>,
+    config: ElectionConfig,
+    hard: HardState,
+    role: Role,
+    leader: Option<ReplicaId>,
+    /// Votes received as candidate, including its own
+    votes: Vec<ReplicaId>,
+    /// Election timeout, or the next heartbeat while leader
+    deadline_ms: u64,
+    /// When this node last heard from its leader, or restarted; it votes for
+    /// no one until the minimum election timeout has passed since
+    leader_contact_ms: u64,
+    /// As leader: the latest heartbeat each peer acknowledged, by send time
+    acked_ms: HashMap<ReplicaId, u64>,
+    wal: WalRotator<S>,
+    rng: R,
+}
+
+impl<S: WalStore, R: Rng> Election<S, R> {
+    /// Start a node as follower, recovering its term and vote from `store`
+    pub fn open(
+        id: ReplicaId,
+        peers: Vec<ReplicaId>,
+        config: ElectionConfig,
+        store: S,
+        rng: R,
+        now_ms: u64,
+    ) -> Result<Self, WalError> {
+        debug_assert!(
+            !peers.contains(&id),
+            "Precondition: a node is not its own peer"
+        );
+        debug_assert!(
+            config.heartbeat_interval_ms > 0
+                && config.heartbeat_interval_ms < config.election_timeout_min_ms
+                && config.election_timeout_min_ms <= config.election_timeout_max_ms,
+            "Precondition: heartbeats must beat the election timeout: {:?}",
+            config
+        );
+        debug_assert!(
+            config.max_clock_drift_percent < 100,
+            "Precondition: clocks must run forward: {:?}",
+            config
+        );
+
+        let wal = WalRotator::new(store, VOTE_WAL_MAX_FILE_SIZE)?;
+        let hard = match wal.recover_all_entries()?.last() {
+            Some(entry) => bincode::deserialize(&entry.data)
+                .map_err(|e| WalError::Corruption(format!("deserialize: {}", e)))?,
+            None => HardState::default(),
+        };
+
+        let mut election = Election {
+            id,
+            peers,
+            config,
+            hard,
+            role: Role::Follower,
+            leader: None,
+            votes: Vec::new(),
+            deadline_ms: 0,
+            leader_contact_ms: now_ms,
+            acked_ms: HashMap::new(),
+            wal,
+            rng,
+        };
+        election.reset_election_timeout(now_ms);
+        Ok(election)
+    }
+
+    pub fn id(&self) -> ReplicaId {
+        self.id
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn term(&self) -> u64 {
+        self.hard.term
+    }
+
+    pub fn hard_state(&self) -> HardState {
+        self.hard
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.role == Role::Leader
+    }
+
+    /// The leader of the current term, as far as this node knows
+    pub fn leader(&self) -> Option<ReplicaId> {
+        self.leader
+    }
+
+    /// When `tick` next has work: an election timeout or a heartbeat
+    pub fn deadline_ms(&self) -> u64 {
+        self.deadline_ms
+    }
+
+    fn quorum(&self) -> usize {
+        let cluster_size = self.peers.len() + 1;
+        cluster_size / 2 + 1
+    }
+
+    /// When this leader's lease runs out: `lease_ms` after the newest
+    /// heartbeat that a majority, itself included, acknowledged
+    pub fn lease_expires_ms(&self) -> Option<u64> {
+        if self.role != Role::Leader {
+            return None;
+        }
+        let needed = self.quorum() - 1;
+        if needed == 0 {
+            return Some(u64::MAX);
+        }
+        let mut acked: Vec<u64> = self.acked_ms.values().copied().collect();
+        if acked.len() < needed {
+            return None;
+        }
+        acked.sort_unstable_by(|a, b| b.cmp(a));
+        Some(acked[needed - 1] + self.config.lease_ms())
+    }
+
+    /// Whether this node leads under a lease at `now_ms`, so that no other
+    /// node can have been elected and it may serve reads alone
+    pub fn has_lease(&self, now_ms: u64) -> bool {
+        self.lease_expires_ms()
+            .is_some_and(|expires_ms| now_ms < expires_ms)
+    }
+
+    /// Advance to `now_ms`: stand for election if the timeout passed, or send
+    /// heartbeats if leading
+    pub fn tick(&mut self, now_ms: u64) -> Result<Outbox, WalError> {
+        if now_ms < self.deadline_ms {
+            return Ok(Vec::new());
+        }
+        match self.role {
+            Role::Leader => {
+                self.deadline_ms = now_ms + self.config.heartbeat_interval_ms;
+                Ok(self.broadcast(ElectionMessage::Heartbeat {
+                    term: self.hard.term,
+                    sent_ms: now_ms,
+                }))
+            }
+            Role::Follower | Role::Candidate => self.start_election(now_ms),
+        }
+    }
+
+    /// Process a message from peer `from`
+    pub fn handle(
+        &mut self,
+        from: ReplicaId,
+        message: ElectionMessage,
+        now_ms: u64,
+    ) -> Result<Outbox, WalError> {
+        debug_assert!(
+            self.peers.contains(&from),
+            "Precondition: message from unknown node {:?}",
+            from
+        );
+
+        if matches!(message, ElectionMessage::RequestVote { .. })
+            && (self.role == Role::Leader
+                || now_ms < self.leader_contact_ms + self.config.election_timeout_min_ms)
+        {
+            // A lease this node granted may still be running
+            return Ok(Vec::new());
+        }
+        self.observe_term(message.term())?;
+        let term = self.hard.term;
+
+        match message {
+            ElectionMessage::RequestVote { term: asked } => {
+                let granted = asked == term
+                    && (self.hard.voted_for.is_none() || self.hard.voted_for == Some(from));
+                if granted {
+                    if self.hard.voted_for.is_none() {
+                        self.persist(HardState {
+                            term,
+                            voted_for: Some(from),
+                        })?;
+                    }
+                    self.reset_election_timeout(now_ms);
+                }
+                Ok(vec![(from, ElectionMessage::Vote { term, granted })])
+            }
+            ElectionMessage::Vote {
+                term: voted,
+                granted,
+            } => {
+                if self.role == Role::Candidate
+                    && voted == term
+                    && granted
+                    && !self.votes.contains(&from)
+                {
+                    self.votes.push(from);
+                    if self.votes.len() >= self.quorum() {
+                        return Ok(self.become_leader(now_ms));
+                    }
+                }
+                Ok(Vec::new())
+            }
+            ElectionMessage::Heartbeat { term: led, sent_ms } => {
+                // A leader of an older term learns of this one from the ack
+                if led == term {
+                    debug_assert!(
+                        self.role != Role::Leader,
+                        "Invariant: two leaders in term {}",
+                        term
+                    );
+                    self.role = Role::Follower;
+                    self.leader = Some(from);
+                    self.votes.clear();
+                    self.leader_contact_ms = now_ms;
+                    self.reset_election_timeout(now_ms);
+                }
+                Ok(vec![(
+                    from,
+                    ElectionMessage::HeartbeatAck { term, sent_ms },
+                )])
+            }
+            ElectionMessage::HeartbeatAck {
+                term: acked,
+                sent_ms,
+            } => {
+                if self.role == Role::Leader && acked == term {
+                    let latest = self.acked_ms.entry(from).or_insert(sent_ms);
+                    *latest = (*latest).max(sent_ms);
+                }
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Follow `term` if it is newer, with no vote cast in it yet. A leader
+    /// fenced off by a replica that has seen a newer term steps down here.
+    pub fn observe_term(&mut self, term: u64) -> Result<(), WalError> {
+        if term > self.hard.term {
+            self.persist(HardState {
+                term,
+                voted_for: None,
+            })?;
+            self.role = Role::Follower;
+            self.leader = None;
+            self.votes.clear();
+        }
+        Ok(())
+    }
+
+    fn start_election(&mut self, now_ms: u64) -> Result<Outbox, WalError> {
+        let term = self
+            .hard
+            .term
+            .checked_add(1)
+            .expect("term overflow is unreachable");
+        self.persist(HardState {
+            term,
+            voted_for: Some(self.id),
+        })?;
+        self.role = Role::Candidate;
+        self.leader = None;
+        self.votes = vec![self.id];
+        self.reset_election_timeout(now_ms);
+
+        if self.votes.len() >= self.quorum() {
+            return Ok(self.become_leader(now_ms));
+        }
+        Ok(self.broadcast(ElectionMessage::RequestVote { term }))
+    }
+
+    fn become_leader(&mut self, now_ms: u64) -> Outbox {
+        debug_assert_eq!(
+            self.hard.voted_for,
+            Some(self.id),
+            "Precondition: a leader voted for itself"
+        );
+        self.role = Role::Leader;
+        self.leader = Some(self.id);
+        self.votes.clear();
+        self.acked_ms.clear();
+        self.deadline_ms = now_ms + self.config.heartbeat_interval_ms;
+        self.broadcast(ElectionMessage::Heartbeat {
+            term: self.hard.term,
+            sent_ms: now_ms,
+        })
+    }
+
+    fn broadcast(&self, message: ElectionMessage) -> Outbox {
+        self.peers.iter().map(|&peer| (peer, message)).collect()
+    }
+
+    fn reset_election_timeout(&mut self, now_ms: u64) {
+        self.deadline_ms = now_ms
+            + self.rng.gen_range(
+                self.config.election_timeout_min_ms,
+                self.config.election_timeout_max_ms + 1,
+            );
+    }
+
+    /// Make `hard` durable, then adopt it
+    fn persist(&mut self, hard: HardState) -> Result<(), WalError> {
+        debug_assert!(
+            hard.term >= self.hard.term,
+            "Precondition: term went back from {} to {}",
+            self.hard.term,
+            hard.term
+        );
+        let data = bincode::serialize(&hard)
+            .map_err(|e| WalError::Corruption(format!("serialize: {}", e)))?;
+        let entry = WalEntry {
+            checksum: crc32fast::hash(&data),
+            data,
+            timestamp: hard.term,
+            batch: false,
+        };
+        self.wal.append(&entry)?;
+        self.wal.sync()?;
+        self.hard = hard;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::simulation::SimulatedRng;
+    use crate::streaming::wal_store::InMemoryWalStore;
+
+    type TestElection = Election<InMemoryWalStore, SimulatedRng>;
+
+    fn open(id: u64, of: u64, store: InMemoryWalStore, now_ms: u64) -> TestElection {
+        let peers = (1..=of).filter(|&p| p != id).map(ReplicaId::new).collect();
+        Election::open(
+            ReplicaId::new(id),
+            peers,
+            ElectionConfig::default(),
+            store,
+            SimulatedRng::new(id),
+            now_ms,
+        )
+        .unwrap()
+    }
+
+    /// Deliver messages until none are left, with no losses
+    fn pump(nodes: &mut [TestElection], from: ReplicaId, outbox: Outbox, now_ms: u64) {
+        let mut queue: Vec<(ReplicaId, ReplicaId, ElectionMessage)> =
+            outbox.into_iter().map(|(to, m)| (from, to, m)).collect();
+        while !queue.is_empty() {
+            let (from, to, message) = queue.remove(0);
+            let node = &mut nodes[to.0 as usize - 1];
+            let replies = node.handle(from, message, now_ms).unwrap();
+            queue.extend(replies.into_iter().map(|(next, m)| (to, next, m)));
+        }
+    }
+
+    #[test]
+    fn test_single_node_leads_itself() {
+        let mut node = open(1, 1, InMemoryWalStore::new(), 0);
+        assert!(node.tick(0).unwrap().is_empty(), "not due yet");
+        assert!(node.tick(300).unwrap().is_empty());
+        assert!(node.is_leader());
+        assert_eq!(node.term(), 1);
+    }
+
+    #[test]
+    fn test_first_timeout_wins_the_election() {
+        let mut nodes: Vec<TestElection> = (1..=3)
+            .map(|id| open(id, 3, InMemoryWalStore::new(), 0))
+            .collect();
+        let first = (0..3).min_by_key(|&i| nodes[i].deadline_ms()).unwrap();
+        let (id, now) = (nodes[first].id(), nodes[first].deadline_ms());
+        let outbox = nodes[first].tick(now).unwrap();
+        pump(&mut nodes, id, outbox, now);
+
+        assert!(nodes[first].is_leader());
+        for node in &nodes {
+            assert_eq!(node.term(), 1);
+            assert_eq!(node.leader(), Some(id));
+        }
+        assert_eq!(
+            nodes.iter().filter(|n| n.is_leader()).count(),
+            1,
+            "heartbeats made the others followers"
+        );
+    }
+
+    #[test]
+    fn test_vote_survives_restart() {
+        let store = InMemoryWalStore::new();
+        let mut voter = open(1, 3, store.clone(), 0);
+        let reply = voter
+            .handle(
+                ReplicaId::new(2),
+                ElectionMessage::RequestVote { term: 4 },
+                200,
+            )
+            .unwrap();
+        assert_eq!(
+            reply,
+            vec![(
+                ReplicaId::new(2),
+                ElectionMessage::Vote {
+                    term: 4,
+                    granted: true
+                }
+            )]
+        );
+
+        store.simulate_crash();
+        let mut voter = open(1, 3, store, 210);
+        assert_eq!(
+            voter.hard_state(),
+            HardState {
+                term: 4,
+                voted_for: Some(ReplicaId::new(2))
+            }
+        );
+        assert!(
+            voter
+                .handle(
+                    ReplicaId::new(3),
+                    ElectionMessage::RequestVote { term: 4 },
+                    220
+                )
+                .unwrap()
+                .is_empty(),
+            "a restarted node waits out any lease it may have granted"
+        );
+        let reply = voter
+            .handle(
+                ReplicaId::new(3),
+                ElectionMessage::RequestVote { term: 4 },
+                400,
+            )
+            .unwrap();
+        assert_eq!(
+            reply[0].1,
+            ElectionMessage::Vote {
+                term: 4,
+                granted: false
+            },
+            "one vote per term, crash or not"
+        );
+    }
+
+    #[test]
+    fn test_leader_steps_down_on_newer_term() {
+        let mut node = open(1, 3, InMemoryWalStore::new(), 0);
+        let deadline = node.deadline_ms();
+        node.tick(deadline).unwrap();
+        for voter in [2, 3] {
+            node.handle(
+                ReplicaId::new(voter),
+                ElectionMessage::Vote {
+                    term: 1,
+                    granted: true,
+                },
+                deadline,
+            )
+            .unwrap();
+        }
+        assert!(node.is_leader());
+
+        node.handle(
+            ReplicaId::new(2),
+            ElectionMessage::Heartbeat {
+                term: 3,
+                sent_ms: 400,
+            },
+            400,
+        )
+        .unwrap();
+        assert_eq!(node.role(), Role::Follower);
+        assert_eq!((node.term(), node.leader()), (3, Some(ReplicaId::new(2))));
+    }
+
+    #[test]
+    fn test_fenced_leader_adopts_the_newer_term() {
+        let store = InMemoryWalStore::new();
+        let mut node = open(1, 3, store.clone(), 0);
+        let deadline = node.deadline_ms();
+        node.tick(deadline).unwrap();
+        node.handle(
+            ReplicaId::new(2),
+            ElectionMessage::Vote {
+                term: 1,
+                granted: true,
+            },
+            deadline,
+        )
+        .unwrap();
+        assert!(node.is_leader());
+
+        // An older or equal term changes nothing
+        node.observe_term(1).unwrap();
+        assert!(node.is_leader());
+
+        node.observe_term(5).unwrap();
+        assert_eq!(node.role(), Role::Follower);
+        assert_eq!((node.term(), node.leader()), (5, None));
+        store.simulate_crash();
+        assert_eq!(open(1, 3, store, 0).term(), 5, "the newer term is synced");
+    }
+
+    #[test]
+    fn test_lease_runs_from_the_acknowledged_heartbeat() {
+        let mut nodes: Vec<TestElection> = (1..=3)
+            .map(|id| open(id, 3, InMemoryWalStore::new(), 0))
+            .collect();
+        let now = nodes[0].deadline_ms();
+        let outbox = nodes[0].tick(now).unwrap();
+        assert_eq!(
+            nodes[0].lease_expires_ms(),
+            None,
+            "candidates hold no lease"
+        );
+        pump(&mut nodes, ReplicaId::new(1), outbox, now);
+
+        let lease = ElectionConfig::default().lease_ms();
+        assert!(nodes[0].is_leader());
+        assert_eq!(nodes[0].lease_expires_ms(), Some(now + lease));
+        assert!(nodes[0].has_lease(now + lease - 1));
+        assert!(!nodes[0].has_lease(now + lease));
+
+        // Followers and the leader refuse to vote while the lease may run
+        let ask = ElectionMessage::RequestVote { term: 2 };
+        assert!(nodes[0]
+            .handle(ReplicaId::new(3), ask, now + 1_000)
+            .unwrap()
+            .is_empty());
+        assert!(nodes[1]
+            .handle(ReplicaId::new(3), ask, now + 149)
+            .unwrap()
+            .is_empty());
+        assert_eq!(nodes[1].term(), 1);
+        assert_eq!(
+            nodes[1].handle(ReplicaId::new(3), ask, now + 150).unwrap(),
+            vec![(
+                ReplicaId::new(3),
+                ElectionMessage::Vote {
+                    term: 2,
+                    granted: true
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_lease_ends_before_a_drifting_voter_can_vote() {
+        for drift in [0, 5, 10, 25, 50] {
+            for min in [10, 150, 1_000] {
+                let config = ElectionConfig {
+                    election_timeout_min_ms: min,
+                    election_timeout_max_ms: min * 2,
+                    heartbeat_interval_ms: min / 3,
+                    max_clock_drift_percent: drift,
+                };
+                // The lease on a clock running `drift` slow against the
+                // timeout on one running `drift` fast, less a rounding ms
+                assert!(
+                    config.lease_ms() * (100 + drift) <= (min - 1) * (100 - drift),
+                    "{:?}",
+                    config
+                );
+            }
+        }
+    }
+}
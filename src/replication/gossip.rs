@@ -1,6 +1,7 @@
 use super::config::ReplicationConfig;
 use super::gossip_router::GossipRouter;
 use super::lattice::ReplicaId;
+use super::offsets::ReplicationOffsets;
 use super::state::ReplicationDelta;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -20,6 +21,9 @@ pub enum GossipMessage {
         source_replica: ReplicaId,
         deltas: Vec<ReplicationDelta>,
         epoch: u64,
+        /// Replication offsets of `deltas`, first and last (see `offsets`)
+        #[serde(default)]
+        offsets: Option<(u64, u64)>,
     },
     /// Targeted delta batch - sent to specific replica (selective gossip mode)
     TargetedDelta {
@@ -40,6 +44,13 @@ pub enum GossipMessage {
         source_replica: ReplicaId,
         epoch: u64,
     },
+    /// `source_replica` holds every delta of `target_replica` through
+    /// `offset`. Broadcast; replicas other than the target ignore it.
+    Ack {
+        source_replica: ReplicaId,
+        target_replica: ReplicaId,
+        offset: u64,
+    },
 }
 
 impl GossipMessage {
@@ -48,6 +59,7 @@ impl GossipMessage {
             source_replica: source,
             deltas,
             epoch,
+            offsets: None,
         }
     }
 
@@ -73,6 +85,14 @@ impl GossipMessage {
         }
     }
 
+    pub fn new_ack(source: ReplicaId, target: ReplicaId, offset: u64) -> Self {
+        GossipMessage::Ack {
+            source_replica: source,
+            target_replica: target,
+            offset,
+        }
+    }
+
     /// Get source replica ID from any message type
     pub fn source_replica(&self) -> ReplicaId {
         match self {
@@ -81,6 +101,15 @@ impl GossipMessage {
             GossipMessage::SyncRequest { source_replica, .. } => *source_replica,
             GossipMessage::SyncResponse { source_replica, .. } => *source_replica,
             GossipMessage::Heartbeat { source_replica, .. } => *source_replica,
+            GossipMessage::Ack { source_replica, .. } => *source_replica,
+        }
+    }
+
+    /// Replication offsets a broadcast delta batch carries
+    pub fn offsets(&self) -> Option<(u64, u64)> {
+        match self {
+            GossipMessage::DeltaBatch { offsets, .. } => *offsets,
+            _ => None,
         }
     }
 
@@ -152,6 +181,8 @@ pub struct GossipState {
     pub outbound_queue: Vec<RoutedMessage>,
     /// Optional gossip router for selective gossip (partitioned mode)
     gossip_router: Option<GossipRouter>,
+    /// Offsets of broadcast deltas and peers' acks of them, for WAIT
+    pub offsets: ReplicationOffsets,
 }
 
 impl GossipState {
@@ -196,6 +227,7 @@ impl GossipState {
             config,
             outbound_queue: Vec::new(),
             gossip_router: None,
+            offsets: ReplicationOffsets::default(),
         }
     }
 
@@ -207,6 +239,7 @@ impl GossipState {
             config,
             outbound_queue: Vec::new(),
             gossip_router: Some(router),
+            offsets: ReplicationOffsets::default(),
         }
     }
 
@@ -275,21 +308,61 @@ impl GossipState {
         }
 
         // Fallback: broadcast to all peers
-        let msg = GossipMessage::new_delta_batch(self.replica_id, deltas, self.epoch);
-        self.outbound_queue.push(RoutedMessage::broadcast(msg));
-        self.enforce_outbound_capacity();
+        self.queue_deltas_broadcast(deltas);
     }
 
-    /// Queue deltas using broadcast (ignore router).
+    /// Queue deltas using broadcast (ignore router), numbered with the next
+    /// replication offsets.
     /// Enforces `MAX_OUTBOUND_QUEUE` capacity after queueing.
     pub fn queue_deltas_broadcast(&mut self, deltas: Vec<ReplicationDelta>) {
         if !deltas.is_empty() {
-            let msg = GossipMessage::new_delta_batch(self.replica_id, deltas, self.epoch);
+            let offsets = self.offsets.advance(deltas.len());
+            let msg = GossipMessage::DeltaBatch {
+                source_replica: self.replica_id,
+                deltas,
+                epoch: self.epoch,
+                offsets,
+            };
             self.outbound_queue.push(RoutedMessage::broadcast(msg));
             self.enforce_outbound_capacity();
         }
     }
 
+    /// Record a broadcast batch from `source`, acknowledging it when this
+    /// replica now holds more of `source`'s deltas
+    pub fn record_delivery(&mut self, source: ReplicaId, first: u64, last: u64) {
+        if let Some(offset) = self.offsets.deliver(source, first, last) {
+            self.queue_ack(source, offset);
+        }
+    }
+
+    /// Re-acknowledge `source` in reply to its heartbeat, so it counts this
+    /// replica as connected even before any deltas flow
+    pub fn ack_heartbeat(&mut self, source: ReplicaId) {
+        self.offsets.add_peer(source);
+        let offset = self.offsets.applied_through(source);
+        self.queue_ack(source, offset);
+    }
+
+    fn queue_ack(&mut self, target: ReplicaId, offset: u64) {
+        let msg = GossipMessage::new_ack(self.replica_id, target, offset);
+        self.outbound_queue.push(RoutedMessage::broadcast(msg));
+        self.enforce_outbound_capacity();
+    }
+
+    /// Record an ack from another replica. An ack addressed elsewhere only
+    /// shows `source` is connected.
+    pub fn record_ack(&mut self, source: ReplicaId, target: ReplicaId, offset: u64) {
+        if source == self.replica_id {
+            return;
+        }
+        if target == self.replica_id {
+            self.offsets.record_ack(source, offset);
+        } else {
+            self.offsets.add_peer(source);
+        }
+    }
+
     pub fn queue_heartbeat(&mut self) {
         let msg = GossipMessage::new_heartbeat(self.replica_id, self.epoch);
         self.outbound_queue.push(RoutedMessage::broadcast(msg));
@@ -498,6 +571,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_broadcast_batches_carry_offsets_and_deliveries_are_acked() {
+        let mut state = GossipState::new(test_config());
+        state.queue_deltas(vec![make_delta("a"), make_delta("b")]);
+        state.queue_deltas(vec![make_delta("c")]);
+        let offsets: Vec<_> = state
+            .drain_outbound()
+            .iter()
+            .map(|routed| routed.message.offsets())
+            .collect();
+        assert_eq!(offsets, vec![Some((1, 2)), Some((3, 3))]);
+
+        let peer = ReplicaId::new(2);
+        state.record_delivery(peer, 1, 4);
+        state.record_delivery(peer, 2, 3);
+        let acks = state.drain_outbound();
+        assert_eq!(acks.len(), 1, "only progress is acknowledged");
+        assert!(matches!(
+            acks[0].message,
+            GossipMessage::Ack { target_replica, offset: 4, .. } if target_replica == peer
+        ));
+    }
+
+    #[test]
+    fn test_record_ack_ignores_acks_for_other_replicas() {
+        let mut state = GossipState::new(test_config());
+        state.queue_deltas(vec![make_delta("a")]);
+        state.record_ack(ReplicaId::new(2), ReplicaId::new(3), 1);
+        assert_eq!(state.offsets.replicas_acked(0), 1);
+        assert_eq!(state.offsets.replicas_acked(1), 0);
+        state.record_ack(ReplicaId::new(2), state.replica_id, 1);
+        assert_eq!(state.offsets.replicas_acked(1), 1);
+    }
+
     #[test]
     fn test_verify_invariants_on_gossip_state() {
         let mut state = GossipState::new(test_config());
@@ -191,6 +191,14 @@ impl VectorClock {
         VectorClock { clocks: merged }
     }
 
+    /// Non-zero entries ordered by replica
+    pub fn entries(&self) -> Vec<(ReplicaId, u64)> {
+        let mut entries: Vec<(ReplicaId, u64)> =
+            self.clocks.iter().map(|(&id, &count)| (id, count)).collect();
+        entries.sort_unstable_by_key(|(id, _)| id.0);
+        entries
+    }
+
     pub fn happens_before(&self, other: &Self) -> bool {
         let mut dominated = false;
         for (replica_id, &self_count) in &self.clocks {
@@ -1,10 +1,12 @@
 pub mod anti_entropy;
 pub mod config;
 pub mod crdt_dst;
+pub mod election;
 pub mod gossip;
 pub mod gossip_router;
 pub mod hash_ring;
 pub mod lattice;
+pub mod offsets;
 pub mod state;
 
 pub use anti_entropy::{
@@ -12,12 +14,15 @@ pub use anti_entropy::{
     SyncResponse,
 };
 pub use config::{ConsistencyLevel, ReplicationConfig};
+pub use election::{Election, ElectionConfig, ElectionMessage};
 pub use gossip::{GossipMessage, GossipState, RoutedMessage};
 pub use gossip_router::{GossipRouter, RoutingStats, RoutingTable};
 pub use hash_ring::{HashRing, VirtualNode};
 pub use lattice::{
     GCounter, GSet, LamportClock, LwwRegister, ORSet, PNCounter, ReplicaId, UniqueTag, VectorClock,
 };
+pub use offsets::{AppliedOffsets, ReplicationOffsets};
 pub use state::{
-    CrdtTypeMismatchError, CrdtValue, ReplicatedValue, ReplicationDelta, ShardReplicaState,
+    CrdtTypeMismatchError, CrdtValue, DeltaRejection, ReplicatedValue, ReplicationDelta,
+    ShardReplicaState,
 };
@@ -0,0 +1,196 @@
+//! Replication offsets and acknowledgements, for WAIT
+//!
+//! Every delta a replica broadcasts takes the next number of its
+//! replication offset, and a broadcast batch carries the range it holds.
+//! A peer acknowledges the highest offset through which it holds every
+//! delta. A batch lost on the way leaves a gap that holds the peer's acks
+//! back: anti-entropy repairs the data, not the offsets, so WAIT keeps
+//! counting that peer as behind.
+//!
+//! Offsets live in memory and start over when a replica restarts. A batch
+//! numbered from 1 tells its peers the sender started over, and acks past
+//! the sender's offset (from its previous run) are dropped.
+//!
+//! Targeted (selective gossip) batches carry no offsets, so WAIT only sees
+//! replicas fed by broadcast.
+
+use super::lattice::ReplicaId;
+use std::collections::{BTreeMap, HashMap};
+
+/// Which of one peer's offsets this replica holds
+#[derive(Debug, Clone, Default)]
+pub struct AppliedOffsets {
+    /// Every offset <= this has been applied
+    applied_through: u64,
+    /// Batches delivered past a gap (a lost or still in-flight message),
+    /// keyed by first offset
+    ahead: BTreeMap<u64, u64>,
+}
+
+impl AppliedOffsets {
+    pub fn applied_through(&self) -> u64 {
+        self.applied_through
+    }
+
+    /// Record the batch holding offsets `first..=last`
+    pub fn deliver(&mut self, first: u64, last: u64) {
+        debug_assert!(
+            first <= last,
+            "Precondition: empty batch {}..={}",
+            first,
+            last
+        );
+        if first > self.applied_through + 1 {
+            let entry = self.ahead.entry(first).or_insert(last);
+            *entry = (*entry).max(last);
+            return;
+        }
+        self.applied_through = self.applied_through.max(last);
+        while let Some((&next_first, &next_last)) = self.ahead.first_key_value() {
+            if next_first > self.applied_through + 1 {
+                break;
+            }
+            self.ahead.remove(&next_first);
+            self.applied_through = self.applied_through.max(next_last);
+        }
+    }
+
+    /// Record that everything through `offset` is held, e.g. after a full sync
+    pub fn sync_to(&mut self, offset: u64) {
+        self.applied_through = self.applied_through.max(offset);
+        self.ahead.retain(|_, last| *last > offset);
+    }
+}
+
+/// This replica's offset, its peers' acks of it, and its progress through
+/// theirs
+#[derive(Debug, Clone, Default)]
+pub struct ReplicationOffsets {
+    /// Deltas broadcast so far; the offset of the newest
+    offset: u64,
+    /// Per peer, the highest of our offsets it acknowledged
+    acked: HashMap<ReplicaId, u64>,
+    /// Per peer, which of its offsets we hold
+    applied: HashMap<ReplicaId, AppliedOffsets>,
+}
+
+impl ReplicationOffsets {
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Number `deltas` more deltas; returns their range, or None for none
+    pub fn advance(&mut self, deltas: usize) -> Option<(u64, u64)> {
+        if deltas == 0 {
+            return None;
+        }
+        let first = self.offset + 1;
+        self.offset = self.offset.saturating_add(deltas as u64);
+        Some((first, self.offset))
+    }
+
+    /// Count `peer` as a replica before it has acknowledged anything
+    pub fn add_peer(&mut self, peer: ReplicaId) {
+        self.acked.entry(peer).or_insert(0);
+    }
+
+    /// `peer` holds every one of our deltas through `offset`
+    pub fn record_ack(&mut self, peer: ReplicaId, offset: u64) {
+        if offset > self.offset {
+            return;
+        }
+        let acked = self.acked.entry(peer).or_insert(0);
+        *acked = (*acked).max(offset);
+    }
+
+    /// Replicas that acknowledged `offset` or later
+    pub fn replicas_acked(&self, offset: u64) -> usize {
+        self.acked
+            .values()
+            .filter(|&&acked| acked >= offset)
+            .count()
+    }
+
+    /// Record `source`'s batch `first..=last`. Returns the offset to
+    /// acknowledge when it moved.
+    pub fn deliver(&mut self, source: ReplicaId, first: u64, last: u64) -> Option<u64> {
+        let progress = self.applied.entry(source).or_default();
+        if first == 1 && progress.applied_through() > 0 {
+            *progress = AppliedOffsets::default();
+        }
+        let before = progress.applied_through();
+        progress.deliver(first, last);
+        (progress.applied_through() > before).then_some(progress.applied_through())
+    }
+
+    /// How far through `source`'s offsets we are
+    pub fn applied_through(&self, source: ReplicaId) -> u64 {
+        self.applied
+            .get(&source)
+            .map_or(0, AppliedOffsets::applied_through)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps_hold_progress_back_until_filled() {
+        let mut progress = AppliedOffsets::default();
+        progress.deliver(1, 3);
+        progress.deliver(7, 9);
+        assert_eq!(progress.applied_through(), 3);
+        progress.deliver(4, 6);
+        assert_eq!(progress.applied_through(), 9);
+
+        progress.deliver(12, 12);
+        progress.sync_to(10);
+        assert_eq!(progress.applied_through(), 10);
+        progress.deliver(11, 11);
+        assert_eq!(progress.applied_through(), 12);
+    }
+
+    #[test]
+    fn test_offsets_number_deltas_and_count_acks() {
+        let (a, b) = (ReplicaId::new(2), ReplicaId::new(3));
+        let mut offsets = ReplicationOffsets::default();
+        assert_eq!(offsets.advance(0), None);
+        assert_eq!(offsets.advance(2), Some((1, 2)));
+        assert_eq!(offsets.advance(1), Some((3, 3)));
+
+        offsets.add_peer(a);
+        offsets.add_peer(b);
+        assert_eq!(offsets.replicas_acked(0), 2);
+        offsets.record_ack(a, 3);
+        offsets.record_ack(b, 2);
+        offsets.record_ack(b, 1);
+        assert_eq!(offsets.replicas_acked(3), 1);
+        assert_eq!(offsets.replicas_acked(2), 2);
+    }
+
+    #[test]
+    fn test_deliver_acks_only_on_progress() {
+        let source = ReplicaId::new(1);
+        let mut offsets = ReplicationOffsets::default();
+        assert_eq!(offsets.deliver(source, 3, 4), None);
+        assert_eq!(offsets.deliver(source, 1, 2), Some(4));
+        assert_eq!(offsets.deliver(source, 5, 5), Some(5));
+        assert_eq!(offsets.applied_through(source), 5);
+
+        // The source restarted and numbers from 1 again
+        assert_eq!(offsets.deliver(source, 1, 2), Some(2));
+        assert_eq!(offsets.applied_through(source), 2);
+    }
+
+    #[test]
+    fn test_acks_from_a_previous_run_are_dropped() {
+        let peer = ReplicaId::new(2);
+        let mut offsets = ReplicationOffsets::default();
+        offsets.advance(3);
+        offsets.record_ack(peer, 40);
+        assert_eq!(offsets.replicas_acked(0), 0);
+        offsets.record_ack(peer, 3);
+        assert_eq!(offsets.replicas_acked(3), 1);
+    }
+}
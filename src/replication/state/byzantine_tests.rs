@@ -0,0 +1,165 @@
+//! Refused remote delta tests for replication state module
+//!
+//! A corrupted or forged delta must be refused with a structured reason and
+//! leave the receiving state exactly as it was, while honest re-deliveries
+//! and stale honest deltas are still accepted.
+
+#[cfg(test)]
+mod tests {
+    use crate::redis::SDS;
+    use crate::replication::config::ConsistencyLevel;
+    use crate::replication::lattice::{LamportClock, ReplicaId, VectorClock};
+    use crate::replication::state::{DeltaRejection, ReplicationDelta, ShardReplicaState};
+
+    /// Everything a refused delta could have touched
+    fn snapshot(state: &ShardReplicaState, key: &str) -> (LamportClock, Option<String>) {
+        let rv = state.get_replicated(key);
+        (
+            state.lamport_clock,
+            rv.map(|rv| format!("{:?} {:?} {:?}", rv.timestamp, rv.crdt, rv.vector_clock)),
+        )
+    }
+
+    fn refuse(state: &mut ShardReplicaState, delta: ReplicationDelta) -> DeltaRejection {
+        let before = snapshot(state, &delta.key);
+        let key = delta.key.clone();
+        let rejection = state.apply_remote_delta(delta).unwrap_err();
+        assert_eq!(snapshot(state, &key), before, "{} changed state", rejection);
+        assert_eq!(rejection.key(), key);
+        rejection
+    }
+
+    #[test]
+    fn test_checksum_survives_serialization_and_catches_tampering() {
+        let mut writer = ShardReplicaState::new(ReplicaId::new(1), ConsistencyLevel::Causal);
+        let fields = (0..32)
+            .map(|i| (format!("f{}", i), SDS::from_str(&format!("v{}", i))))
+            .collect();
+        let delta = writer.record_hash_write("h".to_string(), fields);
+
+        let wire: ReplicationDelta =
+            bincode::deserialize(&bincode::serialize(&delta).unwrap()).unwrap();
+        assert!(wire.verify_checksum());
+
+        let mut reader = ShardReplicaState::new(ReplicaId::new(2), ConsistencyLevel::Causal);
+        reader
+            .apply_remote_delta(writer.record_write("s".to_string(), SDS::from_str("a"), None))
+            .unwrap();
+        let mut tampered = writer.record_write("s".to_string(), SDS::from_str("b"), None);
+        tampered.value.expiry_ms = Some(1);
+        assert!(matches!(
+            refuse(&mut reader, tampered),
+            DeltaRejection::ChecksumMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn test_conflicting_write_at_known_timestamp_is_refused() {
+        let mut writer = ShardReplicaState::new(ReplicaId::new(1), ConsistencyLevel::Eventual);
+        let mut reader = ShardReplicaState::new(ReplicaId::new(2), ConsistencyLevel::Eventual);
+
+        let honest = writer.record_write("k".to_string(), SDS::from_str("real"), None);
+        reader.apply_remote_delta(honest.clone()).unwrap();
+        // Re-delivery of the same write is fine
+        reader.apply_remote_delta(honest.clone()).unwrap();
+
+        let mut value = honest.value.clone();
+        value.lww_mut().unwrap().value = Some(SDS::from_str("fake"));
+        let forged = ReplicationDelta::new("k".to_string(), value, honest.source_replica);
+        match refuse(&mut reader, forged) {
+            DeltaRejection::DuplicateTimestamp {
+                field, timestamp, ..
+            } => {
+                assert_eq!(field, None);
+                assert_eq!(timestamp, honest.value.timestamp);
+            }
+            other => panic!("refused as {:?}", other),
+        }
+
+        // Per hash field too
+        let honest =
+            writer.record_hash_write("h".to_string(), vec![("a".to_string(), SDS::from_str("1"))]);
+        reader.apply_remote_delta(honest.clone()).unwrap();
+        let mut value = honest.value.clone();
+        value
+            .get_hash_mut()
+            .unwrap()
+            .get_mut("a")
+            .unwrap()
+            .tombstone = true;
+        let forged = ReplicationDelta::new("h".to_string(), value, honest.source_replica);
+        assert!(matches!(
+            refuse(&mut reader, forged),
+            DeltaRejection::DuplicateTimestamp { field: Some(f), .. } if f == "a"
+        ));
+        assert_eq!(
+            reader.get_replicated("h").unwrap().hash_get("a"),
+            Some(&SDS::from_str("1"))
+        );
+    }
+
+    #[test]
+    fn test_regressed_vector_clock_is_refused() {
+        let r1 = ReplicaId::new(1);
+        let mut writer = ShardReplicaState::new(r1, ConsistencyLevel::Causal);
+        let mut reader = ShardReplicaState::new(ReplicaId::new(2), ConsistencyLevel::Causal);
+
+        let first = writer.record_write("k".to_string(), SDS::from_str("v1"), None);
+        let second = writer.record_write("k".to_string(), SDS::from_str("v2"), None);
+        reader.apply_remote_delta(second.clone()).unwrap();
+        // A stale honest delta merges harmlessly
+        reader.apply_remote_delta(first.clone()).unwrap();
+
+        // The first write, replayed with a timestamp past the second
+        let mut value = first.value.clone();
+        value.timestamp.time = second.value.timestamp.time + 5;
+        value.lww_mut().unwrap().timestamp = value.timestamp;
+        let forged = ReplicationDelta::new("k".to_string(), value, r1);
+        assert_eq!(
+            refuse(&mut reader, forged),
+            DeltaRejection::RegressedVectorClock {
+                key: "k".to_string(),
+                replica: r1,
+                local: 2,
+                remote: 1,
+            }
+        );
+
+        // A concurrent writer's clock is not a regression
+        let mut other = ShardReplicaState::new(ReplicaId::new(3), ConsistencyLevel::Causal);
+        other.lamport_clock.time = 100;
+        let concurrent = other.record_write("k".to_string(), SDS::from_str("v3"), None);
+        assert_eq!(concurrent.value.vector_clock.as_ref().unwrap().get(&r1), 0);
+        reader.apply_remote_delta(concurrent).unwrap();
+        let merged = reader.get_replicated("k").unwrap();
+        assert_eq!(merged.get(), Some(&SDS::from_str("v3")));
+        let mut expected = VectorClock::new();
+        expected.increment(r1);
+        expected.increment(r1);
+        expected.increment(ReplicaId::new(3));
+        assert_eq!(merged.vector_clock.as_ref(), Some(&expected));
+    }
+
+    #[test]
+    fn test_malformed_deltas_are_refused() {
+        let mut writer = ShardReplicaState::new(ReplicaId::new(1), ConsistencyLevel::Eventual);
+        let mut reader = ShardReplicaState::new(ReplicaId::new(2), ConsistencyLevel::Eventual);
+        let honest = writer.record_write("k".to_string(), SDS::from_str("v"), None);
+
+        let empty =
+            ReplicationDelta::new(String::new(), honest.value.clone(), honest.source_replica);
+        assert!(matches!(
+            refuse(&mut reader, empty),
+            DeltaRejection::Malformed { .. }
+        ));
+
+        let mut value = honest.value.clone();
+        value.timestamp.time = u64::MAX;
+        let overflow = ReplicationDelta::new("k".to_string(), value, honest.source_replica);
+        assert!(matches!(
+            refuse(&mut reader, overflow),
+            DeltaRejection::Malformed { .. }
+        ));
+        assert!(reader.get_replicated("k").is_none());
+    }
+}
@@ -26,8 +26,8 @@ mod tests {
         let delta2 = state2.record_write("lock".to_string(), SDS::from_str("owner_r2"), None);
 
         // Apply cross-replica deltas
-        state1.apply_remote_delta(delta2.clone());
-        state2.apply_remote_delta(delta1.clone());
+        state1.apply_remote_delta(delta2.clone()).unwrap();
+        state2.apply_remote_delta(delta1.clone()).unwrap();
 
         // Both should converge to same winner
         let val1 = state1.get_replicated("lock").unwrap().get();
@@ -52,15 +52,15 @@ mod tests {
 
         // r1 creates the key
         let delta1 = state1.record_write("config".to_string(), SDS::from_str("v1"), None);
-        state2.apply_remote_delta(delta1);
+        state2.apply_remote_delta(delta1).unwrap();
 
         // Both replicas do SET XX (both see key exists)
         let delta2 = state1.record_write("config".to_string(), SDS::from_str("v2_from_r1"), None);
         let delta3 = state2.record_write("config".to_string(), SDS::from_str("v2_from_r2"), None);
 
         // Apply cross-replica deltas
-        state1.apply_remote_delta(delta3.clone());
-        state2.apply_remote_delta(delta2.clone());
+        state1.apply_remote_delta(delta3.clone()).unwrap();
+        state2.apply_remote_delta(delta2.clone()).unwrap();
 
         // Both should converge
         let val1 = state1.get_replicated("config").unwrap().get();
@@ -95,8 +95,8 @@ mod tests {
                 None,
             );
 
-            state1.apply_remote_delta(delta2.clone());
-            state2.apply_remote_delta(delta1.clone());
+            state1.apply_remote_delta(delta2.clone()).unwrap();
+            state2.apply_remote_delta(delta1.clone()).unwrap();
 
             let val1 = state1
                 .get_replicated(&format!("key_{}", seed))
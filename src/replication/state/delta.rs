@@ -1,7 +1,9 @@
 //! ReplicationDelta - Delta for CRDT replication
 
+use super::crdt_value::CrdtValue;
 use super::replicated_value::ReplicatedValue;
-use crate::replication::lattice::ReplicaId;
+use crate::redis::SDS;
+use crate::replication::lattice::{LamportClock, LwwRegister, ReplicaId};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,14 +11,90 @@ pub struct ReplicationDelta {
     pub key: String,
     pub value: ReplicatedValue,
     pub source_replica: ReplicaId,
+    /// CRC32 of the key, the value and the source, set on construction
+    pub checksum: u32,
 }
 
 impl ReplicationDelta {
     pub fn new(key: String, value: ReplicatedValue, source_replica: ReplicaId) -> Self {
-        ReplicationDelta {
+        let mut delta = ReplicationDelta {
             key,
             value,
             source_replica,
+            checksum: 0,
+        };
+        delta.checksum = delta.compute_checksum();
+        delta
+    }
+
+    /// Checksum of the delta's current contents.
+    ///
+    /// Hash maps and sets are fed in sorted order, so a delta that went
+    /// through serialization checksums the same as the original. Counters
+    /// and sets contribute the value a reader sees, not per-replica state.
+    pub fn compute_checksum(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut bytes = |data: &[u8]| {
+            hasher.update(&(data.len() as u64).to_le_bytes());
+            hasher.update(data);
+        };
+        bytes(self.key.as_bytes());
+        bytes(&self.source_replica.0.to_le_bytes());
+
+        let value = &self.value;
+        clock_bytes(&mut bytes, &value.timestamp);
+        match value.expiry_ms {
+            Some(expiry) => bytes(&expiry.to_le_bytes()),
+            None => bytes(&[]),
+        }
+        bytes(value.replication_factor.as_slice());
+        for (replica, count) in value.vector_clock.iter().flat_map(|vc| vc.entries()) {
+            bytes(&replica.0.to_le_bytes());
+            bytes(&count.to_le_bytes());
         }
+
+        bytes(value.crdt.type_name().as_bytes());
+        match &value.crdt {
+            CrdtValue::Lww(lww) => register_bytes(&mut bytes, lww),
+            CrdtValue::GCounter(counter) => bytes(&counter.value().to_le_bytes()),
+            CrdtValue::PNCounter(counter) => bytes(&counter.value().to_le_bytes()),
+            CrdtValue::GSet(set) => {
+                let mut elements: Vec<&String> = set.elements().collect();
+                elements.sort_unstable();
+                elements.iter().for_each(|e| bytes(e.as_bytes()));
+            }
+            CrdtValue::ORSet(set) => {
+                let mut elements: Vec<&String> = set.elements().collect();
+                elements.sort_unstable();
+                elements.iter().for_each(|e| bytes(e.as_bytes()));
+            }
+            CrdtValue::Hash(fields) => {
+                let mut fields: Vec<(&String, &LwwRegister<SDS>)> = fields.iter().collect();
+                fields.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                for (field, register) in fields {
+                    bytes(field.as_bytes());
+                    register_bytes(&mut bytes, register);
+                }
+            }
+        }
+        hasher.finalize()
+    }
+
+    /// Whether the contents still match the checksum they were sent with
+    pub fn verify_checksum(&self) -> bool {
+        self.checksum == self.compute_checksum()
+    }
+}
+
+fn clock_bytes(bytes: &mut impl FnMut(&[u8]), clock: &LamportClock) {
+    bytes(&clock.time.to_le_bytes());
+    bytes(&clock.replica_id.0.to_le_bytes());
+}
+
+fn register_bytes(bytes: &mut impl FnMut(&[u8]), register: &LwwRegister<SDS>) {
+    clock_bytes(bytes, &register.timestamp);
+    bytes(&[u8::from(register.tombstone)]);
+    if let Some(value) = &register.value {
+        bytes(value.as_bytes());
     }
 }
@@ -1,4 +1,7 @@
-//! CRDT Merge Error - Explicit error for type mismatches (TigerStyle)
+//! CRDT Merge Errors - Explicit errors for type mismatches and refused
+//! deltas (TigerStyle)
+
+use crate::replication::lattice::{LamportClock, ReplicaId};
 
 /// Error returned when attempting to merge two CrdtValues of different types.
 /// This makes the conflict explicit rather than silently discarding data.
@@ -21,3 +24,91 @@ impl std::fmt::Display for CrdtTypeMismatchError {
 }
 
 impl std::error::Error for CrdtTypeMismatchError {}
+
+/// Why a remote delta was refused. A refused delta leaves the local state,
+/// Lamport clock included, exactly as it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaRejection {
+    /// The key is empty or the timestamp cannot be advanced past
+    Malformed { key: String, reason: &'static str },
+    /// The contents do not match the checksum the delta was sent with
+    ChecksumMismatch {
+        key: String,
+        expected: u32,
+        actual: u32,
+    },
+    /// A register carries the timestamp of one we hold but different
+    /// contents. A timestamp names exactly one write, so one of the two is
+    /// forged.
+    DuplicateTimestamp {
+        key: String,
+        field: Option<String>,
+        timestamp: LamportClock,
+    },
+    /// The value claims a later timestamp than ours from `replica` while
+    /// its vector clock has seen fewer of that replica's writes
+    RegressedVectorClock {
+        key: String,
+        replica: ReplicaId,
+        local: u64,
+        remote: u64,
+    },
+}
+
+impl DeltaRejection {
+    /// The key the refused delta was for
+    pub fn key(&self) -> &str {
+        match self {
+            DeltaRejection::Malformed { key, .. }
+            | DeltaRejection::ChecksumMismatch { key, .. }
+            | DeltaRejection::DuplicateTimestamp { key, .. }
+            | DeltaRejection::RegressedVectorClock { key, .. } => key,
+        }
+    }
+}
+
+impl std::fmt::Display for DeltaRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeltaRejection::Malformed { key, reason } => {
+                write!(f, "malformed delta for '{}': {}", key, reason)
+            }
+            DeltaRejection::ChecksumMismatch {
+                key,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "checksum mismatch for '{}': sent {:08x}, contents {:08x}",
+                key, expected, actual
+            ),
+            DeltaRejection::DuplicateTimestamp {
+                key,
+                field,
+                timestamp,
+            } => write!(
+                f,
+                "conflicting write for '{}'{} at timestamp {}@{}",
+                key,
+                field
+                    .as_ref()
+                    .map(|field| format!(" field '{}'", field))
+                    .unwrap_or_default(),
+                timestamp.time,
+                timestamp.replica_id.0
+            ),
+            DeltaRejection::RegressedVectorClock {
+                key,
+                replica,
+                local,
+                remote,
+            } => write!(
+                f,
+                "regressed vector clock for '{}': replica {} at {}, already seen {}",
+                key, replica.0, remote, local
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DeltaRejection {}
@@ -26,8 +26,8 @@ mod tests {
         );
 
         // Apply cross-replica deltas
-        state1.apply_remote_delta(delta2.clone());
-        state2.apply_remote_delta(delta1.clone());
+        state1.apply_remote_delta(delta2.clone()).unwrap();
+        state2.apply_remote_delta(delta1.clone()).unwrap();
 
         // Both replicas should have both fields
         let hash1 = state1.get_replicated("myhash").unwrap().get_hash().unwrap();
@@ -80,8 +80,8 @@ mod tests {
         );
 
         // Apply cross-replica deltas
-        state1.apply_remote_delta(delta2.clone());
-        state2.apply_remote_delta(delta1.clone());
+        state1.apply_remote_delta(delta2.clone()).unwrap();
+        state2.apply_remote_delta(delta1.clone()).unwrap();
 
         // Both replicas should converge to the same value (LWW semantics)
         let val1 = state1.get_replicated("myhash").unwrap().hash_get("field");
@@ -109,7 +109,7 @@ mod tests {
                 ("field2".to_string(), SDS::from_str("value2")),
             ],
         );
-        state2.apply_remote_delta(delta1.clone());
+        state2.apply_remote_delta(delta1.clone()).unwrap();
 
         // Delete field1 from state1
         let delete_delta =
@@ -117,7 +117,7 @@ mod tests {
 
         // Apply deletion to state2
         if let Some(d) = delete_delta {
-            state2.apply_remote_delta(d);
+            state2.apply_remote_delta(d).unwrap();
         }
 
         // field1 should be tombstoned, field2 should remain
@@ -169,7 +169,7 @@ mod tests {
             "myhash".to_string(),
             vec![("field".to_string(), SDS::from_str("value"))],
         );
-        state2.apply_remote_delta(write_delta.clone());
+        state2.apply_remote_delta(write_delta.clone()).unwrap();
 
         // r2 deletes the same field (happens after write due to clock)
         let delete_delta =
@@ -177,7 +177,7 @@ mod tests {
 
         // Apply delete to r1
         if let Some(d) = delete_delta {
-            state1.apply_remote_delta(d);
+            state1.apply_remote_delta(d).unwrap();
         }
 
         // Both should have tombstoned field
@@ -242,9 +242,9 @@ mod tests {
 
             // Apply all deltas to all replicas
             for delta in &deltas {
-                state1.apply_remote_delta(delta.clone());
-                state2.apply_remote_delta(delta.clone());
-                state3.apply_remote_delta(delta.clone());
+                state1.apply_remote_delta(delta.clone()).unwrap();
+                state2.apply_remote_delta(delta.clone()).unwrap();
+                state3.apply_remote_delta(delta.clone()).unwrap();
             }
 
             // All replicas should converge
@@ -25,14 +25,14 @@ mod tests {
             "counters".to_string(),
             vec![("hits".to_string(), SDS::from_str("10"))],
         );
-        state2.apply_remote_delta(delta1);
+        state2.apply_remote_delta(delta1).unwrap();
 
         // r1 increments to "15" (simulating HINCRBY 5)
         let delta2 = state1.record_hash_write(
             "counters".to_string(),
             vec![("hits".to_string(), SDS::from_str("15"))],
         );
-        state2.apply_remote_delta(delta2);
+        state2.apply_remote_delta(delta2).unwrap();
 
         // Both should see "15"
         let val1 = state1.get_replicated("counters").unwrap().hash_get("hits");
@@ -57,7 +57,7 @@ mod tests {
             "counters".to_string(),
             vec![("hits".to_string(), SDS::from_str("10"))],
         );
-        state2.apply_remote_delta(init_delta);
+        state2.apply_remote_delta(init_delta).unwrap();
 
         // r1 increments to "15" (HINCRBY 5)
         let delta1 = state1.record_hash_write(
@@ -72,8 +72,8 @@ mod tests {
         );
 
         // Apply cross-replica deltas
-        state1.apply_remote_delta(delta2.clone());
-        state2.apply_remote_delta(delta1.clone());
+        state1.apply_remote_delta(delta2.clone()).unwrap();
+        state2.apply_remote_delta(delta1.clone()).unwrap();
 
         // Both should converge to SAME value (LWW winner)
         // NOT "18" (10+5+3) - increments don't commute in LWW
@@ -128,12 +128,12 @@ mod tests {
             );
 
             // All-to-all delta application
-            state1.apply_remote_delta(delta2.clone());
-            state1.apply_remote_delta(delta3.clone());
-            state2.apply_remote_delta(delta1.clone());
-            state2.apply_remote_delta(delta3.clone());
-            state3.apply_remote_delta(delta1.clone());
-            state3.apply_remote_delta(delta2.clone());
+            state1.apply_remote_delta(delta2.clone()).unwrap();
+            state1.apply_remote_delta(delta3.clone()).unwrap();
+            state2.apply_remote_delta(delta1.clone()).unwrap();
+            state2.apply_remote_delta(delta3.clone()).unwrap();
+            state3.apply_remote_delta(delta1.clone()).unwrap();
+            state3.apply_remote_delta(delta2.clone()).unwrap();
 
             // All three should converge
             let v1 = state1.get_replicated("counter").unwrap().hash_get("val");
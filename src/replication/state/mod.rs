@@ -12,6 +12,8 @@ mod error;
 mod replicated_value;
 mod shard_state;
 
+#[cfg(test)]
+mod byzantine_tests;
 #[cfg(test)]
 mod conditional_tests;
 #[cfg(test)]
@@ -26,6 +28,6 @@ mod type_mismatch_tests;
 // Re-export all public types
 pub use crdt_value::CrdtValue;
 pub use delta::ReplicationDelta;
-pub use error::CrdtTypeMismatchError;
+pub use error::{CrdtTypeMismatchError, DeltaRejection};
 pub use replicated_value::ReplicatedValue;
 pub use shard_state::ShardReplicaState;
@@ -112,7 +112,9 @@ impl ReplicatedValue {
             (Some(e), None) | (None, Some(e)) => Some(e),
             (None, None) => None,
         };
-        let merged_timestamp = self.timestamp.merge(&other.timestamp);
+        // The later of the two, ties broken by replica: merging must not
+        // depend on which side merges, or equal states digest differently
+        let merged_timestamp = self.timestamp.max(other.timestamp);
         // For RF, take the higher value (more replicas = safer)
         let merged_rf = match (self.replication_factor, other.replication_factor) {
             (Some(rf1), Some(rf2)) => Some(rf1.max(rf2)),
@@ -2,10 +2,11 @@
 
 use super::crdt_value::CrdtValue;
 use super::delta::ReplicationDelta;
+use super::error::DeltaRejection;
 use super::replicated_value::ReplicatedValue;
 use crate::redis::SDS;
 use crate::replication::config::ConsistencyLevel;
-use crate::replication::lattice::{LamportClock, ReplicaId, VectorClock};
+use crate::replication::lattice::{LamportClock, LwwRegister, ReplicaId, VectorClock};
 use std::collections::HashMap;
 
 /// Maximum number of pending deltas before oldest are dropped.
@@ -76,6 +77,29 @@ impl ShardReplicaState {
         }
     }
 
+    /// Record a bulk-imported string without queueing a delta: an import
+    /// reaches persistence through one checkpoint, not key by key
+    pub fn record_import(&mut self, key: String, value: SDS, expiry_ms: Option<u64>) {
+        debug_assert!(!key.is_empty(), "Precondition: key must not be empty");
+        let mut replicated = ReplicatedValue::new(self.replica_id);
+        replicated.set(value, &mut self.lamport_clock, None);
+        replicated.expiry_ms = expiry_ms;
+        self.replicated_keys.insert(key, replicated);
+    }
+
+    /// Record a bulk-imported hash without queueing a delta (see `record_import`)
+    pub fn record_hash_import(&mut self, key: String, fields: Vec<(String, SDS)>) {
+        debug_assert!(!key.is_empty(), "Precondition: key must not be empty");
+        debug_assert!(!fields.is_empty(), "Precondition: fields must not be empty");
+        let mut replicated = ReplicatedValue::new(self.replica_id);
+        replicated.crdt = CrdtValue::new_hash();
+        for (field, value) in fields {
+            replicated.hash_set(field, value, &mut self.lamport_clock);
+        }
+        debug_assert!(replicated.is_hash(), "Postcondition: import must record a hash");
+        self.replicated_keys.insert(key, replicated);
+    }
+
     /// Record a hash field write (HSET)
     ///
     /// TigerStyle: Preconditions checked, postconditions verified
@@ -205,7 +229,15 @@ impl ShardReplicaState {
         None
     }
 
-    pub fn apply_remote_delta(&mut self, delta: ReplicationDelta) {
+    /// Merge a delta from another replica.
+    ///
+    /// The delta is checked before anything is touched: a malformed,
+    /// corrupted or forged delta is refused with the reason and leaves the
+    /// state, Lamport clock included, as it was. Re-delivery of a delta we
+    /// already merged is accepted and changes nothing.
+    pub fn apply_remote_delta(&mut self, delta: ReplicationDelta) -> Result<(), DeltaRejection> {
+        self.validate_remote_delta(&delta)?;
+
         // Update our clock from the delta's timestamp
         self.lamport_clock.update(&delta.value.timestamp);
 
@@ -215,6 +247,75 @@ impl ShardReplicaState {
             None => delta.value,
         };
         self.replicated_keys.insert(delta.key, merged);
+        Ok(())
+    }
+
+    fn validate_remote_delta(&self, delta: &ReplicationDelta) -> Result<(), DeltaRejection> {
+        let malformed = |reason| DeltaRejection::Malformed {
+            key: delta.key.clone(),
+            reason,
+        };
+        if delta.key.is_empty() {
+            return Err(malformed("empty key"));
+        }
+        if delta.value.timestamp.time == u64::MAX {
+            return Err(malformed("timestamp at the end of the clock"));
+        }
+        let actual = delta.compute_checksum();
+        if actual != delta.checksum {
+            return Err(DeltaRejection::ChecksumMismatch {
+                key: delta.key.clone(),
+                expected: delta.checksum,
+                actual,
+            });
+        }
+
+        let Some(local) = self.replicated_keys.get(&delta.key) else {
+            return Ok(());
+        };
+        let remote = &delta.value;
+
+        // Registers at the same timestamp must be the same write
+        let duplicate =
+            |field: Option<&String>, mine: &LwwRegister<SDS>, theirs: &LwwRegister<SDS>| {
+                if mine.timestamp == theirs.timestamp
+                    && (mine.tombstone != theirs.tombstone || mine.value != theirs.value)
+                {
+                    return Err(DeltaRejection::DuplicateTimestamp {
+                        key: delta.key.clone(),
+                        field: field.cloned(),
+                        timestamp: theirs.timestamp,
+                    });
+                }
+                Ok(())
+            };
+        match (&local.crdt, &remote.crdt) {
+            (CrdtValue::Lww(mine), CrdtValue::Lww(theirs)) => duplicate(None, mine, theirs)?,
+            (CrdtValue::Hash(mine), CrdtValue::Hash(theirs)) => {
+                for (field, theirs) in theirs {
+                    if let Some(mine) = mine.get(field) {
+                        duplicate(Some(field), mine, theirs)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // A later write by a replica has seen at least as many of that
+        // replica's writes as any earlier one
+        if let (Some(local_vc), Some(remote_vc)) = (&local.vector_clock, &remote.vector_clock) {
+            let replica = remote.timestamp.replica_id;
+            let (seen, claimed) = (local_vc.get(&replica), remote_vc.get(&replica));
+            if remote.timestamp > local.timestamp && claimed < seen {
+                return Err(DeltaRejection::RegressedVectorClock {
+                    key: delta.key.clone(),
+                    replica,
+                    local: seen,
+                    remote: claimed,
+                });
+            }
+        }
+        Ok(())
     }
 
     pub fn drain_pending_deltas(&mut self) -> Vec<ReplicationDelta> {
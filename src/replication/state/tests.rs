@@ -18,8 +18,8 @@ mod tests {
         let delta1 = state1.record_write("key1".to_string(), SDS::from_str("value1"), None);
         let delta2 = state2.record_write("key1".to_string(), SDS::from_str("value2"), None);
 
-        state1.apply_remote_delta(delta2.clone());
-        state2.apply_remote_delta(delta1.clone());
+        state1.apply_remote_delta(delta2.clone()).unwrap();
+        state2.apply_remote_delta(delta1.clone()).unwrap();
 
         let val1 = state1.get_replicated("key1").unwrap().get();
         let val2 = state2.get_replicated("key1").unwrap().get();
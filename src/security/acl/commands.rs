@@ -1,6 +1,6 @@
 //! ACL command handlers
 
-use super::{AclError, AclManager, AclUser, CommandCategory, KeyPattern};
+use super::{AclError, AclManager, AclUser, CommandCategory, KeyPattern, PasswordScheme};
 use std::sync::Arc;
 
 /// Handler for ACL-related commands
@@ -76,6 +76,7 @@ impl AclCommandHandler {
                 commands,
                 keys,
                 channels: "&*".to_string(),
+                keyspace: user.keyspace.clone(),
             }
         })
     }
@@ -101,9 +102,33 @@ impl AclCommandHandler {
             .map(|u| (*u).clone())
             .unwrap_or_else(|| AclUser::new(username.to_string()));
 
-        // Apply each rule
+        // Enforce the password policy before touching the user, so a rejected
+        // SETUSER leaves no partial changes. The error never echoes the password.
+        let policy = manager.password_policy();
+        if !policy.is_permissive() {
+            for rule in rules {
+                if let Some(password) = rule.trim().strip_prefix('>') {
+                    policy.check(password).map_err(|reason| AclError::InvalidRule {
+                        rule: ">...".to_string(),
+                        reason,
+                    })?;
+                }
+            }
+        }
+
+        // Apply each rule. `>password` is stored under the configured scheme;
+        // apply_rule itself always produces Redis-compatible SHA256.
+        let scheme = manager.hash_scheme();
         for rule in rules {
-            apply_rule(&mut user, rule)?;
+            match rule.trim().strip_prefix('>') {
+                Some(password) if scheme != PasswordScheme::Sha256 => {
+                    // Salted hashes differ per call, so dedup by verification
+                    if user.find_password(password).is_none() {
+                        user.add_password_hash(manager.hash_for_storage(password));
+                    }
+                }
+                _ => apply_rule(&mut user, rule)?,
+            }
         }
 
         manager.set_user(user);
@@ -235,36 +260,15 @@ impl AclCommandHandler {
     }
 
     /// Handle ACL GENPASS command
+    ///
+    /// Uses `ProductionRng` (ChaCha seeded from OS entropy), so the output is
+    /// suitable as a credential.
     pub fn handle_genpass(bits: Option<u32>) -> Result<String, String> {
-        use std::time::{SystemTime, UNIX_EPOCH};
+        use crate::security::password::{generate_password, genpass_bits};
 
-        if let Some(b) = bits {
-            if b == 0 || b > 4096 {
-                return Err(
-                    "ERR ACL GENPASS argument must be the number of bits for the output password, a positive number up to 4096".to_string()
-                );
-            }
-        }
-
-        let bits = bits.unwrap_or(256);
-        let bytes = (bits as usize).div_ceil(8);
-
-        // Simple pseudo-random generation (not cryptographically secure)
-        // In production, use proper random source
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-
-        let mut result = String::with_capacity(bytes * 2);
-        let mut state = seed;
-        for _ in 0..bytes {
-            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
-            let byte = ((state >> 33) & 0xFF) as u8;
-            result.push_str(&format!("{:02x}", byte));
-        }
-
-        Ok(result)
+        let bits = genpass_bits(bits)?;
+        let mut rng = crate::io::production::ProductionRng::new();
+        Ok(generate_password(&mut rng, bits))
     }
 }
 
@@ -275,6 +279,8 @@ pub struct AclGetUserInfo {
     pub commands: String,
     pub keys: String,
     pub channels: String,
+    /// Tenant key prefix, reported only when set
+    pub keyspace: Option<String>,
 }
 
 /// Apply a single ACL rule to a user
@@ -312,6 +318,11 @@ pub fn apply_rule(user: &mut AclUser, rule: &str) -> Result<(), AclError> {
             user.keys.reset();
         }
 
+        // Tenant keyspace
+        "nokeyspace" => {
+            user.keyspace = None;
+        }
+
         // Channel permissions (stored but not enforced — PubSub not implemented)
         "allchannels" | "&*" => {
             // No-op: we don't enforce channel ACLs
@@ -338,8 +349,9 @@ pub fn apply_rule(user: &mut AclUser, rule: &str) -> Result<(), AclError> {
                 // Remove password
                 user.remove_password(rest);
             } else if let Some(rest) = rule.strip_prefix('#') {
-                // Add password hash directly — validate it's a valid SHA256 hex string
-                if rest.len() != 64 || !rest.chars().all(|c| c.is_ascii_hexdigit()) {
+                // Add password hash directly — SHA256 hex, or an argon2id PHC
+                // string (as written by ACL SAVE) when this build supports it
+                if !PasswordScheme::of(rest).is_some_and(PasswordScheme::is_available) {
                     return Err(AclError::InvalidRule {
                         rule: format!("#{}", rest),
                         reason: "Syntax error in ACL SETUSER modifier".to_string(),
@@ -384,6 +396,16 @@ pub fn apply_rule(user: &mut AclUser, rule: &str) -> Result<(), AclError> {
                 } else {
                     user.commands.deny_command(rest);
                 }
+            } else if let Some(prefix) = rule.strip_prefix("keyspace:") {
+                // Tenant key prefix; whitespace would not survive ACL SAVE
+                if prefix.is_empty() || prefix.contains(char::is_whitespace) {
+                    return Err(AclError::InvalidRule {
+                        rule: rule.to_string(),
+                        reason: "keyspace prefix must be non-empty and contain no spaces"
+                            .to_string(),
+                    });
+                }
+                user.keyspace = Some(prefix.to_string());
             } else if let Some(rest) = rule.strip_prefix('~') {
                 // Key pattern
                 user.keys.add_pattern(KeyPattern::new(rest.to_string()));
@@ -428,11 +450,11 @@ fn extract_dryrun_keys<'a>(command: &str, args: &'a [String]) -> Vec<&'a str> {
             .map(|s| s.as_str())
             .collect(),
         // All args are keys
-        "DEL" | "UNLINK" | "EXISTS" | "MGET" | "WATCH" => {
+        "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "MGET" | "WATCH" => {
             args.iter().map(|s| s.as_str()).collect()
         }
         // Two-key commands: source + dest
-        "RENAME" | "RENAMENX" | "RPOPLPUSH" => {
+        "RENAME" | "RENAMENX" | "COPY" | "RPOPLPUSH" => {
             args.iter().take(2).map(|s| s.as_str()).collect()
         }
         // LMOVE source dest wherefrom whereto — first 2 args are keys
@@ -571,6 +593,20 @@ mod tests {
         assert!(!user.keys.is_key_permitted("admin:secret"));
     }
 
+    #[test]
+    fn test_apply_keyspace_rules() {
+        let mut user = AclUser::new("tenant".to_string());
+
+        apply_rule(&mut user, "keyspace:acme:").unwrap();
+        assert_eq!(user.keyspace(), Some("acme:"));
+
+        assert!(apply_rule(&mut user, "keyspace:").is_err());
+        assert_eq!(user.keyspace(), Some("acme:"));
+
+        apply_rule(&mut user, "nokeyspace").unwrap();
+        assert_eq!(user.keyspace(), None);
+    }
+
     #[test]
     fn test_auth_handler() {
         let mut manager = AclManager::new();
@@ -607,4 +643,63 @@ mod tests {
         assert!(user.commands.categories.contains(&CommandCategory::Read));
         assert!(user.keys.is_key_permitted("cache:foo"));
     }
+
+    #[test]
+    fn test_setuser_password_policy() {
+        use crate::security::password::PasswordPolicy;
+
+        let mut manager = AclManager::new();
+        manager.set_password_policy(PasswordPolicy::new(12, 3));
+
+        let err = AclCommandHandler::handle_setuser(&mut manager, "weak", &["on", ">short"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("ERR Error in ACL SETUSER modifier '>...'"), "{}", err);
+        assert!(err.contains("at least 12 characters"), "{}", err);
+        assert!(!err.contains("short"), "error must not leak the password");
+        // Rejected SETUSER must not create the user
+        assert!(manager.get_user("weak").is_none());
+
+        AclCommandHandler::handle_setuser(&mut manager, "strong", &["on", ">Correct-Horse-9"])
+            .unwrap();
+        assert!(manager.get_user("strong").is_some());
+    }
+
+    #[cfg(feature = "acl-argon2")]
+    #[test]
+    fn test_setuser_stores_configured_scheme() {
+        use crate::security::acl::{Argon2Params, PasswordScheme};
+
+        let mut manager = AclManager::new();
+        manager.set_hash_scheme(PasswordScheme::Argon2id).unwrap();
+        manager.set_argon2_params(Argon2Params::minimal());
+
+        AclCommandHandler::handle_setuser(&mut manager, "alice", &["on", ">pw", ">pw"]).unwrap();
+        let alice = manager.get_user("alice").unwrap();
+        assert_eq!(alice.password_hashes.len(), 1, "repeated >pw must not add a second hash");
+        assert_eq!(PasswordScheme::of(&alice.password_hashes[0]), Some(PasswordScheme::Argon2id));
+        assert!(manager.authenticate("alice", "pw").is_ok());
+
+        // ACL LIST output (#<phc>) must load back
+        let phc = format!("#{}", alice.password_hashes[0]);
+        AclCommandHandler::handle_setuser(&mut manager, "bob", &["on", &phc]).unwrap();
+        assert!(manager.authenticate("bob", "pw").is_ok());
+
+        // <pw removes the argon2id hash too
+        AclCommandHandler::handle_setuser(&mut manager, "alice", &["<pw"]).unwrap();
+        assert!(manager.get_user("alice").unwrap().password_hashes.is_empty());
+    }
+
+    #[test]
+    fn test_genpass_handler() {
+        assert_eq!(AclCommandHandler::handle_genpass(None).unwrap().len(), 64);
+        assert_eq!(AclCommandHandler::handle_genpass(Some(5)).unwrap().len(), 2);
+        assert!(AclCommandHandler::handle_genpass(Some(0)).is_err());
+        assert!(AclCommandHandler::handle_genpass(Some(4097)).is_err());
+        // CSPRNG output: two 256-bit passwords never collide in practice
+        assert_ne!(
+            AclCommandHandler::handle_genpass(None).unwrap(),
+            AclCommandHandler::handle_genpass(None).unwrap()
+        );
+    }
 }
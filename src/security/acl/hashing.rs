@@ -0,0 +1,254 @@
+//! At-rest password hash schemes
+//!
+//! Every stored hash carries its scheme in its own encoding, so users may hold
+//! a mix of schemes and verification never needs out-of-band metadata:
+//!
+//! - `sha256`: 64 lowercase hex chars, exactly what Redis stores and what
+//!   `#<hash>` rules and ACL files contain. Always available.
+//! - `argon2id`: a PHC string (`$argon2id$v=19$m=..,t=..,p=..$salt$hash`).
+//!   Requires the `acl-argon2` feature.
+//!
+//! SHA256 stays the default for Redis compatibility. When the manager is
+//! configured for argon2id, new `>password` rules are stored as argon2id and
+//! existing SHA256 hashes are upgraded on the next successful AUTH, the only
+//! point at which the plaintext is known.
+
+use sha2::{Digest, Sha256};
+
+/// Prefix identifying an argon2id PHC string
+const ARGON2ID_PREFIX: &str = "$argon2id$";
+
+/// Password hash scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordScheme {
+    /// Unsalted SHA256, hex encoded (Redis-compatible)
+    #[default]
+    Sha256,
+    /// Salted argon2id, PHC string encoded
+    Argon2id,
+}
+
+impl PasswordScheme {
+    /// Parse a scheme name (`sha256` or `argon2id`, case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" => Some(PasswordScheme::Sha256),
+            "argon2id" | "argon2" => Some(PasswordScheme::Argon2id),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PasswordScheme::Sha256 => "sha256",
+            PasswordScheme::Argon2id => "argon2id",
+        }
+    }
+
+    /// Scheme of a stored hash, or `None` if it is not a recognised encoding
+    pub fn of(stored: &str) -> Option<Self> {
+        if stored.len() == 64 && stored.bytes().all(|c| c.is_ascii_hexdigit()) {
+            Some(PasswordScheme::Sha256)
+        } else if stored.starts_with(ARGON2ID_PREFIX) {
+            Some(PasswordScheme::Argon2id)
+        } else {
+            None
+        }
+    }
+
+    /// True if this build can create and verify hashes of this scheme
+    pub fn is_available(self) -> bool {
+        match self {
+            PasswordScheme::Sha256 => true,
+            PasswordScheme::Argon2id => cfg!(feature = "acl-argon2"),
+        }
+    }
+}
+
+/// argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of passes
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Argon2Params {
+    /// Cheapest parameters argon2 accepts. Tests only: every AUTH pays this cost.
+    pub fn minimal() -> Self {
+        Argon2Params {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+}
+
+impl Default for Argon2Params {
+    /// OWASP recommendation for argon2id: 19 MiB, 2 passes, 1 lane
+    fn default() -> Self {
+        Argon2Params {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// SHA256 hex digest of a password (the Redis ACL hash)
+pub fn sha256_hex(password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    super::hex::encode(hasher.finalize())
+}
+
+/// Hash a password for storage. Falls back to SHA256 if `scheme` is not
+/// compiled in; `AclManager::set_hash_scheme` rejects that configuration, so
+/// the fallback is never reached through the manager.
+pub fn hash_password(scheme: PasswordScheme, password: &str, params: Argon2Params) -> String {
+    debug_assert!(
+        scheme.is_available(),
+        "Precondition violated: scheme {} is not compiled in",
+        scheme.name()
+    );
+
+    let stored = match scheme {
+        PasswordScheme::Sha256 => sha256_hex(password),
+        #[cfg(feature = "acl-argon2")]
+        PasswordScheme::Argon2id => argon2_hash(password, params),
+        #[cfg(not(feature = "acl-argon2"))]
+        PasswordScheme::Argon2id => {
+            let _ = params;
+            sha256_hex(password)
+        }
+    };
+
+    // TigerStyle: Postcondition
+    debug_assert!(
+        PasswordScheme::of(&stored).is_some(),
+        "Postcondition violated: stored hash must carry a recognised scheme"
+    );
+    stored
+}
+
+/// Check a plaintext password against a stored hash of any scheme.
+/// Hashes of schemes not compiled in never match.
+pub fn verify_password(stored: &str, password: &str) -> bool {
+    match PasswordScheme::of(stored) {
+        Some(PasswordScheme::Sha256) => {
+            constant_time_eq(stored.as_bytes(), sha256_hex(password).as_bytes())
+        }
+        #[cfg(feature = "acl-argon2")]
+        Some(PasswordScheme::Argon2id) => argon2_verify(stored, password),
+        #[cfg(not(feature = "acl-argon2"))]
+        Some(PasswordScheme::Argon2id) => false,
+        None => false,
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(feature = "acl-argon2")]
+fn argon2_hash(password: &str, params: Argon2Params) -> String {
+    use argon2::password_hash::rand_core::OsRng;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    argon2_instance(params)
+        .hash_password(password.as_bytes(), &salt)
+        .expect("argon2id hashing with validated parameters cannot fail")
+        .to_string()
+}
+
+#[cfg(feature = "acl-argon2")]
+fn argon2_verify(stored: &str, password: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+    // Cost parameters are read from the PHC string, not from the instance
+    match PasswordHash::new(stored) {
+        Ok(parsed) => argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(feature = "acl-argon2")]
+fn argon2_instance(params: Argon2Params) -> argon2::Argon2<'static> {
+    let params = argon2::Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        None,
+    )
+    .unwrap_or_default();
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheme_detection() {
+        assert_eq!(
+            PasswordScheme::of(&sha256_hex("x")),
+            Some(PasswordScheme::Sha256)
+        );
+        assert_eq!(
+            PasswordScheme::of("$argon2id$v=19$m=8,t=1,p=1$c2FsdHNhbHQ$aGFzaA"),
+            Some(PasswordScheme::Argon2id)
+        );
+        assert_eq!(PasswordScheme::of("plaintext"), None);
+        assert_eq!(PasswordScheme::of(&"g".repeat(64)), None);
+
+        assert_eq!(
+            PasswordScheme::from_name("ARGON2ID"),
+            Some(PasswordScheme::Argon2id)
+        );
+        assert_eq!(
+            PasswordScheme::from_name("sha256"),
+            Some(PasswordScheme::Sha256)
+        );
+        assert_eq!(PasswordScheme::from_name("md5"), None);
+    }
+
+    #[test]
+    fn test_sha256_roundtrip() {
+        let stored = hash_password(PasswordScheme::Sha256, "secret", Argon2Params::minimal());
+        assert_eq!(stored, sha256_hex("secret"));
+        assert!(verify_password(&stored, "secret"));
+        assert!(!verify_password(&stored, "Secret"));
+    }
+
+    #[cfg(feature = "acl-argon2")]
+    #[test]
+    fn test_argon2id_roundtrip_is_salted() {
+        let a = hash_password(PasswordScheme::Argon2id, "secret", Argon2Params::minimal());
+        let b = hash_password(PasswordScheme::Argon2id, "secret", Argon2Params::minimal());
+        assert!(a.starts_with("$argon2id$v=19$m=8,t=1,p=1$"), "{}", a);
+        assert_ne!(a, b, "argon2id hashes must use a fresh salt");
+        assert!(verify_password(&a, "secret"));
+        assert!(verify_password(&b, "secret"));
+        assert!(!verify_password(&a, "wrong"));
+        assert!(!verify_password("$argon2id$garbage", "secret"));
+    }
+
+    #[cfg(not(feature = "acl-argon2"))]
+    #[test]
+    fn test_argon2id_unavailable_never_matches() {
+        assert!(!PasswordScheme::Argon2id.is_available());
+        assert!(!verify_password(
+            "$argon2id$v=19$m=8,t=1,p=1$c2FsdHNhbHQ$aGFzaA",
+            "secret"
+        ));
+    }
+}
@@ -1,22 +1,23 @@
 //! Redis 6.0+ compatible ACL (Access Control List) system
 //!
 //! Provides user authentication and command authorization with:
-//! - Multiple users with passwords (SHA256 hashed)
+//! - Multiple users with passwords (SHA256 hashed, optionally argon2id)
 //! - Per-user command permissions (allow/deny lists, categories)
 //! - Per-user key pattern restrictions
 //! - Default user for backwards compatibility
 
 mod commands;
 mod file;
+mod hashing;
 mod patterns;
 mod user;
 
 pub use commands::{apply_rule, AclCommandHandler};
 pub use file::{load_acl_file, save_acl_file, AclFileError};
+pub use hashing::{Argon2Params, PasswordScheme};
 pub use patterns::{KeyPattern, KeyPatterns};
 pub use user::{AclUser, CommandCategory, CommandPermissions};
 
-use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -306,6 +307,12 @@ pub struct AclManager {
     require_auth: bool,
     /// ACL denial log
     pub acl_log: AclLogStore,
+    /// Complexity policy applied to `>password` rules in ACL SETUSER
+    password_policy: crate::security::password::PasswordPolicy,
+    /// Scheme used for newly stored password hashes
+    hash_scheme: PasswordScheme,
+    /// Cost parameters for argon2id hashes
+    argon2_params: Argon2Params,
 }
 
 impl AclManager {
@@ -319,6 +326,9 @@ impl AclManager {
             users,
             require_auth: false,
             acl_log: AclLogStore::new(),
+            password_policy: crate::security::password::PasswordPolicy::permissive(),
+            hash_scheme: PasswordScheme::Sha256,
+            argon2_params: Argon2Params::default(),
         }
     }
 
@@ -331,10 +341,7 @@ impl AclManager {
 
     /// Hash a password using SHA256 (Redis uses SHA256 for ACL passwords)
     pub fn hash_password(password: &str) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(password.as_bytes());
-        let result = hasher.finalize();
-        hex::encode(result)
+        hashing::sha256_hex(password)
     }
 
     /// Check if authentication is required
@@ -347,6 +354,44 @@ impl AclManager {
         self.require_auth = require;
     }
 
+    /// Password policy enforced on `>password` rules
+    pub fn password_policy(&self) -> crate::security::password::PasswordPolicy {
+        self.password_policy
+    }
+
+    /// Set the password policy enforced on `>password` rules
+    pub fn set_password_policy(&mut self, policy: crate::security::password::PasswordPolicy) {
+        self.password_policy = policy;
+    }
+
+    /// Scheme used for newly stored password hashes
+    pub fn hash_scheme(&self) -> PasswordScheme {
+        self.hash_scheme
+    }
+
+    /// Set the scheme for newly stored hashes. Fails if the scheme is not
+    /// compiled into this build.
+    pub fn set_hash_scheme(&mut self, scheme: PasswordScheme) -> Result<(), String> {
+        if !scheme.is_available() {
+            return Err(format!(
+                "password hash scheme '{}' requires the acl-argon2 feature",
+                scheme.name()
+            ));
+        }
+        self.hash_scheme = scheme;
+        Ok(())
+    }
+
+    /// Set the argon2id cost parameters for newly stored hashes
+    pub fn set_argon2_params(&mut self, params: Argon2Params) {
+        self.argon2_params = params;
+    }
+
+    /// Hash a plaintext password with the configured scheme
+    pub fn hash_for_storage(&self, password: &str) -> String {
+        hashing::hash_password(self.hash_scheme, password, self.argon2_params)
+    }
+
     /// Authenticate a user with username and password
     pub fn authenticate(&self, username: &str, password: &str) -> Result<Arc<AclUser>, AclError> {
         self.verify_invariants();
@@ -359,14 +404,51 @@ impl AclManager {
             return Err(AclError::AuthFailed);
         }
 
-        let password_hash = Self::hash_password(password);
-        if !user.verify_password(&password_hash) {
+        if !user.nopass && user.find_password(password).is_none() {
             return Err(AclError::AuthFailed);
         }
 
         Ok(Arc::clone(user))
     }
 
+    /// True if `password` (already authenticated) matched a hash stored with
+    /// a scheme other than the configured one
+    pub fn needs_rehash(&self, user: &AclUser, password: &str) -> bool {
+        user.find_password(password).is_some_and(|i| {
+            PasswordScheme::of(&user.password_hashes[i]) != Some(self.hash_scheme)
+        })
+    }
+
+    /// Re-hash the stored hash matching `password` with the configured scheme.
+    ///
+    /// Called after a successful AUTH to migrate legacy SHA256 hashes. Returns
+    /// true if a hash was replaced.
+    pub fn rehash_password(&mut self, username: &str, password: &str) -> bool {
+        let Some(user) = self.users.get(username) else {
+            return false;
+        };
+        if !self.needs_rehash(user, password) {
+            return false;
+        }
+        let Some(index) = user.find_password(password) else {
+            return false;
+        };
+
+        let mut updated = (**user).clone();
+        updated.password_hashes[index] = self.hash_for_storage(password);
+        self.set_user(updated);
+
+        // TigerStyle: Postcondition
+        debug_assert!(
+            self.users[username]
+                .find_password(password)
+                .is_some_and(|i| PasswordScheme::of(&self.users[username].password_hashes[i])
+                    == Some(self.hash_scheme)),
+            "Postcondition violated: rehashed password must verify under the configured scheme"
+        );
+        true
+    }
+
     /// Check if a user is permitted to execute a command on the given keys
     pub fn check_command(
         &self,
@@ -425,19 +507,12 @@ impl AclManager {
             self.users["default"].enabled,
             "ACL invariant violated: default user must always be enabled"
         );
-        // INV-3: Password hashes are valid SHA256 hex (64 chars)
+        // INV-3: Password hashes carry a recognised scheme (SHA256 hex or argon2id PHC)
         for user in self.users.values() {
             for hash in &user.password_hashes {
-                debug_assert_eq!(
-                    hash.len(),
-                    64,
-                    "ACL invariant violated: password hash for '{}' has length {} (expected 64)",
-                    user.name,
-                    hash.len()
-                );
                 debug_assert!(
-                    hash.chars().all(|c| c.is_ascii_hexdigit()),
-                    "ACL invariant violated: password hash for '{}' contains non-hex chars",
+                    PasswordScheme::of(hash).is_some(),
+                    "ACL invariant violated: password hash for '{}' has an unrecognised scheme",
                     user.name
                 );
             }
@@ -558,6 +633,56 @@ mod tests {
         assert!(matches!(result, Err(AclError::AuthFailed)));
     }
 
+    #[test]
+    fn test_sha256_scheme_needs_no_rehash() {
+        let mut manager = AclManager::new();
+        let mut user = AclUser::new("alice".to_string());
+        user.add_password("secret");
+        user.enabled = true;
+        manager.set_user(user);
+
+        let alice = manager.authenticate("alice", "secret").unwrap();
+        assert!(!manager.needs_rehash(&alice, "secret"));
+        assert!(!manager.rehash_password("alice", "secret"));
+    }
+
+    #[cfg(not(feature = "acl-argon2"))]
+    #[test]
+    fn test_argon2_scheme_rejected_without_feature() {
+        let mut manager = AclManager::new();
+        assert!(manager.set_hash_scheme(PasswordScheme::Argon2id).is_err());
+        assert_eq!(manager.hash_scheme(), PasswordScheme::Sha256);
+    }
+
+    #[cfg(feature = "acl-argon2")]
+    #[test]
+    fn test_sha256_hash_migrates_to_argon2id_on_auth() {
+        let mut manager = AclManager::new();
+        let mut user = AclUser::new("alice".to_string());
+        user.add_password("secret");
+        user.add_password("other");
+        user.enabled = true;
+        manager.set_user(user);
+
+        manager.set_hash_scheme(PasswordScheme::Argon2id).unwrap();
+        manager.set_argon2_params(Argon2Params::minimal());
+
+        // Legacy hashes keep verifying after the scheme change
+        let alice = manager.authenticate("alice", "secret").unwrap();
+        assert!(manager.needs_rehash(&alice, "secret"));
+        assert!(manager.rehash_password("alice", "secret"));
+
+        let alice = manager.get_user("alice").unwrap();
+        assert_eq!(PasswordScheme::of(&alice.password_hashes[0]), Some(PasswordScheme::Argon2id));
+        // Only the hash for the password actually used is migrated
+        assert_eq!(alice.password_hashes[1], AclManager::hash_password("other"));
+
+        assert!(manager.authenticate("alice", "secret").is_ok());
+        assert!(manager.authenticate("alice", "other").is_ok());
+        assert!(manager.authenticate("alice", "wrong").is_err());
+        assert!(!manager.rehash_password("alice", "secret"), "already migrated");
+    }
+
     #[test]
     fn test_command_check() {
         let mut manager = AclManager::new();
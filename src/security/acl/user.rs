@@ -34,7 +34,7 @@ pub enum CommandCategory {
     Scripting,
     /// Transaction commands (MULTI, EXEC, etc.) - not implemented
     Transaction,
-    /// Pub/Sub commands (PUBLISH, SUBSCRIBE, etc.) - stub
+    /// Pub/Sub commands (PUBLISH, SUBSCRIBE, etc.)
     PubSub,
     /// Slow commands
     Slow,
@@ -188,7 +188,10 @@ impl CommandCategory {
             ],
             CommandCategory::Connection => &["AUTH", "PING", "ECHO", "SELECT", "QUIT", "CLIENT"],
             CommandCategory::Server => &["INFO", "DBSIZE", "TIME", "COMMAND"],
-            CommandCategory::Scripting => &["EVAL", "EVALSHA", "SCRIPT"],
+            CommandCategory::Scripting => &[
+                "EVAL", "EVALSHA", "EVAL_RO", "EVALSHA_RO", "SCRIPT", "FUNCTION", "FCALL",
+                "FCALL_RO",
+            ],
             CommandCategory::Transaction => &["MULTI", "EXEC", "DISCARD", "WATCH", "UNWATCH"],
             CommandCategory::PubSub => &[
                 "PUBLISH", "SUBSCRIBE", "UNSUBSCRIBE", "PSUBSCRIBE", "PUNSUBSCRIBE",
@@ -345,7 +348,7 @@ impl Default for CommandPermissions {
 pub struct AclUser {
     /// Username
     pub name: String,
-    /// Password hashes (SHA256, hex encoded)
+    /// Password hashes, each SHA256 hex or an argon2id PHC string
     pub password_hashes: Vec<String>,
     /// Whether the user is enabled
     pub enabled: bool,
@@ -355,6 +358,9 @@ pub struct AclUser {
     pub keys: KeyPatterns,
     /// Whether this user can authenticate without password (nopass)
     pub nopass: bool,
+    /// Tenant key prefix (`keyspace:<prefix>`). Every key the user names is
+    /// transparently confined under it; None = the shared keyspace.
+    pub keyspace: Option<String>,
 }
 
 impl AclUser {
@@ -367,6 +373,7 @@ impl AclUser {
             commands: CommandPermissions::deny_all(),
             keys: KeyPatterns::deny_all(),
             nopass: false,
+            keyspace: None,
         }
     }
 
@@ -379,6 +386,7 @@ impl AclUser {
             commands: CommandPermissions::allow_all(),
             keys: KeyPatterns::allow_all(),
             nopass: true, // Default user has nopass by default
+            keyspace: None,
         }
     }
 
@@ -397,10 +405,17 @@ impl AclUser {
         }
     }
 
-    /// Remove a password
+    /// Remove a password, whatever scheme its hash is stored under
     pub fn remove_password(&mut self, password: &str) {
-        let hash = super::AclManager::hash_password(password);
-        self.password_hashes.retain(|h| h != &hash);
+        self.password_hashes
+            .retain(|h| !super::hashing::verify_password(h, password));
+    }
+
+    /// Index of the stored hash matching a plaintext password (ignores nopass)
+    pub fn find_password(&self, password: &str) -> Option<usize> {
+        self.password_hashes
+            .iter()
+            .position(|h| super::hashing::verify_password(h, password))
     }
 
     /// Clear all passwords
@@ -408,7 +423,7 @@ impl AclUser {
         self.password_hashes.clear();
     }
 
-    /// Verify a password hash
+    /// Verify a precomputed SHA256 password hash
     pub fn verify_password(&self, password_hash: &str) -> bool {
         // If nopass is set, any password works (or no password)
         if self.nopass {
@@ -422,6 +437,11 @@ impl AclUser {
         self.keys.allow_all
     }
 
+    /// Tenant key prefix this user is confined to, if any
+    pub fn keyspace(&self) -> Option<&str> {
+        self.keyspace.as_deref()
+    }
+
     /// Reset user to default state (disabled, no permissions)
     pub fn reset(&mut self) {
         self.password_hashes.clear();
@@ -429,6 +449,7 @@ impl AclUser {
         self.commands = CommandPermissions::deny_all();
         self.keys = KeyPatterns::deny_all();
         self.nopass = false;
+        self.keyspace = None;
     }
 
     /// Format user as ACL rule string (for ACL LIST)
@@ -465,6 +486,11 @@ impl AclUser {
             }
         }
 
+        // Tenant keyspace
+        if let Some(prefix) = &self.keyspace {
+            parts.push(format!("keyspace:{}", prefix));
+        }
+
         // Channels (always &* for now since we don't restrict channels)
         parts.push("&*".to_string());
 
@@ -593,6 +619,19 @@ mod tests {
         assert!(!user.verify_password(&wrong_hash));
     }
 
+    #[test]
+    fn test_keyspace_in_acl_string() {
+        let mut user = AclUser::new("tenant".to_string());
+        assert!(!user.to_acl_string().contains("keyspace:"));
+
+        user.keyspace = Some("acme:".to_string());
+        assert_eq!(user.keyspace(), Some("acme:"));
+        assert!(user.to_acl_string().contains(" keyspace:acme: "));
+
+        user.reset();
+        assert_eq!(user.keyspace(), None);
+    }
+
     #[test]
     fn test_nopass_user() {
         let mut user = AclUser::new("test".to_string());
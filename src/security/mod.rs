@@ -4,6 +4,10 @@
 //! - `tls` feature: TLS encryption via rustls
 //! - `acl` feature: Redis 6.0+ compatible ACL system
 //! - `security` feature: Both TLS and ACL
+//!
+//! `password` (GENPASS generation and password policy) is always available so
+//! the executor can use it without the `acl` feature. `tls_stats` (handshake
+//! counters for INFO) is likewise ungated.
 
 #[cfg(feature = "acl")]
 pub mod acl;
@@ -11,6 +15,10 @@ pub mod acl;
 #[cfg(feature = "acl")]
 pub mod acl_dst;
 
+pub mod password;
+
+pub mod tls_stats;
+
 #[cfg(feature = "tls")]
 pub mod tls;
 
@@ -21,6 +29,8 @@ pub use acl::{AclError, AclManager, AclUser};
 #[cfg(feature = "tls")]
 pub use tls::{MaybeSecureStream, TlsConfig, TlsError};
 
+pub use tls_stats::TlsStats;
+
 // No-op ACL manager when ACL feature is disabled
 #[cfg(not(feature = "acl"))]
 pub mod acl_noop {
@@ -57,6 +67,11 @@ pub mod acl_noop {
         pub fn has_unrestricted_keys(&self) -> bool {
             true
         }
+
+        /// No-op: tenant keyspaces are configured through ACL rules
+        pub fn keyspace(&self) -> Option<&str> {
+            None
+        }
     }
 
     /// No-op ACL manager - always permits everything
@@ -0,0 +1,192 @@
+//! Password generation and policy
+//!
+//! Shared by the connection-level ACL handlers (CSPRNG-backed, with or without
+//! the `acl` feature) and the executor (its seeded RNG under simulation, the
+//! CSPRNG otherwise), so both produce identical GENPASS semantics:
+//!
+//! - `bits` defaults to 256 and must be in `1..=4096`
+//! - the output is `ceil(bits / 4)` lowercase hex characters (4 bits each),
+//!   so `ACL GENPASS 5` returns 2 characters, as in Redis
+//!
+//! `PasswordPolicy` is an optional complexity check applied to `>password`
+//! rules in ACL SETUSER. The default policy accepts everything, matching Redis.
+
+use crate::io::Rng;
+
+/// Default GENPASS output size in bits
+pub const GENPASS_DEFAULT_BITS: u32 = 256;
+
+/// Largest GENPASS output size Redis accepts
+pub const GENPASS_MAX_BITS: u32 = 4096;
+
+/// Validate the optional GENPASS `bits` argument, applying the default
+pub fn genpass_bits(bits: Option<u32>) -> Result<u32, String> {
+    match bits {
+        None => Ok(GENPASS_DEFAULT_BITS),
+        Some(b) if b > 0 && b <= GENPASS_MAX_BITS => Ok(b),
+        Some(_) => Err(
+            "ERR ACL GENPASS argument must be the number of bits for the output password, a positive number up to 4096"
+                .to_string(),
+        ),
+    }
+}
+
+/// Generate `ceil(bits / 4)` hex characters from `rng`.
+///
+/// The caller chooses the entropy source: `ProductionRng` (ChaCha seeded from
+/// the OS) in production, `SimulatedRng` under DST.
+pub fn generate_password<R: Rng>(rng: &mut R, bits: u32) -> String {
+    debug_assert!(
+        bits > 0 && bits <= GENPASS_MAX_BITS,
+        "Precondition violated: GENPASS bits must be in (0, 4096]"
+    );
+
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let chars = bits.div_ceil(4) as usize;
+    let mut result = String::with_capacity(chars);
+    let mut word = 0u64;
+    for i in 0..chars {
+        // Each u64 yields 16 nibbles
+        if i % 16 == 0 {
+            word = rng.next_u64();
+        }
+        result.push(HEX[(word & 0xF) as usize] as char);
+        word >>= 4;
+    }
+
+    // TigerStyle: Postconditions
+    debug_assert_eq!(
+        result.len(),
+        chars,
+        "Postcondition violated: GENPASS output length must be ceil(bits/4)"
+    );
+    debug_assert!(
+        result.bytes().all(|c| c.is_ascii_hexdigit()),
+        "Postcondition violated: GENPASS output must be valid hex"
+    );
+
+    result
+}
+
+/// Optional password complexity policy for `>password` rules.
+///
+/// Character classes are lowercase, uppercase, digits and everything else.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// Minimum password length in bytes (0 = no minimum)
+    pub min_length: usize,
+    /// Minimum number of distinct character classes, 0..=4 (0 = no requirement)
+    pub min_classes: u8,
+}
+
+impl PasswordPolicy {
+    /// Policy that accepts every password (Redis default)
+    pub fn permissive() -> Self {
+        Self::default()
+    }
+
+    pub fn new(min_length: usize, min_classes: u8) -> Self {
+        debug_assert!(min_classes <= 4, "Precondition: at most 4 character classes exist");
+        PasswordPolicy {
+            min_length,
+            min_classes: min_classes.min(4),
+        }
+    }
+
+    /// True if this policy never rejects a password
+    pub fn is_permissive(&self) -> bool {
+        self.min_length == 0 && self.min_classes == 0
+    }
+
+    /// Check a plaintext password, returning a descriptive reason on failure.
+    /// The reason never contains the password itself.
+    pub fn check(&self, password: &str) -> Result<(), String> {
+        if password.len() < self.min_length {
+            return Err(format!(
+                "password must be at least {} characters long",
+                self.min_length
+            ));
+        }
+
+        if self.min_classes > 0 {
+            let classes = Self::character_classes(password);
+            if classes < self.min_classes {
+                return Err(format!(
+                    "password must contain at least {} of: lowercase, uppercase, digits, symbols (found {})",
+                    self.min_classes, classes
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn character_classes(password: &str) -> u8 {
+        let mut lower = false;
+        let mut upper = false;
+        let mut digit = false;
+        let mut other = false;
+        for c in password.chars() {
+            if c.is_ascii_lowercase() {
+                lower = true;
+            } else if c.is_ascii_uppercase() {
+                upper = true;
+            } else if c.is_ascii_digit() {
+                digit = true;
+            } else {
+                other = true;
+            }
+        }
+        [lower, upper, digit, other].iter().filter(|&&b| b).count() as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::simulation::SimulatedRng;
+
+    #[test]
+    fn test_genpass_bits_validation() {
+        assert_eq!(genpass_bits(None), Ok(256));
+        assert_eq!(genpass_bits(Some(1)), Ok(1));
+        assert_eq!(genpass_bits(Some(4096)), Ok(4096));
+        assert!(genpass_bits(Some(0)).is_err());
+        assert!(genpass_bits(Some(4097)).is_err());
+    }
+
+    #[test]
+    fn test_genpass_length_is_ceil_bits_over_4() {
+        let mut rng = SimulatedRng::new(7);
+        assert_eq!(generate_password(&mut rng, 256).len(), 64);
+        assert_eq!(generate_password(&mut rng, 5).len(), 2);
+        assert_eq!(generate_password(&mut rng, 4).len(), 1);
+        assert_eq!(generate_password(&mut rng, 1).len(), 1);
+        assert_eq!(generate_password(&mut rng, 4096).len(), 1024);
+    }
+
+    #[test]
+    fn test_genpass_deterministic_under_simulation() {
+        let a = generate_password(&mut SimulatedRng::new(42), 128);
+        let b = generate_password(&mut SimulatedRng::new(42), 128);
+        let c = generate_password(&mut SimulatedRng::new(43), 128);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_password_policy() {
+        assert!(PasswordPolicy::permissive().check("").is_ok());
+
+        let policy = PasswordPolicy::new(8, 3);
+        let err = policy.check("Ab1").unwrap_err();
+        assert!(err.contains("at least 8 characters"), "{}", err);
+        assert!(!err.contains("Ab1"), "reason must not leak the password");
+
+        let err = policy.check("abcdefgh1").unwrap_err();
+        assert!(err.contains("found 2"), "{}", err);
+
+        assert!(policy.check("Abcdefg1").is_ok());
+        assert!(policy.check("abcdefg1!").is_ok());
+    }
+}
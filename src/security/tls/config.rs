@@ -8,7 +8,7 @@ use std::sync::Arc;
 use rustls_pemfile::{certs, private_key};
 use tokio_rustls::rustls::{
     pki_types::{CertificateDer, PrivateKeyDer},
-    server::WebPkiClientVerifier,
+    server::{NoServerSessionStorage, ServerSessionMemoryCache, WebPkiClientVerifier},
     RootCertStore, ServerConfig,
 };
 use tokio_rustls::TlsAcceptor;
@@ -95,8 +95,17 @@ pub struct TlsConfig {
     pub ca_path: Option<PathBuf>,
     /// Whether to require client certificates (mutual TLS)
     pub require_client_cert: bool,
+    /// ALPN protocols offered, in server preference order (empty = no ALPN)
+    pub alpn_protocols: Vec<String>,
+    /// Stateful session cache capacity (0 = disable session-id resumption)
+    pub session_cache_size: usize,
+    /// Whether to issue stateless session tickets (TLS 1.2 tickets, TLS 1.3 PSKs)
+    pub session_tickets: bool,
 }
 
+/// Default stateful session cache capacity (rustls' own default)
+pub const DEFAULT_SESSION_CACHE_SIZE: usize = 256;
+
 impl TlsConfig {
     /// Create a new TLS configuration
     pub fn new(cert_path: impl AsRef<Path>, key_path: impl AsRef<Path>) -> Self {
@@ -105,6 +114,9 @@ impl TlsConfig {
             key_path: key_path.as_ref().to_path_buf(),
             ca_path: None,
             require_client_cert: false,
+            alpn_protocols: Vec::new(),
+            session_cache_size: DEFAULT_SESSION_CACHE_SIZE,
+            session_tickets: true,
         }
     }
 
@@ -120,6 +132,28 @@ impl TlsConfig {
         self
     }
 
+    /// Set the ALPN protocols offered (e.g. `["redis", "resp3"]`)
+    pub fn with_alpn<I, P>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.alpn_protocols = protocols.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the stateful session cache capacity (0 disables it)
+    pub fn with_session_cache_size(mut self, size: usize) -> Self {
+        self.session_cache_size = size;
+        self
+    }
+
+    /// Enable or disable stateless session tickets
+    pub fn with_session_tickets(mut self, enabled: bool) -> Self {
+        self.session_tickets = enabled;
+        self
+    }
+
     /// Load certificates from file
     fn load_certs(&self) -> Result<Vec<CertificateDer<'static>>, TlsError> {
         let file = File::open(&self.cert_path).map_err(|e| TlsError::CertificateReadError {
@@ -198,7 +232,7 @@ impl TlsConfig {
         let certs = self.load_certs()?;
         let key = self.load_private_key()?;
 
-        let config = if let Some(root_store) = self.load_ca_certs()? {
+        let mut config = if let Some(root_store) = self.load_ca_certs()? {
             // Client certificate verification enabled
             let client_verifier = if self.require_client_cert {
                 WebPkiClientVerifier::builder(Arc::new(root_store))
@@ -231,8 +265,45 @@ impl TlsConfig {
                 })?
         };
 
+        self.configure_resumption(&mut config)?;
+        config.alpn_protocols = self
+            .alpn_protocols
+            .iter()
+            .map(|p| p.as_bytes().to_vec())
+            .collect();
+
         Ok(TlsAcceptor::from(Arc::new(config)))
     }
+
+    /// Configure session resumption so reconnecting clients skip the full
+    /// handshake (and its certificate verification).
+    fn configure_resumption(&self, config: &mut ServerConfig) -> Result<(), TlsError> {
+        if let Some(bad) = self.alpn_protocols.iter().find(|p| p.is_empty() || p.len() > 255) {
+            return Err(TlsError::ConfigError {
+                reason: format!("ALPN protocol {:?} must be 1-255 bytes", bad),
+            });
+        }
+
+        config.session_storage = if self.session_cache_size > 0 {
+            ServerSessionMemoryCache::new(self.session_cache_size)
+        } else {
+            Arc::new(NoServerSessionStorage {})
+        };
+
+        if self.session_tickets {
+            config.ticketer = tokio_rustls::rustls::crypto::aws_lc_rs::Ticketer::new().map_err(
+                |e| TlsError::ConfigError {
+                    reason: format!("failed to create session ticketer: {}", e),
+                },
+            )?;
+        } else if self.session_cache_size == 0 {
+            // TLS 1.3 tickets reference the session cache when no ticketer
+            // is set; with neither there is nothing to resume
+            config.send_tls13_tickets = 0;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -251,5 +322,21 @@ mod tests {
         assert!(config.require_client_cert);
     }
 
+    #[test]
+    fn test_resumption_and_alpn_defaults() {
+        let config = TlsConfig::new("/path/to/cert.pem", "/path/to/key.pem");
+        assert!(config.alpn_protocols.is_empty());
+        assert_eq!(config.session_cache_size, DEFAULT_SESSION_CACHE_SIZE);
+        assert!(config.session_tickets);
+
+        let config = config
+            .with_alpn(["redis", "resp3"])
+            .with_session_cache_size(0)
+            .with_session_tickets(false);
+        assert_eq!(config.alpn_protocols, vec!["redis", "resp3"]);
+        assert_eq!(config.session_cache_size, 0);
+        assert!(!config.session_tickets);
+    }
+
     // Note: Integration tests with actual certificates would go in tests/tls_test.rs
 }
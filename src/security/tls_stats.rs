@@ -0,0 +1,102 @@
+//! TLS handshake counters for INFO
+//!
+//! Ungated so the connection handler can carry them without the `tls`
+//! feature; without it nothing ever records a handshake and the section is
+//! not rendered.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Server-wide TLS handshake counters
+#[derive(Debug, Default)]
+pub struct TlsStats {
+    handshakes: AtomicU64,
+    handshake_failures: AtomicU64,
+    resumed: AtomicU64,
+    alpn_negotiated: AtomicU64,
+}
+
+impl TlsStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a completed handshake
+    pub fn record_handshake(&self, resumed: bool, alpn_negotiated: bool) {
+        self.handshakes.fetch_add(1, Ordering::Relaxed);
+        if resumed {
+            self.resumed.fetch_add(1, Ordering::Relaxed);
+        }
+        if alpn_negotiated {
+            self.alpn_negotiated.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a handshake that failed before the connection was established
+    pub fn record_failure(&self) {
+        self.handshake_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Completed handshakes (full and resumed)
+    pub fn handshakes(&self) -> u64 {
+        self.handshakes.load(Ordering::Relaxed)
+    }
+
+    pub fn handshake_failures(&self) -> u64 {
+        self.handshake_failures.load(Ordering::Relaxed)
+    }
+
+    /// Handshakes that resumed a previous session (ticket or session id)
+    pub fn resumed(&self) -> u64 {
+        self.resumed.load(Ordering::Relaxed)
+    }
+
+    pub fn alpn_negotiated(&self) -> u64 {
+        self.alpn_negotiated.load(Ordering::Relaxed)
+    }
+
+    /// Render the `# TLS` INFO section
+    pub fn render_info(&self) -> String {
+        let handshakes = self.handshakes();
+        let resumed = self.resumed();
+        debug_assert!(
+            resumed <= handshakes,
+            "Invariant: resumed handshakes cannot exceed completed handshakes"
+        );
+        let full = handshakes.saturating_sub(resumed);
+        format!(
+            "# TLS\r\n\
+             tls_handshakes_total:{}\r\n\
+             tls_handshakes_full:{}\r\n\
+             tls_handshakes_resumed:{}\r\n\
+             tls_handshakes_failed:{}\r\n\
+             tls_alpn_negotiated:{}\r\n",
+            handshakes,
+            full,
+            resumed,
+            self.handshake_failures(),
+            self.alpn_negotiated()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_info() {
+        let stats = TlsStats::new();
+        stats.record_handshake(false, true);
+        stats.record_handshake(true, true);
+        stats.record_handshake(true, false);
+        stats.record_failure();
+
+        let info = stats.render_info();
+        assert!(info.starts_with("# TLS\r\n"));
+        assert!(info.contains("tls_handshakes_total:3\r\n"));
+        assert!(info.contains("tls_handshakes_full:1\r\n"));
+        assert!(info.contains("tls_handshakes_resumed:2\r\n"));
+        assert!(info.contains("tls_handshakes_failed:1\r\n"));
+        assert!(info.contains("tls_alpn_negotiated:2\r\n"));
+    }
+}
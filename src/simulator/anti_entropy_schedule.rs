@@ -0,0 +1,206 @@
+//! Anti-Entropy Scheduling Simulation
+//!
+//! Compares the fixed anti-entropy schedule against adaptive per-peer
+//! scheduling on the same workload:
+//! - Writes spread over every node but one, gossiping every tick
+//! - That node reads only, behind lossy links, so its pairs keep diverging
+//! - The other links lossless, so their pairs are mostly in sync and
+//!   exchange little more than digests
+//!
+//! Each run reports the anti-entropy bytes put on the wire and how long keys
+//! stayed divergent, both while writes run and after they stop.
+
+use super::multi_node::MultiNodeSimulation;
+use crate::redis::{Command, SDS};
+use crate::replication::anti_entropy::AntiEntropyConfig;
+
+/// Workload and schedule for one run
+#[derive(Debug, Clone)]
+pub struct ScheduleScenario {
+    pub num_nodes: usize,
+    /// Node that takes no writes and whose links to every other node drop
+    /// `lossy_rate` of gossip
+    pub lossy_node: usize,
+    pub lossy_rate: f64,
+    /// Distinct keys written
+    pub num_keys: usize,
+    /// Chance that a tick writes one key, on a node other than the lossy one
+    pub write_probability: f64,
+    pub tick_ms: u64,
+    /// How long writes run
+    pub write_ms: u64,
+    /// How long to wait for convergence after writes stop
+    pub settle_ms: u64,
+    pub anti_entropy: AntiEntropyConfig,
+}
+
+impl ScheduleScenario {
+    /// A 4-node cluster with a lossy node 3, synced every `interval_ms`
+    pub fn fixed(interval_ms: u64) -> Self {
+        ScheduleScenario {
+            num_nodes: 4,
+            lossy_node: 3,
+            lossy_rate: 0.5,
+            num_keys: 40,
+            write_probability: 0.5,
+            tick_ms: 10,
+            write_ms: 5_000,
+            settle_ms: 10_000,
+            anti_entropy: AntiEntropyConfig {
+                sync_interval_ms: interval_ms,
+                ..AntiEntropyConfig::default()
+            },
+        }
+    }
+
+    /// The same cluster with adaptive intervals between the given bounds,
+    /// starting from the default interval
+    pub fn adaptive(min_ms: u64, max_ms: u64, target_keys_per_sync: usize) -> Self {
+        let mut scenario = Self::fixed(AntiEntropyConfig::default().sync_interval_ms);
+        scenario.anti_entropy.adaptive_sync = true;
+        scenario.anti_entropy.min_sync_interval_ms = min_ms;
+        scenario.anti_entropy.max_sync_interval_ms = max_ms;
+        scenario.anti_entropy.target_keys_per_sync = target_keys_per_sync;
+        scenario
+    }
+}
+
+/// Outcome of one run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleResult {
+    pub seed: u64,
+    /// Anti-entropy rounds run
+    pub rounds: u64,
+    /// Anti-entropy bytes put on the wire
+    pub bytes: u64,
+    /// Sum over ticks of the keys not yet identical on every node
+    pub divergent_key_ticks: u64,
+    /// Time from the last write until every key converged, if it did
+    pub convergence_ms: Option<u64>,
+}
+
+/// Run one scenario with the given seed
+pub fn run_schedule_scenario(scenario: &ScheduleScenario, seed: u64) -> ScheduleResult {
+    debug_assert!(
+        scenario.lossy_node < scenario.num_nodes,
+        "Precondition: lossy node must be in the cluster"
+    );
+    debug_assert!(scenario.tick_ms > 0, "Precondition: tick must be positive");
+
+    let mut sim = MultiNodeSimulation::new(scenario.num_nodes, seed)
+        .with_anti_entropy_config(scenario.anti_entropy.clone());
+    for node in (0..scenario.num_nodes).filter(|&n| n != scenario.lossy_node) {
+        sim = sim.with_link_loss(node, scenario.lossy_node, scenario.lossy_rate);
+    }
+    let keys: Vec<String> = (0..scenario.num_keys)
+        .map(|k| format!("key:{}", k))
+        .collect();
+    let divergent = |sim: &MultiNodeSimulation| {
+        keys.iter()
+            .filter(|key| !sim.check_key_convergence(key))
+            .count() as u64
+    };
+
+    let mut divergent_key_ticks = 0;
+    let mut rounds = 0;
+    let mut tick = |sim: &mut MultiNodeSimulation| {
+        sim.advance_time_ms(scenario.tick_ms);
+        sim.gossip_round();
+        rounds += sim.run_scheduled_anti_entropy() as u64;
+        divergent(sim)
+    };
+
+    let mut version = 0u64;
+    for _ in 0..scenario.write_ms / scenario.tick_ms {
+        if sim.rng.gen_bool(scenario.write_probability) {
+            let mut node = sim.rng.gen_range(0, scenario.num_nodes as u64 - 1) as usize;
+            if node >= scenario.lossy_node {
+                node += 1;
+            }
+            let key = keys[sim.rng.gen_range(0, keys.len() as u64) as usize].clone();
+            version += 1;
+            let value = SDS::from_str(&format!("v{}", version));
+            sim.execute(0, node, Command::set(key, value));
+        }
+        divergent_key_ticks += tick(&mut sim);
+    }
+
+    let write_end = sim.current_time.as_millis();
+    let mut convergence_ms = None;
+    for _ in 0..scenario.settle_ms / scenario.tick_ms {
+        let remaining = tick(&mut sim);
+        divergent_key_ticks += remaining;
+        if remaining == 0 && sim.message_queue.is_empty() {
+            convergence_ms = Some(sim.current_time.as_millis() - write_end);
+            break;
+        }
+    }
+
+    ScheduleResult {
+        seed,
+        rounds,
+        bytes: sim.anti_entropy_bandwidth().bytes_sent,
+        divergent_key_ticks,
+        convergence_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: [u64; 10] = [1, 7, 42, 99, 256, 1234, 4096, 9001, 31337, 65535];
+
+    #[test]
+    fn test_fixed_and_adaptive_schedules_converge() {
+        for scenario in [
+            ScheduleScenario::fixed(200),
+            ScheduleScenario::adaptive(50, 5_000, 1),
+        ] {
+            for seed in SEEDS {
+                let result = run_schedule_scenario(&scenario, seed);
+                assert!(result.convergence_ms.is_some(), "{:?}", result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive_schedule_is_fresher_at_equal_bandwidth() {
+        let adaptive = ScheduleScenario::adaptive(50, 5_000, 1);
+        let pairs = (adaptive.num_nodes * (adaptive.num_nodes - 1) / 2) as u64;
+        let (mut adaptive_ticks, mut fixed_ticks) = (0, 0);
+        let (mut adaptive_bytes, mut fixed_bytes) = (0, 0);
+        for seed in SEEDS {
+            let a = run_schedule_scenario(&adaptive, seed);
+            // The fixed interval that runs as many rounds while writes last
+            let interval = adaptive.write_ms * pairs / a.rounds.max(1);
+            let f = run_schedule_scenario(&ScheduleScenario::fixed(interval), seed);
+            println!(
+                "seed {}: adaptive {:?}\n  fixed {}ms {:?}",
+                seed, a, interval, f
+            );
+            adaptive_ticks += a.divergent_key_ticks;
+            fixed_ticks += f.divergent_key_ticks;
+            adaptive_bytes += a.bytes;
+            fixed_bytes += f.bytes;
+        }
+        // Hot pairs sync faster than the fixed interval, paid for by quiet
+        // pairs backing off
+        assert!(adaptive_bytes <= fixed_bytes);
+        assert!(
+            adaptive_ticks * 10 < fixed_ticks * 8,
+            "adaptive {} vs fixed {} divergent key ticks",
+            adaptive_ticks,
+            fixed_ticks
+        );
+    }
+
+    #[test]
+    fn test_schedule_runs_are_deterministic() {
+        let scenario = ScheduleScenario::adaptive(50, 5_000, 1);
+        assert_eq!(
+            run_schedule_scenario(&scenario, 42),
+            run_schedule_scenario(&scenario, 42)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Byzantine Peer Simulation
+//!
+//! Runs a causal cluster in which one node gossips forged deltas next to
+//! its honest ones: values and timestamps changed under a stale checksum,
+//! different values resealed under a timestamp already used, and stale
+//! values whose timestamp jumps ahead while their vector clock rolls back.
+//!
+//! Every forgery follows the honest delta it was made from, so a receiver
+//! always holds that write (or a later one) when the forgery arrives and
+//! can tell it apart. The scenario checks that no node ever holds a forged
+//! value, that the cluster still converges, and tallies the refusals.
+
+use super::multi_node::{MultiNodeSimulation, FORGED_VALUE};
+use crate::redis::{Command, SDS};
+use crate::replication::{ConsistencyLevel, DeltaRejection};
+
+/// Workload and fault rate for one run
+#[derive(Debug, Clone)]
+pub struct ByzantineScenario {
+    pub num_nodes: usize,
+    pub byzantine_node: usize,
+    /// Chance each delta the faulty node gossips is followed by a forgery
+    pub forge_rate: f64,
+    pub num_keys: usize,
+    /// Chance that a tick writes one key, on any node
+    pub write_probability: f64,
+    /// Share of writes that are DELs
+    pub delete_probability: f64,
+    pub tick_ms: u64,
+    pub write_ms: u64,
+    /// How long to wait for convergence after writes stop
+    pub settle_ms: u64,
+}
+
+impl Default for ByzantineScenario {
+    fn default() -> Self {
+        ByzantineScenario {
+            num_nodes: 4,
+            byzantine_node: 0,
+            forge_rate: 0.5,
+            num_keys: 20,
+            write_probability: 0.6,
+            delete_probability: 0.1,
+            tick_ms: 10,
+            write_ms: 3_000,
+            settle_ms: 5_000,
+        }
+    }
+}
+
+/// Outcome of one run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ByzantineResult {
+    pub seed: u64,
+    /// Forged deltas the faulty node sent
+    pub forged: u64,
+    pub rejected_malformed: u64,
+    pub rejected_checksum: u64,
+    pub rejected_duplicate: u64,
+    pub rejected_regressed: u64,
+    /// Ticks on which some node held a forged value
+    pub corrupted_ticks: u64,
+    /// Time from the last write until every key converged, if it did
+    pub convergence_ms: Option<u64>,
+}
+
+impl ByzantineResult {
+    pub fn rejected(&self) -> u64 {
+        self.rejected_malformed
+            + self.rejected_checksum
+            + self.rejected_duplicate
+            + self.rejected_regressed
+    }
+}
+
+/// Run one scenario with the given seed
+pub fn run_byzantine_scenario(scenario: &ByzantineScenario, seed: u64) -> ByzantineResult {
+    debug_assert!(
+        scenario.byzantine_node < scenario.num_nodes,
+        "Precondition: faulty node must be in the cluster"
+    );
+    debug_assert!(scenario.tick_ms > 0, "Precondition: tick must be positive");
+
+    let mut sim = MultiNodeSimulation::new(scenario.num_nodes, seed)
+        .with_consistency_level(ConsistencyLevel::Causal)
+        .with_byzantine_node(scenario.byzantine_node, scenario.forge_rate);
+    let keys: Vec<String> = (0..scenario.num_keys)
+        .map(|k| format!("key:{}", k))
+        .collect();
+    let corrupted = |sim: &MultiNodeSimulation| {
+        sim.nodes.iter().any(|node| {
+            let executor = node.executor.get_data();
+            keys.iter().any(|key| {
+                node.get_replicated_value(key).as_deref() == Some(FORGED_VALUE)
+                    || executor
+                        .get(key)
+                        .and_then(|v| v.as_string())
+                        .is_some_and(|s| s.as_bytes() == FORGED_VALUE.as_bytes())
+            })
+        })
+    };
+
+    let mut result = ByzantineResult {
+        seed,
+        ..ByzantineResult::default()
+    };
+    let tick = |sim: &mut MultiNodeSimulation, result: &mut ByzantineResult| {
+        sim.advance_time_ms(scenario.tick_ms);
+        sim.gossip_round();
+        sim.run_scheduled_anti_entropy();
+        if corrupted(sim) {
+            result.corrupted_ticks += 1;
+        }
+    };
+
+    let mut version = 0u64;
+    for _ in 0..scenario.write_ms / scenario.tick_ms {
+        if sim.rng.gen_bool(scenario.write_probability) {
+            let node = sim.rng.gen_range(0, scenario.num_nodes as u64) as usize;
+            let key = keys[sim.rng.gen_range(0, keys.len() as u64) as usize].clone();
+            let cmd = if sim.rng.gen_bool(scenario.delete_probability) {
+                Command::del(key)
+            } else {
+                version += 1;
+                Command::set(key, SDS::from_str(&format!("v{}", version)))
+            };
+            sim.execute(0, node, cmd);
+        }
+        tick(&mut sim, &mut result);
+    }
+
+    let write_end = sim.current_time.as_millis();
+    for _ in 0..scenario.settle_ms / scenario.tick_ms {
+        tick(&mut sim, &mut result);
+        if sim.message_queue.is_empty() && keys.iter().all(|k| sim.check_key_convergence(k)) {
+            result.convergence_ms = Some(sim.current_time.as_millis() - write_end);
+            break;
+        }
+    }
+
+    result.forged = sim.forged_deltas.values().sum();
+    for rejection in sim.nodes.iter().flat_map(|node| &node.rejected) {
+        match rejection {
+            DeltaRejection::Malformed { .. } => result.rejected_malformed += 1,
+            DeltaRejection::ChecksumMismatch { .. } => result.rejected_checksum += 1,
+            DeltaRejection::DuplicateTimestamp { .. } => result.rejected_duplicate += 1,
+            DeltaRejection::RegressedVectorClock { .. } => result.rejected_regressed += 1,
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: [u64; 10] = [1, 7, 42, 99, 256, 1234, 4096, 9001, 31337, 65535];
+
+    #[test]
+    fn test_forged_deltas_never_land_and_cluster_converges() {
+        let scenario = ByzantineScenario::default();
+        let mut total = ByzantineResult::default();
+        for seed in SEEDS {
+            let result = run_byzantine_scenario(&scenario, seed);
+            println!("{:?}", result);
+            assert_eq!(result.corrupted_ticks, 0, "{:?}", result);
+            assert!(result.convergence_ms.is_some(), "{:?}", result);
+            assert!(result.rejected() <= result.forged, "{:?}", result);
+            total.forged += result.forged;
+            total.rejected_malformed += result.rejected_malformed;
+            total.rejected_checksum += result.rejected_checksum;
+            total.rejected_duplicate += result.rejected_duplicate;
+            total.rejected_regressed += result.rejected_regressed;
+        }
+        // Every kind of forgery was sent and caught somewhere
+        assert!(total.forged > 0);
+        assert_eq!(total.rejected_malformed, 0);
+        assert!(total.rejected_checksum > 0, "{:?}", total);
+        assert!(total.rejected_duplicate > 0, "{:?}", total);
+        assert!(total.rejected_regressed > 0, "{:?}", total);
+    }
+
+    #[test]
+    fn test_honest_cluster_refuses_nothing() {
+        let scenario = ByzantineScenario {
+            forge_rate: 0.0,
+            ..ByzantineScenario::default()
+        };
+        for seed in SEEDS {
+            let result = run_byzantine_scenario(&scenario, seed);
+            assert_eq!(result.forged, 0);
+            assert_eq!(result.rejected(), 0, "{:?}", result);
+            assert!(result.convergence_ms.is_some(), "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_byzantine_runs_are_deterministic() {
+        let scenario = ByzantineScenario::default();
+        assert_eq!(
+            run_byzantine_scenario(&scenario, 42),
+            run_byzantine_scenario(&scenario, 42)
+        );
+    }
+}
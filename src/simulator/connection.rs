@@ -5,13 +5,15 @@
 //! - Batched responses (multiple responses in one write)
 //! - Partial reads (incomplete RESP data)
 //! - Network delays and drops
+//! - Protocol limits: a request over them gets a protocol error and the
+//!   connection closes (buggify can shrink the limits for a read)
 //!
 //! This closes the gap between CommandExecutor simulation and production
 //! TCP handling, allowing us to test the connection handler logic
 //! deterministically.
 
-use super::{DeterministicRng, VirtualTime};
-use crate::redis::{Command, CommandExecutor, RespCodec, RespValue};
+use super::{DeterministicRng, LatencyProfile, VirtualTime};
+use crate::redis::{Command, CommandExecutor, ProtocolLimits, RespCodec, RespValue};
 use bytes::{BufMut, BytesMut};
 use std::collections::VecDeque;
 
@@ -270,6 +272,13 @@ pub struct SimulatedConnection {
     current_time: VirtualTime,
     /// Enable batched flushing (the fix we implemented)
     batched_flush: bool,
+    /// Declared execution time per command (zero by default)
+    latency: LatencyProfile,
+    latency_rng: DeterministicRng,
+    /// Request sizes beyond which the connection closes
+    limits: ProtocolLimits,
+    /// Closed after a protocol error; nothing more is read
+    closed: bool,
 }
 
 /// Record of a command execution
@@ -292,6 +301,10 @@ impl SimulatedConnection {
             history: Vec::new(),
             current_time: VirtualTime::ZERO,
             batched_flush: true, // Default to the fixed behavior
+            latency: LatencyProfile::default(),
+            latency_rng: DeterministicRng::new(seed),
+            limits: ProtocolLimits::default(),
+            closed: false,
         }
     }
 
@@ -307,6 +320,24 @@ impl SimulatedConnection {
         self
     }
 
+    /// Charge each command an artificial execution time; virtual time
+    /// advances by it, so later commands and the batch flush wait for it
+    pub fn with_latency(mut self, latency: LatencyProfile) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Enforce `limits` instead of the defaults
+    pub fn with_protocol_limits(mut self, limits: ProtocolLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Whether a protocol error closed the connection
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
     /// Queue a single command
     pub fn send_command(&mut self, cmd: Command) {
         // BUGGIFY: packet drop - silently drop the command
@@ -330,11 +361,30 @@ impl SimulatedConnection {
         self.read_buffer.queue_pipeline(commands);
     }
 
+    /// Limits for the next read: the configured ones, or shrunk ones when
+    /// buggify fires
+    fn read_limits(&self) -> ProtocolLimits {
+        // BUGGIFY: shrink the protocol limits so ordinary requests break them
+        #[cfg(feature = "simulation")]
+        {
+            use crate::buggify::faults;
+            let mut rng = crate::io::production::ProductionRng::new();
+            if crate::buggify::should_buggify(&mut rng, faults::protocol::SHRINK_LIMITS) {
+                return ProtocolLimits::shrunk();
+            }
+        }
+        self.limits
+    }
+
     /// Simulate the connection handler processing loop
     ///
     /// This mirrors `OptimizedConnectionHandler::run()` but deterministically
     pub fn process(&mut self) -> Vec<RespValue> {
         let mut responses = Vec::new();
+        if self.closed {
+            return responses;
+        }
+        let limits = self.read_limits();
 
         // BUGGIFY: network delay - add jitter to processing
         #[cfg(feature = "simulation")]
@@ -359,11 +409,13 @@ impl SimulatedConnection {
 
             // Process ALL available commands (pipelining support)
             loop {
-                match RespCodec::parse(&mut self.parse_buffer) {
+                match RespCodec::parse_with_limits(&mut self.parse_buffer, &limits) {
                     Ok(Some(resp_value)) => {
                         match Command::from_resp_zero_copy(&resp_value) {
                             Ok(cmd) => {
                                 let response = self.executor.execute(&cmd);
+                                self.current_time = self.current_time
+                                    + self.latency.sample(&cmd, &mut self.latency_rng);
                                 responses.push(response.clone());
 
                                 // Encode response
@@ -391,9 +443,17 @@ impl SimulatedConnection {
                                     });
                                 }
                             }
-                            Err(_) => {
-                                // Command parse error
-                                break;
+                            Err(e) => {
+                                // Not a valid command: answered in its turn,
+                                // dooming a MULTI it was sent in
+                                let response = self.executor.reject(e);
+                                Self::encode_resp(&response, &mut self.response_buffer);
+                                responses.push(response);
+                                if !self.batched_flush {
+                                    self.write_buffer.write_all(&self.response_buffer);
+                                    self.write_buffer.flush();
+                                    self.response_buffer.clear();
+                                }
                             }
                         }
                     }
@@ -401,16 +461,21 @@ impl SimulatedConnection {
                         // Need more data
                         break;
                     }
-                    Err(_) => {
-                        // RESP parse error
+                    Err(e) => {
+                        // Protocol error: reply, then close like the handler
+                        let response = RespValue::err(format!("ERR {}", e));
+                        Self::encode_resp(&response, &mut self.response_buffer);
+                        responses.push(response);
                         self.parse_buffer.clear();
+                        self.closed = true;
                         break;
                     }
                 }
             }
 
-            // Batched flush: flush ALL responses at once
-            if self.batched_flush && !self.response_buffer.is_empty() {
+            // Batched flush: flush ALL responses at once (and a protocol
+            // error's reply, which is always the last)
+            if (self.batched_flush || self.closed) && !self.response_buffer.is_empty() {
                 self.write_buffer.write_all(&self.response_buffer);
                 self.write_buffer.flush();
                 self.response_buffer.clear();
@@ -420,6 +485,10 @@ impl SimulatedConnection {
                     last.flush_after = true;
                 }
             }
+
+            if self.closed {
+                break;
+            }
         }
 
         responses
@@ -463,6 +532,10 @@ impl SimulatedConnection {
         self.history.len()
     }
 
+    pub fn current_time(&self) -> VirtualTime {
+        self.current_time
+    }
+
     /// Encode a RespValue to bytes
     fn encode_resp(value: &RespValue, buf: &mut BytesMut) {
         match value {
@@ -502,6 +575,7 @@ impl SimulatedConnection {
                     Self::encode_resp(elem, buf);
                 }
             }
+            resp3 => Self::encode_resp(&resp3.to_resp2(), buf),
         }
     }
 }
@@ -629,6 +703,7 @@ impl PipelineSimulator {
 mod tests {
     use super::*;
     use crate::redis::SDS;
+    use crate::simulator::{CommandFamily, LatencyDistribution};
 
     #[test]
     fn test_single_command() {
@@ -809,4 +884,56 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_slow_command_delays_rest_of_pipeline() {
+        let profile = LatencyProfile::new(LatencyDistribution::fixed_ms(1)).with_family(
+            CommandFamily::command("INCR"),
+            LatencyDistribution::fixed_ms(1).scaled(10),
+        );
+        let mut conn = SimulatedConnection::new(42).with_latency(profile);
+        conn.send_pipeline(vec![
+            Command::Get("k".to_string()),
+            Command::Incr("k".to_string()),
+            Command::Get("k".to_string()),
+        ]);
+        conn.process();
+
+        let finished: Vec<u64> = conn.history().iter().map(|r| r.time.as_millis()).collect();
+        assert_eq!(finished, vec![1, 11, 12]);
+        // One batched flush: the first GET's reply waits for the slow command
+        assert_eq!(conn.flush_count(), 1);
+        assert!(conn.history()[2].flush_after);
+        assert_eq!(conn.current_time(), VirtualTime::from_millis(12));
+    }
+
+    #[test]
+    fn test_request_over_the_limits_closes_the_connection() {
+        let limits = ProtocolLimits {
+            max_bulk_len: 8,
+            ..ProtocolLimits::default()
+        };
+        let mut conn = SimulatedConnection::new(42).with_protocol_limits(limits);
+        conn.send_pipeline(vec![
+            Command::set("k1".to_string(), SDS::from_str("short")),
+            Command::set("k2".to_string(), SDS::from_str("far too long")),
+            Command::Get("k1".to_string()),
+        ]);
+
+        let responses = conn.process();
+
+        assert_eq!(
+            responses,
+            vec![
+                RespValue::simple("OK"),
+                RespValue::err("ERR Protocol error: invalid bulk length"),
+            ]
+        );
+        assert!(conn.is_closed());
+        assert_eq!(conn.commands_executed(), 1);
+        assert_eq!(conn.flush_count(), 1);
+
+        conn.send_command(Command::Ping(None));
+        assert!(conn.process().is_empty());
+    }
 }
@@ -0,0 +1,354 @@
+//! Persistent Seed Corpus
+//!
+//! Interesting seeds (ones that failed at some point) are kept on disk so
+//! they can be replayed as a regression gate long after the bug is fixed.
+//!
+//! # Directory format
+//!
+//! One pretty-printed JSON file per entry, named `<profile>-<seed>.json`:
+//!
+//! ```text
+//! tests/dst-corpus/
+//!   chaos-30017.json
+//!   disk-heavy-61204.json
+//! ```
+//!
+//! ```json
+//! {
+//!   "seed": 30017,
+//!   "profile": "chaos",
+//!   "ops": 200,
+//!   "invariant": "linearizability",
+//!   "first_failing_commit": "3242f71",
+//!   "trace_path": "traces/chaos-30017.log"
+//! }
+//! ```
+//!
+//! `(profile, seed)` identifies an entry: appending a seed that is already
+//! present is a no-op, so the first recorded commit and invariant survive
+//! later re-discoveries. Other files in the directory are ignored.
+//!
+//! # Replay
+//!
+//! `SeedCorpus::replay` re-runs every entry under its profile and reports
+//! those that still fail (see `test_replay_corpus` and
+//! `scripts/soak-dst.sh replay-corpus`).
+
+use super::dst::{BatchResult, ChaosProfile, DSTSimulation, SimulationResult};
+use crate::buggify;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A seed worth keeping, with triage metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    pub seed: u64,
+    pub profile: ChaosProfile,
+    /// Operations to run on replay
+    pub ops: usize,
+    /// The invariant the seed violated
+    pub invariant: String,
+    /// First commit the seed was seen failing on, if known
+    pub first_failing_commit: Option<String>,
+    /// Minimized trace, relative to the corpus directory
+    pub trace_path: Option<PathBuf>,
+}
+
+impl CorpusEntry {
+    pub fn new(seed: u64, profile: ChaosProfile, ops: usize, invariant: &str) -> Self {
+        CorpusEntry {
+            seed,
+            profile,
+            ops,
+            invariant: invariant.to_string(),
+            first_failing_commit: None,
+            trace_path: None,
+        }
+    }
+
+    /// Entry for a failed run, naming the first invariant it violated
+    pub fn from_failure(profile: ChaosProfile, ops: usize, result: &SimulationResult) -> Self {
+        debug_assert!(!result.is_success(), "Precondition: run did not fail");
+        let invariant = if !result.linearizable {
+            "linearizability".to_string()
+        } else if !result.converged {
+            "convergence".to_string()
+        } else {
+            result.errors.first().cloned().unwrap_or_default()
+        };
+        CorpusEntry {
+            invariant,
+            ..Self::new(result.seed, profile, ops, "")
+        }
+    }
+
+    pub fn with_commit(mut self, commit: &str) -> Self {
+        self.first_failing_commit = Some(commit.to_string());
+        self
+    }
+
+    pub fn with_trace(mut self, path: impl Into<PathBuf>) -> Self {
+        self.trace_path = Some(path.into());
+        self
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.json", self.profile.name(), self.seed)
+    }
+}
+
+/// Seeds that failed on replay
+#[derive(Debug)]
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub still_failing: Vec<(CorpusEntry, String)>,
+}
+
+impl ReplayReport {
+    pub fn all_passed(&self) -> bool {
+        self.still_failing.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Corpus replay: {}/{} seeds pass",
+            self.replayed - self.still_failing.len(),
+            self.replayed
+        );
+        for (entry, outcome) in &self.still_failing {
+            summary.push_str(&format!(
+                "\n  {} {} ({}): {}",
+                entry.profile, entry.seed, entry.invariant, outcome
+            ));
+        }
+        summary
+    }
+}
+
+/// On-disk seed corpus (see the module docs for the format)
+pub struct SeedCorpus {
+    dir: PathBuf,
+    entries: Vec<CorpusEntry>,
+}
+
+impl SeedCorpus {
+    /// Load the corpus in `dir`, creating the directory if needed
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<_>>()?;
+        paths.retain(|p| p.extension().is_some_and(|ext| ext == "json"));
+        paths.sort();
+
+        let mut entries = Vec::with_capacity(paths.len());
+        for path in paths {
+            let json = std::fs::read_to_string(&path)?;
+            let entry: CorpusEntry = serde_json::from_str(&json).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), e),
+                )
+            })?;
+            entries.push(entry);
+        }
+
+        Ok(SeedCorpus { dir, entries })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn entries(&self) -> &[CorpusEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, profile: ChaosProfile, seed: u64) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.profile == profile && e.seed == seed)
+    }
+
+    /// Add an entry unless its `(profile, seed)` is already known.
+    /// Returns whether it was added.
+    pub fn append(&mut self, entry: CorpusEntry) -> io::Result<bool> {
+        if self.contains(entry.profile, entry.seed) {
+            return Ok(false);
+        }
+
+        // Write-then-rename so a crash never leaves a half-written entry
+        let path = self.dir.join(entry.file_name());
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(&entry).map_err(io::Error::other)?;
+        std::fs::write(&tmp, json + "\n")?;
+        std::fs::rename(&tmp, &path)?;
+
+        self.entries.push(entry);
+        Ok(true)
+    }
+
+    /// Append every failed seed of a profile batch, re-running each one
+    /// (deterministically) to find the invariant it violated. Returns the
+    /// number of new entries; batches without a profile can't be replayed
+    /// and record nothing.
+    pub fn record_batch_failures(
+        &mut self,
+        batch: &BatchResult,
+        ops: usize,
+        commit: Option<&str>,
+    ) -> io::Result<usize> {
+        let Some(profile) = batch.profile else {
+            return Ok(0);
+        };
+
+        let mut added = 0;
+        for &seed in &batch.failed_seeds {
+            let result = run_entry(&CorpusEntry::new(seed, profile, ops, ""));
+            if result.is_success() {
+                continue; // Not reproducible under this harness
+            }
+            let mut entry = CorpusEntry::from_failure(profile, ops, &result);
+            if let Some(commit) = commit {
+                entry = entry.with_commit(commit);
+            }
+            if self.append(entry)? {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Re-run every entry with `DSTSimulation` under its profile
+    pub fn replay(&self) -> ReplayReport {
+        self.replay_with(run_entry)
+    }
+
+    /// Re-run every entry with a custom harness
+    pub fn replay_with<F>(&self, mut run: F) -> ReplayReport
+    where
+        F: FnMut(&CorpusEntry) -> SimulationResult,
+    {
+        let mut still_failing = Vec::new();
+        for entry in &self.entries {
+            let result = run(entry);
+            debug_assert_eq!(
+                result.seed, entry.seed,
+                "Postcondition: replayed wrong seed"
+            );
+            if !result.is_success() {
+                still_failing.push((entry.clone(), result.summary()));
+            }
+        }
+
+        ReplayReport {
+            replayed: self.entries.len(),
+            still_failing,
+        }
+    }
+}
+
+fn run_entry(entry: &CorpusEntry) -> SimulationResult {
+    buggify::reset_stats();
+    let mut sim = DSTSimulation::with_config(entry.profile.config(entry.seed));
+    sim.run_operations(entry.ops).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_corpus_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("seed-corpus-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_append_deduplicates_and_reloads() {
+        let dir = temp_corpus_dir("append");
+        let mut corpus = SeedCorpus::open(&dir).unwrap();
+        assert!(corpus.is_empty());
+
+        let entry = CorpusEntry::new(17, ChaosProfile::NetworkHeavy, 200, "convergence")
+            .with_commit("abc1234")
+            .with_trace("traces/network-heavy-17.log");
+        assert!(corpus.append(entry.clone()).unwrap());
+        assert!(!corpus
+            .append(CorpusEntry::new(
+                17,
+                ChaosProfile::NetworkHeavy,
+                500,
+                "other"
+            ))
+            .unwrap());
+        assert!(corpus
+            .append(CorpusEntry::new(
+                17,
+                ChaosProfile::Chaos,
+                200,
+                "linearizability"
+            ))
+            .unwrap());
+        std::fs::write(dir.join("README.md"), "not an entry").unwrap();
+
+        let reloaded = SeedCorpus::open(&dir).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert!(reloaded.entries().contains(&entry));
+        assert!(dir.join("network-heavy-17.json").exists());
+        let json = std::fs::read_to_string(dir.join("network-heavy-17.json")).unwrap();
+        assert!(json.contains("\"profile\": \"network-heavy\""));
+
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        let err = SeedCorpus::open(&dir).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_reports_seeds_that_still_fail() {
+        let dir = temp_corpus_dir("replay");
+        let mut corpus = SeedCorpus::open(&dir).unwrap();
+        for seed in [1, 2, 3] {
+            corpus
+                .append(CorpusEntry::new(
+                    seed,
+                    ChaosProfile::Calm,
+                    20,
+                    "linearizability",
+                ))
+                .unwrap();
+        }
+
+        let report = corpus.replay();
+        assert_eq!(report.replayed, 3);
+        assert!(report.all_passed(), "{}", report.summary());
+
+        let report = corpus.replay_with(|entry| {
+            let mut result = SimulationResult::new(entry.seed);
+            result.linearizable = entry.seed != 2;
+            result
+        });
+        assert_eq!(report.still_failing.len(), 1);
+        assert_eq!(report.still_failing[0].0.seed, 2);
+        assert!(report.summary().contains("calm 2 (linearizability)"));
+
+        let failed = CorpusEntry::from_failure(ChaosProfile::Calm, 20, &{
+            let mut result = SimulationResult::new(9);
+            result.converged = false;
+            result
+        });
+        assert_eq!(failed.invariant, "convergence");
+        assert_eq!(failed.seed, 9);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
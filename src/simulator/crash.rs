@@ -5,7 +5,12 @@
 //! - Partial state loss on crash
 //! - Recovery timing control
 //! - In-flight operation handling
+//! - Persistent vs ephemeral storage: each node has a `SimulatedDisk`, and a
+//!   crash keeps its synced files (and checkpoints) or wipes them, per
+//!   `CrashConfig::storage_mode`. Memory never survives, so a node restarts
+//!   through whatever recovery path reads its disk.
 
+use super::storage::{SimulatedDisk, StorageMode};
 use super::{HostId, VirtualTime};
 use crate::buggify::{self, faults};
 use crate::io::Rng;
@@ -89,6 +94,8 @@ pub struct CrashConfig {
     pub partial_state_loss_probability: f64,
     /// Whether to enable automatic BUGGIFY-triggered crashes
     pub enable_buggify_crashes: bool,
+    /// What a crash leaves of the node's disk and checkpoints
+    pub storage_mode: StorageMode,
 }
 
 impl Default for CrashConfig {
@@ -99,6 +106,7 @@ impl Default for CrashConfig {
             max_recovery_time_ms: 5000,
             partial_state_loss_probability: 0.1, // 10% chance of losing some pending writes
             enable_buggify_crashes: true,
+            storage_mode: StorageMode::Persistent,
         }
     }
 }
@@ -109,6 +117,8 @@ pub struct CrashSimulator {
     node_states: HashMap<HostId, NodeState>,
     /// Checkpoints for each node (node_id -> list of snapshots)
     checkpoints: HashMap<HostId, Vec<NodeSnapshot>>,
+    /// Each node's disk
+    disks: HashMap<HostId, SimulatedDisk>,
     /// Configuration
     config: CrashConfig,
     /// Statistics
@@ -123,6 +133,10 @@ pub struct CrashStats {
     pub crashes_by_reason: HashMap<String, u64>,
     pub total_state_loss_events: u64,
     pub average_recovery_time_ms: f64,
+    /// Unsynced disk bytes lost to crashes
+    pub unsynced_bytes_lost: u64,
+    /// Crashes that took the node's disk with them (ephemeral storage)
+    pub disks_wiped: u64,
 }
 
 impl CrashSimulator {
@@ -136,6 +150,7 @@ impl CrashSimulator {
         CrashSimulator {
             node_states: HashMap::new(),
             checkpoints: HashMap::new(),
+            disks: HashMap::new(),
             config,
             stats: CrashStats::default(),
         }
@@ -145,6 +160,20 @@ impl CrashSimulator {
     pub fn register_node(&mut self, node_id: HostId) {
         self.node_states.insert(node_id, NodeState::Running);
         self.checkpoints.insert(node_id, Vec::new());
+        self.disks.insert(node_id, SimulatedDisk::new());
+    }
+
+    /// The node's disk; it survives crashes as `storage_mode` allows
+    pub fn disk(&self, node_id: HostId) -> Option<&SimulatedDisk> {
+        self.disks.get(&node_id)
+    }
+
+    /// The node's disk, for writes while it runs
+    pub fn disk_mut(&mut self, node_id: HostId) -> Option<&mut SimulatedDisk> {
+        if !self.is_running(node_id) {
+            return None;
+        }
+        self.disks.get_mut(&node_id)
     }
 
     /// Get current state of a node
@@ -210,6 +239,19 @@ impl CrashSimulator {
 
         self.stats.total_crashes += 1;
         *self.stats.crashes_by_reason.entry(reason_str).or_insert(0) += 1;
+
+        let mode = self.config.storage_mode;
+        if let Some(disk) = self.disks.get_mut(&node_id) {
+            let lost_before = disk.lost_bytes();
+            disk.crash(mode);
+            self.stats.unsynced_bytes_lost += disk.lost_bytes() - lost_before;
+        }
+        if mode == StorageMode::Ephemeral {
+            if let Some(snapshots) = self.checkpoints.get_mut(&node_id) {
+                snapshots.clear();
+            }
+            self.stats.disks_wiped += 1;
+        }
     }
 
     /// Start recovery for a crashed node
@@ -239,7 +281,7 @@ impl CrashSimulator {
             },
         );
 
-        // Return latest checkpoint if available
+        // Return latest checkpoint if available (none after an ephemeral crash)
         self.checkpoints
             .get(&node_id)
             .and_then(|snapshots| snapshots.last())
@@ -251,23 +293,19 @@ impl CrashSimulator {
             Some(NodeState::Recovering {
                 expected_completion,
                 recovery_start,
-            }) => {
-                if time >= *expected_completion {
-                    // Update stats
-                    let recovery_time = time.0 - recovery_start.0;
-                    let total_recoveries = self.stats.total_recoveries as f64;
-                    self.stats.average_recovery_time_ms = (self.stats.average_recovery_time_ms
-                        * total_recoveries
-                        + recovery_time as f64)
-                        / (total_recoveries + 1.0);
-                    self.stats.total_recoveries += 1;
-
-                    // Mark as running
-                    self.node_states.insert(node_id, NodeState::Running);
-                    true
-                } else {
-                    false
-                }
+            }) if time >= *expected_completion => {
+                // Update stats
+                let recovery_time = time.0 - recovery_start.0;
+                let total_recoveries = self.stats.total_recoveries as f64;
+                self.stats.average_recovery_time_ms = (self.stats.average_recovery_time_ms
+                    * total_recoveries
+                    + recovery_time as f64)
+                    / (total_recoveries + 1.0);
+                self.stats.total_recoveries += 1;
+
+                // Mark as running
+                self.node_states.insert(node_id, NodeState::Running);
+                true
             }
             _ => false,
         }
@@ -459,6 +497,44 @@ mod tests {
         assert_eq!(checkpoint.pending_operations.len(), 1);
     }
 
+    #[test]
+    fn test_storage_mode_decides_what_survives_a_crash() {
+        let mut rng = SimulatedRng::new(42);
+        for mode in [StorageMode::Persistent, StorageMode::Ephemeral] {
+            let mut sim = CrashSimulator::with_config(CrashConfig {
+                storage_mode: mode,
+                ..Default::default()
+            });
+            let node = HostId(1);
+            sim.register_node(node);
+            sim.checkpoint(node, VirtualTime(500), vec![1], Vec::new(), 1);
+            let disk = sim.disk_mut(node).unwrap();
+            disk.append("wal", b"acked");
+            disk.sync("wal");
+            disk.append("wal", b"+unsynced");
+
+            sim.crash_node(node, VirtualTime(1000), CrashReason::PowerFailure);
+            assert!(sim.disk_mut(node).is_none(), "a crashed node cannot write");
+            let checkpoint = sim
+                .start_recovery(&mut rng, node, VirtualTime(1000))
+                .cloned();
+            let wal = sim.disk(node).unwrap().read("wal");
+            match mode {
+                StorageMode::Persistent => {
+                    assert_eq!(wal, Some(&b"acked"[..]));
+                    assert_eq!(checkpoint.map(|c| c.state_data), Some(vec![1]));
+                    assert_eq!(sim.stats().disks_wiped, 0);
+                }
+                StorageMode::Ephemeral => {
+                    assert_eq!(wal, None);
+                    assert!(checkpoint.is_none());
+                    assert_eq!(sim.stats().disks_wiped, 1);
+                }
+            }
+            assert_eq!(sim.stats().unsynced_bytes_lost, 9);
+        }
+    }
+
     #[test]
     fn test_stats_tracking() {
         let mut sim = CrashSimulator::new();
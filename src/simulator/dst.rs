@@ -6,6 +6,7 @@
 //! - BUGGIFY fault injection
 //! - CrashSimulator for crash/recovery
 //! - Batch testing capabilities
+//! - Named chaos profiles for CI matrices
 //!
 //! # Example
 //!
@@ -20,10 +21,11 @@
 
 use super::crash::{CrashConfig, CrashReason, CrashSimulator, NodeSnapshot};
 use super::{HostId, VirtualTime};
-use crate::buggify::{self, BuggifyStats, FaultConfig};
+use crate::buggify::{self, faults, BuggifyStats, FaultConfig};
 use crate::io::simulation::{ClockOffset, NodeId, SimulatedRng, SimulationContext};
 use crate::io::Rng;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Configuration for a DST simulation run
@@ -130,6 +132,129 @@ impl DSTConfig {
     }
 }
 
+/// Named combination of fault, crash and clock settings
+///
+/// Each profile names one CI matrix cell, so pass rates can be tracked per
+/// profile over time (see `BatchResult::write_summary`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChaosProfile {
+    /// Minimal faults, no crashes, synchronized clocks
+    Calm,
+    /// The `DSTConfig` default
+    Moderate,
+    /// Aggressive faults everywhere
+    Chaos,
+    /// Moderate baseline with heavy network and gossip faults
+    NetworkHeavy,
+    /// Moderate baseline with heavy disk faults and state loss on recovery
+    DiskHeavy,
+    /// Calm faults, no crashes, large clock offsets, drift and jumps
+    ClockSkew,
+}
+
+impl ChaosProfile {
+    pub const ALL: [ChaosProfile; 6] = [
+        ChaosProfile::Calm,
+        ChaosProfile::Moderate,
+        ChaosProfile::Chaos,
+        ChaosProfile::NetworkHeavy,
+        ChaosProfile::DiskHeavy,
+        ChaosProfile::ClockSkew,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChaosProfile::Calm => "calm",
+            ChaosProfile::Moderate => "moderate",
+            ChaosProfile::Chaos => "chaos",
+            ChaosProfile::NetworkHeavy => "network-heavy",
+            ChaosProfile::DiskHeavy => "disk-heavy",
+            ChaosProfile::ClockSkew => "clock-skew",
+        }
+    }
+
+    /// Full simulation configuration for this profile
+    pub fn config(&self, seed: u64) -> DSTConfig {
+        match self {
+            ChaosProfile::Calm => DSTConfig::calm(seed),
+            ChaosProfile::Moderate => DSTConfig::new(seed),
+            ChaosProfile::Chaos => DSTConfig::chaos(seed),
+            ChaosProfile::NetworkHeavy => {
+                let mut fault_config = FaultConfig::moderate();
+                fault_config
+                    .set(faults::network::PACKET_DROP, 0.10)
+                    .set(faults::network::REORDER, 0.15)
+                    .set(faults::network::CONNECTION_RESET, 0.05)
+                    .set(faults::network::DELAY, 0.25)
+                    .set(faults::network::DUPLICATE, 0.05)
+                    .set(faults::replication::GOSSIP_DROP, 0.15)
+                    .set(faults::replication::GOSSIP_DELAY, 0.25);
+                DSTConfig::new(seed).with_faults(fault_config)
+            }
+            ChaosProfile::DiskHeavy => {
+                let mut fault_config = FaultConfig::moderate();
+                fault_config
+                    .set(faults::disk::WRITE_FAIL, 0.02)
+                    .set(faults::disk::PARTIAL_WRITE, 0.02)
+                    .set(faults::disk::CORRUPTION, 0.005)
+                    .set(faults::disk::SLOW, 0.20)
+                    .set(faults::disk::FSYNC_FAIL, 0.01)
+                    .set(faults::disk::STALE_READ, 0.02)
+                    .set(faults::disk::DISK_FULL, 0.005);
+                DSTConfig::new(seed)
+                    .with_faults(fault_config)
+                    .with_crash_config(CrashConfig {
+                        partial_state_loss_probability: 0.5,
+                        ..Default::default()
+                    })
+            }
+            ChaosProfile::ClockSkew => {
+                let mut fault_config = FaultConfig::calm();
+                fault_config
+                    .set(faults::timer::DRIFT_FAST, 0.05)
+                    .set(faults::timer::DRIFT_SLOW, 0.05)
+                    .set(faults::timer::JUMP_FORWARD, 0.02)
+                    .set(faults::timer::JUMP_BACKWARD, 0.01);
+                DSTConfig {
+                    crash_config: CrashConfig {
+                        enable_buggify_crashes: false,
+                        ..Default::default()
+                    },
+                    enable_clock_skew: true,
+                    max_clock_skew_ms: 5_000,
+                    max_clock_drift_ppm: 20_000,
+                    ..DSTConfig::new(seed).with_faults(fault_config)
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ChaosProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for ChaosProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ChaosProfile::ALL
+            .into_iter()
+            .find(|profile| profile.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = ChaosProfile::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown chaos profile '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
 /// Operation recorded during simulation for linearizability checking
 #[derive(Debug, Clone)]
 pub struct RecordedOperation {
@@ -451,6 +576,7 @@ pub struct BatchRunner {
     base_seed: u64,
     count: usize,
     config_template: DSTConfig,
+    profile: Option<ChaosProfile>,
 }
 
 impl BatchRunner {
@@ -459,11 +585,20 @@ impl BatchRunner {
             base_seed,
             count,
             config_template: DSTConfig::default(),
+            profile: None,
         }
     }
 
     pub fn with_config(mut self, config: DSTConfig) -> Self {
         self.config_template = config;
+        self.profile = None;
+        self
+    }
+
+    /// Use a named profile's configuration and tag the result with it
+    pub fn with_profile(mut self, profile: ChaosProfile) -> Self {
+        self.config_template = profile.config(self.base_seed);
+        self.profile = Some(profile);
         self
     }
 
@@ -488,7 +623,9 @@ impl BatchRunner {
             results.push(sim.result.clone());
         }
 
-        BatchResult::from_results(self.base_seed, results)
+        let mut batch = BatchResult::from_results(self.base_seed, results);
+        batch.profile = self.profile;
+        batch
     }
 
     /// Run with default behavior (just stepping)
@@ -500,6 +637,8 @@ impl BatchRunner {
 /// Result of a batch run
 #[derive(Debug, Clone)]
 pub struct BatchResult {
+    /// Profile the batch ran under, if it was selected by name
+    pub profile: Option<ChaosProfile>,
     pub base_seed: u64,
     pub total_runs: usize,
     pub successful_runs: usize,
@@ -526,6 +665,7 @@ impl BatchResult {
         let total_recoveries: u64 = results.iter().map(|r| r.recoveries).sum();
 
         BatchResult {
+            profile: None,
             base_seed,
             total_runs,
             successful_runs,
@@ -541,9 +681,49 @@ impl BatchResult {
         self.failed_runs == 0
     }
 
+    /// Fraction of runs that passed (1.0 for an empty batch)
+    pub fn pass_rate(&self) -> f64 {
+        if self.total_runs == 0 {
+            1.0
+        } else {
+            self.successful_runs as f64 / self.total_runs as f64
+        }
+    }
+
+    /// Machine-readable summary, one object per batch
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "profile": self.profile.map_or("custom", |p| p.name()),
+            "base_seed": self.base_seed,
+            "total_runs": self.total_runs,
+            "successful_runs": self.successful_runs,
+            "failed_runs": self.failed_runs,
+            "pass_rate": self.pass_rate(),
+            "failed_seeds": self.failed_seeds,
+            "total_operations": self.total_operations,
+            "total_crashes": self.total_crashes,
+            "total_recoveries": self.total_recoveries,
+        })
+    }
+
+    /// Write the summary to `<dir>/dst-summary-<profile>.json` for CI to
+    /// upload as an artifact
+    pub fn write_summary(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        let name = self.profile.map_or("custom", |p| p.name());
+        let path = dir.join(format!("dst-summary-{}.json", name));
+        std::fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(&self.to_json()).map_err(std::io::Error::other)?;
+        std::fs::write(&path, json + "\n")?;
+        Ok(path)
+    }
+
     pub fn summary(&self) -> String {
+        let profile = self
+            .profile
+            .map_or(String::new(), |p| format!(" [{}]", p.name()));
         format!(
-            "Batch {} runs: {}/{} passed, {} total ops, {} crashes, {} recoveries",
+            "Batch{} {} runs: {}/{} passed, {} total ops, {} crashes, {} recoveries",
+            profile,
             self.total_runs,
             self.successful_runs,
             self.total_runs,
@@ -657,6 +837,52 @@ mod tests {
         println!("{}", batch.summary());
     }
 
+    #[test]
+    fn test_chaos_profiles() {
+        for profile in ChaosProfile::ALL {
+            assert_eq!(profile.name().parse::<ChaosProfile>(), Ok(profile));
+            assert_eq!(profile.config(7).seed, 7);
+        }
+        assert_eq!("Network-Heavy".parse(), Ok(ChaosProfile::NetworkHeavy));
+        assert!("stormy".parse::<ChaosProfile>().is_err());
+
+        let moderate = ChaosProfile::Moderate.config(0).fault_config;
+        let network = ChaosProfile::NetworkHeavy.config(0).fault_config;
+        let disk = ChaosProfile::DiskHeavy.config(0).fault_config;
+        assert!(
+            network.get(faults::network::PACKET_DROP) > moderate.get(faults::network::PACKET_DROP)
+        );
+        assert!(disk.get(faults::disk::WRITE_FAIL) > moderate.get(faults::disk::WRITE_FAIL));
+        assert_eq!(
+            disk.get(faults::network::PACKET_DROP),
+            moderate.get(faults::network::PACKET_DROP)
+        );
+
+        let skew = ChaosProfile::ClockSkew.config(0);
+        assert!(skew.enable_clock_skew);
+        assert!(skew.max_clock_skew_ms > DSTConfig::chaos(0).max_clock_skew_ms);
+        assert!(!skew.crash_config.enable_buggify_crashes);
+    }
+
+    #[test]
+    fn test_batch_runner_profile_summary() {
+        let batch = BatchRunner::new(2000, 5)
+            .with_profile(ChaosProfile::DiskHeavy)
+            .run_default(20);
+        assert_eq!(batch.profile, Some(ChaosProfile::DiskHeavy));
+        assert!(batch.summary().starts_with("Batch [disk-heavy] 5 runs"));
+
+        let dir = std::env::temp_dir().join(format!("dst-summary-test-{}", std::process::id()));
+        let path = batch.write_summary(&dir).unwrap();
+        assert!(path.ends_with("dst-summary-disk-heavy.json"));
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["profile"], "disk-heavy");
+        assert_eq!(json["total_runs"], 5);
+        assert_eq!(json["pass_rate"], batch.pass_rate());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_random_running_node() {
         let mut sim = DSTSimulation::with_config(DSTConfig {
@@ -1,11 +1,27 @@
+//! The discrete-event loop: hosts, timers, network messages, virtual time
+//!
+//! `run` pops events in time order and hands each to the caller's handler.
+//! When nothing is due at the current instant the loop is idle, and the
+//! configured `IdlePolicy` decides what happens next: jump the clock to the
+//! next event, step it by a fixed quantum (delivering `EventType::Idle` to
+//! every host so polling code sees time pass), or stop.
+//!
+//! Every run returns a `RunOutcome` saying why it stopped and, per host,
+//! what it is still waiting on: pending timers, messages in flight, and the
+//! waits a host declared with `set_waiting` (a server's parked BLPOP, say).
+//! A run that hits `max_time` or drains with hosts still waiting is usually
+//! a stuck scenario, and the outcome names the culprits.
+
 use super::network::Network;
 use super::*;
 use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
 
 pub struct SimulationConfig {
     pub seed: u64,
     pub max_time: VirtualTime,
     pub simulation_start_epoch: i64,
+    pub idle_policy: IdlePolicy,
 }
 
 impl Default for SimulationConfig {
@@ -14,10 +30,100 @@ impl Default for SimulationConfig {
             seed: 42,
             max_time: VirtualTime::from_millis(60_000),
             simulation_start_epoch: 0,
+            idle_policy: IdlePolicy::default(),
         }
     }
 }
 
+/// What the event loop does when no event is due at the current time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdlePolicy {
+    /// Jump the clock straight to the next event; stop once none are left
+    #[default]
+    AdvanceToNextEvent,
+    /// Step the clock by at most this much, delivering `EventType::Idle`
+    /// to every host after each step that reached no event; keeps ticking
+    /// with an empty queue until the time limit
+    AdvanceBy(Duration),
+    /// Stop instead of advancing the clock
+    Terminate,
+}
+
+/// Why a run stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunEnd {
+    /// No events left to process
+    Drained,
+    /// The next event, or the next idle step, lies beyond the time limit
+    MaxTime,
+    /// Nothing was due and the policy is `IdlePolicy::Terminate`
+    Idle,
+}
+
+/// What one host is still waiting on when a run stops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostWait {
+    pub host_id: HostId,
+    pub name: String,
+    /// Earliest pending timer and the number of pending timers
+    pub next_timer: Option<VirtualTime>,
+    pub timers: usize,
+    /// Messages sent to the host and not yet delivered
+    pub messages_in_flight: usize,
+    /// Waits the host declared with `Simulation::set_waiting`
+    pub waiting_on: Vec<String>,
+}
+
+/// How a `run` or `run_until` call ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub end: RunEnd,
+    pub time: VirtualTime,
+    /// Events handed to the handler, idle ticks included
+    pub events: u64,
+    /// Hosts with anything pending or declared, by host id
+    pub waits: Vec<HostWait>,
+}
+
+impl RunOutcome {
+    /// Hosts still blocked on something they declared: with `Drained`, no
+    /// event will ever release them
+    pub fn is_stuck(&self) -> bool {
+        self.end != RunEnd::Idle && self.waits.iter().any(|w| !w.waiting_on.is_empty())
+    }
+}
+
+impl fmt::Display for RunOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "run ended ({:?}) at {}ms after {} events",
+            self.end,
+            self.time.as_millis(),
+            self.events
+        )?;
+        for wait in &self.waits {
+            write!(f, "  {} ({:?}):", wait.name, wait.host_id)?;
+            if let Some(next) = wait.next_timer {
+                write!(
+                    f,
+                    " {} timers, next at {}ms;",
+                    wait.timers,
+                    next.as_millis()
+                )?;
+            }
+            if wait.messages_in_flight > 0 {
+                write!(f, " {} messages in flight;", wait.messages_in_flight)?;
+            }
+            for reason in &wait.waiting_on {
+                write!(f, " waiting on {};", reason)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 #[allow(dead_code)]
 pub struct Simulation {
     config: SimulationConfig,
@@ -29,16 +135,26 @@ pub struct Simulation {
     next_timer_id: u64,
     next_host_id: usize,
     message_queue: Vec<Message>,
+    /// Declared waits per host (see `set_waiting`)
+    waiting: HashMap<HostId, Vec<String>>,
 }
 
 impl Simulation {
     pub fn simulation_start_epoch(&self) -> i64 {
         self.config.simulation_start_epoch
     }
+
+    /// The seed this run replays from
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
 }
 
 impl Simulation {
     pub fn new(config: SimulationConfig) -> Self {
+        if let IdlePolicy::AdvanceBy(quantum) = config.idle_policy {
+            assert!(quantum > Duration::ZERO, "idle quantum must be positive");
+        }
         Simulation {
             rng: DeterministicRng::new(config.seed),
             current_time: VirtualTime::ZERO,
@@ -48,6 +164,7 @@ impl Simulation {
             next_timer_id: 0,
             next_host_id: 0,
             message_queue: Vec::new(),
+            waiting: HashMap::new(),
             config,
         }
     }
@@ -110,24 +227,261 @@ impl Simulation {
         &mut self.rng
     }
 
+    pub fn idle_policy(&self) -> IdlePolicy {
+        self.config.idle_policy
+    }
+
+    pub fn set_idle_policy(&mut self, policy: IdlePolicy) {
+        if let IdlePolicy::AdvanceBy(quantum) = policy {
+            assert!(quantum > Duration::ZERO, "idle quantum must be positive");
+        }
+        self.config.idle_policy = policy;
+    }
+
+    /// Declare what `host_id` is blocked on, replacing its previous
+    /// declaration; reported in `RunOutcome::waits`. An empty list clears it.
+    pub fn set_waiting(&mut self, host_id: HostId, reasons: Vec<String>) {
+        if reasons.is_empty() {
+            self.waiting.remove(&host_id);
+        } else {
+            self.waiting.insert(host_id, reasons);
+        }
+    }
+
+    /// What every host is waiting on right now, by host id; hosts with
+    /// nothing pending and nothing declared are left out
+    pub fn waits(&self) -> Vec<HostWait> {
+        let mut by_host: HashMap<HostId, HostWait> = HashMap::new();
+        for event in self.events.iter() {
+            let wait = self.host_wait(&mut by_host, event.host_id);
+            match event.event_type {
+                EventType::Timer(_) => {
+                    wait.timers += 1;
+                    wait.next_timer = Some(match wait.next_timer {
+                        Some(next) => next.min(event.time),
+                        None => event.time,
+                    });
+                }
+                EventType::NetworkMessage(_) => wait.messages_in_flight += 1,
+                EventType::HostStart | EventType::Idle => {}
+            }
+        }
+        for (host_id, reasons) in &self.waiting {
+            self.host_wait(&mut by_host, *host_id).waiting_on = reasons.clone();
+        }
+
+        let mut waits: Vec<HostWait> = by_host
+            .into_values()
+            .filter(|w| w.timers + w.messages_in_flight + w.waiting_on.len() > 0)
+            .collect();
+        waits.sort_by_key(|w| w.host_id.0);
+        waits
+    }
+
+    fn host_wait<'a>(
+        &self,
+        by_host: &'a mut HashMap<HostId, HostWait>,
+        host_id: HostId,
+    ) -> &'a mut HostWait {
+        by_host.entry(host_id).or_insert_with(|| HostWait {
+            host_id,
+            name: self
+                .hosts
+                .get(&host_id)
+                .map(|h| h.name.clone())
+                .unwrap_or_default(),
+            next_timer: None,
+            timers: 0,
+            messages_in_flight: 0,
+            waiting_on: Vec::new(),
+        })
+    }
+
     pub fn run_until(
         &mut self,
         max_time: VirtualTime,
         mut event_handler: impl FnMut(&mut Self, &Event),
-    ) {
-        while let Some(event) = self.events.pop() {
-            if event.time > max_time {
-                self.events.push(event);
-                break;
+    ) -> RunOutcome {
+        let start = self.current_time;
+        let mut events = 0u64;
+        let end = loop {
+            let next_time = self.events.peek().map(|e| e.time);
+            if next_time.is_some_and(|t| t <= self.current_time) {
+                let event = self.events.pop().expect("peeked above");
+                events += 1;
+                event_handler(self, &event);
+                continue;
             }
 
-            self.current_time = event.time;
-            event_handler(self, &event);
+            // Idle: nothing is due at the current instant
+            match (self.config.idle_policy, next_time) {
+                (IdlePolicy::Terminate, _) => break RunEnd::Idle,
+                (IdlePolicy::AdvanceToNextEvent, None) => break RunEnd::Drained,
+                (IdlePolicy::AdvanceToNextEvent, Some(next)) => {
+                    if next > max_time {
+                        break RunEnd::MaxTime;
+                    }
+                    self.current_time = next;
+                }
+                (IdlePolicy::AdvanceBy(quantum), next) => {
+                    let step = self.current_time + quantum;
+                    if next.is_some_and(|t| t <= step) {
+                        let next = next.expect("checked above");
+                        if next > max_time {
+                            break RunEnd::MaxTime;
+                        }
+                        self.current_time = next;
+                        continue;
+                    }
+                    if step > max_time {
+                        break RunEnd::MaxTime;
+                    }
+                    self.current_time = step;
+                    let mut hosts: Vec<HostId> = self.hosts.keys().copied().collect();
+                    hosts.sort_by_key(|h| h.0);
+                    for host_id in hosts {
+                        events += 1;
+                        let tick = Event {
+                            time: step,
+                            host_id,
+                            event_type: EventType::Idle,
+                        };
+                        event_handler(self, &tick);
+                    }
+                }
+            }
+        };
+
+        debug_assert!(
+            start <= self.current_time && self.current_time <= max_time.max(start),
+            "Postcondition: the clock only moves forward, and never past max_time"
+        );
+        RunOutcome {
+            end,
+            time: self.current_time,
+            events,
+            waits: self.waits(),
         }
     }
 
-    pub fn run(&mut self, event_handler: impl FnMut(&mut Self, &Event)) {
+    pub fn run(&mut self, event_handler: impl FnMut(&mut Self, &Event)) -> RunOutcome {
         let max_time = self.config.max_time;
-        self.run_until(max_time, event_handler);
+        self.run_until(max_time, event_handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sim(idle_policy: IdlePolicy) -> Simulation {
+        Simulation::new(SimulationConfig {
+            max_time: VirtualTime::from_millis(1_000),
+            idle_policy,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_advance_to_next_event_jumps_and_drains() {
+        let mut sim = sim(IdlePolicy::AdvanceToNextEvent);
+        let host = sim.add_host("a".to_string());
+        sim.schedule_timer(host, Duration::from_millis(700));
+        let mut seen = Vec::new();
+        let outcome = sim.run(|sim, event| seen.push((sim.current_time(), event.time)));
+
+        assert_eq!(outcome.end, RunEnd::Drained);
+        assert_eq!(outcome.events, 2);
+        assert_eq!(seen[1], (VirtualTime(700), VirtualTime(700)));
+        assert_eq!(outcome.time, VirtualTime(700));
+        assert!(outcome.waits.is_empty());
+    }
+
+    #[test]
+    fn test_advance_by_quantum_ticks_every_host() {
+        let mut sim = sim(IdlePolicy::AdvanceBy(Duration::from_millis(300)));
+        let a = sim.add_host("a".to_string());
+        sim.add_host("b".to_string());
+        sim.schedule_timer(a, Duration::from_millis(400));
+        let mut log = Vec::new();
+        let outcome = sim.run(|_, event| {
+            if !matches!(event.event_type, EventType::HostStart) {
+                log.push((event.time.as_millis(), event.host_id.0));
+            }
+        });
+
+        // Ticks at 300, the timer at 400 (not a tick at 600), ticks at 700
+        // and 1000; the next step would pass max_time
+        assert_eq!(
+            log,
+            vec![
+                (300, 0),
+                (300, 1),
+                (400, 0),
+                (700, 0),
+                (700, 1),
+                (1_000, 0),
+                (1_000, 1)
+            ]
+        );
+        assert_eq!(outcome.end, RunEnd::MaxTime);
+        assert_eq!(outcome.time, VirtualTime(1_000));
+    }
+
+    #[test]
+    fn test_terminate_stops_when_idle() {
+        let mut sim = sim(IdlePolicy::Terminate);
+        let host = sim.add_host("a".to_string());
+        sim.schedule_timer(host, Duration::from_millis(50));
+        let outcome = sim.run(|_, _| {});
+        assert_eq!(outcome.end, RunEnd::Idle);
+        assert_eq!(outcome.time, VirtualTime::ZERO);
+        assert_eq!(outcome.events, 1);
+
+        // Switching policy resumes where the run stopped
+        sim.set_idle_policy(IdlePolicy::AdvanceToNextEvent);
+        assert_eq!(sim.run(|_, _| {}).end, RunEnd::Drained);
+        assert_eq!(sim.current_time(), VirtualTime(50));
+    }
+
+    #[test]
+    fn test_outcome_reports_what_hosts_wait_on() {
+        let mut sim = sim(IdlePolicy::AdvanceToNextEvent);
+        let server = sim.add_host("server".to_string());
+        let client = sim.add_host("client".to_string());
+        sim.schedule_timer(server, Duration::from_millis(5_000));
+        sim.schedule_timer(server, Duration::from_millis(2_000));
+        sim.set_waiting(client, vec!["BLPOP q".to_string()]);
+
+        let outcome = sim.run(|_, _| {});
+        assert_eq!(outcome.end, RunEnd::MaxTime);
+        assert!(outcome.is_stuck());
+        assert_eq!(
+            outcome.waits,
+            vec![
+                HostWait {
+                    host_id: server,
+                    name: "server".to_string(),
+                    next_timer: Some(VirtualTime(2_000)),
+                    timers: 2,
+                    messages_in_flight: 0,
+                    waiting_on: Vec::new(),
+                },
+                HostWait {
+                    host_id: client,
+                    name: "client".to_string(),
+                    next_timer: None,
+                    timers: 0,
+                    messages_in_flight: 0,
+                    waiting_on: vec!["BLPOP q".to_string()],
+                },
+            ]
+        );
+        let report = outcome.to_string();
+        assert!(report.contains("server (HostId(0)): 2 timers, next at 2000ms;"));
+        assert!(report.contains("client (HostId(1)): waiting on BLPOP q;"));
+
+        sim.set_waiting(client, Vec::new());
+        assert_eq!(sim.waits().len(), 1);
     }
 }
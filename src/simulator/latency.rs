@@ -0,0 +1,267 @@
+//! Artificial command execution latency for simulations
+//!
+//! Real servers spend very different amounts of time on different commands:
+//! a `ZRANGEBYSCORE` over a large set costs far more than a `GET`. Building
+//! that data just to make a command slow is expensive and makes runs hard
+//! to reproduce, so scenarios declare the cost instead:
+//!
+//! ```ignore
+//! let profile = LatencyProfile::new(LatencyDistribution::fixed_ms(1))
+//!     .with_family(
+//!         CommandFamily::command("ZRANGEBYSCORE"),
+//!         LatencyDistribution::fixed_ms(1).scaled(10),
+//!     );
+//! let server = RedisServer::new(host).with_latency(profile);
+//! ```
+//!
+//! Rules are checked in declaration order and the first matching family
+//! wins; commands no rule matches use the default distribution. Samples are
+//! drawn from the simulation's `DeterministicRng`, so the same seed yields
+//! the same service times.
+//!
+//! The default profile is zero latency everywhere and never touches the
+//! RNG, so existing seeded simulations are unaffected.
+
+use super::{DeterministicRng, Duration};
+use crate::redis::Command;
+
+/// A set of commands sharing one latency distribution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandFamily {
+    /// One command by name, e.g. `ZRANGEBYSCORE`
+    Command(String),
+    /// Every command whose name starts with the prefix: `Z` for sorted
+    /// sets, `ZRANGE` for the `ZRANGE*` family
+    Prefix(String),
+    /// BLPOP, BRPOP, BLMOVE (see `Command::blocking_keys`)
+    Blocking,
+    /// Commands that never modify the keyspace (see `Command::is_read_only`)
+    ReadOnly,
+    /// Everything else
+    Write,
+}
+
+impl CommandFamily {
+    pub fn command(name: &str) -> Self {
+        CommandFamily::Command(name.to_ascii_uppercase())
+    }
+
+    pub fn prefix(prefix: &str) -> Self {
+        CommandFamily::Prefix(prefix.to_ascii_uppercase())
+    }
+
+    pub fn matches(&self, cmd: &Command) -> bool {
+        match self {
+            CommandFamily::Command(name) => cmd.name() == name,
+            CommandFamily::Prefix(prefix) => cmd.name().starts_with(prefix.as_str()),
+            CommandFamily::Blocking => cmd.blocking_keys().is_some(),
+            CommandFamily::ReadOnly => cmd.is_read_only(),
+            CommandFamily::Write => !cmd.is_read_only(),
+        }
+    }
+}
+
+/// Distribution of one command's execution time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDistribution {
+    /// Always the same
+    Fixed(Duration),
+    /// Uniform in `[min, max]`
+    Uniform { min: Duration, max: Duration },
+    /// Usually `base`, `slow` with probability `slow_probability` (tail
+    /// latency, e.g. a rehash or a page fault)
+    Bimodal {
+        base: Duration,
+        slow: Duration,
+        slow_probability: f64,
+    },
+}
+
+impl LatencyDistribution {
+    pub const ZERO: LatencyDistribution = LatencyDistribution::Fixed(Duration::ZERO);
+
+    pub fn fixed_ms(ms: u64) -> Self {
+        LatencyDistribution::Fixed(Duration::from_millis(ms))
+    }
+
+    pub fn uniform_ms(min: u64, max: u64) -> Self {
+        debug_assert!(min <= max, "Precondition: min {} > max {}", min, max);
+        LatencyDistribution::Uniform {
+            min: Duration::from_millis(min),
+            max: Duration::from_millis(max),
+        }
+    }
+
+    /// The same shape, `factor` times slower ("ZRANGEBYSCORE is 10x slower")
+    pub fn scaled(self, factor: u64) -> Self {
+        let scale = |d: Duration| Duration::from_millis(d.as_millis() * factor);
+        match self {
+            LatencyDistribution::Fixed(d) => LatencyDistribution::Fixed(scale(d)),
+            LatencyDistribution::Uniform { min, max } => LatencyDistribution::Uniform {
+                min: scale(min),
+                max: scale(max),
+            },
+            LatencyDistribution::Bimodal {
+                base,
+                slow,
+                slow_probability,
+            } => LatencyDistribution::Bimodal {
+                base: scale(base),
+                slow: scale(slow),
+                slow_probability,
+            },
+        }
+    }
+
+    /// Draw one execution time. Fixed distributions consume no randomness.
+    pub fn sample(&self, rng: &mut DeterministicRng) -> Duration {
+        let sampled = match *self {
+            LatencyDistribution::Fixed(d) => d,
+            LatencyDistribution::Uniform { min, max } => Duration::from_millis(
+                rng.gen_range(min.as_millis(), max.as_millis().saturating_add(1)),
+            ),
+            LatencyDistribution::Bimodal {
+                base,
+                slow,
+                slow_probability,
+            } => {
+                if rng.gen_bool(slow_probability) {
+                    slow
+                } else {
+                    base
+                }
+            }
+        };
+
+        debug_assert!(
+            sampled <= self.max(),
+            "Postcondition: sample {:?} above distribution max {:?}",
+            sampled,
+            self.max()
+        );
+        sampled
+    }
+
+    /// Largest value `sample` can return
+    pub fn max(&self) -> Duration {
+        match *self {
+            LatencyDistribution::Fixed(d) => d,
+            LatencyDistribution::Uniform { max, .. } => max,
+            LatencyDistribution::Bimodal { base, slow, .. } => base.max(slow),
+        }
+    }
+}
+
+/// Per-family execution latency for a simulated server or connection
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyProfile {
+    default: LatencyDistribution,
+    rules: Vec<(CommandFamily, LatencyDistribution)>,
+}
+
+impl Default for LatencyProfile {
+    fn default() -> Self {
+        LatencyProfile::new(LatencyDistribution::ZERO)
+    }
+}
+
+impl LatencyProfile {
+    /// `default` applies to commands that no family rule matches
+    pub fn new(default: LatencyDistribution) -> Self {
+        LatencyProfile {
+            default,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Add a rule; earlier rules take precedence over later ones
+    pub fn with_family(mut self, family: CommandFamily, latency: LatencyDistribution) -> Self {
+        self.rules.push((family, latency));
+        self
+    }
+
+    pub fn distribution_for(&self, cmd: &Command) -> &LatencyDistribution {
+        self.rules
+            .iter()
+            .find(|(family, _)| family.matches(cmd))
+            .map_or(&self.default, |(_, latency)| latency)
+    }
+
+    pub fn sample(&self, cmd: &Command, rng: &mut DeterministicRng) -> Duration {
+        self.distribution_for(cmd).sample(rng)
+    }
+
+    /// True when no command is ever delayed
+    pub fn is_zero(&self) -> bool {
+        self.default.max() == Duration::ZERO
+            && self
+                .rules
+                .iter()
+                .all(|(_, latency)| latency.max() == Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redis::SDS;
+
+    fn zrangebyscore() -> Command {
+        Command::ZRangeByScore {
+            key: "z".to_string(),
+            min: "-inf".to_string(),
+            max: "+inf".to_string(),
+            with_scores: false,
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn test_first_matching_family_wins() {
+        let profile = LatencyProfile::new(LatencyDistribution::fixed_ms(1))
+            .with_family(
+                CommandFamily::command("zrangebyscore"),
+                LatencyDistribution::fixed_ms(1).scaled(10),
+            )
+            .with_family(CommandFamily::prefix("Z"), LatencyDistribution::fixed_ms(3))
+            .with_family(CommandFamily::Write, LatencyDistribution::fixed_ms(2));
+        let mut rng = DeterministicRng::new(1);
+
+        let zcard = Command::ZCard("z".to_string());
+        let set = Command::set("k".to_string(), SDS::from_str("v"));
+        let get = Command::Get("k".to_string());
+        assert_eq!(
+            profile.sample(&zrangebyscore(), &mut rng),
+            Duration::from_millis(10)
+        );
+        assert_eq!(profile.sample(&zcard, &mut rng), Duration::from_millis(3));
+        assert_eq!(profile.sample(&set, &mut rng), Duration::from_millis(2));
+        assert_eq!(profile.sample(&get, &mut rng), Duration::from_millis(1));
+        assert!(!profile.is_zero());
+        assert!(LatencyProfile::default().is_zero());
+    }
+
+    #[test]
+    fn test_samples_are_deterministic_and_bounded() {
+        let dist = LatencyDistribution::uniform_ms(5, 8);
+        let draw = |seed| {
+            let mut rng = DeterministicRng::new(seed);
+            (0..100).map(|_| dist.sample(&mut rng)).collect::<Vec<_>>()
+        };
+        let samples = draw(42);
+        assert_eq!(samples, draw(42));
+        assert!(samples.iter().all(|d| (5..=8).contains(&d.as_millis())));
+        assert!(samples.contains(&Duration::from_millis(8)));
+
+        let tail = LatencyDistribution::Bimodal {
+            base: Duration::from_millis(1),
+            slow: Duration::from_millis(50),
+            slow_probability: 0.1,
+        };
+        let mut rng = DeterministicRng::new(7);
+        let slow = (0..1000)
+            .filter(|_| tail.sample(&mut rng) == Duration::from_millis(50))
+            .count();
+        assert!((50..150).contains(&slow), "slow samples: {}", slow);
+    }
+}
@@ -0,0 +1,916 @@
+//! Leader Election in the Multi-Node Simulation
+//!
+//! `with_consistency_level(ConsistencyLevel::Strong)` gives every node an
+//! `Election` that records its votes in its own in-memory WAL. Election
+//! messages share the gossip network's partitions, packet loss and delays,
+//! and run as discrete events whenever the simulation clock advances.
+//!
+//! A node serves only while it leads under a lease, as its own clock tells
+//! it (`with_clock_skew` makes clocks drift), and once it has pulled the
+//! state of a majority in its term: anti-entropy with each peer whose
+//! heartbeat ack it receives. Reads are then answered from its own state,
+//! with no messages. A write is applied to every live node the leader can
+//! reach, and refused unless they make a majority, so a later leader's pull
+//! finds every acknowledged write. Followers answer writes READONLY;
+//! anything else a node cannot serve is answered TRYAGAIN.
+//!
+//! Leases assume clocks drift within the configured bound. Past it, a
+//! deposed leader can still hold a lease after its successor is elected, so
+//! a write also carries the leader's term as a fencing token: a replica
+//! that has seen a newer term refuses it, and the leader adopts that term
+//! and steps down.
+//!
+//! Every node that wins an election is recorded by term, so
+//! `check_election_safety` covers the whole run rather than the final state;
+//! every acknowledged write is recorded with its term, so `check_fencing`
+//! does the same for writes. `run_election_scenario` drives a cluster
+//! through random partitions and crashes, skewed clocks and a read/write
+//! workload, reports what the elections did, and checks every key's history
+//! for linearizability. `ElectionScenario::split_brain` skews clocks past
+//! the drift bound.
+
+use super::multi_node::{check_single_key_linearizability, MultiNodeSimulation};
+use super::{DeterministicRng, Duration, VirtualTime};
+use crate::io::simulation::SimulatedRng;
+use crate::redis::{Command, RespValue, SDS};
+use crate::replication::state::ReplicationDelta;
+use crate::replication::{ConsistencyLevel, Election, ElectionConfig, ElectionMessage, ReplicaId};
+use crate::streaming::wal_store::InMemoryWalStore;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What a node that is not the leader answers to writes
+pub const READONLY_ERROR: &str = "READONLY You can't write against a read only replica.";
+
+/// What a node answers when it holds no lease to serve under
+pub const NO_LEASE_ERROR: &str = "TRYAGAIN No leader lease on this node, try again later.";
+
+/// What a leader answers to a write it cannot get to a majority
+pub const NOREPLICAS_ERROR: &str = "NOREPLICAS Not enough good replicas to write.";
+
+/// What a leader answers to a write a replica fenced off with a newer term
+pub const FENCED_ERROR: &str = "TRYAGAIN A newer term fenced this leader, try again later.";
+
+type SimElection = Election<InMemoryWalStore, SimulatedRng>;
+
+/// An election message on the wire
+#[derive(Debug, Clone)]
+struct ElectionEnvelope {
+    from: usize,
+    to: usize,
+    message: ElectionMessage,
+    delivery_time: VirtualTime,
+}
+
+/// Every node's election state, plus the messages between them
+pub struct ElectionCluster {
+    config: ElectionConfig,
+    /// None while the node is crashed
+    nodes: Vec<Option<SimElection>>,
+    /// Each node's vote WAL; survives crashes up to its last sync
+    stores: Vec<InMemoryWalStore>,
+    in_flight: Vec<ElectionEnvelope>,
+    /// Every node that led each term
+    leaders_by_term: BTreeMap<u64, BTreeSet<usize>>,
+    /// How fast each node's clock runs against real time, in per mille
+    skew_permille: Vec<i64>,
+    /// The peers each node pulled state from as leader, and in which term
+    pulled: Vec<(u64, BTreeSet<usize>)>,
+    /// Whether replicas refuse writes from a term older than their own
+    fencing: bool,
+    /// Every acknowledged write, in the order it was acknowledged
+    acked_writes: Vec<AckedWrite>,
+}
+
+/// A write a leader acknowledged, and the term it was written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AckedWrite {
+    pub at: VirtualTime,
+    pub node: usize,
+    pub term: u64,
+}
+
+fn replica_id(node: usize) -> ReplicaId {
+    ReplicaId::new(node as u64 + 1)
+}
+
+fn node_index(replica: ReplicaId) -> usize {
+    replica.0 as usize - 1
+}
+
+impl ElectionCluster {
+    pub fn new(
+        num_nodes: usize,
+        config: ElectionConfig,
+        rng: &mut DeterministicRng,
+        now: VirtualTime,
+    ) -> Self {
+        let mut cluster = ElectionCluster {
+            config,
+            nodes: (0..num_nodes).map(|_| None).collect(),
+            stores: (0..num_nodes).map(|_| InMemoryWalStore::new()).collect(),
+            in_flight: Vec::new(),
+            leaders_by_term: BTreeMap::new(),
+            skew_permille: vec![0; num_nodes],
+            pulled: vec![(0, BTreeSet::new()); num_nodes],
+            fencing: true,
+            acked_writes: Vec::new(),
+        };
+        for node in 0..num_nodes {
+            cluster.start(node, rng, now);
+        }
+        cluster
+    }
+
+    /// Open a node's election from its WAL, with a fresh timeout RNG
+    fn start(&mut self, node: usize, rng: &mut DeterministicRng, now: VirtualTime) {
+        debug_assert!(
+            self.nodes[node].is_none(),
+            "Precondition: node {} is up",
+            node
+        );
+        let peers = (0..self.nodes.len())
+            .filter(|&peer| peer != node)
+            .map(replica_id)
+            .collect();
+        let election = Election::open(
+            replica_id(node),
+            peers,
+            self.config,
+            self.stores[node].clone(),
+            SimulatedRng::new(rng.next_u64()),
+            self.local_ms(node, now.as_millis()),
+        )
+        .expect("in-memory WAL does not fail");
+        self.nodes[node] = Some(election);
+    }
+
+    pub fn node(&self, node: usize) -> Option<&SimElection> {
+        self.nodes[node].as_ref()
+    }
+
+    /// Every node that led each term, over the whole run
+    pub fn leaders_by_term(&self) -> &BTreeMap<u64, BTreeSet<usize>> {
+        &self.leaders_by_term
+    }
+
+    /// Every acknowledged write, in the order it was acknowledged
+    pub fn acked_writes(&self) -> &[AckedWrite] {
+        &self.acked_writes
+    }
+
+    fn quorum(&self) -> usize {
+        self.nodes.len() / 2 + 1
+    }
+
+    /// `node`'s clock reading at real time `real_ms`
+    fn local_ms(&self, node: usize, real_ms: u64) -> u64 {
+        real_ms * (1000 + self.skew_permille[node]) as u64 / 1000
+    }
+
+    /// The first real time at which `node`'s clock reads `local_ms`
+    fn real_ms(&self, node: usize, local_ms: u64) -> u64 {
+        let rate = (1000 + self.skew_permille[node]) as u64;
+        (local_ms * 1000).div_ceil(rate)
+    }
+
+    fn next_event(&self) -> Option<u64> {
+        let deadlines = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(node, e)| Some(self.real_ms(node, e.as_ref()?.deadline_ms())));
+        let deliveries = self.in_flight.iter().map(|m| m.delivery_time.as_millis());
+        deadlines.chain(deliveries).min()
+    }
+
+    /// Note that leader `node` pulled `peer`'s state in `term`; false if it
+    /// already had
+    fn record_pull(&mut self, node: usize, term: u64, peer: usize) -> bool {
+        let (pulled_term, peers) = &mut self.pulled[node];
+        if *pulled_term != term {
+            *pulled_term = term;
+            peers.clear();
+        }
+        peers.insert(peer)
+    }
+
+    fn record_leader(&mut self, node: usize) {
+        if let Some(election) = &self.nodes[node] {
+            if election.is_leader() {
+                self.leaders_by_term
+                    .entry(election.term())
+                    .or_default()
+                    .insert(node);
+            }
+        }
+    }
+}
+
+impl MultiNodeSimulation {
+    /// Use this election timing. Requires the `Strong` consistency level.
+    pub fn with_election_config(mut self, config: ElectionConfig) -> Self {
+        debug_assert!(
+            self.elections.is_some(),
+            "Precondition: elections run only at ConsistencyLevel::Strong"
+        );
+        self.elections = Some(ElectionCluster::new(
+            self.nodes.len(),
+            config,
+            &mut self.rng,
+            self.current_time,
+        ));
+        self
+    }
+
+    /// Run `node`'s clock `skew_permille` per mille fast (slow if negative)
+    /// against real time, as its election sees it. Call before time advances.
+    pub fn with_clock_skew(mut self, node: usize, skew_permille: i64) -> Self {
+        debug_assert!(
+            self.current_time == VirtualTime::ZERO,
+            "Precondition: clocks skew from the start of the run"
+        );
+        debug_assert!(
+            skew_permille > -1000,
+            "Precondition: clocks must run forward"
+        );
+        let cluster = self
+            .elections
+            .as_mut()
+            .expect("Precondition: elections run only at ConsistencyLevel::Strong");
+        cluster.skew_permille[node] = skew_permille;
+        self
+    }
+
+    /// Let replicas take writes from any term. Only for showing that
+    /// `check_fencing` catches a deposed leader's writes.
+    pub fn without_fencing(mut self) -> Self {
+        let cluster = self
+            .elections
+            .as_mut()
+            .expect("Precondition: elections run only at ConsistencyLevel::Strong");
+        cluster.fencing = false;
+        self
+    }
+
+    /// Process election timeouts and messages in time order, up to `until`
+    pub(super) fn run_elections_until(&mut self, until: VirtualTime) {
+        let mut cluster = self
+            .elections
+            .take()
+            .expect("Precondition: elections enabled");
+
+        while let Some(next) = cluster.next_event() {
+            if next > until.as_millis() {
+                break;
+            }
+            let now = VirtualTime::from_millis(next.max(self.current_time.as_millis()));
+            self.current_time = now;
+
+            let (due, later): (Vec<_>, Vec<_>) = std::mem::take(&mut cluster.in_flight)
+                .into_iter()
+                .partition(|m| m.delivery_time <= now);
+            cluster.in_flight = later;
+            for envelope in due {
+                // Partitioned after sending, or the recipient is down
+                if !self.can_communicate(envelope.from, envelope.to) {
+                    continue;
+                }
+                let local_ms = cluster.local_ms(envelope.to, now.as_millis());
+                let Some(election) = cluster.nodes[envelope.to].as_mut() else {
+                    continue;
+                };
+                let outbox = election
+                    .handle(replica_id(envelope.from), envelope.message, local_ms)
+                    .expect("in-memory WAL does not fail");
+                let term = election.term();
+                let acked_leader = election.is_leader()
+                    && matches!(
+                        envelope.message,
+                        ElectionMessage::HeartbeatAck { term: acked, .. } if acked == term
+                    );
+                self.send_election_messages(&mut cluster, envelope.to, outbox);
+                cluster.record_leader(envelope.to);
+                // A new leader pulls each acknowledging peer's state once
+                if acked_leader && cluster.record_pull(envelope.to, term, envelope.from) {
+                    self.run_anti_entropy_sync(envelope.to, envelope.from);
+                }
+            }
+
+            for node in 0..cluster.nodes.len() {
+                let local_ms = cluster.local_ms(node, now.as_millis());
+                let Some(election) = cluster.nodes[node].as_mut() else {
+                    continue;
+                };
+                let outbox = election
+                    .tick(local_ms)
+                    .expect("in-memory WAL does not fail");
+                self.send_election_messages(&mut cluster, node, outbox);
+                cluster.record_leader(node);
+            }
+        }
+
+        self.elections = Some(cluster);
+    }
+
+    fn send_election_messages(
+        &mut self,
+        cluster: &mut ElectionCluster,
+        from: usize,
+        outbox: Vec<(ReplicaId, ElectionMessage)>,
+    ) {
+        for (to, message) in outbox {
+            let to = node_index(to);
+            if !self.can_communicate(from, to) {
+                continue;
+            }
+            let loss = self
+                .link_loss
+                .get(&(from.min(to), from.max(to)))
+                .copied()
+                .unwrap_or(self.packet_loss_rate);
+            if self.rng.gen_bool(loss) {
+                continue;
+            }
+            let delay_ms = self
+                .rng
+                .gen_range(self.message_delay_range.0, self.message_delay_range.1 + 1);
+            cluster.in_flight.push(ElectionEnvelope {
+                from,
+                to,
+                message,
+                delivery_time: self.current_time + Duration::from_millis(delay_ms),
+            });
+        }
+    }
+
+    /// Serve `cmd` on `node` in the strongly-consistent mode
+    pub(super) fn execute_strong(&mut self, node: usize, cmd: &Command) -> RespValue {
+        if !cmd.is_read_only() && !self.is_leader(node) {
+            return RespValue::err(READONLY_ERROR);
+        }
+        if !self.holds_lease(node) {
+            return RespValue::err(NO_LEASE_ERROR);
+        }
+        if cmd.is_read_only() {
+            return self.nodes[node].execute(cmd);
+        }
+
+        let cluster = self
+            .elections
+            .as_ref()
+            .expect("Precondition: elections enabled");
+        let replicas: Vec<usize> = (0..self.nodes.len())
+            .filter(|&peer| peer != node && self.can_communicate(node, peer))
+            .filter(|&peer| cluster.node(peer).is_some())
+            .collect();
+        // The write carries the leader's term as its fencing token: a
+        // replica that has moved on to a newer term refuses it, and the
+        // leader learns of that term and steps down
+        let term = cluster.node(node).map_or(0, |e| e.term());
+        let newest = replicas
+            .iter()
+            .filter_map(|&peer| cluster.node(peer).map(|e| e.term()))
+            .max()
+            .unwrap_or(0);
+        if cluster.fencing && newest > term {
+            let cluster = self
+                .elections
+                .as_mut()
+                .expect("Precondition: elections enabled");
+            cluster.nodes[node]
+                .as_mut()
+                .expect("Precondition: the leader is up")
+                .observe_term(newest)
+                .expect("in-memory WAL does not fail");
+            return RespValue::err(FENCED_ERROR);
+        }
+        if replicas.len() + 1 < cluster.quorum() {
+            return RespValue::err(NOREPLICAS_ERROR);
+        }
+        let response = self.nodes[node].execute(cmd);
+        if matches!(response, RespValue::Error(_)) {
+            return response;
+        }
+        let at = self.current_time;
+        self.elections
+            .as_mut()
+            .expect("Precondition: elections enabled")
+            .acked_writes
+            .push(AckedWrite { at, node, term });
+        let leader = &self.nodes[node];
+        let deltas: Vec<ReplicationDelta> = cmd
+            .get_keys()
+            .into_iter()
+            .filter_map(|key| {
+                let value = leader.replica_state.replicated_keys.get(&key)?.clone();
+                Some(ReplicationDelta::new(key, value, leader.replica_id))
+            })
+            .collect();
+        for peer in replicas {
+            self.nodes[peer].apply_remote_deltas(deltas.clone());
+        }
+        response
+    }
+
+    /// Whether `node` may serve: it leads under a lease by its own clock, and
+    /// has pulled the state of a majority (itself included) in its term
+    pub fn holds_lease(&self, node: usize) -> bool {
+        let Some(cluster) = &self.elections else {
+            return false;
+        };
+        let Some(election) = cluster.node(node) else {
+            return false;
+        };
+        let (pulled_term, peers) = &cluster.pulled[node];
+        let pulled = if *pulled_term == election.term() {
+            peers.len()
+        } else {
+            0
+        };
+        election.has_lease(cluster.local_ms(node, self.current_time.as_millis()))
+            && pulled + 1 >= cluster.quorum()
+    }
+
+    /// Whether `node` is up and believes it leads its term
+    pub fn is_leader(&self, node: usize) -> bool {
+        self.elections
+            .as_ref()
+            .and_then(|cluster| cluster.node(node))
+            .is_some_and(|election| election.is_leader())
+    }
+
+    /// The live node leading the highest term, if any
+    pub fn leader(&self) -> Option<usize> {
+        let cluster = self.elections.as_ref()?;
+        (0..self.nodes.len())
+            .filter(|&node| self.is_leader(node))
+            .max_by_key(|&node| cluster.node(node).map(|e| e.term()))
+    }
+
+    /// Stop a node's election: its volatile state is lost, its vote WAL keeps
+    /// what was synced, and messages to it are dropped until it restarts
+    pub fn crash_node(&mut self, node: usize) {
+        let cluster = self
+            .elections
+            .as_mut()
+            .expect("Precondition: elections enabled");
+        cluster.nodes[node] = None;
+        cluster.stores[node].simulate_crash();
+    }
+
+    /// Restart a crashed node as a follower, recovering its term and vote
+    pub fn restart_node(&mut self, node: usize) {
+        let now = self.current_time;
+        let cluster = self
+            .elections
+            .as_mut()
+            .expect("Precondition: elections enabled");
+        cluster.start(node, &mut self.rng, now);
+    }
+
+    /// At most one node led each term, over the whole run
+    pub fn check_election_safety(&self) -> Result<(), String> {
+        let Some(cluster) = &self.elections else {
+            return Ok(());
+        };
+        match cluster
+            .leaders_by_term
+            .iter()
+            .find(|(_, leaders)| leaders.len() > 1)
+        {
+            Some((term, leaders)) => Err(format!("term {} had leaders {:?}", term, leaders)),
+            None => Ok(()),
+        }
+    }
+
+    /// Acknowledged writes never go back to an older term, and one node
+    /// acknowledges all of a term's writes: once a newer leader has taken a
+    /// write, no deposed leader gets another acknowledged
+    pub fn check_fencing(&self) -> Result<(), String> {
+        let Some(cluster) = &self.elections else {
+            return Ok(());
+        };
+        for pair in cluster.acked_writes.windows(2) {
+            let (before, after) = (pair[0], pair[1]);
+            if after.term < before.term {
+                return Err(format!(
+                    "node {} acked a term {} write at {:?}, after node {} acked term {}",
+                    after.node, after.term, after.at, before.node, before.term
+                ));
+            }
+            if after.term == before.term && after.node != before.node {
+                return Err(format!(
+                    "nodes {} and {} both acked writes in term {}",
+                    before.node, after.node, after.term
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Faults and workload for one run of a strongly-consistent cluster
+#[derive(Debug, Clone)]
+pub struct ElectionScenario {
+    pub num_nodes: usize,
+    pub tick_ms: u64,
+    /// How long faults are injected
+    pub chaos_ms: u64,
+    /// Chance per tick that a random node is cut off from all others
+    pub isolate_probability: f64,
+    /// Chance per tick that every partition heals
+    pub heal_probability: f64,
+    /// Chance per tick that a random live node crashes
+    pub crash_probability: f64,
+    /// Chance per tick that each crashed node restarts
+    pub restart_probability: f64,
+    /// Each node's clock runs up to this many per mille fast or slow
+    pub clock_skew_permille: u64,
+    /// Chance per tick that the operation is a GET rather than a SET
+    pub read_probability: f64,
+    /// How long the healed cluster gets to settle on one leader
+    pub settle_ms: u64,
+}
+
+impl Default for ElectionScenario {
+    fn default() -> Self {
+        ElectionScenario {
+            num_nodes: 5,
+            tick_ms: 10,
+            chaos_ms: 10_000,
+            isolate_probability: 0.01,
+            heal_probability: 0.005,
+            crash_probability: 0.002,
+            restart_probability: 0.01,
+            // Within the default election config's drift bound
+            clock_skew_permille: 100,
+            read_probability: 0.5,
+            settle_ms: 3_000,
+        }
+    }
+}
+
+impl ElectionScenario {
+    /// Clocks skewed far past the drift bound, so a deposed leader's lease
+    /// can outlive its successor's election: only term fencing stops the
+    /// deposed leader's writes. Its reads may be stale, so the workload is
+    /// all writes.
+    pub fn split_brain() -> Self {
+        ElectionScenario {
+            isolate_probability: 0.03,
+            heal_probability: 0.02,
+            clock_skew_permille: 900,
+            read_probability: 0.0,
+            ..ElectionScenario::default()
+        }
+    }
+}
+
+/// Outcome of one run
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ElectionResult {
+    pub seed: u64,
+    /// Highest term any node reached
+    pub max_term: u64,
+    /// Terms that elected a leader
+    pub terms_with_leader: usize,
+    /// A term with two leaders, if one happened
+    pub safety_violation: Option<String>,
+    /// Writes a node accepted and refused while faults were injected
+    pub writes_accepted: u64,
+    pub writes_refused: u64,
+    /// Refused writes that a replica fenced off with a newer term
+    pub writes_fenced: u64,
+    /// Reads a leaseholder served while faults were injected
+    pub reads_served: u64,
+    /// An acknowledged write from a deposed leader, if one happened
+    pub fencing_violation: Option<String>,
+    /// Reads that contradict the key's write history
+    pub linearizability_violations: Vec<String>,
+    /// The leader every node agrees on after healing, if they do
+    pub settled_leader: Option<usize>,
+}
+
+/// Run one scenario with the given seed
+pub fn run_election_scenario(scenario: &ElectionScenario, seed: u64) -> ElectionResult {
+    debug_assert!(scenario.tick_ms > 0, "Precondition: tick must be positive");
+    debug_assert!(
+        scenario.num_nodes >= 3,
+        "Precondition: a quorum needs a majority of at least 3 nodes"
+    );
+
+    let mut sim = MultiNodeSimulation::new_without_anti_entropy(scenario.num_nodes, seed)
+        .with_consistency_level(ConsistencyLevel::Strong);
+    let n = scenario.num_nodes;
+    let max_skew = scenario.clock_skew_permille;
+    for node in 0..n {
+        let skew = sim.rng.gen_range(0, 2 * max_skew + 1) as i64 - max_skew as i64;
+        sim = sim.with_clock_skew(node, skew);
+    }
+    let mut result = ElectionResult {
+        seed,
+        ..ElectionResult::default()
+    };
+    let mut crashed = vec![false; n];
+    let heal_all = |sim: &mut MultiNodeSimulation| {
+        for a in 0..n {
+            for b in a + 1..n {
+                sim.heal_partition(a, b);
+            }
+        }
+    };
+
+    for tick in 0..scenario.chaos_ms / scenario.tick_ms {
+        if sim.rng.gen_bool(scenario.isolate_probability) {
+            let node = sim.rng.gen_range(0, n as u64) as usize;
+            for other in (0..n).filter(|&other| other != node) {
+                sim.partition(node, other);
+            }
+        }
+        if sim.rng.gen_bool(scenario.heal_probability) {
+            heal_all(&mut sim);
+        }
+        if sim.rng.gen_bool(scenario.crash_probability) {
+            let node = sim.rng.gen_range(0, n as u64) as usize;
+            if !crashed[node] {
+                sim.crash_node(node);
+                crashed[node] = true;
+            }
+        }
+        for (node, down) in crashed.iter_mut().enumerate() {
+            if *down && sim.rng.gen_bool(scenario.restart_probability) {
+                sim.restart_node(node);
+                *down = false;
+            }
+        }
+
+        let node = sim.rng.gen_range(0, n as u64) as usize;
+        let key = format!("key:{}", tick % 8);
+        let read = sim.rng.gen_bool(scenario.read_probability);
+        let cmd = if read {
+            Command::Get(key)
+        } else {
+            Command::set(key, SDS::from_str(&format!("v{}", tick)))
+        };
+        let response = sim.execute(0, node, cmd);
+        if response == RespValue::err(FENCED_ERROR) {
+            result.writes_fenced += 1;
+        }
+        let refused = matches!(response, RespValue::Error(_));
+        match (read, refused) {
+            (true, false) => result.reads_served += 1,
+            (true, true) => {}
+            (false, false) => result.writes_accepted += 1,
+            (false, true) => result.writes_refused += 1,
+        }
+        sim.advance_time_ms(scenario.tick_ms);
+    }
+
+    heal_all(&mut sim);
+    for (node, down) in crashed.iter().enumerate() {
+        if *down {
+            sim.restart_node(node);
+        }
+    }
+    sim.advance_time_ms(scenario.settle_ms);
+
+    let cluster = sim.elections.as_ref().expect("strong mode runs elections");
+    result.max_term = (0..n)
+        .filter_map(|node| cluster.node(node).map(|e| e.term()))
+        .max()
+        .unwrap_or(0);
+    result.terms_with_leader = cluster.leaders_by_term().len();
+    result.safety_violation = sim.check_election_safety().err();
+    result.fencing_violation = sim.check_fencing().err();
+    for key in 0..8 {
+        let check = check_single_key_linearizability(&sim.history, &format!("key:{}", key));
+        result.linearizability_violations.extend(check.violations);
+    }
+    result.settled_leader = sim.leader().filter(|&leader| {
+        (0..n).all(|node| {
+            cluster
+                .node(node)
+                .is_some_and(|e| e.leader() == Some(replica_id(leader)))
+        })
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strong_mode_elects_one_leader_that_alone_takes_writes() {
+        let mut sim =
+            MultiNodeSimulation::new(3, 7).with_consistency_level(ConsistencyLevel::Strong);
+        assert_eq!(sim.leader(), None);
+        sim.advance_time_ms(1_000);
+
+        let leader = sim.leader().expect("a leader within a few timeouts");
+        let follower = (leader + 1) % 3;
+        let set = |value: &str| Command::set("k".into(), SDS::from_str(value));
+        assert_eq!(sim.execute(0, leader, set("a")), RespValue::ok());
+        assert_eq!(
+            sim.execute(0, follower, set("b")),
+            RespValue::err(READONLY_ERROR)
+        );
+        assert_eq!(
+            sim.execute(0, follower, Command::Get("k".into())),
+            RespValue::err(NO_LEASE_ERROR)
+        );
+        assert_eq!(
+            sim.execute(0, leader, Command::Get("k".into())),
+            RespValue::BulkString(Some(b"a".to_vec()))
+        );
+        assert_eq!(
+            sim.nodes[follower].get_replicated_value("k"),
+            Some("a".to_string()),
+            "a write reaches a majority before its reply"
+        );
+        assert_eq!(sim.check_election_safety(), Ok(()));
+    }
+
+    #[test]
+    fn test_leases_never_overlap_under_clock_skew() {
+        // Node 0 runs slow, stretching its leases; the others run fast
+        let mut sim = MultiNodeSimulation::new(3, 5)
+            .with_consistency_level(ConsistencyLevel::Strong)
+            .with_clock_skew(0, -100)
+            .with_clock_skew(1, 100)
+            .with_clock_skew(2, 100);
+        sim.advance_time_ms(1_000);
+        let old = sim.leader().unwrap();
+        let set = Command::set("k".into(), SDS::from_str("a"));
+        assert_eq!(sim.execute(0, old, set), RespValue::ok());
+        for other in (0..3).filter(|&other| other != old) {
+            sim.partition(old, other);
+        }
+
+        for _ in 0..1_000 {
+            sim.advance_time_ms(1);
+            let serving: Vec<usize> = (0..3).filter(|&node| sim.holds_lease(node)).collect();
+            assert!(
+                serving.len() <= 1,
+                "leases overlap at {:?}: {:?}",
+                sim.current_time,
+                serving
+            );
+        }
+        let new = sim.leader().unwrap();
+        assert_ne!(new, old);
+        assert!(sim.is_leader(old) && !sim.holds_lease(old));
+        assert_eq!(
+            sim.execute(0, old, Command::Get("k".into())),
+            RespValue::err(NO_LEASE_ERROR)
+        );
+        assert_eq!(
+            sim.execute(0, new, Command::Get("k".into())),
+            RespValue::BulkString(Some(b"a".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_isolated_leader_is_replaced_and_steps_down_on_heal() {
+        let mut sim =
+            MultiNodeSimulation::new(3, 11).with_consistency_level(ConsistencyLevel::Strong);
+        sim.advance_time_ms(1_000);
+        let old = sim.leader().unwrap();
+        let old_term = sim.elections.as_ref().unwrap().node(old).unwrap().term();
+        for other in (0..3).filter(|&other| other != old) {
+            sim.partition(old, other);
+        }
+        sim.advance_time_ms(1_000);
+
+        let new = sim.leader().unwrap();
+        assert_ne!(new, old);
+        assert!(
+            sim.is_leader(old),
+            "cut off from everyone, the old leader hears of no newer term"
+        );
+        let new_term = sim.elections.as_ref().unwrap().node(new).unwrap().term();
+        assert!(new_term > old_term);
+
+        for other in (0..3).filter(|&other| other != old) {
+            sim.heal_partition(old, other);
+        }
+        sim.advance_time_ms(500);
+        assert!(!sim.is_leader(old));
+        assert_eq!(sim.leader(), Some(new));
+        assert_eq!(sim.check_election_safety(), Ok(()));
+    }
+
+    #[test]
+    fn test_deposed_leader_is_fenced_off_by_a_newer_term() {
+        for fencing in [true, false] {
+            // Node 0's clock runs at a tenth of real time, far past the drift
+            // bound, so its lease outlives its successor's election
+            let mut sim = MultiNodeSimulation::new(3, 13)
+                .with_consistency_level(ConsistencyLevel::Strong)
+                .with_clock_skew(0, -900);
+            if !fencing {
+                sim = sim.without_fencing();
+            }
+            let set = |value: &str| Command::set("k".into(), SDS::from_str(value));
+
+            // The fast nodes come up just in time to vote for node 0: they
+            // grant no vote for a minimum timeout after starting
+            sim.crash_node(1);
+            sim.crash_node(2);
+            let cluster = sim.elections.as_ref().unwrap();
+            let candidacy = cluster.real_ms(0, cluster.node(0).unwrap().deadline_ms());
+            sim.advance_time_ms(candidacy - ElectionConfig::default().election_timeout_min_ms);
+            sim.restart_node(1);
+            sim.restart_node(2);
+            for _ in 0..1_000 {
+                if sim.holds_lease(0) {
+                    break;
+                }
+                sim.advance_time_ms(1);
+            }
+            assert!(sim.holds_lease(0));
+            assert_eq!(sim.execute(0, 0, set("a")), RespValue::ok());
+
+            for other in [1, 2] {
+                sim.partition(0, other);
+            }
+            let mut new = None;
+            for _ in 0..1_000 {
+                sim.advance_time_ms(1);
+                new = (1..3).find(|&node| sim.holds_lease(node));
+                if new.is_some() {
+                    break;
+                }
+            }
+            let new = new.expect("the others elect a leader among themselves");
+            assert!(sim.holds_lease(0), "two nodes serve at once");
+            assert_eq!(sim.execute(0, new, set("b")), RespValue::ok());
+
+            for other in [1, 2] {
+                sim.heal_partition(0, other);
+            }
+            let stale = sim.execute(0, 0, set("c"));
+            if fencing {
+                assert_eq!(stale, RespValue::err(FENCED_ERROR));
+                assert!(!sim.is_leader(0), "the fenced leader steps down");
+                assert_eq!(sim.check_fencing(), Ok(()));
+                assert_eq!(
+                    sim.nodes[new].get_replicated_value("k"),
+                    Some("b".to_string())
+                );
+            } else {
+                assert_eq!(stale, RespValue::ok());
+                assert!(sim.check_fencing().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_brain_scenario_fences_every_deposed_leader() {
+        let scenario = ElectionScenario::split_brain();
+        let mut fenced = 0;
+        for seed in 0..20 {
+            let result = run_election_scenario(&scenario, seed);
+            assert_eq!(result.safety_violation, None, "seed {}", seed);
+            assert_eq!(result.fencing_violation, None, "seed {}", seed);
+            assert!(result.settled_leader.is_some(), "seed {}", seed);
+            assert_eq!(
+                result.linearizability_violations,
+                Vec::<String>::new(),
+                "seed {}",
+                seed
+            );
+            fenced += result.writes_fenced;
+        }
+        assert!(fenced > 0, "some deposed leader tried to write");
+    }
+
+    #[test]
+    fn test_election_scenario_is_safe_across_seeds() {
+        let scenario = ElectionScenario::default();
+        for seed in 0..20 {
+            let result = run_election_scenario(&scenario, seed);
+            assert_eq!(result.safety_violation, None, "seed {}", seed);
+            assert_eq!(result.fencing_violation, None, "seed {}", seed);
+            assert!(
+                result.settled_leader.is_some(),
+                "seed {}: no leader after healing: {:?}",
+                seed,
+                result
+            );
+            assert!(result.writes_accepted > 0 && result.writes_refused > 0);
+            assert!(result.reads_served > 0, "seed {}", seed);
+            assert_eq!(
+                result.linearizability_violations,
+                Vec::<String>::new(),
+                "seed {}",
+                seed
+            );
+        }
+        assert_eq!(
+            run_election_scenario(&scenario, 3),
+            run_election_scenario(&scenario, 3),
+            "runs are deterministic"
+        );
+    }
+}
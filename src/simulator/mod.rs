@@ -1,25 +1,42 @@
+pub mod anti_entropy_schedule;
+pub mod byzantine;
 pub mod connection;
+pub mod corpus;
 pub mod crash;
 pub mod dst;
 pub mod dst_integration;
 mod executor;
 pub mod harness;
+pub mod latency;
+pub mod leader_election;
 pub mod multi_node;
 mod network;
 pub mod partition_tests;
+pub mod replay;
 mod rng;
+mod storage;
 mod time;
 
+pub use anti_entropy_schedule::{run_schedule_scenario, ScheduleResult, ScheduleScenario};
+pub use byzantine::{run_byzantine_scenario, ByzantineResult, ByzantineScenario};
 pub use connection::{
     ExecutionRecord, PipelineResult, PipelineSimulator, SimulatedConnection, SimulatedReadBuffer,
     SimulatedWriteBuffer,
 };
+pub use corpus::{CorpusEntry, ReplayReport, SeedCorpus};
 pub use crash::{CrashConfig, CrashReason, CrashSimulator, NodeSnapshot, NodeState};
-pub use dst::{BatchResult, BatchRunner, DSTConfig, DSTSimulation, SimulationResult};
-pub use executor::{Simulation, SimulationConfig};
+pub use dst::{
+    BatchResult, BatchRunner, ChaosProfile, DSTConfig, DSTSimulation, SimulationResult,
+};
+pub use executor::{HostWait, IdlePolicy, RunEnd, RunOutcome, Simulation, SimulationConfig};
 pub use harness::{ScenarioBuilder, SimulatedRedisNode, SimulationHarness};
+pub use latency::{CommandFamily, LatencyDistribution, LatencyProfile};
+pub use leader_election::{
+    run_election_scenario, AckedWrite, ElectionCluster, ElectionResult, ElectionScenario,
+};
 pub use multi_node::{
-    check_single_key_linearizability, LinearizabilityResult, MultiNodeSimulation,
+    check_read_staleness, check_single_key_linearizability, ByzantineFault,
+    LinearizabilityResult, MultiNodeSimulation, ReadRouting, StalenessResult,
     TimestampedOperation,
 };
 pub use network::{Host, NetworkEvent, NetworkFault, PacketDelay};
@@ -27,7 +44,12 @@ pub use partition_tests::{
     run_partition_test, run_partition_test_batch, PartitionBatchResult, PartitionConfig,
     PartitionTestResult,
 };
+pub use replay::{
+    replay_workload, CaptureFormat, CapturedCommand, ReplayConfig, WorkloadCapture,
+    WorkloadReplayReport,
+};
 pub use rng::{buggify, DeterministicRng};
+pub use storage::{SimulatedDisk, StorageMode};
 pub use time::{Duration, VirtualTime};
 
 use std::cmp::Ordering;
@@ -67,6 +89,8 @@ pub enum EventType {
     Timer(TimerId),
     NetworkMessage(Message),
     HostStart,
+    /// The clock stepped with nothing due (`IdlePolicy::AdvanceBy`)
+    Idle,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
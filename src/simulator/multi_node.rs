@@ -5,15 +5,29 @@
 //! - Network partitions and message loss
 //! - Selective gossip routing
 //! - CRDT convergence verification
+//! - Read routing to replicas under a max-staleness bound
+//! - Metered, optionally adaptive, scheduled anti-entropy
+//! - Faulty nodes that gossip corrupted and forged deltas
+//! - Quorum leader election in the strongly-consistent mode
+//! - WAIT on replica acknowledgements of a node's replication offset
 
+use super::leader_election::ElectionCluster;
 use super::{DeterministicRng, Duration, VirtualTime};
-use crate::redis::{Command, CommandExecutor, RespValue};
-use crate::replication::anti_entropy::{AntiEntropyConfig, AntiEntropyManager, StateDigest};
+use crate::redis::{Command, CommandExecutor, RespValue, SDS};
+use crate::observability::{noop_metrics, SharedMetrics};
+use crate::replication::anti_entropy::{
+    deltas_wire_size, AntiEntropyConfig, AntiEntropyManager, StateDigest, SyncBandwidth,
+};
 use crate::replication::gossip::GossipState;
 use crate::replication::gossip_router::GossipRouter;
 use crate::replication::hash_ring::HashRing;
-use crate::replication::state::{ReplicationDelta, ShardReplicaState};
-use crate::replication::{ReplicaId, ReplicationConfig};
+use crate::replication::offsets::AppliedOffsets;
+use crate::replication::state::{
+    CrdtValue, DeltaRejection, ReplicatedValue, ReplicationDelta, ShardReplicaState,
+};
+use crate::replication::{
+    ConsistencyLevel, ElectionConfig, ReplicaId, ReplicationConfig, VectorClock,
+};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
 
@@ -35,6 +49,122 @@ pub struct InFlightMessage {
     pub to: usize,
     pub deltas: Vec<ReplicationDelta>,
     pub delivery_time: VirtualTime,
+    /// Primary write sequence numbers `(first, last)` this message carries
+    /// when read routing is enabled
+    pub primary_writes: Option<(u64, u64)>,
+    /// Replication offsets `(first, last)` of `deltas` under broadcast
+    /// gossip, acknowledged back to the sender on delivery
+    pub offsets: Option<(u64, u64)>,
+}
+
+/// Acknowledgement in flight: `from` holds every delta of `to` through
+/// `offset`
+#[derive(Debug, Clone)]
+pub struct InFlightAck {
+    pub from: usize,
+    pub to: usize,
+    pub offset: u64,
+    pub delivery_time: VirtualTime,
+}
+
+/// Salt for the seed of the acknowledgement RNG. Acks draw loss and delay
+/// from their own stream, so a seed's delta traffic is the same whether or
+/// not anyone waits on it.
+const ACK_RNG_SALT: u64 = 0x5741_4954;
+
+/// How a faulty node tampers with a delta it gossips. The forgery is sent
+/// right after the honest delta it was made from, in the same message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByzantineFault {
+    /// The value and timestamp are changed after the checksum was taken
+    CorruptChecksum,
+    /// A different value is resealed under the honest delta's timestamp
+    DuplicateTimestamp,
+    /// The timestamp moves past the honest one while the vector clock rolls
+    /// back one write of the replica that made it
+    RegressedVectorClock,
+}
+
+/// Value every forged delta carries
+pub const FORGED_VALUE: &str = "forged";
+
+impl ByzantineFault {
+    pub const ALL: [ByzantineFault; 3] = [
+        ByzantineFault::CorruptChecksum,
+        ByzantineFault::DuplicateTimestamp,
+        ByzantineFault::RegressedVectorClock,
+    ];
+
+    /// Forge a delta from an honest one. Only string values are forged, and
+    /// a vector clock can only roll back once the writer has an entry in it.
+    pub fn forge(self, honest: &ReplicationDelta) -> Option<ReplicationDelta> {
+        let CrdtValue::Lww(_) = honest.value.crdt else {
+            return None;
+        };
+        let mut value = honest.value.clone();
+        let lww = value.lww_mut().expect("checked above");
+        lww.value = Some(SDS::from_str(FORGED_VALUE));
+        lww.tombstone = false;
+
+        // A timestamp past the honest one, so the forgery would win LWW
+        let bump = |value: &mut ReplicatedValue| {
+            value.timestamp.time += 1;
+            let timestamp = value.timestamp;
+            value.lww_mut().expect("checked above").timestamp = timestamp;
+        };
+        match self {
+            ByzantineFault::CorruptChecksum => {
+                bump(&mut value);
+                Some(ReplicationDelta {
+                    value,
+                    ..honest.clone()
+                })
+            }
+            ByzantineFault::DuplicateTimestamp => Some(ReplicationDelta::new(
+                honest.key.clone(),
+                value,
+                honest.source_replica,
+            )),
+            ByzantineFault::RegressedVectorClock => {
+                let writer = value.timestamp.replica_id;
+                let clock = value.vector_clock.as_ref()?;
+                if clock.get(&writer) == 0 {
+                    return None;
+                }
+                let mut regressed = VectorClock::new();
+                for (replica, count) in clock.entries() {
+                    let count = if replica == writer { count - 1 } else { count };
+                    for _ in 0..count {
+                        regressed.increment(replica);
+                    }
+                }
+                value.vector_clock = Some(regressed);
+                bump(&mut value);
+                Some(ReplicationDelta::new(
+                    honest.key.clone(),
+                    value,
+                    honest.source_replica,
+                ))
+            }
+        }
+    }
+}
+
+/// Read routing: writes go to `primary`, reads go to a replica that lags
+/// the primary by at most `max_staleness`, or to the primary if none does
+#[derive(Debug, Clone, Copy)]
+pub struct ReadRouting {
+    pub primary: usize,
+    pub max_staleness: Duration,
+}
+
+impl ReadRouting {
+    pub fn new(primary: usize, max_staleness: Duration) -> Self {
+        ReadRouting {
+            primary,
+            max_staleness,
+        }
+    }
 }
 
 /// Simulated node in the cluster
@@ -45,6 +175,8 @@ pub struct SimulatedNode {
     pub replica_state: ShardReplicaState,
     pub gossip_state: GossipState,
     pub anti_entropy: AntiEntropyManager,
+    /// Remote deltas the replica state refused
+    pub rejected: Vec<DeltaRejection>,
 }
 
 impl SimulatedNode {
@@ -60,6 +192,7 @@ impl SimulatedNode {
             replica_state: ShardReplicaState::new(replica_id, config.consistency_level),
             gossip_state: GossipState::new(config),
             anti_entropy: AntiEntropyManager::new(replica_id, AntiEntropyConfig::default()),
+            rejected: Vec::new(),
         }
     }
 
@@ -82,6 +215,12 @@ impl SimulatedNode {
     pub fn execute(&mut self, cmd: &Command) -> RespValue {
         let response = self.executor.execute(cmd);
 
+        // Keys evicted to make room for this write replicate as DELs
+        self.record_evictions();
+        if matches!(response, RespValue::Error(_)) {
+            return response;
+        }
+
         // Record writes for replication
         match cmd {
             Command::Set { key, value, ex, .. } => {
@@ -100,6 +239,15 @@ impl SimulatedNode {
         response
     }
 
+    fn record_evictions(&mut self) {
+        for key in self.executor.take_evicted_keys() {
+            // Evicted and then written again by the same command
+            if !self.executor.get_data().contains_key(&key) {
+                self.replica_state.record_delete(key);
+            }
+        }
+    }
+
     /// Collect pending deltas for gossip
     pub fn drain_deltas(&mut self) -> Vec<ReplicationDelta> {
         self.replica_state.drain_pending_deltas()
@@ -109,13 +257,17 @@ impl SimulatedNode {
     ///
     /// Uses the CRDT-merged value from replica_state (not the incoming delta)
     /// to update the executor. This prevents stale deltas from overwriting
-    /// newer local values in the executor.
+    /// newer local values in the executor. Refused deltas are kept in
+    /// `rejected` and touch neither.
     pub fn apply_remote_deltas(&mut self, deltas: Vec<ReplicationDelta>) {
         for delta in deltas {
             let key = delta.key.clone();
 
             // Apply to replica state (CRDT merge)
-            self.replica_state.apply_remote_delta(delta);
+            if let Err(rejection) = self.replica_state.apply_remote_delta(delta) {
+                self.rejected.push(rejection);
+                continue;
+            }
 
             // Read back the MERGED result to update executor
             if let Some(merged) = self.replica_state.replicated_keys.get(&key) {
@@ -129,6 +281,7 @@ impl SimulatedNode {
                     let _ = self.executor.execute(&Command::del(key.clone()));
                 }
             }
+            self.record_evictions();
         }
     }
 
@@ -152,10 +305,17 @@ pub struct MultiNodeSimulation {
     pub rng: DeterministicRng,
     /// Messages in flight (delayed delivery)
     pub message_queue: VecDeque<InFlightMessage>,
+    /// Acknowledgements in flight, for WAIT
+    pub ack_queue: VecDeque<InFlightAck>,
+    /// Loss and delay of acknowledgements
+    ack_rng: DeterministicRng,
     /// Network partitions: (node_a, node_b) pairs that can't communicate
     pub partitions: HashSet<(usize, usize)>,
     /// Packet loss probability (0.0 - 1.0)
     pub packet_loss_rate: f64,
+    /// Per-link packet loss overriding `packet_loss_rate`, keyed by
+    /// (lower node, higher node)
+    pub link_loss: HashMap<(usize, usize), f64>,
     /// Message delay range in ms
     pub message_delay_range: (u64, u64),
     /// Operation history for linearizability checking
@@ -168,6 +328,25 @@ pub struct MultiNodeSimulation {
     pub auto_anti_entropy: bool,
     /// Anti-entropy sync statistics
     pub anti_entropy_syncs: u64,
+    /// Receives a sample for every anti-entropy round
+    pub metrics: SharedMetrics,
+    /// Faulty nodes and the chance each delta they gossip is followed by a
+    /// forgery
+    pub byzantine_nodes: HashMap<usize, f64>,
+    /// Forged deltas put on the wire, by fault
+    pub forged_deltas: HashMap<ByzantineFault, u64>,
+    /// Replica read routing, if enabled
+    pub read_routing: Option<ReadRouting>,
+    /// Routed reads that found no replica within the staleness bound
+    pub read_routing_fallbacks: u64,
+    /// Virtual time of each replicated primary write (sequence number = index + 1)
+    primary_write_times: Vec<VirtualTime>,
+    /// Highest primary write sequence number handed to gossip
+    primary_gossiped_through: u64,
+    /// Per-node view of which primary writes have been applied
+    replica_progress: Vec<AppliedOffsets>,
+    /// Leader election, in the strongly-consistent mode
+    pub elections: Option<ElectionCluster>,
 }
 
 impl MultiNodeSimulation {
@@ -185,20 +364,33 @@ impl MultiNodeSimulation {
                 SimulatedNode::new(i, config)
             })
             .collect();
+        let nodes = with_wait_peers(nodes);
 
         MultiNodeSimulation {
             nodes,
             current_time: VirtualTime::ZERO,
             rng: DeterministicRng::new(seed),
             message_queue: VecDeque::new(),
+            ack_queue: VecDeque::new(),
+            ack_rng: DeterministicRng::new(seed ^ ACK_RNG_SALT),
             partitions: HashSet::new(),
             packet_loss_rate: 0.0,
+            link_loss: HashMap::new(),
             message_delay_range: (1, 10),
             history: Vec::new(),
             hash_ring: None,
             gossip_routers: HashMap::new(),
             auto_anti_entropy: true,
             anti_entropy_syncs: 0,
+            metrics: noop_metrics(),
+            byzantine_nodes: HashMap::new(),
+            forged_deltas: HashMap::new(),
+            read_routing: None,
+            read_routing_fallbacks: 0,
+            primary_write_times: Vec::new(),
+            primary_gossiped_through: 0,
+            replica_progress: vec![AppliedOffsets::default(); num_nodes],
+            elections: None,
         }
     }
 
@@ -240,6 +432,7 @@ impl MultiNodeSimulation {
                 SimulatedNode::new(i, config)
             })
             .collect();
+        let nodes = with_wait_peers(nodes);
 
         // Create gossip routers for each node
         let mut gossip_routers = HashMap::new();
@@ -263,46 +456,142 @@ impl MultiNodeSimulation {
             current_time: VirtualTime::ZERO,
             rng: DeterministicRng::new(seed),
             message_queue: VecDeque::new(),
+            ack_queue: VecDeque::new(),
+            ack_rng: DeterministicRng::new(seed ^ ACK_RNG_SALT),
             partitions: HashSet::new(),
             packet_loss_rate: 0.0,
+            link_loss: HashMap::new(),
             message_delay_range: (1, 10),
             history: Vec::new(),
             hash_ring: Some(hash_ring),
             gossip_routers,
             auto_anti_entropy: true,
             anti_entropy_syncs: 0,
+            metrics: noop_metrics(),
+            byzantine_nodes: HashMap::new(),
+            forged_deltas: HashMap::new(),
+            read_routing: None,
+            read_routing_fallbacks: 0,
+            primary_write_times: Vec::new(),
+            primary_gossiped_through: 0,
+            replica_progress: vec![AppliedOffsets::default(); num_nodes],
+            elections: None,
         }
     }
 
+    /// Route reads to replicas within `routing.max_staleness` of the primary
+    ///
+    /// Requires broadcast gossip: with selective gossip a replica never
+    /// receives keys it doesn't own, so its lag is unbounded for those keys.
+    pub fn with_read_routing(mut self, routing: ReadRouting) -> Self {
+        debug_assert!(
+            routing.primary < self.nodes.len(),
+            "Precondition: primary {} out of range",
+            routing.primary
+        );
+        debug_assert!(
+            self.hash_ring.is_none(),
+            "Precondition: read routing requires broadcast gossip"
+        );
+        self.read_routing = Some(routing);
+        self
+    }
+
     /// Set packet loss rate (0.0 - 1.0)
     pub fn with_packet_loss(mut self, rate: f64) -> Self {
         self.packet_loss_rate = rate.clamp(0.0, 1.0);
         self
     }
 
+    /// Set packet loss on the link between two nodes, in both directions
+    pub fn with_link_loss(mut self, node_a: usize, node_b: usize, rate: f64) -> Self {
+        self.link_loss
+            .insert((node_a.min(node_b), node_a.max(node_b)), rate.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Use this anti-entropy configuration on every node
+    pub fn with_anti_entropy_config(mut self, config: AntiEntropyConfig) -> Self {
+        for node in &mut self.nodes {
+            node.anti_entropy = AntiEntropyManager::new(node.replica_id, config.clone());
+        }
+        self
+    }
+
+    /// Send anti-entropy round samples to `metrics`
+    pub fn with_metrics(mut self, metrics: SharedMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Run every node at this consistency level. Call before any writes.
+    /// `Strong` also starts leader election, with default timing.
+    pub fn with_consistency_level(mut self, level: ConsistencyLevel) -> Self {
+        for node in &mut self.nodes {
+            debug_assert!(
+                node.replica_state.replicated_keys.is_empty(),
+                "Precondition: consistency level must be set before writes"
+            );
+            node.replica_state = ShardReplicaState::new(node.replica_id, level);
+        }
+        self.elections = (level == ConsistencyLevel::Strong).then(|| {
+            ElectionCluster::new(
+                self.nodes.len(),
+                ElectionConfig::default(),
+                &mut self.rng,
+                self.current_time,
+            )
+        });
+        self
+    }
+
+    /// Make `node` faulty: each delta it gossips is followed, with the given
+    /// probability, by a forgery of it
+    pub fn with_byzantine_node(mut self, node: usize, forge_rate: f64) -> Self {
+        debug_assert!(node < self.nodes.len(), "Precondition: node must exist");
+        self.byzantine_nodes
+            .insert(node, forge_rate.clamp(0.0, 1.0));
+        self
+    }
+
     /// Set message delay range in ms
     pub fn with_message_delay(mut self, min_ms: u64, max_ms: u64) -> Self {
         self.message_delay_range = (min_ms, max_ms);
         self
     }
 
-    /// Advance simulation time
+    /// Advance simulation time, running any elections due meanwhile
     pub fn advance_time(&mut self, new_time: VirtualTime) {
         debug_assert!(new_time >= self.current_time, "Time cannot go backwards");
+        if self.elections.is_some() {
+            self.run_elections_until(new_time);
+        }
         self.current_time = new_time;
     }
 
     /// Advance time by milliseconds
     pub fn advance_time_ms(&mut self, ms: u64) {
-        self.current_time = self.current_time + Duration::from_millis(ms);
+        self.advance_time(self.current_time + Duration::from_millis(ms));
     }
 
     /// Execute a command on a specific node
     pub fn execute(&mut self, client_id: usize, node_id: usize, cmd: Command) -> RespValue {
         let invoke_time = self.current_time;
-        let response = self.nodes[node_id].execute(&cmd);
+        let response = if let Command::Wait(numreplicas, timeout_ms) = cmd {
+            RespValue::Integer(self.wait(node_id, numreplicas, timeout_ms) as i64)
+        } else if self.elections.is_some() {
+            self.execute_strong(node_id, &cmd)
+        } else {
+            self.nodes[node_id].execute(&cmd)
+        };
         let complete_time = self.current_time;
 
+        let is_primary = self.read_routing.map(|r| r.primary) == Some(node_id);
+        if is_primary && matches!(cmd, Command::Set { .. } | Command::Del(_)) {
+            self.primary_write_times.push(complete_time);
+            self.replica_progress[node_id].sync_to(self.primary_write_times.len() as u64);
+        }
+
         self.history.push(TimestampedOperation {
             client_id,
             node_id,
@@ -315,6 +604,42 @@ impl MultiNodeSimulation {
         response
     }
 
+    /// Execute a command through read routing: writes go to the primary,
+    /// reads to a random replica within the staleness bound
+    pub fn execute_routed(&mut self, client_id: usize, cmd: Command) -> RespValue {
+        let routing = self
+            .read_routing
+            .expect("Precondition: read routing not enabled");
+        let node_id = if cmd.is_read_only() {
+            self.pick_read_replica(routing)
+        } else {
+            routing.primary
+        };
+        self.execute(client_id, node_id, cmd)
+    }
+
+    fn pick_read_replica(&mut self, routing: ReadRouting) -> usize {
+        let fresh: Vec<usize> = (0..self.nodes.len())
+            .filter(|&n| n != routing.primary)
+            .filter(|&n| self.replica_staleness(n) <= routing.max_staleness)
+            .collect();
+        if fresh.is_empty() {
+            self.read_routing_fallbacks += 1;
+            return routing.primary;
+        }
+        fresh[self.rng.gen_range(0, fresh.len() as u64) as usize]
+    }
+
+    /// How far behind the primary a node is: the time since the oldest
+    /// primary write it has not applied, or zero if it holds them all
+    pub fn replica_staleness(&self, node_id: usize) -> Duration {
+        let applied = self.replica_progress[node_id].applied_through() as usize;
+        match self.primary_write_times.get(applied) {
+            Some(&oldest_missing) => self.current_time - oldest_missing,
+            None => Duration::ZERO,
+        }
+    }
+
     /// Create a network partition between two nodes
     pub fn partition(&mut self, node_a: usize, node_b: usize) {
         let (a, b) = if node_a < node_b {
@@ -343,10 +668,14 @@ impl MultiNodeSimulation {
     }
 
     /// Run anti-entropy sync between two nodes
+    ///
+    /// Both nodes meter the round (digests plus any deltas shipped) and,
+    /// when adaptive, reschedule their next round with each other.
     pub fn run_anti_entropy_sync(&mut self, node_a: usize, node_b: usize) {
         // Get digests from both nodes
         let digest_a = self.nodes[node_a].generate_digest();
         let digest_b = self.nodes[node_b].generate_digest();
+        let mut traffic_a = SyncBandwidth::round(digest_a.wire_size(), digest_b.wire_size(), 0, 0);
 
         // Check if digests differ
         if digest_a.differs_from(&digest_b) {
@@ -364,6 +693,11 @@ impl MultiNodeSimulation {
                     &divergent,
                 );
 
+                traffic_a.bytes_sent += deltas_wire_size(&deltas_a);
+                traffic_a.bytes_received += deltas_wire_size(&deltas_b);
+                traffic_a.keys_sent = deltas_a.len() as u64;
+                traffic_a.keys_received = deltas_b.len() as u64;
+
                 // Apply deltas bidirectionally
                 self.nodes[node_b].apply_remote_deltas(deltas_a);
                 self.nodes[node_a].apply_remote_deltas(deltas_b);
@@ -371,6 +705,41 @@ impl MultiNodeSimulation {
                 self.anti_entropy_syncs += 1;
             }
         }
+
+        // Each side sees the other's sent traffic as received
+        let traffic_b = SyncBandwidth {
+            bytes_sent: traffic_a.bytes_received,
+            bytes_received: traffic_a.bytes_sent,
+            keys_sent: traffic_a.keys_received,
+            keys_received: traffic_a.keys_sent,
+            ..traffic_a
+        };
+        let now = self.current_time.as_millis();
+        for (node, peer, traffic) in [(node_a, node_b, traffic_a), (node_b, node_a, traffic_b)] {
+            let peer_id = self.nodes[peer].replica_id;
+            let next_interval = self.nodes[node]
+                .anti_entropy
+                .record_round(peer_id, traffic, now);
+            if node == node_a {
+                self.metrics.record_anti_entropy_round(
+                    peer_id.0,
+                    traffic.bytes_sent,
+                    traffic.bytes_received,
+                    traffic.keys_sent + traffic.keys_received,
+                    next_interval,
+                );
+            }
+        }
+
+        // Either way both nodes now hold each other's state
+        if let Some(routing) = self.read_routing {
+            let synced = self.replica_progress[routing.primary].applied_through();
+            if node_a == routing.primary {
+                self.replica_progress[node_b].sync_to(synced);
+            } else if node_b == routing.primary {
+                self.replica_progress[node_a].sync_to(synced);
+            }
+        }
     }
 
     /// Run full anti-entropy sync across all connected node pairs
@@ -386,6 +755,36 @@ impl MultiNodeSimulation {
         }
     }
 
+    /// Run anti-entropy on every connected pair whose sync interval has
+    /// elapsed, as seen by the lower-numbered node. Returns the rounds run.
+    pub fn run_scheduled_anti_entropy(&mut self) -> usize {
+        let num_nodes = self.nodes.len();
+        let now = self.current_time.as_millis();
+        let mut rounds = 0;
+
+        for i in 0..num_nodes {
+            for j in (i + 1)..num_nodes {
+                let peer = self.nodes[j].replica_id;
+                if self.can_communicate(i, j) && self.nodes[i].anti_entropy.should_sync(peer, now)
+                {
+                    self.run_anti_entropy_sync(i, j);
+                    rounds += 1;
+                }
+            }
+        }
+        rounds
+    }
+
+    /// Anti-entropy traffic summed over all nodes. Both sides record each
+    /// round, so `bytes_sent` is the total put on the wire.
+    pub fn anti_entropy_bandwidth(&self) -> SyncBandwidth {
+        let mut total = SyncBandwidth::default();
+        for node in &self.nodes {
+            total.add(&node.anti_entropy.total_bandwidth);
+        }
+        total
+    }
+
     /// Check if two nodes can communicate
     pub fn can_communicate(&self, node_a: usize, node_b: usize) -> bool {
         let (a, b) = if node_a < node_b {
@@ -406,10 +805,25 @@ impl MultiNodeSimulation {
             node_deltas.push(node.drain_deltas());
         }
 
+        // Sequence numbers of the primary writes drained this round
+        let primary = self.read_routing.map(|r| r.primary);
+        let primary_seq = self.primary_write_times.len() as u64;
+        let primary_batch = (primary.is_some() && primary_seq > self.primary_gossiped_through)
+            .then_some((self.primary_gossiped_through + 1, primary_seq));
+        self.primary_gossiped_through = primary_seq;
+
         // Route deltas based on mode (selective vs broadcast)
         for from_node in 0..num_nodes {
             let deltas = &node_deltas[from_node];
+            let batch = primary_batch.filter(|_| Some(from_node) == primary);
             if deltas.is_empty() {
+                // Writes that changed nothing (DEL of a missing key) have
+                // nothing to ship; every replica already agrees with them
+                if let Some((first, last)) = batch {
+                    for progress in &mut self.replica_progress {
+                        progress.deliver(first, last);
+                    }
+                }
                 continue;
             }
 
@@ -418,13 +832,17 @@ impl MultiNodeSimulation {
                 let routing_table = router.route_deltas(deltas.clone());
                 for (target_replica, target_deltas) in routing_table {
                     let to_node = target_replica.0 as usize - 1;
-                    self.send_deltas(from_node, to_node, target_deltas);
+                    self.send_deltas(from_node, to_node, target_deltas, None, None);
                 }
             } else {
                 // Broadcast gossip: send to all other nodes
+                let offsets = self.nodes[from_node]
+                    .gossip_state
+                    .offsets
+                    .advance(deltas.len());
                 for to_node in 0..num_nodes {
                     if to_node != from_node {
-                        self.send_deltas(from_node, to_node, deltas.clone());
+                        self.send_deltas(from_node, to_node, deltas.clone(), batch, offsets);
                     }
                 }
             }
@@ -435,17 +853,31 @@ impl MultiNodeSimulation {
     }
 
     /// Send deltas from one node to another (with delay and possible loss)
-    fn send_deltas(&mut self, from: usize, to: usize, deltas: Vec<ReplicationDelta>) {
+    fn send_deltas(
+        &mut self,
+        from: usize,
+        to: usize,
+        deltas: Vec<ReplicationDelta>,
+        primary_writes: Option<(u64, u64)>,
+        offsets: Option<(u64, u64)>,
+    ) {
         // Check partition
         if !self.can_communicate(from, to) {
             return; // Message dropped due to partition
         }
 
         // Check packet loss
-        if self.rng.gen_bool(self.packet_loss_rate) {
+        let loss = self
+            .link_loss
+            .get(&(from.min(to), from.max(to)))
+            .copied()
+            .unwrap_or(self.packet_loss_rate);
+        if self.rng.gen_bool(loss) {
             return; // Message dropped due to packet loss
         }
 
+        let deltas = self.forge_deltas(from, deltas);
+
         // Calculate delivery time
         let delay_ms = self
             .rng
@@ -457,9 +889,70 @@ impl MultiNodeSimulation {
             to,
             deltas,
             delivery_time,
+            primary_writes,
+            offsets,
+        });
+    }
+
+    /// Acknowledge `to`'s offsets through `offset` (with delay and possible
+    /// loss, like deltas)
+    fn send_ack(&mut self, from: usize, to: usize, offset: u64) {
+        if !self.can_communicate(from, to) {
+            return;
+        }
+        let loss = self
+            .link_loss
+            .get(&(from.min(to), from.max(to)))
+            .copied()
+            .unwrap_or(self.packet_loss_rate);
+        if self.ack_rng.gen_bool(loss) {
+            return;
+        }
+        let delay_ms = self
+            .ack_rng
+            .gen_range(self.message_delay_range.0, self.message_delay_range.1 + 1);
+        self.ack_queue.push_back(InFlightAck {
+            from,
+            to,
+            offset,
+            delivery_time: self.current_time + Duration::from_millis(delay_ms),
         });
     }
 
+    /// Follow some of a faulty node's deltas with forgeries, falling back to
+    /// a corrupted checksum where the chosen fault does not apply
+    fn forge_deltas(
+        &mut self,
+        from: usize,
+        deltas: Vec<ReplicationDelta>,
+    ) -> Vec<ReplicationDelta> {
+        let Some(&rate) = self.byzantine_nodes.get(&from) else {
+            return deltas;
+        };
+        let mut sent = Vec::with_capacity(deltas.len());
+        for delta in deltas {
+            let forged = if self.rng.gen_bool(rate) {
+                let fault = ByzantineFault::ALL
+                    [self.rng.gen_range(0, ByzantineFault::ALL.len() as u64) as usize];
+                fault
+                    .forge(&delta)
+                    .map(|forged| (fault, forged))
+                    .or_else(|| {
+                        let fault = ByzantineFault::CorruptChecksum;
+                        fault.forge(&delta).map(|forged| (fault, forged))
+                    })
+            } else {
+                None
+            };
+            sent.push(delta);
+            if let Some((fault, forged)) = forged {
+                *self.forged_deltas.entry(fault).or_insert(0) += 1;
+                sent.push(forged);
+            }
+        }
+        sent
+    }
+
     /// Deliver all messages that are ready
     fn deliver_messages(&mut self) {
         let mut delivered = Vec::new();
@@ -474,12 +967,81 @@ impl MultiNodeSimulation {
             }
         }
 
+        // Acks sent in earlier rounds
+        while let Some(ack) = self.ack_queue.pop_front() {
+            if ack.delivery_time <= self.current_time && self.can_communicate(ack.from, ack.to) {
+                let from_replica = self.nodes[ack.from].replica_id;
+                self.nodes[ack.to]
+                    .gossip_state
+                    .offsets
+                    .record_ack(from_replica, ack.offset);
+            } else {
+                self.ack_queue.push_front(ack);
+                break;
+            }
+        }
+
         // Apply deltas
         for msg in delivered {
             self.nodes[msg.to].apply_remote_deltas(msg.deltas);
+            if let Some((first, last)) = msg.primary_writes {
+                self.replica_progress[msg.to].deliver(first, last);
+            }
+            if let Some((first, last)) = msg.offsets {
+                let source = self.nodes[msg.from].replica_id;
+                let progress = self.nodes[msg.to].gossip_state.offsets.deliver(source, first, last);
+                if let Some(offset) = progress {
+                    self.send_ack(msg.to, msg.from, offset);
+                }
+            }
         }
     }
 
+    /// WAIT on `node_id`: run gossip rounds until `numreplicas` peers have
+    /// acknowledged every write made there so far, or `timeout_ms` of
+    /// virtual time passes. Returns how many did.
+    ///
+    /// Without a timeout (0) it also returns once nothing is left in
+    /// flight: the simulation is blocked here, so nothing else can happen.
+    pub fn wait(&mut self, node_id: usize, numreplicas: i64, timeout_ms: i64) -> usize {
+        debug_assert!(timeout_ms >= 0, "Precondition: WAIT timeout must not be negative");
+        let node = &self.nodes[node_id];
+        // Selective gossip numbers nothing (see replication::offsets)
+        let unsent = if self.gossip_routers.contains_key(&node_id) {
+            0
+        } else {
+            node.replica_state.pending_deltas.len() as u64
+        };
+        let target = node.gossip_state.offsets.offset() + unsent;
+        let deadline =
+            (timeout_ms > 0).then(|| self.current_time + Duration::from_millis(timeout_ms as u64));
+
+        loop {
+            let acked = self.nodes[node_id].gossip_state.offsets.replicas_acked(target);
+            if acked as i64 >= numreplicas {
+                return acked;
+            }
+            let step = match deadline {
+                Some(deadline) if self.current_time >= deadline => return acked,
+                Some(deadline) => (deadline - self.current_time).as_millis().min(10),
+                None if self.is_quiet() => return acked,
+                None => 10,
+            };
+            self.advance_time_ms(step);
+            self.gossip_round();
+        }
+    }
+
+    /// Nothing in flight and nothing left to gossip
+    fn is_quiet(&self) -> bool {
+        self.message_queue.is_empty()
+            && self.ack_queue.is_empty()
+            && self
+                .nodes
+                .iter()
+                .all(|node| node.replica_state.pending_deltas.is_empty())
+    }
+
     /// Run multiple gossip rounds until convergence or max rounds
     pub fn converge(&mut self, max_rounds: usize) -> bool {
         for _ in 0..max_rounds {
@@ -525,6 +1087,20 @@ impl MultiNodeSimulation {
     }
 }
 
+/// Count every other node as a replica for WAIT before it acknowledges
+/// anything, as a connected replica is in Redis
+fn with_wait_peers(mut nodes: Vec<SimulatedNode>) -> Vec<SimulatedNode> {
+    let replicas: Vec<ReplicaId> = nodes.iter().map(|node| node.replica_id).collect();
+    for node in &mut nodes {
+        for &peer in &replicas {
+            if peer != node.replica_id {
+                node.gossip_state.offsets.add_peer(peer);
+            }
+        }
+    }
+    nodes
+}
+
 /// Result of a linearizability check
 #[derive(Debug)]
 pub struct LinearizabilityResult {
@@ -564,7 +1140,8 @@ pub fn check_single_key_linearizability(
 
     for op in &ops {
         match &op.command {
-            Command::Set { value, .. } => {
+            // A refused write took no effect
+            Command::Set { value, .. } if !matches!(op.response, RespValue::Error(_)) => {
                 expected_value = Some(String::from_utf8_lossy(value.as_bytes()).to_string());
             }
             Command::Get(_) => {
@@ -590,6 +1167,100 @@ pub fn check_single_key_linearizability(
     }
 }
 
+/// Result of a read-staleness check
+#[derive(Debug)]
+pub struct StalenessResult {
+    pub is_bounded: bool,
+    /// GETs served by a node other than the primary
+    pub replica_reads: usize,
+    pub violations: Vec<String>,
+}
+
+/// Check that every GET served by a replica returned a value the primary
+/// held at some instant within `max_staleness` before the read
+///
+/// A replica missing a primary write made at `w` still reflects the
+/// primary as it was just before `w`, which is what `replica_staleness`
+/// measures from.
+pub fn check_read_staleness(
+    history: &[TimestampedOperation],
+    primary: usize,
+    max_staleness: Duration,
+) -> StalenessResult {
+    // Primary writes per key, in history (and therefore time) order
+    let mut writes: HashMap<&str, Vec<(VirtualTime, Option<&[u8]>)>> = HashMap::new();
+    for op in history.iter().filter(|op| op.node_id == primary) {
+        match &op.command {
+            Command::Set { key, value, .. } => writes
+                .entry(key.as_str())
+                .or_default()
+                .push((op.complete_time, Some(value.as_bytes()))),
+            Command::Del(keys) => {
+                for key in keys {
+                    writes
+                        .entry(key.as_str())
+                        .or_default()
+                        .push((op.complete_time, None));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut replica_reads = 0;
+    let mut violations = Vec::new();
+    for op in history.iter().filter(|op| op.node_id != primary) {
+        let Command::Get(key) = &op.command else {
+            continue;
+        };
+        let got = match &op.response {
+            RespValue::BulkString(Some(data)) => Some(data.as_slice()),
+            RespValue::BulkString(None) => None,
+            _ => continue,
+        };
+        replica_reads += 1;
+
+        let read_at = op.invoke_time;
+        let lower = VirtualTime::from_millis(
+            read_at
+                .as_millis()
+                .saturating_sub(max_staleness.as_millis()),
+        );
+        let key_writes = writes.get(key.as_str()).map_or(&[][..], Vec::as_slice);
+
+        // The value in place at `lower`, plus every write made since
+        let before_window = key_writes
+            .iter()
+            .take_while(|(time, _)| *time < lower)
+            .last()
+            .and_then(|&(_, value)| value);
+        let mut acceptable = vec![before_window];
+        acceptable.extend(
+            key_writes
+                .iter()
+                .filter(|(time, _)| *time >= lower && *time <= read_at)
+                .map(|&(_, value)| value),
+        );
+
+        if !acceptable.contains(&got) {
+            violations.push(format!(
+                "GET {} on node {} at {:?} returned {:?}, older than {:?} allows",
+                key,
+                op.node_id,
+                read_at,
+                got.map(String::from_utf8_lossy),
+                max_staleness
+            ));
+        }
+    }
+
+    StalenessResult {
+        is_bounded: violations.is_empty(),
+        replica_reads,
+        violations,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -712,6 +1383,63 @@ mod tests {
         // In practice, you'd want to keep trying or have retransmission
     }
 
+    #[test]
+    fn test_primary_evictions_replicate_as_deletes() {
+        for seed in 0..10 {
+            let mut sim = MultiNodeSimulation::new(3, seed).with_packet_loss(0.2);
+
+            // Only the primary is memory-constrained: replicas never evict, so
+            // any key they hold that the primary lacks was resurrected
+            for (param, value) in [("maxmemory-policy", "allkeys-random"), ("maxmemory", "2kb")] {
+                let cmd = Command::ConfigSet(param.to_string(), value.to_string());
+                assert_eq!(sim.execute(1, 0, cmd), RespValue::ok());
+            }
+
+            for i in 0..300 {
+                let key = format!("key_{}", sim.rng.gen_range(0, 120));
+                let value = SDS::from_str(&format!("value_{}_{}", seed, i));
+                assert_eq!(sim.execute(1, 0, Command::set(key, value)), RespValue::ok());
+                if i % 5 == 0 {
+                    sim.advance_time_ms(10);
+                    sim.gossip_round();
+                }
+            }
+
+            // Repair what packet loss dropped, then let the last tombstones land
+            sim.packet_loss_rate = 0.0;
+            sim.converge(20);
+            sim.run_full_anti_entropy();
+            sim.converge(20);
+
+            let primary = &sim.nodes[0].executor;
+            assert!(primary.evicted_keys_total() > 0, "Seed {}: nothing evicted", seed);
+            assert!(primary.used_memory() <= primary.maxmemory() + 128);
+            for node in &sim.nodes[1..] {
+                for (key, value) in node.executor.get_data() {
+                    assert_eq!(
+                        primary.get_data().get(key),
+                        Some(value),
+                        "Seed {}: node {} holds evicted key {}",
+                        seed,
+                        node.node_id,
+                        key
+                    );
+                }
+                assert_eq!(node.executor.get_data().len(), primary.get_data().len());
+            }
+            for i in 0..120 {
+                let key = format!("key_{}", i);
+                assert!(
+                    sim.check_key_convergence(&key),
+                    "Seed {}: {} diverged: {:?}",
+                    seed,
+                    key,
+                    sim.get_all_values(&key)
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_selective_gossip_message_reduction() {
         let sim = MultiNodeSimulation::new_partitioned(10, 3, 42);
@@ -773,4 +1501,127 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_replica_reads_stay_within_staleness_bound() {
+        let bound = Duration::from_millis(40);
+        for seed in 0..20 {
+            let mut sim = MultiNodeSimulation::new(4, seed)
+                .with_message_delay(5, 30)
+                .with_packet_loss(0.1)
+                .with_read_routing(ReadRouting::new(0, bound));
+
+            for step in 0..300 {
+                let key = format!("key{}", sim.rng.gen_range(0, 3));
+                let cmd = if sim.rng.gen_bool(0.3) {
+                    Command::set(key, SDS::from_str(&format!("v{}", step)))
+                } else {
+                    Command::Get(key)
+                };
+                sim.execute_routed(1, cmd);
+                let step_ms = sim.rng.gen_range(1, 8);
+                sim.advance_time_ms(step_ms);
+                sim.gossip_round();
+                if step % 50 == 49 {
+                    sim.run_full_anti_entropy();
+                }
+            }
+
+            let result = check_read_staleness(&sim.history, 0, bound);
+            assert!(
+                result.is_bounded,
+                "Seed {}: {:?}",
+                seed,
+                result.violations
+            );
+            assert!(result.replica_reads > 0, "Seed {} never read a replica", seed);
+        }
+    }
+
+    #[test]
+    fn test_lagging_replica_falls_back_to_primary() {
+        let bound = Duration::from_millis(20);
+        let mut sim =
+            MultiNodeSimulation::new(2, 42).with_read_routing(ReadRouting::new(0, bound));
+        sim.partition(0, 1);
+        sim.execute_routed(1, Command::set("k".into(), SDS::from_str("v1")));
+        sim.gossip_round();
+
+        sim.advance_time_ms(10);
+        assert_eq!(sim.replica_staleness(1), Duration::from_millis(10));
+        sim.execute_routed(1, Command::Get("k".into()));
+        assert_eq!(sim.history.last().unwrap().node_id, 1);
+
+        sim.advance_time_ms(20);
+        let reply = sim.execute_routed(1, Command::Get("k".into()));
+        assert_eq!(sim.history.last().unwrap().node_id, 0);
+        assert_eq!(reply, RespValue::BulkString(Some(b"v1".to_vec())));
+        assert_eq!(sim.read_routing_fallbacks, 1);
+
+        // Healing runs anti-entropy, which brings the replica up to date
+        sim.heal_partition(0, 1);
+        assert_eq!(sim.replica_staleness(1), Duration::ZERO);
+        sim.execute_routed(1, Command::Get("k".into()));
+        assert_eq!(sim.history.last().unwrap().node_id, 1);
+
+        let result = check_read_staleness(&sim.history, 0, bound);
+        assert!(result.is_bounded, "{:?}", result.violations);
+        assert_eq!(result.replica_reads, 2);
+    }
+
+    #[test]
+    fn test_staleness_check_flags_unrouted_stale_read() {
+        let bound = Duration::from_millis(20);
+        let mut sim = MultiNodeSimulation::new_without_anti_entropy(2, 42)
+            .with_read_routing(ReadRouting::new(0, bound));
+        sim.partition(0, 1);
+        sim.execute(1, 0, Command::set("k".into(), SDS::from_str("v1")));
+        sim.advance_time_ms(30);
+
+        // Bypass the router and read the lagging replica directly
+        sim.execute(2, 1, Command::Get("k".into()));
+        let result = check_read_staleness(&sim.history, 0, bound);
+        assert!(!result.is_bounded);
+        assert_eq!(result.violations.len(), 1);
+
+        // A looser bound covers the same read
+        let loose = check_read_staleness(&sim.history, 0, Duration::from_millis(30));
+        assert!(loose.is_bounded, "{:?}", loose.violations);
+    }
+
+    #[test]
+    fn test_wait_blocks_until_replicas_ack() {
+        let mut sim = MultiNodeSimulation::new(3, 42);
+        // Every peer counts as connected before any write
+        assert_eq!(sim.execute(1, 0, Command::Wait(2, 0)), RespValue::Integer(2));
+
+        sim.execute(1, 0, Command::set("k".into(), SDS::from_str("v")));
+        let started = sim.current_time;
+        assert_eq!(sim.execute(1, 0, Command::Wait(2, 1000)), RespValue::Integer(2));
+        assert!(sim.current_time > started, "WAIT must wait for the acks");
+        assert!(sim.current_time < started + Duration::from_millis(1000));
+        for node in &sim.nodes {
+            assert_eq!(node.get_replicated_value("k"), Some("v".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_wait_times_out_on_partitioned_replica() {
+        let mut sim = MultiNodeSimulation::new_without_anti_entropy(3, 42);
+        sim.partition(0, 2);
+        sim.execute(1, 0, Command::set("k".into(), SDS::from_str("v")));
+
+        let started = sim.current_time;
+        assert_eq!(sim.execute(1, 0, Command::Wait(2, 100)), RespValue::Integer(1));
+        assert_eq!(sim.current_time, started + Duration::from_millis(100));
+
+        // Without a timeout it gives up once the network goes quiet
+        assert_eq!(sim.execute(1, 0, Command::Wait(2, 0)), RespValue::Integer(1));
+
+        // A replica that misses a batch stays behind even after healing
+        sim.heal_partition(0, 2);
+        sim.execute(1, 0, Command::set("k2".into(), SDS::from_str("v")));
+        assert_eq!(sim.wait(0, 2, 100), 1);
+        assert_eq!(sim.nodes[2].get_replicated_value("k2"), Some("v".to_string()));
+    }
 }
@@ -0,0 +1,598 @@
+//! Workload Replay
+//!
+//! Replays traffic captured in production against a simulated server, so
+//! real request mixes can drive DST runs and capacity experiments.
+//!
+//! # Capture formats
+//!
+//! **MONITOR** output, as `redis-cli MONITOR > capture.txt` writes it:
+//!
+//! ```text
+//! 1339518083.107412 [0 127.0.0.1:60866] "set" "greeting" "hello \"world\""
+//! ```
+//!
+//! Lines without a timestamp (redis-cli's leading `OK`) are skipped, and so
+//! are `[0 lua]` lines: they are the effects of an EVAL the capture already
+//! holds.
+//!
+//! **Audit log**: JSON lines with a microsecond timestamp, the connection and
+//! the command's arguments:
+//!
+//! ```json
+//! {"ts_us": 1339518083107412, "client": "127.0.0.1:60866", "args": ["set", "k", "v"]}
+//! ```
+//!
+//! # Replay
+//!
+//! Every captured connection becomes one simulated client that sends its
+//! commands in capture order, each at its offset from the first command
+//! divided by `speedup` (time compression). Like a connection that does not
+//! pipeline, a client sends a command only once the previous one has been
+//! answered: when the server falls behind, commands leave late and the lag
+//! shows in the response times. The server is `RedisServer`, charging each
+//! command its `LatencyProfile` execution time.
+//!
+//! Commands the parser rejects are counted and not sent. The same capture
+//! and config always replay the same way, so a replay that breaks an
+//! invariant is a DST seed like any other.
+
+use super::{
+    Duration, EventType, HostId, LatencyProfile, Simulation, SimulationConfig, VirtualTime,
+};
+use crate::redis::{Command, QueueStats, RedisClient, RedisServer, RespParser, RespValue};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+/// Virtual time the server gets to answer after the last command is due
+const DRAIN_WINDOW: Duration = Duration(3_600_000);
+
+/// How a capture is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    /// `redis-cli MONITOR` output
+    Monitor,
+    /// JSON lines: `ts_us`, `client`, `args`
+    AuditLog,
+}
+
+impl FromStr for CaptureFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "monitor" => Ok(CaptureFormat::Monitor),
+            "audit" | "audit-log" => Ok(CaptureFormat::AuditLog),
+            other => Err(format!(
+                "unknown capture format '{}' (expected monitor or audit)",
+                other
+            )),
+        }
+    }
+}
+
+/// One command from a capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedCommand {
+    /// Microseconds since the first command of the capture
+    pub offset_us: u64,
+    /// The connection that sent it, as captured (usually `ip:port`)
+    pub client: String,
+    /// Command name and arguments
+    pub args: Vec<Vec<u8>>,
+}
+
+/// A parsed capture, in timestamp order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkloadCapture {
+    pub commands: Vec<CapturedCommand>,
+    /// Lines that held no client command
+    pub skipped_lines: usize,
+}
+
+/// One audit log line
+#[derive(Deserialize)]
+struct AuditRecord {
+    ts_us: u64,
+    client: String,
+    args: Vec<String>,
+}
+
+/// A command as read from one line: timestamp (µs), client, arguments
+type CaptureLine = (u64, String, Vec<Vec<u8>>);
+
+impl WorkloadCapture {
+    /// Parse `text`; errors name the 1-based line
+    pub fn parse(text: &str, format: CaptureFormat) -> Result<Self, String> {
+        let mut lines = Vec::new();
+        let mut skipped_lines = 0;
+        for (index, line) in text.lines().enumerate() {
+            let parsed = match format {
+                CaptureFormat::Monitor => parse_monitor_line(line),
+                CaptureFormat::AuditLog => parse_audit_line(line),
+            };
+            match parsed.map_err(|e| format!("line {}: {}", index + 1, e))? {
+                Some(command) => lines.push(command),
+                None => skipped_lines += 1,
+            }
+        }
+
+        // Stable: commands sharing a timestamp keep their capture order
+        lines.sort_by_key(|(ts_us, _, _)| *ts_us);
+        let start = lines.first().map_or(0, |(ts_us, _, _)| *ts_us);
+        let commands = lines
+            .into_iter()
+            .map(|(ts_us, client, args)| CapturedCommand {
+                offset_us: ts_us - start,
+                client,
+                args,
+            })
+            .collect();
+        Ok(WorkloadCapture {
+            commands,
+            skipped_lines,
+        })
+    }
+
+    /// Microseconds from the first command to the last
+    pub fn duration_us(&self) -> u64 {
+        self.commands.last().map_or(0, |c| c.offset_us)
+    }
+
+    /// Distinct connections in the capture
+    pub fn num_clients(&self) -> usize {
+        let mut clients: Vec<&str> = self.commands.iter().map(|c| c.client.as_str()).collect();
+        clients.sort_unstable();
+        clients.dedup();
+        clients.len()
+    }
+}
+
+fn parse_monitor_line(line: &str) -> Result<Option<CaptureLine>, String> {
+    let Some((timestamp, rest)) = line.trim_end().split_once(' ') else {
+        return Ok(None);
+    };
+    let Some(ts_us) = parse_monitor_timestamp(timestamp) else {
+        return Ok(None);
+    };
+    let (source, command) = rest
+        .strip_prefix('[')
+        .and_then(|r| r.split_once(']'))
+        .ok_or("expected [db client] after the timestamp")?;
+    let client = source
+        .split_once(' ')
+        .map(|(_, client)| client)
+        .ok_or("expected [db client] after the timestamp")?;
+    if client == "lua" {
+        return Ok(None);
+    }
+
+    let args = parse_quoted_args(command.as_bytes())?;
+    if args.is_empty() {
+        return Err("no command after the client".to_string());
+    }
+    Ok(Some((ts_us, client.to_string(), args)))
+}
+
+/// `seconds.microseconds` to microseconds
+fn parse_monitor_timestamp(s: &str) -> Option<u64> {
+    let (secs, micros) = s.split_once('.')?;
+    if micros.is_empty() || micros.len() > 6 || !micros.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let scale = 10u64.pow(6 - micros.len() as u32);
+    let secs: u64 = secs.parse().ok()?;
+    let micros: u64 = micros.parse().ok()?;
+    secs.checked_mul(1_000_000)?.checked_add(micros * scale)
+}
+
+/// Arguments as MONITOR quotes them (Redis' `sdscatrepr`)
+fn parse_quoted_args(mut input: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let mut args = Vec::new();
+    loop {
+        while let [b' ', rest @ ..] = input {
+            input = rest;
+        }
+        let Some((&first, rest)) = input.split_first() else {
+            return Ok(args);
+        };
+        if first != b'"' {
+            return Err("expected a quoted argument".to_string());
+        }
+        input = rest;
+
+        let mut arg = Vec::new();
+        loop {
+            match input {
+                [b'"', rest @ ..] => {
+                    input = rest;
+                    break;
+                }
+                [b'\\', b'x', hi, lo, rest @ ..] => {
+                    let hex = std::str::from_utf8(&[*hi, *lo])
+                        .ok()
+                        .and_then(|h| u8::from_str_radix(h, 16).ok())
+                        .ok_or("invalid \\x escape")?;
+                    arg.push(hex);
+                    input = rest;
+                }
+                [b'\\', escaped, rest @ ..] => {
+                    arg.push(match escaped {
+                        b'n' => b'\n',
+                        b'r' => b'\r',
+                        b't' => b'\t',
+                        b'a' => 0x07,
+                        b'b' => 0x08,
+                        other => *other,
+                    });
+                    input = rest;
+                }
+                [byte, rest @ ..] => {
+                    arg.push(*byte);
+                    input = rest;
+                }
+                [] => return Err("unterminated quoted argument".to_string()),
+            }
+        }
+        args.push(arg);
+    }
+}
+
+fn parse_audit_line(line: &str) -> Result<Option<CaptureLine>, String> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    let record: AuditRecord = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    if record.args.is_empty() {
+        return Err("empty args".to_string());
+    }
+    let args = record.args.into_iter().map(String::into_bytes).collect();
+    Ok(Some((record.ts_us, record.client, args)))
+}
+
+/// How to replay a capture
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    pub seed: u64,
+    /// Time compression: 10 replays an hour of traffic in six virtual
+    /// minutes
+    pub speedup: u64,
+    /// Execution time the server charges each command
+    pub latency: LatencyProfile,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        ReplayConfig {
+            seed: 42,
+            speedup: 1,
+            latency: LatencyProfile::default(),
+        }
+    }
+}
+
+/// What a replay did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkloadReplayReport {
+    /// Commands sent to the server
+    pub sent: usize,
+    /// Sent commands that got a reply (the rest blocked or were still
+    /// waiting when the run ended)
+    pub answered: usize,
+    /// Commands not sent because the parser rejects them, by name
+    pub rejected: BTreeMap<String, usize>,
+    /// Error replies, by command name
+    pub errors: BTreeMap<String, usize>,
+    pub clients: usize,
+    /// Virtual time from the first command to the last reply
+    pub elapsed: Duration,
+    /// The server's queueing
+    pub queue: QueueStats,
+    /// Per answered command, milliseconds from when it was due to its
+    /// reply; sorted
+    response_ms: Vec<u64>,
+}
+
+impl WorkloadReplayReport {
+    /// Response time at quantile `q` (0.0 to 1.0), None if nothing was
+    /// answered
+    pub fn response_percentile(&self, q: f64) -> Option<Duration> {
+        debug_assert!(
+            (0.0..=1.0).contains(&q),
+            "Precondition: quantile {} outside [0, 1]",
+            q
+        );
+        let last = self.response_ms.len().checked_sub(1)?;
+        let index = ((last as f64) * q).round() as usize;
+        Some(Duration::from_millis(self.response_ms[index.min(last)]))
+    }
+}
+
+impl fmt::Display for WorkloadReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} commands from {} clients: {} sent, {} answered in {}ms",
+            self.sent + self.rejected.values().sum::<usize>(),
+            self.clients,
+            self.sent,
+            self.answered,
+            self.elapsed.as_millis()
+        )?;
+        let percentile = |q| self.response_percentile(q).map_or(0, |d| d.as_millis());
+        writeln!(
+            f,
+            "response time: p50 {}ms, p99 {}ms, max {}ms",
+            percentile(0.5),
+            percentile(0.99),
+            percentile(1.0)
+        )?;
+        writeln!(
+            f,
+            "server queue: max depth {}, max wait {}ms, total wait {}ms",
+            self.queue.max_depth,
+            self.queue.max_wait.as_millis(),
+            self.queue.total_wait.as_millis()
+        )?;
+        for (name, count) in &self.rejected {
+            writeln!(f, "rejected by the parser: {} x{}", name, count)?;
+        }
+        for (name, count) in &self.errors {
+            writeln!(f, "error replies: {} x{}", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// A command waiting for its client to send it
+struct DueCommand {
+    due: VirtualTime,
+    name: &'static str,
+    bytes: Vec<u8>,
+}
+
+/// One captured connection
+struct ReplayClient {
+    host: HostId,
+    client: RedisClient,
+    /// Commands not sent yet, in capture order. Timers only wake the
+    /// client: commands due in the same millisecond keep their order.
+    unsent: VecDeque<DueCommand>,
+    /// The sent command awaiting its reply, with its request id
+    outstanding: Option<(u64, DueCommand)>,
+}
+
+/// Replay `capture` against a simulated server
+pub fn replay_workload(capture: &WorkloadCapture, config: &ReplayConfig) -> WorkloadReplayReport {
+    assert!(config.speedup > 0, "Precondition: speedup must be positive");
+
+    let last_due = Duration::from_millis(capture.duration_us() / 1000 / config.speedup);
+    let mut sim = Simulation::new(SimulationConfig {
+        seed: config.seed,
+        max_time: VirtualTime::ZERO + last_due + DRAIN_WINDOW,
+        ..Default::default()
+    });
+    let server_host = sim.add_host("server".to_string());
+    let mut server = RedisServer::new(server_host).with_latency(config.latency.clone());
+
+    let mut clients: Vec<ReplayClient> = Vec::new();
+    let mut client_of_host: HashMap<HostId, usize> = HashMap::new();
+    let mut client_of_addr: HashMap<&str, usize> = HashMap::new();
+    let mut rejected: BTreeMap<String, usize> = BTreeMap::new();
+
+    for captured in &capture.commands {
+        let resp = RespValue::Array(Some(
+            captured
+                .args
+                .iter()
+                .map(|arg| RespValue::BulkString(Some(arg.clone())))
+                .collect(),
+        ));
+        let cmd = match Command::from_resp(&resp) {
+            Ok(cmd) => cmd,
+            Err(_) => {
+                let name = String::from_utf8_lossy(&captured.args[0]).to_uppercase();
+                *rejected.entry(name).or_insert(0) += 1;
+                continue;
+            }
+        };
+
+        let index = *client_of_addr
+            .entry(captured.client.as_str())
+            .or_insert_with(|| {
+                let host = sim.add_host(format!("client {}", captured.client));
+                client_of_host.insert(host, clients.len());
+                clients.push(ReplayClient {
+                    host,
+                    client: RedisClient::new(host, server_host),
+                    unsent: VecDeque::new(),
+                    outstanding: None,
+                });
+                clients.len() - 1
+            });
+        let due = Duration::from_millis(captured.offset_us / 1000 / config.speedup);
+        sim.schedule_timer(clients[index].host, due);
+        clients[index].unsent.push_back(DueCommand {
+            due: VirtualTime::ZERO + due,
+            name: cmd.name(),
+            bytes: RespParser::encode(&resp),
+        });
+    }
+
+    let mut sent = 0;
+    let mut errors: BTreeMap<String, usize> = BTreeMap::new();
+    let mut response_ms = Vec::new();
+    let mut last_reply = VirtualTime::ZERO;
+    sim.run(|sim, event| {
+        server.handle_event(sim, event);
+        let Some(&index) = client_of_host.get(&event.host_id) else {
+            return;
+        };
+        let replay_client = &mut clients[index];
+        replay_client.client.handle_event(event);
+
+        // Only a reply settles the outstanding command; timers leave it be
+        let replied = match (&event.event_type, &replay_client.outstanding) {
+            (EventType::NetworkMessage(_), Some((request_id, _))) => {
+                replay_client.client.get_response(*request_id).cloned()
+            }
+            _ => None,
+        };
+        if let Some(reply) = replied {
+            let (_, command) = replay_client.outstanding.take().expect("matched above");
+            if matches!(reply, RespValue::Error(_)) {
+                *errors.entry(command.name.to_string()).or_insert(0) += 1;
+            }
+            response_ms.push((event.time - command.due).as_millis());
+            last_reply = event.time;
+        }
+
+        let due = replay_client
+            .unsent
+            .front()
+            .is_some_and(|command| command.due <= sim.current_time());
+        if replay_client.outstanding.is_none() && due {
+            let mut command = replay_client.unsent.pop_front().expect("checked above");
+            let bytes = std::mem::take(&mut command.bytes);
+            let request_id = replay_client.client.send_command(sim, bytes);
+            replay_client.outstanding = Some((request_id, command));
+            sent += 1;
+        }
+    });
+
+    response_ms.sort_unstable();
+    let report = WorkloadReplayReport {
+        sent,
+        answered: response_ms.len(),
+        rejected,
+        errors,
+        clients: clients.len(),
+        elapsed: last_reply - VirtualTime::ZERO,
+        queue: server.queue_stats(),
+        response_ms,
+    };
+
+    // TigerStyle: Postcondition
+    debug_assert!(
+        report.answered <= report.sent,
+        "Postcondition violated: more replies than commands sent"
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::LatencyDistribution;
+
+    fn capture_of(lines: &[String]) -> WorkloadCapture {
+        WorkloadCapture::parse(&lines.join("\n"), CaptureFormat::Monitor).unwrap()
+    }
+
+    /// `count` commands from one client, `gap_ms` apart
+    fn steady_capture(count: usize, gap_ms: u64) -> WorkloadCapture {
+        let lines: Vec<String> = (0..count)
+            .map(|i| {
+                let ts_us = 1_700_000_000_000_000 + i as u64 * gap_ms * 1000;
+                format!(
+                    "{}.{:06} [0 10.0.0.1:5000] \"incr\" \"hits\"",
+                    ts_us / 1_000_000,
+                    ts_us % 1_000_000
+                )
+            })
+            .collect();
+        capture_of(&lines)
+    }
+
+    #[test]
+    fn test_parse_monitor_output() {
+        let text = "OK\n\
+            1700000000.5 [0 10.0.0.2:7000] \"get\" \"a b\"\n\
+            1700000000.000250 [0 10.0.0.1:6000] \"set\" \"k\" \"say \\\"hi\\\"\\r\\n\\x00\"\n\
+            1700000000.000300 [0 lua] \"set\" \"x\" \"1\"\n\
+            1700000000.000300 [0 unix:/tmp/redis.sock] \"ping\"";
+        let capture = WorkloadCapture::parse(text, CaptureFormat::Monitor).unwrap();
+        assert_eq!(capture.skipped_lines, 2);
+        assert_eq!(capture.num_clients(), 3);
+        assert_eq!(
+            capture.commands[0],
+            CapturedCommand {
+                offset_us: 0,
+                client: "10.0.0.1:6000".to_string(),
+                args: vec![b"set".to_vec(), b"k".to_vec(), b"say \"hi\"\r\n\0".to_vec()],
+            }
+        );
+        assert_eq!(capture.commands[1].client, "unix:/tmp/redis.sock");
+        assert_eq!(capture.commands[2].offset_us, 499_750);
+        assert_eq!(capture.commands[2].args[1], b"a b");
+        assert_eq!(capture.duration_us(), 499_750);
+
+        let err = WorkloadCapture::parse("OK\n1.0 [0 a:1] \"get", CaptureFormat::Monitor);
+        assert_eq!(err.unwrap_err(), "line 2: unterminated quoted argument");
+    }
+
+    #[test]
+    fn test_parse_audit_log() {
+        let text = r#"{"ts_us": 2000, "client": "a:1", "args": ["SET", "k", "v"]}
+
+{"ts_us": 1000, "client": "b:2", "args": ["GET", "k"]}"#;
+        let capture = WorkloadCapture::parse(text, CaptureFormat::AuditLog).unwrap();
+        assert_eq!(capture.skipped_lines, 1);
+        assert_eq!(
+            capture.commands[0].args,
+            vec![b"GET".to_vec(), b"k".to_vec()]
+        );
+        assert_eq!(capture.commands[1].offset_us, 1000);
+
+        let err = WorkloadCapture::parse(r#"{"ts_us": 1, "args": []}"#, CaptureFormat::AuditLog);
+        assert!(err.unwrap_err().starts_with("line 1: "));
+        assert_eq!("audit".parse(), Ok(CaptureFormat::AuditLog));
+    }
+
+    #[test]
+    fn test_replay_runs_the_capture_and_counts_failures() {
+        let capture = capture_of(&[
+            "1.000000 [0 a:1] \"set\" \"k\" \"v\"".to_string(),
+            "1.001000 [0 b:2] \"get\"".to_string(),
+            "1.002000 [0 a:1] \"incr\" \"k\"".to_string(),
+            "1.003000 [0 a:1] \"append\" \"k\" \"w\"".to_string(),
+        ]);
+        let report = replay_workload(&capture, &ReplayConfig::default());
+        assert_eq!(report.sent, 3);
+        assert_eq!(report.answered, 3);
+        assert_eq!(report.clients, 1, "b:2 sent nothing the parser accepts");
+        assert_eq!(report.rejected, BTreeMap::from([("GET".to_string(), 1)]));
+        assert_eq!(report.errors, BTreeMap::from([("INCR".to_string(), 1)]));
+        assert_eq!(report.queue.executed, 3);
+    }
+
+    #[test]
+    fn test_time_compression_overloads_a_slow_server() {
+        let capture = steady_capture(100, 50);
+        let config = |speedup| ReplayConfig {
+            seed: 7,
+            speedup,
+            latency: LatencyProfile::new(LatencyDistribution::fixed_ms(5)),
+        };
+
+        // Real time: each INCR (5ms, plus a 2-20ms round trip) is answered
+        // before the next is due
+        let relaxed = replay_workload(&capture, &config(1));
+        assert_eq!(relaxed.sent, 100);
+        assert_eq!(relaxed.answered, 100);
+        assert!(relaxed.response_percentile(1.0).unwrap() < Duration::from_millis(30));
+
+        // Ten times faster: due every 5ms, answered every 7ms or slower
+        let loaded = replay_workload(&capture, &config(10));
+        assert_eq!(loaded.answered, 100);
+        assert!(
+            loaded.response_percentile(0.99).unwrap() > Duration::from_millis(300),
+            "{}",
+            loaded
+        );
+
+        // Same capture, same config, same run
+        assert_eq!(loaded, replay_workload(&capture, &config(10)));
+    }
+}
@@ -0,0 +1,173 @@
+//! Simulated disks: what a host keeps across a crash
+//!
+//! A crash always loses the process's memory. What it loses on disk depends
+//! on the host's `StorageMode`: a persistent volume keeps every file as it
+//! was last synced, while ephemeral storage (a container without a volume,
+//! a node replaced by a fresh one) comes back empty. Writes never synced
+//! are lost either way, so recovery code sees exactly what a real fsync
+//! discipline would leave behind.
+//!
+//! ```ignore
+//! disk.write("dump.rdb", snapshot);
+//! disk.sync("dump.rdb");
+//! disk.crash(StorageMode::Persistent);
+//! assert_eq!(disk.read("dump.rdb"), Some(&snapshot[..]));
+//! ```
+//!
+//! # TigerStyle Invariants
+//!
+//! - After a persistent crash every file reads as it was last synced
+//! - After an ephemeral crash the disk is empty
+
+use std::collections::BTreeMap;
+
+/// Whether a host's disk survives its crash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageMode {
+    /// Synced files survive; unsynced writes are lost
+    #[default]
+    Persistent,
+    /// The disk is lost with the process
+    Ephemeral,
+}
+
+#[derive(Debug, Clone, Default)]
+struct DiskFile {
+    /// Contents as the running process sees them
+    current: Vec<u8>,
+    /// Contents as of the last sync; None if never synced
+    synced: Option<Vec<u8>>,
+}
+
+impl DiskFile {
+    /// Bytes past the synced contents: just the tail after appends, the
+    /// whole file after a rewrite
+    fn unsynced_len(&self) -> u64 {
+        match &self.synced {
+            Some(synced) if self.current.starts_with(synced) => {
+                (self.current.len() - synced.len()) as u64
+            }
+            _ => self.current.len() as u64,
+        }
+    }
+}
+
+/// One host's files, with synced and unsynced contents kept apart
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedDisk {
+    files: BTreeMap<String, DiskFile>,
+    /// Bytes of unsynced writes discarded by crashes
+    lost_bytes: u64,
+}
+
+impl SimulatedDisk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the file's contents (unsynced until `sync`)
+    pub fn write(&mut self, name: &str, data: Vec<u8>) {
+        self.files.entry(name.to_string()).or_default().current = data;
+    }
+
+    /// Append to the file, creating it if needed (unsynced until `sync`)
+    pub fn append(&mut self, name: &str, data: &[u8]) {
+        self.files
+            .entry(name.to_string())
+            .or_default()
+            .current
+            .extend_from_slice(data);
+    }
+
+    /// Make the file's current contents durable; false if there is no such file
+    pub fn sync(&mut self, name: &str) -> bool {
+        match self.files.get_mut(name) {
+            Some(file) => {
+                file.synced = Some(file.current.clone());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn sync_all(&mut self) {
+        for file in self.files.values_mut() {
+            file.synced = Some(file.current.clone());
+        }
+    }
+
+    /// The file's contents as the running process sees them
+    pub fn read(&self, name: &str) -> Option<&[u8]> {
+        self.files.get(name).map(|file| file.current.as_slice())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.files.remove(name).is_some()
+    }
+
+    pub fn file_names(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    /// Bytes a crash right now would lose under `StorageMode::Persistent`
+    pub fn unsynced_bytes(&self) -> u64 {
+        self.files.values().map(DiskFile::unsynced_len).sum()
+    }
+
+    /// Bytes of unsynced writes lost to crashes so far
+    pub fn lost_bytes(&self) -> u64 {
+        self.lost_bytes
+    }
+
+    /// Apply a crash of the owning host: keep what `mode` keeps
+    pub fn crash(&mut self, mode: StorageMode) {
+        self.lost_bytes += self.unsynced_bytes();
+        match mode {
+            StorageMode::Persistent => {
+                self.files.retain(|_, file| file.synced.is_some());
+                for file in self.files.values_mut() {
+                    file.current = file.synced.clone().expect("unsynced files were dropped");
+                }
+            }
+            StorageMode::Ephemeral => self.files.clear(),
+        }
+
+        debug_assert_eq!(
+            self.unsynced_bytes(),
+            0,
+            "Postcondition: a crashed disk holds only synced data"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persistent_crash_keeps_only_synced_data() {
+        let mut disk = SimulatedDisk::new();
+        disk.append("aof", b"one ");
+        disk.sync("aof");
+        disk.append("aof", b"two");
+        disk.write("tmp", b"never synced".to_vec());
+        assert_eq!(disk.read("aof"), Some(&b"one two"[..]));
+        assert_eq!(disk.unsynced_bytes(), 3 + 12);
+
+        disk.crash(StorageMode::Persistent);
+        assert_eq!(disk.read("aof"), Some(&b"one "[..]));
+        assert_eq!(disk.read("tmp"), None);
+        assert_eq!(disk.lost_bytes(), 15);
+        assert_eq!(disk.unsynced_bytes(), 0);
+    }
+
+    #[test]
+    fn test_ephemeral_crash_loses_everything() {
+        let mut disk = SimulatedDisk::new();
+        disk.write("dump.rdb", b"snapshot".to_vec());
+        disk.sync_all();
+        disk.crash(StorageMode::Ephemeral);
+        assert!(disk.file_names().is_empty());
+        assert_eq!(disk.lost_bytes(), 0, "synced data is not counted as lost");
+    }
+}
@@ -0,0 +1,353 @@
+//! Stateright Model for Quorum Leader Election
+//!
+//! Exhaustively verifies election safety for the Raft-lite election in
+//! `src/replication/election.rs`: at most one leader per term, under any
+//! interleaving of timeouts, crashes, and message delivery. The network is
+//! a set of every message ever sent, so a message can be delivered late,
+//! more than once, or never.
+//!
+//! Crashes lose a node's role and gathered votes but keep its term and vote,
+//! as the election's WAL does. `persist_votes: false` loses the vote too,
+//! and the checker finds the double vote that lets two candidates win.
+//!
+//! Corresponds to the election half of Raft (Ongaro & Ousterhout, §5.2)
+
+use stateright::{Model, Property};
+use std::collections::BTreeSet;
+
+/// Node index in the model
+pub type NodeId = u8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ModelRole {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ModelMessage {
+    RequestVote { term: u64 },
+    Vote { term: u64, granted: bool },
+    Heartbeat { term: u64 },
+}
+
+impl ModelMessage {
+    fn term(&self) -> u64 {
+        match *self {
+            ModelMessage::RequestVote { term }
+            | ModelMessage::Vote { term, .. }
+            | ModelMessage::Heartbeat { term } => term,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Envelope {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub message: ModelMessage,
+}
+
+/// One node, mirroring `Election`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ModelNode {
+    /// Persisted
+    pub term: u64,
+    /// Persisted
+    pub voted_for: Option<NodeId>,
+    pub role: ModelRole,
+    pub votes: BTreeSet<NodeId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ElectionState {
+    pub nodes: Vec<ModelNode>,
+    /// Every message sent so far
+    pub network: BTreeSet<Envelope>,
+    /// Every `(term, node)` that became leader (history, for the invariant)
+    pub elected: BTreeSet<(u64, NodeId)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ElectionAction {
+    /// The node's election timeout fires
+    Timeout(NodeId),
+    Deliver(Envelope),
+    Crash(NodeId),
+}
+
+/// Stateright model for election safety
+pub struct ElectionModel {
+    pub num_nodes: u8,
+    /// Timeouts stop at this term, bounding the state space
+    pub max_term: u64,
+    /// Whether a crash keeps the node's vote, as the WAL does
+    pub persist_votes: bool,
+}
+
+impl ElectionModel {
+    pub fn new() -> Self {
+        ElectionModel {
+            num_nodes: 3,
+            max_term: 2,
+            persist_votes: true,
+        }
+    }
+
+    fn quorum(&self) -> usize {
+        self.num_nodes as usize / 2 + 1
+    }
+
+    fn broadcast(&self, state: &mut ElectionState, from: NodeId, message: ModelMessage) {
+        for to in (0..self.num_nodes).filter(|&to| to != from) {
+            state.network.insert(Envelope { from, to, message });
+        }
+    }
+
+    fn become_leader(&self, state: &mut ElectionState, id: NodeId) {
+        let node = &mut state.nodes[id as usize];
+        node.role = ModelRole::Leader;
+        node.votes.clear();
+        let term = node.term;
+        state.elected.insert((term, id));
+        self.broadcast(state, id, ModelMessage::Heartbeat { term });
+    }
+}
+
+impl Default for ElectionModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Model for ElectionModel {
+    type State = ElectionState;
+    type Action = ElectionAction;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        let node = ModelNode {
+            term: 0,
+            voted_for: None,
+            role: ModelRole::Follower,
+            votes: BTreeSet::new(),
+        };
+        vec![ElectionState {
+            nodes: vec![node; self.num_nodes as usize],
+            network: BTreeSet::new(),
+            elected: BTreeSet::new(),
+        }]
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        for (id, node) in state.nodes.iter().enumerate() {
+            let id = id as NodeId;
+            if node.role != ModelRole::Leader && node.term < self.max_term {
+                actions.push(ElectionAction::Timeout(id));
+            }
+            actions.push(ElectionAction::Crash(id));
+        }
+        for envelope in &state.network {
+            actions.push(ElectionAction::Deliver(*envelope));
+        }
+    }
+
+    fn next_state(&self, state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let mut next = state.clone();
+
+        match action {
+            ElectionAction::Timeout(id) => {
+                let node = &mut next.nodes[id as usize];
+                node.term += 1;
+                node.voted_for = Some(id);
+                node.role = ModelRole::Candidate;
+                node.votes = BTreeSet::from([id]);
+                let term = node.term;
+                if node.votes.len() >= self.quorum() {
+                    self.become_leader(&mut next, id);
+                } else {
+                    self.broadcast(&mut next, id, ModelMessage::RequestVote { term });
+                }
+            }
+            ElectionAction::Crash(id) => {
+                let node = &mut next.nodes[id as usize];
+                node.role = ModelRole::Follower;
+                node.votes.clear();
+                if !self.persist_votes {
+                    node.voted_for = None;
+                }
+            }
+            ElectionAction::Deliver(Envelope { from, to, message }) => {
+                let node = &mut next.nodes[to as usize];
+                if message.term() > node.term {
+                    node.term = message.term();
+                    node.voted_for = None;
+                    node.role = ModelRole::Follower;
+                    node.votes.clear();
+                }
+                let term = node.term;
+
+                match message {
+                    ModelMessage::RequestVote { term: asked } => {
+                        let granted = asked == term
+                            && (node.voted_for.is_none() || node.voted_for == Some(from));
+                        if granted {
+                            node.voted_for = Some(from);
+                        }
+                        next.network.insert(Envelope {
+                            from: to,
+                            to: from,
+                            message: ModelMessage::Vote { term, granted },
+                        });
+                    }
+                    ModelMessage::Vote {
+                        term: voted,
+                        granted,
+                    } => {
+                        if node.role == ModelRole::Candidate && voted == term && granted {
+                            node.votes.insert(from);
+                            if node.votes.len() >= self.quorum() {
+                                self.become_leader(&mut next, to);
+                            }
+                        }
+                    }
+                    ModelMessage::Heartbeat { term: led } => {
+                        if led == term && node.role != ModelRole::Leader {
+                            node.role = ModelRole::Follower;
+                            node.votes.clear();
+                        }
+                    }
+                }
+            }
+        }
+
+        Some(next)
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        vec![
+            // INVARIANT: At most one leader per term, over the whole history
+            Property::always(
+                "election_safety",
+                |_model: &ElectionModel, state: &ElectionState| {
+                    let terms: BTreeSet<u64> = state.elected.iter().map(|&(t, _)| t).collect();
+                    terms.len() == state.elected.len()
+                },
+            ),
+            // INVARIANT: Candidates and leaders voted for themselves in their term
+            Property::always(
+                "leader_voted_for_itself",
+                |_model: &ElectionModel, state: &ElectionState| {
+                    state.nodes.iter().enumerate().all(|(id, node)| {
+                        node.role == ModelRole::Follower || node.voted_for == Some(id as NodeId)
+                    })
+                },
+            ),
+            // REACHABLE: Some term elects a leader
+            Property::sometimes(
+                "leader_elected",
+                |_model: &ElectionModel, state: &ElectionState| !state.elected.is_empty(),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(model: &ElectionModel, state: ElectionState, action: ElectionAction) -> ElectionState {
+        model.next_state(&state, action).unwrap()
+    }
+
+    fn deliver(from: NodeId, to: NodeId, message: ModelMessage) -> ElectionAction {
+        ElectionAction::Deliver(Envelope { from, to, message })
+    }
+
+    #[test]
+    fn test_election_state_basic() {
+        let model = ElectionModel::new();
+        let mut state = model.init_states().remove(0);
+        state = step(&model, state, ElectionAction::Timeout(0));
+        assert_eq!(state.network.len(), 2, "RequestVote to both peers");
+
+        state = step(
+            &model,
+            state,
+            deliver(0, 1, ModelMessage::RequestVote { term: 1 }),
+        );
+        assert_eq!(state.nodes[1].voted_for, Some(0));
+        state = step(
+            &model,
+            state,
+            deliver(
+                1,
+                0,
+                ModelMessage::Vote {
+                    term: 1,
+                    granted: true,
+                },
+            ),
+        );
+        assert_eq!(state.nodes[0].role, ModelRole::Leader);
+        assert_eq!(state.elected, BTreeSet::from([(1, 0)]));
+    }
+
+    #[test]
+    fn test_crash_keeps_vote_only_when_persisted() {
+        for persist_votes in [true, false] {
+            let model = ElectionModel {
+                persist_votes,
+                ..ElectionModel::new()
+            };
+            let mut state = model.init_states().remove(0);
+            state = step(&model, state, ElectionAction::Timeout(0));
+            state = step(
+                &model,
+                state,
+                deliver(0, 2, ModelMessage::RequestVote { term: 1 }),
+            );
+            state = step(&model, state, ElectionAction::Crash(2));
+            assert_eq!(
+                state.nodes[2].voted_for.is_some(),
+                persist_votes,
+                "persist_votes: {}",
+                persist_votes
+            );
+        }
+    }
+
+    #[test]
+    #[ignore] // Run with: cargo test stateright_election -- --ignored --nocapture
+    fn stateright_election_model_check() {
+        use stateright::Checker;
+
+        let model = ElectionModel::new();
+        let checker = model.checker().spawn_bfs().join();
+
+        println!("States explored: {}", checker.unique_state_count());
+
+        checker.assert_properties();
+
+        println!("Model check passed! At most one leader per term.");
+    }
+
+    #[test]
+    #[ignore] // Run with: cargo test stateright_election -- --ignored --nocapture
+    fn stateright_election_without_vote_persistence_finds_two_leaders() {
+        use stateright::Checker;
+
+        // One term is enough for the double vote, and keeps the search small
+        let model = ElectionModel {
+            max_term: 1,
+            persist_votes: false,
+            ..ElectionModel::new()
+        };
+        let checker = model.checker().spawn_bfs().join();
+
+        assert!(
+            checker.discovery("election_safety").is_some(),
+            "a crash that forgets a vote must allow two leaders in a term"
+        );
+    }
+}
@@ -29,6 +29,7 @@
 //! - `replication`: CRDT merge properties (commutativity, associativity, idempotence)
 //! - `persistence`: Write buffer bounds and durability
 //! - `anti_entropy`: Merkle tree sync completeness
+//! - `election`: Quorum leader election safety (one leader per term)
 //!
 //! ## Running Model Checks
 //!
@@ -47,14 +48,18 @@
 //! | `CrdtMergeModel` | `ReplicationConvergence.tla` | CRDT_MERGE_COMMUTATIVE |
 //! | `WriteBufferModel` | `StreamingPersistence.tla` | WRITE_BUFFER_BOUNDED |
 //! | `AntiEntropyModel` | `AntiEntropy.tla` | SYNC_COMPLETENESS |
+//! | `ElectionModel` | - | ELECTION_SAFETY |
 
 pub mod anti_entropy;
+pub mod election;
 pub mod persistence;
 pub mod replication;
 
 #[cfg(test)]
 pub use anti_entropy::AntiEntropyModel;
 #[cfg(test)]
+pub use election::ElectionModel;
+#[cfg(test)]
 pub use persistence::{WalDurabilityModel, WriteBufferModel};
 #[cfg(test)]
 pub use replication::CrdtMergeModel;
@@ -27,6 +27,7 @@ use crate::streaming::{
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
+use tracing::{error, info, warn};
 
 /// Configuration for compaction operations
 #[derive(Debug, Clone)]
@@ -266,13 +267,13 @@ impl<S: ObjectStore + Clone + 'static, T: TimeSource> Compactor<S, T> {
                     let reader = match SegmentReader::open(&data) {
                         Ok(r) => r,
                         Err(e) => {
-                            eprintln!("Failed to open segment {}: {}", segment_info.key, e);
+                            warn!(segment = %segment_info.key, error = %e, "Skipping unreadable segment");
                             continue;
                         }
                     };
 
                     if let Err(e) = reader.validate() {
-                        eprintln!("Invalid segment {}: {}", segment_info.key, e);
+                        warn!(segment = %segment_info.key, error = %e, "Skipping invalid segment");
                         continue;
                     }
 
@@ -297,9 +298,10 @@ impl<S: ObjectStore + Clone + 'static, T: TimeSource> Compactor<S, T> {
                                         }
                                     }
                                     Err(e) => {
-                                        eprintln!(
-                                            "Failed to read delta from {}: {}",
-                                            segment_info.key, e
+                                        warn!(
+                                            segment = %segment_info.key,
+                                            error = %e,
+                                            "Skipping unreadable delta"
                                         );
                                     }
                                 }
@@ -307,13 +309,13 @@ impl<S: ObjectStore + Clone + 'static, T: TimeSource> Compactor<S, T> {
                             actually_compacted.push(segment_info);
                         }
                         Err(e) => {
-                            eprintln!("Failed to read deltas from {}: {}", segment_info.key, e);
+                            warn!(segment = %segment_info.key, error = %e, "Skipping segment with unreadable deltas");
                         }
                     }
                 }
                 Err(e) => {
                     // Segment missing (concurrent compaction or crash), mark for manifest cleanup
-                    eprintln!("Segment {} missing: {}", segment_info.key, e);
+                    warn!(segment = %segment_info.key, error = %e, "Segment missing; dropping it from the manifest");
                     missing_segments.push(segment_info.key.clone());
                     actually_compacted.push(segment_info);
                 }
@@ -566,16 +568,11 @@ impl<S: ObjectStore + Clone + 'static, T: TimeSource> CompactionWorker<S, T> {
             // Try to compact
             match self.compactor.compact_if_needed().await {
                 Ok(Some(result)) => {
-                    // Log compaction result (in production, use proper logging)
-                    eprintln!(
-                        "Compaction complete: removed {} segments, created {}, reclaimed {} bytes",
-                        result.segments_removed.len(),
-                        if result.segment_created.is_some() {
-                            1
-                        } else {
-                            0
-                        },
-                        result.bytes_reclaimed
+                    info!(
+                        segments_removed = result.segments_removed.len(),
+                        segment_created = result.segment_created.is_some(),
+                        bytes_reclaimed = result.bytes_reclaimed,
+                        "Compaction complete"
                     );
                 }
                 Ok(None) => {
@@ -585,7 +582,7 @@ impl<S: ObjectStore + Clone + 'static, T: TimeSource> CompactionWorker<S, T> {
                     // Expected when segments are too few
                 }
                 Err(e) => {
-                    eprintln!("Compaction error: {}", e);
+                    error!(error = %e, "Compaction failed");
                 }
             }
 
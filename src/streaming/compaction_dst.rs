@@ -28,6 +28,7 @@ use crate::streaming::{
     StreamingPersistence, WriteBufferConfig,
 };
 use std::sync::Arc;
+use tracing::warn;
 
 /// Configuration for compaction DST
 #[derive(Debug, Clone)]
@@ -118,6 +119,22 @@ impl CompactionDSTConfig {
             ..Default::default()
         }
     }
+
+    /// Cloud mode - region outages, 503 throttling and list lag
+    pub fn cloud(seed: u64) -> Self {
+        CompactionDSTConfig {
+            seed,
+            store_config: SimulatedStoreConfig::cloud_chaos(),
+            flush_probability: 0.20,
+            compact_probability: 0.20,
+            compaction_config: CompactionConfig {
+                min_segments_to_compact: 2,
+                max_segments: 5,
+                ..CompactionConfig::test()
+            },
+            ..Default::default()
+        }
+    }
 }
 
 /// Operation types for compaction DST
@@ -499,9 +516,10 @@ impl CompactionDSTHarness {
                 }
                 Err(e) => {
                     // Store error during check - only warn, may be from fault injection
-                    eprintln!(
-                        "Warning: Store error checking segment {} existence: {}",
-                        segment.key, e
+                    warn!(
+                        segment = %segment.key,
+                        error = %e,
+                        "Store error checking segment existence"
                     );
                 }
             }
@@ -761,6 +779,22 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_compaction_dst_batch_cloud() {
+        // A compaction interrupted by an outage must leave the manifest
+        // pointing only at segments that exist
+        let results = run_compaction_dst_batch(5000, 10, 200, CompactionDSTConfig::cloud).await;
+
+        let summary = summarize_compaction_batch(&results);
+        println!("{}", summary);
+
+        assert!(
+            results.iter().all(|r| r.is_success()),
+            "Cloud-mode compaction must not violate invariants: {}",
+            summary
+        );
+    }
+
     #[tokio::test]
     async fn test_compaction_creates_segments() {
         // Ensure compaction actually happens and creates segments
@@ -122,6 +122,23 @@ pub struct WriteBufferConfig {
     pub backpressure_threshold_bytes: usize,
     /// Enable compression for segments
     pub compression_enabled: bool,
+    /// How long flushes must keep failing before the object store is
+    /// reported degraded (default: 30s)
+    #[serde(with = "duration_millis", default = "default_degraded_after")]
+    pub degraded_after: Duration,
+    /// Bound on buffered plus parked bytes while the object store is
+    /// failing; pushes past it are rejected, and key writes are refused
+    /// from half of it (default: 256MB)
+    #[serde(default = "default_max_parked_bytes")]
+    pub max_parked_bytes: usize,
+}
+
+fn default_degraded_after() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_max_parked_bytes() -> usize {
+    256 * 1024 * 1024
 }
 
 impl Default for WriteBufferConfig {
@@ -132,6 +149,8 @@ impl Default for WriteBufferConfig {
             max_deltas: 10_000,
             backpressure_threshold_bytes: 16 * 1024 * 1024, // 16MB
             compression_enabled: false,
+            degraded_after: default_degraded_after(),
+            max_parked_bytes: default_max_parked_bytes(),
         }
     }
 }
@@ -145,6 +164,8 @@ impl WriteBufferConfig {
             max_deltas: 100,
             backpressure_threshold_bytes: 256 * 1024, // 256KB
             compression_enabled: false,
+            degraded_after: Duration::from_millis(200),
+            max_parked_bytes: 1024 * 1024, // 1MB
         }
     }
 
@@ -156,6 +177,8 @@ impl WriteBufferConfig {
             max_deltas: 50_000,
             backpressure_threshold_bytes: 64 * 1024 * 1024, // 64MB
             compression_enabled: true,
+            degraded_after: default_degraded_after(),
+            max_parked_bytes: 1024 * 1024 * 1024, // 1GB
         }
     }
 }
@@ -278,6 +301,15 @@ mod tests {
         let parsed: WriteBufferConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(config.flush_interval, parsed.flush_interval);
         assert_eq!(config.max_size_bytes, parsed.max_size_bytes);
+        assert_eq!(config.degraded_after, parsed.degraded_after);
+
+        // Configs written before degraded mode existed still load
+        let legacy: WriteBufferConfig = serde_json::from_str(
+            r#"{"flush_interval":250,"max_size_bytes":1024,"max_deltas":10,"backpressure_threshold_bytes":4096,"compression_enabled":false}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.degraded_after, Duration::from_secs(30));
+        assert_eq!(legacy.max_parked_bytes, 256 * 1024 * 1024);
     }
 
     #[test]
@@ -5,6 +5,7 @@
 
 use crate::replication::state::ReplicationDelta;
 use std::sync::mpsc;
+use tracing::{error, warn};
 
 /// Error type for delta sink operations
 #[derive(Debug)]
@@ -119,11 +120,11 @@ impl<S: crate::streaming::ObjectStore> PersistenceWorker<S> {
                 // Final drain and flush before shutdown
                 for delta in self.receiver.drain() {
                     if let Err(e) = self.write_buffer.push(delta) {
-                        eprintln!("Error pushing final delta: {}", e);
+                        error!(error = %e, "Dropped a delta at shutdown");
                     }
                 }
                 if let Err(e) = self.write_buffer.flush().await {
-                    eprintln!("Error during final flush: {}", e);
+                    error!(error = %e, "Final flush failed");
                 }
                 break;
             }
@@ -132,7 +133,7 @@ impl<S: crate::streaming::ObjectStore> PersistenceWorker<S> {
             let deltas = self.receiver.drain();
             for delta in deltas {
                 if let Err(e) = self.write_buffer.push(delta) {
-                    eprintln!("Error pushing delta to write buffer: {}", e);
+                    warn!(error = %e, "Write buffer rejected a delta");
                     // TODO: Handle backpressure more gracefully
                 }
             }
@@ -140,7 +141,7 @@ impl<S: crate::streaming::ObjectStore> PersistenceWorker<S> {
             // Check if flush is needed
             if self.write_buffer.should_flush() {
                 if let Err(e) = self.write_buffer.flush().await {
-                    eprintln!("Error flushing write buffer: {}", e);
+                    warn!(error = %e, "Write buffer flush failed");
                 }
             }
 
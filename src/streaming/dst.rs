@@ -15,18 +15,46 @@
 //!     harness.check_invariants();  // Panics with seed on failure
 //! }
 //! ```
+//!
+//! ## Production path
+//!
+//! Writes take the route a client write takes in the server:
+//!
+//! - **Acknowledge.** A write is refused, unacknowledged, while
+//!   `PersistenceHealth` reports writes refused, as `ReplicatedShardedState`
+//!   does. Otherwise it is acknowledged before persistence sees it.
+//! - **Bridge.** Acknowledged deltas queue in flight and reach the
+//!   `PersistenceActor` in batches of a seeded size, as the delta sink
+//!   bridge forwards them.
+//! - **Actor.** Batches, flushes and shutdown-free restarts go through the
+//!   actor's message handling, stepped in place so runs stay deterministic.
+//!
+//! The actor must never reject an acknowledged delta.
+//!
+//! ## Outage mode
+//!
+//! `StreamingDSTConfig::outage` takes the store down for windows longer
+//! than `degraded_after`, with the WAL holding acknowledged writes durably.
+//! Once the store is back, recovery must return the last acknowledged value
+//! of every key: degraded mode may delay a write, never lose it. Its
+//! `max_parked_bytes` is small enough that long outages reach the
+//! write-refusal mark; buffered plus parked bytes must never pass the bound.
 
 use crate::io::simulation::SimulatedRng;
 use crate::io::Rng;
 use crate::redis::SDS;
 use crate::replication::lattice::{LamportClock, ReplicaId};
 use crate::replication::state::{ReplicatedValue, ReplicationDelta};
+use crate::streaming::integration::{PersistenceActor, PersistenceMessage};
 use crate::streaming::{
-    InMemoryObjectStore, ObjectStore, RecoveryManager, SegmentReader, SimulatedObjectStore,
-    SimulatedStoreConfig, SimulatedStoreStats, StreamingPersistence, WriteBufferConfig,
+    InMemoryObjectStore, ObjectStore, PersistenceHealth, RecoveryManager, SegmentReader,
+    SimulatedClock, SimulatedObjectStore, SimulatedStoreConfig, SimulatedStoreStats,
+    StreamingPersistence, WriteBufferConfig,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::oneshot;
+use tracing::warn;
 
 /// Configuration for streaming DST
 #[derive(Debug, Clone)]
@@ -47,6 +75,10 @@ pub struct StreamingDSTConfig {
     pub crash_probability: f64,
     /// Maximum operations per run
     pub max_operations: usize,
+    /// Simulated milliseconds between operations
+    pub op_interval_ms: u64,
+    /// Check that recovery returns every acknowledged write (no crashes)
+    pub check_acked_writes: bool,
 }
 
 impl Default for StreamingDSTConfig {
@@ -60,6 +92,8 @@ impl Default for StreamingDSTConfig {
             flush_probability: 0.1,
             crash_probability: 0.01,
             max_operations: 1000,
+            op_interval_ms: 10,
+            check_acked_writes: false,
         }
     }
 }
@@ -94,6 +128,38 @@ impl StreamingDSTConfig {
         }
     }
 
+    /// Cloud behaviour - region outages, 503 throttling, list lag
+    pub fn cloud(seed: u64) -> Self {
+        StreamingDSTConfig {
+            seed,
+            store_config: SimulatedStoreConfig::cloud_chaos(),
+            flush_probability: 0.15,
+            crash_probability: 0.05,
+            ..Default::default()
+        }
+    }
+
+    /// Object store outages longer than `degraded_after`, no crashes
+    pub fn outage(seed: u64) -> Self {
+        StreamingDSTConfig {
+            seed,
+            store_config: SimulatedStoreConfig {
+                outage_prob: 0.01,
+                outage_duration_ops: (5, 50),
+                ..SimulatedStoreConfig::no_faults()
+            }
+            .with_outage(40, 90),
+            write_buffer_config: WriteBufferConfig {
+                max_parked_bytes: 4 * 1024,
+                ..WriteBufferConfig::test()
+            },
+            flush_probability: 0.2,
+            crash_probability: 0.0,
+            check_acked_writes: true,
+            ..Default::default()
+        }
+    }
+
     /// Moderate fault injection
     pub fn moderate(seed: u64) -> Self {
         StreamingDSTConfig {
@@ -198,6 +264,11 @@ impl StreamingWorkload {
         self.lamport_time
     }
 
+    /// Size of the next batch the bridge forwards to the actor
+    pub fn bridge_batch(&mut self) -> usize {
+        self.rng.gen_range(1, MAX_BRIDGE_BATCH as u64 + 1) as usize
+    }
+
     /// Get expected state
     pub fn expected_state(&self) -> &HashMap<String, Option<String>> {
         &self.expected_state
@@ -242,6 +313,12 @@ pub struct StreamingDSTResult {
     pub flushes: u64,
     /// Crashes simulated
     pub crashes: u64,
+    /// Operations that ran while persistence reported degraded
+    pub degraded_operations: u64,
+    /// Acknowledged deltas the actor rejected at `max_parked_bytes`
+    pub parked_rejections: u64,
+    /// Writes refused before acknowledgement at the write-refusal mark
+    pub refused_writes: u64,
     /// Store fault statistics
     pub store_stats: SimulatedStoreStats,
     /// Invariant violations found
@@ -259,6 +336,9 @@ impl StreamingDSTResult {
             failed_operations: 0,
             flushes: 0,
             crashes: 0,
+            degraded_operations: 0,
+            parked_rejections: 0,
+            refused_writes: 0,
             store_stats: SimulatedStoreStats::default(),
             invariant_violations: Vec::new(),
             history: Vec::new(),
@@ -292,10 +372,18 @@ pub struct StreamingDSTHarness {
     store: Arc<DSTStore>,
     inner_store: InMemoryObjectStore,
     workload: StreamingWorkload,
-    persistence: Option<StreamingPersistence<DSTStore>>,
+    actor: Option<PersistenceActor<DSTStore, SimulatedClock>>,
+    /// The command path's view of the actor's persistence
+    health: PersistenceHealth,
+    /// Acknowledged deltas the bridge has not forwarded yet
+    in_flight: Vec<ReplicationDelta>,
+    /// Deltas in flight when the bridge next forwards them
+    bridge_batch: usize,
     result: StreamingDSTResult,
     /// Flushed deltas (ground truth for what's in object store)
     flushed_deltas: Vec<ReplicationDelta>,
+    /// Simulated time, advanced before every operation
+    clock: SimulatedClock,
 }
 
 impl StreamingDSTHarness {
@@ -309,16 +397,22 @@ impl StreamingDSTHarness {
             config.store_config.clone(),
         ));
 
-        let persistence = StreamingPersistence::new(
+        let clock = SimulatedClock::new(0);
+        let persistence = StreamingPersistence::with_clock(
             store.clone(),
             config.prefix.clone(),
             config.replica_id,
             config.write_buffer_config.clone(),
+            clock.clone(),
         )
         .await
         .ok();
+        let health = persistence
+            .as_ref()
+            .map_or_else(PersistenceHealth::default, StreamingPersistence::health);
 
-        let workload = StreamingWorkload::new(config.clone());
+        let mut workload = StreamingWorkload::new(config.clone());
+        let bridge_batch = workload.bridge_batch();
         let result = StreamingDSTResult::new(config.seed);
 
         StreamingDSTHarness {
@@ -326,9 +420,13 @@ impl StreamingDSTHarness {
             store,
             inner_store,
             workload,
-            persistence,
+            actor: persistence.map(PersistenceActor::new),
+            health,
+            in_flight: Vec::new(),
+            bridge_batch,
             result,
             flushed_deltas: Vec::new(),
+            clock,
         }
     }
 
@@ -344,6 +442,8 @@ impl StreamingDSTHarness {
     async fn execute_operation(&mut self, op: StreamingOperation) {
         self.result.total_operations += 1;
         let op_id = self.result.total_operations;
+        self.clock.advance_ms(self.config.op_interval_ms);
+        let rejections_before = self.parked_rejections();
 
         let outcome = match &op {
             StreamingOperation::Write { key, value } => self.execute_write(key, value).await,
@@ -370,73 +470,135 @@ impl StreamingDSTHarness {
                 self.result.crashes += 1;
             }
         }
+        if let Some(ref actor) = self.actor {
+            let persistence = actor.persistence();
+            if persistence.is_degraded() {
+                self.result.degraded_operations += 1;
+            }
+            // Parked batches stay within their bound, however long the outage
+            let bound = self.config.write_buffer_config.max_parked_bytes;
+            if persistence.unsynced_bytes() > bound {
+                self.result.invariant_violations.push(format!(
+                    "op {}: {} unsynced bytes exceed max_parked_bytes {}",
+                    op_id,
+                    persistence.unsynced_bytes(),
+                    bound
+                ));
+            }
+        }
+        // A crash starts a fresh instance, whose count restarts at zero
+        let rejected = self.parked_rejections().saturating_sub(rejections_before);
+        if rejected > 0 {
+            // The write-refusal mark must leave room for every delta in flight
+            self.result.invariant_violations.push(format!(
+                "op {}: persistence actor rejected {} acknowledged deltas",
+                op_id, rejected
+            ));
+        }
+        self.result.parked_rejections += rejected;
     }
 
-    async fn execute_write(&mut self, key: &str, value: &str) -> OperationOutcome {
-        let Some(ref mut persistence) = self.persistence else {
-            return OperationOutcome::Failed("No persistence instance".to_string());
-        };
+    fn parked_rejections(&self) -> u64 {
+        self.actor
+            .as_ref()
+            .map_or(0, |a| a.persistence().stats().parked_rejections)
+    }
 
+    async fn execute_write(&mut self, key: &str, value: &str) -> OperationOutcome {
         let delta = self.workload.make_write_delta(key, value);
-
-        match persistence.push(delta) {
-            Ok(()) => {
-                // Note: we don't record to expected_state here because
-                // the data isn't persisted until flush succeeds
-                OperationOutcome::Success
-            }
-            Err(e) => OperationOutcome::Failed(e.to_string()),
+        let outcome = self.acknowledge(delta).await;
+        if let OperationOutcome::Success = outcome {
+            self.workload.record_write(key, value);
         }
+        outcome
     }
 
     async fn execute_delete(&mut self, key: &str) -> OperationOutcome {
-        let Some(ref mut persistence) = self.persistence else {
-            return OperationOutcome::Failed("No persistence instance".to_string());
-        };
-
         let delta = self.workload.make_delete_delta(key);
-
-        match persistence.push(delta) {
-            Ok(()) => OperationOutcome::Success,
-            Err(e) => OperationOutcome::Failed(e.to_string()),
+        let outcome = self.acknowledge(delta).await;
+        if let OperationOutcome::Success = outcome {
+            self.workload.record_delete(key);
         }
+        outcome
     }
 
-    async fn execute_flush(&mut self) -> OperationOutcome {
-        let Some(ref mut persistence) = self.persistence else {
+    /// Acknowledge a write unless persistence refuses writes, then hand it
+    /// to the bridge. Once acknowledged it must survive: the WAL holds it
+    /// until a flush gets it to the store, however long that takes.
+    async fn acknowledge(&mut self, delta: ReplicationDelta) -> OperationOutcome {
+        if self.actor.is_none() {
             return OperationOutcome::Failed("No persistence instance".to_string());
+        }
+        if self.health.is_refusing_writes() {
+            self.result.refused_writes += 1;
+            return OperationOutcome::Failed("MISCONF writes refused".to_string());
+        }
+
+        self.in_flight.push(delta);
+        if self.in_flight.len() >= self.bridge_batch {
+            self.forward_in_flight().await;
+        }
+        OperationOutcome::Success
+    }
+
+    /// Forward every delta in flight to the actor, as one bridge drain does
+    async fn forward_in_flight(&mut self) {
+        let Some(ref mut actor) = self.actor else {
+            return;
         };
+        if !self.in_flight.is_empty() {
+            let deltas = std::mem::take(&mut self.in_flight);
+            actor.handle(PersistenceMessage::PushDeltas(deltas)).await;
+        }
+        self.bridge_batch = self.workload.bridge_batch();
+    }
+
+    /// Forward what is in flight, then flush through the actor
+    async fn flush_through_actor(&mut self) -> Option<Result<(), String>> {
+        self.forward_in_flight().await;
+        let actor = self.actor.as_mut()?;
+        let (response_tx, response_rx) = oneshot::channel();
+        actor
+            .handle(PersistenceMessage::Flush { response_tx })
+            .await;
+        Some(
+            response_rx
+                .await
+                .unwrap_or(Err("Response channel dropped".to_string())),
+        )
+    }
 
-        match persistence.flush().await {
-            Ok(result) => {
+    async fn execute_flush(&mut self) -> OperationOutcome {
+        match self.flush_through_actor().await {
+            Some(Ok(())) => {
                 self.result.flushes += 1;
-                if result.segment.is_some() {
-                    // Flush succeeded - record what was persisted
-                    // Note: In a real implementation we'd track this more precisely
-                }
                 OperationOutcome::Success
             }
-            Err(e) => OperationOutcome::Failed(e.to_string()),
+            Some(Err(e)) => OperationOutcome::Failed(e),
+            None => OperationOutcome::Failed("No persistence instance".to_string()),
         }
     }
 
     async fn execute_crash_recover(&mut self) -> OperationOutcome {
-        // Simulate crash by dropping persistence
-        self.persistence = None;
+        // Simulate crash by dropping the actor and whatever was in flight
+        self.actor = None;
+        self.in_flight.clear();
 
         // Re-create persistence (simulates recovery)
-        let new_persistence = StreamingPersistence::new(
+        let new_persistence = StreamingPersistence::with_clock(
             self.store.clone(),
             self.config.prefix.clone(),
             self.config.replica_id,
             self.config.write_buffer_config.clone(),
+            self.clock.clone(),
         )
         .await;
 
         match new_persistence {
             Ok(p) => {
                 let segments = p.manifest().segments.len();
-                self.persistence = Some(p);
+                self.health = p.health();
+                self.actor = Some(PersistenceActor::new(p));
                 OperationOutcome::Recovered {
                     deltas_recovered: segments, // Approximate
                 }
@@ -447,9 +609,17 @@ impl StreamingDSTHarness {
 
     /// Check invariants after the run
     pub async fn check_invariants(&mut self) {
-        // Force final flush
-        if let Some(ref mut persistence) = self.persistence {
-            let _ = persistence.flush().await;
+        // Force final flush; in outage mode, retry until the store is back
+        let attempts = if self.config.check_acked_writes {
+            MAX_RESYNC_ATTEMPTS
+        } else {
+            1
+        };
+        for _ in 0..attempts {
+            if !matches!(self.flush_through_actor().await, Some(Err(_))) {
+                break;
+            }
+            self.clock.advance_ms(self.config.op_interval_ms);
         }
 
         // Invariant 1: All segments in manifest should exist in store
@@ -461,16 +631,21 @@ impl StreamingDSTHarness {
         // Invariant 3: Recovery should restore all persisted data
         self.check_recovery_completeness().await;
 
+        // Invariant 4: No acknowledged write is lost across an outage
+        if self.config.check_acked_writes {
+            self.check_acked_writes_survive().await;
+        }
+
         // Update store stats
         self.result.store_stats = self.store.stats();
     }
 
     async fn check_segment_existence(&mut self) {
-        let Some(ref persistence) = self.persistence else {
+        let Some(ref actor) = self.actor else {
             return;
         };
 
-        for segment in &persistence.manifest().segments {
+        for segment in &actor.persistence().manifest().segments {
             match self.store.exists(&segment.key).await {
                 Ok(exists) => {
                     if !exists {
@@ -483,18 +658,18 @@ impl StreamingDSTHarness {
                 Err(e) => {
                     // Store error during check - don't count as violation
                     // (fault injection may cause this)
-                    eprintln!("Warning: Store error checking segment: {}", e);
+                    warn!(error = %e, "Store error checking segment");
                 }
             }
         }
     }
 
     async fn check_segment_validity(&mut self) {
-        let Some(ref persistence) = self.persistence else {
+        let Some(ref actor) = self.actor else {
             return;
         };
 
-        for segment in &persistence.manifest().segments {
+        for segment in &actor.persistence().manifest().segments {
             match self.store.get(&segment.key).await {
                 Ok(data) => {
                     // Try to parse the segment
@@ -541,7 +716,8 @@ impl StreamingDSTHarness {
                 let total_deltas: usize = recovered.deltas.len();
 
                 // Check manifest state
-                if let Some(ref persistence) = self.persistence {
+                if let Some(ref actor) = self.actor {
+                    let persistence = actor.persistence();
                     let expected_segments = persistence.manifest().segments.len();
                     // Recovery should see same number of segments
                     // (may differ slightly due to crash timing)
@@ -559,7 +735,60 @@ impl StreamingDSTHarness {
             }
             Err(e) => {
                 // Recovery failure under fault injection is expected
-                eprintln!("Warning: Recovery failed (may be expected): {}", e);
+                warn!(error = %e, "Recovery failed under fault injection");
+            }
+        }
+    }
+
+    async fn check_acked_writes_survive(&mut self) {
+        let Some(ref actor) = self.actor else {
+            return;
+        };
+        let persistence = actor.persistence();
+        if persistence.unsynced_deltas() > 0 || persistence.is_degraded() {
+            self.result.invariant_violations.push(format!(
+                "{} deltas still unsynced after the store came back (degraded: {})",
+                persistence.unsynced_deltas(),
+                persistence.is_degraded()
+            ));
+            return;
+        }
+
+        let recovery = RecoveryManager::new(
+            (*self.store).clone(),
+            &self.config.prefix,
+            self.config.replica_id,
+        );
+        let mut recovered = None;
+        for _ in 0..MAX_RESYNC_ATTEMPTS {
+            if let Ok(state) = recovery.recover().await {
+                recovered = Some(state);
+                break;
+            }
+        }
+        let Some(recovered) = recovered else {
+            self.result
+                .invariant_violations
+                .push("Recovery never succeeded after the outage".to_string());
+            return;
+        };
+
+        // Segments replay in write order, so the last delta per key wins
+        let mut persisted: HashMap<String, Option<String>> = HashMap::new();
+        for delta in &recovered.deltas {
+            let value = delta
+                .value
+                .get()
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned());
+            persisted.insert(delta.key.clone(), value);
+        }
+        for (key, expected) in self.workload.expected_state() {
+            let actual = persisted.get(key).cloned().flatten();
+            if actual != *expected {
+                self.result.invariant_violations.push(format!(
+                    "Acknowledged write lost: {} = {:?}, recovered {:?}",
+                    key, expected, actual
+                ));
             }
         }
     }
@@ -581,6 +810,13 @@ impl StreamingDSTHarness {
     }
 }
 
+/// Store calls allowed for the outage to end before a check gives up
+const MAX_RESYNC_ATTEMPTS: usize = 1000;
+
+/// Most deltas the bridge forwards in one batch. With outage mode's
+/// `max_parked_bytes`, the write-refusal mark's headroom holds this many.
+const MAX_BRIDGE_BATCH: usize = 8;
+
 /// Run a batch of DST tests with different seeds
 pub async fn run_dst_batch(
     base_seed: u64,
@@ -711,6 +947,52 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_dst_batch_cloud() {
+        // Outages and throttling fail operations but never lose or corrupt
+        // acknowledged data, so every run must hold its invariants
+        let results = run_dst_batch(4000, 10, 150, StreamingDSTConfig::cloud).await;
+
+        let summary = summarize_batch(&results);
+        println!("{}", summary);
+
+        assert!(
+            results.iter().all(|r| r.is_success()),
+            "Cloud-mode runs must not violate invariants: {}",
+            summary
+        );
+        let rejected: u64 = results
+            .iter()
+            .map(|r| r.store_stats.outage_rejections + r.store_stats.throttled)
+            .sum();
+        assert!(rejected > 0, "cloud mode should exercise outages/throttling");
+    }
+
+    #[tokio::test]
+    async fn test_dst_batch_outage() {
+        // Outages outlast degraded_after; every acknowledged write must
+        // still be recovered once the store comes back
+        let results = run_dst_batch(5000, 10, 300, StreamingDSTConfig::outage).await;
+
+        let summary = summarize_batch(&results);
+        println!("{}", summary);
+
+        assert!(
+            results.iter().all(|r| r.is_success()),
+            "Outage runs must not lose acknowledged writes: {}",
+            summary
+        );
+        assert!(
+            results.iter().all(|r| r.degraded_operations > 0),
+            "every run should spend time degraded"
+        );
+        assert!(
+            results.iter().any(|r| r.refused_writes > 0),
+            "outages should reach the write-refusal mark"
+        );
+        assert!(results.iter().all(|r| r.parked_rejections == 0));
+    }
+
     #[tokio::test]
     async fn test_workload_generator() {
         let config = StreamingDSTConfig::new(42);
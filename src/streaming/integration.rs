@@ -10,23 +10,25 @@
 //! 2. integration.recover(state).await
 //! 3. integration.start_workers(state)
 //! 4. [server runs]
-//! 5. integration.shutdown().await
+//! 5. handles.shutdown().await       (final flush)
+//! 6. integration.write_checkpoint(state).await
 //! ```
 
 use crate::production::ReplicatedShardedState;
 #[cfg(feature = "s3")]
 use crate::streaming::S3ObjectStore;
 use crate::streaming::{
-    delta_sink_channel, CompactionConfig, CompactionWorker, CompactionWorkerHandle, Compactor,
-    DeltaSinkReceiver, DeltaSinkSender, InMemoryObjectStore, LocalFsObjectStore, ManifestManager,
-    ObjectStore, ObjectStoreType, RecoveryError, RecoveryManager, RecoveryPhase, RecoveryStats,
-    StreamingConfig, StreamingPersistence,
+    delta_sink_channel, CheckpointConfig, CheckpointInfo, CheckpointManager, CheckpointResult,
+    CompactionConfig, CompactionWorker, CompactionWorkerHandle, Compactor, DeltaSinkReceiver,
+    DeltaSinkSender, InMemoryObjectStore, LocalFsObjectStore, ManifestManager, ObjectStore,
+    ObjectStoreType, PersistenceHealth, ProductionClock, RecoveryError, RecoveryManager,
+    RecoveryPhase, RecoveryStats, StreamingClock, StreamingConfig, StreamingPersistence,
 };
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::task::JoinHandle;
-use tracing::{error, info};
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{error, info, warn};
 
 /// Error type for integration operations
 #[derive(Debug)]
@@ -91,6 +93,8 @@ pub struct WorkerHandles {
     compaction_handle: Option<CompactionWorkerHandle>,
     /// Compaction worker task (if enabled)
     compaction_task: Option<JoinHandle<()>>,
+    /// Object store health reported by the persistence actor
+    health: PersistenceHealth,
 }
 
 impl WorkerHandles {
@@ -122,6 +126,49 @@ impl WorkerHandles {
 
         info!("Streaming persistence shutdown complete");
     }
+
+    /// Degraded flag and re-sync backlog, for INFO persistence
+    pub fn health(&self) -> PersistenceHealth {
+        self.health.clone()
+    }
+
+    /// Closure describing each worker as running or exited, for the watchdog
+    pub fn status_probe(&self) -> impl Fn() -> String + Send + Sync + 'static {
+        let workers = self.worker_tasks();
+        move || {
+            workers
+                .iter()
+                .map(|(name, task)| {
+                    let state = if task.is_finished() { "exited" } else { "running" };
+                    format!("{} {}", name, state)
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+
+    /// Closure naming the workers that have exited, for health checks
+    pub fn exited_probe(&self) -> impl Fn() -> Vec<&'static str> + Send + Sync + 'static {
+        let workers = self.worker_tasks();
+        move || {
+            workers
+                .iter()
+                .filter(|(_, task)| task.is_finished())
+                .map(|(name, _)| *name)
+                .collect()
+        }
+    }
+
+    fn worker_tasks(&self) -> Vec<(&'static str, AbortHandle)> {
+        let mut workers = vec![
+            ("bridge", self.bridge_task.abort_handle()),
+            ("persistence", self.actor_task.abort_handle()),
+        ];
+        if let Some(task) = &self.compaction_task {
+            workers.push(("compaction", task.abort_handle()));
+        }
+        workers
+    }
 }
 
 /// Streaming persistence integration helper
@@ -260,6 +307,7 @@ impl<S: ObjectStore + Clone + Send + Sync + 'static> StreamingIntegration<S> {
         )
         .await
         .map_err(|e| IntegrationError::Persistence(e.to_string()))?;
+        let health = persistence.health();
 
         // Spawn persistence actor (owns state, processes messages)
         let (actor_handle, actor_task) = spawn_persistence_actor(persistence);
@@ -329,11 +377,113 @@ impl<S: ObjectStore + Clone + Send + Sync + 'static> StreamingIntegration<S> {
             bridge_task,
             compaction_handle,
             compaction_task,
+            health,
         };
 
         Ok((handles, sender))
     }
 
+    /// Write a checkpoint of `state` covering every segment in the manifest.
+    ///
+    /// Called on graceful shutdown, after `WorkerHandles::shutdown` has
+    /// flushed the write buffer, so the checkpoint subsumes all segments and
+    /// the next startup loads one file instead of replaying the log. Covered
+    /// segments are removed from the manifest and then deleted (best effort:
+    /// a leftover segment is ignored by recovery).
+    ///
+    /// Returns `Ok(None)` if no segment has ever been written.
+    pub async fn write_checkpoint(
+        &self,
+        state: &ReplicatedShardedState,
+    ) -> Result<Option<CheckpointResult>, IntegrationError> {
+        let manifest_manager = ManifestManager::new((*self.store).clone(), &self.prefix);
+        let manifest = manifest_manager
+            .load_or_create(self.replica_id)
+            .await
+            .map_err(|e| IntegrationError::Persistence(e.to_string()))?;
+
+        // Segment ids are allocated from next_segment_id, so everything below
+        // it has been written. Nothing allocated means nothing to cover.
+        let Some(last_segment_id) = manifest.next_segment_id.checked_sub(1) else {
+            return Ok(None);
+        };
+
+        let checkpoint_config = CheckpointConfig {
+            interval: self.config.checkpoint.interval,
+            min_segments: self.config.checkpoint.min_segments,
+            compression_enabled: self.config.checkpoint.compression_enabled,
+        };
+        let manager = CheckpointManager::new(
+            self.store.clone(),
+            self.prefix.clone(),
+            manifest_manager.clone(),
+            checkpoint_config,
+        );
+        let snapshot = state.snapshot_state().await;
+        let result = manager
+            .create_checkpoint(snapshot, last_segment_id)
+            .await
+            .map_err(|e| IntegrationError::Persistence(e.to_string()))?;
+
+        let covered: Vec<String> = manifest
+            .segments
+            .iter()
+            .filter(|s| s.id <= last_segment_id)
+            .map(|s| s.key.clone())
+            .collect();
+        let info = CheckpointInfo {
+            key: result.key.clone(),
+            timestamp_ms: result.timestamp_ms,
+            key_count: result.key_count,
+            last_segment_id,
+        };
+        manifest_manager
+            .update(|m| m.compact_segments(info))
+            .await
+            .map_err(|e| IntegrationError::Persistence(e.to_string()))?;
+
+        for key in &covered {
+            if let Err(e) = self.store.delete(key).await {
+                warn!("Failed to delete checkpointed segment {}: {}", key, e);
+            }
+        }
+
+        info!(
+            "Wrote checkpoint {} ({} keys, covers segments <= {}, {} segments removed)",
+            result.key,
+            result.key_count,
+            last_segment_id,
+            covered.len()
+        );
+        Ok(Some(result))
+    }
+
+    /// Write the single checkpoint that persists a bulk import.
+    ///
+    /// Imported keys queue no deltas, so no segment holds them; a fresh
+    /// store may have no segment at all. A segment id is reserved first so
+    /// the checkpoint always has one to cover and `write_checkpoint` never
+    /// returns `None` here.
+    pub async fn write_import_checkpoint(
+        &self,
+        state: &ReplicatedShardedState,
+    ) -> Result<CheckpointResult, IntegrationError> {
+        let manifest_manager = ManifestManager::new((*self.store).clone(), &self.prefix);
+        let mut manifest = manifest_manager
+            .load_or_create(self.replica_id)
+            .await
+            .map_err(|e| IntegrationError::Persistence(e.to_string()))?;
+        manifest.allocate_segment_id();
+        manifest_manager
+            .save(&manifest)
+            .await
+            .map_err(|e| IntegrationError::Persistence(e.to_string()))?;
+
+        self.write_checkpoint(state).await?.ok_or_else(|| {
+            IntegrationError::Persistence("import checkpoint covered no segment".to_string())
+        })
+    }
+
     /// Get the object store
     pub fn store(&self) -> &Arc<S> {
         &self.store
@@ -396,6 +546,30 @@ pub trait StreamingIntegrationTrait: Send + Sync {
                 + 'a,
         >,
     >;
+
+    /// Write a shutdown checkpoint
+    fn write_checkpoint<'a>(
+        &'a self,
+        state: &'a ReplicatedShardedState,
+    ) -> std::pin::Pin<
+        Box<
+            dyn std::future::Future<Output = Result<Option<CheckpointResult>, IntegrationError>>
+                + Send
+                + 'a,
+        >,
+    >;
+
+    /// Write the checkpoint that persists a bulk import
+    fn write_import_checkpoint<'a>(
+        &'a self,
+        state: &'a ReplicatedShardedState,
+    ) -> std::pin::Pin<
+        Box<
+            dyn std::future::Future<Output = Result<CheckpointResult, IntegrationError>>
+                + Send
+                + 'a,
+        >,
+    >;
 }
 
 /// Wrapper enum for type erasure
@@ -438,6 +612,42 @@ impl StreamingIntegrationTrait for StreamingIntegrationWrapper {
             StreamingIntegrationWrapper::S3(i) => Box::pin(i.start_workers()),
         }
     }
+
+    fn write_checkpoint<'a>(
+        &'a self,
+        state: &'a ReplicatedShardedState,
+    ) -> std::pin::Pin<
+        Box<
+            dyn std::future::Future<Output = Result<Option<CheckpointResult>, IntegrationError>>
+                + Send
+                + 'a,
+        >,
+    > {
+        match self {
+            StreamingIntegrationWrapper::InMemory(i) => Box::pin(i.write_checkpoint(state)),
+            StreamingIntegrationWrapper::LocalFs(i) => Box::pin(i.write_checkpoint(state)),
+            #[cfg(feature = "s3")]
+            StreamingIntegrationWrapper::S3(i) => Box::pin(i.write_checkpoint(state)),
+        }
+    }
+
+    fn write_import_checkpoint<'a>(
+        &'a self,
+        state: &'a ReplicatedShardedState,
+    ) -> std::pin::Pin<
+        Box<
+            dyn std::future::Future<Output = Result<CheckpointResult, IntegrationError>>
+                + Send
+                + 'a,
+        >,
+    > {
+        match self {
+            StreamingIntegrationWrapper::InMemory(i) => Box::pin(i.write_import_checkpoint(state)),
+            StreamingIntegrationWrapper::LocalFs(i) => Box::pin(i.write_import_checkpoint(state)),
+            #[cfg(feature = "s3")]
+            StreamingIntegrationWrapper::S3(i) => Box::pin(i.write_import_checkpoint(state)),
+        }
+    }
 }
 
 // ============================================================================
@@ -451,7 +661,7 @@ use tokio::sync::{mpsc, oneshot};
 #[derive(Debug)]
 pub enum PersistenceMessage {
     /// Push a delta to the buffer
-    PushDelta(ReplicationDelta),
+    PushDelta(Box<ReplicationDelta>),
     /// Push multiple deltas (batch)
     PushDeltas(Vec<ReplicationDelta>),
     /// Force a flush
@@ -466,76 +676,94 @@ pub enum PersistenceMessage {
 
 /// Actor that owns StreamingPersistence exclusively.
 /// Uses bounded channel (PERSISTENCE_CHANNEL_CAPACITY) to prevent unbounded memory growth.
-struct PersistenceActor<S: ObjectStore + Clone + 'static> {
-    persistence: StreamingPersistence<S>,
-    rx: mpsc::Receiver<PersistenceMessage>,
+///
+/// Generic over the clock so the streaming DST can step the same message
+/// handling with simulated time.
+pub(crate) struct PersistenceActor<
+    S: ObjectStore + Clone + 'static,
+    C: StreamingClock = ProductionClock,
+> {
+    persistence: StreamingPersistence<S, C>,
 }
 
-impl<S: ObjectStore + Clone + Send + Sync + 'static> PersistenceActor<S> {
-    fn new(
-        persistence: StreamingPersistence<S>,
-        rx: mpsc::Receiver<PersistenceMessage>,
-    ) -> Self {
-        PersistenceActor { persistence, rx }
+impl<S: ObjectStore + Clone + Send + Sync + 'static, C: StreamingClock> PersistenceActor<S, C> {
+    pub(crate) fn new(persistence: StreamingPersistence<S, C>) -> Self {
+        PersistenceActor { persistence }
     }
 
-    async fn run(mut self) {
-        while let Some(msg) = self.rx.recv().await {
-            match msg {
-                PersistenceMessage::PushDelta(delta) => {
-                    if let Err(e) = self.persistence.push(delta) {
-                        error!("Failed to push delta: {}", e);
-                    }
-                    // Auto-flush if needed
-                    if self.persistence.should_flush() {
-                        if let Err(e) = self.persistence.flush().await {
-                            error!("Failed to flush: {}", e);
-                        }
-                    }
+    pub(crate) fn persistence(&self) -> &StreamingPersistence<S, C> {
+        &self.persistence
+    }
+
+    async fn run(mut self, mut rx: mpsc::Receiver<PersistenceMessage>) {
+        while let Some(msg) = rx.recv().await {
+            if !self.handle(msg).await {
+                break;
+            }
+        }
+    }
+
+    /// Handle one message. Returns false once the actor has shut down.
+    ///
+    /// Deltas arrive after their clients were acknowledged. The command path
+    /// stops taking key writes at `refuse_writes_at`, so a push rejected at
+    /// `max_parked_bytes` means more than that headroom was in flight.
+    pub(crate) async fn handle(&mut self, msg: PersistenceMessage) -> bool {
+        match msg {
+            PersistenceMessage::PushDelta(delta) => {
+                if let Err(e) = self.persistence.push(*delta) {
+                    error!("Failed to push acknowledged delta: {}", e);
                 }
-                PersistenceMessage::PushDeltas(deltas) => {
-                    for delta in deltas {
-                        if let Err(e) = self.persistence.push(delta) {
-                            error!("Failed to push delta: {}", e);
-                            break;
-                        }
-                    }
-                    // Auto-flush if needed
-                    if self.persistence.should_flush() {
-                        if let Err(e) = self.persistence.flush().await {
-                            error!("Failed to flush: {}", e);
-                        }
+                // Auto-flush if needed
+                if self.persistence.should_flush() {
+                    if let Err(e) = self.persistence.flush().await {
+                        error!("Failed to flush: {}", e);
                     }
                 }
-                PersistenceMessage::Flush { response_tx } => {
-                    let result = self
-                        .persistence
-                        .flush()
-                        .await
-                        .map(|_| ()) // Discard FlushResult, just return ()
-                        .map_err(|e| e.to_string());
-                    let _ = response_tx.send(result);
+            }
+            PersistenceMessage::PushDeltas(deltas) => {
+                for delta in deltas {
+                    if let Err(e) = self.persistence.push(delta) {
+                        error!("Failed to push acknowledged delta: {}", e);
+                        break;
+                    }
                 }
-                PersistenceMessage::Tick => {
-                    if self.persistence.should_flush() {
-                        if let Err(e) = self.persistence.flush().await {
-                            error!("Failed periodic flush: {}", e);
-                        }
+                // Auto-flush if needed
+                if self.persistence.should_flush() {
+                    if let Err(e) = self.persistence.flush().await {
+                        error!("Failed to flush: {}", e);
                     }
                 }
-                PersistenceMessage::Shutdown { response_tx } => {
-                    // Final flush before shutdown
-                    info!("Persistence actor shutting down, performing final flush...");
+            }
+            PersistenceMessage::Flush { response_tx } => {
+                let result = self
+                    .persistence
+                    .flush()
+                    .await
+                    .map(|_| ()) // Discard FlushResult, just return ()
+                    .map_err(|e| e.to_string());
+                let _ = response_tx.send(result);
+            }
+            PersistenceMessage::Tick => {
+                if self.persistence.should_flush() {
                     if let Err(e) = self.persistence.flush().await {
-                        error!("Failed final flush: {}", e);
-                    } else {
-                        info!("Final flush complete");
+                        error!("Failed periodic flush: {}", e);
                     }
-                    let _ = response_tx.send(());
-                    break;
                 }
             }
+            PersistenceMessage::Shutdown { response_tx } => {
+                // Final flush before shutdown
+                info!("Persistence actor shutting down, performing final flush...");
+                if let Err(e) = self.persistence.flush().await {
+                    error!("Failed final flush: {}", e);
+                } else {
+                    info!("Final flush complete");
+                }
+                let _ = response_tx.send(());
+                return false;
+            }
         }
+        true
     }
 }
 
@@ -552,7 +780,7 @@ impl PersistenceActorHandle {
     /// Push a delta (fire-and-forget, drops if channel full)
     #[inline]
     pub fn push_delta(&self, delta: ReplicationDelta) {
-        let _ = self.tx.try_send(PersistenceMessage::PushDelta(delta));
+        let _ = self.tx.try_send(PersistenceMessage::PushDelta(Box::new(delta)));
     }
 
     /// Push multiple deltas (fire-and-forget, drops if channel full)
@@ -612,8 +840,8 @@ fn spawn_persistence_actor<S: ObjectStore + Clone + Send + Sync + 'static>(
     persistence: StreamingPersistence<S>,
 ) -> (PersistenceActorHandle, JoinHandle<()>) {
     let (tx, rx) = mpsc::channel(PERSISTENCE_CHANNEL_CAPACITY);
-    let actor = PersistenceActor::new(persistence, rx);
-    let task = tokio::spawn(actor.run());
+    let actor = PersistenceActor::new(persistence);
+    let task = tokio::spawn(actor.run(rx));
     (PersistenceActorHandle { tx }, task)
 }
 
@@ -687,6 +915,7 @@ mod tests {
 
         // Start workers
         let (handles, _sender) = integration.start_workers().await.unwrap();
+        assert!(!handles.health().is_degraded());
 
         // Graceful shutdown
         handles.shutdown().await;
@@ -736,4 +965,98 @@ mod tests {
         // Shutdown
         handles.shutdown().await;
     }
+
+    #[tokio::test]
+    async fn test_key_writes_refused_while_persistence_refuses() {
+        use crate::io::simulation::SimulatedRng;
+        use crate::redis::{RespValue, SDS};
+        use crate::replication::lattice::{LamportClock, ReplicaId};
+        use crate::replication::state::ReplicatedValue;
+        use crate::streaming::{
+            SimulatedClock, SimulatedObjectStore, SimulatedStoreConfig, WriteBufferConfig,
+        };
+
+        // The store goes down right after the manifest loads
+        let store = Arc::new(SimulatedObjectStore::new(
+            InMemoryObjectStore::new(),
+            SimulatedRng::new(7),
+            SimulatedStoreConfig::no_faults().with_outage(2, 1000),
+        ));
+        let config = WriteBufferConfig {
+            max_parked_bytes: 1024,
+            ..WriteBufferConfig::test()
+        };
+        let persistence = StreamingPersistence::with_clock(
+            store,
+            "refuse".to_string(),
+            1,
+            config,
+            SimulatedClock::new(0),
+        )
+        .await
+        .unwrap();
+        let health = persistence.health();
+        let mut actor = PersistenceActor::new(persistence);
+
+        let repl_config = ReplicationConfig {
+            enabled: false,
+            replica_id: 1,
+            consistency_level: ConsistencyLevel::Eventual,
+            gossip_interval_ms: 100,
+            peers: vec![],
+            replication_factor: 3,
+            partitioned_mode: false,
+            selective_gossip: false,
+            virtual_nodes_per_physical: 150,
+        };
+        let mut state = ReplicatedShardedState::new(repl_config);
+        state.set_persistence_health(health.clone());
+
+        // Acknowledged deltas fill the parked bytes up to the mark
+        let replica_id = ReplicaId::new(1);
+        let mut time = 0;
+        while !health.is_refusing_writes() {
+            time += 1;
+            let value =
+                ReplicatedValue::with_value(SDS::from_str("v"), LamportClock { time, replica_id });
+            let delta = ReplicationDelta::new(format!("key{}", time), value, replica_id);
+            assert!(
+                actor
+                    .handle(PersistenceMessage::PushDelta(Box::new(delta)))
+                    .await
+            );
+            let (response_tx, response_rx) = oneshot::channel();
+            assert!(
+                actor
+                    .handle(PersistenceMessage::Flush { response_tx })
+                    .await
+            );
+            assert!(response_rx.await.unwrap().is_err(), "the store is down");
+        }
+        assert!(actor.persistence().unsynced_segments() > 1);
+        assert_eq!(actor.persistence().stats().parked_rejections, 0);
+        assert!(actor.persistence().unsynced_bytes() >= 512);
+
+        // Key writes are refused before they are applied; reads still work
+        let refused = state
+            .execute(crate::redis::Command::set(
+                "k".to_string(),
+                SDS::from_str("v"),
+            ))
+            .await;
+        assert!(matches!(refused, RespValue::Error(ref e) if e.starts_with("MISCONF")));
+        assert_eq!(
+            state
+                .execute(crate::redis::Command::Get("k".to_string()))
+                .await,
+            RespValue::BulkString(None)
+        );
+        let exec = state
+            .execute_transaction(vec![
+                crate::redis::Command::Get("k".to_string()),
+                crate::redis::Command::del("k".to_string()),
+            ])
+            .await;
+        assert!(matches!(exec, RespValue::Error(ref e) if e.starts_with("MISCONF")));
+    }
 }
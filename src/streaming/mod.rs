@@ -35,6 +35,8 @@ pub mod recovery;
 pub mod s3_store;
 pub mod segment;
 pub mod simulated_store;
+pub mod snapshot_diff;
+pub mod transaction_wal_dst;
 pub mod wal;
 pub mod wal_actor;
 pub mod wal_config;
@@ -79,8 +81,8 @@ pub use manifest::{CheckpointInfo, Manifest, ManifestError, ManifestManager, Seg
 pub use object_store::{InMemoryObjectStore, LocalFsObjectStore};
 pub use object_store::{ListResult, ObjectMeta, ObjectStore, ObjectStoreError};
 pub use persistence::{
-    FlushResult, PersistenceError, PersistenceStats, PersistenceWorker, PersistenceWorkerHandle,
-    StreamingPersistence,
+    FlushResult, PersistenceError, PersistenceHealth, PersistenceStats, PersistenceWorker,
+    PersistenceWorkerHandle, StreamingPersistence,
 };
 pub use recovery::{
     RecoveredState, RecoveryError, RecoveryManager, RecoveryPhase, RecoveryProgress, RecoveryStats,
@@ -91,8 +93,13 @@ pub use segment::{
     Compression, Segment, SegmentError, SegmentFooter, SegmentHeader, SegmentReader, SegmentWriter,
 };
 pub use simulated_store::{SimulatedObjectStore, SimulatedStoreConfig, SimulatedStoreStats};
+pub use snapshot_diff::{Expiry, Snapshot, SnapshotDiff, SnapshotFormat, SnapshotKey, TypeCounts};
+pub use transaction_wal_dst::{
+    run_transaction_wal_dst_batch, summarize_transaction_wal_dst_batch, ExecCrashPoint,
+    TransactionWalDSTConfig, TransactionWalDSTHarness, TransactionWalDSTResult,
+};
 pub use wal::{WalEntry, WalReader, WalRotator, WalWriter};
-pub use wal_actor::{spawn_wal_actor, WalActorHandle, WalMessage};
+pub use wal_actor::{spawn_wal_actor, WalActorHandle, WalMessage, WalRecord};
 pub use wal_config::{FsyncPolicy, WalConfig};
 pub use wal_dst::{
     run_wal_dst_batch, summarize_wal_dst_batch, WalDSTConfig, WalDSTHarness, WalDSTResult,
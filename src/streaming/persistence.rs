@@ -17,6 +17,29 @@
 //!         ManifestManager::add_segment()
 //! ```
 //!
+//! ## Object Store Outages
+//!
+//! A batch whose upload fails is parked, not dropped: the next flush
+//! re-syncs parked batches oldest first, one segment each, before the new
+//! buffer, so segments keep their write order. The server keeps serving
+//! from memory while the local WAL holds the same writes durably.
+//!
+//! Parked batches are bounded: once buffered plus parked bytes would pass
+//! `max_parked_bytes`, `push` rejects the delta with backpressure, so a
+//! long outage cannot grow memory without limit.
+//!
+//! A delta reaches `push` after its client was acknowledged, so a rejection
+//! there would lose an acknowledged write. The command path therefore stops
+//! earlier: once unsynced bytes reach half the bound, `PersistenceHealth`
+//! reports writes refused and `ReplicatedShardedState` answers key writes
+//! with MISCONF. The other half is headroom for deltas already on their way
+//! to the persistence actor. The first flush that drains below the mark
+//! lets writes through again.
+//!
+//! Once flushes have failed for `degraded_after`, the store is reported
+//! degraded through `PersistenceHealth`, which INFO reads. The first flush
+//! that gets every parked batch through clears the flag.
+//!
 //! ## DST Compatibility
 //!
 //! All I/O through ObjectStore trait. Time through StreamingClock trait.
@@ -28,7 +51,10 @@ use crate::streaming::{
     SegmentError, SegmentInfo, SegmentWriter, StreamingClock, StreamingTimestamp,
     WriteBufferConfig, WriteBufferError,
 };
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use tracing::{error, info, warn};
 
 /// Error type for persistence operations
 #[derive(Debug)]
@@ -93,6 +119,46 @@ pub struct PersistenceStats {
     pub manifest_updates: u64,
     /// Flush errors
     pub flush_errors: u64,
+    /// Segments written for batches parked by a failed flush
+    pub segments_resynced: u64,
+    /// Deltas rejected because parked batches reached `max_parked_bytes`
+    pub parked_rejections: u64,
+}
+
+/// Object store health as the flush path sees it, shared with INFO
+#[derive(Debug, Clone, Default)]
+pub struct PersistenceHealth {
+    degraded: Arc<AtomicBool>,
+    unsynced_segments: Arc<AtomicU64>,
+    refusing_writes: Arc<AtomicBool>,
+}
+
+impl PersistenceHealth {
+    /// True while flushes have been failing for longer than `degraded_after`
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Batches parked by failed flushes, waiting to be re-synced
+    pub fn unsynced_segments(&self) -> u64 {
+        self.unsynced_segments.load(Ordering::Relaxed)
+    }
+
+    /// True while unsynced bytes are at or past half of `max_parked_bytes`;
+    /// the command path refuses key writes until a flush drains them
+    pub fn is_refusing_writes(&self) -> bool {
+        self.refusing_writes.load(Ordering::Relaxed)
+    }
+
+    fn publish(&self, degraded: bool, unsynced_segments: usize) {
+        self.degraded.store(degraded, Ordering::Relaxed);
+        self.unsynced_segments
+            .store(unsynced_segments as u64, Ordering::Relaxed);
+    }
+
+    fn publish_refusing_writes(&self, refusing: bool) {
+        self.refusing_writes.store(refusing, Ordering::Relaxed);
+    }
 }
 
 /// Result of a flush operation
@@ -125,6 +191,14 @@ pub struct StreamingPersistence<
     buffer: Vec<ReplicationDelta>,
     /// Estimated buffer size in bytes
     buffer_size: usize,
+    /// Batches whose upload failed, oldest first
+    parked: VecDeque<Vec<ReplicationDelta>>,
+    /// Estimated size of the parked batches in bytes
+    parked_bytes: usize,
+    /// When the current run of failed flushes began
+    outage_since: Option<StreamingTimestamp>,
+    /// Degraded flag and re-sync backlog, published after every flush
+    health: PersistenceHealth,
     /// Clock for time operations (DST-compatible)
     clock: C,
     /// Last flush time
@@ -183,6 +257,10 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
             manifest,
             buffer: Vec::new(),
             buffer_size: 0,
+            parked: VecDeque::new(),
+            parked_bytes: 0,
+            outage_since: None,
+            health: PersistenceHealth::default(),
             clock,
             last_flush,
             stats: PersistenceStats::default(),
@@ -191,7 +269,8 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
 
     /// Push a delta to the buffer
     ///
-    /// Returns error if backpressure threshold is exceeded.
+    /// Returns error if backpressure threshold is exceeded, or if the delta
+    /// would take buffered plus parked bytes past `max_parked_bytes`.
     pub fn push(&mut self, delta: ReplicationDelta) -> Result<(), PersistenceError> {
         // Check backpressure
         if self.buffer_size >= self.config.backpressure_threshold_bytes {
@@ -206,6 +285,22 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
         // Estimate delta size
         let delta_size = estimate_delta_size(&delta);
 
+        // Check the parked bound, which only bites while flushes fail
+        let unsynced_after = self
+            .unsynced_bytes()
+            .checked_add(delta_size)
+            .expect("unsynced bytes overflow - indicates corrupted state or logic error");
+        if unsynced_after > self.config.max_parked_bytes {
+            self.stats.parked_rejections = self.stats.parked_rejections.saturating_add(1);
+            self.publish_refusing_writes();
+            return Err(PersistenceError::WriteBuffer(
+                WriteBufferError::BackpressureExceeded {
+                    pending_bytes: self.unsynced_bytes(),
+                    threshold: self.config.max_parked_bytes,
+                },
+            ));
+        }
+
         self.buffer.push(delta);
         // TigerStyle: Use checked arithmetic for buffer size to catch corruption
         self.buffer_size = self
@@ -214,14 +309,25 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
             .expect("buffer_size overflow - indicates corrupted state or logic error");
         // TigerStyle: Use saturating arithmetic for stats (counters, non-critical)
         self.stats.deltas_pushed = self.stats.deltas_pushed.saturating_add(1);
+        self.publish_refusing_writes();
 
+        // TigerStyle: Postcondition - accepted deltas stay within the bound
+        debug_assert!(
+            self.unsynced_bytes() <= self.config.max_parked_bytes,
+            "Postcondition violated: buffered plus parked bytes must fit max_parked_bytes"
+        );
         Ok(())
     }
 
     /// Check if buffer should be flushed
+    ///
+    /// Parked batches are retried every flush interval.
     pub fn should_flush(&self) -> bool {
         if self.buffer.is_empty() {
-            return false;
+            return !self.parked.is_empty()
+                && self
+                    .clock
+                    .has_elapsed(self.last_flush, self.config.flush_interval);
         }
 
         // Size threshold
@@ -247,9 +353,11 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
 
     /// Flush buffer to object store and update manifest
     ///
-    /// Returns info about the flush operation.
+    /// Re-syncs parked batches first. On error every batch not yet written
+    /// stays parked for the next flush. Returns info about the flush
+    /// operation; `segment` is the last segment written.
     pub async fn flush(&mut self) -> Result<FlushResult, PersistenceError> {
-        if self.buffer.is_empty() {
+        if self.buffer.is_empty() && self.parked.is_empty() {
             return Ok(FlushResult {
                 segment: None,
                 deltas_flushed: 0,
@@ -257,11 +365,77 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
             });
         }
 
-        let deltas = std::mem::take(&mut self.buffer);
-        let deltas_count = deltas.len();
-        self.buffer_size = 0;
+        let mut resync_left = self.parked.len();
+        if !self.buffer.is_empty() {
+            self.parked.push_back(std::mem::take(&mut self.buffer));
+            self.parked_bytes = self
+                .parked_bytes
+                .checked_add(self.buffer_size)
+                .expect("parked_bytes overflow - indicates corrupted state or logic error");
+            self.buffer_size = 0;
+        }
         self.last_flush = self.clock.now();
 
+        let mut result = FlushResult {
+            segment: None,
+            deltas_flushed: 0,
+            bytes_written: 0,
+        };
+        while let Some(deltas) = self.parked.pop_front() {
+            match self.write_segment(&deltas).await {
+                Ok(segment_info) => {
+                    let written = deltas.iter().map(estimate_delta_size).sum::<usize>();
+                    self.parked_bytes = self
+                        .parked_bytes
+                        .checked_sub(written)
+                        .expect("parked_bytes underflow - a parked batch was not counted");
+                    result.deltas_flushed += deltas.len();
+                    result.bytes_written += segment_info.size_bytes;
+                    result.segment = Some(segment_info);
+                    if resync_left > 0 {
+                        resync_left -= 1;
+                        self.stats.segments_resynced =
+                            self.stats.segments_resynced.saturating_add(1);
+                    }
+                }
+                Err(e) => {
+                    self.parked.push_front(deltas);
+                    self.record_outage();
+                    self.publish_refusing_writes();
+                    return Err(e);
+                }
+            }
+        }
+
+        if let Some(since) = self.outage_since.take() {
+            info!(
+                "Object store reachable again after {:?}, parked segments re-synced ({} in total)",
+                self.clock.now().saturating_sub(since),
+                self.stats.segments_resynced
+            );
+        }
+        self.health.publish(false, 0);
+        self.publish_refusing_writes();
+
+        // TigerStyle: Postcondition - a successful flush leaves nothing behind
+        debug_assert!(
+            self.buffer.is_empty() && self.parked.is_empty() && self.parked_bytes == 0,
+            "Postcondition violated: flush must write every buffered and parked delta"
+        );
+        Ok(result)
+    }
+
+    /// Write one batch as a segment and add it to the manifest
+    async fn write_segment(
+        &mut self,
+        deltas: &[ReplicationDelta],
+    ) -> Result<SegmentInfo, PersistenceError> {
+        // TigerStyle: Precondition - empty batches are never parked
+        debug_assert!(
+            !deltas.is_empty(),
+            "Precondition: a segment must hold at least one delta"
+        );
+
         // Calculate timestamps
         let min_timestamp = deltas
             .iter()
@@ -288,7 +462,7 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
 
         // Write segment
         let mut writer = SegmentWriter::new(self.compression);
-        for delta in &deltas {
+        for delta in deltas {
             writer.write_delta(delta)?;
         }
         let data = writer.finish()?;
@@ -301,7 +475,7 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
         let segment_info = SegmentInfo {
             id: segment_id,
             key: segment_key,
-            record_count: deltas_count as u32,
+            record_count: deltas.len() as u32,
             size_bytes: bytes_written,
             min_timestamp,
             max_timestamp,
@@ -317,11 +491,33 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
         self.stats.bytes_written = self.stats.bytes_written.saturating_add(bytes_written);
         self.stats.manifest_updates = self.stats.manifest_updates.saturating_add(1);
 
-        Ok(FlushResult {
-            segment: Some(segment_info),
-            deltas_flushed: deltas_count,
-            bytes_written,
-        })
+        Ok(segment_info)
+    }
+
+    /// Note a failed flush, entering degraded mode once the outage has
+    /// lasted `degraded_after`
+    fn record_outage(&mut self) {
+        let since = *self.outage_since.get_or_insert(self.clock.now());
+        let degraded = self.clock.has_elapsed(since, self.config.degraded_after);
+        if degraded && !self.health.is_degraded() {
+            warn!(
+                "Object store unreachable for {:?}, degraded: serving from memory and WAL, {} segments parked",
+                self.config.degraded_after,
+                self.parked.len()
+            );
+        }
+        self.health.publish(degraded, self.parked.len());
+    }
+
+    /// Unsynced bytes at which the command path stops taking key writes,
+    /// half of `max_parked_bytes`
+    pub fn refuse_writes_at(&self) -> usize {
+        self.config.max_parked_bytes / 2
+    }
+
+    fn publish_refusing_writes(&self) {
+        self.health
+            .publish_refusing_writes(self.unsynced_bytes() >= self.refuse_writes_at());
     }
 
     /// Force flush regardless of thresholds
@@ -349,6 +545,34 @@ impl<S: ObjectStore + Clone + 'static, C: StreamingClock> StreamingPersistence<S
         self.buffer_size
     }
 
+    /// Batches parked by failed flushes
+    pub fn unsynced_segments(&self) -> usize {
+        self.parked.len()
+    }
+
+    /// Estimated bytes accepted but not yet in the object store, parked or
+    /// buffered; never more than `max_parked_bytes`
+    pub fn unsynced_bytes(&self) -> usize {
+        self.parked_bytes
+            .checked_add(self.buffer_size)
+            .expect("unsynced bytes overflow - indicates corrupted state or logic error")
+    }
+
+    /// Deltas accepted but not yet in the object store, parked or buffered
+    pub fn unsynced_deltas(&self) -> usize {
+        self.buffer.len() + self.parked.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// True while flushes have been failing for longer than `degraded_after`
+    pub fn is_degraded(&self) -> bool {
+        self.health.is_degraded()
+    }
+
+    /// Shared health handle, for INFO
+    pub fn health(&self) -> PersistenceHealth {
+        self.health.clone()
+    }
+
     /// Get the manifest manager
     pub fn manifest_manager(&self) -> &ManifestManager<S> {
         &self.manifest_manager
@@ -417,7 +641,7 @@ impl<S: ObjectStore + Clone + 'static> PersistenceWorker<S> {
                 // Final flush
                 let mut p = self.persistence.lock().await;
                 if let Err(e) = p.flush().await {
-                    eprintln!("Error during final flush: {}", e);
+                    error!(error = %e, "Final flush failed");
                 }
                 break;
             }
@@ -426,7 +650,7 @@ impl<S: ObjectStore + Clone + 'static> PersistenceWorker<S> {
                 let mut p = self.persistence.lock().await;
                 if p.should_flush() {
                     if let Err(e) = p.flush().await {
-                        eprintln!("Error flushing: {}", e);
+                        warn!(error = %e, "Flush failed");
                         // TigerStyle: Use saturating arithmetic for error counter
                         p.stats.flush_errors = p.stats.flush_errors.saturating_add(1);
                     }
@@ -662,6 +886,144 @@ mod tests {
         assert!(persistence.should_flush());
     }
 
+    // DST test: a flush that fails parks its batch; the outage turns into
+    // degraded mode after degraded_after, and the batches re-sync in order
+    #[tokio::test]
+    async fn test_persistence_degrades_and_resyncs_across_outage() {
+        use crate::io::simulation::SimulatedRng;
+        use crate::streaming::{
+            RecoveryManager, SimulatedClock, SimulatedObjectStore, SimulatedStoreConfig,
+        };
+
+        let inner = InMemoryObjectStore::new();
+        let store = Arc::new(SimulatedObjectStore::new(
+            inner,
+            SimulatedRng::new(7),
+            SimulatedStoreConfig::no_faults().with_outage(10, 30),
+        ));
+        let clock = SimulatedClock::new(0);
+        let mut config = WriteBufferConfig::test();
+        config.degraded_after = std::time::Duration::from_millis(300);
+        let mut persistence = StreamingPersistence::with_clock(
+            store.clone(),
+            "outage".to_string(),
+            1,
+            config,
+            clock.clone(),
+        )
+        .await
+        .unwrap();
+
+        let mut written = 0u64;
+        let mut push = |p: &mut StreamingPersistence<_, _>| {
+            written += 1;
+            p.push(make_delta(&format!("key{}", written), "v", written))
+                .unwrap();
+            written
+        };
+        // Each failed flush parks a batch; the first 300ms are not degraded
+        let health = persistence.health();
+        let mut failures = 0;
+        loop {
+            clock.advance_ms(100);
+            let last = push(&mut persistence);
+            match persistence.flush().await {
+                Err(_) => failures += 1,
+                Ok(result) if failures > 0 => {
+                    assert!(result.deltas_flushed >= 2, "parked batches re-sync too");
+                    assert_eq!(result.segment.unwrap().max_timestamp, last);
+                    break;
+                }
+                Ok(_) => {}
+            }
+            assert_eq!(health.is_degraded(), failures > 3);
+            assert_eq!(health.unsynced_segments(), failures);
+            assert!(last < 100, "outage window never ended");
+        }
+        assert!(failures > 3, "the outage must outlast degraded_after");
+        assert!(!health.is_degraded());
+        assert_eq!(health.unsynced_segments(), 0);
+        assert_eq!(persistence.unsynced_deltas(), 0);
+        assert_eq!(persistence.stats().segments_resynced, failures);
+
+        // Every accepted delta reached the store
+        let recovered = RecoveryManager::new((*store).clone(), "outage", 1)
+            .recover()
+            .await
+            .unwrap();
+        let mut keys: Vec<u64> = recovered
+            .deltas
+            .iter()
+            .map(|d| d.key[3..].parse().unwrap())
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(keys, (1..=written).collect::<Vec<_>>());
+    }
+
+    // DST test: parked batches stop growing at max_parked_bytes; pushes are
+    // rejected until a flush gets them through, and writes are refused from
+    // half the bound
+    #[tokio::test]
+    async fn test_parked_batches_are_bounded() {
+        use crate::io::simulation::SimulatedRng;
+        use crate::streaming::{SimulatedClock, SimulatedObjectStore, SimulatedStoreConfig};
+
+        let store = Arc::new(SimulatedObjectStore::new(
+            InMemoryObjectStore::new(),
+            SimulatedRng::new(11),
+            SimulatedStoreConfig::no_faults().with_outage(2, 20),
+        ));
+        let clock = SimulatedClock::new(0);
+        let mut config = WriteBufferConfig::test();
+        config.max_parked_bytes = 1024;
+        let mut persistence = StreamingPersistence::with_clock(
+            store,
+            "bounded".to_string(),
+            1,
+            config,
+            clock.clone(),
+        )
+        .await
+        .unwrap();
+        let health = persistence.health();
+        assert_eq!(persistence.refuse_writes_at(), 512);
+
+        let mut accepted = 0u64;
+        let mut rejected = 0u64;
+        let mut refused = false;
+        for i in 0..40u64 {
+            clock.advance_ms(100);
+            match persistence.push(make_delta(&format!("key{}", i), "v", i)) {
+                Ok(()) => accepted += 1,
+                Err(PersistenceError::WriteBuffer(WriteBufferError::BackpressureExceeded {
+                    threshold,
+                    ..
+                })) => {
+                    assert_eq!(threshold, 1024);
+                    rejected += 1;
+                }
+                Err(e) => panic!("unexpected push error: {}", e),
+            }
+            assert!(persistence.unsynced_bytes() <= 1024);
+            let _ = persistence.flush().await;
+            assert_eq!(
+                health.is_refusing_writes(),
+                persistence.unsynced_bytes() >= 512
+            );
+            refused |= health.is_refusing_writes();
+        }
+        assert!(refused, "the outage must reach the write-refusal mark");
+        assert!(rejected > 0, "the outage must fill the parked bound");
+        assert_eq!(persistence.stats().parked_rejections, rejected);
+
+        // Once the store is back, every accepted delta drains
+        persistence.flush().await.unwrap();
+        assert_eq!(persistence.unsynced_bytes(), 0);
+        assert!(!health.is_refusing_writes());
+        assert_eq!(persistence.stats().deltas_pushed, accepted);
+        persistence.push(make_delta("after", "v", 100)).unwrap();
+    }
+
     // DST test: deterministic replay with same seed produces same results
     #[tokio::test]
     async fn test_persistence_deterministic_replay() {
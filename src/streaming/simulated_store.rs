@@ -2,10 +2,23 @@
 //!
 //! DST-compatible wrapper that injects faults using buggify.
 //! Follows FoundationDB patterns for deterministic simulation testing.
+//!
+//! Besides independent per-operation faults, the store models cloud
+//! behaviour that spans many operations. Time is a logical tick advanced by
+//! every store call, so windows are deterministic for a given seed:
+//!
+//! - **Region outage**: every operation fails for a window of ticks, either
+//!   scheduled (`scheduled_outages`) or started at random (`outage_prob`)
+//! - **Throttling bursts**: a window in which operations are rejected with
+//!   503 SlowDown at `throttle_reject_prob`
+//! - **List-after-write lag** (pre-2020 S3): newly created keys are missing
+//!   from LIST, and deleted keys still listed, for `list_lag_ops` ticks. GET
+//!   and HEAD stay read-after-write consistent.
 
 use crate::buggify::faults::object_store as faults;
 use crate::io::Rng;
 use crate::streaming::{ListResult, ObjectMeta, ObjectStore};
+use std::collections::HashMap;
 use std::future::Future;
 use std::io::{Error as IoError, ErrorKind, Result as IoResult};
 use std::pin::Pin;
@@ -32,6 +45,21 @@ pub struct SimulatedStoreConfig {
     pub rename_fail_prob: f64,
     /// Simulated latency range in microseconds (min, max)
     pub latency_range_us: (u64, u64),
+    /// Probability per operation that a region outage begins
+    pub outage_prob: f64,
+    /// Length of a random outage in ticks (min, max)
+    pub outage_duration_ops: (u64, u64),
+    /// Scripted outage windows as `[start, end)` tick ranges
+    pub scheduled_outages: Vec<(u64, u64)>,
+    /// Probability per operation that a throttling burst begins
+    pub throttle_burst_prob: f64,
+    /// Length of a throttling burst in ticks (min, max)
+    pub throttle_burst_ops: (u64, u64),
+    /// Probability that an operation inside a burst is rejected with 503
+    pub throttle_reject_prob: f64,
+    /// Ticks before a created (deleted) key appears in (vanishes from) LIST;
+    /// 0 = strongly consistent listing
+    pub list_lag_ops: u64,
 }
 
 impl Default for SimulatedStoreConfig {
@@ -46,6 +74,7 @@ impl Default for SimulatedStoreConfig {
             list_incomplete_prob: 0.02,      // 2%
             rename_fail_prob: 0.01,          // 1%
             latency_range_us: (100, 10_000), // 0.1ms - 10ms
+            ..SimulatedStoreConfig::no_faults()
         }
     }
 }
@@ -63,6 +92,7 @@ impl SimulatedStoreConfig {
             list_incomplete_prob: 0.05,
             rename_fail_prob: 0.05,
             latency_range_us: (1_000, 100_000),
+            ..SimulatedStoreConfig::no_faults()
         }
     }
 
@@ -78,8 +108,61 @@ impl SimulatedStoreConfig {
             list_incomplete_prob: 0.0,
             rename_fail_prob: 0.0,
             latency_range_us: (0, 0),
+            outage_prob: 0.0,
+            outage_duration_ops: (0, 0),
+            scheduled_outages: Vec::new(),
+            throttle_burst_prob: 0.0,
+            throttle_burst_ops: (0, 0),
+            throttle_reject_prob: 0.0,
+            list_lag_ops: 0,
+        }
+    }
+
+    /// Region outages: scripted and random windows where every call fails
+    pub fn region_outage() -> Self {
+        SimulatedStoreConfig {
+            outage_prob: 0.01,
+            outage_duration_ops: (5, 50),
+            scheduled_outages: vec![(20, 40)],
+            ..SimulatedStoreConfig::no_faults()
+        }
+    }
+
+    /// Eventual consistency: LIST lags writes and deletes (pre-2020 S3)
+    pub fn eventual_consistency() -> Self {
+        SimulatedStoreConfig {
+            list_lag_ops: 20,
+            ..SimulatedStoreConfig::no_faults()
+        }
+    }
+
+    /// Bursts of 503 SlowDown rejections
+    pub fn throttled() -> Self {
+        SimulatedStoreConfig {
+            throttle_burst_prob: 0.02,
+            throttle_burst_ops: (10, 40),
+            throttle_reject_prob: 0.7,
+            ..SimulatedStoreConfig::no_faults()
+        }
+    }
+
+    /// Outages, throttling and list lag together, without per-op faults
+    pub fn cloud_chaos() -> Self {
+        SimulatedStoreConfig {
+            list_lag_ops: 20,
+            throttle_burst_prob: 0.02,
+            throttle_burst_ops: (10, 40),
+            throttle_reject_prob: 0.7,
+            ..SimulatedStoreConfig::region_outage()
         }
     }
+
+    /// Add a scripted outage covering ticks `[start, end)`
+    pub fn with_outage(mut self, start: u64, end: u64) -> Self {
+        debug_assert!(start < end, "Precondition: outage window must be non-empty");
+        self.scheduled_outages.push((start, end));
+        self
+    }
 }
 
 /// Statistics for fault injection
@@ -98,12 +181,131 @@ pub struct SimulatedStoreStats {
     pub rename_failures: u64,
     pub timeouts: u64,
     pub partial_writes: u64,
+    /// Random outages started (scheduled windows are not counted)
+    pub outages_started: u64,
+    /// Operations rejected because the store was in an outage
+    pub outage_rejections: u64,
+    pub throttle_bursts: u64,
+    /// Operations rejected with 503 SlowDown
+    pub throttled: u64,
+    /// LIST entries hidden or resurrected by list-after-write lag
+    pub list_lagged: u64,
 }
 
 /// Inner state for the simulated store
 struct SimulatedStoreInner<R: Rng> {
     rng: R,
     stats: SimulatedStoreStats,
+    /// Logical clock, advanced by every store call
+    tick: u64,
+    /// Random outage in effect until this tick (exclusive)
+    outage_until: u64,
+    /// Throttling burst in effect until this tick (exclusive)
+    throttle_until: u64,
+    /// Newly created keys and the tick they become visible to LIST
+    pending_creates: HashMap<String, u64>,
+    /// Deleted keys, their last metadata and the tick they leave LIST
+    pending_deletes: HashMap<String, (ObjectMeta, u64)>,
+}
+
+impl<R: Rng> SimulatedStoreInner<R> {
+    fn random_window(&mut self, (min, max): (u64, u64)) -> u64 {
+        let len = if max > min {
+            self.rng.gen_range(min, max)
+        } else {
+            min
+        };
+        len.max(1)
+    }
+
+    /// Advance the clock and decide whether the store accepts this call.
+    ///
+    /// New RNG draws only happen for enabled modes, so configurations that
+    /// leave them off replay exactly as before.
+    fn admit(&mut self, config: &SimulatedStoreConfig) -> IoResult<()> {
+        self.tick += 1;
+        let now = self.tick;
+
+        self.pending_creates.retain(|_, visible_at| *visible_at > now);
+        self.pending_deletes.retain(|_, (_, gone_at)| *gone_at > now);
+
+        if now >= self.outage_until
+            && config.outage_prob > 0.0
+            && crate::buggify!(&mut self.rng, faults::OUTAGE, config.outage_prob)
+        {
+            self.outage_until = now + self.random_window(config.outage_duration_ops);
+            self.stats.outages_started += 1;
+        }
+        let scheduled = config
+            .scheduled_outages
+            .iter()
+            .any(|&(start, end)| start <= now && now < end);
+        if scheduled || now < self.outage_until {
+            self.stats.outage_rejections += 1;
+            return Err(IoError::new(
+                ErrorKind::ConnectionRefused,
+                "simulated region outage (503 Service Unavailable)",
+            ));
+        }
+
+        if now >= self.throttle_until
+            && config.throttle_burst_prob > 0.0
+            && crate::buggify!(&mut self.rng, faults::THROTTLE, config.throttle_burst_prob)
+        {
+            self.throttle_until = now + self.random_window(config.throttle_burst_ops);
+            self.stats.throttle_bursts += 1;
+        }
+        if now < self.throttle_until && self.rng.gen_bool(config.throttle_reject_prob) {
+            self.stats.throttled += 1;
+            return Err(IoError::new(
+                ErrorKind::Other,
+                "simulated throttling (503 SlowDown)",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Record a newly created key for list-after-write lag
+    fn note_create(&mut self, config: &SimulatedStoreConfig, key: &str) {
+        if config.list_lag_ops > 0 {
+            self.pending_deletes.remove(key);
+            self.pending_creates
+                .insert(key.to_string(), self.tick + config.list_lag_ops);
+        }
+    }
+
+    /// Record a deleted key for list-after-write lag
+    fn note_delete(&mut self, config: &SimulatedStoreConfig, meta: Option<ObjectMeta>) {
+        if config.list_lag_ops > 0 {
+            if let Some(meta) = meta {
+                self.pending_creates.remove(&meta.key);
+                let gone_at = self.tick + config.list_lag_ops;
+                self.pending_deletes.insert(meta.key.clone(), (meta, gone_at));
+            }
+        }
+    }
+
+    /// Apply list-after-write lag to a LIST page
+    fn lag_listing(&mut self, prefix: &str, first_page: bool, result: &mut ListResult) {
+        let before = result.objects.len();
+        result
+            .objects
+            .retain(|o| !self.pending_creates.contains_key(&o.key));
+        let mut lagged = (before - result.objects.len()) as u64;
+
+        // Deleted keys linger on the first page only, to keep pagination sane
+        if first_page {
+            for (key, (meta, _)) in &self.pending_deletes {
+                if key.starts_with(prefix) && !result.objects.iter().any(|o| &o.key == key) {
+                    result.objects.push(meta.clone());
+                    lagged += 1;
+                }
+            }
+            result.objects.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+        self.stats.list_lagged += lagged;
+    }
 }
 
 /// Simulated object store that wraps another store and injects faults
@@ -122,6 +324,11 @@ impl<S: ObjectStore + Clone, R: Rng> SimulatedObjectStore<S, R> {
             state: Arc::new(Mutex::new(SimulatedStoreInner {
                 rng,
                 stats: SimulatedStoreStats::default(),
+                tick: 0,
+                outage_until: 0,
+                throttle_until: 0,
+                pending_creates: HashMap::new(),
+                pending_deletes: HashMap::new(),
             })),
         }
     }
@@ -135,6 +342,23 @@ impl<S: ObjectStore + Clone, R: Rng> SimulatedObjectStore<S, R> {
             .clone()
     }
 
+    /// Current logical tick (number of store calls so far)
+    pub fn tick(&self) -> u64 {
+        self.state.lock().expect("simulated store mutex poisoned").tick
+    }
+
+    /// True if a call made now would hit an outage window
+    pub fn in_outage(&self) -> bool {
+        let s = self.state.lock().expect("simulated store mutex poisoned");
+        let next = s.tick + 1;
+        next < s.outage_until
+            || self
+                .config
+                .scheduled_outages
+                .iter()
+                .any(|&(start, end)| start <= next && next < end)
+    }
+
     /// Reset statistics
     pub fn reset_stats(&self) {
         self.state
@@ -158,6 +382,7 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
             {
                 let mut s = state.lock().expect("simulated store mutex poisoned");
                 s.stats.put_attempts += 1;
+                s.admit(&config)?;
             }
 
             // Check for timeout
@@ -224,7 +449,15 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
                 }
             }
 
-            inner.put(&key, &write_data).await
+            let created = config.list_lag_ops > 0 && !inner.exists(&key).await?;
+            inner.put(&key, &write_data).await?;
+            if created {
+                state
+                    .lock()
+                    .expect("simulated store mutex poisoned")
+                    .note_create(&config, &key);
+            }
+            Ok(())
         })
     }
 
@@ -238,6 +471,7 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
             {
                 let mut s = state.lock().expect("simulated store mutex poisoned");
                 s.stats.get_attempts += 1;
+                s.admit(&config)?;
             }
 
             // Check for timeout
@@ -313,7 +547,15 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
     fn exists(&self, key: &str) -> Pin<Box<dyn Future<Output = IoResult<bool>> + Send>> {
         let key = key.to_string();
         let inner = self.inner_store.clone();
-        Box::pin(async move { inner.exists(&key).await })
+        let state = self.state.clone();
+        let config = self.config.clone();
+        Box::pin(async move {
+            state
+                .lock()
+                .expect("simulated store mutex poisoned")
+                .admit(&config)?;
+            inner.exists(&key).await
+        })
     }
 
     fn delete(&self, key: &str) -> Pin<Box<dyn Future<Output = IoResult<()>> + Send>> {
@@ -326,6 +568,7 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
             {
                 let mut s = state.lock().expect("simulated store mutex poisoned");
                 s.stats.delete_attempts += 1;
+                s.admit(&config)?;
             }
 
             // Check for delete failure
@@ -342,7 +585,17 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
                 return Err(IoError::new(ErrorKind::Other, "simulated delete failure"));
             }
 
-            inner.delete(&key).await
+            let meta = if config.list_lag_ops > 0 {
+                inner.head(&key).await.ok()
+            } else {
+                None
+            };
+            inner.delete(&key).await?;
+            state
+                .lock()
+                .expect("simulated store mutex poisoned")
+                .note_delete(&config, meta);
+            Ok(())
         })
     }
 
@@ -361,9 +614,16 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
             {
                 let mut s = state.lock().expect("simulated store mutex poisoned");
                 s.stats.list_attempts += 1;
+                s.admit(&config)?;
             }
 
-            let result = inner.list(&prefix, token.as_deref()).await?;
+            let mut result = inner.list(&prefix, token.as_deref()).await?;
+            if config.list_lag_ops > 0 {
+                state
+                    .lock()
+                    .expect("simulated store mutex poisoned")
+                    .lag_listing(&prefix, token.is_none(), &mut result);
+            }
 
             // Check for incomplete listing
             let should_truncate = {
@@ -405,6 +665,7 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
             {
                 let mut s = state.lock().expect("simulated store mutex poisoned");
                 s.stats.rename_attempts += 1;
+                s.admit(&config)?;
             }
 
             // Check for rename failure
@@ -421,14 +682,33 @@ impl<S: ObjectStore + Clone + 'static, R: Rng + 'static> ObjectStore
                 return Err(IoError::new(ErrorKind::Other, "simulated rename failure"));
             }
 
-            inner.rename(&from, &to).await
+            let (meta, created) = if config.list_lag_ops > 0 {
+                (inner.head(&from).await.ok(), !inner.exists(&to).await?)
+            } else {
+                (None, false)
+            };
+            inner.rename(&from, &to).await?;
+            let mut s = state.lock().expect("simulated store mutex poisoned");
+            s.note_delete(&config, meta);
+            if created {
+                s.note_create(&config, &to);
+            }
+            Ok(())
         })
     }
 
     fn head(&self, key: &str) -> Pin<Box<dyn Future<Output = IoResult<ObjectMeta>> + Send>> {
         let key = key.to_string();
         let inner = self.inner_store.clone();
-        Box::pin(async move { inner.head(&key).await })
+        let state = self.state.clone();
+        let config = self.config.clone();
+        Box::pin(async move {
+            state
+                .lock()
+                .expect("simulated store mutex poisoned")
+                .admit(&config)?;
+            inner.head(&key).await
+        })
     }
 }
 
@@ -554,6 +834,100 @@ mod tests {
         assert_eq!(stats.get_corruptions, 1);
     }
 
+    #[tokio::test]
+    async fn test_scheduled_outage_window() {
+        let store = SimulatedObjectStore::new(
+            InMemoryObjectStore::new(),
+            SimulatedRng::new(1),
+            SimulatedStoreConfig::no_faults().with_outage(3, 6),
+        );
+
+        store.put("a", b"1").await.unwrap(); // tick 1
+        store.get("a").await.unwrap(); // tick 2
+        assert!(store.in_outage());
+        // Ticks 3..6: every operation fails, including HEAD/EXISTS
+        assert!(store.get("a").await.is_err());
+        assert!(store.exists("a").await.is_err());
+        let err = store.put("b", b"2").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+        assert!(!store.in_outage());
+        // Recovered: data written before the outage is intact
+        assert_eq!(store.get("a").await.unwrap(), b"1");
+        assert!(!store.exists("b").await.unwrap());
+
+        let stats = store.stats();
+        assert_eq!(stats.outage_rejections, 3);
+        assert_eq!(store.tick(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_list_after_write_lag() {
+        let store = SimulatedObjectStore::new(
+            InMemoryObjectStore::new(),
+            SimulatedRng::new(2),
+            SimulatedStoreConfig {
+                list_lag_ops: 3,
+                ..SimulatedStoreConfig::no_faults()
+            },
+        );
+
+        store.put("seg/old", b"x").await.unwrap(); // tick 1, listed from tick 4
+        for _ in 0..3 {
+            store.exists("seg/old").await.unwrap();
+        }
+        store.put("seg/new", b"y").await.unwrap(); // tick 5
+        store.delete("seg/old").await.unwrap(); // tick 6
+
+        let keys: Vec<String> = store
+            .list("seg/", None)
+            .await
+            .unwrap()
+            .objects
+            .into_iter()
+            .map(|o| o.key)
+            .collect();
+        assert_eq!(keys, vec!["seg/old"], "new key hidden, deleted key lingers");
+        // GET is read-after-write consistent even while LIST lags
+        assert_eq!(store.get("seg/new").await.unwrap(), b"y");
+
+        for _ in 0..3 {
+            store.exists("seg/new").await.unwrap();
+        }
+        let keys: Vec<String> = store
+            .list("seg/", None)
+            .await
+            .unwrap()
+            .objects
+            .into_iter()
+            .map(|o| o.key)
+            .collect();
+        assert_eq!(keys, vec!["seg/new"], "listing converges after the lag");
+        assert_eq!(store.stats().list_lagged, 2);
+    }
+
+    #[tokio::test]
+    async fn test_throttling_bursts_are_deterministic() {
+        async fn run(seed: u64) -> (Vec<bool>, SimulatedStoreStats) {
+            let store = SimulatedObjectStore::new(
+                InMemoryObjectStore::new(),
+                SimulatedRng::new(seed),
+                SimulatedStoreConfig::throttled(),
+            );
+            let mut outcomes = Vec::new();
+            for i in 0..500 {
+                outcomes.push(store.put(&format!("k{}", i), b"v").await.is_ok());
+            }
+            (outcomes, store.stats())
+        }
+
+        let (a, stats) = run(77).await;
+        let (b, _) = run(77).await;
+        assert_eq!(a, b, "same seed must throttle identically");
+        assert!(stats.throttle_bursts > 0);
+        assert!(stats.throttled > 0);
+        assert_eq!(a.iter().filter(|ok| !**ok).count() as u64, stats.throttled);
+    }
+
     #[tokio::test]
     async fn test_simulated_store_high_chaos() {
         let inner = InMemoryObjectStore::new();
@@ -0,0 +1,442 @@
+//! Keyspace diff of two snapshots - checkpoints or RDB files
+//!
+//! For verifying a migration (an RDB file from the old server against the
+//! checkpoint the import wrote) and for chasing replication divergence (two
+//! replicas' checkpoints). Either side may be either format; it is told by
+//! its first bytes, as `--import` does.
+//!
+//! A checkpoint holds replicated values, which are read the way recovery
+//! applies them: registers as strings (tombstones are no key), counters as
+//! their decimal value, G-Sets and OR-Sets as sets, hashes by their live
+//! fields. An RDB file holds database 0 (keys of other databases are
+//! counted, not compared).
+//!
+//! Values compare by content, so a hash written in another field order or a
+//! set in another encoding is unchanged. Expiries compare as stored: a
+//! checkpoint keeps the TTL a key was written with, an RDB file the Unix
+//! deadline. Between formats only gaining or losing an expiry is a change,
+//! since a TTL and a deadline cannot be told apart without the write time.
+//!
+//! # TigerStyle Invariants
+//!
+//! - Every key of either side is in exactly one of added, removed,
+//!   changed or unchanged
+//! - Per-type counts add up to the key counts of each side
+
+use crate::redis::rdb::{self, RdbFile};
+use crate::redis::{RedisHash, RedisSet, Value, SDS};
+use crate::replication::state::{CrdtValue, ReplicatedValue};
+use crate::streaming::{CheckpointData, CheckpointReader};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Keys listed per category by `Display`
+const SAMPLE_KEYS: usize = 10;
+
+/// Executor clock origin for RDB field deadlines, as in `import`
+const SNAPSHOT_EPOCH_MS: i64 = 0;
+
+/// Which file format a snapshot was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    Checkpoint,
+    Rdb,
+}
+
+impl fmt::Display for SnapshotFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotFormat::Checkpoint => write!(f, "checkpoint"),
+            SnapshotFormat::Rdb => write!(f, "rdb"),
+        }
+    }
+}
+
+/// A key's expiry as its snapshot stores it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    /// TTL in milliseconds the key was written with (checkpoints)
+    Ttl(u64),
+    /// Deadline in Unix milliseconds (RDB files)
+    At(u64),
+}
+
+impl fmt::Display for Expiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expiry::Ttl(ms) => write!(f, "ttl {}ms", ms),
+            Expiry::At(ms) => write!(f, "at {}", ms),
+        }
+    }
+}
+
+/// One key of a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotKey {
+    pub value: Value,
+    pub expiry: Option<Expiry>,
+}
+
+/// Every key of a checkpoint or RDB file, by name
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub format: SnapshotFormat,
+    pub keys: BTreeMap<String, SnapshotKey>,
+    /// RDB keys outside database 0
+    pub other_db_keys: usize,
+}
+
+impl Snapshot {
+    /// Read a checkpoint or RDB file, told apart by its first bytes
+    pub fn read(bytes: &[u8]) -> Result<Self, String> {
+        if rdb::is_rdb(bytes) {
+            let file = rdb::read_file(bytes, true, SNAPSHOT_EPOCH_MS)?;
+            return Ok(Self::from_rdb(file));
+        }
+        let reader = CheckpointReader::open(bytes).map_err(|e| e.to_string())?;
+        reader.validate().map_err(|e| e.to_string())?;
+        let data = reader.load().map_err(|e| e.to_string())?;
+        Ok(Self::from_checkpoint(data))
+    }
+
+    pub fn from_rdb(file: RdbFile) -> Self {
+        let keys = file
+            .keys
+            .into_iter()
+            .map(|key| {
+                let entry = SnapshotKey {
+                    value: key.value,
+                    expiry: key.expire_at_ms.map(Expiry::At),
+                };
+                (key.key, entry)
+            })
+            .collect();
+        Snapshot {
+            format: SnapshotFormat::Rdb,
+            keys,
+            other_db_keys: file.other_db_keys,
+        }
+    }
+
+    pub fn from_checkpoint(data: CheckpointData) -> Self {
+        let keys = data
+            .state
+            .into_iter()
+            .filter_map(|(key, replicated)| {
+                let value = replicated_to_value(&replicated)?;
+                let entry = SnapshotKey {
+                    value,
+                    expiry: replicated.expiry_ms.map(Expiry::Ttl),
+                };
+                Some((key, entry))
+            })
+            .collect();
+        Snapshot {
+            format: SnapshotFormat::Checkpoint,
+            keys,
+            other_db_keys: 0,
+        }
+    }
+
+    /// Keys per type name
+    pub fn type_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in self.keys.values() {
+            *counts.entry(type_name(&entry.value)).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// What changed going from `self` to `after`
+    pub fn diff(&self, after: &Snapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff {
+            before_keys: self.keys.len(),
+            after_keys: after.keys.len(),
+            ..SnapshotDiff::default()
+        };
+        for (kind, n) in self.type_counts() {
+            diff.types.entry(kind).or_default().before = n;
+        }
+        for (kind, n) in after.type_counts() {
+            diff.types.entry(kind).or_default().after = n;
+        }
+
+        for (key, old) in &self.keys {
+            let Some(new) = after.keys.get(key) else {
+                diff.types.entry(type_name(&old.value)).or_default().removed += 1;
+                diff.removed.push(key.clone());
+                continue;
+            };
+            if old.value != new.value {
+                diff.types.entry(type_name(&new.value)).or_default().changed += 1;
+                diff.changed.push(key.clone());
+            }
+            if expiry_changed(old.expiry, new.expiry) {
+                diff.ttl_changed.push((key.clone(), old.expiry, new.expiry));
+            }
+        }
+        for (key, new) in &after.keys {
+            if !self.keys.contains_key(key) {
+                diff.types.entry(type_name(&new.value)).or_default().added += 1;
+                diff.added.push(key.clone());
+            }
+        }
+
+        debug_assert_eq!(
+            diff.before_keys + diff.added.len(),
+            diff.after_keys + diff.removed.len(),
+            "Postcondition: every key is added, removed or on both sides"
+        );
+        diff
+    }
+}
+
+/// Keys of one type on each side, and how many of them changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeCounts {
+    pub before: usize,
+    pub after: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Changed keys, counted under their new type
+    pub changed: usize,
+}
+
+/// Keyspace difference between two snapshots; key lists are sorted
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotDiff {
+    pub before_keys: usize,
+    pub after_keys: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Keys on both sides whose value or type differs
+    pub changed: Vec<String>,
+    /// Keys on both sides whose expiry differs, with the old and new expiry
+    pub ttl_changed: Vec<(String, Option<Expiry>, Option<Expiry>)>,
+    pub types: BTreeMap<&'static str, TypeCounts>,
+}
+
+impl SnapshotDiff {
+    /// Whether the snapshots hold the same keyspace
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.ttl_changed.is_empty()
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "keys: {} before, {} after",
+            self.before_keys, self.after_keys
+        )?;
+        writeln!(
+            f,
+            "added {}, removed {}, changed {}, ttl changed {}",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.ttl_changed.len()
+        )?;
+        if !self.types.is_empty() {
+            writeln!(
+                f,
+                "{:<8} {:>10} {:>10} {:>8} {:>8} {:>8}",
+                "type", "before", "after", "added", "removed", "changed"
+            )?;
+            for (kind, counts) in &self.types {
+                writeln!(
+                    f,
+                    "{:<8} {:>10} {:>10} {:>8} {:>8} {:>8}",
+                    kind, counts.before, counts.after, counts.added, counts.removed, counts.changed
+                )?;
+            }
+        }
+        write_sample(f, "added", self.added.iter())?;
+        write_sample(f, "removed", self.removed.iter())?;
+        write_sample(f, "changed", self.changed.iter())?;
+        let ttl = self.ttl_changed.iter().map(|(key, old, new)| {
+            let show = |e: &Option<Expiry>| e.map_or("none".to_string(), |e| e.to_string());
+            format!("{} ({} -> {})", key, show(old), show(new))
+        });
+        write_sample(f, "ttl changed", ttl)
+    }
+}
+
+/// One line of up to `SAMPLE_KEYS` items, noting how many more there are
+fn write_sample<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    label: &str,
+    items: impl ExactSizeIterator<Item = T>,
+) -> fmt::Result {
+    let total = items.len();
+    if total == 0 {
+        return Ok(());
+    }
+    write!(f, "{}:", label)?;
+    for item in items.take(SAMPLE_KEYS) {
+        write!(f, " {}", item)?;
+    }
+    if total > SAMPLE_KEYS {
+        write!(f, " ... and {} more", total - SAMPLE_KEYS)?;
+    }
+    writeln!(f)
+}
+
+fn expiry_changed(old: Option<Expiry>, new: Option<Expiry>) -> bool {
+    match (old, new) {
+        (Some(Expiry::Ttl(_)), Some(Expiry::At(_)))
+        | (Some(Expiry::At(_)), Some(Expiry::Ttl(_))) => false,
+        (old, new) => old != new,
+    }
+}
+
+/// Name TYPE reports for `value`
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::List(_) => "list",
+        Value::Set(_) => "set",
+        Value::Hash(_) => "hash",
+        Value::SortedSet(_) => "zset",
+        Value::Stream(_) => "stream",
+        Value::Null => "none",
+    }
+}
+
+/// The value recovery would apply for `replicated`, or None for no key
+fn replicated_to_value(replicated: &ReplicatedValue) -> Option<Value> {
+    match &replicated.crdt {
+        CrdtValue::Lww(lww) => lww.get().cloned().map(Value::String),
+        CrdtValue::GCounter(counter) => {
+            Some(Value::String(SDS::from_str(&counter.value().to_string())))
+        }
+        CrdtValue::PNCounter(counter) => {
+            Some(Value::String(SDS::from_str(&counter.value().to_string())))
+        }
+        CrdtValue::GSet(set) => (!set.is_empty()).then(|| {
+            Value::Set(RedisSet::from_members(
+                set.elements().map(|m| SDS::from_str(m)),
+            ))
+        }),
+        CrdtValue::ORSet(set) => (!set.is_empty()).then(|| {
+            Value::Set(RedisSet::from_members(
+                set.elements().map(|m| SDS::from_str(m)),
+            ))
+        }),
+        CrdtValue::Hash(fields) => {
+            let pairs: Vec<(SDS, SDS)> = fields
+                .iter()
+                .filter_map(|(field, lww)| lww.get().map(|v| (SDS::from_str(field), v.clone())))
+                .collect();
+            (!pairs.is_empty()).then(|| Value::Hash(RedisHash::from_pairs(pairs)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::lattice::{LamportClock, ReplicaId};
+    use crate::streaming::{CheckpointWriter, Compression};
+    use std::collections::HashMap;
+
+    fn string(value: &str, time: u64, expiry_ms: Option<u64>) -> ReplicatedValue {
+        let clock = LamportClock {
+            time,
+            replica_id: ReplicaId::new(1),
+        };
+        let mut replicated = ReplicatedValue::with_value(SDS::from_str(value), clock);
+        replicated.expiry_ms = expiry_ms;
+        replicated
+    }
+
+    fn checkpoint(state: HashMap<String, ReplicatedValue>) -> Vec<u8> {
+        CheckpointWriter::new(Compression::None)
+            .write(state, 1000, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_diff_of_two_checkpoints() {
+        let mut clock = LamportClock::new(ReplicaId::new(1));
+        let mut deleted = string("gone", 1, None);
+        deleted.delete(&mut clock);
+
+        let before = HashMap::from([
+            ("same".to_string(), string("v", 1, None)),
+            ("edited".to_string(), string("old", 1, None)),
+            ("dropped".to_string(), string("v", 1, None)),
+            ("ttl".to_string(), string("v", 1, Some(5000))),
+        ]);
+        let mut after = HashMap::from([
+            ("same".to_string(), string("v", 2, None)),
+            ("edited".to_string(), string("new", 2, None)),
+            ("ttl".to_string(), string("v", 1, Some(9000))),
+            ("new".to_string(), string("v", 1, None)),
+            ("deleted".to_string(), deleted),
+        ]);
+        let mut hash = ReplicatedValue::new(ReplicaId::new(1));
+        hash.hash_set("f".to_string(), SDS::from_str("1"), &mut clock);
+        after.insert("dropped".to_string(), hash);
+
+        let before = Snapshot::read(&checkpoint(before)).unwrap();
+        let after = Snapshot::read(&checkpoint(after)).unwrap();
+        assert_eq!(after.format, SnapshotFormat::Checkpoint);
+        assert_eq!(after.keys.len(), 5, "the tombstone is no key");
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec!["new"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, vec!["dropped", "edited"]);
+        assert_eq!(
+            diff.ttl_changed,
+            vec![(
+                "ttl".to_string(),
+                Some(Expiry::Ttl(5000)),
+                Some(Expiry::Ttl(9000))
+            )]
+        );
+        assert_eq!(diff.types["string"].before, 4);
+        assert_eq!(diff.types["string"].after, 4);
+        assert_eq!(diff.types["hash"].changed, 1);
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_diff_of_an_rdb_file_against_a_checkpoint() {
+        let set = Value::Set(RedisSet::from_members([SDS::from_str("a")]));
+        let plain = Value::String(SDS::from_str("v"));
+        let rdb = rdb::write_file(
+            [
+                ("plain", &plain, None),
+                ("expiring", &plain, Some(1_700_000_000_000)),
+                ("set", &set, None),
+            ],
+            SNAPSHOT_EPOCH_MS,
+        );
+        let migrated = HashMap::from([
+            ("plain".to_string(), string("v", 1, None)),
+            ("expiring".to_string(), string("v", 1, Some(60_000))),
+        ]);
+
+        let before = Snapshot::read(&rdb).unwrap();
+        let after = Snapshot::read(&checkpoint(migrated)).unwrap();
+        assert_eq!(before.format, SnapshotFormat::Rdb);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.removed, vec!["set"]);
+        assert!(diff.changed.is_empty());
+        assert!(
+            diff.ttl_changed.is_empty(),
+            "a deadline and a TTL do not compare"
+        );
+        assert_eq!(diff.types["set"].removed, 1);
+        assert!(diff.to_string().contains("removed: set"));
+
+        assert!(Snapshot::read(b"neither").is_err());
+    }
+}
@@ -0,0 +1,492 @@
+//! Transaction WAL Deterministic Simulation Testing Harness
+//!
+//! Crashes a node in the middle of MULTI/EXEC and checks that recovery sees
+//! each transaction whole or not at all:
+//!
+//! - **Atomicity**: no transaction is recovered with only some of its deltas
+//! - **Durability**: every acknowledged transaction is recovered whole
+//! - **Agreement**: without faults, the recovered keyspace is the executor's
+//!   keyspace as of the last acknowledged EXEC
+//!
+//! ## DST Methodology
+//!
+//! 1. Queue random string writes in a `CommandExecutor` transaction
+//! 2. EXEC, turn the written keys into deltas with a `ShardReplicaState`
+//!    (as replicated shards do) and append them to the WAL as one atomic batch
+//! 3. At a random transaction, crash at a random `ExecCrashPoint`
+//! 4. Recover from the WAL and check the invariants
+//!
+//! With `atomic_batches` off each delta is its own entry, as
+//! `ReplicatedShardedState` writes them one by one; the torn-batch crash then
+//! recovers part of a transaction, which is what the batch entry prevents.
+
+use crate::io::simulation::SimulatedRng;
+use crate::io::Rng;
+use crate::redis::{Command, CommandExecutor, RespValue, SDS};
+use crate::replication::lattice::ReplicaId;
+use crate::replication::state::{ReplicationDelta, ShardReplicaState};
+use crate::replication::ConsistencyLevel;
+use crate::streaming::wal::{WalEntry, WalRotator};
+use crate::streaming::wal_store::{
+    SimulatedWalStore, SimulatedWalStoreConfig, SimulatedWalStoreStats, WalError,
+};
+use std::collections::HashMap;
+
+/// Where in a transaction the node crashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecCrashPoint {
+    /// Commands queued, EXEC never arrives
+    AfterQueue,
+    /// EXEC ran and its batch was written, but not fsynced
+    BeforeSync,
+    /// The batch write itself was cut short
+    TornBatch,
+    /// The batch was fsynced and EXEC acknowledged
+    AfterSync,
+}
+
+const CRASH_POINTS: [ExecCrashPoint; 4] = [
+    ExecCrashPoint::AfterQueue,
+    ExecCrashPoint::BeforeSync,
+    ExecCrashPoint::TornBatch,
+    ExecCrashPoint::AfterSync,
+];
+
+/// Result of a single DST run
+#[derive(Debug)]
+pub struct TransactionWalDSTResult {
+    pub seed: u64,
+    pub transactions: usize,
+    pub acknowledged_transactions: usize,
+    pub failed_transactions: usize,
+    pub crash_point: Option<ExecCrashPoint>,
+    pub recovered_transactions: usize,
+    /// Transactions recovered with only some of their deltas
+    pub torn_transactions: usize,
+    /// Acknowledged transactions not recovered whole
+    pub lost_transactions: usize,
+    pub store_stats: SimulatedWalStoreStats,
+    pub passed: bool,
+    pub error_message: Option<String>,
+}
+
+/// Configuration for the transaction WAL DST harness
+#[derive(Debug, Clone)]
+pub struct TransactionWalDSTConfig {
+    /// Transactions per run (the crash may cut the run short)
+    pub num_transactions: usize,
+    /// Most commands queued in one transaction
+    pub max_queued: usize,
+    /// Number of unique keys
+    pub num_keys: usize,
+    /// Maximum WAL file size (controls rotation)
+    pub max_file_size: usize,
+    /// Fault injection config
+    pub store_config: SimulatedWalStoreConfig,
+    /// Whether to crash in the middle of a transaction
+    pub simulate_crash: bool,
+    /// Write each transaction as one batch entry rather than one entry per delta
+    pub atomic_batches: bool,
+}
+
+impl Default for TransactionWalDSTConfig {
+    fn default() -> Self {
+        TransactionWalDSTConfig {
+            num_transactions: 30,
+            max_queued: 6,
+            num_keys: 12,
+            max_file_size: 1024, // Small to force rotation
+            store_config: SimulatedWalStoreConfig::no_faults(),
+            simulate_crash: true,
+            atomic_batches: true,
+        }
+    }
+}
+
+impl TransactionWalDSTConfig {
+    /// Crash without faults
+    pub fn crash_only() -> Self {
+        Self::default()
+    }
+
+    /// Faults + crash
+    pub fn chaos() -> Self {
+        TransactionWalDSTConfig {
+            store_config: SimulatedWalStoreConfig::high_chaos(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A transaction as it reached the WAL
+struct TransactionRecord {
+    deltas: Vec<ReplicationDelta>,
+    acked: bool,
+}
+
+/// Transaction WAL DST Harness
+pub struct TransactionWalDSTHarness {
+    seed: u64,
+    rng: SimulatedRng,
+    config: TransactionWalDSTConfig,
+}
+
+impl TransactionWalDSTHarness {
+    pub fn new(seed: u64, config: TransactionWalDSTConfig) -> Self {
+        debug_assert!(
+            config.max_queued > 0 && config.num_keys > 0,
+            "Precondition: transactions need commands and keys"
+        );
+        TransactionWalDSTHarness {
+            seed,
+            rng: SimulatedRng::new(seed),
+            config,
+        }
+    }
+
+    /// Run a single DST scenario
+    pub fn run(&mut self) -> TransactionWalDSTResult {
+        let store_rng = SimulatedRng::new(self.rng.next_u64());
+        let store = SimulatedWalStore::new(store_rng, self.config.store_config.clone());
+        let mut result = TransactionWalDSTResult {
+            seed: self.seed,
+            transactions: 0,
+            acknowledged_transactions: 0,
+            failed_transactions: 0,
+            crash_point: None,
+            recovered_transactions: 0,
+            torn_transactions: 0,
+            lost_transactions: 0,
+            store_stats: SimulatedWalStoreStats::default(),
+            passed: false,
+            error_message: None,
+        };
+
+        let mut rotator = match WalRotator::new(store.clone(), self.config.max_file_size) {
+            Ok(r) => r,
+            Err(e) => {
+                result.error_message = Some(format!("Failed to create rotator: {}", e));
+                return result;
+            }
+        };
+
+        let mut executor = CommandExecutor::new();
+        let mut replica = ShardReplicaState::new(ReplicaId::new(1), ConsistencyLevel::Eventual);
+        // Executor keyspace as of the last acknowledged EXEC
+        let mut committed: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut records: Vec<TransactionRecord> = Vec::new();
+
+        let crash_at = if self.config.simulate_crash {
+            let at = self.rng.gen_range(0, self.config.num_transactions as u64) as usize;
+            let point = CRASH_POINTS[self.rng.gen_range(0, CRASH_POINTS.len() as u64) as usize];
+            Some((at, point))
+        } else {
+            None
+        };
+
+        // Phase 1: transactions, until the crash
+        for tx in 0..self.config.num_transactions {
+            let crash = crash_at.filter(|(at, _)| *at == tx).map(|(_, point)| point);
+            result.transactions = tx + 1;
+
+            let keys = self.queue_transaction(&mut executor);
+            if crash == Some(ExecCrashPoint::AfterQueue) {
+                store.inner_store().simulate_crash();
+                result.crash_point = crash;
+                break;
+            }
+            if !matches!(executor.execute(&Command::Exec), RespValue::Array(Some(_))) {
+                result.error_message = Some(format!("EXEC of transaction {} failed", tx));
+                return result;
+            }
+
+            let deltas: Vec<ReplicationDelta> = keys
+                .iter()
+                .filter_map(|key| match read_key(&mut executor, key) {
+                    Some(value) => Some(replica.record_write(key.clone(), SDS::new(value), None)),
+                    None => replica.record_delete(key.clone()),
+                })
+                .collect();
+            replica.drain_pending_deltas();
+
+            // The entry timestamp names the transaction
+            let acked = self
+                .write_transaction(&mut rotator, &store, &deltas, tx as u64, crash)
+                .unwrap_or(false);
+            records.push(TransactionRecord { deltas, acked });
+            if acked {
+                result.acknowledged_transactions += 1;
+                for key in &keys {
+                    match read_key(&mut executor, key) {
+                        Some(value) => committed.insert(key.clone(), value),
+                        None => committed.remove(key),
+                    };
+                }
+            } else if crash.is_none() {
+                result.failed_transactions += 1;
+            }
+            if crash.is_some() {
+                result.crash_point = crash;
+                break;
+            }
+        }
+        // Whatever was not fsynced is gone
+        store.inner_store().simulate_crash();
+
+        // Phase 2: recovery from the inner store (no read faults)
+        let recovery = match WalRotator::new(store.inner_store().clone(), self.config.max_file_size)
+            .and_then(|r| r.recover_all_entries())
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                result.error_message = Some(format!("Recovery failed: {}", e));
+                return result;
+            }
+        };
+        let mut recovered_per_tx: HashMap<u64, usize> = HashMap::new();
+        let mut recovered: HashMap<String, Vec<u8>> = HashMap::new();
+        for entry in &recovery {
+            let deltas = match entry.to_deltas() {
+                Ok(deltas) => deltas,
+                Err(e) => {
+                    result.error_message = Some(format!("Unreadable recovered entry: {}", e));
+                    return result;
+                }
+            };
+            *recovered_per_tx.entry(entry.timestamp).or_default() += deltas.len();
+            for delta in deltas {
+                match delta.value.get() {
+                    Some(value) => recovered.insert(delta.key, value.as_bytes().to_vec()),
+                    None => recovered.remove(&delta.key),
+                };
+            }
+        }
+
+        // Phase 3: verify invariants
+        let mut violations = Vec::new();
+        for (tx, record) in records.iter().enumerate() {
+            let count = recovered_per_tx.get(&(tx as u64)).copied().unwrap_or(0);
+            if count > 0 {
+                result.recovered_transactions += 1;
+            }
+            if count > 0 && count < record.deltas.len() {
+                result.torn_transactions += 1;
+                violations.push(format!(
+                    "transaction {} recovered {} of {} deltas",
+                    tx,
+                    count,
+                    record.deltas.len()
+                ));
+            }
+            if record.acked && count < record.deltas.len() {
+                result.lost_transactions += 1;
+                violations.push(format!("acknowledged transaction {} lost", tx));
+            }
+        }
+        if violations.is_empty() && result.failed_transactions == 0 && recovered != committed {
+            violations.push(format!(
+                "recovered keyspace ({} keys) differs from the last acknowledged EXEC ({} keys)",
+                recovered.len(),
+                committed.len()
+            ));
+        }
+
+        result.store_stats = store.stats();
+        result.passed = violations.is_empty();
+        if !result.passed {
+            violations.truncate(10);
+            result.error_message = Some(format!(
+                "INVARIANT VIOLATION (crash {:?}): {}",
+                result.crash_point,
+                violations.join("; ")
+            ));
+        }
+        result
+    }
+
+    /// MULTI and queue random writes; returns the keys written, in order
+    fn queue_transaction(&mut self, executor: &mut CommandExecutor) -> Vec<String> {
+        let multi = executor.execute(&Command::Multi);
+        debug_assert_eq!(
+            multi,
+            RespValue::simple("OK"),
+            "Invariant: MULTI outside a transaction"
+        );
+
+        let queued = self.rng.gen_range(1, self.config.max_queued as u64 + 1);
+        let mut keys: Vec<String> = Vec::new();
+        for _ in 0..queued {
+            let key = format!("key:{}", self.rng.gen_range(0, self.config.num_keys as u64));
+            let value = SDS::from_str(&format!("v{}", self.rng.next_u64() % 1000));
+            let cmd = match self.rng.gen_range(0, 4) {
+                0 => Command::set(key.clone(), value),
+                1 => Command::Append(key.clone(), value),
+                2 => Command::Incr(key.clone()),
+                _ => Command::del(key.clone()),
+            };
+            let reply = executor.execute(&cmd);
+            debug_assert_eq!(
+                reply,
+                RespValue::simple("QUEUED"),
+                "Invariant: writes are queued"
+            );
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    /// Write one EXEC's deltas, crashing at `crash`; returns whether the
+    /// transaction was acknowledged
+    fn write_transaction(
+        &mut self,
+        rotator: &mut WalRotator<SimulatedWalStore<SimulatedRng>>,
+        store: &SimulatedWalStore<SimulatedRng>,
+        deltas: &[ReplicationDelta],
+        tx: u64,
+        crash: Option<ExecCrashPoint>,
+    ) -> Result<bool, WalError> {
+        if deltas.is_empty() {
+            // Nothing changed: acknowledged without touching the WAL
+            return Ok(true);
+        }
+
+        let entries = if self.config.atomic_batches {
+            vec![WalEntry::from_batch(deltas, tx)?]
+        } else {
+            deltas
+                .iter()
+                .map(|delta| WalEntry::from_delta(delta, tx))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        // Torn: the crash cuts into this entry
+        let torn_at = self.rng.gen_range(0, entries.len() as u64) as usize;
+
+        for (i, entry) in entries.iter().enumerate() {
+            rotator.append(entry)?;
+            if crash == Some(ExecCrashPoint::TornBatch) && i == torn_at {
+                let cut = self.rng.gen_range(1, entry.disk_size() as u64) as usize;
+                store.inner_store().tear_newest(cut);
+                store.inner_store().simulate_crash();
+                return Ok(false);
+            }
+            // Without batches every delta is synced as it is written
+            if !self.config.atomic_batches && i + 1 < entries.len() {
+                rotator.sync()?;
+            }
+        }
+        if crash == Some(ExecCrashPoint::BeforeSync) {
+            store.inner_store().simulate_crash();
+            return Ok(false);
+        }
+        rotator.sync()?;
+        if crash == Some(ExecCrashPoint::AfterSync) {
+            store.inner_store().simulate_crash();
+        }
+        Ok(true)
+    }
+}
+
+/// The executor's value for `key`, None when it does not exist
+fn read_key(executor: &mut CommandExecutor, key: &str) -> Option<Vec<u8>> {
+    match executor.execute(&Command::Get(key.to_string())) {
+        RespValue::BulkString(Some(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Run a batch of DST tests across multiple seeds
+pub fn run_transaction_wal_dst_batch(
+    seeds: std::ops::Range<u64>,
+    config: TransactionWalDSTConfig,
+) -> Vec<TransactionWalDSTResult> {
+    seeds
+        .map(|seed| TransactionWalDSTHarness::new(seed, config.clone()).run())
+        .collect()
+}
+
+/// Summarize batch results
+pub fn summarize_transaction_wal_dst_batch(results: &[TransactionWalDSTResult]) -> String {
+    let total = results.len();
+    let passed = results.iter().filter(|r| r.passed).count();
+    let acked: usize = results.iter().map(|r| r.acknowledged_transactions).sum();
+    let recovered: usize = results.iter().map(|r| r.recovered_transactions).sum();
+    let torn: usize = results.iter().map(|r| r.torn_transactions).sum();
+    let lost: usize = results.iter().map(|r| r.lost_transactions).sum();
+
+    let mut summary = format!(
+        "Transaction WAL DST Batch: {}/{} passed ({} failed)\n\
+         Acknowledged: {}, Recovered: {}, Torn: {}, Lost: {}",
+        passed,
+        total,
+        total - passed,
+        acked,
+        recovered,
+        torn,
+        lost
+    );
+    for r in results.iter().filter(|r| !r.passed) {
+        summary.push_str(&format!(
+            "\n  Seed {}: {}",
+            r.seed,
+            r.error_message.as_deref().unwrap_or("unknown error")
+        ));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_wal_dst_crash_points() {
+        let results = run_transaction_wal_dst_batch(0..100, TransactionWalDSTConfig::crash_only());
+        let summary = summarize_transaction_wal_dst_batch(&results);
+        assert!(summary.contains("100/100 passed"), "{}", summary);
+
+        // Every crash point was exercised
+        for point in CRASH_POINTS {
+            assert!(
+                results.iter().any(|r| r.crash_point == Some(point)),
+                "no seed crashed at {:?}",
+                point
+            );
+        }
+        assert!(results
+            .iter()
+            .all(|r| r.acknowledged_transactions > 0 || r.transactions == 1));
+    }
+
+    #[test]
+    fn test_transaction_wal_dst_chaos() {
+        let results = run_transaction_wal_dst_batch(0..50, TransactionWalDSTConfig::chaos());
+        let summary = summarize_transaction_wal_dst_batch(&results);
+        assert!(summary.contains("50/50 passed"), "{}", summary);
+    }
+
+    #[test]
+    fn test_transaction_wal_dst_catches_per_delta_entries() {
+        let config = TransactionWalDSTConfig {
+            atomic_batches: false,
+            ..TransactionWalDSTConfig::crash_only()
+        };
+        let results = run_transaction_wal_dst_batch(0..100, config);
+
+        // A crash between two of a transaction's deltas leaves half of it
+        assert!(
+            results.iter().any(|r| r.torn_transactions > 0 && !r.passed),
+            "{}",
+            summarize_transaction_wal_dst_batch(&results)
+        );
+        // Crashing outside EXEC's writes is safe either way
+        assert!(results
+            .iter()
+            .filter(|r| matches!(
+                r.crash_point,
+                Some(ExecCrashPoint::AfterQueue | ExecCrashPoint::AfterSync)
+            ))
+            .all(|r| r.passed));
+    }
+}
@@ -13,6 +13,7 @@
 //! ├──────────────────────────────────┤
 //! │ Entry 0                          │
 //! │ - data_length: u32 LE            │
+//! │   (top bit: atomic batch, v2)    │
 //! │ - timestamp: u64 LE              │
 //! │ - checksum: u32 LE (CRC32)       │
 //! │ - data: [u8; data_length]        │
@@ -24,6 +25,11 @@
 //! Crash tolerance: each entry is individually CRC32-checksummed.
 //! The reader stops at the first corrupted or truncated entry,
 //! recovering all fully-written entries before the crash point.
+//!
+//! Atomic batches: an entry made with `WalEntry::from_batch` holds several
+//! deltas under one checksum, so a crash keeps all of them or none, as a
+//! transaction's writes must be. Version 1 files have no batches and are
+//! still read.
 
 use crate::replication::state::ReplicationDelta;
 use crate::streaming::wal_store::{WalError, WalFileReader, WalFileWriter, WalStore};
@@ -31,11 +37,15 @@ use crate::streaming::wal_store::{WalError, WalFileReader, WalFileWriter, WalSto
 /// WAL file magic number
 pub const WAL_MAGIC: [u8; 4] = *b"RWAL";
 /// Current WAL format version
-pub const WAL_VERSION: u8 = 1;
+pub const WAL_VERSION: u8 = 2;
+/// Oldest WAL format version the reader accepts
+pub const WAL_MIN_VERSION: u8 = 1;
 /// Header size in bytes
 pub const WAL_HEADER_SIZE: usize = 16;
 /// Entry overhead: data_length(4) + timestamp(8) + checksum(4) = 16 bytes
 pub const WAL_ENTRY_OVERHEAD: usize = 16;
+/// Bit of data_length marking an atomic batch entry
+const WAL_BATCH_FLAG: u32 = 1 << 31;
 
 /// A single WAL entry
 #[derive(Debug, Clone)]
@@ -46,6 +56,8 @@ pub struct WalEntry {
     pub timestamp: u64,
     /// CRC32 checksum of data
     pub checksum: u32,
+    /// Data holds a batch of deltas rather than one
+    pub batch: bool,
 }
 
 impl WalEntry {
@@ -61,15 +73,55 @@ impl WalEntry {
             data,
             timestamp,
             checksum,
+            batch: false,
+        })
+    }
+
+    /// Create one entry holding all of `deltas`, recovered all or nothing
+    pub fn from_batch(deltas: &[ReplicationDelta], timestamp: u64) -> Result<Self, WalError> {
+        debug_assert!(!deltas.is_empty(), "Precondition: a batch must hold at least one delta");
+
+        let data =
+            bincode::serialize(deltas).map_err(|e| WalError::Corruption(format!("serialize: {}", e)))?;
+        if data.len() >= WAL_BATCH_FLAG as usize {
+            return Err(WalError::Corruption(format!(
+                "batch of {} bytes does not fit in one entry",
+                data.len()
+            )));
+        }
+        let checksum = crc32fast::hash(&data);
+
+        Ok(WalEntry {
+            data,
+            timestamp,
+            checksum,
+            batch: true,
         })
     }
 
     /// Deserialize the entry data into a ReplicationDelta
     pub fn to_delta(&self) -> Result<ReplicationDelta, WalError> {
+        if self.batch {
+            return Err(WalError::Corruption(
+                "batch entry read as a single delta".to_string(),
+            ));
+        }
         bincode::deserialize(&self.data)
             .map_err(|e| WalError::Corruption(format!("deserialize: {}", e)))
     }
 
+    /// Deserialize the entry data into its deltas: one, or a whole batch
+    pub fn to_deltas(&self) -> Result<Vec<ReplicationDelta>, WalError> {
+        if !self.batch {
+            return Ok(vec![self.to_delta()?]);
+        }
+        let deltas: Vec<ReplicationDelta> = bincode::deserialize(&self.data)
+            .map_err(|e| WalError::Corruption(format!("deserialize batch: {}", e)))?;
+
+        debug_assert!(!deltas.is_empty(), "Postcondition: a batch holds at least one delta");
+        Ok(deltas)
+    }
+
     /// Validate the entry checksum
     pub fn validate(&self) -> bool {
         crc32fast::hash(&self.data) == self.checksum
@@ -85,10 +137,13 @@ impl WalEntry {
     /// Encode the entry to bytes for writing
     pub fn encode(&self) -> Vec<u8> {
         debug_assert!(
-            self.data.len() <= u32::MAX as usize,
-            "Precondition: entry data must fit in u32 length field"
+            self.data.len() < WAL_BATCH_FLAG as usize,
+            "Precondition: entry data must fit in the 31-bit length field"
         );
-        let data_len = self.data.len() as u32;
+        let mut data_len = self.data.len() as u32;
+        if self.batch {
+            data_len |= WAL_BATCH_FLAG;
+        }
         let total_size = WAL_ENTRY_OVERHEAD
             .checked_add(self.data.len())
             .expect("entry size overflow is unreachable");
@@ -114,7 +169,9 @@ impl WalEntry {
             return None;
         }
 
-        let data_len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let raw_len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let batch = raw_len & WAL_BATCH_FLAG != 0;
+        let data_len = (raw_len & !WAL_BATCH_FLAG) as usize;
         let timestamp = u64::from_le_bytes([
             data[4], data[5], data[6], data[7], data[8], data[9], data[10], data[11],
         ]);
@@ -138,6 +195,7 @@ impl WalEntry {
                 data: entry_data,
                 timestamp,
                 checksum,
+                batch,
             },
             total_size,
         ))
@@ -280,7 +338,7 @@ impl WalReader {
 
         // Validate version
         let version = data[4];
-        if version != WAL_VERSION {
+        if !(WAL_MIN_VERSION..=WAL_VERSION).contains(&version) {
             return Err(WalError::Corruption(format!(
                 "Unsupported WAL version: {}",
                 version
@@ -474,7 +532,7 @@ impl<S: WalStore> WalRotator<S> {
         let mut deltas = Vec::new();
         for entry in entries {
             if entry.timestamp >= after_timestamp {
-                deltas.push(entry.to_delta()?);
+                deltas.extend(entry.to_deltas()?);
             }
         }
         Ok(deltas)
@@ -663,6 +721,60 @@ mod tests {
         assert_eq!(entries[1].to_delta().unwrap().key, "k1");
     }
 
+    #[test]
+    fn test_batch_entry_is_recovered_whole_or_not_at_all() {
+        let store = InMemoryWalStore::new();
+        let file_writer = store.create("wal-00000001.wal").unwrap();
+        let mut writer = WalWriter::new(file_writer, 1).unwrap();
+
+        writer
+            .append_entry(&WalEntry::from_delta(&make_delta("k0", "v0", 100), 100).unwrap())
+            .unwrap();
+        let batch: Vec<_> = (1..4)
+            .map(|i| make_delta(&format!("k{}", i), &format!("v{}", i), 200))
+            .collect();
+        let entry = WalEntry::from_batch(&batch, 200).unwrap();
+        assert!(entry.to_delta().is_err());
+        writer.append_entry(&entry).unwrap();
+
+        let data = store.get_file_data("wal-00000001.wal").unwrap();
+        let reader = WalReader::open(store.open_read("wal-00000001.wal").unwrap()).unwrap();
+        let entries = reader.entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].batch);
+        let keys: Vec<_> = entries[1].to_deltas().unwrap().into_iter().map(|d| d.key).collect();
+        assert_eq!(keys, ["k1", "k2", "k3"]);
+
+        // Torn anywhere inside the batch: none of its deltas come back
+        for cut in [1, entry.disk_size() / 2, entry.disk_size() - 1] {
+            store.set_file_data("wal-00000001.wal", data[..data.len() - cut].to_vec());
+            let reader = WalReader::open(store.open_read("wal-00000001.wal").unwrap()).unwrap();
+            let entries = reader.entries();
+            assert_eq!(entries.len(), 1, "cut {} bytes", cut);
+            assert_eq!(entries[0].to_deltas().unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_reader_accepts_version_1_files() {
+        let store = InMemoryWalStore::new();
+        let file_writer = store.create("wal-00000001.wal").unwrap();
+        let mut writer = WalWriter::new(file_writer, 1).unwrap();
+        writer
+            .append_entry(&WalEntry::from_delta(&make_delta("k0", "v0", 100), 100).unwrap())
+            .unwrap();
+
+        let mut data = store.get_file_data("wal-00000001.wal").unwrap();
+        data[4] = 1;
+        store.set_file_data("wal-00000001.wal", data.clone());
+        let reader = WalReader::open(store.open_read("wal-00000001.wal").unwrap()).unwrap();
+        assert_eq!(reader.entries()[0].to_delta().unwrap().key, "k0");
+
+        data[4] = WAL_VERSION + 1;
+        store.set_file_data("wal-00000001.wal", data);
+        assert!(WalReader::open(store.open_read("wal-00000001.wal").unwrap()).is_err());
+    }
+
     #[test]
     fn test_entries_after_filter() {
         let store = InMemoryWalStore::new();
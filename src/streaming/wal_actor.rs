@@ -17,12 +17,25 @@
 //! - **Always**: Group commit — batch + fsync before resolving any ack
 //! - **EverySecond**: Append + resolve immediately; fsync on timer
 //! - **No**: Append + resolve immediately; OS decides when to flush
+//!
+//! ## Batches
+//!
+//! A `WalRecord::Batch` holds every delta of one EXEC or script and is
+//! written as a single entry, so recovery applies all of them or none.
+//!
+//! ## Fsync Lag
+//!
+//! The actor publishes when its oldest unsynced entry was appended, and
+//! `WalActorHandle::fsync_lag` reports how long ago that was: the writes a
+//! crash would lose right now. It stays 0 in No mode, which never fsyncs.
 
 use crate::replication::state::ReplicationDelta;
 use crate::streaming::wal::{WalEntry, WalRotator};
 use crate::streaming::wal_config::{FsyncPolicy, WalConfig};
 use crate::streaming::wal_store::{WalError, WalStore};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, oneshot};
 use tracing::{error, info, warn};
 
@@ -30,13 +43,31 @@ use tracing::{error, info, warn};
 /// 256 entries × ~200 bytes ≈ 50KB worst case.
 const WAL_CHANNEL_CAPACITY: usize = 256;
 
+/// What one WAL entry holds
+#[derive(Clone)]
+pub enum WalRecord {
+    /// A single delta
+    Delta(Arc<ReplicationDelta>),
+    /// All the deltas of one EXEC or script, recovered all or nothing
+    Batch(Arc<Vec<ReplicationDelta>>),
+}
+
+impl WalRecord {
+    fn to_entry(&self, timestamp: u64) -> Result<WalEntry, WalError> {
+        match self {
+            WalRecord::Delta(delta) => WalEntry::from_delta(delta, timestamp),
+            WalRecord::Batch(deltas) => WalEntry::from_batch(deltas, timestamp),
+        }
+    }
+}
+
 /// Messages for the WAL actor
 pub enum WalMessage {
-    /// Write a delta to the WAL.
+    /// Write a record to the WAL.
     /// In Always mode, the ack is sent after fsync.
     /// In EverySecond/No mode, the ack is sent after append.
     Write {
-        delta: Arc<ReplicationDelta>,
+        record: WalRecord,
         timestamp: u64,
         ack_tx: Option<oneshot::Sender<Result<(), WalError>>>,
     },
@@ -52,6 +83,47 @@ pub enum WalMessage {
     },
 }
 
+/// When the oldest entry not yet fsynced was appended, shared by the actor
+/// and its handles. Stored as milliseconds since `epoch` plus one, so 0
+/// means every appended entry is durable.
+#[derive(Clone)]
+struct SyncLag {
+    epoch: Instant,
+    oldest_unsynced: Arc<AtomicU64>,
+}
+
+impl SyncLag {
+    fn new() -> Self {
+        SyncLag {
+            epoch: Instant::now(),
+            oldest_unsynced: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// An entry was appended; starts the clock unless one is already running
+    fn appended(&self) {
+        let now = self.epoch.elapsed().as_millis() as u64 + 1;
+        let _ = self
+            .oldest_unsynced
+            .compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// Everything appended so far is durable
+    fn synced(&self) {
+        self.oldest_unsynced.store(0, Ordering::Relaxed);
+    }
+
+    fn lag(&self) -> Duration {
+        match self.oldest_unsynced.load(Ordering::Relaxed) {
+            0 => Duration::ZERO,
+            since => self
+                .epoch
+                .elapsed()
+                .saturating_sub(Duration::from_millis(since - 1)),
+        }
+    }
+}
+
 /// WAL actor that owns the rotator and processes messages
 pub struct WalActor<S: WalStore> {
     rotator: WalRotator<S>,
@@ -61,6 +133,8 @@ pub struct WalActor<S: WalStore> {
     pending_acks: Vec<oneshot::Sender<Result<(), WalError>>>,
     /// Number of entries appended since last fsync
     entries_since_sync: usize,
+    /// Age of the oldest unsynced entry, read by handles
+    sync_lag: SyncLag,
 }
 
 impl<S: WalStore> WalActor<S> {
@@ -68,6 +142,7 @@ impl<S: WalStore> WalActor<S> {
         rotator: WalRotator<S>,
         config: WalConfig,
         rx: mpsc::Receiver<WalMessage>,
+        sync_lag: SyncLag,
     ) -> Self {
         WalActor {
             rotator,
@@ -75,6 +150,7 @@ impl<S: WalStore> WalActor<S> {
             rx,
             pending_acks: Vec::with_capacity(64),
             entries_since_sync: 0,
+            sync_lag,
         }
     }
 
@@ -156,17 +232,18 @@ impl<S: WalStore> WalActor<S> {
     fn handle_message_always(&mut self, msg: WalMessage) -> bool {
         match msg {
             WalMessage::Write {
-                delta,
+                record,
                 timestamp,
                 ack_tx,
             } => {
-                match WalEntry::from_delta(&delta, timestamp) {
+                match record.to_entry(timestamp) {
                     Ok(entry) => match self.rotator.append(&entry) {
                         Ok(_) => {
                             self.entries_since_sync = self
                                 .entries_since_sync
                                 .checked_add(1)
                                 .expect("entries_since_sync overflow unreachable");
+                            self.sync_lag.appended();
                             if let Some(tx) = ack_tx {
                                 self.pending_acks.push(tx);
                             }
@@ -219,6 +296,7 @@ impl<S: WalStore> WalActor<S> {
         match sync_result {
             Ok(()) => {
                 // All entries are durable — resolve all acks with Ok
+                self.sync_lag.synced();
                 for tx in acks {
                     let _ = tx.send(Ok(()));
                 }
@@ -249,11 +327,12 @@ impl<S: WalStore> WalActor<S> {
 
             match msg {
                 WalMessage::Write {
-                    delta,
+                    record,
                     timestamp,
                     ack_tx,
                 } => {
-                    let result = WalEntry::from_delta(&delta, timestamp)
+                    let result = record
+                        .to_entry(timestamp)
                         .and_then(|entry| self.rotator.append(&entry).map(|_| ()));
 
                     // Only count successful writes for sync tracking
@@ -262,6 +341,7 @@ impl<S: WalStore> WalActor<S> {
                             .entries_since_sync
                             .checked_add(1)
                             .expect("entries_since_sync overflow unreachable");
+                        self.sync_lag.appended();
                     }
 
                     // Ack immediately (before fsync) — RPO ≤ 1s
@@ -271,8 +351,10 @@ impl<S: WalStore> WalActor<S> {
                 }
                 WalMessage::SyncTick => {
                     if self.entries_since_sync > 0 {
-                        if let Err(e) = self.rotator.sync() {
-                            error!("WAL periodic fsync failed: {}", e);
+                        // A failed fsync leaves the lag growing until one succeeds
+                        match self.rotator.sync() {
+                            Ok(()) => self.sync_lag.synced(),
+                            Err(e) => error!("WAL periodic fsync failed: {}", e),
                         }
                         self.entries_since_sync = 0;
                     }
@@ -307,11 +389,12 @@ impl<S: WalStore> WalActor<S> {
 
             match msg {
                 WalMessage::Write {
-                    delta,
+                    record,
                     timestamp,
                     ack_tx,
                 } => {
-                    let result = WalEntry::from_delta(&delta, timestamp)
+                    let result = record
+                        .to_entry(timestamp)
                         .and_then(|entry| self.rotator.append(&entry).map(|_| ()));
 
                     if let Some(tx) = ack_tx {
@@ -359,6 +442,7 @@ impl<S: WalStore> WalActor<S> {
 pub struct WalActorHandle {
     tx: mpsc::Sender<WalMessage>,
     fsync_policy: FsyncPolicy,
+    sync_lag: SyncLag,
 }
 
 impl WalActorHandle {
@@ -369,11 +453,27 @@ impl WalActorHandle {
         delta: Arc<ReplicationDelta>,
         timestamp: u64,
     ) -> Result<(), WalError> {
+        self.write_record_durable(WalRecord::Delta(delta), timestamp)
+            .await
+    }
+
+    /// Write a batch as one entry, with the guarantee of `write_durable`
+    pub async fn write_batch_durable(
+        &self,
+        deltas: Arc<Vec<ReplicationDelta>>,
+        timestamp: u64,
+    ) -> Result<(), WalError> {
+        debug_assert!(!deltas.is_empty(), "Precondition: a batch must hold at least one delta");
+        self.write_record_durable(WalRecord::Batch(deltas), timestamp)
+            .await
+    }
+
+    async fn write_record_durable(&self, record: WalRecord, timestamp: u64) -> Result<(), WalError> {
         let (ack_tx, ack_rx) = oneshot::channel();
         if self
             .tx
             .send(WalMessage::Write {
-                delta,
+                record,
                 timestamp,
                 ack_tx: Some(ack_tx),
             })
@@ -407,7 +507,17 @@ impl WalActorHandle {
     /// Used in EverySecond/No modes where immediate ack is acceptable.
     pub fn write_fire_and_forget(&self, delta: Arc<ReplicationDelta>, timestamp: u64) {
         let _ = self.tx.try_send(WalMessage::Write {
-            delta,
+            record: WalRecord::Delta(delta),
+            timestamp,
+            ack_tx: None,
+        });
+    }
+
+    /// Write a batch as one entry without waiting for durability
+    pub fn write_batch_fire_and_forget(&self, deltas: Arc<Vec<ReplicationDelta>>, timestamp: u64) {
+        debug_assert!(!deltas.is_empty(), "Precondition: a batch must hold at least one delta");
+        let _ = self.tx.try_send(WalMessage::Write {
+            record: WalRecord::Batch(deltas),
             timestamp,
             ack_tx: None,
         });
@@ -443,6 +553,16 @@ impl WalActorHandle {
     pub fn fsync_policy(&self) -> FsyncPolicy {
         self.fsync_policy
     }
+
+    /// Messages waiting for the WAL actor
+    pub fn queue_depth(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// How long the oldest appended but unsynced entry has waited for fsync
+    pub fn fsync_lag(&self) -> Duration {
+        self.sync_lag.lag()
+    }
 }
 
 /// Spawn a WAL actor on a dedicated blocking thread and return its handle + join handle.
@@ -464,14 +584,19 @@ pub fn spawn_wal_actor<S: WalStore + 'static>(
     let (tx, rx) = mpsc::channel(WAL_CHANNEL_CAPACITY);
 
     let fsync_policy = config.fsync_policy;
-    let actor = WalActor::new(rotator, config, rx);
+    let sync_lag = SyncLag::new();
+    let actor = WalActor::new(rotator, config, rx, sync_lag.clone());
 
     // Use spawn (not spawn_blocking) since the actor loop is async and
     // only blocks briefly during fsync. For production with slow disks,
     // consider wrapping individual fsync calls in spawn_blocking.
     let task = tokio::spawn(actor.run());
 
-    let handle = WalActorHandle { tx, fsync_policy };
+    let handle = WalActorHandle {
+        tx,
+        fsync_policy,
+        sync_lag,
+    };
     Ok((handle, task))
 }
 
@@ -529,6 +654,29 @@ mod tests {
         assert!(!files.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_wal_actor_batch_is_one_entry() {
+        let store = InMemoryWalStore::new();
+        let (handle, task) = spawn_wal_actor(store.clone(), test_config(FsyncPolicy::Always)).unwrap();
+
+        let single = make_test_delta("k0", "v", 100);
+        handle.write_durable(single, 100).await.unwrap();
+        let batch: Vec<ReplicationDelta> = (1..4)
+            .map(|i| (*make_test_delta(&format!("k{}", i), "v", 200)).clone())
+            .collect();
+        handle.write_batch_durable(Arc::new(batch), 200).await.unwrap();
+        handle.shutdown().await;
+        task.await.unwrap();
+
+        let entries = WalRotator::new(store, 1024 * 1024)
+            .unwrap()
+            .recover_all_entries()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].batch && entries[1].batch);
+        assert_eq!(entries[1].to_deltas().unwrap().len(), 3);
+    }
+
     #[tokio::test]
     async fn test_wal_actor_everysec_mode() {
         let store = InMemoryWalStore::new();
@@ -556,6 +704,28 @@ mod tests {
         assert!(!files.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_wal_actor_fsync_lag() {
+        let store = InMemoryWalStore::new();
+        let config = test_config(FsyncPolicy::EverySecond);
+
+        let (handle, task) = spawn_wal_actor(store, config).unwrap();
+        assert_eq!(handle.fsync_lag(), Duration::ZERO);
+
+        // Acked but not yet fsynced: the lag grows until the tick
+        let delta = make_test_delta("k", "v", 100);
+        handle.write_durable(delta, 100).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(handle.fsync_lag() >= Duration::from_millis(20));
+
+        handle.sync_tick();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(handle.fsync_lag(), Duration::ZERO);
+
+        handle.shutdown().await;
+        task.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_wal_actor_fire_and_forget() {
         let store = InMemoryWalStore::new();
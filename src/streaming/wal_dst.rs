@@ -5,13 +5,16 @@
 //! - **Always mode invariant**: Every acknowledged write MUST survive crash+recovery
 //! - **EverySecond mode invariant**: At most 1 second of writes may be lost
 //! - **Crash tolerance**: Partial writes at file boundary are detected and skipped
+//! - **Batch atomicity**: A batch entry is recovered with all its writes or none
 //!
 //! ## DST Methodology
 //!
 //! 1. Create SimulatedWalStore with buggify fault injection
 //! 2. Write deltas, tracking which ones were acknowledged
-//! 3. Simulate crash (truncate last file at random point)
-//! 4. Recover and verify all acknowledged writes are present
+//! 3. Simulate crash (truncate last file at random point); with batches, a
+//!    crash between two writes of one batch tears that batch's entry
+//! 4. Recover and verify all acknowledged writes are present, and that no
+//!    batch came back in part
 
 use crate::io::simulation::SimulatedRng;
 use crate::io::Rng;
@@ -32,6 +35,8 @@ pub struct WalDSTResult {
     pub failed_writes: usize,
     pub recovered_entries: usize,
     pub missing_after_recovery: usize,
+    /// Batches recovered with some but not all of their writes
+    pub partial_batches: usize,
     pub store_stats: SimulatedWalStoreStats,
     pub passed: bool,
     pub error_message: Option<String>,
@@ -50,6 +55,8 @@ pub struct WalDSTConfig {
     pub simulate_crash: bool,
     /// Whether to do fsync after each write (simulates Always mode)
     pub fsync_after_write: bool,
+    /// Writes per WAL entry; above 1 they share a batch entry (EXEC, scripts)
+    pub batch_size: usize,
 }
 
 impl Default for WalDSTConfig {
@@ -60,6 +67,7 @@ impl Default for WalDSTConfig {
             store_config: SimulatedWalStoreConfig::default(),
             simulate_crash: true,
             fsync_after_write: true,
+            batch_size: 1,
         }
     }
 }
@@ -125,6 +133,7 @@ impl WalDSTHarness {
                     failed_writes: 0,
                     recovered_entries: 0,
                     missing_after_recovery: 0,
+                    partial_batches: 0,
                     store_stats: store.stats(),
                     passed: false,
                     error_message: Some(format!("Failed to create rotator: {}", e)),
@@ -143,24 +152,42 @@ impl WalDSTHarness {
             usize::MAX
         };
 
-        // Phase 1: Write entries (may be interrupted by crash)
-        for i in 0..self.config.num_writes {
+        // Phase 1: Write entries (may be interrupted by crash). Consecutive
+        // writes share a batch entry and are acknowledged together.
+        let batch_size = self.config.batch_size.max(1);
+        let mut crashed = false;
+        let mut start = 0;
+        while start < self.config.num_writes {
             // Simulate crash at random point mid-sequence
-            if i == crash_at {
+            if start == crash_at {
                 store.inner_store().simulate_crash();
+                crashed = true;
                 break;
             }
-            let ts = (i as u64)
-                .checked_add(1)
-                .expect("timestamp overflow unreachable in test");
-            let key = format!("key-{:06}", self.rng.gen_range(0, 1000));
-            let value = format!("val-{}", ts);
-
-            let delta = make_test_delta(&key, &value, ts);
-            let entry = match WalEntry::from_delta(&delta, ts) {
+            let end = start.saturating_add(batch_size).min(self.config.num_writes);
+            let deltas: Vec<ReplicationDelta> = (start..end)
+                .map(|i| {
+                    let ts = (i as u64)
+                        .checked_add(1)
+                        .expect("timestamp overflow unreachable in test");
+                    let key = format!("key-{:06}", self.rng.gen_range(0, 1000));
+                    let value = format!("val-{}", ts);
+                    make_test_delta(&key, &value, ts)
+                })
+                .collect();
+            let batch_start = start;
+            start = end;
+
+            let ts = end as u64;
+            let entry = if deltas.len() == 1 {
+                WalEntry::from_delta(&deltas[0], ts)
+            } else {
+                WalEntry::from_batch(&deltas, ts)
+            };
+            let entry = match entry {
                 Ok(e) => e,
                 Err(_) => {
-                    failed_writes += 1;
+                    failed_writes += deltas.len();
                     continue;
                 }
             };
@@ -168,33 +195,40 @@ impl WalDSTHarness {
             // Append
             match rotator.append(&entry) {
                 Ok(_) => {
+                    // Crash between two writes of this batch: its entry is torn
+                    if batch_start < crash_at && crash_at < end {
+                        let cut = self.rng.gen_range(1, entry.disk_size() as u64) as usize;
+                        store.inner_store().tear_newest(cut);
+                        store.inner_store().simulate_crash();
+                        crashed = true;
+                        break;
+                    }
                     if self.config.fsync_after_write {
                         // Simulate Always mode: fsync after append
                         match rotator.sync() {
                             Ok(()) => {
                                 // Entry is durable — add to shadow state
-                                acked_timestamps.push(ts);
+                                acked_timestamps.extend(deltas.iter().map(delta_time));
                             }
                             Err(_) => {
                                 // Fsync failed — entry is NOT acknowledged
-                                failed_writes += 1;
+                                failed_writes += deltas.len();
                             }
                         }
                     } else {
                         // Simulate EverySecond/No mode: ack immediately
-                        acked_timestamps.push(ts);
+                        acked_timestamps.extend(deltas.iter().map(delta_time));
                     }
                 }
                 Err(_) => {
-                    failed_writes += 1;
+                    failed_writes += deltas.len();
                 }
             }
         }
 
         // Phase 2: Simulate crash if we didn't already crash mid-sequence.
         // Crash truncates all files to their synced position — un-synced data is lost.
-        let already_crashed = self.config.simulate_crash && crash_at < self.config.num_writes;
-        if self.config.simulate_crash && !already_crashed {
+        if self.config.simulate_crash && !crashed {
             store.inner_store().simulate_crash();
         }
 
@@ -213,6 +247,7 @@ impl WalDSTHarness {
                         failed_writes,
                         recovered_entries: 0,
                         missing_after_recovery: acked_timestamps.len(),
+                        partial_batches: 0,
                         store_stats: store.stats(),
                         passed: false,
                         error_message: Some(format!("Recovery failed: {}", e)),
@@ -230,6 +265,7 @@ impl WalDSTHarness {
                     failed_writes,
                     recovered_entries: 0,
                     missing_after_recovery: acked_timestamps.len(),
+                    partial_batches: 0,
                     store_stats: store.stats(),
                     passed: false,
                     error_message: Some(format!("Entry recovery failed: {}", e)),
@@ -238,8 +274,26 @@ impl WalDSTHarness {
         };
 
         // Phase 4: Verify invariant
-        let recovered_timestamps: std::collections::HashSet<u64> =
-            recovered.iter().map(|e| e.timestamp).collect();
+        let recovered_timestamps: std::collections::HashSet<u64> = recovered
+            .iter()
+            .filter_map(|e| e.to_deltas().ok())
+            .flatten()
+            .map(|delta| delta_time(&delta))
+            .collect();
+
+        // BATCH INVARIANT: a batch's writes come back together or not at all
+        let mut per_batch: std::collections::HashMap<u64, usize> =
+            std::collections::HashMap::new();
+        for ts in &recovered_timestamps {
+            *per_batch.entry((ts - 1) / batch_size as u64).or_insert(0) += 1;
+        }
+        let partial_batches = per_batch
+            .iter()
+            .filter(|(batch, count)| {
+                let first = **batch as usize * batch_size;
+                **count < batch_size.min(self.config.num_writes - first)
+            })
+            .count();
 
         let mut missing = 0;
         let mut missing_ts = Vec::new();
@@ -257,8 +311,13 @@ impl WalDSTHarness {
         }
         // For EverySecond/No mode, some loss is acceptable (bounded)
 
-        let passed = missing == 0;
-        let error_message = if !passed {
+        let passed = missing == 0 && partial_batches == 0;
+        let error_message = if partial_batches > 0 {
+            Some(format!(
+                "INVARIANT VIOLATION: {} batches recovered with only some of their writes",
+                partial_batches
+            ))
+        } else if !passed {
             Some(format!(
                 "INVARIANT VIOLATION: {} acknowledged writes missing after recovery. \
                  Acked: {}, Recovered: {}. Missing timestamps (first 10): {:?}",
@@ -278,6 +337,7 @@ impl WalDSTHarness {
             failed_writes,
             recovered_entries: recovered.len(),
             missing_after_recovery: missing,
+            partial_batches,
             store_stats: store.stats(),
             passed,
             error_message,
@@ -308,11 +368,19 @@ pub fn summarize_wal_dst_batch(results: &[WalDSTResult]) -> String {
     let total_acked: usize = results.iter().map(|r| r.acknowledged_writes).sum();
     let total_recovered: usize = results.iter().map(|r| r.recovered_entries).sum();
     let total_missing: usize = results.iter().map(|r| r.missing_after_recovery).sum();
+    let total_partial: usize = results.iter().map(|r| r.partial_batches).sum();
 
     let mut summary = format!(
         "WAL DST Batch: {}/{} passed ({} failed)\n\
-         Total writes: {}, Acknowledged: {}, Recovered: {}, Missing: {}",
-        passed, total, failed, total_writes, total_acked, total_recovered, total_missing
+         Total writes: {}, Acknowledged: {}, Recovered: {}, Missing: {}, Partial batches: {}",
+        passed,
+        total,
+        failed,
+        total_writes,
+        total_acked,
+        total_recovered,
+        total_missing,
+        total_partial
     );
 
     if failed > 0 {
@@ -329,6 +397,11 @@ pub fn summarize_wal_dst_batch(results: &[WalDSTResult]) -> String {
     summary
 }
 
+/// The write number a test delta was made for
+fn delta_time(delta: &ReplicationDelta) -> u64 {
+    delta.value.timestamp.time
+}
+
 fn make_test_delta(key: &str, value: &str, ts: u64) -> ReplicationDelta {
     let replica_id = ReplicaId::new(1);
     let clock = LamportClock {
@@ -401,6 +474,7 @@ mod tests {
             store_config: SimulatedWalStoreConfig::no_faults(),
             simulate_crash: true,
             fsync_after_write: true,
+            batch_size: 1,
         };
 
         let results = run_wal_dst_batch(0..20, config);
@@ -453,6 +527,7 @@ mod tests {
             store_config: SimulatedWalStoreConfig::no_faults(),
             simulate_crash: true,
             fsync_after_write: false, // EverySecond semantics
+            batch_size: 1,
         };
 
         let results = run_wal_dst_batch(0..20, config);
@@ -464,4 +539,18 @@ mod tests {
             assert!(r.acknowledged_writes > 0);
         }
     }
+
+    #[test]
+    fn test_wal_dst_batches_survive_torn_crashes() {
+        let config = WalDSTConfig {
+            batch_size: 4,
+            ..WalDSTConfig::crash_only()
+        };
+        let results = run_wal_dst_batch(0..30, config);
+
+        for r in &results {
+            assert!(r.passed, "Seed {} failed: {:?}", r.seed, r.error_message);
+            assert_eq!(r.partial_batches, 0);
+        }
+    }
 }
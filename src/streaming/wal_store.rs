@@ -145,6 +145,20 @@ impl InMemoryWalStore {
         }
     }
 
+    /// Drop the last `cut` bytes of the newest file: a write torn by a crash
+    pub fn tear_newest(&self, cut: usize) {
+        let mut files = self.files.lock().expect("wal store mutex poisoned");
+        if let Some(file) = files
+            .iter_mut()
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, file)| file)
+        {
+            let len = file.data.len().saturating_sub(cut);
+            file.data.truncate(len);
+            file.synced_pos = file.synced_pos.min(len);
+        }
+    }
+
     /// Simulate a crash: truncate all files to their synced position.
     /// Un-synced data is lost (as it would be in a real crash).
     pub fn simulate_crash(&self) {
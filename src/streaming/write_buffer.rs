@@ -7,6 +7,7 @@ use crate::replication::state::ReplicationDelta;
 use crate::streaming::{Compression, ObjectStore, SegmentWriter, WriteBufferConfig};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tracing::{error, warn};
 
 /// Error type for write buffer operations
 #[derive(Debug)]
@@ -169,7 +170,7 @@ impl<S: ObjectStore> WriteBuffer<S> {
     fn lock_inner(&self) -> std::sync::MutexGuard<'_, WriteBufferInner> {
         self.inner.lock().unwrap_or_else(|poisoned| {
             // Log the poison but recover the data - the buffer state is still usable
-            eprintln!("WriteBuffer: recovering from poisoned lock");
+            warn!("Write buffer lock poisoned; recovering its state");
             poisoned.into_inner()
         })
     }
@@ -352,14 +353,14 @@ impl<S: ObjectStore> FlushWorker<S> {
             if self.shutdown.load(std::sync::atomic::Ordering::SeqCst) {
                 // Final flush before shutdown
                 if let Err(e) = self.buffer.flush().await {
-                    eprintln!("Error during final flush: {}", e);
+                    error!(error = %e, "Final flush failed");
                 }
                 break;
             }
 
             if self.buffer.should_flush() {
                 if let Err(e) = self.buffer.flush().await {
-                    eprintln!("Error flushing write buffer: {}", e);
+                    warn!(error = %e, "Write buffer flush failed");
                 }
             }
 
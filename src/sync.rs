@@ -0,0 +1,48 @@
+//! Locks and atomics for state shared between shard tasks
+//!
+//! Normal builds get std's `Arc`, `RwLock` and atomics and parking_lot's
+//! `Mutex`. Building with `--cfg redis_sim_loom` swaps in loom's
+//! model-checked versions so `tests/loom_test.rs` can explore every
+//! interleaving of the structures built on them. (Not plain `--cfg loom`:
+//! tokio reacts to that too and would stop building.)
+//!
+//! ```text
+//! RUSTFLAGS="--cfg redis_sim_loom" cargo test --release --test loom_test
+//! ```
+
+#[cfg(not(redis_sim_loom))]
+pub(crate) use parking_lot::Mutex;
+#[cfg(not(redis_sim_loom))]
+pub(crate) use std::sync::{atomic, Arc, RwLock};
+
+#[cfg(redis_sim_loom)]
+pub(crate) use loom::sync::{atomic, Arc, RwLock};
+
+/// loom's mutex behind parking_lot's API: `lock()` returns the guard
+#[cfg(redis_sim_loom)]
+pub(crate) struct Mutex<T>(loom::sync::Mutex<T>);
+
+#[cfg(redis_sim_loom)]
+impl<T> Mutex<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Mutex(loom::sync::Mutex::new(value))
+    }
+
+    pub(crate) fn lock(&self) -> loom::sync::MutexGuard<'_, T> {
+        self.0.lock().expect("loom mutex poisoned")
+    }
+}
+
+#[cfg(redis_sim_loom)]
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Mutex::new(T::default())
+    }
+}
+
+#[cfg(redis_sim_loom)]
+impl<T> std::fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mutex").finish_non_exhaustive()
+    }
+}
@@ -79,13 +79,13 @@ fn test_monotonic_reads() {
     let delta3 = writer.record_write("key".to_string(), SDS::from_str("v3"), None);
 
     // Reader applies delta2 first (sees v2)
-    reader.apply_remote_delta(delta2.clone());
+    reader.apply_remote_delta(delta2.clone()).unwrap();
     let read1 = get_value(&reader, "key");
     println!("After applying delta2: {:?}", read1);
     assert_eq!(read1, Some("v2".to_string()));
 
     // Now apply delta1 (older) - should NOT regress to v1
-    reader.apply_remote_delta(delta1.clone());
+    reader.apply_remote_delta(delta1.clone()).unwrap();
     let read2 = get_value(&reader, "key");
     println!("After applying delta1 (older): {:?}", read2);
 
@@ -97,7 +97,7 @@ fn test_monotonic_reads() {
     );
 
     // Apply delta3 (newer) - should advance to v3
-    reader.apply_remote_delta(delta3.clone());
+    reader.apply_remote_delta(delta3.clone()).unwrap();
     let read3 = get_value(&reader, "key");
     println!("After applying delta3 (newer): {:?}", read3);
     assert_eq!(read3, Some("v3".to_string()));
@@ -129,7 +129,7 @@ fn test_writes_follow_reads() {
     println!("Node1 writes key_a = 'value_a'");
 
     // Node2 reads key_a (applies delta from node1)
-    node2.apply_remote_delta(delta_a.clone());
+    node2.apply_remote_delta(delta_a.clone()).unwrap();
     let read_a = get_value(&node2, "key_a");
     println!("Node2 reads key_a = {:?}", read_a);
     assert_eq!(read_a, Some("value_a".to_string()));
@@ -139,7 +139,7 @@ fn test_writes_follow_reads() {
     println!("Node2 writes key_b = 'value_b' (after reading key_a)");
 
     // Node3 receives delta_b but NOT delta_a yet
-    node3.apply_remote_delta(delta_b.clone());
+    node3.apply_remote_delta(delta_b.clone()).unwrap();
     let read_b_on_3 = get_value(&node3, "key_b");
     println!("Node3 sees key_b = {:?}", read_b_on_3);
 
@@ -151,7 +151,7 @@ fn test_writes_follow_reads() {
     println!("Delta_b VC: {:?}", delta_b.value.vector_clock);
 
     // Now node3 gets delta_a
-    node3.apply_remote_delta(delta_a.clone());
+    node3.apply_remote_delta(delta_a.clone()).unwrap();
     let read_a_on_3 = get_value(&node3, "key_a");
     println!("Node3 (after sync) key_a = {:?}", read_a_on_3);
 
@@ -195,12 +195,12 @@ fn test_causal_ordering_happens_before() {
     // Node2 receives deltas in wrong order
     println!("\nNode2 receives delta2 first, then delta1:");
 
-    node2.apply_remote_delta(delta2.clone());
+    node2.apply_remote_delta(delta2.clone()).unwrap();
     let after_delta2 = get_value(&node2, "key");
     println!("  After delta2: {:?}", after_delta2);
     assert_eq!(after_delta2, Some("v2".to_string()));
 
-    node2.apply_remote_delta(delta1.clone());
+    node2.apply_remote_delta(delta1.clone()).unwrap();
     let after_delta1 = get_value(&node2, "key");
     println!("  After delta1: {:?}", after_delta1);
 
@@ -249,8 +249,8 @@ fn test_concurrent_writes_no_causal_order() {
     // System uses LWW with replica_id as tiebreaker
 
     // Apply both deltas to both nodes
-    node1.apply_remote_delta(delta2.clone());
-    node2.apply_remote_delta(delta1.clone());
+    node1.apply_remote_delta(delta2.clone()).unwrap();
+    node2.apply_remote_delta(delta1.clone()).unwrap();
 
     let val1 = get_value(&node1, "key");
     let val2 = get_value(&node2, "key");
@@ -292,14 +292,14 @@ fn test_causal_chain_multi_node() {
     println!("N1 writes: step1_by_n1");
 
     // N2 receives from N1, then writes
-    node2.apply_remote_delta(delta1.clone());
+    node2.apply_remote_delta(delta1.clone()).unwrap();
     let read_on_n2 = get_value(&node2, "key");
     println!("N2 reads: {:?}", read_on_n2);
     let delta2 = node2.record_write("key".to_string(), SDS::from_str("step2_by_n2"), None);
     println!("N2 writes: step2_by_n2");
 
     // N3 receives from N2, then writes
-    node3.apply_remote_delta(delta2.clone());
+    node3.apply_remote_delta(delta2.clone()).unwrap();
     let read_on_n3 = get_value(&node3, "key");
     println!("N3 reads: {:?}", read_on_n3);
     let delta3 = node3.record_write("key".to_string(), SDS::from_str("step3_by_n3"), None);
@@ -315,10 +315,10 @@ fn test_causal_chain_multi_node() {
     assert!(delta3.value.timestamp.time > delta2.value.timestamp.time);
 
     // Now propagate all deltas to all nodes
-    node1.apply_remote_delta(delta2.clone());
-    node1.apply_remote_delta(delta3.clone());
-    node2.apply_remote_delta(delta3.clone());
-    node3.apply_remote_delta(delta1.clone());
+    node1.apply_remote_delta(delta2.clone()).unwrap();
+    node1.apply_remote_delta(delta3.clone()).unwrap();
+    node2.apply_remote_delta(delta3.clone()).unwrap();
+    node3.apply_remote_delta(delta1.clone()).unwrap();
 
     let final1 = get_value(&node1, "key");
     let final2 = get_value(&node2, "key");
@@ -371,8 +371,8 @@ fn test_vector_clock_tracking() {
     );
 
     // Exchange and then write again
-    node1.apply_remote_delta(delta2.clone());
-    node2.apply_remote_delta(delta1.clone());
+    node1.apply_remote_delta(delta2.clone()).unwrap();
+    node2.apply_remote_delta(delta1.clone()).unwrap();
 
     // Now writes should have updated vector clocks
     let delta3 = node1.record_write("key".to_string(), SDS::from_str("v3"), None);
@@ -467,7 +467,7 @@ fn test_multi_key_causal_dependencies() {
     // (same replica, so VC component for r1 should be >= )
 
     // Node2 receives only delta_b first
-    node2.apply_remote_delta(delta_b.clone());
+    node2.apply_remote_delta(delta_b.clone()).unwrap();
     let b_on_n2 = get_value(&node2, "key_b");
     let a_on_n2 = get_value(&node2, "key_a");
 
@@ -480,7 +480,7 @@ fn test_multi_key_causal_dependencies() {
     assert_eq!(a_on_n2, None);
 
     // Now receive delta_a
-    node2.apply_remote_delta(delta_a.clone());
+    node2.apply_remote_delta(delta_a.clone()).unwrap();
     let a_on_n2_after = get_value(&node2, "key_a");
     println!("N2 after receiving delta_a: key_a = {:?}", a_on_n2_after);
     assert_eq!(a_on_n2_after, Some("a_value".to_string()));
@@ -517,7 +517,7 @@ fn test_causal_stress() {
             if !all_deltas.is_empty() && round % 5 == 0 {
                 let sync_count = (all_deltas.len() / 3).max(1);
                 for delta in all_deltas.iter().take(sync_count) {
-                    node.apply_remote_delta(delta.clone());
+                    node.apply_remote_delta(delta.clone()).unwrap();
                 }
             }
 
@@ -535,7 +535,7 @@ fn test_causal_stress() {
     // Apply all deltas to all nodes
     for delta in &all_deltas {
         for node in &mut nodes {
-            node.apply_remote_delta(delta.clone());
+            node.apply_remote_delta(delta.clone()).unwrap();
         }
     }
 
@@ -0,0 +1,286 @@
+//! Client Library Compatibility Tests
+//!
+//! Runs the production server on a loopback port and drives it through
+//! redis-rs (sync, async multiplexed and RESP3 connections) and a
+//! deadpool-redis pool. These pin down what parser unit tests cannot see:
+//!
+//! - the connection handshake each client sends (HELLO, CLIENT SETINFO, SELECT)
+//! - pipelines, and atomic pipelines sent as MULTI/EXEC
+//! - server errors surfacing as client errors without desynchronising the
+//!   connection
+//!
+//! Needs the network, so it only builds with the `client-compat` feature:
+//!
+//! ```text
+//! cargo test --features client-compat --test client_compat_test
+//! ```
+
+use redis_sim::production::OptimizedRedisServer;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Start a production server on a free loopback port; returns its URL once
+/// it accepts connections
+async fn start_server() -> String {
+    let port = {
+        let probe = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe");
+        probe.local_addr().expect("probe address").port()
+    };
+    let addr = format!("127.0.0.1:{}", port);
+    tokio::spawn(OptimizedRedisServer::new(addr.clone()).run());
+
+    for _ in 0..100 {
+        if tokio::net::TcpStream::connect(&addr).await.is_ok() {
+            return format!("redis://{}/", addr);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("server did not start on {}", addr);
+}
+
+#[test]
+fn test_sync_client_round_trips_common_types() -> redis::RedisResult<()> {
+    let runtime = tokio::runtime::Runtime::new().expect("runtime");
+    let url = runtime.block_on(start_server());
+    let client = redis::Client::open(url)?;
+    let mut con = client.get_connection()?;
+
+    let pong: String = redis::cmd("PING").query(&mut con)?;
+    assert_eq!(pong, "PONG");
+
+    redis::cmd("SET")
+        .arg("s")
+        .arg("hello")
+        .query::<()>(&mut con)?;
+    let value: String = redis::cmd("GET").arg("s").query(&mut con)?;
+    assert_eq!(value, "hello");
+    let missing: Option<String> = redis::cmd("GET").arg("nope").query(&mut con)?;
+    assert_eq!(missing, None);
+
+    let n: i64 = redis::cmd("INCRBY").arg("n").arg(41).query(&mut con)?;
+    assert_eq!(n, 41);
+
+    redis::cmd("HSET")
+        .arg("h")
+        .arg(&[("a", "1"), ("b", "2")])
+        .query::<()>(&mut con)?;
+    let hash: HashMap<String, i64> = redis::cmd("HGETALL").arg("h").query(&mut con)?;
+    assert_eq!(
+        hash,
+        HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)])
+    );
+
+    redis::cmd("RPUSH")
+        .arg("l")
+        .arg(&["x", "y", "z"])
+        .query::<()>(&mut con)?;
+    let list: Vec<String> = redis::cmd("LRANGE")
+        .arg("l")
+        .arg(0)
+        .arg(-1)
+        .query(&mut con)?;
+    assert_eq!(list, ["x", "y", "z"]);
+
+    redis::cmd("EXPIRE")
+        .arg("s")
+        .arg(100)
+        .query::<()>(&mut con)?;
+    let ttl: i64 = redis::cmd("TTL").arg("s").query(&mut con)?;
+    assert!((1..=100).contains(&ttl), "TTL {}", ttl);
+
+    let deleted: i64 = redis::cmd("DEL")
+        .arg(&["s", "n", "h", "l"])
+        .query(&mut con)?;
+    assert_eq!(deleted, 4);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_async_pipelines_and_concurrent_callers() -> redis::RedisResult<()> {
+    let client = redis::Client::open(start_server().await)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+
+    // One pipeline, many replies, in order
+    let mut pipe = redis::pipe();
+    for i in 0..100 {
+        pipe.cmd("SET").arg(format!("p{}", i)).arg(i).ignore();
+    }
+    for i in 0..100 {
+        pipe.cmd("GET").arg(format!("p{}", i));
+    }
+    let values: Vec<i64> = pipe.query_async(&mut con).await?;
+    assert_eq!(values, (0..100).collect::<Vec<i64>>());
+
+    // A multiplexed connection interleaves callers on one socket
+    let callers: Vec<_> = (0..50)
+        .map(|_| {
+            let mut con = con.clone();
+            async move {
+                redis::cmd("INCR")
+                    .arg("counter")
+                    .query_async::<i64>(&mut con)
+                    .await
+            }
+        })
+        .collect();
+    for result in futures::future::join_all(callers).await {
+        result?;
+    }
+    let counter: i64 = redis::cmd("GET")
+        .arg("counter")
+        .query_async(&mut con)
+        .await?;
+    assert_eq!(counter, 50);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_atomic_pipeline_runs_as_one_transaction() -> redis::RedisResult<()> {
+    let client = redis::Client::open(start_server().await)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+
+    // Sent as MULTI ... EXEC; only the EXEC reply is decoded
+    let (value, length): (i64, i64) = redis::pipe()
+        .atomic()
+        .cmd("SET")
+        .arg("tx")
+        .arg(1)
+        .ignore()
+        .cmd("INCRBY")
+        .arg("tx")
+        .arg(2)
+        .ignore()
+        .cmd("GET")
+        .arg("tx")
+        .cmd("RPUSH")
+        .arg("tx-list")
+        .arg(&["a", "b"])
+        .query_async(&mut con)
+        .await?;
+    assert_eq!((value, length), (3, 2));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_server_errors_leave_the_connection_usable() -> redis::RedisResult<()> {
+    let client = redis::Client::open(start_server().await)?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+
+    redis::cmd("SET")
+        .arg("str")
+        .arg("not a number")
+        .query_async::<()>(&mut con)
+        .await?;
+
+    let wrong_type = redis::cmd("LPUSH")
+        .arg("str")
+        .arg("x")
+        .query_async::<i64>(&mut con)
+        .await
+        .expect_err("LPUSH on a string must fail");
+    assert!(
+        wrong_type.to_string().contains("wrong kind of value"),
+        "{}",
+        wrong_type
+    );
+
+    let not_integer = redis::cmd("INCR")
+        .arg("str")
+        .query_async::<i64>(&mut con)
+        .await
+        .expect_err("INCR on text must fail");
+    assert!(
+        not_integer.to_string().contains("not an integer"),
+        "{}",
+        not_integer
+    );
+
+    let unknown = redis::cmd("NOSUCHCOMMAND")
+        .query_async::<()>(&mut con)
+        .await
+        .expect_err("unknown commands must fail");
+    assert!(
+        unknown.to_string().contains("unknown command"),
+        "{}",
+        unknown
+    );
+
+    // An error in the middle of a pipeline fails the pipeline, not the socket
+    let failed: redis::RedisResult<(String, i64)> = redis::pipe()
+        .cmd("GET")
+        .arg("str")
+        .cmd("INCR")
+        .arg("str")
+        .query_async(&mut con)
+        .await;
+    assert!(failed.is_err());
+
+    let value: String = redis::cmd("GET").arg("str").query_async(&mut con).await?;
+    assert_eq!(value, "not a number");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_resp3_and_database_selection_handshakes() -> redis::RedisResult<()> {
+    let url = start_server().await;
+
+    // HELLO 3 on connect; maps come back as RESP3 maps
+    let client = redis::Client::open(format!("{}?protocol=resp3", url))?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+    redis::cmd("HSET")
+        .arg("h3")
+        .arg(&[("f", "v")])
+        .query_async::<()>(&mut con)
+        .await?;
+    let hash: HashMap<String, String> = redis::cmd("HGETALL")
+        .arg("h3")
+        .query_async(&mut con)
+        .await?;
+    assert_eq!(hash.get("f").map(String::as_str), Some("v"));
+
+    // SELECT on connect
+    let client = redis::Client::open(format!("{}2", url))?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+    let pong: String = redis::cmd("PING").query_async(&mut con).await?;
+    assert_eq!(pong, "PONG");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_deadpool_connections_share_the_keyspace() {
+    use deadpool_redis::{redis, Config, Runtime};
+
+    let mut config = Config::from_url(start_server().await);
+    config.pool = Some(deadpool_redis::PoolConfig::new(4));
+    let pool = config
+        .create_pool(Some(Runtime::Tokio1))
+        .expect("create pool");
+
+    // More tasks than connections: each waits for a free one
+    let writers: Vec<_> = (0..32)
+        .map(|i| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut con = pool.get().await.expect("pooled connection");
+                redis::cmd("SET")
+                    .arg(format!("pool:{}", i))
+                    .arg(i)
+                    .query_async::<()>(&mut con)
+                    .await
+                    .expect("SET through the pool");
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.await.expect("writer task");
+    }
+    assert!(pool.status().size <= 4, "{:?}", pool.status());
+
+    let mut con = pool.get().await.expect("pooled connection");
+    let keys: Vec<String> = redis::cmd("KEYS")
+        .arg("pool:*")
+        .query_async(&mut con)
+        .await
+        .expect("KEYS through the pool");
+    assert_eq!(keys.len(), 32);
+}
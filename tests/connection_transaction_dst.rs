@@ -6,11 +6,17 @@
 //!
 //! The production server moved transaction state to the connection level to
 //! support cross-shard transactions. This DST exercises that exact code path
-//! using ShardedActorState as the backend.
+//! using ShardedActorState as the backend: WATCH registers keys on their
+//! shards and EXEC goes through the transaction coordinator.
+//!
+//! The contention scenario runs several clients at once against four shards,
+//! moving units between accounts with WATCH retry loops while auditors read
+//! every account in one transaction. Lost updates or a torn read show up as
+//! a total that is not conserved.
 
 use redis_sim::io::simulation::SimulatedRng;
 use redis_sim::io::Rng;
-use redis_sim::production::ShardedActorState;
+use redis_sim::production::{ShardedActorState, WatchGuard};
 use redis_sim::redis::{Command, RespValue, SDS};
 
 /// Simulates a single client connection's transaction state.
@@ -20,7 +26,7 @@ struct SimulatedConnection {
     in_transaction: bool,
     transaction_queue: Vec<Command>,
     transaction_errors: bool,
-    watched_keys: Vec<(String, RespValue)>,
+    watched_keys: Vec<WatchGuard>,
 }
 
 impl SimulatedConnection {
@@ -50,26 +56,10 @@ impl SimulatedConnection {
                         )
                     } else {
                         let watched = std::mem::take(&mut self.watched_keys);
-                        let mut watch_failed = false;
-                        for (key, old_value) in &watched {
-                            let current =
-                                self.state.execute(&Command::Get(key.clone())).await;
-                            if !resp_values_equal(&current, old_value) {
-                                watch_failed = true;
-                                break;
-                            }
-                        }
-                        if watch_failed {
-                            self.transaction_queue.clear();
-                            RespValue::Array(None)
-                        } else {
-                            let queued = std::mem::take(&mut self.transaction_queue);
-                            let mut results = Vec::with_capacity(queued.len());
-                            for queued_cmd in &queued {
-                                let r = self.state.execute(queued_cmd).await;
-                                results.push(r);
-                            }
-                            RespValue::Array(Some(results))
+                        let queued = std::mem::take(&mut self.transaction_queue);
+                        match self.state.execute_transaction(&watched, &queued).await {
+                            Some(results) => RespValue::Array(Some(results)),
+                            None => RespValue::Array(None),
                         }
                     }
                 }
@@ -108,9 +98,9 @@ impl SimulatedConnection {
                 Command::Discard => RespValue::err("ERR DISCARD without MULTI"),
                 Command::Watch(keys) => {
                     for key in keys {
-                        let snapshot =
-                            self.state.execute(&Command::Get(key.clone())).await;
-                        self.watched_keys.push((key.clone(), snapshot));
+                        let guard = self.state.watch(key).await;
+                        self.watched_keys.retain(|watched| watched.key() != key);
+                        self.watched_keys.push(guard);
                     }
                     RespValue::simple("OK")
                 }
@@ -159,7 +149,7 @@ fn is_error(resp: &RespValue) -> bool {
 async fn run_connection_transaction_dst(seed: u64) -> Vec<String> {
     let mut rng = SimulatedRng::new(seed);
     let mut violations: Vec<String> = Vec::new();
-    let state = ShardedActorState::with_shards(1);
+    let state = ShardedActorState::with_shards(4);
 
     let mut conn_a = SimulatedConnection::new(state.clone());
     let mut conn_b = SimulatedConnection::new(state.clone());
@@ -180,7 +170,7 @@ async fn run_connection_transaction_dst(seed: u64) -> Vec<String> {
         let key_idx = rng.gen_range(0, num_keys as u64);
         let key = format!("k:{}", key_idx);
         let val_a = format!("a:{}", rng.gen_range(0, 1000));
-        let val_b = format!("b:{}", rng.gen_range(0, 1000));
+        let _val_b = format!("b:{}", rng.gen_range(0, 1000));
 
         if scenario < 25 {
             // === WATCH + no conflict => EXEC succeeds ===
@@ -427,6 +417,150 @@ async fn run_connection_transaction_dst(seed: u64) -> Vec<String> {
     violations
 }
 
+const NUM_ACCOUNTS: u64 = 8;
+const INITIAL_BALANCE: i64 = 100;
+
+fn account(index: u64) -> String {
+    format!("acct:{}", index)
+}
+
+fn balance(reply: &RespValue) -> i64 {
+    match reply {
+        RespValue::BulkString(Some(data)) => String::from_utf8_lossy(data)
+            .parse()
+            .expect("balances are integers"),
+        other => panic!("expected a balance, got {:?}", other),
+    }
+}
+
+/// Yield to the other tasks a seeded number of times, so where each client
+/// is interrupted follows the seed
+async fn interleave(rng: &mut SimulatedRng) {
+    for _ in 0..rng.gen_range(0, 4) {
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Move units between two accounts with WATCH/GET/MULTI/EXEC, retrying on
+/// abort. Returns how many EXECs committed and aborted.
+async fn run_transfer_client(mut conn: SimulatedConnection, seed: u64) -> (u64, u64) {
+    let mut rng = SimulatedRng::new(seed);
+    let (mut committed, mut aborted) = (0, 0);
+    for _ in 0..20 {
+        let from = account(rng.gen_range(0, NUM_ACCOUNTS));
+        let to = account(rng.gen_range(0, NUM_ACCOUNTS));
+        if from == to {
+            continue;
+        }
+        let amount = rng.gen_range(1, 10) as i64;
+        loop {
+            conn.execute(&Command::Watch(vec![from.clone(), to.clone()]))
+                .await;
+            let from_balance = balance(&conn.execute(&Command::Get(from.clone())).await);
+            interleave(&mut rng).await;
+            let to_balance = balance(&conn.execute(&Command::Get(to.clone())).await);
+            interleave(&mut rng).await;
+
+            conn.execute(&Command::Multi).await;
+            let debit = (from_balance - amount).to_string();
+            let credit = (to_balance + amount).to_string();
+            conn.execute(&Command::set(from.clone(), SDS::from_str(&debit)))
+                .await;
+            conn.execute(&Command::set(to.clone(), SDS::from_str(&credit)))
+                .await;
+            match conn.execute(&Command::Exec).await {
+                RespValue::Array(Some(_)) => {
+                    committed += 1;
+                    break;
+                }
+                RespValue::Array(None) => aborted += 1,
+                other => panic!("seed {}: transfer EXEC replied {:?}", seed, other),
+            }
+        }
+    }
+    (committed, aborted)
+}
+
+/// Read every account in one transaction; the total must never change
+async fn run_auditor(mut conn: SimulatedConnection, seed: u64) -> Vec<String> {
+    let mut rng = SimulatedRng::new(seed);
+    let keys: Vec<String> = (0..NUM_ACCOUNTS).map(account).collect();
+    let expected = INITIAL_BALANCE * NUM_ACCOUNTS as i64;
+    let mut violations = Vec::new();
+    for _ in 0..20 {
+        conn.execute(&Command::Multi).await;
+        conn.execute(&Command::MGet(keys.clone())).await;
+        match conn.execute(&Command::Exec).await {
+            RespValue::Array(Some(results)) => match &results[..] {
+                [RespValue::Array(Some(balances))] => {
+                    let total: i64 = balances.iter().map(balance).sum();
+                    if total != expected {
+                        violations.push(format!(
+                            "seed {}: auditor saw total {}, want {}",
+                            seed, total, expected
+                        ));
+                    }
+                }
+                other => violations.push(format!("seed {}: MGET replied {:?}", seed, other)),
+            },
+            other => violations.push(format!("seed {}: audit EXEC replied {:?}", seed, other)),
+        }
+        interleave(&mut rng).await;
+    }
+    violations
+}
+
+/// Cross-shard contention: four transfer clients and two auditors run at
+/// once on a single-threaded runtime against four shards
+async fn run_contention_dst(seed: u64) -> Vec<String> {
+    let state = ShardedActorState::with_shards(4);
+    let mut setup = SimulatedConnection::new(state.clone());
+    for index in 0..NUM_ACCOUNTS {
+        let initial = INITIAL_BALANCE.to_string();
+        setup
+            .execute(&Command::set(account(index), SDS::from_str(&initial)))
+            .await;
+    }
+
+    let transfers: Vec<_> = (0..4)
+        .map(|client| {
+            let conn = SimulatedConnection::new(state.clone());
+            tokio::spawn(run_transfer_client(conn, seed * 100 + client))
+        })
+        .collect();
+    let auditors: Vec<_> = (0..2)
+        .map(|client| {
+            let conn = SimulatedConnection::new(state.clone());
+            tokio::spawn(run_auditor(conn, seed * 100 + 50 + client))
+        })
+        .collect();
+
+    let mut violations = Vec::new();
+    let mut committed = 0;
+    for transfer in transfers {
+        let (done, _aborted) = transfer.await.expect("transfer client panicked");
+        committed += done;
+    }
+    for auditor in auditors {
+        violations.extend(auditor.await.expect("auditor panicked"));
+    }
+
+    let keys: Vec<String> = (0..NUM_ACCOUNTS).map(account).collect();
+    if let RespValue::Array(Some(balances)) = setup.execute(&Command::MGet(keys)).await {
+        let total: i64 = balances.iter().map(balance).sum();
+        if total != INITIAL_BALANCE * NUM_ACCOUNTS as i64 {
+            violations.push(format!(
+                "seed {}: {} transfers committed but the total is {}",
+                seed, committed, total
+            ));
+        }
+    }
+    if committed == 0 {
+        violations.push(format!("seed {}: no transfer committed", seed));
+    }
+    violations
+}
+
 #[tokio::test]
 async fn test_connection_transaction_dst_single() {
     let violations = run_connection_transaction_dst(42).await;
@@ -462,3 +596,16 @@ async fn test_connection_transaction_dst_100_seeds() {
         );
     }
 }
+
+#[tokio::test]
+async fn test_cross_shard_contention_dst_10_seeds() {
+    for seed in 0..10 {
+        let violations = run_contention_dst(seed).await;
+        assert!(
+            violations.is_empty(),
+            "Seed {} violations: {:?}",
+            seed,
+            violations
+        );
+    }
+}
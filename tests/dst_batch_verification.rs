@@ -4,10 +4,14 @@
 //! - Determinism across all seeds
 //! - Crash/recovery handling
 //! - Fault tolerance
+//! - Per-profile pass rates (CI matrix, see `test_chaos_profile_matrix`)
+//! - Known-bad seeds stay fixed (`test_replay_corpus`)
 
 use redis_sim::buggify::{self, FaultConfig};
-use redis_sim::simulator::dst::{BatchRunner, DSTConfig};
+use redis_sim::simulator::corpus::SeedCorpus;
+use redis_sim::simulator::dst::{BatchRunner, ChaosProfile, DSTConfig};
 use redis_sim::simulator::dst_integration::run_redis_dst_batch;
+use std::path::Path;
 
 #[test]
 fn test_1000_seeds_calm() {
@@ -104,3 +108,64 @@ fn test_batch_runner_core_dst() {
     );
     assert!(results.total_operations >= 100_000);
 }
+
+/// One CI matrix cell per chaos profile.
+///
+/// `DST_PROFILE` selects a profile (default: all of them), `DST_SEEDS`
+/// the batch size, and `DST_SUMMARY_DIR` where to write the per-profile
+/// `dst-summary-<profile>.json` artifact. With `DST_CORPUS_DIR` set,
+/// failing seeds are added to that seed corpus, tagged with `GITHUB_SHA`.
+#[test]
+fn test_chaos_profile_matrix() {
+    let profiles = match std::env::var("DST_PROFILE") {
+        Ok(name) => vec![name.parse::<ChaosProfile>().unwrap()],
+        Err(_) => ChaosProfile::ALL.to_vec(),
+    };
+    let seeds = std::env::var("DST_SEEDS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(50);
+
+    for (i, profile) in profiles.into_iter().enumerate() {
+        let results = BatchRunner::new(60000 + 10000 * i as u64, seeds)
+            .with_profile(profile)
+            .run_default(100);
+        println!("{}", results.summary());
+
+        if let Ok(dir) = std::env::var("DST_SUMMARY_DIR") {
+            let path = results.write_summary(Path::new(&dir)).unwrap();
+            println!("Summary written to {}", path.display());
+        }
+
+        if let Ok(dir) = std::env::var("DST_CORPUS_DIR") {
+            let commit = std::env::var("GITHUB_SHA").ok();
+            let added = SeedCorpus::open(dir)
+                .unwrap()
+                .record_batch_failures(&results, 100, commit.as_deref())
+                .unwrap();
+            println!("{} new corpus entries", added);
+        }
+
+        assert!(
+            results.all_passed(),
+            "Profile {} failed seeds: {:?}",
+            profile,
+            results.failed_seeds
+        );
+    }
+}
+
+/// Regression gate: every seed in the corpus (`tests/dst-corpus`, or
+/// `DST_CORPUS_DIR`) must pass. Run with `scripts/soak-dst.sh replay-corpus`.
+#[test]
+fn test_replay_corpus() {
+    let dir = std::env::var("DST_CORPUS_DIR")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/tests/dst-corpus").to_string());
+    let corpus = SeedCorpus::open(&dir).unwrap();
+    let report = corpus.replay();
+
+    println!("\n=== Seed Corpus ({}) ===", dir);
+    println!("{}", report.summary());
+
+    assert!(report.all_passed(), "{}", report.summary());
+}
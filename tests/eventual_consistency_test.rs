@@ -49,14 +49,14 @@ fn test_lww_convergence_concurrent_writes() {
     println!("Delta3 timestamp: {:?}", delta3.value.timestamp);
 
     // Apply all deltas to all nodes (simulating gossip convergence)
-    state1.apply_remote_delta(delta2.clone());
-    state1.apply_remote_delta(delta3.clone());
+    state1.apply_remote_delta(delta2.clone()).unwrap();
+    state1.apply_remote_delta(delta3.clone()).unwrap();
 
-    state2.apply_remote_delta(delta1.clone());
-    state2.apply_remote_delta(delta3.clone());
+    state2.apply_remote_delta(delta1.clone()).unwrap();
+    state2.apply_remote_delta(delta3.clone()).unwrap();
 
-    state3.apply_remote_delta(delta1.clone());
-    state3.apply_remote_delta(delta2.clone());
+    state3.apply_remote_delta(delta1.clone()).unwrap();
+    state3.apply_remote_delta(delta2.clone()).unwrap();
 
     // All nodes should converge to the same value
     let val1 = state1
@@ -102,12 +102,12 @@ fn test_lww_convergence_different_order() {
     let mut state_b = ShardReplicaState::new(ReplicaId::new(11), ConsistencyLevel::Eventual);
 
     // State A: delta1 then delta2
-    state_a.apply_remote_delta(delta1.clone());
-    state_a.apply_remote_delta(delta2.clone());
+    state_a.apply_remote_delta(delta1.clone()).unwrap();
+    state_a.apply_remote_delta(delta2.clone()).unwrap();
 
     // State B: delta2 then delta1 (reverse order)
-    state_b.apply_remote_delta(delta2.clone());
-    state_b.apply_remote_delta(delta1.clone());
+    state_b.apply_remote_delta(delta2.clone()).unwrap();
+    state_b.apply_remote_delta(delta1.clone()).unwrap();
 
     let val_a = state_a
         .get_replicated("key")
@@ -140,13 +140,13 @@ fn test_delete_convergence() {
     let write_delta = state1.record_write("key".to_string(), SDS::from_str("value"), None);
 
     // First apply write to state2 so it has the key
-    state2.apply_remote_delta(write_delta.clone());
+    state2.apply_remote_delta(write_delta.clone()).unwrap();
 
     // Then delete on state2
     let delete_delta = state2.record_delete("key".to_string()).unwrap();
 
     // Apply delete to state1
-    state1.apply_remote_delta(delete_delta.clone());
+    state1.apply_remote_delta(delete_delta.clone()).unwrap();
 
     let val1 = state1.get_replicated("key");
     let val2 = state2.get_replicated("key");
@@ -238,7 +238,7 @@ fn test_causal_consistency_vector_clocks() {
     let delta1 = state1.record_write("key".to_string(), SDS::from_str("v1"), None);
 
     // Apply to node2, then write on node2 (causally after)
-    state2.apply_remote_delta(delta1.clone());
+    state2.apply_remote_delta(delta1.clone()).unwrap();
     let delta2 = state2.record_write("key".to_string(), SDS::from_str("v2"), None);
 
     println!("=== Causal Consistency Test ===");
@@ -252,7 +252,7 @@ fn test_causal_consistency_vector_clocks() {
     );
 
     // Apply delta2 to node1
-    state1.apply_remote_delta(delta2.clone());
+    state1.apply_remote_delta(delta2.clone()).unwrap();
 
     let val1 = state1
         .get_replicated("key")
@@ -286,7 +286,7 @@ fn test_partition_and_heal_convergence() {
 
     // Both nodes start with same value
     let initial = state1.record_write("key".to_string(), SDS::from_str("initial"), None);
-    state2.apply_remote_delta(initial);
+    state2.apply_remote_delta(initial).unwrap();
 
     // PARTITION: nodes write independently
     let delta1 = state1.record_write("key".to_string(), SDS::from_str("partition_value_1"), None);
@@ -311,8 +311,8 @@ fn test_partition_and_heal_convergence() {
     );
 
     // HEAL: exchange deltas
-    state1.apply_remote_delta(delta2.clone());
-    state2.apply_remote_delta(delta1.clone());
+    state1.apply_remote_delta(delta2.clone()).unwrap();
+    state2.apply_remote_delta(delta1.clone()).unwrap();
 
     let val1 = state1
         .get_replicated("key")
@@ -371,12 +371,12 @@ fn test_many_nodes_convergence() {
     // Propagate all deltas to all nodes (full mesh gossip)
     for delta in &all_deltas {
         for state in &mut states {
-            state.apply_remote_delta(delta.clone());
+            state.apply_remote_delta(delta.clone()).unwrap();
         }
     }
     for delta in &shared_deltas {
         for state in &mut states {
-            state.apply_remote_delta(delta.clone());
+            state.apply_remote_delta(delta.clone()).unwrap();
         }
     }
 
@@ -438,7 +438,7 @@ fn test_high_contention_convergence() {
     // Apply all deltas to all nodes
     for delta in &all_deltas {
         for state in &mut states {
-            state.apply_remote_delta(delta.clone());
+            state.apply_remote_delta(delta.clone()).unwrap();
         }
     }
 
@@ -63,6 +63,40 @@ fn test_executor_dst_chaos_50_seeds() {
     assert_eq!(passed, 50, "All 50 chaos seeds should pass");
 }
 
+// =============================================================================
+// Precise Expiry Tests - every deadline observed 1ms either side
+// =============================================================================
+
+#[test]
+fn test_executor_dst_precise_expiry_50_seeds() {
+    let results = run_executor_batch(0, 50, 1000, ExecutorDSTConfig::precise_expiry);
+    let summary = summarize_executor_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(passed, 50, "All 50 precise-expiry seeds should pass");
+
+    let checks: u64 = results.iter().map(|r| r.expiry_boundary_checks).sum();
+    assert!(checks > 0, "Precise mode should observe expiry deadlines");
+}
+
+// =============================================================================
+// BITFIELD Overflow Tests - WRAP/SAT/FAIL against an i128 shadow
+// =============================================================================
+
+#[test]
+fn test_executor_dst_bitfield_heavy_50_seeds() {
+    let results = run_executor_batch(0, 50, 1000, ExecutorDSTConfig::bitfield_heavy);
+    let summary = summarize_executor_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(passed, 50, "All 50 BITFIELD-heavy seeds should pass");
+
+    let overflows: u64 = results.iter().map(|r| r.bitfield_overflows).sum();
+    assert!(overflows > 0, "BITFIELD-heavy runs should overflow fields");
+}
+
 // =============================================================================
 // Stress Tests - High Operation Count
 // =============================================================================
@@ -128,6 +162,7 @@ fn test_executor_dst_all_categories_exercised() {
     assert!(result.hash_ops > 0, "Hash ops should be exercised");
     assert!(result.sorted_set_ops > 0, "Sorted set ops should be exercised");
     assert!(result.expiry_ops > 0, "Expiry ops should be exercised");
+    assert!(result.bitfield_ops > 0, "BITFIELD ops should be exercised");
 }
 
 // =============================================================================
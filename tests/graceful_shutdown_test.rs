@@ -0,0 +1,229 @@
+//! Graceful Shutdown Integration Tests
+//!
+//! Runs the persistent server's serving path (ClientRegistry +
+//! `handle_persistent_connection`) with LocalFs streaming persistence and a
+//! WAL, shuts it down the way `server_persistent` does on SIGTERM, and checks:
+//!
+//! - every write acknowledged with `+OK` survives restart
+//! - idle clients receive the `-SHUTDOWN` notice before the socket closes
+//! - the shutdown checkpoint is what recovery loads
+
+use redis_sim::production::{
+    drain_clients, handle_persistent_connection, ClientRegistry, ReplicatedShardedState,
+    SHUTDOWN_NOTICE,
+};
+use redis_sim::redis::{Command, RespValue};
+use redis_sim::replication::ReplicationConfig;
+use redis_sim::streaming::config::{CheckpointConfig, CompactionConfig};
+use redis_sim::streaming::wal_store::LocalWalStore;
+use redis_sim::streaming::{
+    spawn_wal_actor, FsyncPolicy, ObjectStoreType, StreamingConfig, StreamingIntegration,
+    WalConfig, WriteBufferConfig,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinSet;
+
+const WRITERS: usize = 4;
+
+fn test_config() -> ReplicationConfig {
+    ReplicationConfig {
+        replica_id: 1,
+        enabled: false,
+        ..Default::default()
+    }
+}
+
+fn streaming_config(dir: &TempDir) -> StreamingConfig {
+    StreamingConfig {
+        enabled: true,
+        store_type: ObjectStoreType::LocalFs,
+        prefix: "shutdown-test".to_string(),
+        local_path: Some(dir.path().to_path_buf()),
+        #[cfg(feature = "s3")]
+        s3: None,
+        write_buffer: WriteBufferConfig::test(),
+        checkpoint: CheckpointConfig::test(),
+        compaction: CompactionConfig::test(),
+        wal: None,
+    }
+}
+
+fn resp_command(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+    }
+    out
+}
+
+/// Write `SET w{id}:{n} v{n}` until the server stops acknowledging.
+/// Returns the keys acknowledged with +OK.
+async fn writer(addr: std::net::SocketAddr, id: usize) -> Vec<String> {
+    let stream = TcpStream::connect(addr).await.expect("connect");
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let mut acked = Vec::new();
+    let mut line = String::new();
+
+    for n in 0.. {
+        let key = format!("w{}:{}", id, n);
+        let value = format!("v{}", n);
+        if write
+            .write_all(&resp_command(&["SET", &key, &value]))
+            .await
+            .is_err()
+        {
+            break;
+        }
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) if line == "+OK\r\n" => acked.push(key),
+            Ok(_) => {
+                assert!(
+                    line.starts_with(&format!("-{}", SHUTDOWN_NOTICE)),
+                    "unexpected reply: {:?}",
+                    line
+                );
+                break;
+            }
+        }
+    }
+    acked
+}
+
+/// Serve connections until `stop` fires; returns the live connection tasks
+async fn serve(
+    listener: TcpListener,
+    state: Arc<ReplicatedShardedState>,
+    registry: Arc<ClientRegistry>,
+    mut stop: oneshot::Receiver<()>,
+) -> JoinSet<()> {
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            result = listener.accept() => {
+                let (stream, addr) = result.expect("accept");
+                let session = registry.register(&addr.to_string(), None);
+                let (state, registry) = (state.clone(), registry.clone());
+                connections.spawn(async move {
+                    let _ = handle_persistent_connection(stream, state, registry, session).await;
+                });
+            }
+            _ = &mut stop => return connections,
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_sigterm_shutdown_loses_no_acknowledged_write() {
+    let data_dir = TempDir::new().unwrap();
+    let wal_dir = TempDir::new().unwrap();
+
+    // Boot: streaming persistence + WAL, as server_persistent does
+    let integration = StreamingIntegration::new_local_fs(streaming_config(&data_dir), 1).unwrap();
+    let mut state = ReplicatedShardedState::new(test_config());
+    let (workers, sink) = integration.start_workers().await.unwrap();
+    state.set_delta_sink(sink);
+    let wal_config = WalConfig {
+        enabled: true,
+        wal_dir: wal_dir.path().to_path_buf(),
+        fsync_policy: FsyncPolicy::EverySecond,
+        ..WalConfig::default()
+    };
+    let (wal, wal_task) = spawn_wal_actor(
+        LocalWalStore::new(wal_dir.path().to_path_buf()).unwrap(),
+        wal_config,
+    )
+    .unwrap();
+    state.set_wal_handle(wal.clone());
+    let state = Arc::new(state);
+
+    let registry = Arc::new(ClientRegistry::new());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let server = tokio::spawn(serve(listener, state.clone(), registry.clone(), stop_rx));
+
+    // An idle client must still be told why it is being disconnected
+    let idle_reply = Arc::new(Mutex::new(String::new()));
+    let idle = {
+        let idle_reply = idle_reply.clone();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stream);
+            reader
+                .read_line(&mut *idle_reply.lock().await)
+                .await
+                .unwrap();
+        })
+    };
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|id| tokio::spawn(writer(addr, id)))
+        .collect();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // SIGTERM: stop accepting, drain, then flush in server_persistent order
+    stop_tx.send(()).unwrap();
+    let mut connections = server.await.unwrap();
+    let report = drain_clients(&registry, &mut connections, Duration::from_secs(5)).await;
+    assert_eq!(report.aborted, 0, "all clients must drain: {:?}", report);
+    assert_eq!(report.sessions, WRITERS + 1);
+
+    wal.shutdown().await;
+    wal_task.await.unwrap();
+    workers.shutdown().await;
+    let checkpoint = integration
+        .write_checkpoint(&state)
+        .await
+        .unwrap()
+        .expect("writes were flushed, so a checkpoint must be written");
+
+    let mut acked = Vec::new();
+    for w in writers {
+        acked.extend(w.await.unwrap());
+    }
+    idle.await.unwrap();
+    assert!(
+        acked.len() > WRITERS * 10,
+        "workload too small to mean anything: {} acks",
+        acked.len()
+    );
+    assert_eq!(
+        *idle_reply.lock().await,
+        format!("-{}\r\n", SHUTDOWN_NOTICE),
+        "idle client must receive the shutdown notice"
+    );
+    assert!(checkpoint.key_count as usize >= acked.len());
+
+    // Restart from the object store alone: the shutdown flush and checkpoint
+    // must cover every acknowledged write without help from the WAL
+    let restarted = ReplicatedShardedState::new(test_config());
+    let recovery = StreamingIntegration::new_local_fs(streaming_config(&data_dir), 1).unwrap();
+    let stats = recovery.recover(&restarted).await.unwrap();
+    assert!(
+        stats.used_checkpoint,
+        "recovery must load the shutdown checkpoint"
+    );
+    assert_eq!(
+        stats.segments_loaded, 0,
+        "checkpoint must cover every segment"
+    );
+
+    for key in &acked {
+        let value = restarted.execute(Command::Get(key.clone())).await;
+        let n = key.split(':').nth(1).unwrap();
+        assert_eq!(
+            value,
+            RespValue::BulkString(Some(format!("v{}", n).into_bytes())),
+            "acknowledged write {} lost across shutdown",
+            key
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Latency Budget DST Tests
+//!
+//! Measures command-ack latency through `ReplicatedShardedState` while fault
+//! storms hit both persistence backends:
+//!
+//! - WAL: `SimulatedWalStore` with heavy write/fsync/disk-full injection
+//! - Object store: `SimulatedObjectStore` with slow ops, region outages and
+//!   503 throttling bursts behind the streaming persistence actor
+//!
+//! INVARIANT: with fire-and-forget durability (`EverySecond`/`No`) the command
+//! path never waits on persistence, so ack p99 stays within a fixed budget no
+//! matter how the backends misbehave.
+//!
+//! Runs on tokio's paused clock: time only advances when every task is idle,
+//! so an ack that (wrongly) awaited a flush or fsync would absorb the injected
+//! store latency and show up in the percentiles. Fire-and-forget acks measure
+//! 0 virtual time; the `Always` contrast test proves the harness is sensitive.
+
+use redis_sim::io::simulation::SimulatedRng;
+use redis_sim::production::ReplicatedShardedState;
+use redis_sim::redis::{Command, SDS};
+use redis_sim::replication::ReplicationConfig;
+use redis_sim::streaming::wal_store::{SimulatedWalStore, SimulatedWalStoreConfig};
+use redis_sim::streaming::{
+    spawn_wal_actor, FsyncPolicy, InMemoryObjectStore, SimulatedObjectStore, SimulatedStoreConfig,
+    StreamingConfig, StreamingIntegration, WalConfig,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Configuration for a single latency budget run
+#[derive(Debug, Clone)]
+struct LatencyBudgetConfig {
+    seed: u64,
+    num_commands: usize,
+    fsync_policy: FsyncPolicy,
+    wal_store: SimulatedWalStoreConfig,
+    object_store: SimulatedStoreConfig,
+    /// Client think time between commands; lets flushes hit the store mid-run
+    think_time: Duration,
+    /// Commands between WAL sync ticks (what the EverySecond timer sends)
+    sync_tick_every: usize,
+    /// Maximum allowed p99 ack latency
+    ack_p99_budget: Duration,
+}
+
+impl LatencyBudgetConfig {
+    /// Fire-and-forget durability under a persistence fault storm
+    fn storm(seed: u64) -> Self {
+        LatencyBudgetConfig {
+            seed,
+            num_commands: 400,
+            fsync_policy: FsyncPolicy::EverySecond,
+            wal_store: storm_wal_store(),
+            object_store: storm_object_store(),
+            think_time: Duration::from_millis(5),
+            sync_tick_every: 16,
+            ack_p99_budget: Duration::from_millis(1),
+        }
+    }
+}
+
+/// WAL backend failing a large fraction of appends and fsyncs
+fn storm_wal_store() -> SimulatedWalStoreConfig {
+    SimulatedWalStoreConfig {
+        write_fail_prob: 0.3,
+        partial_write_prob: 0.1,
+        fsync_fail_prob: 0.3,
+        corruption_prob: 0.05,
+        disk_full_prob: 0.1,
+    }
+}
+
+/// Object store that is slow, regularly down and throttled
+fn storm_object_store() -> SimulatedStoreConfig {
+    SimulatedStoreConfig {
+        put_fail_prob: 0.2,
+        timeout_prob: 0.1,
+        // 20-200ms per op: any ack waiting on a flush would blow the budget
+        latency_range_us: (20_000, 200_000),
+        ..SimulatedStoreConfig::cloud_chaos()
+    }
+    .with_outage(10, 40)
+}
+
+/// Ack latency percentiles for a run
+#[derive(Debug)]
+struct LatencyBudgetResult {
+    seed: u64,
+    acks: usize,
+    p50: Duration,
+    p99: Duration,
+    max: Duration,
+    wal_faults: u64,
+    store_faults: u64,
+    budget: Duration,
+}
+
+impl LatencyBudgetResult {
+    fn within_budget(&self) -> bool {
+        self.p99 <= self.budget
+    }
+}
+
+impl std::fmt::Display for LatencyBudgetResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "seed {}: {} acks, p50={:?} p99={:?} max={:?} (budget {:?}), wal_faults={} store_faults={}",
+            self.seed,
+            self.acks,
+            self.p50,
+            self.p99,
+            self.max,
+            self.budget,
+            self.wal_faults,
+            self.store_faults
+        )
+    }
+}
+
+/// Nearest-rank percentile over sorted samples
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    debug_assert!(!sorted.is_empty(), "Precondition: need at least one sample");
+    debug_assert!(pct <= 100, "Precondition: percentile must be in 0..=100");
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Command mix: mostly writes (which produce deltas), some reads
+fn workload_command(rng_word: u64, i: usize) -> Command {
+    let key = format!("key:{}", rng_word % 64);
+    match rng_word % 10 {
+        0..=5 => Command::set(key, SDS::from_str(&format!("value-{}", i))),
+        6 | 7 => Command::Incr(format!("counter:{}", rng_word % 8)),
+        8 => Command::del(key),
+        _ => Command::Get(key),
+    }
+}
+
+async fn run_latency_budget(config: LatencyBudgetConfig) -> LatencyBudgetResult {
+    use redis_sim::io::Rng;
+
+    let wal_store =
+        SimulatedWalStore::new(SimulatedRng::new(config.seed), config.wal_store.clone());
+    let wal_config = WalConfig {
+        enabled: true,
+        fsync_policy: config.fsync_policy,
+        ..WalConfig::default()
+    };
+    let (wal, wal_task) =
+        spawn_wal_actor(wal_store.clone(), wal_config).expect("WAL actor must start");
+
+    let object_store = Arc::new(SimulatedObjectStore::new(
+        InMemoryObjectStore::new(),
+        SimulatedRng::new(config.seed.wrapping_add(1)),
+        config.object_store.clone(),
+    ));
+    let integration =
+        StreamingIntegration::with_store(object_store.clone(), StreamingConfig::test(), 1);
+    let (workers, sink) = integration
+        .start_workers()
+        .await
+        .expect("streaming workers must start");
+
+    let mut state = ReplicatedShardedState::new(ReplicationConfig {
+        replica_id: 1,
+        enabled: false,
+        ..Default::default()
+    });
+    state.set_wal_handle(wal.clone());
+    state.set_delta_sink(sink);
+
+    let mut rng = SimulatedRng::new(config.seed.wrapping_add(2));
+    let mut samples = Vec::with_capacity(config.num_commands);
+    for i in 0..config.num_commands {
+        let cmd = workload_command(rng.next_u64(), i);
+
+        let start = Instant::now();
+        let _ = state.execute(cmd).await;
+        samples.push(start.elapsed());
+
+        if i % config.sync_tick_every == 0 {
+            wal.sync_tick();
+        }
+        tokio::time::sleep(config.think_time).await;
+    }
+
+    workers.shutdown().await;
+    wal.shutdown().await;
+    let _ = wal_task.await;
+
+    samples.sort();
+    let wal_stats = wal_store.stats();
+    let store_stats = object_store.stats();
+    let result = LatencyBudgetResult {
+        seed: config.seed,
+        acks: samples.len(),
+        p50: percentile(&samples, 50),
+        p99: percentile(&samples, 99),
+        max: *samples.last().expect("at least one sample"),
+        wal_faults: wal_stats.write_failures + wal_stats.sync_failures + wal_stats.disk_full_errors,
+        store_faults: store_stats.put_failures
+            + store_stats.timeouts
+            + store_stats.outage_rejections
+            + store_stats.throttled,
+        budget: config.ack_p99_budget,
+    };
+
+    // TigerStyle: Postconditions
+    debug_assert_eq!(result.acks, config.num_commands);
+    debug_assert!(result.p50 <= result.p99 && result.p99 <= result.max);
+    result
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_ack_latency_budget_under_persistence_storm() {
+    let mut total_wal_faults = 0;
+    let mut total_store_faults = 0;
+
+    for seed in 0..10 {
+        let result = run_latency_budget(LatencyBudgetConfig::storm(seed)).await;
+        println!("{}", result);
+        assert!(
+            result.within_budget(),
+            "persistence faults blocked the command path: {}",
+            result
+        );
+        total_wal_faults += result.wal_faults;
+        total_store_faults += result.store_faults;
+    }
+
+    // The storm must actually have happened for the bound to mean anything
+    assert!(total_wal_faults > 0, "no WAL faults were injected");
+    assert!(
+        total_store_faults > 0,
+        "no object store faults were injected"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_ack_latency_budget_no_fsync_policy() {
+    for seed in 100..105 {
+        let config = LatencyBudgetConfig {
+            fsync_policy: FsyncPolicy::No,
+            ..LatencyBudgetConfig::storm(seed)
+        };
+        let result = run_latency_budget(config).await;
+        assert!(
+            result.within_budget(),
+            "persistence faults blocked the command path: {}",
+            result
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_always_policy_acks_wait_for_wal() {
+    // Contrast: Always mode awaits the WAL group commit before acking, so
+    // the same harness must observe non-zero ack latency. Guards against the
+    // measurement silently reporting zero for everything.
+    let config = LatencyBudgetConfig {
+        fsync_policy: FsyncPolicy::Always,
+        num_commands: 100,
+        ack_p99_budget: Duration::ZERO,
+        ..LatencyBudgetConfig::storm(7)
+    };
+    let result = run_latency_budget(config).await;
+    println!("{}", result);
+    assert!(
+        !result.within_budget(),
+        "Always mode acks must include WAL commit time: {}",
+        result
+    );
+}
@@ -0,0 +1,90 @@
+//! Loom Model Checks for Structures Shared Between Shards
+//!
+//! Explores every interleaving of the script cache and a shard's watchdog
+//! progress under loom. Only builds with the loom cfg (see src/sync.rs):
+//!
+//! ```text
+//! RUSTFLAGS="--cfg redis_sim_loom" cargo test --release --test loom_test
+//! ```
+
+#![cfg(redis_sim_loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+use redis_sim::production::ShardProgress;
+use redis_sim::redis::lua::SharedScriptCache;
+
+#[test]
+fn test_loads_from_two_shards_both_survive() {
+    loom::model(|| {
+        let cache = SharedScriptCache::new();
+        let other = cache.clone();
+        let loader = thread::spawn(move || other.cache_script("return 1"));
+        let mine = cache.cache_script("return 2");
+        let theirs = loader.join().unwrap();
+
+        assert_eq!(cache.get_script(&mine).as_deref(), Some("return 2"));
+        assert_eq!(cache.get_script(&theirs).as_deref(), Some("return 1"));
+    });
+}
+
+#[test]
+fn test_readers_see_a_script_whole_or_not_at_all() {
+    loom::model(|| {
+        let cache = SharedScriptCache::new();
+        let sha = SharedScriptCache::compute_sha1("return KEYS[1]");
+
+        let reader = {
+            let cache = cache.clone();
+            let sha = sha.clone();
+            thread::spawn(move || cache.get_script(&sha))
+        };
+        let flusher = {
+            let cache = cache.clone();
+            thread::spawn(move || cache.flush())
+        };
+        assert_eq!(cache.cache_script("return KEYS[1]"), sha);
+
+        let seen = reader.join().unwrap();
+        assert!(
+            seen.is_none() || seen.as_deref() == Some("return KEYS[1]"),
+            "{:?}",
+            seen
+        );
+        flusher.join().unwrap();
+        // Loaded then flushed, or flushed then loaded
+        if let Some(script) = cache.get_script(&sha) {
+            assert_eq!(script, "return KEYS[1]");
+        }
+    });
+}
+
+#[test]
+fn test_watchdog_snapshots_follow_the_shard() {
+    loom::model(|| {
+        let progress = Arc::new(ShardProgress::new());
+
+        let shard = {
+            let progress = progress.clone();
+            thread::spawn(move || {
+                for command in ["get", "set"] {
+                    progress.begin(command, 1);
+                    progress.end();
+                }
+            })
+        };
+
+        let first = progress.snapshot();
+        let second = progress.snapshot();
+        assert!(first.processed <= second.processed);
+        assert!(second.processed <= 2);
+        if let Some((command, _)) = second.busy {
+            assert!(command == "get" || command == "set", "{}", command);
+        }
+
+        shard.join().unwrap();
+        let last = progress.snapshot();
+        assert_eq!(last.processed, 2);
+        assert!(last.busy.is_none());
+    });
+}
@@ -0,0 +1,69 @@
+//! Pub/Sub Deterministic Simulation Tests
+//!
+//! DST tests for PubSubManager delivery and ordering with multiple seeds.
+
+use redis_sim::redis::{
+    run_pubsub_batch, summarize_pubsub_batch, PubSubDSTConfig, PubSubDSTHarness,
+};
+
+// =============================================================================
+// Standard Configuration Tests - 100+ Seeds
+// =============================================================================
+
+#[test]
+fn test_pubsub_dst_100_seeds_standard() {
+    let results = run_pubsub_batch(0, 100, 500, PubSubDSTConfig::new);
+    let summary = summarize_pubsub_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(
+        passed, 100,
+        "All 100 seeds should pass with standard config"
+    );
+}
+
+#[test]
+fn test_pubsub_dst_100_seeds_slow_consumers() {
+    let results = run_pubsub_batch(1000, 100, 500, PubSubDSTConfig::slow_consumers);
+    let summary = summarize_pubsub_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(passed, 100, "All 100 seeds should pass with slow consumers");
+    let disconnects: u64 = results.iter().map(|r| r.disconnects).sum();
+    assert!(disconnects > 0, "slow consumers were never disconnected");
+}
+
+#[test]
+fn test_pubsub_dst_100_seeds_fan_out() {
+    let results = run_pubsub_batch(2000, 100, 500, PubSubDSTConfig::fan_out);
+    let summary = summarize_pubsub_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(passed, 100, "All 100 seeds should pass with fan-out");
+}
+
+#[test]
+fn test_pubsub_dst_100_seeds_patterns() {
+    let results = run_pubsub_batch(3000, 100, 500, PubSubDSTConfig::patterns);
+    let summary = summarize_pubsub_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(passed, 100, "All 100 seeds should pass with pattern subscriptions");
+}
+
+// =============================================================================
+// Stress Tests
+// =============================================================================
+
+#[test]
+fn test_pubsub_dst_stress_5000_ops() {
+    let mut harness = PubSubDSTHarness::with_seed(12345);
+    harness.run(5000);
+    let result = harness.result();
+    println!("Stress 5000 ops: {}", result.summary());
+    assert!(result.is_success(), "5000 ops should maintain invariants");
+}
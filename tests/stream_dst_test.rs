@@ -0,0 +1,71 @@
+//! Stream Consumer Group Deterministic Simulation Tests
+//!
+//! DST tests for XREADGROUP/XACK/XAUTOCLAIM under consumer crashes with
+//! multiple seeds.
+
+use redis_sim::redis::{
+    run_stream_batch, summarize_stream_batch, StreamDSTConfig, StreamDSTHarness,
+};
+
+// =============================================================================
+// Standard Configuration Tests - 100+ Seeds
+// =============================================================================
+
+#[test]
+fn test_stream_dst_100_seeds_standard() {
+    let results = run_stream_batch(0, 100, 500, StreamDSTConfig::new);
+    let summary = summarize_stream_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(
+        passed, 100,
+        "All 100 seeds should pass with standard config"
+    );
+}
+
+#[test]
+fn test_stream_dst_100_seeds_crash_heavy() {
+    let results = run_stream_batch(1000, 100, 500, StreamDSTConfig::crash_heavy);
+    let summary = summarize_stream_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(
+        passed, 100,
+        "All 100 seeds should pass with frequent crashes"
+    );
+    let claimed: u64 = results.iter().map(|r| r.claimed).sum();
+    assert!(claimed > 0, "abandoned entries were never claimed");
+}
+
+#[test]
+fn test_stream_dst_100_seeds_contended() {
+    let results = run_stream_batch(2000, 100, 500, StreamDSTConfig::contended);
+    let summary = summarize_stream_batch(&results);
+    println!("{}", summary);
+
+    let passed = results.iter().filter(|r| r.is_success()).count();
+    assert_eq!(
+        passed, 100,
+        "All 100 seeds should pass with contended claims"
+    );
+    let stale_acks: u64 = results.iter().map(|r| r.stale_acks).sum();
+    assert!(
+        stale_acks > 0,
+        "no consumer ever acknowledged a claimed entry"
+    );
+}
+
+// =============================================================================
+// Stress Tests
+// =============================================================================
+
+#[test]
+fn test_stream_dst_stress_5000_ops() {
+    let mut harness = StreamDSTHarness::with_seed(12345);
+    harness.run(5000);
+    let result = harness.result();
+    println!("Stress 5000 ops: {}", result.summary());
+    assert!(result.is_success(), "5000 ops should maintain invariants");
+}
@@ -0,0 +1,43 @@
+//! Transaction WAL DST Integration Tests
+//!
+//! Multi-seed crash tests for MULTI/EXEC over the WAL: a node that crashes
+//! between queueing a transaction and acknowledging its EXEC recovers all of
+//! the transaction's writes or none of them.
+
+use redis_sim::streaming::transaction_wal_dst::{
+    run_transaction_wal_dst_batch, summarize_transaction_wal_dst_batch, TransactionWalDSTConfig,
+};
+
+#[test]
+fn test_transaction_wal_dst_500_seeds_crash_only() {
+    let results = run_transaction_wal_dst_batch(0..500, TransactionWalDSTConfig::crash_only());
+    let summary = summarize_transaction_wal_dst_batch(&results);
+    println!("{}", summary);
+
+    for r in &results {
+        assert!(
+            r.passed,
+            "Seed {} failed: {}",
+            r.seed,
+            r.error_message.as_deref().unwrap_or("unknown")
+        );
+        assert_eq!(r.torn_transactions, 0, "Seed {}: torn transaction", r.seed);
+    }
+}
+
+#[test]
+fn test_transaction_wal_dst_200_seeds_chaos_with_rotation() {
+    let config = TransactionWalDSTConfig {
+        max_file_size: 256, // Most batches start a new file
+        ..TransactionWalDSTConfig::chaos()
+    };
+    let results = run_transaction_wal_dst_batch(0..200, config);
+    let summary = summarize_transaction_wal_dst_batch(&results);
+    println!("{}", summary);
+
+    assert!(
+        results.iter().all(|r| r.passed),
+        "Not all seeds passed: {}",
+        summary
+    );
+}
@@ -17,6 +17,7 @@ fn test_wal_dst_100_seeds_always_mode() {
         store_config: SimulatedWalStoreConfig::no_faults(),
         simulate_crash: true,
         fsync_after_write: true,
+        batch_size: 1,
     };
 
     let results = run_wal_dst_batch(0..100, config);
@@ -50,6 +51,7 @@ fn test_wal_dst_50_seeds_with_faults() {
         store_config: SimulatedWalStoreConfig::default(),
         simulate_crash: true,
         fsync_after_write: true,
+        batch_size: 1,
     };
 
     let results = run_wal_dst_batch(0..50, config);
@@ -77,6 +79,7 @@ fn test_wal_dst_crash_during_group_commit() {
         store_config: SimulatedWalStoreConfig::no_faults(),
         simulate_crash: true,
         fsync_after_write: true,
+        batch_size: 1,
     };
 
     let results = run_wal_dst_batch(0..50, config);
@@ -100,6 +103,7 @@ fn test_wal_dst_rotation_under_load() {
         store_config: SimulatedWalStoreConfig::no_faults(),
         simulate_crash: true,
         fsync_after_write: true,
+        batch_size: 1,
     };
 
     let results = run_wal_dst_batch(0..30, config);
@@ -126,7 +130,6 @@ fn test_wal_dst_rotation_under_load() {
 fn test_wal_dst_truncation_correctness() {
     // Verifies that truncation doesn't remove entries that haven't been streamed.
     // We write entries, truncate old ones, crash, and verify recent entries survive.
-    use redis_sim::io::simulation::SimulatedRng;
     use redis_sim::redis::SDS;
     use redis_sim::replication::lattice::{LamportClock, ReplicaId};
     use redis_sim::replication::state::{ReplicatedValue, ReplicationDelta};
@@ -136,7 +139,6 @@ fn test_wal_dst_truncation_correctness() {
     for seed in 0..30 {
         let store = InMemoryWalStore::new();
         let mut rotator = WalRotator::new(store.clone(), 100).unwrap();
-        let mut rng = SimulatedRng::new(seed);
 
         let mut acked_after_truncation = Vec::new();
 
@@ -203,6 +205,35 @@ fn test_wal_dst_truncation_correctness() {
     }
 }
 
+#[test]
+fn test_wal_dst_100_seeds_atomic_batches_with_faults() {
+    // INVARIANT: A batch entry (EXEC, a script) is recovered whole or not at
+    // all, even when a crash lands between two of its writes.
+    let config = WalDSTConfig {
+        num_writes: 120,
+        max_file_size: 256,
+        store_config: SimulatedWalStoreConfig::default(),
+        simulate_crash: true,
+        fsync_after_write: true,
+        batch_size: 5,
+    };
+
+    let results = run_wal_dst_batch(0..100, config);
+    let summary = summarize_wal_dst_batch(&results);
+
+    for r in &results {
+        assert!(
+            r.passed,
+            "Seed {} failed: {}",
+            r.seed,
+            r.error_message.as_deref().unwrap_or("unknown")
+        );
+        assert_eq!(r.partial_batches, 0, "Seed {}: partial batch", r.seed);
+    }
+
+    println!("{}", summary);
+}
+
 #[test]
 #[ignore] // Stress test — run manually with `cargo test --release -- --ignored`
 fn test_wal_dst_1000_seeds_chaos() {
@@ -212,6 +243,7 @@ fn test_wal_dst_1000_seeds_chaos() {
         store_config: SimulatedWalStoreConfig::high_chaos(),
         simulate_crash: true,
         fsync_after_write: true,
+        batch_size: 1,
     };
 
     let results = run_wal_dst_batch(0..1000, config);